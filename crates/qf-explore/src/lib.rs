@@ -0,0 +1,618 @@
+//! Exhaustive interleaving explorer for small active-object systems.
+//!
+//! An [`Explorer`] takes a factory for the system under test, an alphabet of
+//! external events and a set of invariants. It walks every interleaving of
+//! event injections and dispatches up to a depth bound, depth first, and
+//! checks the invariants after the initial transitions and after every step.
+//! The first violation comes back as a [`Violation`] that lists the steps
+//! leading to it:
+//!
+//! ```rust,ignore
+//! use qf_explore::{Explorer, Model};
+//!
+//! let report = Explorer::new(|| {
+//!     let philos = philosophers();
+//!     let mut model = Model::new(philos.clone()).register(table());
+//!     for philo in &philos {
+//!         model = model.register(arc_as_runnable(philo.clone()));
+//!     }
+//!     model
+//! })
+//! .post(PHILO_ID[0], DynEvent::empty_dyn(HUNGRY_SIG))
+//! .post(PHILO_ID[1], DynEvent::empty_dyn(HUNGRY_SIG))
+//! .invariant("neighbours never eat together", |philos| no_adjacent_eaters(philos))
+//! .max_depth(10)
+//! .run();
+//!
+//! if let Err(violation) = report {
+//!     panic!("{violation}");
+//! }
+//! ```
+//!
+//! The explorer does not snapshot state. For each path it builds a fresh
+//! system from the factory and replays the path's steps. This only works if
+//! the factory and the behaviours are deterministic: no clocks, no
+//! randomness, no state shared between runs.
+//!
+//! By default only the highest-priority ready object may run, as on the
+//! cooperative kernel. The interleavings then come from external events
+//! arriving between dispatches. [`Schedule::Any`] lets any ready object run
+//! next. That covers the orders that preemption, threads or a second core
+//! can produce.
+
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use qf::active::{ActiveObjectId, ActiveObjectRef};
+use qf::event::{DynEvent, Signal};
+use qf::services::{with_services, KernelServices, ServiceError};
+
+/// A system under test: its active objects, its subscriptions and a
+/// user-defined `state` handle that invariants inspect.
+pub struct Model<S> {
+    state: S,
+    objects: Vec<ActiveObjectRef>,
+    subscriptions: Vec<(Signal, ActiveObjectId)>,
+}
+
+impl<S> Model<S> {
+    /// Creates an empty model around `state`. Typically `state` holds typed
+    /// handles to the objects, read with `ActiveObject::with_behavior`.
+    pub fn new(state: S) -> Self {
+        Self {
+            state,
+            objects: Vec::new(),
+            subscriptions: Vec::new(),
+        }
+    }
+
+    /// Adds an active object.
+    pub fn register(mut self, object: ActiveObjectRef) -> Self {
+        self.objects.push(object);
+        self
+    }
+
+    /// Delivers published `signal` events to `id`.
+    pub fn subscribe(mut self, signal: Signal, id: ActiveObjectId) -> Self {
+        self.subscriptions.push((signal, id));
+        self
+    }
+}
+
+/// Kernel services for one explored run. Posts land in the target's queue;
+/// the explorer decides what is dispatched.
+struct World {
+    objects: Vec<ActiveObjectRef>,
+    subscriptions: Vec<(Signal, ActiveObjectId)>,
+}
+
+impl World {
+    fn find(&self, id: ActiveObjectId) -> Result<&ActiveObjectRef, ServiceError> {
+        self.objects
+            .iter()
+            .find(|object| object.id() == id)
+            .ok_or(ServiceError::NotFound(id))
+    }
+}
+
+impl KernelServices for World {
+    fn post(&self, target: ActiveObjectId, event: DynEvent) -> Result<(), ServiceError> {
+        self.find(target)?.post(event);
+        Ok(())
+    }
+
+    fn post_lifo(&self, target: ActiveObjectId, event: DynEvent) -> Result<(), ServiceError> {
+        self.find(target)?.post_lifo(event);
+        Ok(())
+    }
+
+    fn publish(&self, signal: Signal, event: DynEvent) {
+        for (_, id) in self.subscriptions.iter().filter(|(s, _)| *s == signal) {
+            if let Ok(object) = self.find(*id) {
+                object.post(event.clone());
+            }
+        }
+    }
+}
+
+/// An external event the explorer may inject at any step.
+#[derive(Debug, Clone)]
+pub enum Stimulus {
+    /// Posted to one active object.
+    Post(ActiveObjectId, DynEvent),
+    /// Published to the subscribers of its signal.
+    Publish(DynEvent),
+}
+
+impl fmt::Display for Stimulus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Post(id, event) => write!(f, "post {} to AO{}", event.header.signal, id.0),
+            Self::Publish(event) => write!(f, "publish {}", event.header.signal),
+        }
+    }
+}
+
+/// Which ready objects may be dispatched next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Schedule {
+    /// Only the highest-priority one, as the cooperative kernel does.
+    #[default]
+    Priority,
+    /// Any of them.
+    Any,
+}
+
+/// One move on an explored path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Inject the stimulus at this index of the alphabet.
+    Inject(usize),
+    /// Dispatch one event to this object.
+    Dispatch(ActiveObjectId),
+}
+
+/// How much of the state space a clean run covered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Distinct paths checked to the end: to the depth bound or to a state
+    /// with nothing left to do.
+    pub paths: usize,
+    /// States checked, counting shared prefixes once.
+    pub states: usize,
+}
+
+/// An invariant that failed, or a handler that panicked, and how to get
+/// there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The invariant's name, or `panic: <message>`.
+    pub invariant: String,
+    /// The steps from the initial transitions to the failing state.
+    pub steps: Vec<Step>,
+    /// The same steps in words, with the signal each dispatch handled.
+    pub trace: Vec<String>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "violated `{}` after {} steps",
+            self.invariant,
+            self.steps.len()
+        )?;
+        for (n, line) in self.trace.iter().enumerate() {
+            write!(f, "\n  {:>3}. {line}", n + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Violation {}
+
+type Factory<S> = Box<dyn Fn() -> Model<S>>;
+type Invariant<S> = (String, Box<dyn Fn(&S) -> bool>);
+
+/// Bounded depth-first search over the interleavings of a [`Model`].
+pub struct Explorer<S> {
+    build: Factory<S>,
+    alphabet: Vec<Stimulus>,
+    invariants: Vec<Invariant<S>>,
+    max_depth: usize,
+    max_injections: usize,
+    schedule: Schedule,
+}
+
+impl<S> Explorer<S> {
+    /// Explores systems built by `build`, 8 steps deep, with no limit on
+    /// injections other than the depth.
+    pub fn new(build: impl Fn() -> Model<S> + 'static) -> Self {
+        Self {
+            build: Box::new(build),
+            alphabet: Vec::new(),
+            invariants: Vec::new(),
+            max_depth: 8,
+            max_injections: usize::MAX,
+            schedule: Schedule::Priority,
+        }
+    }
+
+    /// Adds `event` posted to `target` to the alphabet.
+    pub fn post(self, target: ActiveObjectId, event: DynEvent) -> Self {
+        self.stimulus(Stimulus::Post(target, event))
+    }
+
+    /// Adds `event` published to its subscribers to the alphabet.
+    pub fn publish(self, event: DynEvent) -> Self {
+        self.stimulus(Stimulus::Publish(event))
+    }
+
+    /// Adds a stimulus to the alphabet.
+    pub fn stimulus(mut self, stimulus: Stimulus) -> Self {
+        self.alphabet.push(stimulus);
+        self
+    }
+
+    /// Adds an invariant that must hold in every reachable state.
+    pub fn invariant(
+        mut self,
+        name: impl Into<String>,
+        holds: impl Fn(&S) -> bool + 'static,
+    ) -> Self {
+        self.invariants.push((name.into(), Box::new(holds)));
+        self
+    }
+
+    /// Bounds the length of a path, counting injections and dispatches.
+    pub fn max_depth(mut self, steps: usize) -> Self {
+        self.max_depth = steps;
+        self
+    }
+
+    /// Bounds how many stimuli one path may inject.
+    pub fn max_injections(mut self, count: usize) -> Self {
+        self.max_injections = count;
+        self
+    }
+
+    /// Chooses which ready objects may be dispatched next.
+    pub fn schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// Explores every path up to the bounds and returns the first violation
+    /// found, if any.
+    pub fn run(&self) -> Result<Stats, Violation> {
+        let mut stats = Stats::default();
+        self.visit(&mut Vec::new(), &mut stats)?;
+        Ok(stats)
+    }
+
+    fn visit(&self, path: &mut Vec<Step>, stats: &mut Stats) -> Result<(), Violation> {
+        let choices = {
+            let world = self.replay(path)?;
+            stats.states += 1;
+            if path.len() == self.max_depth {
+                Vec::new()
+            } else {
+                self.choices(&world, path)
+            }
+        };
+        if choices.is_empty() {
+            stats.paths += 1;
+            return Ok(());
+        }
+        for step in choices {
+            path.push(step);
+            self.visit(path, stats)?;
+            path.pop();
+        }
+        Ok(())
+    }
+
+    /// The moves available after `path`.
+    fn choices(&self, world: &World, path: &[Step]) -> Vec<Step> {
+        let ready = world.objects.iter().filter(|object| object.has_events());
+        let mut choices: Vec<Step> = match self.schedule {
+            Schedule::Priority => ready
+                .max_by_key(|object| object.priority())
+                .map(|object| Step::Dispatch(object.id()))
+                .into_iter()
+                .collect(),
+            Schedule::Any => ready.map(|object| Step::Dispatch(object.id())).collect(),
+        };
+        let injected = path
+            .iter()
+            .filter(|step| matches!(step, Step::Inject(_)))
+            .count();
+        if injected < self.max_injections {
+            choices.extend((0..self.alphabet.len()).map(Step::Inject));
+        }
+        choices
+    }
+
+    /// Builds a fresh system, runs `path` on it and checks the state it ends
+    /// in. The states along the way were checked when the prefix was visited.
+    fn replay(&self, path: &[Step]) -> Result<World, Violation> {
+        let model = (self.build)();
+        let mut objects = model.objects;
+        objects.sort_by_key(|object| object.priority());
+        let world = World {
+            objects,
+            subscriptions: model.subscriptions,
+        };
+        let mut trace = Vec::with_capacity(path.len());
+        let fail = |invariant: String, done: usize, trace: &[String]| Violation {
+            invariant,
+            steps: path[..done].to_vec(),
+            trace: trace.to_vec(),
+        };
+
+        let started = catch_unwind(AssertUnwindSafe(|| {
+            for object in &world.objects {
+                with_services(&world, || object.start(None));
+            }
+        }));
+        if let Err(panic) = started {
+            return Err(fail(panic_message(&*panic), 0, &trace));
+        }
+
+        for (n, step) in path.iter().enumerate() {
+            trace.push(self.describe(&world, *step));
+            let ran = catch_unwind(AssertUnwindSafe(|| self.apply(&world, *step)));
+            if let Err(panic) = ran {
+                return Err(fail(panic_message(&*panic), n + 1, &trace));
+            }
+        }
+
+        if let Some((name, _)) = self
+            .invariants
+            .iter()
+            .find(|(_, holds)| !holds(&model.state))
+        {
+            return Err(fail(name.clone(), path.len(), &trace));
+        }
+        Ok(world)
+    }
+
+    fn apply(&self, world: &World, step: Step) {
+        match step {
+            Step::Inject(index) => with_services(world, || match &self.alphabet[index] {
+                Stimulus::Post(target, event) => {
+                    let _ = world.post(*target, event.clone());
+                }
+                Stimulus::Publish(event) => world.publish(event.header.signal, event.clone()),
+            }),
+            Step::Dispatch(id) => {
+                if let Ok(object) = world.find(id) {
+                    with_services(world, || object.dispatch_one());
+                }
+            }
+        }
+    }
+
+    fn describe(&self, world: &World, step: Step) -> String {
+        match step {
+            Step::Inject(index) => self.alphabet[index].to_string(),
+            Step::Dispatch(id) => {
+                match world.find(id).ok().and_then(|object| object.front_signal()) {
+                    Some(signal) => format!("AO{} handles {signal}", id.0),
+                    None => format!("AO{} handles its next event", id.0),
+                }
+            }
+        }
+    }
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    let message = panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string payload");
+    format!("panic: {message}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use qf::active::{
+        arc_as_runnable, new_active_object, ActiveContext, ActiveObject, SignalHandler,
+    };
+
+    const HUNGRY: Signal = Signal(1);
+    const EAT: Signal = Signal(2);
+    const FULL: Signal = Signal(3);
+    const DONE: Signal = Signal(4);
+    const N: usize = 3;
+    const TABLE: ActiveObjectId = ActiveObjectId::new(10);
+
+    fn philo_id(n: usize) -> ActiveObjectId {
+        ActiveObjectId::new(1 + n as u8)
+    }
+
+    /// Hands out forks; `careless` forgets to check the left one.
+    struct Table {
+        forks: [bool; N],
+        careless: bool,
+    }
+
+    impl Table {
+        fn left(n: usize) -> usize {
+            (n + 1) % N
+        }
+    }
+
+    impl qf::active::ActiveBehavior for Table {
+        fn on_start(&mut self, _ctx: &mut ActiveContext) {}
+
+        fn on_event(&mut self, ctx: &mut ActiveContext, event: DynEvent) {
+            let n = *event.payload.downcast_ref::<usize>().unwrap();
+            match event.header.signal {
+                HUNGRY if self.forks[n] && (self.careless || self.forks[Self::left(n)]) => {
+                    self.forks[n] = false;
+                    self.forks[Self::left(n)] = false;
+                    ctx.post(philo_id(n), DynEvent::empty_dyn(EAT)).unwrap();
+                }
+                DONE => {
+                    self.forks[n] = true;
+                    self.forks[Self::left(n)] = true;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    struct Philo {
+        n: usize,
+        eating: bool,
+    }
+
+    impl SignalHandler for Philo {
+        fn handle_signal(&mut self, signal: Signal, ctx: &mut ActiveContext) {
+            match signal {
+                HUNGRY if !self.eating => {
+                    ctx.post(TABLE, DynEvent::with_payload(HUNGRY, self.n))
+                        .unwrap();
+                }
+                EAT => self.eating = true,
+                FULL if self.eating => {
+                    self.eating = false;
+                    ctx.post(TABLE, DynEvent::with_payload(DONE, self.n))
+                        .unwrap();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    type Philos = Vec<Arc<ActiveObject<Philo>>>;
+
+    fn dining(careless: bool) -> Explorer<Philos> {
+        let mut explorer = Explorer::new(move || {
+            let philos: Philos = (0..N)
+                .map(|n| ActiveObject::new(philo_id(n), 1 + n as u8, Philo { n, eating: false }))
+                .collect();
+            let table = Table {
+                forks: [true; N],
+                careless,
+            };
+            let mut model = Model::new(philos.clone()).register(new_active_object(TABLE, 5, table));
+            for philo in philos {
+                model = model.register(arc_as_runnable(philo));
+            }
+            model
+        })
+        .invariant("neighbours never eat together", |philos: &Philos| {
+            let eating: Vec<bool> = philos
+                .iter()
+                .map(|p| p.with_behavior(|p| p.eating))
+                .collect();
+            (0..N).all(|n| !(eating[n] && eating[Table::left(n)]))
+        });
+        for n in 0..N {
+            explorer = explorer
+                .post(philo_id(n), DynEvent::empty_dyn(HUNGRY))
+                .post(philo_id(n), DynEvent::empty_dyn(FULL));
+        }
+        explorer
+    }
+
+    #[test]
+    fn a_correct_table_keeps_neighbours_apart() {
+        let stats = dining(false).max_depth(6).run().unwrap();
+        assert!(stats.paths > 1000, "explored {stats:?}");
+        assert!(stats.states > stats.paths);
+    }
+
+    #[test]
+    fn a_careless_table_is_caught_with_the_steps_that_break_it() {
+        let violation = dining(true).max_depth(8).run().unwrap_err();
+        assert_eq!(violation.invariant, "neighbours never eat together");
+        assert_eq!(violation.steps.len(), violation.trace.len());
+
+        let injected = violation
+            .steps
+            .iter()
+            .filter(|s| matches!(s, Step::Inject(_)))
+            .count();
+        assert_eq!(
+            injected, 2,
+            "two hungry philosophers are enough:\n{violation}"
+        );
+        assert!(
+            violation.trace[0].starts_with("post SIG(0x0001) to AO"),
+            "{violation}"
+        );
+        assert!(
+            violation.to_string().contains("AO10 handles SIG(0x0001)"),
+            "{violation}"
+        );
+    }
+
+    #[test]
+    fn any_schedule_reaches_orders_that_priorities_rule_out() {
+        // Whoever runs first wins; with priorities AO2 always runs after AO1
+        // has been posted to, so the race is only seen with `Schedule::Any`.
+        struct Racer {
+            first: Arc<std::sync::atomic::AtomicU8>,
+            id: u8,
+        }
+
+        impl SignalHandler for Racer {
+            fn handle_signal(&mut self, _signal: Signal, _ctx: &mut ActiveContext) {
+                let _ = self.first.compare_exchange(
+                    0,
+                    self.id,
+                    std::sync::atomic::Ordering::SeqCst,
+                    std::sync::atomic::Ordering::SeqCst,
+                );
+            }
+        }
+
+        let explorer = || {
+            Explorer::new(|| {
+                let first = Arc::new(std::sync::atomic::AtomicU8::new(0));
+                Model::new(Arc::clone(&first))
+                    .register(new_active_object(
+                        ActiveObjectId::new(1),
+                        1,
+                        Racer {
+                            first: Arc::clone(&first),
+                            id: 1,
+                        },
+                    ))
+                    .register(new_active_object(
+                        ActiveObjectId::new(2),
+                        2,
+                        Racer { first, id: 2 },
+                    ))
+                    .subscribe(HUNGRY, ActiveObjectId::new(1))
+                    .subscribe(HUNGRY, ActiveObjectId::new(2))
+            })
+            .publish(DynEvent::empty_dyn(HUNGRY))
+            .invariant("the higher priority wins", |first| {
+                first.load(std::sync::atomic::Ordering::SeqCst) != 1
+            })
+            .max_injections(1)
+        };
+
+        assert!(explorer().run().is_ok());
+        let violation = explorer().schedule(Schedule::Any).run().unwrap_err();
+        assert_eq!(
+            violation.steps,
+            [Step::Inject(0), Step::Dispatch(ActiveObjectId::new(1))]
+        );
+    }
+
+    #[test]
+    fn a_panicking_handler_is_reported_as_a_violation() {
+        struct Grumpy;
+
+        impl SignalHandler for Grumpy {
+            fn handle_signal(&mut self, signal: Signal, _ctx: &mut ActiveContext) {
+                assert_ne!(signal, FULL, "already full");
+            }
+        }
+
+        let violation = Explorer::new(|| {
+            Model::new(()).register(new_active_object(ActiveObjectId::new(1), 1, Grumpy))
+        })
+        .post(ActiveObjectId::new(1), DynEvent::empty_dyn(FULL))
+        .run()
+        .unwrap_err();
+        assert!(
+            violation.invariant.starts_with("panic: assertion"),
+            "{violation}"
+        );
+        assert_eq!(
+            violation.steps,
+            [Step::Inject(0), Step::Dispatch(ActiveObjectId::new(1))]
+        );
+    }
+}
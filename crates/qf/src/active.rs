@@ -10,6 +10,8 @@ use std::collections::VecDeque;
 use alloc::collections::VecDeque;
 
 use crate::dis::Dis;
+use crate::equeue::QEQueue;
+use crate::services::{self, ServiceError};
 #[cfg(not(feature = "static-alloc"))]
 use crate::sync::Arc;
 use crate::sync::Mutex;
@@ -68,6 +70,40 @@ impl ActiveContext {
     pub fn trace_hook(&self) -> Option<TraceHook> {
         self.trace.clone()
     }
+
+    /// A context for the active object being dispatched, for handlers that
+    /// are not given one, such as [`QHsm`](crate::QHsm) state handlers. It
+    /// carries no trace hook.
+    pub fn current() -> Option<Self> {
+        crate::current::current_ao().map(|id| Self::new(id, None))
+    }
+
+    /// Posts `event` to `target` through the kernel dispatching this object
+    /// (see [`services`](crate::services)).
+    pub fn post(&self, target: ActiveObjectId, event: DynEvent) -> Result<(), ServiceError> {
+        services::with_bound(|kernel| kernel.post(target, event))?
+    }
+
+    /// Publishes `event` as `signal` through the kernel dispatching this
+    /// object.
+    pub fn publish(&self, signal: Signal, event: DynEvent) -> Result<(), ServiceError> {
+        services::with_bound(|kernel| kernel.publish(signal, event))
+    }
+
+    /// Parks `event` in `eq` for a later [`recall`](Self::recall). Returns
+    /// `false` if `eq` is full.
+    pub fn defer(&self, eq: &QEQueue, event: DynEvent) -> bool {
+        eq.post(event, 0)
+    }
+
+    /// Moves the oldest event in `eq` to the front of this object's queue, so
+    /// it is handled next. Returns `false` if `eq` was empty.
+    pub fn recall(&self, eq: &QEQueue) -> Result<bool, ServiceError> {
+        services::with_bound(|kernel| match eq.get() {
+            Some(event) => kernel.post_lifo(self.id, event).map(|()| true),
+            None => Ok(false),
+        })?
+    }
 }
 
 /// Trait implemented by application state machines.
@@ -76,6 +112,17 @@ pub trait ActiveBehavior: Send + 'static {
     fn on_start(&mut self, ctx: &mut ActiveContext);
     /// Called for each event dispatched to the active object.
     fn on_event(&mut self, ctx: &mut ActiveContext, event: DynEvent);
+    /// Called once when the kernel [shuts down](crate::kernel::QvKernel::shutdown),
+    /// after the object has handled its [`STOP_SIG`](crate::kernel::STOP_SIG)
+    /// and the queues have drained. Events posted from here are not
+    /// dispatched. Defaults to doing nothing.
+    fn on_stop(&mut self, _ctx: &mut ActiveContext) {}
+    /// Address of the current state, for behaviours that are state machines.
+    /// QS reports it when the host queries the active object as a state
+    /// machine.
+    fn state_addr(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// Object-safe interface used by the kernel.
@@ -86,6 +133,9 @@ pub trait ActiveRunnable: Send + Sync {
     fn priority(&self) -> u8;
     /// Starts the active object, installing the given trace hook.
     fn start(&self, trace: Option<TraceHook>);
+    /// Runs the behaviour's stop hook at kernel shutdown. Objects without
+    /// one keep the default no-op.
+    fn stop(&self) {}
     /// Dispatches at most one queued event; returns `true` if one was handled.
     fn dispatch_one(&self) -> bool;
     /// Posts an event to the back of this active object's queue (FIFO).
@@ -96,6 +146,28 @@ pub trait ActiveRunnable: Send + Sync {
     fn post_lifo(&self, event: DynEvent);
     /// Returns `true` if this active object has queued events.
     fn has_events(&self) -> bool;
+    /// Signal of the event [`dispatch_one`](Self::dispatch_one) would handle
+    /// next, for kernels that trace or time the step.
+    fn front_signal(&self) -> Option<Signal> {
+        None
+    }
+    /// Occupancy of the event queue, for monitors such as the
+    /// [watchdog](crate::watchdog); `None` if the object does not keep it.
+    fn queue_stats(&self) -> Option<QueueStats> {
+        None
+    }
+}
+
+/// A snapshot of an active object's event queue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    /// Events waiting.
+    pub len: usize,
+    /// Most events ever waiting at once.
+    pub high_watermark: usize,
+    /// Events taken for dispatch so far, wrapping; it only moves while the
+    /// object makes progress.
+    pub taken: u32,
 }
 
 /// Default per-active-object queue capacity for the `static-alloc` (heap-free)
@@ -124,6 +196,8 @@ type EventBuf = heapless::Deque<DynEvent, AO_QUEUE_CAPACITY>;
 struct EventQueue {
     buf: EventBuf,
     high_watermark: usize,
+    /// Events taken for dispatch so far (wrapping).
+    taken: u32,
 }
 
 impl EventQueue {
@@ -134,6 +208,7 @@ impl EventQueue {
             #[cfg(feature = "static-alloc")]
             buf: heapless::Deque::new(),
             high_watermark: 0,
+            taken: 0,
         }
     }
 
@@ -170,7 +245,11 @@ impl EventQueue {
 
     #[inline]
     fn pop_front(&mut self) -> Option<DynEvent> {
-        self.buf.pop_front()
+        let event = self.buf.pop_front();
+        if event.is_some() {
+            self.taken = self.taken.wrapping_add(1);
+        }
+        event
     }
 
     #[inline]
@@ -182,6 +261,11 @@ impl EventQueue {
     fn is_empty(&self) -> bool {
         self.buf.is_empty()
     }
+
+    #[inline]
+    fn front_signal(&self) -> Option<Signal> {
+        self.buf.front().map(|event| event.header.signal)
+    }
 }
 
 /// Concrete active object implementation for a specific behavior.
@@ -242,6 +326,13 @@ impl<B: ActiveBehavior> ActiveObject<B> {
         f(&mut *guard)
     }
 
+    /// Address of the behavior, which `QHsm` and `QMsm` behaviors report as
+    /// the object of their QS records. Stable for the object's lifetime, so
+    /// it is the address to name the object under in QS dictionaries.
+    pub fn behavior_addr(&self) -> u64 {
+        self.with_behavior(|b| b as *const B as usize as u64)
+    }
+
     fn pop_event(&self) -> Option<DynEvent> {
         let mut queue = self.queue.lock();
         queue.pop_front()
@@ -267,6 +358,33 @@ impl<B: ActiveBehavior> ActiveObject<B> {
     }
 }
 
+/// Answers host queries as [`AO_OBJ`](qs::query::ObjKind::Ao), with the
+/// queue's free and minimum-free entries, and as
+/// [`SM_OBJ`](qs::query::ObjKind::Sm) when the behaviour reports a state. An
+/// unbounded queue counts its free entries down from `u16::MAX`.
+#[cfg(feature = "qs")]
+impl<B: ActiveBehavior> qs::query::Queryable for ActiveObject<B> {
+    fn query(&self, kind: qs::query::ObjKind) -> Option<qs::query::Snapshot> {
+        use qs::query::{ObjKind, Snapshot};
+        #[cfg(feature = "static-alloc")]
+        const CAPACITY: usize = AO_QUEUE_CAPACITY;
+        #[cfg(not(feature = "static-alloc"))]
+        const CAPACITY: usize = u16::MAX as usize;
+        match kind {
+            ObjKind::Ao => {
+                let queue = self.queue.lock();
+                let free = |used: usize| CAPACITY.saturating_sub(used) as u32;
+                Some(Snapshot::Ao { n_free: free(queue.len()), n_min: free(queue.high_watermark) })
+            }
+            ObjKind::Sm => {
+                let state = self.with_behavior(|b| b.state_addr())?;
+                Some(Snapshot::Sm { state: state as u64 })
+            }
+            _ => None,
+        }
+    }
+}
+
 /// Erase a typed active-object `Arc` to the [`ActiveRunnable`] trait object.
 ///
 /// This is a convenience wrapper around the trait-object coercion
@@ -301,14 +419,33 @@ impl<B: ActiveBehavior> ActiveRunnable for ActiveObject<B> {
         *self.trace_hook.lock() = trace.clone();
         let mut behavior = self.behavior.lock();
         let mut ctx = ActiveContext::new(self.id, trace);
+        let _current = crate::current::DispatchScope::enter(self.id);
         behavior.on_start(&mut ctx);
     }
 
+    fn stop(&self) {
+        let mut behavior = self.behavior.lock();
+        let mut ctx = self.build_context();
+        let _current = crate::current::DispatchScope::enter(self.id);
+        behavior.on_stop(&mut ctx);
+    }
+
     fn dispatch_one(&self) -> bool {
         if let Some(event) = self.pop_event() {
+            crate::jitter::delivered(self.id, event.header.signal);
             let mut behavior = self.behavior.lock();
             let mut ctx = self.build_context();
+            let _current = crate::current::DispatchScope::enter(self.id);
+            #[cfg(all(feature = "qs", feature = "std"))]
+            let _stage = qs::StageScope::enter();
+            // Hold a reference across the dispatch so the release is traced
+            // as QF_gc, once the behaviour has dropped or kept its own.
+            let trace = ctx.trace_hook();
+            let held = trace.as_ref().map(|_| event.clone());
             behavior.on_event(&mut ctx, event);
+            if let Some(held) = held {
+                crate::event_pool::gc(&held, trace.as_ref());
+            }
             true
         } else {
             false
@@ -316,16 +453,29 @@ impl<B: ActiveBehavior> ActiveRunnable for ActiveObject<B> {
     }
 
     fn post(&self, event: DynEvent) {
+        #[cfg(not(feature = "static-alloc"))]
+        crate::replay::posted(self.id, &event, false, self.trace_hook.lock().as_ref());
         self.queue.lock().push_back(event);
     }
 
     fn post_lifo(&self, event: DynEvent) {
+        #[cfg(not(feature = "static-alloc"))]
+        crate::replay::posted(self.id, &event, true, self.trace_hook.lock().as_ref());
         self.queue.lock().push_front(event);
     }
 
     fn has_events(&self) -> bool {
         !self.queue.lock().is_empty()
     }
+
+    fn front_signal(&self) -> Option<Signal> {
+        self.queue.lock().front_signal()
+    }
+
+    fn queue_stats(&self) -> Option<QueueStats> {
+        let queue = self.queue.lock();
+        Some(QueueStats { len: queue.len(), high_watermark: queue.high_watermark, taken: queue.taken })
+    }
 }
 
 /// Type-erased handle to an active object used by the kernel registry.
@@ -375,6 +525,8 @@ pub trait SignalHandler: Send + 'static {
     fn on_start(&mut self, _ctx: &mut ActiveContext) {}
     /// Handles a single incoming signal.
     fn handle_signal(&mut self, signal: Signal, ctx: &mut ActiveContext);
+    /// Optional stop hook; defaults to doing nothing.
+    fn on_stop(&mut self, _ctx: &mut ActiveContext) {}
 }
 
 impl<T: SignalHandler> ActiveBehavior for T {
@@ -385,6 +537,10 @@ impl<T: SignalHandler> ActiveBehavior for T {
     fn on_event(&mut self, ctx: &mut ActiveContext, event: DynEvent) {
         SignalHandler::handle_signal(self, event.signal(), ctx);
     }
+
+    fn on_stop(&mut self, ctx: &mut ActiveContext) {
+        SignalHandler::on_stop(self, ctx);
+    }
 }
 
 /// Type alias matching QP/C++'s QActive.
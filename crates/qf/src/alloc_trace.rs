@@ -0,0 +1,137 @@
+//! Heap-tracing global allocator for host deployments (`alloc-trace` feature).
+//!
+//! [`TracingAllocator`] wraps another [`GlobalAlloc`] and emits a QS user
+//! record for every allocation, reallocation and free of at least
+//! `threshold` bytes, tagged with the [`current_ao`] so heap churn can be
+//! traced back to the state machine that caused it.
+//!
+//! ```rust,ignore
+//! use std::alloc::System;
+//! use qf::alloc_trace::TracingAllocator;
+//!
+//! #[global_allocator]
+//! static HEAP: TracingAllocator = TracingAllocator::new(System, 256, 120);
+//!
+//! fn main() {
+//!     let tracer = /* … */;
+//!     HEAP.set_trace_hook(Some(tracer.hook()));
+//! }
+//! ```
+//!
+//! Record payload (user-record formatted, decodable by qspy):
+//! `U8 kind | U8 ao | U32 size | U64 addr`, where `kind` is one of
+//! [`ALLOC`], [`REALLOC`], [`FREE`] and `ao` is `0xFF` outside any dispatch.
+//! Allocations made while the thread is inside a tracer (see
+//! [`qs::tracer_busy`]) are not traced: emitting then would lock that tracer
+//! again on the same thread. This covers both the hook's own encoding and
+//! large records traced by the application.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::Mutex;
+
+use qs::{make_format, FMT_HEX, FMT_U32, FMT_U64, FMT_U8};
+
+use crate::current::current_ao;
+use crate::trace::TraceHook;
+
+/// `kind` field: block allocated.
+pub const ALLOC: u8 = 0;
+/// `kind` field: block resized (the record carries the new size/address).
+pub const REALLOC: u8 = 1;
+/// `kind` field: block freed.
+pub const FREE: u8 = 2;
+
+/// `ao` field value when no active object is dispatching.
+pub const NO_AO: u8 = 0xFF;
+
+std::thread_local! {
+    static REPORTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Global-allocator wrapper that traces large heap operations. See the
+/// [module docs](self).
+pub struct TracingAllocator<A = System> {
+    inner:     A,
+    threshold: usize,
+    record:    u8,
+    hook:      Mutex<Option<TraceHook>>,
+}
+
+impl<A> TracingAllocator<A> {
+    /// Wraps `inner`, tracing blocks of `threshold` bytes or more as user
+    /// record `record`. Tracing starts once a hook is installed.
+    pub const fn new(inner: A, threshold: usize, record: u8) -> Self {
+        Self { inner, threshold, record, hook: Mutex::new(None) }
+    }
+
+    /// Installs (or removes) the trace hook the records are emitted through.
+    pub fn set_trace_hook(&self, hook: Option<TraceHook>) {
+        *self.hook.lock().unwrap_or_else(|p| p.into_inner()) = hook;
+    }
+
+    fn report(&self, kind: u8, size: usize, addr: *mut u8) {
+        if size < self.threshold || addr.is_null() {
+            return;
+        }
+        // The hook may lock the very tracer this thread is already inside.
+        if qs::tracer_busy() {
+            return;
+        }
+        // Re-entrancy guard: the hook allocates while encoding the frame.
+        let entered = REPORTING.try_with(|r| !r.replace(true)).unwrap_or(false);
+        if !entered {
+            return;
+        }
+        let hook = match self.hook.try_lock() {
+            Ok(guard) => guard.clone(),
+            Err(_) => None,
+        };
+        if let Some(hook) = hook {
+            let ao = current_ao().map_or(NO_AO, |id| id.0);
+            let _ = hook(self.record, &payload(kind, ao, size, addr), true);
+        }
+        let _ = REPORTING.try_with(|r| r.set(false));
+    }
+}
+
+fn payload(kind: u8, ao: u8, size: usize, addr: *mut u8) -> [u8; 19] {
+    let mut buf = [0u8; 19];
+    buf[0] = make_format(0, FMT_U8);
+    buf[1] = kind;
+    buf[2] = make_format(0, FMT_U8);
+    buf[3] = ao;
+    buf[4] = make_format(0, FMT_U32);
+    buf[5..9].copy_from_slice(&(size.min(u32::MAX as usize) as u32).to_le_bytes());
+    buf[9] = FMT_HEX;
+    buf[10] = make_format(0, FMT_U64);
+    buf[11..19].copy_from_slice(&(addr as u64).to_le_bytes());
+    buf
+}
+
+// SAFETY: every call is forwarded unchanged to `inner`; tracing only reads the
+// layout and returned pointer.
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TracingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        self.report(ALLOC, layout.size(), ptr);
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        self.report(ALLOC, layout.size(), ptr);
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.report(FREE, layout.size(), ptr);
+        self.inner.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        self.report(REALLOC, new_size.max(layout.size()), new_ptr);
+        new_ptr
+    }
+}
@@ -0,0 +1,317 @@
+//! Active objects whose behaviour is an `async` function.
+//!
+//! [`AsyncActive`] registers with a kernel like any [`ActiveObject`]: it has an
+//! id, a priority and an event queue that `post` and `publish` feed. Its
+//! behaviour, instead of a state machine, is a future built from an [`Inbox`]:
+//!
+//! ```rust,ignore
+//! let timeout = new_time_event(BLINKY, TimeEventConfig::new(TIMEOUT_SIG));
+//! let blinky = AsyncActive::new(BLINKY, 1, move |inbox| async move {
+//!     loop {
+//!         inbox.sleep(&timeout, 100).await;
+//!         toggle_led();
+//!         if inbox.recv().await.signal() == STOP_SIG {
+//!             break;
+//!         }
+//!     }
+//! });
+//! wheel.register(timeout.clone());
+//! let kernel = Kernel::builder().register(blinky).build();
+//! ```
+//!
+//! Each object is its own executor. The kernel polls the future when the
+//! object has been woken — by a posted event, or by any other waker the
+//! future handed out — and a poll hands the future at most one event, so a
+//! poll is one run-to-completion step for tracing and RTC budgets. Futures
+//! woken from outside (a tokio channel, say) are polled on the kernel's next
+//! pass. [`Inbox::sleep`] maps a [`TimeEvent`] onto a timer: the timeout is an
+//! ordinary event in the object's queue, ticked by the [`TimerWheel`].
+//!
+//! An object whose future has completed stays registered and drops further
+//! events.
+//!
+//! [`ActiveObject`]: crate::ActiveObject
+//! [`TimeEvent`]: crate::TimeEvent
+//! [`TimerWheel`]: crate::TimerWheel
+
+use std::boxed::Box;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use crate::active::{ActiveContext, ActiveObjectId, ActiveRunnable};
+use crate::event::{DynEvent, Signal};
+use crate::sync::Mutex;
+use crate::time::{TickDuration, TimeEventRef};
+use crate::trace::TraceHook;
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+enum Task {
+    NotStarted(Box<dyn FnOnce(Inbox) -> BoxFuture + Send>),
+    Running(BoxFuture),
+    Finished,
+}
+
+/// State shared between the object, its [`Inbox`] and its waker.
+struct Shared {
+    id: ActiveObjectId,
+    queue: Mutex<VecDeque<DynEvent>>,
+    /// Set when the future asked to be polled again.
+    woken: AtomicBool,
+    /// Set once the current poll has handed the future an event.
+    delivered: AtomicBool,
+    trace: Mutex<Option<TraceHook>>,
+}
+
+impl Shared {
+    /// Takes the next event matching `accept` unless this poll already
+    /// delivered one, in which case the object asks for another poll.
+    fn take(&self, accept: impl Fn(&DynEvent) -> bool) -> Option<DynEvent> {
+        let mut queue = self.queue.lock();
+        let index = queue.iter().position(accept)?;
+        if self.delivered.load(Ordering::Acquire) {
+            self.woken.store(true, Ordering::Release);
+            return None;
+        }
+        let event = queue.remove(index)?;
+        self.delivered.store(true, Ordering::Release);
+        crate::jitter::delivered(self.id, event.header.signal);
+        Some(event)
+    }
+}
+
+impl Wake for Shared {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Release);
+    }
+}
+
+/// An active object driven by a future.
+pub struct AsyncActive {
+    id: ActiveObjectId,
+    priority: u8,
+    shared: Arc<Shared>,
+    task: Mutex<Task>,
+    finished: AtomicBool,
+}
+
+impl AsyncActive {
+    /// Creates an active object whose behaviour is the future `body` returns.
+    /// `body` runs when the kernel starts the object, and the future gets its
+    /// first poll straight away, as the initial transition of a state machine
+    /// would.
+    pub fn new<F, Fut>(id: ActiveObjectId, priority: u8, body: F) -> Arc<Self>
+    where
+        F: FnOnce(Inbox) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            id,
+            queue: Mutex::new(VecDeque::new()),
+            woken: AtomicBool::new(false),
+            delivered: AtomicBool::new(false),
+            trace: Mutex::new(None),
+        });
+        Arc::new(Self {
+            id,
+            priority,
+            shared,
+            task: Mutex::new(Task::NotStarted(Box::new(move |inbox| Box::pin(body(inbox))))),
+            finished: AtomicBool::new(false),
+        })
+    }
+
+    /// Returns `true` once the behaviour's future has completed.
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+
+    /// Number of events waiting in the queue.
+    pub fn queue_len(&self) -> usize {
+        self.shared.queue.lock().len()
+    }
+
+    /// Polls the future once; the caller holds the task lock.
+    fn poll(&self, task: &mut Task) {
+        let Task::Running(future) = task else { return };
+        self.shared.delivered.store(false, Ordering::Release);
+        let waker = Waker::from(self.shared.clone());
+        let mut cx = Context::from_waker(&waker);
+        let _current = crate::current::DispatchScope::enter(self.id);
+        #[cfg(feature = "qs")]
+        let _stage = qs::StageScope::enter();
+        if future.as_mut().poll(&mut cx).is_ready() {
+            *task = Task::Finished;
+            self.finished.store(true, Ordering::Release);
+            self.shared.queue.lock().clear();
+        }
+    }
+
+    fn wake(&self) {
+        if !self.is_finished() {
+            self.shared.woken.store(true, Ordering::Release);
+        }
+    }
+}
+
+impl ActiveRunnable for AsyncActive {
+    fn id(&self) -> ActiveObjectId {
+        self.id
+    }
+
+    fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    fn start(&self, trace: Option<TraceHook>) {
+        *self.shared.trace.lock() = trace;
+        let mut task = self.task.lock();
+        if let Task::NotStarted(body) = core::mem::replace(&mut *task, Task::Finished) {
+            *task = Task::Running(body(Inbox { shared: self.shared.clone() }));
+            self.poll(&mut task);
+        }
+    }
+
+    fn dispatch_one(&self) -> bool {
+        if !self.shared.woken.swap(false, Ordering::AcqRel) || self.is_finished() {
+            return false;
+        }
+        self.poll(&mut self.task.lock());
+        true
+    }
+
+    fn post(&self, event: DynEvent) {
+        if self.is_finished() {
+            return;
+        }
+        crate::replay::posted(self.shared.id, &event, false, self.shared.trace.lock().as_ref());
+        self.shared.queue.lock().push_back(event);
+        self.wake();
+    }
+
+    fn post_lifo(&self, event: DynEvent) {
+        if self.is_finished() {
+            return;
+        }
+        crate::replay::posted(self.shared.id, &event, true, self.shared.trace.lock().as_ref());
+        self.shared.queue.lock().push_front(event);
+        self.wake();
+    }
+
+    fn has_events(&self) -> bool {
+        self.shared.woken.load(Ordering::Acquire) && !self.is_finished()
+    }
+
+    fn front_signal(&self) -> Option<Signal> {
+        self.shared.queue.lock().front().map(|event| event.header.signal)
+    }
+}
+
+/// The behaviour's view of its active object.
+pub struct Inbox {
+    shared: Arc<Shared>,
+}
+
+impl Inbox {
+    /// Returns the id of the active object.
+    pub fn id(&self) -> ActiveObjectId {
+        self.shared.id
+    }
+
+    /// A context for emitting trace records on behalf of the object.
+    pub fn context(&self) -> ActiveContext {
+        ActiveContext::new(self.shared.id, self.shared.trace.lock().clone())
+    }
+
+    /// Waits for the next event in the queue.
+    ///
+    /// Timeouts of sleeps that are not being awaited are events like any
+    /// other and are returned here too.
+    pub fn recv(&self) -> Recv<'_> {
+        Recv { shared: &self.shared }
+    }
+
+    /// Takes the next event if one is queued and this step has not handled
+    /// one yet.
+    pub fn try_recv(&self) -> Option<DynEvent> {
+        self.shared.take(|_| true)
+    }
+
+    /// Arms `timer` to fire once after `timeout` ticks and waits for it.
+    ///
+    /// `timer` must target this object. The sleep completes when the timer's
+    /// event reaches the queue, and consumes that event; other events stay
+    /// queued for [`recv`](Self::recv). Dropping the sleep early disarms the
+    /// timer.
+    pub fn sleep(&self, timer: &TimeEventRef, timeout: impl Into<TickDuration>) -> Sleep {
+        debug_assert_eq!(timer.target(), self.shared.id, "timer posts to another object");
+        timer.arm_once(timeout);
+        Sleep { shared: self.shared.clone(), timer: timer.clone(), done: false }
+    }
+}
+
+/// Future returned by [`Inbox::recv`].
+pub struct Recv<'a> {
+    shared: &'a Arc<Shared>,
+}
+
+impl Future for Recv<'_> {
+    type Output = DynEvent;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<DynEvent> {
+        // A post wakes the object, so there is no waker to register.
+        match self.shared.take(|_| true) {
+            Some(event) => Poll::Ready(event),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Future returned by [`Inbox::sleep`].
+pub struct Sleep {
+    shared: Arc<Shared>,
+    timer: TimeEventRef,
+    done: bool,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        if self.done {
+            return Poll::Ready(());
+        }
+        let signal = self.timer.signal();
+        match self.shared.take(|event| event.header.signal == signal) {
+            Some(_) => {
+                self.done = true;
+                Poll::Ready(())
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        // A timer that already fired has left its event in the queue.
+        if self.timer.disarm().is_none() {
+            let signal = self.timer.signal();
+            let mut queue = self.shared.queue.lock();
+            if let Some(index) = queue.iter().position(|event| event.header.signal == signal) {
+                queue.remove(index);
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Dispatch-batch accounting for `run_until_idle`.
+//!
+//! Every `run_until_idle` call that dispatches at least one event emits one
+//! `QS_RUN_BATCH` record (id 56, a QP reserved slot used as a qp-rs
+//! extension) with payload `events: u16 | duration_us: u32`. A cooperative
+//! kernel returns to its idle loop only between batches, so the host-side
+//! profile of these records shows how long the idle loop — and everything
+//! it services — can be starved by an event storm.
+//!
+//! Durations come from the std monotonic clock, or on `no_std` targets from a
+//! microsecond counter installed with [`set_batch_clock`]; without one they
+//! are reported as 0.
+
+use crate::trace::TraceHook;
+
+/// Record id of the per-batch record.
+pub const QS_RUN_BATCH: u8 = qs_protocol::records::qf::RUN_BATCH;
+
+/// A free-running microsecond counter (wrapping).
+pub type BatchClock = fn() -> u32;
+
+/// The installed [`BatchClock`], or null.
+static CLOCK: portable_atomic::AtomicPtr<()> = portable_atomic::AtomicPtr::new(core::ptr::null_mut());
+
+/// Installs the microsecond counter used to time batches, replacing the
+/// default (the std monotonic clock, or none on `no_std`).
+pub fn set_batch_clock(clock: BatchClock) {
+    CLOCK.store(clock as *mut (), portable_atomic::Ordering::Release);
+}
+
+fn now_us() -> u32 {
+    let raw = CLOCK.load(portable_atomic::Ordering::Acquire);
+    if !raw.is_null() {
+        // SAFETY: `CLOCK` only ever holds null or a `BatchClock` stored by
+        // `set_batch_clock`.
+        let clock = unsafe { core::mem::transmute::<*mut (), BatchClock>(raw) };
+        return clock();
+    }
+    #[cfg(feature = "std")]
+    {
+        static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+        EPOCH.get_or_init(std::time::Instant::now).elapsed().as_micros() as u32
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        0
+    }
+}
+
+/// One `run_until_idle` pass in progress.
+pub struct Batch {
+    events: u32,
+    start:  u32,
+}
+
+impl Batch {
+    /// Starts timing a batch.
+    pub fn start() -> Self {
+        Self { events: 0, start: now_us() }
+    }
+
+    /// Counts one dispatched event.
+    pub fn count(&mut self) {
+        self.events = self.events.saturating_add(1);
+    }
+
+    /// Events counted so far.
+    pub fn events(&self) -> u32 {
+        self.events
+    }
+
+    /// Ends the batch, emitting its record through `trace` unless it was
+    /// empty.
+    pub fn finish(self, trace: Option<&TraceHook>) {
+        let Some(trace) = trace else { return };
+        if self.events == 0 {
+            return;
+        }
+        let duration = now_us().wrapping_sub(self.start);
+        let events = self.events.min(u16::MAX as u32) as u16;
+        let mut payload = [0u8; 6];
+        payload[..2].copy_from_slice(&events.to_le_bytes());
+        payload[2..].copy_from_slice(&duration.to_le_bytes());
+        let _ = trace(QS_RUN_BATCH, &payload, true);
+    }
+}
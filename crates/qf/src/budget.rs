@@ -0,0 +1,316 @@
+//! Run-to-completion budgets: how long one dispatch of an active object may
+//! take.
+//!
+//! Run-to-completion only keeps a system responsive while every step is
+//! short. A budget bounds the steps of one active object; the kernels time
+//! each dispatch of a budgeted AO and report a step that overran as a
+//! `QS_RTC_OVERRUN` record (id 82, a QP reserved slot used as a qp-rs
+//! extension) with payload `prio: u8 | signal: u16 | budget: u32 |
+//! elapsed: u32`, and through the callback set with
+//! [`RtcBudgets::on_overrun`].
+//!
+//! Budgets count in the units of the budget clock: microseconds of the std
+//! monotonic clock by default, or whatever [`set_budget_clock`] installs —
+//! typically a cycle counter such as the Cortex-M DWT `CYCCNT`. A `no_std`
+//! target without one falls back to [`time::now`](crate::time::now), so
+//! budgets are then in ticks.
+//!
+//! Under QK a step can be preempted by higher-priority AOs. The time spent in
+//! those nested steps is charged to them, not to the step they preempted.
+//! That bookkeeping assumes one core, so the `smp` build of QV does not time
+//! steps.
+//!
+//! With the `rtc-stats` feature every step is timed, budget or not, and the
+//! durations aggregate per priority into [`RtcStats`]: how many steps ran,
+//! their mean and longest duration, and the signal of the longest one.
+//! [`RtcBudgets::report`] lists them, [`RtcBudgets::slowest`] names the worst
+//! offender, and [`RtcBudgets::emit_stats`] sends one `QS_RTC_STATS` record
+//! (id 88) per priority with payload `prio: u8 | steps: u32 | mean: u32 |
+//! max: u32 | max_signal: u16 | overruns: u32`.
+
+#[cfg(feature = "rtc-stats")]
+use portable_atomic::{AtomicU16, AtomicU64};
+use portable_atomic::{AtomicU32, Ordering};
+
+use crate::active::ActiveRunnable;
+use crate::event::Signal;
+use crate::trace::TraceHook;
+
+/// Record id of a budget overrun.
+pub const QS_RTC_OVERRUN: u8 = qs_protocol::records::qf::RTC_OVERRUN;
+/// Record id of a priority's step statistics.
+#[cfg(feature = "rtc-stats")]
+pub const QS_RTC_STATS: u8 = qs_protocol::records::qf::RTC_STATS;
+
+/// Priorities a budget can be set for (`0..MAX_BUDGETS`).
+pub const MAX_BUDGETS: usize = 64;
+
+/// A free-running counter (wrapping) that budgets are measured against.
+pub type BudgetClock = fn() -> u32;
+
+/// Called for every overrun, in the context of the kernel that detected it.
+pub type OverrunCallback = fn(&Overrun);
+
+static CLOCK: spin::Mutex<Option<BudgetClock>> = spin::Mutex::new(None);
+
+/// Installs the counter budgets are measured against, replacing the default.
+pub fn set_budget_clock(clock: BudgetClock) {
+    *CLOCK.lock() = Some(clock);
+}
+
+pub(crate) fn now() -> u32 {
+    let clock = *CLOCK.lock();
+    if let Some(clock) = clock {
+        return clock();
+    }
+    #[cfg(feature = "std")]
+    {
+        static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+        EPOCH.get_or_init(std::time::Instant::now).elapsed().as_micros() as u32
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        crate::time::now().ticks()
+    }
+}
+
+/// One step that took longer than its budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overrun {
+    /// Priority of the active object.
+    pub priority: u8,
+    /// Signal of the event it was handling.
+    pub signal: Signal,
+    /// The budget, in clock units.
+    pub budget: u32,
+    /// Time the step took, less any steps that preempted it.
+    pub elapsed: u32,
+}
+
+/// Durations of the steps of one active object, in clock units.
+#[cfg(feature = "rtc-stats")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RtcStats {
+    /// Steps timed.
+    pub steps: u32,
+    /// Sum of their durations.
+    pub total: u64,
+    /// Longest step.
+    pub max: u32,
+    /// Signal of the event the longest step handled.
+    pub max_signal: Signal,
+    /// Steps that overran the budget.
+    pub overruns: u32,
+}
+
+#[cfg(feature = "rtc-stats")]
+impl RtcStats {
+    /// Mean step duration, or `None` before the first step.
+    pub fn mean(&self) -> Option<u32> {
+        (self.steps > 0).then(|| (self.total / u64::from(self.steps)) as u32)
+    }
+}
+
+/// [`RtcStats`] of one priority, updated by the step that runs at it.
+#[cfg(feature = "rtc-stats")]
+struct StatSlot {
+    steps: AtomicU32,
+    total: AtomicU64,
+    max: AtomicU32,
+    max_signal: AtomicU16,
+    overruns: AtomicU32,
+}
+
+#[cfg(feature = "rtc-stats")]
+impl StatSlot {
+    const fn new() -> Self {
+        Self {
+            steps: AtomicU32::new(0),
+            total: AtomicU64::new(0),
+            max: AtomicU32::new(0),
+            max_signal: AtomicU16::new(0),
+            overruns: AtomicU32::new(0),
+        }
+    }
+
+    fn record(&self, signal: Signal, elapsed: u32, overrun: bool) {
+        self.steps.fetch_add(1, Ordering::Relaxed);
+        self.total.fetch_add(u64::from(elapsed), Ordering::Relaxed);
+        if elapsed > self.max.load(Ordering::Relaxed) || self.steps.load(Ordering::Relaxed) == 1 {
+            self.max.store(elapsed, Ordering::Relaxed);
+            self.max_signal.store(signal.0, Ordering::Relaxed);
+        }
+        if overrun {
+            self.overruns.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn load(&self) -> RtcStats {
+        RtcStats {
+            steps: self.steps.load(Ordering::Relaxed),
+            total: self.total.load(Ordering::Relaxed),
+            max: self.max.load(Ordering::Relaxed),
+            max_signal: Signal(self.max_signal.load(Ordering::Relaxed)),
+            overruns: self.overruns.load(Ordering::Relaxed),
+        }
+    }
+
+    fn reset(&self) {
+        self.steps.store(0, Ordering::Relaxed);
+        self.total.store(0, Ordering::Relaxed);
+        self.max.store(0, Ordering::Relaxed);
+        self.max_signal.store(0, Ordering::Relaxed);
+        self.overruns.store(0, Ordering::Relaxed);
+    }
+}
+
+/// The budgets of one kernel's active objects, by priority.
+pub struct RtcBudgets {
+    budgets: [u32; MAX_BUDGETS],
+    any: bool,
+    callback: Option<OverrunCallback>,
+    /// Clock units spent in completed steps, nested ones included, for
+    /// taking preemption out of the steps they interrupted.
+    spent: AtomicU32,
+    overruns: AtomicU32,
+    #[cfg(feature = "rtc-stats")]
+    stats: [StatSlot; MAX_BUDGETS],
+}
+
+impl Default for RtcBudgets {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RtcBudgets {
+    /// No budgets.
+    pub const fn new() -> Self {
+        Self {
+            budgets: [0; MAX_BUDGETS],
+            any: false,
+            callback: None,
+            spent: AtomicU32::new(0),
+            overruns: AtomicU32::new(0),
+            #[cfg(feature = "rtc-stats")]
+            stats: [const { StatSlot::new() }; MAX_BUDGETS],
+        }
+    }
+
+    /// Bounds each step of the AO at `priority` to `budget` clock units;
+    /// `0` removes the bound. Returns `false` if `priority` is out of range.
+    pub fn set(&mut self, priority: u8, budget: u32) -> bool {
+        let Some(slot) = self.budgets.get_mut(usize::from(priority)) else {
+            return false;
+        };
+        *slot = budget;
+        self.any = self.budgets.iter().any(|&b| b != 0);
+        true
+    }
+
+    /// The budget of the AO at `priority`, if it has one.
+    pub fn get(&self, priority: u8) -> Option<u32> {
+        self.budgets.get(usize::from(priority)).copied().filter(|&b| b != 0)
+    }
+
+    /// Calls `callback` for every overrun, after its record is emitted.
+    pub fn on_overrun(&mut self, callback: OverrunCallback) {
+        self.callback = Some(callback);
+    }
+
+    /// Overruns detected so far.
+    pub fn overruns(&self) -> u32 {
+        self.overruns.load(Ordering::Relaxed)
+    }
+
+    /// Dispatches one event of `ao`, timing it when any budget is set (or
+    /// always, with `rtc-stats`). The kernels call this in place of
+    /// [`dispatch_one`](ActiveRunnable::dispatch_one).
+    ///
+    /// Every step is timed once a budget exists, budgeted or not, so that a
+    /// preempting step's time can be taken out of the step it preempted.
+    pub fn dispatch(&self, ao: &dyn ActiveRunnable, trace: Option<&TraceHook>) -> bool {
+        if !self.any && !cfg!(feature = "rtc-stats") {
+            return ao.dispatch_one();
+        }
+        let signal = ao.front_signal().unwrap_or_default();
+        let spent_before = self.spent.load(Ordering::Relaxed);
+        let start = now();
+        let handled = ao.dispatch_one();
+        let total = now().wrapping_sub(start);
+        let nested = self.spent.load(Ordering::Relaxed).wrapping_sub(spent_before);
+        self.spent.store(spent_before.wrapping_add(total), Ordering::Relaxed);
+
+        if !handled {
+            return false;
+        }
+        let priority = ao.priority();
+        let elapsed = total.saturating_sub(nested);
+        let budget = self.get(priority);
+        let overrun = budget.is_some_and(|budget| elapsed > budget);
+        #[cfg(feature = "rtc-stats")]
+        if let Some(slot) = self.stats.get(usize::from(priority)) {
+            slot.record(signal, elapsed, overrun);
+        }
+        if let (Some(budget), true) = (budget, overrun) {
+            self.report_overrun(&Overrun { priority, signal, budget, elapsed }, trace);
+        }
+        true
+    }
+
+    /// Step statistics of the AO at `priority`, or `None` if none of its
+    /// steps has been timed.
+    #[cfg(feature = "rtc-stats")]
+    pub fn stats(&self, priority: u8) -> Option<RtcStats> {
+        let stats = self.stats.get(usize::from(priority))?.load();
+        (stats.steps > 0).then_some(stats)
+    }
+
+    /// Step statistics of every priority that has run, lowest first.
+    #[cfg(feature = "rtc-stats")]
+    pub fn report(&self) -> impl Iterator<Item = (u8, RtcStats)> + '_ {
+        (0..MAX_BUDGETS as u8).filter_map(|priority| Some((priority, self.stats(priority)?)))
+    }
+
+    /// The priority whose longest step is the longest of all.
+    #[cfg(feature = "rtc-stats")]
+    pub fn slowest(&self) -> Option<(u8, RtcStats)> {
+        self.report().max_by_key(|(_, stats)| stats.max)
+    }
+
+    /// Starts the statistics afresh, e.g. after start-up.
+    #[cfg(feature = "rtc-stats")]
+    pub fn reset_stats(&self) {
+        self.stats.iter().for_each(StatSlot::reset);
+    }
+
+    /// Emits a `QS_RTC_STATS` record for every priority that has run.
+    #[cfg(feature = "rtc-stats")]
+    pub fn emit_stats(&self, trace: &TraceHook) {
+        for (priority, stats) in self.report() {
+            let mut payload = [0u8; 19];
+            payload[0] = priority;
+            payload[1..5].copy_from_slice(&stats.steps.to_le_bytes());
+            payload[5..9].copy_from_slice(&stats.mean().unwrap_or(0).to_le_bytes());
+            payload[9..13].copy_from_slice(&stats.max.to_le_bytes());
+            payload[13..15].copy_from_slice(&stats.max_signal.0.to_le_bytes());
+            payload[15..].copy_from_slice(&stats.overruns.to_le_bytes());
+            let _ = trace(QS_RTC_STATS, &payload, true);
+        }
+    }
+
+    fn report_overrun(&self, overrun: &Overrun, trace: Option<&TraceHook>) {
+        self.overruns.fetch_add(1, Ordering::Relaxed);
+        if let Some(trace) = trace {
+            let mut payload = [0u8; 11];
+            payload[0] = overrun.priority;
+            payload[1..3].copy_from_slice(&overrun.signal.0.to_le_bytes());
+            payload[3..7].copy_from_slice(&overrun.budget.to_le_bytes());
+            payload[7..].copy_from_slice(&overrun.elapsed.to_le_bytes());
+            let _ = trace(QS_RTC_OVERRUN, &payload, true);
+        }
+        if let Some(callback) = self.callback {
+            callback(overrun);
+        }
+    }
+}
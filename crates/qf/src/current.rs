@@ -0,0 +1,91 @@
+//! The currently dispatching active object.
+//!
+//! [`ActiveObject`](crate::ActiveObject) marks itself current for the length
+//! of its `on_start` and every `on_event` call, whichever kernel drives it,
+//! so code without a context handle (allocators, drivers, trace helpers) can
+//! attribute work to the AO that caused it. Dispatches nest under a preemptive kernel; leaving
+//! a dispatch restores the AO it preempted.
+//!
+//! With `std` the marker is thread-local, so threaded kernels and host tests
+//! each see their own dispatch. Without `std` it is a single global, which is
+//! exact on one core (QV/QK/QXK all dispatch from one stack at a time).
+//!
+//! With the `qs` feature this is also the QS-ID source for local filtering:
+//! the first dispatch installs [`current_ao`] as
+//! [`qs::set_qs_id_source`], so records emitted from a dispatch are
+//! attributed to the dispatching AO.
+
+use crate::active::ActiveObjectId;
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static CURRENT: core::cell::Cell<Option<ActiveObjectId>> =
+        const { core::cell::Cell::new(None) };
+}
+
+#[cfg(not(feature = "std"))]
+static CURRENT: portable_atomic::AtomicU16 = portable_atomic::AtomicU16::new(NONE);
+#[cfg(not(feature = "std"))]
+const NONE: u16 = u16::MAX;
+
+/// Returns the active object whose event handler is executing, if any.
+#[inline]
+pub fn current_ao() -> Option<ActiveObjectId> {
+    #[cfg(feature = "std")]
+    {
+        CURRENT.try_with(|c| c.get()).ok().flatten()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        match CURRENT.load(portable_atomic::Ordering::Relaxed) {
+            NONE => None,
+            id => Some(ActiveObjectId(id as u8)),
+        }
+    }
+}
+
+fn replace(id: Option<ActiveObjectId>) -> Option<ActiveObjectId> {
+    #[cfg(feature = "std")]
+    {
+        CURRENT.try_with(|c| c.replace(id)).ok().flatten()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        // Load + store rather than swap, as `qs::QsIdScope` does: a
+        // preempting dispatch restores what it found before this one resumes.
+        let prev = current_ao();
+        CURRENT.store(id.map_or(NONE, |id| id.0 as u16), portable_atomic::Ordering::Relaxed);
+        prev
+    }
+}
+
+/// Marks `id` current until dropped, then restores the previous marker.
+pub(crate) struct DispatchScope {
+    prev: Option<ActiveObjectId>,
+}
+
+impl DispatchScope {
+    pub(crate) fn enter(id: ActiveObjectId) -> Self {
+        #[cfg(feature = "qs")]
+        install_qs_id_source();
+        Self { prev: replace(Some(id)) }
+    }
+}
+
+#[cfg(feature = "qs")]
+fn install_qs_id_source() {
+    use portable_atomic::{AtomicBool, Ordering};
+
+    // Installing twice is harmless, so a plain load and store will do.
+    static INSTALLED: AtomicBool = AtomicBool::new(false);
+    if !INSTALLED.load(Ordering::Relaxed) {
+        qs::set_qs_id_source(Some(|| current_ao().map(|id| id.0)));
+        INSTALLED.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for DispatchScope {
+    fn drop(&mut self) {
+        replace(self.prev);
+    }
+}
@@ -32,8 +32,9 @@ use alloc::collections::VecDeque;
 use std::collections::VecDeque;
 
 use crate::active::ActiveRunnable;
-use crate::event::DynEvent;
+use crate::event::{DynEvent, EventHeader};
 use crate::sync::Mutex;
+use crate::trace::TraceHook;
 
 // ── Safety-margin / graceful-degradation policy ───────────────────────────────
 
@@ -267,21 +268,39 @@ impl QEQueue {
     }
 }
 
-// ── StaticEQueue (heap-free, `static-alloc` feature) ───────────────────────────
+/// Answers host queries as [`EQ_OBJ`](qs::query::ObjKind::Eq).
+#[cfg(feature = "qs")]
+impl qs::query::Queryable for QEQueue {
+    fn query(&self, kind: qs::query::ObjKind) -> Option<qs::query::Snapshot> {
+        (kind == qs::query::ObjKind::Eq).then(|| qs::query::Snapshot::Eq {
+            n_free: self.get_free() as u32,
+            n_min: self.get_min() as u32,
+        })
+    }
+}
 
-#[cfg(feature = "static-alloc")]
+// ── StaticEQueue (const-generic, heap-free) ────────────────────────────────────
+
+// QS record IDs for raw event-queue operations.
+use qs_protocol::records::qf as rec;
+
+const PTR_SIZE: usize = core::mem::size_of::<usize>();
+
+/// Inline ring of `N` slots: `len` events starting at `head`, wrapping.
 struct StaticEQueueInner<const N: usize> {
-    buffer: heapless::Deque<DynEvent, N>,
+    ring: [Option<DynEvent>; N],
+    head: usize,
+    len: usize,
     min_free: usize,
     safety_margin: usize,
     shed_count: usize,
+    trace: Option<TraceHook>,
 }
 
-#[cfg(feature = "static-alloc")]
 impl<const N: usize> StaticEQueueInner<N> {
     #[inline]
     fn update_watermark(&mut self) {
-        let free = N.saturating_sub(self.buffer.len());
+        let free = self.free();
         if free < self.min_free {
             self.min_free = free;
         }
@@ -289,16 +308,71 @@ impl<const N: usize> StaticEQueueInner<N> {
 
     #[inline]
     fn free(&self) -> usize {
-        N.saturating_sub(self.buffer.len())
+        N - self.len
+    }
+
+    /// Caller guarantees a free slot.
+    fn push_back(&mut self, event: DynEvent) {
+        self.ring[(self.head + self.len) % N] = Some(event);
+        self.len += 1;
+        self.update_watermark();
+    }
+
+    /// Caller guarantees a free slot.
+    fn push_front(&mut self, event: DynEvent) {
+        self.head = (self.head + N - 1) % N;
+        self.ring[self.head] = Some(event);
+        self.len += 1;
+        self.update_watermark();
+    }
+
+    fn pop_front(&mut self) -> Option<DynEvent> {
+        if self.len == 0 {
+            return None;
+        }
+        let event = self.ring[self.head].take();
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        // A filled slot is an invariant of `len`; an empty one means the ring
+        // bookkeeping is corrupt.
+        if event.is_none() {
+            crate::fusa::on_error(module_path!(), line!());
+        }
+        event
     }
 }
 
-/// Fixed-capacity, **heap-free** FIFO event queue with watermark tracking.
+/// `[sig | eq | pool | ref | free | last]`, QS `EQUEUE_*` layout with
+/// one-byte counters (`QF_EQUEUE_CTR_SIZE = 1`), saturating.
+fn eq_payload(eq: usize, header: &EventHeader, free: usize, last: usize) -> [u8; PTR_SIZE + 6] {
+    let mut buf = [0u8; PTR_SIZE + 6];
+    buf[..2].copy_from_slice(&header.signal.0.to_le_bytes());
+    buf[2..2 + PTR_SIZE].copy_from_slice(&eq.to_le_bytes());
+    buf[2 + PTR_SIZE] = header.pool_id.unwrap_or(0);
+    buf[3 + PTR_SIZE] = header.ref_count;
+    buf[4 + PTR_SIZE] = free.min(u8::MAX as usize) as u8;
+    buf[5 + PTR_SIZE] = last.min(u8::MAX as usize) as u8;
+    buf
+}
+
+/// A queue trace record captured under the lock: hook, record id, payload
+/// and payload length.
+type EqRecord = (TraceHook, u8, [u8; PTR_SIZE + 6], usize);
+
+fn emit(record: Option<EqRecord>) {
+    if let Some((hook, rec, buf, len)) = record {
+        let _ = hook(rec, &buf[..len], true);
+    }
+}
+
+/// Fixed-capacity, **heap-free** FIFO event queue with watermark tracking and
+/// QS `EQUEUE_*` tracing.
 ///
-/// A drop-in analogue of [`QEQueue`] whose storage lives entirely inline — no
-/// `VecDeque`, no heap — making it suitable for the `no_std` + `static-alloc`
-/// functional-safety build (see `docs/FUSA.md`, Phase 2). Capacity is fixed at
-/// the type level by the const generic `N`.
+/// A drop-in analogue of [`QEQueue`] whose capacity is fixed at the type level
+/// by the const generic `N` and whose storage is an inline ring of `N` slots —
+/// no `VecDeque`, no heap. It is available in every build and is the queue of
+/// choice for the `no_std` + `static-alloc` functional-safety build (see
+/// `docs/FUSA.md`, Phase 2).
 ///
 /// Traceability: ASR-003 (static allocation); see `docs/traceability.md`.
 ///
@@ -306,25 +380,27 @@ impl<const N: usize> StaticEQueueInner<N> {
 /// storage with no runtime initialisation:
 ///
 /// ```
-/// # #[cfg(feature = "static-alloc")] {
 /// use qf::equeue::StaticEQueue;
 /// static DEFER_Q: StaticEQueue<8> = StaticEQueue::new();
-/// # }
 /// ```
-#[cfg(feature = "static-alloc")]
+///
+/// With a trace hook installed ([`set_trace_hook`](Self::set_trace_hook)) the
+/// queue emits `QS_QF_EQUEUE_POST` / `POST_LIFO` / `GET` for every accepted
+/// operation and `QS_QF_EQUEUE_POST_ATTEMPT` for every refused post, carrying
+/// the free count and the low-watermark (or, for attempts, the margin).
 pub struct StaticEQueue<const N: usize> {
     inner: Mutex<StaticEQueueInner<N>>,
 }
 
-#[cfg(feature = "static-alloc")]
 impl<const N: usize> Default for StaticEQueue<N> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-#[cfg(feature = "static-alloc")]
 impl<const N: usize> StaticEQueue<N> {
+    const NON_EMPTY: () = assert!(N > 0, "StaticEQueue needs at least one slot");
+
     /// Create an empty queue with inline capacity `N`.
     pub const fn new() -> Self {
         Self::with_safety_margin(0)
@@ -335,18 +411,40 @@ impl<const N: usize> StaticEQueue<N> {
     ///
     /// `const`, so a margin-configured queue can live in `static` storage.
     pub const fn with_safety_margin(margin: usize) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::NON_EMPTY;
         // `margin` is clamped to `N` lazily at use; storing it raw keeps this
         // `const`. (A margin >= N simply sheds all normal-priority traffic.)
         Self {
             inner: Mutex::new(StaticEQueueInner {
-                buffer: heapless::Deque::new(),
+                ring: [const { None }; N],
+                head: 0,
+                len: 0,
                 min_free: N,
                 safety_margin: margin,
                 shed_count: 0,
+                trace: None,
             }),
         }
     }
 
+    /// Install (or remove) the QS trace hook and, when installed, emit
+    /// `QS_QF_EQUEUE_INIT` for this queue.
+    pub fn set_trace_hook(&self, hook: Option<TraceHook>) {
+        self.inner.lock().trace = hook.clone();
+        if let Some(hook) = hook {
+            let mut buf = [0u8; PTR_SIZE + 1];
+            buf[..PTR_SIZE].copy_from_slice(&self.addr().to_le_bytes());
+            buf[PTR_SIZE] = N.min(u8::MAX as usize) as u8;
+            let _ = hook(rec::EQUEUE_INIT, &buf, true);
+        }
+    }
+
+    #[inline]
+    fn addr(&self) -> usize {
+        self as *const Self as usize
+    }
+
     /// The configured safety margin (reserved free slots for critical traffic).
     pub fn safety_margin(&self) -> usize {
         self.inner.lock().safety_margin
@@ -378,18 +476,22 @@ impl<const N: usize> StaticEQueue<N> {
     /// status reports whether the queue has now entered the degraded band.
     pub fn post_normal(&self, event: DynEvent) -> PostStatus {
         let mut inner = self.inner.lock();
-        if inner.free() > inner.safety_margin {
-            if inner.buffer.push_back(event).is_err() {
-                crate::fusa::on_error(module_path!(), line!());
-            }
-            inner.update_watermark();
-            if inner.free() <= inner.safety_margin {
+        let margin = inner.safety_margin;
+        if inner.free() > margin {
+            let record = self.push(&mut inner, event, false);
+            let status = if inner.free() <= margin {
                 PostStatus::AcceptedDegraded
             } else {
                 PostStatus::Accepted
-            }
+            };
+            drop(inner);
+            emit(record);
+            status
         } else {
             inner.shed_count += 1;
+            let record = self.attempt(&inner, &event, margin);
+            drop(inner);
+            emit(record);
             PostStatus::Shed
         }
     }
@@ -405,54 +507,81 @@ impl<const N: usize> StaticEQueue<N> {
     /// `margin = 0` succeeds whenever any slot is free.
     pub fn post(&self, event: DynEvent, margin: usize) -> bool {
         let mut inner = self.inner.lock();
-        let free = N.saturating_sub(inner.buffer.len());
-        if free > margin {
-            // `free > margin >= 0` guarantees a free slot, so `push_back`
-            // cannot fail. A failure would mean the length/capacity accounting
-            // is corrupt — a data-integrity invariant violation.
-            if inner.buffer.push_back(event).is_err() {
-                crate::fusa::on_error(module_path!(), line!());
-            }
-            inner.update_watermark();
-            true
+        let accepted = inner.free() > margin;
+        let record = if accepted {
+            self.push(&mut inner, event, false)
         } else {
-            false
-        }
+            self.attempt(&inner, &event, margin)
+        };
+        drop(inner);
+        emit(record);
+        accepted
     }
 
     /// Post `event` LIFO (to the front). Returns `false` if the queue is full.
     pub fn post_lifo(&self, event: DynEvent) -> bool {
         let mut inner = self.inner.lock();
-        if inner.buffer.len() < N {
-            if inner.buffer.push_front(event).is_err() {
-                crate::fusa::on_error(module_path!(), line!());
-            }
-            inner.update_watermark();
-            true
+        let accepted = inner.free() > 0;
+        let record = if accepted {
+            self.push(&mut inner, event, true)
         } else {
-            false
-        }
+            self.attempt(&inner, &event, 0)
+        };
+        drop(inner);
+        emit(record);
+        accepted
     }
 
     /// Remove and return the front event. Returns `None` if empty.
     pub fn get(&self) -> Option<DynEvent> {
-        self.inner.lock().buffer.pop_front()
+        let mut inner = self.inner.lock();
+        let event = inner.pop_front()?;
+        let record = inner.trace.clone().map(|hook| {
+            // `QS_QF_EQUEUE_GET` carries no watermark: drop the last byte.
+            let buf = eq_payload(self.addr(), &event.header, inner.free(), 0);
+            (hook, rec::EQUEUE_GET, buf, PTR_SIZE + 5)
+        });
+        drop(inner);
+        emit(record);
+        Some(event)
+    }
+
+    /// Enqueue into a slot the caller checked is free; returns the trace
+    /// record to emit once the lock is released.
+    fn push(&self, inner: &mut StaticEQueueInner<N>, event: DynEvent, lifo: bool) -> Option<EqRecord> {
+        let header = event.header;
+        if lifo {
+            inner.push_front(event);
+        } else {
+            inner.push_back(event);
+        }
+        let record = if lifo { rec::EQUEUE_POST_LIFO } else { rec::EQUEUE_POST };
+        inner.trace.clone().map(|hook| {
+            (hook, record, eq_payload(self.addr(), &header, inner.free(), inner.min_free), PTR_SIZE + 6)
+        })
+    }
+
+    /// Trace record for a refused post: the margin takes the watermark's slot.
+    fn attempt(&self, inner: &StaticEQueueInner<N>, event: &DynEvent, margin: usize) -> Option<EqRecord> {
+        inner.trace.clone().map(|hook| {
+            let buf = eq_payload(self.addr(), &event.header, inner.free(), margin);
+            (hook, rec::EQUEUE_POST_ATTEMPT, buf, PTR_SIZE + 6)
+        })
     }
 
     /// `true` if the queue holds at least one event.
     pub fn peek_front(&self) -> bool {
-        !self.inner.lock().buffer.is_empty()
+        self.inner.lock().len != 0
     }
 
     /// `true` if the queue currently holds no events.
     pub fn is_empty(&self) -> bool {
-        self.inner.lock().buffer.is_empty()
+        self.inner.lock().len == 0
     }
 
     /// Number of free slots currently available.
     pub fn get_free(&self) -> usize {
-        let inner = self.inner.lock();
-        N.saturating_sub(inner.buffer.len())
+        self.inner.lock().free()
     }
 
     /// Minimum free slots ever observed (low-watermark, sticky).
@@ -462,7 +591,7 @@ impl<const N: usize> StaticEQueue<N> {
 
     /// Number of events currently in the queue.
     pub fn len(&self) -> usize {
-        self.inner.lock().buffer.len()
+        self.inner.lock().len
     }
 
     /// Maximum number of events the queue can hold (the const capacity `N`).
@@ -471,6 +600,17 @@ impl<const N: usize> StaticEQueue<N> {
     }
 }
 
+/// Answers host queries as [`EQ_OBJ`](qs::query::ObjKind::Eq).
+#[cfg(feature = "qs")]
+impl<const N: usize> qs::query::Queryable for StaticEQueue<N> {
+    fn query(&self, kind: qs::query::ObjKind) -> Option<qs::query::Snapshot> {
+        (kind == qs::query::ObjKind::Eq).then(|| qs::query::Snapshot::Eq {
+            n_free: self.get_free() as u32,
+            n_min: self.get_min() as u32,
+        })
+    }
+}
+
 // ── Defer / Recall ────────────────────────────────────────────────────────────
 
 /// Defer `event` to queue `eq` on behalf of active object `ao`.
@@ -554,7 +694,7 @@ mod safety_margin_tests {
     }
 }
 
-#[cfg(all(test, feature = "static-alloc"))]
+#[cfg(test)]
 mod static_tests {
     use super::{PostStatus, StaticEQueue};
     use crate::event::{DynEvent, Signal};
@@ -631,4 +771,55 @@ mod static_tests {
         q.get();
         assert_eq!(q.get_min(), 1);
     }
+
+    #[test]
+    fn ring_wraps_in_both_directions() {
+        let q: StaticEQueue<3> = StaticEQueue::new();
+        for round in 0..4u16 {
+            assert!(q.post(ev(round * 10), 0));
+            assert!(q.post(ev(round * 10 + 1), 0));
+            assert!(q.post_lifo(ev(round * 10 + 2)));
+            assert!(!q.post_lifo(ev(99)));
+            assert_eq!(q.get().unwrap().signal(), Signal(round * 10 + 2));
+            assert_eq!(q.get().unwrap().signal(), Signal(round * 10));
+            assert_eq!(q.get().unwrap().signal(), Signal(round * 10 + 1));
+            assert!(q.get().is_none());
+        }
+    }
+
+    #[cfg(not(feature = "static-alloc"))]
+    #[test]
+    fn operations_emit_equeue_records() {
+        use crate::sync::{Arc, Mutex};
+        use crate::trace::TraceHook;
+
+        let records = Arc::new(Mutex::new(Vec::<(u8, Vec<u8>)>::new()));
+        let sink = Arc::clone(&records);
+        let hook: TraceHook = Arc::new(move |rec, payload, _| {
+            sink.lock().push((rec, payload.to_vec()));
+            Ok(())
+        });
+
+        let q: StaticEQueue<2> = StaticEQueue::new();
+        q.set_trace_hook(Some(hook));
+        assert!(q.post(ev(7), 0));
+        assert!(q.post_lifo(ev(8)));
+        assert!(!q.post(ev(9), 0));
+        q.get();
+
+        let ptr = core::mem::size_of::<usize>();
+        let records = records.lock();
+        let summary: Vec<(u8, u16, u8, Option<u8>)> = records.iter().skip(1).map(|(rec, p)| {
+            let sig = u16::from_le_bytes([p[0], p[1]]);
+            (*rec, sig, p[4 + ptr], p.get(5 + ptr).copied())
+        }).collect();
+        assert_eq!(records[0].0, super::rec::EQUEUE_INIT);
+        assert_eq!(records[0].1[ptr], 2);
+        assert_eq!(summary, vec![
+            (super::rec::EQUEUE_POST, 7, 1, Some(1)),
+            (super::rec::EQUEUE_POST_LIFO, 8, 0, Some(0)),
+            (super::rec::EQUEUE_POST_ATTEMPT, 9, 0, Some(0)),
+            (super::rec::EQUEUE_GET, 8, 1, None),
+        ]);
+    }
 }
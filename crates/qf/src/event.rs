@@ -8,6 +8,7 @@
 #[cfg(not(feature = "static-alloc"))]
 use core::any::Any;
 use core::fmt;
+use core::marker::PhantomData;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
@@ -29,6 +30,13 @@ impl From<u16> for Signal {
     }
 }
 
+impl From<Signal> for u16 {
+    #[inline]
+    fn from(signal: Signal) -> Self {
+        signal.0
+    }
+}
+
 impl fmt::Display for Signal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "SIG({:#06x})", self.0)
@@ -127,10 +135,34 @@ pub type DynEvent = Event<DynPayload>;
 
 impl Event<DynPayload> {
     /// Creates a dynamic event from an already type-erased payload.
+    ///
+    /// Under `static-alloc` the header's `pool_id` is taken from the payload's
+    /// pool block.
     pub fn with_arc(signal: Signal, payload: DynPayload) -> Self {
+        #[cfg(feature = "static-alloc")]
+        {
+            let mut event = Self::new(signal, payload);
+            event.header.pool_id = event.payload.pool_id();
+            event
+        }
+        #[cfg(not(feature = "static-alloc"))]
         Self::new(signal, payload)
     }
 
+    /// Number of live references to the event, saturated to the `u8` QS
+    /// records carry.
+    ///
+    /// Under `static-alloc` the count lives in the payload's pool block and a
+    /// signal-only event reports `0`, like a QP static event; the dynamic
+    /// build reports the payload's `Arc` strong count.
+    pub fn ref_count(&self) -> u8 {
+        #[cfg(feature = "static-alloc")]
+        let count = self.payload.ref_count();
+        #[cfg(not(feature = "static-alloc"))]
+        let count = Arc::strong_count(&self.payload);
+        u8::try_from(count).unwrap_or(u8::MAX)
+    }
+
     /// Creates a signal-only dynamic event (unit payload).
     ///
     /// Allocation-free under `static-alloc` (the empty [`PoolArc`] variant).
@@ -156,3 +188,84 @@ impl Event<DynPayload> {
         Self::with_arc(signal, payload)
     }
 }
+
+/// A signal statically bound to the payload type `T` its events carry.
+///
+/// Declared once next to the signal (see [`typed`] and
+/// [`typed_signals!`](crate::typed_signals)), it builds events with the right
+/// payload and recovers `&T` from a [`DynEvent`] without a
+/// `downcast_ref` chain:
+///
+/// ```
+/// use qf::event::{typed, EventOf};
+/// use qf::Signal;
+///
+/// const HUNGRY: EventOf<u8> = typed(Signal(11));
+///
+/// # // Under `static-alloc` the payload needs an initialised event pool.
+/// # #[cfg(not(feature = "static-alloc"))] {
+/// let event = HUNGRY.event(3);
+/// assert_eq!(HUNGRY.payload(&event), Some(&3));
+/// # }
+/// ```
+pub struct EventOf<T> {
+    signal: Signal,
+    payload: PhantomData<fn() -> T>,
+}
+
+/// Binds `signal` to the payload type `T`.
+pub const fn typed<T>(signal: Signal) -> EventOf<T> {
+    EventOf { signal, payload: PhantomData }
+}
+
+impl<T> EventOf<T> {
+    /// The bound signal.
+    pub const fn signal(&self) -> Signal {
+        self.signal
+    }
+
+    /// Whether `event` carries the bound signal.
+    pub fn matches<P>(&self, event: &Event<P>) -> bool {
+        event.signal() == self.signal
+    }
+}
+
+impl<T: core::any::Any + Send + Sync> EventOf<T> {
+    /// A dynamic event with the bound signal carrying `payload`.
+    pub fn event(&self, payload: T) -> DynEvent {
+        DynEvent::with_payload(self.signal, payload)
+    }
+
+    /// The payload of `event`, if it carries the bound signal.
+    ///
+    /// An event with the bound signal but another payload type is a
+    /// programming error: it panics in debug builds and yields `None`
+    /// otherwise.
+    pub fn payload<'a>(&self, event: &'a DynEvent) -> Option<&'a T> {
+        if !self.matches(event) {
+            return None;
+        }
+        let payload = event.payload.downcast_ref::<T>();
+        debug_assert!(
+            payload.is_some(),
+            "{} carries a payload other than {}",
+            self.signal,
+            core::any::type_name::<T>()
+        );
+        payload
+    }
+}
+
+impl<T> Clone for EventOf<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for EventOf<T> {}
+
+impl<T> fmt::Debug for EventOf<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EventOf<{}>({})", core::any::type_name::<T>(), self.signal)
+    }
+}
@@ -26,11 +26,7 @@ use crate::sync::Arc;
 use crate::trace::TraceHook;
 
 // QS record IDs for event pool operations.
-const QS_QF_NEW:         u8 = 28;
-const QS_QF_GC:          u8 = 30;
-const QS_QF_MPOOL_GET:   u8 = 24;
-const QS_QF_MPOOL_PUT:   u8 = 25;
-const QS_QF_NEW_ATTEMPT: u8 = 23;
+use qs_protocol::records::qf as rec;
 
 /// Maximum number of pools that can be registered (matches QF_MAX_EPOOL).
 pub const MAX_POOLS: usize = 15;
@@ -151,7 +147,6 @@ impl PoolRegistry {
         drop(slot);
         if let Some(hook) = trace {
             emit_mpool_put(hook, pool_id, free as u16, total as u16);
-            emit_gc(hook, pool_id);
         }
     }
 
@@ -270,7 +265,9 @@ impl<T: 'static> EventBox<T> {
         // live allocation of this pool; the block is no longer referenced.
         unsafe { gc_raw(pool_id, raw_ptr as *mut u8, None) };
         let payload = crate::pool_arc::PoolArc::from_value(event.payload);
-        Event { header: event.header, payload }
+        let mut header = event.header;
+        header.pool_id = payload.pool_id();
+        Event { header, payload }
     }
 }
 
@@ -374,16 +371,17 @@ pub fn q_new_x<T: Send + Sync + 'static>(
     }
 }
 
-/// Garbage-collect a reference to a pool event.
-///
-/// For `Arc`-wrapped events the actual deallocation happens when the last
-/// `Arc` clone is dropped (via the embedded [`PoolBlock`] guard).  This
-/// function is provided for API completeness and optionally emits a QS trace.
+/// Traces the release of the caller's reference to a dynamic event,
+/// mirroring QP/C++ `QF::gc(e)`.
 ///
-/// Equivalent to QP/C++ `QF::gc(e)`.
+/// Every active object hands the event it just processed to `gc`, then drops
+/// its reference. If other references remain — the event was published to
+/// several subscribers, or deferred — the call traces `QS_QF_GC_ATTEMPT`.
+/// The last reference traces `QS_QF_GC`, and dropping it returns the pool
+/// block. Events that did not come from a pool are not traced.
 pub fn gc(event: &crate::event::DynEvent, trace: Option<&TraceHook>) {
     if let (Some(pool_id), Some(hook)) = (event.header.pool_id, trace) {
-        emit_gc(hook, pool_id);
+        emit_gc(hook, event.header.signal, pool_id, event.ref_count());
     }
 }
 
@@ -401,23 +399,28 @@ unsafe fn gc_raw(pool_id: u8, ptr: *mut u8, trace: Option<&TraceHook>) {
 // ── QS trace helpers ──────────────────────────────────────────────────────────
 
 fn emit_new(hook: &TraceHook, size: u16, pool_id: u8) {
-    let _ = hook(QS_QF_NEW, &[(size & 0xFF) as u8, (size >> 8) as u8, pool_id], true);
+    let _ = hook(rec::NEW, &[(size & 0xFF) as u8, (size >> 8) as u8, pool_id], true);
 }
 
 fn emit_new_attempt(hook: &TraceHook, size: u16, pool_id: u8, free: u16, total: u16) {
-    let _ = hook(QS_QF_NEW_ATTEMPT, &[
+    let _ = hook(rec::NEW_ATTEMPT, &[
         (size & 0xFF) as u8, (size >> 8) as u8, pool_id,
         (free & 0xFF) as u8, (free >> 8) as u8,
         (total & 0xFF) as u8, (total >> 8) as u8,
     ], true);
 }
 
-fn emit_gc(hook: &TraceHook, pool_id: u8) {
-    let _ = hook(QS_QF_GC, &[pool_id], true);
+#[cfg_attr(not(feature = "qs"), allow(unused_variables))]
+fn emit_gc(hook: &TraceHook, signal: Signal, pool_id: u8, ref_ctr: u8) {
+    if ref_ctr > 1 {
+        crate::trace::emit_record!(hook, qs::records::qf::gc_attempt(signal.0, pool_id, ref_ctr));
+    } else {
+        crate::trace::emit_record!(hook, qs::records::qf::gc(signal.0, pool_id, ref_ctr));
+    }
 }
 
 fn emit_mpool_get(hook: &TraceHook, pool_id: u8, free: u16, total: u16) {
-    let _ = hook(QS_QF_MPOOL_GET, &[
+    let _ = hook(rec::MPOOL_GET, &[
         pool_id,
         (free & 0xFF) as u8, (free >> 8) as u8,
         (total & 0xFF) as u8, (total >> 8) as u8,
@@ -425,7 +428,7 @@ fn emit_mpool_get(hook: &TraceHook, pool_id: u8, free: u16, total: u16) {
 }
 
 fn emit_mpool_put(hook: &TraceHook, pool_id: u8, free: u16, total: u16) {
-    let _ = hook(QS_QF_MPOOL_PUT, &[
+    let _ = hook(rec::MPOOL_PUT, &[
         pool_id,
         (free & 0xFF) as u8, (free >> 8) as u8,
         (total & 0xFF) as u8, (total >> 8) as u8,
@@ -0,0 +1,118 @@
+//! Timing contracts: upper bounds on how long a state may stay active.
+//!
+//! A contract reads "state `Hungry` must be exited within 50 ticks". The
+//! state machine stamps every contracted state's entry with
+//! [`time::now`](crate::time::now) and checks the bound when the state
+//! is exited and at the end of every dispatch, so a state that overstays
+//! without being left is caught as well. Each overstay is reported once per
+//! entry, as a `QS_CONTRACT_VIOLATION` record (id 57, a qp-rs extension in a
+//! QP reserved slot) with payload `state: fun ptr | limit: u32 | elapsed: u32`
+//! in ticks, and counted in [`TimingContracts::violations`].
+
+use crate::time::TickInstant;
+use crate::trace::TraceHook;
+
+/// Record id of a timing-contract violation.
+pub const QS_CONTRACT_VIOLATION: u8 = qs_protocol::records::qep::CONTRACT_VIOLATION;
+
+/// Contracts one state machine can carry.
+pub const MAX_TIMING_CONTRACTS: usize = 4;
+
+const PTR_SIZE: usize = core::mem::size_of::<usize>();
+
+#[derive(Clone, Copy)]
+struct Contract {
+    state: usize,
+    max_ticks: u32,
+    entered: Option<TickInstant>,
+    /// Overstay seen on exit, waiting to be reported.
+    late_exit: Option<u32>,
+    reported: bool,
+}
+
+/// The timing contracts of one state machine.
+pub struct TimingContracts {
+    slots: [Option<Contract>; MAX_TIMING_CONTRACTS],
+    violations: u32,
+}
+
+impl Default for TimingContracts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimingContracts {
+    /// No contracts.
+    pub const fn new() -> Self {
+        Self { slots: [None; MAX_TIMING_CONTRACTS], violations: 0 }
+    }
+
+    /// Bounds the time `state` (a state handler address) may stay active to
+    /// `max_ticks`. Returns `false` when all slots are taken.
+    pub fn add(&mut self, state: usize, max_ticks: u32) -> bool {
+        let Some(slot) = self.slots.iter_mut().find(|s| s.is_none()) else {
+            return false;
+        };
+        *slot = Some(Contract { state, max_ticks, entered: None, late_exit: None, reported: false });
+        true
+    }
+
+    /// Violations detected so far.
+    pub fn violations(&self) -> u32 {
+        self.violations
+    }
+
+    fn find(&mut self, state: usize) -> Option<&mut Contract> {
+        self.slots.iter_mut().flatten().find(|c| c.state == state)
+    }
+
+    pub(crate) fn on_entry(&mut self, state: usize, now: TickInstant) {
+        if let Some(c) = self.find(state) {
+            c.entered = Some(now);
+            c.reported = false;
+        }
+    }
+
+    pub(crate) fn on_exit(&mut self, state: usize, now: TickInstant) {
+        if let Some(c) = self.find(state) {
+            if let Some(entered) = c.entered.take() {
+                let elapsed = elapsed_ticks(now, entered);
+                if elapsed > c.max_ticks && !c.reported {
+                    c.late_exit = Some(elapsed);
+                }
+            }
+        }
+    }
+
+    /// Reports late exits and states still active past their bound.
+    pub(crate) fn check(&mut self, now: TickInstant, trace: Option<&TraceHook>) {
+        for c in self.slots.iter_mut().flatten() {
+            let elapsed = match (c.late_exit.take(), c.entered) {
+                (Some(elapsed), _) => elapsed,
+                (None, Some(entered)) if !c.reported => {
+                    let elapsed = elapsed_ticks(now, entered);
+                    if elapsed <= c.max_ticks {
+                        continue;
+                    }
+                    c.reported = true;
+                    elapsed
+                }
+                _ => continue,
+            };
+            self.violations = self.violations.saturating_add(1);
+            if let Some(trace) = trace {
+                let mut buf = [0u8; PTR_SIZE + 8];
+                buf[..PTR_SIZE].copy_from_slice(&crate::trace::symbol(c.state).to_le_bytes());
+                buf[PTR_SIZE..PTR_SIZE + 4].copy_from_slice(&c.max_ticks.to_le_bytes());
+                buf[PTR_SIZE + 4..].copy_from_slice(&elapsed.to_le_bytes());
+                let _ = trace(QS_CONTRACT_VIOLATION, &buf, true);
+            }
+        }
+    }
+}
+
+/// Ticks between two instants, as carried in the `u32` elapsed field.
+fn elapsed_ticks(now: TickInstant, entered: TickInstant) -> u32 {
+    now.duration_since(entered).ticks() as u32
+}
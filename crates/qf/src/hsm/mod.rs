@@ -1,13 +1,20 @@
-//! Hierarchical State Machine (QHsm) and Meta State Machine (QMsm) event processors.
+//! Hierarchical State Machine (QHsm) and Meta State Machine (QMsm) event
+//! processors, and orthogonal regions built from QHsms.
 
 pub mod common;
+pub mod contract;
 pub mod history;
 pub mod qhsm;
 pub mod qmsm;
+#[cfg(not(feature = "static-alloc"))]
+pub mod regions;
 pub mod trace;
 
 pub use common::{QAsm, SameState};
 pub use common::reserved;
+pub use contract::{MAX_TIMING_CONTRACTS, TimingContracts};
 pub use history::{HSM_HISTORY_CAP, QM_HISTORY_CAP};
-pub use qhsm::{MAX_NEST_DEPTH, QHsm, QHsmResult, StateHandler};
+pub use qhsm::{MAX_NEST_DEPTH, Outcome, QHsm, QHsmResult, StateHandler, TranAction};
 pub use qmsm::{QMInitAction, QMState, QMsm, QMsmResult, QMStateHandler};
+#[cfg(not(feature = "static-alloc"))]
+pub use regions::{Region, Regions};
@@ -10,6 +10,7 @@ use crate::trace::TraceHook;
 
 use super::common::{QAsm, SameState};
 use super::common::reserved::{Q_EMPTY_SIG, Q_ENTRY_SIG, Q_EXIT_SIG, Q_INIT_SIG};
+use super::contract::TimingContracts;
 use super::history::HistoryMap;
 use super::trace;
 
@@ -31,10 +32,25 @@ pub enum QHsmResult<S> {
     /// Execute a state transition to `target`.
     Tran(StateHandler<S>),
 
+    /// A guarded transition to `target`. If the guard passed this is `Tran`;
+    /// otherwise the state does not take the transition and the event moves
+    /// on to the super-state, as if the state did not handle it. Either way
+    /// the evaluation is traced as a `QS_QEP_GUARD` record.
+    TranIf(StateHandler<S>, bool),
+
+    /// Execute a state transition to `target`, running the action after the
+    /// source states have been exited and before the target is entered.
+    TranWith(StateHandler<S>, TranAction<S>),
+
     /// Execute a transition to the **history** of `parent`.  If no history
     /// has been recorded yet, enters `parent`'s initial transition.
     TranHist(StateHandler<S>),
 
+    /// Execute a transition to the **deep history** of `parent`: the leaf
+    /// state that was active when `parent` was last left, however deeply
+    /// nested.  Falls back to `parent`'s initial transition like `TranHist`.
+    TranDeepHist(StateHandler<S>),
+
     /// The event was explicitly recognised but intentionally ignored.
     Ignored,
 
@@ -42,6 +58,17 @@ pub enum QHsmResult<S> {
     Unhandled,
 }
 
+/// What a dispatch did with its event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// A state handled the event without a transition.
+    Handled,
+    /// The event caused a transition, to a state or to history.
+    Transitioned,
+    /// No state handled the event, or a guard rejected it.
+    Ignored,
+}
+
 /// State handler function pointer.
 ///
 /// Each state is a plain free function (or a named function item) with this
@@ -49,6 +76,10 @@ pub enum QHsmResult<S> {
 /// state machine data; the second is the current event.
 pub type StateHandler<S> = fn(&mut S, &DynEvent) -> QHsmResult<S>;
 
+/// Transition action attached with [`QHsmResult::TranWith`]. It receives the
+/// event that triggered the transition.
+pub type TranAction<S> = fn(&mut S, &DynEvent);
+
 impl<S> SameState for StateHandler<S> {
     #[inline]
     fn same_state(self, other: Self) -> bool {
@@ -60,7 +91,7 @@ impl<S> SameState for StateHandler<S> {
 ///
 /// `QHsm<S>` wraps user-defined state machine data `S` and provides the
 /// standard QHsm dispatch algorithm (LCA finding, entry/exit chain
-/// execution, nested initial transitions, shallow history).
+/// execution, nested initial transitions, shallow and deep history).
 ///
 /// It implements [`ActiveBehavior`] so it can be registered directly with the
 /// QF kernel.
@@ -80,6 +111,11 @@ pub struct QHsm<S> {
     /// Shallow history table.  Key = parent state fn-pointer as `usize`,
     /// value = last active direct child state handler.
     history: HistoryMap<StateHandler<S>>,
+    /// Deep history table.  Key = ancestor state fn-pointer as `usize`,
+    /// value = the leaf state that was active when the ancestor was left.
+    deep_history: HistoryMap<StateHandler<S>>,
+    /// Declared state timing contracts, stamped on entry and exit.
+    contracts: TimingContracts,
 }
 
 impl<S: Send + 'static> QAsm for QHsm<S> {
@@ -105,7 +141,27 @@ impl<S: Send + 'static> QHsm<S> {
             temp: initial,
             sm,
             history: HistoryMap::new(),
+            deep_history: HistoryMap::new(),
+            contracts: TimingContracts::new(),
+        }
+    }
+
+    /// Declares that `state` must be exited within `max_ticks` clock ticks of
+    /// being entered (see [`contract`](super::contract)).
+    ///
+    /// At most [`MAX_TIMING_CONTRACTS`](super::contract::MAX_TIMING_CONTRACTS)
+    /// contracts may be declared; one more is a configuration error and faults
+    /// out.
+    pub fn with_timing_contract(mut self, state: StateHandler<S>, max_ticks: u32) -> Self {
+        if !self.contracts.add(state as usize, max_ticks) {
+            crate::fusa::on_error(module_path!(), line!());
         }
+        self
+    }
+
+    /// Number of timing-contract violations detected so far.
+    pub fn timing_violations(&self) -> u32 {
+        self.contracts.violations()
     }
 
     /// Returns `true` if the state machine is in the given state (or any of its substates).
@@ -173,31 +229,33 @@ impl<S: Send + 'static> QHsm<S> {
         for i in (0..len).rev() {
             self.call_entry(path[i]);
             if let Some(ref hook) = trace {
-                trace::emit_state_entry(hook, path[i] as usize);
+                trace::emit_state_entry(hook, self.trace_obj(), path[i] as usize);
             }
         }
 
         self.state.set(target);
 
         if let Some(ref hook) = trace {
-            trace::emit_init_tran(hook, target as usize);
+            trace::emit_init_tran(hook, self.trace_obj(), target as usize);
         }
 
         // Resolve any nested initial transitions in the target composite state.
         self.handle_nested_init(&trace);
+        self.contracts.check(crate::time::now(), trace.as_ref());
     }
 
     // ── Dispatch ─────────────────────────────────────────────────────────────
 
     /// Dispatch an event to the HSM.
-    pub fn dispatch(&mut self, event: &DynEvent) {
-        self.dispatch_traced(event, None);
+    pub fn dispatch(&mut self, event: &DynEvent) -> Outcome {
+        self.dispatch_traced(event, None)
     }
 
     /// Dispatch an event with optional QS tracing.
-    pub fn dispatch_traced(&mut self, event: &DynEvent, trace: Option<TraceHook>) {
+    pub fn dispatch_traced(&mut self, event: &DynEvent, trace: Option<TraceHook>) -> Outcome {
+        crate::signals::debug_assert_user_signal(event.signal());
         if let Some(ref hook) = trace {
-            trace::emit_dispatch(hook, event.signal(), self.state.get() as usize);
+            trace::emit_dispatch(hook, self.trace_obj(), event.signal(), self.state.get() as usize);
         }
 
         // Walk the hierarchy upward until an event handler is found.
@@ -211,6 +269,17 @@ impl<S: Send + 'static> QHsm<S> {
                 QHsmResult::Super(sup) => {
                     s = sup;
                 }
+                QHsmResult::TranIf(target, passed) => {
+                    if let Some(ref hook) = trace {
+                        trace::emit_guard(hook, self.trace_obj(), event.signal(), s as usize, target as usize, passed);
+                    }
+                    if passed {
+                        source = s;
+                        result = QHsmResult::Tran(target);
+                        break;
+                    }
+                    s = self.get_super(s);
+                }
                 _ => {
                     source = s;
                     result = r;
@@ -219,22 +288,32 @@ impl<S: Send + 'static> QHsm<S> {
             }
         }
 
-        match result {
+        let outcome = match result {
             QHsmResult::Handled => {
                 if let Some(ref hook) = trace {
-                    trace::emit_intern_tran(hook, event.signal(), source as usize);
+                    trace::emit_intern_tran(hook, self.trace_obj(), event.signal(), source as usize);
                 }
+                Outcome::Handled
             }
             QHsmResult::Ignored | QHsmResult::Unhandled => {
                 if let Some(ref hook) = trace {
-                    trace::emit_ignored(hook, event.signal(), source as usize);
+                    trace::emit_ignored(hook, self.trace_obj(), event.signal(), source as usize);
                 }
+                Outcome::Ignored
             }
             QHsmResult::Tran(target) => {
                 if let Some(ref hook) = trace {
-                    trace::emit_tran(hook, event.signal(), source as usize, target as usize);
+                    trace::emit_tran(hook, self.trace_obj(), event.signal(), source as usize, target as usize);
+                }
+                self.execute_tran(source, target, None, &trace);
+                Outcome::Transitioned
+            }
+            QHsmResult::TranWith(target, action) => {
+                if let Some(ref hook) = trace {
+                    trace::emit_tran(hook, self.trace_obj(), event.signal(), source as usize, target as usize);
                 }
-                self.execute_tran(source, target, &trace);
+                self.execute_tran(source, target, Some((action, event)), &trace);
+                Outcome::Transitioned
             }
             QHsmResult::TranHist(parent) => {
                 // Look up the remembered substate, falling back to the parent
@@ -245,14 +324,54 @@ impl<S: Send + 'static> QHsm<S> {
                     .copied()
                     .unwrap_or(parent);
                 if let Some(ref hook) = trace {
-                    trace::emit_tran_hist(hook, event.signal(), source as usize, target as usize);
+                    trace::emit_tran_hist(hook, self.trace_obj(), source as usize, target as usize);
+                }
+                self.execute_tran(source, target, None, &trace);
+                Outcome::Transitioned
+            }
+            QHsmResult::TranDeepHist(parent) => {
+                let target = self
+                    .deep_history
+                    .get(&(parent as usize))
+                    .copied()
+                    .unwrap_or(parent);
+                if let Some(ref hook) = trace {
+                    trace::emit_tran_hist(hook, self.trace_obj(), source as usize, target as usize);
                 }
-                self.execute_tran(source, target, &trace);
+                self.execute_tran(source, target, None, &trace);
+                Outcome::Transitioned
             }
-            QHsmResult::Super(_) => {
-                // Hierarchy walk exhausted (should not normally escape).
+            QHsmResult::Super(_) | QHsmResult::TranIf(..) => {
+                // Resolved by the hierarchy walk above; never reached.
+                Outcome::Ignored
             }
+        };
+        self.contracts.check(crate::time::now(), trace.as_ref());
+        outcome
+    }
+
+    /// Exits every active state, innermost first, and leaves the machine in
+    /// the top state, recording history as a transition would.
+    /// [`init`](Self::init) starts it again.
+    pub fn exit(&mut self) {
+        self.exit_traced(None);
+    }
+
+    /// [`exit`](Self::exit) with optional QS tracing.
+    pub fn exit_traced(&mut self, trace: Option<TraceHook>) {
+        let current = self.state.get();
+        let mut s = current;
+        while !s.same_state(Self::top_state as StateHandler<S>) {
+            let parent = self.get_super(s);
+            self.remember(parent, s, current);
+            self.call_exit(s);
+            if let Some(ref hook) = trace {
+                trace::emit_state_exit(hook, self.trace_obj(), s as usize);
+            }
+            s = parent;
         }
+        self.state.set(Self::top_state);
+        self.contracts.check(crate::time::now(), trace.as_ref());
     }
 
     // ── Top-level superstate ─────────────────────────────────────────────────
@@ -278,16 +397,44 @@ impl<S: Send + 'static> QHsm<S> {
         }
     }
 
+    /// Records that `child` was the last active direct child of `parent`,
+    /// and `leaf` the last active leaf below it.
+    fn remember(&mut self, parent: StateHandler<S>, child: StateHandler<S>, leaf: StateHandler<S>) {
+        if parent.same_state(Self::top_state as StateHandler<S>) {
+            return;
+        }
+        #[cfg(not(feature = "static-alloc"))]
+        {
+            self.history.insert(parent as usize, child);
+            self.deep_history.insert(parent as usize, leaf);
+        }
+        // Heap-free map is fixed-capacity: a full history table is a
+        // configuration fault (too many composite states with history).
+        #[cfg(feature = "static-alloc")]
+        if self.history.insert(parent as usize, child).is_err()
+            || self.deep_history.insert(parent as usize, leaf).is_err()
+        {
+            crate::fusa::on_error(module_path!(), line!());
+        }
+    }
+
+    /// Address identifying this state machine in QS records.
+    fn trace_obj(&self) -> usize {
+        self as *const Self as usize
+    }
+
     /// Calls the entry action of state `s` (return value is discarded).
     fn call_entry(&mut self, s: StateHandler<S>) {
         let entry_e = Event::empty_dyn(Q_ENTRY_SIG);
         let _ = (s)(&mut self.sm, &entry_e);
+        self.contracts.on_entry(s as usize, crate::time::now());
     }
 
     /// Calls the exit action of state `s` (return value is discarded).
     fn call_exit(&mut self, s: StateHandler<S>) {
         let exit_e = Event::empty_dyn(Q_EXIT_SIG);
         let _ = (s)(&mut self.sm, &exit_e);
+        self.contracts.on_exit(s as usize, crate::time::now());
     }
 
     /// Builds the ancestry chain from `s` upward, stopping before `top_state`.
@@ -329,6 +476,7 @@ impl<S: Send + 'static> QHsm<S> {
         &mut self,
         source: StateHandler<S>,
         target: StateHandler<S>,
+        action: Option<(TranAction<S>, &DynEvent)>,
         trace: &Option<TraceHook>,
     ) {
         let current = self.state.get();
@@ -355,27 +503,18 @@ impl<S: Send + 'static> QHsm<S> {
         );
 
         // Exit from `current` up to (not including) `lca`, recording shallow
-        // history at each level so TranHist can later restore any ancestor.
+        // and deep history at each level so TranHist and TranDeepHist can
+        // later restore any ancestor.
         let mut s = current;
         while !s.same_state(lca) {
             // get_super first so we call the state handler only once per state.
             let parent = self.get_super(s);
 
-            // Record: `s` was the last active direct child of `parent`.
-            if !parent.same_state(Self::top_state as StateHandler<S>) {
-                #[cfg(not(feature = "static-alloc"))]
-                self.history.insert(parent as usize, s);
-                // Heap-free map is fixed-capacity: a full history table is a
-                // configuration fault (too many composite states with history).
-                #[cfg(feature = "static-alloc")]
-                if self.history.insert(parent as usize, s).is_err() {
-                    crate::fusa::on_error(module_path!(), line!());
-                }
-            }
+            self.remember(parent, s, current);
 
             self.call_exit(s);
             if let Some(ref hook) = trace {
-                trace::emit_state_exit(hook, s as usize);
+                trace::emit_state_exit(hook, self.trace_obj(), s as usize);
             }
 
             s = parent;
@@ -384,6 +523,10 @@ impl<S: Send + 'static> QHsm<S> {
             }
         }
 
+        if let Some((action, event)) = action {
+            action(&mut self.sm, event);
+        }
+
         // Enter states from (not including) `lca` down to `target`.
         // The entry chain is target_path[0..lca_idx] in reverse.
         let lca_idx = target_path[..target_len]
@@ -394,7 +537,7 @@ impl<S: Send + 'static> QHsm<S> {
         for i in (0..lca_idx).rev() {
             self.call_entry(target_path[i]);
             if let Some(ref hook) = trace {
-                trace::emit_state_entry(hook, target_path[i] as usize);
+                trace::emit_state_entry(hook, self.trace_obj(), target_path[i] as usize);
             }
         }
 
@@ -427,12 +570,12 @@ impl<S: Send + 'static> QHsm<S> {
             for i in (0..current_idx).rev() {
                 self.call_entry(next_path[i]);
                 if let Some(ref hook) = trace {
-                    trace::emit_state_entry(hook, next_path[i] as usize);
+                    trace::emit_state_entry(hook, self.trace_obj(), next_path[i] as usize);
                 }
             }
 
             if let Some(ref hook) = trace {
-                trace::emit_state_init(hook, self.state.get() as usize);
+                trace::emit_state_init(hook, self.trace_obj(), current as usize, next as usize);
             }
 
             self.state.set(next);
@@ -450,4 +593,8 @@ impl<S: Send + 'static> ActiveBehavior for QHsm<S> {
     fn on_event(&mut self, ctx: &mut ActiveContext, event: DynEvent) {
         self.dispatch_traced(&event, ctx.trace_hook());
     }
+
+    fn state_addr(&self) -> Option<usize> {
+        Some(self.state.get() as usize)
+    }
 }
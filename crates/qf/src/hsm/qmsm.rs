@@ -138,6 +138,11 @@ impl<S: Send + 'static> QMsm<S> {
         false
     }
 
+    /// Address identifying this state machine in QS records.
+    fn trace_obj(&self) -> usize {
+        self as *const Self as usize
+    }
+
     /// Drive the initial transition.
     pub fn init(&mut self) {
         self.init_traced(None);
@@ -155,14 +160,14 @@ impl<S: Send + 'static> QMsm<S> {
                 entry_act(&mut self.sm);
             }
             if let Some(ref hook) = trace {
-                trace::emit_state_entry(hook, state as *const _ as usize);
+                trace::emit_state_entry(hook, self.trace_obj(), state as *const _ as usize);
             }
         }
 
         self.state = target;
 
         if let Some(ref hook) = trace {
-            trace::emit_init_tran(hook, target as *const _ as usize);
+            trace::emit_init_tran(hook, self.trace_obj(), target as *const _ as usize);
         }
 
         self.handle_nested_init(&trace);
@@ -175,8 +180,9 @@ impl<S: Send + 'static> QMsm<S> {
 
     /// Dispatch an event with tracing.
     pub fn dispatch_traced(&mut self, event: &DynEvent, trace: Option<TraceHook>) {
+        crate::signals::debug_assert_user_signal(event.signal());
         if let Some(ref hook) = trace {
-            trace::emit_dispatch(hook, event.signal(), self.state as *const _ as usize);
+            trace::emit_dispatch(hook, self.trace_obj(), event.signal(), self.state as *const _ as usize);
         }
 
         let mut s = self.state;
@@ -200,17 +206,17 @@ impl<S: Send + 'static> QMsm<S> {
         match result {
             QMsmResult::Handled => {
                 if let Some(ref hook) = trace {
-                    trace::emit_intern_tran(hook, event.signal(), source as *const _ as usize);
+                    trace::emit_intern_tran(hook, self.trace_obj(), event.signal(), source as *const _ as usize);
                 }
             }
             QMsmResult::Ignored | QMsmResult::Unhandled => {
                 if let Some(ref hook) = trace {
-                    trace::emit_ignored(hook, event.signal(), source as *const _ as usize);
+                    trace::emit_ignored(hook, self.trace_obj(), event.signal(), source as *const _ as usize);
                 }
             }
             QMsmResult::Tran(target) => {
                 if let Some(ref hook) = trace {
-                    trace::emit_tran(hook, event.signal(), source as *const _ as usize, target as *const _ as usize);
+                    trace::emit_tran(hook, self.trace_obj(), event.signal(), source as *const _ as usize, target as *const _ as usize);
                 }
                 self.execute_tran(source, target, &trace);
             }
@@ -221,7 +227,7 @@ impl<S: Send + 'static> QMsm<S> {
                     .copied()
                     .unwrap_or(parent);
                 if let Some(ref hook) = trace {
-                    trace::emit_tran_hist(hook, event.signal(), source as *const _ as usize, target as *const _ as usize);
+                    trace::emit_tran_hist(hook, self.trace_obj(), source as *const _ as usize, target as *const _ as usize);
                 }
                 self.execute_tran(source, target, &trace);
             }
@@ -248,7 +254,7 @@ impl<S: Send + 'static> QMsm<S> {
         let lca = find_lca(&lca_source_path, &target_path);
 
         let mut s = current;
-        while lca.map_or(true, |lca_state| !s.same_state(lca_state)) {
+        while lca.is_none_or(|lca_state| !s.same_state(lca_state)) {
             if let Some(parent) = s.superstate {
                 #[cfg(not(feature = "static-alloc"))]
                 self.history.insert(parent as *const _ as usize, s);
@@ -263,7 +269,7 @@ impl<S: Send + 'static> QMsm<S> {
             }
 
             if let Some(ref hook) = trace {
-                trace::emit_state_exit(hook, s as *const _ as usize);
+                trace::emit_state_exit(hook, self.trace_obj(), s as *const _ as usize);
             }
 
             if let Some(parent) = s.superstate {
@@ -285,7 +291,7 @@ impl<S: Send + 'static> QMsm<S> {
                 entry_act(&mut self.sm);
             }
             if let Some(ref hook) = trace {
-                trace::emit_state_entry(hook, state as *const _ as usize);
+                trace::emit_state_entry(hook, self.trace_obj(), state as *const _ as usize);
             }
         }
 
@@ -308,12 +314,12 @@ impl<S: Send + 'static> QMsm<S> {
                     entry_act(&mut self.sm);
                 }
                 if let Some(ref hook) = trace {
-                    trace::emit_state_entry(hook, state as *const _ as usize);
+                    trace::emit_state_entry(hook, self.trace_obj(), state as *const _ as usize);
                 }
             }
 
             if let Some(ref hook) = trace {
-                trace::emit_state_init(hook, self.state as *const _ as usize);
+                trace::emit_state_init(hook, self.trace_obj(), current as *const _ as usize, next as *const _ as usize);
             }
 
             self.state = next;
@@ -339,6 +345,10 @@ impl<S: Send + 'static> ActiveBehavior for QMsm<S> {
     fn on_event(&mut self, ctx: &mut ActiveContext, event: DynEvent) {
         self.dispatch_traced(&event, ctx.trace_hook());
     }
+
+    fn state_addr(&self) -> Option<usize> {
+        Some(self.state as *const _ as usize)
+    }
 }
 
 // ── Helpers ──────────────────────────────────────────────────────────────────
@@ -0,0 +1,184 @@
+//! Orthogonal regions: several [`QHsm`]s driven as one state machine.
+//!
+//! A [`Regions`] container owns one child machine per region and keeps the
+//! UML ordering rules in one place:
+//!
+//! - `init` runs each region's initial transition in declaration order, so
+//!   the entry actions of region 0 all come before those of region 1.
+//! - `dispatch` offers every event to every region, again in declaration
+//!   order, and reports the combined [`Outcome`].
+//! - `exit` leaves the regions in reverse order, each innermost state first.
+//!
+//! The container implements [`ActiveBehavior`], so it can be registered as
+//! an active object directly:
+//!
+//! ```rust,ignore
+//! let behavior = Regions::new()
+//!     .with_region(QHsm::new(Keypad::default(), keypad_initial))
+//!     .with_region(QHsm::new(Display::default(), display_initial));
+//! let ao = new_active_object(PANEL_ID, 3, behavior);
+//! ```
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::active::{ActiveBehavior, ActiveContext};
+use crate::event::DynEvent;
+use crate::trace::TraceHook;
+
+use super::common::QAsm;
+use super::qhsm::{Outcome, QHsm};
+
+/// One region of a [`Regions`] container, implemented by [`QHsm`].
+pub trait Region: Send + 'static {
+    /// Runs the region's initial transition.
+    fn init_traced(&mut self, trace: Option<TraceHook>);
+    /// Dispatches `event` to the region.
+    fn dispatch_traced(&mut self, event: &DynEvent, trace: Option<TraceHook>) -> Outcome;
+    /// Exits the region's active states, innermost first.
+    fn exit_traced(&mut self, trace: Option<TraceHook>);
+    /// Address of the region's current state.
+    fn state_addr(&self) -> usize;
+    /// The region as `Any`, for [`Regions::region`].
+    fn as_any(&self) -> &dyn Any;
+    /// The region as `Any`, for [`Regions::region_mut`].
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<S: Send + 'static> Region for QHsm<S> {
+    fn init_traced(&mut self, trace: Option<TraceHook>) {
+        QHsm::init_traced(self, trace);
+    }
+
+    fn dispatch_traced(&mut self, event: &DynEvent, trace: Option<TraceHook>) -> Outcome {
+        QHsm::dispatch_traced(self, event, trace)
+    }
+
+    fn exit_traced(&mut self, trace: Option<TraceHook>) {
+        QHsm::exit_traced(self, trace);
+    }
+
+    fn state_addr(&self) -> usize {
+        self.state_handler() as usize
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Orthogonal regions dispatched as one state machine.
+#[derive(Default)]
+pub struct Regions {
+    regions: Vec<Box<dyn Region>>,
+}
+
+impl Regions {
+    /// Creates a container with no regions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a region. Regions are initialised and dispatched in the order
+    /// they are added.
+    pub fn with_region(mut self, region: impl Region) -> Self {
+        self.regions.push(Box::new(region));
+        self
+    }
+
+    /// Number of regions.
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// Returns `true` if there are no regions.
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// The region at `index`, if it is a `QHsm<S>`.
+    pub fn region<S: Send + 'static>(&self, index: usize) -> Option<&QHsm<S>> {
+        self.regions.get(index)?.as_any().downcast_ref()
+    }
+
+    /// The region at `index`, mutably, if it is a `QHsm<S>`.
+    pub fn region_mut<S: Send + 'static>(&mut self, index: usize) -> Option<&mut QHsm<S>> {
+        self.regions.get_mut(index)?.as_any_mut().downcast_mut()
+    }
+
+    /// Runs every region's initial transition, in order.
+    pub fn init(&mut self) {
+        self.init_traced(None);
+    }
+
+    /// [`init`](Self::init) with optional QS tracing.
+    pub fn init_traced(&mut self, trace: Option<TraceHook>) {
+        for region in &mut self.regions {
+            region.init_traced(trace.clone());
+        }
+    }
+
+    /// Dispatches `event` to every region, in order.
+    ///
+    /// The outcome is `Transitioned` if any region took a transition,
+    /// otherwise `Handled` if any region handled the event, and `Ignored`
+    /// only if all of them ignored it.
+    pub fn dispatch(&mut self, event: &DynEvent) -> Outcome {
+        self.dispatch_traced(event, None)
+    }
+
+    /// [`dispatch`](Self::dispatch) with optional QS tracing.
+    pub fn dispatch_traced(&mut self, event: &DynEvent, trace: Option<TraceHook>) -> Outcome {
+        let mut combined = Outcome::Ignored;
+        for region in &mut self.regions {
+            combined = match (combined, region.dispatch_traced(event, trace.clone())) {
+                (Outcome::Transitioned, _) | (_, Outcome::Transitioned) => Outcome::Transitioned,
+                (Outcome::Handled, _) | (_, Outcome::Handled) => Outcome::Handled,
+                _ => Outcome::Ignored,
+            };
+        }
+        combined
+    }
+
+    /// Exits every region, last region first.
+    pub fn exit(&mut self) {
+        self.exit_traced(None);
+    }
+
+    /// [`exit`](Self::exit) with optional QS tracing.
+    pub fn exit_traced(&mut self, trace: Option<TraceHook>) {
+        for region in self.regions.iter_mut().rev() {
+            region.exit_traced(trace.clone());
+        }
+    }
+}
+
+impl QAsm for Regions {
+    fn init(&mut self) {
+        self.init();
+    }
+
+    fn dispatch(&mut self, event: &DynEvent) {
+        self.dispatch(event);
+    }
+}
+
+impl ActiveBehavior for Regions {
+    fn on_start(&mut self, ctx: &mut ActiveContext) {
+        self.init_traced(ctx.trace_hook());
+    }
+
+    fn on_event(&mut self, ctx: &mut ActiveContext, event: DynEvent) {
+        self.dispatch_traced(&event, ctx.trace_hook());
+    }
+
+    /// The first region's state, so a host query sees a state machine.
+    fn state_addr(&self) -> Option<usize> {
+        self.regions.first().map(|region| region.state_addr())
+    }
+}
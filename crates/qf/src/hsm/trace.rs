@@ -1,72 +1,51 @@
 //! Centralized software tracing (QS/QSPY) emission helpers for the HSM event processors.
+//!
+//! Payloads come from the `qs::records::qep` builders, so they follow the
+//! QP/Spy layouts. `obj` is the address of the state machine and identifies
+//! it in the object dictionary. Without the `qs` feature there is no QS
+//! encoding to follow and the helpers emit nothing.
 
-use crate::event::Signal;
-use crate::trace::TraceHook;
+#![cfg_attr(not(feature = "qs"), allow(unused_variables))]
 
-// QS record IDs for QEP events. Matches QP/C++ v8.x canonical values.
-const QS_QEP_STATE_ENTRY:  u8 = 1;
-const QS_QEP_STATE_EXIT:   u8 = 2;
-const QS_QEP_STATE_INIT:   u8 = 3;
-const QS_QEP_INIT_TRAN:    u8 = 4;
-const QS_QEP_INTERN_TRAN:  u8 = 5;
-const QS_QEP_TRAN:         u8 = 6;
-const QS_QEP_IGNORED:      u8 = 7;
-const QS_QEP_DISPATCH:     u8 = 8;
-#[allow(dead_code)]
-const QS_QEP_UNHANDLED:    u8 = 9;
-const QS_QEP_TRAN_HIST:    u8 = 55;
+use crate::event::Signal;
+use crate::trace::{emit_record as emit, TraceHook};
 
-const PTR_SIZE: usize = core::mem::size_of::<usize>();
+pub fn emit_state_entry(hook: &TraceHook, obj: usize, state_ptr: usize) {
+    emit!(hook, qs::records::qep::state_entry(obj as u64, state_ptr as u64));
+}
 
-pub fn emit_state_entry(hook: &TraceHook, state_ptr: usize) {
-    let _ = hook(QS_QEP_STATE_ENTRY, &state_ptr.to_le_bytes(), false);
+pub fn emit_state_exit(hook: &TraceHook, obj: usize, state_ptr: usize) {
+    emit!(hook, qs::records::qep::state_exit(obj as u64, state_ptr as u64));
 }
 
-pub fn emit_state_exit(hook: &TraceHook, state_ptr: usize) {
-    let _ = hook(QS_QEP_STATE_EXIT, &state_ptr.to_le_bytes(), false);
+pub fn emit_state_init(hook: &TraceHook, obj: usize, source_ptr: usize, target_ptr: usize) {
+    emit!(hook, qs::records::qep::state_init(obj as u64, source_ptr as u64, target_ptr as u64));
 }
 
-pub fn emit_state_init(hook: &TraceHook, state_ptr: usize) {
-    let _ = hook(QS_QEP_STATE_INIT, &state_ptr.to_le_bytes(), false);
+pub fn emit_init_tran(hook: &TraceHook, obj: usize, state_ptr: usize) {
+    emit!(hook, qs::records::qep::init_tran(obj as u64, state_ptr as u64));
 }
 
-pub fn emit_init_tran(hook: &TraceHook, state_ptr: usize) {
-    let _ = hook(QS_QEP_INIT_TRAN, &state_ptr.to_le_bytes(), false);
+pub fn emit_dispatch(hook: &TraceHook, obj: usize, sig: Signal, state_ptr: usize) {
+    emit!(hook, qs::records::qep::dispatch(obj as u64, sig.0, state_ptr as u64));
 }
 
-pub fn emit_dispatch(hook: &TraceHook, sig: Signal, state_ptr: usize) {
-    let mut buf = [0u8; 2 + PTR_SIZE];
-    buf[0..2].copy_from_slice(&sig.0.to_le_bytes());
-    buf[2..].copy_from_slice(&state_ptr.to_le_bytes());
-    let _ = hook(QS_QEP_DISPATCH, &buf, true);
+pub fn emit_intern_tran(hook: &TraceHook, obj: usize, sig: Signal, state_ptr: usize) {
+    emit!(hook, qs::records::qep::intern_tran(obj as u64, sig.0, state_ptr as u64));
 }
 
-pub fn emit_intern_tran(hook: &TraceHook, sig: Signal, state_ptr: usize) {
-    let mut buf = [0u8; 2 + PTR_SIZE];
-    buf[0..2].copy_from_slice(&sig.0.to_le_bytes());
-    buf[2..].copy_from_slice(&state_ptr.to_le_bytes());
-    let _ = hook(QS_QEP_INTERN_TRAN, &buf, true);
+pub fn emit_ignored(hook: &TraceHook, obj: usize, sig: Signal, state_ptr: usize) {
+    emit!(hook, qs::records::qep::ignored(obj as u64, sig.0, state_ptr as u64));
 }
 
-pub fn emit_ignored(hook: &TraceHook, sig: Signal, state_ptr: usize) {
-    let mut buf = [0u8; 2 + PTR_SIZE];
-    buf[0..2].copy_from_slice(&sig.0.to_le_bytes());
-    buf[2..].copy_from_slice(&state_ptr.to_le_bytes());
-    let _ = hook(QS_QEP_IGNORED, &buf, true);
+pub fn emit_tran(hook: &TraceHook, obj: usize, sig: Signal, source_ptr: usize, target_ptr: usize) {
+    emit!(hook, qs::records::qep::tran(obj as u64, sig.0, source_ptr as u64, target_ptr as u64));
 }
 
-pub fn emit_tran(hook: &TraceHook, sig: Signal, source_ptr: usize, target_ptr: usize) {
-    let mut buf = [0u8; 2 + PTR_SIZE * 2];
-    buf[0..2].copy_from_slice(&sig.0.to_le_bytes());
-    buf[2..2 + PTR_SIZE].copy_from_slice(&source_ptr.to_le_bytes());
-    buf[2 + PTR_SIZE..].copy_from_slice(&target_ptr.to_le_bytes());
-    let _ = hook(QS_QEP_TRAN, &buf, true);
+pub fn emit_tran_hist(hook: &TraceHook, obj: usize, source_ptr: usize, target_ptr: usize) {
+    emit!(hook, qs::records::qep::tran_hist(obj as u64, source_ptr as u64, target_ptr as u64));
 }
 
-pub fn emit_tran_hist(hook: &TraceHook, sig: Signal, source_ptr: usize, target_ptr: usize) {
-    let mut buf = [0u8; 2 + PTR_SIZE * 2];
-    buf[0..2].copy_from_slice(&sig.0.to_le_bytes());
-    buf[2..2 + PTR_SIZE].copy_from_slice(&source_ptr.to_le_bytes());
-    buf[2 + PTR_SIZE..].copy_from_slice(&target_ptr.to_le_bytes());
-    let _ = hook(QS_QEP_TRAN_HIST, &buf, true);
+pub fn emit_guard(hook: &TraceHook, obj: usize, sig: Signal, state_ptr: usize, target_ptr: usize, passed: bool) {
+    emit!(hook, qs::records::qep::guard(obj as u64, sig.0, state_ptr as u64, target_ptr as u64, passed));
 }
@@ -0,0 +1,81 @@
+//! The kernel idle callback: what the CPU does when no active object has
+//! events.
+//!
+//! The kernels do not sleep by themselves. Once a kernel has no ready active
+//! object left, it calls the application's [`IdleCallback`]. There the
+//! application picks a sleep mode, drains trace output, or returns at once on
+//! a hosted build. The `QS_SCHED_IDLE` record for the switch to idle is
+//! emitted before the callback runs.
+//!
+//! A sleeping CPU has to be woken by the interrupt that posts the next event.
+//! If that interrupt fires between the kernel's last look at the queues and
+//! the sleep instruction, the event waits until some other interrupt comes
+//! along. With an [`InterruptLock`] configured (typically the port's scheduler
+//! lock, such as `nvic_cfg::IDLE_LOCK` on Cortex-M), the kernel takes the lock,
+//! checks the queues once more, and calls back with the lock still held in
+//! the [`IdleContext`]. [`IdleContext::sleep`] then runs the sleep instruction
+//! before releasing it, which is the contract of `QV_onIdle()` in QP/C++:
+//!
+//! ```ignore
+//! fn on_idle(cx: IdleContext) {
+//!     if battery_low() {
+//!         cx.sleep(enter_stop_mode);
+//!     } else {
+//!         cx.sleep(qf_port_cortex_m::nvic_cfg::idle_sleep);
+//!     }
+//! }
+//! ```
+
+/// The interrupt lock the kernel holds while the idle callback decides how to
+/// sleep.
+#[derive(Debug, Clone, Copy)]
+pub struct InterruptLock {
+    /// Masks the interrupts that may post events; returns the state to
+    /// restore.
+    pub lock: fn() -> u32,
+    /// Restores the state `lock` returned.
+    pub unlock: fn(u32),
+}
+
+/// Called by a kernel each time it runs out of events.
+pub type IdleCallback = fn(IdleContext);
+
+/// The interrupt state the idle callback runs in.
+///
+/// Dropping the context releases the lock, so a callback that does not sleep
+/// can simply return.
+#[derive(Debug)]
+pub struct IdleContext {
+    held: Option<(fn(u32), u32)>,
+}
+
+impl IdleContext {
+    /// Takes `lock`, if any, for an idle callback.
+    pub fn enter(lock: Option<&InterruptLock>) -> Self {
+        Self { held: lock.map(|lock| (lock.unlock, (lock.lock)())) }
+    }
+
+    /// Whether interrupts are masked by the kernel's [`InterruptLock`]. When
+    /// `false`, no lock is configured and an event may arrive at any time.
+    pub fn is_locked(&self) -> bool {
+        self.held.is_some()
+    }
+
+    /// Runs `wait` (the sleep instruction) with interrupts still masked, then
+    /// releases the lock. An interrupt that became pending in the meantime
+    /// wakes `wait` and runs once the lock is released.
+    pub fn sleep(self, wait: impl FnOnce()) {
+        wait();
+    }
+
+    /// Releases the lock without sleeping.
+    pub fn unlock(self) {}
+}
+
+impl Drop for IdleContext {
+    fn drop(&mut self) {
+        if let Some((unlock, state)) = self.held.take() {
+            unlock(state);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Lock-free event queues from interrupt handlers to active objects.
+//!
+//! Posting straight from an ISR ([`QvKernel::post_from_isr`]) takes the
+//! target's queue lock, and with it a critical section, for the whole copy of
+//! the event. An [`IsrQueue`] instead gives one interrupt handler a
+//! single-producer single-consumer ring towards one active object: the handler
+//! writes an event into a free slot and publishes it with one atomic store, and
+//! the kernel moves it into the object's queue at task level.
+//!
+//! ```rust,ignore
+//! static RX_QUEUE: IsrQueue<8> = IsrQueue::new(RADIO);
+//!
+//! let kernel = QkKernel::builder().register(radio)?.isr_queue(&RX_QUEUE).build()?;
+//!
+//! #[interrupt]
+//! fn DIO1() {
+//!     qf::qk_isr_entry!();
+//!     RX_QUEUE.post_from_isr(DynEvent::empty_dyn(RX_DONE_SIG)).ok();
+//!     qf::qk_isr_exit!();
+//!     KERNEL.end_of_isr(); // posts the queued events and preempts
+//! }
+//! ```
+//!
+//! The cooperative kernel drains its queues on every
+//! [`run_until_idle`](crate::QvKernel::run_until_idle) pass. "Single
+//! producer" is checked rather than trusted: a second handler that posts while
+//! the first is still writing, e.g. from a nested interrupt, gets its event
+//! back, and the queue counts it as [`dropped`](IsrQueue::dropped).
+//!
+//! [`QvKernel::post_from_isr`]: crate::QvKernel::post_from_isr
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+
+use portable_atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+
+use crate::active::ActiveObjectId;
+use crate::event::DynEvent;
+
+/// Queues a kernel drains.
+pub const MAX_ISR_QUEUES: usize = 8;
+
+/// Consumer side of an ISR queue, as a kernel sees it.
+pub trait IsrSource: Sync {
+    /// The active object the events are for.
+    fn target(&self) -> ActiveObjectId;
+
+    /// Returns `true` if an event is waiting.
+    fn is_pending(&self) -> bool;
+
+    /// Takes the oldest event.
+    fn pop(&self) -> Option<DynEvent>;
+
+    /// Events refused so far, for [metrics](crate::metrics).
+    fn dropped(&self) -> u32 {
+        0
+    }
+}
+
+/// Single-producer single-consumer ring of up to `N` events for one active
+/// object.
+///
+/// `N` must be a power of two: slots are picked from free-running counters,
+/// and only then does the mapping stay continuous when they wrap.
+pub struct IsrQueue<const N: usize> {
+    target: ActiveObjectId,
+    slots: [UnsafeCell<MaybeUninit<DynEvent>>; N],
+    /// Events taken so far; only the consumer advances it.
+    head: AtomicUsize,
+    /// Events published so far; only the producer advances it.
+    tail: AtomicUsize,
+    producing: AtomicBool,
+    consuming: AtomicBool,
+    dropped: AtomicU32,
+}
+
+// SAFETY: a slot is written only by the producer while it lies between `tail`
+// and `head + N`, and read only by the consumer once `tail` has been published
+// past it. The `producing` / `consuming` flags keep each side to one caller.
+unsafe impl<const N: usize> Sync for IsrQueue<N> {}
+
+impl<const N: usize> IsrQueue<N> {
+    /// An empty queue towards `target`.
+    pub const fn new(target: ActiveObjectId) -> Self {
+        const { assert!(N.is_power_of_two(), "IsrQueue capacity must be a power of two") };
+        Self {
+            target,
+            slots: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            producing: AtomicBool::new(false),
+            consuming: AtomicBool::new(false),
+            dropped: AtomicU32::new(0),
+        }
+    }
+
+    /// Queues `event` without a critical section. Gives the event back if
+    /// the queue is full or another handler is posting at the same time.
+    pub fn post_from_isr(&self, event: DynEvent) -> Result<(), DynEvent> {
+        if self.producing.swap(true, Ordering::Acquire) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return Err(event);
+        }
+        let tail = self.tail.load(Ordering::Relaxed);
+        let result = if tail.wrapping_sub(self.head.load(Ordering::Acquire)) >= N {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            Err(event)
+        } else {
+            // SAFETY: the slot is free (see the `Sync` impl) and this is the
+            // only producer.
+            unsafe { (*self.slots[tail % N].get()).write(event) };
+            self.tail.store(tail.wrapping_add(1), Ordering::Release);
+            Ok(())
+        };
+        self.producing.store(false, Ordering::Release);
+        result
+    }
+
+    /// Number of events waiting.
+    pub fn len(&self) -> usize {
+        self.tail.load(Ordering::Acquire).wrapping_sub(self.head.load(Ordering::Acquire))
+    }
+
+    /// Returns `true` if no event is waiting.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Maximum number of waiting events.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Events refused because the queue was full or already being posted to.
+    pub fn dropped(&self) -> u32 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl<const N: usize> IsrSource for IsrQueue<N> {
+    fn target(&self) -> ActiveObjectId {
+        self.target
+    }
+
+    fn is_pending(&self) -> bool {
+        !self.is_empty()
+    }
+
+    fn pop(&self) -> Option<DynEvent> {
+        if self.consuming.swap(true, Ordering::Acquire) {
+            return None;
+        }
+        let head = self.head.load(Ordering::Relaxed);
+        let event = (self.tail.load(Ordering::Acquire) != head).then(|| {
+            // SAFETY: the producer published the slot and will not touch it
+            // again until `head` moves past it.
+            let event = unsafe { (*self.slots[head % N].get()).assume_init_read() };
+            self.head.store(head.wrapping_add(1), Ordering::Release);
+            event
+        });
+        self.consuming.store(false, Ordering::Release);
+        event
+    }
+
+    fn dropped(&self) -> u32 {
+        IsrQueue::dropped(self)
+    }
+}
+
+impl<const N: usize> Drop for IsrQueue<N> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+/// The ISR queues registered with a kernel.
+#[derive(Clone, Copy)]
+pub struct IsrQueues {
+    queues: [Option<&'static dyn IsrSource>; MAX_ISR_QUEUES],
+}
+
+impl Default for IsrQueues {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IsrQueues {
+    /// No queues.
+    pub const fn new() -> Self {
+        Self { queues: [None; MAX_ISR_QUEUES] }
+    }
+
+    /// Adds `queue`. Returns `false` if [`MAX_ISR_QUEUES`] are already
+    /// registered.
+    pub fn add(&mut self, queue: &'static dyn IsrSource) -> bool {
+        match self.queues.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(queue);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The registered queues.
+    pub fn iter(&self) -> impl Iterator<Item = &'static dyn IsrSource> + '_ {
+        self.queues.iter().flatten().copied()
+    }
+
+    /// Returns `true` if any queue holds an event.
+    pub fn is_pending(&self) -> bool {
+        self.iter().any(|queue| queue.is_pending())
+    }
+
+    /// Hands every waiting event to `post` with its target, oldest first per
+    /// queue, and returns how many there were.
+    pub fn drain(&self, mut post: impl FnMut(ActiveObjectId, DynEvent)) -> usize {
+        let mut count = 0;
+        for queue in self.iter() {
+            while let Some(event) = queue.pop() {
+                post(queue.target(), event);
+                count += 1;
+            }
+        }
+        count
+    }
+}
@@ -0,0 +1,125 @@
+//! Time-event jitter: how late timeouts reach their active objects.
+//!
+//! A time event expires on the tick it is due, but its handler runs only when
+//! the kernel gets to the target active object. A long run-to-completion step
+//! or lock section in between delays the handler, and for periodic work the
+//! delay shows up as jitter. The timer wheels stamp each expiry with the
+//! [`now`] tick it was due; the target stamps the tick it takes the event
+//! off its queue, and the difference accumulates into the time event's
+//! [`JitterStats`] ([`TimeEvent::jitter`]).
+//!
+//! Both stamps come from the rate-0 tick clock, so the jitter of a time event
+//! on another tick rate is still counted in rate-0 ticks. At most
+//! [`MAX_IN_FLIGHT`] expiries are tracked at a time; beyond that, timeouts go
+//! unmeasured. When a time event expires again before its previous timeout
+//! was handled, the new expiry is counted as an overlap instead of being
+//! measured: the handler has fallen a whole period behind.
+//!
+//! With `qs`, [`TimerWheel::report_jitter_every`](crate::TimerWheel::report_jitter_every)
+//! emits a `QS_QF_TIMEEVT_JITTER` record per measured time event at a fixed
+//! tick interval.
+
+use portable_atomic::{AtomicUsize, Ordering};
+
+use crate::active::ActiveObjectId;
+use crate::event::Signal;
+use crate::sync::Mutex;
+use crate::time::{now, TickInstant, TimeEvent, TimeEventRef};
+
+/// Expiries whose handling is awaited at the same time.
+pub const MAX_IN_FLIGHT: usize = 16;
+
+/// Lateness of a time event's timeouts, in rate-0 ticks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JitterStats {
+    /// Timeouts whose handling was measured.
+    pub samples: u32,
+    /// Lateness of the most recent timeout.
+    pub last: u32,
+    /// Smallest lateness seen.
+    pub min: u32,
+    /// Largest lateness seen.
+    pub max: u32,
+    /// Sum of all lateness samples.
+    pub total: u64,
+    /// Expiries that found the previous timeout still unhandled.
+    pub overlaps: u32,
+}
+
+impl JitterStats {
+    /// No samples.
+    pub const fn new() -> Self {
+        Self { samples: 0, last: 0, min: 0, max: 0, total: 0, overlaps: 0 }
+    }
+
+    /// Mean lateness, or `None` before the first sample.
+    pub fn mean(&self) -> Option<u32> {
+        (self.samples > 0).then(|| (self.total / u64::from(self.samples)) as u32)
+    }
+
+    pub(crate) fn record(&mut self, lateness: u32) {
+        self.min = if self.samples == 0 { lateness } else { self.min.min(lateness) };
+        self.max = self.max.max(lateness);
+        self.last = lateness;
+        self.total += u64::from(lateness);
+        self.samples = self.samples.saturating_add(1);
+    }
+}
+
+struct InFlight {
+    target: ActiveObjectId,
+    signal: Signal,
+    due: TickInstant,
+    /// Address of `timer`, which identifies it.
+    key: usize,
+    timer: TimeEventRef,
+}
+
+static IN_FLIGHT: Mutex<[Option<InFlight>; MAX_IN_FLIGHT]> =
+    Mutex::new([const { None }; MAX_IN_FLIGHT]);
+static PENDING: AtomicUsize = AtomicUsize::new(0);
+
+/// Stamps an expiry of `timer`, due now, whose event is about to be posted.
+/// Timer wheels call this between [`TimeEvent::poll`] and the post.
+pub fn fired(timer: &TimeEventRef, target: ActiveObjectId, signal: Signal) {
+    let overlap = {
+        let mut table = IN_FLIGHT.lock();
+        let key = address(timer);
+        if table.iter().flatten().any(|entry| entry.key == key) {
+            true
+        } else {
+            if let Some(slot) = table.iter_mut().find(|slot| slot.is_none()) {
+                *slot = Some(InFlight { target, signal, due: now(), key, timer: TimeEventRef::clone(timer) });
+                PENDING.fetch_add(1, Ordering::Relaxed);
+            }
+            false
+        }
+    };
+    if overlap {
+        timer.note_overlap();
+    }
+}
+
+fn address(timer: &TimeEvent) -> usize {
+    timer as *const TimeEvent as usize
+}
+
+/// Records the handling of the event `signal` by `target`, if it is a
+/// timeout being measured. Active objects call this as they take an event
+/// off their queue.
+pub fn delivered(target: ActiveObjectId, signal: Signal) {
+    if PENDING.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let entry = {
+        let mut table = IN_FLIGHT.lock();
+        table
+            .iter_mut()
+            .find(|slot| slot.as_ref().is_some_and(|e| e.target == target && e.signal == signal))
+            .and_then(Option::take)
+    };
+    if let Some(entry) = entry {
+        PENDING.fetch_sub(1, Ordering::Relaxed);
+        entry.timer.record_jitter(now().duration_since(entry.due).ticks() as u32);
+    }
+}
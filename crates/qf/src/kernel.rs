@@ -5,7 +5,7 @@ use portable_atomic::{AtomicBool, Ordering};
 
 #[cfg(not(feature = "static-alloc"))]
 use alloc::collections::BTreeMap;
-#[cfg(not(feature = "static-alloc"))]
+#[cfg(any(not(feature = "static-alloc"), feature = "std"))]
 use alloc::vec::Vec;
 
 #[cfg(not(feature = "static-alloc"))]
@@ -14,18 +14,34 @@ use crate::sync::Arc;
 // atomics instead, so it is only needed off the `smp` path.
 #[cfg(not(feature = "smp"))]
 use crate::sync::Mutex;
-use crate::trace::{TraceError, TraceHook};
+use crate::trace::{emit_record, TraceError, TraceFlush, TraceHook};
 
 use crate::active::{ActiveObjectId, ActiveObjectRef};
-use crate::event::{DynEvent, Signal};
+use crate::budget::{OverrunCallback, RtcBudgets};
+use crate::event::{DynEvent, EventHeader, Signal};
+use crate::idle::{IdleCallback, IdleContext, InterruptLock};
+use crate::isr_queue::{IsrQueues, IsrSource};
 use crate::pubsub::PubSubTable;
+use crate::time::KernelClock;
+use crate::services::{with_services, KernelServices, ServiceError};
+use crate::metrics::Metrics;
+use crate::watchdog::{IdleFeeder, StallCallback, Watchdog};
+#[cfg(feature = "std")]
+use crate::threaded::{ThreadPriority, Workers};
+#[cfg(feature = "std")]
+use crate::work_pool::WorkPool;
+#[cfg(any(not(feature = "static-alloc"), feature = "std"))]
+use crate::schedulability::{PriorityPlan, TaskTiming};
 
-const QS_SCHED_LOCK: u8 = 50;
-const QS_SCHED_UNLOCK: u8 = 51;
-const QS_SCHED_NEXT: u8 = 52;
-// The SMP scheduler does not emit a per-core idle record from this path.
-#[cfg(not(feature = "smp"))]
-const QS_SCHED_IDLE: u8 = 53;
+use qs_protocol::records::sched;
+
+/// Signal [`QvKernel::shutdown`] posts to every active object before it
+/// stops them.
+///
+/// It sits at the top of the signal range, clear of the reserved signals and
+/// of any block an application lays out from the bottom, so state machines
+/// that ignore it simply let it bubble past the top state.
+pub const STOP_SIG: Signal = Signal(u16::MAX);
 
 /// Maximum active objects in the heap-free registry (idle priority 0 plus the
 /// 1..=63 application range that the preemptive kernels permit).
@@ -75,6 +91,23 @@ struct SchedulerState {
     sched_ceiling: u8,
 }
 
+/// How [`QvKernel::run`] executes the registered active objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Execution {
+    /// All active objects run on the calling thread, highest priority first.
+    #[default]
+    Cooperative,
+    /// Each active object runs on an OS thread of its own (see
+    /// [`threaded`](crate::threaded)).
+    #[cfg(feature = "std")]
+    Threaded,
+    /// Active objects share `workers` threads that take up an object when an
+    /// event reaches it and steal work from each other (see
+    /// [`work_pool`](crate::work_pool)).
+    #[cfg(feature = "std")]
+    Pool { workers: usize },
+}
+
 /// Configuration for the QF kernel.
 ///
 /// Provides system sizing metadata required by QS tracing and runtime
@@ -95,6 +128,19 @@ pub struct KernelConfig {
     pub time_event_ctr_size: u8,
     /// Optional callback invoked when the kernel goes idle.
     pub idle_callback: Option<fn()>,
+    /// Optional callback invoked when the kernel goes idle, which decides how
+    /// to sleep under the [`idle_lock`](Self::idle_lock) (see
+    /// [`idle`](crate::idle)).
+    pub on_idle: Option<IdleCallback>,
+    /// Lock held from the kernel's last look at the queues until the idle
+    /// callbacks sleep or return.
+    pub idle_lock: Option<InterruptLock>,
+    /// How `run` executes the active objects.
+    pub execution: Execution,
+    /// Maps active-object priorities to native thread priorities under
+    /// [`Execution::Threaded`].
+    #[cfg(feature = "std")]
+    pub thread_priority: Option<ThreadPriority>,
     /// Framework version reported to QS (e.g. `740`).
     pub version: u16,
     /// Optional free-form build information string for QS.
@@ -111,6 +157,11 @@ impl Default for KernelConfig {
             event_queue_ctr_size: 2,
             time_event_ctr_size: 2,
             idle_callback: None,
+            on_idle: None,
+            idle_lock: None,
+            execution: Execution::Cooperative,
+            #[cfg(feature = "std")]
+            thread_priority: None,
             version: 740,
             build_info: None,
         }
@@ -199,6 +250,35 @@ impl KernelConfigBuilder {
         self
     }
 
+    /// Sets the idle callback that is handed the [`IdleContext`] to sleep
+    /// in (see [`idle`](crate::idle)). It runs after the plain
+    /// [`idle_callback`](Self::idle_callback), if both are set.
+    pub fn on_idle(mut self, callback: IdleCallback) -> Self {
+        self.config.on_idle = Some(callback);
+        self
+    }
+
+    /// Sets the interrupt lock the idle callbacks are entered with (see
+    /// [`idle`](crate::idle)).
+    pub fn interrupt_lock(mut self, lock: InterruptLock) -> Self {
+        self.config.idle_lock = Some(lock);
+        self
+    }
+
+    /// Selects how `run` executes the active objects.
+    pub fn execution(mut self, execution: Execution) -> Self {
+        self.config.execution = execution;
+        self
+    }
+
+    /// Sets the hook that gives each active-object thread its native priority
+    /// under [`Execution::Threaded`].
+    #[cfg(feature = "std")]
+    pub fn thread_priority(mut self, hook: ThreadPriority) -> Self {
+        self.config.thread_priority = Some(hook);
+        self
+    }
+
     /// Sets the version number.
     pub fn version(mut self, version: u16) -> Self {
         self.config.version = version;
@@ -223,7 +303,15 @@ pub struct KernelBuilder {
     config: KernelConfig,
     objects: ObjVec,
     trace: Option<TraceHook>,
+    trace_flush: Option<TraceFlush>,
     pubsub: Option<PubSubTable>,
+    budgets: RtcBudgets,
+    watchdog: Watchdog,
+    feeder: Option<&'static IdleFeeder>,
+    metrics: Metrics,
+    isr_queues: IsrQueues,
+    #[cfg(feature = "std")]
+    strands: Vec<Vec<ActiveObjectId>>,
 }
 
 impl KernelBuilder {
@@ -233,7 +321,15 @@ impl KernelBuilder {
             config,
             objects: ObjVec::new(),
             trace: None,
+            trace_flush: None,
             pubsub: None,
+            budgets: RtcBudgets::new(),
+            watchdog: Watchdog::new(),
+            feeder: None,
+            metrics: Metrics::new(),
+            isr_queues: IsrQueues::new(),
+            #[cfg(feature = "std")]
+            strands: Vec::new(),
         }
     }
 
@@ -255,12 +351,118 @@ impl KernelBuilder {
         self
     }
 
+    /// Flushes the trace backend through `flush` when the kernel
+    /// [shuts down](QvKernel::shutdown). Pair it with the hook's tracer,
+    /// e.g. `TracerHandle::flusher`.
+    pub fn with_trace_flush(mut self, flush: TraceFlush) -> Self {
+        self.trace_flush = Some(flush);
+        self
+    }
+
+    /// Bounds each run-to-completion step of the AO at `priority` to
+    /// `budget` units of the [budget clock](crate::budget). Priorities
+    /// from [`MAX_BUDGETS`](crate::budget::MAX_BUDGETS) up cannot carry a
+    /// budget and fault.
+    pub fn with_rtc_budget(mut self, priority: u8, budget: u32) -> Self {
+        if !self.budgets.set(priority, budget) {
+            crate::fusa::on_error(module_path!(), line!());
+        }
+        self
+    }
+
+    /// Calls `callback` for every budget overrun.
+    pub fn on_rtc_overrun(mut self, callback: OverrunCallback) -> Self {
+        self.budgets.on_overrun(callback);
+        self
+    }
+
+    /// Reports an active object that keeps events waiting for more than
+    /// `ticks` ticks without taking one. See [`watchdog`](crate::watchdog).
+    pub fn with_watchdog(mut self, ticks: u32) -> Self {
+        self.watchdog.set_limit(ticks);
+        self
+    }
+
+    /// Calls `callback` for every stall the watchdog reports.
+    pub fn on_stall(mut self, callback: StallCallback) -> Self {
+        self.watchdog.on_stall(callback);
+        self
+    }
+
+    /// Feeds a hardware watchdog through `feeder` whenever
+    /// [`run_until_idle`](QvKernel::run_until_idle) leaves the kernel healthy
+    /// and idle. See [`watchdog`](crate::watchdog#hardware-watchdog).
+    pub fn feed_watchdog(mut self, feeder: &'static IdleFeeder) -> Self {
+        self.feeder = Some(feeder);
+        self
+    }
+
+    /// Counts dispatches, step durations and dropped posts per active object
+    /// and per signal. See [`metrics`](crate::metrics).
+    pub fn with_metrics(mut self) -> Self {
+        self.metrics.enable();
+        self
+    }
+
+    /// Turns metrics on and has [`run`](QvKernel::run) emit them as
+    /// `QS_METRICS` records every `ticks` ticks.
+    pub fn emit_metrics_every(mut self, ticks: u32) -> Self {
+        self.metrics.enable();
+        self.metrics.set_emit_period(ticks);
+        self
+    }
+
+    /// Drains `queue` into its target's event queue on every
+    /// [`run_until_idle`](QvKernel::run_until_idle) pass. More than
+    /// [`MAX_ISR_QUEUES`](crate::isr_queue::MAX_ISR_QUEUES) queues fault.
+    pub fn isr_queue(mut self, queue: &'static dyn IsrSource) -> Self {
+        if !self.isr_queues.add(queue) {
+            crate::fusa::on_error(module_path!(), line!());
+        }
+        self
+    }
+
+    /// Puts the objects `ids` on one strand: under [`Execution::Pool`] their
+    /// steps never overlap and run highest priority first. An id already on a
+    /// strand joins the two strands.
+    #[cfg(feature = "std")]
+    pub fn strand(mut self, ids: &[ActiveObjectId]) -> Self {
+        let mut merged = ids.to_vec();
+        self.strands.retain(|strand| {
+            let overlaps = strand.iter().any(|id| ids.contains(id));
+            if overlaps {
+                merged.extend(strand.iter().filter(|id| !ids.contains(id)));
+            }
+            !overlaps
+        });
+        self.strands.push(merged);
+        self
+    }
+
+    /// Runs a deadline-monotonic analysis of `tasks` and checks it against
+    /// the priorities of the objects registered so far. See
+    /// [`PriorityPlan::check_registered`].
+    #[cfg(any(not(feature = "static-alloc"), feature = "std"))]
+    pub fn deadline_monotonic(&self, tasks: &[TaskTiming]) -> PriorityPlan {
+        PriorityPlan::check_registered(tasks, self.objects.iter().map(|ao| (ao.id(), ao.priority())))
+    }
+
     /// Sorts the registered objects by priority and constructs the [`QvKernel`].
     pub fn build(mut self) -> QvKernel {
         // `sort_unstable_by_key` is in `core` (no alloc) and is fine here:
         // active-object priorities are unique, so stability is irrelevant.
         self.objects.sort_unstable_by_key(|ao| ao.priority());
-        QvKernel::new(self.config, self.objects, self.trace, self.pubsub)
+        let mut kernel = QvKernel::new(self.config, self.objects, self.trace, self.pubsub, self.budgets);
+        kernel.isr_queues = self.isr_queues;
+        kernel.watchdog = self.watchdog;
+        kernel.feeder = self.feeder;
+        kernel.trace_flush = self.trace_flush;
+        kernel.metrics = self.metrics;
+        #[cfg(feature = "std")]
+        {
+            kernel.strands = self.strands;
+        }
+        kernel
     }
 }
 
@@ -271,6 +473,8 @@ pub enum KernelError {
     NotFound(ActiveObjectId),
     /// Emitting a QS trace record failed.
     Trace(TraceError),
+    /// [`shutdown`](QvKernel::shutdown) gave up with events still queued.
+    ShutdownTimeout,
 }
 
 impl fmt::Display for KernelError {
@@ -278,6 +482,7 @@ impl fmt::Display for KernelError {
         match self {
             Self::NotFound(id) => write!(f, "active object {id:?} not found"),
             Self::Trace(_) => write!(f, "trace error"),
+            Self::ShutdownTimeout => write!(f, "shutdown timed out with events still queued"),
         }
     }
 }
@@ -324,6 +529,7 @@ pub struct QvKernel {
     #[cfg(not(feature = "static-alloc"))]
     by_id: BTreeMap<ActiveObjectId, ActiveObjectRef>,
     trace: Option<TraceHook>,
+    trace_flush: Option<TraceFlush>,
     #[cfg(not(feature = "smp"))]
     scheduler: Mutex<SchedulerState>,
     #[cfg(feature = "smp")]
@@ -331,8 +537,36 @@ pub struct QvKernel {
     /// Set by `stop()` to break out of a `run()` loop.
     stop_flag: AtomicBool,
     pubsub: Option<PubSubTable>,
+    budgets: RtcBudgets,
+    watchdog: Watchdog,
+    feeder: Option<&'static IdleFeeder>,
+    metrics: Metrics,
+    isr_queues: IsrQueues,
+    /// Rate-0 ticks of the kernel's timer wheel.
+    clock: KernelClock,
+    /// Channels into the active-object threads while a threaded `run` lasts.
+    #[cfg(feature = "std")]
+    workers: Workers,
+    /// Objects whose steps must not overlap under [`Execution::Pool`].
+    #[cfg(feature = "std")]
+    strands: Vec<Vec<ActiveObjectId>>,
+    /// The worker pool while a pooled `run` lasts.
+    #[cfg(feature = "std")]
+    pool: WorkPool,
+    /// Which [`Execution`] mode a `run` has under way, so posting skips the
+    /// worker channels and the pool unless one is running.
+    #[cfg(feature = "std")]
+    running: portable_atomic::AtomicU8,
 }
 
+/// Values of [`QvKernel::running`].
+#[cfg(feature = "std")]
+const RUNNING_COOPERATIVE: u8 = 0;
+#[cfg(feature = "std")]
+const RUNNING_THREADED: u8 = 1;
+#[cfg(feature = "std")]
+const RUNNING_POOLED: u8 = 2;
+
 /// Backwards-compatible alias for [`QvKernel`], the QP/C++ **QV**-equivalent
 /// cooperative kernel. Prefer `QvKernel` in new code.
 pub type Kernel = QvKernel;
@@ -350,33 +584,36 @@ impl QvKernel {
 
     /// Posts an event to the target active object's queue.
     pub fn post(&self, target: ActiveObjectId, event: DynEvent) -> Result<(), KernelError> {
+        let Some(ao) = self.find(target) else {
+            self.metrics.dropped_signal(event.header.signal);
+            return Err(KernelError::NotFound(target));
+        };
+        self.deliver(ao, event);
+        Ok(())
+    }
+
+    /// The registered active object with id `target`.
+    fn find(&self, target: ActiveObjectId) -> Option<&ActiveObjectRef> {
         // Dynamic build: O(log n) id→handle map. Heap-free build: linear scan of
         // the (small, fixed) registry — no `BTreeMap` allocation.
         #[cfg(not(feature = "static-alloc"))]
-        if let Some(ao) = self.by_id.get(&target) {
-            ao.post(event);
-            return Ok(());
+        {
+            self.by_id.get(&target)
         }
         #[cfg(feature = "static-alloc")]
         {
             #[cfg(not(feature = "smp"))]
-            let iter = self.objects.iter();
+            let mut iter = self.objects.iter();
             #[cfg(feature = "smp")]
-            let iter = self.slots.iter().map(|s| &s.object);
-            for ao in iter {
-                if ao.id() == target {
-                    ao.post(event);
-                    return Ok(());
-                }
-            }
+            let mut iter = self.slots.iter().map(|s| &s.object);
+            iter.find(|ao| ao.id() == target)
         }
-        Err(KernelError::NotFound(target))
     }
 
     /// Broadcasts or multicasts an event (with `signal`) to registered active objects.
     pub fn publish(&self, signal: Signal, event: DynEvent) {
         if let Some(ref pubsub) = self.pubsub {
-            self.emit_publish(signal);
+            self.emit_publish(signal, &event.header);
             let subscribers = pubsub.subscribers(signal);
             #[cfg(not(feature = "smp"))]
             let iter = self.objects.iter();
@@ -388,7 +625,7 @@ impl QvKernel {
                 if (subscribers & (1u64 << priority)) != 0 {
                     let mut cloned = event.clone();
                     cloned.header.signal = signal;
-                    ao.post(cloned);
+                    self.deliver(ao, cloned);
                 }
             }
         } else {
@@ -401,7 +638,7 @@ impl QvKernel {
                 // Basic publish duplicates the event header, but payload is shared via Arc.
                 let mut cloned = event.clone();
                 cloned.header.signal = signal;
-                ao.post(cloned);
+                self.deliver(ao, cloned);
             }
         }
     }
@@ -414,17 +651,42 @@ impl QvKernel {
         let iter = self.slots.iter().map(|s| &s.object);
 
         for ao in iter {
-            ao.start(self.trace.clone());
+            with_services(self, || ao.start(self.trace.clone()));
         }
     }
 
     /// Dispatches ready active objects until none remain, then runs the
-    /// configured idle callback (if any).
+    /// configured idle callbacks (if any).
+    ///
+    /// Events waiting in the [ISR queues](crate::isr_queue) are moved into
+    /// their targets' queues before every dispatch. The callbacks are entered
+    /// under the configured [`InterruptLock`] and skipped if an event arrived
+    /// before the lock was taken. A [watchdog feeder](KernelBuilder::feed_watchdog)
+    /// is consulted before the callbacks.
     pub fn run_until_idle(&self) {
-        while self.dispatch_once() {}
-        // Call idle callback if configured
-        if let Some(idle_cb) = self.config.idle_callback {
-            idle_cb();
+        let started = self.clock.now().ticks();
+        let mut batch = crate::batch::Batch::start();
+        loop {
+            self.drain_isr_queues();
+            if !self.dispatch_once() {
+                break;
+            }
+            batch.count();
+        }
+        batch.finish(self.trace.as_ref());
+        if let Some(feeder) = self.feeder {
+            feeder.on_idle(started, self.clock.now().ticks(), self.active_objects());
+        }
+        if self.config.idle_callback.is_some() || self.config.on_idle.is_some() {
+            let cx = IdleContext::enter(self.config.idle_lock.as_ref());
+            if !self.has_pending_work() {
+                if let Some(idle_callback) = self.config.idle_callback {
+                    idle_callback();
+                }
+                if let Some(on_idle) = self.config.on_idle {
+                    on_idle(cx);
+                }
+            }
         }
     }
 
@@ -433,19 +695,114 @@ impl QvKernel {
     /// Calls `tick_fn` once per iteration (for advancing time events), then
     /// drains all pending events. Returns when `stop()` is called.
     ///
+    /// Under [`Execution::Threaded`] the active objects run on their own
+    /// threads instead, and the calling thread only calls `tick_fn`, which
+    /// should pace itself (e.g. sleep for one tick). `run` returns once every
+    /// thread has handled the events posted before `stop()`.
+    ///
     /// `start()` is called automatically before the first iteration.
     pub fn run(&self, mut tick_fn: impl FnMut()) {
         self.start();
         self.stop_flag.store(false, Ordering::Release);
+        #[cfg(feature = "std")]
+        match self.config.execution {
+            Execution::Threaded => return self.run_threaded(tick_fn),
+            Execution::Pool { workers } => return self.run_pool(workers, tick_fn),
+            Execution::Cooperative => {}
+        }
         loop {
             if self.stop_flag.load(Ordering::Acquire) {
                 break;
             }
             tick_fn();
+            self.check_watchdog();
+            self.metrics.emit_due(self.trace.as_ref());
             self.run_until_idle();
         }
     }
 
+    /// Runs every active object on a thread of its own until `stop()`.
+    #[cfg(feature = "std")]
+    fn run_threaded(&self, mut tick_fn: impl FnMut()) {
+        self.running.store(RUNNING_THREADED, Ordering::SeqCst);
+        std::thread::scope(|scope| {
+            #[cfg(not(feature = "smp"))]
+            let iter = self.objects.iter();
+            #[cfg(feature = "smp")]
+            let iter = self.slots.iter().map(|s| &s.object);
+
+            for ao in iter {
+                let queue = self.workers.open(ao.id());
+                let priority = self.config.thread_priority;
+                let spawned = std::thread::Builder::new()
+                    .name(std::format!("ao-{}", ao.id().0))
+                    .spawn_scoped(scope, move || {
+                        crate::threaded::serve(self, &**ao, queue, &|ao| self.step(ao), priority)
+                    });
+                if spawned.is_err() {
+                    crate::fusa::on_error(module_path!(), line!());
+                }
+            }
+            while !self.stop_flag.load(Ordering::Acquire) {
+                tick_fn();
+            }
+            self.workers.close();
+        });
+        self.running.store(RUNNING_COOPERATIVE, Ordering::SeqCst);
+    }
+
+    /// Runs the active objects on `workers` pool threads until `stop()`.
+    #[cfg(feature = "std")]
+    fn run_pool(&self, workers: usize, mut tick_fn: impl FnMut()) {
+        #[cfg(not(feature = "smp"))]
+        let iter = self.objects.iter();
+        #[cfg(feature = "smp")]
+        let iter = self.slots.iter().map(|s| &s.object);
+
+        // The registry is in ascending priority order, so each strand lists
+        // its objects highest first.
+        let mut strands: Vec<Vec<&dyn crate::active::ActiveRunnable>> = Vec::new();
+        let mut joined: Vec<(usize, usize)> = Vec::new();
+        for ao in iter.rev() {
+            match self.strands.iter().position(|strand| strand.contains(&ao.id())) {
+                Some(declared) => match joined.iter().find(|&&(d, _)| d == declared) {
+                    Some(&(_, s)) => strands[s].push(&**ao),
+                    None => {
+                        joined.push((declared, strands.len()));
+                        strands.push(alloc::vec![&**ao]);
+                    }
+                },
+                None => strands.push(alloc::vec![&**ao]),
+            }
+        }
+        self.running.store(RUNNING_POOLED, Ordering::SeqCst);
+        self.pool.run(self, &strands, workers, &|ao| self.step(ao), || {
+            while !self.stop_flag.load(Ordering::Acquire) {
+                tick_fn();
+            }
+        });
+        self.running.store(RUNNING_COOPERATIVE, Ordering::SeqCst);
+    }
+
+    /// Posts `event` to `ao`, through its thread while a threaded `run`
+    /// lasts, or its pool while a pooled one does.
+    fn deliver(&self, ao: &ActiveObjectRef, event: DynEvent) {
+        #[cfg(feature = "std")]
+        match self.running.load(Ordering::SeqCst) {
+            RUNNING_THREADED => self.workers.deliver(&**ao, event),
+            // Before the pool is up (or after it stops), its start-up scan
+            // or the caller's next `run_until_idle` finds the event queued.
+            RUNNING_POOLED => {
+                if let Err(event) = self.pool.deliver(&**ao, event) {
+                    ao.post(event);
+                }
+            }
+            _ => ao.post(event),
+        }
+        #[cfg(not(feature = "std"))]
+        ao.post(event);
+    }
+
     /// Signal the `run()` loop to exit.
     ///
     /// Thread-safe; may be called from any context including a signal handler.
@@ -453,14 +810,93 @@ impl QvKernel {
         self.stop_flag.store(true, Ordering::Release);
     }
 
-    /// `true` if any registered AO has queued events.
+    /// Shuts the application down in order, for a simulation or a host run
+    /// that must end cleanly rather than be killed mid-frame. Call it once
+    /// [`run`](Self::run) has returned, or in its place, on the thread that
+    /// runs the kernel.
+    ///
+    /// Going from the highest priority down, every active object is posted
+    /// [`STOP_SIG`] and the queues are drained before the next one is, so an
+    /// object sees its stop after everything posted to it by the objects
+    /// already stopped. `tick_fn` is called between draining passes, as in
+    /// `run`, so timeouts armed while winding down still fire; draining gives
+    /// up `timeout` ticks after the call. Every object's
+    /// [`on_stop`](crate::active::ActiveBehavior::on_stop) hook then runs, in
+    /// the same order, and the [trace flush](KernelBuilder::with_trace_flush)
+    /// pushes out the frames the backend still buffers.
+    ///
+    /// The hooks and the flush run even when draining times out; the result
+    /// is then [`KernelError::ShutdownTimeout`].
+    pub fn shutdown(&self, timeout: u32, mut tick_fn: impl FnMut()) -> Result<(), KernelError> {
+        self.stop();
+        let started = self.clock.now();
+        let mut drained = true;
+
+        #[cfg(not(feature = "smp"))]
+        let iter = self.objects.iter();
+        #[cfg(feature = "smp")]
+        let iter = self.slots.iter().map(|s| &s.object);
+
+        for ao in iter.rev() {
+            self.deliver(ao, DynEvent::empty_dyn(STOP_SIG));
+            drained &= self.drain(started, timeout, &mut tick_fn);
+        }
+        for ao in self.active_objects().rev() {
+            with_services(self, || ao.stop());
+        }
+        if let Some(flush) = self.trace_flush.as_ref() {
+            flush()?;
+        }
+        if drained {
+            Ok(())
+        } else {
+            Err(KernelError::ShutdownTimeout)
+        }
+    }
+
+    /// Dispatches until no queue holds an event, calling `tick_fn` between
+    /// passes; `false` once `timeout` ticks have passed since `started`.
+    fn drain(&self, started: crate::time::TickInstant, timeout: u32, tick_fn: &mut impl FnMut()) -> bool {
+        loop {
+            self.drain_isr_queues();
+            while self.dispatch_once() {
+                self.drain_isr_queues();
+            }
+            if !self.has_pending_work() {
+                return true;
+            }
+            if self.clock.now().duration_since(started).ticks() >= u64::from(timeout) {
+                return false;
+            }
+            tick_fn();
+        }
+    }
+
+    /// `true` if any registered AO or ISR queue has queued events.
     pub fn has_pending_work(&self) -> bool {
         #[cfg(not(feature = "smp"))]
         let mut iter = self.objects.iter();
         #[cfg(feature = "smp")]
         let mut iter = self.slots.iter().map(|s| &s.object);
 
-        iter.any(|ao| ao.has_events())
+        iter.any(|ao| ao.has_events()) || self.isr_queues.is_pending()
+    }
+
+    /// Moves the events waiting in the ISR queues to their targets. An event
+    /// for an unregistered object faults.
+    fn drain_isr_queues(&self) {
+        self.isr_queues.drain(|target, event| {
+            if self.post(target, event).is_err() {
+                crate::fusa::on_error(module_path!(), line!());
+            }
+        });
+        if self.metrics.is_enabled() {
+            for (index, queue) in self.isr_queues.iter().enumerate() {
+                if let Some(ao) = self.find(queue.target()) {
+                    self.metrics.isr_dropped(index, queue.dropped(), &**ao);
+                }
+            }
+        }
     }
 
     /// Post `event` to `target` from an ISR context.
@@ -517,12 +953,12 @@ impl QvKernel {
                     if scheduler.prev_prio != 0 {
                         let prev = scheduler.prev_prio;
                         scheduler.prev_prio = 0;
-                        note = Some((QS_SCHED_IDLE, [prev, 0], 1));
+                        note = Some((sched::IDLE, [prev, 0], 1));
                     }
                 } else {
                     let prev = scheduler.prev_prio;
                     if prio != prev {
-                        note = Some((QS_SCHED_NEXT, [prio, prev], 2));
+                        note = Some((sched::NEXT, [prio, prev], 2));
                     }
                     scheduler.prev_prio = prio;
                 }
@@ -536,7 +972,7 @@ impl QvKernel {
                 return false;
             }
 
-            ao.dispatch_one()
+            with_services(self, || self.step(&*ao))
         } else {
             let mut note: Option<(u8, [u8; 2], usize)> = None;
             {
@@ -544,7 +980,7 @@ impl QvKernel {
                 if scheduler.prev_prio != 0 {
                     let prev = scheduler.prev_prio;
                     scheduler.prev_prio = 0;
-                    note = Some((QS_SCHED_IDLE, [prev, 0], 1));
+                    note = Some((sched::IDLE, [prev, 0], 1));
                 }
             }
 
@@ -586,8 +1022,8 @@ impl QvKernel {
                 let mut dispatched = false;
                 if slot.object.has_events() {
                     let prio = slot.object.priority();
-                    self.emit_scheduler_record(QS_SCHED_NEXT, &[prio, 0]);
-                    dispatched = slot.object.dispatch_one();
+                    self.emit_scheduler_record(sched::NEXT, &[prio, 0]);
+                    dispatched = with_services(self, || self.step(&*slot.object));
                 }
                 slot.executing_core.store(CORE_ID_NONE, Ordering::Release);
                 return dispatched;
@@ -600,11 +1036,88 @@ impl QvKernel {
     pub fn trace_hook(&self) -> Option<TraceHook> {
         self.trace.clone()
     }
+
+    /// The run-to-completion budgets and their overrun count.
+    pub fn rtc_budgets(&self) -> &RtcBudgets {
+        &self.budgets
+    }
+
+    /// The kernel's rate-0 tick clock, advanced by its timer wheel.
+    pub fn clock(&self) -> &KernelClock {
+        &self.clock
+    }
+
+    /// Looks for active objects whose events have waited longer than the
+    /// [watchdog](crate::watchdog) allows, and reports them. Returns how many
+    /// it reported. [`run`](Self::run) calls this after every tick.
+    pub fn check_watchdog(&self) -> usize {
+        self.clock.enter();
+        self.watchdog.check(self.active_objects(), self.trace.as_ref())
+    }
+
+    /// The registered active objects, lowest priority first.
+    pub(crate) fn active_objects(&self) -> impl DoubleEndedIterator<Item = &dyn crate::active::ActiveRunnable> {
+        #[cfg(not(feature = "smp"))]
+        let iter = self.objects.iter();
+        #[cfg(feature = "smp")]
+        let iter = self.slots.iter().map(|s| &s.object);
+
+        iter.map(|ao| &**ao as &dyn crate::active::ActiveRunnable)
+    }
+
+    /// The watchdog and its stall count.
+    pub fn watchdog(&self) -> &Watchdog {
+        &self.watchdog
+    }
+
+    /// Emits `QS_WDT_RESET` with the chip's raw reset-reason code. Ports
+    /// call this once at boot when the last reset came from a hardware
+    /// watchdog.
+    pub fn report_watchdog_reset(&self, reason: u32) {
+        crate::watchdog::report_reset(reason, self.trace.as_ref());
+    }
+
+    /// Dispatch counts, step durations, queue high-water marks and dropped
+    /// posts per active object and per signal, if
+    /// [`with_metrics`](KernelBuilder::with_metrics) turned them on.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// One step of `ao` under its RTC budget, counted by the metrics.
+    fn step(&self, ao: &dyn crate::active::ActiveRunnable) -> bool {
+        self.metrics.dispatch(ao, || self.budgets.dispatch(ao, self.trace.as_ref()))
+    }
+}
+
+impl KernelServices for QvKernel {
+    fn post(&self, target: ActiveObjectId, event: DynEvent) -> Result<(), ServiceError> {
+        QvKernel::post(self, target, event).map_err(|_| ServiceError::NotFound(target))
+    }
+
+    fn post_lifo(&self, target: ActiveObjectId, event: DynEvent) -> Result<(), ServiceError> {
+        self.find(target).ok_or(ServiceError::NotFound(target))?.post_lifo(event);
+        Ok(())
+    }
+
+    fn publish(&self, signal: Signal, event: DynEvent) {
+        QvKernel::publish(self, signal, event);
+    }
+
+    fn clock(&self) -> Option<&KernelClock> {
+        Some(&self.clock)
+    }
 }
 
 impl QvKernel {
     #[cfg(not(feature = "smp"))]
-    fn new(config: KernelConfig, objects: ObjVec, trace: Option<TraceHook>, pubsub: Option<PubSubTable>) -> Self {
+    fn new(
+        config: KernelConfig,
+        objects: ObjVec,
+        trace: Option<TraceHook>,
+        pubsub: Option<PubSubTable>,
+        budgets: RtcBudgets,
+    ) -> Self {
         #[cfg(not(feature = "static-alloc"))]
         let by_id = {
             let mut by_id = BTreeMap::new();
@@ -619,14 +1132,35 @@ impl QvKernel {
             #[cfg(not(feature = "static-alloc"))]
             by_id,
             trace,
+            trace_flush: None,
             scheduler: Mutex::new(SchedulerState::default()),
             stop_flag: AtomicBool::new(false),
             pubsub,
+            budgets,
+            watchdog: Watchdog::new(),
+            feeder: None,
+            metrics: Metrics::new(),
+            isr_queues: IsrQueues::new(),
+            clock: KernelClock::new(),
+            #[cfg(feature = "std")]
+            workers: Workers::new(),
+            #[cfg(feature = "std")]
+            strands: Vec::new(),
+            #[cfg(feature = "std")]
+            pool: WorkPool::new(),
+            #[cfg(feature = "std")]
+            running: portable_atomic::AtomicU8::new(RUNNING_COOPERATIVE),
         }
     }
 
     #[cfg(feature = "smp")]
-    fn new(config: KernelConfig, objects: ObjVec, trace: Option<TraceHook>, pubsub: Option<PubSubTable>) -> Self {
+    fn new(
+        config: KernelConfig,
+        objects: ObjVec,
+        trace: Option<TraceHook>,
+        pubsub: Option<PubSubTable>,
+        budgets: RtcBudgets,
+    ) -> Self {
         #[cfg(not(feature = "static-alloc"))]
         let mut by_id = BTreeMap::new();
         let mut slots = SlotVec::new();
@@ -650,9 +1184,24 @@ impl QvKernel {
             #[cfg(not(feature = "static-alloc"))]
             by_id,
             trace,
+            trace_flush: None,
             sched_ceiling: portable_atomic::AtomicU8::new(0),
             stop_flag: AtomicBool::new(false),
             pubsub,
+            budgets,
+            watchdog: Watchdog::new(),
+            feeder: None,
+            metrics: Metrics::new(),
+            isr_queues: IsrQueues::new(),
+            clock: KernelClock::new(),
+            #[cfg(feature = "std")]
+            workers: Workers::new(),
+            #[cfg(feature = "std")]
+            strands: Vec::new(),
+            #[cfg(feature = "std")]
+            pool: WorkPool::new(),
+            #[cfg(feature = "std")]
+            running: portable_atomic::AtomicU8::new(RUNNING_COOPERATIVE),
         }
     }
 
@@ -685,24 +1234,28 @@ impl QvKernel {
         }
     }
 
+    // Active objects are identified by priority in the `ao` field, and
+    // `publish` has no sender to report.
+
+    #[cfg_attr(not(feature = "qs"), allow(unused_variables))]
     fn emit_subscribe(&self, priority: u8, signal: Signal) {
         if let Some(trace) = &self.trace {
-            let sig_bytes = signal.0.to_le_bytes();
-            let _ = trace(12, &[priority, sig_bytes[0], sig_bytes[1]], true);
+            emit_record!(trace, qs::records::qf::subscribe(u64::from(priority), signal.0));
         }
     }
 
+    #[cfg_attr(not(feature = "qs"), allow(unused_variables))]
     fn emit_unsubscribe(&self, priority: u8, signal: Signal) {
         if let Some(trace) = &self.trace {
-            let sig_bytes = signal.0.to_le_bytes();
-            let _ = trace(13, &[priority, sig_bytes[0], sig_bytes[1]], true);
+            emit_record!(trace, qs::records::qf::unsubscribe(u64::from(priority), signal.0));
         }
     }
 
-    fn emit_publish(&self, signal: Signal) {
+    #[cfg_attr(not(feature = "qs"), allow(unused_variables))]
+    fn emit_publish(&self, signal: Signal, header: &EventHeader) {
         if let Some(trace) = &self.trace {
-            let sig_bytes = signal.0.to_le_bytes();
-            let _ = trace(26, &[sig_bytes[0], sig_bytes[1]], true);
+            let (pool, ref_ctr) = (header.pool_id.unwrap_or(0), header.ref_count);
+            emit_record!(trace, qs::records::qf::publish(0, signal.0, pool, ref_ctr));
         }
     }
 }
@@ -723,7 +1276,7 @@ impl QvKernel {
         }
 
         if let Some(payload) = note {
-            self.emit_scheduler_record(QS_SCHED_LOCK, &payload);
+            self.emit_scheduler_record(sched::LOCK, &payload);
         }
     }
 
@@ -741,7 +1294,7 @@ impl QvKernel {
         }
 
         if let Some(payload) = note {
-            self.emit_scheduler_record(QS_SCHED_UNLOCK, &payload);
+            self.emit_scheduler_record(sched::UNLOCK, &payload);
         }
     }
 
@@ -767,7 +1320,7 @@ impl QvKernel {
         }
 
         if let Some(payload) = note {
-            self.emit_scheduler_record(QS_SCHED_LOCK, &payload);
+            self.emit_scheduler_record(sched::LOCK, &payload);
         }
     }
 
@@ -792,7 +1345,7 @@ impl QvKernel {
         }
 
         if let Some(payload) = note {
-            self.emit_scheduler_record(QS_SCHED_UNLOCK, &payload);
+            self.emit_scheduler_record(sched::UNLOCK, &payload);
         }
     }
 }
@@ -21,45 +21,82 @@ The crate keeps modules loosely coupled so that alternative front-ends can reuse
 extern crate alloc;
 
 pub mod active;
+#[cfg(all(feature = "std", not(feature = "static-alloc")))]
+pub mod async_ao;
+#[cfg(feature = "alloc-trace")]
+pub mod alloc_trace;
+pub mod batch;
+pub mod budget;
+pub mod current;
 pub mod dis;
 pub mod equeue;
 pub mod event;
 pub mod event_pool;
 pub mod fusa;
 pub mod hsm;
+pub mod idle;
 pub mod qmsm {
     pub use crate::hsm::qmsm::*;
 }
 pub mod isr;
+pub mod isr_queue;
+pub mod jitter;
 pub mod kernel;
+pub mod metrics;
+#[cfg(feature = "metrics-export")]
+pub mod metrics_export;
 pub mod pool;
 #[cfg(feature = "static-alloc")]
 pub mod pool_arc;
+pub mod persist;
 pub mod port;
 pub mod pubsub;
 pub mod priospec;
+#[cfg(not(feature = "static-alloc"))]
+pub mod replay;
+#[cfg(any(not(feature = "static-alloc"), feature = "std"))]
+pub mod schedulability;
+pub mod services;
+pub mod signals;
+pub mod static_ao;
 mod sync;
+#[cfg(feature = "std")]
+pub mod threaded;
 pub mod time;
+pub mod watchdog;
+#[cfg(feature = "std")]
+pub mod work_pool;
 pub use active::{ActiveObject, ActiveObjectId, ActiveObjectRef, QActive, Q};
+#[cfg(all(feature = "std", not(feature = "static-alloc")))]
+pub use async_ao::{AsyncActive, Inbox};
+pub use current::current_ao;
 pub use dis::{Dis, DisAtomicU16, DisInt};
-pub use equeue::{defer, flush_deferred, recall, PostStatus, QEQueue};
-#[cfg(feature = "static-alloc")]
-pub use equeue::StaticEQueue;
-pub use event::{Event, EventHeader, Signal};
+pub use equeue::{defer, flush_deferred, recall, PostStatus, QEQueue, StaticEQueue};
+pub use event::{Event, EventHeader, EventOf, Signal};
 pub use event_pool::{gc, q_new, q_new_x, EventBox, PoolRegistry, POOL_REGISTRY, MAX_POOLS};
 pub use fusa::{clear_error_handler, on_error, set_error_handler, ErrorHandler};
 pub use hsm::{SameState, QHsm, QHsmResult, StateHandler, MAX_NEST_DEPTH, QAsm};
 pub use qmsm::{QMsm, QMState, QMsmResult, QMStateHandler};
+pub use idle::{IdleCallback, IdleContext, InterruptLock};
 pub use isr::{in_isr, isr_nesting};
-pub use kernel::{Kernel, KernelBuilder, KernelConfig, QvKernel};
+pub use isr_queue::{IsrQueue, IsrSource};
+pub use jitter::JitterStats;
+pub use kernel::{Execution, Kernel, KernelBuilder, KernelConfig, QvKernel, STOP_SIG};
 pub use pool::QMPool;
 pub use port::{ContextSwitch, NoopContextSwitch, Runtime, TraceSink};
 pub use pubsub::PubSubTable;
 pub use priospec::{QPrioSpec, q_prio};
+#[cfg(any(not(feature = "static-alloc"), feature = "std"))]
+pub use schedulability::{PriorityPlan, ScheduleWarning, TaskTiming};
+pub use services::{KernelServices, ServiceError};
+pub use signals::{SignalBlock, SignalNamespace, SignalRange, SignalSpace};
+pub use static_ao::StaticActive;
+#[cfg(feature = "std")]
+pub use threaded::ThreadPriority;
 #[cfg(feature = "qs")]
 pub use qs::{QsConfig, QsRecord, TraceBackend, Tracer, TracerHandle};
 pub use time::{TimeEvent, TimeEventConfig, TimeEventTraceInfo, TimerWheel};
-pub use trace::{ContextSwitchHook, TraceError, TraceHook, TraceResult};
+pub use trace::{ContextSwitchHook, TraceError, TraceFlush, TraceHook, TraceResult};
 #[cfg(test)]
 mod tests;
 mod trace;
@@ -120,6 +157,63 @@ macro_rules! q_tran_hist {
     };
 }
 
+/// Transition to the deep history of composite state `$parent`.
+///
+/// Returns `QHsmResult::TranDeepHist($parent)` from a state handler.
+#[macro_export]
+macro_rules! q_tran_deep_hist {
+    ($parent:expr) => {
+        $crate::hsm::QHsmResult::TranDeepHist($parent)
+    };
+}
+
+/// Transition to `$target` if `$guard` holds.
+///
+/// Returns `QHsmResult::TranIf($target, $guard)` from a state handler, so the
+/// guard evaluation shows up in the trace:
+/// ```rust,ignore
+/// TIMEOUT => q_tran_if!(sm.retries < 3, retrying)
+/// ```
+#[macro_export]
+macro_rules! q_tran_if {
+    ($guard:expr, $target:expr) => {
+        $crate::hsm::QHsmResult::TranIf($target, $guard)
+    };
+}
+
+/// Transition to `$target`, running `$action` between exit and entry.
+///
+/// Returns `QHsmResult::TranWith($target, $action)` from a state handler.
+#[macro_export]
+macro_rules! q_tran_with {
+    ($target:expr, $action:expr) => {
+        $crate::hsm::QHsmResult::TranWith($target, $action)
+    };
+}
+
+/// Declares signals bound to their payload types.
+///
+/// Each entry becomes a `const` [`EventOf`](crate::event::EventOf); an entry
+/// without a type carries no payload:
+/// ```rust,ignore
+/// qf::typed_signals! {
+///     pub HUNGRY: TableMsg = 11;
+///     pub TIMEOUT = 10;
+/// }
+/// ```
+#[macro_export]
+macro_rules! typed_signals {
+    ($($(#[$meta:meta])* $vis:vis $name:ident $(: $payload:ty)? = $value:expr;)*) => {
+        $(
+            $(#[$meta])*
+            $vis const $name: $crate::event::EventOf<$crate::typed_signals!(@payload $($payload)?)> =
+                $crate::event::typed($crate::Signal($value));
+        )*
+    };
+    (@payload $payload:ty) => { $payload };
+    (@payload) => { () };
+}
+
 // ── QMsm convenience macros ───────────────────────────────────────────────────
 
 /// Declare a QMsm state transition to `$target`.
@@ -0,0 +1,320 @@
+//! Runtime metrics: what each active object and each signal costs.
+//!
+//! With metrics on ([`KernelBuilder::with_metrics`]), the cooperative kernel
+//! counts every dispatch twice: once against the priority of the active
+//! object that ran it and once against the signal of the event. Each count
+//! comes with the duration of the step, in units of the
+//! [budget clock](crate::budget), so [`Counters`] hold the number of
+//! dispatches and their mean and longest duration. An active object also
+//! reports the high-water mark of its queue.
+//!
+//! Dropped posts are counted too. A post to an id no object is registered
+//! under is charged to its signal. An event an [ISR queue](crate::isr_queue)
+//! refused is charged to the queue's target when the kernel next drains it.
+//!
+//! [`Metrics::emit`] sends one `QS_METRICS` record (id 94, a qp-rs
+//! extension) per active object and per signal seen, with payload
+//! `kind: u8 | key: u16 | dispatches: u32 | mean: u32 | max: u32 |
+//! high_watermark: u16 | dropped: u32`. `kind` is [`KIND_AO`], with the
+//! priority as `key`, or [`KIND_SIGNAL`], with the signal; a signal's
+//! high-water mark is `0`. [`KernelBuilder::emit_metrics_every`] makes
+//! [`QvKernel::run`] send them periodically.
+//!
+//! [`KernelBuilder::with_metrics`]: crate::KernelBuilder::with_metrics
+//! [`KernelBuilder::emit_metrics_every`]: crate::KernelBuilder::emit_metrics_every
+//! [`QvKernel::run`]: crate::QvKernel::run
+
+use portable_atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, AtomicU8, Ordering};
+
+use crate::active::{ActiveObjectId, ActiveRunnable};
+use crate::event::Signal;
+use crate::isr_queue::MAX_ISR_QUEUES;
+use crate::trace::TraceHook;
+
+/// Record id of one metrics entry.
+pub const QS_METRICS: u8 = qs_protocol::records::qf::METRICS;
+
+/// `kind` of a record about an active object.
+pub const KIND_AO: u8 = 0;
+/// `kind` of a record about a signal.
+pub const KIND_SIGNAL: u8 = 1;
+
+/// Priorities metrics are kept for (`0..MAX_AO_METRICS`).
+pub const MAX_AO_METRICS: usize = 64;
+/// Signals metrics are kept for (`0..MAX_SIGNAL_METRICS`); higher signals
+/// are not counted.
+pub const MAX_SIGNAL_METRICS: usize = 256;
+
+/// Dispatches and drops, with step durations in budget-clock units.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counters {
+    /// Events dispatched.
+    pub dispatches: u32,
+    /// Sum of the step durations.
+    pub total: u64,
+    /// Longest step.
+    pub max: u32,
+    /// Posts dropped.
+    pub dropped: u32,
+}
+
+impl Counters {
+    /// Mean step duration, or `None` before the first dispatch.
+    pub fn mean(&self) -> Option<u32> {
+        (self.dispatches > 0).then(|| (self.total / u64::from(self.dispatches)) as u32)
+    }
+}
+
+/// Metrics of one active object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AoMetrics {
+    /// The object's id.
+    pub id: ActiveObjectId,
+    /// The object's priority.
+    pub priority: u8,
+    /// Its dispatches and drops.
+    pub counters: Counters,
+    /// Most events ever waiting in its queue at once.
+    pub queue_high_watermark: usize,
+}
+
+/// Metrics of one signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignalMetrics {
+    /// The signal.
+    pub signal: Signal,
+    /// Its dispatches and drops.
+    pub counters: Counters,
+}
+
+struct CounterSlot {
+    dispatches: AtomicU32,
+    total: AtomicU64,
+    max: AtomicU32,
+    dropped: AtomicU32,
+}
+
+impl CounterSlot {
+    const fn new() -> Self {
+        Self {
+            dispatches: AtomicU32::new(0),
+            total: AtomicU64::new(0),
+            max: AtomicU32::new(0),
+            dropped: AtomicU32::new(0),
+        }
+    }
+
+    fn record(&self, elapsed: u32) {
+        self.dispatches.fetch_add(1, Ordering::Relaxed);
+        self.total.fetch_add(u64::from(elapsed), Ordering::Relaxed);
+        self.max.fetch_max(elapsed, Ordering::Relaxed);
+    }
+
+    fn load(&self) -> Counters {
+        Counters {
+            dispatches: self.dispatches.load(Ordering::Relaxed),
+            total: self.total.load(Ordering::Relaxed),
+            max: self.max.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    fn reset(&self) {
+        self.dispatches.store(0, Ordering::Relaxed);
+        self.total.store(0, Ordering::Relaxed);
+        self.max.store(0, Ordering::Relaxed);
+        self.dropped.store(0, Ordering::Relaxed);
+    }
+}
+
+struct AoSlot {
+    counters: CounterSlot,
+    id: AtomicU8,
+    high_watermark: AtomicU16,
+    seen: AtomicBool,
+}
+
+impl AoSlot {
+    const fn new() -> Self {
+        Self {
+            counters: CounterSlot::new(),
+            id: AtomicU8::new(0),
+            high_watermark: AtomicU16::new(0),
+            seen: AtomicBool::new(false),
+        }
+    }
+}
+
+/// The metrics of one kernel.
+pub struct Metrics {
+    enabled: bool,
+    period: u32,
+    last_emit: AtomicU32,
+    aos: [AoSlot; MAX_AO_METRICS],
+    signals: [CounterSlot; MAX_SIGNAL_METRICS],
+    /// Running drop totals of the ISR queues at the last drain.
+    isr_seen: [AtomicU32; MAX_ISR_QUEUES],
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// Metrics off.
+    pub const fn new() -> Self {
+        Self {
+            enabled: false,
+            period: 0,
+            last_emit: AtomicU32::new(0),
+            aos: [const { AoSlot::new() }; MAX_AO_METRICS],
+            signals: [const { CounterSlot::new() }; MAX_SIGNAL_METRICS],
+            isr_seen: [const { AtomicU32::new(0) }; MAX_ISR_QUEUES],
+        }
+    }
+
+    /// Starts counting.
+    pub fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    /// Returns `true` if metrics are being counted.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Emits the metrics every `ticks` ticks of the
+    /// [tick clock](crate::time::now) from [`emit_due`](Self::emit_due);
+    /// `0` stops the periodic emission.
+    pub fn set_emit_period(&mut self, ticks: u32) {
+        self.period = ticks;
+    }
+
+    /// Metrics of the active object at `priority`, or `None` if it has not
+    /// been seen.
+    pub fn ao(&self, priority: u8) -> Option<AoMetrics> {
+        let slot = self.aos.get(usize::from(priority))?;
+        slot.seen.load(Ordering::Relaxed).then(|| AoMetrics {
+            id: ActiveObjectId(slot.id.load(Ordering::Relaxed)),
+            priority,
+            counters: slot.counters.load(),
+            queue_high_watermark: usize::from(slot.high_watermark.load(Ordering::Relaxed)),
+        })
+    }
+
+    /// Metrics of every active object seen, lowest priority first.
+    pub fn aos(&self) -> impl Iterator<Item = AoMetrics> + '_ {
+        (0..MAX_AO_METRICS as u8).filter_map(|priority| self.ao(priority))
+    }
+
+    /// Metrics of `signal`, or `None` if it has been neither dispatched nor
+    /// dropped.
+    pub fn signal(&self, signal: Signal) -> Option<SignalMetrics> {
+        let counters = self.signals.get(usize::from(signal.0))?.load();
+        (counters != Counters::default()).then_some(SignalMetrics { signal, counters })
+    }
+
+    /// Metrics of every signal seen, lowest first.
+    pub fn signals(&self) -> impl Iterator<Item = SignalMetrics> + '_ {
+        (0..MAX_SIGNAL_METRICS as u16).filter_map(|signal| self.signal(Signal(signal)))
+    }
+
+    /// Starts the metrics afresh.
+    pub fn reset(&self) {
+        for slot in &self.aos {
+            slot.counters.reset();
+            slot.high_watermark.store(0, Ordering::Relaxed);
+            slot.seen.store(false, Ordering::Relaxed);
+        }
+        self.signals.iter().for_each(CounterSlot::reset);
+    }
+
+    /// Emits one `QS_METRICS` record per active object and per signal seen.
+    pub fn emit(&self, trace: &TraceHook) {
+        for ao in self.aos() {
+            emit_one(trace, KIND_AO, u16::from(ao.priority), &ao.counters, ao.queue_high_watermark);
+        }
+        for signal in self.signals() {
+            emit_one(trace, KIND_SIGNAL, signal.signal.0, &signal.counters, 0);
+        }
+    }
+
+    /// Emits the metrics if a period is set and has passed since the last
+    /// emission. Returns `true` if it emitted them.
+    pub fn emit_due(&self, trace: Option<&TraceHook>) -> bool {
+        let (Some(trace), true) = (trace, self.enabled && self.period != 0) else {
+            return false;
+        };
+        let now = crate::time::now().ticks();
+        let last = self.last_emit.load(Ordering::Relaxed);
+        if now.wrapping_sub(last) < self.period {
+            return false;
+        }
+        self.last_emit.store(now, Ordering::Relaxed);
+        self.emit(trace);
+        true
+    }
+
+    /// Runs `step`, one dispatch of `ao`, and counts it.
+    pub(crate) fn dispatch(&self, ao: &dyn ActiveRunnable, step: impl FnOnce() -> bool) -> bool {
+        if !self.enabled {
+            return step();
+        }
+        let signal = ao.front_signal().unwrap_or_default();
+        let start = crate::budget::now();
+        let handled = step();
+        let elapsed = crate::budget::now().wrapping_sub(start);
+        if !handled {
+            return false;
+        }
+        if let Some(slot) = self.ao_slot(ao) {
+            slot.counters.record(elapsed);
+            if let Some(stats) = ao.queue_stats() {
+                let high = u16::try_from(stats.high_watermark).unwrap_or(u16::MAX);
+                slot.high_watermark.fetch_max(high, Ordering::Relaxed);
+            }
+        }
+        if let Some(slot) = self.signals.get(usize::from(signal.0)) {
+            slot.record(elapsed);
+        }
+        true
+    }
+
+    /// Counts a post of `signal` that no object took.
+    pub(crate) fn dropped_signal(&self, signal: Signal) {
+        if let (true, Some(slot)) = (self.enabled, self.signals.get(usize::from(signal.0))) {
+            slot.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Charges to `ao` the events ISR queue `index` refused since the last
+    /// call, given the queue's running total.
+    pub(crate) fn isr_dropped(&self, index: usize, total: u32, ao: &dyn ActiveRunnable) {
+        let Some(seen) = self.isr_seen.get(index) else { return };
+        let count = total.wrapping_sub(seen.swap(total, Ordering::Relaxed));
+        if let (true, true, Some(slot)) = (self.enabled, count != 0, self.ao_slot(ao)) {
+            slot.counters.dropped.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    fn ao_slot(&self, ao: &dyn ActiveRunnable) -> Option<&AoSlot> {
+        let slot = self.aos.get(usize::from(ao.priority()))?;
+        slot.id.store(ao.id().0, Ordering::Relaxed);
+        slot.seen.store(true, Ordering::Relaxed);
+        Some(slot)
+    }
+}
+
+fn emit_one(trace: &TraceHook, kind: u8, key: u16, counters: &Counters, high_watermark: usize) {
+    let mut payload = [0u8; 21];
+    payload[0] = kind;
+    payload[1..3].copy_from_slice(&key.to_le_bytes());
+    payload[3..7].copy_from_slice(&counters.dispatches.to_le_bytes());
+    payload[7..11].copy_from_slice(&counters.mean().unwrap_or(0).to_le_bytes());
+    payload[11..15].copy_from_slice(&counters.max.to_le_bytes());
+    payload[15..17].copy_from_slice(&u16::try_from(high_watermark).unwrap_or(u16::MAX).to_le_bytes());
+    payload[17..].copy_from_slice(&counters.dropped.to_le_bytes());
+    let _ = trace(QS_METRICS, &payload, true);
+}
@@ -0,0 +1,545 @@
+//! Prometheus endpoint for host-side kernels (`metrics-export` feature).
+//!
+//! An [`Exporter`] listens on a TCP port and answers `GET /metrics` with the
+//! state of a kernel in the Prometheus text exposition format, which
+//! OpenMetrics scrapers read as well:
+//!
+//! - per active object: queue depth and high-water mark, and events taken,
+//!   a counter Prometheus turns into a dispatch rate;
+//! - RTC budget overruns and watchdog stalls;
+//! - with [metrics](crate::metrics) on, dispatches, step durations and
+//!   dropped posts per active object and per signal;
+//! - with [`TraceCounters`] wrapped around the trace hook, the records and
+//!   payload bytes emitted and the backend errors, plus the frames lost by
+//!   the QS trace rings.
+//!
+//! The exporter talks to scrapers on a thread of its own, so a slow or
+//! stalled scraper never holds up the kernel. The kernel's state is read
+//! only by [`Exporter::poll`], which renders it when a scrape is waiting and
+//! returns at once, so the tick function of [`QvKernel::run`] can call it:
+//!
+//! ```rust,ignore
+//! let exporter = Exporter::bind("127.0.0.1:9464")?;
+//! kernel.run(|| {
+//!     std::thread::sleep(TICK);
+//!     let _ = exporter.poll(&kernel);
+//! });
+//! ```
+//!
+//! A kernel other than [`QvKernel`] is exported through [`MetricsSource`].
+
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::string::String;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::vec::Vec;
+
+use portable_atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::active::ActiveRunnable;
+use crate::kernel::QvKernel;
+use crate::metrics::Metrics;
+
+/// Longest request head read from a scraper.
+const MAX_REQUEST: usize = 1024;
+
+/// How long a scraper gets to send its request, or to take the answer.
+const IO_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// How long a scrape waits for [`Exporter::poll`] to render a snapshot
+/// before it is answered with the previous one.
+const SNAPSHOT_WAIT: Duration = Duration::from_secs(1);
+
+/// What a kernel reports to the exporter.
+pub trait MetricsSource {
+    /// Calls `f` for every registered active object.
+    fn for_each_object(&self, f: &mut dyn FnMut(&dyn ActiveRunnable));
+
+    /// RTC budget overruns so far.
+    fn rtc_overruns(&self) -> u32;
+
+    /// Watchdog stalls so far, if the kernel has a watchdog.
+    fn stalls(&self) -> Option<u32> {
+        None
+    }
+
+    /// The kernel's metrics, if it keeps them.
+    fn metrics(&self) -> Option<&Metrics> {
+        None
+    }
+}
+
+impl MetricsSource for QvKernel {
+    fn for_each_object(&self, f: &mut dyn FnMut(&dyn ActiveRunnable)) {
+        self.active_objects().for_each(f);
+    }
+
+    fn rtc_overruns(&self) -> u32 {
+        self.rtc_budgets().overruns()
+    }
+
+    fn stalls(&self) -> Option<u32> {
+        self.watchdog().limit().map(|_| self.watchdog().stalls())
+    }
+
+    fn metrics(&self) -> Option<&Metrics> {
+        Some(QvKernel::metrics(self)).filter(|metrics| metrics.is_enabled())
+    }
+}
+
+/// Records, bytes and errors seen by a trace hook.
+#[derive(Debug, Default)]
+pub struct TraceCounters {
+    records: AtomicU64,
+    bytes: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl TraceCounters {
+    /// All counts at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A hook that passes every record to `hook` and counts the outcome.
+    #[cfg(any(feature = "qs", not(feature = "static-alloc")))]
+    pub fn wrap(self: &Arc<Self>, hook: crate::TraceHook) -> crate::TraceHook {
+        let counters = Arc::clone(self);
+        Arc::new(move |record, payload: &[u8], timestamp| {
+            let result = hook(record, payload, timestamp);
+            match result {
+                Ok(()) => {
+                    counters.records.fetch_add(1, Ordering::Relaxed);
+                    counters
+                        .bytes
+                        .fetch_add(payload.len() as u64, Ordering::Relaxed);
+                }
+                Err(_) => {
+                    counters.errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            result
+        })
+    }
+
+    /// Records the hook passed on.
+    pub fn records(&self) -> u64 {
+        self.records.load(Ordering::Relaxed)
+    }
+
+    /// Payload bytes of those records.
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Records the hook failed to pass on.
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+}
+
+/// A listening Prometheus endpoint.
+///
+/// Scrapes are accepted and answered on the exporter's own thread, which
+/// stops when the exporter is dropped.
+pub struct Exporter {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    server: Option<JoinHandle<()>>,
+    trace: Option<Arc<TraceCounters>>,
+}
+
+/// State handed between [`Exporter::poll`] and the exporter's thread.
+#[derive(Default)]
+struct Shared {
+    snapshot: Mutex<Snapshot>,
+    rendered: Condvar,
+    shutdown: AtomicBool,
+}
+
+#[derive(Default)]
+struct Snapshot {
+    /// The last exposition rendered, if any.
+    text: Option<String>,
+    /// Bumped whenever `text` is replaced.
+    generation: u64,
+    /// Scrapes waiting for the next rendering.
+    wanted: usize,
+    /// The error that stopped the exporter's thread.
+    error: Option<io::Error>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Snapshot> {
+        self.snapshot.lock().unwrap_or_else(|p| p.into_inner())
+    }
+}
+
+impl Exporter {
+    /// Listens on `addr` and starts the exporter's thread.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let shared = Arc::new(Shared::default());
+        let server = std::thread::Builder::new()
+            .name(String::from("qf-metrics-export"))
+            .spawn({
+                let shared = Arc::clone(&shared);
+                move || serve(listener, &shared)
+            })?;
+        Ok(Self {
+            addr,
+            shared,
+            server: Some(server),
+            trace: None,
+        })
+    }
+
+    /// Exports the trace counts in `counters` too.
+    pub fn with_trace_counters(mut self, counters: Arc<TraceCounters>) -> Self {
+        self.trace = Some(counters);
+        self
+    }
+
+    /// The address the exporter listens on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.addr)
+    }
+
+    /// Renders the state of `source` for the scrapes waiting on the
+    /// exporter's thread, and returns how many there were. Does nothing when
+    /// no scrape is waiting. Never blocks on a scraper.
+    ///
+    /// Returns the error that stopped the exporter's thread, once.
+    pub fn poll(&self, source: &dyn MetricsSource) -> io::Result<usize> {
+        {
+            let mut snapshot = self.shared.lock();
+            if let Some(err) = snapshot.error.take() {
+                return Err(err);
+            }
+            if snapshot.wanted == 0 {
+                return Ok(0);
+            }
+        }
+        let text = render(source, self.trace.as_deref());
+        let mut snapshot = self.shared.lock();
+        snapshot.text = Some(text);
+        snapshot.generation += 1;
+        let answered = core::mem::take(&mut snapshot.wanted);
+        self.shared.rendered.notify_all();
+        Ok(answered)
+    }
+}
+
+impl Drop for Exporter {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Release);
+        // Wake the thread blocked in `accept`.
+        let mut wake = self.addr;
+        if wake.ip().is_unspecified() {
+            wake.set_ip(match wake {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        let _ = TcpStream::connect_timeout(&wake, IO_TIMEOUT);
+        if let Some(server) = self.server.take() {
+            let _ = server.join();
+        }
+    }
+}
+
+/// The exporter's thread: answers scrapes one at a time until shut down.
+fn serve(listener: TcpListener, shared: &Shared) {
+    for stream in listener.incoming() {
+        if shared.shutdown.load(Ordering::Acquire) {
+            return;
+        }
+        match stream {
+            // A scraper that fails half-way is skipped.
+            Ok(stream) => {
+                let _ = answer(stream, shared);
+            }
+            Err(err) => {
+                shared.lock().error = Some(err);
+                return;
+            }
+        }
+    }
+}
+
+fn answer(mut stream: TcpStream, shared: &Shared) -> io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let head = read_head(&mut stream)?;
+    let path = head.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = match path {
+        "/metrics" | "/" => match snapshot(shared) {
+            Some(text) => ("200 OK", text),
+            None => ("503 Service Unavailable", String::from("no snapshot yet\n")),
+        },
+        _ => ("404 Not Found", String::from("not found\n")),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// Waits for [`Exporter::poll`] to render a fresh snapshot, and falls back
+/// to the previous one if it does not within [`SNAPSHOT_WAIT`].
+fn snapshot(shared: &Shared) -> Option<String> {
+    let mut snapshot = shared.lock();
+    let seen = snapshot.generation;
+    snapshot.wanted += 1;
+    let (mut snapshot, _) = shared
+        .rendered
+        .wait_timeout_while(snapshot, SNAPSHOT_WAIT, |s| s.generation == seen)
+        .unwrap_or_else(|p| p.into_inner());
+    if snapshot.generation == seen {
+        // `poll` did not come round, so this scrape is still counted.
+        snapshot.wanted -= 1;
+    }
+    snapshot.text.clone()
+}
+
+/// Reads up to the end of the request head.
+fn read_head(stream: &mut TcpStream) -> io::Result<String> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 256];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST {
+        match stream.read(&mut buf)? {
+            0 => break,
+            n => head.extend_from_slice(&buf[..n]),
+        }
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// The state of `source`, and the counts in `trace`, in the Prometheus text
+/// format.
+pub fn render(source: &dyn MetricsSource, trace: Option<&TraceCounters>) -> String {
+    let mut out = Exposition::default();
+
+    let mut objects = Vec::new();
+    source.for_each_object(&mut |ao| {
+        if let Some(stats) = ao.queue_stats() {
+            objects.push((ao.id().0, ao.priority(), stats));
+        }
+    });
+    let label = |id: u8, prio: u8| format!("ao=\"{id}\",prio=\"{prio}\"");
+    out.family(
+        "qf_queue_depth",
+        "gauge",
+        "Events waiting in an active object's queue.",
+    );
+    for (id, prio, stats) in &objects {
+        out.sample("qf_queue_depth", &label(*id, *prio), stats.len as u64);
+    }
+    out.family(
+        "qf_queue_high_watermark",
+        "gauge",
+        "Most events ever waiting in the queue at once.",
+    );
+    for (id, prio, stats) in &objects {
+        out.sample(
+            "qf_queue_high_watermark",
+            &label(*id, *prio),
+            stats.high_watermark as u64,
+        );
+    }
+    out.family(
+        "qf_events_taken_total",
+        "counter",
+        "Events taken from the queue.",
+    );
+    for (id, prio, stats) in &objects {
+        out.sample(
+            "qf_events_taken_total",
+            &label(*id, *prio),
+            u64::from(stats.taken),
+        );
+    }
+
+    out.family(
+        "qf_rtc_overruns_total",
+        "counter",
+        "Run-to-completion steps over budget.",
+    );
+    out.sample(
+        "qf_rtc_overruns_total",
+        "",
+        u64::from(source.rtc_overruns()),
+    );
+    if let Some(stalls) = source.stalls() {
+        out.family(
+            "qf_watchdog_stalls_total",
+            "counter",
+            "Active objects reported stalled.",
+        );
+        out.sample("qf_watchdog_stalls_total", "", u64::from(stalls));
+    }
+
+    if let Some(metrics) = source.metrics() {
+        render_metrics(&mut out, metrics);
+    }
+
+    if let Some(trace) = trace {
+        out.family(
+            "qs_trace_records_total",
+            "counter",
+            "Trace records emitted.",
+        );
+        out.sample("qs_trace_records_total", "", trace.records());
+        out.family(
+            "qs_trace_bytes_total",
+            "counter",
+            "Payload bytes of the trace records emitted.",
+        );
+        out.sample("qs_trace_bytes_total", "", trace.bytes());
+        out.family(
+            "qs_trace_errors_total",
+            "counter",
+            "Trace records the backend failed to take.",
+        );
+        out.sample("qs_trace_errors_total", "", trace.errors());
+    }
+    #[cfg(feature = "qs")]
+    {
+        let lost = qs::stats();
+        out.family(
+            "qs_frames_dropped_total",
+            "counter",
+            "Frames dropped by a full trace ring.",
+        );
+        out.sample("qs_frames_dropped_total", "", u64::from(lost.dropped));
+        out.family(
+            "qs_frames_overwritten_total",
+            "counter",
+            "Frames overwritten in a full trace ring.",
+        );
+        out.sample(
+            "qs_frames_overwritten_total",
+            "",
+            u64::from(lost.overwritten),
+        );
+    }
+    out.text
+}
+
+fn render_metrics(out: &mut Exposition, metrics: &Metrics) {
+    let aos: Vec<_> = metrics.aos().collect();
+    let signals: Vec<_> = metrics.signals().collect();
+    let prio = |prio: u8| format!("prio=\"{prio}\"");
+    let signal = |signal: u16| format!("signal=\"{signal}\"");
+
+    out.family(
+        "qf_dispatches_total",
+        "counter",
+        "Events dispatched to an active object.",
+    );
+    for ao in &aos {
+        out.sample(
+            "qf_dispatches_total",
+            &prio(ao.priority),
+            u64::from(ao.counters.dispatches),
+        );
+    }
+    out.family(
+        "qf_dispatch_time_mean",
+        "gauge",
+        "Mean step of an active object, in budget-clock units.",
+    );
+    for ao in &aos {
+        out.sample(
+            "qf_dispatch_time_mean",
+            &prio(ao.priority),
+            u64::from(ao.counters.mean().unwrap_or(0)),
+        );
+    }
+    out.family(
+        "qf_dispatch_time_max",
+        "gauge",
+        "Longest step of an active object, in budget-clock units.",
+    );
+    for ao in &aos {
+        out.sample(
+            "qf_dispatch_time_max",
+            &prio(ao.priority),
+            u64::from(ao.counters.max),
+        );
+    }
+    out.family(
+        "qf_posts_dropped_total",
+        "counter",
+        "Events refused on their way to an active object.",
+    );
+    for ao in &aos {
+        out.sample(
+            "qf_posts_dropped_total",
+            &prio(ao.priority),
+            u64::from(ao.counters.dropped),
+        );
+    }
+    out.family(
+        "qf_signal_dispatches_total",
+        "counter",
+        "Events dispatched, by signal.",
+    );
+    for s in &signals {
+        out.sample(
+            "qf_signal_dispatches_total",
+            &signal(s.signal.0),
+            u64::from(s.counters.dispatches),
+        );
+    }
+    out.family(
+        "qf_signal_dispatch_time_max",
+        "gauge",
+        "Longest step, by signal, in budget-clock units.",
+    );
+    for s in &signals {
+        out.sample(
+            "qf_signal_dispatch_time_max",
+            &signal(s.signal.0),
+            u64::from(s.counters.max),
+        );
+    }
+    out.family(
+        "qf_signal_posts_dropped_total",
+        "counter",
+        "Posts no active object took, by signal.",
+    );
+    for s in &signals {
+        out.sample(
+            "qf_signal_posts_dropped_total",
+            &signal(s.signal.0),
+            u64::from(s.counters.dropped),
+        );
+    }
+}
+
+/// Text exposition being written.
+#[derive(Default)]
+struct Exposition {
+    text: String,
+}
+
+impl Exposition {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.text, "# HELP {name} {help}\n# TYPE {name} {kind}");
+    }
+
+    fn sample(&mut self, name: &str, labels: &str, value: u64) {
+        let _ = match labels {
+            "" => writeln!(self.text, "{name} {value}"),
+            _ => writeln!(self.text, "{name}{{{labels}}} {value}"),
+        };
+    }
+}
@@ -0,0 +1,443 @@
+//! Active-object state that survives power cycles.
+//!
+//! A device that loses power halfway through a workflow has to pick up where
+//! it left off. The behaviours of the objects involved implement
+//! [`Persistent`], turning the part of their extended state worth keeping into
+//! a blob and back. A [`Persistence`] service holds those objects and a
+//! [`BlobStore`]:
+//!
+//! * at boot, [`restore_all`](Persistence::restore_all) runs before
+//!   `kernel.start()`, so each initial transition already sees the saved
+//!   state;
+//! * [`save`](Persistence::save) stores one object on request, e.g. after a
+//!   workflow step completes, and [`save_all`](Persistence::save_all) stores
+//!   every object on an orderly shutdown.
+//!
+//! Each save and restore is traced as `QS_AO_SAVE` or `QS_AO_RESTORE` with the
+//! object, the blob size and a [`PersistStatus`].
+//!
+//! [`FlashStore`] keeps the blobs in raw flash sectors, such as the data
+//! partition the ESP32 ports expose. Every object owns up to two sectors and a
+//! save goes to the one not holding the newest copy, so losing power during a
+//! save leaves the previous state in place.
+//!
+//! ```rust,ignore
+//! static OBJECTS: [&dyn PersistentActive; 2] = [&DOSER, &LOGGER];
+//!
+//! let mut persistence = Persistence::<_, 128>::new(FlashStore::new(nvs), &OBJECTS)
+//!     .with_trace_hook(hook.clone());
+//! persistence.restore_all();
+//! kernel.start();
+//! ```
+
+use core::fmt;
+
+use crate::active::{ActiveBehavior, ActiveObject, ActiveObjectId};
+use crate::trace::{emit_record, TraceHook};
+
+/// Extended state that can be saved and restored.
+pub trait Persistent {
+    /// Writes the state to keep into `buf` and returns its length, or `None`
+    /// if it does not fit.
+    fn save(&self, buf: &mut [u8]) -> Option<usize>;
+
+    /// Takes the state back from a saved blob. Returning `false` rejects the
+    /// blob (e.g. one written by an older firmware) and keeps the state the
+    /// behaviour was built with.
+    fn restore(&mut self, blob: &[u8]) -> bool;
+}
+
+/// An active object whose behaviour is [`Persistent`].
+pub trait PersistentActive: Sync {
+    /// The object's id, which keys its blob in the store.
+    fn id(&self) -> ActiveObjectId;
+
+    /// Address QS reports for the object.
+    fn qs_addr(&self) -> u64;
+
+    /// See [`Persistent::save`].
+    fn save_state(&self, buf: &mut [u8]) -> Option<usize>;
+
+    /// See [`Persistent::restore`].
+    fn restore_state(&self, blob: &[u8]) -> bool;
+}
+
+impl<B: ActiveBehavior + Persistent> PersistentActive for ActiveObject<B> {
+    fn id(&self) -> ActiveObjectId {
+        crate::active::ActiveRunnable::id(self)
+    }
+
+    fn qs_addr(&self) -> u64 {
+        self as *const Self as usize as u64
+    }
+
+    fn save_state(&self, buf: &mut [u8]) -> Option<usize> {
+        self.with_behavior(|b| b.save(buf))
+    }
+
+    fn restore_state(&self, blob: &[u8]) -> bool {
+        self.with_behavior_mut(|b| b.restore(blob))
+    }
+}
+
+/// Non-volatile storage of one blob per active object.
+pub trait BlobStore {
+    type Error: fmt::Debug;
+
+    /// Reads the blob of `id` into `buf` and returns its length, or `None` if
+    /// nothing is stored for `id`.
+    fn load(&mut self, id: ActiveObjectId, buf: &mut [u8]) -> Result<Option<usize>, Self::Error>;
+
+    /// Replaces the blob of `id`.
+    fn store(&mut self, id: ActiveObjectId, blob: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Outcome of a save or restore, as carried by its QS record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PersistStatus {
+    Ok = 0,
+    /// Nothing was stored for the object.
+    Missing = 1,
+    /// The behaviour rejected the blob.
+    Rejected = 2,
+    /// The state does not fit the buffer.
+    TooLarge = 3,
+    /// The store failed.
+    StoreFailed = 4,
+}
+
+/// Errors of [`Persistence`] operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PersistError<E> {
+    /// The object is not one of the service's objects.
+    NotFound(ActiveObjectId),
+    /// The object's state does not fit the service's buffer.
+    TooLarge(ActiveObjectId),
+    /// The behaviour rejected its saved blob.
+    Rejected(ActiveObjectId),
+    /// The store failed.
+    Store(E),
+}
+
+impl<E> PersistError<E> {
+    fn status(&self) -> PersistStatus {
+        match self {
+            Self::NotFound(_) => PersistStatus::Missing,
+            Self::TooLarge(_) => PersistStatus::TooLarge,
+            Self::Rejected(_) => PersistStatus::Rejected,
+            Self::Store(_) => PersistStatus::StoreFailed,
+        }
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for PersistError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(id) => write!(f, "active object {id:?} is not persistent"),
+            Self::TooLarge(id) => write!(f, "state of active object {id:?} is too large"),
+            Self::Rejected(id) => write!(f, "active object {id:?} rejected its saved state"),
+            Self::Store(e) => write!(f, "store error: {e:?}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug> std::error::Error for PersistError<E> {}
+
+/// Saves and restores the state of a fixed set of active objects. Blobs are
+/// staged in a `BLOB`-byte stack buffer.
+pub struct Persistence<'a, S: BlobStore, const BLOB: usize = 256> {
+    store: S,
+    objects: &'a [&'a dyn PersistentActive],
+    trace: Option<TraceHook>,
+}
+
+impl<'a, S: BlobStore, const BLOB: usize> Persistence<'a, S, BLOB> {
+    pub fn new(store: S, objects: &'a [&'a dyn PersistentActive]) -> Self {
+        Self { store, objects, trace: None }
+    }
+
+    /// Traces saves and restores through `hook`.
+    pub fn with_trace_hook(mut self, hook: TraceHook) -> Self {
+        self.trace = Some(hook);
+        self
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn store_mut(&mut self) -> &mut S {
+        &mut self.store
+    }
+
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    fn object(&self, id: ActiveObjectId) -> Result<&'a dyn PersistentActive, PersistError<S::Error>> {
+        self.objects.iter().copied().find(|o| o.id() == id).ok_or(PersistError::NotFound(id))
+    }
+
+    /// Restores the saved state of `id`. Returns `false` if none was saved.
+    /// Call before the kernel starts the object.
+    pub fn restore(&mut self, id: ActiveObjectId) -> Result<bool, PersistError<S::Error>> {
+        let object = self.object(id)?;
+        let mut buf = [0u8; BLOB];
+        let (len, result) = match self.store.load(id, &mut buf) {
+            Ok(Some(len)) if len > BLOB => (len, Err(PersistError::TooLarge(id))),
+            Ok(Some(len)) if object.restore_state(&buf[..len]) => (len, Ok(true)),
+            Ok(Some(len)) => (len, Err(PersistError::Rejected(id))),
+            Ok(None) => (0, Ok(false)),
+            Err(e) => (0, Err(PersistError::Store(e))),
+        };
+        let status = match &result {
+            Ok(true) => PersistStatus::Ok,
+            Ok(false) => PersistStatus::Missing,
+            Err(e) => e.status(),
+        };
+        self.trace_restore(object, len, status);
+        result
+    }
+
+    /// Restores every object that has saved state and returns how many did.
+    /// Objects without usable state keep their initial state; their records
+    /// say why.
+    pub fn restore_all(&mut self) -> usize {
+        let objects = self.objects;
+        objects.iter().filter(|o| matches!(self.restore(o.id()), Ok(true))).count()
+    }
+
+    /// Saves the state of `id`.
+    pub fn save(&mut self, id: ActiveObjectId) -> Result<(), PersistError<S::Error>> {
+        let object = self.object(id)?;
+        let mut buf = [0u8; BLOB];
+        let (len, result) = match object.save_state(&mut buf) {
+            Some(len) if len <= BLOB => (len, self.store.store(id, &buf[..len]).map_err(PersistError::Store)),
+            _ => (0, Err(PersistError::TooLarge(id))),
+        };
+        let status = result.as_ref().map_or_else(PersistError::status, |()| PersistStatus::Ok);
+        self.trace_save(object, len, status);
+        result
+    }
+
+    /// Saves every object, e.g. on an orderly shutdown. An object that fails
+    /// does not stop the others; the first error is returned.
+    pub fn save_all(&mut self) -> Result<(), PersistError<S::Error>> {
+        let objects = self.objects;
+        let mut first = Ok(());
+        for object in objects {
+            if let Err(e) = self.save(object.id()) {
+                first = first.and(Err(e));
+            }
+        }
+        first
+    }
+
+    #[allow(unused_variables)] // no QS record without the `qs` feature
+    fn trace_save(&self, object: &dyn PersistentActive, len: usize, status: PersistStatus) {
+        if let Some(trace) = &self.trace {
+            emit_record!(trace, qs::records::qf::ao_save(object.qs_addr(), len as u16, status as u8));
+        }
+    }
+
+    #[allow(unused_variables)] // no QS record without the `qs` feature
+    fn trace_restore(&self, object: &dyn PersistentActive, len: usize, status: PersistStatus) {
+        if let Some(trace) = &self.trace {
+            emit_record!(trace, qs::records::qf::ao_restore(object.qs_addr(), len as u16, status as u8));
+        }
+    }
+}
+
+/// Raw flash: erasable sectors of [`SECTOR_SIZE`](Self::SECTOR_SIZE) bytes
+/// whose erased bytes read `0xFF`.
+pub trait Flash {
+    type Error: fmt::Debug;
+
+    /// Bytes per erasable sector.
+    const SECTOR_SIZE: usize;
+
+    /// Sectors available.
+    fn sectors(&self) -> usize;
+
+    /// Reads `buf.len()` bytes from `offset`.
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Erases sector `sector` to `0xFF`.
+    fn erase(&mut self, sector: usize) -> Result<(), Self::Error>;
+
+    /// Programs `data` at `offset`, a multiple of four, in an erased area.
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Errors of a [`FlashStore`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlashStoreError<E> {
+    Flash(E),
+    /// No sector is free for a new object.
+    Full,
+    /// The blob does not fit a sector, or the caller's buffer.
+    TooLarge,
+    /// The newest copy fails its checksum.
+    Corrupt,
+}
+
+/// `magic | id | 0 | seq | len | crc32` at the start of each used sector.
+const HEADER: usize = 12;
+const MAGIC: u16 = 0x5150;
+
+#[derive(Debug, Clone, Copy)]
+struct Header {
+    id: u8,
+    seq: u16,
+    len: usize,
+    crc: u32,
+}
+
+impl Header {
+    fn encode(&self) -> [u8; HEADER] {
+        let mut out = [0u8; HEADER];
+        out[0..2].copy_from_slice(&MAGIC.to_le_bytes());
+        out[2] = self.id;
+        out[4..6].copy_from_slice(&self.seq.to_le_bytes());
+        out[6..8].copy_from_slice(&(self.len as u16).to_le_bytes());
+        out[8..12].copy_from_slice(&self.crc.to_le_bytes());
+        out
+    }
+
+    fn decode(raw: &[u8; HEADER]) -> Option<Self> {
+        (u16::from_le_bytes([raw[0], raw[1]]) == MAGIC).then(|| Self {
+            id: raw[2],
+            seq: u16::from_le_bytes([raw[4], raw[5]]),
+            len: usize::from(u16::from_le_bytes([raw[6], raw[7]])),
+            crc: u32::from_le_bytes([raw[8], raw[9], raw[10], raw[11]]),
+        })
+    }
+
+    /// Whether this copy was written after `other`.
+    fn newer_than(&self, other: &Self) -> bool {
+        (self.seq.wrapping_sub(other.seq) as i16) > 0
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// A [`BlobStore`] on raw [`Flash`] sectors, safe against power loss during a
+/// save.
+pub struct FlashStore<F: Flash> {
+    flash: F,
+}
+
+impl<F: Flash> FlashStore<F> {
+    pub fn new(flash: F) -> Self {
+        Self { flash }
+    }
+
+    pub fn into_inner(self) -> F {
+        self.flash
+    }
+
+    fn header(&mut self, sector: usize) -> Result<Option<Header>, F::Error> {
+        let mut raw = [0u8; HEADER];
+        self.flash.read(sector * F::SECTOR_SIZE, &mut raw)?;
+        Ok(Header::decode(&raw))
+    }
+
+    /// The sectors holding copies of `id`, newest first, and the first free
+    /// sector.
+    #[allow(clippy::type_complexity)]
+    fn scan(&mut self, id: u8) -> Result<([Option<(usize, Header)>; 2], Option<usize>), F::Error> {
+        let mut copies: [Option<(usize, Header)>; 2] = [None, None];
+        let mut free = None;
+        for sector in 0..self.flash.sectors() {
+            match self.header(sector)? {
+                Some(h) if h.id == id => {
+                    let newest = copies[0].is_none_or(|(_, n)| h.newer_than(&n));
+                    if newest {
+                        copies[1] = copies[0];
+                        copies[0] = Some((sector, h));
+                    } else {
+                        copies[1] = Some((sector, h));
+                    }
+                }
+                Some(_) => {}
+                None => {
+                    if free.is_none() && self.is_erased(sector)? {
+                        free = Some(sector);
+                    }
+                }
+            }
+        }
+        Ok((copies, free))
+    }
+
+    fn is_erased(&mut self, sector: usize) -> Result<bool, F::Error> {
+        let mut raw = [0u8; HEADER];
+        self.flash.read(sector * F::SECTOR_SIZE, &mut raw)?;
+        Ok(raw.iter().all(|&b| b == 0xFF))
+    }
+
+    fn read_copy(&mut self, sector: usize, header: &Header, buf: &mut [u8]) -> Result<bool, F::Error> {
+        let data = &mut buf[..header.len];
+        self.flash.read(sector * F::SECTOR_SIZE + HEADER, data)?;
+        Ok(crc32(data) == header.crc)
+    }
+}
+
+impl<F: Flash> BlobStore for FlashStore<F> {
+    type Error = FlashStoreError<F::Error>;
+
+    /// Reads the newest intact copy; an older one stands in for a newest copy
+    /// that a power loss left half-written.
+    fn load(&mut self, id: ActiveObjectId, buf: &mut [u8]) -> Result<Option<usize>, Self::Error> {
+        let (copies, _) = self.scan(id.0).map_err(FlashStoreError::Flash)?;
+        let mut found = false;
+        for (sector, header) in copies.into_iter().flatten() {
+            found = true;
+            if header.len > buf.len() {
+                return Err(FlashStoreError::TooLarge);
+            }
+            if self.read_copy(sector, &header, buf).map_err(FlashStoreError::Flash)? {
+                return Ok(Some(header.len));
+            }
+        }
+        if found {
+            Err(FlashStoreError::Corrupt)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Writes a new copy over the older one, or into a free sector, and never
+    /// touches the newest copy. Only when no sector is free does an object's
+    /// single copy get rewritten in place.
+    fn store(&mut self, id: ActiveObjectId, blob: &[u8]) -> Result<(), Self::Error> {
+        if blob.len() > F::SECTOR_SIZE - HEADER || blob.len() > usize::from(u16::MAX) {
+            return Err(FlashStoreError::TooLarge);
+        }
+        let (copies, free) = self.scan(id.0).map_err(FlashStoreError::Flash)?;
+        let target = match (copies, free) {
+            ([_, Some((older, _))], _) => older,
+            (_, Some(free)) => free,
+            ([Some((only, _)), None], None) => only,
+            _ => return Err(FlashStoreError::Full),
+        };
+        let seq = copies[0].map_or(0, |(_, h)| h.seq.wrapping_add(1));
+        let header = Header { id: id.0, seq, len: blob.len(), crc: crc32(blob) };
+        let base = target * F::SECTOR_SIZE;
+        self.flash.erase(target).map_err(FlashStoreError::Flash)?;
+        self.flash.write(base + HEADER, blob).map_err(FlashStoreError::Flash)?;
+        // The header goes last: until it is written the sector reads as free.
+        self.flash.write(base, &header.encode()).map_err(FlashStoreError::Flash)
+    }
+}
@@ -260,3 +260,14 @@ impl QMPool {
         !self.storage.is_null()
     }
 }
+
+/// Answers host queries as [`MP_OBJ`](qs::query::ObjKind::Mp).
+#[cfg(feature = "qs")]
+impl qs::query::Queryable for QMPool {
+    fn query(&self, kind: qs::query::ObjKind) -> Option<qs::query::Snapshot> {
+        (kind == qs::query::ObjKind::Mp).then(|| qs::query::Snapshot::Mp {
+            n_free: self.get_free() as u32,
+            n_min: self.get_min() as u32,
+        })
+    }
+}
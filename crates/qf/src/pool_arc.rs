@@ -144,6 +144,27 @@ impl PoolArc {
     pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
         self.as_any().downcast_ref::<T>()
     }
+
+    /// The pool the payload was allocated from, or `None` for
+    /// [`empty`](Self::empty) payloads.
+    pub fn pool_id(&self) -> Option<u8> {
+        match self.repr {
+            Repr::Empty => None,
+            // SAFETY: a live strong reference keeps the control block valid.
+            Repr::Pooled(p) => Some(unsafe { p.as_ref().pool_id.get() }),
+        }
+    }
+
+    /// Number of live references to the pooled block; `0` for
+    /// [`empty`](Self::empty) payloads, which are never collected, like QP's
+    /// static events.
+    pub fn ref_count(&self) -> u16 {
+        match self.repr {
+            Repr::Empty => 0,
+            // SAFETY: a live strong reference keeps the control block valid.
+            Repr::Pooled(p) => unsafe { p.as_ref().ref_count.load() },
+        }
+    }
 }
 
 impl Clone for PoolArc {
@@ -234,6 +255,25 @@ mod tests {
         assert_eq!(POOL_REGISTRY.get_free(pool_id).unwrap(), before);
     }
 
+    #[test]
+    fn dynamic_events_carry_the_pool_and_live_reference_count() {
+        use crate::event::{DynEvent, Signal};
+
+        let pool_id = ensure_pool();
+        let event = DynEvent::with_payload(Signal(10), 7u32);
+        assert_eq!(event.header.pool_id, Some(pool_id));
+        assert_eq!(event.ref_count(), 1);
+
+        let subscriber = event.clone();
+        assert_eq!(event.ref_count(), 2);
+        drop(subscriber);
+        assert_eq!(event.ref_count(), 1);
+
+        let signal_only = DynEvent::empty_dyn(Signal(11));
+        assert_eq!(signal_only.header.pool_id, None);
+        assert_eq!(signal_only.ref_count(), 0, "signal-only events are static");
+    }
+
     #[test]
     fn empty_is_allocation_free() {
         let pool_id = ensure_pool();
@@ -0,0 +1,409 @@
+//! Recording posted events and replaying them into a fresh kernel.
+//!
+//! A bug that depends on how events interleave is hard to reproduce from a
+//! description. While a recording is on, every event posted to an active
+//! object is logged as a [`ReplayEntry`]: the target, the signal, a snapshot
+//! of the payload taken by a [`PayloadCodec`], the tick it was posted on and
+//! where it came from. [`ReplayLog::replay`] then feeds the same sequence to a
+//! newly built kernel, ticking its timer wheel in between so that timeouts
+//! fall where they fell in the original run.
+//!
+//! Only posts from outside the application are replayed: those made by an
+//! ISR, a driver or `main`. The kernel under replay makes the posts of its
+//! own active objects and time events again by itself.
+//!
+//! Each entry also goes out through the receiving object's trace hook as a
+//! `QS_QF_EVT_LOG` record, so a target can record into its QS stream. A
+//! binary capture saved by qspy turns back into a log with
+//! [`ReplayLog::from_capture`]:
+//!
+//! ```rust,ignore
+//! // on the target
+//! qf::replay::start_recording(Arc::new(SensorCodec));
+//!
+//! // on the host
+//! let log = ReplayLog::from_capture(&std::fs::read("field.qs")?);
+//! let sim = qf_port_sim::SimRuntime::with_builder(app::builder())?;
+//! log.replay(&*sim.kernel(), &sim, &SensorCodec)?;
+//! ```
+//!
+//! With `std` a recording belongs to the thread that started it, like
+//! [`current_ao`](crate::current_ao), so host tests running in parallel keep
+//! apart. Without `std` there is one recording for the whole target.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use portable_atomic::{AtomicBool, Ordering};
+
+use crate::active::ActiveObjectId;
+use crate::event::{DynEvent, DynPayload, Signal};
+use crate::port::Runtime;
+use crate::services::{KernelServices, ServiceError};
+use crate::sync::Arc;
+use crate::trace::TraceHook;
+
+/// `QS_QF_EVT_LOG` record id.
+pub const QS_EVT_LOG: u8 = qs_protocol::records::qf::EVT_LOG;
+
+/// Bytes of a `QS_QF_EVT_LOG` record before the payload snapshot.
+pub const RECORD_HEADER_LEN: usize = 12;
+
+/// Takes snapshots of event payloads and rebuilds payloads from them.
+pub trait PayloadCodec: Send + Sync {
+    /// Snapshot of the payload of an event with `signal`, or `None` if it
+    /// carries nothing worth keeping.
+    fn encode(&self, signal: Signal, payload: &DynPayload) -> Option<Vec<u8>>;
+
+    /// Payload for a replayed event with `signal` from its snapshot.
+    fn decode(&self, signal: Signal, snapshot: &[u8]) -> DynPayload;
+}
+
+/// Codec for applications whose events carry no parameters: snapshots are
+/// empty and replayed events have a `()` payload.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SignalOnly;
+
+impl PayloadCodec for SignalOnly {
+    fn encode(&self, _signal: Signal, _payload: &DynPayload) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn decode(&self, _signal: Signal, _snapshot: &[u8]) -> DynPayload {
+        Arc::new(())
+    }
+}
+
+/// Where a logged post came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostSource {
+    /// Outside any active object and timer wheel: an ISR, a driver, `main`.
+    External,
+    /// A time event expiring.
+    TimeEvent,
+    /// An active object's handler.
+    Ao(ActiveObjectId),
+}
+
+/// One logged post.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayEntry {
+    /// Tick the event was posted on.
+    pub tick: u32,
+    /// Receiving active object.
+    pub target: ActiveObjectId,
+    /// Event signal.
+    pub signal: Signal,
+    /// Origin of the post.
+    pub source: PostSource,
+    /// Posted to the front of the queue.
+    pub lifo: bool,
+    /// Payload snapshot; empty if the codec kept none.
+    pub payload: Vec<u8>,
+}
+
+impl ReplayEntry {
+    /// The `QS_QF_EVT_LOG` payload: `tick: u32 | target: u8 | source: u8 |
+    /// sender: u8 | lifo: u8 | sig: u16 | len: u16 | snapshot`, little-endian.
+    /// `source` is 0 external, 1 time event, 2 active object (`sender`).
+    pub fn encode(&self) -> Vec<u8> {
+        let (source, sender) = match self.source {
+            PostSource::External => (0, 0),
+            PostSource::TimeEvent => (1, 0),
+            PostSource::Ao(id) => (2, id.0),
+        };
+        let mut out = Vec::with_capacity(RECORD_HEADER_LEN + self.payload.len());
+        out.extend_from_slice(&self.tick.to_le_bytes());
+        out.extend_from_slice(&[self.target.0, source, sender, u8::from(self.lifo)]);
+        out.extend_from_slice(&self.signal.0.to_le_bytes());
+        out.extend_from_slice(&(self.payload.len() as u16).to_le_bytes());
+        out.extend_from_slice(&self.payload);
+        out
+    }
+
+    /// Parses a `QS_QF_EVT_LOG` payload; `None` if it is malformed.
+    pub fn decode(record: &[u8]) -> Option<Self> {
+        let header = record.get(..RECORD_HEADER_LEN)?;
+        let len = usize::from(u16::from_le_bytes([header[10], header[11]]));
+        let payload = record.get(RECORD_HEADER_LEN..RECORD_HEADER_LEN + len)?;
+        let source = match header[5] {
+            0 => PostSource::External,
+            1 => PostSource::TimeEvent,
+            2 => PostSource::Ao(ActiveObjectId(header[6])),
+            _ => return None,
+        };
+        Some(Self {
+            tick: u32::from_le_bytes([header[0], header[1], header[2], header[3]]),
+            target: ActiveObjectId(header[4]),
+            signal: Signal(u16::from_le_bytes([header[8], header[9]])),
+            source,
+            lifo: header[7] != 0,
+            payload: payload.to_vec(),
+        })
+    }
+}
+
+/// Posts logged by one recording, in the order they were made.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayLog {
+    /// Tick the recording started on.
+    pub start_tick: u32,
+    /// Tick the recording stopped on.
+    pub end_tick: u32,
+    /// Logged posts.
+    pub entries: Vec<ReplayEntry>,
+}
+
+/// Outcome of [`ReplayLog::replay`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplaySummary {
+    /// External posts made again.
+    pub posted: usize,
+    /// Posts left to the kernel under replay.
+    pub skipped: usize,
+    /// Ticks the runtime was advanced by.
+    pub ticks: u32,
+}
+
+/// Why a replay stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError<T> {
+    /// Ticking the runtime failed.
+    Tick(T),
+    /// The kernel under replay refused a post.
+    Post(ServiceError),
+}
+
+impl<T: fmt::Debug> fmt::Display for ReplayError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tick(e) => write!(f, "tick failed during replay: {e:?}"),
+            Self::Post(e) => write!(f, "replayed post failed: {e}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> std::error::Error for ReplayError<T> {}
+
+impl ReplayLog {
+    /// Collects the `QS_QF_EVT_LOG` records of a binary QS capture, such as
+    /// one saved by qspy. Frames with a bad checksum are skipped. The capture
+    /// does not say when recording started and stopped, so both are taken
+    /// from the first and last entry.
+    #[cfg(feature = "qs")]
+    pub fn from_capture(capture: &[u8]) -> Self {
+        use qs::hdlc::{checksum_ok, Unstuffed, Unstuffer};
+
+        let mut log = Self::default();
+        let mut unstuffer = Unstuffer::new();
+        let mut body = Vec::new();
+        for &byte in capture {
+            match unstuffer.push(byte) {
+                Unstuffed::Byte(b) => body.push(b),
+                Unstuffed::Escape => {}
+                Unstuffed::End => {
+                    // seq | record type | data | checksum
+                    if body.len() >= 3 && checksum_ok(&body) && body[1] == QS_EVT_LOG {
+                        if let Some(entry) = ReplayEntry::decode(&body[2..body.len() - 1]) {
+                            log.entries.push(entry);
+                        }
+                    }
+                    body.clear();
+                }
+            }
+        }
+        if let (Some(first), Some(last)) = (log.entries.first(), log.entries.last()) {
+            log.start_tick = first.tick;
+            log.end_tick = last.tick;
+        }
+        log
+    }
+
+    /// Posts the external entries into `kernel`, in order, rebuilding their
+    /// payloads with `codec`. Before each post `runtime` is ticked up to the
+    /// entry's tick, and afterwards it runs until idle; at the end it is
+    /// ticked on to the tick the recording stopped on.
+    pub fn replay<R: Runtime>(
+        &self,
+        kernel: &dyn KernelServices,
+        runtime: &R,
+        codec: &dyn PayloadCodec,
+    ) -> Result<ReplaySummary, ReplayError<R::TickError>> {
+        let mut summary = ReplaySummary::default();
+        let mut now = self.start_tick;
+        let mut advance_to = |tick: u32, summary: &mut ReplaySummary| {
+            while now != tick {
+                runtime.tick().map_err(ReplayError::Tick)?;
+                now = now.wrapping_add(1);
+                summary.ticks += 1;
+            }
+            Ok(())
+        };
+        for entry in &self.entries {
+            if entry.source != PostSource::External {
+                summary.skipped += 1;
+                continue;
+            }
+            advance_to(entry.tick, &mut summary)?;
+            let event =
+                DynEvent::with_arc(entry.signal, codec.decode(entry.signal, &entry.payload));
+            let posted = if entry.lifo {
+                kernel.post_lifo(entry.target, event)
+            } else {
+                kernel.post(entry.target, event)
+            };
+            posted.map_err(ReplayError::Post)?;
+            runtime.run_until_idle();
+            summary.posted += 1;
+        }
+        advance_to(self.end_tick, &mut summary)?;
+        runtime.run_until_idle();
+        Ok(summary)
+    }
+}
+
+/// Clock the entries are stamped with.
+pub type TickClock = fn() -> u32;
+
+struct Recording {
+    codec: Arc<dyn PayloadCodec>,
+    clock: TickClock,
+    log: ReplayLog,
+}
+
+/// Set once a recording has started; until then posts skip the lookup.
+static ANY_RECORDING: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static RECORDING: core::cell::RefCell<Option<Recording>> = const { core::cell::RefCell::new(None) };
+    static IN_TIMER: core::cell::Cell<bool> = const { core::cell::Cell::new(false) };
+}
+
+#[cfg(not(feature = "std"))]
+static RECORDING: crate::sync::Mutex<Option<Recording>> = crate::sync::Mutex::new(None);
+#[cfg(not(feature = "std"))]
+static IN_TIMER: AtomicBool = AtomicBool::new(false);
+
+fn with_recording<R>(f: impl FnOnce(&mut Option<Recording>) -> R) -> R {
+    #[cfg(feature = "std")]
+    {
+        RECORDING.with(|r| f(&mut r.borrow_mut()))
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        f(&mut RECORDING.lock())
+    }
+}
+
+/// Starts logging posts, stamped with the rate-0 tick clock
+/// ([`time::now`](crate::time::now)). Replaces a recording already running.
+pub fn start_recording(codec: Arc<dyn PayloadCodec>) {
+    start_recording_with_clock(codec, || crate::time::now().ticks());
+}
+
+/// Starts logging posts, stamped with `clock`. The rate-0 tick clock is the
+/// one of the kernel running on the posting thread; a recording of posts
+/// made on threads the kernel does not run on passes its own tick count.
+pub fn start_recording_with_clock(codec: Arc<dyn PayloadCodec>, clock: TickClock) {
+    let start_tick = clock();
+    with_recording(|r| {
+        *r = Some(Recording {
+            codec,
+            clock,
+            log: ReplayLog {
+                start_tick,
+                end_tick: start_tick,
+                entries: Vec::new(),
+            },
+        })
+    });
+    ANY_RECORDING.store(true, Ordering::Release);
+}
+
+/// Stops the recording and returns its log; `None` if none was running.
+pub fn stop_recording() -> Option<ReplayLog> {
+    with_recording(|r| {
+        r.take().map(|rec| {
+            let mut log = rec.log;
+            log.end_tick = (rec.clock)();
+            log
+        })
+    })
+}
+
+/// Marks posts made until it drops as time-event expiries. Timer wheels
+/// hold one while they post what expired.
+pub struct TimerPostScope {
+    prev: bool,
+}
+
+impl TimerPostScope {
+    pub fn enter() -> Self {
+        #[cfg(feature = "std")]
+        let prev = IN_TIMER.with(|t| t.replace(true));
+        #[cfg(not(feature = "std"))]
+        let prev = IN_TIMER.swap(true, Ordering::Relaxed);
+        Self { prev }
+    }
+}
+
+impl Drop for TimerPostScope {
+    fn drop(&mut self) {
+        #[cfg(feature = "std")]
+        IN_TIMER.with(|t| t.set(self.prev));
+        #[cfg(not(feature = "std"))]
+        IN_TIMER.store(self.prev, Ordering::Relaxed);
+    }
+}
+
+fn in_timer() -> bool {
+    #[cfg(feature = "std")]
+    {
+        IN_TIMER.try_with(|t| t.get()).unwrap_or(false)
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        IN_TIMER.load(Ordering::Relaxed)
+    }
+}
+
+/// Logs `event` as posted to `target` if a recording is on, and traces it
+/// through the target's hook.
+pub(crate) fn posted(
+    target: ActiveObjectId,
+    event: &DynEvent,
+    lifo: bool,
+    trace: Option<&TraceHook>,
+) {
+    if !ANY_RECORDING.load(Ordering::Acquire) {
+        return;
+    }
+    // A preemptive kernel may run handlers while a wheel is still posting.
+    let source = match crate::current_ao() {
+        Some(id) => PostSource::Ao(id),
+        None if in_timer() => PostSource::TimeEvent,
+        None => PostSource::External,
+    };
+    let record = with_recording(|r| {
+        let rec = r.as_mut()?;
+        let signal = event.header.signal;
+        let entry = ReplayEntry {
+            tick: (rec.clock)(),
+            target,
+            signal,
+            source,
+            lifo,
+            payload: rec.codec.encode(signal, &event.payload).unwrap_or_default(),
+        };
+        let record = trace.map(|_| entry.encode());
+        rec.log.entries.push(entry);
+        record
+    });
+    if let (Some(hook), Some(record)) = (trace, record) {
+        // A snapshot longer than the tracer's record limit is refused there;
+        // the in-memory log still has it.
+        let _ = hook(QS_EVT_LOG, &record, false);
+    }
+}
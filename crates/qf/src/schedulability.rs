@@ -0,0 +1,263 @@
+//! Deadline-monotonic priority assignment and schedulability checks.
+//!
+//! Active-object priorities are fixed when the objects are created, so the
+//! kernel cannot pick them itself. This module closes the loop from the other
+//! side: describe each periodic active object by its period, relative
+//! deadline and worst-case execution time (all in ticks), and
+//! [`PriorityPlan::deadline_monotonic`] orders them shortest-deadline-first
+//! (higher number = higher priority, as everywhere in QP) and runs the
+//! classic analyses on that order:
+//!
+//! - total utilization against 100 % and the Liu & Layland bound
+//!   `n(2^(1/n) − 1)`, and
+//! - exact response-time analysis, which decides the cases the bound leaves
+//!   open (the bound is sufficient, not necessary).
+//!
+//! Kernel builders expose the same analysis over the objects already
+//! registered (`deadline_monotonic` on [`KernelBuilder`](crate::KernelBuilder)
+//! and on the QK builder), which also flags objects whose registered priority
+//! disagrees with the deadline-monotonic order.
+//!
+//! Everything here is arithmetic on integers: utilization is reported in
+//! per mille, and no floating point is needed on `no_std` targets. The
+//! analysis allocates, so it is absent from the heap-free `static-alloc`
+//! build; run it from a host test of the same task set instead.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::active::ActiveObjectId;
+use crate::time::TickDuration;
+
+/// Timing declaration of one periodic (or sporadic, with `period` as the
+/// minimum inter-arrival time) active object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskTiming {
+    /// The active object.
+    pub id: ActiveObjectId,
+    /// Release period.
+    pub period: TickDuration,
+    /// Relative deadline; equal to `period` unless set otherwise.
+    pub deadline: TickDuration,
+    /// Worst-case execution time of one release.
+    pub wcet: TickDuration,
+}
+
+impl TaskTiming {
+    /// A task whose deadline is its period.
+    pub const fn new(id: ActiveObjectId, period: TickDuration, wcet: TickDuration) -> Self {
+        Self { id, period, deadline: period, wcet }
+    }
+
+    /// Sets a relative deadline shorter than the period.
+    pub const fn with_deadline(mut self, deadline: TickDuration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+}
+
+/// A finding of the analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleWarning {
+    /// The task has a zero period or deadline, or a WCET longer than its
+    /// deadline; it is left out of the analysis.
+    InvalidTiming(ActiveObjectId),
+    /// Total utilization exceeds 100 %: no priority order can meet every
+    /// deadline.
+    Overloaded { utilization_permille: u32 },
+    /// Utilization is above the Liu & Layland bound, so schedulability rests
+    /// on the response-time analysis alone.
+    AboveBound { utilization_permille: u32, bound_permille: u32 },
+    /// The worst-case response time exceeds the deadline.
+    DeadlineMiss { id: ActiveObjectId, deadline: TickDuration },
+    /// A registered object's priority is out of deadline-monotonic order.
+    PriorityOrder { id: ActiveObjectId, registered: u8, suggested: u8 },
+}
+
+impl fmt::Display for ScheduleWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ScheduleWarning::InvalidTiming(id) => {
+                write!(f, "AO {} has invalid timing (zero period/deadline or WCET > deadline)", id.0)
+            }
+            ScheduleWarning::Overloaded { utilization_permille } => {
+                write!(f, "utilization {}.{}% exceeds 100%", utilization_permille / 10, utilization_permille % 10)
+            }
+            ScheduleWarning::AboveBound { utilization_permille, bound_permille } => write!(
+                f,
+                "utilization {}.{}% is above the Liu & Layland bound {}.{}%",
+                utilization_permille / 10,
+                utilization_permille % 10,
+                bound_permille / 10,
+                bound_permille % 10
+            ),
+            ScheduleWarning::DeadlineMiss { id, deadline } => {
+                write!(f, "AO {} cannot complete within its deadline of {}", id.0, deadline)
+            }
+            ScheduleWarning::PriorityOrder { id, registered, suggested } => write!(
+                f,
+                "AO {} is registered at priority {} but deadline-monotonic order puts it at {}",
+                id.0, registered, suggested
+            ),
+        }
+    }
+}
+
+/// Result of a deadline-monotonic analysis.
+#[derive(Debug, Clone, Default)]
+pub struct PriorityPlan {
+    /// `(id, priority, worst-case response)` from highest priority down.
+    assignments: Vec<(ActiveObjectId, u8, Option<TickDuration>)>,
+    utilization_permille: u32,
+    warnings: Vec<ScheduleWarning>,
+}
+
+impl PriorityPlan {
+    /// Assigns priorities `n..=1` in order of increasing deadline (ties
+    /// broken by period, then id) and analyzes the result.
+    ///
+    /// At most 255 tasks are ranked; more is a caller error.
+    pub fn deadline_monotonic(tasks: &[TaskTiming]) -> Self {
+        let mut plan = PriorityPlan::default();
+        let mut ranked: Vec<&TaskTiming> = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            if task.period.is_zero() || task.deadline.is_zero() || task.wcet > task.deadline {
+                plan.warnings.push(ScheduleWarning::InvalidTiming(task.id));
+            } else {
+                ranked.push(task);
+            }
+        }
+        crate::q_require!(ranked.len() <= u8::MAX as usize);
+        ranked.sort_by_key(|t| (t.deadline, t.period, t.id.0));
+
+        let mut utilization: u64 = 0;
+        for t in &ranked {
+            utilization = utilization
+                .saturating_add(t.wcet.ticks().saturating_mul(1_000).div_ceil(t.period.ticks()));
+        }
+        plan.utilization_permille = u32::try_from(utilization).unwrap_or(u32::MAX);
+        let bound = liu_layland_permille(ranked.len());
+        if plan.utilization_permille > 1_000 {
+            plan.warnings.push(ScheduleWarning::Overloaded {
+                utilization_permille: plan.utilization_permille,
+            });
+        } else if plan.utilization_permille > bound {
+            plan.warnings.push(ScheduleWarning::AboveBound {
+                utilization_permille: plan.utilization_permille,
+                bound_permille: bound,
+            });
+        }
+
+        let n = ranked.len() as u8;
+        for (i, t) in ranked.iter().enumerate() {
+            let response = response_time(t, &ranked[..i]);
+            if response.is_none() {
+                plan.warnings.push(ScheduleWarning::DeadlineMiss { id: t.id, deadline: t.deadline });
+            }
+            plan.assignments.push((t.id, n - i as u8, response));
+        }
+        plan
+    }
+
+    /// [`deadline_monotonic`](Self::deadline_monotonic), then compares the
+    /// resulting order with the priorities the objects were actually
+    /// registered at, adding a [`ScheduleWarning::PriorityOrder`] for each
+    /// object that ranks differently. Objects without a `TaskTiming` are
+    /// ignored.
+    pub fn check_registered(
+        tasks: &[TaskTiming],
+        registered: impl IntoIterator<Item = (ActiveObjectId, u8)>,
+    ) -> Self {
+        let mut plan = Self::deadline_monotonic(tasks);
+        let mut actual: Vec<(ActiveObjectId, u8)> = registered
+            .into_iter()
+            .filter(|(id, _)| plan.priority_of(*id).is_some())
+            .collect();
+        actual.sort_by_key(|&(_, prio)| core::cmp::Reverse(prio));
+        let ranked: Vec<(ActiveObjectId, u8)> = plan
+            .assignments
+            .iter()
+            .filter(|(id, ..)| actual.iter().any(|(a, _)| a == id))
+            .map(|&(id, prio, _)| (id, prio))
+            .collect();
+        for (&(id, registered), &(expected, _)) in actual.iter().zip(&ranked) {
+            if id != expected {
+                let suggested = plan.priority_of(id).unwrap_or(0);
+                plan.warnings.push(ScheduleWarning::PriorityOrder { id, registered, suggested });
+            }
+        }
+        plan
+    }
+
+    /// The suggested priority of `id`.
+    pub fn priority_of(&self, id: ActiveObjectId) -> Option<u8> {
+        self.assignments.iter().find(|(a, ..)| *a == id).map(|&(_, prio, _)| prio)
+    }
+
+    /// The worst-case response time of `id` under the suggested order;
+    /// `None` if it misses its deadline.
+    pub fn response_time(&self, id: ActiveObjectId) -> Option<TickDuration> {
+        self.assignments.iter().find(|(a, ..)| *a == id).and_then(|&(.., r)| r)
+    }
+
+    /// `(id, priority)` from highest priority down.
+    pub fn assignments(&self) -> impl Iterator<Item = (ActiveObjectId, u8)> + '_ {
+        self.assignments.iter().map(|&(id, prio, _)| (id, prio))
+    }
+
+    /// Total utilization in per mille, rounded up.
+    pub fn utilization_permille(&self) -> u32 {
+        self.utilization_permille
+    }
+
+    /// Everything the analysis found.
+    pub fn warnings(&self) -> &[ScheduleWarning] {
+        &self.warnings
+    }
+
+    /// `true` if every analyzed task meets its deadline under the suggested
+    /// order. Being above the Liu & Layland bound or registered out of order
+    /// does not by itself make a plan unschedulable.
+    pub fn is_schedulable(&self) -> bool {
+        !self.warnings.iter().any(|w| {
+            matches!(
+                w,
+                ScheduleWarning::Overloaded { .. }
+                    | ScheduleWarning::DeadlineMiss { .. }
+                    | ScheduleWarning::InvalidTiming(_)
+            )
+        })
+    }
+}
+
+/// Liu & Layland utilization bound `n(2^(1/n) − 1)` in per mille, rounded
+/// down; tends to ln 2 for large `n`.
+fn liu_layland_permille(n: usize) -> u32 {
+    const BOUNDS: [u32; 10] = [1_000, 828, 779, 756, 743, 734, 728, 724, 720, 717];
+    match n {
+        0 => 1_000,
+        n if n <= BOUNDS.len() => BOUNDS[n - 1],
+        _ => 693,
+    }
+}
+
+/// Worst-case response time of `task` preempted by `higher`:
+/// `R = C + Σ ⌈R / Tj⌉ · Cj`, iterated to a fixed point. `None` once it
+/// grows past the deadline.
+fn response_time(task: &TaskTiming, higher: &[&TaskTiming]) -> Option<TickDuration> {
+    let mut r = task.wcet.ticks();
+    loop {
+        let mut next = task.wcet.ticks();
+        for h in higher {
+            let releases = r.div_ceil(h.period.ticks());
+            next = next.checked_add(releases.checked_mul(h.wcet.ticks())?)?;
+        }
+        if next > task.deadline.ticks() {
+            return None;
+        }
+        if next == r {
+            return Some(TickDuration::from_ticks(r));
+        }
+        r = next;
+    }
+}
@@ -0,0 +1,138 @@
+//! Kernel services for the active object being dispatched.
+//!
+//! Behaviours post and publish through their [`ActiveContext`] instead of
+//! reaching a kernel stored in a global:
+//!
+//! ```rust,ignore
+//! fn handle_signal(&mut self, signal: Signal, ctx: &mut ActiveContext) {
+//!     if signal == HUNGRY_SIG {
+//!         ctx.post(TABLE_ID, DynEvent::empty_dyn(EAT_SIG)).ok();
+//!     }
+//! }
+//! ```
+//!
+//! Each kernel implements [`KernelServices`] and wraps every initial
+//! transition and every dispatch in [`with_services`], so the context talks to
+//! the kernel that runs the object at that moment. The binding lives only for
+//! the call: outside a dispatch, e.g. in an interrupt handler that did not
+//! preempt one, the context's kernel calls fail with
+//! [`ServiceError::NoKernel`].
+//!
+//! [`ActiveContext`]: crate::ActiveContext
+
+use core::fmt;
+
+use crate::active::ActiveObjectId;
+use crate::event::{DynEvent, Signal};
+use crate::time::KernelClock;
+
+/// What a kernel offers the active objects it dispatches.
+pub trait KernelServices: Sync {
+    /// Posts `event` to the back of `target`'s queue.
+    fn post(&self, target: ActiveObjectId, event: DynEvent) -> Result<(), ServiceError>;
+
+    /// Posts `event` to the front of `target`'s queue, as a recall does.
+    fn post_lifo(&self, target: ActiveObjectId, event: DynEvent) -> Result<(), ServiceError>;
+
+    /// Publishes `event` as `signal` to its subscribers.
+    fn publish(&self, signal: Signal, event: DynEvent);
+
+    /// The kernel's tick clock, which [`with_services`] makes the one
+    /// [`time::now`](crate::time::now) reads. `None` keeps the clock already
+    /// current.
+    fn clock(&self) -> Option<&KernelClock> {
+        None
+    }
+}
+
+/// Why a kernel call from an [`ActiveContext`](crate::ActiveContext) failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceError {
+    /// The call was made outside a kernel's dispatch.
+    NoKernel,
+    /// The kernel has no active object with this id.
+    NotFound(ActiveObjectId),
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoKernel => write!(f, "no kernel is dispatching"),
+            Self::NotFound(id) => write!(f, "active object {id:?} not found"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ServiceError {}
+
+// The binding is a thin pointer to the `&dyn KernelServices` on the stack of
+// the innermost `with_services` call, so it fits one atomic word on targets
+// without thread-locals. Like `current`, it is per thread with `std` and a
+// single global otherwise; nested dispatches restore the outer binding.
+type Slot = *mut ();
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static BOUND: core::cell::Cell<Slot> = const { core::cell::Cell::new(core::ptr::null_mut()) };
+}
+
+#[cfg(not(feature = "std"))]
+static BOUND: portable_atomic::AtomicPtr<()> = portable_atomic::AtomicPtr::new(core::ptr::null_mut());
+
+fn replace(slot: Slot) -> Slot {
+    #[cfg(feature = "std")]
+    {
+        BOUND.try_with(|bound| bound.replace(slot)).unwrap_or(core::ptr::null_mut())
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        BOUND.swap(slot, portable_atomic::Ordering::AcqRel)
+    }
+}
+
+fn load() -> Slot {
+    #[cfg(feature = "std")]
+    {
+        BOUND.try_with(|bound| bound.get()).unwrap_or(core::ptr::null_mut())
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        BOUND.load(portable_atomic::Ordering::Acquire)
+    }
+}
+
+struct Restore(Slot);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        replace(self.0);
+    }
+}
+
+/// Runs `f` with `services` as the kernel of the active-object contexts used
+/// inside it. Kernels call this around initial transitions and dispatches.
+pub fn with_services<R>(services: &dyn KernelServices, f: impl FnOnce() -> R) -> R {
+    if let Some(clock) = services.clock() {
+        clock.enter();
+    }
+    let binding: &dyn KernelServices = services;
+    let _restore = Restore(replace(&binding as *const &dyn KernelServices as Slot));
+    f()
+}
+
+/// Calls `f` with the kernel bound by the innermost running
+/// [`with_services`], if any.
+pub(crate) fn with_bound<R>(f: impl FnOnce(&dyn KernelServices) -> R) -> Result<R, ServiceError> {
+    let slot = load();
+    if slot.is_null() {
+        return Err(ServiceError::NoKernel);
+    }
+    // SAFETY: a non-null slot points at the `binding` local of a
+    // `with_services` call that is still running below us on this stack (or
+    // on the stack this interrupt preempted); that call puts the previous
+    // slot back before `binding` goes out of scope. `f` cannot keep the
+    // reference past this call.
+    let services = unsafe { *(slot as *const &dyn KernelServices) };
+    Ok(f(services))
+}
@@ -0,0 +1,341 @@
+//! Signal namespaces for composing independently numbered signal sets.
+//!
+//! An application built from several subsystems (its own AOs plus reusable
+//! libraries) needs one global signal numbering. Instead of every subsystem
+//! hard-coding raw numbers, each declares a [`SignalBlock`]: a name, a base
+//! and the names of its signals. A block's signals are offsets from its base,
+//! so a library only fixes the *order* of its signals and the application
+//! decides where the block lands. [`SignalBlock::after`] chains blocks
+//! without arithmetic.
+//!
+//! [`SignalNamespace`] collects the blocks of an application. Built in a
+//! `const`, it rejects overlapping blocks and blocks that reach into the
+//! framework's reserved signals at compile time:
+//!
+//! ```
+//! use qf::signals::{SignalBlock, SignalNamespace};
+//!
+//! const TABLE: SignalBlock = SignalBlock::new("Table", 4, &["EAT", "DONE"]);
+//! const PHILO: SignalBlock = SignalBlock::after(&TABLE, "Philo", &["TIMEOUT", "HUNGRY"]);
+//! const SIGNALS: SignalNamespace<2> = SignalNamespace::new([TABLE, PHILO]);
+//!
+//! const EAT: qf::Signal = TABLE.signal(0);
+//! const HUNGRY: qf::Signal = PHILO.signal(1);
+//! assert_eq!(HUNGRY.0, 7);
+//! assert_eq!(SIGNALS.name_of(EAT), Some(("Table", "EAT")));
+//! ```
+//!
+//! With the `qs` feature, [`SignalNamespace::emit_dictionaries`] sends one
+//! `QS_SIG_DICT` entry per signal, named `Block::SIGNAL`, so the host decodes
+//! every subsystem's signals without a hand-written dictionary.
+//!
+//! [`SignalSpace`] splits the 16-bit signal range the way QP does: the
+//! reserved framework signals, then the published signals up to
+//! `MAX_PUB_SIG`, then the signals private to one active object. Blocks taken
+//! from it land in the right range by construction:
+//!
+//! ```
+//! use qf::signals::{SignalRange, SignalSpace};
+//!
+//! const SPACE: SignalSpace = SignalSpace::new(16);
+//! const EVENTS: qf::SignalBlock = SPACE.published_block("App", &["EAT", "DONE"]);
+//! const PHILO: qf::SignalBlock = SPACE.private_block("Philo", &["TIMEOUT"]);
+//!
+//! assert_eq!(EVENTS.signal(0).0, 4);
+//! assert_eq!(PHILO.signal(0).0, 16);
+//! assert_eq!(SPACE.range_of(PHILO.signal(0)), SignalRange::Private);
+//! assert!(SPACE.is_publishable(EVENTS.signal(1)));
+//! ```
+//!
+//! The state machine engines check in debug builds that no dispatched event
+//! carries a reserved signal: an event numbered like `Q_ENTRY_SIG` would
+//! otherwise run the state's entry action.
+
+use core::ops::{Range, RangeInclusive};
+
+use crate::event::Signal;
+use crate::hsm::reserved::Q_USER_SIG;
+#[cfg(feature = "qs")]
+use crate::trace::{TraceHook, TraceResult};
+
+/// A contiguous range of signals owned by one subsystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignalBlock {
+    name: &'static str,
+    base: u16,
+    signals: &'static [&'static str],
+}
+
+impl SignalBlock {
+    /// A block named `name` whose signals, in order, are numbered from
+    /// `base`.
+    ///
+    /// # Panics
+    /// If `base` is below [`Q_USER_SIG`] or the block runs past `u16::MAX`;
+    /// in a `const` this is a compile error.
+    pub const fn new(name: &'static str, base: u16, signals: &'static [&'static str]) -> Self {
+        assert!(base >= Q_USER_SIG.0, "signal block overlaps the reserved framework signals");
+        assert!(
+            base as usize + signals.len() <= u16::MAX as usize + 1,
+            "signal block runs past the 16-bit signal range"
+        );
+        Self { name, base, signals }
+    }
+
+    /// A block placed directly after `previous`.
+    pub const fn after(
+        previous: &SignalBlock,
+        name: &'static str,
+        signals: &'static [&'static str],
+    ) -> Self {
+        Self::new(name, previous.end(), signals)
+    }
+
+    /// The block's name.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The first signal number of the block.
+    pub const fn base(&self) -> u16 {
+        self.base
+    }
+
+    /// One past the last signal number of the block.
+    pub const fn end(&self) -> u16 {
+        self.base + self.signals.len() as u16
+    }
+
+    /// Number of signals in the block.
+    pub const fn len(&self) -> usize {
+        self.signals.len()
+    }
+
+    /// Whether the block declares no signals.
+    pub const fn is_empty(&self) -> bool {
+        self.signals.is_empty()
+    }
+
+    /// The block's signal names, in numbering order.
+    pub const fn signal_names(&self) -> &'static [&'static str] {
+        self.signals
+    }
+
+    /// The `index`-th signal of the block.
+    ///
+    /// # Panics
+    /// If the block has no such signal; in a `const` this is a compile
+    /// error.
+    pub const fn signal(&self, index: u16) -> Signal {
+        assert!((index as usize) < self.signals.len(), "signal index outside its block");
+        Signal(self.base + index)
+    }
+
+    /// Whether `signal` belongs to the block.
+    pub const fn contains(&self, signal: Signal) -> bool {
+        signal.0 >= self.base && signal.0 < self.end()
+    }
+
+    /// Position of `signal` within the block.
+    pub const fn index_of(&self, signal: Signal) -> Option<u16> {
+        if self.contains(signal) {
+            Some(signal.0 - self.base)
+        } else {
+            None
+        }
+    }
+
+    /// Name of `signal`, if it belongs to the block.
+    pub fn name_of(&self, signal: Signal) -> Option<&'static str> {
+        self.index_of(signal).map(|index| self.signals[usize::from(index)])
+    }
+
+    const fn overlaps(&self, other: &SignalBlock) -> bool {
+        !self.is_empty() && !other.is_empty() && self.base < other.end() && other.base < self.end()
+    }
+
+    /// Emits a `QS_SIG_DICT` record for each signal of the block.
+    #[cfg(feature = "qs")]
+    pub fn emit_dictionary(&self, hook: &TraceHook) -> TraceResult {
+        for (offset, name) in self.signals.iter().enumerate() {
+            let payload =
+                qs::predefined::scoped_sig_dict_payload(self.base + offset as u16, 0, self.name, name);
+            hook(qs::predefined::SIG_DICT, &payload, false)?;
+        }
+        Ok(())
+    }
+}
+
+/// The signal blocks of an application, checked not to overlap.
+#[derive(Debug, Clone, Copy)]
+pub struct SignalNamespace<const N: usize> {
+    blocks: [SignalBlock; N],
+}
+
+impl<const N: usize> SignalNamespace<N> {
+    /// Collects `blocks`.
+    ///
+    /// # Panics
+    /// If two blocks share a signal number; in a `const` this is a compile
+    /// error.
+    pub const fn new(blocks: [SignalBlock; N]) -> Self {
+        let mut i = 0;
+        while i < N {
+            let mut j = i + 1;
+            while j < N {
+                assert!(!blocks[i].overlaps(&blocks[j]), "signal blocks overlap");
+                j += 1;
+            }
+            i += 1;
+        }
+        Self { blocks }
+    }
+
+    /// The blocks, in declaration order.
+    pub fn blocks(&self) -> &[SignalBlock] {
+        &self.blocks
+    }
+
+    /// The block `signal` belongs to.
+    pub fn block_of(&self, signal: Signal) -> Option<&SignalBlock> {
+        self.blocks.iter().find(|block| block.contains(signal))
+    }
+
+    /// Block name and signal name of `signal`.
+    pub fn name_of(&self, signal: Signal) -> Option<(&'static str, &'static str)> {
+        let block = self.block_of(signal)?;
+        Some((block.name, block.name_of(signal)?))
+    }
+
+    /// One past the highest signal number in use, for sizing
+    /// [`PubSubTable`](crate::PubSubTable)s and the like.
+    pub fn end(&self) -> u16 {
+        self.blocks.iter().map(SignalBlock::end).max().unwrap_or(Q_USER_SIG.0)
+    }
+
+    /// Emits the signal dictionary of every block.
+    #[cfg(feature = "qs")]
+    pub fn emit_dictionaries(&self, hook: &TraceHook) -> TraceResult {
+        self.blocks.iter().try_for_each(|block| block.emit_dictionary(hook))
+    }
+}
+
+/// The part of a [`SignalSpace`] a signal belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalRange {
+    /// Framework signals below [`Q_USER_SIG`].
+    Reserved,
+    /// Signals that may be published, below `MAX_PUB_SIG`.
+    Published,
+    /// Signals only ever posted directly to one active object.
+    Private,
+}
+
+/// The application's signal range split into reserved, published and
+/// private signals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignalSpace {
+    max_pub_sig: u16,
+}
+
+impl SignalSpace {
+    /// The reserved framework signals.
+    pub const RESERVED: Range<u16> = 0..Q_USER_SIG.0;
+
+    /// A space whose published signals end just below `max_pub_sig`.
+    ///
+    /// # Panics
+    /// If `max_pub_sig` is below [`Q_USER_SIG`]; in a `const` this is a
+    /// compile error.
+    pub const fn new(max_pub_sig: u16) -> Self {
+        assert!(max_pub_sig >= Q_USER_SIG.0, "MAX_PUB_SIG overlaps the reserved framework signals");
+        Self { max_pub_sig }
+    }
+
+    /// One past the last published signal, as `MAX_PUB_SIG` in QP. Size the
+    /// publish-subscribe table with it.
+    pub const fn max_pub_sig(&self) -> u16 {
+        self.max_pub_sig
+    }
+
+    /// The published signals.
+    pub const fn published(&self) -> Range<u16> {
+        Q_USER_SIG.0..self.max_pub_sig
+    }
+
+    /// The private signals.
+    pub const fn private(&self) -> RangeInclusive<u16> {
+        self.max_pub_sig..=u16::MAX
+    }
+
+    /// The range `signal` falls in.
+    pub const fn range_of(&self, signal: Signal) -> SignalRange {
+        if signal.0 < Q_USER_SIG.0 {
+            SignalRange::Reserved
+        } else if signal.0 < self.max_pub_sig {
+            SignalRange::Published
+        } else {
+            SignalRange::Private
+        }
+    }
+
+    /// Whether `signal` may be published.
+    pub const fn is_publishable(&self, signal: Signal) -> bool {
+        matches!(self.range_of(signal), SignalRange::Published)
+    }
+
+    /// A block at the start of the published range.
+    ///
+    /// # Panics
+    /// If the block does not fit below `MAX_PUB_SIG`; in a `const` this is a
+    /// compile error.
+    pub const fn published_block(
+        &self,
+        name: &'static str,
+        signals: &'static [&'static str],
+    ) -> SignalBlock {
+        let block = SignalBlock::new(name, Q_USER_SIG.0, signals);
+        assert!(block.end() <= self.max_pub_sig, "published signal block runs past MAX_PUB_SIG");
+        block
+    }
+
+    /// A block at the start of the private range.
+    pub const fn private_block(
+        &self,
+        name: &'static str,
+        signals: &'static [&'static str],
+    ) -> SignalBlock {
+        SignalBlock::new(name, self.max_pub_sig, signals)
+    }
+
+    /// A block placed directly after `previous`, checked to stay in the same
+    /// range.
+    ///
+    /// # Panics
+    /// If the block crosses `MAX_PUB_SIG`; in a `const` this is a compile
+    /// error.
+    pub const fn block_after(
+        &self,
+        previous: &SignalBlock,
+        name: &'static str,
+        signals: &'static [&'static str],
+    ) -> SignalBlock {
+        let block = SignalBlock::after(previous, name, signals);
+        assert!(
+            previous.end() >= self.max_pub_sig || block.end() <= self.max_pub_sig,
+            "published signal block runs past MAX_PUB_SIG"
+        );
+        block
+    }
+}
+
+/// Debug-build check that a dispatched event does not carry a reserved
+/// signal.
+#[inline]
+#[track_caller]
+pub(crate) fn debug_assert_user_signal(signal: Signal) {
+    debug_assert!(
+        signal.0 >= Q_USER_SIG.0,
+        "{signal} collides with a reserved framework signal; user signals start at Q_USER_SIG"
+    );
+}
@@ -0,0 +1,153 @@
+//! Active objects declared as `static` items.
+//!
+//! Registering an active object normally means building it at startup and
+//! keeping the handle alive for the kernel: an `Arc` on the dynamic build, or
+//! a `&'static` borrowed out of application-owned storage under
+//! `static-alloc`. The second case pushes every application into a
+//! `StaticCell` or a `static mut` of its own. [`q_active!`](crate::q_active)
+//! declares the storage, the id and the priority in one item instead, much as
+//! RTIC declares its tasks:
+//!
+//! ```ignore
+//! qf::q_active! {
+//!     /// Blinks the LED.
+//!     pub static BLINKY: Blinky = Blinky::new(), id = 1, priority = 2;
+//!     static BUTTON: Button = Button::default(), id = 2, priority = 3;
+//! }
+//!
+//! let kernel = Kernel::builder()
+//!     .register(BLINKY.handle())
+//!     .register(BUTTON.handle())
+//!     .build();
+//! ```
+//!
+//! The behavior is built on the first [`StaticActive::handle`] or
+//! [`StaticActive::get`] call, so its initializer need not be `const`. Under
+//! `static-alloc` the active object, and with it the inline event queue of
+//! [`AO_QUEUE_CAPACITY`](crate::active::AO_QUEUE_CAPACITY) entries, lives in
+//! the `static` itself; the dynamic build keeps the `Arc` there.
+//!
+//! With the `qs` feature, [`qs_ao!`](crate::qs_ao) sends the object dictionary
+//! of declared active objects so QSPY shows them by name.
+
+use crate::active::{ActiveBehavior, ActiveObject, ActiveObjectId, ActiveObjectRef};
+#[cfg(feature = "qs")]
+use crate::trace::{TraceHook, TraceResult};
+
+#[cfg(not(feature = "static-alloc"))]
+type Slot<B> = crate::sync::Arc<ActiveObject<B>>;
+#[cfg(feature = "static-alloc")]
+type Slot<B> = ActiveObject<B>;
+
+/// Storage for one active object declared with [`q_active!`](crate::q_active).
+pub struct StaticActive<B: ActiveBehavior> {
+    name: &'static str,
+    id: ActiveObjectId,
+    priority: u8,
+    init: fn() -> B,
+    slot: spin::Once<Slot<B>>,
+}
+
+impl<B: ActiveBehavior> StaticActive<B> {
+    /// Storage for an active object named `name` whose behavior `init` builds
+    /// on first use.
+    pub const fn new(name: &'static str, id: ActiveObjectId, priority: u8, init: fn() -> B) -> Self {
+        Self { name, id, priority, init, slot: spin::Once::new() }
+    }
+
+    fn slot(&'static self) -> &'static Slot<B> {
+        self.slot.call_once(|| ActiveObject::new(self.id, self.priority, (self.init)()))
+    }
+
+    /// The active object, built on the first call.
+    pub fn get(&'static self) -> &'static ActiveObject<B> {
+        self.slot()
+    }
+
+    /// The handle to register with a kernel. Every call returns the same
+    /// active object.
+    pub fn handle(&'static self) -> ActiveObjectRef {
+        #[cfg(not(feature = "static-alloc"))]
+        let handle = crate::active::arc_as_runnable(self.slot().clone());
+        #[cfg(feature = "static-alloc")]
+        let handle = self.slot();
+        handle
+    }
+
+    /// Whether the active object has been built yet.
+    pub fn is_initialized(&self) -> bool {
+        self.slot.is_completed()
+    }
+
+    /// Name of the `static` item.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Declared id.
+    pub fn id(&self) -> ActiveObjectId {
+        self.id
+    }
+
+    /// Declared priority.
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// Emits the `QS_OBJ_DICT` record naming this active object. Its address
+    /// is that of the [`ActiveObject`].
+    #[cfg(feature = "qs")]
+    pub fn emit_dictionary(&'static self, hook: &TraceHook) -> TraceResult {
+        let address = self.get() as *const ActiveObject<B> as usize as u64;
+        let payload = qs::predefined::obj_dict_payload(address, self.name);
+        hook(qs::predefined::OBJ_DICT, &payload, false)
+    }
+}
+
+/// Declares one or more active objects as `static` items.
+///
+/// Each item names the behavior type and its initializer, then the active
+/// object's id and priority. The item has type
+/// [`StaticActive<B>`](crate::static_ao::StaticActive); register it with
+/// [`handle`](crate::static_ao::StaticActive::handle).
+///
+/// ```ignore
+/// q_active! {
+///     pub static TABLE: Table = Table::new(N_PHILO), id = 6, priority = 6;
+/// }
+/// ```
+#[macro_export]
+macro_rules! q_active {
+    ($(
+        $(#[$meta:meta])*
+        $vis:vis static $name:ident : $ty:ty = $init:expr, id = $id:expr, priority = $prio:expr;
+    )+) => {$(
+        $(#[$meta])*
+        $vis static $name: $crate::static_ao::StaticActive<$ty> = {
+            fn init() -> $ty {
+                $init
+            }
+            $crate::static_ao::StaticActive::new(
+                ::core::stringify!($name),
+                $crate::ActiveObjectId::new($id),
+                $prio,
+                init,
+            )
+        };
+    )+};
+}
+
+/// Emits the QS object dictionary of active objects declared with
+/// [`q_active!`](crate::q_active), stopping at the first failed record.
+///
+/// ```ignore
+/// qs_ao!(&hook; BLINKY, BUTTON)?;
+/// ```
+#[cfg(feature = "qs")]
+#[macro_export]
+macro_rules! qs_ao {
+    ($hook:expr; $($name:path),+ $(,)?) => {{
+        let hook: &$crate::TraceHook = $hook;
+        Ok(())$(.and_then(|()| $name.emit_dictionary(hook)))+
+    }};
+}
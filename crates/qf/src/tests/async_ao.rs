@@ -0,0 +1,89 @@
+use std::sync::{Arc, Mutex};
+
+use crate::async_ao::AsyncActive;
+use crate::event::DynEvent;
+use crate::kernel::Kernel;
+use crate::time::{new_time_event, share_kernel, TimeEventConfig, TimerWheel};
+use crate::{ActiveObjectId, Signal};
+
+const AO: ActiveObjectId = ActiveObjectId::new(1);
+
+#[test]
+fn recv_hands_the_future_one_event_per_step() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    let ao = AsyncActive::new(AO, 1, move |inbox| async move {
+        loop {
+            let event = inbox.recv().await;
+            log.lock().unwrap().push(event.signal());
+            if event.signal() == Signal(3) {
+                break;
+            }
+        }
+    });
+    let kernel = Kernel::builder().register(ao.clone()).build();
+    kernel.start();
+
+    for signal in 1..=3 {
+        kernel.post(AO, DynEvent::empty_dyn(Signal(signal))).unwrap();
+    }
+    assert!(kernel.dispatch_once());
+    assert_eq!(*seen.lock().unwrap(), [Signal(1)]);
+
+    kernel.run_until_idle();
+    assert_eq!(*seen.lock().unwrap(), [Signal(1), Signal(2), Signal(3)]);
+    assert!(ao.is_finished());
+
+    // A finished object drops what it is sent.
+    kernel.post(AO, DynEvent::empty_dyn(Signal(4))).unwrap();
+    assert_eq!(ao.queue_len(), 0);
+    assert!(!kernel.dispatch_once());
+}
+
+#[test]
+fn sleep_completes_when_the_time_event_fires() {
+    let timer = new_time_event(AO, TimeEventConfig::new(Signal(0x10)));
+    let steps = Arc::new(Mutex::new(Vec::new()));
+    let log = steps.clone();
+    let sleeper = timer.clone();
+    let ao = AsyncActive::new(AO, 1, move |inbox| async move {
+        log.lock().unwrap().push("sleeping");
+        inbox.sleep(&sleeper, 2).await;
+        log.lock().unwrap().push("woke");
+        let event = inbox.recv().await;
+        assert_eq!(event.signal(), Signal(5));
+        log.lock().unwrap().push("done");
+    });
+    let kernel = share_kernel(Kernel::builder().register(ao.clone()).build());
+    kernel.start();
+    assert!(timer.is_armed());
+
+    let mut wheel = TimerWheel::new(kernel.clone());
+    wheel.register(timer.clone());
+
+    // An event that arrives during the sleep waits for `recv`.
+    kernel.post(AO, DynEvent::empty_dyn(Signal(5))).unwrap();
+    wheel.tick().unwrap();
+    kernel.run_until_idle();
+    assert_eq!(*steps.lock().unwrap(), ["sleeping"]);
+    assert_eq!(ao.queue_len(), 1);
+
+    wheel.tick().unwrap();
+    kernel.run_until_idle();
+    assert_eq!(*steps.lock().unwrap(), ["sleeping", "woke", "done"]);
+    assert!(ao.is_finished());
+}
+
+#[test]
+fn dropping_a_sleep_disarms_its_timer() {
+    let timer = new_time_event(AO, TimeEventConfig::new(Signal(0x10)));
+    let sleeper = timer.clone();
+    let ao = AsyncActive::new(AO, 1, move |inbox| async move {
+        drop(inbox.sleep(&sleeper, 5));
+        inbox.recv().await;
+    });
+    let kernel = Kernel::builder().register(ao.clone()).build();
+    kernel.start();
+    assert!(!timer.is_armed());
+    assert!(!ao.is_finished());
+}
@@ -0,0 +1,125 @@
+use std::sync::Mutex;
+
+use portable_atomic::{AtomicU32, Ordering};
+
+use crate::active::{new_active_object, ActiveContext, SignalHandler};
+use crate::budget::{set_budget_clock, Overrun};
+use crate::event::{DynEvent, Signal};
+use crate::kernel::Kernel;
+use crate::ActiveObjectId;
+
+/// The budget clock and the overrun log are global; tests that use them,
+/// here and in `metrics`, run one at a time.
+pub(super) static SERIAL: Mutex<()> = Mutex::new(());
+pub(super) static NOW: AtomicU32 = AtomicU32::new(0);
+static OVERRUNS: Mutex<Vec<Overrun>> = Mutex::new(Vec::new());
+
+pub(super) fn fake_clock() -> u32 {
+    NOW.load(Ordering::Relaxed)
+}
+
+fn log_overrun(overrun: &Overrun) {
+    OVERRUNS.lock().unwrap().push(*overrun);
+}
+
+/// Takes ten clock units per unit of signal value.
+pub(super) struct Worker;
+
+impl SignalHandler for Worker {
+    fn handle_signal(&mut self, signal: Signal, _ctx: &mut ActiveContext) {
+        NOW.fetch_add(u32::from(signal.0) * 10, Ordering::Relaxed);
+    }
+}
+
+pub(super) fn run(kernel: &Kernel, target: u8, signals: &[u16]) {
+    for &sig in signals {
+        kernel.post(ActiveObjectId::new(target), DynEvent::empty_dyn(Signal(sig))).unwrap();
+    }
+    kernel.run_until_idle();
+}
+
+#[test]
+fn steps_over_budget_are_reported() {
+    let _serial = SERIAL.lock().unwrap();
+    set_budget_clock(fake_clock);
+    OVERRUNS.lock().unwrap().clear();
+
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(1), 1, Worker))
+        .register(new_active_object(ActiveObjectId::new(2), 2, Worker))
+        .with_rtc_budget(1, 25)
+        .on_rtc_overrun(log_overrun)
+        .build();
+    kernel.start();
+
+    run(&kernel, 1, &[2, 3, 1]);
+    run(&kernel, 2, &[9]);
+
+    let overrun = Overrun { priority: 1, signal: Signal(3), budget: 25, elapsed: 30 };
+    assert_eq!(*OVERRUNS.lock().unwrap(), [overrun]);
+    assert_eq!(kernel.rtc_budgets().overruns(), 1);
+    assert_eq!(kernel.rtc_budgets().get(2), None);
+}
+
+#[cfg(not(feature = "static-alloc"))]
+#[test]
+fn overruns_are_traced() {
+    use std::sync::Arc;
+
+    use crate::budget::QS_RTC_OVERRUN;
+
+    let _serial = SERIAL.lock().unwrap();
+    set_budget_clock(fake_clock);
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&records);
+    let hook: crate::TraceHook = Arc::new(move |record, payload: &[u8], ts| {
+        if record == QS_RTC_OVERRUN {
+            sink.lock().unwrap().push((payload.to_vec(), ts));
+        }
+        Ok(())
+    });
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(3), 3, Worker))
+        .with_rtc_budget(3, 15)
+        .with_trace_hook(hook)
+        .build();
+    kernel.start();
+
+    run(&kernel, 3, &[1, 4]);
+
+    let mut expected = vec![3, 4, 0];
+    expected.extend_from_slice(&15u32.to_le_bytes());
+    expected.extend_from_slice(&40u32.to_le_bytes());
+    assert_eq!(*records.lock().unwrap(), [(expected, true)]);
+}
+
+#[cfg(feature = "rtc-stats")]
+#[test]
+fn stats_name_the_slowest_handler() {
+    use crate::budget::RtcStats;
+
+    let _serial = SERIAL.lock().unwrap();
+    set_budget_clock(fake_clock);
+
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(4), 4, Worker))
+        .register(new_active_object(ActiveObjectId::new(5), 5, Worker))
+        .with_rtc_budget(5, 50)
+        .build();
+    kernel.start();
+
+    run(&kernel, 4, &[2, 7, 3]);
+    run(&kernel, 5, &[6]);
+
+    let budgets = kernel.rtc_budgets();
+    let stats = RtcStats { steps: 3, total: 120, max: 70, max_signal: Signal(7), overruns: 0 };
+    assert_eq!(budgets.stats(4), Some(stats));
+    assert_eq!(stats.mean(), Some(40));
+    assert_eq!(budgets.stats(5).map(|s| (s.max, s.overruns)), Some((60, 1)));
+    assert_eq!(budgets.slowest().map(|(prio, _)| prio), Some(4));
+    assert_eq!(budgets.report().map(|(prio, _)| prio).collect::<Vec<_>>(), [4, 5]);
+
+    budgets.reset_stats();
+    assert_eq!(budgets.stats(4), None);
+}
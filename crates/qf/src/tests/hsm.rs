@@ -32,7 +32,7 @@ use crate::event::{DynEvent, Signal};
 use crate::hsm::{SameState, QHsm, QHsmResult, StateHandler};
 use crate::hsm::reserved::*;
 use crate::kernel::Kernel;
-use crate::{q_handled, q_ignored, q_super, q_tran, q_tran_hist};
+use crate::{q_handled, q_ignored, q_super, q_tran, q_tran_deep_hist, q_tran_hist, q_tran_if, q_tran_with};
 
 // ── Signal definitions ────────────────────────────────────────────────────────
 const A_SIG: u16 = 4;
@@ -312,6 +312,113 @@ fn tran_hist_restores_last_active_substate() {
     assert!(enters_s2_branch, "TranHist should restore s2 branch: {:?}", log);
 }
 
+// ── Deep history: a player paused from inside a nested state ───────────────
+//
+// top
+// ├── running            (initial → stopped)
+// │   ├── stopped
+// │   └── playing        (initial → normal)
+// │       ├── normal
+// │       └── fast
+// └── paused             RESUME → deep history of running,
+//                        RESUME_SHALLOW → shallow history of running
+
+const PLAY_SIG: u16 = 20;
+const FAST_SIG: u16 = 21;
+const PAUSE_SIG: u16 = 22;
+const RESUME_SIG: u16 = 23;
+const RESUME_SHALLOW_SIG: u16 = 24;
+
+fn player_initial(_sm: &mut TestSm, _e: &DynEvent) -> QHsmResult<TestSm> {
+    q_tran!(running)
+}
+
+fn running(sm: &mut TestSm, e: &DynEvent) -> QHsmResult<TestSm> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => { sm.trace.push("running-ENTRY"); q_handled!() }
+        Q_INIT_SIG_VAL => q_tran!(stopped),
+        PAUSE_SIG => q_tran!(paused),
+        _ => q_super!(QHsm::<TestSm>::top_state),
+    }
+}
+
+fn stopped(_sm: &mut TestSm, e: &DynEvent) -> QHsmResult<TestSm> {
+    match e.signal().0 {
+        PLAY_SIG => q_tran!(playing),
+        _ => q_super!(running),
+    }
+}
+
+fn playing(sm: &mut TestSm, e: &DynEvent) -> QHsmResult<TestSm> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => { sm.trace.push("playing-ENTRY"); q_handled!() }
+        Q_INIT_SIG_VAL => q_tran!(normal),
+        FAST_SIG => q_tran!(fast),
+        _ => q_super!(running),
+    }
+}
+
+fn normal(sm: &mut TestSm, e: &DynEvent) -> QHsmResult<TestSm> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => { sm.trace.push("normal-ENTRY"); q_handled!() }
+        _ => q_super!(playing),
+    }
+}
+
+fn fast(sm: &mut TestSm, e: &DynEvent) -> QHsmResult<TestSm> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => { sm.trace.push("fast-ENTRY"); q_handled!() }
+        _ => q_super!(playing),
+    }
+}
+
+fn paused(_sm: &mut TestSm, e: &DynEvent) -> QHsmResult<TestSm> {
+    match e.signal().0 {
+        RESUME_SIG => q_tran_deep_hist!(running),
+        RESUME_SHALLOW_SIG => q_tran_hist!(running),
+        _ => q_super!(QHsm::<TestSm>::top_state),
+    }
+}
+
+fn paused_while_fast() -> QHsm<TestSm> {
+    let mut hsm = QHsm::new(TestSm::default(), player_initial);
+    hsm.init();
+    dispatch(&mut hsm, PLAY_SIG);
+    dispatch(&mut hsm, FAST_SIG);
+    dispatch(&mut hsm, PAUSE_SIG);
+    assert!(hsm.state_handler().same_state(paused as StateHandler<TestSm>));
+    hsm.sm_mut().trace.clear();
+    hsm
+}
+
+#[test]
+fn tran_deep_hist_restores_the_nested_leaf() {
+    let mut hsm = paused_while_fast();
+    dispatch(&mut hsm, RESUME_SIG);
+    assert!(hsm.state_handler().same_state(fast as StateHandler<TestSm>));
+    assert_eq!(hsm.sm().trace, ["running-ENTRY", "playing-ENTRY", "fast-ENTRY"]);
+}
+
+#[test]
+fn tran_hist_restores_only_the_direct_child() {
+    let mut hsm = paused_while_fast();
+    dispatch(&mut hsm, RESUME_SHALLOW_SIG);
+    assert!(hsm.state_handler().same_state(normal as StateHandler<TestSm>));
+    assert_eq!(hsm.sm().trace, ["running-ENTRY", "playing-ENTRY", "normal-ENTRY"]);
+}
+
+#[test]
+fn tran_deep_hist_without_history_takes_the_initial_transition() {
+    let mut hsm = QHsm::new(TestSm::default(), paused_initial);
+    hsm.init();
+    dispatch(&mut hsm, RESUME_SIG);
+    assert!(hsm.state_handler().same_state(stopped as StateHandler<TestSm>));
+}
+
+fn paused_initial(_sm: &mut TestSm, _e: &DynEvent) -> QHsmResult<TestSm> {
+    q_tran!(paused)
+}
+
 #[test]
 fn b_sig_guard_transitions_once_then_ignored() {
     let mut hsm = make_hsm();
@@ -337,6 +444,88 @@ fn b_sig_guard_transitions_once_then_ignored() {
     );
 }
 
+// ── Guarded transitions and transition actions ──────────────────────────────
+//
+// top
+// ├── door          (composite, OPEN → forced)
+// │   ├── closed    (OPEN [!locked] → opened)
+// │   └── opened    (CLOSE / close-action → closed)
+// └── forced
+//
+// `foo` plays the lock.
+
+const OPEN_SIG: u16 = 30;
+const CLOSE_SIG: u16 = 31;
+
+fn door_initial(_sm: &mut TestSm, _e: &DynEvent) -> QHsmResult<TestSm> {
+    q_tran!(closed)
+}
+
+fn door(_sm: &mut TestSm, e: &DynEvent) -> QHsmResult<TestSm> {
+    match e.signal().0 {
+        OPEN_SIG => q_tran!(forced),
+        _ => q_super!(QHsm::<TestSm>::top_state),
+    }
+}
+
+fn closed(sm: &mut TestSm, e: &DynEvent) -> QHsmResult<TestSm> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => { sm.trace.push("closed-ENTRY"); q_handled!() }
+        Q_EXIT_SIG_VAL  => { sm.trace.push("closed-EXIT");  q_handled!() }
+        OPEN_SIG => q_tran_if!(!sm.foo, opened),
+        _ => q_super!(door),
+    }
+}
+
+fn opened(sm: &mut TestSm, e: &DynEvent) -> QHsmResult<TestSm> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => { sm.trace.push("opened-ENTRY"); q_handled!() }
+        Q_EXIT_SIG_VAL  => { sm.trace.push("opened-EXIT");  q_handled!() }
+        CLOSE_SIG => q_tran_with!(closed, |sm, _e| sm.trace.push("close-action")),
+        _ => q_super!(door),
+    }
+}
+
+fn forced(sm: &mut TestSm, e: &DynEvent) -> QHsmResult<TestSm> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => { sm.trace.push("forced-ENTRY"); q_handled!() }
+        _ => q_super!(QHsm::<TestSm>::top_state),
+    }
+}
+
+fn make_door(locked: bool) -> QHsm<TestSm> {
+    let mut hsm = QHsm::new(TestSm { foo: locked, ..TestSm::default() }, door_initial);
+    hsm.init();
+    hsm.sm_mut().trace.clear();
+    hsm
+}
+
+#[test]
+fn tran_if_takes_the_transition_when_the_guard_passes() {
+    let mut hsm = make_door(false);
+    dispatch(&mut hsm, OPEN_SIG);
+    assert!(hsm.state_handler().same_state(opened as StateHandler<TestSm>));
+    assert_eq!(hsm.sm().trace, ["closed-EXIT", "opened-ENTRY"]);
+}
+
+#[test]
+fn tran_if_with_a_failed_guard_passes_the_event_to_the_superstate() {
+    let mut hsm = make_door(true);
+    dispatch(&mut hsm, OPEN_SIG);
+    assert!(hsm.state_handler().same_state(forced as StateHandler<TestSm>));
+    assert_eq!(hsm.sm().trace, ["closed-EXIT", "forced-ENTRY"]);
+}
+
+#[test]
+fn tran_with_runs_the_action_between_exit_and_entry() {
+    let mut hsm = make_door(false);
+    dispatch(&mut hsm, OPEN_SIG);
+    hsm.sm_mut().trace.clear();
+    dispatch(&mut hsm, CLOSE_SIG);
+    assert!(hsm.state_handler().same_state(closed as StateHandler<TestSm>));
+    assert_eq!(hsm.sm().trace, ["opened-EXIT", "close-action", "closed-ENTRY"]);
+}
+
 #[test]
 fn unknown_signal_bubbles_to_top_is_ignored() {
     let mut hsm = make_hsm();
@@ -412,3 +601,185 @@ fn g_sig_tran_from_s11_to_s1() {
     assert!(log.contains(&"s11-ENTRY"), "s11 re-entered via nested init");
     assert!(!log.contains(&"s1-EXIT"),  "s1 must not exit (it's the LCA/target)");
 }
+
+#[cfg(not(feature = "static-alloc"))]
+#[test]
+fn timing_contract_reports_overstay_once_per_entry() {
+    use crate::hsm::contract::QS_CONTRACT_VIOLATION;
+    use crate::trace::TraceHook;
+
+    let records: Arc<Mutex<Vec<Vec<u8>>>> = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&records);
+    let hook: TraceHook = Arc::new(move |rec, payload, _| {
+        if rec == QS_CONTRACT_VIOLATION {
+            sink.lock().unwrap().push(payload.to_vec());
+        }
+        Ok(())
+    });
+
+    let mut hsm = make_hsm()
+        .with_timing_contract(s21, 3)
+        .with_timing_contract(s11, u32::MAX);
+    // Stands in for the wheel of the kernel that would run the HSM.
+    let clock = crate::time::KernelClock::new();
+    clock.enter();
+    hsm.init_traced(Some(hook.clone()));
+    for _ in 0..5 {
+        clock.advance();
+    }
+    assert_eq!(hsm.timing_violations(), 0);
+
+    // Still in s21 past its bound: caught at the end of the next step.
+    let e = DynEvent::empty_dyn(Signal(I_SIG));
+    hsm.dispatch_traced(&e, Some(hook.clone()));
+    assert_eq!(hsm.timing_violations(), 1);
+    {
+        let recorded = records.lock().unwrap();
+        assert_eq!(recorded.len(), 1);
+        let ptr = core::mem::size_of::<usize>();
+        assert_eq!(recorded[0][..ptr], (s21 as StateHandler<TestSm> as usize).to_le_bytes());
+        assert_eq!(recorded[0][ptr..ptr + 4], 3u32.to_le_bytes());
+        let elapsed = u32::from_le_bytes(recorded[0][ptr + 4..].try_into().unwrap());
+        assert_eq!(elapsed, 5);
+    }
+
+    // Neither the next step nor the late exit reports it again; s11 is
+    // within its bound.
+    hsm.dispatch_traced(&e, Some(hook.clone()));
+    let e = DynEvent::empty_dyn(Signal(C_SIG));
+    hsm.dispatch_traced(&e, Some(hook));
+    assert_eq!(hsm.timing_violations(), 1);
+    assert_eq!(records.lock().unwrap().len(), 1);
+}
+
+#[cfg(all(feature = "qs", not(feature = "static-alloc")))]
+#[test]
+fn traced_records_use_the_qs_layouts() {
+    use crate::trace::TraceHook;
+    use qs::records::{qep, RecordSizes};
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&records);
+    let hook: TraceHook = Arc::new(move |rec, payload, ts| {
+        sink.lock().unwrap().push((rec, payload.to_vec(), ts));
+        Ok(())
+    });
+
+    let mut hsm = make_hsm();
+    hsm.init();
+    hsm.dispatch_traced(&DynEvent::empty_dyn(Signal(C_SIG)), Some(hook));
+
+    let obj = &hsm as *const QHsm<TestSm> as u64;
+    let state = |s: StateHandler<TestSm>| s as usize as u64;
+    let expect = |record: qs::records::Predefined| {
+        (record.record_type(), record.encode(&RecordSizes::NATIVE).to_vec(), record.has_timestamp())
+    };
+    let records = records.lock().unwrap();
+    assert_eq!(records[0], expect(qep::dispatch(obj, C_SIG, state(s21))));
+    assert_eq!(records[1], expect(qep::tran(obj, C_SIG, state(s2), state(s1))));
+    assert!(records.contains(&expect(qep::state_init(obj, state(s1), state(s11)))));
+}
+
+#[cfg(all(feature = "qs", not(feature = "static-alloc")))]
+#[test]
+fn tran_deep_hist_is_traced_as_tran_hist_to_the_leaf() {
+    use crate::trace::TraceHook;
+    use qs::records::{qep, RecordSizes};
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&records);
+    let hook: TraceHook = Arc::new(move |rec, payload, _| {
+        sink.lock().unwrap().push((rec, payload.to_vec()));
+        Ok(())
+    });
+
+    let mut hsm = paused_while_fast();
+    hsm.dispatch_traced(&DynEvent::empty_dyn(Signal(RESUME_SIG)), Some(hook));
+
+    let obj = &hsm as *const QHsm<TestSm> as u64;
+    let record = qep::tran_hist(obj, paused as StateHandler<TestSm> as usize as u64, fast as StateHandler<TestSm> as usize as u64);
+    let expected = (record.record_type(), record.encode(&RecordSizes::NATIVE).to_vec());
+    assert!(records.lock().unwrap().contains(&expected));
+}
+
+#[cfg(feature = "qs")]
+#[test]
+fn active_object_answers_host_queries_as_sm_and_ao() {
+    use qs::query::{ObjKind, Queryable, Snapshot};
+
+    use crate::active::{ActiveObject, ActiveRunnable};
+
+    let ao = ActiveObject::new(ActiveObjectId::new(1), 1, make_hsm());
+    ao.start(None);
+    assert_eq!(ao.query(ObjKind::Sm), Some(Snapshot::Sm { state: s21 as StateHandler<TestSm> as usize as u64 }));
+
+    ao.post(DynEvent::empty_dyn(Signal(4)));
+    ao.post(DynEvent::empty_dyn(Signal(5)));
+    ao.dispatch_one();
+    let Some(Snapshot::Ao { n_free, n_min }) = ao.query(ObjKind::Ao) else {
+        panic!("no AO snapshot");
+    };
+    assert_eq!(n_min + 1, n_free);
+    assert_eq!(ao.query(ObjKind::Te), None);
+}
+
+#[cfg(all(feature = "qs", not(feature = "static-alloc")))]
+#[test]
+fn active_object_behavior_addr_is_the_object_of_its_sm_records() {
+    use crate::active::{ActiveObject, ActiveRunnable};
+    use crate::trace::TraceHook;
+    use qs::records::{qep, RecordSizes};
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&records);
+    let hook: TraceHook = Arc::new(move |rec, payload, _| {
+        sink.lock().unwrap().push((rec, payload.to_vec()));
+        Ok(())
+    });
+
+    let ao = ActiveObject::new(ActiveObjectId::new(1), 1, make_hsm());
+    ao.start(Some(hook));
+    records.lock().unwrap().clear();
+    ao.post(DynEvent::empty_dyn(Signal(C_SIG)));
+    ao.dispatch_one();
+
+    let state = s21 as StateHandler<TestSm> as usize as u64;
+    let record = qep::dispatch(ao.behavior_addr(), C_SIG, state);
+    let expected = (record.record_type(), record.encode(&RecordSizes::NATIVE).to_vec());
+    assert_eq!(records.lock().unwrap().first(), Some(&expected));
+}
+
+#[cfg(all(feature = "qs", not(feature = "static-alloc")))]
+#[test]
+fn tran_if_traces_every_guard_evaluation() {
+    use crate::trace::TraceHook;
+    use qs::records::{qep, RecordSizes};
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&records);
+    let hook: TraceHook = Arc::new(move |rec, payload, _| {
+        sink.lock().unwrap().push((rec, payload.to_vec()));
+        Ok(())
+    });
+
+    let state = |s: StateHandler<TestSm>| s as usize as u64;
+    let guards = |hsm: &QHsm<TestSm>| {
+        let records = records.lock().unwrap();
+        let guards: Vec<_> = records.iter().filter(|(rec, _)| *rec == qep::GUARD).cloned().collect();
+        let obj = hsm as *const QHsm<TestSm> as u64;
+        (guards, obj)
+    };
+
+    let mut locked = make_door(true);
+    locked.dispatch_traced(&DynEvent::empty_dyn(Signal(OPEN_SIG)), Some(Arc::clone(&hook)));
+    let (seen, obj) = guards(&locked);
+    let failed = qep::guard(obj, OPEN_SIG, state(closed), state(opened), false);
+    assert_eq!(seen, [(qep::GUARD, failed.encode(&RecordSizes::NATIVE).to_vec())]);
+
+    records.lock().unwrap().clear();
+    let mut unlocked = make_door(false);
+    unlocked.dispatch_traced(&DynEvent::empty_dyn(Signal(OPEN_SIG)), Some(hook));
+    let (seen, obj) = guards(&unlocked);
+    let passed = qep::guard(obj, OPEN_SIG, state(closed), state(opened), true);
+    assert_eq!(seen, [(qep::GUARD, passed.encode(&RecordSizes::NATIVE).to_vec())]);
+}
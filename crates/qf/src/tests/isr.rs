@@ -1,5 +1,5 @@
 //! Unit tests for ISR nesting, run/stop lifecycle, rearm/was_disarmed,
-//! post_from_isr, and tick_from_isr.
+//! post_from_isr, tick_from_isr, and ISR queues.
 
 // Under `static-alloc`, the kernel / time-event handles are `Copy` `&'static`
 // references, so `.clone()` on them is a no-op (it bumps an `Arc` refcount on
@@ -10,8 +10,9 @@ use std::sync::{Arc, Mutex};
 use std::sync::atomic::Ordering;
 
 use crate::active::{new_active_object, ActiveContext, ActiveObjectId, SignalHandler};
-use crate::event::Signal;
+use crate::event::{DynEvent, Signal};
 use crate::isr;
+use crate::isr_queue::{IsrQueue, IsrSource};
 use crate::kernel::Kernel;
 use crate::time::{new_time_event, share_kernel, TimeEventConfig, TimerWheel};
 
@@ -224,3 +225,47 @@ fn tick_from_isr_advances_timer_wheel() {
     kernel.run_until_idle();
     assert_eq!(*count.lock().unwrap(), 1, "tick_from_isr delivered event");
 }
+
+// ── ISR queues ────────────────────────────────────────────────────────────────
+
+#[test]
+fn isr_queue_is_fifo_and_refuses_when_full() {
+    let queue = IsrQueue::<2>::new(ActiveObjectId::new(1));
+    assert!(queue.post_from_isr(DynEvent::empty_dyn(Signal(1))).is_ok());
+    assert!(queue.post_from_isr(DynEvent::empty_dyn(Signal(2))).is_ok());
+    let refused = queue.post_from_isr(DynEvent::empty_dyn(Signal(3))).unwrap_err();
+    assert_eq!((refused.header.signal, queue.len(), queue.dropped()), (Signal(3), 2, 1));
+
+    assert_eq!(queue.pop().map(|e| e.header.signal), Some(Signal(1)));
+    assert!(queue.post_from_isr(DynEvent::empty_dyn(Signal(4))).is_ok());
+    assert_eq!(queue.pop().map(|e| e.header.signal), Some(Signal(2)));
+    assert_eq!(queue.pop().map(|e| e.header.signal), Some(Signal(4)));
+    assert!(queue.pop().is_none() && queue.is_empty());
+}
+
+struct Signals(Arc<Mutex<Vec<Signal>>>);
+impl SignalHandler for Signals {
+    fn handle_signal(&mut self, signal: Signal, _ctx: &mut ActiveContext) {
+        self.0.lock().unwrap().push(signal);
+    }
+}
+
+#[test]
+fn cooperative_kernel_drains_isr_queues() {
+    static QUEUE: IsrQueue<4> = IsrQueue::new(ActiveObjectId::new(12));
+    let _guard = lock_isr_test();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let ao = new_active_object(ActiveObjectId::new(12), 1, Signals(log.clone()));
+    let kernel = Kernel::builder().register(ao).isr_queue(&QUEUE).build();
+    kernel.start();
+
+    crate::qk_isr_entry!();
+    QUEUE.post_from_isr(DynEvent::empty_dyn(Signal(0x40))).unwrap();
+    QUEUE.post_from_isr(DynEvent::empty_dyn(Signal(0x41))).unwrap();
+    crate::qk_isr_exit!();
+    assert!(kernel.has_pending_work());
+
+    kernel.run_until_idle();
+    assert_eq!(*log.lock().unwrap(), [Signal(0x40), Signal(0x41)]);
+    assert!(!kernel.has_pending_work());
+}
@@ -61,3 +61,419 @@ fn kernel_delivers_events() {
     assert_eq!(events.len(), 1);
     assert_eq!(events[0], Signal(0x42));
 }
+
+#[test]
+fn current_ao_is_set_only_while_dispatching() {
+    #[derive(Clone, Default)]
+    struct Observer {
+        seen: Arc<Mutex<Vec<Option<ActiveObjectId>>>>,
+    }
+
+    impl SignalHandler for Observer {
+        fn on_start(&mut self, _ctx: &mut ActiveContext) {}
+
+        fn handle_signal(&mut self, _signal: Signal, _ctx: &mut ActiveContext) {
+            self.seen.lock().unwrap().push(crate::current_ao());
+        }
+    }
+
+    let observer = Observer::default();
+    let ao = ActiveObject::new(ActiveObjectId::new(4), 1, observer.clone());
+    #[cfg(not(feature = "static-alloc"))]
+    let r: &dyn ActiveRunnable = &*ao;
+    #[cfg(feature = "static-alloc")]
+    let r: &dyn ActiveRunnable = &ao;
+
+    assert_eq!(crate::current_ao(), None);
+    ActiveRunnable::post(r, DynEvent::empty_dyn(Signal(1)));
+    assert!(ActiveRunnable::dispatch_one(r));
+    assert_eq!(*observer.seen.lock().unwrap(), vec![Some(ActiveObjectId::new(4))]);
+    assert_eq!(crate::current_ao(), None);
+}
+
+#[cfg(feature = "qs")]
+#[test]
+fn context_records_carry_the_ao_qs_id() {
+    struct Emitter;
+
+    impl SignalHandler for Emitter {
+        fn on_start(&mut self, _ctx: &mut ActiveContext) {}
+
+        fn handle_signal(&mut self, _signal: Signal, ctx: &mut ActiveContext) {
+            ctx.emit_trace(100, &[]).unwrap();
+            // Records emitted straight through the hook are attributed too.
+            ctx.trace_hook().unwrap()(101, &[], false).unwrap();
+        }
+    }
+
+    let tagged = Arc::new(Mutex::new(Vec::new()));
+    let sink = tagged.clone();
+    let hook: crate::TraceHook = Arc::new(move |record, _payload: &[u8], _ts| {
+        sink.lock().unwrap().push((record, qs::current_qs_id()));
+        Ok(())
+    });
+
+    let ao = ActiveObject::new(ActiveObjectId::new(5), 1, Emitter);
+    #[cfg(not(feature = "static-alloc"))]
+    let r: &dyn ActiveRunnable = &*ao;
+    #[cfg(feature = "static-alloc")]
+    let r: &dyn ActiveRunnable = &ao;
+    ActiveRunnable::start(r, Some(hook));
+    ActiveRunnable::post(r, DynEvent::empty_dyn(Signal(1)));
+    assert!(ActiveRunnable::dispatch_one(r));
+
+    assert_eq!(*tagged.lock().unwrap(), vec![(100, Some(5)), (101, Some(5))]);
+    assert_eq!(qs::current_qs_id(), None);
+}
+
+#[cfg(not(feature = "static-alloc"))]
+#[test]
+fn run_until_idle_reports_each_batch() {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let hook: crate::TraceHook = Arc::new(move |record, payload: &[u8], _ts| {
+        if record == crate::batch::QS_RUN_BATCH {
+            sink.lock().unwrap().push(u16::from_le_bytes([payload[0], payload[1]]));
+        }
+        Ok(())
+    });
+
+    let ao = new_active_object(ActiveObjectId::new(1), 1, Collector::default());
+    let kernel = Kernel::builder().register(ao).with_trace_hook(hook).build();
+    kernel.start();
+
+    for sig in 1..=3 {
+        kernel.post(ActiveObjectId::new(1), DynEvent::empty_dyn(Signal(sig))).unwrap();
+    }
+    kernel.run_until_idle();
+    // An idle pass dispatches nothing and stays silent.
+    kernel.run_until_idle();
+
+    assert_eq!(*records.lock().unwrap(), vec![3]);
+}
+
+// The smp kernel traces no scheduler idle switch.
+#[cfg(not(any(feature = "static-alloc", feature = "smp")))]
+#[test]
+fn idle_callback_runs_under_the_interrupt_lock_after_the_idle_record() {
+    use crate::idle::{IdleContext, InterruptLock};
+    use crate::kernel::KernelConfig;
+    use crate::ActiveObjectRef;
+
+    static LOG: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+    // Posted to by the lock, standing in for an ISR that fires just before it.
+    static LATE_POST: Mutex<Option<ActiveObjectRef>> = Mutex::new(None);
+
+    fn log(entry: &'static str) {
+        LOG.lock().unwrap().push(entry);
+    }
+    fn lock() -> u32 {
+        log("lock");
+        if let Some(ao) = LATE_POST.lock().unwrap().take() {
+            ao.post(DynEvent::empty_dyn(Signal(9)));
+        }
+        7
+    }
+    fn unlock(state: u32) {
+        assert_eq!(state, 7);
+        log("unlock");
+    }
+    fn on_idle(cx: IdleContext) {
+        assert!(cx.is_locked());
+        log("on_idle");
+        cx.sleep(|| log("wfi"));
+    }
+
+    let hook: crate::TraceHook = Arc::new(|record, _payload: &[u8], _ts| {
+        if record == 53 {
+            log("SCHED_IDLE");
+        }
+        Ok(())
+    });
+    let config = KernelConfig::builder()
+        .on_idle(on_idle)
+        .interrupt_lock(InterruptLock { lock, unlock })
+        .build();
+    let collector = Collector::default();
+    let probe = collector.clone();
+    let ao = new_active_object(ActiveObjectId::new(1), 1, collector);
+    let kernel = Kernel::with_config(config).register(ao.clone()).with_trace_hook(hook).build();
+    kernel.start();
+
+    kernel.post(ActiveObjectId::new(1), DynEvent::empty_dyn(Signal(4))).unwrap();
+    kernel.run_until_idle();
+    assert_eq!(*LOG.lock().unwrap(), ["SCHED_IDLE", "lock", "on_idle", "wfi", "unlock"]);
+
+    // An event that lands before the lock is taken keeps the kernel awake.
+    LOG.lock().unwrap().clear();
+    *LATE_POST.lock().unwrap() = Some(ao);
+    kernel.run_until_idle();
+    assert_eq!(*LOG.lock().unwrap(), ["lock", "unlock"]);
+    assert!(kernel.has_pending_work());
+    kernel.run_until_idle();
+    assert_eq!(*probe.events.lock().unwrap(), [Signal(4), Signal(9)]);
+}
+
+#[test]
+fn threaded_execution_runs_each_ao_on_its_own_thread() {
+    use crate::kernel::{Execution, KernelConfig};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    static PRIORITIES: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+    fn set_priority(priority: u8) {
+        PRIORITIES.lock().unwrap().push(priority);
+    }
+
+    struct Reporter {
+        id: u8,
+        seen: mpsc::Sender<(u8, Signal, Option<String>)>,
+    }
+    impl SignalHandler for Reporter {
+        fn on_start(&mut self, _ctx: &mut ActiveContext) {}
+
+        fn handle_signal(&mut self, signal: Signal, _ctx: &mut ActiveContext) {
+            let thread = std::thread::current().name().map(str::to_owned);
+            self.seen.send((self.id, signal, thread)).unwrap();
+        }
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let config = KernelConfig::builder()
+        .execution(Execution::Threaded)
+        .thread_priority(set_priority)
+        .build();
+    let kernel = Kernel::with_config(config)
+        .register(new_active_object(ActiveObjectId::new(1), 1, Reporter { id: 1, seen: tx.clone() }))
+        .register(new_active_object(ActiveObjectId::new(2), 2, Reporter { id: 2, seen: tx }))
+        .build();
+    // Queued before the threads exist.
+    kernel.post(ActiveObjectId::new(1), DynEvent::empty_dyn(Signal(4))).unwrap();
+
+    let mut seen = std::thread::scope(|scope| {
+        scope.spawn(|| kernel.run(|| std::thread::sleep(Duration::from_millis(1))));
+        kernel.post(ActiveObjectId::new(2), DynEvent::empty_dyn(Signal(5))).unwrap();
+        kernel.publish(Signal(6), DynEvent::empty_dyn(Signal(6)));
+        let seen: Vec<_> = (0..4).map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap()).collect();
+        kernel.stop();
+        seen
+    });
+    seen.sort_by_key(|&(id, signal, _)| (id, signal));
+    let name = |id: u8| Some(format!("ao-{id}"));
+    assert_eq!(
+        seen,
+        [(1, Signal(4), name(1)), (1, Signal(6), name(1)), (2, Signal(5), name(2)), (2, Signal(6), name(2))]
+    );
+    let mut priorities = PRIORITIES.lock().unwrap().clone();
+    priorities.sort_unstable();
+    assert_eq!(priorities, [1, 2]);
+
+    // With the threads gone, posts queue for the cooperative path again.
+    kernel.post(ActiveObjectId::new(1), DynEvent::empty_dyn(Signal(7))).unwrap();
+    assert!(kernel.has_pending_work());
+}
+
+#[test]
+fn pool_execution_spreads_aos_over_workers() {
+    use crate::kernel::{Execution, KernelConfig};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    const AOS: u8 = 40;
+    // Within a static-alloc queue: 7 own events and 8 passed on.
+    const EVENTS: u16 = 8;
+
+    /// Logs its signals and passes each on to the next AO once; AOs 1 and 2
+    /// also check that they never run at the same time.
+    struct Hop {
+        id: u8,
+        seen: mpsc::Sender<(u8, Signal, Option<String>)>,
+        busy: Arc<AtomicBool>,
+        overlaps: Arc<AtomicUsize>,
+    }
+    impl SignalHandler for Hop {
+        fn handle_signal(&mut self, signal: Signal, ctx: &mut ActiveContext) {
+            let stranded = self.id <= 2;
+            if stranded && self.busy.swap(true, Ordering::SeqCst) {
+                self.overlaps.fetch_add(1, Ordering::SeqCst);
+            }
+            std::thread::sleep(Duration::from_micros(50));
+            let thread = std::thread::current().name().map(str::to_owned);
+            self.seen.send((self.id, signal, thread)).unwrap();
+            if signal.0 < EVENTS {
+                let next = ActiveObjectId::new(self.id % AOS + 1);
+                ctx.post(next, DynEvent::empty_dyn(Signal(signal.0 + EVENTS))).unwrap();
+            }
+            if stranded {
+                self.busy.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let busy = Arc::new(AtomicBool::new(false));
+    let overlaps = Arc::new(AtomicUsize::new(0));
+    let config = KernelConfig::builder().execution(Execution::Pool { workers: 3 }).build();
+    let mut builder = Kernel::with_config(config)
+        .strand(&[ActiveObjectId::new(1), ActiveObjectId::new(2)]);
+    for id in 1..=AOS {
+        let hop = Hop { id, seen: tx.clone(), busy: Arc::clone(&busy), overlaps: Arc::clone(&overlaps) };
+        builder = builder.register(new_active_object(ActiveObjectId::new(id), id, hop));
+    }
+    let kernel = builder.build();
+    // Queued before the workers exist.
+    kernel.post(ActiveObjectId::new(1), DynEvent::empty_dyn(Signal(0))).unwrap();
+
+    // Every own event is handled once and passed on once.
+    let total = (usize::from(AOS) * usize::from(EVENTS - 1) + 1) * 2;
+    let seen = std::thread::scope(|scope| {
+        scope.spawn(|| kernel.run(|| std::thread::sleep(Duration::from_millis(1))));
+        for id in 1..=AOS {
+            for sig in 1..EVENTS {
+                kernel.post(ActiveObjectId::new(id), DynEvent::empty_dyn(Signal(sig))).unwrap();
+            }
+        }
+        let seen: Vec<_> = (0..total).map_while(|_| rx.recv_timeout(Duration::from_secs(10)).ok()).collect();
+        kernel.stop();
+        seen
+    });
+    assert_eq!(seen.len(), total);
+
+    // Each AO handled its own events in the order they were posted.
+    for id in 1..=AOS {
+        let own: Vec<u16> =
+            seen.iter().filter(|&&(ao, sig, _)| ao == id && sig.0 < EVENTS).map(|(_, sig, _)| sig.0).collect();
+        let expected: Vec<u16> = if id == 1 { (0..EVENTS).collect() } else { (1..EVENTS).collect() };
+        assert_eq!(own, expected, "AO {id}");
+    }
+    assert!(seen.iter().all(|(_, _, thread)| thread.as_deref().is_some_and(|t| t.starts_with("qf-worker-"))));
+    assert_eq!(overlaps.load(Ordering::SeqCst), 0);
+    assert!(!kernel.has_pending_work());
+}
+
+/// Forwards signal 1 to the collector, defers signal 2 and recalls it on
+/// signal 3, through its context only.
+struct Relay {
+    deferred: crate::equeue::QEQueue,
+    seen: Arc<Mutex<Vec<Signal>>>,
+}
+
+impl SignalHandler for Relay {
+    fn on_start(&mut self, ctx: &mut ActiveContext) {
+        ctx.post(ActiveObjectId::new(2), DynEvent::empty_dyn(Signal(9))).unwrap();
+    }
+
+    fn handle_signal(&mut self, signal: Signal, ctx: &mut ActiveContext) {
+        self.seen.lock().unwrap().push(signal);
+        match signal.0 {
+            1 => ctx.post(ActiveObjectId::new(2), DynEvent::empty_dyn(Signal(10))).unwrap(),
+            2 => assert!(ctx.defer(&self.deferred, DynEvent::empty_dyn(Signal(4)))),
+            3 => assert_eq!(ctx.recall(&self.deferred), Ok(true)),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn contexts_post_defer_and_recall_through_their_kernel() {
+    use crate::services::ServiceError;
+
+    let collector = Collector::default();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let relay = Relay { deferred: crate::equeue::QEQueue::new(2), seen: Arc::clone(&seen) };
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(1), 2, relay))
+        .register(new_active_object(ActiveObjectId::new(2), 1, collector.clone()))
+        .build();
+    kernel.start();
+    for signal in [1, 2, 3, 5] {
+        kernel.post(ActiveObjectId::new(1), DynEvent::empty_dyn(Signal(signal))).unwrap();
+    }
+    kernel.run_until_idle();
+
+    assert_eq!(*seen.lock().unwrap(), [Signal(1), Signal(2), Signal(3), Signal(4), Signal(5)]);
+    assert_eq!(*collector.events.lock().unwrap(), [Signal(9), Signal(10)]);
+
+    let outside = ActiveContext::new(ActiveObjectId::new(1), None);
+    let refused = outside.post(ActiveObjectId::new(2), DynEvent::empty_dyn(Signal(1)));
+    assert_eq!(refused, Err(ServiceError::NoKernel));
+}
+
+#[cfg(not(feature = "static-alloc"))]
+#[test]
+fn shutdown_stops_highest_priority_first_and_flushes_the_tracer() {
+    use crate::kernel::STOP_SIG;
+
+    const LOW: ActiveObjectId = ActiveObjectId::new(1);
+    const HIGH: ActiveObjectId = ActiveObjectId::new(2);
+    const LAST_WORDS: Signal = Signal(20);
+
+    #[derive(Clone)]
+    struct Stoppable {
+        id: ActiveObjectId,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl SignalHandler for Stoppable {
+        fn handle_signal(&mut self, signal: Signal, ctx: &mut ActiveContext) {
+            self.log.lock().unwrap().push(format!("{} sig {}", self.id.0, signal.0));
+            // The producer hands the consumer one last event as it stops.
+            if signal == STOP_SIG && self.id == HIGH {
+                ctx.post(LOW, DynEvent::empty_dyn(LAST_WORDS)).unwrap();
+            }
+        }
+
+        fn on_stop(&mut self, _ctx: &mut ActiveContext) {
+            self.log.lock().unwrap().push(format!("{} stopped", self.id.0));
+        }
+    }
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let flushes = Arc::new(Mutex::new(0));
+    let counter = flushes.clone();
+    let kernel = Kernel::builder()
+        .register(new_active_object(LOW, 1, Stoppable { id: LOW, log: log.clone() }))
+        .register(new_active_object(HIGH, 2, Stoppable { id: HIGH, log: log.clone() }))
+        .with_trace_flush(Arc::new(move || {
+            *counter.lock().unwrap() += 1;
+            Ok(())
+        }))
+        .build();
+    kernel.start();
+
+    kernel.shutdown(10, || {}).unwrap();
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        ["2 sig 65535", "1 sig 20", "1 sig 65535", "2 stopped", "1 stopped"]
+    );
+    assert_eq!(*flushes.lock().unwrap(), 1);
+    assert!(!kernel.has_pending_work());
+}
+
+#[cfg(not(feature = "static-alloc"))]
+#[test]
+fn shutdown_gives_up_on_queues_that_do_not_drain() {
+    use crate::kernel::KernelError;
+
+    let collector = Collector::default();
+    let probe = collector.clone();
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(1), 1, collector))
+        .build();
+    kernel.start();
+    // A scheduler lock left held keeps the object from ever being dispatched.
+    kernel.lock_scheduler(1);
+
+    let mut ticks = 0;
+    let result = kernel.shutdown(3, || {
+        ticks += 1;
+        kernel.clock().advance();
+    });
+
+    assert!(matches!(result, Err(KernelError::ShutdownTimeout)));
+    assert_eq!(ticks, 3);
+    assert!(probe.events.lock().unwrap().is_empty());
+    assert!(kernel.has_pending_work());
+}
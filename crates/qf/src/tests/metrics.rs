@@ -0,0 +1,158 @@
+use super::budget::{fake_clock, run, Worker, SERIAL};
+use crate::active::new_active_object;
+use crate::budget::set_budget_clock;
+use crate::event::{DynEvent, Signal};
+use crate::isr_queue::IsrQueue;
+use crate::kernel::{Kernel, KernelError};
+use crate::metrics::Counters;
+use crate::ActiveObjectId;
+
+#[test]
+fn dispatches_are_counted_per_object_and_per_signal() {
+    let _serial = SERIAL.lock().unwrap();
+    set_budget_clock(fake_clock);
+
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(1), 1, Worker))
+        .register(new_active_object(ActiveObjectId::new(2), 2, Worker))
+        .with_metrics()
+        .build();
+    kernel.start();
+
+    run(&kernel, 1, &[2, 3, 1]);
+    run(&kernel, 2, &[3]);
+
+    let metrics = kernel.metrics();
+    let first = metrics.ao(1).unwrap();
+    assert_eq!(first.id, ActiveObjectId::new(1));
+    assert_eq!(
+        first.counters,
+        Counters {
+            dispatches: 3,
+            total: 60,
+            max: 30,
+            dropped: 0
+        }
+    );
+    assert_eq!(first.counters.mean(), Some(20));
+    assert_eq!(first.queue_high_watermark, 3);
+    assert_eq!(metrics.ao(2).map(|ao| ao.counters.dispatches), Some(1));
+
+    let three = metrics.signal(Signal(3)).unwrap();
+    assert_eq!(
+        three.counters,
+        Counters {
+            dispatches: 2,
+            total: 60,
+            max: 30,
+            dropped: 0
+        }
+    );
+    let seen: Vec<_> = metrics.signals().map(|s| s.signal).collect();
+    assert_eq!(seen, [Signal(1), Signal(2), Signal(3)]);
+
+    metrics.reset();
+    assert_eq!(metrics.aos().count(), 0);
+    assert!(metrics.signal(Signal(3)).is_none());
+}
+
+#[test]
+fn dropped_posts_are_charged_to_their_signal_or_target() {
+    static QUEUE: IsrQueue<1> = IsrQueue::new(ActiveObjectId::new(21));
+    let _serial = SERIAL.lock().unwrap();
+    set_budget_clock(fake_clock);
+
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(21), 6, Worker))
+        .isr_queue(&QUEUE)
+        .with_metrics()
+        .build();
+    kernel.start();
+
+    let missing = ActiveObjectId::new(22);
+    let err = kernel
+        .post(missing, DynEvent::empty_dyn(Signal(5)))
+        .unwrap_err();
+    assert!(matches!(err, KernelError::NotFound(id) if id == missing));
+    assert_eq!(
+        kernel
+            .metrics()
+            .signal(Signal(5))
+            .map(|s| s.counters.dropped),
+        Some(1)
+    );
+
+    QUEUE.post_from_isr(DynEvent::empty_dyn(Signal(1))).unwrap();
+    assert!(QUEUE.post_from_isr(DynEvent::empty_dyn(Signal(1))).is_err());
+    kernel.run_until_idle();
+    let ao = kernel.metrics().ao(6).unwrap();
+    assert_eq!((ao.counters.dispatches, ao.counters.dropped), (1, 1));
+
+    // Only refusals since the last drain are charged.
+    kernel.run_until_idle();
+    assert_eq!(
+        kernel.metrics().ao(6).map(|ao| ao.counters.dropped),
+        Some(1)
+    );
+}
+
+#[test]
+fn metrics_are_off_unless_asked_for() {
+    let _serial = SERIAL.lock().unwrap();
+
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(7), 7, Worker))
+        .build();
+    kernel.start();
+    run(&kernel, 7, &[1]);
+
+    assert!(!kernel.metrics().is_enabled());
+    assert!(kernel.metrics().ao(7).is_none());
+    assert_eq!(kernel.metrics().signals().count(), 0);
+}
+
+#[cfg(not(feature = "static-alloc"))]
+#[test]
+fn metrics_are_emitted_one_record_per_object_and_signal() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::metrics::{KIND_AO, KIND_SIGNAL, QS_METRICS};
+
+    let _serial = SERIAL.lock().unwrap();
+    set_budget_clock(fake_clock);
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&records);
+    let hook: crate::TraceHook = Arc::new(move |record, payload: &[u8], _| {
+        if record == QS_METRICS {
+            sink.lock().unwrap().push(payload.to_vec());
+        }
+        Ok(())
+    });
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(8), 8, Worker))
+        .with_trace_hook(hook.clone())
+        .with_metrics()
+        .build();
+    kernel.start();
+    run(&kernel, 8, &[4]);
+
+    // No period was set, so nothing is due.
+    assert!(!kernel.metrics().emit_due(Some(&hook)));
+    kernel.metrics().emit(&hook);
+
+    let record = |kind: u8, key: u16, hwm: u16| {
+        let mut payload = vec![kind];
+        payload.extend_from_slice(&key.to_le_bytes());
+        for value in [1u32, 40, 40] {
+            payload.extend_from_slice(&value.to_le_bytes());
+        }
+        payload.extend_from_slice(&hwm.to_le_bytes());
+        payload.extend_from_slice(&0u32.to_le_bytes());
+        payload
+    };
+    assert_eq!(
+        *records.lock().unwrap(),
+        [record(KIND_AO, 8, 1), record(KIND_SIGNAL, 4, 0)]
+    );
+}
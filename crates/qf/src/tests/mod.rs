@@ -1,7 +1,21 @@
+#[cfg(not(feature = "static-alloc"))]
+mod async_ao;
+mod budget;
 mod equeue;
 mod hsm;
 mod isr;
 mod kernel;
+mod metrics;
+mod persist;
 mod pool;
 mod pubsub;
+#[cfg(not(feature = "static-alloc"))]
+mod regions;
+#[cfg(not(feature = "static-alloc"))]
+mod replay;
+mod schedulability;
+mod signals;
+mod static_ao;
 mod time;
+#[cfg(not(feature = "smp"))]
+mod watchdog;
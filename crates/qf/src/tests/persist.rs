@@ -0,0 +1,183 @@
+use crate::active::{ActiveBehavior, ActiveContext, ActiveObject, ActiveObjectId};
+use crate::event::DynEvent;
+use crate::persist::{
+    BlobStore, Flash, FlashStore, FlashStoreError, PersistError, Persistence, Persistent, PersistentActive,
+};
+
+/// Sector-erasable RAM standing in for flash. Writes can only clear bits, and
+/// can be cut off to simulate a power loss.
+struct RamFlash {
+    bytes: Vec<u8>,
+    writes_left: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct PowerLost;
+
+impl RamFlash {
+    fn new(sectors: usize) -> Self {
+        Self { bytes: vec![0xFF; sectors * Self::SECTOR_SIZE], writes_left: None }
+    }
+}
+
+impl Flash for RamFlash {
+    type Error = PowerLost;
+    const SECTOR_SIZE: usize = 64;
+
+    fn sectors(&self) -> usize {
+        self.bytes.len() / Self::SECTOR_SIZE
+    }
+
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), PowerLost> {
+        buf.copy_from_slice(&self.bytes[offset..offset + buf.len()]);
+        Ok(())
+    }
+
+    fn erase(&mut self, sector: usize) -> Result<(), PowerLost> {
+        let start = sector * Self::SECTOR_SIZE;
+        self.bytes[start..start + Self::SECTOR_SIZE].fill(0xFF);
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), PowerLost> {
+        assert_eq!(offset % 4, 0);
+        if let Some(left) = &mut self.writes_left {
+            if *left == 0 {
+                return Err(PowerLost);
+            }
+            *left -= 1;
+        }
+        for (cell, byte) in self.bytes[offset..].iter_mut().zip(data) {
+            *cell &= byte;
+        }
+        Ok(())
+    }
+}
+
+/// A dispensing workflow: the step it is on and the volume dispensed.
+#[derive(Default)]
+struct Doser {
+    step: u8,
+    dispensed: u16,
+    started_at_step: Option<u8>,
+}
+
+impl ActiveBehavior for Doser {
+    fn on_start(&mut self, _ctx: &mut ActiveContext) {
+        self.started_at_step = Some(self.step);
+    }
+
+    fn on_event(&mut self, _ctx: &mut ActiveContext, _event: DynEvent) {}
+}
+
+impl Persistent for Doser {
+    fn save(&self, buf: &mut [u8]) -> Option<usize> {
+        let blob = [1, self.step, self.dispensed as u8, (self.dispensed >> 8) as u8];
+        buf.get_mut(..blob.len())?.copy_from_slice(&blob);
+        Some(blob.len())
+    }
+
+    fn restore(&mut self, blob: &[u8]) -> bool {
+        let [1, step, lo, hi] = *blob else { return false };
+        self.step = step;
+        self.dispensed = u16::from_le_bytes([lo, hi]);
+        true
+    }
+}
+
+fn doser(id: u8, step: u8, dispensed: u16) -> ActiveObject<Doser> {
+    let ao = ActiveObject::new(ActiveObjectId::new(id), id, Doser { step, dispensed, started_at_step: None });
+    // `new` hands back an `Arc` on the dynamic build; unwrap it.
+    #[cfg(not(feature = "static-alloc"))]
+    let ao = std::sync::Arc::into_inner(ao).unwrap();
+    ao
+}
+
+#[cfg(feature = "qs")]
+#[test]
+fn saved_state_is_restored_before_the_initial_transition() {
+    use crate::active::ActiveRunnable;
+    use std::sync::{Arc, Mutex};
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&records);
+    let hook: crate::TraceHook = Arc::new(move |record, payload: &[u8], _ts| {
+        sink.lock().unwrap().push((record, payload[payload.len() - 3..].to_vec()));
+        Ok(())
+    });
+
+    let before = [doser(1, 3, 250), doser(2, 1, 40)];
+    let objects: [&dyn PersistentActive; 2] = [&before[0], &before[1]];
+    let mut persistence = Persistence::<_, 16>::new(FlashStore::new(RamFlash::new(4)), &objects);
+    persistence.save_all().unwrap();
+    let flash = persistence.into_store().into_inner();
+
+    // Power cycle: fresh objects at their initial state.
+    let after = [doser(1, 0, 0), doser(2, 0, 0)];
+    let objects: [&dyn PersistentActive; 2] = [&after[0], &after[1]];
+    let mut persistence =
+        Persistence::<_, 16>::new(FlashStore::new(flash), &objects).with_trace_hook(hook);
+    assert_eq!(persistence.restore_all(), 2);
+    for ao in &after {
+        ActiveRunnable::start(ao, None);
+    }
+    assert_eq!(after[0].with_behavior(|d| (d.started_at_step, d.dispensed)), (Some(3), 250));
+    assert_eq!(after[1].with_behavior(|d| (d.started_at_step, d.dispensed)), (Some(1), 40));
+
+    // QS_AO_RESTORE: ... | len: u16 | status: u8.
+    let restore = qs::records::qf::AO_RESTORE;
+    assert_eq!(*records.lock().unwrap(), [(restore, vec![4, 0, 0]), (restore, vec![4, 0, 0])]);
+}
+
+#[test]
+fn missing_and_rejected_state_keep_the_initial_state() {
+    let ao = doser(5, 7, 7);
+    let objects: [&dyn PersistentActive; 1] = [&ao];
+    let mut store = FlashStore::new(RamFlash::new(2));
+    store.store(ActiveObjectId::new(6), &[9]).unwrap();
+    let mut persistence = Persistence::<_, 16>::new(store, &objects);
+    assert_eq!(persistence.restore(ActiveObjectId::new(5)), Ok(false));
+
+    persistence.store_mut().store(ActiveObjectId::new(5), &[2, 0, 0, 0]).unwrap();
+    assert_eq!(persistence.restore(ActiveObjectId::new(5)), Err(PersistError::Rejected(ActiveObjectId::new(5))));
+    assert_eq!(ao.with_behavior(|d| d.step), 7);
+    assert_eq!(persistence.save(ActiveObjectId::new(6)), Err(PersistError::NotFound(ActiveObjectId::new(6))));
+}
+
+#[test]
+fn a_save_cut_short_leaves_the_previous_state() {
+    let mut store = FlashStore::new(RamFlash::new(3));
+    let id = ActiveObjectId::new(1);
+    let mut buf = [0u8; 16];
+    for round in 0u8..4 {
+        store.store(id, &[round; 5]).unwrap();
+        assert_eq!(store.load(id, &mut buf), Ok(Some(5)));
+        assert_eq!(buf[..5], [round; 5]);
+    }
+
+    // Power fails after the data but before the header of the next copy.
+    let mut flash = store.into_inner();
+    flash.writes_left = Some(1);
+    let mut store = FlashStore::new(flash);
+    assert_eq!(store.store(id, &[9; 5]), Err(FlashStoreError::Flash(PowerLost)));
+    assert_eq!(store.load(id, &mut buf), Ok(Some(5)));
+    assert_eq!(buf[..5], [3; 5]);
+}
+
+#[test]
+fn a_corrupt_newest_copy_falls_back_to_the_older_one() {
+    let mut store = FlashStore::new(RamFlash::new(2));
+    let id = ActiveObjectId::new(4);
+    store.store(id, b"old").unwrap();
+    store.store(id, b"new").unwrap();
+    let mut flash = store.into_inner();
+    // Flip a data bit of whichever sector holds the newer copy.
+    let newer = (0..2).find(|s| flash.bytes[s * 64 + 12..s * 64 + 15] == *b"new").unwrap();
+    flash.bytes[newer * 64 + 12] ^= 1;
+
+    let mut store = FlashStore::new(flash);
+    let mut buf = [0u8; 8];
+    assert_eq!(store.load(id, &mut buf), Ok(Some(3)));
+    assert_eq!(&buf[..3], b"old");
+    assert_eq!(store.store(ActiveObjectId::new(5), b"x"), Err(FlashStoreError::Full));
+}
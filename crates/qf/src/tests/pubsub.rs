@@ -125,3 +125,65 @@ fn fallback_broadcast_when_no_ps_init() {
     assert_eq!(probe1.events.lock().unwrap().as_slice(), &[Signal(10)]);
     assert_eq!(probe2.events.lock().unwrap().as_slice(), &[Signal(10)]);
 }
+
+#[cfg(all(feature = "qs", not(feature = "static-alloc")))]
+#[test]
+fn each_subscriber_releases_a_published_pool_event_through_gc() {
+    use qs::records::{qf, RecordSizes};
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let hook: crate::TraceHook = Arc::new(move |record, payload: &[u8], _ts| {
+        if record == qf::GC || record == qf::GC_ATTEMPT {
+            sink.lock().unwrap().push((record, payload.to_vec()));
+        }
+        Ok(())
+    });
+
+    let kernel = Kernel::builder()
+        .ps_init(100)
+        .register(new_active_object(ActiveObjectId::new(1), 1, Collector::default()))
+        .register(new_active_object(ActiveObjectId::new(2), 2, Collector::default()))
+        .with_trace_hook(hook)
+        .build();
+    kernel.start();
+    kernel.subscribe(Signal(10), 1);
+    kernel.subscribe(Signal(10), 2);
+
+    let mut event = DynEvent::with_payload(Signal(10), 7u32);
+    event.header = event.header.with_pool(3);
+    kernel.publish(Signal(10), event);
+    kernel.run_until_idle();
+
+    let encode = |record: qs::records::Predefined| {
+        (record.record_type(), record.encode(&RecordSizes::NATIVE).to_vec())
+    };
+    assert_eq!(
+        *records.lock().unwrap(),
+        vec![encode(qf::gc_attempt(10, 3, 2)), encode(qf::gc(10, 3, 1))],
+        "the first subscriber leaves the event to the second, which frees it"
+    );
+}
+
+#[cfg(not(feature = "static-alloc"))]
+#[test]
+fn events_outside_a_pool_are_not_collected() {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let hook: crate::TraceHook = Arc::new(move |record, _payload: &[u8], _ts| {
+        sink.lock().unwrap().push(record);
+        Ok(())
+    });
+
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(1), 1, Collector::default()))
+        .with_trace_hook(hook)
+        .build();
+    kernel.start();
+    kernel.post(ActiveObjectId::new(1), DynEvent::with_payload(Signal(10), 7u32)).unwrap();
+    kernel.run_until_idle();
+
+    let records = records.lock().unwrap();
+    assert!(!records.contains(&qs_protocol::records::qf::GC));
+    assert!(!records.contains(&qs_protocol::records::qf::GC_ATTEMPT));
+}
@@ -0,0 +1,165 @@
+//! Orthogonal regions: a door with a lock region and a light region.
+//!
+//! ```text
+//! lock region                     light region
+//! ├── unlocked                    ├── off
+//! └── locked   (initial → armed)  └── on
+//!     ├── armed
+//!     └── alarm
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use crate::event::{DynEvent, Signal};
+use crate::hsm::reserved::*;
+use crate::hsm::{Outcome, QHsm, QHsmResult, Regions, SameState, StateHandler};
+use crate::{q_handled, q_super, q_tran};
+
+const LOCK_SIG: u16 = 4;
+const FORCE_SIG: u16 = 5;
+const SWITCH_SIG: u16 = 6;
+const KNOCK_SIG: u16 = 7;
+const NOISE_SIG: u16 = 8;
+
+type Log = Arc<Mutex<Vec<&'static str>>>;
+
+struct Lock {
+    log: Log,
+    knocks: u32,
+}
+
+struct Light {
+    log: Log,
+}
+
+fn lock_initial(_sm: &mut Lock, _e: &DynEvent) -> QHsmResult<Lock> {
+    q_tran!(unlocked)
+}
+
+fn unlocked(sm: &mut Lock, e: &DynEvent) -> QHsmResult<Lock> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => { sm.log.lock().unwrap().push("unlocked-ENTRY"); q_handled!() }
+        Q_EXIT_SIG_VAL => { sm.log.lock().unwrap().push("unlocked-EXIT"); q_handled!() }
+        LOCK_SIG => q_tran!(locked),
+        _ => q_super!(QHsm::<Lock>::top_state),
+    }
+}
+
+fn locked(sm: &mut Lock, e: &DynEvent) -> QHsmResult<Lock> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => { sm.log.lock().unwrap().push("locked-ENTRY"); q_handled!() }
+        Q_EXIT_SIG_VAL => { sm.log.lock().unwrap().push("locked-EXIT"); q_handled!() }
+        Q_INIT_SIG_VAL => q_tran!(armed),
+        KNOCK_SIG => { sm.knocks += 1; q_handled!() }
+        _ => q_super!(QHsm::<Lock>::top_state),
+    }
+}
+
+fn armed(sm: &mut Lock, e: &DynEvent) -> QHsmResult<Lock> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => { sm.log.lock().unwrap().push("armed-ENTRY"); q_handled!() }
+        Q_EXIT_SIG_VAL => { sm.log.lock().unwrap().push("armed-EXIT"); q_handled!() }
+        FORCE_SIG => q_tran!(alarm),
+        _ => q_super!(locked),
+    }
+}
+
+fn alarm(sm: &mut Lock, e: &DynEvent) -> QHsmResult<Lock> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => { sm.log.lock().unwrap().push("alarm-ENTRY"); q_handled!() }
+        Q_EXIT_SIG_VAL => { sm.log.lock().unwrap().push("alarm-EXIT"); q_handled!() }
+        _ => q_super!(locked),
+    }
+}
+
+fn light_initial(_sm: &mut Light, _e: &DynEvent) -> QHsmResult<Light> {
+    q_tran!(off)
+}
+
+fn off(sm: &mut Light, e: &DynEvent) -> QHsmResult<Light> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => { sm.log.lock().unwrap().push("off-ENTRY"); q_handled!() }
+        Q_EXIT_SIG_VAL => { sm.log.lock().unwrap().push("off-EXIT"); q_handled!() }
+        SWITCH_SIG | FORCE_SIG => q_tran!(on),
+        _ => q_super!(QHsm::<Light>::top_state),
+    }
+}
+
+fn on(sm: &mut Light, e: &DynEvent) -> QHsmResult<Light> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => { sm.log.lock().unwrap().push("on-ENTRY"); q_handled!() }
+        Q_EXIT_SIG_VAL => { sm.log.lock().unwrap().push("on-EXIT"); q_handled!() }
+        SWITCH_SIG => q_tran!(off),
+        _ => q_super!(QHsm::<Light>::top_state),
+    }
+}
+
+fn door() -> (Regions, Log) {
+    let log = Log::default();
+    let regions = Regions::new()
+        .with_region(QHsm::new(Lock { log: Arc::clone(&log), knocks: 0 }, lock_initial))
+        .with_region(QHsm::new(Light { log: Arc::clone(&log) }, light_initial));
+    (regions, log)
+}
+
+fn dispatch(regions: &mut Regions, sig: u16) -> Outcome {
+    regions.dispatch(&DynEvent::empty_dyn(Signal(sig)))
+}
+
+fn take(log: &Log) -> Vec<&'static str> {
+    std::mem::take(&mut *log.lock().unwrap())
+}
+
+#[test]
+fn regions_start_in_declaration_order() {
+    let (mut regions, log) = door();
+    regions.init();
+    assert_eq!(take(&log), ["unlocked-ENTRY", "off-ENTRY"]);
+}
+
+#[test]
+fn every_region_sees_the_event_and_the_outcomes_combine() {
+    let (mut regions, log) = door();
+    regions.init();
+    take(&log);
+
+    assert_eq!(dispatch(&mut regions, LOCK_SIG), Outcome::Transitioned);
+    assert_eq!(take(&log), ["unlocked-EXIT", "locked-ENTRY", "armed-ENTRY"]);
+
+    assert_eq!(dispatch(&mut regions, KNOCK_SIG), Outcome::Handled);
+    assert_eq!(regions.region::<Lock>(0).unwrap().sm().knocks, 1);
+
+    assert_eq!(dispatch(&mut regions, NOISE_SIG), Outcome::Ignored);
+
+    // Both regions react to FORCE; the lock region goes first.
+    assert_eq!(dispatch(&mut regions, FORCE_SIG), Outcome::Transitioned);
+    assert_eq!(take(&log), ["armed-EXIT", "alarm-ENTRY", "off-EXIT", "on-ENTRY"]);
+}
+
+#[test]
+fn regions_exit_in_reverse_order_innermost_first() {
+    let (mut regions, log) = door();
+    regions.init();
+    dispatch(&mut regions, LOCK_SIG);
+    dispatch(&mut regions, SWITCH_SIG);
+    take(&log);
+
+    regions.exit();
+    assert_eq!(take(&log), ["on-EXIT", "armed-EXIT", "locked-EXIT"]);
+    let lock = regions.region::<Lock>(0).unwrap();
+    assert!(lock.state_handler().same_state(QHsm::<Lock>::top_state as StateHandler<Lock>));
+
+    regions.init();
+    assert_eq!(take(&log), ["unlocked-ENTRY", "off-ENTRY"]);
+}
+
+#[test]
+fn regions_are_reached_by_index_and_type() {
+    let (mut regions, _log) = door();
+    regions.init();
+    assert_eq!(regions.len(), 2);
+    assert!(regions.region::<Light>(0).is_none(), "region 0 is the lock");
+    assert!(regions.region::<Light>(2).is_none());
+    regions.region_mut::<Lock>(0).unwrap().sm_mut().knocks = 5;
+    assert_eq!(regions.region::<Lock>(0).unwrap().sm().knocks, 5);
+}
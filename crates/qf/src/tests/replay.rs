@@ -0,0 +1,247 @@
+use std::cell::Cell;
+use std::sync::{Arc, Mutex};
+
+use crate::active::{new_active_object, ActiveBehavior, ActiveContext};
+use crate::event::{DynEvent, DynPayload};
+use crate::kernel::{Kernel, KernelError};
+use crate::port::Runtime;
+use crate::replay::{
+    start_recording_with_clock, stop_recording, PayloadCodec, PostSource, ReplayEntry, ReplayLog,
+};
+use crate::time::{new_time_event, share_kernel, TimeEventConfig, TimerWheel};
+use crate::{ActiveObjectId, Signal};
+
+const SAMPLE: Signal = Signal(1);
+const SUM: Signal = Signal(2);
+const TIMEOUT: Signal = Signal(3);
+const SENSOR: ActiveObjectId = ActiveObjectId::new(1);
+const DISPLAY: ActiveObjectId = ActiveObjectId::new(2);
+
+thread_local! {
+    static TICKS: Cell<u32> = const { Cell::new(0) };
+}
+
+fn ticks() -> u32 {
+    TICKS.with(Cell::get)
+}
+
+/// Snapshots `u32` sample payloads.
+struct Samples;
+
+impl PayloadCodec for Samples {
+    fn encode(&self, signal: Signal, payload: &DynPayload) -> Option<Vec<u8>> {
+        (signal == SAMPLE).then(|| {
+            payload
+                .downcast_ref::<u32>()
+                .unwrap()
+                .to_le_bytes()
+                .to_vec()
+        })
+    }
+
+    fn decode(&self, signal: Signal, snapshot: &[u8]) -> DynPayload {
+        if signal == SAMPLE {
+            Arc::new(u32::from_le_bytes(snapshot.try_into().unwrap()))
+        } else {
+            Arc::new(())
+        }
+    }
+}
+
+/// Adds up samples and passes the running sum to the display; on a timeout
+/// it starts over.
+struct Sensor {
+    sum: u32,
+}
+
+impl ActiveBehavior for Sensor {
+    fn on_start(&mut self, _ctx: &mut ActiveContext) {}
+
+    fn on_event(&mut self, ctx: &mut ActiveContext, event: DynEvent) {
+        match event.header.signal {
+            SAMPLE => {
+                self.sum += *event.payload.downcast_ref::<u32>().unwrap();
+                ctx.post(
+                    DISPLAY,
+                    DynEvent::new(SUM, Arc::new(self.sum) as DynPayload),
+                )
+                .unwrap();
+            }
+            TIMEOUT => self.sum = 0,
+            _ => {}
+        }
+    }
+}
+
+/// Remembers the sums it was shown, with the tick they arrived on.
+struct Display {
+    shown: Arc<Mutex<Vec<(u32, u32)>>>,
+}
+
+impl ActiveBehavior for Display {
+    fn on_start(&mut self, _ctx: &mut ActiveContext) {}
+
+    fn on_event(&mut self, _ctx: &mut ActiveContext, event: DynEvent) {
+        let sum = *event.payload.downcast_ref::<u32>().unwrap();
+        self.shown.lock().unwrap().push((ticks(), sum));
+    }
+}
+
+/// The application on a kernel and wheel of its own, ticked by the test.
+struct App {
+    kernel: Arc<Kernel>,
+    wheel: TimerWheel,
+    shown: Arc<Mutex<Vec<(u32, u32)>>>,
+}
+
+impl App {
+    fn new() -> Self {
+        let shown = Arc::new(Mutex::new(Vec::new()));
+        let kernel = share_kernel(
+            Kernel::builder()
+                .register(new_active_object(SENSOR, 1, Sensor { sum: 0 }))
+                .register(new_active_object(
+                    DISPLAY,
+                    2,
+                    Display {
+                        shown: Arc::clone(&shown),
+                    },
+                ))
+                .build(),
+        );
+        kernel.start();
+        let mut wheel = TimerWheel::new(Arc::clone(&kernel));
+        let timeout = new_time_event(SENSOR, TimeEventConfig::new(TIMEOUT));
+        timeout.arm_periodic(4u64, 4u64);
+        wheel.register(timeout);
+        Self {
+            kernel,
+            wheel,
+            shown,
+        }
+    }
+
+    fn sample(&self, value: u32) {
+        self.kernel
+            .post(SENSOR, DynEvent::new(SAMPLE, Arc::new(value) as DynPayload))
+            .unwrap();
+        self.kernel.run_until_idle();
+    }
+}
+
+impl Runtime for App {
+    type TickError = KernelError;
+
+    fn tick(&self) -> Result<(), KernelError> {
+        TICKS.with(|t| t.set(t.get() + 1));
+        self.wheel
+            .tick()
+            .map_err(|_| KernelError::NotFound(SENSOR))?;
+        self.kernel.run_until_idle();
+        Ok(())
+    }
+
+    fn run_until_idle(&self) {
+        self.kernel.run_until_idle();
+    }
+
+    fn has_pending_work(&self) -> bool {
+        self.kernel.has_pending_work()
+    }
+}
+
+fn record_session() -> (ReplayLog, Vec<(u32, u32)>) {
+    TICKS.with(|t| t.set(0));
+    let app = App::new();
+    start_recording_with_clock(Arc::new(Samples), ticks);
+    app.sample(5);
+    app.tick().unwrap();
+    app.sample(7);
+    for _ in 0..3 {
+        app.tick().unwrap();
+    }
+    app.sample(2);
+    app.tick().unwrap();
+    let log = stop_recording().unwrap();
+    let shown = app.shown.lock().unwrap().clone();
+    (log, shown)
+}
+
+#[test]
+fn recording_logs_every_post_with_its_origin() {
+    let (log, shown) = record_session();
+    assert_eq!(shown, [(0, 5), (1, 12), (4, 2)]);
+    assert_eq!((log.start_tick, log.end_tick), (0, 5));
+
+    let origins: Vec<(u32, ActiveObjectId, PostSource)> = log
+        .entries
+        .iter()
+        .map(|e| (e.tick, e.target, e.source))
+        .collect();
+    assert_eq!(
+        origins,
+        [
+            (0, SENSOR, PostSource::External),
+            (0, DISPLAY, PostSource::Ao(SENSOR)),
+            (1, SENSOR, PostSource::External),
+            (1, DISPLAY, PostSource::Ao(SENSOR)),
+            (4, SENSOR, PostSource::TimeEvent),
+            (4, SENSOR, PostSource::External),
+            (4, DISPLAY, PostSource::Ao(SENSOR)),
+        ]
+    );
+    assert_eq!(log.entries[2].payload, 7u32.to_le_bytes());
+    assert!(
+        log.entries[1].payload.is_empty(),
+        "the codec keeps sample payloads only"
+    );
+    assert!(stop_recording().is_none());
+}
+
+#[test]
+fn replay_reproduces_the_session_in_a_fresh_kernel() {
+    let (log, shown) = record_session();
+
+    TICKS.with(|t| t.set(0));
+    let app = App::new();
+    let summary = log.replay(&*app.kernel, &app, &Samples).unwrap();
+    assert_eq!((summary.posted, summary.skipped, summary.ticks), (3, 4, 5));
+    assert_eq!(*app.shown.lock().unwrap(), shown);
+}
+
+#[test]
+fn entries_round_trip() {
+    assert_eq!(
+        ReplayEntry::decode(&sample_entry().encode()).as_ref(),
+        Some(&sample_entry())
+    );
+    assert_eq!(ReplayEntry::decode(&sample_entry().encode()[..11]), None);
+}
+
+fn sample_entry() -> ReplayEntry {
+    ReplayEntry {
+        tick: 0x0102_0304,
+        target: DISPLAY,
+        signal: Signal(0x7E7D),
+        source: PostSource::Ao(SENSOR),
+        lifo: true,
+        payload: vec![0x7E, 0x00, 0x7D],
+    }
+}
+
+#[cfg(feature = "qs")]
+#[test]
+fn entries_survive_a_qs_capture() {
+    let entry = sample_entry();
+    let mut capture = Vec::new();
+    for (seq, record) in [(1u8, 200u8), (2, crate::replay::QS_EVT_LOG)].into_iter() {
+        let mut body = vec![seq, record];
+        body.extend_from_slice(&entry.encode());
+        let mut buf = [0u8; 64];
+        capture.extend_from_slice(qs::hdlc::encode_into(&mut buf, &[&body]).unwrap());
+    }
+
+    let log = ReplayLog::from_capture(&capture);
+    assert_eq!(log.entries, [entry]);
+    assert_eq!((log.start_tick, log.end_tick), (0x0102_0304, 0x0102_0304));
+}
@@ -0,0 +1,85 @@
+use crate::active::{new_active_object, ActiveContext, SignalHandler};
+use crate::kernel::Kernel;
+use crate::schedulability::{PriorityPlan, ScheduleWarning, TaskTiming};
+use crate::time::TickDuration;
+use crate::{ActiveObjectId, Signal};
+
+struct Idle;
+
+impl SignalHandler for Idle {
+    fn on_start(&mut self, _ctx: &mut ActiveContext) {}
+
+    fn handle_signal(&mut self, _signal: Signal, _ctx: &mut ActiveContext) {}
+}
+
+fn task(id: u8, period: u64, wcet: u64) -> TaskTiming {
+    TaskTiming::new(ActiveObjectId::new(id), TickDuration::from_ticks(period), TickDuration::from_ticks(wcet))
+}
+
+#[test]
+fn shorter_deadline_gets_higher_priority() {
+    let tasks = [
+        task(1, 10, 1),
+        task(2, 20, 2).with_deadline(TickDuration::from_ticks(5)),
+        task(3, 8, 1),
+    ];
+    let plan = PriorityPlan::deadline_monotonic(&tasks);
+    let order: Vec<_> = plan.assignments().collect();
+    assert_eq!(
+        order,
+        [(ActiveObjectId::new(2), 3), (ActiveObjectId::new(3), 2), (ActiveObjectId::new(1), 1)]
+    );
+    assert!(plan.is_schedulable());
+    assert!(plan.warnings().is_empty());
+}
+
+#[test]
+fn response_time_analysis_decides_above_the_bound() {
+    // U = 1/4 + 2/6 + 3/12 ≈ 83.4 %, above the 3-task bound of 77.9 %, yet
+    // the lowest-priority task still responds within 10 of its 12 ticks.
+    let plan = PriorityPlan::deadline_monotonic(&[task(1, 4, 1), task(2, 6, 2), task(3, 12, 3)]);
+    assert_eq!(plan.utilization_permille(), 834);
+    assert_eq!(
+        plan.warnings(),
+        [ScheduleWarning::AboveBound { utilization_permille: 834, bound_permille: 779 }]
+    );
+    assert_eq!(plan.response_time(ActiveObjectId::new(3)), Some(TickDuration::from_ticks(10)));
+    assert!(plan.is_schedulable());
+}
+
+#[test]
+fn overload_reports_the_missed_deadline() {
+    let plan = PriorityPlan::deadline_monotonic(&[task(1, 2, 1), task(2, 3, 2), task(3, 0, 1)]);
+    assert!(!plan.is_schedulable());
+    assert_eq!(
+        plan.warnings(),
+        [
+            ScheduleWarning::InvalidTiming(ActiveObjectId::new(3)),
+            ScheduleWarning::Overloaded { utilization_permille: 1167 },
+            ScheduleWarning::DeadlineMiss {
+                id: ActiveObjectId::new(2),
+                deadline: TickDuration::from_ticks(3),
+            },
+        ]
+    );
+    assert_eq!(plan.priority_of(ActiveObjectId::new(3)), None);
+}
+
+#[test]
+fn kernel_builder_flags_priorities_out_of_order() {
+    let builder = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(1), 2, Idle))
+        .register(new_active_object(ActiveObjectId::new(2), 1, Idle));
+    let in_order = builder.deadline_monotonic(&[task(1, 5, 1), task(2, 10, 1)]);
+    assert!(in_order.warnings().is_empty());
+
+    let plan = builder.deadline_monotonic(&[task(1, 10, 1), task(2, 5, 1)]);
+    assert_eq!(
+        plan.warnings(),
+        [
+            ScheduleWarning::PriorityOrder { id: ActiveObjectId::new(1), registered: 2, suggested: 1 },
+            ScheduleWarning::PriorityOrder { id: ActiveObjectId::new(2), registered: 1, suggested: 2 },
+        ]
+    );
+    assert!(plan.is_schedulable());
+}
@@ -0,0 +1,154 @@
+use crate::event::Signal;
+use crate::signals::{SignalBlock, SignalNamespace, SignalRange, SignalSpace};
+
+const TABLE: SignalBlock = SignalBlock::new("Table", 4, &["EAT", "DONE"]);
+const PHILO: SignalBlock = SignalBlock::after(&TABLE, "Philo", &["TIMEOUT", "HUNGRY"]);
+const LOGGER: SignalBlock = SignalBlock::new("Logger", 100, &["FLUSH"]);
+const APP: SignalNamespace<3> = SignalNamespace::new([TABLE, PHILO, LOGGER]);
+
+const SPACE: SignalSpace = SignalSpace::new(8);
+const SHARED: SignalBlock = SPACE.published_block("Shared", &["EAT", "DONE"]);
+const MORE: SignalBlock = SPACE.block_after(&SHARED, "More", &["PAUSE", "SERVE"]);
+const OWN: SignalBlock = SPACE.private_block("Own", &["TIMEOUT"]);
+
+#[test]
+fn blocks_number_their_signals_from_the_base() {
+    assert_eq!(TABLE.signal(1), Signal(5));
+    assert_eq!(PHILO.base(), 6);
+    assert_eq!(PHILO.signal(0), Signal(6));
+    assert_eq!(PHILO.index_of(Signal(7)), Some(1));
+    assert_eq!(PHILO.index_of(Signal(5)), None);
+    assert_eq!(APP.end(), 101);
+}
+
+#[test]
+fn namespace_resolves_signals_to_their_block() {
+    assert_eq!(APP.name_of(Signal(7)), Some(("Philo", "HUNGRY")));
+    assert_eq!(APP.name_of(Signal(100)), Some(("Logger", "FLUSH")));
+    assert_eq!(APP.block_of(Signal(50)), None);
+    assert_eq!(APP.name_of(Signal(1)), None);
+}
+
+#[test]
+#[should_panic(expected = "signal blocks overlap")]
+fn overlapping_blocks_are_rejected() {
+    let _ = SignalNamespace::new([TABLE, SignalBlock::new("Late", 5, &["X"])]);
+}
+
+#[test]
+#[should_panic(expected = "reserved framework signals")]
+fn blocks_stay_clear_of_reserved_signals() {
+    let _ = SignalBlock::new("Early", 3, &["X"]);
+}
+
+#[test]
+fn space_partitions_the_signal_range() {
+    assert_eq!(SignalSpace::RESERVED, 0..4);
+    assert_eq!(SPACE.published(), 4..8);
+    assert_eq!(SPACE.private(), 8..=u16::MAX);
+    assert_eq!(SPACE.range_of(Signal(3)), SignalRange::Reserved);
+    assert_eq!(SPACE.range_of(MORE.signal(1)), SignalRange::Published);
+    assert_eq!(SPACE.range_of(OWN.signal(0)), SignalRange::Private);
+    assert!(SPACE.is_publishable(SHARED.signal(0)));
+    assert!(!SPACE.is_publishable(OWN.signal(0)));
+    let _ = SignalNamespace::new([SHARED, MORE, OWN]);
+}
+
+#[test]
+#[should_panic(expected = "runs past MAX_PUB_SIG")]
+fn published_blocks_stay_below_max_pub_sig() {
+    let _ = SPACE.block_after(&SHARED, "Wide", &["A", "B", "C"]);
+}
+
+#[test]
+#[should_panic(expected = "reserved framework signals")]
+fn max_pub_sig_stays_clear_of_reserved_signals() {
+    let _ = SignalSpace::new(2);
+}
+
+#[test]
+fn blocks_after_max_pub_sig_are_private() {
+    let next = SPACE.block_after(&OWN, "Next", &["X"]);
+    assert_eq!(SPACE.range_of(next.signal(0)), SignalRange::Private);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "collides with a reserved framework signal")]
+fn dispatching_a_reserved_signal_is_caught_in_debug_builds() {
+    use crate::event::DynEvent;
+    use crate::hsm::{QHsm, QHsmResult};
+
+    fn only(_sm: &mut (), _e: &DynEvent) -> QHsmResult<()> {
+        QHsmResult::Super(QHsm::<()>::top_state)
+    }
+    fn initial(_sm: &mut (), _e: &DynEvent) -> QHsmResult<()> {
+        QHsmResult::Tran(only)
+    }
+
+    let mut hsm = QHsm::new((), initial);
+    hsm.init();
+    hsm.dispatch(&DynEvent::empty_dyn(Signal(1)));
+}
+
+#[test]
+fn empty_blocks_never_collide() {
+    let none = SignalBlock::new("None", 5, &[]);
+    assert!(none.is_empty());
+    let _ = SignalNamespace::new([TABLE, none]);
+}
+
+#[cfg(feature = "qs")]
+#[test]
+fn dictionaries_name_signals_by_block() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::TraceHook;
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&records);
+    let hook: TraceHook = Arc::new(move |record, payload: &[u8], _| {
+        sink.lock().unwrap().push((record, payload.to_vec()));
+        Ok(())
+    });
+    APP.emit_dictionaries(&hook).unwrap();
+
+    let records = records.lock().unwrap();
+    assert_eq!(records.len(), 5);
+    let (record, payload) = &records[3];
+    assert_eq!(*record, qs::predefined::SIG_DICT);
+    let ptr = core::mem::size_of::<usize>();
+    assert_eq!(payload[..2], 7u16.to_le_bytes());
+    assert_eq!(&payload[2 + ptr..], b"Philo::HUNGRY\0");
+}
+
+#[derive(Debug, PartialEq)]
+struct Order {
+    seat: u8,
+}
+
+crate::typed_signals! {
+    ORDER: Order = 20;
+    /// No payload.
+    CLOSE = 21;
+}
+
+#[test]
+fn typed_signals_build_and_read_their_own_events() {
+    let event = ORDER.event(Order { seat: 3 });
+    assert_eq!(event.signal(), Signal(20));
+    assert_eq!(ORDER.payload(&event), Some(&Order { seat: 3 }));
+
+    let close = CLOSE.event(());
+    assert!(CLOSE.matches(&close));
+    assert_eq!(ORDER.payload(&close), None, "another signal is not an order");
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "carries a payload other than")]
+fn a_mismatched_payload_is_caught_in_debug_builds() {
+    use crate::event::DynEvent;
+
+    let _ = ORDER.payload(&DynEvent::with_payload(ORDER.signal(), 3u8));
+}
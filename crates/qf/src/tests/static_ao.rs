@@ -0,0 +1,89 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::active::{ActiveContext, ActiveRunnable, SignalHandler};
+use crate::event::{DynEvent, Signal};
+use crate::kernel::Kernel;
+use crate::ActiveObjectId;
+
+#[derive(Default)]
+struct Recorder {
+    signals: Vec<Signal>,
+}
+
+impl SignalHandler for Recorder {
+    fn handle_signal(&mut self, signal: Signal, _ctx: &mut ActiveContext) {
+        self.signals.push(signal);
+    }
+}
+
+static BUILT: AtomicUsize = AtomicUsize::new(0);
+
+fn counted() -> Recorder {
+    BUILT.fetch_add(1, Ordering::SeqCst);
+    Recorder::default()
+}
+
+crate::q_active! {
+    static LOW: Recorder = Recorder::default(), id = 1, priority = 1;
+    /// Built by `counted`.
+    static HIGH: Recorder = counted(), id = 2, priority = 3;
+}
+
+#[test]
+fn declared_active_objects_register_and_receive_events() {
+    assert_eq!((LOW.name(), LOW.id(), LOW.priority()), ("LOW", ActiveObjectId::new(1), 1));
+
+    let kernel = Kernel::builder().register(LOW.handle()).register(HIGH.handle()).build();
+    kernel.start();
+    kernel.post(ActiveObjectId::new(1), DynEvent::empty_dyn(Signal(10))).unwrap();
+    kernel.post(ActiveObjectId::new(2), DynEvent::empty_dyn(Signal(20))).unwrap();
+    kernel.run_until_idle();
+
+    assert_eq!(LOW.get().with_behavior(|b| b.signals.clone()), [Signal(10)]);
+    assert_eq!(HIGH.get().with_behavior(|b| b.signals.clone()), [Signal(20)]);
+    assert_eq!(HIGH.handle().priority(), 3);
+}
+
+#[test]
+fn the_behavior_is_built_once_on_first_use() {
+    crate::q_active! {
+        static LAZY: Recorder = counted(), id = 9, priority = 2;
+    }
+    let before = BUILT.load(Ordering::SeqCst);
+    assert!(!LAZY.is_initialized());
+
+    let first = LAZY.handle();
+    let second = LAZY.handle();
+    assert!(LAZY.is_initialized());
+    assert!(BUILT.load(Ordering::SeqCst) > before);
+    // Works for both handle types: `Arc` and, under `static-alloc`, `&'static`.
+    fn addr(h: &impl core::ops::Deref<Target = dyn ActiveRunnable>) -> *const () {
+        &**h as *const dyn ActiveRunnable as *const ()
+    }
+    assert_eq!(addr(&first), addr(&second));
+    assert_eq!(addr(&first), LAZY.get() as *const _ as *const ());
+}
+
+#[cfg(feature = "qs")]
+#[test]
+fn qs_ao_names_each_active_object() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::TraceHook;
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&records);
+    let hook: TraceHook = Arc::new(move |record, payload: &[u8], _| {
+        sink.lock().unwrap().push((record, payload.to_vec()));
+        Ok(())
+    });
+    crate::qs_ao!(&hook; LOW, HIGH).unwrap();
+
+    let records = records.lock().unwrap();
+    assert_eq!(records.len(), 2);
+    let ptr = core::mem::size_of::<usize>();
+    let (record, payload) = &records[1];
+    assert_eq!(*record, qs::predefined::OBJ_DICT);
+    assert_eq!(payload[..ptr], (HIGH.get() as *const _ as usize).to_le_bytes());
+    assert_eq!(&payload[ptr..], b"HIGH\0");
+}
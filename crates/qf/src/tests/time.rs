@@ -7,7 +7,9 @@ use std::sync::{Arc, Mutex};
 
 use crate::active::{new_active_object, ActiveContext, SignalHandler};
 use crate::kernel::Kernel;
-use crate::time::{new_time_event, share_kernel, TimeEventConfig, TimerWheel};
+use crate::time::{
+    new_time_event, share_kernel, TickDuration, TickInstant, TimeEventConfig, TimerWheel,
+};
 use crate::{ActiveObjectId, Signal};
 
 #[derive(Clone, Default)]
@@ -94,3 +96,207 @@ fn multi_tick_rate_domains() {
 
     assert!(wheel.no_active(1));
 }
+
+#[test]
+fn tick_duration_rounds_conversions_up() {
+    assert_eq!(TickDuration::from_millis(10, 100), Some(TickDuration::from_ticks(1)));
+    assert_eq!(TickDuration::from_millis(11, 100), Some(TickDuration::from_ticks(2)));
+    assert_eq!(TickDuration::from_micros(1, 1_000), Some(TickDuration::from_ticks(1)));
+    assert_eq!(
+        TickDuration::from_duration(std::time::Duration::from_millis(250), 1_000),
+        Some(TickDuration::from_ticks(250))
+    );
+    assert_eq!(TickDuration::from_millis(5, 0), None);
+    assert_eq!(TickDuration::from_ticks(3).as_millis(100), Some(30));
+}
+
+#[test]
+fn tick_duration_arithmetic_is_checked() {
+    let max = TickDuration::from_ticks(u64::MAX);
+    assert_eq!(max.checked_add(TickDuration::from_ticks(1)), None);
+    assert_eq!(TickDuration::ZERO.checked_sub(TickDuration::from_ticks(1)), None);
+    assert_eq!(TickDuration::ZERO.saturating_sub(TickDuration::from_ticks(1)), TickDuration::ZERO);
+    assert_eq!(max.checked_mul(2), None);
+    assert_eq!(TickDuration::from_ticks(2) + TickDuration::from_ticks(3), TickDuration::from_ticks(5));
+}
+
+#[test]
+fn tick_instant_spans_counter_wrap() {
+    let before = TickInstant::from_ticks(u32::MAX - 1);
+    let after = before.checked_add(TickDuration::from_ticks(4)).unwrap();
+    assert_eq!(after.ticks(), 2);
+    assert_eq!(after - before, TickDuration::from_ticks(4));
+    assert_eq!(before.checked_add(TickDuration::from_ticks(1 << 32)), None);
+}
+
+#[cfg(not(feature = "static-alloc"))]
+#[test]
+fn wheel_traces_ticks_and_time_event_changes() {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let hook: crate::TraceHook = Arc::new(move |record, payload: &[u8], _ts| {
+        sink.lock().unwrap().push((record, payload.to_vec()));
+        Ok(())
+    });
+    let ao = new_active_object(ActiveObjectId::new(3), 1, Collector::default());
+    let kernel = share_kernel(Kernel::builder().register(ao).with_trace_hook(hook).build());
+    kernel.start();
+
+    let mut wheel = TimerWheel::new(kernel.clone());
+    let time_evt = new_time_event(ActiveObjectId::new(3), TimeEventConfig::new(Signal(0x10)));
+    wheel.register(time_evt.clone());
+    time_evt.arm(2, None);
+    wheel.tick().unwrap();
+    wheel.tick().unwrap();
+    assert_eq!(wheel.tick_count(0), 2);
+
+    let records = records.lock().unwrap();
+    let timer_records: Vec<_> = records.iter().filter(|(r, _)| (31..=37).contains(r)).collect();
+    let ids: Vec<u8> = timer_records.iter().map(|(r, _)| *r).collect();
+    assert_eq!(ids, [32, 31, 31, 33, 37]);
+
+    // Without trace metadata the target is identified by its id.
+    let ptr = core::mem::size_of::<usize>();
+    let arm = &timer_records[0].1;
+    assert_eq!(arm[ptr], 3);
+    assert_eq!(arm[2 * ptr..], [2, 0, 0, 0, 0]);
+    assert_eq!(timer_records[1].1, [1, 0, 0]);
+    assert_eq!(timer_records[2].1, [2, 0, 0]);
+}
+
+#[cfg(feature = "qs")]
+#[test]
+fn time_event_answers_host_queries() {
+    use qs::query::{ObjKind, Queryable, Snapshot};
+
+    let time_evt = new_time_event(
+        ActiveObjectId::new(4),
+        TimeEventConfig::new(Signal(0x10)).with_period(TickDuration::from_ticks(5)),
+    );
+    time_evt.arm(3, Some(TickDuration::from_ticks(5)));
+    assert_eq!(
+        time_evt.query(ObjKind::Te),
+        Some(Snapshot::Te { ao: 4, ctr: 3, interval: 5, signal: 0x10, flags: 1 })
+    );
+    time_evt.disarm();
+    assert_eq!(
+        time_evt.query(ObjKind::Te),
+        Some(Snapshot::Te { ao: 4, ctr: 0, interval: 5, signal: 0x10, flags: 0 })
+    );
+    assert_eq!(time_evt.query(ObjKind::Ao), None);
+}
+
+#[cfg(all(feature = "qs", not(feature = "static-alloc")))]
+#[test]
+fn kernel_ticks_timestamp_source_follows_the_wheel() {
+    use qs::TimestampSource;
+
+    let kernel = share_kernel(Kernel::builder().build());
+    let wheel = TimerWheel::new(kernel);
+    let before = crate::time::KernelTicks.now();
+    wheel.tick().unwrap();
+    wheel.tick().unwrap();
+    assert!(crate::time::KernelTicks.now().wrapping_sub(before) >= 2);
+}
+
+#[test]
+fn one_shot_and_periodic_arming() {
+    let once = new_time_event(ActiveObjectId::new(1), TimeEventConfig::new(Signal(0x10)));
+    once.arm_once(2);
+    assert!(once.poll().is_none());
+    assert!(once.poll().is_some());
+    assert!(!once.is_armed());
+    assert!(once.was_disarmed());
+    assert!(once.poll().is_none());
+
+    let periodic = new_time_event(ActiveObjectId::new(1), TimeEventConfig::new(Signal(0x11)));
+    periodic.arm_periodic(1, 3);
+    let fired: Vec<bool> = (0..7).map(|_| periodic.poll().is_some()).collect();
+    assert_eq!(fired, [true, false, false, true, false, false, true]);
+    assert!(periodic.is_armed());
+
+    // A zero interval is a one-shot, as in QP.
+    let zero = new_time_event(ActiveObjectId::new(1), TimeEventConfig::new(Signal(0x12)));
+    zero.arm_periodic(1, 0);
+    assert!(zero.poll().is_some());
+    assert!(!zero.is_armed());
+}
+
+#[test]
+fn disarm_reports_remaining_ticks_and_rearm_reports_running() {
+    let time_evt = new_time_event(ActiveObjectId::new(1), TimeEventConfig::new(Signal(0x10)));
+    assert!(!time_evt.rearm(5));
+    time_evt.poll();
+    time_evt.poll();
+    assert!(time_evt.rearm(4));
+    time_evt.poll();
+    assert_eq!(time_evt.disarm(), Some(TickDuration::from_ticks(3)));
+    assert_eq!(time_evt.disarm(), None);
+
+    time_evt.arm_once(1);
+    time_evt.poll();
+    assert_eq!(time_evt.disarm(), None, "a fired one-shot is already disarmed");
+}
+
+#[test]
+fn jitter_measures_how_late_timeouts_are_handled() {
+    // An id and signal of their own: expiries are matched to their handling
+    // across the whole process.
+    let ao = new_active_object(ActiveObjectId::new(48), 1, Collector::default());
+    let kernel = share_kernel(Kernel::builder().register(ao).build());
+    kernel.start();
+
+    let mut wheel = TimerWheel::new(kernel.clone());
+    let time_evt = new_time_event(ActiveObjectId::new(48), TimeEventConfig::new(Signal(0x4801)));
+    wheel.register(time_evt.clone());
+    time_evt.arm_periodic(1, 1);
+
+    wheel.tick().unwrap();
+    kernel.run_until_idle();
+    assert_eq!(time_evt.jitter().samples, 1);
+
+    // The AO falls behind: the second expiry is late, the third overlaps it.
+    wheel.tick().unwrap();
+    wheel.tick().unwrap();
+    kernel.run_until_idle();
+    let stats = time_evt.jitter();
+    assert_eq!((stats.samples, stats.overlaps), (2, 1));
+    // Other tests tick the shared clock too, so lateness is a lower bound.
+    assert!(stats.max >= 1 && stats.last == stats.max);
+    assert!(stats.mean().is_some_and(|mean| mean <= stats.max));
+
+    time_evt.reset_jitter();
+    assert_eq!(time_evt.jitter(), crate::JitterStats::default());
+}
+
+#[cfg(all(feature = "qs", not(feature = "static-alloc")))]
+#[test]
+fn wheel_reports_jitter_periodically() {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    let hook: crate::TraceHook = Arc::new(move |record, payload: &[u8], _ts| {
+        sink.lock().unwrap().push((record, payload.to_vec()));
+        Ok(())
+    });
+    let ao = new_active_object(ActiveObjectId::new(49), 1, Collector::default());
+    let kernel = share_kernel(Kernel::builder().register(ao).with_trace_hook(hook).build());
+    kernel.start();
+
+    let mut wheel = TimerWheel::new(kernel.clone());
+    let time_evt = new_time_event(ActiveObjectId::new(49), TimeEventConfig::new(Signal(0x4901)));
+    wheel.register(time_evt.clone());
+    wheel.report_jitter_every(1);
+    time_evt.arm_periodic(1, 1);
+    wheel.tick().unwrap();
+    kernel.run_until_idle();
+    wheel.tick().unwrap();
+
+    let jitter = qs::records::qf::TIMEEVT_JITTER;
+    let reports: Vec<_> =
+        records.lock().unwrap().iter().filter(|(r, _)| *r == jitter).map(|(_, p)| p.clone()).collect();
+    assert_eq!(reports.len(), 1);
+    // te | ao | samples: u16 | ...
+    let ptr = core::mem::size_of::<usize>();
+    assert_eq!(reports[0][ptr], 49);
+    assert_eq!(reports[0][2 * ptr..2 * ptr + 2], [1, 0]);
+}
@@ -0,0 +1,170 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+use crate::active::{new_active_object, ActiveContext, SignalHandler};
+use crate::event::{DynEvent, Signal};
+use crate::kernel::Kernel;
+use crate::watchdog::{IdleFeeder, Stall};
+use crate::ActiveObjectId;
+
+static STALLS: Mutex<Vec<Stall>> = Mutex::new(Vec::new());
+
+fn log_stall(stall: &Stall) {
+    STALLS.lock().unwrap().push(*stall);
+}
+
+struct Idle;
+
+impl SignalHandler for Idle {
+    fn handle_signal(&mut self, _signal: Signal, _ctx: &mut ActiveContext) {}
+}
+
+fn post(kernel: &Kernel, target: u8, signals: &[u16]) {
+    for &sig in signals {
+        kernel.post(ActiveObjectId::new(target), DynEvent::empty_dyn(Signal(sig))).unwrap();
+    }
+}
+
+/// Ticks `kernel`'s clock as its timer wheel would.
+fn advance(kernel: &Kernel, ticks: u32) {
+    for _ in 0..ticks {
+        kernel.clock().advance();
+    }
+}
+
+#[test]
+fn an_object_starved_of_dispatch_is_reported_once() {
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(1), 1, Idle))
+        .register(new_active_object(ActiveObjectId::new(2), 2, Idle))
+        .with_watchdog(3)
+        .on_stall(log_stall)
+        .build();
+    kernel.start();
+
+    // The ceiling keeps priority 1 from running; priority 2 drains.
+    kernel.lock_scheduler(1);
+    post(&kernel, 1, &[7, 8]);
+    post(&kernel, 2, &[9]);
+    kernel.run_until_idle();
+    assert_eq!(kernel.check_watchdog(), 0, "the first check only starts the clock");
+
+    advance(&kernel, 5);
+    assert_eq!(kernel.check_watchdog(), 1);
+    assert_eq!(kernel.check_watchdog(), 0, "one report per stall");
+    let stall = STALLS.lock().unwrap().iter().copied().find(|s| s.priority == 1).unwrap();
+    assert_eq!((stall.signal, stall.queued, stall.max_queued), (Signal(7), 2, 2));
+    assert!(stall.stale >= 5);
+
+    // Progress clears the stall.
+    kernel.unlock_scheduler();
+    kernel.run_until_idle();
+    post(&kernel, 1, &[7]);
+    kernel.check_watchdog();
+    assert_eq!(kernel.watchdog().stalls(), 1);
+}
+
+#[test]
+fn idle_objects_are_never_stale() {
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(3), 3, Idle))
+        .with_watchdog(1)
+        .build();
+    kernel.start();
+
+    kernel.check_watchdog();
+    advance(&kernel, 4);
+    post(&kernel, 3, &[1]);
+    assert_eq!(kernel.check_watchdog(), 0, "the event only just arrived");
+    kernel.run_until_idle();
+    assert_eq!(kernel.watchdog().stalls(), 0);
+}
+
+#[cfg(not(feature = "static-alloc"))]
+#[test]
+fn stalls_are_traced() {
+    use std::sync::Arc;
+
+    use crate::watchdog::QS_AO_STALL;
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&records);
+    let hook: crate::TraceHook = Arc::new(move |record, payload: &[u8], ts| {
+        if record == QS_AO_STALL {
+            sink.lock().unwrap().push((payload[..7].to_vec(), ts));
+        }
+        Ok(())
+    });
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(4), 4, Idle))
+        .with_watchdog(2)
+        .with_trace_hook(hook)
+        .build();
+    kernel.start();
+
+    kernel.lock_scheduler(4);
+    post(&kernel, 4, &[0x0102]);
+    kernel.check_watchdog();
+    advance(&kernel, 3);
+    kernel.check_watchdog();
+
+    let expected = vec![4, 0x02, 0x01, 1, 0, 1, 0];
+    assert_eq!(*records.lock().unwrap(), [(expected, true)]);
+}
+
+static FED: AtomicU32 = AtomicU32::new(0);
+
+fn kick() {
+    FED.fetch_add(1, Ordering::Relaxed);
+}
+
+#[test]
+fn the_feeder_wants_a_timely_drain_and_a_moving_tick() {
+    static FEEDER: IdleFeeder = IdleFeeder::new(kick, 5);
+    let none = core::iter::empty();
+
+    assert!(FEEDER.on_idle(0, 1, none.clone()));
+    assert!(!FEEDER.on_idle(1, 1, none.clone()), "the tick has not moved");
+    assert!(!FEEDER.on_idle(2, 10, none.clone()), "the pass overran the deadline");
+    assert!(FEEDER.on_idle(7, 12, none));
+    assert_eq!((FEEDER.feeds(), FEEDER.withheld()), (2, 1));
+}
+
+#[test]
+fn the_kernel_withholds_feeding_while_events_wait() {
+    static FEEDER: IdleFeeder = IdleFeeder::new(kick, 100);
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(5), 5, Idle))
+        .feed_watchdog(&FEEDER)
+        .build();
+    kernel.start();
+
+    kernel.lock_scheduler(5);
+    post(&kernel, 5, &[1]);
+    kernel.run_until_idle();
+    assert_eq!((FEEDER.feeds(), FEEDER.withheld()), (0, 1));
+
+    kernel.unlock_scheduler();
+    kernel.run_until_idle();
+    assert_eq!(FEEDER.feeds(), 1);
+    assert!(FED.load(Ordering::Relaxed) >= 1);
+}
+
+#[cfg(not(feature = "static-alloc"))]
+#[test]
+fn watchdog_resets_are_traced() {
+    use std::sync::Arc;
+
+    use crate::watchdog::QS_WDT_RESET;
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&records);
+    let hook: crate::TraceHook = Arc::new(move |record, payload: &[u8], _| {
+        sink.lock().unwrap().push((record, payload.to_vec()));
+        Ok(())
+    });
+    let kernel = Kernel::builder().with_trace_hook(hook).build();
+    kernel.report_watchdog_reset(0x07);
+
+    assert_eq!(*records.lock().unwrap(), [(QS_WDT_RESET, vec![7, 0, 0, 0])]);
+}
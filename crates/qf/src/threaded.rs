@@ -0,0 +1,98 @@
+//! Thread-per-active-object execution on hosted targets.
+//!
+//! With [`Execution::Threaded`](crate::kernel::Execution::Threaded),
+//! [`QvKernel::run`](crate::kernel::QvKernel::run) gives every registered
+//! active object an OS thread of its own, the architecture of the QP POSIX
+//! port. Each thread blocks on a channel that the kernel's `post` and
+//! `publish` feed, and runs its object's events to completion as they arrive.
+//! The operating system decides which thread runs; an optional
+//! [`ThreadPriority`] hook maps each object's QF priority onto a native thread
+//! priority as its thread starts (`qf_port_posix::native_priority` does this
+//! with `SCHED_FIFO`).
+//!
+//! Events for a running object must go through the kernel. One posted straight
+//! to the object's handle is queued, but its thread only notices it when the
+//! kernel next wakes it. The scheduler ceiling has no effect in this mode; RTC
+//! budgets and [metrics](crate::metrics) still apply.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::vec::Vec;
+
+use crate::active::{ActiveObjectId, ActiveRunnable};
+use crate::event::DynEvent;
+use crate::services::{with_services, KernelServices};
+use crate::sync::Mutex;
+
+/// Sets the calling thread's native priority for an active object of QF
+/// `priority`. Called on each active-object thread before its first event.
+pub type ThreadPriority = fn(priority: u8);
+
+/// What an active-object thread receives.
+pub(crate) enum Work {
+    Event(DynEvent),
+    Stop,
+}
+
+/// The channels into the running active-object threads.
+pub(crate) struct Workers {
+    senders: Mutex<Vec<(ActiveObjectId, Sender<Work>)>>,
+}
+
+impl Workers {
+    pub(crate) const fn new() -> Self {
+        Self { senders: Mutex::new(Vec::new()) }
+    }
+
+    /// Opens the channel into the thread of `id`.
+    pub(crate) fn open(&self, id: ActiveObjectId) -> Receiver<Work> {
+        let (tx, rx) = mpsc::channel();
+        self.senders.lock().push((id, tx));
+        rx
+    }
+
+    /// Hands `event` to the thread of `ao`, or queues it on `ao` if that has
+    /// no running thread. Queuing under the lock means a thread that opens
+    /// its channel afterwards finds the event when it starts.
+    pub(crate) fn deliver(&self, ao: &dyn ActiveRunnable, event: DynEvent) {
+        let senders = self.senders.lock();
+        let event = match senders.iter().find(|(id, _)| *id == ao.id()) {
+            // The thread is gone only if its object panicked.
+            Some((_, tx)) => match tx.send(Work::Event(event)) {
+                Err(mpsc::SendError(Work::Event(event))) => event,
+                _ => return,
+            },
+            None => event,
+        };
+        ao.post(event);
+    }
+
+    /// Stops every thread once it has handled the events already sent to it.
+    /// Later events go straight to the objects' queues.
+    pub(crate) fn close(&self) {
+        for (_, tx) in self.senders.lock().drain(..) {
+            let _ = tx.send(Work::Stop);
+        }
+    }
+}
+
+/// Body of the thread of `ao`.
+pub(crate) fn serve(
+    kernel: &dyn KernelServices,
+    ao: &dyn ActiveRunnable,
+    queue: Receiver<Work>,
+    step: &(dyn Fn(&dyn ActiveRunnable) -> bool + Sync),
+    priority: Option<ThreadPriority>,
+) {
+    if let Some(set_priority) = priority {
+        set_priority(ao.priority());
+    }
+    loop {
+        // Drains what was queued before the thread started, and anything the
+        // object posted to itself (e.g. a recall).
+        while with_services(kernel, || step(ao)) {}
+        match queue.recv() {
+            Ok(Work::Event(event)) => ao.post(event),
+            Ok(Work::Stop) | Err(_) => return,
+        }
+    }
+}
@@ -6,11 +6,286 @@ use core::fmt;
 
 use crate::active::ActiveObjectId;
 use crate::event::{DynEvent, Signal};
+use crate::jitter::JitterStats;
 use crate::kernel::{Kernel, KernelError};
 #[cfg(not(feature = "static-alloc"))]
 use crate::sync::Arc;
 use crate::sync::Mutex;
-use crate::trace::TraceHook;
+use crate::trace::{emit_record, TraceHook};
+#[cfg(feature = "qs")]
+use qs::records::qf::time_evt;
+
+// ── Tick units ────────────────────────────────────────────────────────────────
+
+/// A span of clock ticks of one tick rate.
+///
+/// Time-event timeouts and intervals are counted in ticks of their tick rate,
+/// not in seconds. `TickDuration` keeps those counts apart from other
+/// integers, and [`from_millis`](Self::from_millis) /
+/// [`as_millis`](Self::as_millis) convert at an explicit tick rate instead of
+/// an assumed one. A plain `u64` converts into it as a tick count.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TickDuration(u64);
+
+impl TickDuration {
+    /// No ticks.
+    pub const ZERO: Self = Self(0);
+
+    /// `ticks` ticks.
+    pub const fn from_ticks(ticks: u64) -> Self {
+        Self(ticks)
+    }
+
+    /// The tick count.
+    pub const fn ticks(self) -> u64 {
+        self.0
+    }
+
+    /// `true` for a zero-length span.
+    pub const fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// `ms` milliseconds at `rate_hz` ticks per second, rounded up to whole
+    /// ticks so a timeout never expires early. `None` for a zero rate or on
+    /// overflow.
+    pub const fn from_millis(ms: u64, rate_hz: u32) -> Option<Self> {
+        Self::from_scaled(ms, rate_hz, 1_000)
+    }
+
+    /// `us` microseconds at `rate_hz`, rounded up like
+    /// [`from_millis`](Self::from_millis).
+    pub const fn from_micros(us: u64, rate_hz: u32) -> Option<Self> {
+        Self::from_scaled(us, rate_hz, 1_000_000)
+    }
+
+    /// A [`core::time::Duration`] at `rate_hz`, rounded up to whole ticks.
+    pub const fn from_duration(d: core::time::Duration, rate_hz: u32) -> Option<Self> {
+        if rate_hz == 0 {
+            return None;
+        }
+        let ticks = (d.as_nanos() * rate_hz as u128).div_ceil(1_000_000_000);
+        if ticks > u64::MAX as u128 {
+            return None;
+        }
+        Some(Self(ticks as u64))
+    }
+
+    const fn from_scaled(value: u64, rate_hz: u32, per_second: u128) -> Option<Self> {
+        if rate_hz == 0 {
+            return None;
+        }
+        let ticks = (value as u128 * rate_hz as u128).div_ceil(per_second);
+        if ticks > u64::MAX as u128 {
+            return None;
+        }
+        Some(Self(ticks as u64))
+    }
+
+    /// Whole milliseconds this span lasts at `rate_hz`, rounded down. `None`
+    /// for a zero rate or on overflow.
+    pub const fn as_millis(self, rate_hz: u32) -> Option<u64> {
+        if rate_hz == 0 {
+            return None;
+        }
+        let ms = self.0 as u128 * 1_000 / rate_hz as u128;
+        if ms > u64::MAX as u128 {
+            return None;
+        }
+        Some(ms as u64)
+    }
+
+    /// `self + rhs`, `None` on overflow.
+    pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_add(rhs.0) {
+            Some(t) => Some(Self(t)),
+            None => None,
+        }
+    }
+
+    /// `self - rhs`, `None` if `rhs` is longer.
+    pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_sub(rhs.0) {
+            Some(t) => Some(Self(t)),
+            None => None,
+        }
+    }
+
+    /// `self - rhs`, clamped at zero.
+    pub const fn saturating_sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+
+    /// `self * n`, `None` on overflow.
+    pub const fn checked_mul(self, n: u64) -> Option<Self> {
+        match self.0.checked_mul(n) {
+            Some(t) => Some(Self(t)),
+            None => None,
+        }
+    }
+}
+
+impl From<u64> for TickDuration {
+    fn from(ticks: u64) -> Self {
+        Self(ticks)
+    }
+}
+
+impl From<TickDuration> for u64 {
+    fn from(d: TickDuration) -> Self {
+        d.0
+    }
+}
+
+impl core::ops::Add for TickDuration {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.checked_add(rhs).expect("TickDuration overflow")
+    }
+}
+
+impl core::ops::Sub for TickDuration {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.checked_sub(rhs).expect("TickDuration underflow")
+    }
+}
+
+impl fmt::Display for TickDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ticks", self.0)
+    }
+}
+
+/// A point on the rate-0 tick clock (see [`now`]).
+///
+/// The clock is a wrapping 32-bit counter, like QP's time-event counters:
+/// arithmetic wraps, and [`duration_since`](Self::duration_since) is exact
+/// as long as the two instants are less than 2³² ticks apart.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TickInstant(u32);
+
+impl TickInstant {
+    /// The instant `ticks` ticks after the clock started.
+    pub const fn from_ticks(ticks: u32) -> Self {
+        Self(ticks)
+    }
+
+    /// The raw counter value.
+    pub const fn ticks(self) -> u32 {
+        self.0
+    }
+
+    /// Ticks from `earlier` to `self`, across counter wrap.
+    pub const fn duration_since(self, earlier: Self) -> TickDuration {
+        TickDuration(self.0.wrapping_sub(earlier.0) as u64)
+    }
+
+    /// The instant `d` ticks later, wrapping; `None` if `d` spans a full
+    /// counter period or more.
+    pub const fn checked_add(self, d: TickDuration) -> Option<Self> {
+        if d.0 > u32::MAX as u64 {
+            return None;
+        }
+        Some(Self(self.0.wrapping_add(d.0 as u32)))
+    }
+}
+
+impl core::ops::Sub for TickInstant {
+    type Output = TickDuration;
+
+    fn sub(self, earlier: Self) -> TickDuration {
+        self.duration_since(earlier)
+    }
+}
+
+/// A kernel's rate-0 tick clock: the ticks its timer wheel has processed.
+///
+/// Every kernel keeps its own, and its wheel advances it. The kernel running
+/// on a thread — the one that last ticked there or is dispatching there —
+/// is the one [`now`] reads, so kernels in one process (host tests, a
+/// simulation beside the application) keep separate time.
+#[derive(Debug, Default)]
+pub struct KernelClock {
+    ticks: portable_atomic::AtomicU32,
+}
+
+impl KernelClock {
+    /// A clock at tick 0.
+    pub const fn new() -> Self {
+        Self { ticks: portable_atomic::AtomicU32::new(0) }
+    }
+
+    /// Ticks counted so far.
+    pub fn now(&self) -> TickInstant {
+        TickInstant(self.ticks.load(portable_atomic::Ordering::Relaxed))
+    }
+
+    /// Counts one tick and makes this the clock [`now`] reads on the calling
+    /// thread. Timer wheels call this once per rate-0 tick, before expiring
+    /// that tick's events.
+    pub fn advance(&self) {
+        let ticks = self.ticks.fetch_add(1, portable_atomic::Ordering::Relaxed).wrapping_add(1);
+        set_current(ticks);
+    }
+
+    /// Makes this the clock [`now`] reads on the calling thread. Kernels
+    /// call this before each dispatch (through
+    /// [`with_services`](crate::services::with_services)).
+    pub fn enter(&self) {
+        set_current(self.now().0);
+    }
+}
+
+// The current kernel's tick count as of its last tick or dispatch. Like
+// `current_ao`, per thread with `std` and one for the target otherwise.
+#[cfg(feature = "std")]
+std::thread_local! {
+    static CURRENT: core::cell::Cell<u32> = const { core::cell::Cell::new(0) };
+}
+
+#[cfg(not(feature = "std"))]
+static CURRENT: portable_atomic::AtomicU32 = portable_atomic::AtomicU32::new(0);
+
+fn set_current(ticks: u32) {
+    #[cfg(feature = "std")]
+    let _ = CURRENT.try_with(|current| current.set(ticks));
+    #[cfg(not(feature = "std"))]
+    CURRENT.store(ticks, portable_atomic::Ordering::Relaxed);
+}
+
+/// The rate-0 tick clock of the kernel running on this thread (see
+/// [`KernelClock`]): the time base of
+/// [`timing contracts`](crate::hsm::contract). Reads 0 on a thread where no
+/// kernel has ticked or dispatched yet.
+pub fn now() -> TickInstant {
+    #[cfg(feature = "std")]
+    let ticks = CURRENT.try_with(|current| current.get()).unwrap_or(0);
+    #[cfg(not(feature = "std"))]
+    let ticks = CURRENT.load(portable_atomic::Ordering::Relaxed);
+    TickInstant(ticks)
+}
+
+/// The tick clock ([`now`]) as a QS timestamp source, so that trace
+/// timestamps count the ticks of the kernel emitting the record. Under virtual time, where a test or simulation drives
+/// the timer wheel, records then line up with the ticks that produced them
+/// instead of with the host's clock.
+///
+/// ```rust,ignore
+/// let cfg = QsConfig::default().with_timestamp_source(qf::time::KernelTicks);
+/// ```
+#[cfg(feature = "qs")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KernelTicks;
+
+#[cfg(feature = "qs")]
+impl qs::TimestampSource for KernelTicks {
+    fn now(&self) -> u32 {
+        now().ticks()
+    }
+}
 
 /// Shared handle to a [`TimeEvent`] held by the timer wheel. Dynamic:
 /// `Arc<TimeEvent>`; heap-free `static-alloc`: `&'static TimeEvent` (the time
@@ -56,19 +331,6 @@ pub fn new_time_event(target: ActiveObjectId, config: TimeEventConfig) -> TimeEv
     alloc::boxed::Box::leak(alloc::boxed::Box::new(TimeEvent::new(target, config)))
 }
 
-/// QS record: Time event armed with timeout and optional interval.
-const QS_QF_TIMEEVT_ARM: u8 = 32;
-/// QS record: One-shot time event auto-disarmed after firing.
-const QS_QF_TIMEEVT_AUTO_DISARM: u8 = 33;
-/// QS record: Attempted to disarm an already disarmed time event.
-const QS_QF_TIMEEVT_DISARM_ATTEMPT: u8 = 34;
-/// QS record: Time event successfully disarmed.
-const QS_QF_TIMEEVT_DISARM: u8 = 35;
-/// QS record: Time event counter updated via rearm() without disarm/rearm cycle.
-const QS_QF_TIMEEVT_REARM: u8 = 36;
-/// QS record: Time event posted to target active object.
-const QS_QF_TIMEEVT_POST: u8 = 37;
-
 /// Configuration for a [`TimeEvent`]: the signal it posts and an optional
 /// periodic interval.
 #[derive(Debug, Clone)]
@@ -91,9 +353,10 @@ impl TimeEventConfig {
         }
     }
 
-    /// Makes the configuration periodic, re-arming every `interval` ticks.
-    pub fn with_period(mut self, interval: u64) -> Self {
-        self.interval_ticks = Some(interval);
+    /// Makes the configuration periodic, re-arming every `interval`. A zero
+    /// interval leaves it one-shot.
+    pub fn with_period(mut self, interval: impl Into<TickDuration>) -> Self {
+        self.interval_ticks = Some(interval.into().ticks()).filter(|&t| t > 0);
         self
     }
 
@@ -143,9 +406,13 @@ pub struct TimeEvent {
     inner: Mutex<TimeEventInner>,
     trace: Mutex<Option<TraceHook>>,
     meta: Mutex<Option<TimeEventTraceInfo>>,
+    jitter: Mutex<JitterStats>,
 }
 
 /// Identifying addresses and tick rate emitted with a time event's QS records.
+///
+/// Without one, the records identify the time event by its own address and
+/// the target by its [`ActiveObjectId`].
 #[derive(Debug, Clone, Copy)]
 pub struct TimeEventTraceInfo {
     /// Synthetic address identifying the time event in traces.
@@ -185,12 +452,16 @@ impl TimeEvent {
             }),
             trace: Mutex::new(None),
             meta: Mutex::new(None),
+            jitter: Mutex::new(JitterStats::new()),
         }
     }
 
-    /// Arms the event to fire after `timeout_ticks`, optionally re-arming every
-    /// `interval_ticks` thereafter (periodic).
-    pub fn arm(&self, timeout_ticks: u64, interval_ticks: Option<u64>) {
+    /// Arms the event to fire after `timeout`, optionally re-arming every
+    /// `interval` thereafter (periodic). A zero `interval` arms a one-shot,
+    /// as in QP.
+    pub fn arm(&self, timeout: impl Into<TickDuration>, interval: Option<TickDuration>) {
+        let timeout_ticks = timeout.into().ticks();
+        let interval_ticks = interval.filter(|i| !i.is_zero()).map(TickDuration::ticks);
         let mut inner = self.inner.lock();
         inner.remaining = timeout_ticks;
         inner.cfg.interval_ticks = interval_ticks;
@@ -200,8 +471,24 @@ impl TimeEvent {
         self.emit_arm(timeout_ticks, interval_ticks.unwrap_or(0));
     }
 
+    /// Arms a one-shot: the event fires once after `timeout`, then disarms
+    /// itself (emitting `QS_QF_TIMEEVT_AUTO_DISARM`).
+    pub fn arm_once(&self, timeout: impl Into<TickDuration>) {
+        self.arm(timeout, None);
+    }
+
+    /// Arms a periodic event: it fires after `timeout` and every `interval`
+    /// after that until disarmed.
+    pub fn arm_periodic(&self, timeout: impl Into<TickDuration>, interval: impl Into<TickDuration>) {
+        self.arm(timeout, Some(interval.into()));
+    }
+
     /// Cancels the time event if armed, setting the sticky "was disarmed" flag.
-    pub fn disarm(&self) {
+    ///
+    /// Returns the ticks that were left before it would have fired, or `None`
+    /// if it was not armed (a one-shot that already fired, say). QP/C++'s
+    /// `QTimeEvt::disarm()` returns `disarm().is_some()`.
+    pub fn disarm(&self) -> Option<TickDuration> {
         let mut inner = self.inner.lock();
         if inner.armed {
             let remaining = inner.remaining;
@@ -211,9 +498,11 @@ impl TimeEvent {
             inner.disarmed_flag = true;
             drop(inner);
             self.emit_disarm(remaining, interval);
+            Some(TickDuration::from_ticks(remaining))
         } else {
             drop(inner);
             self.emit_disarm_attempt();
+            None
         }
     }
 
@@ -225,7 +514,8 @@ impl TimeEvent {
     /// (was not armed).
     ///
     /// Corresponds to `QTimeEvt::rearm()` in QP/C++.
-    pub fn rearm(&self, timeout_ticks: u64) -> bool {
+    pub fn rearm(&self, timeout: impl Into<TickDuration>) -> bool {
+        let timeout_ticks = timeout.into().ticks();
         let mut inner = self.inner.lock();
         let was_armed = inner.armed;
         inner.remaining = timeout_ticks;
@@ -255,11 +545,50 @@ impl TimeEvent {
         self.inner.lock().armed
     }
 
+    /// Returns the signal this time event posts.
+    pub fn signal(&self) -> Signal {
+        self.inner.lock().cfg.signal
+    }
+
+    /// Returns the active object this time event posts to.
+    pub fn target(&self) -> ActiveObjectId {
+        self.inner.lock().target
+    }
+
     /// Returns the tick-rate domain this time event is registered with.
     pub fn tick_rate(&self) -> u8 {
         self.inner.lock().cfg.tick_rate
     }
 
+    /// How late this event's timeouts have reached their handler (see
+    /// [`jitter`](crate::jitter)).
+    pub fn jitter(&self) -> JitterStats {
+        *self.jitter.lock()
+    }
+
+    /// Clears the jitter statistics.
+    pub fn reset_jitter(&self) {
+        *self.jitter.lock() = JitterStats::new();
+    }
+
+    /// Emits the jitter statistics as `QS_QF_TIMEEVT_JITTER`, if any timeout
+    /// has been measured.
+    pub fn report_jitter(&self) {
+        let stats = self.jitter();
+        if stats.samples > 0 {
+            self.emit_jitter(&stats);
+        }
+    }
+
+    pub(crate) fn record_jitter(&self, lateness: u32) {
+        self.jitter.lock().record(lateness);
+    }
+
+    pub(crate) fn note_overlap(&self) {
+        let mut stats = self.jitter.lock();
+        stats.overlaps = stats.overlaps.saturating_add(1);
+    }
+
     /// Installs (or clears) the QS trace hook used for this event's records.
     pub fn set_trace(&self, hook: Option<TraceHook>) {
         *self.trace.lock() = hook;
@@ -284,9 +613,12 @@ impl TimeEvent {
 
         if inner.remaining == 0 {
             let target = inner.target;
-            let periodic = inner.cfg.interval_ticks.is_some();
+            // A zero period (set through the public config field) would fire
+            // on every tick; treat it as a one-shot, like `arm`.
+            let period = inner.cfg.interval_ticks.filter(|&p| p > 0);
+            let periodic = period.is_some();
             inner.armed = periodic;
-            if let Some(period) = inner.cfg.interval_ticks {
+            if let Some(period) = period {
                 inner.remaining = period;
             }
             if !periodic {
@@ -314,15 +646,72 @@ pub const MAX_TICK_RATES: usize = 4;
 pub const MAX_TIMERS_PER_RATE: usize = 32;
 
 #[cfg(not(feature = "static-alloc"))]
-type RateBucket = Vec<TimeEventRef>;
+type RateEvents = Vec<TimeEventRef>;
 #[cfg(feature = "static-alloc")]
-type RateBucket = heapless::Vec<TimeEventRef, MAX_TIMERS_PER_RATE>;
+type RateEvents = heapless::Vec<TimeEventRef, MAX_TIMERS_PER_RATE>;
 
 #[cfg(not(feature = "static-alloc"))]
 type WheelEvents = Vec<RateBucket>;
 #[cfg(feature = "static-alloc")]
 type WheelEvents = heapless::Vec<RateBucket, MAX_TICK_RATES>;
 
+/// The time events of one tick rate and the count of ticks that rate has
+/// processed (`QTimeEvt_tickCtr_[rate]` in QP/C). Shared by the timer wheels
+/// of all kernels.
+#[derive(Default)]
+pub struct RateBucket {
+    events: RateEvents,
+    ticks: portable_atomic::AtomicU32,
+}
+
+impl RateBucket {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `event`; hands it back if the bucket is full (`static-alloc`).
+    pub fn push(&mut self, event: TimeEventRef) -> Result<(), TimeEventRef> {
+        #[cfg(feature = "static-alloc")]
+        return self.events.push(event);
+        #[cfg(not(feature = "static-alloc"))]
+        {
+            self.events.push(event);
+            Ok(())
+        }
+    }
+
+    /// The registered time events.
+    pub fn events(&self) -> &[TimeEventRef] {
+        &self.events
+    }
+
+    /// Ticks processed so far (wrapping).
+    pub fn tick_count(&self) -> u32 {
+        self.ticks.load(portable_atomic::Ordering::Relaxed)
+    }
+
+    /// Counts one tick of `tick_rate` and traces it as `QS_QF_TICK`
+    /// (`[ts | tick counter u16 | rate u8]`). Call before polling the events
+    /// so the tick record precedes the posts it causes.
+    #[cfg_attr(not(feature = "qs"), allow(unused_variables))]
+    pub fn count_tick(&self, tick_rate: u8, trace: Option<&TraceHook>) {
+        let ctr = self.ticks.fetch_add(1, portable_atomic::Ordering::Relaxed).wrapping_add(1);
+        if let Some(trace) = trace {
+            emit_record!(trace, qs::records::qf::tick(ctr, tick_rate));
+        }
+    }
+
+    /// Emits the [jitter](crate::jitter) of each event that has some.
+    pub fn report_jitter(&self) {
+        self.events.iter().for_each(|event| event.report_jitter());
+    }
+
+    /// `true` if none of the events is armed.
+    pub fn no_active(&self) -> bool {
+        self.events.iter().all(|event| !event.is_armed())
+    }
+}
+
 /// Cooperative timer wheel that calls into the kernel every tick.
 ///
 /// Under `static-alloc` the per-rate buckets are fixed-capacity, heap-free
@@ -331,6 +720,7 @@ pub struct TimerWheel {
     kernel: KernelRef,
     events: WheelEvents,
     trace: Option<TraceHook>,
+    jitter_period: u32,
 }
 
 impl TimerWheel {
@@ -342,7 +732,7 @@ impl TimerWheel {
         let events = {
             let mut events = Vec::with_capacity(max_rate);
             for _ in 0..max_rate.max(1) {
-                events.push(Vec::new());
+                events.push(RateBucket::new());
             }
             events
         };
@@ -365,46 +755,66 @@ impl TimerWheel {
             kernel,
             events,
             trace,
+            jitter_period: 0,
         }
     }
 
+    /// Emits the [jitter](crate::jitter) of every registered time event
+    /// every `ticks` rate-0 ticks; `0` stops the reports.
+    pub fn report_jitter_every(&mut self, ticks: u32) {
+        self.jitter_period = ticks;
+    }
+
+    /// Emits the [jitter](crate::jitter) of every registered time event now.
+    pub fn report_jitter(&self) {
+        self.events.iter().for_each(RateBucket::report_jitter);
+    }
+
     /// Registers a time event with the wheel, wiring up the wheel's trace hook.
     pub fn register(&mut self, event: TimeEventRef) {
         event.set_trace(self.trace.clone());
         let rate = event.tick_rate() as usize;
         #[cfg(feature = "static-alloc")]
-        {
-            if rate >= self.events.len() {
-                crate::fusa::on_error(module_path!(), line!());
-            }
-            if self.events[rate].push(event).is_err() {
-                crate::fusa::on_error(module_path!(), line!());
-            }
+        if rate >= self.events.len() {
+            crate::fusa::on_error(module_path!(), line!());
         }
         #[cfg(not(feature = "static-alloc"))]
-        if rate < self.events.len() {
-            self.events[rate].push(event);
-        } else {
-            while self.events.len() <= rate {
-                self.events.push(Vec::new());
-            }
-            self.events[rate].push(event);
+        while self.events.len() <= rate {
+            self.events.push(RateBucket::new());
+        }
+        if self.events[rate].push(event).is_err() {
+            crate::fusa::on_error(module_path!(), line!());
         }
     }
 
     /// Advances the wheel for the specified `tick_rate` domain by one tick, posting any events that have expired.
     pub fn tick_rate(&self, tick_rate: u8) -> Result<(), TimeEventError> {
-        let rate = tick_rate as usize;
-        if rate < self.events.len() {
-            for event in &self.events[rate] {
+        if tick_rate == 0 {
+            self.kernel.clock().advance();
+        }
+        if let Some(bucket) = self.events.get(tick_rate as usize) {
+            bucket.count_tick(tick_rate, self.trace.as_ref());
+            for event in bucket.events() {
                 if let Some((target, evt)) = event.poll() {
+                    crate::jitter::fired(event, target, evt.header.signal);
+                    #[cfg(not(feature = "static-alloc"))]
+                    let _timer = crate::replay::TimerPostScope::enter();
                     self.kernel.post(target, evt)?;
                 }
             }
         }
+        if tick_rate == 0 && self.jitter_period != 0 && self.tick_count(0).is_multiple_of(self.jitter_period) {
+            self.report_jitter();
+        }
         Ok(())
     }
 
+    /// Ticks processed so far by the `tick_rate` domain (wrapping); the
+    /// counter traced in `QS_QF_TICK`.
+    pub fn tick_count(&self, tick_rate: u8) -> u32 {
+        self.events.get(tick_rate as usize).map_or(0, RateBucket::tick_count)
+    }
+
     /// Advances the default (tick_rate 0) wheel by one tick.
     pub fn tick(&self) -> Result<(), TimeEventError> {
         self.tick_rate(0)
@@ -435,139 +845,101 @@ impl TimerWheel {
 
     /// Returns `true` if there are no armed time events in the specified `tick_rate` domain.
     pub fn no_active(&self, tick_rate: u8) -> bool {
-        let rate = tick_rate as usize;
-        if rate < self.events.len() {
-            for event in &self.events[rate] {
-                if event.is_armed() {
-                    return false;
-                }
-            }
-        }
-        true
+        self.events.get(tick_rate as usize).is_none_or(RateBucket::no_active)
     }
 }
 
+#[cfg_attr(not(feature = "qs"), allow(unused_variables))]
 impl TimeEvent {
     fn obtain_trace(&self) -> Option<(TraceHook, TimeEventTraceInfo)> {
         let trace = self.trace.lock().clone()?;
-        let mut meta = (*self.meta.lock())?;
-        meta.tick_rate = self.inner.lock().cfg.tick_rate;
+        let meta = *self.meta.lock();
+        let inner = self.inner.lock();
+        let meta = TimeEventTraceInfo {
+            tick_rate: inner.cfg.tick_rate,
+            ..meta.unwrap_or(TimeEventTraceInfo {
+                time_event_addr: self as *const Self as usize as u64,
+                target_addr: u64::from(inner.target.0),
+                tick_rate: 0,
+            })
+        };
         Some((trace, meta))
     }
 
-    /// Helper to emit a trace record with a payload built on the stack.
-    ///
-    /// Uses a fixed-size buffer to avoid heap allocations in hot paths.
-    fn emit_trace<F>(&self, record: u8, timestamp: bool, builder: F)
-    where
-        F: FnOnce(&mut [u8]) -> usize,
-    {
-        if let Some((trace, _)) = self.obtain_trace() {
-            let mut buf = [0u8; 32];
-            let len = builder(&mut buf);
-            let _ = trace(record, &buf[..len], timestamp);
-        }
-    }
-
     fn emit_arm(&self, n_ticks: u64, interval: u64) {
-        if let Some((_, meta)) = self.obtain_trace() {
-            self.emit_trace(QS_QF_TIMEEVT_ARM, true, |buf| {
-                let mut pos = 0;
-                buf[pos..pos + 8].copy_from_slice(&meta.time_event_addr.to_le_bytes());
-                pos += 8;
-                buf[pos..pos + 8].copy_from_slice(&meta.target_addr.to_le_bytes());
-                pos += 8;
-                buf[pos..pos + 2].copy_from_slice(&truncate_u16(n_ticks).to_le_bytes());
-                pos += 2;
-                buf[pos..pos + 2].copy_from_slice(&truncate_u16(interval).to_le_bytes());
-                pos += 2;
-                buf[pos] = meta.tick_rate;
-                pos + 1
-            });
+        if let Some((trace, meta)) = self.obtain_trace() {
+            let (te, ao, rate) = (meta.time_event_addr, meta.target_addr, meta.tick_rate);
+            emit_record!(&trace, time_evt::arm(te, ao, counter(n_ticks), counter(interval), rate));
         }
     }
 
     fn emit_disarm(&self, remaining: u64, interval: u64) {
-        if let Some((_, meta)) = self.obtain_trace() {
-            self.emit_trace(QS_QF_TIMEEVT_DISARM, true, |buf| {
-                let mut pos = 0;
-                buf[pos..pos + 8].copy_from_slice(&meta.time_event_addr.to_le_bytes());
-                pos += 8;
-                buf[pos..pos + 8].copy_from_slice(&meta.target_addr.to_le_bytes());
-                pos += 8;
-                buf[pos..pos + 2].copy_from_slice(&truncate_u16(remaining).to_le_bytes());
-                pos += 2;
-                buf[pos..pos + 2].copy_from_slice(&truncate_u16(interval).to_le_bytes());
-                pos += 2;
-                buf[pos] = meta.tick_rate;
-                pos + 1
-            });
+        if let Some((trace, meta)) = self.obtain_trace() {
+            let (te, ao, rate) = (meta.time_event_addr, meta.target_addr, meta.tick_rate);
+            emit_record!(&trace, time_evt::disarm(te, ao, counter(remaining), counter(interval), rate));
         }
     }
 
     fn emit_rearm(&self, n_ticks: u64, interval: u64) {
-        if let Some((_, meta)) = self.obtain_trace() {
-            self.emit_trace(QS_QF_TIMEEVT_REARM, true, |buf| {
-                let mut pos = 0;
-                buf[pos..pos + 8].copy_from_slice(&meta.time_event_addr.to_le_bytes());
-                pos += 8;
-                buf[pos..pos + 8].copy_from_slice(&meta.target_addr.to_le_bytes());
-                pos += 8;
-                buf[pos..pos + 2].copy_from_slice(&truncate_u16(n_ticks).to_le_bytes());
-                pos += 2;
-                buf[pos..pos + 2].copy_from_slice(&truncate_u16(interval).to_le_bytes());
-                pos += 2;
-                buf[pos] = meta.tick_rate;
-                pos + 1
-            });
+        if let Some((trace, meta)) = self.obtain_trace() {
+            let (te, ao, rate) = (meta.time_event_addr, meta.target_addr, meta.tick_rate);
+            emit_record!(&trace, time_evt::rearm(te, ao, counter(n_ticks), counter(interval), rate));
         }
     }
 
     fn emit_disarm_attempt(&self) {
-        if let Some((_, meta)) = self.obtain_trace() {
-            self.emit_trace(QS_QF_TIMEEVT_DISARM_ATTEMPT, true, |buf| {
-                let mut pos = 0;
-                buf[pos..pos + 8].copy_from_slice(&meta.time_event_addr.to_le_bytes());
-                pos += 8;
-                buf[pos..pos + 8].copy_from_slice(&meta.target_addr.to_le_bytes());
-                pos += 8;
-                buf[pos] = meta.tick_rate;
-                pos + 1
-            });
+        if let Some((trace, meta)) = self.obtain_trace() {
+            emit_record!(&trace, time_evt::disarm_attempt(meta.time_event_addr, meta.target_addr, meta.tick_rate));
         }
     }
 
     fn emit_auto_disarm(&self) {
-        if let Some((_, meta)) = self.obtain_trace() {
-            self.emit_trace(QS_QF_TIMEEVT_AUTO_DISARM, false, |buf| {
-                let mut pos = 0;
-                buf[pos..pos + 8].copy_from_slice(&meta.time_event_addr.to_le_bytes());
-                pos += 8;
-                buf[pos..pos + 8].copy_from_slice(&meta.target_addr.to_le_bytes());
-                pos += 8;
-                buf[pos] = meta.tick_rate;
-                pos + 1
-            });
+        if let Some((trace, meta)) = self.obtain_trace() {
+            emit_record!(&trace, time_evt::auto_disarm(meta.time_event_addr, meta.target_addr, meta.tick_rate));
+        }
+    }
+
+    fn emit_jitter(&self, stats: &JitterStats) {
+        if let Some((trace, meta)) = self.obtain_trace() {
+            let (te, ao) = (meta.time_event_addr, meta.target_addr);
+            let ticks = |lateness: u32| counter(u64::from(lateness));
+            let (last, max, mean) = (ticks(stats.last), ticks(stats.max), ticks(stats.mean().unwrap_or(0)));
+            emit_record!(&trace, qs::records::qf::timeevt_jitter(te, ao, stats.samples, last, max, mean, stats.overlaps));
         }
     }
 
     fn emit_post(&self, signal: Signal) {
-        if let Some((_, meta)) = self.obtain_trace() {
-            self.emit_trace(QS_QF_TIMEEVT_POST, true, |buf| {
-                let mut pos = 0;
-                buf[pos..pos + 8].copy_from_slice(&meta.time_event_addr.to_le_bytes());
-                pos += 8;
-                buf[pos..pos + 2].copy_from_slice(&signal.0.to_le_bytes());
-                pos += 2;
-                buf[pos..pos + 8].copy_from_slice(&meta.target_addr.to_le_bytes());
-                pos += 8;
-                buf[pos] = meta.tick_rate;
-                pos + 1
-            });
+        if let Some((trace, meta)) = self.obtain_trace() {
+            let (te, ao, rate) = (meta.time_event_addr, meta.target_addr, meta.tick_rate);
+            emit_record!(&trace, time_evt::post(te, signal.0, ao, rate));
+        }
+    }
+}
+
+/// Answers host queries as [`TE_OBJ`](qs::query::ObjKind::Te): the target
+/// (its trace address, or its id without trace metadata), the ticks left and
+/// the interval, the signal, and the armed state in bit 0 of the flags.
+#[cfg(feature = "qs")]
+impl qs::query::Queryable for TimeEvent {
+    fn query(&self, kind: qs::query::ObjKind) -> Option<qs::query::Snapshot> {
+        if kind != qs::query::ObjKind::Te {
+            return None;
         }
+        let meta = *self.meta.lock();
+        let inner = self.inner.lock();
+        Some(qs::query::Snapshot::Te {
+            ao: meta.map_or(u64::from(inner.target.0), |meta| meta.target_addr),
+            ctr: counter(inner.remaining),
+            interval: counter(inner.cfg.interval_ticks.unwrap_or(0)),
+            signal: inner.cfg.signal.0,
+            flags: u8::from(inner.armed),
+        })
     }
 }
 
-fn truncate_u16(value: u64) -> u16 {
-    value.min(u16::MAX as u64) as u16
+/// A tick count as a 16-bit time-event counter, saturating rather than
+/// wrapping so a long timeout does not show up as a short one.
+#[cfg_attr(not(feature = "qs"), allow(dead_code))]
+fn counter(value: u64) -> u32 {
+    value.min(u64::from(u16::MAX)) as u32
 }
@@ -1,5 +1,5 @@
 #[cfg(feature = "qs")]
-pub use qs::{TraceError, TraceHook};
+pub use qs::{TraceError, TraceFlush, TraceHook};
 
 /// Result of emitting a trace record.
 #[cfg(feature = "qs")]
@@ -23,6 +23,14 @@ pub type TraceHook = Arc<dyn Fn(u8, &[u8], bool) -> TraceResult + Send + Sync>;
 #[cfg(all(not(feature = "qs"), feature = "static-alloc"))]
 pub type TraceHook = &'static (dyn Fn(u8, &[u8], bool) -> TraceResult + Send + Sync);
 
+/// Flushes the backend behind a [`TraceHook`]; a kernel calls it on
+/// [`shutdown`](crate::kernel::QvKernel::shutdown) so no frame is left
+/// buffered. Same representation rules as the hook.
+#[cfg(all(not(feature = "qs"), not(feature = "static-alloc")))]
+pub type TraceFlush = Arc<dyn Fn() -> TraceResult + Send + Sync>;
+#[cfg(all(not(feature = "qs"), feature = "static-alloc"))]
+pub type TraceFlush = &'static (dyn Fn() -> TraceResult + Send + Sync);
+
 /// Callback invoked on a kernel context switch, receiving `(prev_prio, next_prio)`.
 ///
 /// Priority `0` denotes the idle context, so a switch *to* idle reports
@@ -37,3 +45,33 @@ pub type TraceHook = &'static (dyn Fn(u8, &[u8], bool) -> TraceResult + Send + S
 pub type ContextSwitchHook = crate::sync::Arc<dyn Fn(u8, u8) + Send + Sync>;
 #[cfg(feature = "static-alloc")]
 pub type ContextSwitchHook = &'static (dyn Fn(u8, u8) + Send + Sync);
+
+/// Emits a predefined QS record laid out for this target's native sizes.
+/// Errors are dropped: tracing never fails the framework operation.
+#[cfg(feature = "qs")]
+pub(crate) fn emit_predefined(hook: &TraceHook, record: qs::records::Predefined) {
+    let _ = record.emit(hook, &qs::records::RecordSizes::NATIVE);
+}
+
+/// What QS records carry for the object or function at `addr`: the address,
+/// or its id when `qs::symbols` hands out stable ids. For payloads laid out
+/// by hand; the `qs::records` builders map their fields themselves.
+pub(crate) fn symbol(addr: usize) -> usize {
+    #[cfg(feature = "qs")]
+    return qs::symbols::symbol(addr as u64) as usize;
+    #[cfg(not(feature = "qs"))]
+    addr
+}
+
+/// Emits the record built by a `qs::records` builder through a hook.
+///
+/// Without the `qs` feature there is no QS layout to follow and this expands
+/// to nothing, leaving the arguments unused; callers allow `unused_variables`
+/// for that configuration.
+macro_rules! emit_record {
+    ($hook:expr, $record:expr) => {
+        #[cfg(feature = "qs")]
+        $crate::trace::emit_predefined($hook, $record);
+    };
+}
+pub(crate) use emit_record;
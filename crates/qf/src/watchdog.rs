@@ -0,0 +1,284 @@
+//! Kernel watchdog: active objects that stop making progress.
+//!
+//! A deadlock in an active-object design rarely crashes anything. In the
+//! dining philosophers, a table that never answers leaves its philosophers
+//! with `HUNGRY` events queued forever, and the rest of the system carries on.
+//! The watchdog catches that: on every [`check`](Watchdog::check) it looks at
+//! each active object's queue, and one that has had events waiting with no
+//! event taken for longer than the staleness limit is reported, once per
+//! stall, as a `QS_AO_STALL` record (id 93, a qp-rs extension) with payload
+//! `prio: u8 | signal: u16 | queued: u16 | max_queued: u16 | stale: u32`,
+//! and through the callback set with [`Watchdog::on_stall`].
+//!
+//! Staleness counts ticks of the [tick clock](crate::time::now). The
+//! watchdog only sees what it samples, so it is measured at the resolution of
+//! the checks: the cooperative [`QvKernel::run`](crate::QvKernel::run) loop
+//! checks after every tick, and an application that drives the kernel itself
+//! calls [`QvKernel::check_watchdog`](crate::QvKernel::check_watchdog) from
+//! its tick.
+//!
+//! Objects that do not report their [queue](crate::active::QueueStats), such
+//! as an [`AsyncActive`](crate::async_ao::AsyncActive), are not watched.
+//!
+//! # Hardware watchdog
+//!
+//! An [`IdleFeeder`] ties a hardware watchdog to the health of the kernel.
+//! Registered with a kernel's builder (`feed_watchdog`), it is consulted
+//! each time the kernel runs out of work, and feeds the watchdog only if
+//! every active object's queue is empty, the pass that emptied them took no
+//! longer than the feeder's deadline, and the tick clock has moved since the
+//! last feed. A kernel wedged in a long step, an object whose events are
+//! never taken, or a dead tick interrupt therefore all end in a hardware
+//! reset. After that reset the port calls the kernel's
+//! `report_watchdog_reset` during boot, which emits a `QS_WDT_RESET` record
+//! (id 95, a qp-rs extension) with payload `reason: u32`, the chip's raw
+//! reset-reason code.
+
+use portable_atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::active::{ActiveRunnable, QueueStats};
+use crate::event::Signal;
+use crate::trace::TraceHook;
+
+/// Record id of a stalled active object.
+pub const QS_AO_STALL: u8 = qs_protocol::records::qf::AO_STALL;
+
+/// Record id of a boot that follows a hardware watchdog reset.
+pub const QS_WDT_RESET: u8 = qs_protocol::records::qf::WDT_RESET;
+
+/// Priorities the watchdog can watch (`0..MAX_WATCHED`).
+pub const MAX_WATCHED: usize = 64;
+
+/// Called for every stall, in the context of the check that found it.
+pub type StallCallback = fn(&Stall);
+
+/// An active object that had events waiting and took none of them for longer
+/// than the staleness limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stall {
+    /// Priority of the active object.
+    pub priority: u8,
+    /// Signal of the event at the head of its queue.
+    pub signal: Signal,
+    /// Events waiting.
+    pub queued: usize,
+    /// Most events ever waiting at once.
+    pub max_queued: usize,
+    /// Ticks since the object last made progress, or since its events
+    /// started waiting.
+    pub stale: u32,
+}
+
+/// What the watchdog last saw of one priority.
+struct WatchSlot {
+    /// `QueueStats::taken` at the last check.
+    taken: AtomicU32,
+    /// Tick of the last check that saw progress, or the first to see events
+    /// waiting.
+    since: AtomicU32,
+    /// The current stall has been reported.
+    reported: AtomicBool,
+    /// The last check saw events waiting.
+    waiting: AtomicBool,
+}
+
+impl WatchSlot {
+    const fn new() -> Self {
+        Self {
+            taken: AtomicU32::new(0),
+            since: AtomicU32::new(0),
+            reported: AtomicBool::new(false),
+            waiting: AtomicBool::new(false),
+        }
+    }
+}
+
+/// The staleness limit of one kernel and what it has seen so far.
+pub struct Watchdog {
+    limit: u32,
+    callback: Option<StallCallback>,
+    slots: [WatchSlot; MAX_WATCHED],
+    stalls: AtomicU32,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Watchdog {
+    /// A watchdog with no limit, which never reports.
+    pub const fn new() -> Self {
+        Self {
+            limit: 0,
+            callback: None,
+            slots: [const { WatchSlot::new() }; MAX_WATCHED],
+            stalls: AtomicU32::new(0),
+        }
+    }
+
+    /// Reports an active object that keeps events waiting for more than
+    /// `ticks` ticks without taking one; `0` turns the watchdog off.
+    pub fn set_limit(&mut self, ticks: u32) {
+        self.limit = ticks;
+    }
+
+    /// The staleness limit, if the watchdog is on.
+    pub fn limit(&self) -> Option<u32> {
+        (self.limit != 0).then_some(self.limit)
+    }
+
+    /// Calls `callback` for every stall, after its record is emitted.
+    pub fn on_stall(&mut self, callback: StallCallback) {
+        self.callback = Some(callback);
+    }
+
+    /// Stalls reported so far.
+    pub fn stalls(&self) -> u32 {
+        self.stalls.load(Ordering::Relaxed)
+    }
+
+    /// Looks at every object in `objects` and reports those that became
+    /// stale since the last check. Returns how many it reported.
+    pub fn check<'a>(
+        &self,
+        objects: impl IntoIterator<Item = &'a dyn ActiveRunnable>,
+        trace: Option<&TraceHook>,
+    ) -> usize {
+        if self.limit == 0 {
+            return 0;
+        }
+        let now = crate::time::now().ticks();
+        let mut reported = 0;
+        for ao in objects {
+            let (Some(stats), Some(slot)) = (ao.queue_stats(), self.slots.get(usize::from(ao.priority()))) else {
+                continue;
+            };
+            let progressed = stats.taken != slot.taken.swap(stats.taken, Ordering::Relaxed);
+            let was_waiting = slot.waiting.swap(stats.len != 0, Ordering::Relaxed);
+            if stats.len == 0 || progressed || !was_waiting {
+                slot.since.store(now, Ordering::Relaxed);
+                slot.reported.store(false, Ordering::Relaxed);
+                continue;
+            }
+            let stale = now.wrapping_sub(slot.since.load(Ordering::Relaxed));
+            if stale > self.limit && !slot.reported.swap(true, Ordering::Relaxed) {
+                self.report(&stall(ao, &stats, stale), trace);
+                reported += 1;
+            }
+        }
+        reported
+    }
+
+    fn report(&self, stall: &Stall, trace: Option<&TraceHook>) {
+        self.stalls.fetch_add(1, Ordering::Relaxed);
+        if let Some(trace) = trace {
+            let clamp = |n: usize| u16::try_from(n).unwrap_or(u16::MAX).to_le_bytes();
+            let mut payload = [0u8; 11];
+            payload[0] = stall.priority;
+            payload[1..3].copy_from_slice(&stall.signal.0.to_le_bytes());
+            payload[3..5].copy_from_slice(&clamp(stall.queued));
+            payload[5..7].copy_from_slice(&clamp(stall.max_queued));
+            payload[7..].copy_from_slice(&stall.stale.to_le_bytes());
+            let _ = trace(QS_AO_STALL, &payload, true);
+        }
+        if let Some(callback) = self.callback {
+            callback(stall);
+        }
+    }
+}
+
+fn stall(ao: &dyn ActiveRunnable, stats: &QueueStats, stale: u32) -> Stall {
+    Stall {
+        priority: ao.priority(),
+        signal: ao.front_signal().unwrap_or_default(),
+        queued: stats.len,
+        max_queued: stats.high_watermark,
+        stale,
+    }
+}
+
+/// Feeds a hardware watchdog from the idle loop while the kernel keeps up.
+///
+/// `feed` is called with no kernel lock held; it usually kicks the chip's
+/// watchdog timer through a handle the port keeps in a static.
+pub struct IdleFeeder {
+    feed: fn(),
+    deadline: AtomicU32,
+    last_fed: AtomicU32,
+    fed_once: AtomicBool,
+    feeds: AtomicU32,
+    withheld: AtomicU32,
+}
+
+impl IdleFeeder {
+    /// A feeder calling `feed`, requiring every pass to drain within
+    /// `deadline` ticks.
+    pub const fn new(feed: fn(), deadline: u32) -> Self {
+        Self {
+            feed,
+            deadline: AtomicU32::new(deadline),
+            last_fed: AtomicU32::new(0),
+            fed_once: AtomicBool::new(false),
+            feeds: AtomicU32::new(0),
+            withheld: AtomicU32::new(0),
+        }
+    }
+
+    /// Ticks a pass may take from entering the kernel to draining every
+    /// queue and still count as healthy.
+    pub fn set_deadline(&self, ticks: u32) {
+        self.deadline.store(ticks, Ordering::Relaxed);
+    }
+
+    /// The drain deadline in ticks.
+    pub fn deadline(&self) -> u32 {
+        self.deadline.load(Ordering::Relaxed)
+    }
+
+    /// Times the watchdog was fed.
+    pub fn feeds(&self) -> u32 {
+        self.feeds.load(Ordering::Relaxed)
+    }
+
+    /// Idle passes that found the kernel unhealthy and did not feed.
+    pub fn withheld(&self) -> u32 {
+        self.withheld.load(Ordering::Relaxed)
+    }
+
+    /// Called by a kernel that ran out of work at tick `now`, having started
+    /// the pass at tick `started`. Feeds the watchdog if `objects` are all
+    /// drained, the pass met the deadline and the tick clock has advanced
+    /// since the last feed. Returns whether it fed.
+    pub fn on_idle<'a>(
+        &self,
+        started: u32,
+        now: u32,
+        objects: impl IntoIterator<Item = &'a dyn ActiveRunnable>,
+    ) -> bool {
+        if objects.into_iter().any(|ao| ao.has_events())
+            || now.wrapping_sub(started) > self.deadline()
+        {
+            self.withheld.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        // One feed per tick: a stopped tick clock stops the feeding too.
+        if self.fed_once.load(Ordering::Relaxed) && self.last_fed.load(Ordering::Relaxed) == now {
+            return false;
+        }
+        self.last_fed.store(now, Ordering::Relaxed);
+        self.fed_once.store(true, Ordering::Relaxed);
+        self.feeds.fetch_add(1, Ordering::Relaxed);
+        (self.feed)();
+        true
+    }
+}
+
+/// Emits `QS_WDT_RESET` for a boot after a hardware watchdog reset with the
+/// chip's raw reset-reason code.
+pub fn report_reset(reason: u32, trace: Option<&TraceHook>) {
+    if let Some(trace) = trace {
+        let _ = trace(QS_WDT_RESET, &reason.to_le_bytes(), true);
+    }
+}
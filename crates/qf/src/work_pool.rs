@@ -0,0 +1,217 @@
+//! Work-stealing execution of active objects on hosted targets.
+//!
+//! With [`Execution::Pool`](crate::kernel::Execution::Pool),
+//! [`QvKernel::run`](crate::kernel::QvKernel::run) runs the registered active
+//! objects on a few worker threads rather than one thread each, which keeps
+//! host simulations with hundreds of objects within reach of the machine's
+//! cores. An object is scheduled when an event reaches it; the worker that
+//! posted the event takes it up next, and idle workers steal scheduled
+//! objects from busy ones.
+//!
+//! Every object still runs its events one at a time and in order, on
+//! whichever worker holds it. Objects that must never run concurrently with
+//! each other — say, two that share state outside their queues — go on one
+//! *strand* ([`KernelBuilder::strand`](crate::kernel::KernelBuilder::strand)):
+//! a strand is scheduled as a unit, and its objects' steps run in priority
+//! order. Objects on no strand are independent.
+//!
+//! Traces stay readable when the kernel's hook comes from a
+//! [`qs::OrderedTracer`] with staging on: each step's records are then
+//! released as one consecutively numbered block. Priorities only order the
+//! objects within a strand, and the scheduler ceiling has no effect; RTC
+//! budgets and [metrics](crate::metrics) still apply.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex as StdMutex, RwLock};
+use std::vec::Vec;
+
+use crate::active::ActiveRunnable;
+use crate::event::DynEvent;
+use crate::services::{with_services, KernelServices};
+use crate::sync::Mutex;
+
+/// Steps a worker runs on one strand before giving others a turn.
+const STEPS_PER_TURN: usize = 32;
+
+const NO_STRAND: u16 = u16::MAX;
+
+std::thread_local! {
+    /// The pool and worker index of the current thread, if it is a worker.
+    static WORKER: core::cell::Cell<Option<(usize, usize)>> = const { core::cell::Cell::new(None) };
+}
+
+/// The scheduling state of a running pool. It holds indices, not objects, so
+/// the kernel can share it with the posting side.
+struct Pool {
+    /// Strand of each active-object id.
+    strand_of: [u16; 256],
+    /// Set while a strand is queued or running.
+    scheduled: Vec<AtomicBool>,
+    /// One deque of scheduled strands per worker.
+    local: Vec<Mutex<VecDeque<u16>>>,
+    /// Strands scheduled from outside the workers.
+    injector: Mutex<VecDeque<u16>>,
+    /// Strands waiting in any deque.
+    queued: AtomicUsize,
+    sleep: StdMutex<()>,
+    wake: Condvar,
+    stopping: AtomicBool,
+}
+
+impl Pool {
+    fn key(self: &Arc<Self>) -> usize {
+        Arc::as_ptr(self) as usize
+    }
+
+    /// Queues strand `s` unless it is queued or running already; a running
+    /// strand looks at its queues again when it is released.
+    fn schedule(self: &Arc<Self>, s: u16) {
+        if self.scheduled[usize::from(s)].swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let worker = WORKER.with(|w| w.get()).filter(|&(pool, _)| pool == self.key());
+        match worker {
+            Some((_, index)) => self.local[index].lock().push_back(s),
+            None => self.injector.lock().push_back(s),
+        }
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        let _sleep = self.sleep.lock().unwrap_or_else(|e| e.into_inner());
+        self.wake.notify_one();
+    }
+
+    /// Next strand for worker `index`: its own oldest, then the injector's,
+    /// then the newest of another worker.
+    fn find(&self, index: usize) -> Option<u16> {
+        // One lock at a time: a guard kept across the chain would let two
+        // thieves deadlock on each other's deques.
+        let own = self.local[index].lock().pop_front();
+        let found = own
+            .or_else(|| self.injector.lock().pop_front())
+            .or_else(|| {
+                let n = self.local.len();
+                (1..n).find_map(|k| self.local[(index + k) % n].lock().pop_back())
+            });
+        if found.is_some() {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+        }
+        found
+    }
+
+    /// Blocks until a strand may be waiting or the pool stops.
+    fn idle(&self) {
+        let mut sleep = self.sleep.lock().unwrap_or_else(|e| e.into_inner());
+        while self.queued.load(Ordering::SeqCst) == 0 && !self.stopping.load(Ordering::SeqCst) {
+            sleep = self.wake.wait(sleep).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+/// The kernel's handle on its pool while a pooled `run` lasts.
+pub(crate) struct WorkPool {
+    running: RwLock<Option<Arc<Pool>>>,
+}
+
+impl WorkPool {
+    pub(crate) const fn new() -> Self {
+        Self { running: RwLock::new(None) }
+    }
+
+    /// Queues `event` on `ao` and schedules its strand. Returns the event if
+    /// no pooled `run` is in progress.
+    pub(crate) fn deliver(&self, ao: &dyn ActiveRunnable, event: DynEvent) -> Result<(), DynEvent> {
+        let running = self.running.read().unwrap_or_else(|e| e.into_inner());
+        let Some(pool) = running.as_ref() else { return Err(event) };
+        ao.post(event);
+        match pool.strand_of[usize::from(ao.id().0)] {
+            NO_STRAND => {}
+            s => pool.schedule(s),
+        }
+        Ok(())
+    }
+
+    /// Runs `strands` on `workers` threads until `until` returns, then
+    /// until the events posted so far are handled. Each strand lists its
+    /// objects highest priority first.
+    pub(crate) fn run(
+        &self,
+        kernel: &dyn KernelServices,
+        strands: &[Vec<&dyn ActiveRunnable>],
+        workers: usize,
+        step: &(dyn Fn(&dyn ActiveRunnable) -> bool + Sync),
+        until: impl FnOnce(),
+    ) {
+        let workers = workers.max(1);
+        let mut strand_of = [NO_STRAND; 256];
+        for (s, strand) in strands.iter().enumerate() {
+            for ao in strand {
+                strand_of[usize::from(ao.id().0)] = s as u16;
+            }
+        }
+        let pool = Arc::new(Pool {
+            strand_of,
+            scheduled: strands.iter().map(|_| AtomicBool::new(false)).collect(),
+            local: (0..workers).map(|_| Mutex::new(VecDeque::new())).collect(),
+            injector: Mutex::new(VecDeque::new()),
+            queued: AtomicUsize::new(0),
+            sleep: StdMutex::new(()),
+            wake: Condvar::new(),
+            stopping: AtomicBool::new(false),
+        });
+        *self.running.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::clone(&pool));
+        // Events queued before the pool existed.
+        for (s, strand) in strands.iter().enumerate() {
+            if strand.iter().any(|ao| ao.has_events()) {
+                pool.schedule(s as u16);
+            }
+        }
+
+        std::thread::scope(|scope| {
+            for index in 0..workers {
+                let pool = &pool;
+                let spawned = std::thread::Builder::new()
+                    .name(std::format!("qf-worker-{index}"))
+                    .spawn_scoped(scope, move || serve(pool, index, kernel, strands, step));
+                if spawned.is_err() {
+                    crate::fusa::on_error(module_path!(), line!());
+                }
+            }
+            until();
+            pool.stopping.store(true, Ordering::SeqCst);
+            let _sleep = pool.sleep.lock().unwrap_or_else(|e| e.into_inner());
+            pool.wake.notify_all();
+        });
+        *self.running.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// Body of worker `index`.
+fn serve(
+    pool: &Arc<Pool>,
+    index: usize,
+    kernel: &dyn KernelServices,
+    strands: &[Vec<&dyn ActiveRunnable>],
+    step: &(dyn Fn(&dyn ActiveRunnable) -> bool + Sync),
+) {
+    WORKER.with(|w| w.set(Some((pool.key(), index))));
+    loop {
+        let Some(s) = pool.find(index) else {
+            if pool.stopping.load(Ordering::SeqCst) {
+                break;
+            }
+            pool.idle();
+            continue;
+        };
+        let strand = &strands[usize::from(s)];
+        for _ in 0..STEPS_PER_TURN {
+            let Some(ao) = strand.iter().find(|ao| ao.has_events()) else { break };
+            with_services(kernel, || step(*ao));
+        }
+        // A post that found the strand still scheduled left it to us.
+        pool.scheduled[usize::from(s)].store(false, Ordering::SeqCst);
+        if strand.iter().any(|ao| ao.has_events()) {
+            pool.schedule(s);
+        }
+    }
+    WORKER.with(|w| w.set(None));
+}
@@ -0,0 +1,89 @@
+//! Tests for the tracing global allocator (`alloc-trace` feature).
+
+use std::alloc::System;
+use std::sync::{Arc, Mutex};
+
+use qf::active::{ActiveBehavior, ActiveContext, ActiveObject, ActiveRunnable};
+use qf::alloc_trace::{TracingAllocator, ALLOC, FREE, NO_AO};
+use qf::event::{DynEvent, Signal};
+use qf::{ActiveObjectId, TraceHook};
+use qs::{QsConfig, Tracer, WriterBackend};
+
+const RECORD: u8 = 120;
+const THRESHOLD: usize = 4096;
+
+#[global_allocator]
+static HEAP: TracingAllocator = TracingAllocator::new(System, THRESHOLD, RECORD);
+
+type Records = Arc<Mutex<Vec<(u8, Vec<u8>)>>>;
+
+fn capture() -> Records {
+    let records: Records = Arc::default();
+    let sink = records.clone();
+    let hook: TraceHook = Arc::new(move |id, payload: &[u8], _ts| {
+        sink.lock().unwrap().push((id, payload.to_vec()));
+        Ok(())
+    });
+    HEAP.set_trace_hook(Some(hook));
+    records
+}
+
+/// `(kind, ao, size)` of one record payload.
+fn fields(payload: &[u8]) -> (u8, u8, u32) {
+    assert_eq!(payload.len(), 19);
+    (payload[1], payload[3], u32::from_le_bytes(payload[5..9].try_into().unwrap()))
+}
+
+struct BigAlloc;
+
+impl ActiveBehavior for BigAlloc {
+    fn on_start(&mut self, _: &mut ActiveContext) {}
+
+    fn on_event(&mut self, _: &mut ActiveContext, _: DynEvent) {
+        let block = vec![0u8; THRESHOLD * 2];
+        std::hint::black_box(&block);
+    }
+}
+
+// A single test: the allocator and its hook are process-global.
+#[test]
+fn large_blocks_are_traced_and_attributed() {
+    let records = capture();
+
+    let outside = vec![0u8; THRESHOLD];
+    let small = vec![0u8; 16];
+    drop(std::hint::black_box(small));
+    drop(std::hint::black_box(outside));
+
+    // An `Arc`, or the object itself under `static-alloc`.
+    let ao = ActiveObject::new(ActiveObjectId::new(7), 1, BigAlloc);
+    let ao: &ActiveObject<BigAlloc> = &ao;
+    ActiveRunnable::post(ao, DynEvent::empty_dyn(Signal(1)));
+    assert!(ActiveRunnable::dispatch_one(ao));
+
+    HEAP.set_trace_hook(None);
+    let records = records.lock().unwrap().clone();
+    assert!(records.iter().all(|(id, _)| *id == RECORD));
+    let seen: Vec<_> = records
+        .iter()
+        .map(|(_, p)| fields(p))
+        .filter(|&(_, _, size)| size == THRESHOLD as u32 || size == THRESHOLD as u32 * 2)
+        .collect();
+
+    assert!(seen.contains(&(ALLOC, NO_AO, THRESHOLD as u32)));
+    assert!(seen.contains(&(FREE, NO_AO, THRESHOLD as u32)));
+    assert!(seen.contains(&(ALLOC, 7, THRESHOLD as u32 * 2)));
+    assert!(seen.contains(&(FREE, 7, THRESHOLD as u32 * 2)));
+    assert!(records.iter().all(|(_, p)| fields(p).2 >= THRESHOLD as u32));
+
+    // A record large enough that encoding it allocates past the threshold:
+    // the allocator runs while the tracer is locked and must not emit
+    // through it again.
+    let cfg = QsConfig { max_record_len: THRESHOLD * 4, ..QsConfig::default() };
+    let tracer = Tracer::new(cfg, WriterBackend::new(Vec::new())).into_handle();
+    HEAP.set_trace_hook(Some(tracer.hook()));
+    let big = vec![0x5Au8; THRESHOLD * 2];
+    let sent = tracer.emit(100, &big).unwrap();
+    HEAP.set_trace_hook(None);
+    assert_eq!(sent.payload.len(), THRESHOLD * 2);
+}
@@ -0,0 +1,130 @@
+//! Tests for the Prometheus endpoint (`metrics-export` feature).
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use qf::active::{new_active_object, ActiveContext, SignalHandler};
+use qf::event::{DynEvent, Signal};
+use qf::metrics_export::{render, Exporter, TraceCounters};
+use qf::{ActiveObjectId, Kernel, QvKernel, TraceHook};
+
+struct Sink;
+
+impl SignalHandler for Sink {
+    fn handle_signal(&mut self, _signal: Signal, _ctx: &mut ActiveContext) {}
+}
+
+fn kernel(counters: &Arc<TraceCounters>) -> QvKernel {
+    let hook: TraceHook = Arc::new(|_, _, _| Ok(()));
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(1), 2, Sink))
+        .with_trace_hook(counters.wrap(hook))
+        .with_metrics()
+        .build();
+    kernel.start();
+    for signal in [7, 7, 9] {
+        kernel
+            .post(ActiveObjectId::new(1), DynEvent::empty_dyn(Signal(signal)))
+            .unwrap();
+    }
+    kernel.run_until_idle();
+    kernel
+}
+
+fn scrape(addr: SocketAddr, path: &str) -> thread::JoinHandle<String> {
+    let request = format!("GET {path} HTTP/1.1\r\nHost: qf\r\n\r\n");
+    thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    })
+}
+
+fn answer(exporter: &Exporter, kernel: &QvKernel) {
+    for _ in 0..500 {
+        if exporter.poll(kernel).unwrap() == 1 {
+            return;
+        }
+        thread::sleep(Duration::from_millis(2));
+    }
+    panic!("no scrape arrived");
+}
+
+#[test]
+fn the_exposition_covers_queues_dispatches_and_trace_output() {
+    let counters = Arc::new(TraceCounters::new());
+    let kernel = kernel(&counters);
+    let text = render(&kernel, Some(&counters));
+
+    for line in [
+        "# TYPE qf_queue_depth gauge",
+        "qf_queue_depth{ao=\"1\",prio=\"2\"} 0",
+        "qf_queue_high_watermark{ao=\"1\",prio=\"2\"} 3",
+        "qf_events_taken_total{ao=\"1\",prio=\"2\"} 3",
+        "qf_rtc_overruns_total 0",
+        "qf_dispatches_total{prio=\"2\"} 3",
+        "qf_signal_dispatches_total{signal=\"7\"} 2",
+        "qf_signal_dispatches_total{signal=\"9\"} 1",
+        "qs_trace_errors_total 0",
+    ] {
+        assert!(
+            text.lines().any(|l| l == line),
+            "missing {line:?} in\n{text}"
+        );
+    }
+    assert!(counters.records() > 0 && counters.bytes() > 0);
+    assert!(text.contains(&format!("qs_trace_records_total {}\n", counters.records())));
+    // The kernel has no watchdog to report.
+    assert!(!text.contains("qf_watchdog_stalls_total"));
+}
+
+#[test]
+fn scrapes_are_answered_over_http() {
+    let counters = Arc::new(TraceCounters::new());
+    let kernel = kernel(&counters);
+    let exporter = Exporter::bind("127.0.0.1:0")
+        .unwrap()
+        .with_trace_counters(counters);
+    let addr = exporter.local_addr().unwrap();
+    assert_eq!(exporter.poll(&kernel).unwrap(), 0);
+
+    let client = scrape(addr, "/metrics");
+    answer(&exporter, &kernel);
+    let response = client.join().unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+    assert!(response.contains("Content-Type: text/plain; version=0.0.4\r\n"));
+    assert!(response.contains("qf_events_taken_total{ao=\"1\",prio=\"2\"} 3\n"));
+
+    // Other paths are answered without a snapshot.
+    let client = scrape(addr, "/other");
+    assert!(client
+        .join()
+        .unwrap()
+        .starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
+
+#[test]
+fn a_silent_scraper_does_not_hold_up_poll() {
+    let counters = Arc::new(TraceCounters::new());
+    let kernel = kernel(&counters);
+    let exporter = Exporter::bind("127.0.0.1:0").unwrap();
+    let addr = exporter.local_addr().unwrap();
+
+    // Connects and never sends its request.
+    let _silent = TcpStream::connect(addr).unwrap();
+    let started = Instant::now();
+    for _ in 0..10 {
+        assert_eq!(exporter.poll(&kernel).unwrap(), 0);
+    }
+    assert!(started.elapsed() < Duration::from_millis(100));
+
+    // Once the silent scraper times out, the next one is answered.
+    let client = scrape(addr, "/metrics");
+    answer(&exporter, &kernel);
+    assert!(client.join().unwrap().starts_with("HTTP/1.1 200 OK\r\n"));
+}
@@ -0,0 +1,81 @@
+//! Stable symbol ids in the records a state machine emits. The id table is
+//! global, so these tests run in their own binary.
+
+use std::sync::{Arc, Mutex};
+
+use qf::active::{ActiveObject, ActiveRunnable};
+use qf::event::{DynEvent, Signal};
+use qf::hsm::reserved::*;
+use qf::{q_handled, q_super, q_tran, ActiveObjectId, QHsm, QHsmResult, TraceHook};
+use qs::predefined::{FUN_DICT, OBJ_DICT, SYM_MODE};
+use qs::records::qep;
+use qs::symbols::{self, SymbolMode};
+
+const GO_SIG: u16 = 4;
+
+struct Light;
+
+fn initial(_sm: &mut Light, _e: &DynEvent) -> QHsmResult<Light> {
+    q_tran!(off)
+}
+
+fn off(_sm: &mut Light, e: &DynEvent) -> QHsmResult<Light> {
+    match e.signal() {
+        Signal(GO_SIG) => q_tran!(on),
+        _ => q_super!(QHsm::<Light>::top_state),
+    }
+}
+
+fn on(_sm: &mut Light, e: &DynEvent) -> QHsmResult<Light> {
+    match e.signal() {
+        Q_ENTRY_SIG => q_handled!(),
+        _ => q_super!(QHsm::<Light>::top_state),
+    }
+}
+
+type Records = Arc<Mutex<Vec<(u8, Vec<u8>)>>>;
+
+fn capture() -> (TraceHook, Records) {
+    let records: Records = Arc::default();
+    let sink = Arc::clone(&records);
+    let hook: TraceHook = Arc::new(move |record, payload: &[u8], _| {
+        sink.lock().unwrap().push((record, payload.to_vec()));
+        Ok(())
+    });
+    (hook, records)
+}
+
+#[test]
+fn records_name_dictionary_ids_instead_of_addresses() {
+    symbols::set_mode(SymbolMode::StableIds);
+    let (hook, records) = capture();
+
+    let light = ActiveObject::new(ActiveObjectId::new(1), 1, QHsm::new(Light, initial));
+    let mut dict = qs::qs_dictionary! { fun "Light" { off, on } };
+    dict.obj(light.behavior_addr(), "Light::inst");
+    dict.emit(&hook).unwrap();
+
+    light.start(Some(Arc::clone(&hook)));
+    records.lock().unwrap().clear();
+    light.post(DynEvent::empty_dyn(Signal(GO_SIG)));
+    light.dispatch_one();
+
+    // Ids 1 and 2 went to the handlers, 3 to the object, in the order sent.
+    let ptr = core::mem::size_of::<usize>();
+    let field = |id: u64| id.to_le_bytes()[..ptr].to_vec();
+    let tran = [GO_SIG.to_le_bytes().to_vec(), field(3), field(1), field(2)].concat();
+    assert!(records.lock().unwrap().contains(&(qep::TRAN, tran)));
+    assert_eq!(symbols::address(3), Some(light.behavior_addr()));
+
+    // The same program order gives the same ids in the next run.
+    symbols::set_mode(SymbolMode::StableIds);
+    let (hook, records) = capture();
+    dict.emit(&hook).unwrap();
+    let sent = records.lock().unwrap();
+    let ids: Vec<_> = sent.iter().skip(1).map(|(record, payload)| (*record, payload[0])).collect();
+    assert_eq!(sent[0], (SYM_MODE, vec![1]));
+    assert_eq!(ids, [(FUN_DICT, 1), (FUN_DICT, 2), (OBJ_DICT, 3)]);
+    assert!(sent.iter().skip(1).all(|(_, payload)| payload[1..ptr].iter().all(|&b| b == 0)));
+
+    symbols::set_mode(SymbolMode::Addresses);
+}
@@ -2,13 +2,24 @@
 use alloc::collections::BTreeMap;
 use core::fmt;
 
-use qf::active::{ActiveObjectId, ActiveObjectRef};
-use qf::event::{DynEvent, Signal};
+use qf::active::{ActiveObjectId, ActiveObjectRef, ActiveRunnable};
+use qf::budget::{OverrunCallback, RtcBudgets};
+use qf::event::{DynEvent, EventHeader, Signal};
+use qf::idle::{IdleCallback, IdleContext, InterruptLock};
+use qf::isr_queue::{IsrQueues, IsrSource};
 use qf::pubsub::PubSubTable;
 use qf::priospec::QPrioSpec;
+use qf::services::{with_services, KernelServices, ServiceError};
+use qf::time::KernelClock;
+use qf::watchdog::IdleFeeder;
+#[cfg(any(not(feature = "static-alloc"), feature = "std"))]
+use qf::schedulability::{PriorityPlan, TaskTiming};
 use qf::{ContextSwitchHook, TraceHook};
+#[cfg(feature = "qs")]
+use qs::records::RecordSizes;
 
 use crate::scheduler::{QkScheduler, SchedStatus, ScheduleDecision};
+use crate::threshold::{ThresholdAnalysis, ThresholdWarning};
 #[cfg(not(feature = "static-alloc"))]
 use crate::sync::Arc;
 
@@ -58,9 +69,6 @@ struct Registration {
     object: ActiveObjectRef,
     priority: u8,
     threshold: u8,
-    // Used to build the id→priority map on the dynamic build; the heap-free
-    // build scans `slots` by id instead, so the field is set but not read there.
-    #[cfg_attr(feature = "static-alloc", allow(dead_code))]
     id: ActiveObjectId,
 }
 
@@ -77,6 +85,14 @@ pub struct QkKernelBuilder {
     trace: Option<TraceHook>,
     context_sw: Option<ContextSwitchHook>,
     pubsub: Option<PubSubTable>,
+    budgets: RtcBudgets,
+    idle: Option<IdleCallback>,
+    idle_lock: Option<InterruptLock>,
+    feeder: Option<&'static IdleFeeder>,
+    isr_queues: IsrQueues,
+    threshold_warning: Option<fn(&ThresholdWarning)>,
+    #[cfg(feature = "qs")]
+    nmi_trace: Option<&'static dyn qs::NmiSource>,
 }
 
 impl Default for QkKernelBuilder {
@@ -93,6 +109,14 @@ impl QkKernelBuilder {
             trace: None,
             context_sw: None,
             pubsub: None,
+            budgets: RtcBudgets::new(),
+            idle: None,
+            idle_lock: None,
+            feeder: None,
+            isr_queues: IsrQueues::new(),
+            threshold_warning: None,
+            #[cfg(feature = "qs")]
+            nmi_trace: None,
         }
     }
 
@@ -193,9 +217,108 @@ impl QkKernelBuilder {
         self
     }
 
-    /// Validates the registrations and constructs the [`QkKernel`].
+    /// Runs a deadline-monotonic analysis of `tasks` and checks it against
+    /// the priorities registered so far. See [`PriorityPlan::check_registered`].
+    #[cfg(any(not(feature = "static-alloc"), feature = "std"))]
+    pub fn deadline_monotonic(&self, tasks: &[TaskTiming]) -> PriorityPlan {
+        PriorityPlan::check_registered(tasks, self.registrations.iter().map(|r| (r.id, r.priority)))
+    }
+
+    /// Analyzes the preemption thresholds registered so far; print it for a
+    /// summary table. See [`ThresholdAnalysis`].
+    pub fn threshold_analysis(&self) -> ThresholdAnalysis {
+        ThresholdAnalysis::new(self.registrations.iter().map(|r| (r.priority, r.threshold)))
+    }
+
+    /// Calls `callback` from [`build`](Self::build) for every warning of the
+    /// [threshold analysis](Self::threshold_analysis) of the final set of
+    /// registrations.
+    pub fn on_threshold_warning(mut self, callback: fn(&ThresholdWarning)) -> Self {
+        self.threshold_warning = Some(callback);
+        self
+    }
+
+    /// Bounds each run-to-completion step of the AO at `priority` to
+    /// `budget` units of the [budget clock](qf::budget). Time spent in
+    /// higher-priority steps that preempt it does not count.
+    pub fn with_rtc_budget(mut self, priority: u8, budget: u32) -> Result<Self, QkKernelError> {
+        self.validate_priority(priority)?;
+        self.budgets.set(priority, budget);
+        Ok(self)
+    }
+
+    /// Calls `callback` for every budget overrun.
+    pub fn on_rtc_overrun(mut self, callback: OverrunCallback) -> Self {
+        self.budgets.on_overrun(callback);
+        self
+    }
+
+    /// Calls `callback` each time [`run_until_idle`](QkKernel::run_until_idle)
+    /// finds no task ready (see [`qf::idle`]).
+    pub fn on_idle(mut self, callback: IdleCallback) -> Self {
+        self.idle = Some(callback);
+        self
+    }
+
+    /// Enters the idle callback under `lock`, typically the port's scheduler
+    /// lock, so an ISR cannot post between the last ready check and sleep.
+    pub fn with_interrupt_lock(mut self, lock: InterruptLock) -> Self {
+        self.idle_lock = Some(lock);
+        self
+    }
+
+    /// Feeds a hardware watchdog through `feeder` whenever
+    /// [`run_until_idle`](QkKernel::run_until_idle) leaves every queue drained
+    /// (see [`qf::watchdog`]).
+    pub fn feed_watchdog(mut self, feeder: &'static IdleFeeder) -> Self {
+        self.feeder = Some(feeder);
+        self
+    }
+
+    /// Moves the events `queue` received from an interrupt handler into its
+    /// target's queue at [`end_of_isr`](QkKernel::end_of_isr) and on every
+    /// [`run_until_idle`](QkKernel::run_until_idle) pass. More than
+    /// [`MAX_ISR_QUEUES`](qf::isr_queue::MAX_ISR_QUEUES) queues fault.
+    pub fn isr_queue(mut self, queue: &'static dyn IsrSource) -> Self {
+        if !self.isr_queues.add(queue) {
+            qf::fusa::on_error(module_path!(), line!());
+        }
+        self
+    }
+
+    /// Merges the breadcrumbs that non-maskable handlers left in `source` into
+    /// the trace hook at the start of every
+    /// [`run_until_idle`](QkKernel::run_until_idle) (see [`qs::nmi`]).
+    #[cfg(feature = "qs")]
+    pub fn with_nmi_trace(mut self, source: &'static dyn qs::NmiSource) -> Self {
+        self.nmi_trace = Some(source);
+        self
+    }
+
+    /// Validates the registrations and constructs the [`QkKernel`]. An ISR
+    /// queue towards an unregistered object is [`QkKernelError::NotFound`].
     pub fn build(self) -> Result<QkKernel, QkKernelError> {
-        QkKernel::new(self.registrations, self.trace, self.context_sw, self.pubsub)
+        if let Some(queue) = self
+            .isr_queues
+            .iter()
+            .find(|queue| !self.registrations.iter().any(|r| r.id == queue.target()))
+        {
+            return Err(QkKernelError::NotFound(queue.target()));
+        }
+        if let Some(callback) = self.threshold_warning {
+            self.threshold_analysis().warnings().for_each(|warning| callback(&warning));
+        }
+        let mut kernel =
+            QkKernel::new(self.registrations, self.trace, self.context_sw, self.pubsub, self.budgets)?;
+        kernel.idle = self.idle;
+        kernel.idle_lock = self.idle_lock;
+        kernel.feeder = self.feeder;
+        kernel.isr_queues = self.isr_queues;
+        #[cfg(feature = "qs")]
+        {
+            kernel.nmi_trace = self.nmi_trace;
+        }
+        Ok(kernel)
     }
 }
 
@@ -220,6 +343,8 @@ pub enum QkKernelError {
         /// The AO priority the threshold must be `>=`.
         priority: u8,
     },
+    /// A core-affinity mask allowed no core at all.
+    NoCores(ActiveObjectId),
 }
 
 impl fmt::Display for QkKernelError {
@@ -229,6 +354,7 @@ impl fmt::Display for QkKernelError {
                 write!(f, "active object priority {prio} already registered")
             }
             Self::NotFound(id) => write!(f, "active object {id:?} not found"),
+            Self::NoCores(id) => write!(f, "active object {id:?} pinned to no core"),
             Self::InvalidPriority { priority, reason } => {
                 write!(f, "invalid priority {priority}: {reason}")
             }
@@ -258,6 +384,15 @@ pub struct QkKernel {
     id_to_prio: BTreeMap<ActiveObjectId, u8>,
     trace: Option<TraceHook>,
     pubsub: Option<PubSubTable>,
+    budgets: RtcBudgets,
+    idle: Option<IdleCallback>,
+    idle_lock: Option<InterruptLock>,
+    feeder: Option<&'static IdleFeeder>,
+    isr_queues: IsrQueues,
+    /// Rate-0 ticks of the kernel's timer wheel.
+    clock: KernelClock,
+    #[cfg(feature = "qs")]
+    nmi_trace: Option<&'static dyn qs::NmiSource>,
 }
 
 impl QkKernel {
@@ -271,6 +406,7 @@ impl QkKernel {
         trace: Option<TraceHook>,
         context_sw: Option<ContextSwitchHook>,
         pubsub: Option<PubSubTable>,
+        budgets: RtcBudgets,
     ) -> Result<Self, QkKernelError> {
         let mut slots: [Option<ActiveSlot>; MAX_PRIORITY + 1] =
             core::array::from_fn(|_| None);
@@ -301,6 +437,14 @@ impl QkKernel {
             id_to_prio,
             trace,
             pubsub,
+            budgets,
+            idle: None,
+            idle_lock: None,
+            feeder: None,
+            isr_queues: IsrQueues::new(),
+            clock: KernelClock::new(),
+            #[cfg(feature = "qs")]
+            nmi_trace: None,
         })
     }
 
@@ -332,6 +476,30 @@ impl QkKernel {
         self.trace.clone()
     }
 
+    /// The run-to-completion budgets and their overrun count.
+    pub fn rtc_budgets(&self) -> &RtcBudgets {
+        &self.budgets
+    }
+
+    /// The kernel's rate-0 tick clock, advanced by its timer wheel.
+    pub fn clock(&self) -> &KernelClock {
+        &self.clock
+    }
+
+    /// Emits `QS_WDT_RESET` with the chip's raw reset-reason code. Ports
+    /// call this once at boot when the last reset came from a hardware
+    /// watchdog.
+    pub fn report_watchdog_reset(&self, reason: u32) {
+        qf::watchdog::report_reset(reason, self.trace.as_ref());
+    }
+
+    /// Analyzes the preemption thresholds of the registered AOs.
+    pub fn threshold_analysis(&self) -> ThresholdAnalysis {
+        ThresholdAnalysis::new(self.slots.iter().enumerate().filter_map(|(prio, slot)| {
+            slot.as_ref().map(|slot| (prio as u8, slot.threshold))
+        }))
+    }
+
     /// Subscribe the active object at `priority` to the given `signal`.
     pub fn subscribe(&self, signal: Signal, priority: u8) {
         if let Some(ref pubsub) = self.pubsub {
@@ -355,24 +523,31 @@ impl QkKernel {
         }
     }
 
+    // Same layouts as `qf::Kernel`: active objects are identified by
+    // priority, and `publish` has no sender to report.
+
+    #[cfg_attr(not(feature = "qs"), allow(unused_variables))]
     fn emit_subscribe(&self, priority: u8, signal: Signal) {
+        #[cfg(feature = "qs")]
         if let Some(trace) = &self.trace {
-            let sig_bytes = signal.0.to_le_bytes();
-            let _ = trace(12, &[priority, sig_bytes[0], sig_bytes[1]], true);
+            let _ = qs::records::qf::subscribe(u64::from(priority), signal.0).emit(trace, &RecordSizes::NATIVE);
         }
     }
 
+    #[cfg_attr(not(feature = "qs"), allow(unused_variables))]
     fn emit_unsubscribe(&self, priority: u8, signal: Signal) {
+        #[cfg(feature = "qs")]
         if let Some(trace) = &self.trace {
-            let sig_bytes = signal.0.to_le_bytes();
-            let _ = trace(13, &[priority, sig_bytes[0], sig_bytes[1]], true);
+            let _ = qs::records::qf::unsubscribe(u64::from(priority), signal.0).emit(trace, &RecordSizes::NATIVE);
         }
     }
 
-    fn emit_publish(&self, signal: Signal) {
+    #[cfg_attr(not(feature = "qs"), allow(unused_variables))]
+    fn emit_publish(&self, signal: Signal, header: &EventHeader) {
+        #[cfg(feature = "qs")]
         if let Some(trace) = &self.trace {
-            let sig_bytes = signal.0.to_le_bytes();
-            let _ = trace(26, &[sig_bytes[0], sig_bytes[1]], true);
+            let (pool, ref_ctr) = (header.pool_id.unwrap_or(0), header.ref_count);
+            let _ = qs::records::qf::publish(0, signal.0, pool, ref_ctr).emit(trace, &RecordSizes::NATIVE);
         }
     }
 
@@ -401,7 +576,7 @@ impl QkKernel {
     /// as ready. Call once before driving the dispatch loop.
     pub fn start(&self) {
         for slot in self.slots.iter().flatten() {
-            slot.object.start(self.trace.clone());
+            with_services(self, || slot.object.start(self.trace.clone()));
             if slot.object.has_events() {
                 self.scheduler.mark_ready(slot.object.priority());
             }
@@ -411,26 +586,49 @@ impl QkKernel {
     /// Posts an event to the target active object's queue and marks it ready.
     /// Does not itself run the scheduler.
     pub fn post(&self, target: ActiveObjectId, event: DynEvent) -> Result<(), QkKernelError> {
+        self.enqueue(target, event, false)
+    }
+
+    /// Posts `event` to the back of `target`'s queue, or to the front for a
+    /// recall, and marks the object ready.
+    fn priority_of(&self, target: ActiveObjectId) -> Result<u8, QkKernelError> {
         // Dynamic: O(log n) id→priority map. Heap-free: scan the fixed slot array.
         #[cfg(not(feature = "static-alloc"))]
-        let prio = self
-            .id_to_prio
-            .get(&target)
-            .copied()
-            .ok_or(QkKernelError::NotFound(target))?;
+        let prio = self.id_to_prio.get(&target).copied();
         #[cfg(feature = "static-alloc")]
         let prio = self
             .slots
             .iter()
             .flatten()
             .find(|slot| slot.object.id() == target)
-            .map(|slot| slot.object.priority())
-            .ok_or(QkKernelError::NotFound(target))?;
+            .map(|slot| slot.object.priority());
+        prio.ok_or(QkKernelError::NotFound(target))
+    }
+
+    /// Pins the active object `id` to the cores set in `cores` (bit n is
+    /// core n). Other cores leave it ready for one of those; see
+    /// [`QkScheduler::set_cross_core_hook`] for how they are told.
+    #[cfg(feature = "smp")]
+    pub fn set_core_affinity(&self, id: ActiveObjectId, cores: u8) -> Result<(), QkKernelError> {
+        let prio = self.priority_of(id)?;
+        if cores == 0 {
+            return Err(QkKernelError::NoCores(id));
+        }
+        self.scheduler.set_core_affinity(prio, cores);
+        Ok(())
+    }
+
+    fn enqueue(&self, target: ActiveObjectId, event: DynEvent, lifo: bool) -> Result<(), QkKernelError> {
+        let prio = self.priority_of(target)?;
         let slot = self.slots[prio as usize]
             .as_ref()
             .unwrap_or_else(|| qf::fusa::on_error(module_path!(), line!()));
         let was_empty = !slot.object.has_events();
-        slot.object.post(event);
+        if lifo {
+            slot.object.post_lifo(event);
+        } else {
+            slot.object.post(event);
+        }
         if was_empty {
             self.scheduler.mark_ready(prio);
         }
@@ -452,7 +650,7 @@ impl QkKernel {
     /// marking each recipient as ready. Does not itself run the scheduler.
     pub fn publish(&self, signal: Signal, event: DynEvent) {
         if let Some(ref pubsub) = self.pubsub {
-            self.emit_publish(signal);
+            self.emit_publish(signal, &event.header);
             let subscribers = pubsub.subscribers(signal);
             for (prio, slot_opt) in self.slots.iter().enumerate() {
                 if let Some(slot) = slot_opt {
@@ -500,14 +698,84 @@ impl QkKernel {
         }
     }
 
-    /// Repeatedly dispatches ready tasks until none remain.
+    /// Repeatedly dispatches ready tasks until none remain, then runs the idle
+    /// callback (if any) unless a task became ready before its lock was taken.
+    /// Events waiting in the ISR queues are posted before every dispatch. A
+    /// [watchdog feeder](QkKernelBuilder::feed_watchdog) is consulted before
+    /// the callback.
     pub fn run_until_idle(&self) {
-        while self.dispatch_once() {}
+        self.merge_nmi_trace();
+        let started = self.clock.now().ticks();
+        let mut batch = qf::batch::Batch::start();
+        loop {
+            self.drain_isr_queues();
+            if !self.dispatch_once() {
+                break;
+            }
+            batch.count();
+        }
+        batch.finish(self.trace.as_ref());
+        if let Some(feeder) = self.feeder {
+            // `&*` derefs the `Arc`; under `static-alloc` the slot already holds a reference.
+            #[cfg_attr(feature = "static-alloc", allow(clippy::borrow_deref_ref))]
+            let objects = self.slots.iter().flatten().map(|slot| &*slot.object as &dyn ActiveRunnable);
+            feeder.on_idle(started, self.clock.now().ticks(), objects);
+        }
+        if let Some(on_idle) = self.idle {
+            let cx = IdleContext::enter(self.idle_lock.as_ref());
+            if !self.has_pending_work() {
+                on_idle(cx);
+            }
+        }
+    }
+
+    /// Moves the breadcrumbs left by non-maskable handlers into the trace.
+    fn merge_nmi_trace(&self) {
+        #[cfg(feature = "qs")]
+        if let (Some(source), Some(trace)) = (self.nmi_trace, &self.trace) {
+            source.merge(trace);
+        }
     }
 
-    /// Returns `true` if any task is ready to run under current constraints.
+    /// Returns `true` if any task is ready to run under current constraints,
+    /// or an ISR queue holds an event.
     pub fn has_pending_work(&self) -> bool {
-        self.scheduler.has_ready_to_run()
+        self.scheduler.has_ready_to_run() || self.isr_queues.is_pending()
+    }
+
+    /// End-of-ISR scheduler trigger: call it after `qk_isr_exit!()`. Once
+    /// the outermost handler is leaving, posts the events waiting in the ISR
+    /// queues and runs whatever they made eligible to preempt the interrupted
+    /// task. Inside a nested handler it does nothing; the outer one drains.
+    pub fn end_of_isr(&self) {
+        if qf::in_isr() {
+            return;
+        }
+        if self.drain_isr_queues() > 0 {
+            if let Some(decision) = self.scheduler.plan_activation() {
+                self.activate(decision);
+            }
+        }
+    }
+
+    /// Activator for ports that leave interrupt context before preempting
+    /// (PendSV on Cortex-M): posts the events waiting in the ISR queues, then
+    /// runs every ready task allowed to preempt the interrupted one. Unlike
+    /// [`run_until_idle`](Self::run_until_idle) it never calls the idle
+    /// callback.
+    pub fn activate_pending(&self) {
+        self.drain_isr_queues();
+        if let Some(decision) = self.scheduler.plan_activation() {
+            self.activate(decision);
+        }
+    }
+
+    fn drain_isr_queues(&self) -> usize {
+        self.isr_queues.drain(|target, event| {
+            if self.post(target, event).is_err() {
+                qf::fusa::on_error(module_path!(), line!());
+            }
+        })
     }
 
     fn activate(&self, initial: ScheduleDecision) {
@@ -525,7 +793,7 @@ impl QkKernel {
 
             self.scheduler.commit_activation(&decision, threshold);
 
-            let processed = object.dispatch_one();
+            let processed = with_services(self, || self.budgets.dispatch(&*object, self.trace.as_ref()));
             debug_assert!(processed, "scheduled active object had no event");
 
             if !object.has_events() {
@@ -559,6 +827,39 @@ impl QkKernel {
     }
 }
 
+impl KernelServices for QkKernel {
+    fn post(&self, target: ActiveObjectId, event: DynEvent) -> Result<(), ServiceError> {
+        self.enqueue(target, event, false).map_err(|_| ServiceError::NotFound(target))
+    }
+
+    fn post_lifo(&self, target: ActiveObjectId, event: DynEvent) -> Result<(), ServiceError> {
+        self.enqueue(target, event, true).map_err(|_| ServiceError::NotFound(target))
+    }
+
+    fn publish(&self, signal: Signal, event: DynEvent) {
+        QkKernel::publish(self, signal, event);
+    }
+
+    fn clock(&self) -> Option<&KernelClock> {
+        Some(&self.clock)
+    }
+}
+
+#[cfg(feature = "metrics-export")]
+impl qf::metrics_export::MetricsSource for QkKernel {
+    // `&*` derefs the `Arc`; under `static-alloc` the slot already holds a reference.
+    #[cfg_attr(feature = "static-alloc", allow(clippy::borrow_deref_ref))]
+    fn for_each_object(&self, f: &mut dyn FnMut(&dyn qf::active::ActiveRunnable)) {
+        for slot in self.slots.iter().flatten() {
+            f(&*slot.object);
+        }
+    }
+
+    fn rtc_overruns(&self) -> u32 {
+        self.budgets.overruns()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -663,6 +964,157 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn build_reports_thresholds_that_block_the_top_priority() -> Result<(), QkKernelError> {
+        static WARNINGS: Mutex<Vec<ThresholdWarning>> = Mutex::new(Vec::new());
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let ao = |id: u8, prio: u8| {
+            let id = ActiveObjectId::new(id);
+            new_active_object(id, prio, Recorder::new(id, Arc::clone(&log)))
+        };
+
+        let builder = QkKernel::builder()
+            .register_with_threshold(ao(1, 2), 6)?
+            .register(ao(2, 3))?
+            .register(ao(3, 6))?
+            .on_threshold_warning(|warning| WARNINGS.lock().unwrap().push(*warning));
+        assert!(builder.threshold_analysis().blocked(2).eq([3, 6]));
+        let kernel = builder.build()?;
+
+        let blocked = ThresholdWarning::HighestBlocked { priority: 2, threshold: 6, highest: 6 };
+        assert_eq!(*WARNINGS.lock().unwrap(), [blocked]);
+        assert!(kernel.threshold_analysis().groups().eq([2..=6]));
+        Ok(())
+    }
+
+    /// Takes an interrupt in the middle of its own step: the handler posts to a
+    /// higher-priority AO through its ISR queue and the end-of-ISR trigger
+    /// preempts the step right there.
+    struct Interrupted {
+        id: ActiveObjectId,
+        queue: &'static qf::IsrQueue<1>,
+        kernel: Arc<std::sync::OnceLock<Arc<QkKernel>>>,
+        log: Arc<Mutex<Vec<(ActiveObjectId, Signal)>>>,
+    }
+
+    impl SignalHandler for Interrupted {
+        fn handle_signal(&mut self, signal: Signal, _ctx: &mut ActiveContext) {
+            self.log.lock().unwrap().push((self.id, signal));
+            self.queue.post_from_isr(DynEvent::empty_dyn(Signal(7))).unwrap();
+            self.kernel.get().expect("kernel installed").end_of_isr();
+            self.log.lock().unwrap().push((self.id, Signal(signal.0 + 1)));
+        }
+    }
+
+    #[test]
+    fn nested_activation_preempts_the_running_step() -> Result<(), QkKernelError> {
+        static QUEUE: qf::IsrQueue<1> = qf::IsrQueue::new(ActiveObjectId::new(2));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let cell = Arc::new(std::sync::OnceLock::new());
+        let low_id = ActiveObjectId::new(1);
+        let high_id = ActiveObjectId::new(2);
+
+        let low = new_active_object(
+            low_id,
+            2,
+            Interrupted { id: low_id, queue: &QUEUE, kernel: Arc::clone(&cell), log: Arc::clone(&log) },
+        );
+        let high = new_active_object(high_id, 5, Recorder::new(high_id, Arc::clone(&log)));
+        let kernel = Arc::new(QkKernel::builder().register(low)?.register(high)?.isr_queue(&QUEUE).build()?);
+        let _ = cell.set(Arc::clone(&kernel));
+        kernel.start();
+
+        kernel.post_and_run(low_id, DynEvent::empty_dyn(Signal(1)))?;
+
+        let entries = log.lock().unwrap();
+        assert_eq!(
+            entries.as_slice(),
+            &[(low_id, Signal(1)), (high_id, Signal(7)), (low_id, Signal(2))]
+        );
+        assert_eq!(kernel.scheduler().current_priority(), 0);
+        assert!(QUEUE.is_empty() && !kernel.has_pending_work());
+        Ok(())
+    }
+
+    /// Forwards every event to `target` through its context.
+    struct Forwarder {
+        target: ActiveObjectId,
+    }
+
+    impl SignalHandler for Forwarder {
+        fn handle_signal(&mut self, signal: Signal, ctx: &mut ActiveContext) {
+            ctx.post(self.target, DynEvent::empty_dyn(signal)).unwrap();
+        }
+    }
+
+    #[test]
+    fn contexts_post_through_the_dispatching_kernel() -> Result<(), QkKernelError> {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (low_id, high_id) = (ActiveObjectId::new(1), ActiveObjectId::new(2));
+        let low = new_active_object(low_id, 2, Forwarder { target: high_id });
+        let high = new_active_object(high_id, 5, Recorder::new(high_id, Arc::clone(&log)));
+        let kernel = QkKernel::builder().register(low)?.register(high)?.build()?;
+        kernel.start();
+
+        kernel.post_and_run(low_id, DynEvent::empty_dyn(Signal(3)))?;
+        assert_eq!(*log.lock().unwrap(), [(high_id, Signal(3))]);
+        assert!(!kernel.has_pending_work());
+        Ok(())
+    }
+
+    static BUDGET_NOW: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+    /// Spends `cost` budget-clock units, preempted halfway by a post to
+    /// `target` when there is one.
+    struct Busy {
+        cost: u32,
+        target: Option<ActiveObjectId>,
+        kernel: Arc<std::sync::OnceLock<Arc<QkKernel>>>,
+    }
+
+    impl SignalHandler for Busy {
+        fn handle_signal(&mut self, _signal: Signal, _ctx: &mut ActiveContext) {
+            use std::sync::atomic::Ordering::Relaxed;
+            BUDGET_NOW.fetch_add(self.cost / 2, Relaxed);
+            if let Some(target) = self.target {
+                let kernel = self.kernel.get().expect("kernel installed");
+                kernel.post(target, DynEvent::empty_dyn(Signal(8))).unwrap();
+                assert!(kernel.dispatch_once(), "higher priority should preempt");
+            }
+            BUDGET_NOW.fetch_add(self.cost - self.cost / 2, Relaxed);
+        }
+    }
+
+    #[test]
+    fn preempting_steps_are_not_charged_to_the_preempted_budget() -> Result<(), QkKernelError> {
+        qf::budget::set_budget_clock(|| BUDGET_NOW.load(std::sync::atomic::Ordering::Relaxed));
+        let cell = Arc::new(std::sync::OnceLock::new());
+        let low_id = ActiveObjectId::new(1);
+        let high_id = ActiveObjectId::new(2);
+        let low = Busy { cost: 20, target: Some(high_id), kernel: Arc::clone(&cell) };
+        let high = Busy { cost: 100, target: None, kernel: Arc::clone(&cell) };
+
+        let kernel = Arc::new(
+            QkKernel::builder()
+                .register(new_active_object(low_id, 2, low))?
+                .register(new_active_object(high_id, 5, high))?
+                .with_rtc_budget(2, 30)?
+                .with_rtc_budget(5, 50)?
+                .build()?,
+        );
+        let _ = cell.set(Arc::clone(&kernel));
+        kernel.start();
+
+        kernel.post(low_id, DynEvent::empty_dyn(Signal(1)))?;
+        kernel.run_until_idle();
+        assert_eq!(kernel.rtc_budgets().overruns(), 1, "only the preempting step overran");
+        assert!(matches!(
+            QkKernel::builder().with_rtc_budget(0, 10),
+            Err(QkKernelError::InvalidPriority { priority: 0, .. })
+        ));
+        Ok(())
+    }
+
     #[test]
     fn post_and_run_dispatches_event() -> Result<(), QkKernelError> {
         let log = Arc::new(Mutex::new(Vec::new()));
@@ -768,6 +1220,120 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn idle_callback_follows_the_idle_record_under_the_lock() -> Result<(), QkKernelError> {
+        use qf::idle::{IdleContext, InterruptLock};
+
+        static LOG: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+        fn log(entry: &'static str) {
+            LOG.lock().unwrap().push(entry);
+        }
+        fn on_idle(cx: IdleContext) {
+            assert!(cx.is_locked());
+            log("on_idle");
+        }
+        let lock = InterruptLock { lock: || 0, unlock: |_| log("unlock") };
+
+        let hook: TraceHook = Arc::new(|record, _payload: &[u8], _ts| {
+            if record == crate::scheduler::sched::IDLE {
+                log("SCHED_IDLE");
+            }
+            Ok(())
+        });
+        let log_ao = Arc::new(Mutex::new(Vec::new()));
+        let id = ActiveObjectId::new(8);
+        let ao = new_active_object(id, 4, Recorder::new(id, Arc::clone(&log_ao)));
+        let kernel = QkKernel::builder()
+            .register(ao)?
+            .with_trace_hook(hook)
+            .on_idle(on_idle)
+            .with_interrupt_lock(lock)
+            .build()?;
+        kernel.start();
+
+        kernel.post_and_run(id, DynEvent::empty_dyn(Signal(1)))?;
+        assert_eq!(*LOG.lock().unwrap(), ["SCHED_IDLE", "on_idle", "unlock"]);
+        assert_eq!(log_ao.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[cfg(feature = "qs")]
+    #[test]
+    fn nmi_breadcrumbs_are_merged_before_the_run() -> Result<(), QkKernelError> {
+        static NMI: qs::NmiTrace<4> = qs::NmiTrace::new();
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&records);
+        let hook: TraceHook = Arc::new(move |record, _payload: &[u8], _ts| {
+            sink.lock().unwrap().push(record);
+            Ok(())
+        });
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let id = ActiveObjectId::new(9);
+        let ao = new_active_object(id, 2, Recorder::new(id, Arc::clone(&log)));
+        let kernel = QkKernel::builder().register(ao)?.with_trace_hook(hook).with_nmi_trace(&NMI).build()?;
+        kernel.start();
+
+        NMI.breadcrumb(120, 7);
+        kernel.post_and_run(id, DynEvent::empty_dyn(Signal(1)))?;
+        let records = records.lock().unwrap();
+        let merged = records.iter().position(|&r| r == 120).expect("breadcrumb merged");
+        let dispatched = records.iter().position(|&r| r == crate::scheduler::sched::NEXT).expect("task scheduled");
+        assert!(merged < dispatched);
+        assert!(NMI.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn end_of_isr_posts_the_isr_queues_and_runs_the_target() -> Result<(), QkKernelError> {
+        static QUEUE: qf::IsrQueue<4> = qf::IsrQueue::new(ActiveObjectId::new(11));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let id = ActiveObjectId::new(11);
+        let ao = new_active_object(id, 3, Recorder::new(id, Arc::clone(&log)));
+        let kernel = QkKernel::builder().register(ao)?.isr_queue(&QUEUE).build()?;
+        kernel.start();
+
+        QUEUE.post_from_isr(DynEvent::empty_dyn(Signal(7))).unwrap();
+        QUEUE.post_from_isr(DynEvent::empty_dyn(Signal(8))).unwrap();
+        assert!(kernel.has_pending_work());
+        kernel.end_of_isr();
+        assert_eq!(*log.lock().unwrap(), [(id, Signal(7)), (id, Signal(8))]);
+        assert!(QUEUE.is_empty() && !kernel.has_pending_work());
+
+        static STRAY: qf::IsrQueue<1> = qf::IsrQueue::new(ActiveObjectId::new(99));
+        let ao = new_active_object(id, 3, Recorder::new(id, Arc::clone(&log)));
+        let result = QkKernel::builder().register(ao)?.isr_queue(&STRAY).build();
+        assert!(matches!(result, Err(QkKernelError::NotFound(target)) if target == ActiveObjectId::new(99)));
+        Ok(())
+    }
+
+    #[test]
+    fn activate_pending_runs_only_what_preempts_the_interrupted_task() -> Result<(), QkKernelError> {
+        static QUEUE: qf::IsrQueue<2> = qf::IsrQueue::new(ActiveObjectId::new(14));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (low_id, high_id) = (ActiveObjectId::new(13), ActiveObjectId::new(14));
+        let kernel = QkKernel::builder()
+            .register(new_active_object(low_id, 2, Recorder::new(low_id, Arc::clone(&log))))?
+            .register(new_active_object(high_id, 5, Recorder::new(high_id, Arc::clone(&log))))?
+            .isr_queue(&QUEUE)
+            .build()?;
+        kernel.start();
+
+        // The low-priority task is mid-step when the interrupt arrives.
+        kernel.scheduler().configure_active(2, 2);
+        kernel.post(low_id, DynEvent::empty_dyn(Signal(1)))?;
+        QUEUE.post_from_isr(DynEvent::empty_dyn(Signal(2))).unwrap();
+        kernel.activate_pending();
+        assert_eq!(*log.lock().unwrap(), [(high_id, Signal(2))]);
+        assert_eq!(kernel.scheduler().current_priority(), 2);
+
+        // Its step finishes; the queued event runs next.
+        kernel.scheduler().complete_execution(2);
+        kernel.scheduler().configure_active(0, 0);
+        kernel.activate_pending();
+        assert_eq!(log.lock().unwrap().len(), 2);
+        Ok(())
+    }
+
     #[test]
     fn register_prio_sets_threshold() -> Result<(), QkKernelError> {
         let log = Arc::new(Mutex::new(Vec::new()));
@@ -908,4 +1474,59 @@ mod tests {
         assert_eq!(max_concurrent, 1, "AO behavior was executed concurrently by multiple cores!");
         Ok(())
     }
+
+    #[test]
+    #[cfg(feature = "smp")]
+    fn test_smp_pinned_object_runs_only_on_its_core() -> Result<(), QkKernelError> {
+        use std::sync::mpsc;
+        use std::sync::{Arc, Mutex};
+        use qf::active::ActiveContext;
+
+        #[derive(Clone)]
+        struct CoreLog(Arc<Mutex<Vec<u8>>>);
+
+        impl SignalHandler for CoreLog {
+            fn handle_signal(&mut self, _signal: Signal, _ctx: &mut ActiveContext) {
+                self.0.lock().unwrap().push(qf::port::current_core_id());
+            }
+        }
+
+        let log = CoreLog(Arc::new(Mutex::new(Vec::new())));
+        let ao_id = ActiveObjectId::new(11);
+        let kernel = Arc::new(
+            QkKernel::builder()
+                .register(new_active_object(ao_id, 9, log.clone()))?
+                .build()?,
+        );
+        kernel.start();
+        assert!(matches!(
+            kernel.set_core_affinity(ao_id, 0),
+            Err(QkKernelError::NoCores(id)) if id == ao_id
+        ));
+
+        let (core_tx, core_rx) = mpsc::channel();
+        let (go_tx, go_rx) = mpsc::channel::<()>();
+        let worker = {
+            let kernel = Arc::clone(&kernel);
+            std::thread::spawn(move || {
+                core_tx.send(qf::port::current_core_id()).unwrap();
+                go_rx.recv().unwrap();
+                kernel.run_until_idle();
+            })
+        };
+        let pinned = core_rx.recv().unwrap();
+        assert_ne!(pinned, qf::port::current_core_id());
+        kernel.set_core_affinity(ao_id, 1 << pinned)?;
+
+        for i in 0..3 {
+            kernel.post(ao_id, DynEvent::empty_dyn(Signal(i)))?;
+        }
+        assert!(!kernel.has_pending_work(), "pinned object is not runnable here");
+        assert!(!kernel.dispatch_once());
+
+        go_tx.send(()).unwrap();
+        worker.join().unwrap();
+        assert_eq!(*log.0.lock().unwrap(), [pinned; 3]);
+        Ok(())
+    }
 }
@@ -17,6 +17,8 @@ switching.
 - [`QkScheduler`] / [`SchedStatus`] – O(1) ready-set scheduler (64-bit bitmap) with
   nested scheduler locking and priority ceiling.
 - [`QkTimerWheel`] – timer wheel driving [`qf`] time events under the QK kernel.
+- [`ThresholdAnalysis`] – which priorities the registered thresholds keep from
+  preempting which, and the configurations that defeat high priorities.
 
 ## QF vs QK
 
@@ -43,10 +45,14 @@ extern crate alloc;
 mod kernel;
 mod scheduler;
 mod sync;
+mod threshold;
 mod time;
 
 pub use kernel::{QkKernel, QkKernelBuilder, QkKernelError};
 pub use scheduler::{QkScheduler, SchedStatus};
+#[cfg(feature = "smp")]
+pub use scheduler::{CrossCoreHook, ALL_CORES};
+pub use threshold::{ThresholdAnalysis, ThresholdWarning};
 pub use time::{QkTimeEventError, QkTimerWheel};
 /// Host helper to wrap a built kernel into the timer-wheel's shareable handle
 /// (`Arc` dynamically, leaked `&'static` under `static-alloc` + `std`).
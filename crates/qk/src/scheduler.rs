@@ -1,19 +1,24 @@
 use crate::sync::Mutex;
 use qf::{ContextSwitchHook, TraceHook};
 
-#[cfg(feature = "qs")]
-use qs::records::sched;
-
-#[cfg(not(feature = "qs"))]
-mod sched {
-    pub const LOCK: u8 = 0;
-    pub const UNLOCK: u8 = 1;
-    pub const NEXT: u8 = 2;
-    pub const IDLE: u8 = 3;
-}
+pub(crate) use qs_protocol::records::sched;
 
 const SCHED_UNLOCKED: u8 = 0xFF;
 
+/// Affinity mask allowing every core.
+#[cfg(feature = "smp")]
+pub const ALL_CORES: u8 = 0xFF;
+
+/// Asks another core to reschedule, typically by raising a cross-core
+/// software interrupt on it. Called with the target core id after
+/// [`QkScheduler::mark_ready`] makes ready a priority that only other cores
+/// may run; the callee's handler then runs the kernel on that core.
+#[cfg(all(feature = "smp", not(feature = "static-alloc")))]
+pub type CrossCoreHook = crate::sync::Arc<dyn Fn(u8) + Send + Sync>;
+/// See the dynamic variant above; heap-free builds take a `'static` function.
+#[cfg(all(feature = "smp", feature = "static-alloc"))]
+pub type CrossCoreHook = &'static (dyn Fn(u8) + Send + Sync);
+
 /// Saved scheduler-lock status, returned by [`QkScheduler::lock`] and passed
 /// back to [`QkScheduler::unlock`] to restore the previous ceiling.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -115,6 +120,8 @@ struct State {
     cores: [CoreState; 8],
     ready: ReadySet,
     executing_cores: [u8; 64],
+    /// Per-priority bitmask of the cores allowed to run it.
+    affinity: [u8; 64],
 }
 
 #[cfg(feature = "smp")]
@@ -125,10 +132,26 @@ impl Default for State {
             cores: [CoreState::default(); 8],
             ready: ReadySet::default(),
             executing_cores: [0xFF; 64],
+            affinity: [ALL_CORES; 64],
         }
     }
 }
 
+#[cfg(feature = "smp")]
+impl State {
+    /// Whether `prio` may start on `core_id`: its affinity allows the core
+    /// and no other core has claimed it or is still running it.
+    fn runnable_on(&self, prio: u8, core_id: usize) -> bool {
+        if self.affinity[prio as usize] & (1 << core_id) == 0 {
+            return false;
+        }
+        let claimed = (0..8).any(|c| {
+            c != core_id && (self.cores[c].active_prio == prio || self.cores[c].next_prio == prio)
+        });
+        !claimed && self.executing_cores[prio as usize] == 0xFF
+    }
+}
+
 #[cfg(not(feature = "smp"))]
 #[derive(Default)]
 struct State {
@@ -147,6 +170,10 @@ pub struct ScheduleDecision {
     pub next_prio: u8,
     /// Priority of the active object that was running before this decision.
     pub previous_prio: u8,
+    /// `true` when `previous_prio` is interrupted mid-step rather than
+    /// finished: the decision came from [`QkScheduler::plan_activation`] while
+    /// a task was active.
+    pub preempts: bool,
 }
 
 /// O(1) priority scheduler for the QK kernel: a 64-bit ready-set bitmap plus
@@ -155,6 +182,8 @@ pub struct QkScheduler {
     state: Mutex<State>,
     trace: Mutex<Option<TraceHook>>,
     context_sw: Mutex<Option<ContextSwitchHook>>,
+    #[cfg(feature = "smp")]
+    cross_core: Mutex<Option<CrossCoreHook>>,
 }
 
 impl QkScheduler {
@@ -164,6 +193,8 @@ impl QkScheduler {
             state: Mutex::new(State::default()),
             trace: Mutex::new(trace),
             context_sw: Mutex::new(None),
+            #[cfg(feature = "smp")]
+            cross_core: Mutex::new(None),
         }
     }
 
@@ -178,6 +209,27 @@ impl QkScheduler {
         *self.context_sw.lock() = hook;
     }
 
+    /// Installs (or clears) the hook that asks another core to reschedule.
+    #[cfg(feature = "smp")]
+    pub fn set_cross_core_hook(&self, hook: Option<CrossCoreHook>) {
+        *self.cross_core.lock() = hook;
+    }
+
+    /// Restricts `prio` to the cores set in the `cores` bitmask (bit n is
+    /// core n). Priorities start out with [`ALL_CORES`].
+    #[cfg(feature = "smp")]
+    pub fn set_core_affinity(&self, prio: u8, cores: u8) {
+        ReadySet::assert_range(prio);
+        self.state.lock().affinity[prio as usize] = cores;
+    }
+
+    /// The cores allowed to run `prio`, as a bitmask.
+    #[cfg(feature = "smp")]
+    pub fn core_affinity(&self, prio: u8) -> u8 {
+        ReadySet::assert_range(prio);
+        self.state.lock().affinity[prio as usize]
+    }
+
     /// Locks the scheduler at the given priority ceiling.
     ///
     /// Prevents preemption by tasks with priority <= ceiling. Returns the
@@ -215,9 +267,44 @@ impl QkScheduler {
     }
 
     /// Marks the given priority as ready to run.
+    #[cfg(not(feature = "smp"))]
+    pub fn mark_ready(&self, prio: u8) {
+        let mut state = self.state.lock();
+        state.ready.insert(prio);
+    }
+
+    /// Marks the given priority as ready to run. If the calling core cannot
+    /// take it now, every other allowed core it would preempt is asked to
+    /// reschedule through the cross-core hook.
+    #[cfg(feature = "smp")]
     pub fn mark_ready(&self, prio: u8) {
+        let core_id = qf::port::current_core_id() as usize;
         let mut state = self.state.lock();
         state.ready.insert(prio);
+
+        let affinity = state.affinity[prio as usize];
+        let local = affinity & (1 << core_id) != 0 && prio > state.cores[core_id].active_threshold;
+        if local || prio <= state.lock_ceiling {
+            return;
+        }
+        let targets = (0..8)
+            .filter(|&c| c != core_id && affinity & (1 << c) != 0)
+            .filter(|&c| prio > state.cores[c].active_threshold)
+            .fold(0u8, |mask, c| mask | 1 << c);
+        drop(state);
+        if targets == 0 {
+            return;
+        }
+
+        #[cfg(not(feature = "static-alloc"))]
+        let hook = self.cross_core.lock().clone();
+        #[cfg(feature = "static-alloc")]
+        let hook = *self.cross_core.lock();
+        if let Some(hook) = hook {
+            for core in (0..8u8).filter(|c| targets & (1 << c) != 0) {
+                hook(core);
+            }
+        }
     }
 
     /// Clears the ready flag for the given priority.
@@ -262,6 +349,7 @@ impl QkScheduler {
 
     /// Selects the highest-priority ready task that may preempt the current one
     /// (above both the active threshold and the lock ceiling), or `None`.
+    /// Planning is not traced; the switch is, once the decision is committed.
     #[cfg(not(feature = "smp"))]
     pub fn plan_activation(&self) -> Option<ScheduleDecision> {
         let mut state = self.state.lock();
@@ -278,11 +366,13 @@ impl QkScheduler {
         Some(ScheduleDecision {
             next_prio: candidate,
             previous_prio: state.active_prio,
+            preempts: state.active_prio != 0,
         })
     }
 
     /// Selects the highest-priority ready task that may preempt the current one
     /// (above both the active threshold and the lock ceiling), or `None`.
+    /// Planning is not traced; the switch is, once the decision is committed.
     #[cfg(feature = "smp")]
     pub fn plan_activation(&self) -> Option<ScheduleDecision> {
         let core_id = qf::port::current_core_id() as usize;
@@ -292,22 +382,23 @@ impl QkScheduler {
 
         let mut found_candidate = None;
         for prio in (1..64).rev() {
-            if state.ready.contains(prio) && prio > active_threshold && prio > lock_ceiling {
-                let already_running = (0..8).any(|c| {
-                    c != core_id && (state.cores[c].active_prio == prio || state.cores[c].next_prio == prio)
-                }) || state.executing_cores[prio as usize] != 0xFF;
-                if !already_running {
-                    found_candidate = Some(prio);
-                    break;
-                }
+            if state.ready.contains(prio)
+                && prio > active_threshold
+                && prio > lock_ceiling
+                && state.runnable_on(prio, core_id)
+            {
+                found_candidate = Some(prio);
+                break;
             }
         }
 
         if let Some(candidate) = found_candidate {
             state.cores[core_id].next_prio = candidate;
+            let previous_prio = state.cores[core_id].active_prio;
             Some(ScheduleDecision {
                 next_prio: candidate,
-                previous_prio: state.cores[core_id].active_prio,
+                previous_prio,
+                preempts: previous_prio != 0,
             })
         } else {
             state.cores[core_id].next_prio = 0;
@@ -333,13 +424,12 @@ impl QkScheduler {
         let lock_ceiling = state.lock_ceiling;
 
         for prio in (1..64).rev() {
-            if state.ready.contains(prio) && prio > active_threshold && prio > lock_ceiling {
-                let already_running = (0..8).any(|c| {
-                    c != core_id && (state.cores[c].active_prio == prio || state.cores[c].next_prio == prio)
-                }) || state.executing_cores[prio as usize] != 0xFF;
-                if !already_running {
-                    return true;
-                }
+            if state.ready.contains(prio)
+                && prio > active_threshold
+                && prio > lock_ceiling
+                && state.runnable_on(prio, core_id)
+            {
+                return true;
             }
         }
         false
@@ -363,6 +453,7 @@ impl QkScheduler {
         Some(ScheduleDecision {
             next_prio: candidate,
             previous_prio: state.active_prio,
+            preempts: false,
         })
     }
 
@@ -376,14 +467,13 @@ impl QkScheduler {
 
         let mut found_candidate = None;
         for prio in (1..64).rev() {
-            if state.ready.contains(prio) && prio > initial_threshold && prio > lock_ceiling {
-                let already_running = (0..8).any(|c| {
-                    c != core_id && (state.cores[c].active_prio == prio || state.cores[c].next_prio == prio)
-                }) || state.executing_cores[prio as usize] != 0xFF;
-                if !already_running {
-                    found_candidate = Some(prio);
-                    break;
-                }
+            if state.ready.contains(prio)
+                && prio > initial_threshold
+                && prio > lock_ceiling
+                && state.runnable_on(prio, core_id)
+            {
+                found_candidate = Some(prio);
+                break;
             }
         }
 
@@ -392,6 +482,7 @@ impl QkScheduler {
             Some(ScheduleDecision {
                 next_prio: candidate,
                 previous_prio: state.cores[core_id].active_prio,
+                preempts: false,
             })
         } else {
             state.cores[core_id].next_prio = 0;
@@ -400,7 +491,8 @@ impl QkScheduler {
     }
 
     /// Commits a planned [`ScheduleDecision`], making `next_prio` the active
-    /// priority with the given threshold and emitting a `NEXT` trace record.
+    /// priority with the given threshold and emitting a `PREEMPT` trace record
+    /// when it interrupts a running task, `NEXT` otherwise.
     #[cfg(not(feature = "smp"))]
     pub fn commit_activation(&self, decision: &ScheduleDecision, next_threshold: u8) {
         let mut state = self.state.lock();
@@ -413,13 +505,15 @@ impl QkScheduler {
         drop(state);
 
         if decision.next_prio != previous {
-            self.emit_record(sched::NEXT, &[decision.next_prio, previous], true);
+            let record = if decision.preempts { sched::PREEMPT } else { sched::NEXT };
+            self.emit_record(record, &[decision.next_prio, previous], true);
             self.emit_context_sw(previous, decision.next_prio);
         }
     }
 
     /// Commits a planned [`ScheduleDecision`], making `next_prio` the active
-    /// priority with the given threshold and emitting a `NEXT` trace record.
+    /// priority with the given threshold and emitting a `PREEMPT` trace record
+    /// when it interrupts a running task, `NEXT` otherwise.
     #[cfg(feature = "smp")]
     pub fn commit_activation(&self, decision: &ScheduleDecision, next_threshold: u8) {
         let core_id = qf::port::current_core_id() as usize;
@@ -436,13 +530,14 @@ impl QkScheduler {
         drop(state);
 
         if decision.next_prio != previous {
-            self.emit_record(sched::NEXT, &[decision.next_prio, previous], true);
+            let record = if decision.preempts { sched::PREEMPT } else { sched::NEXT };
+            self.emit_record(record, &[decision.next_prio, previous], true);
             self.emit_context_sw(previous, decision.next_prio);
         }
     }
 
     /// Restores the active priority and threshold after a preemption returns,
-    /// emitting a `NEXT` or `IDLE` trace record as appropriate.
+    /// emitting a `RESTORE` (resumed task) or `IDLE` trace record.
     #[cfg(not(feature = "smp"))]
     pub fn restore_active(&self, prio: u8, threshold: u8) {
         let mut state = self.state.lock();
@@ -458,13 +553,13 @@ impl QkScheduler {
                 self.emit_context_sw(previous, 0);
             }
         } else if prio != previous {
-            self.emit_record(sched::NEXT, &[prio, previous], true);
+            self.emit_record(sched::RESTORE, &[prio, previous], true);
             self.emit_context_sw(previous, prio);
         }
     }
 
     /// Restores the active priority and threshold after a preemption returns,
-    /// emitting a `NEXT` or `IDLE` trace record as appropriate.
+    /// emitting a `RESTORE` (resumed task) or `IDLE` trace record.
     #[cfg(feature = "smp")]
     pub fn restore_active(&self, prio: u8, threshold: u8) {
         let core_id = qf::port::current_core_id() as usize;
@@ -481,7 +576,7 @@ impl QkScheduler {
                 self.emit_context_sw(previous, 0);
             }
         } else if prio != previous {
-            self.emit_record(sched::NEXT, &[prio, previous], true);
+            self.emit_record(sched::RESTORE, &[prio, previous], true);
             self.emit_context_sw(previous, prio);
         }
     }
@@ -644,6 +739,59 @@ mod tests {
         assert_eq!(recorded[1], (sched::IDLE, vec![4], true));
     }
 
+    #[test]
+    fn preemption_is_traced_as_preempt_and_restore() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let hook_records = Arc::clone(&records);
+        let hook: TraceHook = Arc::new(move |id, payload, timestamp| {
+            hook_records.lock().push((id, payload.to_vec(), timestamp));
+            Ok(())
+        });
+
+        let scheduler = QkScheduler::new(Some(hook));
+        scheduler.configure_active(2, 2);
+        scheduler.mark_ready(5);
+
+        let decision = scheduler.plan_activation().expect("priority 5 should preempt");
+        assert!(decision.preempts);
+        scheduler.commit_activation(&decision, 5);
+        scheduler.mark_not_ready(5);
+        assert!(scheduler.next_after_dispatch(2).is_none());
+        scheduler.restore_active(2, 2);
+
+        let recorded = records.lock();
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(recorded[0], (sched::PREEMPT, vec![5, 2], true));
+        assert_eq!(recorded[1], (sched::RESTORE, vec![2, 5], true));
+    }
+
+    #[test]
+    fn follow_up_after_dispatch_is_traced_as_next() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let hook_records = Arc::clone(&records);
+        let hook: TraceHook = Arc::new(move |id, payload, timestamp| {
+            hook_records.lock().push((id, payload.to_vec(), timestamp));
+            Ok(())
+        });
+
+        let scheduler = QkScheduler::new(Some(hook));
+        scheduler.configure_active(0, 0);
+        scheduler.mark_ready(6);
+        scheduler.mark_ready(3);
+
+        let first = scheduler.plan_activation().expect("priority 6 should run");
+        assert!(!first.preempts);
+        scheduler.commit_activation(&first, 6);
+        scheduler.mark_not_ready(6);
+        let follow_up = scheduler.next_after_dispatch(0).expect("priority 3 should follow");
+        assert!(!follow_up.preempts);
+        scheduler.commit_activation(&follow_up, 3);
+
+        let recorded = records.lock();
+        assert_eq!(recorded[0], (sched::NEXT, vec![6, 0], true));
+        assert_eq!(recorded[1], (sched::NEXT, vec![3, 6], true));
+    }
+
     // The context-switch hook is a `&'static` function object under
     // `static-alloc` (no allocator), so these `Arc`-closure tests are
     // dynamic-only; the hook firing logic is identical on both builds.
@@ -759,4 +907,31 @@ mod tests {
         scheduler.mark_ready(2);
         assert!(!scheduler.has_ready_to_run());
     }
+
+    #[test]
+    #[cfg(all(feature = "smp", not(feature = "static-alloc")))]
+    fn pinned_priority_is_left_to_its_core_and_requested_there() {
+        let this = qf::port::current_core_id();
+        let other = (this + 1) % 8;
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&requests);
+        let hook: CrossCoreHook = Arc::new(move |core| sink.lock().push(core));
+
+        let scheduler = QkScheduler::new(None);
+        scheduler.set_cross_core_hook(Some(hook));
+        scheduler.set_core_affinity(6, 1 << other);
+        assert_eq!(scheduler.core_affinity(6), 1 << other);
+        assert_eq!(scheduler.core_affinity(5), ALL_CORES);
+
+        scheduler.mark_ready(6);
+        assert_eq!(*requests.lock(), [other]);
+        assert!(!scheduler.has_ready_to_run());
+        assert!(scheduler.plan_activation().is_none());
+
+        // A priority this core may run is taken locally: no request.
+        scheduler.mark_ready(5);
+        assert_eq!(*requests.lock(), [other]);
+        let decision = scheduler.plan_activation().expect("priority 5 runs here");
+        assert_eq!(decision.next_prio, 5);
+    }
 }
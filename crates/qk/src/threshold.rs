@@ -0,0 +1,271 @@
+//! Preemption-threshold analysis.
+//!
+//! A threshold `T` on the AO at priority `P` keeps every priority in
+//! `P+1..=T` from preempting it. That saves stack and context switches, but
+//! every priority a threshold shields an AO from has to wait out that AO's
+//! whole run-to-completion step. [`ThresholdAnalysis`] makes the effect of a
+//! set of registrations visible before the kernel runs:
+//!
+//! - [`blocked`](ThresholdAnalysis::blocked) lists the registered priorities
+//!   that can never preempt a given AO;
+//! - [`groups`](ThresholdAnalysis::groups) merges AOs linked that way into
+//!   the effective non-preemption groups;
+//! - [`warnings`](ThresholdAnalysis::warnings) flags configurations that
+//!   defeat the point of priorities, and
+//! - its `Display` prints all of it as a table.
+//!
+//! [`QkKernelBuilder::on_threshold_warning`](crate::QkKernelBuilder::on_threshold_warning)
+//! runs the analysis when the kernel is built. It needs no heap.
+
+use core::fmt;
+use core::ops::RangeInclusive;
+
+const PRIORITIES: usize = 64;
+
+/// A configuration the analysis considers harmful or pointless.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThresholdWarning {
+    /// The threshold reaches the highest registered priority, so even the
+    /// most urgent AO waits for every step of this one.
+    HighestBlocked { priority: u8, threshold: u8, highest: u8 },
+    /// No registered AO can preempt any other: the kernel schedules like the
+    /// cooperative QV kernel.
+    Cooperative { lowest: u8, highest: u8 },
+    /// The threshold is above the priority but shields the AO from no
+    /// registered priority.
+    NoEffect { priority: u8, threshold: u8 },
+}
+
+impl fmt::Display for ThresholdWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::HighestBlocked { priority, threshold, highest } => write!(
+                f,
+                "threshold {threshold} of priority {priority} blocks the highest priority {highest} \
+                 for whole steps"
+            ),
+            Self::Cooperative { lowest, highest } => write!(
+                f,
+                "priorities {lowest}..={highest} never preempt each other; \
+                 the kernel runs cooperatively"
+            ),
+            Self::NoEffect { priority, threshold } => write!(
+                f,
+                "threshold {threshold} of priority {priority} shields it from no registered priority"
+            ),
+        }
+    }
+}
+
+/// Which registered priorities can preempt which, given their thresholds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThresholdAnalysis {
+    /// Bit `p` set when an AO is registered at priority `p`.
+    registered: u64,
+    thresholds: [u8; PRIORITIES],
+}
+
+impl ThresholdAnalysis {
+    /// Analyzes `(priority, threshold)` pairs; priorities outside `1..=63`
+    /// are ignored, and a threshold below its priority counts as the
+    /// priority.
+    pub fn new(registrations: impl IntoIterator<Item = (u8, u8)>) -> Self {
+        let mut analysis = Self { registered: 0, thresholds: [0; PRIORITIES] };
+        for (priority, threshold) in registrations {
+            if priority == 0 || usize::from(priority) >= PRIORITIES {
+                continue;
+            }
+            analysis.registered |= 1 << priority;
+            analysis.thresholds[usize::from(priority)] = threshold.max(priority);
+        }
+        analysis
+    }
+
+    /// The registered priorities, lowest first.
+    pub fn priorities(&self) -> impl Iterator<Item = u8> + '_ {
+        (1..PRIORITIES as u8).filter(|&p| self.is_registered(p))
+    }
+
+    /// The threshold of the AO at `priority`, if one is registered there.
+    pub fn threshold(&self, priority: u8) -> Option<u8> {
+        self.is_registered(priority).then(|| self.thresholds[usize::from(priority)])
+    }
+
+    /// The registered priorities above `priority` that can never preempt it.
+    pub fn blocked(&self, priority: u8) -> impl Iterator<Item = u8> + '_ {
+        let threshold = self.threshold(priority).unwrap_or(priority);
+        self.priorities().filter(move |&q| q > priority && q <= threshold)
+    }
+
+    /// `true` if the AO at `high` can preempt the one at `low`.
+    pub fn can_preempt(&self, high: u8, low: u8) -> bool {
+        match (self.threshold(high), self.threshold(low)) {
+            (Some(_), Some(threshold)) => high > threshold,
+            _ => false,
+        }
+    }
+
+    /// The effective non-preemption groups, lowest first: registered
+    /// priorities chained together because a lower one's threshold covers a
+    /// higher one. Every AO is in exactly one group; a fully preemptible AO
+    /// is a group of its own.
+    pub fn groups(&self) -> impl Iterator<Item = RangeInclusive<u8>> + '_ {
+        let mut next = self.priorities().peekable();
+        core::iter::from_fn(move || {
+            let lowest = next.next()?;
+            let mut highest = lowest;
+            let mut reach = self.thresholds[usize::from(lowest)];
+            while let Some(&q) = next.peek() {
+                if q > reach {
+                    break;
+                }
+                next.next();
+                highest = q;
+                reach = reach.max(self.thresholds[usize::from(q)]);
+            }
+            Some(lowest..=highest)
+        })
+    }
+
+    /// The group `priority` belongs to.
+    pub fn group_of(&self, priority: u8) -> Option<RangeInclusive<u8>> {
+        self.groups().find(|group| group.contains(&priority))
+    }
+
+    /// Everything the analysis found, in priority order.
+    pub fn warnings(&self) -> impl Iterator<Item = ThresholdWarning> + '_ {
+        let highest = self.priorities().last().unwrap_or(0);
+        let lowest = self.priorities().next().unwrap_or(0);
+        let below_highest = || self.priorities().filter(move |&p| p < highest);
+        let cooperative = below_highest().next().is_some()
+            && below_highest().all(|p| self.thresholds[usize::from(p)] >= highest);
+
+        let cooperative_warning =
+            cooperative.then_some(ThresholdWarning::Cooperative { lowest, highest });
+        let per_priority = self.priorities().filter_map(move |priority| {
+            let threshold = self.thresholds[usize::from(priority)];
+            if threshold == priority {
+                None
+            } else if priority < highest && threshold >= highest {
+                (!cooperative)
+                    .then_some(ThresholdWarning::HighestBlocked { priority, threshold, highest })
+            } else if self.blocked(priority).next().is_none() {
+                Some(ThresholdWarning::NoEffect { priority, threshold })
+            } else {
+                None
+            }
+        });
+        cooperative_warning.into_iter().chain(per_priority)
+    }
+
+    fn is_registered(&self, priority: u8) -> bool {
+        usize::from(priority) < PRIORITIES && self.registered & (1 << priority) != 0
+    }
+}
+
+/// One row per registered AO, then the warnings:
+///
+/// ```text
+/// prio  thr  group   not preempted by
+///    2    4  2..=3   3
+///    3    3  2..=3   -
+///    6    6  6..=6   -
+/// ```
+impl fmt::Display for ThresholdAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "prio  thr  group   not preempted by")?;
+        for priority in self.priorities() {
+            let threshold = self.thresholds[usize::from(priority)];
+            let group = self.group_of(priority).unwrap_or(priority..=priority);
+            write!(f, "{priority:>4} {threshold:>4}  {:<7} ", Range(&group))?;
+            let mut blocked = self.blocked(priority);
+            match blocked.next() {
+                None => write!(f, "-")?,
+                Some(first) => {
+                    write!(f, "{first}")?;
+                    for q in blocked {
+                        write!(f, ",{q}")?;
+                    }
+                }
+            }
+            writeln!(f)?;
+        }
+        for warning in self.warnings() {
+            writeln!(f, "warning: {warning}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats a group as `lo..=hi`, honouring width and alignment.
+struct Range<'a>(&'a RangeInclusive<u8>);
+
+impl fmt::Display for Range<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (lo, hi) = (*self.0.start(), *self.0.end());
+        let width = digits(lo) + 3 + digits(hi);
+        let pad = f.width().unwrap_or(0).saturating_sub(width);
+        write!(f, "{lo}..={hi}")?;
+        for _ in 0..pad {
+            f.write_str(" ")?;
+        }
+        Ok(())
+    }
+}
+
+fn digits(n: u8) -> usize {
+    match n {
+        0..=9 => 1,
+        10..=99 => 2,
+        _ => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_follow_threshold_chains() {
+        // 1 shields itself from 2, 2 from 3; 5 is preemptible; 6 from 7.
+        let analysis = ThresholdAnalysis::new([(1, 2), (2, 3), (3, 3), (5, 5), (6, 7), (7, 7)]);
+        let groups: [_; 3] = [1..=3, 5..=5, 6..=7];
+        assert!(analysis.groups().eq(groups));
+        assert!(analysis.blocked(1).eq([2]));
+        assert!(analysis.can_preempt(3, 1));
+        assert!(!analysis.can_preempt(2, 1));
+        assert!(!analysis.can_preempt(7, 6));
+        // 6..=7 holds the highest priority: 6 blocks it.
+        assert!(analysis.warnings().eq([ThresholdWarning::HighestBlocked {
+            priority: 6,
+            threshold: 7,
+            highest: 7,
+        }]));
+    }
+
+    #[test]
+    fn flat_thresholds_are_reported_as_cooperative() {
+        let analysis = ThresholdAnalysis::new([(2, 9), (4, 9), (9, 9)]);
+        assert!(analysis.warnings().eq([ThresholdWarning::Cooperative { lowest: 2, highest: 9 }]));
+    }
+
+    #[test]
+    fn thresholds_over_empty_priorities_do_nothing() {
+        let analysis = ThresholdAnalysis::new([(2, 4), (5, 5)]);
+        assert!(analysis.warnings().eq([ThresholdWarning::NoEffect { priority: 2, threshold: 4 }]));
+        assert_eq!(analysis.group_of(2), Some(2..=2));
+    }
+
+    #[test]
+    fn table_lists_every_priority() {
+        let analysis = ThresholdAnalysis::new([(2, 4), (3, 3), (6, 6)]);
+        let table = std::format!("{analysis}");
+        assert_eq!(
+            table,
+            "prio  thr  group   not preempted by\n   \
+             2    4  2..=3   3\n   \
+             3    3  2..=3   -\n   \
+             6    6  6..=6   -\n"
+        );
+    }
+}
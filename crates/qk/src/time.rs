@@ -2,9 +2,9 @@
 use alloc::vec::Vec;
 use core::fmt;
 
-use qf::time::TimeEventRef;
+use qf::time::{RateBucket, TimeEventRef};
 #[cfg(feature = "static-alloc")]
-use qf::time::{MAX_TICK_RATES, MAX_TIMERS_PER_RATE};
+use qf::time::MAX_TICK_RATES;
 use qf::TraceHook;
 
 use crate::kernel::{QkKernel, QkKernelError};
@@ -33,11 +33,6 @@ pub fn share_kernel(kernel: QkKernel) -> QkKernelRef {
     alloc::boxed::Box::leak(alloc::boxed::Box::new(kernel))
 }
 
-#[cfg(not(feature = "static-alloc"))]
-type RateBucket = Vec<TimeEventRef>;
-#[cfg(feature = "static-alloc")]
-type RateBucket = heapless::Vec<TimeEventRef, MAX_TIMERS_PER_RATE>;
-
 #[cfg(not(feature = "static-alloc"))]
 type WheelEvents = Vec<RateBucket>;
 #[cfg(feature = "static-alloc")]
@@ -52,6 +47,7 @@ pub struct QkTimerWheel {
     kernel: QkKernelRef,
     events: WheelEvents,
     trace: Option<TraceHook>,
+    jitter_period: u32,
 }
 
 impl QkTimerWheel {
@@ -63,7 +59,7 @@ impl QkTimerWheel {
         let events = {
             let mut events = Vec::with_capacity(4);
             for _ in 0..4 {
-                events.push(Vec::new());
+                events.push(RateBucket::new());
             }
             events
         };
@@ -83,46 +79,72 @@ impl QkTimerWheel {
             kernel,
             events,
             trace,
+            jitter_period: 0,
         }
     }
 
+    /// Emits the [jitter](qf::jitter) of every registered time event every
+    /// `ticks` rate-0 ticks; `0` stops the reports.
+    pub fn report_jitter_every(&mut self, ticks: u32) {
+        self.jitter_period = ticks;
+    }
+
+    /// Emits the [jitter](qf::jitter) of every registered time event now.
+    pub fn report_jitter(&self) {
+        self.events.iter().for_each(RateBucket::report_jitter);
+    }
+
     /// Registers a time event with the wheel, wiring up the wheel's trace hook.
     pub fn register(&mut self, event: TimeEventRef) {
         event.set_trace(self.trace.clone());
         let rate = event.tick_rate() as usize;
         #[cfg(feature = "static-alloc")]
-        {
-            if rate >= self.events.len() {
-                qf::fusa::on_error(module_path!(), line!());
-            }
-            if self.events[rate].push(event).is_err() {
-                qf::fusa::on_error(module_path!(), line!());
-            }
+        if rate >= self.events.len() {
+            qf::fusa::on_error(module_path!(), line!());
         }
         #[cfg(not(feature = "static-alloc"))]
-        if rate < self.events.len() {
-            self.events[rate].push(event);
-        } else {
-            while self.events.len() <= rate {
-                self.events.push(Vec::new());
-            }
-            self.events[rate].push(event);
+        while self.events.len() <= rate {
+            self.events.push(RateBucket::new());
+        }
+        if self.events[rate].push(event).is_err() {
+            qf::fusa::on_error(module_path!(), line!());
         }
     }
 
     /// Advances the wheel for the specified `tick_rate` domain by one tick, posting any events that have expired.
     pub fn tick_rate(&self, tick_rate: u8) -> Result<(), QkTimeEventError> {
-        let rate = tick_rate as usize;
-        if rate < self.events.len() {
-            for event in &self.events[rate] {
+        if tick_rate == 0 {
+            self.kernel.clock().advance();
+        }
+        if let Some(bucket) = self.events.get(tick_rate as usize) {
+            bucket.count_tick(tick_rate, self.trace.as_ref());
+            for event in bucket.events() {
                 if let Some((target, evt)) = event.poll() {
-                    self.kernel.post_and_run(target, evt)?;
+                    qf::jitter::fired(event, target, evt.header.signal);
+                    #[cfg(not(feature = "static-alloc"))]
+                    let _timer = qf::replay::TimerPostScope::enter();
+                    if qf::isr::in_isr() {
+                        // The handler's exit (end_of_isr or the port's
+                        // PendSV) runs whatever this readied.
+                        self.kernel.post(target, evt)?;
+                    } else {
+                        self.kernel.post_and_run(target, evt)?;
+                    }
                 }
             }
         }
+        if tick_rate == 0 && self.jitter_period != 0 && self.tick_count(0).is_multiple_of(self.jitter_period) {
+            self.report_jitter();
+        }
         Ok(())
     }
 
+    /// Ticks processed so far by the `tick_rate` domain (wrapping); the
+    /// counter traced in `QS_QF_TICK`.
+    pub fn tick_count(&self, tick_rate: u8) -> u32 {
+        self.events.get(tick_rate as usize).map_or(0, RateBucket::tick_count)
+    }
+
     /// Advances the default (tick_rate 0) wheel by one tick.
     pub fn tick(&self) -> Result<(), QkTimeEventError> {
         self.tick_rate(0)
@@ -140,7 +162,8 @@ impl QkTimerWheel {
         self.tick_rate(tick_rate)
     }
 
-    /// Advance the default timer wheel from an ISR context.
+    /// Advance the default timer wheel from an ISR context. Expired events
+    /// are posted, not run.
     ///
     /// Caller must have called `qf::qk_isr_entry!()` before this.
     /// Corresponds to `QTimeEvt::tickFromISR_()` in QP/C++.
@@ -154,15 +177,7 @@ impl QkTimerWheel {
 
     /// Returns `true` if there are no armed time events in the specified `tick_rate` domain.
     pub fn no_active(&self, tick_rate: u8) -> bool {
-        let rate = tick_rate as usize;
-        if rate < self.events.len() {
-            for event in &self.events[rate] {
-                if event.is_armed() {
-                    return false;
-                }
-            }
-        }
-        true
+        self.events.get(tick_rate as usize).is_none_or(RateBucket::no_active)
     }
 }
 
@@ -205,7 +220,7 @@ mod tests {
         new_active_object, ActiveContext, ActiveObjectId, ActiveObjectRef, SignalHandler,
     };
     use qf::event::Signal;
-    use qf::time::{new_time_event, TimeEventConfig, TimeEventTraceInfo};
+    use qf::time::{new_time_event, TickDuration, TimeEventConfig, TimeEventTraceInfo};
 
     #[derive(Clone)]
     struct Recorder {
@@ -275,7 +290,7 @@ mod tests {
         let mut wheel = QkTimerWheel::new(kernel.clone());
         wheel.register(event.clone());
 
-        event.arm(1, Some(2));
+        event.arm(1, Some(TickDuration::from_ticks(2)));
 
         for _ in 0..5 {
             wheel.tick().expect("tick should succeed");
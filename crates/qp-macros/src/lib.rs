@@ -0,0 +1,490 @@
+//! Declarative state machines for `qf::QHsm`.
+//!
+//! [`hsm!`] turns a description of states and transitions into the
+//! `StateHandler` functions that `QHsm` runs. The expansion names `::qf`
+//! paths, plus `::qs` when a dictionary is requested.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    braced, parenthesized, parse_macro_input, Attribute, Block, Expr, Ident, Token, Type,
+    Visibility,
+};
+
+mod kw {
+    syn::custom_keyword!(machine);
+    syn::custom_keyword!(initial);
+    syn::custom_keyword!(state);
+    syn::custom_keyword!(parent);
+    syn::custom_keyword!(entry);
+    syn::custom_keyword!(exit);
+    syn::custom_keyword!(on);
+    syn::custom_keyword!(history);
+    syn::custom_keyword!(deep_history);
+    syn::custom_keyword!(dictionary);
+}
+
+/// Declares a hierarchical state machine over a data type.
+///
+/// ```ignore
+/// qp_macros::hsm! {
+///     #[dictionary]
+///     pub machine philo for PhiloData {
+///         initial { sm.arm_think_timer(); } => thinking;
+///
+///         state thinking {
+///             entry { sm.arm_think_timer(); }
+///             on TIMEOUT => hungry;
+///         }
+///
+///         state hungry {
+///             entry { sm.post_hungry(); }
+///             on EAT if sm.is_mine(e) => eating;
+///         }
+///
+///         state eating {
+///             parent top;
+///             exit { sm.post_done(); }
+///             on TIMEOUT => thinking;
+///         }
+///     }
+/// }
+///
+/// let hsm: QHsm<PhiloData> = philo::new(PhiloData::default());
+/// ```
+///
+/// The machine becomes a module holding one handler per state, `initial`
+/// (the initial pseudo-state) and `new`, which wraps the data in a `QHsm`.
+/// Inside a state:
+///
+/// - `parent s;` makes `s` the superstate. The default is `top`, the
+///   `QHsm` top state.
+/// - `entry { .. }` and `exit { .. }` run on entry and exit.
+/// - `initial { .. } => s;` is the initial transition of a composite state.
+///   The action block is optional.
+/// - `on SIG if guard { .. } => target;` handles `SIG`. The guard and the
+///   action are optional. A target is a state, `history(s)` (the last
+///   active direct substate of `s`) or `deep_history(s)` (the last active
+///   leaf below `s`). Without a target the event is handled in place.
+///   Rules are tried in order. If none matches, the event goes to the
+///   superstate. A guarded transition to a state that is the last rule for
+///   its signal becomes `QHsmResult::TranIf`, so the guard is traced.
+///
+/// Blocks and guards see the data as `sm` and the event as `e`. Transition
+/// targets and parents must be states of the same machine, which is checked
+/// at compile time.
+///
+/// With `#[dictionary]` the module also gets `emit_dictionary(hook)`. It
+/// sends a `QS_FUN_DICT` record for each handler, named `machine::state`, so
+/// QSpy shows state names. This needs the `qs` crate.
+#[proc_macro]
+pub fn hsm(input: TokenStream) -> TokenStream {
+    let machine = parse_macro_input!(input as Machine);
+    match machine.expand() {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct Machine {
+    attrs: Vec<Attribute>,
+    dictionary: bool,
+    vis: Visibility,
+    name: Ident,
+    data: Type,
+    initial: Initial,
+    states: Vec<State>,
+}
+
+struct Initial {
+    action: Option<Block>,
+    target: Ident,
+}
+
+struct State {
+    attrs: Vec<Attribute>,
+    name: Ident,
+    parent: Option<Ident>,
+    entry: Option<Block>,
+    exit: Option<Block>,
+    initial: Option<Initial>,
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    signal: Expr,
+    guard: Option<Expr>,
+    action: Option<Block>,
+    target: Option<Target>,
+}
+
+enum Target {
+    State(Ident),
+    History(Ident),
+    DeepHistory(Ident),
+}
+
+impl Parse for Initial {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<kw::initial>()?;
+        let action = if input.peek(syn::token::Brace) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        input.parse::<Token![=>]>()?;
+        let target = input.parse()?;
+        input.parse::<Token![;]>()?;
+        Ok(Self { action, target })
+    }
+}
+
+impl Parse for Target {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(kw::history) && input.peek2(syn::token::Paren) {
+            input.parse::<kw::history>()?;
+            let content;
+            parenthesized!(content in input);
+            Ok(Self::History(content.parse()?))
+        } else if input.peek(kw::deep_history) && input.peek2(syn::token::Paren) {
+            input.parse::<kw::deep_history>()?;
+            let content;
+            parenthesized!(content in input);
+            Ok(Self::DeepHistory(content.parse()?))
+        } else {
+            Ok(Self::State(input.parse()?))
+        }
+    }
+}
+
+impl Parse for Rule {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<kw::on>()?;
+        let signal = Expr::parse_without_eager_brace(input)?;
+        let guard = if input.peek(Token![if]) {
+            input.parse::<Token![if]>()?;
+            Some(Expr::parse_without_eager_brace(input)?)
+        } else {
+            None
+        };
+        let action: Option<Block> = if input.peek(syn::token::Brace) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        let target = if input.peek(Token![=>]) {
+            input.parse::<Token![=>]>()?;
+            let target = input.parse()?;
+            input.parse::<Token![;]>()?;
+            Some(target)
+        } else {
+            if action.is_none() || input.peek(Token![;]) {
+                input.parse::<Token![;]>()?;
+            }
+            None
+        };
+        Ok(Self {
+            signal,
+            guard,
+            action,
+            target,
+        })
+    }
+}
+
+impl Parse for State {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        input.parse::<kw::state>()?;
+        let name: Ident = input.parse()?;
+        let body;
+        braced!(body in input);
+
+        let mut state = State {
+            attrs,
+            name,
+            parent: None,
+            entry: None,
+            exit: None,
+            initial: None,
+            rules: Vec::new(),
+        };
+        while !body.is_empty() {
+            let lookahead = body.lookahead1();
+            if lookahead.peek(kw::parent) {
+                let keyword = body.parse::<kw::parent>()?;
+                let parent = body.parse()?;
+                body.parse::<Token![;]>()?;
+                set_once(&mut state.parent, parent, keyword.span, "parent")?;
+            } else if lookahead.peek(kw::entry) {
+                let keyword = body.parse::<kw::entry>()?;
+                set_once(&mut state.entry, body.parse()?, keyword.span, "entry")?;
+            } else if lookahead.peek(kw::exit) {
+                let keyword = body.parse::<kw::exit>()?;
+                set_once(&mut state.exit, body.parse()?, keyword.span, "exit")?;
+            } else if lookahead.peek(kw::initial) {
+                let span = body.span();
+                set_once(&mut state.initial, body.parse()?, span, "initial")?;
+            } else if lookahead.peek(kw::on) {
+                state.rules.push(body.parse()?);
+            } else {
+                return Err(lookahead.error());
+            }
+        }
+        Ok(state)
+    }
+}
+
+impl Parse for Machine {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attrs = input.call(Attribute::parse_outer)?;
+        let before = attrs.len();
+        attrs.retain(|attr| !attr.path().is_ident("dictionary"));
+        let dictionary = attrs.len() != before;
+        let vis = input.parse()?;
+        input.parse::<kw::machine>()?;
+        let name = input.parse()?;
+        input.parse::<Token![for]>()?;
+        let data = input.parse()?;
+        let body;
+        braced!(body in input);
+
+        if !body.peek(kw::initial) {
+            return Err(
+                body.error("a machine starts with its initial transition: `initial => state;`")
+            );
+        }
+        let initial = body.parse()?;
+        let mut states = Vec::new();
+        while !body.is_empty() {
+            states.push(body.parse()?);
+        }
+        Ok(Self {
+            attrs,
+            dictionary,
+            vis,
+            name,
+            data,
+            initial,
+            states,
+        })
+    }
+}
+
+fn set_once<T>(slot: &mut Option<T>, value: T, span: Span, what: &str) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(syn::Error::new(span, format!("`{what}` is given twice")));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+impl Machine {
+    fn expand(&self) -> syn::Result<TokenStream2> {
+        self.check()?;
+
+        let Self {
+            attrs,
+            vis,
+            name,
+            data,
+            ..
+        } = self;
+        let sm = Ident::new("sm", Span::call_site());
+        let e = Ident::new("e", Span::call_site());
+        let result = quote!(::qf::hsm::QHsmResult<#data>);
+        let signature = quote!((#sm: &mut #data, #e: &::qf::event::DynEvent) -> #result);
+
+        let initial_action = &self.initial.action;
+        let initial_target = &self.initial.target;
+        let handlers = self
+            .states
+            .iter()
+            .map(|state| self.expand_state(state, &signature));
+        let dictionary = self.dictionary.then(|| self.expand_dictionary());
+
+        Ok(quote! {
+            #(#attrs)*
+            #[allow(unused_variables, clippy::needless_return)]
+            #vis mod #name {
+                use super::*;
+
+                /// Initial pseudo-state.
+                pub fn initial #signature {
+                    #initial_action
+                    ::qf::hsm::QHsmResult::Tran(#initial_target)
+                }
+
+                #(#handlers)*
+
+                /// Wraps `sm` in a `QHsm` that starts in this machine.
+                pub fn new(#sm: #data) -> ::qf::hsm::QHsm<#data> {
+                    ::qf::hsm::QHsm::new(#sm, initial)
+                }
+
+                #dictionary
+            }
+        })
+    }
+
+    /// Rejects states declared twice and references to undeclared states.
+    fn check(&self) -> syn::Result<()> {
+        let known = |ident: &Ident| self.states.iter().any(|state| state.name == *ident);
+        let reject = |ident: &Ident| {
+            syn::Error::new(
+                ident.span(),
+                format!("`{ident}` is not a state of `{}`", self.name),
+            )
+        };
+        for (n, state) in self.states.iter().enumerate() {
+            if state.name == "top" || state.name == "initial" || state.name == "new" {
+                return Err(syn::Error::new(
+                    state.name.span(),
+                    format!("`{}` is reserved", state.name),
+                ));
+            }
+            if self.states[..n]
+                .iter()
+                .any(|other| other.name == state.name)
+            {
+                return Err(syn::Error::new(
+                    state.name.span(),
+                    format!("state `{}` is declared twice", state.name),
+                ));
+            }
+        }
+        if !known(&self.initial.target) {
+            return Err(reject(&self.initial.target));
+        }
+        for state in &self.states {
+            if let Some(parent) = state.parent.as_ref().filter(|p| *p != "top") {
+                if !known(parent) {
+                    return Err(reject(parent));
+                }
+            }
+            if let Some(initial) = &state.initial {
+                if !known(&initial.target) {
+                    return Err(reject(&initial.target));
+                }
+            }
+            for rule in &state.rules {
+                match &rule.target {
+                    Some(Target::State(target) | Target::History(target) | Target::DeepHistory(target)) if !known(target) => {
+                        return Err(reject(target));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn expand_state(&self, state: &State, signature: &TokenStream2) -> TokenStream2 {
+        let data = &self.data;
+        let State { attrs, name, .. } = state;
+        let reserved = quote!(::qf::hsm::reserved);
+        let parent = match &state.parent {
+            Some(parent) if parent != "top" => quote!(#parent),
+            _ => quote!(::qf::hsm::QHsm::<#data>::top_state),
+        };
+
+        let entry = state.entry.as_ref().map(|block| {
+            quote! {
+                if signal == #reserved::Q_ENTRY_SIG {
+                    #block
+                    return ::qf::hsm::QHsmResult::Handled;
+                }
+            }
+        });
+        let exit = state.exit.as_ref().map(|block| {
+            quote! {
+                if signal == #reserved::Q_EXIT_SIG {
+                    #block
+                    return ::qf::hsm::QHsmResult::Handled;
+                }
+            }
+        });
+        let initial = state.initial.as_ref().map(|Initial { action, target }| {
+            quote! {
+                if signal == #reserved::Q_INIT_SIG {
+                    #action
+                    return ::qf::hsm::QHsmResult::Tran(#target);
+                }
+            }
+        });
+        let rules = state.rules.iter().enumerate().map(|(i, rule)| {
+            let Rule { signal, action, .. } = rule;
+            // The last guarded rule for a signal can leave the fallback to the
+            // dispatcher, which traces the guard; earlier ones fall through to
+            // the next rule instead.
+            let last_for_signal = state.rules[i + 1..]
+                .iter()
+                .all(|later| later.signal.to_token_stream().to_string() != signal.to_token_stream().to_string());
+            if let (Some(guard), Some(Target::State(target)), true) = (&rule.guard, &rule.target, last_for_signal) {
+                return quote! {
+                    if signal == (#signal) {
+                        let passed = #guard;
+                        if passed {
+                            #action
+                        }
+                        return ::qf::hsm::QHsmResult::TranIf(#target, passed);
+                    }
+                };
+            }
+            let guard = rule.guard.as_ref().map(|guard| quote!(&& (#guard)));
+            let outcome = match &rule.target {
+                Some(Target::State(target)) => quote!(::qf::hsm::QHsmResult::Tran(#target)),
+                Some(Target::History(target)) => quote!(::qf::hsm::QHsmResult::TranHist(#target)),
+                Some(Target::DeepHistory(target)) => quote!(::qf::hsm::QHsmResult::TranDeepHist(#target)),
+                None => quote!(::qf::hsm::QHsmResult::Handled),
+            };
+            quote! {
+                if signal == (#signal) #guard {
+                    #action
+                    return #outcome;
+                }
+            }
+        });
+
+        quote! {
+            #(#attrs)*
+            pub fn #name #signature {
+                let signal = e.signal();
+                #entry
+                #exit
+                #initial
+                #(#rules)*
+                ::qf::hsm::QHsmResult::Super(#parent)
+            }
+        }
+    }
+
+    fn expand_dictionary(&self) -> TokenStream2 {
+        let data = &self.data;
+        let machine = self.name.to_string();
+        let initial_name = format!("{machine}::initial");
+        let names = self
+            .states
+            .iter()
+            .map(|state| format!("{machine}::{}", state.name));
+        let handlers = self.states.iter().map(|state| &state.name);
+        quote! {
+            /// Sends a `QS_FUN_DICT` record naming each handler of this
+            /// machine, and the `QHsm` top state.
+            pub fn emit_dictionary(hook: &::qf::TraceHook) -> ::qf::TraceResult {
+                let entries: &[(::qf::hsm::StateHandler<#data>, &str)] = &[
+                    (::qf::hsm::QHsm::<#data>::top_state, "QP::QHsm::top"),
+                    (initial, #initial_name),
+                    #((#handlers, #names),)*
+                ];
+                for (handler, name) in entries {
+                    let payload = ::qs::predefined::fun_dict_payload(*handler as usize as u64, name);
+                    hook(::qs::predefined::FUN_DICT, &payload, false)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! defmt log frames carried inside QS user records.
+//!
+//! Firmware that already logs with [defmt](https://defmt.ferrous-systems.com)
+//! can route those logs through the QS link instead of a second transport,
+//! so they land in the QSPY timeline between the state-machine records they
+//! explain. The bridge wraps each encoded defmt frame — opaque bytes, only
+//! decodable against the firmware's ELF — in one or more user records:
+//!
+//! ```text
+//! U8  flags      FIRST (bit 0), LAST (bit 1), chunk index mod 64 (bits 2..7)
+//! MEM data       up to max_record_len - 4 bytes of the frame
+//! ```
+//!
+//! # Record allocation
+//!
+//! The wrapper uses a single user record id chosen by the application from
+//! its own user range, [`DEFAULT_RECORD`] unless that is taken. The id is
+//! not part of the protocol: [`DefmtBridge::announce`] names it
+//! [`DICT_NAME`] in a `QS_USR_DICT` record, and the host recognises the
+//! wrapper by that name, whatever the number.
+//!
+//! # Host side
+//!
+//! [`Chunk::parse`] and [`Reassembler`] undo the wrapping; `qspy` uses them
+//! to print each defmt frame in place and hand it to a decoder.
+//!
+//! # Target side
+//!
+//! With the `logger` feature the crate provides the `#[defmt::global_logger]`
+//! itself; [`install`] connects it to a tracer:
+//!
+//! ```ignore
+//! let tracer = Tracer::new(QsConfig::default(), &QS_BUF).into_handle();
+//! qs_defmt::install(DefmtBridge::new(qs_defmt::DEFAULT_RECORD, tracer.hook()))?;
+//! defmt::info!("boot {}", 42);
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use qs::predefined::{self, usr_dict_payload};
+use qs::{make_format, TraceError, TraceHook, UserRecordBuilder, FMT_MEM, FMT_U8};
+
+#[cfg(feature = "logger")]
+mod logger;
+
+#[cfg(feature = "logger")]
+pub use logger::{dropped_frames, install};
+
+/// Suggested user record id for the wrapper.
+pub const DEFAULT_RECORD: u8 = 120;
+
+/// `QS_USR_DICT` name that marks the wrapper record.
+pub const DICT_NAME: &str = "QS_DEFMT";
+
+/// Set on the chunk that starts a frame.
+pub const FIRST: u8 = 0x01;
+/// Set on the chunk that ends a frame.
+pub const LAST: u8 = 0x02;
+
+/// Format bytes and length byte around the chunk data.
+const CHUNK_OVERHEAD: usize = 4;
+/// Largest MEM field a user record can carry.
+const MAX_CHUNK: usize = 255;
+
+/// Splits defmt frames into wrapper records and emits them through a
+/// [`TraceHook`].
+#[derive(Clone)]
+pub struct DefmtBridge {
+    record: u8,
+    chunk_len: usize,
+    hook: TraceHook,
+}
+
+impl DefmtBridge {
+    /// A bridge emitting `record` through `hook`, sized for the default QS
+    /// record length.
+    pub fn new(record: u8, hook: TraceHook) -> Self {
+        Self { record, chunk_len: chunk_len(qs::QsConfig::default().max_record_len), hook }
+    }
+
+    /// Sizes chunks for a tracer configured with a different
+    /// `max_record_len`.
+    pub fn with_max_record_len(mut self, max_record_len: usize) -> Self {
+        self.chunk_len = chunk_len(max_record_len);
+        self
+    }
+
+    /// The wrapper record id.
+    pub fn record(&self) -> u8 {
+        self.record
+    }
+
+    /// Emits the `QS_USR_DICT` record that tells the host which id carries
+    /// defmt frames. Send it with the other dictionaries.
+    pub fn announce(&self) -> Result<(), TraceError> {
+        (self.hook)(predefined::USR_DICT, &usr_dict_payload(self.record, DICT_NAME), false)
+    }
+
+    /// Emits one encoded defmt frame as consecutive wrapper records.
+    pub fn forward(&self, frame: &[u8]) -> Result<(), TraceError> {
+        let mut chunks = frame.chunks(self.chunk_len).enumerate().peekable();
+        if chunks.peek().is_none() {
+            return self.emit_chunk(FIRST | LAST, &[]);
+        }
+        while let Some((index, data)) = chunks.next() {
+            let mut flags = ((index as u8) & 0x3F) << 2;
+            if index == 0 {
+                flags |= FIRST;
+            }
+            if chunks.peek().is_none() {
+                flags |= LAST;
+            }
+            self.emit_chunk(flags, data)?;
+        }
+        Ok(())
+    }
+
+    fn emit_chunk(&self, flags: u8, data: &[u8]) -> Result<(), TraceError> {
+        let mut buf = [0u8; CHUNK_OVERHEAD + MAX_CHUNK];
+        let mut payload = UserRecordBuilder::encode_into(&mut buf);
+        payload.push_u8(0, flags).push_mem(data);
+        (self.hook)(self.record, payload.finish()?, true)
+    }
+}
+
+fn chunk_len(max_record_len: usize) -> usize {
+    assert!(max_record_len > CHUNK_OVERHEAD, "QS records too short to carry defmt data");
+    (max_record_len - CHUNK_OVERHEAD).min(MAX_CHUNK)
+}
+
+/// One wrapper record, as read back on the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunk<'a> {
+    /// [`FIRST`], [`LAST`] and the chunk index.
+    pub flags: u8,
+    /// Frame bytes carried by this record.
+    pub data: &'a [u8],
+}
+
+impl<'a> Chunk<'a> {
+    /// Parses the fields of a wrapper record (the user-record payload after
+    /// the timestamp). Returns `None` if they are not a wrapper chunk.
+    pub fn parse(fields: &'a [u8]) -> Option<Self> {
+        match fields {
+            [fu8, flags, fmem, len, data @ ..]
+                if *fu8 == make_format(0, FMT_U8)
+                    && *fmem == make_format(0, FMT_MEM)
+                    && data.len() == *len as usize =>
+            {
+                Some(Self { flags: *flags, data })
+            }
+            _ => None,
+        }
+    }
+
+    /// Position of this chunk in its frame, modulo 64.
+    pub fn index(&self) -> u8 {
+        self.flags >> 2
+    }
+}
+
+/// Rebuilds defmt frames from wrapper chunks.
+///
+/// A frame with a missing chunk — a dropped QS record, or a target reset
+/// mid-frame — is discarded and counted in [`lost`](Self::lost) rather than
+/// passed on corrupted.
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    frame: Vec<u8>,
+    /// Index expected next, or `None` between frames.
+    next: Option<u8>,
+    lost: u32,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Frames discarded as incomplete so far.
+    pub fn lost(&self) -> u32 {
+        self.lost
+    }
+
+    /// Adds `chunk`; returns the frame it completes, if any.
+    pub fn push(&mut self, chunk: Chunk<'_>) -> Option<Vec<u8>> {
+        if chunk.flags & FIRST != 0 {
+            if self.next.is_some() {
+                self.lost += 1;
+            }
+            self.frame.clear();
+        } else if self.next != Some(chunk.index()) {
+            if self.next.is_some() {
+                self.lost += 1;
+                self.next = None;
+            }
+            return None;
+        }
+        self.frame.extend_from_slice(chunk.data);
+        if chunk.flags & LAST != 0 {
+            self.next = None;
+            return Some(core::mem::take(&mut self.frame));
+        }
+        self.next = Some((chunk.index() + 1) & 0x3F);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use alloc::sync::Arc;
+    use std::sync::Mutex;
+
+    type Emitted = Arc<Mutex<Vec<(u8, Vec<u8>, bool)>>>;
+
+    fn capture() -> (TraceHook, Emitted) {
+        let emitted: Emitted = Arc::default();
+        let sink = Arc::clone(&emitted);
+        let hook: TraceHook = Arc::new(move |record, payload: &[u8], ts| {
+            sink.lock().unwrap().push((record, payload.to_vec(), ts));
+            Ok(())
+        });
+        (hook, emitted)
+    }
+
+    #[test]
+    fn announce_names_the_record() {
+        let (hook, emitted) = capture();
+        DefmtBridge::new(110, hook).announce().unwrap();
+        let emitted = emitted.lock().unwrap();
+        assert_eq!(emitted[0].0, predefined::USR_DICT);
+        assert_eq!(emitted[0].1, b"\x6eQS_DEFMT\0");
+    }
+
+    #[test]
+    fn frames_split_and_reassemble() {
+        let (hook, emitted) = capture();
+        let bridge = DefmtBridge::new(DEFAULT_RECORD, hook).with_max_record_len(8);
+        let frame: Vec<u8> = (0..10).collect();
+        bridge.forward(&frame).unwrap();
+        bridge.forward(&[0xAA]).unwrap();
+
+        let emitted = emitted.lock().unwrap();
+        assert_eq!(emitted.len(), 4);
+        assert!(emitted.iter().all(|(rec, p, ts)| *rec == DEFAULT_RECORD && p.len() <= 8 && *ts));
+
+        let mut rx = Reassembler::new();
+        let frames: Vec<_> =
+            emitted.iter().filter_map(|(_, p, _)| rx.push(Chunk::parse(p).unwrap())).collect();
+        assert_eq!(frames, [frame, alloc::vec![0xAA]]);
+        assert_eq!(rx.lost(), 0);
+    }
+
+    #[test]
+    fn missing_chunk_discards_the_frame() {
+        let (hook, emitted) = capture();
+        let bridge = DefmtBridge::new(DEFAULT_RECORD, hook).with_max_record_len(6);
+        bridge.forward(&[1, 2, 3, 4, 5, 6]).unwrap();
+        bridge.forward(&[7, 8]).unwrap();
+
+        let emitted = emitted.lock().unwrap();
+        let mut rx = Reassembler::new();
+        let frames: Vec<_> = emitted
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 1)
+            .filter_map(|(_, (_, p, _))| rx.push(Chunk::parse(p).unwrap()))
+            .collect();
+        assert_eq!(frames, [alloc::vec![7, 8]]);
+        assert_eq!(rx.lost(), 1);
+    }
+
+    #[test]
+    fn other_user_records_are_not_chunks() {
+        let mut buf = [0u8; 16];
+        let mut fields = UserRecordBuilder::encode_into(&mut buf);
+        fields.push_u8(0, FIRST).push_u16(0, 7);
+        assert_eq!(Chunk::parse(fields.finish().unwrap()), None);
+    }
+}
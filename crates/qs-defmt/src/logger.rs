@@ -0,0 +1,125 @@
+//! `#[defmt::global_logger]` that forwards every frame through the installed
+//! [`DefmtBridge`].
+//!
+//! A frame is encoded into a static buffer while the logger holds a critical
+//! section (from `acquire` to `release`) and emitted on `release`. The
+//! tracer behind the bridge therefore runs with interrupts masked; logging
+//! from an interrupt that preempted a QS record in progress would wait on
+//! the tracer's lock, so keep defmt calls out of such handlers.
+
+use core::cell::{RefCell, UnsafeCell};
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use critical_section::{Mutex, RestoreState};
+use qs::TraceError;
+
+use crate::DefmtBridge;
+
+/// Longest encoded frame; longer frames are dropped.
+const FRAME_CAP: usize = 256;
+
+static BRIDGE: Mutex<RefCell<Option<DefmtBridge>>> = Mutex::new(RefCell::new(None));
+static TAKEN: AtomicBool = AtomicBool::new(false);
+static DROPPED: AtomicU32 = AtomicU32::new(0);
+static STATE: State = State {
+    restore: UnsafeCell::new(RestoreState::invalid()),
+    encoder: UnsafeCell::new(defmt::Encoder::new()),
+    frame: UnsafeCell::new(FrameBuf { bytes: [0; FRAME_CAP], len: 0, overflow: false }),
+};
+
+struct State {
+    restore: UnsafeCell<RestoreState>,
+    encoder: UnsafeCell<defmt::Encoder>,
+    frame: UnsafeCell<FrameBuf>,
+}
+
+// SAFETY: the cells are only touched between `acquire` and `release`, inside
+// a critical section, and `TAKEN` rejects re-entry from the same context.
+unsafe impl Sync for State {}
+
+/// The encoder and the buffer it writes into, borrowed separately.
+///
+/// # Safety
+/// Only between `acquire` and `release`, once per call.
+unsafe fn parts() -> (&'static mut defmt::Encoder, &'static mut FrameBuf) {
+    unsafe { (&mut *STATE.encoder.get(), &mut *STATE.frame.get()) }
+}
+
+struct FrameBuf {
+    bytes: [u8; FRAME_CAP],
+    len: usize,
+    overflow: bool,
+}
+
+impl FrameBuf {
+    fn push(&mut self, bytes: &[u8]) {
+        match self.bytes.get_mut(self.len..self.len + bytes.len()) {
+            Some(dst) if !self.overflow => {
+                dst.copy_from_slice(bytes);
+                self.len += bytes.len();
+            }
+            _ => self.overflow = true,
+        }
+    }
+}
+
+/// Connects the global logger to `bridge` and announces its record. Frames
+/// logged before this are discarded.
+pub fn install(bridge: DefmtBridge) -> Result<(), TraceError> {
+    bridge.announce()?;
+    critical_section::with(|cs| *BRIDGE.borrow_ref_mut(cs) = Some(bridge));
+    Ok(())
+}
+
+/// Frames dropped because they exceeded the frame buffer or the tracer
+/// rejected them.
+pub fn dropped_frames() -> u32 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+#[defmt::global_logger]
+struct QsLogger;
+
+unsafe impl defmt::Logger for QsLogger {
+    fn acquire() {
+        // SAFETY: paired with the `release` below.
+        let restore = unsafe { critical_section::acquire() };
+        if TAKEN.swap(true, Ordering::Relaxed) {
+            panic!("defmt logger taken reentrantly");
+        }
+        // SAFETY: inside the critical section, and not taken before.
+        let (encoder, frame) = unsafe {
+            *STATE.restore.get() = restore;
+            parts()
+        };
+        frame.len = 0;
+        frame.overflow = false;
+        encoder.start_frame(|b| frame.push(b));
+    }
+
+    unsafe fn flush() {}
+
+    unsafe fn release() {
+        // SAFETY: called after `acquire`, inside its critical section.
+        let (encoder, frame) = unsafe { parts() };
+        encoder.end_frame(|b| frame.push(b));
+        let dropped = frame.overflow
+            || critical_section::with(|cs| match BRIDGE.borrow_ref(cs).as_ref() {
+                Some(bridge) => bridge.forward(&frame.bytes[..frame.len]).is_err(),
+                None => false,
+            });
+        if dropped {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+        // SAFETY: as above; written by the matching `acquire`.
+        let restore = unsafe { *STATE.restore.get() };
+        TAKEN.store(false, Ordering::Relaxed);
+        unsafe { critical_section::release(restore) };
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        // SAFETY: called between `acquire` and `release`.
+        let (encoder, frame) = unsafe { parts() };
+        encoder.write(bytes, |b| frame.push(b));
+    }
+}
@@ -0,0 +1,50 @@
+//! Attribute macros for QS tracing. Use them through `qs` with its `macros`
+//! feature; the expansions name `::qs` paths.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Expr, ItemFn};
+
+/// Traces an interrupt handler as `QS_QF_ISR_ENTRY` / `QS_QF_ISR_EXIT`.
+///
+/// ```ignore
+/// #[qs::qs_traced_isr(prio = 3)]
+/// #[interrupt]
+/// fn TIMER0() {
+///     // ...
+/// }
+/// ```
+///
+/// The body runs inside a `qs::isr::IsrScope`, so the exit record is sent on
+/// every path out of the handler. `prio` is any `u8` expression and defaults
+/// to 0. Put this attribute above the port's own interrupt attribute so it
+/// wraps the body as written; the other attributes are kept as they are.
+#[proc_macro_attribute]
+pub fn qs_traced_isr(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut prio: Option<Expr> = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("prio") {
+            prio = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `prio = <u8>`"))
+        }
+    });
+    parse_macro_input!(args with parser);
+
+    let ItemFn { attrs, vis, sig, block } = parse_macro_input!(item as ItemFn);
+    if let Some(asyncness) = sig.asyncness {
+        return syn::Error::new_spanned(asyncness, "an interrupt handler cannot be async")
+            .to_compile_error()
+            .into();
+    }
+    let prio = prio.map_or_else(|| quote!(0), |prio| quote!(#prio));
+    quote! {
+        #(#attrs)*
+        #vis #sig {
+            let __qs_isr_scope = ::qs::isr::IsrScope::enter(#prio);
+            #block
+        }
+    }
+    .into()
+}
@@ -0,0 +1,43 @@
+//! Format bytes of application (user) records.
+//!
+//! Each field of a user record is preceded by a descriptor byte: the base
+//! format in the low nibble and a width hint in the high nibble.
+
+/// Format identifier for `QS_I8_ENUM_FMT` records.
+pub const FMT_I8_ENUM: u8 = 0x0;
+/// Format identifier for `QS_U8_FMT` records.
+pub const FMT_U8: u8 = 0x1;
+/// Format identifier for `QS_I16_FMT` records.
+pub const FMT_I16: u8 = 0x2;
+/// Format identifier for `QS_U16_FMT` records.
+pub const FMT_U16: u8 = 0x3;
+/// Format identifier for `QS_I32_FMT` records.
+pub const FMT_I32: u8 = 0x4;
+/// Format identifier for `QS_U32_FMT` records.
+pub const FMT_U32: u8 = 0x5;
+/// Format identifier for `QS_F32_FMT` records.
+pub const FMT_F32: u8 = 0x6;
+/// Format identifier for `QS_F64_FMT` records.
+pub const FMT_F64: u8 = 0x7;
+/// Format identifier for `QS_STR_FMT` records.
+pub const FMT_STR: u8 = 0x8;
+/// Format identifier for `QS_MEM_FMT` records.
+pub const FMT_MEM: u8 = 0x9;
+/// Format identifier for `QS_SIG_FMT` records.
+pub const FMT_SIG: u8 = 0xA;
+/// Format identifier for `QS_OBJ_FMT` records.
+pub const FMT_OBJ: u8 = 0xB;
+/// Format identifier for `QS_FUN_FMT` records.
+pub const FMT_FUN: u8 = 0xC;
+/// Format identifier for `QS_I64_FMT` records.
+pub const FMT_I64: u8 = 0xD;
+/// Format identifier for `QS_U64_FMT` records.
+pub const FMT_U64: u8 = 0xE;
+/// Format identifier for the optional hexadecimal flag (`QS_HEX_FMT`).
+pub const FMT_HEX: u8 = 0xF;
+
+/// Computes a user-record format descriptor by combining a width hint with a
+/// base format identifier.
+pub const fn make_format(width: u8, base: u8) -> u8 {
+    ((width & 0x0F) << 4) | (base & 0x0F)
+}
@@ -0,0 +1,140 @@
+//! Record groups: the coarse classification behind filtering.
+//!
+//! Every record id maps to exactly one [`RecordGroup`], on the numeric id
+//! alone. A host tool that learns user-record names from `QS_USR_DICT` may
+//! additionally treat those ids as [`RecordGroup::Usr`].
+
+use core::fmt;
+
+use crate::records::{dict, infra, qep, qf, qf::time_evt, qxk, sched, FIRST_USER};
+
+/// Coarse record category.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordGroup {
+    /// State-machine (QEP) records.
+    Sm,
+    /// Active-object queue, defer/recall and subscription records.
+    Ao,
+    /// Raw event-queue records.
+    Eq,
+    /// Memory-pool records.
+    Mp,
+    /// Time-event records and the clock tick.
+    Te,
+    /// Scheduler records.
+    Sc,
+    /// QXK semaphore records.
+    Sem,
+    /// QXK mutex records.
+    Mtx,
+    /// Application (user) records.
+    Usr,
+    /// Remaining framework records: publish, event lifecycle, critical
+    /// sections and interrupts.
+    Qf,
+    /// Dictionaries, target info, assertions and test back-channel. Never
+    /// filtered out.
+    Info,
+}
+
+impl RecordGroup {
+    /// Every group accepted by `--filter`.
+    pub const SELECTABLE: [Self; 10] = [
+        Self::Sm, Self::Ao, Self::Eq, Self::Mp, Self::Te,
+        Self::Sc, Self::Sem, Self::Mtx, Self::Usr, Self::Qf,
+    ];
+
+    /// Group of a predefined record id; ids from 100 up are user records.
+    pub fn of(record_type: u8) -> Self {
+        match record_type {
+            qep::STATE_ENTRY..=qep::UNHANDLED | qep::TRAN_HIST | qep::CONTRACT_VIOLATION | qep::GUARD => Self::Sm,
+
+            qf::ACTIVE_DEFER..=qf::ACTIVE_RECALL_ATTEMPT
+            | qf::ACTIVE_POST_ATTEMPT
+            | qf::ACTIVE_DEFER_ATTEMPT
+            | qf::AO_SAVE
+            | qf::AO_RESTORE
+            | qf::EVT_LOG => Self::Ao,
+
+            qf::EQUEUE_INIT..=qf::EQUEUE_GET | qf::EQUEUE_POST_ATTEMPT => Self::Eq,
+
+            qf::MPOOL_INIT..=qf::MPOOL_PUT | qf::MPOOL_GET_ATTEMPT => Self::Mp,
+
+            qf::TICK | time_evt::ARM..=time_evt::POST | qf::TIMEEVT_JITTER => Self::Te,
+
+            qf::PUBLISH..=qf::GC | qf::DELETE_REF..=qf::INT_ENABLE | qf::NEW_ATTEMPT => Self::Qf,
+
+            sched::PREEMPT..=sched::IDLE | qf::RUN_BATCH | qf::RTC_OVERRUN | qf::RTC_STATS | qf::AO_STALL | qf::METRICS | qf::WDT_RESET | qxk::THREAD_STACK => Self::Sc,
+
+            qxk::SEM_TAKE..=qxk::SEM_BLOCK_ATTEMPT => Self::Sem,
+            qxk::MTX_LOCK..=qxk::MTX_UNLOCK_ATTEMPT => Self::Mtx,
+
+            dict::ENUM_DICT
+            | infra::TEST_PAUSED..=infra::QF_RUN => Self::Info,
+
+            rec if rec >= FIRST_USER => Self::Usr,
+            _ => Self::Info,
+        }
+    }
+
+    /// Short name as accepted by `--filter`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sm   => "SM",
+            Self::Ao   => "AO",
+            Self::Eq   => "EQ",
+            Self::Mp   => "MP",
+            Self::Te   => "TE",
+            Self::Sc   => "SC",
+            Self::Sem  => "SEM",
+            Self::Mtx  => "MTX",
+            Self::Usr  => "USR",
+            Self::Qf   => "QF",
+            Self::Info => "INFO",
+        }
+    }
+
+    /// Parse a group name, case-insensitively.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::SELECTABLE.into_iter().find(|g| g.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The `QS_RX_GLB_FILTER` mask that lets exactly this group through.
+    pub fn mask(self) -> [u8; 16] {
+        filter_mask(|record| Self::of(record) == self)
+    }
+}
+
+impl fmt::Display for RecordGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The 128-bit `QS_RX_GLB_FILTER` mask (records 0–127, little-endian) with
+/// the bit of every record `allowed` accepts set.
+pub fn filter_mask(mut allowed: impl FnMut(u8) -> bool) -> [u8; 16] {
+    let mut mask = [0u8; 16];
+    for record in 0..128u8 {
+        if allowed(record) {
+            mask[usize::from(record / 8)] |= 1 << (record % 8);
+        }
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_masks_cover_their_records_only() {
+        let mask = RecordGroup::Sc.mask();
+        let set = |r: u8| mask[usize::from(r / 8)] & (1 << (r % 8)) != 0;
+        assert!(set(sched::PREEMPT) && set(sched::IDLE) && set(qf::RTC_STATS));
+        assert!(!set(qep::DISPATCH) && !set(dict::TARGET_INFO));
+        let usr = RecordGroup::Usr.mask();
+        assert_eq!(usr[..12], [0; 12]);
+        assert_eq!(usr[12..], [0xF0, 0xFF, 0xFF, 0xFF], "records 100..=127");
+    }
+}
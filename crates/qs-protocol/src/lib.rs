@@ -0,0 +1,21 @@
+//! The QS wire protocol, in one place.
+//!
+//! Record identifiers, user-record format bytes, the `QS_TARGET_INFO` layout
+//! and the record groups used for filtering are defined here once. The
+//! framework crates emit with these values, [`qs`] encodes with them and
+//! `qspy` decodes with them, so the three cannot drift apart.
+//!
+//! The crate is `no_std`, allocation-free and has no dependencies, so a
+//! kernel built without the `qs` feature can still name its record ids.
+//!
+//! [`qs`]: https://docs.rs/qs
+
+#![no_std]
+
+pub mod format;
+pub mod groups;
+pub mod records;
+pub mod target_info;
+
+pub use groups::RecordGroup;
+pub use target_info::TargetInfo;
@@ -0,0 +1,261 @@
+//! Record identifiers of the QS protocol.
+//!
+//! The ids match the QP/Spy trace protocol, so the standard QSpy host tools
+//! decode records emitted by qp-rs. Ids the reference protocol leaves free
+//! carry qp-rs extensions; each says so. Dictionary and target-info ids are
+//! in [`dict`], and every id from [`FIRST_USER`] up is an application record.
+
+/// First application (user) record id.
+pub const FIRST_USER: u8 = 100;
+
+/// QEP (state machine) related record identifiers.
+pub mod qep {
+    /// State entry action executed.
+    pub const STATE_ENTRY: u8 = 1;
+    /// State exit action executed.
+    pub const STATE_EXIT:  u8 = 2;
+    /// Nested initial transition taken.
+    pub const STATE_INIT:  u8 = 3;
+    /// Top-most initial transition taken.
+    pub const INIT_TRAN:   u8 = 4;
+    /// Internal transition (no state change).
+    pub const INTERN_TRAN: u8 = 5;
+    /// Regular state transition.
+    pub const TRAN:        u8 = 6;
+    /// Event ignored by the state machine.
+    pub const IGNORED:     u8 = 7;
+    /// Event dispatched to the state machine.
+    pub const DISPATCH:    u8 = 8;
+    /// Event reached the top state unhandled.
+    pub const UNHANDLED:   u8 = 9;
+    /// Transition to history pseudo-state (no timestamp, RTC step).
+    pub const TRAN_HIST:   u8 = 55;
+    /// State overstayed its declared timing contract
+    /// (qp-rs extension in the reserved slot 57).
+    pub const CONTRACT_VIOLATION: u8 = 57;
+    /// A transition guard was evaluated (qp-rs extension).
+    pub const GUARD:       u8 = 92;
+}
+
+/// QF (framework) record identifiers.
+pub mod qf {
+    /// Active object deferred an event.
+    pub const ACTIVE_DEFER:            u8 = 10;
+    /// Active object recalled a deferred event.
+    pub const ACTIVE_RECALL:           u8 = 11;
+    /// Active object subscribed to a signal.
+    pub const ACTIVE_SUBSCRIBE:        u8 = 12;
+    /// Active object unsubscribed from a signal.
+    pub const ACTIVE_UNSUBSCRIBE:      u8 = 13;
+    /// Event posted (FIFO) to an active object.
+    pub const ACTIVE_POST:             u8 = 14;
+    /// Event posted (LIFO) to an active object.
+    pub const ACTIVE_POST_LIFO:        u8 = 15;
+    /// Event retrieved from an active object's queue.
+    pub const ACTIVE_GET:              u8 = 16;
+    /// Last event retrieved (queue became empty).
+    pub const ACTIVE_GET_LAST:         u8 = 17;
+    /// Recall attempted but no event was deferred.
+    pub const ACTIVE_RECALL_ATTEMPT:   u8 = 18;
+    /// Raw event queue initialized.
+    pub const EQUEUE_INIT:             u8 = 19;
+    /// Event posted (FIFO) to a raw event queue.
+    pub const EQUEUE_POST:             u8 = 20;
+    /// Event posted (LIFO) to a raw event queue.
+    pub const EQUEUE_POST_LIFO:        u8 = 21;
+    /// Event retrieved from a raw event queue.
+    pub const EQUEUE_GET:              u8 = 22;
+    /// Memory pool initialized.
+    pub const MPOOL_INIT:              u8 = 23;
+    /// Block obtained from a memory pool.
+    pub const MPOOL_GET:               u8 = 24;
+    /// Block returned to a memory pool.
+    pub const MPOOL_PUT:               u8 = 25;
+    /// Event published to subscribers.
+    pub const PUBLISH:                 u8 = 26;
+    /// New reference taken to an event.
+    pub const NEW_REF:                 u8 = 27;
+    /// New event allocated.
+    pub const NEW:                     u8 = 28;
+    /// Garbage-collect attempted (refcount not yet zero).
+    pub const GC_ATTEMPT:              u8 = 29;
+    /// Event garbage-collected (freed).
+    pub const GC:                      u8 = 30;
+    /// System clock tick processed.
+    pub const TICK:                    u8 = 31;
+    /// Reference to an event deleted.
+    pub const DELETE_REF:              u8 = 38;
+    /// Critical section entered.
+    pub const CRIT_ENTRY:              u8 = 39;
+    /// Critical section exited.
+    pub const CRIT_EXIT:               u8 = 40;
+    /// Interrupt service routine entered.
+    pub const ISR_ENTRY:              u8 = 41;
+    /// Interrupt service routine exited.
+    pub const ISR_EXIT:                u8 = 42;
+    /// Interrupts disabled.
+    pub const INT_DISABLE:             u8 = 43;
+    /// Interrupts enabled.
+    pub const INT_ENABLE:              u8 = 44;
+    /// Post (FIFO) attempt failed (queue full / margin not met).
+    pub const ACTIVE_POST_ATTEMPT:     u8 = 45;
+    /// Raw event-queue post attempt failed.
+    pub const EQUEUE_POST_ATTEMPT:     u8 = 46;
+    /// Memory-pool get attempt failed (pool exhausted).
+    pub const MPOOL_GET_ATTEMPT:       u8 = 47;
+    /// Defer attempt failed (added in QP/C++ v8.0.4).
+    pub const ACTIVE_DEFER_ATTEMPT:    u8 = 81;
+    /// One `run_until_idle` batch: events dispatched and its duration
+    /// (qp-rs extension in the reserved slot 56).
+    pub const RUN_BATCH:               u8 = 56;
+    /// A run-to-completion step overran its budget (qp-rs extension in the
+    /// reserved slot 82).
+    pub const RTC_OVERRUN:             u8 = 82;
+    /// An active object's extended state was saved to non-volatile storage
+    /// (qp-rs extension).
+    pub const AO_SAVE:                 u8 = 83;
+    /// An active object's extended state was restored at boot (qp-rs
+    /// extension).
+    pub const AO_RESTORE:              u8 = 84;
+    /// A time event's timeout-handling lateness so far (qp-rs extension).
+    pub const TIMEEVT_JITTER:          u8 = 85;
+    /// Step-duration statistics of one priority (qp-rs extension).
+    pub const RTC_STATS:               u8 = 88;
+    /// No event pool could supply a block for a new event (qp-rs extension
+    /// in the reserved slot 89).
+    pub const NEW_ATTEMPT:             u8 = 89;
+    /// An event posted to an active object, with a snapshot of its payload,
+    /// logged for deterministic replay (qp-rs extension).
+    pub const EVT_LOG:                 u8 = 91;
+    /// An active object with pending events made no progress for longer
+    /// than the kernel watchdog allows (qp-rs extension).
+    pub const AO_STALL:                u8 = 93;
+    /// Dispatch and drop counts of one active object or signal (qp-rs
+    /// extension).
+    pub const METRICS:                 u8 = 94;
+    /// The target booted after a hardware watchdog reset (qp-rs
+    /// extension).
+    pub const WDT_RESET:               u8 = 95;
+
+    /// Time-event record identifiers (32–37).
+    pub mod time_evt {
+        /// Time event armed.
+        pub const ARM:                 u8 = 32;
+        /// One-shot time event auto-disarmed on expiry.
+        pub const AUTO_DISARM:         u8 = 33;
+        /// Disarm attempted on an already-disarmed event.
+        pub const DISARM_ATTEMPT:      u8 = 34;
+        /// Time event disarmed.
+        pub const DISARM:              u8 = 35;
+        /// Time event counter updated via `rearm()`.
+        pub const REARM:               u8 = 36;
+        /// Time event posted to its target active object.
+        pub const POST:                u8 = 37;
+    }
+}
+
+/// Scheduler related record identifiers (50–53).
+pub mod sched {
+    /// Scheduler preempted a running task: `[next, prev]` priorities.
+    pub const PREEMPT: u8 = 48;
+    /// Scheduler resumed a preempted task: `[resumed, prev]` priorities.
+    pub const RESTORE: u8 = 49;
+    /// Scheduler locked at a priority ceiling.
+    pub const LOCK:   u8 = 50;
+    /// Scheduler unlocked.
+    pub const UNLOCK: u8 = 51;
+    /// Scheduler selected the next task to run.
+    pub const NEXT:   u8 = 52;
+    /// Scheduler went idle.
+    pub const IDLE:   u8 = 53;
+}
+
+/// Test/infrastructure record identifiers (58–70).
+pub mod infra {
+    /// QUTest run paused, awaiting host.
+    pub const TEST_PAUSED: u8 = 58;
+    /// QUTest probe value requested.
+    pub const TEST_PROBE:  u8 = 59;
+    /// Host-tool back-channel: target acknowledges a command.
+    pub const TARGET_DONE: u8 = 65;
+    /// Host-tool back-channel: status of last RX command.
+    pub const RX_STATUS:   u8 = 66;
+    /// Response to a host query for object data.
+    pub const QUERY_DATA:  u8 = 67;
+    /// Response to a host memory-peek request.
+    pub const PEEK_DATA:   u8 = 68;
+    /// Assertion failed on the target.
+    pub const ASSERT_FAIL: u8 = 69;
+    /// Framework `run()` loop entered.
+    pub const QF_RUN:      u8 = 70;
+    /// The trace buffer lost frames (qp-rs extension): `ts | dropped u32 |
+    /// overwritten u32`, running totals. Never filtered out.
+    pub const OVERFLOW:    u8 = 90;
+
+    /// Object kinds of `QS_RX_CURR_OBJ` and `QUERY_DATA`.
+    pub mod obj_kind {
+        /// State machine.
+        pub const SM: u8 = 0;
+        /// Active object.
+        pub const AO: u8 = 1;
+        /// Memory pool.
+        pub const MP: u8 = 2;
+        /// Event queue.
+        pub const EQ: u8 = 3;
+        /// Time event.
+        pub const TE: u8 = 4;
+        /// Application object.
+        pub const AP: u8 = 5;
+    }
+}
+
+/// QXK extended-kernel record identifiers (71–80, plus the qp-rs thread-stack
+/// record).
+pub mod qxk {
+    /// Semaphore taken (count was > 0).
+    pub const SEM_TAKE:           u8 = 71;
+    /// Semaphore wait blocked (count == 0, thread suspended).
+    pub const SEM_BLOCK:          u8 = 72;
+    /// Semaphore signalled (count incremented, waiter possibly woken).
+    pub const SEM_SIGNAL:         u8 = 73;
+    /// Semaphore `try_wait` attempt failed (non-blocking path).
+    pub const SEM_BLOCK_ATTEMPT:  u8 = 74;
+    /// Mutex locked by calling thread.
+    pub const MTX_LOCK:           u8 = 75;
+    /// Mutex lock blocked (already held, thread suspended).
+    pub const MTX_BLOCK:          u8 = 76;
+    /// Mutex unlocked by calling thread.
+    pub const MTX_UNLOCK:         u8 = 77;
+    /// Mutex `try_lock` attempt failed (already held).
+    pub const MTX_LOCK_ATTEMPT:   u8 = 78;
+    /// Mutex `try_lock` attempt failed (non-blocking path, already held).
+    pub const MTX_BLOCK_ATTEMPT:  u8 = 79;
+    /// Mutex `unlock` attempt failed (caller is not the owner).
+    pub const MTX_UNLOCK_ATTEMPT: u8 = 80;
+    /// A thread's stack reached the usage threshold or overflowed (qp-rs
+    /// extension).
+    pub const THREAD_STACK:       u8 = 86;
+}
+
+/// Dictionary and target-information record identifiers.
+pub mod dict {
+    /// Record identifier for `QS_ENUM_DICT`.
+    pub const ENUM_DICT: u8 = 54;
+    /// Record identifier for `QS_SIG_DICT`.
+    pub const SIG_DICT: u8 = 60;
+    /// Record identifier for `QS_OBJ_DICT`.
+    pub const OBJ_DICT: u8 = 61;
+    /// Record identifier for `QS_FUN_DICT`.
+    pub const FUN_DICT: u8 = 62;
+    /// Record identifier for `QS_USR_DICT`.
+    pub const USR_DICT: u8 = 63;
+    /// Record identifier for `QS_TARGET_INFO`.
+    pub const TARGET_INFO: u8 = 64;
+    /// Record identifier for `QS_DICT_HASH` (qp-rs extension): a digest of
+    /// the dictionary records sent so far.
+    pub const DICT_HASH: u8 = 87;
+    /// Record identifier for `QS_SYM_MODE` (qp-rs extension): whether the
+    /// object and function fields that follow carry addresses (payload `0`)
+    /// or stable symbol ids (payload `1`).
+    pub const SYM_MODE: u8 = 96;
+}
@@ -0,0 +1,148 @@
+//! The `QS_TARGET_INFO` record.
+//!
+//! The payload is 16 bytes, sizes packed two to a byte:
+//!
+//! | byte  | content                                   |
+//! |-------|-------------------------------------------|
+//! | 0     | `is_reset`                                |
+//! | 1–2   | `version`, little-endian                  |
+//! | 3     | `signal_size \| event_size << 4`          |
+//! | 4     | `equeue_ctr_size \| time_evt_ctr_size << 4` |
+//! | 5     | `mpool_size_size \| mpool_ctr_size << 4`  |
+//! | 6     | `obj_ptr_size \| fun_ptr_size << 4`       |
+//! | 7     | `time_size`                               |
+//! | 8     | `max_active`                              |
+//! | 9     | `max_event_pools \| max_tick_rate << 4`   |
+//! | 10–12 | build time: second, minute, hour          |
+//! | 13–15 | build date: day, month, year % 100        |
+
+/// Contents of the `QS_TARGET_INFO` record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetInfo {
+    /// `0xFF` for a reset (power-up) info record, `0x00` otherwise.
+    pub is_reset: u8,
+    /// QP framework version (e.g. `740`).
+    pub version: u16,
+    /// Byte width of a signal on the target.
+    pub signal_size: u8,
+    /// Byte width of an event size field.
+    pub event_size: u8,
+    /// Byte width of an event-queue counter.
+    pub equeue_ctr_size: u8,
+    /// Byte width of a time-event counter.
+    pub time_evt_ctr_size: u8,
+    /// Byte width of a memory-pool block-size field.
+    pub mpool_size_size: u8,
+    /// Byte width of a memory-pool counter.
+    pub mpool_ctr_size: u8,
+    /// Byte width of an object pointer on the target.
+    pub obj_ptr_size: u8,
+    /// Byte width of a function pointer on the target.
+    pub fun_ptr_size: u8,
+    /// Byte width of a QS timestamp.
+    pub time_size: u8,
+    /// Maximum number of active objects.
+    pub max_active: u8,
+    /// Maximum number of event pools.
+    pub max_event_pools: u8,
+    /// Maximum number of tick-rate domains.
+    pub max_tick_rate: u8,
+    /// Build time as `(hour, minute, second)`.
+    pub build_time: (u8, u8, u8),
+    /// Build date as `(day, month, year % 100)`.
+    pub build_date: (u8, u8, u8),
+}
+
+impl Default for TargetInfo {
+    fn default() -> Self {
+        Self {
+            is_reset: 0xFF,
+            version: 740,
+            signal_size: 2,
+            event_size: 2,
+            equeue_ctr_size: 2,
+            time_evt_ctr_size: 2,
+            mpool_size_size: 2,
+            mpool_ctr_size: 2,
+            obj_ptr_size: 8,
+            fun_ptr_size: 8,
+            time_size: 4,
+            max_active: 16,
+            max_event_pools: 3,
+            max_tick_rate: 4,
+            build_time: (11, 13, 21),
+            build_date: (18, 10, 25),
+        }
+    }
+}
+
+impl TargetInfo {
+    /// Length of the encoded payload.
+    pub const LEN: usize = 16;
+
+    /// The record payload.
+    pub fn encode(&self) -> [u8; Self::LEN] {
+        let [version_lo, version_hi] = self.version.to_le_bytes();
+        let (hour, minute, second) = self.build_time;
+        let (day, month, year) = self.build_date;
+        [
+            self.is_reset,
+            version_lo,
+            version_hi,
+            self.signal_size | (self.event_size << 4),
+            self.equeue_ctr_size | (self.time_evt_ctr_size << 4),
+            self.mpool_size_size | (self.mpool_ctr_size << 4),
+            self.obj_ptr_size | (self.fun_ptr_size << 4),
+            self.time_size,
+            self.max_active,
+            self.max_event_pools | (self.max_tick_rate << 4),
+            second,
+            minute,
+            hour,
+            day,
+            month,
+            year,
+        ]
+    }
+
+    /// Parses a payload; `None` if it is shorter than [`LEN`](Self::LEN).
+    /// Sizes are returned as sent, valid or not.
+    pub fn decode(payload: &[u8]) -> Option<Self> {
+        let p: &[u8; Self::LEN] = payload.get(..Self::LEN)?.try_into().ok()?;
+        let lo = |b: u8| b & 0x0F;
+        let hi = |b: u8| b >> 4;
+        Some(Self {
+            is_reset: p[0],
+            version: u16::from_le_bytes([p[1], p[2]]),
+            signal_size: lo(p[3]),
+            event_size: hi(p[3]),
+            equeue_ctr_size: lo(p[4]),
+            time_evt_ctr_size: hi(p[4]),
+            mpool_size_size: lo(p[5]),
+            mpool_ctr_size: hi(p[5]),
+            obj_ptr_size: lo(p[6]),
+            fun_ptr_size: hi(p[6]),
+            time_size: p[7],
+            max_active: p[8],
+            max_event_pools: lo(p[9]),
+            max_tick_rate: hi(p[9]),
+            build_time: (p[12], p[11], p[10]),
+            build_date: (p[13], p[14], p[15]),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_rejects_short_payloads() {
+        let info = TargetInfo { obj_ptr_size: 4, fun_ptr_size: 4, equeue_ctr_size: 1, ..TargetInfo::default() };
+        let bytes = info.encode();
+        assert_eq!(bytes[6], 0x44);
+        assert_eq!(bytes[10..], [21, 13, 11, 18, 10, 25]);
+        assert_eq!(TargetInfo::decode(&bytes), Some(info));
+        assert_eq!(TargetInfo::decode(&bytes[..15]), None);
+    }
+}
@@ -0,0 +1,205 @@
+//! Assertion reporting: `QS_ASSERT_FAIL` emission and a panic adapter.
+//!
+//! A target registers its tracer once with [`set_assert_tracer`]; from then on
+//! [`assert_fail`] emits `QS_ASSERT_FAIL` (record 69) and flushes the backend
+//! before returning, so the fault location is on the wire before the caller
+//! halts or resets. [`report_panic`] maps a panic onto the same record, which
+//! makes it usable from a `#[panic_handler]` on `no_std` targets (enable the
+//! `panic-handler` feature to have this crate provide one) or from a std panic
+//! hook via [`install_panic_hook`].
+//!
+//! Emission never blocks: if the tracer is already locked — e.g. the fault
+//! happened while a record was being encoded — the report is dropped rather
+//! than deadlocking the crash path.
+//!
+//! Payload layout, as decoded by qspy: `[ts] | loc: u16 | module: C string`.
+//! The payload is built on the stack, so reporting never allocates.
+
+use alloc::sync::Arc;
+
+#[cfg(feature = "std")]
+use std::sync::{Mutex, TryLockError};
+#[cfg(not(feature = "std"))]
+use spin::Mutex;
+
+use crate::records::infra::ASSERT_FAIL;
+use crate::{TraceBackend, TracerHandle};
+
+/// Longest module name carried in the record; longer names keep their tail,
+/// which holds the most specific path segments.
+pub const MAX_MODULE_LEN: usize = 48;
+
+/// Largest `QS_ASSERT_FAIL` payload: location, module name and terminator.
+const MAX_PAYLOAD_LEN: usize = 2 + MAX_MODULE_LEN + 1;
+
+type AssertSink = Arc<dyn Fn(&str, u32) + Send + Sync>;
+
+static SINK: Mutex<Option<AssertSink>> = Mutex::new(None);
+
+/// Route [`assert_fail`] reports through `tracer`, replacing any previous one.
+pub fn set_assert_tracer<B: TraceBackend + 'static>(tracer: &TracerHandle<B>) {
+    let inner = Arc::clone(&tracer.inner);
+    let sink: AssertSink = Arc::new(move |module, loc| {
+        #[cfg(feature = "std")]
+        let mut guard = match inner.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => return,
+        };
+        #[cfg(not(feature = "std"))]
+        let Some(mut guard) = inner.try_lock() else { return };
+
+        let payload = assert_fail_payload(module, loc, guard.cfg.max_record_len);
+        if !payload.is_empty() && guard.record(ASSERT_FAIL, &payload, true).is_ok() {
+            let _ = guard.backend.flush();
+        }
+    });
+    *lock_sink() = Some(sink);
+}
+
+/// Stop reporting assertions (the next [`assert_fail`] becomes a no-op).
+pub fn clear_assert_tracer() {
+    *lock_sink() = None;
+}
+
+/// Emit `QS_ASSERT_FAIL` for `module` at `loc` and flush it synchronously.
+///
+/// Does nothing if no tracer was registered. `loc` is carried as `u16` on the
+/// wire (QS convention) and saturates above `u16::MAX`.
+pub fn assert_fail(module: &str, loc: u32) {
+    let sink = lock_sink().clone();
+    if let Some(sink) = sink {
+        sink(module, loc);
+    }
+}
+
+/// A `QS_ASSERT_FAIL` payload held in a fixed buffer; derefs to its bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct AssertPayload {
+    buf: [u8; MAX_PAYLOAD_LEN],
+    len: usize,
+}
+
+impl core::ops::Deref for AssertPayload {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+/// Build the `QS_ASSERT_FAIL` payload (without timestamp) for `module`/`loc`.
+///
+/// The module name keeps its tail so the payload fits in both
+/// [`MAX_MODULE_LEN`] and `max_record_len`; below the 3 bytes of location and
+/// terminator the payload comes back empty.
+pub fn assert_fail_payload(module: &str, loc: u32, max_record_len: usize) -> AssertPayload {
+    let mut payload = AssertPayload { buf: [0; MAX_PAYLOAD_LEN], len: 0 };
+    let cap = max_record_len.min(MAX_PAYLOAD_LEN);
+    if cap < 3 {
+        return payload;
+    }
+    payload.buf[..2].copy_from_slice(&(loc.min(u16::MAX as u32) as u16).to_le_bytes());
+    let bytes = module.as_bytes();
+    let name = &bytes[bytes.len().saturating_sub(cap - 3)..];
+    let mut len = 2;
+    for &b in name.iter().filter(|&&b| b != 0) {
+        payload.buf[len] = b;
+        len += 1;
+    }
+    // The terminator is already in place: the buffer starts zeroed.
+    payload.len = len + 1;
+    payload
+}
+
+/// Report a panic as `QS_ASSERT_FAIL`, using the panic location's file and
+/// line (or `"panic"`/0 when the location is unknown). Takes the location
+/// rather than the panic info so it serves both `#[panic_handler]` and std
+/// panic hooks.
+pub fn report_panic(location: Option<&core::panic::Location<'_>>) {
+    match location {
+        Some(loc) => assert_fail(loc.file(), loc.line()),
+        None => assert_fail("panic", 0),
+    }
+}
+
+/// Chain [`report_panic`] in front of the current std panic hook.
+#[cfg(feature = "std")]
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(alloc::boxed::Box::new(move |info| {
+        report_panic(info.location());
+        previous(info);
+    }));
+}
+
+#[cfg(all(feature = "panic-handler", not(feature = "std")))]
+#[panic_handler]
+fn panic(info: &core::panic::PanicInfo<'_>) -> ! {
+    report_panic(info.location());
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[cfg(feature = "std")]
+fn lock_sink() -> std::sync::MutexGuard<'static, Option<AssertSink>> {
+    SINK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(not(feature = "std"))]
+fn lock_sink() -> spin::MutexGuard<'static, Option<AssertSink>> {
+    SINK.lock()
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{QsConfig, TraceError, Tracer};
+
+    #[derive(Clone, Default)]
+    struct Capture {
+        frames:  Arc<Mutex<Vec<Vec<u8>>>>,
+        flushes: Arc<Mutex<usize>>,
+    }
+
+    impl TraceBackend for Capture {
+        fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
+            self.frames.lock().unwrap().push(frame.to_vec());
+            Ok(())
+        }
+        fn flush(&self) -> Result<(), TraceError> {
+            *self.flushes.lock().unwrap() += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn payload_layout_and_truncation() {
+        assert_eq!(*assert_fail_payload("qf", 7, 64), [7, 0, b'q', b'f', 0]);
+        assert_eq!(assert_fail_payload("m", 70_000, 64)[..2], [0xFF, 0xFF]);
+
+        let long = "a".repeat(10) + &"b".repeat(MAX_MODULE_LEN);
+        let p = assert_fail_payload(&long, 1, 64);
+        assert_eq!(p.len(), 2 + MAX_MODULE_LEN + 1);
+        assert!(p[2..2 + MAX_MODULE_LEN].iter().all(|&b| b == b'b'));
+
+        assert_eq!(*assert_fail_payload("qf::kernel", 7, 6), [7, 0, b'n', b'e', b'l', 0]);
+        assert!(assert_fail_payload("qf", 7, 2).is_empty());
+    }
+
+    #[test]
+    fn assert_fail_emits_and_flushes() {
+        let backend = Capture::default();
+        let handle = Tracer::new(QsConfig::default(), backend.clone()).into_handle();
+        set_assert_tracer(&handle);
+        assert_fail("qf::kernel", 42);
+        clear_assert_tracer();
+        assert_fail("qf::kernel", 43); // no tracer: dropped
+
+        let frames = backend.frames.lock().unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0][1], ASSERT_FAIL);
+        assert_eq!(*backend.flushes.lock().unwrap(), 1);
+    }
+}
@@ -0,0 +1,128 @@
+//! Critical-section tracing: `QS_QF_CRIT_ENTRY` / `QS_QF_CRIT_EXIT`.
+//!
+//! A port calls [`crit_entry`] right after disabling interrupts and
+//! [`crit_exit`] right before enabling them again; both records carry the
+//! nesting level, entry counting up from 1 and exit reporting the level being
+//! left. The host pairs them by timestamp to measure how long interrupts
+//! stayed off. Register the tracer once with [`set_crit_tracer`].
+//!
+//! With the `critical-section` feature, [`with`] wraps
+//! `critical_section::with` and makes both calls itself, so a kernel that
+//! takes its critical sections through it is traced without further changes.
+//!
+//! Like assertion reporting, emission never blocks: a record is dropped when
+//! the tracer is locked, e.g. when the section was entered while a record was
+//! being encoded. The nesting level is still kept.
+//!
+//! Payload layout, as decoded by qspy: `[ts] | nesting: u8`.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::records::qf::{CRIT_ENTRY, CRIT_EXIT};
+use crate::sink::Sink;
+use crate::{TraceBackend, TracerHandle};
+
+static SINK: Sink = Sink::new();
+
+/// Current nesting. Only touched with interrupts disabled, so a plain
+/// load/store pair is enough and works on cores without atomic RMW.
+static NESTING: AtomicU8 = AtomicU8::new(0);
+
+/// Route critical-section records through `tracer`, replacing any previous one.
+pub fn set_crit_tracer<B: TraceBackend + 'static>(tracer: &TracerHandle<B>) {
+    SINK.set(tracer);
+}
+
+/// Stop tracing critical sections; the nesting level is still counted.
+pub fn clear_crit_tracer() {
+    SINK.clear();
+}
+
+/// Current critical-section nesting level, 0 outside any section.
+pub fn crit_nesting() -> u8 {
+    NESTING.load(Ordering::Relaxed)
+}
+
+/// Record entry into a critical section. Call with interrupts disabled.
+pub fn crit_entry() {
+    let nesting = NESTING.load(Ordering::Relaxed).wrapping_add(1);
+    NESTING.store(nesting, Ordering::Relaxed);
+    SINK.emit(CRIT_ENTRY, &[nesting]);
+}
+
+/// Record exit from a critical section. Call before enabling interrupts.
+pub fn crit_exit() {
+    let nesting = NESTING.load(Ordering::Relaxed);
+    SINK.emit(CRIT_EXIT, &[nesting]);
+    NESTING.store(nesting.saturating_sub(1), Ordering::Relaxed);
+}
+
+/// `critical_section::with`, traced with [`crit_entry`] and [`crit_exit`].
+#[cfg(feature = "critical-section")]
+pub fn with<R>(f: impl FnOnce(critical_section::CriticalSection<'_>) -> R) -> R {
+    critical_section::with(|cs| {
+        crit_entry();
+        let result = f(cs);
+        crit_exit();
+        result
+    })
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{QsConfig, TraceError, Tracer};
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use std::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    struct Capture {
+        frames: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl TraceBackend for Capture {
+        fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
+            self.frames.lock().unwrap().push(frame.to_vec());
+            Ok(())
+        }
+    }
+
+    /// (record, nesting) of every traced frame: `[seq, rec, ts.., nesting, chk, FLAG]`.
+    fn records(capture: &Capture) -> Vec<(u8, u8)> {
+        capture.frames.lock().unwrap().iter().map(|frame| (frame[1], frame[frame.len() - 3])).collect()
+    }
+
+    // The sink and the nesting counter are process-wide, so one test covers
+    // them all to keep parallel tests out of each other's way.
+    #[test]
+    fn nested_sections_report_their_levels() {
+        let capture = Capture::default();
+        let tracer = Tracer::new(QsConfig::default(), capture.clone()).into_handle();
+        set_crit_tracer(&tracer);
+
+        crit_entry();
+        crit_entry();
+        assert_eq!(crit_nesting(), 2);
+        crit_exit();
+        crit_exit();
+        assert_eq!(crit_nesting(), 0);
+        assert_eq!(
+            records(&capture),
+            [(CRIT_ENTRY, 1), (CRIT_ENTRY, 2), (CRIT_EXIT, 2), (CRIT_EXIT, 1)]
+        );
+
+        #[cfg(feature = "critical-section")]
+        {
+            assert_eq!(with(|_| crit_nesting()), 1);
+            assert_eq!(records(&capture)[4..], [(CRIT_ENTRY, 1), (CRIT_EXIT, 1)]);
+        }
+
+        let traced = records(&capture).len();
+        clear_crit_tracer();
+        crit_entry();
+        crit_exit();
+        assert_eq!(records(&capture).len(), traced);
+        assert_eq!(crit_nesting(), 0);
+    }
+}
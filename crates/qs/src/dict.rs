@@ -0,0 +1,282 @@
+//! Dictionaries gathered in one place and sent in one call.
+//!
+//! QSpy names objects, state handlers, signals and user records only if the
+//! target sends a dictionary entry for each. [`Dictionary`] collects those
+//! entries — under the addresses the trace records really carry — and
+//! [`emit`](Dictionary::emit) sends them all at startup. [`qs_dictionary!`]
+//! builds one from lists, naming functions and signals after their
+//! identifiers:
+//!
+//! ```
+//! # fn initial() {} fn thinking() {} fn hungry() {}
+//! # const EAT_SIG: u16 = 4; const DONE_SIG: u16 = 5; const PHILO_STAT: u8 = 100;
+//! let mut dict = qs::qs_dictionary! {
+//!     fun "Philo" { initial, thinking, hungry }
+//!     sig { EAT_SIG, DONE_SIG }
+//!     usr { PHILO_STAT => "PHILO_STAT" }
+//! };
+//! // Objects known only at run time join the same dictionary.
+//! static TABLE: u8 = 0;
+//! dict.obj(qs::dict::addr_of(&TABLE), "Table::inst");
+//!
+//! assert_eq!(dict.len(), 7);
+//! assert!(dict.entries().contains(&qs::dict::DictEntry::Fun {
+//!     addr: thinking as *const () as usize as u64,
+//!     name: "Philo::thinking".into(),
+//! }));
+//! ```
+//!
+//! `fun` and `sig` entries take a bare identifier, named after it, or
+//! `expr => "name"`. A `fun` list may carry a prefix, joined to each name
+//! with `::`. `obj` and `usr` entries always give the name.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::predefined::{
+    fun_dict_payload, obj_dict_payload, sig_dict_payload, usr_dict_payload, FUN_DICT, OBJ_DICT, SIG_DICT,
+    USR_DICT,
+};
+use crate::symbols::{self, SymbolMode};
+use crate::{TraceError, TraceHook};
+
+/// One dictionary record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DictEntry {
+    /// `QS_OBJ_DICT`: an object's address.
+    Obj { addr: u64, name: String },
+    /// `QS_FUN_DICT`: a function's address, typically a state handler.
+    Fun { addr: u64, name: String },
+    /// `QS_SIG_DICT`: a signal, global (`obj` 0) or private to an object.
+    Sig { signal: u16, obj: u64, name: String },
+    /// `QS_USR_DICT`: an application record id.
+    Usr { record: u8, name: String },
+}
+
+impl DictEntry {
+    /// Record type the entry is sent as.
+    pub fn record_type(&self) -> u8 {
+        match self {
+            Self::Obj { .. } => OBJ_DICT,
+            Self::Fun { .. } => FUN_DICT,
+            Self::Sig { .. } => SIG_DICT,
+            Self::Usr { .. } => USR_DICT,
+        }
+    }
+
+    /// Payload of the record.
+    pub fn payload(&self) -> Vec<u8> {
+        match self {
+            Self::Obj { addr, name } => obj_dict_payload(*addr, name),
+            Self::Fun { addr, name } => fun_dict_payload(*addr, name),
+            Self::Sig { signal, obj, name } => sig_dict_payload(*signal, *obj, name),
+            Self::Usr { record, name } => usr_dict_payload(*record, name),
+        }
+    }
+}
+
+/// Dictionary entries, in the order they are sent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dictionary {
+    entries: Vec<DictEntry>,
+}
+
+impl Dictionary {
+    /// An empty dictionary.
+    pub const fn new() -> Self {
+        Self { entries: Vec::new() }
+    }
+
+    /// Names the object at `addr`.
+    pub fn obj(&mut self, addr: u64, name: impl Into<String>) -> &mut Self {
+        self.entries.push(DictEntry::Obj { addr, name: name.into() });
+        self
+    }
+
+    /// Names the function at `addr`.
+    pub fn fun(&mut self, addr: u64, name: impl Into<String>) -> &mut Self {
+        self.entries.push(DictEntry::Fun { addr, name: name.into() });
+        self
+    }
+
+    /// Names `signal`, for every object when `obj` is 0.
+    pub fn sig(&mut self, signal: impl Into<u16>, obj: u64, name: impl Into<String>) -> &mut Self {
+        self.entries.push(DictEntry::Sig { signal: signal.into(), obj, name: name.into() });
+        self
+    }
+
+    /// Names a table of global signals, such as an application's
+    /// `(signal, name)` list.
+    pub fn signals<S: Into<u16>, N: Into<String>>(&mut self, signals: impl IntoIterator<Item = (S, N)>) -> &mut Self {
+        for (signal, name) in signals {
+            self.sig(signal, 0, name);
+        }
+        self
+    }
+
+    /// Names the user record `record`.
+    pub fn usr(&mut self, record: u8, name: impl Into<String>) -> &mut Self {
+        self.entries.push(DictEntry::Usr { record, name: name.into() });
+        self
+    }
+
+    /// The entries so far.
+    pub fn entries(&self) -> &[DictEntry] {
+        &self.entries
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// `true` if there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Sends every entry through `hook`, stopping at the first error. With
+    /// [stable ids](crate::symbols) a `QS_SYM_MODE` record goes first, so the
+    /// host knows the entries name ids.
+    pub fn emit(&self, hook: &TraceHook) -> Result<(), TraceError> {
+        if symbols::mode() == SymbolMode::StableIds {
+            symbols::announce(hook)?;
+        }
+        for entry in &self.entries {
+            hook(entry.record_type(), &entry.payload(), false)?;
+        }
+        Ok(())
+    }
+}
+
+/// Address of `obj` as QS records carry it, for [`Dictionary::obj`].
+pub fn addr_of<T: ?Sized>(obj: &T) -> u64 {
+    obj as *const T as *const () as usize as u64
+}
+
+/// Builds a [`Dictionary`](crate::dict::Dictionary) from `obj`, `fun`,
+/// `sig` and `usr` lists. See the [module documentation](crate::dict).
+#[macro_export]
+macro_rules! qs_dictionary {
+    (@sections $d:ident) => {};
+    (@sections $d:ident obj { $($addr:expr => $name:expr),* $(,)? } $($rest:tt)*) => {
+        $( $d.obj($addr, $name); )*
+        $crate::qs_dictionary!(@sections $d $($rest)*);
+    };
+    (@sections $d:ident usr { $($record:expr => $name:expr),* $(,)? } $($rest:tt)*) => {
+        $( $d.usr($record, $name); )*
+        $crate::qs_dictionary!(@sections $d $($rest)*);
+    };
+    (@sections $d:ident fun $prefix:literal { $($body:tt)* } $($rest:tt)*) => {
+        $crate::qs_dictionary!(@fun $d [$prefix] $($body)*);
+        $crate::qs_dictionary!(@sections $d $($rest)*);
+    };
+    (@sections $d:ident fun { $($body:tt)* } $($rest:tt)*) => {
+        $crate::qs_dictionary!(@fun $d [] $($body)*);
+        $crate::qs_dictionary!(@sections $d $($rest)*);
+    };
+    (@sections $d:ident sig { $($body:tt)* } $($rest:tt)*) => {
+        $crate::qs_dictionary!(@sig $d $($body)*);
+        $crate::qs_dictionary!(@sections $d $($rest)*);
+    };
+
+    (@fun $d:ident [$($prefix:literal)?]) => {};
+    (@fun $d:ident [$($prefix:literal)?] , $($rest:tt)*) => {
+        $crate::qs_dictionary!(@fun $d [$($prefix)?] $($rest)*);
+    };
+    (@fun $d:ident [] $f:expr => $name:literal $(, $($rest:tt)*)?) => {
+        $d.fun($f as *const () as usize as u64, $name);
+        $crate::qs_dictionary!(@fun $d [] $($($rest)*)?);
+    };
+    (@fun $d:ident [$prefix:literal] $f:expr => $name:literal $(, $($rest:tt)*)?) => {
+        $d.fun($f as *const () as usize as u64, concat!($prefix, "::", $name));
+        $crate::qs_dictionary!(@fun $d [$prefix] $($($rest)*)?);
+    };
+    (@fun $d:ident [] $f:ident $($rest:tt)*) => {
+        $d.fun($f as *const () as usize as u64, stringify!($f));
+        $crate::qs_dictionary!(@fun $d [] $($rest)*);
+    };
+    (@fun $d:ident [$prefix:literal] $f:ident $($rest:tt)*) => {
+        $d.fun($f as *const () as usize as u64, concat!($prefix, "::", stringify!($f)));
+        $crate::qs_dictionary!(@fun $d [$prefix] $($rest)*);
+    };
+
+    (@sig $d:ident) => {};
+    (@sig $d:ident , $($rest:tt)*) => {
+        $crate::qs_dictionary!(@sig $d $($rest)*);
+    };
+    (@sig $d:ident $signal:expr => $name:expr $(, $($rest:tt)*)?) => {
+        $d.sig($signal, 0, $name);
+        $crate::qs_dictionary!(@sig $d $($($rest)*)?);
+    };
+    (@sig $d:ident $signal:ident $($rest:tt)*) => {
+        $d.sig($signal, 0, stringify!($signal));
+        $crate::qs_dictionary!(@sig $d $($rest)*);
+    };
+
+    ($($sections:tt)*) => {{
+        let mut dict = $crate::dict::Dictionary::new();
+        $crate::qs_dictionary!(@sections dict $($sections)*);
+        dict
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::sync::Arc;
+
+    fn initial() {}
+    fn serving() {}
+    fn top() {}
+
+    const EAT_SIG: u16 = 4;
+    const DONE_SIG: u16 = 5;
+
+    #[test]
+    fn macro_names_entries_after_their_identifiers_and_prefix() {
+        let dict = crate::qs_dictionary! {
+            usr { 100 => "PHILO_STAT" }
+            fun "Table" { initial, serving }
+            fun { top => "QP::QHsm::top" }
+            sig { EAT_SIG, DONE_SIG + 1 => "HUNGRY_SIG", }
+        };
+        let fun = |f: fn(), name: &str| DictEntry::Fun { addr: f as usize as u64, name: name.into() };
+        let sig = |signal, name: &str| DictEntry::Sig { signal, obj: 0, name: name.into() };
+        assert_eq!(
+            dict.entries(),
+            [
+                DictEntry::Usr { record: 100, name: "PHILO_STAT".into() },
+                fun(initial, "Table::initial"),
+                fun(serving, "Table::serving"),
+                fun(top, "QP::QHsm::top"),
+                sig(4, "EAT_SIG"),
+                sig(6, "HUNGRY_SIG"),
+            ]
+        );
+    }
+
+    #[test]
+    fn emit_sends_each_entry_as_its_dictionary_record() {
+        static TABLE: u8 = 0;
+        let mut dict = Dictionary::new();
+        dict.obj(addr_of(&TABLE), "Table::inst").signals([(EAT_SIG, "EAT_SIG")]);
+
+        let sent = Arc::new(spin::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&sent);
+        let hook: TraceHook = Arc::new(move |record, payload: &[u8], _| {
+            sink.lock().push((record, payload.to_vec()));
+            Ok(())
+        });
+        dict.emit(&hook).unwrap();
+
+        assert_eq!(
+            *sent.lock(),
+            [
+                (OBJ_DICT, obj_dict_payload(&TABLE as *const u8 as u64, "Table::inst")),
+                (SIG_DICT, sig_dict_payload(EAT_SIG, 0, "EAT_SIG")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! Moving buffered trace bytes to the transport from the idle callback.
+//!
+//! A target that traces into a [`TraceRing`] has to move the bytes out to a
+//! UART or RTT channel when it has nothing else to do. Doing that by hand
+//! goes wrong in a few ways: looping until the ring is empty never returns
+//! while the application keeps tracing, and retrying a full transport spins
+//! the idle loop instead of letting it sleep. [`IdleDrain`] does a bounded
+//! amount of work per call and stops as soon as the transport is full:
+//!
+//! ```rust,ignore
+//! static QS_BUF: TraceRing<2048> = TraceRing::new();
+//! let mut drain = IdleDrain::new(&QS_BUF, uart_tx).with_budget(64);
+//!
+//! fn on_idle() {
+//!     if !drain.on_idle().more_pending() {
+//!         cortex_m::asm::wfi();
+//!     }
+//! }
+//! ```
+
+use crate::ring::TraceRing;
+use crate::rtt::RttChannel;
+
+/// A transport the drain writes trace bytes to.
+pub trait TransportSink {
+    /// Writes a prefix of `bytes` without blocking and returns its length;
+    /// `0` when the transport has no room right now.
+    fn write(&mut self, bytes: &[u8]) -> usize;
+}
+
+impl<F: FnMut(&[u8]) -> usize> TransportSink for F {
+    fn write(&mut self, bytes: &[u8]) -> usize {
+        self(bytes)
+    }
+}
+
+impl TransportSink for RttChannel {
+    fn write(&mut self, bytes: &[u8]) -> usize {
+        RttChannel::write(self, bytes)
+    }
+}
+
+/// What one [`IdleDrain::on_idle`] call did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrainReport {
+    /// Bytes handed to the transport.
+    pub sent: usize,
+    /// Bytes still buffered afterwards.
+    pub pending: usize,
+    /// The transport took less than it was offered.
+    pub sink_full: bool,
+}
+
+impl DrainReport {
+    /// Whether calling again right away would move more bytes: some are
+    /// buffered and the transport had room.
+    pub fn more_pending(&self) -> bool {
+        self.pending > 0 && !self.sink_full
+    }
+}
+
+/// Drains a [`TraceRing`] into a [`TransportSink`], at most
+/// [`budget`](Self::with_budget) bytes per call.
+pub struct IdleDrain<'a, const N: usize, S: TransportSink> {
+    ring: &'a TraceRing<N>,
+    sink: S,
+    budget: usize,
+    block: usize,
+    sent: u64,
+}
+
+impl<'a, const N: usize, S: TransportSink> IdleDrain<'a, N, S> {
+    /// Default bytes moved per call.
+    pub const DEFAULT_BUDGET: usize = 128;
+
+    /// A drain from `ring` into `sink`, moving up to
+    /// [`DEFAULT_BUDGET`](Self::DEFAULT_BUDGET) bytes per call.
+    pub fn new(ring: &'a TraceRing<N>, sink: S) -> Self {
+        Self { ring, sink, budget: Self::DEFAULT_BUDGET, block: usize::MAX, sent: 0 }
+    }
+
+    /// Moves at most `bytes` per call (at least one).
+    pub fn with_budget(mut self, bytes: usize) -> Self {
+        self.budget = bytes.max(1);
+        self
+    }
+
+    /// Offers the transport at most `bytes` per write, for sinks with a
+    /// small FIFO or DMA buffer (at least one).
+    pub fn with_block_size(mut self, bytes: usize) -> Self {
+        self.block = bytes.max(1);
+        self
+    }
+
+    /// Moves buffered bytes to the transport until the budget is spent, the
+    /// ring is empty or the transport is full.
+    pub fn on_idle(&mut self) -> DrainReport {
+        let mut sent = 0;
+        let mut sink_full = false;
+        while sent < self.budget {
+            let max = (self.budget - sent).min(self.block);
+            let mut offered = 0;
+            let sink = &mut self.sink;
+            let taken = self.ring.get_block(max, |block| {
+                offered = block.len();
+                sink.write(block)
+            });
+            sent += taken;
+            if offered == 0 {
+                break;
+            }
+            if taken < offered {
+                sink_full = true;
+                break;
+            }
+        }
+        self.sent += sent as u64;
+        DrainReport { sent, pending: self.ring.len(), sink_full }
+    }
+
+    /// Bytes sent over the drain's lifetime.
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+
+    /// The transport.
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// The transport, mutably (to service it outside the idle callback).
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A transport with room for `room` more bytes.
+    struct Uart {
+        out: Vec<u8>,
+        room: usize,
+        writes: usize,
+    }
+
+    impl TransportSink for Uart {
+        fn write(&mut self, bytes: &[u8]) -> usize {
+            self.writes += 1;
+            let n = bytes.len().min(self.room);
+            self.out.extend_from_slice(&bytes[..n]);
+            self.room -= n;
+            n
+        }
+    }
+
+    fn uart(room: usize) -> Uart {
+        Uart { out: Vec::new(), room, writes: 0 }
+    }
+
+    #[test]
+    fn each_call_moves_at_most_the_budget() {
+        let ring = TraceRing::<64>::new();
+        ring.push_frame(&[7; 40]);
+        let mut drain = IdleDrain::new(&ring, uart(usize::MAX)).with_budget(16).with_block_size(6);
+
+        let report = drain.on_idle();
+        assert_eq!(report, DrainReport { sent: 16, pending: 24, sink_full: false });
+        assert!(report.more_pending());
+        assert_eq!(drain.sink().writes, 3);
+        drain.on_idle();
+        let report = drain.on_idle();
+        assert_eq!(report, DrainReport { sent: 8, pending: 0, sink_full: false });
+        assert!(!report.more_pending());
+        assert_eq!(drain.sent(), 40);
+        assert_eq!(drain.sink().out, [7; 40]);
+    }
+
+    #[test]
+    fn a_full_transport_ends_the_call_without_retrying() {
+        let ring = TraceRing::<16>::new();
+        ring.push_frame(&[1, 2, 3, 4, 5]);
+        let mut drain = IdleDrain::new(&ring, uart(2));
+
+        let report = drain.on_idle();
+        assert_eq!(report, DrainReport { sent: 2, pending: 3, sink_full: true });
+        assert!(!report.more_pending());
+        let report = drain.on_idle();
+        assert_eq!((report.sent, drain.sink().writes), (0, 2));
+
+        drain.sink_mut().room = 8;
+        assert_eq!(drain.on_idle().sent, 3);
+        assert_eq!(drain.sink().out, [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn wrapped_bytes_are_drained_in_order() {
+        let ring = TraceRing::<8>::new();
+        ring.push_frame(&[1, 2, 3, 4, 5, 6]);
+        let mut out = Vec::new();
+        let mut drain = IdleDrain::new(&ring, |bytes: &[u8]| {
+            out.extend_from_slice(bytes);
+            bytes.len()
+        });
+        drain.on_idle();
+        ring.push_frame(&[7, 8, 9, 10]);
+        drain.on_idle();
+        assert_eq!(out, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+    }
+}
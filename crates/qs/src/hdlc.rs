@@ -0,0 +1,207 @@
+//! HDLC framing of the QS stream, shared by every encoder and decoder.
+//!
+//! A frame is the byte-stuffed body `seq | record type | data… | checksum`
+//! followed by [`FLAG`]. The checksum is the complement of the 8-bit sum of
+//! the unstuffed body, so the body including it sums to `0xFF`. [`FLAG`] and
+//! [`ESC`] inside the body go out as [`ESC`], `byte ^ ESC_XOR`.
+//!
+//! Nothing here allocates: [`FrameEncoder`] writes through a byte callback
+//! and [`encode_into`] into a borrowed buffer, so a `no_std` target without
+//! a heap frames records the same way the host tools do.
+
+use crate::TraceError;
+
+/// Frame delimiter.
+pub const FLAG: u8 = 0x7E;
+/// Escape byte announcing a stuffed byte.
+pub const ESC: u8 = 0x7D;
+/// XOR mask applied to a stuffed byte.
+pub const ESC_XOR: u8 = 0x20;
+
+/// Longest encoding of a frame with a `body`-byte unstuffed body before the
+/// checksum: every byte escaped, plus the checksum and the closing flag.
+pub const fn max_frame_len(body: usize) -> usize {
+    2 * (body + 1) + 1
+}
+
+/// The checksum byte closing a frame whose body is `body`.
+pub fn checksum(body: &[u8]) -> u8 {
+    !body.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))
+}
+
+/// `true` if `frame` — an unstuffed body ending in its checksum — sums to
+/// `0xFF`.
+pub fn checksum_ok(frame: &[u8]) -> bool {
+    frame.iter().fold(0u8, |sum, &b| sum.wrapping_add(b)) == 0xFF
+}
+
+/// Writes `byte` through `out`, stuffed if it is [`FLAG`] or [`ESC`].
+#[inline]
+pub fn stuff(byte: u8, out: &mut impl FnMut(u8)) {
+    if byte == FLAG || byte == ESC {
+        out(ESC);
+        out(byte ^ ESC_XOR);
+    } else {
+        out(byte);
+    }
+}
+
+/// Builds one frame byte by byte, keeping the checksum as it goes.
+///
+/// ```
+/// let mut wire = Vec::new();
+/// let mut frame = qs::hdlc::FrameEncoder::new(|b| wire.push(b));
+/// frame.push(0x01); // seq
+/// frame.push(0x7E); // record type, stuffed
+/// frame.finish();
+/// assert_eq!(wire, [0x01, 0x7D, 0x5E, 0x80, 0x7E]);
+/// ```
+pub struct FrameEncoder<F: FnMut(u8)> {
+    out:      F,
+    checksum: u8,
+}
+
+impl<F: FnMut(u8)> FrameEncoder<F> {
+    pub fn new(out: F) -> Self {
+        Self { out, checksum: 0 }
+    }
+
+    /// Adds one body byte.
+    #[inline]
+    pub fn push(&mut self, byte: u8) {
+        self.checksum = self.checksum.wrapping_add(byte);
+        stuff(byte, &mut self.out);
+    }
+
+    /// Adds body bytes.
+    pub fn extend(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.push(b);
+        }
+    }
+
+    /// Writes the checksum and the closing flag.
+    pub fn finish(mut self) {
+        stuff(!self.checksum, &mut self.out);
+        (self.out)(FLAG);
+    }
+}
+
+/// Encodes the body `parts`, in order, as one frame into `buf`.
+///
+/// Returns the frame, or [`TraceError::PayloadTooLarge`] with the size it
+/// needed if `buf` is too small.
+pub fn encode_into<'a>(buf: &'a mut [u8], parts: &[&[u8]]) -> Result<&'a [u8], TraceError> {
+    let mut len = 0;
+    let mut needed = 0;
+    let capacity = buf.len();
+    let mut frame = FrameEncoder::new(|b| {
+        if needed < capacity {
+            buf[needed] = b;
+            len += 1;
+        }
+        needed += 1;
+    });
+    for part in parts {
+        frame.extend(part);
+    }
+    frame.finish();
+    if len < needed {
+        return Err(TraceError::PayloadTooLarge(needed));
+    }
+    Ok(&buf[..len])
+}
+
+/// What one stream byte means once unstuffed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unstuffed {
+    /// A body byte.
+    Byte(u8),
+    /// The flag: the frame so far is complete.
+    End,
+    /// An escape; the next byte carries the value.
+    Escape,
+}
+
+/// Undoes byte stuffing one stream byte at a time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Unstuffer {
+    escaped: bool,
+}
+
+impl Unstuffer {
+    pub const fn new() -> Self {
+        Self { escaped: false }
+    }
+
+    /// Interprets `byte`. A flag always ends the frame, even right after an
+    /// escape, so a corrupt frame cannot bleed into the next one.
+    #[inline]
+    pub fn push(&mut self, byte: u8) -> Unstuffed {
+        if byte == FLAG {
+            self.escaped = false;
+            Unstuffed::End
+        } else if core::mem::take(&mut self.escaped) {
+            Unstuffed::Byte(byte ^ ESC_XOR)
+        } else if byte == ESC {
+            self.escaped = true;
+            Unstuffed::Escape
+        } else {
+            Unstuffed::Byte(byte)
+        }
+    }
+
+    /// Forgets a pending escape.
+    pub fn reset(&mut self) {
+        self.escaped = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_into_a_buffer_and_reports_shortfall() {
+        let mut buf = [0u8; 16];
+        let frame = encode_into(&mut buf, &[&[0x05, 0x7D], &[0x10]]).unwrap();
+        // 0x05 + 0x7D + 0x10 = 0x92, checksum 0x6D.
+        assert_eq!(frame, [0x05, 0x7D, 0x5D, 0x10, 0x6D, FLAG]);
+
+        let mut small = [0u8; 4];
+        assert!(matches!(encode_into(&mut small, &[&[0x05, 0x7D], &[0x10]]), Err(TraceError::PayloadTooLarge(6))));
+        assert!(max_frame_len(3) >= 6);
+    }
+
+    #[test]
+    fn unstuffing_round_trips_and_checks_the_sum() {
+        let body = [0x7E, 0x00, 0x7D, 0xFF];
+        let mut buf = [0u8; 16];
+        let frame = encode_into(&mut buf, &[&body]).unwrap();
+
+        let mut unstuffer = Unstuffer::new();
+        let mut decoded = [0u8; 8];
+        let mut n = 0;
+        for &b in frame {
+            match unstuffer.push(b) {
+                Unstuffed::Byte(v) => {
+                    decoded[n] = v;
+                    n += 1;
+                }
+                Unstuffed::Escape => {}
+                Unstuffed::End => break,
+            }
+        }
+        assert_eq!(&decoded[..4], body);
+        assert_eq!(decoded[4], checksum(&body));
+        assert!(checksum_ok(&decoded[..n]));
+    }
+
+    #[test]
+    fn a_flag_cancels_a_pending_escape() {
+        let mut unstuffer = Unstuffer::new();
+        assert_eq!(unstuffer.push(ESC), Unstuffed::Escape);
+        assert_eq!(unstuffer.push(FLAG), Unstuffed::End);
+        assert_eq!(unstuffer.push(0x41), Unstuffed::Byte(0x41));
+    }
+}
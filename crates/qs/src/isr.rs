@@ -0,0 +1,127 @@
+//! Interrupt tracing: `QS_QF_ISR_ENTRY` / `QS_QF_ISR_EXIT`.
+//!
+//! An interrupt handler calls [`isr_entry`] first and [`isr_exit`] last,
+//! passing its priority. Both records carry the interrupt nesting level and
+//! that priority, so the host sees which handler preempted which and how
+//! long each ran. [`IsrScope`] makes the exit call on drop, and with the
+//! `macros` feature `#[qs::qs_traced_isr(prio = N)]` wraps a whole handler
+//! in one. Register the tracer once with [`set_isr_tracer`].
+//!
+//! Records that would wait for the tracer are dropped; the nesting level is
+//! kept regardless.
+//!
+//! Payload layout, as decoded by qspy: `[ts] | nesting: u8 | prio: u8`.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::records::qf::{ISR_ENTRY, ISR_EXIT};
+use crate::sink::Sink;
+use crate::{TraceBackend, TracerHandle};
+
+static SINK: Sink = Sink::new();
+
+/// Current nesting. Interrupts nest strictly, so a handler that preempts
+/// another between its load and store has put the level back by the time
+/// the preempted one resumes: a load/store pair is enough.
+static NESTING: AtomicU8 = AtomicU8::new(0);
+
+/// Route ISR records through `tracer`, replacing any previous one.
+pub fn set_isr_tracer<B: TraceBackend + 'static>(tracer: &TracerHandle<B>) {
+    SINK.set(tracer);
+}
+
+/// Stop tracing interrupts; the nesting level is still counted.
+pub fn clear_isr_tracer() {
+    SINK.clear();
+}
+
+/// Current interrupt nesting level, 0 in thread mode.
+pub fn isr_nesting() -> u8 {
+    NESTING.load(Ordering::Relaxed)
+}
+
+/// Record entry into a handler running at priority `prio`.
+pub fn isr_entry(prio: u8) {
+    let nesting = NESTING.load(Ordering::Relaxed).wrapping_add(1);
+    NESTING.store(nesting, Ordering::Relaxed);
+    SINK.emit(ISR_ENTRY, &[nesting, prio]);
+}
+
+/// Record exit from the handler at priority `prio`.
+pub fn isr_exit(prio: u8) {
+    let nesting = NESTING.load(Ordering::Relaxed);
+    SINK.emit(ISR_EXIT, &[nesting, prio]);
+    NESTING.store(nesting.saturating_sub(1), Ordering::Relaxed);
+}
+
+/// A traced handler body: [`isr_entry`] on creation, [`isr_exit`] on drop,
+/// so early returns are covered too.
+#[must_use = "the exit is traced when the scope is dropped"]
+pub struct IsrScope {
+    prio: u8,
+}
+
+impl IsrScope {
+    pub fn enter(prio: u8) -> Self {
+        isr_entry(prio);
+        Self { prio }
+    }
+}
+
+impl Drop for IsrScope {
+    fn drop(&mut self) {
+        isr_exit(self.prio);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{QsConfig, TraceError, Tracer};
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use std::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    struct Capture {
+        frames: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl TraceBackend for Capture {
+        fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
+            self.frames.lock().unwrap().push(frame.to_vec());
+            Ok(())
+        }
+    }
+
+    // Process-wide state again: a single test.
+    #[test]
+    fn nested_handlers_report_level_and_priority() {
+        let capture = Capture::default();
+        let tracer = Tracer::new(QsConfig::default(), capture.clone()).into_handle();
+        set_isr_tracer(&tracer);
+
+        {
+            let _low = IsrScope::enter(1);
+            isr_entry(5);
+            assert_eq!(isr_nesting(), 2);
+            isr_exit(5);
+        }
+        assert_eq!(isr_nesting(), 0);
+        clear_isr_tracer();
+        drop(IsrScope::enter(3));
+
+        // [seq, rec, ts.., nesting, prio, chk, FLAG]
+        let records: Vec<_> = capture
+            .frames
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|frame| (frame[1], frame[frame.len() - 4], frame[frame.len() - 3]))
+            .collect();
+        assert_eq!(
+            records,
+            [(ISR_ENTRY, 1, 1), (ISR_ENTRY, 2, 5), (ISR_EXIT, 2, 5), (ISR_EXIT, 1, 1)]
+        );
+    }
+}
@@ -15,18 +15,14 @@ extern crate alloc;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
-#[cfg(not(feature = "std"))]
-use core::time::Duration;
-
 #[cfg(feature = "std")]
 use std::io::{self, Write};
 #[cfg(feature = "std")]
 use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
 #[cfg(feature = "std")]
-use std::time::SystemTime;
-#[cfg(feature = "std")]
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use alloc::collections::BTreeSet;
 use alloc::sync::Arc;
 
 #[cfg(feature = "std")]
@@ -35,17 +31,62 @@ use std::sync::Mutex;
 use spin::Mutex;
 
 mod record;
-
+mod sink;
+
+pub mod assert;
+pub mod crit;
+pub mod dict;
+pub mod drain;
+pub mod hdlc;
+pub mod isr;
+pub mod local;
+pub mod nmi;
+#[cfg(feature = "std")]
+pub mod order;
+pub mod pack;
+pub mod peek;
 pub mod predefined;
+pub mod query;
 pub mod qutest;
 pub mod records;
+pub mod reset;
+pub mod ring;
+pub mod rtt;
 pub mod rx;
+pub mod selftest;
+pub mod stats;
+pub mod symbols;
+pub mod timestamp;
 
-pub use predefined::TargetInfo;
+pub use assert::{assert_fail, report_panic, set_assert_tracer};
+pub use crit::{crit_entry, crit_exit, set_crit_tracer};
+#[cfg(feature = "std")]
+pub use assert::install_panic_hook;
+pub use drain::{DrainReport, IdleDrain, TransportSink};
+pub use isr::{isr_entry, isr_exit, set_isr_tracer};
+#[cfg(feature = "macros")]
+pub use qs_macros::qs_traced_isr;
+pub use local::{current_qs_id, set_qs_id_source, LocFilter, QsIdScope, QsIdSource};
+pub use nmi::{Breadcrumb, NmiSource, NmiTrace};
+#[cfg(feature = "std")]
+pub use order::{OrderedTracer, StageScope};
+pub use pack::DatagramPacker;
+pub use predefined::{DictHash, TargetInfo};
+pub use qs_protocol as protocol;
+pub use records::{Predefined, RecordSizes};
 pub use qutest::{clear_test_probes, set_test_probe, take_test_probe};
+pub use ring::{OverflowPolicy, TraceRing};
+#[cfg(feature = "rtt")]
+pub use rtt::init_rtt;
+pub use rtt::{RttChannel, RttControlBlock};
 pub use rx::{RxCmd, RxParser};
+pub use selftest::qs_selftest;
+pub use stats::{stats, QsStats};
+#[cfg(feature = "std")]
+pub use timestamp::MonotonicClock;
+pub use timestamp::{ManualClock, TickCounter, TimestampClock, TimestampSize, TimestampSource};
 pub use record::{
-    make_format, UserRecordBuilder, FMT_F32, FMT_F64, FMT_FUN, FMT_HEX, FMT_I16, FMT_I32, FMT_I64,
+    make_format, UserRecordBuilder, UserRecordEncoder, FMT_F32, FMT_F64, FMT_FUN, FMT_HEX, FMT_I16, FMT_I32, FMT_I64,
     FMT_I8_ENUM, FMT_MEM, FMT_OBJ, FMT_SIG, FMT_STR, FMT_U16, FMT_U32, FMT_U64, FMT_U8,
 };
 
@@ -59,6 +100,16 @@ pub struct QsConfig {
     pub max_record_len: usize,
     /// Whether to include a timestamp in records that request one.
     pub include_timestamp: bool,
+    /// Clock read for timestamps. Defaults to a [`MonotonicClock`] in
+    /// microseconds on `std`, and to none (records go out untimed) on
+    /// `no_std`, where the port must supply one.
+    pub timestamp_source: Option<TimestampClock>,
+    /// Timestamp width on the wire; must match the `time_size` announced in
+    /// `QS_TARGET_INFO`.
+    pub timestamp_size: TimestampSize,
+    /// Field widths for predefined records built with [`records`]; must
+    /// match the sizes announced in `QS_TARGET_INFO`.
+    pub sizes: RecordSizes,
 }
 
 impl Default for QsConfig {
@@ -66,7 +117,41 @@ impl Default for QsConfig {
         Self {
             max_record_len: DEFAULT_MAX_RECORD_LEN,
             include_timestamp: true,
+            #[cfg(feature = "std")]
+            timestamp_source: Some(TimestampClock::monotonic()),
+            #[cfg(not(feature = "std"))]
+            timestamp_source: None,
+            timestamp_size: TimestampSize::default(),
+            sizes: RecordSizes::NATIVE,
+        }
+    }
+}
+
+impl QsConfig {
+    /// Reads timestamps from `source`.
+    pub fn with_timestamp_source(mut self, source: impl TimestampSource + 'static) -> Self {
+        self.timestamp_source = Some(TimestampClock::new(source));
+        self
+    }
+
+    /// Sends timestamps `size` wide.
+    pub fn with_timestamp_size(mut self, size: TimestampSize) -> Self {
+        self.timestamp_size = size;
+        self
+    }
+
+    /// Lays predefined records out at `sizes`.
+    pub fn with_sizes(mut self, sizes: RecordSizes) -> Self {
+        self.sizes = sizes;
+        self
+    }
+
+    /// The timestamp for a record that asks for one, if timestamps are on.
+    pub(crate) fn timestamp(&self, with_timestamp: bool) -> Option<u32> {
+        if !(self.include_timestamp && with_timestamp) {
+            return None;
         }
+        self.timestamp_source.as_ref().map(|clock| clock.read(self.timestamp_size))
     }
 }
 
@@ -77,8 +162,9 @@ pub struct QsRecord {
     pub seq: u8,
     /// QS record type id.
     pub record_type: u8,
-    /// Optional timestamp captured when the record was emitted.
-    pub timestamp: Option<Duration>,
+    /// Timestamp captured when the record was emitted, in counts of the
+    /// configured [`TimestampSource`], already truncated to its width.
+    pub timestamp: Option<u32>,
     /// Record payload bytes.
     pub payload: Vec<u8>,
 }
@@ -129,6 +215,18 @@ impl From<io::Error> for TraceError {
 pub trait TraceBackend: Send + Sync {
     /// Writes one complete HDLC-framed record to the transport.
     fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError>;
+
+    /// Pushes any buffered frames out to the transport. Unbuffered backends
+    /// keep the default no-op.
+    fn flush(&self) -> Result<(), TraceError> {
+        Ok(())
+    }
+
+    /// Frames this backend has lost to overflow. Backends that never drop
+    /// keep the default zeros.
+    fn stats(&self) -> QsStats {
+        QsStats::default()
+    }
 }
 
 /// Simple backend that writes frames to any `Write` implementation.
@@ -153,6 +251,10 @@ impl<W: Write + Send + Sync + 'static> TraceBackend for WriterBackend<W> {
         let mut guard = self.writer.lock().unwrap();
         guard.write_all(frame).map_err(TraceError::from)
     }
+
+    fn flush(&self) -> Result<(), TraceError> {
+        self.writer.lock().unwrap().flush().map_err(TraceError::from)
+    }
 }
 
 /// QS frame encoder.
@@ -161,9 +263,52 @@ pub struct Tracer<B: TraceBackend> {
     backend: B,
     cfg: QsConfig,
     seq: u8,
-    #[cfg(feature = "std")]
-    epoch: SystemTime,
     filter: GlbFilter,
+    loc_filter: LocFilter,
+    dict_hash: DictHash,
+    /// Entries already in `dict_hash`.
+    dict_entries: BTreeSet<u32>,
+    /// Backend losses last reported with `QS_OVERFLOW`.
+    reported: QsStats,
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static BUSY: core::cell::Cell<bool> = const { core::cell::Cell::new(false) };
+}
+
+/// Returns `true` while this thread is inside a tracer: encoding a record or
+/// holding a [`TracerHandle`]'s lock.
+///
+/// Code that may run underneath the tracer and emits through it in turn
+/// (allocators, panic hooks) checks this first, since emitting would lock
+/// the tracer again on the same thread.
+#[cfg(feature = "std")]
+pub fn tracer_busy() -> bool {
+    BUSY.try_with(|b| b.get()).unwrap_or(false)
+}
+
+/// Marks this thread [busy](tracer_busy) until dropped. A no-op without
+/// `std`, where there is nothing to ask.
+struct BusyScope {
+    #[cfg(feature = "std")]
+    prev: bool,
+}
+
+impl BusyScope {
+    fn enter() -> Self {
+        Self {
+            #[cfg(feature = "std")]
+            prev: BUSY.try_with(|b| b.replace(true)).unwrap_or(false),
+        }
+    }
+}
+
+impl Drop for BusyScope {
+    fn drop(&mut self) {
+        #[cfg(feature = "std")]
+        let _ = BUSY.try_with(|b| b.set(self.prev));
+    }
 }
 
 /// Cheaply clonable, thread-safe handle to a shared [`Tracer`].
@@ -179,12 +324,19 @@ impl<B: TraceBackend> Tracer<B> {
             backend,
             cfg,
             seq: 0,
-            #[cfg(feature = "std")]
-            epoch: SystemTime::now(),
             filter: GlbFilter::allow_all(),
+            loc_filter: LocFilter::allow_all(),
+            dict_hash: DictHash::new(),
+            dict_entries: BTreeSet::new(),
+            reported: QsStats::default(),
         }
     }
 
+    /// Digest of the dictionary records sent so far.
+    pub fn dict_hash(&self) -> DictHash {
+        self.dict_hash
+    }
+
     /// Replace the global filter.  Records whose bit is 0 are silently dropped.
     pub fn set_filter(&mut self, filter: GlbFilter) {
         self.filter = filter;
@@ -195,6 +347,23 @@ impl<B: TraceBackend> Tracer<B> {
         &self.filter
     }
 
+    /// Replace the local filter. Records emitted under a blocked QS-ID (see
+    /// [`current_qs_id`]) are silently dropped.
+    pub fn set_loc_filter(&mut self, filter: LocFilter) {
+        self.loc_filter = filter;
+    }
+
+    /// Returns a reference to the current local filter.
+    pub fn loc_filter(&self) -> &LocFilter {
+        &self.loc_filter
+    }
+
+    /// Replace the clock timestamps are read from; `None` sends records
+    /// untimed.
+    pub fn set_timestamp_source(&mut self, source: Option<TimestampClock>) {
+        self.cfg.timestamp_source = source;
+    }
+
     /// Wraps the tracer in a shareable [`TracerHandle`].
     pub fn into_handle(self) -> TracerHandle<B> {
         TracerHandle {
@@ -203,14 +372,18 @@ impl<B: TraceBackend> Tracer<B> {
     }
 
     /// Encodes and writes one record, returning the encoded [`QsRecord`].
-    /// Records suppressed by the global filter are returned without being sent.
+    /// Records suppressed by the global or local filter are returned without
+    /// being sent.
     pub fn record(
         &mut self,
         record_type: u8,
         payload: &[u8],
         with_timestamp: bool,
     ) -> Result<QsRecord, TraceError> {
-        if !self.filter.is_allowed(record_type) {
+        let _busy = BusyScope::enter();
+        let overflow = record_type == records::infra::OVERFLOW;
+        let from_allowed = current_qs_id().is_none_or(|id| self.loc_filter.is_allowed(id));
+        if !overflow && (!self.filter.is_allowed(record_type) || !from_allowed) {
             return Ok(QsRecord {
                 seq: self.seq,
                 record_type,
@@ -222,14 +395,16 @@ impl<B: TraceBackend> Tracer<B> {
             return Err(TraceError::PayloadTooLarge(payload.len()));
         }
 
-        let timestamp = if self.cfg.include_timestamp && with_timestamp {
-            #[cfg(feature = "std")]
-            { Some(self.epoch.elapsed().unwrap_or_default()) }
-            #[cfg(not(feature = "std"))]
-            { None }
-        } else {
-            None
-        };
+        // Losses since the last report go out first, so QSPY can tell a
+        // gap in the sequence from a corrupt link. A report that is lost too
+        // bumps the count and is retried with the next record.
+        let lost = self.backend.stats();
+        if !overflow && lost != self.reported {
+            self.reported = lost;
+            self.record(records::infra::OVERFLOW, &lost.payload(), true)?;
+        }
+
+        let timestamp = self.cfg.timestamp(with_timestamp);
 
         self.seq = self.seq.wrapping_add(1);
         #[cfg(all(debug_assertions, feature = "std"))]
@@ -243,69 +418,61 @@ impl<B: TraceBackend> Tracer<B> {
             payload: payload.to_vec(),
         };
 
-        let frame = self.build_frame(&record);
+        let frame = encode_frame(&record, self.cfg.timestamp_size);
         self.backend.write_frame(&frame)?;
-        Ok(record)
-    }
-
-    fn build_frame(&self, record: &QsRecord) -> Vec<u8> {
-        const FLAG: u8 = 0x7E;
-        const ESC: u8 = 0x7D;
-        const ESC_XOR: u8 = 0x20;
-
-        let mut bytes = Vec::with_capacity(record.payload.len() + 8);
-        let mut checksum: u8 = 0;
-
-        let push_escaped = |dest: &mut Vec<u8>, sum: &mut u8, byte: u8| {
-            *sum = sum.wrapping_add(byte);
-            if byte == FLAG || byte == ESC {
-                dest.push(ESC);
-                dest.push(byte ^ ESC_XOR);
-            } else {
-                dest.push(byte);
+        if DictHash::covers(record_type) {
+            let entry = DictHash::entry(record_type, payload);
+            if self.dict_entries.insert(entry) {
+                self.dict_hash.add_entry(entry);
             }
-        };
-
-        let push_literal = |dest: &mut Vec<u8>, byte: u8| {
-            if byte == FLAG || byte == ESC {
-                dest.push(ESC);
-                dest.push(byte ^ ESC_XOR);
-            } else {
-                dest.push(byte);
-            }
-        };
-
-        push_escaped(&mut bytes, &mut checksum, record.seq);
-        push_escaped(&mut bytes, &mut checksum, record.record_type);
-
-        if let Some(ts) = record.timestamp {
-            let ticks = (ts.as_micros() as u32).to_le_bytes();
-            for byte in ticks {
-                push_escaped(&mut bytes, &mut checksum, byte);
-            }
-        }
-
-        for &byte in &record.payload {
-            push_escaped(&mut bytes, &mut checksum, byte);
         }
-
-        let checksum_byte = !checksum;
-        push_literal(&mut bytes, checksum_byte);
-
-        bytes.push(FLAG);
-        bytes
+        Ok(record)
     }
 }
 
+/// HDLC-encodes `record`: escaped `seq | type | [timestamp] | payload |
+/// checksum`, then the flag byte. The timestamp is `ts_size` bytes wide.
+pub fn encode_frame(record: &QsRecord, ts_size: TimestampSize) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(record.payload.len() + 8);
+    let mut frame = hdlc::FrameEncoder::new(|b| bytes.push(b));
+    frame.push(record.seq);
+    frame.push(record.record_type);
+    if let Some(ts) = record.timestamp {
+        frame.extend(&ts.to_le_bytes()[..ts_size.bytes() as usize]);
+    }
+    frame.extend(&record.payload);
+    frame.finish();
+    bytes
+}
+
 impl<B: TraceBackend + 'static> TracerHandle<B> {
     /// Replace the global filter on the underlying tracer.
     pub fn set_filter(&self, filter: GlbFilter) {
+        let _busy = BusyScope::enter();
         #[cfg(feature = "std")]
         self.inner.lock().unwrap().set_filter(filter);
         #[cfg(not(feature = "std"))]
         self.inner.lock().set_filter(filter);
     }
 
+    /// Replace the local filter on the underlying tracer.
+    pub fn set_loc_filter(&self, filter: LocFilter) {
+        let _busy = BusyScope::enter();
+        #[cfg(feature = "std")]
+        self.inner.lock().unwrap().set_loc_filter(filter);
+        #[cfg(not(feature = "std"))]
+        self.inner.lock().set_loc_filter(filter);
+    }
+
+    /// Replace the timestamp clock on the underlying tracer.
+    pub fn set_timestamp_source(&self, source: Option<TimestampClock>) {
+        let _busy = BusyScope::enter();
+        #[cfg(feature = "std")]
+        self.inner.lock().unwrap().set_timestamp_source(source);
+        #[cfg(not(feature = "std"))]
+        self.inner.lock().set_timestamp_source(source);
+    }
+
     /// Emits a record without a timestamp.
     pub fn emit(&self, record_type: u8, payload: &[u8]) -> Result<QsRecord, TraceError> {
         self.emit_internal(record_type, payload, false)
@@ -332,12 +499,48 @@ impl<B: TraceBackend + 'static> TracerHandle<B> {
             .map(|_| ())
     }
 
+    /// Emits a predefined record laid out at the configured
+    /// [`sizes`](QsConfig::sizes).
+    pub fn emit_predefined(&self, record: &Predefined) -> Result<QsRecord, TraceError> {
+        let _busy = BusyScope::enter();
+        #[cfg(feature = "std")]
+        let mut guard = self.inner.lock().unwrap();
+        #[cfg(not(feature = "std"))]
+        let mut guard = self.inner.lock();
+        let payload = record.encode(&guard.cfg.sizes);
+        guard.record(record.record_type(), &payload, record.has_timestamp())
+    }
+
+    /// Emits `QS_DICT_HASH` with the digest of the dictionaries sent so far,
+    /// letting a viewer check the dictionaries it cached. Send it after the
+    /// dictionaries, and again whenever a viewer may have attached late.
+    pub fn emit_dict_hash(&self) -> Result<QsRecord, TraceError> {
+        let _busy = BusyScope::enter();
+        #[cfg(feature = "std")]
+        let mut guard = self.inner.lock().unwrap();
+        #[cfg(not(feature = "std"))]
+        let mut guard = self.inner.lock();
+        let payload = guard.dict_hash.payload();
+        guard.record(predefined::DICT_HASH, &payload, false)
+    }
+
+    /// Flushes the backend of the underlying tracer.
+    pub fn flush(&self) -> Result<(), TraceError> {
+        let _busy = BusyScope::enter();
+        #[cfg(feature = "std")]
+        let guard = self.inner.lock().unwrap();
+        #[cfg(not(feature = "std"))]
+        let guard = self.inner.lock();
+        guard.backend.flush()
+    }
+
     fn emit_internal(
         &self,
         record_type: u8,
         payload: &[u8],
         with_timestamp: bool,
     ) -> Result<QsRecord, TraceError> {
+        let _busy = BusyScope::enter();
         #[cfg(feature = "std")]
         let mut guard = self.inner.lock().unwrap();
         #[cfg(not(feature = "std"))]
@@ -350,6 +553,7 @@ impl<B: TraceBackend + 'static> TracerHandle<B> {
     pub fn hook(&self) -> TraceHook {
         let inner = Arc::clone(&self.inner);
         Arc::new(move |record_type, payload, with_timestamp| {
+            let _busy = BusyScope::enter();
             #[cfg(feature = "std")]
             let mut guard = inner.lock().unwrap();
             #[cfg(not(feature = "std"))]
@@ -359,12 +563,30 @@ impl<B: TraceBackend + 'static> TracerHandle<B> {
                 .map(|_| ())
         })
     }
+
+    /// Returns a [`TraceFlush`] closure that flushes this handle's backend,
+    /// for a kernel to call when it shuts down.
+    pub fn flusher(&self) -> TraceFlush {
+        let inner = Arc::clone(&self.inner);
+        Arc::new(move || {
+            let _busy = BusyScope::enter();
+            #[cfg(feature = "std")]
+            let guard = inner.lock().unwrap();
+            #[cfg(not(feature = "std"))]
+            let guard = inner.lock();
+            guard.backend.flush()
+        })
+    }
 }
 
 /// Shared callback used across the framework to emit a QS record
 /// `(record_type, payload, with_timestamp)`.
 pub type TraceHook = Arc<dyn Fn(u8, &[u8], bool) -> Result<(), TraceError> + Send + Sync>;
 
+/// Callback pushing the frames a tracer's backend still buffers out to the
+/// transport, paired with the [`TraceHook`] emitting through that tracer.
+pub type TraceFlush = Arc<dyn Fn() -> Result<(), TraceError> + Send + Sync>;
+
 /// 256-bit per-record-type filter.
 ///
 /// Each bit position corresponds to a QS record type (0–255). When a bit is 0
@@ -470,28 +692,206 @@ mod std_backends {
             let mut guard = self.stream.lock().unwrap();
             guard.write_all(frame).map_err(TraceError::from)
         }
+
+        fn flush(&self) -> Result<(), TraceError> {
+            self.stream.lock().unwrap().flush().map_err(TraceError::from)
+        }
     }
 
     /// Backend that streams QS frames over a UDP socket.
+    ///
+    /// [`connect`](Self::connect) sends one datagram per frame.
+    /// [`connect_batched`](Self::connect_batched) packs frames into datagrams
+    /// as configured by [`UdpBatching`] (see [`DatagramPacker`]); buffered
+    /// frames leave when the datagram fills up, on [`TraceBackend::flush`],
+    /// or once the oldest has waited `max_delay`, so a quiet system still
+    /// delivers its last records promptly.
     pub struct UdpBackend {
         socket: Arc<Mutex<UdpSocket>>,
+        batch: Option<Arc<Mutex<Batch>>>,
+    }
+
+    /// Datagram batching for [`UdpBackend::connect_batched`].
+    #[derive(Debug, Clone, Copy)]
+    pub struct UdpBatching {
+        /// Largest datagram built from several frames.
+        pub mtu: usize,
+        /// Longest a frame may wait in a partly filled datagram.
+        pub max_delay: Duration,
+        /// Cut a frame longer than `mtu` across consecutive datagrams
+        /// ([`DatagramPacker::new`]) instead of sending it whole in one
+        /// oversize datagram ([`DatagramPacker::whole_frames`]). The qspy
+        /// stream decoder accepts both; receivers that look at datagrams one
+        /// at a time need whole frames.
+        pub split_oversize: bool,
+    }
+
+    impl Default for UdpBatching {
+        fn default() -> Self {
+            Self {
+                mtu: pack::DEFAULT_UDP_MTU,
+                max_delay: Duration::from_millis(10),
+                split_oversize: false,
+            }
+        }
+    }
+
+    struct Batch {
+        packer: DatagramPacker,
+        /// When the oldest buffered frame was pushed.
+        since: Option<Instant>,
+    }
+
+    impl Batch {
+        fn new(packer: DatagramPacker) -> Self {
+            Self { packer, since: None }
+        }
+
+        fn push(&mut self, socket: &UdpSocket, frame: &[u8]) -> Result<(), TraceError> {
+            let result = self.packer.push(frame, |d| send(socket, d));
+            self.stamp();
+            result
+        }
+
+        fn flush(&mut self, socket: &UdpSocket) -> Result<(), TraceError> {
+            let result = self.packer.flush(|d| send(socket, d));
+            self.stamp();
+            result
+        }
+
+        fn stamp(&mut self) {
+            match self.packer.pending() {
+                0 => self.since = None,
+                _ => {
+                    self.since.get_or_insert_with(Instant::now);
+                }
+            }
+        }
+    }
+
+    fn send(socket: &UdpSocket, datagram: &[u8]) -> Result<(), TraceError> {
+        socket.send(datagram).map(|_| ()).map_err(TraceError::from)
     }
 
     impl UdpBackend {
         /// Binds a local UDP socket and connects it to the provided remote address.
         pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
-            let socket = UdpSocket::bind("0.0.0.0:0")?;
-            socket.connect(addr)?;
             Ok(Self {
-                socket: Arc::new(Mutex::new(socket)),
+                socket: Arc::new(Mutex::new(Self::bind(addr)?)),
+                batch: None,
             })
         }
+
+        /// Like [`connect`](Self::connect), but coalesces frames into
+        /// datagrams as configured by `batching`. A background thread sends a
+        /// partly filled datagram once its oldest frame has waited
+        /// `batching.max_delay`; it exits when the backend is dropped.
+        pub fn connect_batched<A: ToSocketAddrs>(addr: A, batching: UdpBatching) -> io::Result<Self> {
+            let socket = Arc::new(Mutex::new(Self::bind(addr)?));
+            let packer = match batching.split_oversize {
+                true => DatagramPacker::new(batching.mtu),
+                false => DatagramPacker::whole_frames(batching.mtu),
+            };
+            let batch = Arc::new(Mutex::new(Batch::new(packer)));
+            let weak = Arc::downgrade(&batch);
+            let flusher_socket = Arc::clone(&socket);
+            let max_delay = batching.max_delay.max(Duration::from_micros(100));
+            std::thread::Builder::new()
+                .name("qs-udp-flush".into())
+                .spawn(move || {
+                    let mut wait = max_delay;
+                    loop {
+                        std::thread::sleep(wait);
+                        let Some(batch) = weak.upgrade() else { break };
+                        let socket = flusher_socket.lock().unwrap_or_else(|p| p.into_inner());
+                        let mut batch = batch.lock().unwrap_or_else(|p| p.into_inner());
+                        wait = match batch.since.map(|t| t.elapsed()) {
+                            Some(age) if age >= max_delay => {
+                                let _ = batch.flush(&socket);
+                                max_delay
+                            }
+                            Some(age) => max_delay - age,
+                            None => max_delay,
+                        };
+                    }
+                })?;
+            Ok(Self { socket, batch: Some(batch) })
+        }
+
+        fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<UdpSocket> {
+            let socket = UdpSocket::bind("0.0.0.0:0")?;
+            socket.connect(addr)?;
+            Ok(socket)
+        }
     }
 
     impl TraceBackend for UdpBackend {
         fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
-            let guard = self.socket.lock().unwrap();
-            guard.send(frame).map(|_| ()).map_err(TraceError::from)
+            let guard = self.socket.lock().unwrap_or_else(|p| p.into_inner());
+            match &self.batch {
+                Some(batch) => batch.lock().unwrap_or_else(|p| p.into_inner()).push(&guard, frame),
+                None => send(&guard, frame),
+            }
+        }
+
+        fn flush(&self) -> Result<(), TraceError> {
+            let Some(batch) = &self.batch else { return Ok(()) };
+            let guard = self.socket.lock().unwrap_or_else(|p| p.into_inner());
+            batch.lock().unwrap_or_else(|p| p.into_inner()).flush(&guard)
+        }
+    }
+
+    impl Drop for UdpBackend {
+        /// Sends any partly filled datagram.
+        fn drop(&mut self) {
+            let _ = TraceBackend::flush(self);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn batched_udp_keeps_frames_whole_and_flushes_on_timer() {
+            let rx = UdpSocket::bind("127.0.0.1:0").unwrap();
+            rx.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+            let batching = UdpBatching { mtu: 8, max_delay: Duration::from_millis(20), split_oversize: false };
+            let backend = UdpBackend::connect_batched(rx.local_addr().unwrap(), batching).unwrap();
+
+            backend.write_frame(&[1, 2, 3, 0x7E]).unwrap();
+            backend.write_frame(&[4, 5, 6, 0x7E]).unwrap();
+            backend.write_frame(&[7, 8, 9, 10, 11, 12, 13, 14, 0x7E]).unwrap();
+            backend.write_frame(&[15, 0x7E]).unwrap();
+
+            let mut buf = [0u8; 64];
+            let mut next = || {
+                let n = rx.recv(&mut buf).unwrap();
+                buf[..n].to_vec()
+            };
+            assert_eq!(next(), [1, 2, 3, 0x7E, 4, 5, 6, 0x7E]);
+            assert_eq!(next(), [7, 8, 9, 10, 11, 12, 13, 14, 0x7E]);
+            // Nothing fills the last datagram: the timer sends it.
+            assert_eq!(next(), [15, 0x7E]);
+        }
+
+        #[test]
+        fn split_batching_cuts_oversize_frames_and_flushes_on_timer() {
+            let rx = UdpSocket::bind("127.0.0.1:0").unwrap();
+            rx.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+            let batching = UdpBatching { mtu: 4, max_delay: Duration::from_millis(20), split_oversize: true };
+            let backend = UdpBackend::connect_batched(rx.local_addr().unwrap(), batching).unwrap();
+
+            backend.write_frame(&[1, 2, 3, 4, 5, 6, 0x7E]).unwrap();
+
+            let mut buf = [0u8; 64];
+            let mut next = || {
+                let n = rx.recv(&mut buf).unwrap();
+                buf[..n].to_vec()
+            };
+            assert_eq!(next(), [1, 2, 3, 4]);
+            // The tail of the frame waits for more; the timer sends it.
+            assert_eq!(next(), [5, 6, 0x7E]);
         }
     }
 }
@@ -0,0 +1,236 @@
+//! Local (per-object) filtering by QS-ID.
+//!
+//! The global filter ([`GlbFilter`](crate::GlbFilter)) selects record *types*;
+//! the local filter selects record *sources*. A source is identified by its
+//! QS-ID; tracers drop records whose [current QS-ID](current_qs_id) is
+//! blocked. Records emitted while there is none are unattributed and always
+//! pass.
+//!
+//! The framework installs a [`QsIdSource`] reading the active object being
+//! dispatched (`qf::current_ao`), so every record emitted from a dispatch is
+//! attributed to that AO without application code passing its id around.
+//! Code outside a dispatch, such as a driver, can declare its own QS-ID for
+//! the duration of a call with [`QsIdScope`], which takes precedence over the
+//! source. Equivalent to `QS_LOC_FILTER()` in QP/C++.
+//!
+//! Both are read on every record, so neither takes a lock: the source is an
+//! atomic pointer, and scopes use atomic loads and stores only (no
+//! read-modify-write), which targets without CAS support too.
+
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// 128-bit per-QS-ID filter (QS-IDs 0–127, the range QP assigns).
+///
+/// QS-IDs 128–255 fall outside the filter and are always allowed.
+#[derive(Clone, Debug)]
+pub struct LocFilter {
+    bits: [u64; 2],
+}
+
+impl LocFilter {
+    /// Allow every QS-ID.
+    pub const fn allow_all() -> Self {
+        Self { bits: [u64::MAX; 2] }
+    }
+
+    /// Block every QS-ID in the filtered range.
+    pub const fn deny_all() -> Self {
+        Self { bits: [0; 2] }
+    }
+
+    /// Construct from a 16-byte little-endian bitmask (the `LOC_FILTER` wire
+    /// layout).
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        let lo = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+        let hi = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        Self { bits: [lo, hi] }
+    }
+
+    /// Allow records tagged with `qs_id`.
+    pub fn allow(&mut self, qs_id: u8) {
+        if let Some((word, bit)) = Self::addr(qs_id) {
+            self.bits[word] |= 1u64 << bit;
+        }
+    }
+
+    /// Block records tagged with `qs_id`.
+    pub fn block(&mut self, qs_id: u8) {
+        if let Some((word, bit)) = Self::addr(qs_id) {
+            self.bits[word] &= !(1u64 << bit);
+        }
+    }
+
+    /// Returns `true` if records tagged with `qs_id` are allowed.
+    pub fn is_allowed(&self, qs_id: u8) -> bool {
+        match Self::addr(qs_id) {
+            Some((word, bit)) => (self.bits[word] >> bit) & 1 != 0,
+            None => true,
+        }
+    }
+
+    fn addr(qs_id: u8) -> Option<(usize, u32)> {
+        (qs_id < 128).then_some(((qs_id / 64) as usize, (qs_id % 64) as u32))
+    }
+}
+
+impl Default for LocFilter {
+    fn default() -> Self {
+        Self::allow_all()
+    }
+}
+
+/// Returns the QS-ID records emitted right now are attributed to, if any.
+pub type QsIdSource = fn() -> Option<u8>;
+
+/// The installed [`QsIdSource`], or null.
+static SOURCE: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Installs (or removes) the function tracers ask for the current QS-ID.
+pub fn set_qs_id_source(source: Option<QsIdSource>) {
+    let raw = source.map_or(core::ptr::null_mut(), |f| f as *mut ());
+    SOURCE.store(raw, Ordering::Release);
+}
+
+fn source() -> Option<QsIdSource> {
+    let raw = SOURCE.load(Ordering::Acquire);
+    // SAFETY: `SOURCE` only ever holds null or a `QsIdSource` stored by
+    // `set_qs_id_source`.
+    (!raw.is_null()).then(|| unsafe { core::mem::transmute::<*mut (), QsIdSource>(raw) })
+}
+
+// The QS-ID declared by the innermost `QsIdScope`: per thread with `std`, a
+// single global otherwise (scopes then nest strictly, one preemption level
+// inside the next).
+#[cfg(feature = "std")]
+std::thread_local! {
+    static SCOPED: core::cell::Cell<Option<u8>> = const { core::cell::Cell::new(None) };
+}
+
+#[cfg(not(feature = "std"))]
+static SCOPED: core::sync::atomic::AtomicU16 = core::sync::atomic::AtomicU16::new(NONE);
+#[cfg(not(feature = "std"))]
+const NONE: u16 = u16::MAX;
+
+fn scoped() -> Option<u8> {
+    #[cfg(feature = "std")]
+    {
+        SCOPED.try_with(|c| c.get()).ok().flatten()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        decode(SCOPED.load(Ordering::Relaxed))
+    }
+}
+
+fn set_scoped(qs_id: Option<u8>) -> Option<u8> {
+    #[cfg(feature = "std")]
+    {
+        SCOPED.try_with(|c| c.replace(qs_id)).ok().flatten()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        // Load + store rather than swap: a preempting scope restores what it
+        // found before this one resumes.
+        let prev = decode(SCOPED.load(Ordering::Relaxed));
+        SCOPED.store(qs_id.map_or(NONE, u16::from), Ordering::Relaxed);
+        prev
+    }
+}
+
+#[cfg(not(feature = "std"))]
+fn decode(raw: u16) -> Option<u8> {
+    (raw != NONE).then_some(raw as u8)
+}
+
+/// QS-ID that records emitted right now are attributed to, if any: the
+/// innermost [`QsIdScope`], else the installed [`QsIdSource`].
+pub fn current_qs_id() -> Option<u8> {
+    scoped().or_else(|| source().and_then(|read| read()))
+}
+
+/// Attributes records emitted until drop to `qs_id`, then restores the
+/// enclosing attribution.
+#[must_use = "the QS-ID is only current while the scope is alive"]
+pub struct QsIdScope {
+    prev: Option<u8>,
+}
+
+impl QsIdScope {
+    /// Makes `qs_id` current.
+    pub fn enter(qs_id: u8) -> Self {
+        Self { prev: set_scoped(Some(qs_id)) }
+    }
+}
+
+impl Drop for QsIdScope {
+    fn drop(&mut self) {
+        set_scoped(self.prev);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_covers_low_ids_only() {
+        let mut f = LocFilter::deny_all();
+        assert!(!f.is_allowed(0));
+        assert!(!f.is_allowed(127));
+        assert!(f.is_allowed(128));
+        f.allow(65);
+        assert!(f.is_allowed(65));
+        f.block(65);
+        assert!(!f.is_allowed(65));
+
+        let mut bytes = [0u8; 16];
+        bytes[8] = 0b10;
+        assert!(LocFilter::from_bytes(bytes).is_allowed(65));
+    }
+
+    #[test]
+    fn scopes_nest() {
+        assert_eq!(scoped(), None);
+        {
+            let _outer = QsIdScope::enter(3);
+            assert_eq!(current_qs_id(), Some(3));
+            {
+                let _inner = QsIdScope::enter(9);
+                assert_eq!(current_qs_id(), Some(9));
+            }
+            assert_eq!(current_qs_id(), Some(3));
+        }
+        assert_eq!(scoped(), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn tracer_drops_records_from_blocked_ids() {
+        use core::cell::Cell;
+
+        use crate::{QsConfig, Tracer, WriterBackend};
+
+        std::thread_local! {
+            static ID: Cell<Option<u8>> = const { Cell::new(None) };
+        }
+        set_qs_id_source(Some(|| ID.with(Cell::get)));
+
+        let mut tracer = Tracer::new(QsConfig::default(), WriterBackend::new(Vec::new()));
+        let mut f = LocFilter::allow_all();
+        f.block(7);
+        tracer.set_loc_filter(f);
+
+        let sent = tracer.record(100, &[1], false).unwrap();
+        assert_eq!(sent.payload, [1]);
+        ID.with(|id| id.set(Some(7)));
+        assert!(tracer.record(100, &[2], false).unwrap().payload.is_empty());
+        ID.with(|id| id.set(Some(8)));
+        assert_eq!(tracer.record(100, &[3], false).unwrap().payload, [3]);
+        {
+            // A scope overrides the source.
+            let _scope = QsIdScope::enter(7);
+            assert!(tracer.record(100, &[4], false).unwrap().payload.is_empty());
+        }
+        ID.with(|id| id.set(None));
+    }
+}
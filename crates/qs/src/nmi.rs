@@ -0,0 +1,262 @@
+//! Trace breadcrumbs from non-maskable interrupts.
+//!
+//! Every path into the main QS buffer takes a lock: the tracer's mutex, the
+//! producer lock of a [`TraceRing`](crate::TraceRing), or the port's critical
+//! section. An NMI or a fault handler that preempts the holder and then
+//! traces waits forever for it. [`NmiTrace`] is a separate, much smaller
+//! buffer for such handlers. It holds fixed-size breadcrumbs (a user record id
+//! and one 32-bit value) and takes no lock on the write side: reserving a slot
+//! is a single compare-and-swap, so a handler preempting another writer
+//! simply gets the next slot.
+//!
+//! Breadcrumbs are merged into the regular trace later, from thread context,
+//! as user records with one `u32` field:
+//!
+//! ```rust,ignore
+//! static NMI_QS: NmiTrace<16> = NmiTrace::new();
+//!
+//! fn nmi_handler() {
+//!     NMI_QS.breadcrumb(WATCHDOG_NMI, read_fault_address());
+//! }
+//!
+//! // Later, e.g. from the idle callback or a QK kernel (`with_nmi_trace`):
+//! NMI_QS.merge(&hook);
+//! ```
+
+use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+
+use spin::Mutex;
+
+use crate::{TraceHook, UserRecordBuilder};
+
+/// One entry left by an interrupt handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Breadcrumb {
+    /// User record id the breadcrumb is merged as.
+    pub record: u8,
+    /// The value traced.
+    pub data: u32,
+}
+
+struct Slot {
+    /// Index + 1 of the breadcrumb stored here once it is complete.
+    ready: AtomicU32,
+    record: AtomicU8,
+    data: AtomicU32,
+}
+
+impl Slot {
+    const fn new() -> Self {
+        Self { ready: AtomicU32::new(0), record: AtomicU8::new(0), data: AtomicU32::new(0) }
+    }
+}
+
+/// A lock-free buffer of `N` breadcrumbs for handlers that must never wait.
+///
+/// Any number of handlers may write, at any priority; one thread-context
+/// consumer merges at a time.
+pub struct NmiTrace<const N: usize> {
+    slots: [Slot; N],
+    /// Breadcrumbs ever reserved.
+    head: AtomicU32,
+    /// Breadcrumbs ever merged.
+    tail: AtomicU32,
+    dropped: AtomicU32,
+    consumer: Mutex<()>,
+}
+
+impl<const N: usize> Default for NmiTrace<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> NmiTrace<N> {
+    /// An empty buffer, usable as a `static`.
+    pub const fn new() -> Self {
+        assert!(N > 0 && N <= u32::MAX as usize / 2, "NmiTrace needs 1..2^31 slots");
+        Self {
+            slots: [const { Slot::new() }; N],
+            head: AtomicU32::new(0),
+            tail: AtomicU32::new(0),
+            dropped: AtomicU32::new(0),
+            consumer: Mutex::new(()),
+        }
+    }
+
+    /// Leaves a breadcrumb, or counts it as dropped and returns `false` if
+    /// the buffer is full. Never blocks, so it is safe from an NMI.
+    pub fn breadcrumb(&self, record: u8, data: u32) -> bool {
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            if head.wrapping_sub(self.tail.load(Ordering::Acquire)) as usize >= N {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+            match self.head.compare_exchange_weak(head, head.wrapping_add(1), Ordering::AcqRel, Ordering::Relaxed) {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+        let slot = &self.slots[head as usize % N];
+        slot.record.store(record, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+        slot.ready.store(head.wrapping_add(1), Ordering::Release);
+        true
+    }
+
+    /// Breadcrumbs reserved but not merged yet, including any still being
+    /// written.
+    pub fn len(&self) -> usize {
+        self.head.load(Ordering::Acquire).wrapping_sub(self.tail.load(Ordering::Acquire)) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Breadcrumbs dropped because the buffer was full.
+    pub fn dropped(&self) -> u32 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Hands the complete breadcrumbs to `sink`, oldest first, and frees
+    /// their slots. Stops at a breadcrumb whose handler was interrupted
+    /// mid-write; it is taken by the next call. Returns the number taken.
+    pub fn take(&self, mut sink: impl FnMut(Breadcrumb)) -> usize {
+        let _guard = self.consumer.lock();
+        let mut tail = self.tail.load(Ordering::Relaxed);
+        let mut taken = 0;
+        loop {
+            let slot = &self.slots[tail as usize % N];
+            if slot.ready.load(Ordering::Acquire) != tail.wrapping_add(1) {
+                break;
+            }
+            let crumb = Breadcrumb {
+                record: slot.record.load(Ordering::Relaxed),
+                data: slot.data.load(Ordering::Relaxed),
+            };
+            tail = tail.wrapping_add(1);
+            self.tail.store(tail, Ordering::Release);
+            sink(crumb);
+            taken += 1;
+        }
+        taken
+    }
+
+    /// Emits the complete breadcrumbs through `hook` as user records with a
+    /// timestamp of the merge. Returns the number merged.
+    pub fn merge(&self, hook: &TraceHook) -> usize {
+        self.take(|crumb| {
+            let mut buf = [0u8; 5];
+            let mut payload = UserRecordBuilder::encode_into(&mut buf);
+            payload.push_u32(0, crumb.data);
+            if let Ok(payload) = payload.finish() {
+                let _ = hook(crumb.record, payload, true);
+            }
+        })
+    }
+}
+
+/// An [`NmiTrace`] of any size, for a kernel that merges it.
+pub trait NmiSource: Sync {
+    /// See [`NmiTrace::merge`].
+    fn merge(&self, hook: &TraceHook) -> usize;
+}
+
+impl<const N: usize> NmiSource for NmiTrace<N> {
+    fn merge(&self, hook: &TraceHook) -> usize {
+        NmiTrace::merge(self, hook)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+
+    fn take_all<const N: usize>(trace: &NmiTrace<N>) -> Vec<Breadcrumb> {
+        let mut out = Vec::new();
+        trace.take(|crumb| out.push(crumb));
+        out
+    }
+
+    #[test]
+    fn breadcrumbs_come_out_in_order_across_the_wrap() {
+        let trace = NmiTrace::<4>::new();
+        for i in 0..3 {
+            assert!(trace.breadcrumb(100, i));
+        }
+        assert_eq!(take_all(&trace).len(), 3);
+        for i in 3..7 {
+            assert!(trace.breadcrumb(101, i));
+        }
+        let data: Vec<u32> = take_all(&trace).iter().map(|c| c.data).collect();
+        assert_eq!(data, [3, 4, 5, 6]);
+        assert!(trace.is_empty());
+    }
+
+    #[test]
+    fn a_full_buffer_drops_new_breadcrumbs() {
+        let trace = NmiTrace::<2>::new();
+        assert!(trace.breadcrumb(100, 1));
+        assert!(trace.breadcrumb(100, 2));
+        assert!(!trace.breadcrumb(100, 3));
+        assert_eq!(trace.dropped(), 1);
+        assert_eq!(take_all(&trace), [Breadcrumb { record: 100, data: 1 }, Breadcrumb { record: 100, data: 2 }]);
+        assert!(trace.breadcrumb(100, 4));
+    }
+
+    #[test]
+    fn an_unfinished_breadcrumb_holds_back_later_ones() {
+        let trace = NmiTrace::<4>::new();
+        // A handler reserved slot 0 and was preempted before publishing it.
+        trace.head.store(1, Ordering::Relaxed);
+        assert!(trace.breadcrumb(100, 7));
+        assert_eq!(trace.take(|_| {}), 0);
+
+        let slot = &trace.slots[0];
+        slot.record.store(102, Ordering::Relaxed);
+        slot.data.store(6, Ordering::Relaxed);
+        slot.ready.store(1, Ordering::Release);
+        let data: Vec<u32> = take_all(&trace).iter().map(|c| c.data).collect();
+        assert_eq!(data, [6, 7]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn concurrent_writers_keep_their_own_order() {
+        let trace = Arc::new(NmiTrace::<1024>::new());
+        let writers: Vec<_> = (0..4u8)
+            .map(|w| {
+                let trace = Arc::clone(&trace);
+                std::thread::spawn(move || (0..200).for_each(|i| assert!(trace.breadcrumb(100 + w, i))))
+            })
+            .collect();
+        writers.into_iter().for_each(|w| w.join().unwrap());
+
+        let crumbs = take_all(&trace);
+        assert_eq!(crumbs.len(), 800);
+        for w in 0..4u8 {
+            let data: Vec<u32> = crumbs.iter().filter(|c| c.record == 100 + w).map(|c| c.data).collect();
+            assert_eq!(data, (0..200).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn merge_emits_timestamped_user_records() {
+        let trace = NmiTrace::<4>::new();
+        trace.breadcrumb(105, 0xDEAD_BEEF);
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&records);
+        let hook: TraceHook = Arc::new(move |record, payload: &[u8], stamped| {
+            sink.lock().push((record, payload.to_vec(), stamped));
+            Ok(())
+        });
+        assert_eq!(trace.merge(&hook), 1);
+        let mut expected = UserRecordBuilder::new();
+        expected.push_u32(0, 0xDEAD_BEEF);
+        assert_eq!(*records.lock(), [(105, expected.into_vec(), true)]);
+    }
+}
@@ -0,0 +1,401 @@
+//! Globally ordered emission for tracers shared by many threads.
+//!
+//! [`Tracer`](crate::Tracer) serialises everything — filtering, encoding and
+//! the backend write — behind one mutex. [`OrderedTracer`] takes the encoding
+//! out of the lock: each record draws a ticket from an atomic counter, the
+//! ticket becomes the wire sequence number, and frames are released to the
+//! backend strictly in ticket order however the threads that encoded them
+//! race. The decoder therefore sees sequence numbers in the order the frames
+//! arrive, and a gap always means a lost frame.
+//!
+//! With staging enabled ([`OrderedTracer::set_staging`]) records emitted
+//! inside a [`StageScope`] — the framework opens one around every
+//! run-to-completion step of an active object — are held back and released
+//! as one contiguous, consecutively numbered block when the outermost scope
+//! on that thread ends. An AO's step then reads as an unbroken sequence
+//! instead of being interleaved with concurrently running AOs. Timestamps
+//! are still taken when each record is emitted, so they may step backwards
+//! at a block boundary.
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::{
+    current_qs_id, encode_frame, GlbFilter, LocFilter, QsConfig, QsRecord, TraceBackend,
+    TraceError, TraceFlush, TraceHook,
+};
+
+/// A record accepted by the filters, waiting for its ticket.
+struct Pending {
+    record_type: u8,
+    timestamp: Option<u32>,
+    payload: Vec<u8>,
+}
+
+/// Frames encoded out of order, keyed by the ticket of their first frame.
+struct Release {
+    next: u32,
+    pending: BTreeMap<u32, Vec<Vec<u8>>>,
+}
+
+trait Sink: Send + Sync {
+    fn release(&self, records: Vec<Pending>) -> Result<(), TraceError>;
+}
+
+struct Shared<B: TraceBackend> {
+    backend: B,
+    cfg: QsConfig,
+    filters: RwLock<(GlbFilter, LocFilter)>,
+    /// Per-thread stage bound in records; 0 disables staging.
+    staging: AtomicUsize,
+    next_ticket: AtomicU32,
+    release: Mutex<Release>,
+}
+
+impl<B: TraceBackend> Sink for Shared<B> {
+    /// Numbers `records` consecutively and hands them to the backend once
+    /// every earlier ticket has been written. Whichever thread completes the
+    /// run in front of the queue writes it, and sees the first backend error.
+    fn release(&self, records: Vec<Pending>) -> Result<(), TraceError> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let first = self.next_ticket.fetch_add(records.len() as u32, Ordering::Relaxed);
+        let frames = records
+            .into_iter()
+            .zip(first..)
+            .map(|(rec, ticket)| {
+                let record = QsRecord {
+                    seq: ticket.wrapping_add(1) as u8,
+                    record_type: rec.record_type,
+                    timestamp: rec.timestamp,
+                    payload: rec.payload,
+                };
+                encode_frame(&record, self.cfg.timestamp_size)
+            })
+            .collect();
+
+        let mut release = self.release.lock().unwrap();
+        release.pending.insert(first, frames);
+        let mut result = Ok(());
+        loop {
+            let next = release.next;
+            let Some(frames) = release.pending.remove(&next) else { break };
+            release.next = release.next.wrapping_add(frames.len() as u32);
+            for frame in frames {
+                if let Err(err) = self.backend.write_frame(&frame) {
+                    result = result.and(Err(err));
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Thread-safe tracer that emits frames in sequence-number order.
+///
+/// Cheap to clone; clones share one sequence and one backend.
+pub struct OrderedTracer<B: TraceBackend> {
+    inner: Arc<Shared<B>>,
+}
+
+impl<B: TraceBackend> Clone for OrderedTracer<B> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<B: TraceBackend + 'static> OrderedTracer<B> {
+    /// Creates a tracer writing to `backend`, with staging disabled.
+    pub fn new(cfg: QsConfig, backend: B) -> Self {
+        Self {
+            inner: Arc::new(Shared {
+                backend,
+                cfg,
+                filters: RwLock::new((GlbFilter::allow_all(), LocFilter::allow_all())),
+                staging: AtomicUsize::new(0),
+                next_ticket: AtomicU32::new(0),
+                release: Mutex::new(Release { next: 0, pending: BTreeMap::new() }),
+            }),
+        }
+    }
+
+    /// Stage up to `records` records per thread inside a [`StageScope`]
+    /// before releasing them early; 0 turns staging off.
+    pub fn set_staging(&self, records: usize) {
+        self.inner.staging.store(records, Ordering::Relaxed);
+    }
+
+    /// Replace the global filter.
+    pub fn set_filter(&self, filter: GlbFilter) {
+        self.inner.filters.write().unwrap().0 = filter;
+    }
+
+    /// Replace the local filter.
+    pub fn set_loc_filter(&self, filter: LocFilter) {
+        self.inner.filters.write().unwrap().1 = filter;
+    }
+
+    /// Emits one record. Filtered records are dropped without consuming a
+    /// sequence number; staged records report `Ok` when queued, or the error
+    /// of the earlier records a full stage released to make room.
+    pub fn emit(
+        &self,
+        record_type: u8,
+        payload: &[u8],
+        with_timestamp: bool,
+    ) -> Result<(), TraceError> {
+        let shared = &self.inner;
+        {
+            let filters = shared.filters.read().unwrap();
+            let from_allowed = current_qs_id().is_none_or(|id| filters.1.is_allowed(id));
+            if !filters.0.is_allowed(record_type) || !from_allowed {
+                return Ok(());
+            }
+        }
+        if payload.len() > shared.cfg.max_record_len {
+            return Err(TraceError::PayloadTooLarge(payload.len()));
+        }
+        let timestamp = shared.cfg.timestamp(with_timestamp);
+        let record = Pending { record_type, timestamp, payload: payload.to_vec() };
+
+        let depth = shared.staging.load(Ordering::Relaxed);
+        if depth > 0 && DEPTH.with(Cell::get) > 0 {
+            return stage(Arc::clone(shared) as Arc<dyn Sink>, record, depth);
+        }
+        shared.release(vec![record])
+    }
+
+    /// Releases this thread's staged records and flushes the backend.
+    /// Returns the first error of either, or of a release made on this thread
+    /// when a [`StageScope`] ended since the last flush.
+    pub fn flush(&self) -> Result<(), TraceError> {
+        let unreported = UNREPORTED.with(|e| e.borrow_mut().take()).map_or(Ok(()), Err);
+        let released = release_stage();
+        unreported.and(released).and(self.inner.backend.flush())
+    }
+
+    /// Returns a [`TraceHook`] emitting through this tracer.
+    pub fn hook(&self) -> TraceHook {
+        let tracer = self.clone();
+        Arc::new(move |record_type, payload, with_timestamp| {
+            tracer.emit(record_type, payload, with_timestamp)
+        })
+    }
+
+    /// Returns a [`TraceFlush`] releasing this thread's staged records and
+    /// flushing the backend.
+    pub fn flusher(&self) -> TraceFlush {
+        let tracer = self.clone();
+        Arc::new(move || tracer.flush())
+    }
+}
+
+std::thread_local! {
+    static DEPTH: Cell<u32> = const { Cell::new(0) };
+    static STAGE: RefCell<Vec<(Arc<dyn Sink>, Pending)>> = const { RefCell::new(Vec::new()) };
+    /// The first error of a release made by a dropped scope, for `flush`.
+    static UNREPORTED: RefCell<Option<TraceError>> = const { RefCell::new(None) };
+}
+
+/// Queues `record`, first releasing the stage if it holds `depth` records
+/// already. Returns the first backend error of that release.
+fn stage(sink: Arc<dyn Sink>, record: Pending, depth: usize) -> Result<(), TraceError> {
+    let released = if STAGE.with(|s| s.borrow().len()) >= depth {
+        release_stage()
+    } else {
+        Ok(())
+    };
+    STAGE.with(|s| s.borrow_mut().push((sink, record)));
+    released
+}
+
+/// Releases the current thread's stage, one block per tracer. Every block is
+/// released; the first backend error is returned.
+fn release_stage() -> Result<(), TraceError> {
+    let staged = STAGE.with(|s| std::mem::take(&mut *s.borrow_mut()));
+    let mut run: Vec<Pending> = Vec::new();
+    let mut run_sink: Option<Arc<dyn Sink>> = None;
+    let mut result = Ok(());
+    for (sink, record) in staged {
+        if let Some(current) = &run_sink {
+            if !Arc::ptr_eq(current, &sink) {
+                result = result.and(current.release(std::mem::take(&mut run)));
+                run_sink = Some(sink);
+            }
+        } else {
+            run_sink = Some(sink);
+        }
+        run.push(record);
+    }
+    if let Some(sink) = run_sink {
+        result = result.and(sink.release(run));
+    }
+    result
+}
+
+/// Marks one run-to-completion step on this thread. Records that staging
+/// [`OrderedTracer`]s receive meanwhile are released together when the
+/// outermost scope drops.
+#[must_use = "records are only staged while the scope is alive"]
+pub struct StageScope {
+    _not_send: core::marker::PhantomData<*const ()>,
+}
+
+impl StageScope {
+    /// Opens a (possibly nested) scope.
+    pub fn enter() -> Self {
+        DEPTH.with(|d| d.set(d.get() + 1));
+        Self { _not_send: core::marker::PhantomData }
+    }
+}
+
+impl Drop for StageScope {
+    fn drop(&mut self) {
+        let depth = DEPTH.with(|d| {
+            d.set(d.get() - 1);
+            d.get()
+        });
+        if depth == 0 {
+            // A drop cannot report the error; the next `flush` does.
+            if let Err(err) = release_stage() {
+                UNREPORTED.with(|e| {
+                    e.borrow_mut().get_or_insert(err);
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hdlc;
+
+    #[derive(Clone, Default)]
+    struct Capture {
+        frames: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl TraceBackend for Capture {
+        fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
+            self.frames.lock().unwrap().push(frame.to_vec());
+            Ok(())
+        }
+    }
+
+    /// Unescaped `(seq, record_type)` of an encoded frame.
+    fn header(frame: &[u8]) -> (u8, u8) {
+        let mut bytes = Vec::new();
+        let mut iter = frame.iter();
+        while bytes.len() < 2 {
+            let b = *iter.next().unwrap();
+            bytes.push(if b == hdlc::ESC { iter.next().unwrap() ^ hdlc::ESC_XOR } else { b });
+        }
+        (bytes[0], bytes[1])
+    }
+
+    fn untimed() -> QsConfig {
+        QsConfig { include_timestamp: false, ..QsConfig::default() }
+    }
+
+    #[test]
+    fn concurrent_emitters_arrive_in_sequence_order() {
+        let backend = Capture::default();
+        let tracer = OrderedTracer::new(untimed(), backend.clone());
+
+        let threads: Vec<_> = (0..4u8)
+            .map(|t| {
+                let hook = tracer.hook();
+                std::thread::spawn(move || {
+                    for _ in 0..300 {
+                        hook(100 + t, &[t], false).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        let frames = backend.frames.lock().unwrap();
+        assert_eq!(frames.len(), 1200);
+        for (i, frame) in frames.iter().enumerate() {
+            assert_eq!(header(frame).0, (i as u32 + 1) as u8);
+        }
+    }
+
+    #[test]
+    fn staged_step_is_released_as_one_block() {
+        let backend = Capture::default();
+        let tracer = OrderedTracer::new(untimed(), backend.clone());
+        tracer.set_staging(16);
+
+        let scope = StageScope::enter();
+        tracer.emit(100, &[], false).unwrap();
+        {
+            let _nested = StageScope::enter();
+            tracer.emit(101, &[], false).unwrap();
+        }
+        let other = tracer.clone();
+        std::thread::spawn(move || other.emit(120, &[], false).unwrap()).join().unwrap();
+        assert_eq!(backend.frames.lock().unwrap().len(), 1);
+        drop(scope);
+
+        let headers: Vec<_> = backend.frames.lock().unwrap().iter().map(|f| header(f)).collect();
+        assert_eq!(headers, vec![(1, 120), (2, 100), (3, 101)]);
+    }
+
+    #[test]
+    fn full_stage_is_released_early() {
+        let backend = Capture::default();
+        let tracer = OrderedTracer::new(untimed(), backend.clone());
+        tracer.set_staging(2);
+
+        let _scope = StageScope::enter();
+        for rec in 100..103 {
+            tracer.emit(rec, &[], false).unwrap();
+        }
+        assert_eq!(backend.frames.lock().unwrap().len(), 2);
+        tracer.flush().unwrap();
+        assert_eq!(backend.frames.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn staged_backend_errors_surface_from_flush() {
+        struct Failing;
+        impl TraceBackend for Failing {
+            fn write_frame(&self, _frame: &[u8]) -> Result<(), TraceError> {
+                Err(TraceError::Backend(std::io::Error::other("link down")))
+            }
+        }
+        let tracer = OrderedTracer::new(untimed(), Failing);
+        tracer.set_staging(16);
+
+        let scope = StageScope::enter();
+        tracer.emit(100, &[], false).unwrap();
+        assert!(matches!(tracer.flush(), Err(TraceError::Backend(_))));
+        tracer.emit(101, &[], false).unwrap();
+        drop(scope);
+        // The scope's release failed; the next flush reports it, once.
+        assert!(matches!(tracer.flush(), Err(TraceError::Backend(_))));
+        tracer.flush().unwrap();
+    }
+
+    #[test]
+    fn filtered_records_take_no_sequence_number() {
+        let backend = Capture::default();
+        let tracer = OrderedTracer::new(untimed(), backend.clone());
+        let mut filter = GlbFilter::allow_all();
+        filter.block(101);
+        tracer.set_filter(filter);
+
+        for rec in 100..103 {
+            tracer.emit(rec, &[], false).unwrap();
+        }
+        let headers: Vec<_> = backend.frames.lock().unwrap().iter().map(|f| header(f)).collect();
+        assert_eq!(headers, vec![(1, 100), (2, 102)]);
+    }
+}
@@ -0,0 +1,148 @@
+//! Datagram packing for packet transports.
+//!
+//! QS frames are small (typically 10–30 bytes) and self-delimiting, and the
+//! host decoder treats its input as a byte stream. A packet transport can
+//! therefore coalesce several frames into one datagram, and cut a frame that
+//! does not fit across consecutive datagrams, without any host-side change.
+//! [`DatagramPacker`] implements that policy independently of the socket so
+//! it can be reused by any packet backend (see `UdpBackend::connect_batched`).
+//!
+//! Receivers that look at datagrams one at a time — a capture filter, a
+//! relay that drops late packets — want every datagram to hold whole frames
+//! instead. [`DatagramPacker::whole_frames`] never cuts a frame: one larger
+//! than the MTU travels alone in an oversize datagram and is left to IP
+//! fragmentation (see `UdpBatching::split_oversize`).
+
+use alloc::vec::Vec;
+
+/// Payload bytes that fit in one UDP datagram on a 1500-byte Ethernet MTU
+/// (1500 − 20 IPv4 header − 8 UDP header).
+pub const DEFAULT_UDP_MTU: usize = 1472;
+
+/// Coalesces HDLC frames into datagrams of at most `mtu` bytes.
+#[derive(Debug)]
+pub struct DatagramPacker {
+    mtu: usize,
+    buf: Vec<u8>,
+    split: bool,
+}
+
+impl DatagramPacker {
+    /// Creates a packer emitting datagrams of at most `mtu` bytes (minimum 1).
+    pub fn new(mtu: usize) -> Self {
+        let mtu = mtu.max(1);
+        Self { mtu, buf: Vec::with_capacity(mtu), split: true }
+    }
+
+    /// Creates a packer that keeps every frame inside one datagram: frames
+    /// are coalesced up to `mtu` bytes, and a frame longer than `mtu` is sent
+    /// on its own, whole.
+    pub fn whole_frames(mtu: usize) -> Self {
+        Self { split: false, ..Self::new(mtu) }
+    }
+
+    /// Maximum datagram size.
+    pub fn mtu(&self) -> usize {
+        self.mtu
+    }
+
+    /// Bytes buffered and not yet handed to `send`.
+    pub fn pending(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Appends `frame`, calling `send` for every datagram that fills up.
+    ///
+    /// A frame that would overflow the current datagram first flushes it, so
+    /// frames are only split when they are larger than the MTU themselves —
+    /// and never by a [`whole_frames`](Self::whole_frames) packer.
+    pub fn push<E>(
+        &mut self,
+        frame: &[u8],
+        mut send: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        if self.buf.len() + frame.len() > self.mtu {
+            self.flush(&mut send)?;
+        }
+        if !self.split && frame.len() > self.mtu {
+            return send(frame);
+        }
+        let mut rest = frame;
+        while rest.len() > self.mtu {
+            let (chunk, tail) = rest.split_at(self.mtu);
+            send(chunk)?;
+            rest = tail;
+        }
+        self.buf.extend_from_slice(rest);
+        if self.buf.len() == self.mtu {
+            self.flush(send)?;
+        }
+        Ok(())
+    }
+
+    /// Sends whatever is buffered as one (possibly short) datagram.
+    pub fn flush<E>(&mut self, mut send: impl FnMut(&[u8]) -> Result<(), E>) -> Result<(), E> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let result = send(&self.buf);
+        self.buf.clear();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(packer: &mut DatagramPacker, frames: &[&[u8]]) -> Vec<Vec<u8>> {
+        let mut out = Vec::new();
+        for f in frames {
+            packer
+                .push(f, |d| {
+                    out.push(d.to_vec());
+                    Ok::<_, ()>(())
+                })
+                .unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn small_frames_are_coalesced() {
+        let mut packer = DatagramPacker::new(8);
+        let sent = collect(&mut packer, &[&[1, 2, 3], &[4, 5, 6], &[7, 8, 9]]);
+        // Third frame would overflow: the first two leave as one datagram.
+        assert_eq!(sent, vec![vec![1, 2, 3, 4, 5, 6]]);
+        assert_eq!(packer.pending(), 3);
+
+        let mut tail = Vec::new();
+        packer.flush(|d| { tail.push(d.to_vec()); Ok::<_, ()>(()) }).unwrap();
+        assert_eq!(tail, vec![vec![7, 8, 9]]);
+        assert_eq!(packer.pending(), 0);
+    }
+
+    #[test]
+    fn oversize_frame_is_split_in_order() {
+        let mut packer = DatagramPacker::new(4);
+        let sent = collect(&mut packer, &[&[1], &[2, 3, 4, 5, 6, 7, 8, 9, 10]]);
+        assert_eq!(sent, vec![vec![1], vec![2, 3, 4, 5], vec![6, 7, 8, 9]]);
+        assert_eq!(packer.pending(), 1);
+    }
+
+    #[test]
+    fn whole_frames_are_never_split() {
+        let mut packer = DatagramPacker::whole_frames(4);
+        let sent = collect(&mut packer, &[&[1], &[2, 3, 4, 5, 6], &[7, 8], &[9, 10, 11]]);
+        assert_eq!(sent, vec![vec![1], vec![2, 3, 4, 5, 6], vec![7, 8]]);
+        assert_eq!(packer.pending(), 3);
+    }
+
+    #[test]
+    fn exact_fit_is_sent_immediately() {
+        let mut packer = DatagramPacker::new(4);
+        let sent = collect(&mut packer, &[&[1, 2], &[3, 4]]);
+        assert_eq!(sent, vec![vec![1, 2, 3, 4]]);
+        assert_eq!(packer.pending(), 0);
+    }
+}
@@ -0,0 +1,362 @@
+//! Target side of `QS_RX_PEEK`, `QS_RX_POKE` and `QS_RX_FILL`.
+//!
+//! The host names memory by address, offset and element count. Serving such
+//! requests against raw addresses would let a debugging link read or corrupt
+//! anything, so the target only answers for regions it registered: an
+//! object the application chose to expose, read-only or writable. A request
+//! that is not wholly inside one registered region is refused.
+//!
+//! ```rust,ignore
+//! #[repr(C)]
+//! struct Tuning { gain: u16, limit: u16 }
+//! struct Tunable(UnsafeCell<Tuning>);
+//! // SAFETY: the application only reads TUNING with `read_volatile`.
+//! unsafe impl Sync for Tunable {}
+//! static TUNING: Tunable = Tunable(UnsafeCell::new(Tuning { gain: 4, limit: 100 }));
+//!
+//! // SAFETY: TUNING is a static, so the bytes outlive the region, and the
+//! // `UnsafeCell` allows writing through a pointer from a shared reference.
+//! // Any bit pattern is a valid `Tuning`, so a poke that races a reader can
+//! // only leave a mix of old and new field values.
+//! unsafe { qs::peek::expose(TUNING.0.get() as *const u8, size_of::<Tuning>(), Access::ReadWrite)? };
+//!
+//! RxCmd::Peek { addr, offset, size, num } =>
+//!     qs::peek::peek(&hook, cfg.max_record_len, addr, offset, size, num),
+//! ```
+//!
+//! A peek answers with `QS_PEEK_DATA` records (`offset: u16 | len: u8 |
+//! data`), split so each fits in `max_record_len` without splitting an
+//! element.
+
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(not(feature = "std"))]
+use spin::Mutex;
+
+use crate::records::infra::PEEK_DATA;
+use crate::{TraceError, TraceHook};
+
+/// Maximum number of regions registered at once.
+pub const MAX_REGIONS: usize = 8;
+
+/// Bytes of `QS_PEEK_DATA` ahead of the data.
+const PEEK_HEADER: usize = 3;
+
+/// What the host may do with a region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Peek only.
+    ReadOnly,
+    /// Peek, poke and fill.
+    ReadWrite,
+}
+
+/// Why a memory request was refused.
+#[derive(Debug)]
+pub enum MemError {
+    /// All [`MAX_REGIONS`] slots are taken.
+    RegistryFull,
+    /// The request is not inside a registered region.
+    OutOfRegion,
+    /// The region is [`Access::ReadOnly`].
+    ReadOnly,
+    /// Element size is zero, or the data does not match `size` and `num`.
+    BadLength,
+    /// The tracer rejected a `QS_PEEK_DATA` record.
+    Trace(TraceError),
+}
+
+impl core::fmt::Display for MemError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::RegistryFull => write!(f, "no free memory region slot"),
+            Self::OutOfRegion => write!(f, "memory outside the exposed regions"),
+            Self::ReadOnly => write!(f, "memory region is read-only"),
+            Self::BadLength => write!(f, "element size and count do not match the data"),
+            Self::Trace(err) => write!(f, "trace error: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MemError {}
+
+impl From<TraceError> for MemError {
+    fn from(err: TraceError) -> Self {
+        Self::Trace(err)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Region {
+    base: usize,
+    len: usize,
+    access: Access,
+}
+
+impl Region {
+    fn covers(&self, start: usize, len: usize) -> bool {
+        start >= self.base && start - self.base <= self.len && len <= self.len - (start - self.base)
+    }
+}
+
+struct Registry {
+    slots: [Option<Region>; MAX_REGIONS],
+}
+
+impl Registry {
+    const fn new() -> Self {
+        Self { slots: [None; MAX_REGIONS] }
+    }
+
+    fn insert(&mut self, region: Region) -> Result<(), MemError> {
+        let slot = self
+            .slots
+            .iter_mut()
+            .find(|slot| slot.is_none_or(|r| r.base == region.base))
+            .ok_or(MemError::RegistryFull)?;
+        *slot = Some(region);
+        Ok(())
+    }
+
+    fn remove(&mut self, base: usize) {
+        for slot in &mut self.slots {
+            if slot.is_some_and(|r| r.base == base) {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Start address of `len` bytes at `addr + offset`, if one region holds
+    /// them all and grants `write` when asked.
+    fn resolve(&self, addr: u64, offset: u16, len: usize, write: bool) -> Result<usize, MemError> {
+        let start = usize::try_from(addr)
+            .ok()
+            .and_then(|addr| addr.checked_add(usize::from(offset)))
+            .ok_or(MemError::OutOfRegion)?;
+        let region = self
+            .slots
+            .iter()
+            .flatten()
+            .find(|r| r.covers(start, len))
+            .ok_or(MemError::OutOfRegion)?;
+        if write && region.access == Access::ReadOnly {
+            return Err(MemError::ReadOnly);
+        }
+        Ok(start)
+    }
+}
+
+static REGIONS: Mutex<Registry> = Mutex::new(Registry::new());
+
+fn with_regions<R>(f: impl FnOnce(&mut Registry) -> R) -> R {
+    #[cfg(feature = "std")]
+    { f(&mut REGIONS.lock().unwrap()) }
+    #[cfg(not(feature = "std"))]
+    { f(&mut REGIONS.lock()) }
+}
+
+/// Lets the host reach `len` bytes at `base`. Registering the same `base`
+/// again replaces its length and access.
+///
+/// # Safety
+/// The bytes must stay valid for reads, and for writes with
+/// [`Access::ReadWrite`], until the region is withdrawn. Peeks and pokes copy
+/// bytes without synchronising with the code that owns them; expose only
+/// memory where a torn read or write is harmless.
+pub unsafe fn expose(base: *const u8, len: usize, access: Access) -> Result<(), MemError> {
+    with_regions(|r| r.insert(Region { base: base as usize, len, access }))
+}
+
+/// Lets the host read `bytes`; safe, as they can never change.
+pub fn expose_bytes(bytes: &'static [u8]) -> Result<(), MemError> {
+    // SAFETY: a `'static` shared slice is readable forever and immutable.
+    unsafe { expose(bytes.as_ptr(), bytes.len(), Access::ReadOnly) }
+}
+
+/// Stops serving the region registered at `base`.
+pub fn withdraw(base: *const u8) {
+    with_regions(|r| r.remove(base as usize));
+}
+
+/// Withdraws every region.
+pub fn clear_regions() {
+    with_regions(|r| *r = Registry::new());
+}
+
+/// Serves `QS_RX_PEEK`: emits `num` elements of `size` bytes from
+/// `addr + offset` as `QS_PEEK_DATA` records of at most `max_record_len`
+/// bytes.
+pub fn peek(
+    hook: &TraceHook,
+    max_record_len: usize,
+    addr: u64,
+    offset: u16,
+    size: u8,
+    num: u8,
+) -> Result<(), MemError> {
+    let size = usize::from(size);
+    let room = max_record_len.saturating_sub(PEEK_HEADER).min(usize::from(u8::MAX));
+    if size == 0 || size > room {
+        return Err(MemError::BadLength);
+    }
+    let len = size * usize::from(num);
+    let start = with_regions(|r| r.resolve(addr, offset, len, false))?;
+    let chunk = room - room % size;
+
+    let mut record = [0u8; PEEK_HEADER + u8::MAX as usize];
+    let mut done = 0;
+    while done < len {
+        let n = chunk.min(len - done);
+        record[..2].copy_from_slice(&(offset as usize + done).to_le_bytes()[..2]);
+        record[2] = n as u8;
+        // SAFETY: `resolve` checked `start..start + len` lies in a region
+        // exposed as readable.
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                (start + done) as *const u8,
+                record[PEEK_HEADER..].as_mut_ptr(),
+                n,
+            );
+        }
+        hook(PEEK_DATA, &record[..PEEK_HEADER + n], false)?;
+        done += n;
+    }
+    Ok(())
+}
+
+/// Serves `QS_RX_POKE`: writes `data`, `num` elements of `size` bytes, at
+/// `addr + offset`.
+pub fn poke(addr: u64, offset: u16, size: u8, num: u8, data: &[u8]) -> Result<(), MemError> {
+    if size == 0 || data.len() != usize::from(size) * usize::from(num) {
+        return Err(MemError::BadLength);
+    }
+    let start = with_regions(|r| r.resolve(addr, offset, data.len(), true))?;
+    // SAFETY: `resolve` checked the range lies in a writable region.
+    unsafe { core::ptr::copy_nonoverlapping(data.as_ptr(), start as *mut u8, data.len()) };
+    Ok(())
+}
+
+/// Serves `QS_RX_FILL`: writes the `size`-byte `pattern` `num` times from
+/// `addr + offset`.
+pub fn fill(addr: u64, offset: u16, size: u8, num: u8, pattern: &[u8]) -> Result<(), MemError> {
+    if size == 0 || pattern.len() != usize::from(size) {
+        return Err(MemError::BadLength);
+    }
+    let len = pattern.len() * usize::from(num);
+    let start = with_regions(|r| r.resolve(addr, offset, len, true))?;
+    for i in 0..usize::from(num) {
+        // SAFETY: as in `poke`; element `i` ends within `start + len`.
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                pattern.as_ptr(),
+                (start + i * pattern.len()) as *mut u8,
+                pattern.len(),
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::cell::UnsafeCell;
+
+    /// The registry is global; tests that use it run one at a time.
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    struct Target(UnsafeCell<[u8; 16]>);
+    // SAFETY: only touched by the serialised tests below.
+    unsafe impl Sync for Target {}
+
+    static TARGET: Target = Target(UnsafeCell::new([0; 16]));
+
+    fn target() -> (u64, &'static mut [u8; 16]) {
+        // SAFETY: the tests hold `SERIAL`.
+        let bytes = unsafe { &mut *TARGET.0.get() };
+        *bytes = core::array::from_fn(|i| i as u8);
+        (bytes.as_ptr() as u64, bytes)
+    }
+
+    fn capture() -> (TraceHook, Arc<std::sync::Mutex<Vec<Vec<u8>>>>) {
+        let records = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&records);
+        let hook: TraceHook = Arc::new(move |record, payload: &[u8], ts| {
+            assert_eq!((record, ts), (PEEK_DATA, false));
+            sink.lock().unwrap().push(payload.to_vec());
+            Ok(())
+        });
+        (hook, records)
+    }
+
+    #[test]
+    fn peek_splits_into_whole_elements() {
+        let _serial = SERIAL.lock();
+        clear_regions();
+        let (addr, bytes) = target();
+        unsafe { expose(bytes.as_ptr(), 16, Access::ReadOnly).unwrap() };
+
+        let (hook, records) = capture();
+        peek(&hook, 8, addr, 2, 2, 6).unwrap();
+        let records = records.lock().unwrap();
+        assert_eq!(
+            *records,
+            [
+                alloc::vec![2, 0, 4, 2, 3, 4, 5],
+                alloc::vec![6, 0, 4, 6, 7, 8, 9],
+                alloc::vec![10, 0, 4, 10, 11, 12, 13],
+            ]
+        );
+    }
+
+    #[test]
+    fn requests_outside_a_region_are_refused() {
+        let _serial = SERIAL.lock();
+        clear_regions();
+        let (addr, bytes) = target();
+        unsafe { expose(bytes.as_ptr(), 8, Access::ReadOnly).unwrap() };
+
+        let (hook, records) = capture();
+        assert!(matches!(peek(&hook, 64, addr, 4, 1, 5), Err(MemError::OutOfRegion)));
+        assert!(matches!(peek(&hook, 64, addr - 1, 0, 1, 1), Err(MemError::OutOfRegion)));
+        assert!(matches!(poke(addr, 0, 1, 1, &[9]), Err(MemError::ReadOnly)));
+        assert!(records.lock().unwrap().is_empty());
+
+        withdraw(bytes.as_ptr());
+        assert!(matches!(peek(&hook, 64, addr, 0, 1, 1), Err(MemError::OutOfRegion)));
+    }
+
+    #[test]
+    fn poke_and_fill_write_writable_regions() {
+        let _serial = SERIAL.lock();
+        clear_regions();
+        let (addr, bytes) = target();
+        unsafe { expose(bytes.as_ptr(), 16, Access::ReadWrite).unwrap() };
+
+        poke(addr, 1, 2, 1, &[0xAA, 0xBB]).unwrap();
+        fill(addr, 8, 2, 3, &[0x11, 0x22]).unwrap();
+        assert!(matches!(poke(addr, 0, 2, 2, &[1, 2, 3]), Err(MemError::BadLength)));
+        assert!(matches!(fill(addr, 15, 1, 2, &[0]), Err(MemError::OutOfRegion)));
+
+        assert_eq!(bytes[..4], [0, 0xAA, 0xBB, 3]);
+        assert_eq!(bytes[8..], [0x11, 0x22, 0x11, 0x22, 0x11, 0x22, 14, 15]);
+    }
+
+    #[test]
+    fn registry_has_a_fixed_number_of_slots() {
+        let _serial = SERIAL.lock();
+        clear_regions();
+        static BYTES: [u8; MAX_REGIONS + 1] = [0; MAX_REGIONS + 1];
+        for i in 0..MAX_REGIONS {
+            expose_bytes(&BYTES[i..=i]).unwrap();
+        }
+        expose_bytes(&BYTES[0..2]).unwrap();
+        assert!(matches!(expose_bytes(&BYTES[MAX_REGIONS..]), Err(MemError::RegistryFull)));
+        clear_regions();
+    }
+}
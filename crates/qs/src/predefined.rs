@@ -12,110 +12,22 @@ extern crate alloc;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
-/// Record identifier for `QS_ENUM_DICT`.
-pub const ENUM_DICT: u8 = 54;
-/// Record identifier for `QS_SIG_DICT`.
-pub const SIG_DICT: u8 = 60;
-/// Record identifier for `QS_OBJ_DICT`.
-pub const OBJ_DICT: u8 = 61;
-/// Record identifier for `QS_FUN_DICT`.
-pub const FUN_DICT: u8 = 62;
-/// Record identifier for `QS_USR_DICT`.
-pub const USR_DICT: u8 = 63;
-/// Record identifier for `QS_TARGET_INFO`.
-pub const TARGET_INFO: u8 = 64;
-
-/// Helper describing the payload of the `QS_TARGET_INFO` record.
-#[derive(Debug, Clone)]
-pub struct TargetInfo {
-    /// `0xFF` for a reset (power-up) info record, `0x00` otherwise.
-    pub is_reset: u8,
-    /// QP framework version (e.g. `740`).
-    pub version: u16,
-    /// Byte width of a signal on the target.
-    pub signal_size: u8,
-    /// Byte width of an event size field.
-    pub event_size: u8,
-    /// Byte width of an event-queue counter.
-    pub equeue_ctr_size: u8,
-    /// Byte width of a time-event counter.
-    pub time_evt_ctr_size: u8,
-    /// Byte width of a memory-pool block-size field.
-    pub mpool_size_size: u8,
-    /// Byte width of a memory-pool counter.
-    pub mpool_ctr_size: u8,
-    /// Byte width of an object pointer on the target.
-    pub obj_ptr_size: u8,
-    /// Byte width of a function pointer on the target.
-    pub fun_ptr_size: u8,
-    /// Byte width of a QS timestamp.
-    pub time_size: u8,
-    /// Maximum number of active objects.
-    pub max_active: u8,
-    /// Maximum number of event pools.
-    pub max_event_pools: u8,
-    /// Maximum number of tick-rate domains.
-    pub max_tick_rate: u8,
-    /// Build time as `(hour, minute, second)`.
-    pub build_time: (u8, u8, u8),
-    /// Build date as `(day, month, year % 100)`.
-    pub build_date: (u8, u8, u8),
-}
-
-impl Default for TargetInfo {
-    fn default() -> Self {
-        Self {
-            is_reset: 0xFF,
-            version: 740,
-            signal_size: 2,
-            event_size: 2,
-            equeue_ctr_size: 2,
-            time_evt_ctr_size: 2,
-            mpool_size_size: 2,
-            mpool_ctr_size: 2,
-            obj_ptr_size: 8,
-            fun_ptr_size: 8,
-            time_size: 4,
-            max_active: 16,
-            max_event_pools: 3,
-            max_tick_rate: 4,
-            build_time: (11, 13, 21),
-            build_date: (18, 10, 25),
-        }
-    }
-}
+pub use qs_protocol::records::dict::{
+    DICT_HASH, ENUM_DICT, FUN_DICT, OBJ_DICT, SIG_DICT, SYM_MODE, TARGET_INFO, USR_DICT,
+};
+pub use qs_protocol::TargetInfo;
 
 /// Produces the payload bytes for a `QS_TARGET_INFO` record.
 pub fn target_info_payload(info: &TargetInfo) -> Vec<u8> {
-    let mut bytes = Vec::with_capacity(16);
-    bytes.push(info.is_reset);
-    bytes.extend_from_slice(&info.version.to_le_bytes());
-    bytes.push(info.signal_size | (info.event_size << 4));
-    bytes.push(info.equeue_ctr_size | (info.time_evt_ctr_size << 4));
-    bytes.push(info.mpool_size_size | (info.mpool_ctr_size << 4));
-    bytes.push(info.obj_ptr_size | (info.fun_ptr_size << 4));
-    bytes.push(info.time_size);
-    bytes.push(info.max_active);
-    bytes.push(info.max_event_pools | (info.max_tick_rate << 4));
-
-    let (hour, minute, second) = info.build_time;
-    bytes.push(second);
-    bytes.push(minute);
-    bytes.push(hour);
-
-    let (day, month, year) = info.build_date;
-    bytes.push(day);
-    bytes.push(month);
-    bytes.push(year);
-
-    bytes
+    info.encode().to_vec()
 }
 
-/// Builds the payload for `QS_OBJ_DICT` records.
+/// Builds the payload for `QS_OBJ_DICT` records. In
+/// [stable-id mode](crate::symbols) the entry names the address's id.
 pub fn obj_dict_payload(address: u64, name: &str) -> Vec<u8> {
     let ptr_size = core::mem::size_of::<usize>();
     let mut bytes = Vec::with_capacity(ptr_size + name.len() + 1);
-    bytes.extend_from_slice(&address.to_le_bytes()[..ptr_size]);
+    bytes.extend_from_slice(&crate::symbols::symbol(address).to_le_bytes()[..ptr_size]);
     push_c_string(&mut bytes, name);
     bytes
 }
@@ -138,11 +50,87 @@ pub fn sig_dict_payload(signal: u16, object: u64, name: &str) -> Vec<u8> {
     let ptr_size = core::mem::size_of::<usize>();
     let mut bytes = Vec::with_capacity(2 + ptr_size + name.len() + 1);
     bytes.extend_from_slice(&signal.to_le_bytes());
-    bytes.extend_from_slice(&object.to_le_bytes()[..ptr_size]);
+    bytes.extend_from_slice(&crate::symbols::symbol(object).to_le_bytes()[..ptr_size]);
+    push_c_string(&mut bytes, name);
+    bytes
+}
+
+/// Builds a `QS_SIG_DICT` payload whose name is `scope::name`, for signals
+/// declared in a namespace.
+pub fn scoped_sig_dict_payload(signal: u16, object: u64, scope: &str, name: &str) -> Vec<u8> {
+    let mut bytes = sig_dict_payload(signal, object, scope);
+    bytes.pop();
+    bytes.extend_from_slice(b"::");
     push_c_string(&mut bytes, name);
     bytes
 }
 
+/// Digest of a set of dictionary records.
+///
+/// Each record contributes the 32-bit FNV-1a hash of its record type and
+/// payload, and the contributions are summed, so the digest does not depend
+/// on the order the dictionaries were sent in. A viewer recomputes it from
+/// the dictionaries it holds, e.g. ones cached from an earlier session, and
+/// compares the two to tell whether its names still match the firmware.
+/// [`Tracer`](crate::Tracer) counts each distinct record once, so sending the
+/// dictionaries again, e.g. for a viewer that attached late, leaves the digest
+/// as it was.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DictHash {
+    hash: u32,
+    entries: u16,
+}
+
+impl DictHash {
+    /// The digest of no dictionaries.
+    pub const fn new() -> Self {
+        Self { hash: 0, entries: 0 }
+    }
+
+    /// Returns `true` for the record types a digest covers.
+    pub fn covers(record_type: u8) -> bool {
+        matches!(record_type, ENUM_DICT | SIG_DICT | OBJ_DICT | FUN_DICT | USR_DICT)
+    }
+
+    /// The contribution of one dictionary record.
+    pub fn entry(record_type: u8, payload: &[u8]) -> u32 {
+        let mut hash = 0x811C_9DC5u32;
+        for &byte in core::iter::once(&record_type).chain(payload) {
+            hash = (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193);
+        }
+        hash
+    }
+
+    /// Adds one dictionary record.
+    pub fn add(&mut self, record_type: u8, payload: &[u8]) {
+        self.add_entry(Self::entry(record_type, payload));
+    }
+
+    /// Adds the [`entry`](Self::entry) of one dictionary record.
+    pub fn add_entry(&mut self, entry: u32) {
+        self.hash = self.hash.wrapping_add(entry);
+        self.entries = self.entries.wrapping_add(1);
+    }
+
+    /// The digest.
+    pub fn hash(&self) -> u32 {
+        self.hash
+    }
+
+    /// Number of records added.
+    pub fn entries(&self) -> u16 {
+        self.entries
+    }
+
+    /// Payload of the `QS_DICT_HASH` record: `hash: u32, entries: u16`.
+    pub fn payload(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(6);
+        bytes.extend_from_slice(&self.hash.to_le_bytes());
+        bytes.extend_from_slice(&self.entries.to_le_bytes());
+        bytes
+    }
+}
+
 fn push_c_string(target: &mut Vec<u8>, value: &str) {
     target.extend_from_slice(value.as_bytes());
     target.push(0);
@@ -0,0 +1,403 @@
+//! Target side of `QS_RX_CURR_OBJ` and `QS_RX_QUERY_CURR`.
+//!
+//! The host picks a "current object" of each kind (state machine, active
+//! object, memory pool, event queue, time event, application object) by
+//! address, then asks the target to report on it. The target answers with a
+//! `QS_QUERY_DATA` record holding a snapshot: the current state of a state
+//! machine, the free and minimum-free counts of a queue or pool, or the
+//! counters of a time event.
+//!
+//! Addresses only mean something to the target once it registered the
+//! object behind them, so the application registers what the host may
+//! query, under the address its dictionaries announce:
+//!
+//! ```rust,ignore
+//! static POOL: QMPool = ...;
+//! qs::query::register(&POOL as *const _ as u64, &POOL)?;
+//!
+//! RxCmd::CurrObj { kind, obj_ptr } => qs::query::set_current_raw(kind, obj_ptr),
+//! RxCmd::QueryCurr { kind } => qs::query::query_raw(&hook, &RecordSizes::NATIVE, kind),
+//! ```
+//!
+//! `qf` implements [`Queryable`] for its queues, pools, time events and
+//! active objects.
+
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex};
+#[cfg(not(feature = "std"))]
+use spin::Mutex;
+
+use crate::records::infra::{self, obj_kind};
+use crate::records::RecordSizes;
+use crate::{TraceError, TraceHook};
+
+/// Maximum number of objects registered at once.
+pub const MAX_OBJECTS: usize = 16;
+
+/// The kinds of object the host can make current.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjKind {
+    /// State machine (`SM_OBJ`).
+    Sm,
+    /// Active object (`AO_OBJ`).
+    Ao,
+    /// Memory pool (`MP_OBJ`).
+    Mp,
+    /// Event queue (`EQ_OBJ`).
+    Eq,
+    /// Time event (`TE_OBJ`).
+    Te,
+    /// Application object (`AP_OBJ`).
+    Ap,
+}
+
+impl ObjKind {
+    /// Every kind, in wire order.
+    pub const ALL: [ObjKind; 6] = [Self::Sm, Self::Ao, Self::Mp, Self::Eq, Self::Te, Self::Ap];
+
+    /// The kind with wire code `code`.
+    pub fn from_u8(code: u8) -> Option<Self> {
+        Self::ALL.get(usize::from(code)).copied()
+    }
+
+    /// The wire code.
+    pub fn code(self) -> u8 {
+        match self {
+            Self::Sm => obj_kind::SM,
+            Self::Ao => obj_kind::AO,
+            Self::Mp => obj_kind::MP,
+            Self::Eq => obj_kind::EQ,
+            Self::Te => obj_kind::TE,
+            Self::Ap => obj_kind::AP,
+        }
+    }
+}
+
+/// What an object reports about itself when queried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Snapshot {
+    /// A state machine in `state` (handler address).
+    Sm {
+        /// Address of the current state handler.
+        state: u64,
+    },
+    /// An active object's event queue.
+    Ao {
+        /// Free entries now.
+        n_free: u32,
+        /// Fewest free entries ever.
+        n_min: u32,
+    },
+    /// A memory pool.
+    Mp {
+        /// Free blocks now.
+        n_free: u32,
+        /// Fewest free blocks ever.
+        n_min: u32,
+    },
+    /// A standalone event queue.
+    Eq {
+        /// Free entries now.
+        n_free: u32,
+        /// Fewest free entries ever.
+        n_min: u32,
+    },
+    /// A time event.
+    Te {
+        /// Address of the active object it posts to.
+        ao: u64,
+        /// Ticks left until it fires; `0` when disarmed.
+        ctr: u32,
+        /// Re-arm interval in ticks; `0` for one-shot.
+        interval: u32,
+        /// Signal it posts.
+        signal: u16,
+        /// Bit 0: armed.
+        flags: u8,
+    },
+    /// An application object, which reports only that it exists.
+    Ap,
+}
+
+impl Snapshot {
+    /// The kind of object this snapshot describes.
+    pub fn kind(&self) -> ObjKind {
+        match self {
+            Self::Sm { .. } => ObjKind::Sm,
+            Self::Ao { .. } => ObjKind::Ao,
+            Self::Mp { .. } => ObjKind::Mp,
+            Self::Eq { .. } => ObjKind::Eq,
+            Self::Te { .. } => ObjKind::Te,
+            Self::Ap => ObjKind::Ap,
+        }
+    }
+
+    /// The `QS_QUERY_DATA` record reporting this snapshot of `obj`.
+    pub fn record(&self, obj: u64) -> crate::Predefined {
+        match *self {
+            Self::Sm { state } => infra::query_sm(obj, state),
+            Self::Ao { n_free, n_min } => infra::query_ao(obj, n_free, n_min),
+            Self::Mp { n_free, n_min } => infra::query_mp(obj, n_free, n_min),
+            Self::Eq { n_free, n_min } => infra::query_eq(obj, n_free, n_min),
+            Self::Te { ao, ctr, interval, signal, flags } => {
+                infra::query_te(obj, ao, ctr, interval, signal, flags)
+            }
+            Self::Ap => infra::query_ap(obj),
+        }
+    }
+}
+
+/// An object the host may query.
+pub trait Queryable: Send + Sync {
+    /// A snapshot of the object as a `kind`, or `None` if it is not one. An
+    /// active object, for instance, answers both as [`ObjKind::Ao`] and, when
+    /// its behaviour is a state machine, as [`ObjKind::Sm`].
+    fn query(&self, kind: ObjKind) -> Option<Snapshot>;
+}
+
+/// Why a query went unanswered.
+#[derive(Debug)]
+pub enum QueryError {
+    /// All [`MAX_OBJECTS`] slots are taken.
+    RegistryFull,
+    /// Not an object kind of the protocol.
+    UnknownKind(u8),
+    /// The host has not made an object of this kind current.
+    NoCurrent(ObjKind),
+    /// The current object is not registered.
+    NotRegistered(u64),
+    /// The current object is not of the queried kind.
+    WrongKind(ObjKind),
+    /// The tracer rejected the `QS_QUERY_DATA` record.
+    Trace(TraceError),
+}
+
+impl core::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::RegistryFull => write!(f, "no free query object slot"),
+            Self::UnknownKind(code) => write!(f, "unknown object kind {}", code),
+            Self::NoCurrent(kind) => write!(f, "no current {:?} object", kind),
+            Self::NotRegistered(addr) => write!(f, "object {:#x} is not registered", addr),
+            Self::WrongKind(kind) => write!(f, "current object is not a {:?} object", kind),
+            Self::Trace(err) => write!(f, "trace error: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for QueryError {}
+
+impl From<TraceError> for QueryError {
+    fn from(err: TraceError) -> Self {
+        Self::Trace(err)
+    }
+}
+
+enum ObjRef {
+    Static(&'static dyn Queryable),
+    #[cfg(feature = "std")]
+    Shared(Arc<dyn Queryable>),
+}
+
+impl ObjRef {
+    fn get(&self) -> &dyn Queryable {
+        match self {
+            Self::Static(obj) => *obj,
+            #[cfg(feature = "std")]
+            Self::Shared(obj) => &**obj,
+        }
+    }
+}
+
+struct Registry {
+    objects: [Option<(u64, ObjRef)>; MAX_OBJECTS],
+    current: [Option<u64>; ObjKind::ALL.len()],
+}
+
+impl Registry {
+    const EMPTY: Option<(u64, ObjRef)> = None;
+
+    const fn new() -> Self {
+        Self { objects: [Self::EMPTY; MAX_OBJECTS], current: [None; ObjKind::ALL.len()] }
+    }
+
+    fn insert(&mut self, addr: u64, obj: ObjRef) -> Result<(), QueryError> {
+        let slot = self
+            .objects
+            .iter_mut()
+            .find(|slot| slot.as_ref().is_none_or(|(a, _)| *a == addr))
+            .ok_or(QueryError::RegistryFull)?;
+        *slot = Some((addr, obj));
+        Ok(())
+    }
+
+    fn snapshot(&self, kind: ObjKind) -> Result<(u64, Snapshot), QueryError> {
+        let addr = self.current[usize::from(kind.code())].ok_or(QueryError::NoCurrent(kind))?;
+        let (_, obj) = self
+            .objects
+            .iter()
+            .flatten()
+            .find(|(a, _)| *a == addr)
+            .ok_or(QueryError::NotRegistered(addr))?;
+        let snapshot = obj.get().query(kind).ok_or(QueryError::WrongKind(kind))?;
+        Ok((addr, snapshot))
+    }
+}
+
+static OBJECTS: Mutex<Registry> = Mutex::new(Registry::new());
+
+fn with_objects<R>(f: impl FnOnce(&mut Registry) -> R) -> R {
+    #[cfg(feature = "std")]
+    { f(&mut OBJECTS.lock().unwrap()) }
+    #[cfg(not(feature = "std"))]
+    { f(&mut OBJECTS.lock()) }
+}
+
+/// Lets the host query `obj` under the address `addr`. Registering `addr`
+/// again replaces the object.
+pub fn register(addr: u64, obj: &'static dyn Queryable) -> Result<(), QueryError> {
+    with_objects(|r| r.insert(addr, ObjRef::Static(obj)))
+}
+
+/// [`register`] for an object shared through an [`Arc`].
+#[cfg(feature = "std")]
+pub fn register_shared(addr: u64, obj: Arc<dyn Queryable>) -> Result<(), QueryError> {
+    with_objects(|r| r.insert(addr, ObjRef::Shared(obj)))
+}
+
+/// Stops answering for the object at `addr`.
+pub fn unregister(addr: u64) {
+    with_objects(|r| {
+        for slot in &mut r.objects {
+            if slot.as_ref().is_some_and(|(a, _)| *a == addr) {
+                *slot = None;
+            }
+        }
+    });
+}
+
+/// Unregisters every object and forgets the current ones.
+pub fn clear_objects() {
+    with_objects(|r| *r = Registry::new());
+}
+
+/// Makes the object at `addr` the current one of its `kind`. With
+/// [stable ids](crate::symbols) `addr` is the id the host knows the object
+/// by.
+pub fn set_current(kind: ObjKind, addr: u64) {
+    let addr = crate::symbols::address(addr).unwrap_or(addr);
+    with_objects(|r| r.current[usize::from(kind.code())] = Some(addr));
+}
+
+/// Serves `QS_RX_CURR_OBJ` with its raw kind code.
+pub fn set_current_raw(kind: u8, addr: u64) -> Result<(), QueryError> {
+    set_current(ObjKind::from_u8(kind).ok_or(QueryError::UnknownKind(kind))?, addr);
+    Ok(())
+}
+
+/// The current object of `kind`.
+pub fn current(kind: ObjKind) -> Option<u64> {
+    with_objects(|r| r.current[usize::from(kind.code())])
+}
+
+/// Serves `QS_RX_QUERY_CURR`: emits a `QS_QUERY_DATA` record with a snapshot
+/// of the current object of `kind`, its fields at `sizes`.
+pub fn query(hook: &TraceHook, sizes: &RecordSizes, kind: ObjKind) -> Result<(), QueryError> {
+    // Snapshot under the registry lock, emit after releasing it.
+    let (addr, snapshot) = with_objects(|r| r.snapshot(kind))?;
+    snapshot.record(addr).emit(hook, sizes)?;
+    Ok(())
+}
+
+/// [`query`] with the raw kind code of `QS_RX_QUERY_CURR`.
+pub fn query_raw(hook: &TraceHook, sizes: &RecordSizes, kind: u8) -> Result<(), QueryError> {
+    query(hook, sizes, ObjKind::from_u8(kind).ok_or(QueryError::UnknownKind(kind))?)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use crate::records::infra::QUERY_DATA;
+
+    /// The registry is global; tests that use it run one at a time.
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    struct Pool {
+        free: AtomicU32,
+    }
+
+    impl Queryable for Pool {
+        fn query(&self, kind: ObjKind) -> Option<Snapshot> {
+            (kind == ObjKind::Mp).then(|| Snapshot::Mp { n_free: self.free.load(Ordering::Relaxed), n_min: 1 })
+        }
+    }
+
+    static POOL: Pool = Pool { free: AtomicU32::new(5) };
+
+    fn capture() -> (TraceHook, Arc<std::sync::Mutex<Vec<Vec<u8>>>>) {
+        let records = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&records);
+        let hook: TraceHook = Arc::new(move |record, payload: &[u8], ts| {
+            assert_eq!((record, ts), (QUERY_DATA, true));
+            sink.lock().unwrap().push(payload.to_vec());
+            Ok(())
+        });
+        (hook, records)
+    }
+
+    const SIZES: RecordSizes =
+        RecordSizes { signal: 2, obj_ptr: 4, fun_ptr: 4, equeue_ctr: 1, time_evt_ctr: 2, mpool_ctr: 2 };
+
+    #[test]
+    fn query_reports_the_current_object() {
+        let _serial = SERIAL.lock();
+        clear_objects();
+        register(0x2000_0100, &POOL).unwrap();
+        set_current_raw(obj_kind::MP, 0x2000_0100).unwrap();
+
+        let (hook, records) = capture();
+        query_raw(&hook, &SIZES, obj_kind::MP).unwrap();
+        POOL.free.store(3, Ordering::Relaxed);
+        query(&hook, &SIZES, ObjKind::Mp).unwrap();
+        assert_eq!(
+            *records.lock().unwrap(),
+            [
+                alloc::vec![obj_kind::MP, 0x00, 0x01, 0x00, 0x20, 5, 0, 1, 0],
+                alloc::vec![obj_kind::MP, 0x00, 0x01, 0x00, 0x20, 3, 0, 1, 0],
+            ]
+        );
+        POOL.free.store(5, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn unanswerable_queries_are_refused() {
+        let _serial = SERIAL.lock();
+        clear_objects();
+        let (hook, records) = capture();
+
+        assert!(matches!(query(&hook, &SIZES, ObjKind::Mp), Err(QueryError::NoCurrent(ObjKind::Mp))));
+        set_current(ObjKind::Mp, 0x10);
+        assert!(matches!(query(&hook, &SIZES, ObjKind::Mp), Err(QueryError::NotRegistered(0x10))));
+        register(0x10, &POOL).unwrap();
+        set_current(ObjKind::Eq, 0x10);
+        assert!(matches!(query(&hook, &SIZES, ObjKind::Eq), Err(QueryError::WrongKind(ObjKind::Eq))));
+        assert!(matches!(set_current_raw(9, 0x10), Err(QueryError::UnknownKind(9))));
+        unregister(0x10);
+        assert!(matches!(query(&hook, &SIZES, ObjKind::Mp), Err(QueryError::NotRegistered(0x10))));
+        assert!(records.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn time_event_snapshots_use_the_time_event_widths() {
+        let te = Snapshot::Te { ao: 0x30, ctr: 7, interval: 0x1_0005, signal: 4, flags: 1 };
+        assert_eq!(te.kind(), ObjKind::Te);
+        assert_eq!(*te.record(0x20).encode(&SIZES), [obj_kind::TE, 0x20, 0, 0, 0, 0x30, 0, 0, 0, 7, 0, 5, 0, 4, 0, 1]);
+    }
+}
@@ -4,6 +4,8 @@
 //! *format descriptor* followed by the field payload encoded in little-endian
 //! order. The [`UserRecordBuilder`] mirrors this layout so that payloads emitted
 //! by the Rust port remain interoperable with the QSPY tooling.
+//! [`UserRecordEncoder`] writes the same layout into a borrowed buffer for
+//! records emitted too often to allocate for.
 
 #[cfg(not(feature = "std"))]
 extern crate alloc;
@@ -11,43 +13,83 @@ extern crate alloc;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
-/// Format identifier for `QS_I8_ENUM_FMT` records.
-pub const FMT_I8_ENUM: u8 = 0x0;
-/// Format identifier for `QS_U8_FMT` records.
-pub const FMT_U8: u8 = 0x1;
-/// Format identifier for `QS_I16_FMT` records.
-pub const FMT_I16: u8 = 0x2;
-/// Format identifier for `QS_U16_FMT` records.
-pub const FMT_U16: u8 = 0x3;
-/// Format identifier for `QS_I32_FMT` records.
-pub const FMT_I32: u8 = 0x4;
-/// Format identifier for `QS_U32_FMT` records.
-pub const FMT_U32: u8 = 0x5;
-/// Format identifier for `QS_F32_FMT` records.
-pub const FMT_F32: u8 = 0x6;
-/// Format identifier for `QS_F64_FMT` records.
-pub const FMT_F64: u8 = 0x7;
-/// Format identifier for `QS_STR_FMT` records.
-pub const FMT_STR: u8 = 0x8;
-/// Format identifier for `QS_MEM_FMT` records.
-pub const FMT_MEM: u8 = 0x9;
-/// Format identifier for `QS_SIG_FMT` records.
-pub const FMT_SIG: u8 = 0xA;
-/// Format identifier for `QS_OBJ_FMT` records.
-pub const FMT_OBJ: u8 = 0xB;
-/// Format identifier for `QS_FUN_FMT` records.
-pub const FMT_FUN: u8 = 0xC;
-/// Format identifier for `QS_I64_FMT` records.
-pub const FMT_I64: u8 = 0xD;
-/// Format identifier for `QS_U64_FMT` records.
-pub const FMT_U64: u8 = 0xE;
-/// Format identifier for the optional hexadecimal flag (`QS_HEX_FMT`).
-pub const FMT_HEX: u8 = 0xF;
-
-/// Computes a user-record format descriptor by combining a width hint with a
-/// base format identifier.
-pub fn make_format(width: u8, base: u8) -> u8 {
-    ((width & 0x0F) << 4) | (base & 0x0F)
+use crate::TraceError;
+
+pub use qs_protocol::format::{
+    make_format, FMT_F32, FMT_F64, FMT_FUN, FMT_HEX, FMT_I16, FMT_I32, FMT_I64, FMT_I8_ENUM, FMT_MEM, FMT_OBJ, FMT_SIG,
+    FMT_STR, FMT_U16, FMT_U32, FMT_U64, FMT_U8,
+};
+
+/// Field-appending methods shared by [`UserRecordBuilder`] and
+/// [`UserRecordEncoder`]; each type supplies `put`.
+macro_rules! field_methods {
+    () => {
+        /// Adds an unsigned 8-bit field using the provided width hint.
+        pub fn push_u8(&mut self, width: u8, value: u8) -> &mut Self {
+            self.put(&[make_format(width, FMT_U8), value])
+        }
+
+        /// Adds an unsigned 16-bit field using the provided width hint.
+        pub fn push_u16(&mut self, width: u8, value: u16) -> &mut Self {
+            self.put(&[make_format(width, FMT_U16)]).put(&value.to_le_bytes())
+        }
+
+        /// Adds an unsigned 32-bit field using the provided width hint.
+        pub fn push_u32(&mut self, width: u8, value: u32) -> &mut Self {
+            self.put(&[make_format(width, FMT_U32)]).put(&value.to_le_bytes())
+        }
+
+        /// Adds an unsigned 64-bit field using the provided width hint.
+        pub fn push_u64(&mut self, width: u8, value: u64) -> &mut Self {
+            self.put(&[make_format(width, FMT_U64)]).put(&value.to_le_bytes())
+        }
+
+        /// Adds a raw memory blob (length limited to 255 bytes).
+        pub fn push_mem(&mut self, data: &[u8]) -> &mut Self {
+            let len = u8::try_from(data.len()).expect("QS MEM payloads must be <= 255 bytes");
+            self.put(&[make_format(0, FMT_MEM), len]).put(data)
+        }
+
+        /// Adds a signed 8-bit (or enum) field.
+        pub fn push_i8_enum(&mut self, value: i8) -> &mut Self {
+            self.put(&[make_format(0, FMT_I8_ENUM), value as u8])
+        }
+
+        /// Adds a signed 16-bit field.
+        pub fn push_i16(&mut self, width: u8, value: i16) -> &mut Self {
+            self.put(&[make_format(width, FMT_I16)]).put(&value.to_le_bytes())
+        }
+
+        /// Adds a signed 32-bit field.
+        pub fn push_i32(&mut self, width: u8, value: i32) -> &mut Self {
+            self.put(&[make_format(width, FMT_I32)]).put(&value.to_le_bytes())
+        }
+
+        /// Adds a signed 64-bit field.
+        pub fn push_i64(&mut self, width: u8, value: i64) -> &mut Self {
+            self.put(&[make_format(width, FMT_I64)]).put(&value.to_le_bytes())
+        }
+
+        /// Adds a 32-bit float field.
+        pub fn push_f32(&mut self, value: f32) -> &mut Self {
+            self.put(&[make_format(0, FMT_F32)]).put(&value.to_le_bytes())
+        }
+
+        /// Adds a 64-bit float field.
+        pub fn push_f64(&mut self, value: f64) -> &mut Self {
+            self.put(&[make_format(0, FMT_F64)]).put(&value.to_le_bytes())
+        }
+
+        /// Adds a null-terminated ASCII string field.
+        pub fn push_str(&mut self, value: &str) -> &mut Self {
+            self.put(&[make_format(0, FMT_STR)]).put(value.as_bytes()).put(&[0])
+        }
+
+        /// Adds a pre-computed format descriptor alongside raw bytes.
+        pub fn push_raw(&mut self, format: u8, bytes: &[u8]) -> &mut Self {
+            self.put(&[format]).put(bytes)
+        }
+    };
 }
 
 /// Incremental builder for QS user-record payloads.
@@ -69,103 +111,59 @@ impl UserRecordBuilder {
         }
     }
 
-    /// Adds an unsigned 8-bit field using the provided width hint.
-    pub fn push_u8(&mut self, width: u8, value: u8) -> &mut Self {
-        self.bytes.push(make_format(width, FMT_U8));
-        self.bytes.push(value);
-        self
-    }
-
-    /// Adds an unsigned 16-bit field using the provided width hint.
-    pub fn push_u16(&mut self, width: u8, value: u16) -> &mut Self {
-        self.bytes.push(make_format(width, FMT_U16));
-        self.bytes.extend_from_slice(&value.to_le_bytes());
-        self
-    }
-
-    /// Adds an unsigned 32-bit field using the provided width hint.
-    pub fn push_u32(&mut self, width: u8, value: u32) -> &mut Self {
-        self.bytes.push(make_format(width, FMT_U32));
-        self.bytes.extend_from_slice(&value.to_le_bytes());
-        self
-    }
-
-    /// Adds an unsigned 64-bit field using the provided width hint.
-    pub fn push_u64(&mut self, width: u8, value: u64) -> &mut Self {
-        self.bytes.push(make_format(width, FMT_U64));
-        self.bytes.extend_from_slice(&value.to_le_bytes());
-        self
+    /// Starts a payload encoded straight into `buf` — a stack array or a
+    /// pooled buffer — without allocating. See [`UserRecordEncoder`].
+    pub fn encode_into(buf: &mut [u8]) -> UserRecordEncoder<'_> {
+        UserRecordEncoder { buf, len: 0, needed: 0 }
     }
 
-    /// Adds a raw memory blob (length limited to 255 bytes).
-    pub fn push_mem(&mut self, data: &[u8]) -> &mut Self {
-        let len = u8::try_from(data.len()).expect("QS MEM payloads must be <= 255 bytes");
-        self.bytes.push(make_format(0, FMT_MEM));
-        self.bytes.push(len);
-        self.bytes.extend_from_slice(data);
-        self
-    }
-
-    /// Adds a signed 8-bit (or enum) field.
-    pub fn push_i8_enum(&mut self, value: i8) -> &mut Self {
-        self.bytes.push(make_format(0, FMT_I8_ENUM));
-        self.bytes.push(value as u8);
-        self
-    }
-
-    /// Adds a signed 16-bit field.
-    pub fn push_i16(&mut self, width: u8, value: i16) -> &mut Self {
-        self.bytes.push(make_format(width, FMT_I16));
-        self.bytes.extend_from_slice(&value.to_le_bytes());
-        self
-    }
-
-    /// Adds a signed 32-bit field.
-    pub fn push_i32(&mut self, width: u8, value: i32) -> &mut Self {
-        self.bytes.push(make_format(width, FMT_I32));
-        self.bytes.extend_from_slice(&value.to_le_bytes());
+    fn put(&mut self, bytes: &[u8]) -> &mut Self {
+        self.bytes.extend_from_slice(bytes);
         self
     }
 
-    /// Adds a signed 64-bit field.
-    pub fn push_i64(&mut self, width: u8, value: i64) -> &mut Self {
-        self.bytes.push(make_format(width, FMT_I64));
-        self.bytes.extend_from_slice(&value.to_le_bytes());
-        self
-    }
+    field_methods!();
 
-    /// Adds a 32-bit float field.
-    pub fn push_f32(&mut self, value: f32) -> &mut Self {
-        self.bytes.push(make_format(0, FMT_F32));
-        self.bytes.extend_from_slice(&value.to_le_bytes());
-        self
+    /// Consumes the builder and returns the accumulated payload bytes.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.bytes
     }
+}
 
-    /// Adds a 64-bit float field.
-    pub fn push_f64(&mut self, value: f64) -> &mut Self {
-        self.bytes.push(make_format(0, FMT_F64));
-        self.bytes.extend_from_slice(&value.to_le_bytes());
-        self
-    }
+/// Borrowing counterpart of [`UserRecordBuilder`] that writes fields into a
+/// caller-provided buffer.
+///
+/// The `push_*` methods stay chainable when the buffer runs out: the field
+/// that does not fit and everything after it are dropped, and
+/// [`finish`](Self::finish) reports the size the payload would have needed.
+#[derive(Debug)]
+pub struct UserRecordEncoder<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    /// Bytes requested so far, written or not.
+    needed: usize,
+}
 
-    /// Adds a null-terminated ASCII string field.
-    pub fn push_str(&mut self, value: &str) -> &mut Self {
-        self.bytes.push(make_format(0, FMT_STR));
-        self.bytes.extend_from_slice(value.as_bytes());
-        self.bytes.push(0);
+impl<'a> UserRecordEncoder<'a> {
+    fn put(&mut self, bytes: &[u8]) -> &mut Self {
+        let end = self.needed + bytes.len();
+        if self.len == self.needed && end <= self.buf.len() {
+            self.buf[self.len..end].copy_from_slice(bytes);
+            self.len = end;
+        }
+        self.needed = end;
         self
     }
 
-    /// Adds a pre-computed format descriptor alongside raw bytes.
-    pub fn push_raw(&mut self, format: u8, bytes: &[u8]) -> &mut Self {
-        self.bytes.push(format);
-        self.bytes.extend_from_slice(bytes);
-        self
-    }
+    field_methods!();
 
-    /// Consumes the builder and returns the accumulated payload bytes.
-    pub fn into_vec(self) -> Vec<u8> {
-        self.bytes
+    /// Returns the encoded payload, or [`TraceError::PayloadTooLarge`] with
+    /// the full payload size if it did not fit the buffer.
+    pub fn finish(self) -> Result<&'a [u8], TraceError> {
+        if self.len < self.needed {
+            return Err(TraceError::PayloadTooLarge(self.needed));
+        }
+        Ok(&self.buf[..self.len])
     }
 }
 
@@ -193,4 +191,23 @@ mod tests {
         builder.push_str("hi");
         assert_eq!(builder.into_vec(), vec![0x08, b'h', b'i', 0]);
     }
+
+    #[test]
+    fn encoder_matches_builder_without_allocating() {
+        let mut builder = UserRecordBuilder::new();
+        builder.push_u8(1, 3).push_str("eating").push_mem(&[0xDE, 0xAD]).push_i32(0, -2);
+
+        let mut buf = [0u8; 32];
+        let mut encoder = UserRecordBuilder::encode_into(&mut buf);
+        encoder.push_u8(1, 3).push_str("eating").push_mem(&[0xDE, 0xAD]).push_i32(0, -2);
+        assert_eq!(encoder.finish().unwrap(), builder.into_vec().as_slice());
+    }
+
+    #[test]
+    fn encoder_reports_the_size_it_needed() {
+        let mut buf = [0u8; 4];
+        let mut encoder = UserRecordBuilder::encode_into(&mut buf);
+        encoder.push_u16(0, 7).push_u32(0, 9).push_u8(0, 1);
+        assert!(matches!(encoder.finish(), Err(TraceError::PayloadTooLarge(10))));
+    }
 }
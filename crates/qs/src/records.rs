@@ -2,167 +2,518 @@
 //!
 //! These numeric ids match the QP/Spy trace protocol so that the standard QSpy
 //! host tools can decode records emitted by qp-rs.
+//!
+//! The ids themselves are defined in [`qs_protocol::records`] and re-exported
+//! here. Next to them sits a builder for the record's payload. A builder takes
+//! the values in protocol order and returns a [`Predefined`] record, which
+//! lays the fields out at the widths given by a [`RecordSizes`]:
+//!
+//! ```
+//! use qs::records::{qep, RecordSizes};
+//!
+//! let tran = qep::tran(0x2000, 4, 0x8000_1000, 0x8000_1040);
+//! let payload = tran.encode(&RecordSizes::from_target_info(&qs::TargetInfo::default()));
+//! assert_eq!(payload.len(), 2 + 8 * 3);
+//! ```
+
+use core::ops::Deref;
+
+use crate::predefined::TargetInfo;
+use crate::{TraceError, TraceHook};
 
 /// QEP (state machine) related record identifiers.
 pub mod qep {
-    /// State entry action executed.
-    pub const STATE_ENTRY: u8 = 1;
-    /// State exit action executed.
-    pub const STATE_EXIT:  u8 = 2;
-    /// Nested initial transition taken.
-    pub const STATE_INIT:  u8 = 3;
-    /// Top-most initial transition taken.
-    pub const INIT_TRAN:   u8 = 4;
-    /// Internal transition (no state change).
-    pub const INTERN_TRAN: u8 = 5;
-    /// Regular state transition.
-    pub const TRAN:        u8 = 6;
-    /// Event ignored by the state machine.
-    pub const IGNORED:     u8 = 7;
-    /// Event dispatched to the state machine.
-    pub const DISPATCH:    u8 = 8;
-    /// Event reached the top state unhandled.
-    pub const UNHANDLED:   u8 = 9;
-    /// Transition to history pseudo-state (no timestamp, RTC step).
-    pub const TRAN_HIST:   u8 = 55;
+    pub use qs_protocol::records::qep::*;
+    use super::{Field, Predefined};
+
+    /// `obj | state`, untimed.
+    pub fn state_entry(obj: u64, state: u64) -> Predefined {
+        Predefined::new(STATE_ENTRY, false, &[Field::Obj(obj), Field::Fun(state)])
+    }
+
+    /// `obj | state`, untimed.
+    pub fn state_exit(obj: u64, state: u64) -> Predefined {
+        Predefined::new(STATE_EXIT, false, &[Field::Obj(obj), Field::Fun(state)])
+    }
+
+    /// `obj | source | target`, untimed.
+    pub fn state_init(obj: u64, source: u64, target: u64) -> Predefined {
+        Predefined::new(STATE_INIT, false, &[Field::Obj(obj), Field::Fun(source), Field::Fun(target)])
+    }
+
+    /// `ts | obj | target`.
+    pub fn init_tran(obj: u64, target: u64) -> Predefined {
+        Predefined::new(INIT_TRAN, true, &[Field::Obj(obj), Field::Fun(target)])
+    }
+
+    /// `ts | sig | obj | state`.
+    pub fn intern_tran(obj: u64, sig: u16, state: u64) -> Predefined {
+        Predefined::new(INTERN_TRAN, true, &[Field::Sig(sig), Field::Obj(obj), Field::Fun(state)])
+    }
+
+    /// `ts | sig | obj | source | target`.
+    pub fn tran(obj: u64, sig: u16, source: u64, target: u64) -> Predefined {
+        Predefined::new(
+            TRAN,
+            true,
+            &[Field::Sig(sig), Field::Obj(obj), Field::Fun(source), Field::Fun(target)],
+        )
+    }
+
+    /// `ts | sig | obj | state`.
+    pub fn ignored(obj: u64, sig: u16, state: u64) -> Predefined {
+        Predefined::new(IGNORED, true, &[Field::Sig(sig), Field::Obj(obj), Field::Fun(state)])
+    }
+
+    /// `ts | sig | obj | state`.
+    pub fn dispatch(obj: u64, sig: u16, state: u64) -> Predefined {
+        Predefined::new(DISPATCH, true, &[Field::Sig(sig), Field::Obj(obj), Field::Fun(state)])
+    }
+
+    /// `sig | obj | state`, untimed.
+    pub fn unhandled(obj: u64, sig: u16, state: u64) -> Predefined {
+        Predefined::new(UNHANDLED, false, &[Field::Sig(sig), Field::Obj(obj), Field::Fun(state)])
+    }
+
+    /// `obj | source | target`, untimed.
+    pub fn tran_hist(obj: u64, source: u64, target: u64) -> Predefined {
+        Predefined::new(TRAN_HIST, false, &[Field::Obj(obj), Field::Fun(source), Field::Fun(target)])
+    }
+
+    /// `ts | sig | obj | state | target | passed`.
+    pub fn guard(obj: u64, sig: u16, state: u64, target: u64, passed: bool) -> Predefined {
+        Predefined::new(
+            GUARD,
+            true,
+            &[Field::Sig(sig), Field::Obj(obj), Field::Fun(state), Field::Fun(target), Field::U8(passed as u8)],
+        )
+    }
 }
 
 /// QF (framework) record identifiers.
 pub mod qf {
-    /// Active object deferred an event.
-    pub const ACTIVE_DEFER:            u8 = 10;
-    /// Active object recalled a deferred event.
-    pub const ACTIVE_RECALL:           u8 = 11;
-    /// Active object subscribed to a signal.
-    pub const ACTIVE_SUBSCRIBE:        u8 = 12;
-    /// Active object unsubscribed from a signal.
-    pub const ACTIVE_UNSUBSCRIBE:      u8 = 13;
-    /// Event posted (FIFO) to an active object.
-    pub const ACTIVE_POST:             u8 = 14;
-    /// Event posted (LIFO) to an active object.
-    pub const ACTIVE_POST_LIFO:        u8 = 15;
-    /// Event retrieved from an active object's queue.
-    pub const ACTIVE_GET:              u8 = 16;
-    /// Last event retrieved (queue became empty).
-    pub const ACTIVE_GET_LAST:         u8 = 17;
-    /// Recall attempted but no event was deferred.
-    pub const ACTIVE_RECALL_ATTEMPT:   u8 = 18;
-    /// Raw event queue initialized.
-    pub const EQUEUE_INIT:             u8 = 19;
-    /// Event posted (FIFO) to a raw event queue.
-    pub const EQUEUE_POST:             u8 = 20;
-    /// Event posted (LIFO) to a raw event queue.
-    pub const EQUEUE_POST_LIFO:        u8 = 21;
-    /// Event retrieved from a raw event queue.
-    pub const EQUEUE_GET:              u8 = 22;
-    /// Memory pool initialized.
-    pub const MPOOL_INIT:              u8 = 23;
-    /// Block obtained from a memory pool.
-    pub const MPOOL_GET:               u8 = 24;
-    /// Block returned to a memory pool.
-    pub const MPOOL_PUT:               u8 = 25;
-    /// Event published to subscribers.
-    pub const PUBLISH:                 u8 = 26;
-    /// New reference taken to an event.
-    pub const NEW_REF:                 u8 = 27;
-    /// New event allocated.
-    pub const NEW:                     u8 = 28;
-    /// Garbage-collect attempted (refcount not yet zero).
-    pub const GC_ATTEMPT:              u8 = 29;
-    /// Event garbage-collected (freed).
-    pub const GC:                      u8 = 30;
-    /// System clock tick processed.
-    pub const TICK:                    u8 = 31;
+    pub use qs_protocol::records::qf::*;
+    use super::{Field, Predefined};
+
+    /// `ts | sig | sender | ao | pool | ref | free | min`. `free` and `min`
+    /// are the queue's free entries now and at its low-water mark.
+    #[allow(clippy::too_many_arguments)]
+    pub fn active_post(
+        sender: u64,
+        ao: u64,
+        sig: u16,
+        pool: u8,
+        ref_ctr: u8,
+        free: u32,
+        min: u32,
+    ) -> Predefined {
+        Predefined::new(
+            ACTIVE_POST,
+            true,
+            &[
+                Field::Sig(sig),
+                Field::Obj(sender),
+                Field::Obj(ao),
+                Field::U8(pool),
+                Field::U8(ref_ctr),
+                Field::EqCtr(free),
+                Field::EqCtr(min),
+            ],
+        )
+    }
+
+    /// `ts | sig | ao`.
+    pub fn subscribe(ao: u64, sig: u16) -> Predefined {
+        Predefined::new(ACTIVE_SUBSCRIBE, true, &[Field::Sig(sig), Field::Obj(ao)])
+    }
+
+    /// `ts | sig | ao`.
+    pub fn unsubscribe(ao: u64, sig: u16) -> Predefined {
+        Predefined::new(ACTIVE_UNSUBSCRIBE, true, &[Field::Sig(sig), Field::Obj(ao)])
+    }
+
+    /// `ts | sender | sig | pool | ref`.
+    pub fn publish(sender: u64, sig: u16, pool: u8, ref_ctr: u8) -> Predefined {
+        Predefined::new(
+            PUBLISH,
+            true,
+            &[Field::Obj(sender), Field::Sig(sig), Field::U8(pool), Field::U8(ref_ctr)],
+        )
+    }
+
+    /// `ts | sig | pool | ref`: a dynamic event released by its last user,
+    /// `ref` being the count before the release.
+    pub fn gc(sig: u16, pool: u8, ref_ctr: u8) -> Predefined {
+        Predefined::new(GC, true, &[Field::Sig(sig), Field::U8(pool), Field::U8(ref_ctr)])
+    }
+
+    /// `ts | sig | pool | ref`: a dynamic event released by one user while
+    /// `ref - 1` others still hold it.
+    pub fn gc_attempt(sig: u16, pool: u8, ref_ctr: u8) -> Predefined {
+        Predefined::new(GC_ATTEMPT, true, &[Field::Sig(sig), Field::U8(pool), Field::U8(ref_ctr)])
+    }
+
+    /// `ts | ctr | rate`, where `ctr` counts the ticks of `rate`.
+    pub fn tick(ctr: u32, rate: u8) -> Predefined {
+        Predefined::new(TICK, true, &[Field::TeCtr(ctr), Field::U8(rate)])
+    }
 
     /// Time-event record identifiers (32–37).
     pub mod time_evt {
-        /// Time event armed.
-        pub const ARM:                 u8 = 32;
-        /// One-shot time event auto-disarmed on expiry.
-        pub const AUTO_DISARM:         u8 = 33;
-        /// Disarm attempted on an already-disarmed event.
-        pub const DISARM_ATTEMPT:      u8 = 34;
-        /// Time event disarmed.
-        pub const DISARM:              u8 = 35;
-        /// Time event counter updated via `rearm()`.
-        pub const REARM:               u8 = 36;
-        /// Time event posted to its target active object.
-        pub const POST:                u8 = 37;
-    }
-
-    /// Reference to an event deleted.
-    pub const DELETE_REF:              u8 = 38;
-    /// Critical section entered.
-    pub const CRIT_ENTRY:              u8 = 39;
-    /// Critical section exited.
-    pub const CRIT_EXIT:               u8 = 40;
-    /// Interrupt service routine entered.
-    pub const ISR_ENTRY:              u8 = 41;
-    /// Interrupt service routine exited.
-    pub const ISR_EXIT:                u8 = 42;
-
-    /// Post (FIFO) attempt failed (queue full / margin not met).
-    pub const ACTIVE_POST_ATTEMPT:     u8 = 45;
-    /// Raw event-queue post attempt failed.
-    pub const EQUEUE_POST_ATTEMPT:     u8 = 46;
-    /// Memory-pool get attempt failed (pool exhausted).
-    pub const MPOOL_GET_ATTEMPT:       u8 = 47;
-    /// Defer attempt failed (added in QP/C++ v8.0.4).
-    pub const ACTIVE_DEFER_ATTEMPT:    u8 = 81;
-}
+        pub use qs_protocol::records::qf::time_evt::*;
+        use crate::records::{Field, Predefined};
+
+        fn counters(record: u8, te: u64, ao: u64, ctr: u32, interval: u32, rate: u8) -> Predefined {
+            Predefined::new(
+                record,
+                true,
+                &[Field::Obj(te), Field::Obj(ao), Field::TeCtr(ctr), Field::TeCtr(interval), Field::U8(rate)],
+            )
+        }
+
+        /// `ts | te | ao | ctr | interval | rate`.
+        pub fn arm(te: u64, ao: u64, ctr: u32, interval: u32, rate: u8) -> Predefined {
+            counters(ARM, te, ao, ctr, interval, rate)
+        }
+
+        /// `ts | te | ao | remaining | interval | rate`.
+        pub fn disarm(te: u64, ao: u64, remaining: u32, interval: u32, rate: u8) -> Predefined {
+            counters(DISARM, te, ao, remaining, interval, rate)
+        }
 
-/// Scheduler related record identifiers (50–53).
-pub mod sched {
-    /// Scheduler locked at a priority ceiling.
-    pub const LOCK:   u8 = 50;
-    /// Scheduler unlocked.
-    pub const UNLOCK: u8 = 51;
-    /// Scheduler selected the next task to run.
-    pub const NEXT:   u8 = 52;
-    /// Scheduler went idle.
-    pub const IDLE:   u8 = 53;
+        /// `ts | te | ao | ctr | interval | rate`.
+        pub fn rearm(te: u64, ao: u64, ctr: u32, interval: u32, rate: u8) -> Predefined {
+            counters(REARM, te, ao, ctr, interval, rate)
+        }
+
+        /// `te | ao | rate`, untimed: it belongs to the tick that posted it.
+        pub fn auto_disarm(te: u64, ao: u64, rate: u8) -> Predefined {
+            Predefined::new(AUTO_DISARM, false, &[Field::Obj(te), Field::Obj(ao), Field::U8(rate)])
+        }
+
+        /// `ts | te | ao | rate`.
+        pub fn disarm_attempt(te: u64, ao: u64, rate: u8) -> Predefined {
+            Predefined::new(DISARM_ATTEMPT, true, &[Field::Obj(te), Field::Obj(ao), Field::U8(rate)])
+        }
+
+        /// `ts | te | sig | ao | rate`.
+        pub fn post(te: u64, sig: u16, ao: u64, rate: u8) -> Predefined {
+            Predefined::new(
+                POST,
+                true,
+                &[Field::Obj(te), Field::Sig(sig), Field::Obj(ao), Field::U8(rate)],
+            )
+        }
+    }
+
+    /// `ts | ao | len | status`: `len` bytes saved, `status` `0` on success.
+    pub fn ao_save(ao: u64, len: u16, status: u8) -> Predefined {
+        Predefined::new(AO_SAVE, true, &[Field::Obj(ao), Field::U16(len), Field::U8(status)])
+    }
+
+    /// `ts | ao | len | status`: `len` bytes restored, `status` `0` on
+    /// success.
+    pub fn ao_restore(ao: u64, len: u16, status: u8) -> Predefined {
+        Predefined::new(AO_RESTORE, true, &[Field::Obj(ao), Field::U16(len), Field::U8(status)])
+    }
+
+    /// `ts | te | ao | samples | last | max | mean | overlaps`: lateness in
+    /// ticks as time-event counters, `samples` and `overlaps` as `u16`,
+    /// saturating.
+    pub fn timeevt_jitter(te: u64, ao: u64, samples: u32, last: u32, max: u32, mean: u32, overlaps: u32) -> Predefined {
+        let u16_sat = |v: u32| v.min(u32::from(u16::MAX)) as u16;
+        Predefined::new(
+            TIMEEVT_JITTER,
+            true,
+            &[
+                Field::Obj(te),
+                Field::Obj(ao),
+                Field::U16(u16_sat(samples)),
+                Field::TeCtr(last),
+                Field::TeCtr(max),
+                Field::TeCtr(mean),
+                Field::U16(u16_sat(overlaps)),
+            ],
+        )
+    }
 }
 
+pub use qs_protocol::records::sched;
+
 /// Test/infrastructure record identifiers (58–70).
 pub mod infra {
-    /// QUTest run paused, awaiting host.
-    pub const TEST_PAUSED: u8 = 58;
-    /// QUTest probe value requested.
-    pub const TEST_PROBE:  u8 = 59;
-    /// Host-tool back-channel: target acknowledges a command.
-    pub const TARGET_DONE: u8 = 65;
-    /// Host-tool back-channel: status of last RX command.
-    pub const RX_STATUS:   u8 = 66;
-    /// Response to a host query for object data.
-    pub const QUERY_DATA:  u8 = 67;
-    /// Response to a host memory-peek request.
-    pub const PEEK_DATA:   u8 = 68;
-    /// Assertion failed on the target.
-    pub const ASSERT_FAIL: u8 = 69;
-    /// Framework `run()` loop entered.
-    pub const QF_RUN:      u8 = 70;
+    pub use qs_protocol::records::infra::*;
+    use super::{Field, Predefined};
+
+
+    /// `ts | SM | obj | state`.
+    pub fn query_sm(obj: u64, state: u64) -> Predefined {
+        Predefined::new(QUERY_DATA, true, &[Field::U8(obj_kind::SM), Field::Obj(obj), Field::Fun(state)])
+    }
+
+    /// `ts | AO | obj | n_free | n_min`, counters at the event-queue width.
+    pub fn query_ao(obj: u64, n_free: u32, n_min: u32) -> Predefined {
+        Predefined::new(
+            QUERY_DATA,
+            true,
+            &[Field::U8(obj_kind::AO), Field::Obj(obj), Field::EqCtr(n_free), Field::EqCtr(n_min)],
+        )
+    }
+
+    /// `ts | MP | obj | n_free | n_min`, counters at the pool width.
+    pub fn query_mp(obj: u64, n_free: u32, n_min: u32) -> Predefined {
+        Predefined::new(
+            QUERY_DATA,
+            true,
+            &[Field::U8(obj_kind::MP), Field::Obj(obj), Field::MpCtr(n_free), Field::MpCtr(n_min)],
+        )
+    }
+
+    /// `ts | EQ | obj | n_free | n_min`.
+    pub fn query_eq(obj: u64, n_free: u32, n_min: u32) -> Predefined {
+        Predefined::new(
+            QUERY_DATA,
+            true,
+            &[Field::U8(obj_kind::EQ), Field::Obj(obj), Field::EqCtr(n_free), Field::EqCtr(n_min)],
+        )
+    }
+
+    /// `ts | TE | obj | ao | ctr | interval | sig | flags`.
+    pub fn query_te(te: u64, ao: u64, ctr: u32, interval: u32, sig: u16, flags: u8) -> Predefined {
+        Predefined::new(
+            QUERY_DATA,
+            true,
+            &[
+                Field::U8(obj_kind::TE),
+                Field::Obj(te),
+                Field::Obj(ao),
+                Field::TeCtr(ctr),
+                Field::TeCtr(interval),
+                Field::Sig(sig),
+                Field::U8(flags),
+            ],
+        )
+    }
+
+    /// `ts | AP | obj`.
+    pub fn query_ap(obj: u64) -> Predefined {
+        Predefined::new(QUERY_DATA, true, &[Field::U8(obj_kind::AP), Field::Obj(obj)])
+    }
 }
 
-/// QXK extended-kernel record identifiers (71–80).
+/// QXK extended-kernel record identifiers (71–80, plus the qp-rs thread-stack
+/// record).
 pub mod qxk {
-    /// Semaphore taken (count was > 0).
-    pub const SEM_TAKE:           u8 = 71;
-    /// Semaphore wait blocked (count == 0, thread suspended).
-    pub const SEM_BLOCK:          u8 = 72;
-    /// Semaphore signalled (count incremented, waiter possibly woken).
-    pub const SEM_SIGNAL:         u8 = 73;
-    /// Semaphore `try_wait` attempt failed (non-blocking path).
-    pub const SEM_BLOCK_ATTEMPT:  u8 = 74;
-    /// Mutex locked by calling thread.
-    pub const MTX_LOCK:           u8 = 75;
-    /// Mutex lock blocked (already held, thread suspended).
-    pub const MTX_BLOCK:          u8 = 76;
-    /// Mutex unlocked by calling thread.
-    pub const MTX_UNLOCK:         u8 = 77;
-    /// Mutex `try_lock` attempt failed (already held).
-    pub const MTX_LOCK_ATTEMPT:   u8 = 78;
-    /// Mutex `try_lock` attempt failed (non-blocking path, already held).
-    pub const MTX_BLOCK_ATTEMPT:  u8 = 79;
-    /// Mutex `unlock` attempt failed (caller is not the owner).
-    pub const MTX_UNLOCK_ATTEMPT: u8 = 80;
+    pub use qs_protocol::records::qxk::*;
+    use crate::records::{Field, Predefined};
+
+    /// `ts | thread | size | used | overflow`: stack bytes as `u32`,
+    /// `overflow` `1` once the canary is gone.
+    pub fn thread_stack(thread: u8, size: u32, used: u32, overflow: u8) -> Predefined {
+        Predefined::new(
+            THREAD_STACK,
+            true,
+            &[Field::U8(thread), Field::U32(size), Field::U32(used), Field::U8(overflow)],
+        )
+    }
+}
+
+/// Wire widths of the sized fields in predefined records — the sizes a
+/// target announces in `QS_TARGET_INFO`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordSizes {
+    /// Signal width: 1, 2 or 4 bytes.
+    pub signal: u8,
+    /// Object pointer width: 1, 2, 4 or 8 bytes.
+    pub obj_ptr: u8,
+    /// Function (state handler) pointer width: 1, 2, 4 or 8 bytes.
+    pub fun_ptr: u8,
+    /// Event-queue counter width: 1, 2 or 4 bytes.
+    pub equeue_ctr: u8,
+    /// Time-event counter width: 1, 2 or 4 bytes.
+    pub time_evt_ctr: u8,
+    /// Memory-pool counter width: 1, 2 or 4 bytes.
+    pub mpool_ctr: u8,
+}
+
+impl RecordSizes {
+    /// The sizes of the machine this code runs on: native pointers, 16-bit
+    /// signals and 16-bit counters.
+    pub const NATIVE: Self = Self {
+        signal: 2,
+        obj_ptr: core::mem::size_of::<usize>() as u8,
+        fun_ptr: core::mem::size_of::<usize>() as u8,
+        equeue_ctr: 2,
+        time_evt_ctr: 2,
+        mpool_ctr: 2,
+    };
+
+    /// The sizes announced in `info`.
+    pub fn from_target_info(info: &TargetInfo) -> Self {
+        Self {
+            signal: info.signal_size,
+            obj_ptr: info.obj_ptr_size,
+            fun_ptr: info.fun_ptr_size,
+            equeue_ctr: info.equeue_ctr_size,
+            time_evt_ctr: info.time_evt_ctr_size,
+            mpool_ctr: info.mpool_ctr_size,
+        }
+    }
+}
+
+impl Default for RecordSizes {
+    fn default() -> Self {
+        Self::NATIVE
+    }
+}
+
+/// Most fields in any predefined record (`ACTIVE_POST`).
+const MAX_FIELDS: usize = 7;
+/// Longest payload: every field at the widest size.
+const MAX_PAYLOAD: usize = MAX_FIELDS * 8;
+
+/// One payload field and how [`RecordSizes`] sizes it. Values wider than
+/// their field keep the low bytes, as a cast to the target's type would.
+/// Object and function fields go out as [symbols](crate::symbols::symbol).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Field {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    Sig(u16),
+    Obj(u64),
+    Fun(u64),
+    EqCtr(u32),
+    TeCtr(u32),
+    MpCtr(u32),
+}
+
+impl Field {
+    fn encode(self, sizes: &RecordSizes, out: &mut RecordPayload) {
+        match self {
+            Self::U8(v) => out.push(u64::from(v), 1),
+            Self::U16(v) => out.push(u64::from(v), 2),
+            Self::U32(v) => out.push(u64::from(v), 4),
+            Self::Sig(v) => out.push(u64::from(v), sizes.signal),
+            Self::Obj(v) => out.push(crate::symbols::symbol(v), sizes.obj_ptr),
+            Self::Fun(v) => out.push(crate::symbols::symbol(v), sizes.fun_ptr),
+            Self::EqCtr(v) => out.push(u64::from(v), sizes.equeue_ctr),
+            Self::TeCtr(v) => out.push(u64::from(v), sizes.time_evt_ctr),
+            Self::MpCtr(v) => out.push(u64::from(v), sizes.mpool_ctr),
+        }
+    }
+}
+
+/// A predefined record ready to encode: its id, whether it carries a
+/// timestamp, and its field values in protocol order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Predefined {
+    record_type: u8,
+    timestamp: bool,
+    fields: [Field; MAX_FIELDS],
+    len: usize,
+}
+
+impl Predefined {
+    pub(crate) fn new(record_type: u8, timestamp: bool, fields: &[Field]) -> Self {
+        let mut all = [Field::U8(0); MAX_FIELDS];
+        all[..fields.len()].copy_from_slice(fields);
+        Self { record_type, timestamp, fields: all, len: fields.len() }
+    }
+
+    /// The record id.
+    pub fn record_type(&self) -> u8 {
+        self.record_type
+    }
+
+    /// Whether the protocol puts a timestamp before the payload.
+    pub fn has_timestamp(&self) -> bool {
+        self.timestamp
+    }
+
+    /// Lays the fields out at `sizes`.
+    pub fn encode(&self, sizes: &RecordSizes) -> RecordPayload {
+        let mut out = RecordPayload { bytes: [0; MAX_PAYLOAD], len: 0 };
+        for field in &self.fields[..self.len] {
+            field.encode(sizes, &mut out);
+        }
+        out
+    }
+
+    /// Encodes the record at `sizes` and emits it through `hook`.
+    pub fn emit(&self, hook: &TraceHook, sizes: &RecordSizes) -> Result<(), TraceError> {
+        hook(self.record_type, &self.encode(sizes), self.timestamp)
+    }
+}
+
+/// An encoded predefined payload, on the stack.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordPayload {
+    bytes: [u8; MAX_PAYLOAD],
+    len: usize,
+}
+
+impl RecordPayload {
+    fn push(&mut self, value: u64, width: u8) {
+        let width = usize::from(width);
+        self.bytes[self.len..self.len + width].copy_from_slice(&value.to_le_bytes()[..width]);
+        self.len += width;
+    }
+}
+
+impl Deref for RecordPayload {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: RecordSizes =
+        RecordSizes { signal: 1, obj_ptr: 4, fun_ptr: 2, equeue_ctr: 1, time_evt_ctr: 1, mpool_ctr: 1 };
+
+    #[test]
+    fn tran_follows_the_configured_widths() {
+        let tran = qep::tran(0x1122_3344_5566, 0x0405, 0xAABB_CCDD, 0x0102);
+        assert_eq!(tran.record_type(), qep::TRAN);
+        assert!(tran.has_timestamp());
+        assert_eq!(*tran.encode(&SMALL), [0x05, 0x66, 0x55, 0x44, 0x33, 0xDD, 0xCC, 0x02, 0x01]);
+    }
+
+    #[test]
+    fn wide_values_keep_their_low_bytes() {
+        let post = qf::active_post(1, 2, 0x0103, 4, 5, 300, 7);
+        assert_eq!(*post.encode(&SMALL), [3, 1, 0, 0, 0, 2, 0, 0, 0, 4, 5, 44, 7]);
+        let tick = qf::tick(0x1_0002, 1);
+        assert_eq!(*tick.encode(&RecordSizes::NATIVE), [2, 0, 1]);
+    }
+
+    #[test]
+    fn untimed_records_say_so() {
+        for record in [
+            qep::state_entry(1, 2),
+            qep::state_exit(1, 2),
+            qep::state_init(1, 2, 3),
+            qep::unhandled(1, 2, 3),
+            qep::tran_hist(1, 2, 3),
+            qf::time_evt::auto_disarm(1, 2, 0),
+        ] {
+            assert!(!record.has_timestamp(), "record {}", record.record_type());
+        }
+    }
+
+    #[test]
+    fn sizes_come_from_target_info() {
+        let info = TargetInfo { obj_ptr_size: 4, fun_ptr_size: 4, equeue_ctr_size: 1, ..TargetInfo::default() };
+        let sizes = RecordSizes::from_target_info(&info);
+        assert_eq!(
+            sizes,
+            RecordSizes { signal: 2, obj_ptr: 4, fun_ptr: 4, equeue_ctr: 1, time_evt_ctr: 2, mpool_ctr: 2 }
+        );
+        assert_eq!(qf::subscribe(0x1234, 9).encode(&sizes).len(), 6);
+    }
 }
@@ -0,0 +1,89 @@
+//! Target reset on host request (`QS_RX_RESET`).
+//!
+//! QSPY and QUTest reset the target between test groups and whenever the
+//! user asks for it. The target's QS-RX loop hands the request to
+//! [`on_reset`], which runs the handler the application registered with
+//! [`set_reset_handler`], the equivalent of QP's `QS_onReset()`:
+//!
+//! ```rust,ignore
+//! // POSIX: start the application again, which re-emits TARGET_INFO and
+//! // the dictionaries.
+//! qs::reset::set_reset_handler(qf_port_posix::restart_process);
+//! // ESP32-C6: reset the chip.
+//! qs::reset::set_reset_handler(hal_rvsis::esp32c6::wdt::software_reset);
+//!
+//! RxCmd::Reset => {
+//!     qs::reset::on_reset();
+//!     // Only reached without a handler.
+//! }
+//! ```
+//!
+//! A reset does not acknowledge the command: the host learns it happened
+//! from the `TARGET_INFO` record the restarted target sends, whose
+//! `is_reset` byte makes the host drop the dictionaries it had.
+
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(not(feature = "std"))]
+use spin::Mutex;
+
+/// Restarts the target. Never returns: the process or the chip starts over.
+pub type ResetHandler = fn() -> !;
+
+static HANDLER: Mutex<Option<ResetHandler>> = Mutex::new(None);
+
+/// Registers the handler [`on_reset`] runs, replacing any previous one.
+pub fn set_reset_handler(handler: ResetHandler) {
+    *lock_handler() = Some(handler);
+}
+
+/// Removes the reset handler; `QS_RX_RESET` is then refused.
+pub fn clear_reset_handler() {
+    *lock_handler() = None;
+}
+
+/// The registered reset handler, if any.
+pub fn reset_handler() -> Option<ResetHandler> {
+    *lock_handler()
+}
+
+/// Handles `QS_RX_RESET`: runs the registered handler, which does not
+/// return. Returns only when no handler is registered, so the caller can
+/// report the command as refused.
+pub fn on_reset() {
+    // Copied out first: the handler must not run with the lock held.
+    let handler = reset_handler();
+    if let Some(handler) = handler {
+        handler();
+    }
+}
+
+#[cfg(feature = "std")]
+fn lock_handler() -> std::sync::MutexGuard<'static, Option<ResetHandler>> {
+    HANDLER.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(not(feature = "std"))]
+fn lock_handler() -> spin::MutexGuard<'static, Option<ResetHandler>> {
+    HANDLER.lock()
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn panicking_reset() -> ! {
+        panic!("reset requested");
+    }
+
+    #[test]
+    fn on_reset_runs_the_registered_handler_until_cleared() {
+        set_reset_handler(panicking_reset);
+        let reset = std::panic::catch_unwind(on_reset);
+        assert!(reset.is_err());
+
+        clear_reset_handler();
+        assert!(reset_handler().is_none());
+        on_reset();
+    }
+}
@@ -0,0 +1,404 @@
+//! Fixed-size trace buffer for targets without a heap-backed transport.
+//!
+//! On a microcontroller the record path must not wait for a UART or debug
+//! probe. [`TraceRing`] is the QS buffer of QP/C (`QS_initBuf`): the tracer
+//! appends complete frames to a statically allocated byte ring, and the
+//! application moves bytes out to the transport when it has time — from the
+//! idle callback, a low-priority timer hook, or a DMA-complete interrupt —
+//! with [`TraceRing::drain`], or bounded per call with
+//! [`IdleDrain`](crate::drain::IdleDrain).
+//!
+//! A ring never stores part of a frame, so the stream stays frame-aligned
+//! whatever happens when it fills up. What gives way is set by its
+//! [`OverflowPolicy`]: by default the new frame is dropped and counted in
+//! [`TraceRing::dropped`]; a ring built with
+//! [`OverflowPolicy::OverwriteOldest`] instead discards the oldest whole
+//! frames, counted in [`TraceRing::overwritten`]. Both counts also add up in
+//! [`crate::stats()`], and a [`Tracer`](crate::Tracer) writing to the ring
+//! reports them to QSPY in a `QS_OVERFLOW` record. QSPY sees the gap either
+//! way as a jump in the sequence number.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use spin::Mutex;
+
+use crate::hdlc::FLAG;
+use crate::stats::{self, QsStats};
+use crate::{TraceBackend, TraceError};
+
+/// What a full [`TraceRing`] gives up to a frame that does not fit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the new frame whole, as `QS_initBuf` does. The oldest records
+    /// survive: the right choice when the lead-up to a fault matters.
+    #[default]
+    DropNewest,
+    /// Discard the oldest complete frames until the new one fits ("last is
+    /// best"). A frame the transport has partly taken is kept whole. When
+    /// the drain is running, or no amount of discarding makes room, the new
+    /// frame is dropped instead.
+    OverwriteOldest,
+}
+
+/// A byte ring of `N` bytes holding encoded QS frames.
+///
+/// Any number of producers may write (they are serialised internally); one
+/// consumer drains at a time. `N` must be a power of two, so byte positions
+/// stay continuous when the free-running counters wrap.
+pub struct TraceRing<const N: usize> {
+    buf: UnsafeCell<[u8; N]>,
+    /// Total bytes ever written; the write position is `head % N`.
+    head: AtomicUsize,
+    /// Total bytes ever drained.
+    tail: AtomicUsize,
+    policy: OverflowPolicy,
+    dropped: AtomicU32,
+    overwritten: AtomicU32,
+    producer: Mutex<()>,
+    /// `true` while the transport has taken part of the frame at `tail`.
+    consumer: Mutex<bool>,
+}
+
+// SAFETY: bytes in `head..tail + N` are only written by the producer holding
+// `producer`, bytes in `tail..head` only read by the consumer holding
+// `consumer`, and the two ranges are published through `head` and `tail`
+// with release/acquire ordering. The producer only moves `tail`, or touches
+// bytes in `tail..head`, while it also holds `consumer`.
+unsafe impl<const N: usize> Sync for TraceRing<N> {}
+
+impl<const N: usize> Default for TraceRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> TraceRing<N> {
+    /// An empty ring that drops new frames when full, usable as a `static`.
+    pub const fn new() -> Self {
+        Self::with_policy(OverflowPolicy::DropNewest)
+    }
+
+    /// An empty ring that handles overflow by `policy`.
+    pub const fn with_policy(policy: OverflowPolicy) -> Self {
+        const { assert!(N.is_power_of_two(), "TraceRing capacity must be a power of two") };
+        Self {
+            buf: UnsafeCell::new([0; N]),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            policy,
+            dropped: AtomicU32::new(0),
+            overwritten: AtomicU32::new(0),
+            producer: Mutex::new(()),
+            consumer: Mutex::new(false),
+        }
+    }
+
+    /// Capacity in bytes.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub const fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Bytes waiting to be drained.
+    pub fn len(&self) -> usize {
+        self.head.load(Ordering::Acquire).wrapping_sub(self.tail.load(Ordering::Acquire))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// New frames dropped because the ring was full.
+    pub fn dropped(&self) -> u32 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Old frames discarded to make room under
+    /// [`OverflowPolicy::OverwriteOldest`].
+    pub fn overwritten(&self) -> u32 {
+        self.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Both loss counts of this ring.
+    pub fn stats(&self) -> QsStats {
+        QsStats { dropped: self.dropped(), overwritten: self.overwritten() }
+    }
+
+    /// Appends `frame` whole, making room as the policy allows. Returns
+    /// `false` if the frame was dropped.
+    pub fn push_frame(&self, frame: &[u8]) -> bool {
+        let _guard = self.producer.lock();
+        let head = self.head.load(Ordering::Relaxed);
+        let free = N - head.wrapping_sub(self.tail.load(Ordering::Acquire));
+        let fits = frame.len() <= free
+            || (self.policy == OverflowPolicy::OverwriteOldest && self.make_room(head, frame.len()));
+        if !fits {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            stats::count_dropped();
+            return false;
+        }
+        let start = head % N;
+        let first = frame.len().min(N - start);
+        let buf = self.buf.get() as *mut u8;
+        // SAFETY: both copies land in the free region, which the consumer
+        // does not read until `head` is published below.
+        unsafe {
+            core::ptr::copy_nonoverlapping(frame.as_ptr(), buf.add(start), first);
+            core::ptr::copy_nonoverlapping(frame.as_ptr().add(first), buf, frame.len() - first);
+        }
+        self.head.store(head.wrapping_add(frame.len()), Ordering::Release);
+        true
+    }
+
+    /// Discards the oldest whole frames until `need` bytes are free. Called
+    /// by the producer; gives up if the consumer is busy or the frames in
+    /// the ring cannot free enough.
+    fn make_room(&self, head: usize, need: usize) -> bool {
+        let Some(mid_frame) = self.consumer.try_lock() else { return false };
+        let tail = self.tail.load(Ordering::Relaxed);
+        let free = N - head.wrapping_sub(tail);
+        // The rest of a partly sent frame stays; the whole frames behind it go.
+        let keep = match *mid_frame {
+            true => match self.frame_end(tail, head) {
+                Some(end) => end.wrapping_sub(tail),
+                None => return false,
+            },
+            false => 0,
+        };
+        let first = tail.wrapping_add(keep);
+        let mut cut = first;
+        let mut frames = 0;
+        while free + cut.wrapping_sub(first) < need {
+            match self.frame_end(cut, head) {
+                Some(end) => cut = end,
+                None => return false,
+            }
+            frames += 1;
+        }
+        let buf = self.buf.get() as *mut u8;
+        for i in (0..keep).rev() {
+            // SAFETY: holding both locks; the partial frame moves up, last
+            // byte first, over the discarded frames it directly precedes.
+            unsafe {
+                *buf.add(cut.wrapping_sub(keep).wrapping_add(i) % N) = *buf.add(tail.wrapping_add(i) % N);
+            }
+        }
+        self.tail.store(cut.wrapping_sub(keep), Ordering::Release);
+        self.overwritten.fetch_add(frames, Ordering::Relaxed);
+        stats::count_overwritten(frames);
+        true
+    }
+
+    /// Position just past the flag that ends the frame at `from`, if that
+    /// frame is complete before `head`.
+    fn frame_end(&self, from: usize, head: usize) -> Option<usize> {
+        let buf = self.buf.get() as *const u8;
+        let mut pos = from;
+        while pos != head {
+            // SAFETY: `from..head` is buffered data; the caller holds both
+            // locks, so no one else touches it.
+            let byte = unsafe { *buf.add(pos % N) };
+            pos = pos.wrapping_add(1);
+            if byte == FLAG {
+                return Some(pos);
+            }
+        }
+        None
+    }
+
+    /// Hands the buffered bytes to `sink` in at most two contiguous slices,
+    /// oldest first. `sink` returns how many bytes it took; draining stops
+    /// early when it takes fewer than offered, and the rest stays buffered
+    /// for the next call. Returns the number of bytes drained.
+    pub fn drain(&self, mut sink: impl FnMut(&[u8]) -> usize) -> usize {
+        let mut mid_frame = self.consumer.lock();
+        let head = self.head.load(Ordering::Acquire);
+        let mut tail = self.tail.load(Ordering::Relaxed);
+        let mut drained = 0;
+        while tail != head {
+            let start = tail % N;
+            let len = head.wrapping_sub(tail).min(N - start);
+            // SAFETY: `tail..head` was published by the producer and is not
+            // overwritten until `tail` moves past it.
+            let chunk = unsafe { core::slice::from_raw_parts((self.buf.get() as *const u8).add(start), len) };
+            let taken = sink(chunk).min(len);
+            if taken > 0 {
+                *mid_frame = chunk[taken - 1] != FLAG;
+            }
+            tail = tail.wrapping_add(taken);
+            drained += taken;
+            self.tail.store(tail, Ordering::Release);
+            if taken < len {
+                break;
+            }
+        }
+        drained
+    }
+
+    /// Hands `sink` the oldest buffered bytes, at most `max` of them and in
+    /// one contiguous slice, like `QS_getBlock()`. `sink` returns how many
+    /// it took; the rest stay buffered. Returns that count.
+    pub fn get_block(&self, max: usize, sink: impl FnOnce(&[u8]) -> usize) -> usize {
+        let mut mid_frame = self.consumer.lock();
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Relaxed);
+        let start = tail % N;
+        let len = head.wrapping_sub(tail).min(N - start).min(max);
+        if len == 0 {
+            return 0;
+        }
+        // SAFETY: as in `drain`.
+        let block = unsafe { core::slice::from_raw_parts((self.buf.get() as *const u8).add(start), len) };
+        let taken = sink(block).min(len);
+        if taken > 0 {
+            *mid_frame = block[taken - 1] != FLAG;
+        }
+        self.tail.store(tail.wrapping_add(taken), Ordering::Release);
+        taken
+    }
+}
+
+impl<const N: usize> TraceBackend for TraceRing<N> {
+    /// Never fails: a full ring counts what it lost instead of stalling the
+    /// caller.
+    fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
+        self.push_frame(frame);
+        Ok(())
+    }
+
+    fn stats(&self) -> QsStats {
+        TraceRing::stats(self)
+    }
+}
+
+impl<const N: usize> TraceBackend for &'static TraceRing<N> {
+    fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
+        self.push_frame(frame);
+        Ok(())
+    }
+
+    fn stats(&self) -> QsStats {
+        TraceRing::stats(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain_all<const N: usize>(ring: &TraceRing<N>) -> Vec<u8> {
+        let mut out = Vec::new();
+        ring.drain(|chunk| {
+            out.extend_from_slice(chunk);
+            chunk.len()
+        });
+        out
+    }
+
+    #[test]
+    fn frames_wrap_around_the_end() {
+        let ring = TraceRing::<8>::new();
+        assert!(ring.push_frame(&[1, 2, 3, 4, 5]));
+        assert_eq!(drain_all(&ring), [1, 2, 3, 4, 5]);
+        assert!(ring.push_frame(&[6, 7, 8, 9, 10, 11]));
+        assert_eq!(ring.len(), 6);
+        assert_eq!(drain_all(&ring), [6, 7, 8, 9, 10, 11]);
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn full_ring_drops_whole_frames() {
+        let ring = TraceRing::<8>::new();
+        assert!(ring.push_frame(&[1; 6]));
+        assert!(!ring.push_frame(&[2; 3]));
+        assert!(ring.push_frame(&[3; 2]));
+        assert_eq!(ring.dropped(), 1);
+        assert_eq!(drain_all(&ring), [1, 1, 1, 1, 1, 1, 3, 3]);
+    }
+
+    #[test]
+    fn get_block_stops_at_the_wrap_and_the_limit() {
+        let ring = TraceRing::<8>::new();
+        ring.push_frame(&[1, 2, 3, 4, 5, 6]);
+        ring.drain(|chunk| chunk.len().min(5));
+        ring.push_frame(&[7, 8, 9]);
+        let mut blocks = Vec::new();
+        while ring.get_block(3, |block| {
+            blocks.push(block.to_vec());
+            block.len()
+        }) > 0 {}
+        assert_eq!(blocks, [vec![6, 7, 8], vec![9]]);
+    }
+
+    #[test]
+    fn overwrite_discards_the_oldest_whole_frames() {
+        let ring = TraceRing::<16>::with_policy(OverflowPolicy::OverwriteOldest);
+        for b in 1..=4 {
+            assert!(ring.push_frame(&[b, b, b, FLAG]));
+        }
+        assert!(ring.push_frame(&[5, 5, 5, 5, FLAG]));
+        assert_eq!((ring.dropped(), ring.overwritten()), (0, 2));
+        assert_eq!(drain_all(&ring), [3, 3, 3, FLAG, 4, 4, 4, FLAG, 5, 5, 5, 5, FLAG]);
+    }
+
+    #[test]
+    fn overwrite_keeps_a_partly_sent_frame_whole() {
+        let ring = TraceRing::<16>::with_policy(OverflowPolicy::OverwriteOldest);
+        ring.push_frame(&[1, 2, 3, FLAG]);
+        ring.push_frame(&[4, 4, 4, FLAG]);
+        ring.push_frame(&[5, 5, 5, FLAG]);
+        ring.push_frame(&[6, 6, 6, FLAG]);
+        assert_eq!(ring.get_block(2, |block| block.len()), 2);
+
+        assert!(ring.push_frame(&[7, 7, 7, 7, FLAG]));
+        assert_eq!(ring.overwritten(), 1);
+        assert_eq!(drain_all(&ring), [3, FLAG, 5, 5, 5, FLAG, 6, 6, 6, FLAG, 7, 7, 7, 7, FLAG]);
+    }
+
+    #[test]
+    fn overwrite_drops_the_new_frame_while_draining_or_if_too_big() {
+        let ring = TraceRing::<8>::with_policy(OverflowPolicy::OverwriteOldest);
+        ring.push_frame(&[1, 1, 1, FLAG]);
+        ring.push_frame(&[2, 2, 2, FLAG]);
+        assert!(!ring.push_frame(&[0; 9]));
+        ring.drain(|_| {
+            assert!(!ring.push_frame(&[3, FLAG]));
+            0
+        });
+        assert_eq!((ring.dropped(), ring.overwritten()), (2, 0));
+        assert_eq!(drain_all(&ring), [1, 1, 1, FLAG, 2, 2, 2, FLAG]);
+    }
+
+    #[test]
+    fn losses_add_up_globally_and_reach_the_stream() {
+        let before = crate::stats();
+        let ring: &'static TraceRing<32> = Box::leak(Box::new(TraceRing::new()));
+        let mut filter = crate::GlbFilter::deny_all();
+        filter.allow(100);
+        let mut tracer = crate::Tracer::new(crate::QsConfig::default(), ring);
+        tracer.set_filter(filter);
+        while ring.dropped() == 0 {
+            tracer.record(100, &[0; 6], false).unwrap();
+        }
+        let after = crate::stats();
+        assert!(after.dropped.wrapping_sub(before.dropped) >= 1);
+
+        drain_all(ring);
+        tracer.record(100, &[0; 6], false).unwrap();
+        let bytes = drain_all(ring);
+        let records: Vec<u8> = bytes.split(|&b| b == FLAG).filter(|f| !f.is_empty()).map(|f| f[1]).collect();
+        assert_eq!(records, [crate::records::infra::OVERFLOW, 100]);
+    }
+
+    #[test]
+    fn partial_drain_keeps_the_rest() {
+        let ring = TraceRing::<16>::new();
+        ring.push_frame(&[1, 2, 3, 4]);
+        assert_eq!(ring.drain(|chunk| chunk.len().min(3)), 3);
+        assert_eq!(drain_all(&ring), [4]);
+    }
+}
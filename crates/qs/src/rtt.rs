@@ -0,0 +1,250 @@
+//! SEGGER Real-Time Transfer (RTT) up-channel for QS output.
+//!
+//! RTT moves data through a ring buffer in target RAM that a debug probe
+//! reads in the background over SWD/JTAG, at several MB/s and without
+//! stopping the core — far more trace bandwidth than a UART. The probe finds
+//! the buffer through a control block whose first bytes are the string
+//! `"SEGGER RTT"`; with the `rtt` feature this crate exports that block as
+//! the `_SEGGER_RTT` symbol, and [`init_rtt`] sets up up-channel 0 over a
+//! caller-provided buffer.
+//!
+//! Records are not written to RTT directly. The tracer fills a
+//! [`TraceRing`](crate::ring::TraceRing), and [`RttChannel::pump`] moves
+//! whatever the probe has room for into the RTT channel, typically from the
+//! idle callback:
+//!
+//! ```ignore
+//! static QS_BUF: TraceRing<2048> = TraceRing::new();
+//! static RTT: OnceCell<RttChannel> = OnceCell::new();
+//!
+//! let rtt = init_rtt(singleton!(: [u8; 1024] = [0; 1024]).unwrap()).unwrap();
+//! RTT.set(rtt).ok();
+//! let tracer = Tracer::new(QsConfig::default(), &QS_BUF).into_handle();
+//!
+//! fn on_idle() {
+//!     if let Some(rtt) = RTT.get() { rtt.pump(&QS_BUF); }
+//! }
+//! ```
+//!
+//! On the host, `qspy --rtt` reads the channel from probe-rs or any tool
+//! that dumps the raw channel bytes to a file.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::ring::TraceRing;
+
+/// `SEGGER_RTT_MODE_NO_BLOCK_TRIM`: write what fits, never block.
+const MODE_NO_BLOCK_TRIM: u32 = 1;
+
+/// Name of up-channel 0 as shown by RTT viewers.
+const CHANNEL_NAME: &[u8] = b"QS\0";
+
+/// One up-buffer descriptor, laid out as `SEGGER_RTT_BUFFER_UP`.
+#[repr(C)]
+struct RttUpBuffer {
+    name: UnsafeCell<*const u8>,
+    buffer: UnsafeCell<*mut u8>,
+    size: UnsafeCell<u32>,
+    /// Next byte the target writes; only the target moves it.
+    write: AtomicU32,
+    /// Next byte the probe reads; only the probe moves it.
+    read: AtomicU32,
+    flags: UnsafeCell<u32>,
+}
+
+/// The RTT control block (`SEGGER_RTT_CB`) with one up-channel and no
+/// down-channels.
+#[repr(C)]
+pub struct RttControlBlock {
+    id: UnsafeCell<[u8; 16]>,
+    max_up: UnsafeCell<i32>,
+    max_down: UnsafeCell<i32>,
+    up: RttUpBuffer,
+    initialised: AtomicBool,
+}
+
+// SAFETY: the descriptor fields are written once by `init` before the id is
+// published; afterwards the target only touches `write` and the free part of
+// the buffer, and the probe only `read` and the filled part.
+unsafe impl Sync for RttControlBlock {}
+
+impl Default for RttControlBlock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RttControlBlock {
+    /// An uninitialised block: invisible to the probe until [`init`](Self::init).
+    pub const fn new() -> Self {
+        Self {
+            id: UnsafeCell::new([0; 16]),
+            max_up: UnsafeCell::new(1),
+            max_down: UnsafeCell::new(0),
+            up: RttUpBuffer {
+                name: UnsafeCell::new(core::ptr::null()),
+                buffer: UnsafeCell::new(core::ptr::null_mut()),
+                size: UnsafeCell::new(0),
+                write: AtomicU32::new(0),
+                read: AtomicU32::new(0),
+                flags: UnsafeCell::new(MODE_NO_BLOCK_TRIM),
+            },
+            initialised: AtomicBool::new(false),
+        }
+    }
+
+    /// Points up-channel 0 at `buffer` (at least two bytes) and publishes
+    /// the block to the probe. Returns `None` if the block was already
+    /// initialised.
+    pub fn init(&'static self, buffer: &'static mut [u8]) -> Option<RttChannel> {
+        assert!(buffer.len() >= 2, "RTT buffer too small");
+        if self.initialised.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        // SAFETY: `initialised` guarantees a single writer, and the probe
+        // ignores the block until the id below is in place.
+        unsafe {
+            *self.up.name.get() = CHANNEL_NAME.as_ptr();
+            *self.up.buffer.get() = buffer.as_mut_ptr();
+            *self.up.size.get() = buffer.len() as u32;
+            *self.up.flags.get() = MODE_NO_BLOCK_TRIM;
+        }
+        self.up.write.store(0, Ordering::Relaxed);
+        self.up.read.store(0, Ordering::Relaxed);
+        core::sync::atomic::fence(Ordering::SeqCst);
+        // Assembled at run time, and the leading 'S' last, so the only
+        // complete "SEGGER RTT" in memory is this one once it is valid.
+        let id = self.id.get() as *mut u8;
+        for (i, &b) in b"SEGGER RTT".iter().enumerate().skip(1) {
+            // SAFETY: `id` points at 16 bytes owned by this block.
+            unsafe { core::ptr::write_volatile(id.add(i), b) };
+        }
+        core::sync::atomic::fence(Ordering::SeqCst);
+        // SAFETY: as above.
+        unsafe { core::ptr::write_volatile(id, b'S') };
+        Some(RttChannel { block: self })
+    }
+}
+
+/// Handle to the initialised up-channel 0. Writes from more than one
+/// context at a time must be serialised by the caller; [`pump`](Self::pump)
+/// is, through the ring's consumer lock.
+pub struct RttChannel {
+    block: &'static RttControlBlock,
+}
+
+impl RttChannel {
+    fn size(&self) -> u32 {
+        // SAFETY: fixed by `init` before this handle existed.
+        unsafe { *self.block.up.size.get() }
+    }
+
+    /// Bytes that can be written before catching up with the probe.
+    pub fn free(&self) -> usize {
+        let size = self.size();
+        let write = self.block.up.write.load(Ordering::Relaxed);
+        let read = self.block.up.read.load(Ordering::Acquire);
+        // One slot stays empty so that `write == read` means empty.
+        ((read + size - write - 1) % size) as usize
+    }
+
+    /// Copies as much of `bytes` as fits without overtaking the probe and
+    /// returns how many bytes were written. Never blocks.
+    pub fn write(&self, bytes: &[u8]) -> usize {
+        let size = self.size();
+        let n = bytes.len().min(self.free());
+        let write = self.block.up.write.load(Ordering::Relaxed);
+        let first = n.min((size - write) as usize);
+        // SAFETY: `buffer` is `size` bytes and `write..write + n` (mod size)
+        // is free, so the probe is not reading it.
+        unsafe {
+            let buf = *self.block.up.buffer.get();
+            core::ptr::copy_nonoverlapping(bytes.as_ptr(), buf.add(write as usize), first);
+            core::ptr::copy_nonoverlapping(bytes.as_ptr().add(first), buf, n - first);
+        }
+        self.block.up.write.store((write + n as u32) % size, Ordering::Release);
+        n
+    }
+
+    /// Moves buffered trace bytes from `ring` into the channel, as many as
+    /// the probe has room for; the rest stay in `ring`. Returns the number
+    /// of bytes moved.
+    pub fn pump<const N: usize>(&self, ring: &TraceRing<N>) -> usize {
+        ring.drain(|chunk| self.write(chunk))
+    }
+}
+
+/// The control block debug probes look for.
+#[cfg(feature = "rtt")]
+#[no_mangle]
+pub static _SEGGER_RTT: RttControlBlock = RttControlBlock::new();
+
+/// Initialises `_SEGGER_RTT` with `buffer` as the QS up-channel. Returns
+/// `None` on a second call.
+#[cfg(feature = "rtt")]
+pub fn init_rtt(buffer: &'static mut [u8]) -> Option<RttChannel> {
+    _SEGGER_RTT.init(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leak_block(size: usize) -> (&'static RttControlBlock, RttChannel) {
+        let block: &'static RttControlBlock = Box::leak(Box::new(RttControlBlock::new()));
+        let buffer = Box::leak(vec![0u8; size].into_boxed_slice());
+        let channel = block.init(buffer).unwrap();
+        (block, channel)
+    }
+
+    /// What a probe does: read from `read` up to `write`, then advance `read`.
+    fn probe_read(block: &RttControlBlock) -> Vec<u8> {
+        let up = &block.up;
+        let size = unsafe { *up.size.get() };
+        let buf = unsafe { *up.buffer.get() };
+        let mut read = up.read.load(Ordering::Relaxed);
+        let write = up.write.load(Ordering::Acquire);
+        let mut out = Vec::new();
+        while read != write {
+            out.push(unsafe { *buf.add(read as usize) });
+            read = (read + 1) % size;
+        }
+        up.read.store(read, Ordering::Release);
+        out
+    }
+
+    #[test]
+    fn init_publishes_the_control_block_once() {
+        let (block, _) = leak_block(16);
+        let id = unsafe { &*block.id.get() };
+        assert_eq!(&id[..10], b"SEGGER RTT");
+        assert_eq!(unsafe { *block.max_up.get() }, 1);
+        let again = Box::leak(vec![0u8; 4].into_boxed_slice());
+        assert!(block.init(again).is_none());
+    }
+
+    #[test]
+    fn writes_trim_to_free_space_and_wrap() {
+        let (block, rtt) = leak_block(8);
+        assert_eq!(rtt.free(), 7);
+        assert_eq!(rtt.write(&[1, 2, 3, 4, 5, 6, 7, 8, 9]), 7);
+        assert_eq!(rtt.write(&[10]), 0);
+        assert_eq!(probe_read(block), [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(rtt.write(&[8, 9, 10]), 3);
+        assert_eq!(probe_read(block), [8, 9, 10]);
+    }
+
+    #[test]
+    fn pump_keeps_what_the_probe_has_no_room_for() {
+        let (block, rtt) = leak_block(8);
+        let ring = TraceRing::<32>::new();
+        ring.push_frame(&[0xAA; 10]);
+        assert_eq!(rtt.pump(&ring), 7);
+        assert_eq!(ring.len(), 3);
+        assert_eq!(probe_read(block).len(), 7);
+        assert_eq!(rtt.pump(&ring), 3);
+        assert!(ring.is_empty());
+        assert_eq!(probe_read(block), [0xAA; 3]);
+    }
+}
@@ -7,7 +7,7 @@
 //! Frame format (mirrors QS-TX direction):
 //!   `FLAG(0x7E) | SEQ | CMD_TYPE | [PAYLOAD…] | CHECKSUM | FLAG`
 //!
-//! Byte stuffing: `0x7E` and `0x7D` are escaped as `0x7D, byte ^ 0x20`.
+//! Byte stuffing and the checksum are those of [`hdlc`](crate::hdlc).
 //!
 //! Command IDs match the `QS_RX*` enum in QP/C++ and the companion QSpy tool
 //! in `tools/qspy/src/commands.rs`.
@@ -18,6 +18,8 @@ extern crate alloc;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
+use crate::hdlc::{self, Unstuffed, Unstuffer};
+
 /// Strongly-typed commands decoded from QS-RX frames.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RxCmd {
@@ -183,63 +185,50 @@ pub mod cmd {
 /// Feed bytes one at a time with [`push`].  Complete, checksum-verified frames
 /// are returned as `Some(RxCmd)`.
 pub struct RxParser {
-    state:    RxState,
-    buf:      Vec<u8>,
-    checksum: u8,
+    state:     RxState,
+    unstuffer: Unstuffer,
+    buf:       Vec<u8>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum RxState {
     Idle,
     InFrame,
-    Escaped,
 }
 
-const FLAG:    u8 = 0x7E;
-const ESC:     u8 = 0x7D;
-const ESC_XOR: u8 = 0x20;
-
 impl RxParser {
     /// Creates a parser in the idle state, ready to receive frames.
     pub fn new() -> Self {
         Self {
-            state:    RxState::Idle,
-            buf:      Vec::with_capacity(32),
-            checksum: 0,
+            state:     RxState::Idle,
+            unstuffer: Unstuffer::new(),
+            buf:       Vec::with_capacity(32),
         }
     }
 
     /// Feed one byte.  Returns a decoded command if the byte completed a valid frame.
     pub fn push(&mut self, byte: u8) -> Option<RxCmd> {
-        match self.state {
-            RxState::Idle => {
-                if byte == FLAG {
-                    self.buf.clear();
-                    self.checksum = 0;
-                    self.state = RxState::InFrame;
-                }
+        match (self.state, self.unstuffer.push(byte)) {
+            (RxState::Idle, Unstuffed::End) => {
+                self.buf.clear();
+                self.state = RxState::InFrame;
                 None
             }
-            RxState::InFrame => {
-                if byte == FLAG {
-                    let result = self.try_decode();
-                    self.buf.clear();
-                    self.checksum = 0;
-                    self.state = RxState::Idle;
-                    result
-                } else if byte == ESC {
-                    self.state = RxState::Escaped;
-                    None
-                } else {
-                    self.accept(byte);
-                    None
-                }
+            (RxState::Idle, _) => {
+                self.unstuffer.reset();
+                None
             }
-            RxState::Escaped => {
-                self.state = RxState::InFrame;
-                self.accept(byte ^ ESC_XOR);
+            (RxState::InFrame, Unstuffed::End) => {
+                let result = self.try_decode();
+                self.buf.clear();
+                self.state = RxState::Idle;
+                result
+            }
+            (RxState::InFrame, Unstuffed::Byte(b)) => {
+                self.buf.push(b);
                 None
             }
+            (RxState::InFrame, Unstuffed::Escape) => None,
         }
     }
 
@@ -248,19 +237,12 @@ impl RxParser {
         bytes.iter().filter_map(|&b| self.push(b)).collect()
     }
 
-    fn accept(&mut self, byte: u8) {
-        self.checksum = self.checksum.wrapping_add(byte);
-        self.buf.push(byte);
-    }
-
     fn try_decode(&mut self) -> Option<RxCmd> {
         // Minimum frame: SEQ(1) + CMD(1) + CHECKSUM(1) = 3 bytes
         if self.buf.len() < 3 {
             return None;
         }
-        // QP/C++ convention: sum of all bytes including checksum complement == 0xFF
-        let total: u8 = self.buf.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
-        if total != 0xFF {
+        if !hdlc::checksum_ok(&self.buf) {
             return None;
         }
         // buf = [seq, cmd_type, payload..., checksum_complement]
@@ -286,10 +268,14 @@ impl RxParser {
             cmd::QUERY_CURR if !payload.is_empty() =>
                 RxCmd::QueryCurr { kind: payload[0] },
 
+            // GLB_FILTER: [len: 1 = 16] [bits: 16], as QSPY sends it; a bare
+            // 16-byte mask is accepted too.
             cmd::GLB_FILTER if payload.len() >= 16 => {
-                let mut bits = [0u8; 16];
-                bits.copy_from_slice(&payload[..16]);
-                RxCmd::GlbFilter { bits }
+                let bits = match payload {
+                    [16, bits @ ..] if bits.len() >= 16 => &bits[..16],
+                    _ => &payload[..16],
+                };
+                RxCmd::GlbFilter { bits: bits.try_into().unwrap() }
             }
 
             // LOC_FILTER / CURR_OBJ: [kind: 1] [obj_ptr: 8 LE]
@@ -375,23 +361,12 @@ mod tests {
     use super::*;
 
     fn encode_frame(seq: u8, cmd: u8, payload: &[u8]) -> Vec<u8> {
-        let mut raw: Vec<u8> = Vec::new();
-        raw.push(seq);
-        raw.push(cmd);
-        raw.extend_from_slice(payload);
-        let sum: u8 = raw.iter().fold(0u8, |a, &b| a.wrapping_add(b));
-        raw.push(!sum);
-
-        let mut frame = vec![FLAG];
-        for &byte in &raw {
-            if byte == FLAG || byte == ESC {
-                frame.push(ESC);
-                frame.push(byte ^ ESC_XOR);
-            } else {
-                frame.push(byte);
-            }
-        }
-        frame.push(FLAG);
+        let mut frame = vec![hdlc::FLAG];
+        let mut encoder = hdlc::FrameEncoder::new(|b| frame.push(b));
+        encoder.push(seq);
+        encoder.push(cmd);
+        encoder.extend(payload);
+        encoder.finish();
         frame
     }
 
@@ -431,6 +406,16 @@ mod tests {
         assert_eq!(cmds, vec![RxCmd::GlbFilter { bits }]);
     }
 
+    #[test]
+    fn decode_length_prefixed_glb_filter() {
+        let mut bits = [0u8; 16];
+        bits[0] = 0x0F;
+        let mut payload = vec![16];
+        payload.extend_from_slice(&bits);
+        let frame = encode_frame(6, cmd::GLB_FILTER, &payload);
+        assert_eq!(RxParser::new().push_slice(&frame), vec![RxCmd::GlbFilter { bits }]);
+    }
+
     #[test]
     fn decode_test_setup() {
         let frame = encode_frame(1, cmd::TEST_SETUP, &[]);
@@ -0,0 +1,198 @@
+//! Trace self-test: one of every predefined record, with known values.
+//!
+//! [`qs_selftest`] sends `QS_TARGET_INFO`, one dictionary entry of each kind,
+//! every predefined record that has a fixed layout, and finally
+//! `QS_DICT_HASH`. Every object field carries [`OBJ`], every function
+//! [`FUN`], every signal [`SIG`] and every other number [`NUM`], cut to the
+//! field's width as usual. `qspy -f capture.qs --verify-selftest` then checks
+//! that each record arrived and decodes to exactly those values: a quick
+//! interoperability check after changing the protocol on either side.
+//!
+//! Call it once after start-up with every record enabled in the global
+//! filter. The suite takes a few hundred bytes of trace buffer.
+
+use alloc::vec::Vec;
+
+use qs_protocol::records::{dict, FIRST_USER};
+
+use crate::records::{infra, qep, qf, qf::time_evt, qxk, sched, Field, Predefined, RecordSizes};
+use crate::{TargetInfo, TraceBackend, TraceError, TracerHandle};
+
+/// Object pointer in every object field, and the `QS_OBJ_DICT` entry.
+pub const OBJ: u64 = 0x2000_1A5C;
+/// Function pointer in every function field, and the `QS_FUN_DICT` entry.
+pub const FUN: u64 = 0x0800_2C3D;
+/// Signal in every signal field, and the `QS_SIG_DICT` entry. Fits one byte.
+pub const SIG: u16 = 0x42;
+/// Every other numeric field.
+pub const NUM: u8 = 0x5A;
+/// User record named by the `QS_USR_DICT` entry.
+pub const USR: u8 = FIRST_USER;
+/// Names in the dictionary entries.
+pub const OBJ_NAME: &str = "selftest_obj";
+pub const FUN_NAME: &str = "selftest_fun";
+pub const SIG_NAME: &str = "SELFTEST_SIG";
+pub const USR_NAME: &str = "SELFTEST_USR";
+/// `QS_ENUM_DICT` entry: value [`NUM`] of enum group 0.
+pub const ENUM_NAME: &str = "SELFTEST_ENUM";
+
+/// Every predefined record with a fixed layout, in id order, filled with
+/// the known values.
+pub fn suite() -> Vec<Predefined> {
+    use Field::*;
+    let (o, f, s, n) = (Obj(OBJ), Fun(FUN), Sig(SIG), U8(NUM));
+    let (n16, n32) = (U16(u16::from(NUM)), U32(u32::from(NUM)));
+    let (eq, mp, te) = (EqCtr(u32::from(NUM)), MpCtr(u32::from(NUM)), TeCtr(u32::from(NUM)));
+    let timed = |record: u8, fields: &[Field]| Predefined::new(record, true, fields);
+    let untimed = |record: u8, fields: &[Field]| Predefined::new(record, false, fields);
+
+    let mut suite = Vec::new();
+    suite.extend([
+        untimed(qep::STATE_ENTRY, &[o, f]),
+        untimed(qep::STATE_EXIT, &[o, f]),
+        untimed(qep::STATE_INIT, &[o, f, f]),
+        timed(qep::INIT_TRAN, &[o, f]),
+        timed(qep::INTERN_TRAN, &[s, o, f]),
+        timed(qep::TRAN, &[s, o, f, f]),
+        timed(qep::IGNORED, &[s, o, f]),
+        timed(qep::DISPATCH, &[s, o, f]),
+        untimed(qep::UNHANDLED, &[s, o, f]),
+    ]);
+    for record in [qf::ACTIVE_DEFER, qf::ACTIVE_RECALL] {
+        suite.push(timed(record, &[o, o, s, n, n]));
+    }
+    suite.push(timed(qf::ACTIVE_SUBSCRIBE, &[s, o]));
+    suite.push(timed(qf::ACTIVE_UNSUBSCRIBE, &[s, o]));
+    for record in [qf::ACTIVE_POST, qf::ACTIVE_POST_LIFO] {
+        suite.push(timed(record, &[s, o, o, n, n, eq, eq]));
+    }
+    suite.extend([
+        timed(qf::ACTIVE_GET, &[s, o, n, n, eq]),
+        timed(qf::ACTIVE_GET_LAST, &[s, o, n, n]),
+        timed(qf::ACTIVE_RECALL_ATTEMPT, &[o, o]),
+        timed(qf::EQUEUE_INIT, &[o, eq]),
+        timed(qf::EQUEUE_POST, &[s, o, n, n, eq, eq]),
+        timed(qf::EQUEUE_POST_LIFO, &[s, o, n, n, eq, eq]),
+        timed(qf::EQUEUE_GET, &[s, o, n, n, eq]),
+        timed(qf::MPOOL_INIT, &[o, mp, mp]),
+        timed(qf::MPOOL_GET, &[o, mp, mp]),
+        timed(qf::MPOOL_PUT, &[o, mp]),
+        timed(qf::PUBLISH, &[o, s, n, n]),
+        timed(qf::NEW_REF, &[s, n, n]),
+        // The event size is announced 2 bytes wide below.
+        timed(qf::NEW, &[n16, s]),
+        timed(qf::GC_ATTEMPT, &[s, n, n]),
+        timed(qf::GC, &[s, n, n]),
+        timed(qf::TICK, &[te, n]),
+        timed(time_evt::ARM, &[o, o, te, te, n]),
+        untimed(time_evt::AUTO_DISARM, &[o, o, n]),
+        timed(time_evt::DISARM_ATTEMPT, &[o, o, n]),
+        timed(time_evt::DISARM, &[o, o, te, te, n]),
+        timed(time_evt::REARM, &[o, o, te, te, n]),
+        timed(time_evt::POST, &[o, s, o, n]),
+        timed(qf::DELETE_REF, &[s, n, n]),
+        timed(qf::CRIT_ENTRY, &[n]),
+        timed(qf::CRIT_EXIT, &[n]),
+    ]);
+    for record in [qf::ISR_ENTRY, qf::ISR_EXIT, qf::INT_DISABLE, qf::INT_ENABLE] {
+        suite.push(timed(record, &[n, n]));
+    }
+    suite.extend([
+        timed(qf::ACTIVE_POST_ATTEMPT, &[s, o, o, n, n, eq, eq]),
+        timed(qf::EQUEUE_POST_ATTEMPT, &[s, o, n, n, eq, eq]),
+        timed(qf::MPOOL_GET_ATTEMPT, &[o, mp, mp]),
+        timed(sched::PREEMPT, &[n, n]),
+        timed(sched::RESTORE, &[n, n]),
+        timed(sched::LOCK, &[n, n]),
+        timed(sched::UNLOCK, &[n, n]),
+        timed(sched::NEXT, &[n, n]),
+        timed(sched::IDLE, &[n]),
+        untimed(qep::TRAN_HIST, &[o, f, f]),
+        timed(qf::RUN_BATCH, &[n16, n32]),
+        timed(qep::CONTRACT_VIOLATION, &[f, n32, n32]),
+    ]);
+    for record in qxk::SEM_TAKE..=qxk::SEM_BLOCK_ATTEMPT {
+        suite.push(timed(record, &[o, n, n16]));
+    }
+    for record in qxk::MTX_LOCK..=qxk::MTX_UNLOCK_ATTEMPT {
+        suite.push(timed(record, &[o, n]));
+    }
+    suite.extend([
+        timed(qf::ACTIVE_DEFER_ATTEMPT, &[o, o, s, n, n]),
+        timed(qf::RTC_OVERRUN, &[n, U16(SIG), n32, n32]),
+        timed(qf::AO_SAVE, &[o, n16, n]),
+        timed(qf::AO_RESTORE, &[o, n16, n]),
+        timed(qf::TIMEEVT_JITTER, &[o, o, n16, te, te, te, n16]),
+        timed(qxk::THREAD_STACK, &[n, n32, n32, n]),
+        timed(qf::RTC_STATS, &[n, n32, n32, n32, U16(SIG), n32]),
+        timed(qf::NEW_ATTEMPT, &[n16, n, n16, n16]),
+        timed(infra::OVERFLOW, &[n32, n32]),
+        timed(qep::GUARD, &[s, o, f, f, n]),
+        timed(qf::AO_STALL, &[n, U16(SIG), n16, n16, n32]),
+        timed(qf::METRICS, &[n, n16, n32, n32, n32, n16, n32]),
+        timed(qf::WDT_RESET, &[n32]),
+    ]);
+    suite
+}
+
+/// The `QS_TARGET_INFO` the suite starts with, announcing `sizes` and the
+/// timestamp width `time_size`. It is not a reset, so a viewer keeps the
+/// dictionaries it already has.
+pub fn target_info(sizes: &RecordSizes, time_size: u8) -> TargetInfo {
+    TargetInfo {
+        is_reset: 0,
+        signal_size: sizes.signal,
+        event_size: 2,
+        equeue_ctr_size: sizes.equeue_ctr,
+        time_evt_ctr_size: sizes.time_evt_ctr,
+        mpool_ctr_size: sizes.mpool_ctr,
+        obj_ptr_size: sizes.obj_ptr,
+        fun_ptr_size: sizes.fun_ptr,
+        time_size,
+        ..TargetInfo::default()
+    }
+}
+
+/// Sends the self-test suite through `tracer`.
+pub fn qs_selftest<B: TraceBackend + 'static>(tracer: &TracerHandle<B>) -> Result<(), TraceError> {
+    let (sizes, time_size) = {
+        #[cfg(feature = "std")]
+        let guard = tracer.inner.lock().unwrap();
+        #[cfg(not(feature = "std"))]
+        let guard = tracer.inner.lock();
+        (guard.cfg.sizes, guard.cfg.timestamp_size.bytes())
+    };
+    tracer.emit(dict::TARGET_INFO, &target_info(&sizes, time_size).encode())?;
+
+    let named = |head: &[Field], name: &str| {
+        let mut payload = Predefined::new(0, false, head).encode(&sizes).to_vec();
+        payload.extend_from_slice(name.as_bytes());
+        payload.push(0);
+        payload
+    };
+    tracer.emit(dict::OBJ_DICT, &named(&[Field::Obj(OBJ)], OBJ_NAME))?;
+    tracer.emit(dict::FUN_DICT, &named(&[Field::Fun(FUN)], FUN_NAME))?;
+    tracer.emit(dict::SIG_DICT, &named(&[Field::Sig(SIG), Field::Obj(0)], SIG_NAME))?;
+    tracer.emit(dict::USR_DICT, &named(&[Field::U8(USR)], USR_NAME))?;
+    tracer.emit(dict::ENUM_DICT, &named(&[Field::U8(NUM), Field::U8(0)], ENUM_NAME))?;
+
+    for record in suite() {
+        tracer.emit_predefined(&record)?;
+    }
+    tracer.emit_dict_hash().map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeSet;
+
+    #[test]
+    fn suite_covers_each_record_once_and_fits_a_record() {
+        let suite = suite();
+        let ids: BTreeSet<u8> = suite.iter().map(Predefined::record_type).collect();
+        assert_eq!(ids.len(), suite.len());
+        let widest = RecordSizes { signal: 4, obj_ptr: 8, fun_ptr: 8, equeue_ctr: 4, time_evt_ctr: 4, mpool_ctr: 4 };
+        assert!(suite.iter().all(|record| record.encode(&widest).len() <= 64));
+    }
+}
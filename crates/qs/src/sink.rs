@@ -0,0 +1,86 @@
+//! Registered tracer for records emitted from interrupt-level code.
+//!
+//! Critical-section and ISR records are emitted by free functions that a
+//! port calls with no tracer at hand, so each kind keeps its tracer in a
+//! static [`Sink`]. Emission only ever try-locks, both the sink and the
+//! tracer behind it: a record that would wait is dropped, because the code
+//! holding the lock may be exactly what the interrupt preempted.
+
+use alloc::sync::Arc;
+
+#[cfg(feature = "std")]
+use std::sync::{Mutex, TryLockError};
+#[cfg(not(feature = "std"))]
+use spin::Mutex;
+
+use crate::{TraceBackend, TracerHandle};
+
+type Emit = Arc<dyn Fn(u8, &[u8]) + Send + Sync>;
+
+pub(crate) struct Sink(Mutex<Option<Emit>>);
+
+impl Sink {
+    pub(crate) const fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    /// Emit through `tracer` from now on, replacing any previous one.
+    pub(crate) fn set<B: TraceBackend + 'static>(&self, tracer: &TracerHandle<B>) {
+        let inner = Arc::clone(&tracer.inner);
+        let emit: Emit = Arc::new(move |record, payload| {
+            #[cfg(feature = "std")]
+            let mut guard = match inner.try_lock() {
+                Ok(guard) => guard,
+                Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+                Err(TryLockError::WouldBlock) => return,
+            };
+            #[cfg(not(feature = "std"))]
+            let Some(mut guard) = inner.try_lock() else { return };
+
+            let _ = guard.record(record, payload, true);
+        });
+        *self.lock() = Some(emit);
+    }
+
+    pub(crate) fn clear(&self) {
+        *self.lock() = None;
+    }
+
+    /// Emit a timed `record`, or drop it if no tracer is set or either lock
+    /// is taken.
+    pub(crate) fn emit(&self, record: u8, payload: &[u8]) {
+        // Clone out of the lock so a backend that itself enters a traced
+        // section cannot deadlock on it.
+        let emit = match self.try_lock() {
+            Some(guard) => guard.clone(),
+            None => return,
+        };
+        if let Some(emit) = emit {
+            emit(record, payload);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Emit>> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn lock(&self) -> spin::MutexGuard<'_, Option<Emit>> {
+        self.0.lock()
+    }
+
+    #[cfg(feature = "std")]
+    fn try_lock(&self) -> Option<std::sync::MutexGuard<'_, Option<Emit>>> {
+        match self.0.try_lock() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn try_lock(&self) -> Option<spin::MutexGuard<'_, Option<Emit>>> {
+        self.0.try_lock()
+    }
+}
@@ -0,0 +1,50 @@
+//! Trace-loss counters.
+//!
+//! Every [`TraceRing`](crate::TraceRing) counts what it loses on overflow.
+//! [`stats()`] adds the counts of all rings up, for a health check or a
+//! watchdog to read; a [`Tracer`](crate::Tracer) reports its own backend's
+//! counts in the stream with a `QS_OVERFLOW` record, which no filter
+//! suppresses.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+static DROPPED: AtomicU32 = AtomicU32::new(0);
+static OVERWRITTEN: AtomicU32 = AtomicU32::new(0);
+
+/// Frames lost to a full trace buffer. The counts wrap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QsStats {
+    /// New frames dropped because they did not fit.
+    pub dropped: u32,
+    /// Old frames discarded to make room for new ones.
+    pub overwritten: u32,
+}
+
+impl QsStats {
+    /// Frames lost either way.
+    pub fn overflows(&self) -> u32 {
+        self.dropped.wrapping_add(self.overwritten)
+    }
+
+    /// `QS_OVERFLOW` payload after the timestamp: dropped, then overwritten,
+    /// both `u32` little-endian.
+    pub fn payload(&self) -> [u8; 8] {
+        let mut bytes = [0; 8];
+        bytes[..4].copy_from_slice(&self.dropped.to_le_bytes());
+        bytes[4..].copy_from_slice(&self.overwritten.to_le_bytes());
+        bytes
+    }
+}
+
+/// Frames lost by all trace rings since start-up.
+pub fn stats() -> QsStats {
+    QsStats { dropped: DROPPED.load(Ordering::Relaxed), overwritten: OVERWRITTEN.load(Ordering::Relaxed) }
+}
+
+pub(crate) fn count_dropped() {
+    DROPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn count_overwritten(frames: u32) {
+    OVERWRITTEN.fetch_add(frames, Ordering::Relaxed);
+}
@@ -0,0 +1,204 @@
+//! Stable symbol ids in place of addresses.
+//!
+//! Object and function fields of QS records normally carry addresses. Those
+//! move from run to run under ASLR, and in Rust one generic state handler is
+//! monomorphized into several functions, so an address says little on its
+//! own. In [`SymbolMode::StableIds`] the target numbers each address the
+//! first time a dictionary or record carries it — 1, 2, 3, … — and sends the
+//! number instead. The dictionaries go out at startup in program order and
+//! take the first numbers, so an object keeps its id from one run to the
+//! next, and a host can keep dictionaries it cached.
+//!
+//! ```
+//! use qs::symbols::{self, SymbolMode};
+//!
+//! static TABLE: u8 = 0;
+//! symbols::set_mode(SymbolMode::StableIds);
+//! let dict = qs::qs_dictionary! { obj { qs::dict::addr_of(&TABLE) => "Table::inst" } };
+//! assert_eq!(dict.entries()[0].payload()[..2], [1, 0]);
+//! assert_eq!(symbols::address(1), Some(qs::dict::addr_of(&TABLE)));
+//! ```
+//!
+//! Ids travel in the pointer-sized fields, so the host decodes them as it
+//! would addresses. [`announce`] tells it which it is getting, with a
+//! `QS_SYM_MODE` record that [`Dictionary::emit`](crate::dict::Dictionary::emit)
+//! sends ahead of the entries. An id the host sends back, for instance in
+//! `QS_RX_CURR_OBJ`, is mapped to its address by [`query`](crate::query).
+//!
+//! Every object or function field takes the table's lock in this mode. On a
+//! single-core `no_std` target, an interrupt that emits records while the
+//! interrupted code holds it would spin forever, so keep tracing out of
+//! interrupt handlers there.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(not(feature = "std"))]
+use spin::Mutex;
+
+use crate::predefined::SYM_MODE;
+use crate::{TraceError, TraceHook};
+
+/// What the object and function fields of records carry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymbolMode {
+    /// The addresses themselves, as QP sends them.
+    #[default]
+    Addresses,
+    /// Ids numbered in the order addresses are first sent.
+    StableIds,
+}
+
+impl SymbolMode {
+    /// Payload of the `QS_SYM_MODE` record announcing the mode.
+    pub fn payload(self) -> [u8; 1] {
+        [matches!(self, Self::StableIds) as u8]
+    }
+
+    /// The mode announced by a `QS_SYM_MODE` payload.
+    pub fn from_payload(payload: &[u8]) -> Option<Self> {
+        match payload.first()? {
+            0 => Some(Self::Addresses),
+            1 => Some(Self::StableIds),
+            _ => None,
+        }
+    }
+}
+
+/// Ids given out so far, both ways. The null address is always id 0.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    ids: BTreeMap<u64, u64>,
+    addrs: Vec<u64>,
+}
+
+impl SymbolTable {
+    /// A table with no ids given out.
+    pub const fn new() -> Self {
+        Self { ids: BTreeMap::new(), addrs: Vec::new() }
+    }
+
+    /// The id of `addr`, giving it the next one on first sight.
+    pub fn id(&mut self, addr: u64) -> u64 {
+        if addr == 0 {
+            return 0;
+        }
+        if let Some(&id) = self.ids.get(&addr) {
+            return id;
+        }
+        self.addrs.push(addr);
+        let id = self.addrs.len() as u64;
+        self.ids.insert(addr, id);
+        id
+    }
+
+    /// The address `id` was given to.
+    pub fn address(&self, id: u64) -> Option<u64> {
+        match id {
+            0 => Some(0),
+            _ => self.addrs.get(usize::try_from(id - 1).ok()?).copied(),
+        }
+    }
+
+    /// Number of ids given out.
+    pub fn len(&self) -> usize {
+        self.addrs.len()
+    }
+
+    /// `true` if no id was given out.
+    pub fn is_empty(&self) -> bool {
+        self.addrs.is_empty()
+    }
+
+    /// Forgets every id.
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.addrs.clear();
+    }
+}
+
+/// Fast path for [`symbol`]: addresses need no lock.
+static STABLE: AtomicBool = AtomicBool::new(false);
+static TABLE: Mutex<SymbolTable> = Mutex::new(SymbolTable::new());
+
+/// Switches modes and forgets the ids given out so far. Set the mode before
+/// the first dictionary is sent.
+pub fn set_mode(mode: SymbolMode) {
+    let mut table = lock_table();
+    table.clear();
+    STABLE.store(mode == SymbolMode::StableIds, Ordering::Release);
+}
+
+/// The current mode.
+pub fn mode() -> SymbolMode {
+    match STABLE.load(Ordering::Acquire) {
+        true => SymbolMode::StableIds,
+        false => SymbolMode::Addresses,
+    }
+}
+
+/// What records carry for `addr` in the current mode.
+pub fn symbol(addr: u64) -> u64 {
+    if !STABLE.load(Ordering::Acquire) {
+        return addr;
+    }
+    lock_table().id(addr)
+}
+
+/// The address behind a value the host sent back, or `None` for an id
+/// that was never given out.
+pub fn address(symbol: u64) -> Option<u64> {
+    if !STABLE.load(Ordering::Acquire) {
+        return Some(symbol);
+    }
+    lock_table().address(symbol)
+}
+
+/// Sends `QS_SYM_MODE` with the current mode.
+pub fn announce(hook: &TraceHook) -> Result<(), TraceError> {
+    hook(SYM_MODE, &mode().payload(), false)
+}
+
+#[cfg(feature = "std")]
+fn lock_table() -> std::sync::MutexGuard<'static, SymbolTable> {
+    TABLE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(not(feature = "std"))]
+fn lock_table() -> spin::MutexGuard<'static, SymbolTable> {
+    TABLE.lock()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_follow_first_sight_and_map_back() {
+        let mut table = SymbolTable::new();
+        assert_eq!(table.id(0x8000_1040), 1);
+        assert_eq!(table.id(0x2000), 2);
+        assert_eq!(table.id(0x8000_1040), 1);
+        assert_eq!(table.id(0), 0);
+        assert_eq!(table.len(), 2);
+
+        assert_eq!(table.address(2), Some(0x2000));
+        assert_eq!(table.address(0), Some(0));
+        assert_eq!(table.address(3), None);
+
+        table.clear();
+        assert_eq!(table.id(0x2000), 1);
+    }
+
+    #[test]
+    fn mode_round_trips_through_its_payload() {
+        for mode in [SymbolMode::Addresses, SymbolMode::StableIds] {
+            assert_eq!(SymbolMode::from_payload(&mode.payload()), Some(mode));
+        }
+        assert_eq!(SymbolMode::from_payload(&[2]), None);
+        assert_eq!(SymbolMode::from_payload(&[]), None);
+    }
+}
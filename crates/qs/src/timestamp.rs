@@ -0,0 +1,246 @@
+//! Where record timestamps come from, and how wide they are on the wire.
+//!
+//! QS timestamps are opaque target counts: QSPY prints them as they are and
+//! only needs their byte width, which the target announces as `time_size` in
+//! `QS_TARGET_INFO`. A [`TimestampSource`] produces the count — a monotonic
+//! host clock, a tick counter the port advances, a [`ManualClock`] a test or
+//! simulation sets, or any `Fn() -> u32` — and
+//! [`TimestampSize`] truncates it to the advertised 1, 2 or 4 bytes, so the
+//! stream wraps exactly where the decoder expects it to.
+
+use alloc::sync::Arc;
+use core::fmt;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// A clock the tracer reads once per timestamped record.
+pub trait TimestampSource: Send + Sync {
+    /// The current count. Only the low [`TimestampSize`] bytes are sent.
+    fn now(&self) -> u32;
+}
+
+impl<F: Fn() -> u32 + Send + Sync> TimestampSource for F {
+    fn now(&self) -> u32 {
+        self()
+    }
+}
+
+/// Microseconds since the clock was created, from [`std::time::Instant`].
+/// The default source on hosted builds.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct MonotonicClock {
+    epoch: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl MonotonicClock {
+    /// Starts counting from now.
+    pub fn new() -> Self {
+        Self { epoch: std::time::Instant::now() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl TimestampSource for MonotonicClock {
+    fn now(&self) -> u32 {
+        self.epoch.elapsed().as_micros() as u32
+    }
+}
+
+/// A counter advanced elsewhere — typically by the port's tick ISR, or a
+/// free-running hardware timer mirrored into it — read as the timestamp.
+#[derive(Debug, Clone, Copy)]
+pub struct TickCounter(&'static AtomicU32);
+
+impl TickCounter {
+    /// Reads `counter`.
+    pub const fn new(counter: &'static AtomicU32) -> Self {
+        Self(counter)
+    }
+}
+
+impl TimestampSource for TickCounter {
+    fn now(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A clock that only moves when told to. Clones share the count, so a
+/// simulation keeps one and hands another to the tracer; the records then
+/// carry the simulation's time rather than the host's.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock(Arc<AtomicU32>);
+
+impl ManualClock {
+    /// A clock at `0`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the count.
+    pub fn set(&self, count: u32) {
+        self.0.store(count, Ordering::Relaxed);
+    }
+
+    /// Moves the count forward by `delta`, wrapping.
+    pub fn advance(&self, delta: u32) {
+        self.0.fetch_add(delta, Ordering::Relaxed);
+    }
+
+    /// The current count.
+    pub fn get(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl TimestampSource for ManualClock {
+    fn now(&self) -> u32 {
+        self.get()
+    }
+}
+
+/// Shared handle to a [`TimestampSource`], as held by [`QsConfig`](crate::QsConfig).
+#[derive(Clone)]
+pub struct TimestampClock(Arc<dyn TimestampSource>);
+
+impl TimestampClock {
+    /// Wraps `source`.
+    pub fn new(source: impl TimestampSource + 'static) -> Self {
+        Self(Arc::new(source))
+    }
+
+    /// A [`MonotonicClock`] started now.
+    #[cfg(feature = "std")]
+    pub fn monotonic() -> Self {
+        Self::new(MonotonicClock::new())
+    }
+
+    /// The current count, truncated to `size`.
+    pub fn read(&self, size: TimestampSize) -> u32 {
+        size.truncate(self.0.now())
+    }
+}
+
+impl fmt::Debug for TimestampClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TimestampClock(..)")
+    }
+}
+
+/// Byte width of a timestamp on the wire (`QS_TIME_SIZE`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampSize {
+    /// One byte; wraps every 256 counts.
+    One,
+    /// Two bytes; wraps every 65 536 counts.
+    Two,
+    /// Four bytes.
+    #[default]
+    Four,
+}
+
+impl TimestampSize {
+    /// The width in bytes, as carried in `TargetInfo::time_size`.
+    pub const fn bytes(self) -> u8 {
+        match self {
+            Self::One => 1,
+            Self::Two => 2,
+            Self::Four => 4,
+        }
+    }
+
+    /// The width for a `time_size` byte; `None` unless it is 1, 2 or 4.
+    pub const fn from_bytes(bytes: u8) -> Option<Self> {
+        match bytes {
+            1 => Some(Self::One),
+            2 => Some(Self::Two),
+            4 => Some(Self::Four),
+            _ => None,
+        }
+    }
+
+    /// Keeps the low `bytes()` bytes of `count`.
+    pub const fn truncate(self, count: u32) -> u32 {
+        match self {
+            Self::One => count & 0xFF,
+            Self::Two => count & 0xFFFF,
+            Self::Four => count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{QsConfig, Tracer, TraceBackend, TraceError};
+    use std::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    struct Frames(Arc<Mutex<Vec<Vec<u8>>>>);
+
+    impl TraceBackend for Frames {
+        fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
+            self.0.lock().unwrap().push(frame.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn sizes_truncate_to_their_width() {
+        assert_eq!(TimestampSize::One.truncate(0x1234_5678), 0x78);
+        assert_eq!(TimestampSize::Two.truncate(0x1234_5678), 0x5678);
+        assert_eq!(TimestampSize::Four.truncate(0x1234_5678), 0x1234_5678);
+        assert_eq!(TimestampSize::from_bytes(2), Some(TimestampSize::Two));
+        assert_eq!(TimestampSize::from_bytes(3), None);
+    }
+
+    #[test]
+    fn tracer_sends_tick_counter_at_configured_width() {
+        static TICKS: AtomicU32 = AtomicU32::new(0x0001_0203);
+        let frames = Frames::default();
+        let cfg = QsConfig::default()
+            .with_timestamp_source(TickCounter::new(&TICKS))
+            .with_timestamp_size(TimestampSize::Two);
+        let mut tracer = Tracer::new(cfg, frames.clone());
+
+        let record = tracer.record(9, &[0xAA], true).unwrap();
+        assert_eq!(record.timestamp, Some(0x0203));
+        let untimed = tracer.record(9, &[0xAA], false).unwrap();
+        assert_eq!(untimed.timestamp, None);
+
+        let frames = frames.0.lock().unwrap();
+        // seq | type | ts (2 bytes) | payload | checksum | flag
+        assert_eq!(&frames[0][..5], &[1, 9, 0x03, 0x02, 0xAA]);
+        assert_eq!(frames[1].len(), 5);
+    }
+
+    #[test]
+    fn manual_clock_is_shared_with_the_tracer() {
+        let clock = ManualClock::new();
+        let frames = Frames::default();
+        let tracer = Tracer::new(QsConfig::default(), frames.clone()).into_handle();
+        tracer.set_timestamp_source(Some(TimestampClock::new(clock.clone())));
+
+        clock.set(40);
+        assert_eq!(tracer.emit_with_timestamp(1, &[]).unwrap().timestamp, Some(40));
+        clock.advance(2);
+        assert_eq!(tracer.emit_with_timestamp(1, &[]).unwrap().timestamp, Some(42));
+
+        tracer.set_timestamp_source(None);
+        assert_eq!(tracer.emit_with_timestamp(1, &[]).unwrap().timestamp, None);
+    }
+
+    #[test]
+    fn closures_are_sources() {
+        let cfg = QsConfig::default().with_timestamp_source(|| 7u32);
+        let mut tracer = Tracer::new(cfg, Frames::default());
+        assert_eq!(tracer.record(1, &[], true).unwrap().timestamp, Some(7));
+    }
+}
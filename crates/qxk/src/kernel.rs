@@ -9,9 +9,11 @@ use core::fmt;
 
 use qf::active::{ActiveObjectId, ActiveObjectRef};
 use qf::event::{DynEvent, Signal};
+use qf::services::{with_services, KernelServices, ServiceError};
 use qf::TraceHook;
 
 use crate::scheduler::{QxkScheduler, ScheduleMode, SchedStatus};
+use crate::stack::{StackCheck, ThreadStats};
 #[cfg(not(feature = "static-alloc"))]
 use crate::sync::Arc;
 use crate::sync::Mutex;
@@ -155,6 +157,14 @@ pub struct QxkKernelBuilder {
     ao_registrations: AoRegVec,
     thread_configs: ThreadCfgVec,
     trace: Option<TraceHook>,
+    stacks: StackPolicy,
+}
+
+/// When and at what usage the kernel reports thread stacks.
+#[derive(Debug, Clone, Copy)]
+struct StackPolicy {
+    check: StackCheck,
+    threshold: u8,
 }
 
 impl QxkKernelBuilder {
@@ -164,6 +174,7 @@ impl QxkKernelBuilder {
             ao_registrations: AoRegVec::new(),
             thread_configs: ThreadCfgVec::new(),
             trace: None,
+            stacks: StackPolicy { check: StackCheck::Manual, threshold: 80 },
         }
     }
 
@@ -196,9 +207,23 @@ impl QxkKernelBuilder {
         self
     }
 
+    /// Sets when the kernel scans the painted thread stacks on its own
+    /// (default: only in [`QxkKernel::check_stacks`]).
+    pub fn stack_check(mut self, check: StackCheck) -> Self {
+        self.stacks.check = check;
+        self
+    }
+
+    /// Sets the stack usage, in percent, from which scans emit
+    /// `QS_QXK_THREAD_STACK` (default 80).
+    pub fn stack_threshold(mut self, percent: u8) -> Self {
+        self.stacks.threshold = percent;
+        self
+    }
+
     /// Builds the QXK kernel.
     pub fn build(self) -> Result<QxkKernel, QxkKernelError> {
-        QxkKernel::new(self.ao_registrations, self.thread_configs, self.trace)
+        QxkKernel::new(self.ao_registrations, self.thread_configs, self.trace, self.stacks)
     }
 
     fn validate_ao_priority(&self, priority: u8) -> Result<(), QxkKernelError> {
@@ -237,6 +262,7 @@ pub struct QxkKernel {
     ao_id_to_prio: BTreeMap<ActiveObjectId, u8>,
     threads: ThreadStore,
     trace: Option<TraceHook>,
+    stacks: StackPolicy,
 }
 
 impl QxkKernel {
@@ -249,6 +275,7 @@ impl QxkKernel {
         ao_registrations: AoRegVec,
         thread_configs: ThreadCfgVec,
         trace: Option<TraceHook>,
+        stacks: StackPolicy,
     ) -> Result<Self, QxkKernelError> {
         // Set up active object slots
         let mut ao_slots: [Option<AoSlot>; MAX_AO_PRIORITY + 1] =
@@ -285,6 +312,7 @@ impl QxkKernel {
             ao_id_to_prio,
             threads,
             trace,
+            stacks,
         })
     }
 
@@ -358,7 +386,7 @@ impl QxkKernel {
     pub fn start(&mut self) {
         // Start active objects
         for slot in self.ao_slots.iter().flatten() {
-            slot.object.start(self.trace.clone());
+            with_services(self, || slot.object.start(self.trace.clone()));
             if slot.object.has_events() {
                 self.scheduler.mark_ao_ready(slot.object.priority());
             }
@@ -380,6 +408,12 @@ impl QxkKernel {
         target: ActiveObjectId,
         event: DynEvent,
     ) -> Result<(), QxkKernelError> {
+        self.enqueue_ao(target, event, false)
+    }
+
+    /// Posts `event` to the back of `target`'s queue, or to the front for a
+    /// recall, and marks the active object ready.
+    fn enqueue_ao(&self, target: ActiveObjectId, event: DynEvent, lifo: bool) -> Result<(), QxkKernelError> {
         // Dynamic: O(log n) id→priority map. Heap-free: scan the fixed slot array.
         #[cfg(not(feature = "static-alloc"))]
         let prio = self
@@ -399,7 +433,11 @@ impl QxkKernel {
             .as_ref()
             .unwrap_or_else(|| qf::fusa::on_error(module_path!(), line!()));
         let was_empty = !slot.object.has_events();
-        slot.object.post(event);
+        if lifo {
+            slot.object.post_lifo(event);
+        } else {
+            slot.object.post(event);
+        }
         if was_empty {
             self.scheduler.mark_ao_ready(prio);
         }
@@ -446,6 +484,9 @@ impl QxkKernel {
                 if let Some(thread_mtx) = self.find_thread(id) {
                     let mut thread = thread_mtx.lock();
                     let action = thread.poll(self.scheduler());
+                    if self.stacks.check == StackCheck::OnSwitch {
+                        self.check_stack(&mut thread);
+                    }
 
                     self.scheduler.complete_execution(mode);
 
@@ -484,7 +525,11 @@ impl QxkKernel {
 
     /// Runs the kernel until all work is complete.
     pub fn run_until_idle(&self) {
-        while self.dispatch_once() {}
+        let mut batch = qf::batch::Batch::start();
+        while self.dispatch_once() {
+            batch.count();
+        }
+        batch.finish(self.trace.as_ref());
     }
 
     /// Checks if there is pending work.
@@ -492,9 +537,50 @@ impl QxkKernel {
         self.scheduler.has_work()
     }
 
+    /// Stack usage of thread `id`, if it exists and has a painted stack.
+    pub fn thread_stats(&self, id: ThreadId) -> Option<ThreadStats> {
+        self.find_thread(id)?.lock().stack_stats()
+    }
+
+    /// Scans the stack of every thread not running right now, reporting
+    /// those at or above the threshold. Call it periodically, e.g. from a
+    /// time event, when the kernel does not check on every switch.
+    pub fn check_stacks(&self) {
+        for thread_mtx in self.thread_mutexes() {
+            if let Some(mut thread) = thread_mtx.try_lock() {
+                self.check_stack(&mut thread);
+            }
+        }
+    }
+
+    /// Reports the stack of `thread` if it needs it; an overflow is fatal.
+    fn check_stack(&self, thread: &mut ExtendedThread) {
+        let id = thread.id();
+        let Some(stack) = thread.stack_mut() else { return };
+        if let Some(stats) = stack.check(self.stacks.threshold) {
+            let overflowed = stack.is_overflowed();
+            self.emit_stack(id, &stats, overflowed);
+            if overflowed {
+                qf::fusa::on_error(module_path!(), line!());
+            }
+        }
+    }
+
+    #[cfg(feature = "qs")]
+    fn emit_stack(&self, id: ThreadId, stats: &ThreadStats, overflowed: bool) {
+        if let Some(trace) = &self.trace {
+            let bytes = |n: usize| n.min(u32::MAX as usize) as u32;
+            let record = qs::records::qxk::thread_stack(id.0, bytes(stats.size), bytes(stats.used), u8::from(overflowed));
+            let _ = record.emit(trace, &qs::records::RecordSizes::NATIVE);
+        }
+    }
+
+    #[cfg(not(feature = "qs"))]
+    fn emit_stack(&self, _id: ThreadId, _stats: &ThreadStats, _overflowed: bool) {}
+
     fn dispatch_ao(&self, priority: u8) {
         if let Some(slot) = &self.ao_slots[priority as usize] {
-            let processed = slot.object.dispatch_one();
+            let processed = with_services(self, || slot.object.dispatch_one());
             debug_assert!(processed, "scheduled AO had no event");
 
             if !slot.object.has_events() {
@@ -504,6 +590,20 @@ impl QxkKernel {
     }
 }
 
+impl KernelServices for QxkKernel {
+    fn post(&self, target: ActiveObjectId, event: DynEvent) -> Result<(), ServiceError> {
+        self.enqueue_ao(target, event, false).map_err(|_| ServiceError::NotFound(target))
+    }
+
+    fn post_lifo(&self, target: ActiveObjectId, event: DynEvent) -> Result<(), ServiceError> {
+        self.enqueue_ao(target, event, true).map_err(|_| ServiceError::NotFound(target))
+    }
+
+    fn publish(&self, signal: Signal, event: DynEvent) {
+        self.publish_ao(signal, event);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -718,4 +818,75 @@ mod tests {
         assert_eq!(max_concurrent, 1, "Thread was executed/polled concurrently by multiple cores!");
         Ok(())
     }
+
+    /// Writes into the top `bytes` of thread `id`'s painted stack, as a
+    /// thread running on it would.
+    fn touch_stack(kernel: &QxkKernel, id: ThreadId, bytes: usize) {
+        let mut thread = kernel.find_thread(id).unwrap().lock();
+        let words = thread.stack_mut().unwrap().memory_mut();
+        let len = words.len();
+        words[len - bytes / 4..].fill(0);
+    }
+
+    #[cfg(all(feature = "qs", not(feature = "static-alloc")))]
+    #[test]
+    fn stack_checks_report_usage_past_the_threshold() -> Result<(), QxkKernelError> {
+        use crate::stack::{stack_memory, ThreadStack};
+        use crate::thread::{thread_handler, ThreadPriority};
+
+        let records = StdArc::new(Mutex::new(Vec::new()));
+        let sink = StdArc::clone(&records);
+        let hook: TraceHook = StdArc::new(move |record, payload: &[u8], _| {
+            if record == qs::records::qxk::THREAD_STACK {
+                sink.lock().unwrap().push(payload.to_vec());
+            }
+            Ok(())
+        });
+        let id = ThreadId(1);
+        let config = ThreadConfig::new(id, ThreadPriority(1), thread_handler(|_| ThreadAction::Yield))
+            .with_stack(ThreadStack::new(stack_memory(1024)));
+        let kernel = QxkKernel::builder()
+            .register_thread(config)?
+            .stack_threshold(75)
+            .with_trace_hook(hook)
+            .build()?;
+
+        touch_stack(&kernel, id, 512);
+        kernel.check_stacks();
+        assert!(records.lock().unwrap().is_empty());
+        let stats = kernel.thread_stats(id).unwrap();
+        assert_eq!((stats.used, stats.free, stats.high_water), (512, 512, 512));
+
+        touch_stack(&kernel, id, 800);
+        kernel.check_stacks();
+        kernel.check_stacks();
+        // thread | size | used | overflow
+        let mut expected = vec![1];
+        expected.extend_from_slice(&1024u32.to_le_bytes());
+        expected.extend_from_slice(&800u32.to_le_bytes());
+        expected.push(0);
+        assert_eq!(*records.lock().unwrap(), [expected]);
+        assert_eq!(kernel.thread_stats(ThreadId(9)), None);
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "qp-rs FuSa fault")]
+    fn overflowing_a_stack_is_fatal_on_the_next_switch() {
+        use crate::stack::{stack_memory, StackCheck, ThreadStack};
+        use crate::thread::{thread_handler, ThreadPriority};
+
+        let id = ThreadId(2);
+        let config = ThreadConfig::new(id, ThreadPriority(1), thread_handler(|_| ThreadAction::Yield))
+            .with_stack(ThreadStack::new(stack_memory(256)));
+        let mut kernel = QxkKernel::builder()
+            .register_thread(config)
+            .and_then(|builder| builder.stack_check(StackCheck::OnSwitch).build())
+            .unwrap();
+        kernel.start();
+        assert!(kernel.dispatch_once());
+
+        touch_stack(&kernel, id, 256);
+        kernel.dispatch_once();
+    }
 }
@@ -22,6 +22,7 @@
 //!
 //! - [`thread`] - Extended thread abstraction with stack management
 //! - [`scheduler`] - Dual-mode scheduler for AOs and threads
+//! - [`stack`] - Stack painting and high-water marks for extended threads
 //! - [`sync`] - Synchronization primitives (semaphores, mutexes)
 //! - [`kernel`] - QXK kernel with builder pattern
 
@@ -41,6 +42,7 @@ extern crate alloc;
 pub mod kernel;
 pub mod primitives;
 pub mod scheduler;
+pub mod stack;
 mod sync;
 pub mod thread;
 
@@ -55,6 +57,9 @@ pub const MAX_AO_PRIORITY: usize = 63;
 pub use kernel::{QxkKernel, QxkKernelBuilder, QxkKernelError};
 pub use primitives::{CondVar, MessageQueue, MutexPrim, Semaphore, SyncError, SyncResult};
 pub use scheduler::{QxkScheduler, SchedStatus, ScheduleMode};
+pub use stack::{StackCheck, ThreadStack, ThreadStats};
+#[cfg(any(not(feature = "static-alloc"), feature = "std"))]
+pub use stack::stack_memory;
 pub use thread::{ExtendedThread, ThreadAction, ThreadConfig, ThreadId, ThreadPriority, ThreadState};
 #[cfg(any(not(feature = "static-alloc"), feature = "std"))]
 pub use thread::thread_handler;
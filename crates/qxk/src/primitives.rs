@@ -31,20 +31,7 @@ use crate::thread::{ThreadId, ThreadPriority};
 
 #[cfg(feature = "qs")]
 use qf::TraceHook;
-#[cfg(feature = "qs")]
-use qs::records::qxk as rec;
-
-// Fallback record-ID module when the `qs` feature is disabled.
-// The values are never used (emit() is a no-op), but the names must resolve.
-#[cfg(not(feature = "qs"))]
-mod rec {
-    pub const SEM_TAKE:   u8 = 71;
-    pub const SEM_BLOCK:  u8 = 72;
-    pub const SEM_SIGNAL: u8 = 73;
-    pub const MTX_LOCK:   u8 = 75;
-    pub const MTX_BLOCK:  u8 = 76;
-    pub const MTX_UNLOCK: u8 = 77;
-}
+use qs_protocol::records::qxk as rec;
 
 /// Waiter list storage. Dynamic: heap [`Vec`]; `static-alloc`: heap-free
 /// [`heapless::Vec`] bounded by [`crate::MAX_WAITERS`].
@@ -119,6 +106,10 @@ fn share<T>(inner: T) -> Shared<T> {
     }
 }
 
+/// Width of the object address in QS records (the target's `obj_ptr_size`).
+#[cfg(feature = "qs")]
+const PTR_SIZE: usize = core::mem::size_of::<usize>();
+
 /// Stable address of the shared state, used as a QS object id.
 #[cfg(feature = "qs")]
 #[inline]
@@ -274,11 +265,11 @@ impl Semaphore {
     fn emit(&self, record_id: u8, thread_prio: u8, count: usize) {
         if let Some(ref hook) = self.trace {
             let ptr = shared_ptr(&self.inner);
-            let mut payload = [0u8; 11];
-            payload[..8].copy_from_slice(&ptr.to_le_bytes());
-            payload[8] = thread_prio;
+            let mut payload = [0u8; PTR_SIZE + 3];
+            payload[..PTR_SIZE].copy_from_slice(&ptr.to_le_bytes()[..PTR_SIZE]);
+            payload[PTR_SIZE] = thread_prio;
             let c = count.min(u16::MAX as usize) as u16;
-            payload[9..11].copy_from_slice(&c.to_le_bytes());
+            payload[PTR_SIZE + 1..].copy_from_slice(&c.to_le_bytes());
             let _ = hook(record_id, &payload, true);
         }
     }
@@ -503,9 +494,9 @@ impl MutexPrim {
     fn emit(&self, record_id: u8, thread_prio: u8) {
         if let Some(ref hook) = self.trace {
             let ptr = shared_ptr(&self.inner);
-            let mut payload = [0u8; 9];
-            payload[..8].copy_from_slice(&ptr.to_le_bytes());
-            payload[8] = thread_prio;
+            let mut payload = [0u8; PTR_SIZE + 1];
+            payload[..PTR_SIZE].copy_from_slice(&ptr.to_le_bytes()[..PTR_SIZE]);
+            payload[PTR_SIZE] = thread_prio;
             let _ = hook(record_id, &payload, true);
         }
     }
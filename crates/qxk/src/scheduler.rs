@@ -15,16 +15,7 @@ use crate::sync::Mutex;
 use crate::thread::{ThreadId, ThreadPriority};
 use qf::TraceHook;
 
-#[cfg(feature = "qs")]
-use qs::records::sched;
-
-#[cfg(not(feature = "qs"))]
-mod sched {
-    pub const LOCK: u8 = 0;
-    pub const UNLOCK: u8 = 1;
-    pub const NEXT: u8 = 2;
-    pub const IDLE: u8 = 3;
-}
+use qs_protocol::records::sched;
 
 // Custom QXK scheduler record for thread scheduling
 const THREAD_NEXT: u8 = 100;
@@ -0,0 +1,188 @@
+//! Stack painting and high-water marks for extended threads.
+//!
+//! A [`ThreadStack`] fills its memory with [`STACK_PAINT`] when it is
+//! created. The stack grows down from the end of the memory towards index 0,
+//! so the painted words still intact at the low end are the part the thread
+//! has never reached; everything above them is its high-water mark. The
+//! lowest [`CANARY_WORDS`] words are the canary: once any of them changes,
+//! the thread has run off the end of its stack.
+//!
+//! The kernel scans a thread's stack when it switches away from the thread
+//! ([`StackCheck::OnSwitch`]) or when the application calls
+//! [`QxkKernel::check_stacks`](crate::QxkKernel::check_stacks), e.g. from a
+//! periodic time event. A scan that finds the usage at or above the kernel's
+//! threshold for the first time, or higher than the last report, emits
+//! `QS_QXK_THREAD_STACK`. A scan that finds the canary overwritten reports the
+//! overflow the same way and then stops through
+//! [`qf::fusa::on_error`]: the memory beyond the stack is already corrupt.
+//!
+//! The port that runs a thread on its stack gets the memory from
+//! [`ThreadStack::memory_mut`]; the cooperative host port polls threads on
+//! the dispatcher's stack and only keeps the bookkeeping.
+
+/// Pattern painted over a fresh stack.
+pub const STACK_PAINT: u32 = 0xDEAD_BEEF;
+/// Words at the far end of the stack that must keep their paint.
+pub const CANARY_WORDS: usize = 4;
+
+/// Memory backing a [`ThreadStack`]. Dynamic: a boxed slice; heap-free
+/// `static-alloc`: a `&'static mut` slice in application-owned storage.
+#[cfg(not(feature = "static-alloc"))]
+pub type StackMemory = alloc::boxed::Box<[u32]>;
+/// Heap-free stack memory: a `&'static mut` slice. See the dynamic variant.
+#[cfg(feature = "static-alloc")]
+pub type StackMemory = &'static mut [u32];
+
+/// Allocates `bytes` of stack memory, rounded up to whole words (dynamic
+/// build).
+#[cfg(not(feature = "static-alloc"))]
+pub fn stack_memory(bytes: usize) -> StackMemory {
+    alloc::vec![0; bytes.div_ceil(4)].into_boxed_slice()
+}
+
+/// Allocates `bytes` of stack memory (`static-alloc` + `std`): leaks a boxed
+/// slice for host tests. Heap-free targets pass a `&'static mut` array.
+#[cfg(all(feature = "static-alloc", feature = "std"))]
+pub fn stack_memory(bytes: usize) -> StackMemory {
+    alloc::boxed::Box::leak(alloc::vec![0; bytes.div_ceil(4)].into_boxed_slice())
+}
+
+/// When the kernel scans thread stacks on its own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StackCheck {
+    /// Only from [`QxkKernel::check_stacks`](crate::QxkKernel::check_stacks).
+    #[default]
+    Manual,
+    /// Also every time a thread gives up the CPU.
+    OnSwitch,
+}
+
+/// Stack usage of a thread, in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThreadStats {
+    /// Size of the stack.
+    pub size: usize,
+    /// Bytes the thread has reached so far.
+    pub used: usize,
+    /// Bytes it has never touched.
+    pub free: usize,
+    /// Largest `used` seen by any scan, kept across
+    /// [`repaint`](ThreadStack::repaint)s.
+    pub high_water: usize,
+}
+
+impl ThreadStats {
+    /// Usage as a percentage of the size.
+    pub fn percent_used(&self) -> u8 {
+        match self.size {
+            0 => 0,
+            size => (self.used * 100 / size) as u8,
+        }
+    }
+}
+
+/// A painted stack of an extended thread.
+pub struct ThreadStack {
+    memory: StackMemory,
+    high_water: usize,
+    /// `used` at the last threshold report.
+    reported: usize,
+}
+
+impl ThreadStack {
+    /// Paints `memory` and takes it as a thread's stack.
+    pub fn new(memory: StackMemory) -> Self {
+        let mut stack = Self { memory, high_water: 0, reported: 0 };
+        stack.repaint();
+        stack
+    }
+
+    /// Size of the stack in bytes.
+    pub fn size(&self) -> usize {
+        self.memory.len() * 4
+    }
+
+    /// The stack memory, for the port that switches the thread onto it.
+    pub fn memory_mut(&mut self) -> &mut [u32] {
+        &mut self.memory[..]
+    }
+
+    /// `true` if a canary word has been overwritten.
+    pub fn is_overflowed(&self) -> bool {
+        self.memory.iter().take(CANARY_WORDS).any(|&word| word != STACK_PAINT)
+    }
+
+    /// Scans the stack for its high-water mark.
+    pub fn stats(&mut self) -> ThreadStats {
+        let untouched = self.memory.iter().take_while(|&&word| word == STACK_PAINT).count();
+        let size = self.size();
+        let used = size - untouched * 4;
+        self.high_water = self.high_water.max(used);
+        ThreadStats { size, used, free: size - used, high_water: self.high_water }
+    }
+
+    /// Paints the whole stack again, to measure a new phase of the thread.
+    /// Only valid while the thread is not running on it.
+    pub fn repaint(&mut self) {
+        self.memory.fill(STACK_PAINT);
+        self.reported = 0;
+    }
+
+    /// Scans the stack and returns its usage if it needs reporting: at or
+    /// above `threshold` percent and deeper than at the last report, or
+    /// overflowed.
+    pub(crate) fn check(&mut self, threshold: u8) -> Option<ThreadStats> {
+        let stats = self.stats();
+        let over = stats.percent_used() >= threshold && stats.used > self.reported;
+        if over || self.is_overflowed() {
+            self.reported = stats.used;
+            return Some(stats);
+        }
+        None
+    }
+}
+
+impl core::fmt::Debug for ThreadStack {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ThreadStack")
+            .field("size", &self.size())
+            .field("high_water", &self.high_water)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_finds_the_deepest_word_written() {
+        let mut stack = ThreadStack::new(stack_memory(256));
+        assert_eq!(stack.stats(), ThreadStats { size: 256, used: 0, free: 256, high_water: 0 });
+
+        // The thread reaches 100 bytes down from the top.
+        let words = stack.memory_mut();
+        let len = words.len();
+        words[len - 25..].fill(0);
+        let stats = stack.stats();
+        assert_eq!((stats.used, stats.free, stats.percent_used()), (100, 156, 39));
+
+        stack.repaint();
+        let stats = stack.stats();
+        assert_eq!((stats.used, stats.high_water), (0, 100));
+        assert!(!stack.is_overflowed());
+    }
+
+    #[test]
+    fn threshold_reports_only_new_depths_and_overflow() {
+        let mut stack = ThreadStack::new(stack_memory(64));
+        assert_eq!(stack.check(50), None);
+        stack.memory_mut()[8..].fill(0);
+        assert_eq!(stack.check(50).map(|s| s.used), Some(32));
+        assert_eq!(stack.check(50), None);
+
+        stack.memory_mut()[0] = 0;
+        assert!(stack.is_overflowed());
+        assert_eq!(stack.check(50).map(|s| s.used), Some(64));
+    }
+}
@@ -65,4 +65,20 @@ impl<T> Mutex<T> {
             self.inner.lock()
         }
     }
+
+    /// Acquires the mutex if it is free right now.
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        #[cfg(feature = "std")]
+        {
+            match self.inner.try_lock() {
+                Ok(guard) => Some(guard),
+                Err(std::sync::TryLockError::WouldBlock) => None,
+                Err(std::sync::TryLockError::Poisoned(_)) => qf::fusa::on_error(module_path!(), line!()),
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            self.inner.try_lock()
+        }
+    }
 }
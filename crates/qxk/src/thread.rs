@@ -12,6 +12,7 @@
 use core::fmt;
 
 use crate::scheduler::QxkScheduler;
+use crate::stack::{ThreadStack, ThreadStats};
 
 /// Action returned by a thread handler indicating what to do next.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -134,6 +135,8 @@ pub struct ThreadConfig {
     pub priority: ThreadPriority,
     /// Stack size in bytes.
     pub stack_size: usize,
+    /// Painted stack, for high-water tracking.
+    pub stack: Option<ThreadStack>,
     /// Thread handler function.
     pub handler: ThreadHandler,
 }
@@ -145,6 +148,7 @@ impl ThreadConfig {
             id,
             priority,
             stack_size: 4096, // Default 4KB stack
+            stack: None,
             handler,
         }
     }
@@ -154,6 +158,13 @@ impl ThreadConfig {
         self.stack_size = size;
         self
     }
+
+    /// Gives the thread a painted stack, whose size becomes the stack size.
+    pub fn with_stack(mut self, stack: ThreadStack) -> Self {
+        self.stack_size = stack.size();
+        self.stack = Some(stack);
+        self
+    }
 }
 
 /// An extended thread in the QXK kernel.
@@ -168,6 +179,7 @@ pub struct ExtendedThread {
     /// on the dispatcher's stack, so no separate stack is allocated; this is
     /// retained for diagnostics and future preemptive ports.
     stack_size: usize,
+    stack: Option<ThreadStack>,
     handler: Option<ThreadHandler>,
     iteration: u64,
 }
@@ -180,6 +192,7 @@ impl ExtendedThread {
             priority: config.priority,
             state: ThreadState::Ready,
             stack_size: config.stack_size,
+            stack: config.stack,
             handler: Some(config.handler),
             iteration: 0,
         }
@@ -215,6 +228,16 @@ impl ExtendedThread {
         self.state == ThreadState::Terminated
     }
 
+    /// Scans the thread's painted stack, if it has one.
+    pub fn stack_stats(&mut self) -> Option<ThreadStats> {
+        self.stack.as_mut().map(ThreadStack::stats)
+    }
+
+    /// The thread's painted stack, if it has one.
+    pub fn stack_mut(&mut self) -> Option<&mut ThreadStack> {
+        self.stack.as_mut()
+    }
+
     #[cfg(test)]
     pub(crate) fn set_state(&mut self, state: ThreadState) {
         self.state = state;
@@ -0,0 +1,98 @@
+//! Blinky on the ESP32-C6, on the QV kernel.
+//!
+//! The SYSTIMER alarm ticks at `TICKS_PER_SEC` and its handler only counts
+//! the tick. `Esp32C6QvRuntime::run` is the main loop: it runs the timer
+//! wheel for every counted tick, which posts the blink `TIMEOUT`, dispatches
+//! it to the AO, and sleeps in `WFI` whenever nothing is left to do. The
+//! LED is on GPIO8.
+//!
+//! ```sh
+//! cargo build --bin blinky-esp32-c6 --features esp32c6 --no-default-features
+//! ```
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+use esp_backtrace as _;
+use esp_hal::clock::CpuClock;
+use esp_hal::interrupt;
+use esp_hal::main;
+use esp_hal::peripherals::Interrupt;
+
+use hal::gpio::{OutputPin, PinMode};
+use hal_rvsis::esp32c6::Esp32C6Pin;
+use qf::active::{new_active_object, ActiveObjectId};
+use qf::event::Signal;
+use qf::idle::IdleContext;
+use qf::kernel::{Kernel, KernelConfig};
+use qf::time::{TimeEvent, TimeEventConfig};
+use qf_port_esp32_c6::interrupts::{idle_sleep, IDLE_LOCK};
+use qf_port_esp32_c6::{on_systimer_alarm, Esp32C6Port, Esp32C6QvRuntime, PortConfig};
+
+use blinky::{sig, Blinky};
+
+const BLINKY_ID: ActiveObjectId = ActiveObjectId::new(1);
+const LED_PIN: u8 = 8;
+const TICKS_PER_SEC: u32 = 100;
+
+static LED: Mutex<RefCell<Option<Esp32C6Pin>>> = Mutex::new(RefCell::new(None));
+
+fn led(on: bool) {
+    critical_section::with(|cs| {
+        if let Some(pin) = LED.borrow_ref_mut(cs).as_mut() {
+            let _ = if on { pin.set_high() } else { pin.set_low() };
+        }
+    });
+}
+
+/// `QV_onIdle()`: sleep until the next tick, with interrupts masked from the
+/// kernel's last look at the queues until `WFI`.
+fn on_idle(cx: IdleContext) {
+    cx.sleep(idle_sleep);
+}
+
+extern "C" fn systimer_tick() {
+    on_systimer_alarm();
+}
+
+#[main]
+fn main() -> ! {
+    let config = esp_hal::Config::default().with_cpu_clock(CpuClock::max());
+    let _peripherals = esp_hal::init(config);
+    esp_alloc::heap_allocator!(size: 16 * 1024);
+
+    esp_println::println!("Blinky starting on ESP32-C6 (QV)");
+
+    // Safety: GPIO8 is used for nothing else.
+    let mut pin = unsafe { Esp32C6Pin::new(LED_PIN) };
+    pin.set_mode(PinMode::Output).expect("LED pin config failed");
+    critical_section::with(|cs| LED.borrow_ref_mut(cs).replace(pin));
+
+    let timer = TimeEvent::new(BLINKY_ID, TimeEventConfig::new(Signal(sig::TIMEOUT)));
+    let blinky = Blinky::new(Arc::clone(&timer), u64::from(TICKS_PER_SEC / 2), led).into_hsm();
+    let builder = Kernel::with_config(
+        KernelConfig::builder()
+            .name("Blinky")
+            .on_idle(on_idle)
+            .interrupt_lock(IDLE_LOCK)
+            .build(),
+    )
+    .register(new_active_object(BLINKY_ID, 1, blinky));
+
+    // Safety: the handler only acknowledges the alarm and counts the tick.
+    unsafe { interrupt::bind_interrupt(Interrupt::SYSTIMER_TARGET0, systimer_tick) };
+
+    let mut config = PortConfig::new();
+    config.tick_hz = TICKS_PER_SEC;
+    let mut runtime = Esp32C6QvRuntime::with_builder(builder, Esp32C6Port::new(), config);
+    runtime.register_time_event(timer);
+
+    runtime.run();
+    panic!("QV run loop returned");
+}
@@ -0,0 +1,89 @@
+//! Blinky: the smallest complete QP application.
+//!
+//! One active object, one periodic time event, two states:
+//!
+//! ```text
+//! top
+//! ├── off   TIMEOUT ▶ on
+//! └── on    TIMEOUT ▶ off
+//! ```
+//!
+//! The initial transition arms the time event to fire every half period,
+//! and the entry action of each state drives the LED. Nothing here knows
+//! which board or kernel it runs on: the LED is a plain function and the
+//! time event is ticked by whatever runtime owns the timer wheel — the host
+//! loop in `main.rs`, or the SYSTIMER on the ESP32-C6.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+
+use qf::event::{DynEvent, Signal};
+use qf::hsm::reserved::*;
+use qf::time::{TickDuration, TimeEvent};
+use qf::{q_handled, q_super, q_tran, QHsm, QHsmResult, StateHandler};
+
+/// Application signals.
+pub mod sig {
+    /// The blink time event expired.
+    pub const TIMEOUT: u16 = 4;
+}
+
+/// Extended state of the blinky.
+pub struct Blinky {
+    timer: Arc<TimeEvent>,
+    half_period: u64,
+    led: fn(bool),
+}
+
+impl Blinky {
+    /// A blinky timed by `timer` (targeting this AO with [`sig::TIMEOUT`]),
+    /// switching `led` every `half_period` ticks.
+    pub fn new(timer: Arc<TimeEvent>, half_period: u64, led: fn(bool)) -> Self {
+        Self { timer, half_period: half_period.max(1), led }
+    }
+
+    /// The blinky as a state machine, ready to register as an AO.
+    pub fn into_hsm(self) -> QHsm<Blinky> {
+        QHsm::new(self, initial)
+    }
+}
+
+/// Every state handler with its dictionary name, for `QS_FUN_DICT`.
+pub const STATES: [(StateHandler<Blinky>, &str); 3] = [
+    (initial, "Blinky::initial"),
+    (off, "Blinky::off"),
+    (on, "Blinky::on"),
+];
+
+/// Every application signal with its dictionary name, for `QS_SIG_DICT`.
+pub const SIGNALS: [(Signal, &str); 1] = [(Signal(sig::TIMEOUT), "TIMEOUT_SIG")];
+
+pub fn initial(me: &mut Blinky, _e: &DynEvent) -> QHsmResult<Blinky> {
+    me.timer.arm(me.half_period, Some(TickDuration::from_ticks(me.half_period)));
+    q_tran!(off)
+}
+
+pub fn off(me: &mut Blinky, e: &DynEvent) -> QHsmResult<Blinky> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => {
+            (me.led)(false);
+            q_handled!()
+        }
+        sig::TIMEOUT => q_tran!(on),
+        _ => q_super!(QHsm::<Blinky>::top_state),
+    }
+}
+
+pub fn on(me: &mut Blinky, e: &DynEvent) -> QHsmResult<Blinky> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => {
+            (me.led)(true);
+            q_handled!()
+        }
+        sig::TIMEOUT => q_tran!(off),
+        _ => q_super!(QHsm::<Blinky>::top_state),
+    }
+}
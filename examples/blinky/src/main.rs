@@ -0,0 +1,51 @@
+//! Blinky on the host, driven by the QV kernel's own run loop.
+//!
+//! `Kernel::run` plays the part of `QF::run()`: every pass sleeps for one
+//! tick, advances the timer wheel and dispatches until idle. The LED is the
+//! terminal.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use qf::active::{new_active_object, ActiveObjectId};
+use qf::event::Signal;
+use qf::time::{share_kernel, TimeEvent, TimeEventConfig, TimerWheel};
+use qf::Kernel;
+
+use blinky::{sig, Blinky};
+
+const BLINKY_ID: ActiveObjectId = ActiveObjectId::new(1);
+const TICKS_PER_SEC: u64 = 10;
+const RUN_TICKS: u32 = 100;
+
+static TICKS: AtomicU32 = AtomicU32::new(0);
+
+fn led(on: bool) {
+    let tick = TICKS.load(Ordering::Relaxed);
+    println!("[{tick:3}] LED {}", if on { "ON" } else { "OFF" });
+}
+
+fn main() {
+    println!("Starting Blinky on the QV kernel");
+
+    let timer = TimeEvent::new(BLINKY_ID, TimeEventConfig::new(Signal(sig::TIMEOUT)));
+    let blinky = Blinky::new(timer.clone(), TICKS_PER_SEC / 2, led).into_hsm();
+    let kernel = share_kernel(
+        Kernel::builder()
+            .register(new_active_object(BLINKY_ID, 1, blinky))
+            .build(),
+    );
+    let mut wheel = TimerWheel::new(kernel.clone());
+    wheel.register(timer);
+
+    kernel.run(|| {
+        thread::sleep(Duration::from_millis(1000 / TICKS_PER_SEC));
+        if let Err(err) = wheel.tick() {
+            eprintln!("tick failed: {err:?}");
+        }
+        if TICKS.fetch_add(1, Ordering::Relaxed) + 1 >= RUN_TICKS {
+            kernel.stop();
+        }
+    });
+}
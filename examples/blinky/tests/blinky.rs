@@ -0,0 +1,68 @@
+//! Blinky on the QV kernel: the LED follows the time event, both when the
+//! test ticks the wheel by hand and when `Kernel::run` drives the loop.
+
+use std::sync::{Arc, Mutex};
+
+use qf::active::{new_active_object, ActiveObjectId};
+use qf::event::Signal;
+use qf::time::{share_kernel, TimeEvent, TimeEventConfig, TimerWheel};
+use qf::Kernel;
+
+use blinky::{sig, Blinky};
+
+const BLINKY_ID: ActiveObjectId = ActiveObjectId::new(1);
+const HALF_PERIOD: u64 = 3;
+
+static STEPPED: Mutex<Vec<bool>> = Mutex::new(Vec::new());
+static RUN: Mutex<Vec<bool>> = Mutex::new(Vec::new());
+
+fn stepped_led(on: bool) {
+    STEPPED.lock().unwrap().push(on);
+}
+
+fn run_led(on: bool) {
+    RUN.lock().unwrap().push(on);
+}
+
+fn blinky(led: fn(bool)) -> (Arc<Kernel>, TimerWheel) {
+    let timer = TimeEvent::new(BLINKY_ID, TimeEventConfig::new(Signal(sig::TIMEOUT)));
+    let hsm = Blinky::new(Arc::clone(&timer), HALF_PERIOD, led).into_hsm();
+    let kernel = share_kernel(
+        Kernel::builder()
+            .register(new_active_object(BLINKY_ID, 1, hsm))
+            .build(),
+    );
+    let mut wheel = TimerWheel::new(kernel.clone());
+    wheel.register(timer);
+    (kernel, wheel)
+}
+
+#[test]
+fn the_led_toggles_every_half_period() {
+    let (kernel, wheel) = blinky(stepped_led);
+    kernel.start();
+    kernel.run_until_idle();
+    assert_eq!(*STEPPED.lock().unwrap(), [false]);
+
+    for tick in 1..=4 * HALF_PERIOD {
+        wheel.tick().unwrap();
+        kernel.run_until_idle();
+        let toggles = STEPPED.lock().unwrap().len() as u64 - 1;
+        assert_eq!(toggles, tick / HALF_PERIOD, "after tick {tick}");
+    }
+    assert_eq!(*STEPPED.lock().unwrap(), [false, true, false, true, false]);
+}
+
+#[test]
+fn the_qv_run_loop_drives_the_blinky() {
+    let (kernel, wheel) = blinky(run_led);
+    let mut ticks = 0;
+    kernel.run(|| {
+        wheel.tick().unwrap();
+        ticks += 1;
+        if ticks == 2 * HALF_PERIOD {
+            kernel.stop();
+        }
+    });
+    assert_eq!(*RUN.lock().unwrap(), [false, true, false]);
+}
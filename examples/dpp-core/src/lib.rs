@@ -0,0 +1,141 @@
+//! Dining Philosophers, independent of the board it runs on.
+//!
+//! The state machines, signals and the table's fork algorithm of the DPP
+//! example, shared by the POSIX binary in `examples/dpp` and the ESP32-C6
+//! one, so both exercise the same code and emit comparable QS traces.
+//!
+//! ```text
+//! Philo                                   Table
+//! ├── thinking  TIMEOUT ▶ hungry          └── active
+//! ├── hungry    EAT     ▶ eating              ├── serving  PAUSE ▶ paused
+//! └── eating    TIMEOUT ▶ thinking            └── paused   SERVE ▶ serving
+//! ```
+//!
+//! Everything board-specific — printing, QS user records, QUTest probes —
+//! goes through the [`Bsp`] hooks each state machine is given. Thinking and
+//! eating times come from [`Rng`], a fixed-width generator that produces
+//! the same sequence on 32- and 64-bit targets, so a host run and a target
+//! run schedule the philosophers identically.
+
+#![no_std]
+
+extern crate alloc;
+
+use core::fmt;
+
+use qf::active::ActiveObjectId;
+use qf::event::Signal;
+
+mod philo;
+mod table;
+
+pub use philo::{eating, hungry, philo_initial, thinking, PhiloData};
+pub use table::{active, paused, serving, table_initial, Forks, TableData, TableMsg};
+
+/// Number of philosophers (and forks).
+pub const N_PHILO: usize = 5;
+
+/// Id of the table active object.
+pub const TABLE_ID: ActiveObjectId = ActiveObjectId::new(1);
+
+/// Id of philosopher 0; the others follow.
+pub const PHILO_BASE_ID: u8 = 2;
+
+/// The philosophers' names, by index.
+pub static NAMES: [&str; N_PHILO] = ["Aristotle", "Kant", "Spinoza", "Marx", "Russell"];
+
+/// Id of philosopher `index`.
+pub const fn philo_id(index: usize) -> ActiveObjectId {
+    ActiveObjectId::new(PHILO_BASE_ID + index as u8)
+}
+
+/// Application signals.
+pub mod sig {
+    use qf::event::Signal;
+
+    use crate::TableMsg;
+
+    /// The table grants a philosopher its forks.
+    pub const EAT_SIG: Signal = Signal(4);
+    /// Stop handing out forks.
+    pub const PAUSE_SIG: Signal = Signal(6);
+    /// Resume handing out forks.
+    pub const SERVE_SIG: Signal = Signal(7);
+    /// QUTest stimulus; handled and ignored.
+    pub const TEST_SIG: Signal = Signal(8);
+    /// A philosopher's time event expired.
+    pub const TIMEOUT_SIG: Signal = Signal(10);
+
+    // Philosophers tell the table who they are.
+    qf::typed_signals! {
+        pub DONE_SIG: TableMsg = 5;
+        pub HUNGRY_SIG: TableMsg = 11;
+    }
+}
+
+/// Every application signal with its dictionary name, for `QS_SIG_DICT`.
+pub const SIGNALS: [(Signal, &str); 7] = [
+    (sig::EAT_SIG, "EAT_SIG"),
+    (sig::DONE_SIG.signal(), "DONE_SIG"),
+    (sig::PAUSE_SIG, "PAUSE_SIG"),
+    (sig::SERVE_SIG, "SERVE_SIG"),
+    (sig::TEST_SIG, "TEST_SIG"),
+    (sig::TIMEOUT_SIG, "TIMEOUT_SIG"),
+    (sig::HUNGRY_SIG.signal(), "HUNGRY_SIG"),
+];
+
+/// QS user record reporting a philosopher's state: index, then state name.
+pub const PHILO_STAT_RECORD: u8 = 100;
+
+/// QS user record reporting whether the table is paused.
+pub const PAUSED_STAT_RECORD: u8 = 101;
+
+/// Board services the state machines call out to.
+#[derive(Debug, Clone, Copy)]
+pub struct Bsp {
+    /// Philosopher `index` entered `"thinking"`, `"hungry"` or `"eating"`.
+    pub philo_stat: fn(index: usize, stat: &'static str),
+    /// The table was paused (`true`) or resumed.
+    pub paused_stat: fn(paused: bool),
+    /// One line of commentary from the table.
+    pub log: fn(fmt::Arguments<'_>),
+    /// QUTest probe for the state handler at `fun`, if one was set.
+    pub test_probe: fn(fun: u64) -> Option<u32>,
+}
+
+impl Bsp {
+    /// A board that reports nothing and never probes.
+    pub const SILENT: Bsp = Bsp {
+        philo_stat: |_, _| {},
+        paused_stat: |_| {},
+        log: |_| {},
+        test_probe: |_| None,
+    };
+}
+
+/// Small xorshift generator for thinking and eating times.
+#[derive(Debug, Clone)]
+pub struct Rng(u32);
+
+impl Rng {
+    /// A generator seeded with `seed` (zero is replaced, as xorshift would
+    /// stay at zero forever).
+    pub const fn new(seed: u32) -> Self {
+        Self(if seed == 0 { 0x9E37_79B9 } else { seed })
+    }
+
+    /// Next raw value.
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    /// Uniform-enough value in `lo..=hi`.
+    pub fn range(&mut self, lo: u64, hi: u64) -> u64 {
+        lo + u64::from(self.next_u32()) % (hi - lo + 1)
+    }
+}
@@ -0,0 +1,118 @@
+use alloc::sync::Arc;
+
+use qf::active::ActiveContext;
+use qf::event::{DynEvent, EventOf};
+use qf::hsm::reserved::*;
+use qf::time::TimeEvent;
+use qf::{q_handled, q_super, q_tran, QHsm, QHsmResult};
+
+use crate::sig::*;
+use crate::{philo_id, Bsp, Rng, TableMsg, NAMES, TABLE_ID};
+
+/// Extended state of one philosopher.
+pub struct PhiloData {
+    index: usize,
+    timer: Arc<TimeEvent>,
+    rng: Rng,
+    bsp: &'static Bsp,
+}
+
+impl PhiloData {
+    /// Philosopher `index`, timed by `timer` (targeting it with
+    /// [`TIMEOUT_SIG`]).
+    pub fn new(index: usize, timer: Arc<TimeEvent>, bsp: &'static Bsp) -> Self {
+        Self { index, timer, rng: Rng::new(index as u32 + 1), bsp }
+    }
+
+    /// The philosopher as a state machine, ready to register as an AO.
+    pub fn into_hsm(self) -> QHsm<PhiloData> {
+        QHsm::new(self, philo_initial)
+    }
+
+    /// Index at the table.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Name, for commentary.
+    pub fn name(&self) -> &'static str {
+        NAMES[self.index]
+    }
+
+    fn think_ticks(&mut self) -> u64 {
+        self.rng.range(3, 6)
+    }
+
+    fn eat_ticks(&mut self) -> u64 {
+        self.rng.range(2, 5)
+    }
+
+    fn post_table(&self, signal: EventOf<TableMsg>) {
+        if let Some(ctx) = ActiveContext::current() {
+            let _ = ctx.post(TABLE_ID, signal.event(TableMsg::new(philo_id(self.index))));
+        }
+    }
+
+    fn stat(&self, stat: &'static str) {
+        (self.bsp.philo_stat)(self.index, stat);
+    }
+}
+
+pub fn philo_initial(_sm: &mut PhiloData, _e: &DynEvent) -> QHsmResult<PhiloData> {
+    q_tran!(thinking)
+}
+
+pub fn thinking(sm: &mut PhiloData, e: &DynEvent) -> QHsmResult<PhiloData> {
+    match e.signal() {
+        Q_ENTRY_SIG => {
+            let ticks = sm.think_ticks();
+            sm.timer.arm(ticks, None);
+            sm.stat("thinking");
+            q_handled!()
+        }
+        Q_EXIT_SIG => {
+            sm.timer.disarm();
+            q_handled!()
+        }
+        TIMEOUT_SIG => {
+            // A non-zero QUTest probe keeps the philosopher thinking.
+            let probe = (sm.bsp.test_probe)(thinking as *const () as usize as u64);
+            if probe.is_some_and(|tp| tp != 0) {
+                return q_handled!();
+            }
+            q_tran!(hungry)
+        }
+        TEST_SIG => q_handled!(),
+        _ => q_super!(QHsm::<PhiloData>::top_state),
+    }
+}
+
+pub fn hungry(sm: &mut PhiloData, e: &DynEvent) -> QHsmResult<PhiloData> {
+    match e.signal() {
+        Q_ENTRY_SIG => {
+            sm.post_table(HUNGRY_SIG);
+            sm.stat("hungry");
+            q_handled!()
+        }
+        EAT_SIG => q_tran!(eating),
+        _ => q_super!(QHsm::<PhiloData>::top_state),
+    }
+}
+
+pub fn eating(sm: &mut PhiloData, e: &DynEvent) -> QHsmResult<PhiloData> {
+    match e.signal() {
+        Q_ENTRY_SIG => {
+            let ticks = sm.eat_ticks();
+            sm.timer.arm(ticks, None);
+            sm.stat("eating");
+            q_handled!()
+        }
+        Q_EXIT_SIG => {
+            sm.timer.disarm();
+            sm.post_table(DONE_SIG);
+            q_handled!()
+        }
+        TIMEOUT_SIG => q_tran!(thinking),
+        _ => q_super!(QHsm::<PhiloData>::top_state),
+    }
+}
@@ -0,0 +1,216 @@
+use qf::active::{ActiveContext, ActiveObjectId};
+use qf::event::DynEvent;
+use qf::hsm::reserved::*;
+use qf::{q_handled, q_super, q_tran, QHsm, QHsmResult};
+
+use crate::sig::*;
+use crate::{philo_id, Bsp, N_PHILO, NAMES, PHILO_BASE_ID};
+
+/// Payload of `HUNGRY_SIG` and `DONE_SIG`: who is asking.
+#[derive(Clone, Copy, Debug)]
+pub struct TableMsg {
+    pub philo: ActiveObjectId,
+}
+
+impl TableMsg {
+    pub fn new(philo: ActiveObjectId) -> Self {
+        Self { philo }
+    }
+
+    /// Index of the philosopher at the table.
+    pub fn index(&self) -> usize {
+        (self.philo.0 - PHILO_BASE_ID) as usize
+    }
+}
+
+/// The forks on the table and who is waiting for them.
+///
+/// Philosopher `n` eats with fork `n` and fork `left(n)`; a hungry
+/// philosopher whose forks are taken waits until a neighbour puts one down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forks {
+    free: [bool; N_PHILO],
+    hungry: [bool; N_PHILO],
+}
+
+impl Default for Forks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Forks {
+    /// All forks on the table, nobody waiting.
+    pub const fn new() -> Self {
+        Self { free: [true; N_PHILO], hungry: [false; N_PHILO] }
+    }
+
+    /// Neighbour on `n`'s left, with whom it shares fork `left(n)`.
+    pub const fn left(n: usize) -> usize {
+        (n + 1) % N_PHILO
+    }
+
+    /// Neighbour on `n`'s right, with whom it shares fork `n`.
+    pub const fn right(n: usize) -> usize {
+        (n + N_PHILO - 1) % N_PHILO
+    }
+
+    /// Whether both of `n`'s forks are free.
+    pub fn can_eat(&self, n: usize) -> bool {
+        self.free[n] && self.free[Self::left(n)]
+    }
+
+    /// Whether `n` is waiting for forks.
+    pub fn is_hungry(&self, n: usize) -> bool {
+        self.hungry[n]
+    }
+
+    /// `n` is hungry: hands it both forks and returns `true` if they are
+    /// free, otherwise marks it waiting.
+    pub fn request(&mut self, n: usize) -> bool {
+        if self.can_eat(n) {
+            self.grant(n);
+            true
+        } else {
+            self.hungry[n] = true;
+            false
+        }
+    }
+
+    /// `n` finished eating: puts its forks back.
+    pub fn release(&mut self, n: usize) {
+        self.free[n] = true;
+        self.free[Self::left(n)] = true;
+    }
+
+    /// Marks `n` waiting without trying to serve it.
+    pub fn wait(&mut self, n: usize) {
+        self.hungry[n] = true;
+    }
+
+    /// Serves `n` if it is waiting and both its forks are free.
+    pub fn serve(&mut self, n: usize) -> bool {
+        if self.hungry[n] && self.can_eat(n) {
+            self.grant(n);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn grant(&mut self, n: usize) {
+        self.free[n] = false;
+        self.free[Self::left(n)] = false;
+        self.hungry[n] = false;
+    }
+}
+
+/// Extended state of the table.
+pub struct TableData {
+    forks: Forks,
+    bsp: &'static Bsp,
+}
+
+impl TableData {
+    pub fn new(bsp: &'static Bsp) -> Self {
+        Self { forks: Forks::new(), bsp }
+    }
+
+    /// The table as a state machine, ready to register as an AO.
+    pub fn into_hsm(self) -> QHsm<TableData> {
+        QHsm::new(self, table_initial)
+    }
+
+    /// The forks, for inspection.
+    pub fn forks(&self) -> &Forks {
+        &self.forks
+    }
+
+    fn eat(&self, n: usize, prefix: &str) {
+        (self.bsp.log)(format_args!("{} {}", prefix, NAMES[n]));
+        if let Some(ctx) = ActiveContext::current() {
+            let _ = ctx.post(philo_id(n), DynEvent::empty_dyn(EAT_SIG));
+        }
+    }
+
+    fn serve(&mut self, ns: impl IntoIterator<Item = usize>) {
+        for n in ns {
+            if self.forks.serve(n) {
+                self.eat(n, "Table now serves");
+            }
+        }
+    }
+}
+
+pub fn table_initial(_sm: &mut TableData, _e: &DynEvent) -> QHsmResult<TableData> {
+    q_tran!(serving)
+}
+
+pub fn active(_sm: &mut TableData, e: &DynEvent) -> QHsmResult<TableData> {
+    match e.signal() {
+        Q_INIT_SIG => q_tran!(serving),
+        TEST_SIG => q_handled!(),
+        _ => q_super!(QHsm::<TableData>::top_state),
+    }
+}
+
+pub fn serving(sm: &mut TableData, e: &DynEvent) -> QHsmResult<TableData> {
+    match e.signal() {
+        Q_ENTRY_SIG => {
+            (sm.bsp.log)(format_args!("Table is ready"));
+            sm.serve(0..N_PHILO);
+            q_handled!()
+        }
+        s if s == HUNGRY_SIG.signal() => {
+            if let Some(n) = HUNGRY_SIG.payload(e).map(TableMsg::index) {
+                if sm.forks.request(n) {
+                    sm.eat(n, "Table grants forks to");
+                } else {
+                    (sm.bsp.log)(format_args!("{} waits for forks", NAMES[n]));
+                }
+            }
+            q_handled!()
+        }
+        s if s == DONE_SIG.signal() => {
+            if let Some(n) = DONE_SIG.payload(e).map(TableMsg::index) {
+                sm.forks.release(n);
+                (sm.bsp.log)(format_args!("{} is done eating", NAMES[n]));
+                sm.serve([Forks::right(n), Forks::left(n)]);
+            }
+            q_handled!()
+        }
+        PAUSE_SIG => q_tran!(paused),
+        _ => q_super!(active),
+    }
+}
+
+pub fn paused(sm: &mut TableData, e: &DynEvent) -> QHsmResult<TableData> {
+    match e.signal() {
+        Q_ENTRY_SIG => {
+            (sm.bsp.log)(format_args!("Table paused"));
+            (sm.bsp.paused_stat)(true);
+            q_handled!()
+        }
+        Q_EXIT_SIG => {
+            (sm.bsp.log)(format_args!("Table resumed"));
+            (sm.bsp.paused_stat)(false);
+            q_handled!()
+        }
+        SERVE_SIG => q_tran!(serving),
+        s if s == HUNGRY_SIG.signal() => {
+            if let Some(n) = HUNGRY_SIG.payload(e).map(TableMsg::index) {
+                sm.forks.wait(n);
+                (sm.bsp.log)(format_args!("{} waits for forks", NAMES[n]));
+            }
+            q_handled!()
+        }
+        s if s == DONE_SIG.signal() => {
+            if let Some(n) = DONE_SIG.payload(e).map(TableMsg::index) {
+                sm.forks.release(n);
+                (sm.bsp.log)(format_args!("{} is done eating", NAMES[n]));
+            }
+            q_handled!()
+        }
+        _ => q_super!(active),
+    }
+}
@@ -0,0 +1,72 @@
+//! The shared DPP state machines on the QV kernel, with a board that only
+//! records what the philosophers report.
+
+use std::sync::{Arc, Mutex};
+
+use qf::active::new_active_object;
+use qf::time::{share_kernel, TimeEvent, TimeEventConfig, TimerWheel};
+use qf::Kernel;
+
+use dpp_core::sig::TIMEOUT_SIG;
+use dpp_core::*;
+
+static STATS: Mutex<Vec<(usize, &'static str)>> = Mutex::new(Vec::new());
+
+static RECORDING: Bsp = Bsp {
+    philo_stat: |index, stat| STATS.lock().unwrap().push((index, stat)),
+    ..Bsp::SILENT
+};
+
+#[test]
+fn neighbours_never_share_a_fork() {
+    let mut forks = Forks::new();
+    assert!(forks.request(0));
+    assert!(!forks.request(1), "1 needs fork 1, which 0 holds");
+    assert!(!forks.request(4), "4 needs fork 0, which 0 holds");
+    assert!(forks.request(2));
+    assert!(forks.is_hungry(1) && forks.is_hungry(4));
+
+    forks.release(0);
+    assert!(!forks.serve(1), "2 still holds fork 2");
+    assert!(forks.serve(4));
+    assert!(!forks.is_hungry(4));
+}
+
+#[test]
+fn every_philosopher_eats_and_neighbours_never_together() {
+    let mut builder = Kernel::builder()
+        .register(new_active_object(TABLE_ID, 10, TableData::new(&RECORDING).into_hsm()));
+    let mut timers = Vec::new();
+    for index in 0..N_PHILO {
+        let timer = TimeEvent::new(philo_id(index), TimeEventConfig::new(TIMEOUT_SIG));
+        timers.push(Arc::clone(&timer));
+        let philo = PhiloData::new(index, timer, &RECORDING).into_hsm();
+        builder = builder.register(new_active_object(philo_id(index), index as u8 + 1, philo));
+    }
+    let kernel = share_kernel(builder.build());
+    let mut wheel = TimerWheel::new(kernel.clone());
+    for timer in timers {
+        wheel.register(timer);
+    }
+
+    kernel.start();
+    kernel.run_until_idle();
+    let mut eating = [false; N_PHILO];
+    let mut meals = [0; N_PHILO];
+    for _ in 0..80 {
+        wheel.tick().unwrap();
+        kernel.run_until_idle();
+        for (index, stat) in STATS.lock().unwrap().drain(..) {
+            eating[index] = stat == "eating";
+            if eating[index] {
+                meals[index] += 1;
+                assert!(
+                    !eating[Forks::left(index)] && !eating[Forks::right(index)],
+                    "{} eats next to a neighbour",
+                    NAMES[index]
+                );
+            }
+        }
+    }
+    assert!(meals.iter().all(|&m| m > 0), "meals: {meals:?}");
+}
@@ -1,9 +1,15 @@
 #![no_std]
 #![no_main]
 
+//! Dining Philosophers on the ESP32-C6 (bare metal, QK kernel).
+//!
+//! The state machines are the `dpp-core` ones the host binary runs, with
+//! the same names in the QS dictionaries, so a trace from the board lines
+//! up with a host trace record for record. Without the `qs` feature the
+//! board prints its commentary over the USB serial JTAG instead.
+
 extern crate alloc;
 
-use alloc::collections::VecDeque;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 
@@ -11,29 +17,18 @@ use esp_backtrace as _;
 use esp_hal::clock::CpuClock;
 use esp_hal::main;
 
-use qf::active::{new_active_object, ActiveObjectId};
-use qf::event::{DynEvent, DynPayload, Event, Signal};
+use dpp_core::sig::TIMEOUT_SIG;
+use dpp_core::{philo_id, Bsp, PhiloData, TableData, N_PHILO, TABLE_ID};
+use qf::active::{arc_as_runnable, ActiveObject};
 use qf::time::{TimeEvent, TimeEventConfig};
 #[cfg(feature = "qs")]
 use qf::time::TimeEventTraceInfo;
-use qf::{qm_tran, qm_super, qm_handled, qm_ignored, QMsm, QMState, QMsmResult};
 use qf_port_esp32_c6::{Esp32C6Port, Esp32C6QkRuntime, PortConfig};
 use qk::{QkKernel, QkKernelBuilder};
 
 #[cfg(feature = "qs")]
 use qs;
 
-static KERNEL: spin::Once<Arc<QkKernel>> = spin::Once::new();
-
-const N_PHILO: usize = 5;
-const TABLE_ID: ActiveObjectId = ActiveObjectId::new(1);
-const PHILO_BASE_ID: u8 = 2;
-
-const EAT_SIG: Signal = Signal(4);
-const DONE_SIG: Signal = Signal(5);
-const TIMEOUT_SIG: Signal = Signal(10);
-const HUNGRY_SIG: Signal = Signal(11);
-
 macro_rules! dpp_println {
     ($($arg:tt)*) => {
         #[cfg(not(feature = "qs"))]
@@ -42,15 +37,35 @@ macro_rules! dpp_println {
 }
 
 #[cfg(feature = "qs")]
-fn dict_handle(name: &str) -> u64 {
-    const FNV_OFFSET: u64 = 0xcbf29ce484222325;
-    const FNV_PRIME: u64 = 0x100000001b3;
-    let mut hash = FNV_OFFSET;
-    for byte in name.as_bytes() {
-        hash ^= u64::from(*byte);
-        hash = hash.wrapping_mul(FNV_PRIME);
+static TRACE: spin::Once<qf::TraceHook> = spin::Once::new();
+
+static BOARD: Bsp = Bsp {
+    philo_stat,
+    paused_stat,
+    log: |line| {
+        dpp_println!("{}", line);
+    },
+    test_probe: |_| None,
+};
+
+fn philo_stat(index: usize, stat: &'static str) {
+    dpp_println!("{} is {}", dpp_core::NAMES[index], stat);
+    #[cfg(feature = "qs")]
+    if let Some(hook) = TRACE.get() {
+        let mut buf = [0u8; 32];
+        let mut record = qs::UserRecordBuilder::encode_into(&mut buf);
+        record.push_u8(1, index as u8).push_str(stat);
+        if let Ok(payload) = record.finish() {
+            let _ = hook(dpp_core::PHILO_STAT_RECORD, payload, true);
+        }
+    }
+}
+
+fn paused_stat(_paused: bool) {
+    #[cfg(feature = "qs")]
+    if let Some(hook) = TRACE.get() {
+        let _ = hook(dpp_core::PAUSED_STAT_RECORD, &[_paused as u8], true);
     }
-    hash
 }
 
 #[cfg(feature = "qs")]
@@ -75,7 +90,6 @@ fn main() -> ! {
     let resources = build_application();
 
     let kernel = Arc::new(resources.builder.build().expect("kernel should build"));
-    KERNEL.call_once(|| Arc::clone(&kernel));
 
     let mut config = PortConfig::new();
     config.tick_hz = 100;
@@ -105,289 +119,89 @@ struct ApplicationResources {
     timers: Vec<Arc<TimeEvent>>,
 }
 
+#[cfg(feature = "qs")]
+fn emit_target_info(tracer: &qs::TracerHandle<EspPrintlnBackend>) {
+    use qs::predefined::{target_info_payload, TARGET_INFO};
+
+    let mut target_info = qs::TargetInfo::default();
+    target_info.obj_ptr_size = core::mem::size_of::<usize>() as u8;
+    target_info.fun_ptr_size = core::mem::size_of::<usize>() as u8;
+    let _ = tracer.emit(TARGET_INFO, &target_info_payload(&target_info));
+}
+
+/// Names known before any object exists; the objects join as they are
+/// built.
+#[cfg(feature = "qs")]
+fn static_dictionary() -> qs::dict::Dictionary {
+    use dpp_core::{active, eating, hungry, paused, philo_initial, serving, table_initial, thinking};
+    use qf::QHsm;
+
+    let mut dict = qs::qs_dictionary! {
+        usr {
+            dpp_core::PHILO_STAT_RECORD => "PHILO_STAT",
+            dpp_core::PAUSED_STAT_RECORD => "PAUSED_STAT",
+        }
+        fun {
+            QHsm::<TableData>::top_state => "QP::QHsm::top",
+            QHsm::<PhiloData>::top_state => "QP::QHsm::top",
+        }
+        fun "Philo" { philo_initial => "initial", thinking, hungry, eating }
+        fun "Table" { table_initial => "initial", active, serving, paused }
+    };
+    dict.signals(dpp_core::SIGNALS);
+    dict
+}
+
 fn build_application() -> ApplicationResources {
     let builder = QkKernel::builder();
 
     #[cfg(feature = "qs")]
-    let builder = {
+    let (builder, hook) = {
         let tracer = qs::Tracer::new(qs::QsConfig::default(), EspPrintlnBackend).into_handle();
-        let mut target_info = qs::TargetInfo::default();
-        target_info.obj_ptr_size = core::mem::size_of::<usize>() as u8;
-        target_info.fun_ptr_size = core::mem::size_of::<usize>() as u8;
-        let payload = qs::predefined::target_info_payload(&target_info);
-        let _ = tracer.emit(qs::predefined::TARGET_INFO, &payload);
-
-        let _ = tracer.emit(qs::predefined::SIG_DICT, &qs::predefined::sig_dict_payload(EAT_SIG.0, 0, "EAT"));
-        let _ = tracer.emit(qs::predefined::SIG_DICT, &qs::predefined::sig_dict_payload(DONE_SIG.0, 0, "DONE"));
-        let _ = tracer.emit(qs::predefined::SIG_DICT, &qs::predefined::sig_dict_payload(TIMEOUT_SIG.0, 0, "TIMEOUT"));
-        let _ = tracer.emit(qs::predefined::SIG_DICT, &qs::predefined::sig_dict_payload(HUNGRY_SIG.0, 0, "HUNGRY"));
-
-        // Register Table states and object
-        let _ = tracer.emit(qs::predefined::OBJ_DICT, &qs::predefined::obj_dict_payload(dict_handle("Table"), "Table"));
-        let _ = tracer.emit(qs::predefined::FUN_DICT, &qs::predefined::fun_dict_payload(&TABLE_ACTIVE as *const _ as usize as u64, "Table::active"));
-        let _ = tracer.emit(qs::predefined::FUN_DICT, &qs::predefined::fun_dict_payload(&TABLE_SERVING as *const _ as usize as u64, "Table::serving"));
-
-        // Register Philosopher states
-        let _ = tracer.emit(qs::predefined::FUN_DICT, &qs::predefined::fun_dict_payload(&PHILO_ACTIVE as *const _ as usize as u64, "Philo::active"));
-        let _ = tracer.emit(qs::predefined::FUN_DICT, &qs::predefined::fun_dict_payload(&PHILO_THINKING as *const _ as usize as u64, "Philo::thinking"));
-        let _ = tracer.emit(qs::predefined::FUN_DICT, &qs::predefined::fun_dict_payload(&PHILO_HUNGRY as *const _ as usize as u64, "Philo::hungry"));
-        let _ = tracer.emit(qs::predefined::FUN_DICT, &qs::predefined::fun_dict_payload(&PHILO_EATING as *const _ as usize as u64, "Philo::eating"));
-
-        // Register Philosopher objects and timers dynamically
-        for index in 0..N_PHILO {
-            let obj_name = alloc::format!("Philo::inst[{index}]");
-            let timer_name = alloc::format!("Philo::inst[{index}].m_timeEvt");
-            let _ = tracer.emit(qs::predefined::OBJ_DICT, &qs::predefined::obj_dict_payload(dict_handle(&obj_name), &obj_name));
-            let _ = tracer.emit(qs::predefined::OBJ_DICT, &qs::predefined::obj_dict_payload(dict_handle(&timer_name), &timer_name));
-        }
-
-        builder.with_trace_hook(tracer.hook())
+        emit_target_info(&tracer);
+        let hook = tracer.hook();
+        TRACE.call_once(|| hook.clone());
+        (builder.with_trace_hook(hook.clone()), hook)
     };
+    #[cfg(feature = "qs")]
+    let mut dict = static_dictionary();
 
-    let mut builder = builder;
-
-    let table_sm = QMsm::new(Table::new(), &TABLE_ACTIVE);
-    builder = builder
-        .register(new_active_object(TABLE_ID, 8, table_sm))
+    let table = ActiveObject::new(TABLE_ID, 8, TableData::new(&BOARD).into_hsm());
+    #[cfg(feature = "qs")]
+    dict.obj(table.behavior_addr(), "Table::inst");
+    let mut builder = builder
+        .register(arc_as_runnable(table))
         .expect("table registration should succeed");
 
     let mut timers = Vec::with_capacity(N_PHILO);
 
     for index in 0..N_PHILO {
-        let philo_id = ActiveObjectId::new(PHILO_BASE_ID + index as u8);
-        let timer = TimeEvent::new(philo_id, TimeEventConfig::new(TIMEOUT_SIG));
+        let id = philo_id(index);
+        let timer = TimeEvent::new(id, TimeEventConfig::new(TIMEOUT_SIG));
+        let philo_hsm = PhiloData::new(index, Arc::clone(&timer), &BOARD).into_hsm();
+        let philo = ActiveObject::new(id, 3 + index as u8, philo_hsm);
 
         #[cfg(feature = "qs")]
         {
-            let timer_name = alloc::format!("Philo::inst[{index}].m_timeEvt");
-            let obj_name = alloc::format!("Philo::inst[{index}]");
+            let philo_addr = philo.behavior_addr();
+            let timer_addr = qs::dict::addr_of(&*timer);
             timer.set_trace_meta(TimeEventTraceInfo {
-                time_event_addr: dict_handle(&timer_name),
-                target_addr: dict_handle(&obj_name),
+                time_event_addr: timer_addr,
+                target_addr: philo_addr,
                 tick_rate: 0,
             });
+            dict.obj(philo_addr, alloc::format!("Philo::inst[{index}]"))
+                .obj(timer_addr, alloc::format!("Philo::inst[{index}].m_timeEvt"));
         }
 
-        timers.push(Arc::clone(&timer));
-
-        let priority = 3 + index as u8;
-        let philo_data = Philosopher::new(index, philo_id, Arc::clone(&timer));
-        let philo_sm = QMsm::new(philo_data, &PHILO_ACTIVE);
-        let philo = new_active_object(
-            philo_id,
-            priority,
-            philo_sm,
-        );
+        timers.push(timer);
         builder = builder
-            .register(philo)
+            .register(arc_as_runnable(philo))
             .expect("philosopher registration should succeed");
     }
 
-    ApplicationResources { builder, timers }
-}
-
-struct Philosopher {
-    index: usize,
-    _id: ActiveObjectId,
-    timer: Arc<TimeEvent>,
-}
-
-impl Philosopher {
-    fn new(index: usize, id: ActiveObjectId, timer: Arc<TimeEvent>) -> Self {
-        Self {
-            index,
-            _id: id,
-            timer,
-        }
-    }
-
-    fn schedule_think(&self) {
-        let ticks = 3 + (self.index as u64 % 3);
-        self.timer.arm(ticks, None);
-    }
-
-    fn schedule_eat(&self) {
-        let ticks = 2 + (self.index as u64 % 3);
-        self.timer.arm(ticks, None);
-    }
-
-    fn post_table(&self, signal: Signal) {
-        if let Some(kernel) = KERNEL.get() {
-            let payload: DynPayload = Arc::new(TableMsg { index: self.index });
-            let evt = Event::with_arc(signal, payload);
-            let _ = kernel.post(TABLE_ID, evt);
-        }
-    }
-}
-
-static PHILO_ACTIVE: QMState<Philosopher> = QMState {
-    superstate: None,
-    state_handler: philo_active,
-    entry_action: None,
-    exit_action: None,
-    init_action: Some(|_sm| Some(&PHILO_THINKING)),
-};
-
-static PHILO_THINKING: QMState<Philosopher> = QMState {
-    superstate: Some(&PHILO_ACTIVE),
-    state_handler: philo_thinking,
-    entry_action: Some(|sm| {
-        sm.schedule_think();
-        dpp_println!("Philosopher {} starts thinking", sm.index);
-    }),
-    exit_action: Some(|sm| {
-        sm.timer.disarm();
-    }),
-    init_action: None,
-};
-
-static PHILO_HUNGRY: QMState<Philosopher> = QMState {
-    superstate: Some(&PHILO_ACTIVE),
-    state_handler: philo_hungry,
-    entry_action: Some(|sm| {
-        sm.post_table(HUNGRY_SIG);
-        dpp_println!("Philosopher {} is hungry", sm.index);
-    }),
-    exit_action: None,
-    init_action: None,
-};
-
-static PHILO_EATING: QMState<Philosopher> = QMState {
-    superstate: Some(&PHILO_ACTIVE),
-    state_handler: philo_eating,
-    entry_action: Some(|sm| {
-        sm.schedule_eat();
-        dpp_println!("Philosopher {} starts eating", sm.index);
-    }),
-    exit_action: Some(|sm| {
-        sm.timer.disarm();
-        sm.post_table(DONE_SIG);
-        dpp_println!("Philosopher {} returns to thinking", sm.index);
-    }),
-    init_action: None,
-};
-
-fn philo_active(_sm: &mut Philosopher, _e: &DynEvent) -> QMsmResult<Philosopher> {
-    qm_ignored!()
-}
-
-fn philo_thinking(_sm: &mut Philosopher, e: &DynEvent) -> QMsmResult<Philosopher> {
-    match e.signal() {
-        TIMEOUT_SIG => qm_tran!(&PHILO_HUNGRY),
-        _ => qm_super!(&PHILO_ACTIVE),
-    }
-}
-
-fn philo_hungry(_sm: &mut Philosopher, e: &DynEvent) -> QMsmResult<Philosopher> {
-    match e.signal() {
-        EAT_SIG => qm_tran!(&PHILO_EATING),
-        _ => qm_super!(&PHILO_ACTIVE),
-    }
-}
-
-fn philo_eating(_sm: &mut Philosopher, e: &DynEvent) -> QMsmResult<Philosopher> {
-    match e.signal() {
-        TIMEOUT_SIG => qm_tran!(&PHILO_THINKING),
-        _ => qm_super!(&PHILO_ACTIVE),
-    }
-}
-
-struct Table {
-    forks: [bool; N_PHILO],
-    waiting: VecDeque<usize>,
-}
-
-impl Table {
-    fn new() -> Self {
-        Self {
-            forks: [false; N_PHILO],
-            waiting: VecDeque::new(),
-        }
-    }
-
-    fn left(index: usize) -> usize {
-        index
-    }
-
-    fn right(index: usize) -> usize {
-        (index + 1) % N_PHILO
-    }
-
-    fn try_grant(&mut self, index: usize) -> bool {
-        if !self.forks[Self::left(index)] && !self.forks[Self::right(index)] {
-            self.forks[Self::left(index)] = true;
-            self.forks[Self::right(index)] = true;
-            let philo_id = ActiveObjectId::new(PHILO_BASE_ID + index as u8);
-            if let Some(kernel) = KERNEL.get() {
-                let _ = kernel.post(philo_id, DynEvent::empty_dyn(EAT_SIG));
-            }
-            true
-        } else {
-            false
-        }
-    }
-
-    fn release_forks(&mut self, index: usize) {
-        self.forks[Self::left(index)] = false;
-        self.forks[Self::right(index)] = false;
-    }
-}
-
-static TABLE_ACTIVE: QMState<Table> = QMState {
-    superstate: None,
-    state_handler: table_active,
-    entry_action: None,
-    exit_action: None,
-    init_action: Some(|_sm| Some(&TABLE_SERVING)),
-};
-
-static TABLE_SERVING: QMState<Table> = QMState {
-    superstate: Some(&TABLE_ACTIVE),
-    state_handler: table_serving,
-    entry_action: Some(|_sm| {
-        dpp_println!("Table active object initialised");
-    }),
-    exit_action: None,
-    init_action: None,
-};
-
-fn table_active(_sm: &mut Table, _e: &DynEvent) -> QMsmResult<Table> {
-    qm_ignored!()
-}
-
-fn table_serving(sm: &mut Table, e: &DynEvent) -> QMsmResult<Table> {
-    match e.signal() {
-        HUNGRY_SIG => {
-            let payload = e.payload.clone();
-            let msg = Arc::downcast::<TableMsg>(payload).expect("table message downcast");
-            let index = msg.index;
-            dpp_println!("Table: philosopher {} requests forks", index);
-            if !sm.try_grant(index) {
-                sm.waiting.push_back(index);
-            }
-            qm_handled!()
-        }
-        DONE_SIG => {
-            let payload = e.payload.clone();
-            let msg = Arc::downcast::<TableMsg>(payload).expect("table message downcast");
-            let index = msg.index;
-            dpp_println!("Table: philosopher {} releases forks", index);
-            sm.release_forks(index);
-
-            let mut still_waiting = VecDeque::new();
-            while let Some(waiting_index) = sm.waiting.pop_front() {
-                if !sm.try_grant(waiting_index) {
-                    still_waiting.push_back(waiting_index);
-                }
-            }
-            sm.waiting = still_waiting;
-            qm_handled!()
-        }
-        _ => qm_super!(&TABLE_ACTIVE),
-    }
-}
+    #[cfg(feature = "qs")]
+    let _ = dict.emit(&hook);
 
-#[derive(Debug)]
-struct TableMsg {
-    index: usize,
+    ApplicationResources { builder, timers }
 }
@@ -0,0 +1,43 @@
+//! Host board services for the shared DPP state machines: commentary on
+//! stdout, `PHILO_STAT`/`PAUSED_STAT` user records and QUTest probes on the
+//! QS port.
+
+use dpp_core::{Bsp, NAMES, PAUSED_STAT_RECORD, PHILO_STAT_RECORD};
+use qs::qutest::{make_probe_record, take_test_probe};
+use qs::records::infra::TEST_PROBE as QS_TEST_PROBE_GET;
+use qs::UserRecordBuilder;
+
+use crate::PORT;
+
+pub(crate) static HOST_BSP: Bsp = Bsp {
+    philo_stat,
+    paused_stat,
+    log: |line| println!("{line}"),
+    test_probe,
+};
+
+fn philo_stat(index: usize, stat: &'static str) {
+    println!("{} is {}", NAMES[index], stat);
+    if let Some(port) = PORT.get() {
+        let mut buf = [0u8; 32];
+        let mut record = UserRecordBuilder::encode_into(&mut buf);
+        record.push_u8(1, index as u8).push_str(stat);
+        if let Ok(payload) = record.finish() {
+            let _ = port.emit_record(PHILO_STAT_RECORD, payload, true);
+        }
+    }
+}
+
+fn paused_stat(paused: bool) {
+    if let Some(port) = PORT.get() {
+        let _ = port.emit_record(PAUSED_STAT_RECORD, &[paused as u8], true);
+    }
+}
+
+fn test_probe(fun: u64) -> Option<u32> {
+    let tp = take_test_probe(fun)?;
+    if let Some(port) = PORT.get() {
+        let _ = port.emit_record(QS_TEST_PROBE_GET, &make_probe_record(fun, tp), false);
+    }
+    Some(tp)
+}
@@ -7,17 +7,22 @@ use std::thread;
 use std::time::Duration;
 use qf::active::ActiveObjectId;
 use qf::event::{DynEvent, Signal};
-use qf_port_posix::PosixPort;
+use qf_port_posix::{PosixPort, ReconnectConfig};
 use qs::rx::{cmd as rx_cmd, RxCmd, RxParser};
-use qs::{clear_test_probes, set_test_probe, GlbFilter, TargetInfo};
+use qs::{clear_test_probes, peek, query, set_test_probe, GlbFilter, QsConfig, RecordSizes, TargetInfo};
 
 pub(crate) fn init_port() -> Arc<PosixPort> {
+    // Ids rather than addresses, so dictionaries qspy cached stay valid.
+    if env::var_os("QS_STABLE_IDS").is_some() {
+        qs::symbols::set_mode(qs::symbols::SymbolMode::StableIds);
+    }
     let cmd_addr = env::var("QSPY_CMD_ADDR").unwrap_or_else(|_| "127.0.0.1:6601".to_string());
     let port = if let Ok(raw_addr) = env::var("QSPY_ADDR") {
         let addr = raw_addr.trim().to_string();
-        match PosixPort::connect(&addr) {
+        // Survive qspy restarts: buffer while it is away, replay on return.
+        match PosixPort::connect_resilient(&addr, ReconnectConfig::default()) {
             Ok(port) => {
-                println!("QS tracing connected to tcp://{addr}");
+                println!("QS tracing to tcp://{addr}");
                 port
             }
             Err(err) => {
@@ -33,10 +38,21 @@ pub(crate) fn init_port() -> Arc<PosixPort> {
 
     let port = Arc::new(port);
     PORT.set(Arc::clone(&port)).unwrap_or_else(|_| panic!("port already set"));
+    qs::reset::set_reset_handler(reset_target);
     start_command_channel(&cmd_addr, Arc::clone(&port));
     port
 }
 
+/// `QS_RX_RESET`: sends what the tracer still holds, then starts the
+/// application over, which announces itself to qspy with a fresh
+/// `TARGET_INFO` and dictionaries.
+fn reset_target() -> ! {
+    if let Some(port) = PORT.get() {
+        let _ = port.flush();
+    }
+    qf_port_posix::restart_process()
+}
+
 fn connect_udp_default() -> PosixPort {
     let udp_addr = env::var("QSPY_UDP_ADDR").unwrap_or_else(|_| "127.0.0.1:7701".to_string());
     match PosixPort::connect_udp(&udp_addr) {
@@ -119,7 +135,8 @@ impl QsRxContext {
                 }
             }
             RxCmd::Reset => {
-                eprintln!("QS-RX RESET (not implemented in this demo)");
+                qs::reset::on_reset();
+                let _ = self.port.emit_record(QS_RX_STATUS, &[0x80 | rx_cmd::RESET], false);
             }
             RxCmd::Command { id, p1, p2, p3 } => {
                 self.ack(rx_cmd::COMMAND);
@@ -157,11 +174,22 @@ impl QsRxContext {
             RxCmd::Tick { .. }       => self.ack_done(rx_cmd::TICK),
             RxCmd::AoFilter { .. }   => self.ack_done(rx_cmd::AO_FILTER),
             RxCmd::LocFilter { .. }  => self.ack_done(rx_cmd::LOC_FILTER),
-            RxCmd::CurrObj { .. }    => self.ack_done(rx_cmd::CURR_OBJ),
-            RxCmd::QueryCurr { .. }  => self.ack_done(rx_cmd::QUERY_CURR),
-            RxCmd::Peek { .. }       => self.ack_done(rx_cmd::PEEK),
-            RxCmd::Poke { .. }       => self.ack_done(rx_cmd::POKE),
-            RxCmd::Fill { .. }       => self.ack_done(rx_cmd::FILL),
+            RxCmd::CurrObj { kind, obj_ptr } =>
+                self.reply(rx_cmd::CURR_OBJ, query::set_current_raw(kind, obj_ptr)),
+            RxCmd::QueryCurr { kind } => {
+                let hook = self.port.trace_hook();
+                let sizes = RecordSizes::from_target_info(&TargetInfo::default());
+                self.reply(rx_cmd::QUERY_CURR, query::query_raw(&hook, &sizes, kind));
+            }
+            RxCmd::Peek { addr, offset, size, num } => {
+                let hook = self.port.trace_hook();
+                let max_len = QsConfig::default().max_record_len;
+                self.reply(rx_cmd::PEEK, peek::peek(&hook, max_len, addr, offset, size, num));
+            }
+            RxCmd::Poke { addr, offset, size, num, data } =>
+                self.reply(rx_cmd::POKE, peek::poke(addr, offset, size, num, &data)),
+            RxCmd::Fill { addr, offset, size, num, data } =>
+                self.reply(rx_cmd::FILL, peek::fill(addr, offset, size, num, &data)),
             RxCmd::Unknown { cmd, .. } => {
                 eprintln!("unknown QS-RX record {cmd:#04x}");
                 let _ = self.port.emit_record(QS_RX_STATUS, &[0x80 | 0x43u8], false);
@@ -181,4 +209,15 @@ impl QsRxContext {
         self.ack(rec_id);
         self.done(rec_id);
     }
+
+    /// Acknowledges a memory or query command, or reports it failed.
+    fn reply<E: std::fmt::Display>(&self, rec_id: u8, result: Result<(), E>) {
+        match result {
+            Ok(()) => self.ack_done(rec_id),
+            Err(err) => {
+                eprintln!("QS-RX {rec_id:#04x} refused: {err}");
+                let _ = self.port.emit_record(QS_RX_STATUS, &[0x80 | rec_id], false);
+            }
+        }
+    }
 }
@@ -24,7 +24,7 @@ use hal_rvsis::esp32c6::{Esp32C6Pin, Esp32C6Spi, radio::Sx1262};
 use qf::active::{new_active_object, ActiveObjectId, ActiveRunnable};
 use qf::event::{DynEvent, DynPayload, Event, Signal};
 use qf::hsm::reserved::*;
-use qf::time::{TimeEvent, TimeEventConfig};
+use qf::time::{TickDuration, TimeEvent, TimeEventConfig};
 use qf::{q_handled, q_super, q_tran, QHsm, QHsmResult};
 use qf_port_esp32_c6::{Esp32C6Port, Esp32C6QkRuntime, PortConfig, rf_isr};
 use qk::QkKernel;
@@ -76,7 +76,7 @@ fn sending(sm: &mut LoRaSenderData, e: &DynEvent) -> QHsmResult<LoRaSenderData>
     match e.signal().0 {
         Q_ENTRY_SIG_VAL => {
             println!("LoRaSenderAO: started — sending every 5 ticks");
-            sm.timer.arm(5, Some(5));
+            sm.timer.arm(5, Some(TickDuration::from_ticks(5)));
             q_handled!()
         }
         10 => { // TIMEOUT_SIG
@@ -20,7 +20,7 @@ use hal_lxsis::esp32s3::{Esp32S3Pin, Esp32S3Spi, radio::Sx1276};
 use qf::active::{new_active_object, ActiveObjectId, ActiveRunnable};
 use qf::event::{DynEvent, DynPayload, Event, Signal};
 use qf::hsm::reserved::*;
-use qf::time::{TimeEvent, TimeEventConfig};
+use qf::time::{TickDuration, TimeEvent, TimeEventConfig};
 use qf::{q_handled, q_super, q_tran, QHsm, QHsmResult};
 use qf_port_esp32_s3::{Esp32S3Port, Esp32S3QkRuntime, PortConfig, rf_isr};
 use qk::QkKernel;
@@ -72,7 +72,7 @@ fn sending(sm: &mut LoRaSenderData, e: &DynEvent) -> QHsmResult<LoRaSenderData>
     match e.signal().0 {
         Q_ENTRY_SIG_VAL => {
             println!("LoRaSenderAO: started — sending every 5 ticks");
-            sm.timer.arm(5, Some(5));
+            sm.timer.arm(5, Some(TickDuration::from_ticks(5)));
             q_handled!()
         }
         10 => { // TIMEOUT_SIG
@@ -31,7 +31,7 @@ use qf::active::{
 };
 use qf::event::{DynEvent, DynPayload, Event, Signal};
 use qf::hsm::reserved::*;
-use qf::time::{TimeEvent, TimeEventConfig};
+use qf::time::{TickDuration, TimeEvent, TimeEventConfig};
 use qf::{q_handled, q_super, q_tran, QHsm, QHsmResult, TraceError};
 use qf_port_posix::{PosixPort, PosixQkRuntime};
 use qk::{QkKernel, QkKernelError};
@@ -79,7 +79,7 @@ fn sending(sm: &mut LoRaSenderData, e: &DynEvent) -> QHsmResult<LoRaSenderData>
     match e.signal().0 {
         Q_ENTRY_SIG_VAL => {
             println!("LoRaSenderAO: started — sending every 5 ticks");
-            sm.timer.arm(5, Some(5));
+            sm.timer.arm(5, Some(TickDuration::from_ticks(5)));
             q_handled!()
         }
         10 => { // TIMEOUT_SIG
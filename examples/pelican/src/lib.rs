@@ -0,0 +1,321 @@
+//! PELICAN (PEdestrian LIght CONtrolled) crossing.
+//!
+//! The second canonical QP example. Where DPP is about active objects
+//! talking to each other, PELICAN is one active object whose behavior lives
+//! in the state hierarchy:
+//!
+//! ```text
+//! top
+//! ├── operational ──────────────── OFF ──▶ offline
+//! │   ├── carsEnabled
+//! │   │   ├── carsGreen
+//! │   │   │   ├── carsGreenNoPed    PEDS_WAITING ▶ carsGreenPedWait, TIMEOUT ▶ carsGreenInt
+//! │   │   │   ├── carsGreenInt      PEDS_WAITING ▶ carsYellow
+//! │   │   │   └── carsGreenPedWait  TIMEOUT ▶ carsYellow
+//! │   │   └── carsYellow            TIMEOUT ▶ pedsEnabled
+//! │   └── pedsEnabled
+//! │       ├── pedsWalk              TIMEOUT ▶ pedsFlash
+//! │       └── pedsFlash             TIMEOUT × 2·FLASH_COUNT+1 ▶ carsEnabled
+//! └── offline ───────────────────── ON ──▶ history of operational
+//! ```
+//!
+//! Entry and exit actions drive the lamps, every timeout comes from one time
+//! event armed on entry, and switching the crossing back `ON` returns to the
+//! half of the cycle (cars or pedestrians) it was switched off in, through
+//! shallow history.
+
+use std::sync::{Arc, Mutex};
+
+use qf::event::{DynEvent, Signal};
+use qf::hsm::reserved::*;
+use qf::time::{TickDuration, TimeEvent};
+use qf::{q_handled, q_super, q_tran, q_tran_hist, QHsm, QHsmResult, StateHandler};
+
+/// Application signals.
+pub mod sig {
+    /// A pedestrian pressed the button.
+    pub const PEDS_WAITING: u16 = 4;
+    /// The crossing's time event expired.
+    pub const TIMEOUT: u16 = 5;
+    /// Switch the crossing off (flashing).
+    pub const OFF: u16 = 6;
+    /// Switch the crossing back on.
+    pub const ON: u16 = 7;
+}
+
+/// Minimum green time for cars, in ticks.
+pub const CARS_GREEN_MIN_TOUT: u64 = 8;
+/// Yellow time for cars.
+pub const CARS_YELLOW_TOUT: u64 = 3;
+/// Walk time for pedestrians.
+pub const PEDS_WALK_TOUT: u64 = 6;
+/// Half-period of the flashing "don't walk" and offline lamps.
+pub const FLASH_TOUT: u64 = 1;
+/// Flashes of "don't walk" before cars get green again.
+pub const FLASH_COUNT: u8 = 3;
+
+/// Car lamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cars {
+    Red,
+    Yellow,
+    Green,
+    Blank,
+}
+
+/// Pedestrian lamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Peds {
+    DontWalk,
+    Walk,
+    Blank,
+}
+
+/// Both lamps at one moment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lamps {
+    pub cars: Cars,
+    pub peds: Peds,
+}
+
+/// Every lamp change, in order; shared with whoever watches the crossing.
+pub type LampLog = Arc<Mutex<Vec<Lamps>>>;
+
+/// Extended state of the crossing.
+pub struct Pelican {
+    timer: Arc<TimeEvent>,
+    lamps: Lamps,
+    log: LampLog,
+    flash_ctr: u8,
+}
+
+impl Pelican {
+    /// A crossing timed by `timer` (targeting this AO with [`sig::TIMEOUT`]),
+    /// reporting lamp changes to `log`.
+    pub fn new(timer: Arc<TimeEvent>, log: LampLog) -> Self {
+        Self {
+            timer,
+            lamps: Lamps { cars: Cars::Blank, peds: Peds::Blank },
+            log,
+            flash_ctr: 0,
+        }
+    }
+
+    /// The crossing as a state machine, ready to register as an AO.
+    pub fn into_hsm(self) -> QHsm<Pelican> {
+        QHsm::new(self, initial)
+    }
+
+    fn cars(&mut self, cars: Cars) {
+        self.set(Lamps { cars, ..self.lamps });
+    }
+
+    fn peds(&mut self, peds: Peds) {
+        self.set(Lamps { peds, ..self.lamps });
+    }
+
+    fn set(&mut self, lamps: Lamps) {
+        if lamps != self.lamps {
+            self.lamps = lamps;
+            self.log.lock().unwrap().push(lamps);
+        }
+    }
+
+    fn arm(&self, ticks: u64) {
+        self.timer.arm(ticks, None);
+    }
+
+    fn arm_flashing(&self) {
+        self.timer.arm(FLASH_TOUT, Some(TickDuration::from_ticks(FLASH_TOUT)));
+    }
+}
+
+/// Every state handler with its dictionary name, for `QS_FUN_DICT`.
+pub const STATES: [(StateHandler<Pelican>, &str); 12] = [
+    (initial, "Pelican::initial"),
+    (operational, "Pelican::operational"),
+    (cars_enabled, "Pelican::carsEnabled"),
+    (cars_green, "Pelican::carsGreen"),
+    (cars_green_no_ped, "Pelican::carsGreenNoPed"),
+    (cars_green_int, "Pelican::carsGreenInt"),
+    (cars_green_ped_wait, "Pelican::carsGreenPedWait"),
+    (cars_yellow, "Pelican::carsYellow"),
+    (peds_enabled, "Pelican::pedsEnabled"),
+    (peds_walk, "Pelican::pedsWalk"),
+    (peds_flash, "Pelican::pedsFlash"),
+    (offline, "Pelican::offline"),
+];
+
+pub fn initial(_me: &mut Pelican, _e: &DynEvent) -> QHsmResult<Pelican> {
+    q_tran!(operational)
+}
+
+pub fn operational(me: &mut Pelican, e: &DynEvent) -> QHsmResult<Pelican> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => {
+            me.set(Lamps { cars: Cars::Red, peds: Peds::DontWalk });
+            q_handled!()
+        }
+        Q_EXIT_SIG_VAL => {
+            me.timer.disarm();
+            q_handled!()
+        }
+        Q_INIT_SIG_VAL => q_tran!(cars_enabled),
+        sig::OFF => q_tran!(offline),
+        _ => q_super!(QHsm::<Pelican>::top_state),
+    }
+}
+
+pub fn cars_enabled(me: &mut Pelican, e: &DynEvent) -> QHsmResult<Pelican> {
+    match e.signal().0 {
+        Q_EXIT_SIG_VAL => {
+            me.cars(Cars::Red);
+            q_handled!()
+        }
+        Q_INIT_SIG_VAL => q_tran!(cars_green),
+        _ => q_super!(operational),
+    }
+}
+
+pub fn cars_green(me: &mut Pelican, e: &DynEvent) -> QHsmResult<Pelican> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => {
+            me.cars(Cars::Green);
+            me.arm(CARS_GREEN_MIN_TOUT);
+            q_handled!()
+        }
+        Q_EXIT_SIG_VAL => {
+            me.timer.disarm();
+            q_handled!()
+        }
+        Q_INIT_SIG_VAL => q_tran!(cars_green_no_ped),
+        _ => q_super!(cars_enabled),
+    }
+}
+
+/// Green, minimum time not yet over, nobody waiting.
+pub fn cars_green_no_ped(_me: &mut Pelican, e: &DynEvent) -> QHsmResult<Pelican> {
+    match e.signal().0 {
+        sig::PEDS_WAITING => q_tran!(cars_green_ped_wait),
+        sig::TIMEOUT => q_tran!(cars_green_int),
+        _ => q_super!(cars_green),
+    }
+}
+
+/// Green past its minimum time: the next pedestrian stops the cars at once.
+pub fn cars_green_int(_me: &mut Pelican, e: &DynEvent) -> QHsmResult<Pelican> {
+    match e.signal().0 {
+        sig::PEDS_WAITING => q_tran!(cars_yellow),
+        _ => q_super!(cars_green),
+    }
+}
+
+/// Green with a pedestrian waiting for the minimum time to run out.
+pub fn cars_green_ped_wait(_me: &mut Pelican, e: &DynEvent) -> QHsmResult<Pelican> {
+    match e.signal().0 {
+        sig::TIMEOUT => q_tran!(cars_yellow),
+        _ => q_super!(cars_green),
+    }
+}
+
+pub fn cars_yellow(me: &mut Pelican, e: &DynEvent) -> QHsmResult<Pelican> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => {
+            me.cars(Cars::Yellow);
+            me.arm(CARS_YELLOW_TOUT);
+            q_handled!()
+        }
+        Q_EXIT_SIG_VAL => {
+            me.timer.disarm();
+            q_handled!()
+        }
+        sig::TIMEOUT => q_tran!(peds_enabled),
+        _ => q_super!(cars_enabled),
+    }
+}
+
+pub fn peds_enabled(me: &mut Pelican, e: &DynEvent) -> QHsmResult<Pelican> {
+    match e.signal().0 {
+        Q_EXIT_SIG_VAL => {
+            me.peds(Peds::DontWalk);
+            q_handled!()
+        }
+        Q_INIT_SIG_VAL => q_tran!(peds_walk),
+        _ => q_super!(operational),
+    }
+}
+
+pub fn peds_walk(me: &mut Pelican, e: &DynEvent) -> QHsmResult<Pelican> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => {
+            me.peds(Peds::Walk);
+            me.arm(PEDS_WALK_TOUT);
+            q_handled!()
+        }
+        Q_EXIT_SIG_VAL => {
+            me.timer.disarm();
+            q_handled!()
+        }
+        sig::TIMEOUT => q_tran!(peds_flash),
+        _ => q_super!(peds_enabled),
+    }
+}
+
+/// "Don't walk" flashing; ends on a steady "don't walk".
+pub fn peds_flash(me: &mut Pelican, e: &DynEvent) -> QHsmResult<Pelican> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => {
+            me.flash_ctr = FLASH_COUNT * 2 + 1;
+            me.arm_flashing();
+            q_handled!()
+        }
+        Q_EXIT_SIG_VAL => {
+            me.timer.disarm();
+            q_handled!()
+        }
+        sig::TIMEOUT => {
+            me.flash_ctr -= 1;
+            if me.flash_ctr == 0 {
+                return q_tran!(cars_enabled);
+            }
+            me.peds(if me.flash_ctr & 1 == 0 { Peds::DontWalk } else { Peds::Blank });
+            q_handled!()
+        }
+        _ => q_super!(peds_enabled),
+    }
+}
+
+/// Switched off: cars see flashing red, pedestrians flashing "don't walk".
+pub fn offline(me: &mut Pelican, e: &DynEvent) -> QHsmResult<Pelican> {
+    match e.signal().0 {
+        Q_ENTRY_SIG_VAL => {
+            me.flash_ctr = 0;
+            me.arm_flashing();
+            q_handled!()
+        }
+        Q_EXIT_SIG_VAL => {
+            me.timer.disarm();
+            q_handled!()
+        }
+        sig::TIMEOUT => {
+            me.flash_ctr ^= 1;
+            let lamps = if me.flash_ctr == 1 {
+                Lamps { cars: Cars::Blank, peds: Peds::Blank }
+            } else {
+                Lamps { cars: Cars::Red, peds: Peds::DontWalk }
+            };
+            me.set(lamps);
+            q_handled!()
+        }
+        sig::ON => q_tran_hist!(operational),
+        _ => q_super!(QHsm::<Pelican>::top_state),
+    }
+}
+
+/// Every application signal with its dictionary name, for `QS_SIG_DICT`.
+pub const SIGNALS: [(Signal, &str); 4] = [
+    (Signal(sig::PEDS_WAITING), "PEDS_WAITING_SIG"),
+    (Signal(sig::TIMEOUT), "TIMEOUT_SIG"),
+    (Signal(sig::OFF), "OFF_SIG"),
+    (Signal(sig::ON), "ON_SIG"),
+];
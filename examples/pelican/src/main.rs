@@ -0,0 +1,110 @@
+//! PELICAN crossing on the `qk` preemptive kernel, traced to QSPY.
+//!
+//! Mirrors the QP `pelican` example: a scripted pedestrian presses the
+//! button, the crossing cycles through cars-yellow, walk and flashing
+//! don't-walk, and is later switched off and back on, resuming through
+//! history. Run `qspy` on UDP 7701 (or set `QSPY_UDP_ADDR`) to watch the
+//! state-machine trace.
+
+use std::env;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use qf::active::{new_active_object, ActiveObjectId};
+use qf::event::{DynEvent, Signal};
+use qf::time::{TimeEvent, TimeEventConfig, TimeEventTraceInfo};
+use qf::{QHsm, TraceError};
+use qf_port_posix::{PosixPort, PosixQkRuntime};
+use qk::QkKernel;
+use qs::TargetInfo;
+
+use pelican::{sig, LampLog, Pelican, SIGNALS, STATES};
+
+const PELICAN_ID: ActiveObjectId = ActiveObjectId::new(1);
+const PELICAN_OBJ: u64 = 0x1000;
+const PELICAN_TIMER_OBJ: u64 = 0x1010;
+
+/// `(tick, signal)` posted to the crossing by the "outside world".
+const SCRIPT: [(u32, u16); 4] = [
+    (3, sig::PEDS_WAITING),
+    (25, sig::PEDS_WAITING),
+    (36, sig::OFF),
+    (42, sig::ON),
+];
+const RUN_TICKS: u32 = 60;
+
+fn connect_port() -> PosixPort {
+    let addr = env::var("QSPY_UDP_ADDR").unwrap_or_else(|_| "127.0.0.1:7701".to_string());
+    match PosixPort::connect_udp(&addr) {
+        Ok(port) => {
+            println!("QS tracing connected to udp://{addr}");
+            port
+        }
+        Err(err) => {
+            eprintln!("failed to connect to qspy at {addr}: {err}; falling back to stdout");
+            PosixPort::new()
+        }
+    }
+}
+
+fn emit_dictionaries(port: &PosixPort) -> Result<(), TraceError> {
+    port.emit_target_info(&TargetInfo::default())?;
+    port.emit_obj_dict(PELICAN_OBJ, "Pelican::inst")?;
+    port.emit_obj_dict(PELICAN_TIMER_OBJ, "Pelican::inst.m_timeEvt")?;
+    port.emit_fun_dict(QHsm::<Pelican>::top_state as *const () as usize as u64, "QP::QHsm::top")?;
+    for (state, name) in STATES {
+        port.emit_fun_dict(state as *const () as usize as u64, name)?;
+    }
+    for (signal, name) in SIGNALS {
+        port.emit_sig_dict(signal.0, 0, name)?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("Starting PELICAN crossing demo");
+
+    let port = connect_port();
+    if let Err(err) = emit_dictionaries(&port) {
+        eprintln!("failed to send QS dictionaries: {err:?}");
+    }
+
+    let timer = TimeEvent::new(PELICAN_ID, TimeEventConfig::new(Signal(sig::TIMEOUT)));
+    timer.set_trace_meta(TimeEventTraceInfo {
+        time_event_addr: PELICAN_TIMER_OBJ,
+        target_addr: PELICAN_OBJ,
+        tick_rate: 0,
+    });
+    let lamps: LampLog = Arc::new(Mutex::new(Vec::new()));
+    let pelican = Pelican::new(Arc::clone(&timer), Arc::clone(&lamps)).into_hsm();
+
+    let builder = QkKernel::builder().register(new_active_object(PELICAN_ID, 1, pelican))?;
+    let mut runtime = PosixQkRuntime::with_port(builder, &port)?;
+    runtime.register_time_event(timer);
+    let kernel = runtime.kernel();
+    runtime.run_until_idle();
+
+    let mut shown = 0;
+    for tick in 0..RUN_TICKS {
+        for &(_, signal) in SCRIPT.iter().filter(|(at, _)| *at == tick) {
+            let name = SIGNALS.iter().find(|(s, _)| s.0 == signal).map_or("?", |(_, n)| n);
+            println!("[{tick:3}] -> {name}");
+            kernel.post(PELICAN_ID, DynEvent::empty_dyn(Signal(signal)))?;
+            runtime.run_until_idle();
+        }
+        runtime.tick()?;
+        runtime.run_until_idle();
+
+        let log = lamps.lock().unwrap();
+        for change in &log[shown..] {
+            println!("[{tick:3}] cars {:?}, peds {:?}", change.cars, change.peds);
+        }
+        shown = log.len();
+        drop(log);
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,208 @@
+//! Scripted acceptance run of the PELICAN crossing on the QV kernel.
+//!
+//! The script drives the crossing tick by tick, checks the lamp sequence a
+//! pedestrian and a driver would see, and decodes the QS trace with QSPY's
+//! HDLC decoder to confirm that switching back on resumes through history.
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use qf::active::{new_active_object, ActiveObjectId};
+use qf::event::{DynEvent, Signal};
+use qf::time::{share_kernel, TimeEvent, TimeEventConfig, TimerWheel};
+use qf::{Kernel, QsConfig, StateHandler, Tracer};
+use qs::WriterBackend;
+use qspy::{HdlcDecoder, QsFrame};
+
+use pelican::*;
+
+const PELICAN_ID: ActiveObjectId = ActiveObjectId::new(1);
+const QS_QEP_STATE_ENTRY: u8 = 1;
+const QS_QEP_DISPATCH: u8 = 8;
+const QS_QEP_TRAN_HIST: u8 = 55;
+const PTR: usize = core::mem::size_of::<usize>();
+
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct Crossing {
+    kernel: Arc<Kernel>,
+    wheel: TimerWheel,
+    lamps: LampLog,
+    capture: Capture,
+}
+
+impl Crossing {
+    fn start() -> Self {
+        let capture = Capture::default();
+        let tracer = Tracer::new(QsConfig::default(), WriterBackend::new(capture.clone())).into_handle();
+
+        let timer = TimeEvent::new(PELICAN_ID, TimeEventConfig::new(Signal(sig::TIMEOUT)));
+        let lamps = LampLog::default();
+        let hsm = Pelican::new(Arc::clone(&timer), Arc::clone(&lamps)).into_hsm();
+        let kernel = share_kernel(
+            Kernel::builder()
+                .register(new_active_object(PELICAN_ID, 1, hsm))
+                .with_trace_hook(tracer.hook())
+                .build(),
+        );
+        kernel.start();
+        let mut wheel = TimerWheel::new(kernel.clone());
+        wheel.register(timer);
+        kernel.run_until_idle();
+        Self { kernel, wheel, lamps, capture }
+    }
+
+    fn post(&self, signal: u16) {
+        self.kernel.post(PELICAN_ID, DynEvent::empty_dyn(Signal(signal))).unwrap();
+        self.kernel.run_until_idle();
+    }
+
+    fn ticks(&self, n: u64) {
+        for _ in 0..n {
+            self.wheel.tick().unwrap();
+            self.kernel.run_until_idle();
+        }
+    }
+
+    /// Lamp changes since the last call.
+    fn changes(&self) -> Vec<Lamps> {
+        std::mem::take(&mut *self.lamps.lock().unwrap())
+    }
+
+    fn frames(&self) -> Vec<QsFrame> {
+        let bytes = self.capture.0.lock().unwrap().clone();
+        HdlcDecoder::new().push_bytes(&bytes).into_iter().flatten().collect()
+    }
+}
+
+fn lamps(cars: Cars, peds: Peds) -> Lamps {
+    Lamps { cars, peds }
+}
+
+fn addr(state: StateHandler<Pelican>) -> u64 {
+    state as *const () as usize as u64
+}
+
+fn read_ptr(bytes: &[u8]) -> u64 {
+    let mut raw = [0u8; 8];
+    raw[..PTR].copy_from_slice(&bytes[..PTR]);
+    u64::from_le_bytes(raw)
+}
+
+/// The flashing "don't walk" phase, ending in steady "don't walk".
+fn flashing() -> Vec<Lamps> {
+    let mut seq = Vec::new();
+    for _ in 0..FLASH_COUNT {
+        seq.push(lamps(Cars::Red, Peds::DontWalk));
+        seq.push(lamps(Cars::Red, Peds::Blank));
+    }
+    seq.push(lamps(Cars::Red, Peds::DontWalk));
+    seq
+}
+
+#[test]
+fn pedestrian_cycle_follows_the_lamp_sequence() {
+    let crossing = Crossing::start();
+    assert_eq!(
+        crossing.changes(),
+        [lamps(Cars::Red, Peds::DontWalk), lamps(Cars::Green, Peds::DontWalk)]
+    );
+
+    // A pedestrian waiting during the minimum green does not cut it short.
+    crossing.post(sig::PEDS_WAITING);
+    crossing.ticks(CARS_GREEN_MIN_TOUT - 1);
+    assert!(crossing.changes().is_empty());
+    crossing.ticks(1);
+    assert_eq!(crossing.changes(), [lamps(Cars::Yellow, Peds::DontWalk)]);
+
+    crossing.ticks(CARS_YELLOW_TOUT);
+    assert_eq!(
+        crossing.changes(),
+        [lamps(Cars::Red, Peds::DontWalk), lamps(Cars::Red, Peds::Walk)]
+    );
+
+    crossing.ticks(PEDS_WALK_TOUT);
+    crossing.ticks(FLASH_TOUT * (2 * FLASH_COUNT as u64 + 1));
+    let mut expected = flashing();
+    expected.push(lamps(Cars::Green, Peds::DontWalk));
+    assert_eq!(crossing.changes(), expected);
+}
+
+#[test]
+fn button_after_minimum_green_stops_cars_at_once() {
+    let crossing = Crossing::start();
+    crossing.changes();
+
+    crossing.ticks(CARS_GREEN_MIN_TOUT + 5);
+    assert!(crossing.changes().is_empty(), "cars stay green while nobody waits");
+
+    crossing.post(sig::PEDS_WAITING);
+    assert_eq!(crossing.changes(), [lamps(Cars::Yellow, Peds::DontWalk)]);
+}
+
+#[test]
+fn switching_back_on_resumes_through_history() {
+    let crossing = Crossing::start();
+    crossing.post(sig::PEDS_WAITING);
+    crossing.ticks(CARS_GREEN_MIN_TOUT + CARS_YELLOW_TOUT + 2);
+    assert_eq!(crossing.changes().last(), Some(&lamps(Cars::Red, Peds::Walk)));
+
+    crossing.post(sig::OFF);
+    crossing.ticks(4 * FLASH_TOUT);
+    assert_eq!(
+        crossing.changes(),
+        [
+            lamps(Cars::Red, Peds::DontWalk),
+            lamps(Cars::Blank, Peds::Blank),
+            lamps(Cars::Red, Peds::DontWalk),
+            lamps(Cars::Blank, Peds::Blank),
+            lamps(Cars::Red, Peds::DontWalk),
+        ]
+    );
+
+    // Back on: pedestrians get a fresh walk phase, not green for cars.
+    crossing.post(sig::ON);
+    assert_eq!(crossing.changes(), [lamps(Cars::Red, Peds::Walk)]);
+    crossing.ticks(PEDS_WALK_TOUT - 1);
+    assert!(crossing.changes().is_empty());
+    crossing.ticks(1 + FLASH_TOUT);
+    assert_eq!(crossing.changes(), [lamps(Cars::Red, Peds::DontWalk)]);
+
+    let frames = crossing.frames();
+    let hist: Vec<&QsFrame> = frames.iter().filter(|f| f.record_type == QS_QEP_TRAN_HIST).collect();
+    assert_eq!(hist.len(), 1, "exactly one history transition");
+    // obj | source: fun | target: fun, untimed, the target being the
+    // remembered child of `operational`.
+    let payload = &hist[0].payload;
+    assert_eq!(read_ptr(&payload[PTR..]), addr(offline));
+    assert_eq!(read_ptr(&payload[2 * PTR..]), addr(peds_enabled));
+    // The event that took it is in the dispatch record before it:
+    // ts: u32 | sig: u16 | obj | state.
+    let at = frames.iter().position(|f| f.record_type == QS_QEP_TRAN_HIST).unwrap();
+    let dispatch = frames[..at].iter().rfind(|f| f.record_type == QS_QEP_DISPATCH).unwrap();
+    assert_eq!(u16::from_le_bytes([dispatch.payload[4], dispatch.payload[5]]), sig::ON);
+
+    // The history transition re-enters the walk state, not carsGreen.
+    let entries_after: Vec<u64> = frames
+        .iter()
+        .skip_while(|f| f.record_type != QS_QEP_TRAN_HIST)
+        .filter(|f| f.record_type == QS_QEP_STATE_ENTRY)
+        .map(|f| read_ptr(&f.payload[PTR..]))
+        .collect();
+    assert_eq!(
+        &entries_after[..3],
+        &[addr(operational), addr(peds_enabled), addr(peds_walk)]
+    );
+}
@@ -1,7 +1,7 @@
 //! UART Echo Example
 //!
 //! Demonstrates UART usage with the HAL.
-//! Echoes back any data received on UART1.
+//! Echoes back any data received on UART0, read through the RX interrupt.
 //!
 //! Build for ESP32:
 //! ```
@@ -35,25 +35,38 @@ fn panic(_info: &core::panic::PanicInfo) -> ! {
 pub extern "C" fn main() -> ! {
     // Example demonstrating the API
     //
-    // In actual usage with ESP32:
+    // In actual usage with ESP32-C6 (ESP32/S3: `hal_lxsis::esp32s3::Esp32S3Uart`
+    // and friends):
     //
-    // use hal::uart::{UartPort, UartConfig};
-    // use hal_esp::EspUart;
+    // use embedded_io::Write;
+    // use hal::timer::Timer;
+    // use hal::uart::{RxRing, UartConfig};
+    // use hal_rvsis::esp32c6::{regs::UART0_BASE, Esp32C6Systimer, Esp32C6Uart};
     //
-    // // Create and configure UART1
-    // let mut uart = EspUart::new(1).unwrap();
+    // static RX: RxRing<256> = RxRing::new();
+    //
+    // // UART0 interrupt, routed through the interrupt matrix
+    // fn uart0_isr() {
+    //     let uart = unsafe { Esp32C6Uart::new(UART0_BASE as *const _) };
+    //     uart.on_rx_interrupt(&RX);
+    // }
+    //
+    // // Configure UART0 and raise the RX interrupt for every byte
+    // let mut uart = unsafe { Esp32C6Uart::new(UART0_BASE as *const _) };
     // uart.configure(&UartConfig::default()).unwrap();
+    // uart.enable_rx_interrupt(1).unwrap();
     //
+    // let timer = unsafe { Esp32C6Systimer::new() };
     // let mut buffer = [0u8; 128];
     //
     // loop {
-    //     // Read with 100ms timeout
-    //     match uart.read(&mut buffer, 100) {
-    //         Ok(n) if n > 0 => {
+    //     // Read with a 100 ms timeout (SYSTIMER counts at 16 MHz)
+    //     match RX.read_timeout(&mut buffer, 1_600_000, || timer.counter()) {
+    //         Ok(n) => {
     //             // Echo back
-    //             uart.write(&buffer[..n]).unwrap();
+    //             uart.write_all(&buffer[..n]).unwrap();
     //         }
-    //         _ => {}
+    //         Err(_) => {}
     //     }
     // }
 
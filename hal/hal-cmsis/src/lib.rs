@@ -7,6 +7,7 @@
 pub mod asm;
 pub mod basepri;
 pub mod nvic;
+pub mod primask;
 pub mod systick;
 pub mod scb;
 
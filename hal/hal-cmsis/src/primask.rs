@@ -0,0 +1,33 @@
+//! PRIMASK (global interrupt mask) control for ARM Cortex-M
+
+/// Mask all configurable-priority interrupts (`cpsid i`)
+#[inline(always)]
+pub fn disable() {
+    #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+    unsafe { core::arch::asm!("cpsid i", options(nomem, nostack, preserves_flags)) }
+}
+
+/// Unmask interrupts (`cpsie i`)
+///
+/// # Safety
+/// Must not be called inside a critical section that relies on PRIMASK.
+#[inline(always)]
+pub unsafe fn enable() {
+    #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+    unsafe { core::arch::asm!("cpsie i", options(nomem, nostack, preserves_flags)) }
+}
+
+/// Returns `true` while PRIMASK masks interrupts
+#[inline(always)]
+pub fn is_masked() -> bool {
+    #[cfg(target_arch = "arm")]
+    {
+        let primask: u32;
+        unsafe { core::arch::asm!("mrs {}, PRIMASK", out(reg) primask, options(nomem, nostack, preserves_flags)) }
+        primask & 1 != 0
+    }
+    #[cfg(not(target_arch = "arm"))]
+    {
+        false
+    }
+}
@@ -1,5 +1,24 @@
 //! SCB (System Control Block) and Cache operations for ARM Cortex-M7
 
+const AIRCR: usize = 0xE000_ED0C;
+const VECTKEY: u32 = 0x05FA << 16;
+const PRIGROUP_MASK: u32 = 0x7 << 8;
+const SYSRESETREQ: u32 = 1 << 2;
+
+/// Requests a system reset through `AIRCR.SYSRESETREQ`, keeping the
+/// priority grouping, and waits for it (CMSIS `NVIC_SystemReset()`).
+pub fn system_reset() -> ! {
+    crate::asm::dsb();
+    unsafe {
+        let prigroup = core::ptr::read_volatile(AIRCR as *const u32) & PRIGROUP_MASK;
+        core::ptr::write_volatile(AIRCR as *mut u32, VECTKEY | prigroup | SYSRESETREQ);
+    }
+    crate::asm::dsb();
+    loop {
+        crate::asm::nop();
+    }
+}
+
 /// Flush cache lines covering `buf` to SRAM before a DMA TX.
 ///
 /// # Safety
@@ -32,3 +32,19 @@ pub fn waiti(level: u32) {
     #[cfg(not(target_arch = "xtensa"))]
     let _ = level;
 }
+
+/// Index of the executing core, from PRID bit 13 (0xCDCD on the PRO CPU,
+/// 0xABAB on the APP CPU of the dual-core ESP32 parts). 0 off-target.
+#[inline(always)]
+pub fn core_id() -> u32 {
+    #[cfg(target_arch = "xtensa")]
+    {
+        let prid: u32;
+        unsafe { core::arch::asm!("rsr.prid {0}", out(reg) prid, options(nomem, nostack)) }
+        (prid >> 13) & 1
+    }
+    #[cfg(not(target_arch = "xtensa"))]
+    {
+        0
+    }
+}
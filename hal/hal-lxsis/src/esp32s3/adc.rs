@@ -0,0 +1,330 @@
+//! ESP32-S3 SAR ADC1 driver
+//!
+//! Oneshot reads go through the RTC SAR controller; continuous sampling
+//! uses the APB digital controller, whose conversions a GDMA channel
+//! streams into a [`SampleSink`] (usually a [`hal::adc::DoubleBuffer`]).
+//! Each DMA sample is a 32-bit word; see [`decode_sample`].
+
+use hal::adc::{
+    AdcCalibration, AdcChannel, AdcConfig, AdcController, AdcReference, AdcResolution, SampleSink,
+};
+use hal::error::{HalError, HalResult};
+use super::gdma::{DmaDescriptor, Esp32S3Gdma, GdmaPeripheral, DMA_MAX_CHUNK};
+use super::regs::{SarAdcRegs, SensRegs, APB_SARADC_BASE, SENS_BASE};
+
+/// ADC1 input channels (GPIO1..GPIO10)
+pub const ADC1_CHANNELS: u8 = 10;
+
+/// Continuous sample-rate limits, as ESP-IDF enforces them
+pub const SAMPLE_HZ_MIN: u32 = 611;
+pub const SAMPLE_HZ_MAX: u32 = 83_333;
+
+// SENS_SAR_MEAS1_CTRL2
+const MEAS1_DATA_MASK: u32 = 0xFFFF;
+const MEAS1_DONE: u32 = 1 << 16;
+const MEAS1_START: u32 = 1 << 17;
+const MEAS1_START_FORCE: u32 = 1 << 18;
+const SAR1_EN_PAD_SHIFT: u32 = 19;
+const SAR1_EN_PAD_FORCE: u32 = 1 << 31;
+// SENS_SAR_MEAS1_MUX
+const SAR1_DIG_FORCE: u32 = 1 << 31;
+
+// APB_SARADC_CTRL / CTRL2
+const SAR1_PATT_LEN_SHIFT: u32 = 15;
+const SAR1_PATT_P_CLEAR: u32 = 1 << 23;
+const TIMER_TARGET_SHIFT: u32 = 12;
+const TIMER_EN: u32 = 1 << 24;
+// APB_SARADC_DMA_CONF
+const ADC_RESET_FSM: u32 = 1 << 30;
+const ADC_TRANS: u32 = 1 << 31;
+// APB_SARADC_CLKM_CONF: APB / (31 + 1) = 2.5 MHz conversion timer
+const CLKM_DIV: u32 = 31;
+const CLKM_CLK_EN: u32 = 1 << 20;
+const CLKM_SEL_APB: u32 = 2 << 21;
+const TIMER_HZ: u32 = 80_000_000 / (CLKM_DIV + 1);
+
+const ONESHOT_SPINS: u32 = 100_000;
+
+/// Input attenuation, which sets the measurable voltage range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attenuation {
+    /// 0 dB, up to ~950 mV
+    Db0 = 0,
+    /// 2.5 dB, up to ~1250 mV
+    Db2_5 = 1,
+    /// 6 dB, up to ~1750 mV
+    Db6 = 2,
+    /// 11 dB, up to ~3100 mV
+    Db11 = 3,
+}
+
+impl Attenuation {
+    /// Nominal (uncalibrated) 12-bit transfer curve.
+    pub const fn typical_calibration(self) -> AdcCalibration {
+        let full_scale = match self {
+            Attenuation::Db0 => 950,
+            Attenuation::Db2_5 => 1250,
+            Attenuation::Db6 => 1750,
+            Attenuation::Db11 => 3100,
+        };
+        AdcCalibration::two_point(0, 0, 4095, full_scale)
+    }
+}
+
+/// Split a continuous-mode sample into `(channel, raw)`.
+pub fn decode_sample(word: u32) -> (u8, u16) {
+    (((word >> 13) & 0xF) as u8, (word & 0xFFF) as u16)
+}
+
+/// DMA state of a configured continuous conversion.
+struct Stream {
+    dma: Esp32S3Gdma,
+    descriptors: &'static mut [DmaDescriptor; 2],
+    sink: &'static dyn SampleSink,
+    running: bool,
+}
+
+/// ESP32-S3 ADC1
+pub struct Esp32S3Adc {
+    atten: [Attenuation; ADC1_CHANNELS as usize],
+    calibration: [AdcCalibration; 4],
+    stream: Option<Stream>,
+    on_ready: Option<fn(usize)>,
+}
+
+unsafe impl Send for Esp32S3Adc {}
+unsafe impl Sync for Esp32S3Adc {}
+
+fn sens() -> &'static SensRegs {
+    unsafe { &*(SENS_BASE as *const SensRegs) }
+}
+
+fn saradc() -> &'static SarAdcRegs {
+    unsafe { &*(APB_SARADC_BASE as *const SarAdcRegs) }
+}
+
+impl Esp32S3Adc {
+    /// Create a new Esp32S3Adc handle, every channel at 11 dB with the
+    /// typical calibration.
+    ///
+    /// # Safety
+    /// Unique ownership of SAR ADC1 must be guaranteed by the caller, and
+    /// the ADC and GDMA peripheral clocks must already be enabled.
+    pub unsafe fn new() -> Self {
+        Self {
+            atten: [Attenuation::Db11; ADC1_CHANNELS as usize],
+            calibration: [
+                Attenuation::Db0.typical_calibration(),
+                Attenuation::Db2_5.typical_calibration(),
+                Attenuation::Db6.typical_calibration(),
+                Attenuation::Db11.typical_calibration(),
+            ],
+            stream: None,
+            on_ready: None,
+        }
+    }
+
+    /// Set the input range of `channel`.
+    pub fn set_attenuation(&mut self, channel: u8, atten: Attenuation) -> HalResult<()> {
+        let slot = self.atten.get_mut(channel as usize).ok_or(HalError::InvalidParameter)?;
+        *slot = atten;
+        Ok(())
+    }
+
+    /// Replace the transfer curve used for `atten`, e.g. with the two-point
+    /// values burned into eFuse at the factory.
+    pub fn set_calibration(&mut self, atten: Attenuation, calibration: AdcCalibration) {
+        self.calibration[atten as usize] = calibration;
+    }
+
+    /// One conversion of `channel`. Fails with [`HalError::Busy`] while a
+    /// continuous conversion owns the converter.
+    pub fn read_oneshot(&mut self, channel: u8) -> HalResult<u16> {
+        if channel >= ADC1_CHANNELS {
+            return Err(HalError::InvalidParameter);
+        }
+        if self.stream.as_ref().is_some_and(|s| s.running) {
+            return Err(HalError::Busy);
+        }
+        let atten = self.atten[channel as usize] as u32;
+        let shift = 2 * channel as u32;
+        sens().sar_atten1.modify(|v| (v & !(0x3 << shift)) | (atten << shift));
+        oneshot(channel)
+    }
+
+    /// Calibrated voltage of one conversion of `channel`.
+    pub fn read_millivolts(&mut self, channel: u8) -> HalResult<u32> {
+        let raw = self.read_oneshot(channel)?;
+        let atten = self.atten[channel as usize];
+        Ok(self.calibration[atten as usize].millivolts(raw))
+    }
+
+    /// Prepare a continuous conversion cycling through `channels` at
+    /// `sample_hz` conversions per second, streamed by `dma` into `sink`.
+    /// Each half of `sink` (a multiple of 4 bytes, at most
+    /// [`DMA_MAX_CHUNK`]) ends one frame. Start it with
+    /// [`start_continuous`](AdcController::start_continuous).
+    pub fn configure_continuous(
+        &mut self,
+        channels: &[u8],
+        sample_hz: u32,
+        mut dma: Esp32S3Gdma,
+        descriptors: &'static mut [DmaDescriptor; 2],
+        sink: &'static dyn SampleSink,
+    ) -> HalResult<()> {
+        let half = sink.half_len();
+        if channels.is_empty()
+            || channels.len() > 16
+            || channels.iter().any(|&c| c >= ADC1_CHANNELS)
+            || !(SAMPLE_HZ_MIN..=SAMPLE_HZ_MAX).contains(&sample_hz)
+            || half == 0
+            || half & 3 != 0
+            || half > DMA_MAX_CHUNK
+        {
+            return Err(HalError::InvalidParameter);
+        }
+        self.stop_continuous()?;
+
+        // Pattern table: 6 bits per entry (channel << 2 | attenuation), four
+        // entries per register, first entry in the top bits.
+        let mut tab = [0u32; 4];
+        for (i, &channel) in channels.iter().enumerate() {
+            let entry = ((channel as u32) << 2) | self.atten[channel as usize] as u32;
+            tab[i / 4] |= entry << (18 - 6 * (i % 4));
+        }
+        let adc = saradc();
+        for (reg, value) in adc.sar1_patt_tab.iter().zip(tab) {
+            reg.write(value);
+        }
+        adc.ctrl.modify(|v| {
+            (v & !(0xF << SAR1_PATT_LEN_SHIFT))
+                | ((channels.len() as u32 - 1) << SAR1_PATT_LEN_SHIFT)
+                | SAR1_PATT_P_CLEAR
+        });
+        adc.ctrl.modify(|v| v & !SAR1_PATT_P_CLEAR);
+        adc.clkm_conf.write(CLKM_DIV | CLKM_CLK_EN | CLKM_SEL_APB);
+        adc.ctrl2.modify(|v| {
+            (v & !(TIMER_EN | (0xFFF << TIMER_TARGET_SHIFT)))
+                | ((TIMER_HZ / sample_hz) << TIMER_TARGET_SHIFT)
+        });
+        // The controller ends a frame every `half / 4` samples.
+        adc.dma_conf.write((half / 4) as u32);
+
+        let [first, second] = descriptors;
+        first.set(sink.half_ptr(0), half, 0, false);
+        second.set(sink.half_ptr(1), half, 0, false);
+        first.link(second);
+        second.link(first);
+
+        dma.connect(GdmaPeripheral::Adc);
+        self.stream = Some(Stream { dma, descriptors, sink, running: false });
+        Ok(())
+    }
+
+    /// Call `hook` with the index of each half the DMA engine fills.
+    pub fn on_ready(&mut self, hook: fn(usize)) {
+        self.on_ready = Some(hook);
+    }
+
+    /// DMA interrupt body: acknowledge the end of frame, mark the filled
+    /// half ready in the sink and run the `on_ready` hook. Returns the half.
+    pub fn on_dma_interrupt(&mut self) -> Option<usize> {
+        let stream = self.stream.as_mut()?;
+        let first = stream.dma.take_rx_eof(&stream.descriptors[0])?;
+        let half = if first { 0 } else { 1 };
+        stream.sink.mark_ready(half);
+        if let Some(hook) = self.on_ready {
+            hook(half);
+        }
+        Some(half)
+    }
+}
+
+/// RTC-controller conversion of `channel`, attenuation already set.
+fn oneshot(channel: u8) -> HalResult<u16> {
+    let sens = sens();
+    sens.sar_meas1_mux.modify(|v| v & !SAR1_DIG_FORCE);
+    sens.sar_meas1_ctrl2.write(
+        MEAS1_START_FORCE | SAR1_EN_PAD_FORCE | (1 << (SAR1_EN_PAD_SHIFT + channel as u32)),
+    );
+    sens.sar_meas1_ctrl2.modify(|v| v | MEAS1_START);
+
+    let mut spins = 0;
+    let result = loop {
+        let ctrl = sens.sar_meas1_ctrl2.read();
+        if ctrl & MEAS1_DONE != 0 {
+            break Ok((ctrl & MEAS1_DATA_MASK) as u16);
+        }
+        spins += 1;
+        if spins > ONESHOT_SPINS {
+            break Err(HalError::Timeout);
+        }
+    };
+    sens.sar_meas1_ctrl2.modify(|v| v & !MEAS1_START);
+    result
+}
+
+impl AdcController for Esp32S3Adc {
+    type Channel = Esp32S3AdcChannel;
+
+    /// Only 12-bit conversions against the internal reference exist.
+    fn configure(&mut self, config: &AdcConfig) -> HalResult<()> {
+        if config.resolution != AdcResolution::Bits12 || config.reference == AdcReference::External {
+            return Err(HalError::NotSupported);
+        }
+        Ok(())
+    }
+
+    fn get_channel(&self, channel: u8) -> HalResult<Esp32S3AdcChannel> {
+        let atten = *self.atten.get(channel as usize).ok_or(HalError::InvalidParameter)?;
+        Ok(Esp32S3AdcChannel { channel, atten, calibration: self.calibration[atten as usize] })
+    }
+
+    fn start_continuous(&mut self) -> HalResult<()> {
+        let stream = self.stream.as_mut().ok_or(HalError::ConfigurationError)?;
+        let adc = saradc();
+        adc.dma_conf.modify(|v| v | ADC_RESET_FSM);
+        adc.dma_conf.modify(|v| v & !ADC_RESET_FSM);
+        stream.dma.listen_rx_eof(true);
+        stream.dma.start(None, Some(&stream.descriptors[0]));
+        adc.dma_conf.modify(|v| v | ADC_TRANS);
+        adc.ctrl2.modify(|v| v | TIMER_EN);
+        stream.running = true;
+        Ok(())
+    }
+
+    fn stop_continuous(&mut self) -> HalResult<()> {
+        if let Some(stream) = self.stream.as_mut().filter(|s| s.running) {
+            let adc = saradc();
+            adc.ctrl2.modify(|v| v & !TIMER_EN);
+            adc.dma_conf.modify(|v| v & !ADC_TRANS);
+            stream.dma.listen_rx_eof(false);
+            stream.running = false;
+        }
+        Ok(())
+    }
+}
+
+/// One ADC1 input, with the attenuation and calibration it was taken with
+pub struct Esp32S3AdcChannel {
+    channel: u8,
+    atten: Attenuation,
+    calibration: AdcCalibration,
+}
+
+impl AdcChannel for Esp32S3AdcChannel {
+    fn read_raw(&mut self) -> HalResult<u16> {
+        let shift = 2 * self.channel as u32;
+        sens().sar_atten1.modify(|v| (v & !(0x3 << shift)) | ((self.atten as u32) << shift));
+        oneshot(self.channel)
+    }
+
+    fn read_millivolts(&mut self) -> HalResult<u32> {
+        let raw = self.read_raw()?;
+        Ok(self.calibration.millivolts(raw))
+    }
+
+    fn channel_number(&self) -> u8 {
+        self.channel
+    }
+}
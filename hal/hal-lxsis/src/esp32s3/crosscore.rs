@@ -0,0 +1,58 @@
+//! ESP32-S3 cross-core software interrupts
+//!
+//! SYSTEM_CPU_INTR_FROM_CPU_n raises interrupt source `FROM_CPU_INTR0 + n`
+//! while it holds 1. Channel n is used to signal core n: the sender sets it,
+//! the handler on core n clears it. Each core has its own interrupt matrix,
+//! so the source is routed separately on each, by the core that owns it.
+
+/// SYSTEM register block base address
+pub const SYSTEM_BASE: usize = 0x600C_0000;
+/// Interrupt matrix of the PRO CPU (core 0); the APP CPU's follows at +0x800
+pub const INTERRUPT_CORE0_BASE: usize = 0x600C_2000;
+/// Interrupt source of FROM_CPU_INTR0; channels 1-3 follow
+pub const FROM_CPU_INTR0_SOURCE: u32 = 79;
+
+const CPU_INTR_FROM_CPU_0: usize = 0x30;
+const INTERRUPT_CORE_STRIDE: usize = 0x800;
+
+/// Cross-core interrupt channels 0 and 1, one per core
+pub struct Esp32S3CrossCore {
+    _private: (),
+}
+
+impl Esp32S3CrossCore {
+    /// Create a new Esp32S3CrossCore handle
+    ///
+    /// # Safety
+    /// Channels 0 and 1 must not be used by anything else (ESP-IDF's IPC
+    /// task claims them too).
+    pub const unsafe fn new() -> Self {
+        Self { _private: () }
+    }
+
+    fn reg(core: u8) -> *mut u32 {
+        (SYSTEM_BASE + CPU_INTR_FROM_CPU_0 + 4 * usize::from(core & 1)) as *mut u32
+    }
+
+    /// Raise the interrupt on `core`.
+    pub fn raise(&mut self, core: u8) {
+        unsafe { core::ptr::write_volatile(Self::reg(core), 1) }
+    }
+
+    /// Clear the interrupt on `core`; returns whether it was raised.
+    pub fn clear(&mut self, core: u8) -> bool {
+        let raised = unsafe { core::ptr::read_volatile(Self::reg(core)) } & 1 != 0;
+        unsafe { core::ptr::write_volatile(Self::reg(core), 0) }
+        raised
+    }
+
+    /// Route channel `core` to CPU interrupt `cpu_int` in that core's
+    /// interrupt matrix. The line still has to be enabled in INTENABLE on
+    /// that core.
+    pub fn route(&mut self, core: u8, cpu_int: u32) {
+        let matrix = INTERRUPT_CORE0_BASE + INTERRUPT_CORE_STRIDE * usize::from(core & 1);
+        let source = FROM_CPU_INTR0_SOURCE + u32::from(core & 1);
+        let map = (matrix + 4 * source as usize) as *mut u32;
+        unsafe { core::ptr::write_volatile(map, cpu_int & 0x1F) }
+    }
+}
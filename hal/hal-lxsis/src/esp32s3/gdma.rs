@@ -0,0 +1,157 @@
+//! ESP32-S3 GDMA channel driver
+//!
+//! Each of the five general-purpose DMA channels has an RX (peripheral to
+//! memory) and a TX (memory to peripheral) half that walk linked lists of
+//! [`DmaDescriptor`]s. Descriptors and buffers must sit in internal SRAM;
+//! the link registers only hold the low 20 address bits.
+
+use hal::error::{HalError, HalResult};
+use super::regs::{GdmaChRegs, GDMA_BASE};
+
+/// Largest buffer one descriptor can carry (12-bit length, word aligned).
+pub const DMA_MAX_CHUNK: usize = 4092;
+
+/// Number of GDMA channels on the ESP32-S3.
+pub const GDMA_CHANNELS: u8 = 5;
+
+const CHANNEL_STRIDE: usize = 0xC0;
+
+const CONF0_RST: u32 = 1 << 0;
+const IN_SUC_EOF: u32 = 1 << 1;
+const IN_LINK_START: u32 = 1 << 22;
+const OUT_LINK_START: u32 = 1 << 21;
+const LINK_ADDR_MASK: u32 = 0x000F_FFFF;
+
+const DW0_SUC_EOF: u32 = 1 << 30;
+const DW0_OWNER_DMA: u32 = 1 << 31;
+
+// Data-bus window of the internal SRAM GDMA can reach.
+const SRAM_START: usize = 0x3FC8_8000;
+const SRAM_END: usize = 0x3FD0_0000;
+
+/// Peripheral a GDMA channel is connected to (`*_PERI_SEL` values).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum GdmaPeripheral {
+    Spi2 = 0,
+    Spi3 = 1,
+    Uhci0 = 2,
+    I2s0 = 3,
+    I2s1 = 4,
+    LcdCam = 5,
+    Aes = 6,
+    Sha = 7,
+    Adc = 8,
+    Rmt = 9,
+}
+
+/// Linked-list descriptor shared with the GDMA engine.
+#[repr(C, align(4))]
+#[derive(Debug, Default)]
+pub struct DmaDescriptor {
+    dw0: u32,
+    buffer: u32,
+    next: u32,
+}
+
+impl DmaDescriptor {
+    /// An empty, CPU-owned descriptor.
+    pub const fn new() -> Self {
+        Self { dw0: 0, buffer: 0, next: 0 }
+    }
+
+    /// Hand a single buffer to the DMA engine. `length` is the number of
+    /// valid bytes (TX) or `0` (RX); `size` is the buffer capacity.
+    pub(crate) fn set(&mut self, buffer: *const u8, size: usize, length: usize, eof: bool) {
+        let eof = if eof { DW0_SUC_EOF } else { 0 };
+        self.dw0 = DW0_OWNER_DMA | eof | ((length as u32 & 0xFFF) << 12) | (size as u32 & 0xFFF);
+        self.buffer = buffer as u32;
+        self.next = 0;
+    }
+
+    /// Chain `next` after this descriptor (a ring if it points back).
+    pub(crate) fn link(&mut self, next: &DmaDescriptor) {
+        self.next = next as *const Self as u32;
+    }
+
+    fn link_addr(&self) -> u32 {
+        (self as *const Self as u32) & LINK_ADDR_MASK
+    }
+}
+
+/// Whether `len` bytes at `ptr` lie in SRAM the GDMA engine can reach.
+/// Flash-resident data (e.g. `const` tables) cannot be sent by DMA.
+pub fn is_dma_capable(ptr: *const u8, len: usize) -> bool {
+    let start = ptr as usize;
+    start >= SRAM_START && start.checked_add(len).is_some_and(|end| end <= SRAM_END)
+}
+
+/// One GDMA channel, used for both directions of a peripheral.
+pub struct Esp32S3Gdma {
+    regs: *const GdmaChRegs,
+}
+
+unsafe impl Send for Esp32S3Gdma {}
+unsafe impl Sync for Esp32S3Gdma {}
+
+impl Esp32S3Gdma {
+    /// Create a handle for GDMA channel `channel` (0..=4).
+    ///
+    /// # Safety
+    /// Unique ownership of the channel must be guaranteed by the caller, and
+    /// the GDMA peripheral clock must already be enabled.
+    pub unsafe fn new(channel: u8) -> HalResult<Self> {
+        if channel >= GDMA_CHANNELS {
+            return Err(HalError::InvalidParameter);
+        }
+        let base = GDMA_BASE + channel as usize * CHANNEL_STRIDE;
+        Ok(Self { regs: base as *const GdmaChRegs })
+    }
+
+    fn regs(&self) -> &GdmaChRegs {
+        unsafe { &*self.regs }
+    }
+
+    /// Route both halves of the channel to `peripheral`.
+    pub fn connect(&mut self, peripheral: GdmaPeripheral) {
+        self.regs().in_peri_sel.write(peripheral as u32);
+        self.regs().out_peri_sel.write(peripheral as u32);
+    }
+
+    /// Reset the channel FIFOs and start walking `tx` and/or `rx`.
+    ///
+    /// The descriptors (and their buffers) must stay in place until the
+    /// peripheral reports the transfer done.
+    pub fn start(&mut self, tx: Option<&DmaDescriptor>, rx: Option<&DmaDescriptor>) {
+        let regs = self.regs();
+        regs.in_conf0.modify(|v| v | CONF0_RST);
+        regs.in_conf0.modify(|v| v & !CONF0_RST);
+        regs.out_conf0.modify(|v| v | CONF0_RST);
+        regs.out_conf0.modify(|v| v & !CONF0_RST);
+
+        if let Some(rx) = rx {
+            regs.in_link.write(rx.link_addr() | IN_LINK_START);
+        }
+        if let Some(tx) = tx {
+            regs.out_link.write(tx.link_addr() | OUT_LINK_START);
+        }
+    }
+
+    /// Raise the channel interrupt whenever the RX half reaches a
+    /// descriptor the peripheral marked end-of-frame.
+    pub fn listen_rx_eof(&mut self, enable: bool) {
+        self.regs().in_int_clr.write(IN_SUC_EOF);
+        self.regs().in_int_ena.modify(|v| if enable { v | IN_SUC_EOF } else { v & !IN_SUC_EOF });
+    }
+
+    /// Acknowledge a pending RX end-of-frame and return whether `desc` is
+    /// the descriptor it completed. `None` if no end-of-frame is pending.
+    pub fn take_rx_eof(&mut self, desc: &DmaDescriptor) -> Option<bool> {
+        let regs = self.regs();
+        if regs.in_int_raw.read() & IN_SUC_EOF == 0 {
+            return None;
+        }
+        regs.in_int_clr.write(IN_SUC_EOF);
+        Some(regs.in_suc_eof_des_addr.read() == desc as *const DmaDescriptor as u32)
+    }
+}
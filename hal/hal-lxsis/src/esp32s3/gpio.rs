@@ -4,6 +4,13 @@ use hal::gpio::PinMode;
 use hal::error::{HalError, HalResult};
 use super::regs::gpio;
 
+/// Number of GPIO pins (GPIO0..GPIO48)
+pub const GPIO_PINS: u8 = 49;
+
+// GPIO_FUNCn_OUT_SEL_CFG: output enable comes from GPIO_ENABLE, not the peripheral
+const FUNC_OEN_SEL: u32 = 1 << 9;
+const FUNC_OUT_SEL_MAX: u16 = 0x1FF;
+
 /// ESP32-S3 GPIO Pin
 pub struct Esp32S3Pin {
     pin: u8,
@@ -41,6 +48,16 @@ impl Esp32S3Pin {
         Ok(())
     }
 
+    /// Drive the pin from peripheral output `signal` (a GPIO-matrix
+    /// signal index) and enable its output driver.
+    pub fn connect_output(&mut self, signal: u16) -> HalResult<()> {
+        if self.pin >= GPIO_PINS || signal > FUNC_OUT_SEL_MAX {
+            return Err(HalError::InvalidParameter);
+        }
+        gpio().func_out_sel_cfg[self.pin as usize].write(signal as u32 | FUNC_OEN_SEL);
+        self.set_mode(PinMode::Output)
+    }
+
     /// Pin number on the port.
     pub fn pin_number(&self) -> u32 {
         self.pin as u32
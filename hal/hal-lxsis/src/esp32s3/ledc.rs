@@ -0,0 +1,245 @@
+//! ESP32-S3 LEDC PWM driver
+//!
+//! Eight channels share four timers. A channel runs at the frequency and
+//! resolution of the timer it is bound to, so reconfiguring one channel
+//! retunes every other channel on the same timer. Fades run in hardware:
+//! the channel steps its duty toward the target every few PWM periods and
+//! raises its `DUTY_CHNG_END` interrupt when it gets there.
+
+use hal::error::{HalError, HalResult};
+use hal::pwm::{duty_to_ticks, PwmChannel, PwmConfig, SetDutyCycle};
+use super::gpio::Esp32S3Pin;
+use super::regs::{LedcChRegs, LedcRegs, LedcTimerRegs, LEDC_BASE};
+
+/// Number of LEDC channels
+pub const LEDC_CHANNELS: u8 = 8;
+
+/// Number of LEDC timers
+pub const LEDC_TIMERS: u8 = 4;
+
+/// Widest duty resolution the timers support
+pub const LEDC_MAX_RESOLUTION: u8 = 14;
+
+/// GPIO-matrix index of `LEDC_LS_SIG_OUT0`; channel n is this plus n
+const SIG_OUT0: u16 = 73;
+
+const APB_HZ: u64 = 80_000_000;
+
+// LEDC_CHn_CONF0
+const SIG_OUT_EN: u32 = 1 << 2;
+const CH_PARA_UP: u32 = 1 << 4;
+// LEDC_CHn_CONF1
+const DUTY_CYCLE_SHIFT: u32 = 10;
+const DUTY_NUM_SHIFT: u32 = 20;
+const DUTY_INC: u32 = 1 << 30;
+const DUTY_START: u32 = 1 << 31;
+const FADE_FIELD_MAX: u32 = 0x3FF;
+// LEDC_CHn_DUTY holds the duty with four fractional bits
+const DUTY_FRAC_BITS: u32 = 4;
+// LEDC_TIMERn_CONF
+const CLK_DIV_SHIFT: u32 = 4;
+const CLK_DIV_MIN: u64 = 1 << 8; // 1.0 in 10.8 fixed point
+const CLK_DIV_MAX: u64 = (1 << 18) - 1;
+const TIMER_PAUSE: u32 = 1 << 22;
+const TIMER_RST: u32 = 1 << 23;
+const TIMER_PARA_UP: u32 = 1 << 25;
+// LEDC_CONF
+const APB_CLK_SEL_APB: u32 = 1;
+const CLK_EN: u32 = 1 << 31;
+// LEDC_INT_*: DUTY_CHNG_END for channel n
+const DUTY_CHNG_END_SHIFT: u32 = 4;
+
+fn ledc() -> &'static LedcRegs {
+    unsafe { &*(LEDC_BASE as *const LedcRegs) }
+}
+
+/// Register values for a hardware fade of `delta` ticks spread over
+/// `periods` PWM periods: `(scale, cycle, num)`, stepping `scale` ticks
+/// every `cycle` periods, `num` times.
+fn fade_steps(delta: u32, periods: u64) -> (u32, u32, u32) {
+    let scale = delta.div_ceil(FADE_FIELD_MAX).clamp(1, FADE_FIELD_MAX);
+    let num = (delta / scale).clamp(1, FADE_FIELD_MAX);
+    let cycle = (periods / num as u64).clamp(1, FADE_FIELD_MAX as u64) as u32;
+    (scale, cycle, num)
+}
+
+/// ESP32-S3 LEDC channel
+pub struct Esp32S3LedcChannel {
+    channel: u8,
+    timer: u8,
+    config: PwmConfig,
+    duty: u32,
+    fading: bool,
+}
+
+unsafe impl Send for Esp32S3LedcChannel {}
+unsafe impl Sync for Esp32S3LedcChannel {}
+
+impl Esp32S3LedcChannel {
+    /// Create a handle for LEDC `channel` (0..=7) clocked by `timer` (0..=3).
+    /// The output stays off until [`configure`](PwmChannel::configure) and
+    /// [`enable`](PwmChannel::enable).
+    ///
+    /// # Safety
+    /// Unique ownership of the channel must be guaranteed by the caller, the
+    /// LEDC peripheral clock must already be enabled, and channels sharing
+    /// `timer` must agree on its configuration.
+    pub unsafe fn new(channel: u8, timer: u8) -> HalResult<Self> {
+        if channel >= LEDC_CHANNELS || timer >= LEDC_TIMERS {
+            return Err(HalError::InvalidParameter);
+        }
+        ledc().conf.write(APB_CLK_SEL_APB | CLK_EN);
+        Ok(Self { channel, timer, config: PwmConfig::default(), duty: 0, fading: false })
+    }
+
+    fn ch(&self) -> &LedcChRegs {
+        &ledc().ch[self.channel as usize]
+    }
+
+    fn timer(&self) -> &LedcTimerRegs {
+        &ledc().timer[self.timer as usize]
+    }
+
+    fn full_scale(&self) -> u32 {
+        1 << self.config.resolution_bits
+    }
+
+    /// Route the channel output to `pin`.
+    pub fn attach_pin(&self, pin: &mut Esp32S3Pin) -> HalResult<()> {
+        pin.connect_output(SIG_OUT0 + self.channel as u16)
+    }
+
+    /// Current duty in timer ticks, as last requested.
+    pub fn duty_ticks(&self) -> u32 {
+        self.duty
+    }
+
+    /// Raise the LEDC interrupt when a fade on this channel finishes.
+    pub fn listen_fade_end(&mut self, enable: bool) {
+        let bit = 1 << (DUTY_CHNG_END_SHIFT + self.channel as u32);
+        ledc().int_clr.write(bit);
+        ledc().int_ena.modify(|v| if enable { v | bit } else { v & !bit });
+    }
+
+    /// Acknowledge a finished fade. Returns `false` if none was pending.
+    pub fn take_fade_end(&mut self) -> bool {
+        let bit = 1 << (DUTY_CHNG_END_SHIFT + self.channel as u32);
+        if ledc().int_raw.read() & bit == 0 {
+            return false;
+        }
+        ledc().int_clr.write(bit);
+        self.fading = false;
+        true
+    }
+
+    /// Load `ticks` immediately, cancelling any fade in progress.
+    fn write_duty(&mut self, ticks: u32) {
+        let ticks = ticks.min(self.full_scale());
+        self.ch().duty.write(ticks << DUTY_FRAC_BITS);
+        self.ch().conf1.write(
+            DUTY_START | DUTY_INC | (1 << DUTY_NUM_SHIFT) | (1 << DUTY_CYCLE_SHIFT),
+        );
+        self.ch().conf0.modify(|v| v | CH_PARA_UP);
+        self.duty = ticks;
+        self.fading = false;
+    }
+}
+
+impl PwmChannel for Esp32S3LedcChannel {
+    fn configure(&mut self, config: &PwmConfig) -> HalResult<()> {
+        let bits = config.resolution_bits;
+        if bits == 0 || bits > LEDC_MAX_RESOLUTION || config.frequency_hz == 0 {
+            return Err(HalError::InvalidParameter);
+        }
+        let div = (APB_HZ << 8) / ((config.frequency_hz as u64) << bits);
+        if !(CLK_DIV_MIN..=CLK_DIV_MAX).contains(&div) {
+            return Err(HalError::InvalidParameter);
+        }
+        self.timer().conf.write(bits as u32 | ((div as u32) << CLK_DIV_SHIFT));
+        self.timer().conf.modify(|v| v | TIMER_PARA_UP);
+
+        // Keep the duty fraction across a resolution change.
+        let duty = (self.duty as u64 * (1u64 << bits) / self.full_scale() as u64) as u32;
+        self.config = *config;
+        self.write_duty(duty);
+        Ok(())
+    }
+
+    fn set_duty(&mut self, duty: f32) -> HalResult<()> {
+        let ticks = duty_to_ticks(duty, self.config.resolution_bits)?;
+        self.write_duty(ticks);
+        Ok(())
+    }
+
+    fn set_frequency(&mut self, freq_hz: u32) -> HalResult<()> {
+        let config = PwmConfig { frequency_hz: freq_hz, ..self.config };
+        self.configure(&config)
+    }
+
+    fn fade_to(&mut self, duty: f32, duration_ms: u32) -> HalResult<()> {
+        let target = duty_to_ticks(duty, self.config.resolution_bits)?;
+        let delta = target.abs_diff(self.duty);
+        if delta == 0 || duration_ms == 0 {
+            self.write_duty(target);
+            return Ok(());
+        }
+        let periods = self.config.frequency_hz as u64 * duration_ms as u64 / 1000;
+        let (scale, cycle, num) = fade_steps(delta, periods);
+        // Start where `num` whole steps land exactly on the target; this is
+        // less than one step away from the current duty.
+        let (start, inc) = if target > self.duty {
+            (target - scale * num, DUTY_INC)
+        } else {
+            (target + scale * num, 0)
+        };
+
+        ledc().int_clr.write(1 << (DUTY_CHNG_END_SHIFT + self.channel as u32));
+        self.ch().duty.write(start << DUTY_FRAC_BITS);
+        self.ch().conf1.write(
+            DUTY_START
+                | inc
+                | (num << DUTY_NUM_SHIFT)
+                | (cycle << DUTY_CYCLE_SHIFT)
+                | scale,
+        );
+        self.ch().conf0.modify(|v| v | CH_PARA_UP);
+        self.duty = target;
+        self.fading = true;
+        Ok(())
+    }
+
+    fn is_fading(&self) -> bool {
+        let bit = 1 << (DUTY_CHNG_END_SHIFT + self.channel as u32);
+        self.fading && ledc().int_raw.read() & bit == 0
+    }
+
+    fn enable(&mut self) -> HalResult<()> {
+        self.timer().conf.modify(|v| v & !(TIMER_PAUSE | TIMER_RST));
+        self.ch().conf0.write(self.timer as u32 | SIG_OUT_EN | CH_PARA_UP);
+        Ok(())
+    }
+
+    fn disable(&mut self) -> HalResult<()> {
+        // Output falls to the idle level (low).
+        self.ch().conf0.write(self.timer as u32 | CH_PARA_UP);
+        Ok(())
+    }
+}
+
+impl embedded_hal::pwm::ErrorType for Esp32S3LedcChannel {
+    type Error = HalError;
+}
+
+impl SetDutyCycle for Esp32S3LedcChannel {
+    fn max_duty_cycle(&self) -> u16 {
+        self.full_scale() as u16
+    }
+
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+        if duty as u32 > self.full_scale() {
+            return Err(HalError::InvalidParameter);
+        }
+        self.write_duty(duty as u32);
+        Ok(())
+    }
+}
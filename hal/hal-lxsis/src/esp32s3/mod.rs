@@ -1,12 +1,22 @@
 //! ESP32-S3 vendor module
 
 pub mod regs;
+pub mod adc;
+pub mod crosscore;
 pub mod gpio;
+pub mod gdma;
+pub mod ledc;
 pub mod spi;
 pub mod uart;
+pub mod wdt;
 pub mod radio;
 
 pub use regs::GpioRegs;
+pub use adc::{Attenuation, Esp32S3Adc};
+pub use crosscore::Esp32S3CrossCore;
 pub use gpio::Esp32S3Pin;
+pub use gdma::{DmaDescriptor, Esp32S3Gdma, GdmaPeripheral};
+pub use ledc::Esp32S3LedcChannel;
 pub use spi::Esp32S3Spi;
 pub use uart::Esp32S3Uart;
+pub use wdt::Esp32S3Wdt;
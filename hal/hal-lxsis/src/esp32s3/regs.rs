@@ -6,6 +6,13 @@ use hal::mmio::{RO, RW, WO};
 pub const GPIO_BASE:  usize = 0x6000_4000;
 pub const SPI2_BASE:  usize = 0x6002_4000; // FSPI/HSPI
 pub const UART0_BASE: usize = 0x6000_0000;
+pub const GDMA_BASE:  usize = 0x6003_F000;
+pub const SENS_BASE:  usize = 0x6000_8800; // RTC SAR controller
+pub const APB_SARADC_BASE: usize = 0x6004_0000;
+pub const LEDC_BASE:  usize = 0x6001_9000;
+pub const TIMG0_BASE: usize = 0x6001_F000;
+pub const TIMG1_BASE: usize = 0x6002_0000;
+pub const RTC_CNTL_BASE: usize = 0x6000_8000;
 
 /// ESP32-S3 GPIO registers
 #[repr(C)]
@@ -27,6 +34,8 @@ pub struct GpioRegs {
     _reserved1:      u32,       // 0x038
     pub in_:         RO<u32>,   // 0x03C
     pub in1:         RO<u32>,   // 0x040
+    _reserved2:      [u32; 324], // 0x044 - 0x550
+    pub func_out_sel_cfg: [RW<u32>; 49], // 0x554 - 0x614 (GPIO0 to GPIO48)
 }
 
 /// ESP32-S3 SPI registers
@@ -44,7 +53,11 @@ pub struct SpiRegs {
     pub user2:     RW<u32>,   // 0x24
     pub mosi_dlen: RW<u32>,   // 0x28
     pub miso_dlen: RW<u32>,   // 0x2C
-    _r1:           [u32; 20], // 0x30 - 0x7C (20 * 4 = 80 bytes)
+    pub dma_conf:  RW<u32>,   // 0x30
+    pub dma_int_ena: RW<u32>, // 0x34
+    pub dma_int_clr: WO<u32>, // 0x38
+    pub dma_int_raw: RO<u32>, // 0x3C
+    _r1:           [u32; 16], // 0x40 - 0x7C (16 * 4 = 64 bytes)
     pub w:         [RW<u32>; 16], // 0x80 - 0xBC (W0 to W15)
 }
 
@@ -63,6 +76,102 @@ pub struct UartRegs {
     pub conf1:    RW<u32>,   // 0x24
 }
 
+/// ESP32-S3 RTC SAR ADC1 registers (oneshot conversions)
+#[repr(C)]
+pub struct SensRegs {
+    pub sar_reader1_ctrl:   RW<u32>, // 0x00
+    pub sar_reader1_status: RO<u32>, // 0x04
+    pub sar_meas1_ctrl1:    RW<u32>, // 0x08
+    pub sar_meas1_ctrl2:    RW<u32>, // 0x0C
+    pub sar_meas1_mux:      RW<u32>, // 0x10
+    pub sar_atten1:         RW<u32>, // 0x14
+}
+
+/// ESP32-S3 APB SAR ADC digital controller registers (continuous mode)
+#[repr(C)]
+pub struct SarAdcRegs {
+    pub ctrl:          RW<u32>,      // 0x00
+    pub ctrl2:         RW<u32>,      // 0x04
+    pub filter_ctrl1:  RW<u32>,      // 0x08
+    pub fsm_wait:      RW<u32>,      // 0x0C
+    pub sar1_status:   RO<u32>,      // 0x10
+    pub sar2_status:   RO<u32>,      // 0x14
+    pub sar1_patt_tab: [RW<u32>; 4], // 0x18 - 0x24
+    pub sar2_patt_tab: [RW<u32>; 4], // 0x28 - 0x34
+    pub arb_ctrl:      RW<u32>,      // 0x38
+    _r0:               [u32; 4],     // 0x3C - 0x48
+    pub dma_conf:      RW<u32>,      // 0x4C
+    _r1:               u32,          // 0x50
+    pub clkm_conf:     RW<u32>,      // 0x54
+}
+
+/// ESP32-S3 GDMA channel registers (one RX and one TX half, 0xC0 apart)
+#[repr(C)]
+pub struct GdmaChRegs {
+    pub in_conf0:     RW<u32>,   // 0x00
+    pub in_conf1:     RW<u32>,   // 0x04
+    pub in_int_raw:   RO<u32>,   // 0x08
+    pub in_int_st:    RO<u32>,   // 0x0C
+    pub in_int_ena:   RW<u32>,   // 0x10
+    pub in_int_clr:   WO<u32>,   // 0x14
+    _r0:              [u32; 2],  // 0x18 - 0x1C
+    pub in_link:      RW<u32>,   // 0x20
+    pub in_state:     RO<u32>,   // 0x24
+    pub in_suc_eof_des_addr: RO<u32>, // 0x28
+    _r1:              [u32; 7],  // 0x2C - 0x44
+    pub in_peri_sel:  RW<u32>,   // 0x48
+    _r2:              [u32; 5],  // 0x4C - 0x5C
+    pub out_conf0:    RW<u32>,   // 0x60
+    _r3:              [u32; 7],  // 0x64 - 0x7C
+    pub out_link:     RW<u32>,   // 0x80
+    _r4:              [u32; 9],  // 0x84 - 0xA4
+    pub out_peri_sel: RW<u32>,   // 0xA8
+    _r5:              [u32; 5],  // 0xAC - 0xBC
+}
+
+/// ESP32-S3 LEDC channel registers (eight channels, 0x14 apart)
+#[repr(C)]
+pub struct LedcChRegs {
+    pub conf0:  RW<u32>,   // 0x00
+    pub hpoint: RW<u32>,   // 0x04
+    pub duty:   RW<u32>,   // 0x08
+    pub conf1:  RW<u32>,   // 0x0C
+    pub duty_r: RO<u32>,   // 0x10
+}
+
+/// ESP32-S3 LEDC timer registers (four timers, 0x08 apart)
+#[repr(C)]
+pub struct LedcTimerRegs {
+    pub conf:  RW<u32>,    // 0x00
+    pub value: RO<u32>,    // 0x04
+}
+
+/// ESP32-S3 LEDC PWM controller registers
+#[repr(C)]
+pub struct LedcRegs {
+    pub ch:      [LedcChRegs; 8],    // 0x00 - 0x9C
+    pub timer:   [LedcTimerRegs; 4], // 0xA0 - 0xBC
+    pub int_raw: RO<u32>,            // 0xC0
+    pub int_st:  RO<u32>,            // 0xC4
+    pub int_ena: RW<u32>,            // 0xC8
+    pub int_clr: WO<u32>,            // 0xCC
+    pub conf:    RW<u32>,            // 0xD0
+}
+
+/// ESP32-S3 timer group registers (the main system watchdog part)
+#[repr(C)]
+pub struct TimgRegs {
+    _r0:             [u32; 18], // 0x00 - 0x44 (general-purpose timers)
+    pub wdtconfig0:  RW<u32>,   // 0x48
+    pub wdtconfig1:  RW<u32>,   // 0x4C
+    pub wdtconfig2:  RW<u32>,   // 0x50 (stage 0 timeout)
+    pub wdtconfig3:  RW<u32>,   // 0x54
+    pub wdtconfig4:  RW<u32>,   // 0x58
+    pub wdtconfig5:  RW<u32>,   // 0x5C
+    pub wdtfeed:     WO<u32>,   // 0x60
+    pub wdtwprotect: RW<u32>,   // 0x64
+}
+
 /// Get global reference to GpioRegs
 pub fn gpio() -> &'static GpioRegs {
     unsafe { &*(GPIO_BASE as *const GpioRegs) }
@@ -1,12 +1,36 @@
 //! ESP32-S3 SPI driver
+//!
+//! Transfers go through the 64-byte `W0..W15` buffer by default. With a
+//! GDMA channel attached ([`Esp32S3Spi::with_dma`]) transfers longer than
+//! the threshold are streamed by DMA instead, provided the buffers live in
+//! internal SRAM. Wrap the bus in [`hal::spi::CsDevice`] for chip-select
+//! handling.
 
 use hal::spi::{SpiConfig, SpiMode, BitOrder};
 use hal::error::{HalError, HalResult};
+use super::gdma::{is_dma_capable, DmaDescriptor, Esp32S3Gdma, GdmaPeripheral, DMA_MAX_CHUNK};
 use super::regs::SpiRegs;
 
+const CMD_USR: u32 = 1 << 18;
+
+const DMA_RX_ENA: u32 = 1 << 27;
+const DMA_TX_ENA: u32 = 1 << 28;
+const DMA_AFIFO_RST: u32 = (1 << 29) | (1 << 30) | (1 << 31);
+const TRANS_DONE_INT: u32 = 1 << 12;
+
+/// GDMA channel plus the descriptor pair used for one chunk.
+struct SpiDma {
+    channel: Esp32S3Gdma,
+    threshold: usize,
+    tx: DmaDescriptor,
+    rx: DmaDescriptor,
+}
+
 /// ESP32-S3 SPI Master
 pub struct Esp32S3Spi {
     regs: *const SpiRegs,
+    dma: Option<SpiDma>,
+    on_complete: Option<fn(usize)>,
 }
 
 unsafe impl Send for Esp32S3Spi {}
@@ -18,7 +42,7 @@ impl Esp32S3Spi {
     /// # Safety
     /// Unique ownership of the SPI peripheral must be guaranteed by the caller.
     pub unsafe fn new(regs: *const SpiRegs) -> Self {
-        Self { regs }
+        Self { regs, dma: None, on_complete: None }
     }
 
     fn regs(&self) -> &SpiRegs {
@@ -68,6 +92,86 @@ impl Esp32S3Spi {
 
         Ok(())
     }
+
+    /// Stream transfers longer than `threshold` bytes through `channel`.
+    pub fn with_dma(&mut self, mut channel: Esp32S3Gdma, threshold: usize) {
+        channel.connect(GdmaPeripheral::Spi2);
+        self.dma = Some(SpiDma {
+            channel,
+            threshold,
+            tx: DmaDescriptor::new(),
+            rx: DmaDescriptor::new(),
+        });
+    }
+
+    /// Call `hook` with the byte count whenever a DMA transfer completes.
+    /// CPU-buffered transfers below the threshold do not invoke it.
+    pub fn on_complete(&mut self, hook: fn(usize)) {
+        self.on_complete = Some(hook);
+    }
+
+    /// Run a `len`-byte transfer by DMA if one is attached and the transfer
+    /// qualifies. Returns `false` when the caller should use the CPU path.
+    ///
+    /// `rx` and `tx` come from live slices of at least `len` bytes; they may
+    /// point at the same buffer (GDMA reads each TX byte before the RX byte
+    /// landing on it).
+    fn try_dma(&mut self, rx: Option<*mut u8>, tx: Option<*const u8>, len: usize) -> bool {
+        let Some(dma) = &self.dma else { return false };
+        let capable = rx.is_none_or(|p| is_dma_capable(p, len))
+            && tx.is_none_or(|p| is_dma_capable(p, len));
+        if len <= dma.threshold || !capable {
+            return false;
+        }
+
+        let mut offset = 0;
+        while offset < len {
+            let chunk = (len - offset).min(DMA_MAX_CHUNK);
+            // SAFETY: `offset + chunk <= len`, which both buffers cover.
+            unsafe {
+                self.dma_chunk(rx.map(|p| p.add(offset)), tx.map(|p| p.add(offset)), chunk);
+            }
+            offset += chunk;
+        }
+
+        if let Some(hook) = self.on_complete {
+            hook(len);
+        }
+        true
+    }
+
+    /// # Safety
+    /// `rx` and `tx` must be valid for `len` bytes of writes/reads.
+    unsafe fn dma_chunk(&mut self, rx: Option<*mut u8>, tx: Option<*const u8>, len: usize) {
+        let regs = &*self.regs;
+        let Some(dma) = self.dma.as_mut() else { return };
+        let bit_len = (len * 8) as u32;
+        let mut ena = 0;
+
+        if let Some(tx) = tx {
+            dma.tx.set(tx, len, len, true);
+            ena |= DMA_TX_ENA;
+        }
+        if let Some(rx) = rx {
+            dma.rx.set(rx, len, 0, true);
+            ena |= DMA_RX_ENA;
+        }
+
+        regs.dma_conf.write(DMA_AFIFO_RST);
+        regs.dma_conf.write(0);
+        dma.channel.start(tx.map(|_| &dma.tx), rx.map(|_| &dma.rx));
+
+        regs.mosi_dlen.write(if tx.is_some() { bit_len - 1 } else { 0 });
+        regs.miso_dlen.write(if rx.is_some() { bit_len - 1 } else { 0 });
+        regs.dma_int_clr.write(TRANS_DONE_INT);
+        regs.dma_conf.write(ena);
+
+        regs.cmd.modify(|v| v | CMD_USR);
+        while regs.dma_int_raw.read() & TRANS_DONE_INT == 0 {}
+
+        regs.dma_int_clr.write(TRANS_DONE_INT);
+        regs.dma_conf.write(0);
+    }
 }
 
 impl embedded_hal::spi::ErrorType for Esp32S3Spi {
@@ -76,6 +180,10 @@ impl embedded_hal::spi::ErrorType for Esp32S3Spi {
 
 impl embedded_hal::spi::SpiBus<u8> for Esp32S3Spi {
     fn transfer(&mut self, rx_buffer: &mut [u8], tx_data: &[u8]) -> HalResult<()> {
+        let len = tx_data.len().min(rx_buffer.len());
+        if self.try_dma(Some(rx_buffer.as_mut_ptr()), Some(tx_data.as_ptr()), len) {
+            return Ok(());
+        }
         let total_len = tx_data.len().min(rx_buffer.len());
         let mut offset = 0;
 
@@ -123,6 +231,9 @@ impl embedded_hal::spi::SpiBus<u8> for Esp32S3Spi {
     }
 
     fn write(&mut self, data: &[u8]) -> HalResult<()> {
+        if self.try_dma(None, Some(data.as_ptr()), data.len()) {
+            return Ok(());
+        }
         let total_len = data.len();
         let mut offset = 0;
 
@@ -153,6 +264,9 @@ impl embedded_hal::spi::SpiBus<u8> for Esp32S3Spi {
     }
 
     fn read(&mut self, buffer: &mut [u8]) -> HalResult<()> {
+        if self.try_dma(Some(buffer.as_mut_ptr()), None, buffer.len()) {
+            return Ok(());
+        }
         let total_len = buffer.len();
         let mut offset = 0;
 
@@ -182,6 +296,10 @@ impl embedded_hal::spi::SpiBus<u8> for Esp32S3Spi {
     }
 
     fn transfer_in_place(&mut self, words: &mut [u8]) -> HalResult<()> {
+        let ptr = words.as_mut_ptr();
+        if self.try_dma(Some(ptr), Some(ptr as *const u8), words.len()) {
+            return Ok(());
+        }
         let total_len = words.len();
         let mut offset = 0;
 
@@ -1,9 +1,12 @@
 //! ESP32-S3 UART driver
 
-use hal::uart::{UartConfig, DataBits, StopBits, Parity};
+use hal::uart::{UartConfig, DataBits, StopBits, Parity, RxRing};
 use hal::error::{HalError, HalResult};
 use super::regs::UartRegs;
 
+/// RXFIFO_FULL and RXFIFO_OVF in the `int_*` registers
+const RX_INTS: u32 = (1 << 0) | (1 << 4);
+
 /// ESP32-S3 UART Port
 pub struct Esp32S3Uart {
     regs: *const UartRegs,
@@ -64,6 +67,35 @@ impl Esp32S3Uart {
     pub fn available(&self) -> usize {
         (self.regs().status.read() & 0xFF) as usize
     }
+
+    /// Raise the RX interrupt once `threshold` bytes (1..=127) wait in the
+    /// FIFO, or when it overflows. The interrupt handler calls
+    /// [`on_rx_interrupt`](Self::on_rx_interrupt).
+    pub fn enable_rx_interrupt(&mut self, threshold: u8) -> HalResult<()> {
+        if threshold == 0 || threshold > 127 {
+            return Err(HalError::InvalidParameter);
+        }
+        // RXFIFO_FULL_THRHD in conf1 bits 0-6
+        self.regs().conf1.modify(|v| (v & !0x7F) | threshold as u32);
+        self.regs().int_clr.write(RX_INTS);
+        self.regs().int_ena.modify(|v| v | RX_INTS);
+        Ok(())
+    }
+
+    /// Stop raising the RX interrupt.
+    pub fn disable_rx_interrupt(&mut self) {
+        self.regs().int_ena.modify(|v| v & !RX_INTS);
+    }
+
+    /// RX interrupt handler body: move the FIFO into `ring`, then
+    /// acknowledge the interrupt. Only the RX FIFO and interrupt registers
+    /// are touched, so the handler may use its own handle on the same UART.
+    pub fn on_rx_interrupt<const N: usize>(&self, ring: &RxRing<N>) {
+        while (self.regs().status.read() & 0xFF) != 0 {
+            ring.push(self.regs().fifo.read() as u8);
+        }
+        self.regs().int_clr.write(RX_INTS);
+    }
 }
 
 impl embedded_io::ErrorType for Esp32S3Uart {
@@ -0,0 +1,127 @@
+//! ESP32-S3 timer-group watchdog (MWDT) driver and reset cause
+//!
+//! Each timer group has one main watchdog. The driver uses stage 0 only,
+//! set to reset the whole system when it expires. Its registers are write
+//! protected; every access below unlocks and relocks them.
+
+use hal::error::{HalError, HalResult};
+use hal::mmio::{RO, RW};
+use hal::watchdog::{ResetCause, Watchdog};
+use super::regs::{TimgRegs, RTC_CNTL_BASE};
+
+// TIMG_WDTCONFIG0
+const WDT_EN: u32 = 1 << 31;
+const STG0_SHIFT: u32 = 29;
+const STG_RESET_SYSTEM: u32 = 3;
+const CONF_UPDATE_EN: u32 = 1 << 22;
+const SYS_RESET_LENGTH_SHIFT: u32 = 15;
+const CPU_RESET_LENGTH_SHIFT: u32 = 18;
+const RESET_LENGTH_3_2_US: u32 = 7;
+// TIMG_WDTCONFIG1: APB / 40_000 = 2 kHz, half-millisecond ticks
+const PRESCALE_SHIFT: u32 = 16;
+const PRESCALE: u32 = 40_000;
+const TICKS_PER_MS: u32 = 2;
+const WPROTECT_KEY: u32 = 0x50D8_3AA1;
+
+// RTC_CNTL_RESET_STATE_REG, RESET_CAUSE_PROCPU in bits 0..=5
+const RESET_STATE: usize = 0x38;
+const RESET_CAUSE_MASK: u32 = 0x3F;
+
+// RTC_CNTL_OPTIONS0_REG
+const OPTIONS0: usize = 0x00;
+const SW_SYS_RST: u32 = 1 << 31;
+
+/// Raw reset-reason code of the PRO CPU, as listed in the technical
+/// reference manual (e.g. `0x07` for a timer-group 0 watchdog reset).
+pub fn reset_reason() -> u32 {
+    let reg = unsafe { &*((RTC_CNTL_BASE + RESET_STATE) as *const RO<u32>) };
+    reg.read() & RESET_CAUSE_MASK
+}
+
+/// Resets the whole digital system, both cores included, as
+/// `esp_restart()` does; the next boot reports [`ResetCause::Software`].
+pub fn software_reset() -> ! {
+    let reg = unsafe { &*((RTC_CNTL_BASE + OPTIONS0) as *const RW<u32>) };
+    reg.modify(|v| v | SW_SYS_RST);
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+/// Why the chip last came out of reset.
+pub fn reset_cause() -> ResetCause {
+    match reset_reason() {
+        0x01 => ResetCause::PowerOn,
+        0x03 | 0x0C => ResetCause::Software,
+        0x05 => ResetCause::DeepSleep,
+        0x07..=0x09 | 0x0B | 0x0D | 0x10..=0x12 => ResetCause::Watchdog,
+        0x0F => ResetCause::Brownout,
+        _ => ResetCause::Other,
+    }
+}
+
+/// ESP32-S3 main system watchdog of one timer group
+pub struct Esp32S3Wdt {
+    regs: *const TimgRegs,
+}
+
+unsafe impl Send for Esp32S3Wdt {}
+unsafe impl Sync for Esp32S3Wdt {}
+
+impl Esp32S3Wdt {
+    /// Create a new Esp32S3Wdt handle
+    ///
+    /// # Safety
+    /// Unique ownership of the timer group's watchdog must be guaranteed by
+    /// the caller.
+    pub unsafe fn new(regs: *const TimgRegs) -> Self {
+        Self { regs }
+    }
+
+    fn regs(&self) -> &TimgRegs {
+        unsafe { &*self.regs }
+    }
+
+    /// Run `f` with the watchdog registers unlocked.
+    fn unlocked(&self, f: impl FnOnce(&TimgRegs)) {
+        let regs = self.regs();
+        regs.wdtwprotect.write(WPROTECT_KEY);
+        f(regs);
+        regs.wdtwprotect.write(0);
+    }
+}
+
+impl Watchdog for Esp32S3Wdt {
+    fn start(&mut self, timeout_ms: u32) -> HalResult<()> {
+        let ticks = timeout_ms
+            .checked_mul(TICKS_PER_MS)
+            .filter(|&t| t != 0)
+            .ok_or(HalError::InvalidParameter)?;
+        self.unlocked(|regs| {
+            regs.wdtconfig0.write(0);
+            regs.wdtconfig1.write(PRESCALE << PRESCALE_SHIFT);
+            regs.wdtconfig2.write(ticks);
+            regs.wdtconfig0.write(
+                WDT_EN
+                    | (STG_RESET_SYSTEM << STG0_SHIFT)
+                    | (RESET_LENGTH_3_2_US << SYS_RESET_LENGTH_SHIFT)
+                    | (RESET_LENGTH_3_2_US << CPU_RESET_LENGTH_SHIFT),
+            );
+            regs.wdtconfig0.modify(|v| v | CONF_UPDATE_EN);
+            regs.wdtfeed.write(1);
+        });
+        Ok(())
+    }
+
+    fn feed(&mut self) {
+        self.unlocked(|regs| regs.wdtfeed.write(1));
+    }
+
+    fn stop(&mut self) -> HalResult<()> {
+        self.unlocked(|regs| {
+            regs.wdtconfig0.write(0);
+            regs.wdtconfig0.modify(|v| v | CONF_UPDATE_EN);
+        });
+        Ok(())
+    }
+}
@@ -1,9 +1,27 @@
 //! ESP32-C6 GPIO driver
 
-use hal::gpio::PinMode;
+use hal::gpio::{Edge, PinMode};
 use hal::error::{HalError, HalResult};
 use super::regs::gpio;
 
+/// Number of GPIO pins (and `PINn` registers) on the ESP32-C6
+pub const GPIO_PINS: u8 = 31;
+
+/// INT_TYPE field (bits 7..9) of a `PINn` register
+const PIN_INT_TYPE_SHIFT: u32 = 7;
+const PIN_INT_TYPE_MASK: u32 = 0x7 << PIN_INT_TYPE_SHIFT;
+/// INT_ENA field (bits 13..17); bit 13 routes the pin to the CPU interrupt
+const PIN_INT_ENA_MASK: u32 = 0x1F << 13;
+const PIN_INT_ENA_CPU: u32 = 1 << 13;
+
+/// Take and acknowledge the pending GPIO interrupts, one bit per pin. Call
+/// from the GPIO interrupt handler.
+pub fn take_interrupts() -> u32 {
+    let pending = gpio().status.read();
+    gpio().status_w1tc.write(pending);
+    pending
+}
+
 /// ESP32-C6 GPIO Pin
 pub struct Esp32C6Pin {
     pin: u8,
@@ -41,6 +59,33 @@ impl Esp32C6Pin {
         Ok(())
     }
 
+    /// Raise the GPIO interrupt on `edge`. Pending pins are reported by
+    /// [`take_interrupts`].
+    pub fn listen(&mut self, edge: Edge) -> HalResult<()> {
+        if self.pin >= GPIO_PINS {
+            return Err(HalError::InvalidParameter);
+        }
+        let int_type = match edge {
+            Edge::Rising => 1,
+            Edge::Falling => 2,
+            Edge::Both => 3,
+        };
+        gpio().status_w1tc.write(1 << self.pin);
+        gpio().pin[self.pin as usize].modify(|v| {
+            (v & !(PIN_INT_TYPE_MASK | PIN_INT_ENA_MASK))
+                | (int_type << PIN_INT_TYPE_SHIFT)
+                | PIN_INT_ENA_CPU
+        });
+        Ok(())
+    }
+
+    /// Stop raising the GPIO interrupt for this pin.
+    pub fn unlisten(&mut self) {
+        if let Some(reg) = gpio().pin.get(self.pin as usize) {
+            reg.modify(|v| v & !(PIN_INT_TYPE_MASK | PIN_INT_ENA_MASK));
+        }
+    }
+
     /// Pin number on the port.
     pub fn pin_number(&self) -> u32 {
         self.pin as u32
@@ -0,0 +1,352 @@
+//! ESP32-C6 I2C master driver
+//!
+//! The controller runs a list of up to eight commands (RSTART, WRITE, READ,
+//! STOP, END) against a 32-byte FIFO. Longer transfers are split into
+//! batches ending in END, which keeps the bus held between them. Slaves may
+//! stretch SCL for up to the configured timeout; a stuck bus is cleared with
+//! [`Esp32C6I2c::recover_bus`], which failed transfers call automatically.
+
+use hal::i2c::{I2cAddress, I2cConfig, I2cSpeed, Operation, SevenBitAddress, TenBitAddress};
+use hal::error::{HalError, HalResult};
+use super::regs::I2cRegs;
+
+/// Source clock (XTAL) of the I2C controller.
+const SCLK_HZ: u32 = 40_000_000;
+
+const FIFO_LEN: usize = 32;
+const MAX_CMDS: usize = 8;
+
+// CTR
+const CTR_SDA_FORCE_OUT: u32 = 1 << 0;
+const CTR_SCL_FORCE_OUT: u32 = 1 << 1;
+const CTR_MS_MODE: u32 = 1 << 4;
+const CTR_TRANS_START: u32 = 1 << 5;
+const CTR_CLK_EN: u32 = 1 << 8;
+const CTR_ARBITRATION_EN: u32 = 1 << 9;
+const CTR_FSM_RST: u32 = 1 << 10;
+const CTR_CONF_UPGATE: u32 = 1 << 11;
+
+const SR_BUS_BUSY: u32 = 1 << 4;
+const TO_EN: u32 = 1 << 5;
+const FIFO_RST: u32 = (1 << 12) | (1 << 13);
+const CLK_SCLK_ACTIVE: u32 = 1 << 21;
+const SCL_RST_SLV_EN: u32 = 1 << 0;
+
+// INT_RAW / INT_CLR
+const INT_END_DETECT: u32 = 1 << 3;
+const INT_ARBITRATION_LOST: u32 = 1 << 5;
+const INT_TRANS_COMPLETE: u32 = 1 << 7;
+const INT_TIME_OUT: u32 = 1 << 8;
+const INT_NACK: u32 = 1 << 10;
+const INT_SCL_ST_TO: u32 = 1 << 13;
+const INT_SCL_MAIN_ST_TO: u32 = 1 << 14;
+const INT_ALL: u32 = 0x3_FFFF;
+
+// COMD
+const CMD_ACK_CHECK: u32 = 1 << 8;
+const CMD_NACK: u32 = 1 << 10;
+const OP_WRITE: u32 = 1 << 11;
+const OP_STOP: u32 = 2 << 11;
+const OP_READ: u32 = 3 << 11;
+const OP_END: u32 = 4 << 11;
+const OP_RSTART: u32 = 6 << 11;
+
+/// Longest stretch timeout the `TO` register can express (2^22 cycles).
+const MAX_TIMEOUT_LOG2: u32 = 22;
+
+/// Commands and TX FIFO bytes queued for one hardware run.
+#[derive(Default)]
+struct Batch {
+    cmds: [u32; MAX_CMDS],
+    len: usize,
+    tx: usize,
+}
+
+impl Batch {
+    /// Whether `cmds` more commands (plus the END/STOP) and `tx` more FIFO
+    /// bytes still fit.
+    fn fits(&self, cmds: usize, tx: usize) -> bool {
+        self.len + cmds < MAX_CMDS && self.tx + tx <= FIFO_LEN
+    }
+
+    fn push(&mut self, cmd: u32) {
+        self.cmds[self.len] = cmd;
+        self.len += 1;
+    }
+}
+
+/// ESP32-C6 I2C master
+pub struct Esp32C6I2c {
+    regs: *const I2cRegs,
+}
+
+unsafe impl Send for Esp32C6I2c {}
+unsafe impl Sync for Esp32C6I2c {}
+
+impl Esp32C6I2c {
+    /// Create a new Esp32C6I2c handle
+    ///
+    /// # Safety
+    /// Unique ownership of the I2C peripheral must be guaranteed by the
+    /// caller, and SDA/SCL must already be routed as open-drain pins.
+    pub unsafe fn new(regs: *const I2cRegs) -> Self {
+        Self { regs }
+    }
+
+    fn regs(&self) -> &I2cRegs {
+        unsafe { &*self.regs }
+    }
+
+    /// Configure the bus speed in master mode and allow 10 ms of clock
+    /// stretching. Call once before use (embedded-hal `I2c` has no
+    /// configure step).
+    pub fn configure(&mut self, config: &I2cConfig) -> HalResult<()> {
+        let freq = match config.speed {
+            I2cSpeed::Standard => 100_000,
+            I2cSpeed::Fast => 400_000,
+            I2cSpeed::FastPlus => 1_000_000,
+            I2cSpeed::HighSpeed => return Err(HalError::NotSupported),
+        };
+        let regs = self.regs();
+
+        regs.ctr.write(
+            CTR_SDA_FORCE_OUT | CTR_SCL_FORCE_OUT | CTR_MS_MODE | CTR_CLK_EN | CTR_ARBITRATION_EN,
+        );
+
+        // Timing follows ESP-IDF's i2c_ll_master_cal_bus_clk.
+        let div = SCLK_HZ / (freq * 1024) + 1;
+        let half = SCLK_HZ / div / freq / 2;
+        let wait_high = if freq >= 80_000 { half / 2 - 2 } else { half / 4 };
+        regs.clk_conf.write(CLK_SCLK_ACTIVE | (div - 1));
+        regs.scl_low_period.write(half - 1);
+        regs.scl_high_period.write((half - wait_high) | (wait_high << 9));
+        regs.sda_hold.write(half / 4);
+        regs.sda_sample.write(half / 2);
+        regs.scl_rstart_setup.write(half);
+        regs.scl_stop_setup.write(half);
+        regs.scl_start_hold.write(half - 1);
+        regs.scl_stop_hold.write(half - 1);
+
+        self.set_stretch_timeout(10_000)?;
+        self.reset_fifos();
+        Ok(())
+    }
+
+    /// Longest time a slave may hold SCL low before the transfer fails
+    /// with [`HalError::HardwareError`] (rounded up to a power of two of
+    /// the 40 MHz source clock, at most ~104 ms).
+    pub fn set_stretch_timeout(&mut self, timeout_us: u32) -> HalResult<()> {
+        let cycles = (timeout_us as u64 * (SCLK_HZ / 1_000_000) as u64).max(1);
+        let log2 = 64 - (cycles - 1).leading_zeros();
+        if log2 > MAX_TIMEOUT_LOG2 {
+            return Err(HalError::InvalidParameter);
+        }
+        self.regs().to.write(TO_EN | log2);
+        self.update();
+        Ok(())
+    }
+
+    /// Free a bus left busy by an aborted transfer: reset the controller
+    /// and clock out up to nine SCL pulses so a slave stuck mid-byte
+    /// releases SDA.
+    pub fn recover_bus(&mut self) -> HalResult<()> {
+        self.reset_fsm();
+        self.regs().scl_sp_conf.write(SCL_RST_SLV_EN | (9 << 1));
+        self.update();
+        let mut spins = 0u32;
+        while self.regs().scl_sp_conf.read() & SCL_RST_SLV_EN != 0 {
+            spins += 1;
+            if spins > 100_000 {
+                return Err(HalError::Timeout);
+            }
+        }
+
+        self.reset_fifos();
+        self.regs().int_clr.write(INT_ALL);
+        if self.regs().sr.read() & SR_BUS_BUSY != 0 {
+            return Err(HalError::HardwareError);
+        }
+        Ok(())
+    }
+
+    fn update(&self) {
+        self.regs().ctr.modify(|v| v | CTR_CONF_UPGATE);
+    }
+
+    fn reset_fsm(&self) {
+        self.regs().ctr.modify(|v| v | CTR_FSM_RST);
+        self.regs().ctr.modify(|v| v & !CTR_FSM_RST);
+    }
+
+    fn reset_fifos(&self) {
+        self.regs().fifo_conf.modify(|v| v | FIFO_RST);
+        self.regs().fifo_conf.modify(|v| v & !FIFO_RST);
+    }
+
+    /// Queue a (repeated) START and the address phase for `read`.
+    fn address(&mut self, batch: &mut Batch, address: I2cAddress, read: bool) -> HalResult<()> {
+        if !batch.fits(4, 3) {
+            self.run(batch, OP_END, &mut [])?;
+        }
+        let fifo = &self.regs().data;
+        batch.push(OP_RSTART);
+        match address {
+            I2cAddress::SevenBit(addr) => {
+                fifo.write(((addr as u32) << 1) | read as u32);
+                batch.push(OP_WRITE | CMD_ACK_CHECK | 1);
+                batch.tx += 1;
+            }
+            I2cAddress::TenBit(addr) => {
+                // 11110xx0 header with the two high address bits, then the
+                // low byte; a read repeats the header with R/W set.
+                let header = 0xF0 | ((addr >> 7) as u32 & 0x06);
+                fifo.write(header);
+                fifo.write(addr as u32 & 0xFF);
+                batch.push(OP_WRITE | CMD_ACK_CHECK | 2);
+                batch.tx += 2;
+                if read {
+                    fifo.write(header | 1);
+                    batch.push(OP_RSTART);
+                    batch.push(OP_WRITE | CMD_ACK_CHECK | 1);
+                    batch.tx += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Terminate `batch` with `last` (END or STOP), run it, and drain the
+    /// bytes it read into `rx`.
+    fn run(&mut self, batch: &mut Batch, last: u32, rx: &mut [u8]) -> HalResult<()> {
+        batch.push(last);
+        let regs = self.regs();
+        for (reg, &cmd) in regs.comd.iter().zip(&batch.cmds[..batch.len]) {
+            reg.write(cmd);
+        }
+        *batch = Batch::default();
+
+        regs.int_clr.write(INT_ALL);
+        self.update();
+        regs.ctr.modify(|v| v | CTR_TRANS_START);
+
+        loop {
+            let raw = regs.int_raw.read();
+            if raw & INT_NACK != 0 {
+                // Abandon the rest of the command list; the next transfer
+                // begins with a fresh START.
+                self.reset_fsm();
+                self.reset_fifos();
+                return Err(HalError::Timeout);
+            }
+            if raw & INT_ARBITRATION_LOST != 0 {
+                self.recover_bus()?;
+                return Err(HalError::Busy);
+            }
+            if raw & (INT_TIME_OUT | INT_SCL_ST_TO | INT_SCL_MAIN_ST_TO) != 0 {
+                self.recover_bus()?;
+                return Err(HalError::HardwareError);
+            }
+            if raw & (INT_END_DETECT | INT_TRANS_COMPLETE) != 0 {
+                break;
+            }
+        }
+
+        for slot in rx {
+            *slot = regs.data.read() as u8;
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, address: I2cAddress, operations: &mut [Operation<'_>]) -> HalResult<()> {
+        if operations.is_empty() {
+            return Ok(());
+        }
+        if self.regs().sr.read() & SR_BUS_BUSY != 0 {
+            self.recover_bus()?;
+        }
+        self.reset_fifos();
+
+        let mut batch = Batch::default();
+        let mut prev_read = None;
+        let mut stopped = false;
+        let count = operations.len();
+
+        for i in 0..count {
+            let read = matches!(operations[i], Operation::Read(_));
+            // Adjacent reads form one segment: only its final byte is NACKed.
+            let nack_last = !matches!(operations.get(i + 1), Some(Operation::Read(_)));
+            if prev_read != Some(read) {
+                self.address(&mut batch, address, read)?;
+            }
+            prev_read = Some(read);
+
+            match &mut operations[i] {
+                Operation::Write(bytes) => {
+                    let mut rest = &bytes[..];
+                    while !rest.is_empty() {
+                        if !batch.fits(1, 1) {
+                            self.run(&mut batch, OP_END, &mut [])?;
+                        }
+                        let take = rest.len().min(FIFO_LEN - batch.tx);
+                        for &b in &rest[..take] {
+                            self.regs().data.write(b as u32);
+                        }
+                        batch.push(OP_WRITE | CMD_ACK_CHECK | take as u32);
+                        batch.tx += take;
+                        rest = &rest[take..];
+                    }
+                }
+                Operation::Read(buf) => {
+                    let len = buf.len();
+                    let mut offset = 0;
+                    while offset < len {
+                        let take = (len - offset).min(FIFO_LEN);
+                        let end = offset + take;
+                        if !batch.fits(2, 0) {
+                            self.run(&mut batch, OP_END, &mut [])?;
+                        }
+                        if end == len && nack_last {
+                            if take > 1 {
+                                batch.push(OP_READ | (take as u32 - 1));
+                            }
+                            batch.push(OP_READ | CMD_NACK | 1);
+                        } else {
+                            batch.push(OP_READ | take as u32);
+                        }
+                        stopped = end == len && i + 1 == count;
+                        let last = if stopped { OP_STOP } else { OP_END };
+                        self.run(&mut batch, last, &mut buf[offset..end])?;
+                        offset = end;
+                    }
+                }
+            }
+        }
+
+        if !stopped {
+            self.run(&mut batch, OP_STOP, &mut [])?;
+        }
+        Ok(())
+    }
+}
+
+impl embedded_hal::i2c::ErrorType for Esp32C6I2c {
+    type Error = HalError;
+}
+
+impl embedded_hal::i2c::I2c<SevenBitAddress> for Esp32C6I2c {
+    fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> HalResult<()> {
+        if address > 0x7F {
+            return Err(HalError::InvalidParameter);
+        }
+        self.execute(I2cAddress::SevenBit(address), operations)
+    }
+}
+
+impl embedded_hal::i2c::I2c<TenBitAddress> for Esp32C6I2c {
+    fn transaction(&mut self, address: u16, operations: &mut [Operation<'_>]) -> HalResult<()> {
+        if address > 0x3FF {
+            return Err(HalError::InvalidParameter);
+        }
+        self.execute(I2cAddress::TenBit(address), operations)
+    }
+}
@@ -1,8 +1,23 @@
 //! ESP32-C6 Interrupt Matrix routing and priority control
+//!
+//! The interrupt matrix (INTMTX) maps peripheral sources onto the 31 CPU
+//! interrupt lines; INTPRI enables each line, gives it a priority 1–15 and
+//! holds the threshold below which lines are masked.
 
 use hal::error::{HalError, HalResult};
 use hal::interrupt::{InterruptController, InterruptPriority};
 
+const INTPRI_BASE:    usize = 0x600C_5000;
+const CPU_INT_ENABLE: usize = 0x000;
+const CPU_INT_TYPE:   usize = 0x004;
+const CPU_INT_THRESH: usize = 0x08C;
+const CPU_INT_CLEAR:  usize = 0x0A8;
+
+/// Lowest usable CPU interrupt priority; a threshold at this level masks nothing
+pub const PRIORITY_MIN: u8 = 1;
+/// Highest CPU interrupt priority
+pub const PRIORITY_MAX: u8 = 15;
+
 /// ESP32-C6 Interrupt Matrix controller
 pub struct Esp32C6IntMatrix {
     _private: (),
@@ -24,6 +39,42 @@ impl Esp32C6IntMatrix {
     fn pri_reg(&self, cpu_int: u32) -> *mut u32 {
         (0x600C_5000 + 0x0010 + 4 * (cpu_int - 1) as usize) as *mut u32
     }
+
+    fn intpri(&self, offset: usize) -> *mut u32 {
+        (INTPRI_BASE + offset) as *mut u32
+    }
+
+    /// Route peripheral interrupt `source` to CPU line `cpu_int` (1–31),
+    /// level-triggered at `priority` (1–15), and enable the line.
+    pub fn route(&mut self, source: u32, cpu_int: u32, priority: u8) -> HalResult<()> {
+        if source >= 128 || !(1..32).contains(&cpu_int) || !(PRIORITY_MIN..=PRIORITY_MAX).contains(&priority) {
+            return Err(HalError::InvalidParameter);
+        }
+        let line = 1u32 << cpu_int;
+        unsafe {
+            core::ptr::write_volatile(self.map_reg(source), cpu_int);
+            core::ptr::write_volatile(self.pri_reg(cpu_int), priority as u32);
+            let level = core::ptr::read_volatile(self.intpri(CPU_INT_TYPE)) & !line;
+            core::ptr::write_volatile(self.intpri(CPU_INT_TYPE), level);
+            core::ptr::write_volatile(self.intpri(CPU_INT_CLEAR), line);
+            core::ptr::write_volatile(self.intpri(CPU_INT_CLEAR), 0);
+            let enabled = core::ptr::read_volatile(self.intpri(CPU_INT_ENABLE)) | line;
+            core::ptr::write_volatile(self.intpri(CPU_INT_ENABLE), enabled);
+        }
+        Ok(())
+    }
+
+    /// Current interrupt threshold: lines with a lower priority are masked.
+    pub fn threshold(&self) -> u8 {
+        unsafe { core::ptr::read_volatile(self.intpri(CPU_INT_THRESH)) as u8 }
+    }
+
+    /// Set the interrupt threshold. Takes effect before this returns, so a
+    /// masked interrupt cannot fire after the call.
+    pub fn set_threshold(&mut self, threshold: u8) {
+        unsafe { core::ptr::write_volatile(self.intpri(CPU_INT_THRESH), threshold as u32) }
+        core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+    }
 }
 
 impl InterruptController for Esp32C6IntMatrix {
@@ -2,13 +2,19 @@
 
 pub mod regs;
 pub mod gpio;
+pub mod i2c;
 pub mod spi;
 pub mod uart;
+pub mod wdt;
 pub mod intmtx;
+pub mod systimer;
 pub mod radio;
 
 pub use regs::GpioRegs;
 pub use gpio::Esp32C6Pin;
+pub use i2c::Esp32C6I2c;
 pub use spi::Esp32C6Spi;
 pub use uart::Esp32C6Uart;
+pub use wdt::Esp32C6Wdt;
 pub use intmtx::Esp32C6IntMatrix;
+pub use systimer::Esp32C6Systimer;
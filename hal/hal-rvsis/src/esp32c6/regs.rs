@@ -6,6 +6,11 @@ use hal::mmio::{RO, RW, WO};
 pub const GPIO_BASE:  usize = 0x6009_1000;
 pub const SPI2_BASE:  usize = 0x6008_1000; // GP-SPI2
 pub const UART0_BASE: usize = 0x6000_0000;
+pub const I2C0_BASE:  usize = 0x6000_4000;
+pub const TIMG0_BASE: usize = 0x6000_8000;
+pub const TIMG1_BASE: usize = 0x6000_9000;
+pub const LP_CLKRST_BASE: usize = 0x600B_0400;
+pub const LP_AON_BASE:    usize = 0x600B_1000;
 
 /// ESP32-C6 GPIO registers
 #[repr(C)]
@@ -27,6 +32,11 @@ pub struct GpioRegs {
     _reserved1:      u32,       // 0x038
     pub in_:         RO<u32>,   // 0x03C
     pub in1:         RO<u32>,   // 0x040
+    pub status:      RW<u32>,   // 0x044
+    pub status_w1ts: WO<u32>,   // 0x048
+    pub status_w1tc: WO<u32>,   // 0x04C
+    _reserved2:      [u32; 9],  // 0x050 - 0x070
+    pub pin:         [RW<u32>; 31], // 0x074 - 0x0EC (PIN0 to PIN30)
 }
 
 /// ESP32-C6 SPI registers
@@ -63,6 +73,52 @@ pub struct UartRegs {
     pub conf1:    RW<u32>,   // 0x24
 }
 
+/// ESP32-C6 I2C registers
+#[repr(C)]
+pub struct I2cRegs {
+    pub scl_low_period:   RW<u32>,  // 0x00
+    pub ctr:              RW<u32>,  // 0x04
+    pub sr:               RO<u32>,  // 0x08
+    pub to:               RW<u32>,  // 0x0C
+    pub slave_addr:       RW<u32>,  // 0x10
+    pub fifo_st:          RO<u32>,  // 0x14
+    pub fifo_conf:        RW<u32>,  // 0x18
+    pub data:             RW<u32>,  // 0x1C
+    pub int_raw:          RO<u32>,  // 0x20
+    pub int_clr:          WO<u32>,  // 0x24
+    pub int_ena:          RW<u32>,  // 0x28
+    pub int_status:       RO<u32>,  // 0x2C
+    pub sda_hold:         RW<u32>,  // 0x30
+    pub sda_sample:       RW<u32>,  // 0x34
+    pub scl_high_period:  RW<u32>,  // 0x38
+    _r0:                  u32,      // 0x3C
+    pub scl_start_hold:   RW<u32>,  // 0x40
+    pub scl_rstart_setup: RW<u32>,  // 0x44
+    pub scl_stop_hold:    RW<u32>,  // 0x48
+    pub scl_stop_setup:   RW<u32>,  // 0x4C
+    pub filter_cfg:       RW<u32>,  // 0x50
+    pub clk_conf:         RW<u32>,  // 0x54
+    pub comd:             [RW<u32>; 8], // 0x58 - 0x74
+    pub scl_st_time_out:  RW<u32>,  // 0x78
+    pub scl_main_st_time_out: RW<u32>, // 0x7C
+    pub scl_sp_conf:      RW<u32>,  // 0x80
+    pub scl_stretch_conf: RW<u32>,  // 0x84
+}
+
+/// ESP32-C6 timer group registers (the main system watchdog part)
+#[repr(C)]
+pub struct TimgRegs {
+    _r0:             [u32; 18], // 0x00 - 0x44 (general-purpose timers)
+    pub wdtconfig0:  RW<u32>,   // 0x48
+    pub wdtconfig1:  RW<u32>,   // 0x4C
+    pub wdtconfig2:  RW<u32>,   // 0x50 (stage 0 timeout)
+    pub wdtconfig3:  RW<u32>,   // 0x54
+    pub wdtconfig4:  RW<u32>,   // 0x58
+    pub wdtconfig5:  RW<u32>,   // 0x5C
+    pub wdtfeed:     WO<u32>,   // 0x60
+    pub wdtwprotect: RW<u32>,   // 0x64
+}
+
 /// Get global reference to GpioRegs
 pub fn gpio() -> &'static GpioRegs {
     unsafe { &*(GPIO_BASE as *const GpioRegs) }
@@ -0,0 +1,120 @@
+//! ESP32-C6 SYSTIMER alarm driver
+//!
+//! Counter unit 0 free-runs at 16 MHz; esp-hal reads it for `Instant`, so
+//! this driver only ever starts it, never reloads it. Alarm comparator 0
+//! (TARGET0) provides the periodic or one-shot timeout and raises interrupt
+//! source [`SYSTIMER_TARGET0_SOURCE`].
+
+use hal::error::{HalError, HalResult};
+use hal::timer::{Timer, TimerMode};
+
+/// SYSTIMER peripheral base address
+pub const SYSTIMER_BASE: usize = 0x6000_A000;
+/// Counter frequency (XTAL 40 MHz / 2.5)
+pub const SYSTIMER_HZ: u64 = 16_000_000;
+/// Interrupt matrix source of the TARGET0 alarm
+pub const SYSTIMER_TARGET0_SOURCE: u32 = 57;
+
+const CONF:           usize = 0x00;
+const UNIT0_OP:       usize = 0x04;
+const TARGET0_HI:     usize = 0x1C;
+const TARGET0_LO:     usize = 0x20;
+const TARGET0_CONF:   usize = 0x34;
+const UNIT0_VALUE_HI: usize = 0x40;
+const UNIT0_VALUE_LO: usize = 0x44;
+const COMP0_LOAD:     usize = 0x50;
+const INT_ENA:        usize = 0x64;
+const INT_CLR:        usize = 0x6C;
+
+const CONF_CLK_EN:          u32 = 1 << 31;
+const CONF_UNIT0_WORK_EN:   u32 = 1 << 30;
+const CONF_TARGET0_WORK_EN: u32 = 1 << 24;
+const OP_UPDATE:            u32 = 1 << 30;
+const OP_VALUE_VALID:       u32 = 1 << 29;
+const TARGET_PERIOD_MODE:   u32 = 1 << 30;
+const TARGET_PERIOD_MAX:    u64 = (1 << 26) - 1;
+const INT_TARGET0:          u32 = 1 << 0;
+
+/// SYSTIMER alarm 0 on counter unit 0
+pub struct Esp32C6Systimer {
+    _private: (),
+}
+
+impl Esp32C6Systimer {
+    /// Create a new Esp32C6Systimer handle
+    ///
+    /// # Safety
+    /// Alarm comparator 0 must not be used by anything else (esp-hal's
+    /// embassy time driver claims it too).
+    pub const unsafe fn new() -> Self {
+        Self { _private: () }
+    }
+
+    fn read(&self, offset: usize) -> u32 {
+        unsafe { core::ptr::read_volatile((SYSTIMER_BASE + offset) as *const u32) }
+    }
+
+    fn write(&self, offset: usize, value: u32) {
+        unsafe { core::ptr::write_volatile((SYSTIMER_BASE + offset) as *mut u32, value) }
+    }
+
+    fn modify(&self, offset: usize, f: impl FnOnce(u32) -> u32) {
+        self.write(offset, f(self.read(offset)));
+    }
+}
+
+impl Timer for Esp32C6Systimer {
+    fn start(&mut self, period_us: u64, mode: TimerMode) -> HalResult<()> {
+        let ticks = period_us * SYSTIMER_HZ / 1_000_000;
+        if ticks == 0 {
+            return Err(HalError::InvalidParameter);
+        }
+        self.modify(CONF, |conf| (conf | CONF_CLK_EN | CONF_UNIT0_WORK_EN) & !CONF_TARGET0_WORK_EN);
+        match mode {
+            TimerMode::Periodic => {
+                if ticks > TARGET_PERIOD_MAX {
+                    return Err(HalError::InvalidParameter);
+                }
+                // Unit 0, period mode.
+                self.write(TARGET0_CONF, TARGET_PERIOD_MODE | ticks as u32);
+            }
+            TimerMode::OneShot => {
+                let target = self.counter() + ticks;
+                self.write(TARGET0_CONF, 0);
+                self.write(TARGET0_HI, (target >> 32) as u32 & 0xF_FFFF);
+                self.write(TARGET0_LO, target as u32);
+            }
+        }
+        self.write(COMP0_LOAD, 1);
+        self.modify(CONF, |conf| conf | CONF_TARGET0_WORK_EN);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> HalResult<()> {
+        self.modify(CONF, |conf| conf & !CONF_TARGET0_WORK_EN);
+        Ok(())
+    }
+
+    fn counter(&self) -> u64 {
+        self.write(UNIT0_OP, OP_UPDATE);
+        while self.read(UNIT0_OP) & OP_VALUE_VALID == 0 {}
+        let hi = self.read(UNIT0_VALUE_HI) & 0xF_FFFF;
+        let lo = self.read(UNIT0_VALUE_LO);
+        (hi as u64) << 32 | lo as u64
+    }
+
+    fn enable_interrupt(&mut self) -> HalResult<()> {
+        self.modify(INT_ENA, |ena| ena | INT_TARGET0);
+        Ok(())
+    }
+
+    fn disable_interrupt(&mut self) -> HalResult<()> {
+        self.modify(INT_ENA, |ena| ena & !INT_TARGET0);
+        Ok(())
+    }
+
+    fn clear_interrupt(&mut self) -> HalResult<()> {
+        self.write(INT_CLR, INT_TARGET0);
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+//! ESP32-C6 timer-group watchdog (MWDT) driver and reset cause
+//!
+//! Each timer group has one main watchdog. The driver uses stage 0 only,
+//! set to reset the whole system when it expires. Its registers are write
+//! protected; every access below unlocks and relocks them.
+
+use hal::error::{HalError, HalResult};
+use hal::mmio::{RO, RW};
+use hal::watchdog::{ResetCause, Watchdog};
+use super::regs::{TimgRegs, LP_AON_BASE, LP_CLKRST_BASE};
+
+// TIMG_WDTCONFIG0
+const WDT_EN: u32 = 1 << 31;
+const STG0_SHIFT: u32 = 29;
+const STG_RESET_SYSTEM: u32 = 3;
+const CONF_UPDATE_EN: u32 = 1 << 22;
+const SYS_RESET_LENGTH_SHIFT: u32 = 15;
+const CPU_RESET_LENGTH_SHIFT: u32 = 18;
+const RESET_LENGTH_3_2_US: u32 = 7;
+// TIMG_WDTCONFIG1: XTAL / 20_000 = 2 kHz, half-millisecond ticks
+const PRESCALE_SHIFT: u32 = 16;
+const PRESCALE: u32 = 20_000;
+const TICKS_PER_MS: u32 = 2;
+const WPROTECT_KEY: u32 = 0x50D8_3AA1;
+
+// LP_CLKRST_RESET_CAUSE_REG, core 0 cause in bits 0..=4
+const RESET_CAUSE: usize = 0x10;
+const RESET_CAUSE_MASK: u32 = 0x1F;
+
+// LP_AON_SYS_CFG_REG
+const SYS_CFG: usize = 0x08;
+const HPSYS_SW_RESET: u32 = 1 << 31;
+
+/// Raw reset-reason code of the HP core, as listed in the technical
+/// reference manual (e.g. `0x07` for a timer-group 0 watchdog reset).
+pub fn reset_reason() -> u32 {
+    let reg = unsafe { &*((LP_CLKRST_BASE + RESET_CAUSE) as *const RO<u32>) };
+    reg.read() & RESET_CAUSE_MASK
+}
+
+/// Resets the whole HP system, as `esp_restart()` does; the next boot
+/// reports [`ResetCause::Software`].
+pub fn software_reset() -> ! {
+    let reg = unsafe { &*((LP_AON_BASE + SYS_CFG) as *const RW<u32>) };
+    reg.modify(|v| v | HPSYS_SW_RESET);
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+/// Why the chip last came out of reset.
+pub fn reset_cause() -> ResetCause {
+    match reset_reason() {
+        0x01 => ResetCause::PowerOn,
+        0x03 | 0x0C => ResetCause::Software,
+        0x05 => ResetCause::DeepSleep,
+        0x07..=0x09 | 0x0B | 0x0D | 0x10..=0x12 => ResetCause::Watchdog,
+        0x0F => ResetCause::Brownout,
+        _ => ResetCause::Other,
+    }
+}
+
+/// ESP32-C6 main system watchdog of one timer group
+pub struct Esp32C6Wdt {
+    regs: *const TimgRegs,
+}
+
+unsafe impl Send for Esp32C6Wdt {}
+unsafe impl Sync for Esp32C6Wdt {}
+
+impl Esp32C6Wdt {
+    /// Create a new Esp32C6Wdt handle
+    ///
+    /// # Safety
+    /// Unique ownership of the timer group's watchdog must be guaranteed by
+    /// the caller.
+    pub unsafe fn new(regs: *const TimgRegs) -> Self {
+        Self { regs }
+    }
+
+    fn regs(&self) -> &TimgRegs {
+        unsafe { &*self.regs }
+    }
+
+    /// Run `f` with the watchdog registers unlocked.
+    fn unlocked(&self, f: impl FnOnce(&TimgRegs)) {
+        let regs = self.regs();
+        regs.wdtwprotect.write(WPROTECT_KEY);
+        f(regs);
+        regs.wdtwprotect.write(0);
+    }
+}
+
+impl Watchdog for Esp32C6Wdt {
+    fn start(&mut self, timeout_ms: u32) -> HalResult<()> {
+        let ticks = timeout_ms
+            .checked_mul(TICKS_PER_MS)
+            .filter(|&t| t != 0)
+            .ok_or(HalError::InvalidParameter)?;
+        self.unlocked(|regs| {
+            regs.wdtconfig0.write(0);
+            regs.wdtconfig1.write(PRESCALE << PRESCALE_SHIFT);
+            regs.wdtconfig2.write(ticks);
+            regs.wdtconfig0.write(
+                WDT_EN
+                    | (STG_RESET_SYSTEM << STG0_SHIFT)
+                    | (RESET_LENGTH_3_2_US << SYS_RESET_LENGTH_SHIFT)
+                    | (RESET_LENGTH_3_2_US << CPU_RESET_LENGTH_SHIFT),
+            );
+            regs.wdtconfig0.modify(|v| v | CONF_UPDATE_EN);
+            regs.wdtfeed.write(1);
+        });
+        Ok(())
+    }
+
+    fn feed(&mut self) {
+        self.unlocked(|regs| regs.wdtfeed.write(1));
+    }
+
+    fn stop(&mut self) -> HalResult<()> {
+        self.unlocked(|regs| {
+            regs.wdtconfig0.write(0);
+            regs.wdtconfig0.modify(|v| v | CONF_UPDATE_EN);
+        });
+        Ok(())
+    }
+}
@@ -1,4 +1,13 @@
 //! ADC (Analog-to-Digital Converter) abstraction
+//!
+//! Besides the channel/controller traits this module provides the pieces
+//! platform drivers share: [`AdcCalibration`] to turn raw codes into
+//! millivolts, and [`DoubleBuffer`], the ping-pong buffer a continuous
+//! (DMA) conversion fills while the application consumes the other half.
+
+use core::cell::UnsafeCell;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
 
 use crate::error::HalResult;
 
@@ -67,3 +76,172 @@ pub trait AdcController: Send + Sync {
     /// Stop continuous conversion
     fn stop_continuous(&mut self) -> HalResult<()>;
 }
+
+// ---------------------------------------------------------------------------
+// Calibration
+// ---------------------------------------------------------------------------
+
+/// Two-point linear map from raw codes to millivolts.
+///
+/// Built from the chip's factory calibration (eFuse) or from a measurement
+/// of two known voltages; codes outside the two points are extrapolated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdcCalibration {
+    raw_lo: u16,
+    mv_lo: u32,
+    raw_hi: u16,
+    mv_hi: u32,
+}
+
+impl AdcCalibration {
+    /// Line through (`raw_lo`, `mv_lo`) and (`raw_hi`, `mv_hi`);
+    /// `raw_hi` must be greater than `raw_lo`.
+    pub const fn two_point(raw_lo: u16, mv_lo: u32, raw_hi: u16, mv_hi: u32) -> Self {
+        assert!(raw_hi > raw_lo, "calibration points must be distinct");
+        Self { raw_lo, mv_lo, raw_hi, mv_hi }
+    }
+
+    /// Voltage for `raw`, clamped at 0 mV.
+    pub fn millivolts(&self, raw: u16) -> u32 {
+        let span = i64::from(self.raw_hi) - i64::from(self.raw_lo);
+        let rise = i64::from(self.mv_hi) - i64::from(self.mv_lo);
+        let offset = i64::from(raw) - i64::from(self.raw_lo);
+        let mv = i64::from(self.mv_lo) + (offset * rise + span / 2).div_euclid(span);
+        mv.clamp(0, i64::from(u32::MAX)) as u32
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Continuous-mode buffering
+// ---------------------------------------------------------------------------
+
+/// Receiving side of a continuous conversion: two equally sized halves that
+/// the DMA engine fills alternately.
+pub trait SampleSink: Sync {
+    /// Bytes per half.
+    fn half_len(&self) -> usize;
+
+    /// Start of half `half` (0 or 1), for programming the DMA engine.
+    fn half_ptr(&self, half: usize) -> *mut u8;
+
+    /// Called from the DMA interrupt once `half` is full.
+    fn mark_ready(&self, half: usize);
+}
+
+#[repr(C, align(4))]
+struct Half<const N: usize>(UnsafeCell<[u8; N]>);
+
+/// Ping-pong buffer for continuous conversions.
+///
+/// The DMA interrupt marks a half ready; the consumer [`take`](Self::take)s
+/// it and hands it back by dropping the guard. A half the DMA engine comes
+/// back to while it is still marked ready counts as an
+/// [`overrun`](Self::overruns): the consumer fell behind and that data may
+/// be torn.
+pub struct DoubleBuffer<const N: usize> {
+    halves: [Half<N>; 2],
+    ready: AtomicU8,
+    overruns: AtomicU32,
+}
+
+// SAFETY: the buffer memory is written by DMA hardware only; the CPU reads a
+// half through `take` while it is marked ready.
+unsafe impl<const N: usize> Sync for DoubleBuffer<N> {}
+
+impl<const N: usize> Default for DoubleBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> DoubleBuffer<N> {
+    /// Two zeroed halves of `N` bytes.
+    pub const fn new() -> Self {
+        Self {
+            halves: [Half(UnsafeCell::new([0; N])), Half(UnsafeCell::new([0; N]))],
+            ready: AtomicU8::new(0),
+            overruns: AtomicU32::new(0),
+        }
+    }
+
+    /// Borrow `half` if it is ready; it stays ready until the guard drops.
+    pub fn take(&self, half: usize) -> Option<ReadyHalf<'_, N>> {
+        let bit = 1u8 << (half & 1);
+        (self.ready.load(Ordering::Acquire) & bit != 0).then_some(ReadyHalf { buffer: self, bit })
+    }
+
+    /// Halves refilled before the consumer released them.
+    pub fn overruns(&self) -> u32 {
+        self.overruns.load(Ordering::Relaxed)
+    }
+}
+
+impl<const N: usize> SampleSink for DoubleBuffer<N> {
+    fn half_len(&self) -> usize {
+        N
+    }
+
+    fn half_ptr(&self, half: usize) -> *mut u8 {
+        self.halves[half & 1].0.get().cast()
+    }
+
+    fn mark_ready(&self, half: usize) {
+        let bit = 1u8 << (half & 1);
+        if self.ready.fetch_or(bit, Ordering::AcqRel) & bit != 0 {
+            self.overruns.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// A ready half of a [`DoubleBuffer`], released on drop.
+pub struct ReadyHalf<'a, const N: usize> {
+    buffer: &'a DoubleBuffer<N>,
+    bit: u8,
+}
+
+impl<const N: usize> Deref for ReadyHalf<'_, N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        let half = self.bit.trailing_zeros() as usize;
+        // SAFETY: see the `Sync` impl.
+        unsafe { &*self.buffer.halves[half].0.get() }
+    }
+}
+
+impl<const N: usize> Drop for ReadyHalf<'_, N> {
+    fn drop(&mut self) {
+        self.buffer.ready.fetch_and(!self.bit, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calibration_interpolates_and_clamps() {
+        let cal = AdcCalibration::two_point(200, 150, 3800, 2950);
+        assert_eq!(cal.millivolts(200), 150);
+        assert_eq!(cal.millivolts(3800), 2950);
+        assert_eq!(cal.millivolts(2000), 1550);
+        assert_eq!(cal.millivolts(0), 0);
+        assert_eq!(cal.millivolts(4095), 3179);
+    }
+
+    #[test]
+    fn test_double_buffer_hands_halves_over_and_counts_overruns() {
+        let buffer = DoubleBuffer::<8>::new();
+        assert!(buffer.take(0).is_none());
+
+        unsafe { *buffer.half_ptr(1) = 0x5A };
+        buffer.mark_ready(1);
+        {
+            let half = buffer.take(1).unwrap();
+            assert_eq!((half.len(), half[0]), (8, 0x5A));
+            buffer.mark_ready(1);
+        }
+        assert_eq!(buffer.overruns(), 1);
+        assert!(buffer.take(1).is_none());
+    }
+}
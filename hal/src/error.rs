@@ -78,6 +78,12 @@ impl embedded_hal::digital::Error for HalError {
     }
 }
 
+impl embedded_hal::pwm::Error for HalError {
+    fn kind(&self) -> embedded_hal::pwm::ErrorKind {
+        embedded_hal::pwm::ErrorKind::Other
+    }
+}
+
 impl embedded_io::Error for HalError {
     fn kind(&self) -> embedded_io::ErrorKind {
         match self {
@@ -3,7 +3,8 @@
 //! The canonical bus trait is re-exported from [`embedded_hal::i2c`]:
 //! - [`I2c`] — blocking write, read, write_read, and transaction operations
 //!
-//! Platform crates implement `I2c` for their concrete bus types.  The legacy
+//! Platform crates implement `I2c` for their concrete bus types; [`scan`]
+//! probes any such bus for responding devices.  The legacy
 //! [`I2cMaster`] / [`I2cDevice`] traits below are **deprecated** and will be
 //! removed in a future release.
 
@@ -58,6 +59,55 @@ impl Default for I2cConfig {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Bus scanning
+// ---------------------------------------------------------------------------
+
+/// First and last 7-bit addresses outside the reserved ranges.
+const SCAN_FIRST: u8 = 0x08;
+const SCAN_LAST: u8 = 0x77;
+
+/// Set of 7-bit addresses that acknowledged a [`scan`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct I2cScan(u128);
+
+impl I2cScan {
+    /// Whether `address` responded.
+    pub fn contains(&self, address: u8) -> bool {
+        address < 0x80 && self.0 & (1 << address) != 0
+    }
+
+    /// Number of responding devices.
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Whether no device responded.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Responding addresses in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..0x80u8).filter(|&a| self.contains(a))
+    }
+}
+
+/// Probe every non-reserved 7-bit address (0x08..=0x77) with an empty
+/// write and return those that acknowledged.
+///
+/// Any error counts as "no device", so a bus fault shows up as an empty
+/// result rather than aborting the scan.
+pub fn scan<B: I2c>(bus: &mut B) -> I2cScan {
+    let mut found = 0u128;
+    for address in SCAN_FIRST..=SCAN_LAST {
+        if bus.write(address, &[]).is_ok() {
+            found |= 1 << address;
+        }
+    }
+    I2cScan(found)
+}
+
 // ---------------------------------------------------------------------------
 // Legacy traits — DEPRECATED; implement embedded_hal::i2c::I2c instead
 // ---------------------------------------------------------------------------
@@ -104,3 +154,51 @@ pub trait I2cDevice: Send + Sync {
     /// Write then read from this device
     fn write_read(&mut self, write_data: &[u8], read_buffer: &mut [u8]) -> HalResult<()>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::HalError;
+
+    /// Acknowledges a fixed set of addresses and records every probe.
+    struct Bus {
+        present: &'static [u8],
+        probed: u128,
+    }
+
+    impl I2cErrorType for Bus {
+        type Error = HalError;
+    }
+
+    impl I2c for Bus {
+        fn transaction(&mut self, address: u8, _: &mut [Operation<'_>]) -> HalResult<()> {
+            self.probed |= 1 << address;
+            if self.present.contains(&address) {
+                Ok(())
+            } else {
+                Err(HalError::Timeout)
+            }
+        }
+    }
+
+    #[test]
+    fn test_scan_reports_acknowledging_addresses() {
+        let mut bus = Bus { present: &[0x3C, 0x76, 0x78], probed: 0 };
+        let found = scan(&mut bus);
+
+        assert_eq!(found.len(), 2);
+        assert!(found.contains(0x3C) && found.contains(0x76));
+        assert!(!found.contains(0x78) && !found.contains(0xFF));
+        assert!(found.iter().eq([0x3C, 0x76]));
+        assert_eq!(bus.probed.count_ones(), 0x70);
+        assert_eq!(bus.probed.trailing_zeros(), 0x08);
+    }
+
+    #[test]
+    fn test_scan_of_empty_bus() {
+        let mut bus = Bus { present: &[], probed: 0 };
+        let found = scan(&mut bus);
+        assert!(found.is_empty());
+        assert_eq!(found.iter().count(), 0);
+    }
+}
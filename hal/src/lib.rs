@@ -15,6 +15,8 @@ pub mod uart;
 pub mod spi;
 pub mod i2c;
 pub mod timer;
+pub mod pwm;
+pub mod watchdog;
 pub mod adc;
 pub mod dac;
 pub mod interrupt;
@@ -0,0 +1,110 @@
+//! PWM (Pulse-Width Modulation) abstraction
+//!
+//! The portable duty-cycle trait is re-exported from [`embedded_hal::pwm`]:
+//! - [`SetDutyCycle`] — set the duty as a fraction of [`max_duty_cycle`]
+//!
+//! [`PwmChannel`] adds what `embedded-hal` leaves out: configuring the
+//! frequency and resolution, and hardware fades. Platform crates implement
+//! both on their channel types (e.g. `Esp32S3LedcChannel`).
+//!
+//! [`max_duty_cycle`]: SetDutyCycle::max_duty_cycle
+
+pub use embedded_hal::pwm::SetDutyCycle;
+
+use crate::error::{HalError, HalResult};
+
+/// PWM configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PwmConfig {
+    /// Output frequency in Hz
+    pub frequency_hz: u32,
+    /// Duty resolution in bits (duty ranges over `0..=2^bits`)
+    pub resolution_bits: u8,
+}
+
+impl Default for PwmConfig {
+    fn default() -> Self {
+        Self {
+            frequency_hz: 1_000,
+            resolution_bits: 10,
+        }
+    }
+}
+
+/// PWM channel trait
+pub trait PwmChannel: Send + Sync {
+    /// Apply frequency and resolution, keeping the current duty fraction
+    fn configure(&mut self, config: &PwmConfig) -> HalResult<()>;
+
+    /// Set duty cycle (0.0 to 1.0)
+    fn set_duty(&mut self, duty: f32) -> HalResult<()>;
+
+    /// Set frequency in Hz
+    fn set_frequency(&mut self, freq_hz: u32) -> HalResult<()>;
+
+    /// Ramp the duty cycle to `duty` (0.0 to 1.0) over `duration_ms`
+    /// without CPU involvement. Returns once the fade is started.
+    fn fade_to(&mut self, duty: f32, duration_ms: u32) -> HalResult<()> {
+        let _ = (duty, duration_ms);
+        Err(HalError::NotSupported)
+    }
+
+    /// Whether a fade started by [`fade_to`](Self::fade_to) is still running
+    fn is_fading(&self) -> bool {
+        false
+    }
+
+    /// Enable PWM output
+    fn enable(&mut self) -> HalResult<()>;
+
+    /// Disable PWM output
+    fn disable(&mut self) -> HalResult<()>;
+}
+
+/// Convert a duty fraction into counter ticks at `resolution_bits`.
+///
+/// `1.0` maps to `2^bits`, i.e. an output that never goes low.
+pub fn duty_to_ticks(duty: f32, resolution_bits: u8) -> HalResult<u32> {
+    if !(0.0..=1.0).contains(&duty) || resolution_bits == 0 || resolution_bits > 20 {
+        return Err(HalError::InvalidParameter);
+    }
+    let full = 1u32 << resolution_bits;
+    Ok((duty * full as f32 + 0.5) as u32)
+}
+
+/// Highest resolution a counter clocked at `clock_hz` can run at
+/// `freq_hz`, capped at `max_bits`. `None` if even one bit is out of reach.
+pub fn max_resolution_bits(clock_hz: u32, freq_hz: u32, max_bits: u8) -> Option<u8> {
+    if freq_hz == 0 {
+        return None;
+    }
+    let ticks = clock_hz / freq_hz;
+    if ticks < 2 {
+        return None;
+    }
+    Some((ticks.ilog2() as u8).min(max_bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duty_to_ticks() {
+        assert_eq!(duty_to_ticks(0.0, 10), Ok(0));
+        assert_eq!(duty_to_ticks(0.5, 10), Ok(512));
+        assert_eq!(duty_to_ticks(1.0, 8), Ok(256));
+        assert_eq!(duty_to_ticks(1.5, 8), Err(HalError::InvalidParameter));
+        assert_eq!(duty_to_ticks(-0.1, 8), Err(HalError::InvalidParameter));
+        assert_eq!(duty_to_ticks(0.5, 0), Err(HalError::InvalidParameter));
+    }
+
+    #[test]
+    fn test_max_resolution_bits() {
+        assert_eq!(max_resolution_bits(80_000_000, 5_000, 14), Some(13));
+        assert_eq!(max_resolution_bits(80_000_000, 50, 14), Some(14));
+        assert_eq!(max_resolution_bits(80_000_000, 40_000_000, 14), Some(1));
+        assert_eq!(max_resolution_bits(80_000_000, 80_000_000, 14), None);
+        assert_eq!(max_resolution_bits(80_000_000, 0, 14), None);
+    }
+}
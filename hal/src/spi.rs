@@ -5,19 +5,97 @@
 //! - [`SpiDevice`]    — bus + CS management per logical device
 //! - [`ErrorType`]    — associate an `Error` type with an impl
 //!
-//! Platform crates implement `SpiBus<u8>` (and optionally `SpiDevice`) for
-//! their concrete peripheral types.
+//! Platform crates implement `SpiBus<u8>` for their concrete peripheral
+//! types; [`CsDevice`] turns such a bus plus a GPIO chip-select pin into an
+//! `SpiDevice`.
 
 // ---------------------------------------------------------------------------
 // Re-exports from embedded-hal
 // ---------------------------------------------------------------------------
 pub use embedded_hal::spi::{
-    ErrorType, Mode, Phase, Polarity,
+    ErrorType, Mode, Operation, Phase, Polarity,
     SpiBus,
     SpiDevice as EmbeddedSpiDevice,
     MODE_0, MODE_1, MODE_2, MODE_3,
 };
 
+use embedded_hal::digital::OutputPin;
+
+use crate::error::{HalError, HalResult};
+
+// ---------------------------------------------------------------------------
+// Chip-select management
+// ---------------------------------------------------------------------------
+
+/// An exclusively owned bus plus a software-driven chip-select pin.
+///
+/// CS is asserted (driven low) for the whole of each
+/// [`transaction`](EmbeddedSpiDevice::transaction) and released afterwards,
+/// even when one of the operations fails.
+pub struct CsDevice<BUS, CS> {
+    bus: BUS,
+    cs: CS,
+    delay_ns: Option<fn(u32)>,
+}
+
+impl<BUS, CS: OutputPin> CsDevice<BUS, CS> {
+    /// Wrap `bus` and `cs`, driving CS high (deselected) straight away.
+    pub fn new(bus: BUS, mut cs: CS) -> HalResult<Self> {
+        cs.set_high().map_err(|_| HalError::HardwareError)?;
+        Ok(Self { bus, cs, delay_ns: None })
+    }
+
+    /// Busy-wait used for [`Operation::DelayNs`]. Without one, transactions
+    /// containing a delay fail with [`HalError::NotSupported`].
+    pub fn with_delay(mut self, delay_ns: fn(u32)) -> Self {
+        self.delay_ns = Some(delay_ns);
+        self
+    }
+
+    /// The underlying bus, e.g. to reconfigure its clock between devices.
+    pub fn bus_mut(&mut self) -> &mut BUS {
+        &mut self.bus
+    }
+
+    /// Give back the bus and the chip-select pin.
+    pub fn release(self) -> (BUS, CS) {
+        (self.bus, self.cs)
+    }
+}
+
+impl<BUS, CS> ErrorType for CsDevice<BUS, CS> {
+    type Error = HalError;
+}
+
+impl<BUS, CS> EmbeddedSpiDevice<u8> for CsDevice<BUS, CS>
+where
+    BUS: SpiBus<u8, Error = HalError>,
+    CS: OutputPin,
+{
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> HalResult<()> {
+        self.cs.set_low().map_err(|_| HalError::HardwareError)?;
+
+        let result = operations
+            .iter_mut()
+            .try_for_each(|op| match op {
+                Operation::Read(buf) => self.bus.read(buf),
+                Operation::Write(buf) => self.bus.write(buf),
+                Operation::Transfer(read, write) => self.bus.transfer(read, write),
+                Operation::TransferInPlace(buf) => self.bus.transfer_in_place(buf),
+                Operation::DelayNs(ns) => {
+                    self.bus.flush()?;
+                    let delay = self.delay_ns.ok_or(HalError::NotSupported)?;
+                    delay(*ns);
+                    Ok(())
+                }
+            })
+            .and_then(|()| self.bus.flush());
+
+        let released = self.cs.set_high().map_err(|_| HalError::HardwareError);
+        result.and(released)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Configuration helpers (not part of embedded-hal; kept for platform `new()`)
 // ---------------------------------------------------------------------------
@@ -73,3 +151,100 @@ impl Default for SpiConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::RefCell;
+
+    /// Records bus traffic and CS edges into a shared log.
+    struct Log(RefCell<[u8; 16]>, RefCell<usize>);
+
+    impl Log {
+        fn push(&self, b: u8) {
+            let mut n = self.1.borrow_mut();
+            self.0.borrow_mut()[*n] = b;
+            *n += 1;
+        }
+
+        fn entries(&self) -> [u8; 16] {
+            *self.0.borrow()
+        }
+    }
+
+    struct Bus<'a>(&'a Log);
+    struct Pin<'a>(&'a Log);
+
+    impl ErrorType for Bus<'_> {
+        type Error = HalError;
+    }
+
+    impl SpiBus<u8> for Bus<'_> {
+        fn read(&mut self, words: &mut [u8]) -> HalResult<()> {
+            words.fill(0xAA);
+            Ok(())
+        }
+
+        fn write(&mut self, words: &[u8]) -> HalResult<()> {
+            words.iter().for_each(|&w| self.0.push(w));
+            Ok(())
+        }
+
+        fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> HalResult<()> {
+            self.write(write)?;
+            self.read(read)
+        }
+
+        fn transfer_in_place(&mut self, words: &mut [u8]) -> HalResult<()> {
+            let copy = [words[0]];
+            self.transfer(words, &copy)
+        }
+
+        fn flush(&mut self) -> HalResult<()> {
+            Ok(())
+        }
+    }
+
+    impl embedded_hal::digital::ErrorType for Pin<'_> {
+        type Error = HalError;
+    }
+
+    impl OutputPin for Pin<'_> {
+        fn set_low(&mut self) -> HalResult<()> {
+            self.0.push(0xC0);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> HalResult<()> {
+            self.0.push(0xC1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_cs_device_frames_each_transaction() {
+        let log = Log(RefCell::new([0; 16]), RefCell::new(0));
+        let mut dev = CsDevice::new(Bus(&log), Pin(&log)).unwrap();
+
+        let mut rx = [0u8; 2];
+        dev.transaction(&mut [Operation::Write(&[1, 2]), Operation::Read(&mut rx)])
+            .unwrap();
+        assert_eq!(rx, [0xAA, 0xAA]);
+        assert_eq!(log.entries()[..5], [0xC1, 0xC0, 1, 2, 0xC1]);
+    }
+
+    #[test]
+    fn test_cs_device_releases_cs_when_delay_is_unsupported() {
+        let log = Log(RefCell::new([0; 16]), RefCell::new(0));
+        let mut dev = CsDevice::new(Bus(&log), Pin(&log)).unwrap();
+
+        let result = dev.transaction(&mut [Operation::DelayNs(100), Operation::Write(&[9])]);
+        assert_eq!(result, Err(HalError::NotSupported));
+        assert_eq!(log.entries()[..3], [0xC1, 0xC0, 0xC1]);
+
+        let mut dev = dev.with_delay(|_| {});
+        dev.transaction(&mut [Operation::DelayNs(100), Operation::Write(&[9])])
+            .unwrap();
+        assert_eq!(log.entries()[3..6], [0xC0, 9, 0xC1]);
+    }
+}
@@ -1,4 +1,4 @@
-//! Timer and PWM abstraction
+//! Timer abstraction
 
 use crate::error::HalResult;
 
@@ -30,17 +30,5 @@ pub trait Timer: Send + Sync {
     fn clear_interrupt(&mut self) -> HalResult<()>;
 }
 
-/// PWM channel trait
-pub trait PwmChannel: Send + Sync {
-    /// Set duty cycle (0.0 to 1.0)
-    fn set_duty(&mut self, duty: f32) -> HalResult<()>;
-
-    /// Set frequency in Hz
-    fn set_frequency(&mut self, freq_hz: u32) -> HalResult<()>;
-
-    /// Enable PWM output
-    fn enable(&mut self) -> HalResult<()>;
-
-    /// Disable PWM output
-    fn disable(&mut self) -> HalResult<()>;
-}
+/// PWM channel trait, now in [`crate::pwm`]
+pub use crate::pwm::PwmChannel;
@@ -9,6 +9,15 @@
 //! platform's own `configure()` inherent method. RX-available queries and
 //! interrupt-enable APIs likewise have no `embedded-io` equivalent and are
 //! exposed as platform inherent methods.
+//!
+//! Interrupt-driven reception goes through an [`RxRing`]: the platform's RX
+//! interrupt handler moves bytes from the hardware FIFO into the ring, and
+//! the application reads them back, blocking or with a timeout.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use crate::error::{HalError, HalResult};
 
 // Re-export embedded-io traits for convenience
 pub use embedded_io::{ErrorType as IoErrorType, Read as IoRead, Write as IoWrite};
@@ -65,3 +74,169 @@ impl Default for UartConfig {
         }
     }
 }
+
+/// Receive ring of `N` bytes filled from a UART RX interrupt.
+///
+/// Single producer (the interrupt handler, through [`push`](Self::push)) and
+/// single consumer (the task reading it). Bytes that arrive while the ring
+/// is full are dropped and counted in [`overruns`](Self::overruns). `N` must
+/// be a power of two, so slots stay continuous when the counters wrap.
+pub struct RxRing<const N: usize> {
+    buf: [UnsafeCell<u8>; N],
+    /// Bytes taken so far; only the consumer advances it.
+    head: AtomicUsize,
+    /// Bytes stored so far; only the producer advances it.
+    tail: AtomicUsize,
+    overruns: AtomicU32,
+}
+
+// SAFETY: a slot is written only by the producer while it lies between
+// `tail` and `head + N`, and read only by the consumer once `tail` has
+// moved past it.
+unsafe impl<const N: usize> Sync for RxRing<N> {}
+
+impl<const N: usize> Default for RxRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> RxRing<N> {
+    /// An empty ring, usable as a `static`.
+    pub const fn new() -> Self {
+        const { assert!(N.is_power_of_two(), "RxRing capacity must be a power of two") };
+        Self {
+            buf: [const { UnsafeCell::new(0) }; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            overruns: AtomicU32::new(0),
+        }
+    }
+
+    /// Stores `byte`; called from the RX interrupt. Returns `false`, and
+    /// counts an overrun, if the ring is full.
+    pub fn push(&self, byte: u8) -> bool {
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail.wrapping_sub(self.head.load(Ordering::Acquire)) >= N {
+            self.overruns.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        // SAFETY: the slot is free (see the `Sync` impl).
+        unsafe { *self.buf[tail % N].get() = byte };
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        true
+    }
+
+    /// Takes the oldest byte.
+    pub fn pop(&self) -> Option<u8> {
+        let head = self.head.load(Ordering::Relaxed);
+        if self.tail.load(Ordering::Acquire) == head {
+            return None;
+        }
+        // SAFETY: the producer published the slot and will not touch it
+        // again until `head` moves past it.
+        let byte = unsafe { *self.buf[head % N].get() };
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(byte)
+    }
+
+    /// Moves as many waiting bytes as fit into `buf`, without blocking.
+    pub fn read(&self, buf: &mut [u8]) -> usize {
+        let mut count = 0;
+        while count < buf.len() {
+            match self.pop() {
+                Some(byte) => buf[count] = byte,
+                None => break,
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// Waits for at least one byte, then reads like [`read`](Self::read).
+    pub fn read_blocking(&self, buf: &mut [u8]) -> usize {
+        if buf.is_empty() {
+            return 0;
+        }
+        while self.is_empty() {
+            core::hint::spin_loop();
+        }
+        self.read(buf)
+    }
+
+    /// Like [`read_blocking`](Self::read_blocking), but gives up with
+    /// [`HalError::Timeout`] once `now` has advanced `timeout` units past
+    /// the call. `now` is any free-running counter, such as a
+    /// [`Timer::counter`](crate::timer::Timer::counter).
+    pub fn read_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: u64,
+        mut now: impl FnMut() -> u64,
+    ) -> HalResult<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let start = now();
+        while self.is_empty() {
+            if now().wrapping_sub(start) >= timeout {
+                return Err(HalError::Timeout);
+            }
+            core::hint::spin_loop();
+        }
+        Ok(self.read(buf))
+    }
+
+    /// Bytes waiting.
+    pub fn len(&self) -> usize {
+        self.tail.load(Ordering::Acquire).wrapping_sub(self.head.load(Ordering::Acquire))
+    }
+
+    /// Returns `true` if no byte is waiting.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes dropped because the ring was full. The count wraps.
+    pub fn overruns(&self) -> u32 {
+        self.overruns.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rx_ring_is_fifo_and_counts_overruns() {
+        let ring = RxRing::<4>::new();
+        assert!(ring.push(1) && ring.push(2) && ring.push(3) && ring.push(4));
+        assert!(!ring.push(5));
+        assert_eq!((ring.len(), ring.overruns()), (4, 1));
+
+        let mut buf = [0u8; 3];
+        assert_eq!(ring.read(&mut buf), 3);
+        assert_eq!(buf, [1, 2, 3]);
+        assert!(ring.push(6));
+        assert_eq!(ring.read(&mut buf), 2);
+        assert_eq!(buf[..2], [4, 6]);
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn test_rx_ring_read_timeout() {
+        let ring = RxRing::<4>::new();
+        let mut clock = 0u64;
+        let mut buf = [0u8; 4];
+        let result = ring.read_timeout(&mut buf, 10, || {
+            clock += 1;
+            clock
+        });
+        assert_eq!(result, Err(HalError::Timeout));
+        assert_eq!(clock, 11);
+
+        ring.push(7);
+        assert_eq!(ring.read_timeout(&mut buf, 10, || 0), Ok(1));
+        assert_eq!(buf[0], 7);
+    }
+}
@@ -0,0 +1,47 @@
+//! Watchdog timer abstraction
+//!
+//! `embedded-hal` 1.0 has no watchdog traits, so [`Watchdog`] is defined
+//! here. Platform crates also report why the chip last reset as a
+//! [`ResetCause`], so an application can tell a watchdog reset from a power
+//! cycle at boot.
+
+use crate::error::HalResult;
+
+/// Why the chip last came out of reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ResetCause {
+    /// Power applied
+    PowerOn = 0,
+    /// External reset pin
+    External = 1,
+    /// Software-requested reset
+    Software = 2,
+    /// A watchdog expired
+    Watchdog = 3,
+    /// Supply voltage dropped below the brown-out threshold
+    Brownout = 4,
+    /// Wake-up from deep sleep
+    DeepSleep = 5,
+    /// Any other, platform-specific cause
+    Other = 0xFF,
+}
+
+impl ResetCause {
+    /// Whether a watchdog caused the reset.
+    pub fn is_watchdog(self) -> bool {
+        self == ResetCause::Watchdog
+    }
+}
+
+/// Watchdog timer trait
+pub trait Watchdog: Send + Sync {
+    /// Arm the watchdog to reset the chip unless fed within `timeout_ms`
+    fn start(&mut self, timeout_ms: u32) -> HalResult<()>;
+
+    /// Restart the timeout
+    fn feed(&mut self);
+
+    /// Disarm the watchdog
+    fn stop(&mut self) -> HalResult<()>;
+}
@@ -15,6 +15,9 @@
 //! - [`CortexMQxkRuntime`] — integrates [`qxk`]'s dual-mode scheduler with the
 //!   Cortex-M exception model.  The `PendSV` exception is used as the
 //!   context-switch mechanism; `SVC #0` is used as the scheduler-lock primitive.
+//! - [`CortexMQkRuntime`] — the QK kernel with PendSV-based activation
+//!   ([`qk_port`]), SysTick ticks and BASEPRI scheduler locking.
+//! - [`startup`] — `.data`/`.bss` initialisation for a bare `Reset_Handler`.
 //! - Stubs for `PendSV_Handler` and `SVC_Handler` that must be provided in the
 //!   `#[cfg(feature = "hw")]` configuration.  Without `hw` they are unreachable
 //!   no-op stubs so the crate compiles on the host for testing.
@@ -54,6 +57,8 @@ extern crate alloc;
 pub mod context;
 pub mod mpu;
 pub mod nvic_cfg;
+pub mod qk_port;
+pub mod startup;
 
 pub use context::{ContextFrame, ThreadStack};
 pub use mpu::{Access, RegionConfig};
@@ -64,7 +69,7 @@ use qf::time::TimerWheel;
 use qk::QkKernel;
 use qk::QkTimerWheel;
 use qxk::QxkKernel;
-#[cfg(feature = "hw")]
+#[cfg(all(feature = "hw", not(feature = "qk")))]
 use qxk::ScheduleMode;
 
 /// Cortex-M QF runtime.
@@ -126,6 +131,10 @@ impl qf::port::Runtime for CortexMQfRuntime {
 }
 
 /// Cortex-M QK runtime.
+///
+/// Interrupts post and return; PendSV runs whatever they made ready (see
+/// [`qk_port`]). SysTick drives the timer wheel through
+/// [`on_systick`](Self::on_systick).
 pub struct CortexMQkRuntime {
     kernel: alloc::sync::Arc<QkKernel>,
     timers: QkTimerWheel,
@@ -139,9 +148,47 @@ impl CortexMQkRuntime {
         Self { kernel, timers }
     }
 
-    /// Starts the kernel and all registered active objects.
+    /// Starts the kernel and all registered active objects, and hands the
+    /// kernel to the PendSV activator. The runtime must live as long as
+    /// interrupts may post, so keep it in a `static`.
     pub fn start(&self) {
         self.kernel.start();
+        qk_port::install(&self.kernel);
+    }
+
+    /// Starts SysTick at `tick_hz` from a `core_hz` processor clock. Its
+    /// handler calls [`on_systick`](Self::on_systick).
+    #[cfg(feature = "hw")]
+    pub fn start_systick(&self, core_hz: u32, tick_hz: u32) -> hal::error::HalResult<()> {
+        use hal::timer::{Timer, TimerMode};
+        let period_us = 1_000_000 / u64::from(tick_hz.max(1));
+        hal_cmsis::systick::SysTickTimer::new(core_hz / 1_000_000).start(period_us, TimerMode::Periodic)
+    }
+
+    /// SysTick handler body: advances the timer wheel and pends PendSV if an
+    /// expired time event readied a task that preempts the interrupted code.
+    pub fn on_systick(&self) -> Result<(), qk::QkTimeEventError> {
+        qf::qk_isr_entry!();
+        let result = self.timers.tick_from_isr();
+        qf::qk_isr_exit!();
+        self.isr_exit();
+        result
+    }
+
+    /// Call at the end of any handler that posts, after `qk_isr_exit!()`.
+    pub fn isr_exit(&self) {
+        qk_port::request_activation(&self.kernel);
+    }
+
+    /// Runs the activator if PendSV is pending, standing in for the
+    /// exception on the host.
+    #[cfg(not(feature = "hw"))]
+    pub fn run_pendsv(&self) {
+        if qk_port::take_pend_sv() {
+            let prev = qk_lock();
+            qk_port::qk_activate_();
+            qk_unlock(prev);
+        }
     }
 
     /// Registers a time event.
@@ -227,7 +274,7 @@ impl CortexMQxkRuntime {
         #[cfg(feature = "hw")]
         unsafe {
             KERNEL_PTR = &*self.kernel as *const spin::Mutex<QxkKernel>;
-            Self::set_pendsv_priority_lowest();
+            nvic_cfg::set_pendsv_priority_lowest();
         }
     }
 
@@ -265,17 +312,6 @@ impl CortexMQxkRuntime {
             core::ptr::write_volatile(SCB_ICSR, 1 << 28);
         }
     }
-
-    #[cfg(feature = "hw")]
-    fn set_pendsv_priority_lowest() {
-        // Set PendSV (exception 14) to the lowest priority (0xFF on M3/M4).
-        // SHPR3 bits [23:16] hold PendSV priority.
-        unsafe {
-            const SCB_SHPR3: *mut u32 = 0xE000_ED20 as *mut u32;
-            let prev = core::ptr::read_volatile(SCB_SHPR3);
-            core::ptr::write_volatile(SCB_SHPR3, (prev & !0x00FF_0000) | 0x00FF_0000);
-        }
-    }
 }
 
 impl qf::port::ContextSwitch for CortexMQxkRuntime {
@@ -294,7 +330,7 @@ impl qf::port::ContextSwitch for CortexMQxkRuntime {
 /// Must be called only from the `PendSV` exception path after [`CortexMQxkRuntime::start`]
 /// has installed `KERNEL_PTR`; it dereferences that pointer and writes the
 /// `NEXT_THREAD_SP` static.
-#[cfg(feature = "hw")]
+#[cfg(all(feature = "hw", not(feature = "qk")))]
 #[no_mangle]
 pub unsafe extern "C" fn qxk_schedule() {
     if KERNEL_PTR.is_null() {
@@ -312,7 +348,8 @@ pub unsafe extern "C" fn qxk_schedule() {
     }
 }
 
-/// PendSV exception handler (context switch).
+/// PendSV exception handler (context switch). With the `qk` feature PendSV
+/// runs the QK activator instead; see [`qk_port`].
 ///
 /// On a real Cortex-M target the linker script must route `PendSV_Handler` here.
 /// The assembly body saves callee-saved registers (`r4–r11`), switches `SP` to
@@ -323,7 +360,7 @@ pub unsafe extern "C" fn qxk_schedule() {
 ///
 /// This is an interrupt handler; it must be called only by the processor's
 /// exception entry mechanism.
-#[cfg(feature = "hw")]
+#[cfg(all(feature = "hw", not(feature = "qk")))]
 #[unsafe(naked)]
 #[no_mangle]
 pub unsafe extern "C" fn PendSV_Handler() {
@@ -404,6 +441,45 @@ mod tests {
         runtime.run_until_idle();
     }
 
+    #[test]
+    fn systick_readies_work_that_runs_in_pendsv() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        use qf::active::{new_active_object, ActiveContext, ActiveObjectId, SignalHandler};
+        use qf::event::Signal;
+        use qf::time::{TimeEvent, TimeEventConfig};
+
+        static TIMEOUTS: AtomicUsize = AtomicUsize::new(0);
+        struct Blinky;
+        impl SignalHandler for Blinky {
+            fn handle_signal(&mut self, signal: Signal, _ctx: &mut ActiveContext) {
+                if signal == Signal(42) {
+                    TIMEOUTS.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+
+        let id = ActiveObjectId::new(1);
+        let kernel = QkKernel::builder()
+            .register(new_active_object(id, 3, Blinky))
+            .unwrap()
+            .build()
+            .unwrap();
+        let mut runtime = CortexMQkRuntime::new(kernel);
+        runtime.start();
+        let event = alloc::sync::Arc::new(TimeEvent::new(id, TimeEventConfig::new(Signal(42))));
+        runtime.register_time_event(alloc::sync::Arc::clone(&event));
+        event.arm(1, None);
+
+        runtime.on_systick().unwrap();
+        assert_eq!(TIMEOUTS.load(Ordering::SeqCst), 0, "nothing runs in the handler");
+        runtime.run_pendsv();
+        assert_eq!(TIMEOUTS.load(Ordering::SeqCst), 1);
+
+        runtime.on_systick().unwrap();
+        runtime.run_pendsv();
+        assert_eq!(TIMEOUTS.load(Ordering::SeqCst), 1, "one-shot event");
+    }
+
     #[test]
     fn context_frame_size_is_correct() {
         assert_eq!(core::mem::size_of::<ContextFrame>(), 8 * 4);
@@ -32,6 +32,17 @@ pub fn qk_unlock(prev: u8) {
     asm::isb();
 }
 
+/// Sets PendSV (exception 14) to the lowest priority, so it only fires once
+/// no other exception is active. `SCB.SHPR3[23:16]` holds its priority.
+#[cfg(feature = "hw")]
+pub(crate) fn set_pendsv_priority_lowest() {
+    unsafe {
+        const SCB_SHPR3: *mut u32 = 0xE000_ED20 as *mut u32;
+        let prev = core::ptr::read_volatile(SCB_SHPR3);
+        core::ptr::write_volatile(SCB_SHPR3, (prev & !0x00FF_0000) | 0x00FF_0000);
+    }
+}
+
 /// Stub implementation for non-hardware (host) builds.
 #[cfg(not(feature = "hw"))]
 #[inline]
@@ -43,3 +54,34 @@ pub fn qk_lock() -> u8 {
 #[cfg(not(feature = "hw"))]
 #[inline]
 pub fn qk_unlock(_prev: u8) {}
+
+/// The scheduler lock as the kernels' idle [`InterruptLock`]: the idle
+/// callback runs with BASEPRI at [`QK_BASEPRI`].
+///
+/// [`InterruptLock`]: qf::idle::InterruptLock
+pub const IDLE_LOCK: qf::idle::InterruptLock = qf::idle::InterruptLock {
+    lock: || u32::from(qk_lock()),
+    unlock: |prev| qk_unlock(prev as u8),
+};
+
+/// Sleeps until an interrupt, from an idle callback entered under
+/// [`IDLE_LOCK`]: `cx.sleep(nvic_cfg::idle_sleep)`.
+///
+/// An interrupt masked by BASEPRI does not wake `WFI`, so, like
+/// `QV_CPU_SLEEP()`, this moves the mask to PRIMASK, lifts BASEPRI and then
+/// sleeps. A pending interrupt wakes the core and is taken when PRIMASK is
+/// cleared on the way out.
+#[cfg(feature = "hw")]
+#[inline]
+pub fn idle_sleep() {
+    hal_cmsis::primask::disable();
+    unsafe { basepri::write(0) }
+    asm::isb();
+    asm::wfi();
+    unsafe { hal_cmsis::primask::enable() }
+}
+
+/// Stub implementation for non-hardware (host) builds.
+#[cfg(not(feature = "hw"))]
+#[inline]
+pub fn idle_sleep() {}
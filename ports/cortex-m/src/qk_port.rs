@@ -0,0 +1,214 @@
+//! QK activation through PendSV.
+//!
+//! An interrupt that makes a higher-priority active object ready does not
+//! run it. On its way out it pends PendSV, the lowest-priority exception, so
+//! the object runs once every handler has returned ([`request_activation`]).
+//! The PendSV handler then builds an exception frame that "returns" to
+//! thread mode in [`qk_activate_`]. The activator runs every ready object
+//! allowed to preempt the interrupted one, on the interrupted object's stack,
+//! so handlers can preempt the activator in turn.
+//!
+//! When the activator returns, `qk_thread_ret` raises the NMI. The NMI
+//! handler drops its own frame and returns through the frame PendSV was
+//! entered with, resuming the interrupted code. This is the scheme of QP/C's
+//! `qk_port.c`; the NMI is taken even with BASEPRI raised, so it must not be
+//! used for anything else. On `eabihf` targets the FPU registers s16–s31 are
+//! saved around the activation when the interrupted code had a live FP
+//! context.
+//!
+//! The handlers are linked with the `hw` and `qk` features. Without `hw`,
+//! pending PendSV sets a flag and
+//! [`CortexMQkRuntime::run_pendsv`](crate::CortexMQkRuntime::run_pendsv)
+//! stands in for the exception.
+
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use qk::QkKernel;
+
+use crate::nvic_cfg::{qk_lock, qk_unlock};
+
+/// Kernel run by the activator, set by [`install`].
+static KERNEL: AtomicPtr<QkKernel> = AtomicPtr::new(core::ptr::null_mut());
+
+#[cfg(not(feature = "hw"))]
+static PENDSV_PENDING: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
+
+/// Makes `kernel` the one PendSV activates and drops PendSV to the lowest
+/// priority. The kernel must stay alive while PendSV can fire.
+pub(crate) fn install(kernel: &QkKernel) {
+    KERNEL.store(kernel as *const QkKernel as *mut QkKernel, Ordering::Release);
+    #[cfg(feature = "hw")]
+    crate::nvic_cfg::set_pendsv_priority_lowest();
+}
+
+/// End-of-interrupt hook: pends PendSV when the outermost handler leaves
+/// work that preempts the interrupted code. Call it after `qk_isr_exit!()`.
+pub fn request_activation(kernel: &QkKernel) {
+    if !qf::isr::in_isr() && kernel.has_pending_work() {
+        pend_sv();
+    }
+}
+
+/// Pends the PendSV exception (`SCB.ICSR[28]`).
+#[inline]
+pub fn pend_sv() {
+    #[cfg(feature = "hw")]
+    unsafe {
+        // SAFETY: write-only bit in SCB_ICSR, no other side effects.
+        const SCB_ICSR: *mut u32 = 0xE000_ED04 as *mut u32;
+        core::ptr::write_volatile(SCB_ICSR, 1 << 28);
+    }
+    #[cfg(not(feature = "hw"))]
+    PENDSV_PENDING.store(true, Ordering::Release);
+}
+
+/// Clears the emulated PendSV request; returns whether one was pending.
+#[cfg(not(feature = "hw"))]
+pub(crate) fn take_pend_sv() -> bool {
+    PENDSV_PENDING.swap(false, Ordering::AcqRel)
+}
+
+/// The activator. Entered with the scheduler locked and returns with it
+/// locked; the objects themselves run unlocked.
+pub extern "C" fn qk_activate_() {
+    let kernel = KERNEL.load(Ordering::Acquire);
+    if kernel.is_null() {
+        return;
+    }
+    qk_unlock(0);
+    // SAFETY: `install` stored a kernel that outlives PendSV activity.
+    unsafe { &*kernel }.activate_pending();
+    let _ = qk_lock();
+}
+
+/// PendSV exception handler: fabricates a thread-mode frame entering
+/// [`qk_activate_`] with `qk_thread_ret` as its return address.
+///
+/// # Safety
+///
+/// Exception handler; only the processor may call it.
+#[cfg(all(feature = "hw", feature = "qk", not(target_abi = "eabihf")))]
+#[unsafe(naked)]
+#[no_mangle]
+pub unsafe extern "C" fn PendSV_Handler() {
+    core::arch::naked_asm!(
+        "ldr r3, =0xE000ED04",
+        "mov r1, #1",
+        "lsl r1, r1, #27",
+        "mov r0, #{basepri}",
+        "cpsid i",
+        "msr BASEPRI, r0",
+        "cpsie i",
+        "str r1, [r3]",
+        "lsr r3, r1, #3",
+        "ldr r2, ={activate}",
+        "sub r2, r2, #1",
+        "ldr r1, ={thread_ret}",
+        "sub sp, sp, #(8*4)",
+        "add r0, sp, #(5*4)",
+        "stm r0!, {{r1-r3}}",
+        "mov r0, #6",
+        "mvn r0, r0",
+        "bx r0",
+        basepri = const crate::nvic_cfg::QK_BASEPRI,
+        activate = sym qk_activate_,
+        thread_ret = sym qk_thread_ret,
+    );
+}
+
+/// PendSV exception handler for FPU targets; see the non-FPU variant.
+///
+/// # Safety
+///
+/// Exception handler; only the processor may call it.
+#[cfg(all(feature = "hw", feature = "qk", target_abi = "eabihf"))]
+#[unsafe(naked)]
+#[no_mangle]
+pub unsafe extern "C" fn PendSV_Handler() {
+    core::arch::naked_asm!(
+        "ldr r3, =0xE000ED04",
+        "mov r1, #1",
+        "lsl r1, r1, #27",
+        "mov r0, #{basepri}",
+        "cpsid i",
+        "msr BASEPRI, r0",
+        "cpsie i",
+        "str r1, [r3]",
+        "tst lr, #(1 << 4)",
+        "it eq",
+        "vstmdbeq sp!, {{s16-s31}}",
+        "push {{r0, lr}}",
+        "lsr r3, r1, #3",
+        "ldr r2, ={activate}",
+        "sub r2, r2, #1",
+        "ldr r1, ={thread_ret}",
+        "sub sp, sp, #(8*4)",
+        "add r0, sp, #(5*4)",
+        "stm r0!, {{r1-r3}}",
+        "mov r0, #6",
+        "mvn r0, r0",
+        "bx r0",
+        basepri = const crate::nvic_cfg::QK_BASEPRI,
+        activate = sym qk_activate_,
+        thread_ret = sym qk_thread_ret,
+    );
+}
+
+/// Return address of the fabricated frame: pends the NMI, which unwinds to
+/// the interrupted code. An FP context opened by the activator is dropped
+/// first so the NMI stacks a basic frame.
+#[cfg(all(feature = "hw", feature = "qk"))]
+#[unsafe(naked)]
+unsafe extern "C" fn qk_thread_ret() {
+    #[cfg(target_abi = "eabihf")]
+    core::arch::naked_asm!(
+        "mrs r0, CONTROL",
+        "bic r0, r0, #4",
+        "msr CONTROL, r0",
+        "isb",
+        "ldr r0, =0xE000ED04",
+        "mov r1, #1",
+        "lsl r1, r1, #31",
+        "str r1, [r0]",
+        "b .",
+    );
+    #[cfg(not(target_abi = "eabihf"))]
+    core::arch::naked_asm!(
+        "ldr r0, =0xE000ED04",
+        "mov r1, #1",
+        "lsl r1, r1, #31",
+        "str r1, [r0]",
+        "b .",
+    );
+}
+
+/// NMI handler: discards its own frame, unlocks the scheduler and returns
+/// through the frame PendSV was entered with.
+///
+/// # Safety
+///
+/// Exception handler; only the processor may call it.
+#[cfg(all(feature = "hw", feature = "qk"))]
+#[unsafe(naked)]
+#[no_mangle]
+pub unsafe extern "C" fn NMI_Handler() {
+    #[cfg(target_abi = "eabihf")]
+    core::arch::naked_asm!(
+        "add sp, sp, #(8*4)",
+        "mov r0, #0",
+        "msr BASEPRI, r0",
+        "pop {{r0, lr}}",
+        "dsb",
+        "tst lr, #(1 << 4)",
+        "it eq",
+        "vldmiaeq sp!, {{s16-s31}}",
+        "bx lr",
+    );
+    #[cfg(not(target_abi = "eabihf"))]
+    core::arch::naked_asm!(
+        "add sp, sp, #(8*4)",
+        "mov r0, #0",
+        "msr BASEPRI, r0",
+        "bx lr",
+    );
+}
@@ -0,0 +1,60 @@
+//! Reset-time RAM initialisation for applications linked without
+//! `cortex-m-rt`.
+//!
+//! The linker script provides the section bounds under `cortex-m-rt`'s
+//! names: `__sidata` (load address of `.data`), `__sdata`/`__edata` and
+//! `__sbss`/`__ebss`, all word-aligned. [`qk_reset_handler!`] defines a
+//! `Reset_Handler` that fills RAM from them and calls the application's
+//! entry point:
+//!
+//! ```ignore
+//! qf_port_cortex_m::qk_reset_handler!(main);
+//!
+//! fn main() -> ! {
+//!     // build the kernel, RUNTIME.start(), RUNTIME.start_systick(..), then idle
+//! }
+//! ```
+//!
+//! With `cortex-m-rt` in the link, use its `#[entry]` instead; it does the
+//! same before calling `main`.
+
+/// Copies `.data` from flash and zeroes `.bss`.
+///
+/// # Safety
+///
+/// Call once, first thing after reset, before anything touches a `static`.
+#[cfg(feature = "hw")]
+pub unsafe fn init_ram() {
+    extern "C" {
+        static __sidata: u32;
+        static mut __sdata: u32;
+        static mut __edata: u32;
+        static mut __sbss: u32;
+        static mut __ebss: u32;
+    }
+    let mut src = &raw const __sidata;
+    let mut dst = &raw mut __sdata;
+    while dst < &raw mut __edata {
+        dst.write_volatile(src.read());
+        dst = dst.add(1);
+        src = src.add(1);
+    }
+    let mut dst = &raw mut __sbss;
+    while dst < &raw mut __ebss {
+        dst.write_volatile(0);
+        dst = dst.add(1);
+    }
+}
+
+/// Defines `Reset_Handler`: [`init_ram`](crate::startup::init_ram), then
+/// `$main`, which must not return.
+#[macro_export]
+macro_rules! qk_reset_handler {
+    ($main:path) => {
+        #[no_mangle]
+        pub unsafe extern "C" fn Reset_Handler() -> ! {
+            $crate::startup::init_ram();
+            $main()
+        }
+    };
+}
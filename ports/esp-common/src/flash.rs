@@ -0,0 +1,365 @@
+//! Active-object persistence in a dedicated flash data partition.
+//!
+//! [`PartitionFlash`] exposes one data partition as raw
+//! [`qf::persist::Flash`] sectors, programmed through the SPI-flash routines
+//! in the boot ROM (`rom` feature). A [`qf::persist::FlashStore`] on top of
+//! it keeps the designated active objects' state:
+//!
+//! ```rust,ignore
+//! let flash = PartitionFlash::find(DEFAULT_LABEL).expect("no qp_state partition");
+//! let mut persistence = Persistence::<_, 128>::new(PartitionStore::new(flash), &PERSISTENT)
+//!     .with_trace_hook(hook.clone());
+//! persistence.restore_all(); // before the initial transitions
+//! kernel.start();
+//! // ... on an orderly shutdown:
+//! persistence.save_all()?;
+//! ```
+//!
+//! The store writes its own sector format, not the ESP-IDF NVS page format,
+//! so it must not use the `nvs` partition: ESP-IDF and esp-wifi keep their
+//! calibration and configuration data there. Give it a partition of its own
+//! in `partitions.csv`, two sectors per persistent object:
+//!
+//! ```text
+//! # Name,   Type, SubType, Offset,  Size
+//! nvs,      data, nvs,     0x9000,  0x6000
+//! phy_init, data, phy,     0xf000,  0x1000
+//! factory,  app,  factory, 0x10000, 1M
+//! qp_state, data, 0x40,    ,        0x4000
+//! ```
+//!
+//! Code running from flash must not execute while the flash is busy. Every
+//! ROM call runs with interrupts masked; with the `esp32s3` feature it also
+//! parks the other core and suspends both caches for its duration, from a
+//! routine placed in IRAM (`.rwtext`).
+
+#[cfg(feature = "rom")]
+use qf::persist::Flash;
+use qf::persist::FlashStore;
+
+/// Offset of the partition table in flash.
+pub const PARTITION_TABLE_OFFSET: u32 = 0x8000;
+/// Largest partition table, in bytes.
+pub const PARTITION_TABLE_SIZE: usize = 0xC00;
+/// Label of the partition `PartitionFlash::find` is usually given.
+pub const DEFAULT_LABEL: &str = "qp_state";
+/// Erase granularity of the SPI flash.
+pub const SECTOR_SIZE: usize = 4096;
+
+/// Partition type of data partitions.
+const DATA_TYPE: u8 = 0x01;
+/// Bytes per partition-table entry.
+const ENTRY_SIZE: usize = 32;
+/// First two bytes of a partition entry.
+const ENTRY_MAGIC: [u8; 2] = [0xAA, 0x50];
+
+/// Persistence store on a dedicated data partition.
+pub type PartitionStore = FlashStore<PartitionFlash>;
+
+/// One partition-table entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Partition {
+    /// Partition type: `0x00` app, `0x01` data.
+    pub kind: u8,
+    /// Partition subtype; `0x40`–`0xFE` are free for custom data.
+    pub subtype: u8,
+    /// Offset in flash, in bytes.
+    pub offset: u32,
+    /// Size in bytes.
+    pub size: u32,
+}
+
+impl Partition {
+    /// Decodes a 32-byte entry with a label, or `None` at the end of the
+    /// table (erased bytes or the MD5 entry).
+    pub fn parse(entry: &[u8; ENTRY_SIZE]) -> Option<(Self, &[u8])> {
+        if entry[..2] != ENTRY_MAGIC {
+            return None;
+        }
+        let word = |at: usize| u32::from_le_bytes(entry[at..at + 4].try_into().unwrap());
+        let label = &entry[12..28];
+        let len = label.iter().position(|&b| b == 0).unwrap_or(label.len());
+        let part = Self { kind: entry[2], subtype: entry[3], offset: word(4), size: word(8) };
+        Some((part, &label[..len]))
+    }
+
+    /// Looks `label` up in a raw partition table.
+    pub fn find_in(table: &[u8], label: &str) -> Option<Self> {
+        table
+            .chunks_exact(ENTRY_SIZE)
+            .map(|entry| Self::parse(entry.try_into().unwrap()))
+            .take_while(Option::is_some)
+            .flatten()
+            .find(|(_, name)| *name == label.as_bytes())
+            .map(|(part, _)| part)
+    }
+}
+
+/// Error code returned by a boot-ROM SPI-flash routine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RomFlashError(pub i32);
+
+/// Why `PartitionFlash::find` found no usable partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindError {
+    /// No partition carries the label.
+    Missing,
+    /// The partition is not a data partition, or not sector-aligned.
+    Unusable(Partition),
+    /// Reading the partition table failed.
+    Rom(RomFlashError),
+}
+
+/// A range of whole flash sectors, accessed through the boot ROM.
+#[derive(Debug)]
+pub struct PartitionFlash {
+    base: u32,
+    sectors: usize,
+}
+
+impl PartitionFlash {
+    /// The partition at `offset` spanning `size` bytes, both multiples of
+    /// [`SECTOR_SIZE`].
+    pub const fn new(offset: u32, size: u32) -> Self {
+        assert!(offset.is_multiple_of(SECTOR_SIZE as u32) && size.is_multiple_of(SECTOR_SIZE as u32));
+        Self { base: offset, sectors: size as usize / SECTOR_SIZE }
+    }
+
+    /// Offset of the partition in flash.
+    pub const fn offset(&self) -> u32 {
+        self.base
+    }
+
+    /// Size of the partition in bytes.
+    pub const fn size(&self) -> u32 {
+        (self.sectors * SECTOR_SIZE) as u32
+    }
+
+    /// The data partition labelled `label` in the flashed partition table.
+    #[cfg(feature = "rom")]
+    pub fn find(label: &str) -> Result<Self, FindError> {
+        let mut entry = [0u8; ENTRY_SIZE];
+        for index in 0..PARTITION_TABLE_SIZE / ENTRY_SIZE {
+            read(PARTITION_TABLE_OFFSET + (index * ENTRY_SIZE) as u32, &mut entry).map_err(FindError::Rom)?;
+            let Some((part, name)) = Partition::parse(&entry) else { break };
+            if name == label.as_bytes() {
+                return Self::try_from(part);
+            }
+        }
+        Err(FindError::Missing)
+    }
+}
+
+impl TryFrom<Partition> for PartitionFlash {
+    type Error = FindError;
+
+    /// Accepts sector-aligned, non-empty data partitions.
+    fn try_from(part: Partition) -> Result<Self, FindError> {
+        let aligned = |v: u32| v.is_multiple_of(SECTOR_SIZE as u32);
+        if part.kind != DATA_TYPE || !aligned(part.offset) || !aligned(part.size) || part.size == 0 {
+            return Err(FindError::Unusable(part));
+        }
+        Ok(Self::new(part.offset, part.size))
+    }
+}
+
+#[cfg(feature = "rom")]
+mod rom {
+    extern "C" {
+        pub fn esp_rom_spiflash_read(src_addr: u32, data: *mut u32, len: u32) -> i32;
+        pub fn esp_rom_spiflash_write(dest_addr: u32, data: *const u32, len: u32) -> i32;
+        pub fn esp_rom_spiflash_erase_sector(sector_number: u32) -> i32;
+        pub fn esp_rom_spiflash_unlock() -> i32;
+    }
+
+    #[cfg(feature = "esp32s3")]
+    extern "C" {
+        pub fn Cache_Suspend_ICache() -> u32;
+        pub fn Cache_Resume_ICache(autoload: u32);
+        pub fn Cache_Suspend_DCache() -> u32;
+        pub fn Cache_Resume_DCache(autoload: u32);
+    }
+}
+
+/// Stalls and releases the other LX7 core through the RTC_CNTL software
+/// stall fields, as esp-hal's `CpuControl::park_core` does.
+#[cfg(feature = "esp32s3")]
+mod park {
+    const RTC_CNTL_OPTIONS0: *mut u32 = 0x6000_8000 as *mut u32;
+    const RTC_CNTL_SW_CPU_STALL: *mut u32 = 0x6000_80BC as *mut u32;
+
+    /// `(C0 shift, C1 shift)` of the stall fields of the core other than
+    /// `core`.
+    #[link_section = ".rwtext"]
+    fn fields(core: u32) -> (u32, u32) {
+        match core {
+            0 => (0, 20), // park the APP CPU
+            _ => (2, 26), // park the PRO CPU
+        }
+    }
+
+    /// Stalls the core not running the caller.
+    #[link_section = ".rwtext"]
+    pub unsafe fn other(core: u32) {
+        let (c0, c1) = fields(core);
+        let stall = core::ptr::read_volatile(RTC_CNTL_SW_CPU_STALL);
+        core::ptr::write_volatile(RTC_CNTL_SW_CPU_STALL, (stall & !(0x3F << c1)) | (0x21 << c1));
+        let options = core::ptr::read_volatile(RTC_CNTL_OPTIONS0);
+        core::ptr::write_volatile(RTC_CNTL_OPTIONS0, (options & !(0x3 << c0)) | (0x2 << c0));
+    }
+
+    /// Lets the core parked by [`other`] run again.
+    #[link_section = ".rwtext"]
+    pub unsafe fn release(core: u32) {
+        let (c0, c1) = fields(core);
+        let options = core::ptr::read_volatile(RTC_CNTL_OPTIONS0);
+        core::ptr::write_volatile(RTC_CNTL_OPTIONS0, options & !(0x3 << c0));
+        let stall = core::ptr::read_volatile(RTC_CNTL_SW_CPU_STALL);
+        core::ptr::write_volatile(RTC_CNTL_SW_CPU_STALL, stall & !(0x3F << c1));
+    }
+}
+
+/// One ROM flash operation.
+#[cfg(feature = "rom")]
+#[derive(Clone, Copy)]
+enum Op {
+    Read { addr: u32, words: *mut u32, len: u32 },
+    Write { addr: u32, words: *const u32, len: u32 },
+    Erase { sector: u32 },
+    Unlock,
+}
+
+/// Runs `op` with nothing else fetching from flash.
+///
+/// Everything executed between parking the other core and releasing it is
+/// in IRAM or ROM.
+#[cfg(feature = "rom")]
+#[inline(never)]
+#[link_section = ".rwtext"]
+unsafe fn rom_op(op: Op) -> i32 {
+    #[cfg(feature = "esp32s3")]
+    let core = {
+        let core: u32;
+        core::arch::asm!("rsr.prid {0}", "extui {0}, {0}, 13, 1", out(reg) core);
+        park::other(core);
+        core
+    };
+    #[cfg(feature = "esp32s3")]
+    let (icache, dcache) = (rom::Cache_Suspend_ICache(), rom::Cache_Suspend_DCache());
+
+    let code = match op {
+        Op::Read { addr, words, len } => rom::esp_rom_spiflash_read(addr, words, len),
+        Op::Write { addr, words, len } => rom::esp_rom_spiflash_write(addr, words, len),
+        Op::Erase { sector } => rom::esp_rom_spiflash_erase_sector(sector),
+        Op::Unlock => rom::esp_rom_spiflash_unlock(),
+    };
+
+    #[cfg(feature = "esp32s3")]
+    {
+        rom::Cache_Resume_DCache(dcache);
+        rom::Cache_Resume_ICache(icache);
+        park::release(core);
+    }
+    code
+}
+
+/// Runs `op` with interrupts masked.
+#[cfg(feature = "rom")]
+fn rom_call(op: Op) -> Result<(), RomFlashError> {
+    // SAFETY: every pointer in `op` covers `len` bytes for the call.
+    match critical_section::with(|_| unsafe { rom_op(op) }) {
+        0 => Ok(()),
+        code => Err(RomFlashError(code)),
+    }
+}
+
+/// Words staged per ROM call; the routines need word-aligned buffers.
+#[cfg(feature = "rom")]
+const CHUNK_WORDS: usize = 16;
+
+#[cfg(feature = "rom")]
+fn read(mut addr: u32, buf: &mut [u8]) -> Result<(), RomFlashError> {
+    let mut words = [0u32; CHUNK_WORDS];
+    for chunk in buf.chunks_mut(CHUNK_WORDS * 4) {
+        let len = chunk.len().next_multiple_of(4) as u32;
+        rom_call(Op::Read { addr, words: words.as_mut_ptr(), len })?;
+        for (dst, src) in chunk.iter_mut().zip(words.iter().flat_map(|w| w.to_le_bytes())) {
+            *dst = src;
+        }
+        addr += chunk.len() as u32;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "rom")]
+impl Flash for PartitionFlash {
+    type Error = RomFlashError;
+    const SECTOR_SIZE: usize = SECTOR_SIZE;
+
+    fn sectors(&self) -> usize {
+        self.sectors
+    }
+
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), RomFlashError> {
+        read(self.base + offset as u32, buf)
+    }
+
+    fn erase(&mut self, sector: usize) -> Result<(), RomFlashError> {
+        let number = self.base / SECTOR_SIZE as u32 + sector as u32;
+        rom_call(Op::Unlock)?;
+        rom_call(Op::Erase { sector: number })
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), RomFlashError> {
+        let mut addr = self.base + offset as u32;
+        rom_call(Op::Unlock)?;
+        for chunk in data.chunks(CHUNK_WORDS * 4) {
+            // The tail of the last word is padded with 0xFF, which leaves
+            // those erased bytes untouched.
+            let mut words = [u32::MAX; CHUNK_WORDS];
+            for (i, &byte) in chunk.iter().enumerate() {
+                let shift = (i % 4) * 8;
+                words[i / 4] &= !(0xFF << shift) | (u32::from(byte) << shift);
+            }
+            let len = chunk.len().next_multiple_of(4) as u32;
+            rom_call(Op::Write { addr, words: words.as_ptr(), len })?;
+            addr += chunk.len() as u32;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: u8, subtype: u8, offset: u32, size: u32, label: &str) -> [u8; ENTRY_SIZE] {
+        let mut e = [0u8; ENTRY_SIZE];
+        e[..2].copy_from_slice(&ENTRY_MAGIC);
+        e[2] = kind;
+        e[3] = subtype;
+        e[4..8].copy_from_slice(&offset.to_le_bytes());
+        e[8..12].copy_from_slice(&size.to_le_bytes());
+        e[12..12 + label.len()].copy_from_slice(label.as_bytes());
+        e
+    }
+
+    #[test]
+    fn partitions_are_found_by_label_up_to_the_end_marker() {
+        let mut table = [0xFFu8; ENTRY_SIZE * 5];
+        table[..32].copy_from_slice(&entry(DATA_TYPE, 0x02, 0x9000, 0x6000, "nvs"));
+        table[32..64].copy_from_slice(&entry(0x00, 0x00, 0x10000, 0x10_0000, "factory"));
+        table[64..96].copy_from_slice(&entry(DATA_TYPE, 0x40, 0x11_0000, 0x4000, DEFAULT_LABEL));
+        // An entry after the end marker is not part of the table.
+        table[128..].copy_from_slice(&entry(DATA_TYPE, 0x41, 0x20_0000, 0x1000, "stale"));
+
+        let part = Partition::find_in(&table, DEFAULT_LABEL).unwrap();
+        assert_eq!((part.offset, part.size, part.subtype), (0x11_0000, 0x4000, 0x40));
+        let flash = PartitionFlash::try_from(part).unwrap();
+        assert_eq!((flash.offset(), flash.size()), (0x11_0000, 0x4000));
+
+        assert_eq!(Partition::find_in(&table, "stale"), None);
+        let factory = Partition::find_in(&table, "factory").unwrap();
+        assert_eq!(PartitionFlash::try_from(factory).unwrap_err(), FindError::Unusable(factory));
+    }
+}
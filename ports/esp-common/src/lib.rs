@@ -0,0 +1,8 @@
+#![no_std]
+
+//! Code shared by the ESP32 ports.
+//!
+//! The ESP32-C6 and ESP32-S3 ports re-export these modules; chip
+//! differences are selected with this crate's features.
+
+pub mod flash;
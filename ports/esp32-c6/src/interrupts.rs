@@ -1,11 +1,35 @@
-use core::sync::atomic::{AtomicBool, Ordering};
-#[cfg(feature = "critical-section")]
-use critical_section::with;
+//! ESP32-C6 interrupt priorities and the scheduler lock.
+//!
+//! CPU interrupt lines have priorities 1–15 and INTPRI masks every line
+//! below its threshold. Locking the scheduler raises the threshold above a
+//! ceiling, so kernel-aware interrupts at or below it (the tick among them)
+//! wait while interrupts above it, such as the radio's DIO1, still run. The
+//! previous threshold comes back when the [`SchedulerGuard`] drops, which
+//! makes locks nest.
+//!
+//! Without the `rt` feature the threshold is kept in memory, so the locking
+//! logic runs on the host.
 
-/// Minimal placeholder for the ESP32-C6 interrupt controller interface.
+#[cfg(not(feature = "rt"))]
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// Priority of the SYSTIMER tick interrupt.
+pub const TICK_PRIORITY: u8 = 1;
+/// CPU interrupt line the SYSTIMER alarm is routed to.
+pub const TICK_CPU_INTERRUPT: u32 = 1;
+/// Default scheduler-lock ceiling: priorities 1–7 are kernel-aware.
+pub const DEFAULT_CEILING: u8 = 7;
+/// Threshold with nothing masked.
+const UNMASKED: u8 = 1;
+/// Highest interrupt priority; a ceiling here masks every line.
+const PRIORITY_MAX: u8 = 15;
+
+/// ESP32-C6 interrupt controller: tick routing and threshold-based locking.
 #[derive(Debug)]
 pub struct InterruptController {
-    scheduler_locked: AtomicBool,
+    ceiling: u8,
+    #[cfg(not(feature = "rt"))]
+    threshold: AtomicU8,
 }
 
 impl Default for InterruptController {
@@ -15,62 +39,143 @@ impl Default for InterruptController {
 }
 
 impl InterruptController {
-    /// Creates a controller with scheduler interrupts enabled.
+    /// Creates a controller with nothing masked and [`DEFAULT_CEILING`].
     pub const fn new() -> Self {
         Self {
-            scheduler_locked: AtomicBool::new(false),
+            ceiling: DEFAULT_CEILING,
+            #[cfg(not(feature = "rt"))]
+            threshold: AtomicU8::new(UNMASKED),
         }
     }
 
-    /// Configures interrupt priorities via the PLIC to align with the QK scheduler.
+    /// Sets the priority at and below which [`lock_scheduler`](Self::lock_scheduler)
+    /// masks interrupts, clamped to 1–15.
+    pub fn set_ceiling(&mut self, ceiling: u8) {
+        self.ceiling = ceiling.clamp(UNMASKED, PRIORITY_MAX);
+    }
+
+    /// The scheduler-lock ceiling.
+    pub fn ceiling(&self) -> u8 {
+        self.ceiling
+    }
+
+    /// Routes the SYSTIMER alarm to [`TICK_CPU_INTERRUPT`] at
+    /// [`TICK_PRIORITY`] and unmasks all priorities.
     pub fn configure_priorities(&mut self) {
         #[cfg(feature = "rt")]
         {
-            use hal_rvsis::plic::PlicController;
-            use hal::interrupt::InterruptController;
-            // ESP32-C6 PLIC base is 0x2040_0000 in Renode
-            // Safety: called once during port init with exclusive peripheral access.
-            let mut plic = unsafe { PlicController::new(0x2040_0000, 0) };
-            // Enable the RISC-V machine-timer interrupt (source 7 on ESP32-C6).
-            let _ = plic.set_priority(7, 1);
+            use hal_rvsis::esp32c6::systimer::SYSTIMER_TARGET0_SOURCE;
+            // Safety: called once during port init; the handle is stateless.
+            let mut intmtx = unsafe { hal_rvsis::esp32c6::Esp32C6IntMatrix::new() };
+            let _ = intmtx.route(SYSTIMER_TARGET0_SOURCE, TICK_CPU_INTERRUPT, TICK_PRIORITY);
         }
+        self.write_threshold(UNMASKED);
     }
 
-    /// Locks the scheduler and returns a guard that releases on drop.
+    /// Masks interrupts at and below the ceiling until the guard drops.
     pub fn lock_scheduler(&self) -> SchedulerGuard<'_> {
-        #[cfg(feature = "critical-section")]
+        self.mask_up_to(self.ceiling)
+    }
+
+    /// Masks interrupts at and below `ceiling` until the guard drops. Never
+    /// lowers a threshold an enclosing lock has raised.
+    pub fn mask_up_to(&self, ceiling: u8) -> SchedulerGuard<'_> {
+        let previous = self.threshold();
+        let threshold = previous.max(ceiling.min(PRIORITY_MAX) + 1);
+        self.write_threshold(threshold);
+        SchedulerGuard { controller: self, previous }
+    }
+
+    /// Returns true while any scheduler lock is held.
+    pub fn is_scheduler_locked(&self) -> bool {
+        self.threshold() > UNMASKED
+    }
+
+    /// Current interrupt threshold; priorities below it are masked.
+    pub fn threshold(&self) -> u8 {
+        #[cfg(feature = "rt")]
         {
-            let previous = self.scheduler_locked.swap(true, Ordering::AcqRel);
-            debug_assert!(!previous, "scheduler lock re-entered");
-            with(|_| {});
-            SchedulerGuard { controller: self }
+            // Safety: reading the threshold has no side effects.
+            unsafe { hal_rvsis::esp32c6::Esp32C6IntMatrix::new() }.threshold()
         }
-        #[cfg(not(feature = "critical-section"))]
+        #[cfg(not(feature = "rt"))]
         {
-            let previous = self.scheduler_locked.swap(true, Ordering::AcqRel);
-            debug_assert!(!previous, "scheduler lock re-entered");
-            SchedulerGuard { controller: self }
+            self.threshold.load(Ordering::Acquire)
         }
     }
 
-    /// Returns true when the scheduler is currently locked.
-    pub fn is_scheduler_locked(&self) -> bool {
-        self.scheduler_locked.load(Ordering::Acquire)
-    }
-
-    pub(crate) fn unlock_scheduler(&self) {
-        self.scheduler_locked.store(false, Ordering::Release);
+    fn write_threshold(&self, threshold: u8) {
+        #[cfg(feature = "rt")]
+        {
+            // Safety: the threshold is only written here, by the thread
+            // holding the outermost lock or restoring an inner one.
+            unsafe { hal_rvsis::esp32c6::Esp32C6IntMatrix::new() }.set_threshold(threshold);
+        }
+        #[cfg(not(feature = "rt"))]
+        {
+            self.threshold.store(threshold, Ordering::Release);
+        }
     }
 }
 
-/// Guard that unlocks the scheduler when dropped.
+/// Restores the interrupt threshold when dropped.
 #[derive(Debug)]
 pub struct SchedulerGuard<'a> {
     controller: &'a InterruptController,
+    previous: u8,
 }
 
 impl Drop for SchedulerGuard<'_> {
     fn drop(&mut self) {
-        self.controller.unlock_scheduler();
+        self.controller.write_threshold(self.previous);
+    }
+}
+
+/// Idle [`InterruptLock`] for the QV kernel: clears `mstatus.MIE`. Unlike a
+/// raised threshold this still lets a pending line wake `WFI`, so the idle
+/// callback can sleep with `cx.sleep(interrupts::idle_sleep)` and the tick
+/// that posts the next event is taken as soon as the lock is released.
+///
+/// [`InterruptLock`]: qf::idle::InterruptLock
+#[cfg(feature = "rt")]
+pub const IDLE_LOCK: qf::idle::InterruptLock = qf::idle::InterruptLock {
+    lock: hal_rvsis::mstatus::qk_lock,
+    unlock: hal_rvsis::mstatus::qk_unlock,
+};
+
+/// Sleeps until an interrupt, from an idle callback entered under
+/// [`IDLE_LOCK`].
+#[cfg(feature = "rt")]
+#[inline]
+pub fn idle_sleep() {
+    hal_rvsis::asm::wfi();
+}
+
+#[cfg(all(test, not(feature = "rt")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locks_mask_up_to_the_ceiling_and_nest() {
+        let mut controller = InterruptController::new();
+        controller.configure_priorities();
+        assert!(!controller.is_scheduler_locked());
+
+        {
+            let _outer = controller.lock_scheduler();
+            assert_eq!(controller.threshold(), DEFAULT_CEILING + 1);
+            {
+                let _inner = controller.mask_up_to(2);
+                assert_eq!(controller.threshold(), DEFAULT_CEILING + 1);
+                let _higher = controller.mask_up_to(12);
+                assert_eq!(controller.threshold(), 13);
+            }
+            assert_eq!(controller.threshold(), DEFAULT_CEILING + 1);
+        }
+        assert!(!controller.is_scheduler_locked());
+
+        controller.set_ceiling(40);
+        let _all = controller.lock_scheduler();
+        assert_eq!(controller.threshold(), 16);
     }
 }
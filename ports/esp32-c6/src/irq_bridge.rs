@@ -0,0 +1,177 @@
+//! Interrupt-to-event bridge — binds HAL interrupt sources to active objects.
+//!
+//! At init time each source is bound to a target active object and signal.
+//! The handler the application installs for the source then calls the
+//! matching `on_*` function, which acknowledges the peripheral, posts a
+//! signal-only event to the bound object and lets the kernel preempt on the
+//! way out:
+//!
+//! ```rust,ignore
+//! irq_bridge::init(&KERNEL);
+//! irq_bridge::bind_interrupt(&mut button, Edge::Falling, BUTTON_AO, BUTTON_SIG)?;
+//! irq_bridge::bind_line(Line::UartRx(0), SHELL_AO, RX_SIG, || UART0.on_rx_interrupt(&RX))?;
+//!
+//! #[interrupt] fn GPIO()  { irq_bridge::on_gpio_interrupt(); }
+//! #[interrupt] fn UART0() { irq_bridge::on_line_interrupt(Line::UartRx(0)); }
+//! ```
+//!
+//! The `on_*` functions bracket themselves with `qk_isr_entry!()` /
+//! `qk_isr_exit!()`. Edges on unbound pins are acknowledged and dropped.
+
+#![cfg(feature = "qp-integration")]
+
+use core::cell::{Cell, RefCell};
+
+use critical_section::Mutex;
+use hal::error::{HalError, HalResult};
+use hal::gpio::Edge;
+use hal_rvsis::esp32c6::gpio::{take_interrupts, GPIO_PINS};
+use hal_rvsis::esp32c6::Esp32C6Pin;
+use qf::active::ActiveObjectId;
+use qf::event::{DynEvent, Signal};
+use qk::QkKernel;
+
+/// Where an interrupt's event goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Binding {
+    target: ActiveObjectId,
+    signal: Signal,
+}
+
+/// Interrupt lines other than GPIO, each served by its own handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line {
+    /// UART0 or UART1 receive (FIFO threshold or overflow).
+    UartRx(u8),
+    /// SYSTIMER alarm comparator 0..=2.
+    TimerAlarm(u8),
+}
+
+const UARTS: usize = 2;
+const ALARMS: usize = 3;
+const LINES: usize = UARTS + ALARMS;
+
+impl Line {
+    fn index(self) -> HalResult<usize> {
+        match self {
+            Line::UartRx(n) if (n as usize) < UARTS => Ok(n as usize),
+            Line::TimerAlarm(n) if (n as usize) < ALARMS => Ok(UARTS + n as usize),
+            _ => Err(HalError::InvalidParameter),
+        }
+    }
+}
+
+static KERNEL: Mutex<Cell<Option<&'static QkKernel>>> = Mutex::new(Cell::new(None));
+
+static GPIO: Mutex<RefCell<[Option<Binding>; GPIO_PINS as usize]>> =
+    Mutex::new(RefCell::new([None; GPIO_PINS as usize]));
+
+/// A line binding with the function that acknowledges the peripheral.
+type LineBinding = (Binding, fn());
+
+static LINE: Mutex<RefCell<[Option<LineBinding>; LINES]>> =
+    Mutex::new(RefCell::new([None; LINES]));
+
+/// Kernel the bridged events are posted to.
+pub fn init(kernel: &'static QkKernel) {
+    critical_section::with(|cs| KERNEL.borrow(cs).set(Some(kernel)));
+}
+
+/// Post `signal` to `target` whenever `pin` sees `edge`.
+pub fn bind_interrupt(
+    pin: &mut Esp32C6Pin,
+    edge: Edge,
+    target: ActiveObjectId,
+    signal: Signal,
+) -> HalResult<()> {
+    let slot = pin.pin_number() as usize;
+    if slot >= GPIO_PINS as usize {
+        return Err(HalError::InvalidParameter);
+    }
+    critical_section::with(|cs| {
+        GPIO.borrow_ref_mut(cs)[slot] = Some(Binding { target, signal });
+    });
+    pin.listen(edge)
+}
+
+/// Stop posting for `pin` and disable its interrupt.
+pub fn unbind_interrupt(pin: &mut Esp32C6Pin) {
+    pin.unlisten();
+    let slot = pin.pin_number() as usize;
+    critical_section::with(|cs| {
+        if let Some(binding) = GPIO.borrow_ref_mut(cs).get_mut(slot) {
+            *binding = None;
+        }
+    });
+}
+
+/// Post `signal` to `target` whenever `line` fires. `ack` runs first, in
+/// the handler, and must clear the peripheral's interrupt condition (e.g.
+/// drain the UART FIFO into an `RxRing`).
+pub fn bind_line(line: Line, target: ActiveObjectId, signal: Signal, ack: fn()) -> HalResult<()> {
+    let index = line.index()?;
+    critical_section::with(|cs| {
+        LINE.borrow_ref_mut(cs)[index] = Some((Binding { target, signal }, ack));
+    });
+    Ok(())
+}
+
+/// Stop posting for `line`.
+pub fn unbind_line(line: Line) -> HalResult<()> {
+    let index = line.index()?;
+    critical_section::with(|cs| LINE.borrow_ref_mut(cs)[index] = None);
+    Ok(())
+}
+
+/// GPIO interrupt body: acknowledges every pending pin and posts the
+/// bound events.
+pub fn on_gpio_interrupt() {
+    qf::qk_isr_entry!();
+    let mut pending = take_interrupts();
+    while pending != 0 {
+        let pin = pending.trailing_zeros() as usize;
+        pending &= pending - 1;
+        let binding = critical_section::with(|cs| GPIO.borrow_ref(cs).get(pin).copied().flatten());
+        if let Some(binding) = binding {
+            post(binding);
+        }
+    }
+    qf::qk_isr_exit!();
+    schedule();
+}
+
+/// Interrupt body for `line`: runs its acknowledge function and posts the
+/// bound event.
+pub fn on_line_interrupt(line: Line) {
+    let Ok(index) = line.index() else { return };
+    qf::qk_isr_entry!();
+    if let Some((binding, ack)) = critical_section::with(|cs| LINE.borrow_ref(cs)[index]) {
+        ack();
+        post(binding);
+    }
+    qf::qk_isr_exit!();
+    schedule();
+}
+
+fn kernel() -> Option<&'static QkKernel> {
+    critical_section::with(|cs| KERNEL.borrow(cs).get())
+}
+
+fn post(binding: Binding) {
+    if let Some(kernel) = kernel() {
+        if kernel.post(binding.target, DynEvent::empty_dyn(binding.signal)).is_err() {
+            qf::fusa::on_error(module_path!(), line!());
+        }
+    }
+}
+
+/// Run whatever the posted events made eligible to preempt, once the
+/// outermost handler is leaving.
+fn schedule() {
+    if qf::in_isr() {
+        return;
+    }
+    if let Some(kernel) = kernel() {
+        kernel.activate_pending();
+    }
+}
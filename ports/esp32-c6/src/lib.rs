@@ -2,9 +2,13 @@
 
 //! ESP32-C6 port scaffolding for the Quantum Platform kernels.
 //!
-//! This crate currently mirrors the ESP32-S3 port but targets Espressif's
-//! RISC-V based ESP32-C6. All interrupt controller and timer plumbing remain
-//! placeholders until real HAL bindings are introduced.
+//! This crate mirrors the ESP32-S3 port but targets Espressif's RISC-V based
+//! ESP32-C6. With the `rt` feature the tick comes from the SYSTIMER alarm and
+//! the scheduler lock raises the INTPRI interrupt threshold; without it both
+//! are emulated in memory so the port logic can be tested on the host.
+//! Applications run on either the preemptive QK kernel
+//! (`Esp32C6QkRuntime`) or the cooperative QV kernel
+//! (`Esp32C6QvRuntime`), which sleeps between ticks.
 
 #[cfg(feature = "rt")]
 extern crate alloc;
@@ -12,17 +16,29 @@ extern crate alloc;
 pub mod interrupts;
 pub mod timer;
 
+#[cfg(feature = "rt")]
+pub use qf_port_esp_common::flash;
+
 #[cfg(feature = "rt")]
 pub mod runtime;
 
 #[cfg(feature = "rt")]
 pub mod rf_isr;
 
+#[cfg(feature = "qp-integration")]
+pub mod irq_bridge;
+
+#[cfg(feature = "qp-integration")]
+pub mod wdt_health;
+
 pub use interrupts::{InterruptController, SchedulerGuard};
-pub use timer::SystemTimer;
+pub use timer::{on_systimer_alarm, SystemTimer};
 
 #[cfg(feature = "rt")]
-pub use runtime::Esp32C6QkRuntime;
+pub use flash::{PartitionFlash, PartitionStore};
+
+#[cfg(feature = "rt")]
+pub use runtime::{Esp32C6QkRuntime, Esp32C6QvRuntime};
 
 /// Aggregates the platform-specific subsystems managed by the ESP32-C6 port.
 #[derive(Debug)]
@@ -60,12 +76,12 @@ impl Esp32C6Port {
         &mut self.timer
     }
 
-    /// Placeholder for configuring interrupt priorities.
+    /// Routes the tick interrupt and unmasks all priorities.
     pub fn init_interrupts(&mut self) {
         self.interrupts.configure_priorities();
     }
 
-    /// Placeholder for configuring the hardware timer that drives `TimeEvent`s.
+    /// Starts the hardware timer that drives `TimeEvent`s.
     pub fn init_system_timer(&mut self, tick_hz: u32) {
         self.timer.configure_periodic(tick_hz);
     }
@@ -114,4 +130,13 @@ mod tests {
         port.init_system_timer(1000);
         assert_eq!(port.timer().tick_hz(), 1000);
     }
+
+    #[test]
+    fn alarms_are_counted_until_taken() {
+        let port = Esp32C6Port::new();
+        on_systimer_alarm();
+        on_systimer_alarm();
+        assert_eq!(port.timer().take_pending_ticks(), 2);
+        assert_eq!(port.timer().take_pending_ticks(), 0);
+    }
 }
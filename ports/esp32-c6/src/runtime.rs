@@ -2,7 +2,8 @@
 
 use alloc::sync::Arc;
 
-use qf::time::TimeEvent;
+use qf::kernel::{Kernel, KernelBuilder};
+use qf::time::{TimeEvent, TimeEventError, TimerWheel};
 use qk::{QkKernel, QkKernelBuilder, QkKernelError, QkTimeEventError, QkTimerWheel};
 
 use crate::{Esp32C6Port, PortConfig};
@@ -73,6 +74,103 @@ impl Esp32C6QkRuntime {
         self.timers.tick()
     }
 
+    /// Runs the timer wheel once for every tick the SYSTIMER alarm counted
+    /// since the last call, and returns how many that was.
+    pub fn service_ticks(&self) -> Result<u32, QkTimeEventError> {
+        let ticks = self.port.timer().take_pending_ticks();
+        for _ in 0..ticks {
+            self.timers.tick()?;
+        }
+        Ok(ticks)
+    }
+
+    /// Runs the kernel until all ready work completes.
+    pub fn run_until_idle(&self) {
+        self.kernel.run_until_idle();
+    }
+
+    /// Indicates whether there is outstanding work for the kernel.
+    pub fn has_pending_work(&self) -> bool {
+        self.kernel.has_pending_work()
+    }
+}
+
+/// QV (cooperative) runtime harness for the ESP32-C6 port.
+///
+/// The SYSTIMER alarm counts ticks; [`run`](Self::run) services them on the
+/// thread-mode loop and dispatches until idle. Build the kernel with an idle
+/// callback entered under [`IDLE_LOCK`](crate::interrupts::IDLE_LOCK) so the
+/// core sleeps between ticks instead of spinning.
+pub struct Esp32C6QvRuntime {
+    kernel: Arc<Kernel>,
+    timers: TimerWheel,
+    port: Esp32C6Port,
+    config: PortConfig,
+}
+
+impl Esp32C6QvRuntime {
+    /// Wraps an already constructed kernel and initialises the port. The
+    /// kernel is started by [`run`](Self::run) or [`start`](Self::start).
+    pub fn new(kernel: Arc<Kernel>, mut port: Esp32C6Port, config: PortConfig) -> Self {
+        port.init_interrupts();
+        port.init_system_timer(config.tick_hz);
+
+        let timers = TimerWheel::new(Arc::clone(&kernel));
+
+        Self {
+            kernel,
+            timers,
+            port,
+            config,
+        }
+    }
+
+    /// Builds a kernel from the provided builder and initialises the port.
+    pub fn with_builder(builder: KernelBuilder, port: Esp32C6Port, config: PortConfig) -> Self {
+        Self::new(Arc::new(builder.build()), port, config)
+    }
+
+    /// Returns a clone of the kernel handle stored in this runtime.
+    pub fn kernel(&self) -> Arc<Kernel> {
+        Arc::clone(&self.kernel)
+    }
+
+    /// Gives access to the embedded port instance.
+    pub fn port(&self) -> &Esp32C6Port {
+        &self.port
+    }
+
+    /// Retrieves the configuration used to start the runtime.
+    pub fn config(&self) -> PortConfig {
+        self.config
+    }
+
+    /// Registers a time event with the timer wheel.
+    pub fn register_time_event(&mut self, event: Arc<TimeEvent>) {
+        self.timers.register(event);
+    }
+
+    /// Starts every registered active object, for callers that drive the
+    /// loop themselves instead of calling [`run`](Self::run).
+    pub fn start(&self) {
+        self.kernel.start();
+    }
+
+    /// Processes a single tick.
+    pub fn tick(&self) -> Result<(), TimeEventError> {
+        self.timers.tick()
+    }
+
+    /// Runs the timer wheel once for every tick the SYSTIMER alarm counted
+    /// since the last call, and returns how many that was.
+    pub fn service_ticks(&self) -> Result<u32, TimeEventError> {
+        let ticks = self.port.timer().take_pending_ticks();
+        for _ in 0..ticks {
+            self.timers.tick()?;
+        }
+        Ok(ticks)
+    }
+
     /// Runs the kernel until all ready work completes.
     pub fn run_until_idle(&self) {
         self.kernel.run_until_idle();
@@ -82,6 +180,33 @@ impl Esp32C6QkRuntime {
     pub fn has_pending_work(&self) -> bool {
         self.kernel.has_pending_work()
     }
+
+    /// The QV main loop, `QF::run()`: starts the active objects, then
+    /// services counted ticks and dispatches until idle, sleeping in the
+    /// idle callback in between. Returns only after `Kernel::stop`.
+    pub fn run(&self) {
+        self.kernel.run(|| {
+            if self.service_ticks().is_err() {
+                qf::fusa::on_error(module_path!(), line!());
+            }
+        });
+    }
+}
+
+impl qf::port::Runtime for Esp32C6QvRuntime {
+    type TickError = TimeEventError;
+
+    fn tick(&self) -> Result<(), Self::TickError> {
+        Esp32C6QvRuntime::tick(self)
+    }
+
+    fn run_until_idle(&self) {
+        Esp32C6QvRuntime::run_until_idle(self);
+    }
+
+    fn has_pending_work(&self) -> bool {
+        Esp32C6QvRuntime::has_pending_work(self)
+    }
 }
 
 #[cfg(all(test, feature = "rt"))]
@@ -91,7 +216,7 @@ mod tests {
 
     use qf::active::{new_active_object, ActiveContext, ActiveObjectId, SignalHandler};
     use qf::event::Signal;
-    use qf::time::TimeEventConfig;
+    use qf::time::{TickDuration, TimeEventConfig};
 
     #[derive(Clone)]
     struct Recorder {
@@ -138,10 +263,40 @@ mod tests {
         ));
 
         runtime.register_time_event(Arc::clone(&event));
-        event.arm(1, Some(1));
+        event.arm(1, Some(TickDuration::from_ticks(1)));
 
         runtime.tick().expect("tick succeeds");
 
         assert_eq!(counter.load(core::sync::atomic::Ordering::SeqCst), 1);
     }
+
+    #[test]
+    fn qv_runtime_services_counted_ticks() {
+        let counter = Arc::new(core::sync::atomic::AtomicUsize::new(0));
+        let ao_id = ActiveObjectId::new(3);
+        let ao = new_active_object(ao_id, 2, Recorder::new(ao_id, Arc::clone(&counter)));
+
+        let mut runtime = Esp32C6QvRuntime::with_builder(
+            Kernel::builder().register(ao),
+            Esp32C6Port::new(),
+            PortConfig {
+                enable_trace: false,
+                tick_hz: 100,
+            },
+        );
+        runtime.start();
+
+        let event = TimeEvent::new(ao_id, TimeEventConfig::new(Signal(42)));
+        runtime.register_time_event(Arc::clone(&event));
+        event.arm(2, Some(TickDuration::from_ticks(2)));
+
+        for _ in 0..4 {
+            crate::on_systimer_alarm();
+        }
+        assert_eq!(runtime.service_ticks().expect("ticks succeed"), 4);
+        runtime.run_until_idle();
+
+        assert_eq!(counter.load(core::sync::atomic::Ordering::SeqCst), 2);
+        assert!(!runtime.has_pending_work());
+    }
 }
@@ -1,6 +1,22 @@
+//! SYSTIMER-driven QP tick.
+//!
+//! [`SystemTimer::configure_periodic`] starts SYSTIMER alarm 0 in period
+//! mode; the interrupt controller routes it to the tick line (see
+//! [`crate::interrupts`]). The application binds the alarm interrupt to a
+//! handler that calls [`on_systimer_alarm`], which acknowledges the alarm
+//! and counts the tick. The thread-mode loop then runs the timer wheel once
+//! per counted tick with [`Esp32C6QkRuntime::service_ticks`] (or the QV
+//! runtime's equivalent), so time events are never processed in interrupt
+//! context and no tick is lost while the loop is busy.
+//!
+//! [`Esp32C6QkRuntime::service_ticks`]: crate::Esp32C6QkRuntime::service_ticks
+
 use core::sync::atomic::{AtomicU32, Ordering};
 
-/// Placeholder for the ESP32-C6 system timer driving QK ticks.
+/// Ticks signalled by the alarm and not yet serviced.
+static PENDING_TICKS: AtomicU32 = AtomicU32::new(0);
+
+/// SYSTIMER alarm 0 driving QK ticks.
 #[derive(Debug)]
 pub struct SystemTimer {
     tick_hz: AtomicU32,
@@ -20,17 +36,21 @@ impl SystemTimer {
         }
     }
 
-    /// Configures the periodic tick frequency expected by the kernel.
+    /// Starts the periodic tick at `tick_hz` (at least 1 Hz) and enables the
+    /// alarm interrupt.
     pub fn configure_periodic(&self, tick_hz: u32) {
         self.tick_hz.store(tick_hz, Ordering::Release);
         #[cfg(feature = "rt")]
         {
-            use hal_rvsis::clint::ClintTimer;
             use hal::timer::{Timer, TimerMode};
-            // ESP32-C6 XTAL clock is 40 MHz; CLINT base is 0x2000_0000 in Renode
-            let period_us = 1_000_000u64 / tick_hz as u64;
-            let mut clint = ClintTimer::new(0x2000_0000, 40_000_000);
-            let _ = clint.start(period_us, TimerMode::Periodic);
+            // Safety: the port owns alarm 0; counter unit 0 is left running
+            // for esp-hal's time base.
+            let mut systimer = unsafe { hal_rvsis::esp32c6::Esp32C6Systimer::new() };
+            let period_us = 1_000_000u64 / u64::from(tick_hz.max(1));
+            let _ = systimer.clear_interrupt();
+            if systimer.start(period_us, TimerMode::Periodic).is_ok() {
+                let _ = systimer.enable_interrupt();
+            }
         }
     }
 
@@ -38,4 +58,22 @@ impl SystemTimer {
     pub fn tick_hz(&self) -> u32 {
         self.tick_hz.load(Ordering::Acquire)
     }
+
+    /// Ticks counted by [`on_systimer_alarm`] since the last call, which
+    /// resets the count.
+    pub fn take_pending_ticks(&self) -> u32 {
+        PENDING_TICKS.swap(0, Ordering::AcqRel)
+    }
+}
+
+/// SYSTIMER alarm 0 interrupt body: acknowledges the alarm and counts one
+/// tick. Call it from the handler bound to the SYSTIMER_TARGET0 interrupt.
+pub fn on_systimer_alarm() {
+    #[cfg(feature = "rt")]
+    {
+        use hal::timer::Timer;
+        // Safety: only clears the alarm-0 interrupt flag.
+        let _ = unsafe { hal_rvsis::esp32c6::Esp32C6Systimer::new() }.clear_interrupt();
+    }
+    PENDING_TICKS.fetch_add(1, Ordering::AcqRel);
 }
@@ -0,0 +1,46 @@
+//! ESP32-S3 ADC buffer bridge — turns filled sample buffers into events.
+//!
+//! [`attach`] installs a ready hook on an [`Esp32S3Adc`]; each time the DMA
+//! engine fills one half of the continuous-mode double buffer, the
+//! registered active object receives an [`AdcBufferReady`] event and takes
+//! that half with `DoubleBuffer::take`. The hook runs from
+//! [`Esp32S3Adc::on_dma_interrupt`], so the GDMA handler calling it must be
+//! bracketed by `qf::qk_isr_entry!()` / `qf::qk_isr_exit!()`.
+
+#![cfg(feature = "qp-integration")]
+
+use core::cell::Cell;
+
+use critical_section::Mutex;
+use hal_lxsis::esp32s3::Esp32S3Adc;
+use qf::active::ActiveRunnable;
+use qf::event::{DynEvent, Signal};
+
+/// Payload of the buffer-ready event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdcBufferReady {
+    /// Half of the double buffer that was just filled (0 or 1).
+    pub half: usize,
+}
+
+type Target = (&'static dyn ActiveRunnable, Signal);
+
+static ADC_AO: Mutex<Cell<Option<Target>>> = Mutex::new(Cell::new(None));
+
+/// Post [`AdcBufferReady`] events under `signal` to `ao`.
+pub fn register_adc_ao(ao: &'static dyn ActiveRunnable, signal: Signal) {
+    critical_section::with(|cs| ADC_AO.borrow(cs).set(Some((ao, signal))));
+}
+
+/// Route `adc`'s filled buffers to the registered active object.
+pub fn attach(adc: &mut Esp32S3Adc) {
+    adc.on_ready(buffer_ready);
+}
+
+/// Runs inside the GDMA interrupt, from [`Esp32S3Adc::on_dma_interrupt`].
+fn buffer_ready(half: usize) {
+    let Some((ao, signal)) = critical_section::with(|cs| ADC_AO.borrow(cs).get()) else {
+        return;
+    };
+    ao.post(DynEvent::with_payload(signal, AdcBufferReady { half }));
+}
@@ -6,22 +6,46 @@
 //! controller and system timer integration required by [`qk`] on Espressif's
 //! ESP32-S3 SoC. The implementation will evolve as hardware-specific HAL
 //! bindings are introduced.
+//!
+//! With the `smp` feature the kernel runs on both cores and active objects
+//! can be pinned to one of them; see [`smp`].
 
-#[cfg(feature = "rt")]
+#[cfg(any(feature = "rt", feature = "smp"))]
 extern crate alloc;
 
 pub mod interrupts;
 pub mod timer;
 
+#[cfg(feature = "smp")]
+pub mod smp;
+
+#[cfg(feature = "rt")]
+pub use qf_port_esp_common::flash;
+
 #[cfg(feature = "rt")]
 pub mod runtime;
 
 #[cfg(feature = "rt")]
 pub mod rf_isr;
 
+#[cfg(feature = "qp-integration")]
+pub mod spi_done;
+
+#[cfg(feature = "qp-integration")]
+pub mod adc_ready;
+
+#[cfg(feature = "qp-integration")]
+pub mod wdt_health;
+
 pub use interrupts::{InterruptController, SchedulerGuard};
 pub use timer::SystemTimer;
 
+#[cfg(feature = "smp")]
+pub use smp::Core;
+
+#[cfg(feature = "rt")]
+pub use flash::{PartitionFlash, PartitionStore};
+
 #[cfg(feature = "rt")]
 pub use runtime::Esp32S3QkRuntime;
 
@@ -2,9 +2,13 @@
 
 use alloc::sync::Arc;
 
+#[cfg(feature = "smp")]
+use qf::active::ActiveObjectId;
 use qf::time::TimeEvent;
 use qk::{QkKernel, QkKernelBuilder, QkKernelError, QkTimeEventError, QkTimerWheel};
 
+#[cfg(feature = "smp")]
+use crate::smp::{self, Core};
 use crate::{Esp32S3Port, PortConfig};
 
 /// QK runtime harness for the ESP32-S3 port.
@@ -31,6 +35,11 @@ impl Esp32S3QkRuntime {
     pub fn new(kernel: Arc<QkKernel>, mut port: Esp32S3Port, config: PortConfig) -> Self {
         port.init_interrupts();
         port.init_system_timer(config.tick_hz);
+        #[cfg(feature = "smp")]
+        {
+            smp::install_cross_core_hook(&kernel);
+            smp::configure_cross_core_interrupt();
+        }
 
         let timers = QkTimerWheel::new(Arc::clone(&kernel));
 
@@ -54,6 +63,29 @@ impl Esp32S3QkRuntime {
         Ok(Self::new(kernel, port, config))
     }
 
+    /// Pins `ao` to `core`: the other core leaves it ready and interrupts
+    /// `core` to run it.
+    ///
+    /// ```ignore
+    /// let runtime = Esp32S3QkRuntime::with_builder(builder, port, config)?
+    ///     .with_core_affinity(RADIO_ID, Core::App)?;
+    /// ```
+    #[cfg(feature = "smp")]
+    pub fn with_core_affinity(self, ao: ActiveObjectId, core: Core) -> Result<Self, QkKernelError> {
+        self.kernel.set_core_affinity(ao, core.mask())?;
+        Ok(self)
+    }
+
+    /// Cross-core interrupt body: acknowledges the request and runs the work
+    /// it announced on this core. Bind it to [`smp::CROSS_CORE_CPU_INTERRUPT`]
+    /// on both cores.
+    #[cfg(feature = "smp")]
+    pub fn on_cross_core_interrupt(&self) {
+        if smp::take_reschedule_request(Core::current()) {
+            self.kernel.run_until_idle();
+        }
+    }
+
     /// Returns a clone of the kernel handle stored in this runtime.
     pub fn kernel(&self) -> Arc<QkKernel> {
         Arc::clone(&self.kernel)
@@ -102,7 +134,7 @@ mod tests {
 
     use qf::active::{new_active_object, ActiveContext, ActiveObjectId, SignalHandler};
     use qf::event::Signal;
-    use qf::time::TimeEventConfig;
+    use qf::time::{TickDuration, TimeEventConfig};
 
     #[derive(Clone)]
     struct Recorder {
@@ -149,7 +181,7 @@ mod tests {
         ));
 
         runtime.register_time_event(Arc::clone(&event));
-        event.arm(1, Some(1));
+        event.arm(1, Some(TickDuration::from_ticks(1)));
 
         runtime.tick().expect("tick succeeds");
 
@@ -0,0 +1,178 @@
+//! Dual-core scheduling for the ESP32-S3.
+//!
+//! Both LX7 cores run the same [`QkKernel`]. Its ready set lives behind a
+//! spinlock (`spin::Mutex` in `no_std` builds), so either core may post and
+//! dispatch. An active object pinned to a [`Core`] with
+//! [`QkKernel::set_core_affinity`] only runs there. When the other core makes
+//! it ready, the kernel raises the cross-core interrupt (SYSTEM
+//! `CPU_INTR_FROM_CPU_n`) on its core. That core's handler calls
+//! [`Esp32S3QkRuntime::on_cross_core_interrupt`], which preempts whatever
+//! runs below it there.
+//!
+//! Each core routes and enables its own channel with
+//! [`configure_cross_core_interrupt`]. The port runtime does this for the PRO
+//! CPU; the APP CPU's entry point calls it before it starts dispatching.
+//!
+//! Without the `rt` feature the interrupt lines are flags in memory, so the
+//! request path runs on the host.
+//!
+//! [`Esp32S3QkRuntime::on_cross_core_interrupt`]: crate::Esp32S3QkRuntime::on_cross_core_interrupt
+
+use alloc::sync::Arc;
+
+use qk::QkKernel;
+
+#[cfg(not(feature = "rt"))]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// CPU interrupt line the cross-core request is routed to on both cores.
+pub const CROSS_CORE_CPU_INTERRUPT: u32 = 2;
+
+/// Cross-core interrupt lines, one per core.
+#[cfg(not(feature = "rt"))]
+static RAISED: [AtomicBool; 2] = [AtomicBool::new(false), AtomicBool::new(false)];
+
+/// One of the two LX7 cores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Core {
+    /// Core 0, which boots and runs the port initialisation.
+    Pro = 0,
+    /// Core 1.
+    App = 1,
+}
+
+impl Core {
+    /// The core executing the caller. Off-target this follows
+    /// [`qf::port::current_core_id`], folded onto the two cores.
+    pub fn current() -> Self {
+        #[cfg(feature = "rt")]
+        let id = hal_lxsis::asm::core_id() as u8;
+        #[cfg(not(feature = "rt"))]
+        let id = qf::port::current_core_id();
+        Self::from_id(id)
+    }
+
+    /// The core with index `id & 1`.
+    pub const fn from_id(id: u8) -> Self {
+        if id & 1 == 0 {
+            Self::Pro
+        } else {
+            Self::App
+        }
+    }
+
+    /// The core index, 0 or 1.
+    pub const fn id(self) -> u8 {
+        self as u8
+    }
+
+    /// This core alone, as a [`QkKernel::set_core_affinity`] mask.
+    pub const fn mask(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// The other core.
+    pub const fn other(self) -> Self {
+        match self {
+            Self::Pro => Self::App,
+            Self::App => Self::Pro,
+        }
+    }
+}
+
+/// Raises the cross-core interrupt on core `core`. This is the kernel's
+/// [`qk::CrossCoreHook`]; ids above 1 are folded onto the two cores.
+pub fn request_reschedule(core: u8) {
+    #[cfg(feature = "rt")]
+    {
+        // Safety: the port owns cross-core channels 0 and 1.
+        unsafe { hal_lxsis::esp32s3::Esp32S3CrossCore::new() }.raise(core);
+    }
+    #[cfg(not(feature = "rt"))]
+    RAISED[usize::from(core & 1)].store(true, Ordering::Release);
+}
+
+/// Acknowledges the cross-core interrupt on `core`; returns whether one was
+/// raised.
+pub fn take_reschedule_request(core: Core) -> bool {
+    #[cfg(feature = "rt")]
+    {
+        // Safety: the port owns cross-core channels 0 and 1.
+        unsafe { hal_lxsis::esp32s3::Esp32S3CrossCore::new() }.clear(core.id())
+    }
+    #[cfg(not(feature = "rt"))]
+    {
+        RAISED[usize::from(core.id())].swap(false, Ordering::AcqRel)
+    }
+}
+
+/// Routes the calling core's cross-core channel to
+/// [`CROSS_CORE_CPU_INTERRUPT`] and enables the line. Call it once on each
+/// core.
+pub fn configure_cross_core_interrupt() {
+    #[cfg(feature = "rt")]
+    {
+        use hal::interrupt::InterruptController as _;
+        let core = Core::current();
+        // Safety: each core writes only its own interrupt matrix and
+        // INTENABLE, during its start-up.
+        unsafe { hal_lxsis::esp32s3::Esp32S3CrossCore::new() }.route(core.id(), CROSS_CORE_CPU_INTERRUPT);
+        let _ = unsafe { hal_lxsis::intenable::IntenableController::new() }
+            .enable_interrupt(CROSS_CORE_CPU_INTERRUPT);
+    }
+}
+
+/// Makes `kernel` raise the cross-core interrupt for work that only the
+/// other core may run.
+pub fn install_cross_core_hook(kernel: &QkKernel) {
+    kernel
+        .scheduler()
+        .set_cross_core_hook(Some(Arc::new(request_reschedule)));
+}
+
+/// Core id hook for `qf::port::current_core_id`.
+#[cfg(feature = "rt")]
+#[no_mangle]
+pub fn qf_port_current_core_id() -> u8 {
+    hal_lxsis::asm::core_id() as u8
+}
+
+#[cfg(all(test, not(feature = "rt")))]
+mod tests {
+    use super::*;
+    use qf::active::{new_active_object, ActiveContext, ActiveObjectId, SignalHandler};
+    use qf::event::{DynEvent, Signal};
+
+    struct Idle;
+
+    impl SignalHandler for Idle {
+        fn handle_signal(&mut self, _signal: Signal, _ctx: &mut ActiveContext) {}
+    }
+
+    #[test]
+    fn posting_work_pinned_elsewhere_interrupts_that_core() {
+        let here = Core::current();
+        let pinned = ActiveObjectId::new(3);
+        let local = ActiveObjectId::new(4);
+        let kernel = QkKernel::builder()
+            .register(new_active_object(pinned, 6, Idle))
+            .unwrap()
+            .register(new_active_object(local, 2, Idle))
+            .unwrap()
+            .build()
+            .unwrap();
+        kernel.start();
+        install_cross_core_hook(&kernel);
+        kernel.set_core_affinity(pinned, here.other().mask()).unwrap();
+
+        kernel.post(pinned, DynEvent::empty_dyn(Signal(10))).unwrap();
+        assert!(take_reschedule_request(here.other()));
+        assert!(!take_reschedule_request(here.other()));
+        assert!(!kernel.has_pending_work());
+
+        kernel.post(local, DynEvent::empty_dyn(Signal(10))).unwrap();
+        assert!(!take_reschedule_request(here.other()));
+        assert!(!take_reschedule_request(here));
+        assert!(kernel.has_pending_work());
+    }
+}
@@ -0,0 +1,42 @@
+//! ESP32-S3 SPI completion bridge — turns finished DMA transfers into events.
+//!
+//! [`attach`] installs a completion hook on an [`Esp32S3Spi`]; every DMA
+//! transfer it completes is posted to the registered active object as a
+//! [`SpiDone`] event under the signal given to [`register_spi_ao`].
+
+#![cfg(feature = "qp-integration")]
+
+use core::cell::Cell;
+
+use critical_section::Mutex;
+use hal_lxsis::esp32s3::Esp32S3Spi;
+use qf::active::ActiveRunnable;
+use qf::event::{DynEvent, Signal};
+
+/// Payload of the completion event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpiDone {
+    /// Bytes clocked by the finished transfer.
+    pub len: usize,
+}
+
+type Target = (&'static dyn ActiveRunnable, Signal);
+
+static SPI_AO: Mutex<Cell<Option<Target>>> = Mutex::new(Cell::new(None));
+
+/// Post [`SpiDone`] events under `signal` to `ao`.
+pub fn register_spi_ao(ao: &'static dyn ActiveRunnable, signal: Signal) {
+    critical_section::with(|cs| SPI_AO.borrow(cs).set(Some((ao, signal))));
+}
+
+/// Route `spi`'s DMA completions to the registered active object.
+pub fn attach(spi: &mut Esp32S3Spi) {
+    spi.on_complete(spi_done);
+}
+
+fn spi_done(len: usize) {
+    let Some((ao, signal)) = critical_section::with(|cs| SPI_AO.borrow(cs).get()) else {
+        return;
+    };
+    ao.post(DynEvent::with_payload(signal, SpiDone { len }));
+}
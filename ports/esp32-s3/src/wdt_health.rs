@@ -0,0 +1,59 @@
+//! Kernel-health watchdog — a timer-group watchdog fed only by a healthy kernel.
+//!
+//! [`install`] arms an [`Esp32S3Wdt`] and hands it to [`FEEDER`], which the
+//! kernel consults each time it runs out of work (see
+//! [`qf::watchdog`](qf::watchdog#hardware-watchdog)). The watchdog is fed
+//! only when every queue has drained within the deadline and the tick has
+//! advanced, so a hung step, a starved object or a dead tick resets the
+//! chip. On the next boot [`report_reset`] turns a watchdog reset into a
+//! `QS_WDT_RESET` record:
+//!
+//! ```rust,ignore
+//! let kernel = QkKernel::builder().register(ao)?.feed_watchdog(&wdt_health::FEEDER).build()?;
+//! wdt_health::report_reset(&kernel);
+//! wdt_health::install(unsafe { Esp32S3Wdt::new(TIMG0_BASE as *const _) }, 2_000, 50)?;
+//! ```
+
+#![cfg(feature = "qp-integration")]
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+use hal::error::HalResult;
+use hal::watchdog::{ResetCause, Watchdog};
+use hal_lxsis::esp32s3::wdt::{reset_cause, reset_reason};
+use hal_lxsis::esp32s3::Esp32S3Wdt;
+use qf::watchdog::IdleFeeder;
+use qk::QkKernel;
+
+/// Feeder to register with `QkKernelBuilder::feed_watchdog`.
+pub static FEEDER: IdleFeeder = IdleFeeder::new(feed, 0);
+
+static WDT: Mutex<RefCell<Option<Esp32S3Wdt>>> = Mutex::new(RefCell::new(None));
+
+/// Arm `wdt` to reset the chip after `timeout_ms` without a feed, and have
+/// [`FEEDER`] feed it after passes that drain within `deadline` ticks.
+pub fn install(mut wdt: Esp32S3Wdt, timeout_ms: u32, deadline: u32) -> HalResult<()> {
+    wdt.start(timeout_ms)?;
+    FEEDER.set_deadline(deadline);
+    critical_section::with(|cs| WDT.borrow_ref_mut(cs).replace(wdt));
+    Ok(())
+}
+
+/// Emit `QS_WDT_RESET` on `kernel`'s trace if a watchdog caused the last
+/// reset. Call once at boot, after the trace hook is installed.
+pub fn report_reset(kernel: &QkKernel) -> ResetCause {
+    let cause = reset_cause();
+    if cause.is_watchdog() {
+        kernel.report_watchdog_reset(reset_reason());
+    }
+    cause
+}
+
+fn feed() {
+    critical_section::with(|cs| {
+        if let Some(wdt) = WDT.borrow_ref_mut(cs).as_mut() {
+            wdt.feed();
+        }
+    });
+}
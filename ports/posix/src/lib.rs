@@ -4,20 +4,57 @@
 //! gradually absorbs Rust equivalents, starting with helper utilities for
 //! tracing and runtime configuration.
 
+pub mod priority;
+pub mod reconnect;
+pub mod restart;
+
 use std::io;
 use std::net::ToSocketAddrs;
 use std::sync::Arc;
 
 use qf::time::TimeEvent;
-use qf::{QsConfig, TraceError, TraceHook, Tracer, TracerHandle};
+use qf::{QsConfig, TraceError, TraceFlush, TraceHook, Tracer, TracerHandle};
 use qk::{QkKernel, QkKernelBuilder, QkKernelError, QkTimeEventError, QkTimerWheel};
 use qs::predefined::{self, TargetInfo};
-use qs::{stdout_backend, GlbFilter, TcpBackend, UdpBackend, WriterBackend};
+use qs::{
+    make_format, stdout_backend, GlbFilter, TcpBackend, TimestampClock, TimestampSource, UdpBackend,
+    UdpBatching, WriterBackend, FMT_U32, FMT_U8,
+};
+
+pub use priority::{native_priority, set_thread_priority};
+pub use reconnect::{LinkMonitor, ReconnectConfig, Reconnected, ReconnectingTcpBackend};
+pub use restart::restart_process;
 
 enum BackendHandle {
     Stdout(TracerHandle<WriterBackend<std::io::Stdout>>),
     Tcp(TracerHandle<TcpBackend>),
     Udp(TracerHandle<UdpBackend>),
+    Resilient(Arc<Resilient>),
+}
+
+/// Reconnecting TCP tracer plus what is needed to announce reconnects.
+struct Resilient {
+    handle: TracerHandle<ReconnectingTcpBackend>,
+    monitor: LinkMonitor,
+    status_record: u8,
+}
+
+impl Resilient {
+    /// Emits the link-status record (and its dictionary entry, as the viewer
+    /// may have restarted) once per reconnect.
+    fn announce_reconnect(&self) {
+        let Some(report) = self.monitor.take_reconnected() else { return };
+        let dict = predefined::usr_dict_payload(self.status_record, "QS_LINK_UP");
+        let _ = self.handle.emit_with_flag(predefined::USR_DICT, &dict, false);
+
+        let mut payload = Vec::with_capacity(12);
+        payload.extend_from_slice(&[make_format(0, FMT_U8), 1]);
+        payload.push(make_format(0, FMT_U32));
+        payload.extend_from_slice(&report.replayed.to_le_bytes());
+        payload.push(make_format(0, FMT_U32));
+        payload.extend_from_slice(&report.dropped.to_le_bytes());
+        let _ = self.handle.emit_with_flag(self.status_record, &payload, true);
+    }
 }
 
 /// Convenience wrapper that owns a QS tracer and exposes a trace hook.
@@ -58,12 +95,78 @@ impl PosixPort {
         })
     }
 
+    /// Connects to a remote qspy listener over UDP, coalescing frames into
+    /// datagrams as configured by `batching` (see
+    /// [`UdpBackend::connect_batched`]).
+    pub fn connect_udp_batched<A: ToSocketAddrs>(addr: A, batching: UdpBatching) -> io::Result<Self> {
+        let backend = UdpBackend::connect_batched(addr, batching)?;
+        let handle = Tracer::new(QsConfig::default(), backend).into_handle();
+        Ok(Self {
+            backend: BackendHandle::Udp(handle),
+        })
+    }
+
+    /// Connects to a remote qspy listener over TCP, tolerating the listener
+    /// being absent or going away mid-session.
+    ///
+    /// Emits never fail on a dead link: frames are buffered up to
+    /// [`ReconnectConfig::backlog_frames`] and replayed when qspy comes back,
+    /// followed by a `QS_LINK_UP` user record carrying the replayed and
+    /// dropped frame counts. Only address resolution errors are returned.
+    pub fn connect_resilient<A: ToSocketAddrs>(addr: A, cfg: ReconnectConfig) -> io::Result<Self> {
+        let status_record = cfg.status_record;
+        let backend = ReconnectingTcpBackend::connect(addr, cfg)?;
+        let monitor = backend.monitor();
+        let handle = Tracer::new(QsConfig::default(), backend).into_handle();
+        Ok(Self {
+            backend: BackendHandle::Resilient(Arc::new(Resilient { handle, monitor, status_record })),
+        })
+    }
+
+    /// Link state of a [`connect_resilient`](Self::connect_resilient) port;
+    /// `None` for the other transports.
+    pub fn link_monitor(&self) -> Option<&LinkMonitor> {
+        match &self.backend {
+            BackendHandle::Resilient(r) => Some(&r.monitor),
+            _ => None,
+        }
+    }
+
     /// Returns the QS trace hook to be passed into the kernel.
     pub fn trace_hook(&self) -> TraceHook {
         match &self.backend {
             BackendHandle::Stdout(handle) => handle.hook(),
             BackendHandle::Tcp(handle) => handle.hook(),
             BackendHandle::Udp(handle) => handle.hook(),
+            BackendHandle::Resilient(r) => {
+                let inner = r.handle.hook();
+                let r = Arc::clone(r);
+                Arc::new(move |record_type, payload, with_timestamp| {
+                    let result = inner(record_type, payload, with_timestamp);
+                    r.announce_reconnect();
+                    result
+                })
+            }
+        }
+    }
+
+    /// Returns the flush to pass to `KernelBuilder::with_trace_flush`.
+    pub fn trace_flush(&self) -> TraceFlush {
+        match &self.backend {
+            BackendHandle::Stdout(handle) => handle.flusher(),
+            BackendHandle::Tcp(handle)    => handle.flusher(),
+            BackendHandle::Udp(handle)    => handle.flusher(),
+            BackendHandle::Resilient(r)   => r.handle.flusher(),
+        }
+    }
+
+    /// Pushes the frames the backend still buffers out to qspy.
+    pub fn flush(&self) -> Result<(), TraceError> {
+        match &self.backend {
+            BackendHandle::Stdout(handle) => handle.flush(),
+            BackendHandle::Tcp(handle)    => handle.flush(),
+            BackendHandle::Udp(handle)    => handle.flush(),
+            BackendHandle::Resilient(r)   => r.handle.flush(),
         }
     }
 
@@ -83,6 +186,11 @@ impl PosixPort {
             BackendHandle::Udp(handle) => {
                 handle.emit_with_flag(record_type, payload, with_timestamp)
             }
+            BackendHandle::Resilient(r) => {
+                let result = r.handle.emit_with_flag(record_type, payload, with_timestamp);
+                r.announce_reconnect();
+                result
+            }
         }
     }
 
@@ -115,12 +223,36 @@ impl PosixPort {
         self.emit_dictionary(predefined::SIG_DICT, &payload)
     }
 
+    /// Emits the digest of the dictionaries sent so far (`QS_DICT_HASH`), so
+    /// that qspy can check its cached dictionaries against this build.
+    pub fn emit_dict_hash(&self) -> Result<(), TraceError> {
+        match &self.backend {
+            BackendHandle::Stdout(handle) => handle.emit_dict_hash().map(drop),
+            BackendHandle::Tcp(handle)    => handle.emit_dict_hash().map(drop),
+            BackendHandle::Udp(handle)    => handle.emit_dict_hash().map(drop),
+            BackendHandle::Resilient(r)   => r.handle.emit_dict_hash().map(drop),
+        }
+    }
+
+    /// Replace the clock record timestamps are read from, for instance with
+    /// [`qf::time::KernelTicks`] when the kernel runs on virtual time.
+    pub fn set_timestamp_source(&self, source: impl TimestampSource + 'static) {
+        let clock = Some(TimestampClock::new(source));
+        match &self.backend {
+            BackendHandle::Stdout(handle) => handle.set_timestamp_source(clock),
+            BackendHandle::Tcp(handle)    => handle.set_timestamp_source(clock),
+            BackendHandle::Udp(handle)    => handle.set_timestamp_source(clock),
+            BackendHandle::Resilient(r)   => r.handle.set_timestamp_source(clock),
+        }
+    }
+
     /// Update the global trace filter.  Records whose bit is 0 are suppressed.
     pub fn set_filter(&self, filter: GlbFilter) {
         match &self.backend {
             BackendHandle::Stdout(handle) => handle.set_filter(filter),
             BackendHandle::Tcp(handle)    => handle.set_filter(filter),
             BackendHandle::Udp(handle)    => handle.set_filter(filter),
+            BackendHandle::Resilient(r)   => r.handle.set_filter(filter),
         }
     }
 }
@@ -257,6 +389,58 @@ mod tests {
         assert!(!runtime.has_pending_work());
     }
 
+    #[test]
+    fn resilient_port_buffers_and_replays_across_reconnect() {
+        use std::io::Read;
+        use std::net::TcpListener;
+        use std::time::Duration;
+
+        // Reserve a port, then close it so the first attempt fails.
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let cfg = ReconnectConfig {
+            backlog_frames: 3,
+            retry_interval: Duration::from_millis(10),
+            ..ReconnectConfig::default()
+        };
+        let port = PosixPort::connect_resilient(addr, cfg).expect("address resolves");
+        let monitor = port.link_monitor().unwrap().clone();
+        assert!(!monitor.is_connected());
+
+        for rec in [100, 101, 102, 103] {
+            port.emit_record(rec, &[rec], false).expect("emit never fails while down");
+        }
+        assert_eq!(monitor.backlog_len(), 3);
+
+        // The link thread reconnects and replays without any further emit.
+        let listener = TcpListener::bind(addr).unwrap();
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !monitor.is_connected() {
+            assert!(std::time::Instant::now() < deadline, "link thread never reconnected");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(monitor.backlog_len(), 0);
+        port.emit_record(104, &[104], false).unwrap();
+        port.flush().unwrap();
+
+        let (mut viewer, _) = listener.accept().unwrap();
+        viewer.set_read_timeout(Some(Duration::from_millis(500))).unwrap();
+        let mut bytes = Vec::new();
+        let mut buf = [0u8; 256];
+        while let Ok(n) = viewer.read(&mut buf) {
+            if n == 0 { break; }
+            bytes.extend_from_slice(&buf[..n]);
+        }
+        // seq | rec | payload… per frame: backlog 101..=103, then 104, then the
+        // dictionary entry and the status record (replayed=3, dropped=1).
+        let frames: Vec<&[u8]> = bytes.split(|&b| b == 0x7E).filter(|f| f.len() > 2).collect();
+        let records: Vec<u8> = frames.iter().map(|f| f[1]).collect();
+        assert_eq!(records, [101, 102, 103, 104, predefined::USR_DICT, reconnect::LINK_RECORD]);
+        let status = frames[5];
+        assert_eq!(&status[6..16], &[make_format(0, FMT_U8), 1,
+            make_format(0, FMT_U32), 3, 0, 0, 0, make_format(0, FMT_U32), 1, 0][..]);
+        assert!(monitor.take_reconnected().is_none());
+    }
+
     #[test]
     fn runtime_is_usable_through_generic_trait() {
         // Proves application code can be written generic over the port runtime.
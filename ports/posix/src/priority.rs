@@ -0,0 +1,82 @@
+//! Native priorities for thread-per-AO execution.
+//!
+//! The QP POSIX port runs every active object on a `SCHED_FIFO` thread whose
+//! priority rises with the object's QF priority. [`native_priority`] does the
+//! same for a kernel configured with `Execution::Threaded`:
+//!
+//! ```rust,ignore
+//! let config = KernelConfig::builder()
+//!     .execution(Execution::Threaded)
+//!     .thread_priority(qf_port_posix::native_priority)
+//!     .build();
+//! ```
+//!
+//! Real-time scheduling needs `CAP_SYS_NICE` (or root) on Linux. Without it the
+//! threads keep the default policy and the application still runs, with the
+//! OS sharing time between the objects.
+
+use std::io;
+
+/// Maps QF priority `priority` into the native range `min..=max`: priority 1
+/// gets `min`, and priorities beyond the range share `max`.
+pub fn map_priority(priority: u8, min: i32, max: i32) -> i32 {
+    min.saturating_add(i32::from(priority.saturating_sub(1))).min(max)
+}
+
+/// Moves the calling thread to `SCHED_FIFO` at the native priority of QF
+/// priority `priority`.
+#[cfg(target_os = "linux")]
+pub fn set_thread_priority(priority: u8) -> io::Result<()> {
+    use std::os::raw::{c_int, c_ulong};
+
+    const SCHED_FIFO: c_int = 1;
+
+    #[repr(C)]
+    struct SchedParam {
+        sched_priority: c_int,
+    }
+
+    extern "C" {
+        fn pthread_self() -> c_ulong;
+        fn pthread_setschedparam(thread: c_ulong, policy: c_int, param: *const SchedParam) -> c_int;
+        fn sched_get_priority_min(policy: c_int) -> c_int;
+        fn sched_get_priority_max(policy: c_int) -> c_int;
+    }
+
+    // SAFETY: plain libc calls; `param` outlives the call that reads it.
+    let rc = unsafe {
+        let (min, max) = (sched_get_priority_min(SCHED_FIFO), sched_get_priority_max(SCHED_FIFO));
+        let param = SchedParam { sched_priority: map_priority(priority, min, max) };
+        pthread_setschedparam(pthread_self(), SCHED_FIFO, &param)
+    };
+    match rc {
+        0 => Ok(()),
+        errno => Err(io::Error::from_raw_os_error(errno)),
+    }
+}
+
+/// Native thread priorities are only mapped on Linux.
+#[cfg(not(target_os = "linux"))]
+pub fn set_thread_priority(_priority: u8) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// [`ThreadPriority`](qf::ThreadPriority) hook: best-effort
+/// [`set_thread_priority`], leaving the thread as it is when the system
+/// refuses.
+pub fn native_priority(priority: u8) {
+    let _ = set_thread_priority(priority);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priorities_map_upwards_from_the_native_minimum() {
+        assert_eq!(map_priority(1, 1, 99), 1);
+        assert_eq!(map_priority(63, 1, 99), 63);
+        assert_eq!(map_priority(63, 1, 31), 31);
+        assert_eq!(map_priority(0, 10, 20), 10);
+    }
+}
@@ -0,0 +1,281 @@
+//! TCP trace transport that survives the viewer going away.
+//!
+//! [`ReconnectingTcpBackend`] never fails or blocks an emit because qspy is
+//! gone: encoded frames go into a bounded backlog (oldest dropped first) and a
+//! background thread owns the socket. While the link is down that thread
+//! attempts a reconnect at most once per [`ReconnectConfig::retry_interval`];
+//! when the viewer returns the backlog is replayed in order, so it sees the
+//! most recent history leading up to the reconnect, and
+//! [`PosixPort`](crate::PosixPort) then emits a link-status record reporting
+//! how much was replayed and how much was lost.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use qs::{TraceBackend, TraceError};
+
+/// Default user-record id of the link-status record.
+pub const LINK_RECORD: u8 = 124;
+
+/// Reconnect and buffering policy for [`ReconnectingTcpBackend`].
+#[derive(Debug, Clone)]
+pub struct ReconnectConfig {
+    /// Encoded frames queued for the link thread, which holds them while
+    /// disconnected; older frames are dropped.
+    pub backlog_frames: usize,
+    /// Minimum delay between two connection attempts.
+    pub retry_interval: Duration,
+    /// Upper bound on one connection attempt, one blocked write, or one
+    /// [`flush`](TraceBackend::flush).
+    pub io_timeout: Duration,
+    /// User-record id of the link-status record emitted after a reconnect.
+    pub status_record: u8,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            backlog_frames: 4096,
+            retry_interval: Duration::from_secs(1),
+            io_timeout: Duration::from_millis(200),
+            status_record: LINK_RECORD,
+        }
+    }
+}
+
+/// Outcome of a completed reconnect, reported once per reconnect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reconnected {
+    /// Backlog frames delivered after the link came back.
+    pub replayed: u32,
+    /// Frames lost to the backlog bound while the link was down.
+    pub dropped: u32,
+}
+
+struct Link {
+    connected: bool,
+    backlog: VecDeque<Vec<u8>>,
+    /// A frame taken off the backlog is being written by the link thread.
+    writing: bool,
+    dropped: u32,
+    pending_report: Option<Reconnected>,
+    shutdown: bool,
+}
+
+struct Shared {
+    link: Mutex<Link>,
+    /// Signalled when frames are queued, when the backlog drains, and on
+    /// shutdown.
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Link> {
+        self.link.lock().unwrap()
+    }
+}
+
+/// Shared view of a [`ReconnectingTcpBackend`]'s link state.
+#[derive(Clone)]
+pub struct LinkMonitor {
+    shared: Arc<Shared>,
+}
+
+impl LinkMonitor {
+    /// Returns `true` while frames are reaching the viewer.
+    pub fn is_connected(&self) -> bool {
+        self.shared.lock().connected
+    }
+
+    /// Frames currently waiting for the link thread.
+    pub fn backlog_len(&self) -> usize {
+        self.shared.lock().backlog.len()
+    }
+
+    /// Takes the report of the last reconnect, if one happened since the
+    /// previous call.
+    pub fn take_reconnected(&self) -> Option<Reconnected> {
+        self.shared.lock().pending_report.take()
+    }
+}
+
+/// TCP backend with bounded buffering and background reconnect.
+pub struct ReconnectingTcpBackend {
+    cfg: ReconnectConfig,
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl ReconnectingTcpBackend {
+    /// Resolves `addr`, makes a first connection attempt and starts the link
+    /// thread. A viewer that is not up yet is not an error: frames are
+    /// buffered until it appears.
+    pub fn connect<A: ToSocketAddrs>(addr: A, cfg: ReconnectConfig) -> io::Result<Self> {
+        let addrs: Vec<_> = addr.to_socket_addrs()?.collect();
+        if addrs.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no address to connect to"));
+        }
+        // Nothing was lost before the first connection, so this attempt is
+        // not reported.
+        let stream = open(&addrs, &cfg);
+        let shared = Arc::new(Shared {
+            link: Mutex::new(Link {
+                connected: stream.is_some(),
+                backlog: VecDeque::new(),
+                writing: false,
+                dropped: 0,
+                pending_report: None,
+                shutdown: false,
+            }),
+            changed: Condvar::new(),
+        });
+        let worker = {
+            let shared = Arc::clone(&shared);
+            let cfg = cfg.clone();
+            thread::Builder::new()
+                .name("qs-reconnect".into())
+                .spawn(move || LinkThread { addrs, cfg, stream, last_attempt: Instant::now() }.run(&shared))?
+        };
+        Ok(Self { cfg, shared, worker: Some(worker) })
+    }
+
+    /// Handle for observing the link from outside the tracer.
+    pub fn monitor(&self) -> LinkMonitor {
+        LinkMonitor { shared: Arc::clone(&self.shared) }
+    }
+}
+
+impl TraceBackend for ReconnectingTcpBackend {
+    fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
+        let mut link = self.shared.lock();
+        if self.cfg.backlog_frames == 0 {
+            link.dropped = link.dropped.saturating_add(1);
+            return Ok(());
+        }
+        if link.backlog.len() == self.cfg.backlog_frames {
+            link.backlog.pop_front();
+            link.dropped = link.dropped.saturating_add(1);
+        }
+        link.backlog.push_back(frame.to_vec());
+        self.shared.changed.notify_all();
+        Ok(())
+    }
+
+    /// Waits, up to [`ReconnectConfig::io_timeout`], for the link thread to
+    /// hand the queued frames to the socket. Returns at once while the link
+    /// is down.
+    fn flush(&self) -> Result<(), TraceError> {
+        let deadline = Instant::now() + self.cfg.io_timeout;
+        let mut link = self.shared.lock();
+        while link.connected && (link.writing || !link.backlog.is_empty()) {
+            let Some(left) = deadline.checked_duration_since(Instant::now()) else { break };
+            link = self.shared.changed.wait_timeout(link, left).unwrap().0;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ReconnectingTcpBackend {
+    fn drop(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.changed.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn open(addrs: &[SocketAddr], cfg: &ReconnectConfig) -> Option<TcpStream> {
+    let stream = addrs.iter().find_map(|a| TcpStream::connect_timeout(a, cfg.io_timeout).ok())?;
+    stream.set_nodelay(true).ok();
+    stream.set_write_timeout(Some(cfg.io_timeout)).ok();
+    Some(stream)
+}
+
+/// Owns the socket; the only place that connects or writes.
+struct LinkThread {
+    addrs: Vec<SocketAddr>,
+    cfg: ReconnectConfig,
+    stream: Option<TcpStream>,
+    last_attempt: Instant,
+}
+
+impl LinkThread {
+    fn run(mut self, shared: &Shared) {
+        let mut link = shared.lock();
+        loop {
+            if link.shutdown && (link.backlog.is_empty() || self.stream.is_none()) {
+                return;
+            }
+            if link.backlog.is_empty() {
+                link = shared.changed.wait(link).unwrap();
+                continue;
+            }
+            if self.stream.is_none() {
+                let next = self.last_attempt + self.cfg.retry_interval;
+                if let Some(wait) = next.checked_duration_since(Instant::now()).filter(|d| !d.is_zero()) {
+                    link = shared.changed.wait_timeout(link, wait).unwrap().0;
+                    continue;
+                }
+                drop(link);
+                link = self.reconnect(shared);
+                continue;
+            }
+            link = self.send_next(shared, link);
+        }
+    }
+
+    /// Connects and replays the backlog present at that moment, reporting the
+    /// reconnect once it is through. Frames queued meanwhile follow normally.
+    fn reconnect<'s>(&mut self, shared: &'s Shared) -> MutexGuard<'s, Link> {
+        self.last_attempt = Instant::now();
+        self.stream = open(&self.addrs, &self.cfg);
+        let mut link = shared.lock();
+        if self.stream.is_none() {
+            return link;
+        }
+        let mut replayed = 0u32;
+        for _ in 0..link.backlog.len() {
+            link = self.send_next(shared, link);
+            if self.stream.is_none() {
+                return link;
+            }
+            replayed += 1;
+        }
+        link.pending_report = Some(Reconnected { replayed, dropped: link.dropped });
+        link.dropped = 0;
+        link.connected = true;
+        link
+    }
+
+    /// Writes the oldest queued frame with the lock released. On failure the
+    /// frame goes back to the front unless the backlog filled up meanwhile,
+    /// in which case it is the oldest frame and is dropped.
+    fn send_next<'s>(&mut self, shared: &'s Shared, mut link: MutexGuard<'s, Link>) -> MutexGuard<'s, Link> {
+        let Some(frame) = link.backlog.pop_front() else { return link };
+        link.writing = true;
+        drop(link);
+
+        let sent = self.stream.as_mut().is_some_and(|s| s.write_all(&frame).is_ok());
+
+        let mut link = shared.lock();
+        link.writing = false;
+        if !sent {
+            // A failed write may have sent part of the frame; the decoder
+            // resynchronises on the next flag byte.
+            self.stream = None;
+            link.connected = false;
+            if link.backlog.len() < self.cfg.backlog_frames {
+                link.backlog.push_front(frame);
+            } else {
+                link.dropped = link.dropped.saturating_add(1);
+            }
+        }
+        shared.changed.notify_all();
+        link
+    }
+}
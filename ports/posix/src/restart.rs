@@ -0,0 +1,50 @@
+//! Host-side answer to `QS_RX_RESET`.
+//!
+//! A board resets and boots into the same firmware; the POSIX equivalent is
+//! to run the same executable again. [`restart_process`] replaces the
+//! process with a fresh copy of itself, with the same arguments and
+//! environment, so the application starts its kernel from scratch and sends
+//! `TARGET_INFO` and its dictionaries as it did the first time. Register it
+//! as the QS reset handler:
+//!
+//! ```rust,ignore
+//! qs::reset::set_reset_handler(qf_port_posix::restart_process);
+//! ```
+//!
+//! Sockets the Rust standard library opened are closed across the restart,
+//! so the new process reconnects to qspy. Trace frames still buffered in a
+//! backend are lost; flush the port first when they matter.
+
+use std::env;
+use std::io::{self, Write};
+use std::process::{self, Command};
+
+/// Runs this executable again in place of the current process. Exits with
+/// status 1 if the new copy cannot be started.
+pub fn restart_process() -> ! {
+    let _ = io::stdout().flush();
+    let err = respawn();
+    eprintln!("restart failed: {err}");
+    process::exit(1)
+}
+
+#[cfg(unix)]
+fn respawn() -> io::Error {
+    use std::os::unix::process::CommandExt;
+
+    match env::current_exe() {
+        Ok(exe) => Command::new(exe).args(env::args_os().skip(1)).exec(),
+        Err(err) => err,
+    }
+}
+
+/// Without `exec`, the new copy is started as a child and this one exits.
+#[cfg(not(unix))]
+fn respawn() -> io::Error {
+    let spawned = env::current_exe()
+        .and_then(|exe| Command::new(exe).args(env::args_os().skip(1)).spawn());
+    match spawned {
+        Ok(_) => process::exit(0),
+        Err(err) => err,
+    }
+}
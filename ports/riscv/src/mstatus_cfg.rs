@@ -39,3 +39,11 @@ pub fn qk_lock() -> u32 {
 #[cfg(not(feature = "hw"))]
 #[inline]
 pub fn qk_unlock(_prev: u32) {}
+
+/// The scheduler lock as the kernels' idle [`InterruptLock`]. `WFI` still
+/// wakes for an interrupt enabled in `mie` while `MSTATUS.MIE` is clear, so an
+/// idle callback can sleep with `cx.sleep(hal_rvsis::asm::wfi)`.
+///
+/// [`InterruptLock`]: qf::idle::InterruptLock
+pub const IDLE_LOCK: qf::idle::InterruptLock =
+    qf::idle::InterruptLock { lock: qk_lock, unlock: qk_unlock };
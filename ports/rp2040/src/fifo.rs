@@ -0,0 +1,199 @@
+//! Posting events to the other core through the SIO FIFOs.
+//!
+//! Each core writes into an 8-word FIFO that the other core reads. The
+//! reader's `SIO_IRQ_PROCn` interrupt stays raised while its FIFO holds data.
+//! An event does not fit in a FIFO word, so it travels through an
+//! [`InterCoreQueue`]: an `IsrQueue` registered with the receiving core's
+//! kernel, with the target's id pushed into the FIFO as a doorbell. The
+//! receiving core's handler,
+//! [`Rp2040QkRuntime::on_fifo_interrupt`](crate::Rp2040QkRuntime::on_fifo_interrupt),
+//! empties the FIFO and lets its kernel take the events.
+//!
+//! ```rust,ignore
+//! static TO_DISPLAY: InterCoreQueue<8> = InterCoreQueue::new(DISPLAY_ID);
+//!
+//! // core 1
+//! let builder = QkKernel::builder().register(display)?.isr_queue(&TO_DISPLAY);
+//!
+//! // core 0, in any handler or task
+//! TO_DISPLAY.post(DynEvent::empty_dyn(REDRAW_SIG)).ok();
+//! ```
+//!
+//! Without `rt` the FIFO pair lives in memory, indexed by
+//! [`sio::core_id`](crate::sio::core_id).
+
+use qf::active::ActiveObjectId;
+use qf::event::DynEvent;
+use qf::{IsrQueue, IsrSource};
+
+use crate::sio;
+
+/// Depth of each FIFO, in words.
+pub const FIFO_DEPTH: usize = 8;
+/// NVIC line of core 0's FIFO interrupt.
+pub const SIO_IRQ_PROC0: u32 = 15;
+/// NVIC line of core 1's FIFO interrupt.
+pub const SIO_IRQ_PROC1: u32 = 16;
+
+#[cfg(feature = "rt")]
+const FIFO_ST: usize = 0x050;
+#[cfg(feature = "rt")]
+const FIFO_WR: usize = 0x054;
+#[cfg(feature = "rt")]
+const FIFO_RD: usize = 0x058;
+/// The receive side holds data.
+#[cfg(feature = "rt")]
+const ST_VLD: u32 = 1 << 0;
+/// The transmit side has room.
+#[cfg(feature = "rt")]
+const ST_RDY: u32 = 1 << 1;
+
+#[cfg(not(feature = "rt"))]
+struct HostFifo {
+    words: [u32; FIFO_DEPTH],
+    head: usize,
+    len: usize,
+}
+
+/// FIFO read by core n.
+#[cfg(not(feature = "rt"))]
+static HOST_FIFOS: [spin::Mutex<HostFifo>; 2] = [const {
+    spin::Mutex::new(HostFifo {
+        words: [0; FIFO_DEPTH],
+        head: 0,
+        len: 0,
+    })
+}; 2];
+
+/// NVIC line of the calling core's FIFO interrupt.
+pub fn irq() -> u32 {
+    if sio::core_id() == 0 {
+        SIO_IRQ_PROC0
+    } else {
+        SIO_IRQ_PROC1
+    }
+}
+
+/// Writes `word` to the other core; `false` if its FIFO is full.
+pub fn try_push(word: u32) -> bool {
+    #[cfg(feature = "rt")]
+    {
+        if read(FIFO_ST) & ST_RDY == 0 {
+            return false;
+        }
+        write(FIFO_WR, word);
+        // Wake the other core if it sleeps in WFE.
+        // Safety: SEV only sets the event register.
+        unsafe { core::arch::asm!("sev", options(nomem, nostack, preserves_flags)) }
+        true
+    }
+    #[cfg(not(feature = "rt"))]
+    {
+        let mut fifo = HOST_FIFOS[usize::from(1 - sio::core_id())].lock();
+        if fifo.len == FIFO_DEPTH {
+            return false;
+        }
+        let tail = (fifo.head + fifo.len) % FIFO_DEPTH;
+        fifo.words[tail] = word;
+        fifo.len += 1;
+        true
+    }
+}
+
+/// Reads the next word the other core wrote.
+pub fn pop() -> Option<u32> {
+    #[cfg(feature = "rt")]
+    {
+        (read(FIFO_ST) & ST_VLD != 0).then(|| read(FIFO_RD))
+    }
+    #[cfg(not(feature = "rt"))]
+    {
+        let mut fifo = HOST_FIFOS[usize::from(sio::core_id())].lock();
+        (fifo.len != 0).then(|| {
+            let word = fifo.words[fifo.head];
+            fifo.head = (fifo.head + 1) % FIFO_DEPTH;
+            fifo.len -= 1;
+            word
+        })
+    }
+}
+
+/// Empties this core's FIFO and clears its sticky error flags, which
+/// lowers the FIFO interrupt. Returns the number of words read.
+pub fn drain() -> usize {
+    let mut words = 0;
+    while pop().is_some() {
+        words += 1;
+    }
+    #[cfg(feature = "rt")]
+    write(FIFO_ST, 0);
+    words
+}
+
+/// Enables the calling core's FIFO interrupt.
+pub fn enable_interrupt() {
+    #[cfg(feature = "rt")]
+    {
+        use hal::interrupt::InterruptController;
+        // Safety: each core enables only its own FIFO line.
+        let _ = unsafe { hal_cmsis::nvic::NvicController::new() }.enable_interrupt(irq());
+    }
+}
+
+#[cfg(feature = "rt")]
+fn read(offset: usize) -> u32 {
+    // Safety: the FIFO registers are core-local SIO words.
+    unsafe { core::ptr::read_volatile((sio::SIO_BASE + offset) as *const u32) }
+}
+
+#[cfg(feature = "rt")]
+fn write(offset: usize, value: u32) {
+    // Safety: as for `read`.
+    unsafe { core::ptr::write_volatile((sio::SIO_BASE + offset) as *mut u32, value) }
+}
+
+/// Up to `N` events for an active object on the other core.
+///
+/// One core posts, from one handler or task at a time; the other core's
+/// kernel drains it like an `IsrQueue`. `N` must be a power of two.
+pub struct InterCoreQueue<const N: usize> {
+    queue: IsrQueue<N>,
+}
+
+impl<const N: usize> InterCoreQueue<N> {
+    /// An empty queue towards `target`.
+    pub const fn new(target: ActiveObjectId) -> Self {
+        Self {
+            queue: IsrQueue::new(target),
+        }
+    }
+
+    /// Queues `event` and rings the other core. Hands the event back if the
+    /// queue is full.
+    pub fn post(&self, event: DynEvent) -> Result<(), DynEvent> {
+        self.queue.post_from_isr(event)?;
+        // A full FIFO already holds doorbells the other core has not read,
+        // and its handler takes every queued event at once.
+        let _ = try_push(u32::from(self.queue.target().0));
+        Ok(())
+    }
+
+    /// Events rejected so far.
+    pub fn dropped(&self) -> u32 {
+        self.queue.dropped()
+    }
+}
+
+impl<const N: usize> IsrSource for InterCoreQueue<N> {
+    fn target(&self) -> ActiveObjectId {
+        self.queue.target()
+    }
+
+    fn is_pending(&self) -> bool {
+        self.queue.is_pending()
+    }
+
+    fn pop(&self) -> Option<DynEvent> {
+        self.queue.pop()
+    }
+}
@@ -0,0 +1,187 @@
+#![no_std]
+
+//! RP2040 port for the Quantum Platform kernels.
+//!
+//! Each Cortex-M0+ core of the RP2040 runs its own [`qk`] kernel. The port
+//! supplies what the two cores share:
+//!
+//! - [`sio`]: hardware spinlocks and, with `rt`, the `critical-section`
+//!   implementation that excludes both cores.
+//! - [`timer`]: the TIMER alarm that drives the QP tick.
+//! - [`fifo`]: the inter-core FIFOs, through which an interrupt or task on
+//!   one core posts events to active objects on the other.
+//!
+//! Without the `rt` feature the registers are emulated in memory so the port
+//! logic can be tested on the host.
+
+extern crate alloc;
+
+pub mod fifo;
+pub mod runtime;
+pub mod sio;
+pub mod timer;
+
+pub use fifo::InterCoreQueue;
+pub use runtime::Rp2040QkRuntime;
+pub use timer::{on_timer_alarm, SystemTimer};
+
+/// Aggregates the platform-specific subsystems managed by the RP2040 port.
+#[derive(Debug)]
+pub struct Rp2040Port {
+    timer: SystemTimer,
+}
+
+impl Rp2040Port {
+    /// Creates a new port instance.
+    pub const fn new() -> Self {
+        Self {
+            timer: SystemTimer::new(),
+        }
+    }
+
+    /// Returns a reference to the system timer abstraction.
+    pub const fn timer(&self) -> &SystemTimer {
+        &self.timer
+    }
+
+    /// Returns a mutable reference to the system timer.
+    pub fn timer_mut(&mut self) -> &mut SystemTimer {
+        &mut self.timer
+    }
+
+    /// Starts the TIMER alarm that drives `TimeEvent`s.
+    pub fn init_system_timer(&mut self, tick_hz: u32) {
+        self.timer.configure_periodic(tick_hz);
+    }
+
+    /// Enables the calling core's FIFO interrupt.
+    pub fn init_fifo_interrupt(&mut self) {
+        fifo::enable_interrupt();
+    }
+}
+
+impl Default for Rp2040Port {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Optional runtime configuration shared with board support glue.
+#[derive(Debug, Clone, Copy)]
+pub struct PortConfig {
+    pub enable_trace: bool,
+    /// Tick rate; 0 on the core that does not own the TIMER.
+    pub tick_hz: u32,
+}
+
+impl Default for PortConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PortConfig {
+    pub const fn new() -> Self {
+        Self {
+            enable_trace: true,
+            tick_hz: 1000,
+        }
+    }
+}
+
+#[cfg(test)]
+extern crate std;
+
+#[cfg(all(test, not(feature = "rt")))]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use qf::active::{new_active_object, ActiveContext, ActiveObjectId, SignalHandler};
+    use qf::event::{DynEvent, Signal};
+    use qf::time::{TimeEvent, TimeEventConfig};
+    use qk::QkKernel;
+
+    struct Recorder {
+        signal: Signal,
+        counter: Arc<AtomicUsize>,
+    }
+
+    impl SignalHandler for Recorder {
+        fn handle_signal(&mut self, signal: Signal, _ctx: &mut ActiveContext) {
+            if signal == self.signal {
+                self.counter.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn runtime(id: ActiveObjectId, signal: Signal, tick_hz: u32) -> (Rp2040QkRuntime, Arc<AtomicUsize>) {
+        runtime_with(id, signal, tick_hz, |builder| builder)
+    }
+
+    fn runtime_with(
+        id: ActiveObjectId,
+        signal: Signal,
+        tick_hz: u32,
+        extend: impl FnOnce(qk::QkKernelBuilder) -> qk::QkKernelBuilder,
+    ) -> (Rp2040QkRuntime, Arc<AtomicUsize>) {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let ao = new_active_object(
+            id,
+            3,
+            Recorder {
+                signal,
+                counter: Arc::clone(&counter),
+            },
+        );
+        let builder = extend(QkKernel::builder().register(ao).expect("register succeeds"));
+        let runtime = Rp2040QkRuntime::with_builder(
+            builder,
+            Rp2040Port::new(),
+            PortConfig {
+                enable_trace: false,
+                tick_hz,
+            },
+        )
+        .expect("runtime builds");
+        (runtime, counter)
+    }
+
+    #[test]
+    fn alarms_drive_time_events() {
+        let id = ActiveObjectId::new(4);
+        let (mut runtime, counter) = runtime(id, Signal(42), 1000);
+        assert_eq!(runtime.port().timer().tick_hz(), 1000);
+
+        let event = Arc::new(TimeEvent::new(id, TimeEventConfig::new(Signal(42))));
+        runtime.register_time_event(Arc::clone(&event));
+        event.arm_once(1);
+
+        on_timer_alarm();
+        assert_eq!(runtime.service_ticks().expect("ticks are serviced"), 1);
+        runtime.run_until_idle();
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn core0_posts_reach_an_object_on_core1() {
+        static TO_CORE1: InterCoreQueue<4> = InterCoreQueue::new(ActiveObjectId::new(9));
+
+        sio::set_host_core(1);
+        let (runtime, counter) =
+            runtime_with(ActiveObjectId::new(9), Signal(7), 0, |builder| builder.isr_queue(&TO_CORE1));
+
+        sio::set_host_core(0);
+        TO_CORE1
+            .post(DynEvent::empty_dyn(Signal(7)))
+            .expect("queue has room");
+        assert_eq!(fifo::pop(), None, "core 0's own FIFO stays empty");
+
+        sio::set_host_core(1);
+        runtime.on_fifo_interrupt();
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        assert_eq!(fifo::pop(), None);
+        sio::set_host_core(0);
+    }
+}
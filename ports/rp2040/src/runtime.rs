@@ -0,0 +1,107 @@
+use alloc::sync::Arc;
+
+use qf::time::TimeEvent;
+use qk::{QkKernel, QkKernelBuilder, QkKernelError, QkTimeEventError, QkTimerWheel};
+
+use crate::{fifo, Rp2040Port, PortConfig};
+
+/// QK runtime harness for one RP2040 core.
+///
+/// Each core runs its own kernel and builds its own runtime. Only one of
+/// them should own the tick: give the other a `tick_hz` of 0 so it leaves
+/// the TIMER alone.
+pub struct Rp2040QkRuntime {
+    kernel: Arc<QkKernel>,
+    timers: QkTimerWheel,
+    port: Rp2040Port,
+    config: PortConfig,
+}
+
+impl Rp2040QkRuntime {
+    /// Wraps an already constructed kernel and initialises the port on the
+    /// calling core.
+    pub fn new(kernel: Arc<QkKernel>, mut port: Rp2040Port, config: PortConfig) -> Self {
+        if config.tick_hz != 0 {
+            port.init_system_timer(config.tick_hz);
+        }
+        port.init_fifo_interrupt();
+
+        let timers = QkTimerWheel::new(Arc::clone(&kernel));
+
+        Self {
+            kernel,
+            timers,
+            port,
+            config,
+        }
+    }
+
+    /// Builds a kernel from the provided builder and starts it before
+    /// initialising the port.
+    pub fn with_builder(
+        builder: QkKernelBuilder,
+        port: Rp2040Port,
+        config: PortConfig,
+    ) -> Result<Self, QkKernelError> {
+        let kernel = Arc::new(builder.build()?);
+        kernel.start();
+        Ok(Self::new(kernel, port, config))
+    }
+
+    /// Returns a clone of the kernel handle stored in this runtime.
+    pub fn kernel(&self) -> Arc<QkKernel> {
+        Arc::clone(&self.kernel)
+    }
+
+    /// Gives access to the embedded port instance.
+    pub fn port(&self) -> &Rp2040Port {
+        &self.port
+    }
+
+    /// Retrieves the configuration used to start the runtime.
+    pub fn config(&self) -> PortConfig {
+        self.config
+    }
+
+    /// Registers a time event with the timer wheel.
+    pub fn register_time_event(&mut self, event: Arc<TimeEvent>) {
+        self.timers.register(event);
+    }
+
+    /// Processes a single tick from the underlying hardware timer.
+    pub fn tick(&self) -> Result<(), QkTimeEventError> {
+        self.timers.tick()
+    }
+
+    /// Runs the timer wheel once for every tick the TIMER alarm counted
+    /// since the last call, and returns how many that was.
+    pub fn service_ticks(&self) -> Result<u32, QkTimeEventError> {
+        let ticks = self.port.timer().take_pending_ticks();
+        for _ in 0..ticks {
+            self.timers.tick()?;
+        }
+        Ok(ticks)
+    }
+
+    /// FIFO interrupt body: empties this core's FIFO and runs the events the
+    /// other core queued for objects that preempt the interrupted one. Bind
+    /// it to this core's `SIO_IRQ_PROCn`.
+    ///
+    /// The kernel's own end-of-interrupt path is not used here because the
+    /// interrupt nesting count is shared by both cores.
+    pub fn on_fifo_interrupt(&self) {
+        if fifo::drain() != 0 {
+            self.kernel.activate_pending();
+        }
+    }
+
+    /// Runs the kernel until all ready work completes.
+    pub fn run_until_idle(&self) {
+        self.kernel.run_until_idle();
+    }
+
+    /// Indicates whether there is outstanding work for the kernel.
+    pub fn has_pending_work(&self) -> bool {
+        self.kernel.has_pending_work()
+    }
+}
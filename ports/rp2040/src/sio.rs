@@ -0,0 +1,160 @@
+//! RP2040 SIO: core id, hardware spinlocks and the critical section.
+//!
+//! Both Cortex-M0+ cores see the same 32 SIO spinlocks. Reading `SPINLOCKn`
+//! claims lock n and returns non-zero if it was free; writing it releases
+//! the lock. The M0+ has no atomic read-modify-write, so these locks are the
+//! only way to exclude the other core.
+//!
+//! With the `rt` feature this module provides the `critical-section`
+//! implementation. It masks interrupts on the calling core with PRIMASK and
+//! then takes [`CRITICAL_SECTION_SPINLOCK`], which keeps the other core out
+//! too. Nested sections on the same core just pass through.
+//!
+//! Without `rt` the spinlocks are atomics and the core id is a variable, so
+//! the two-core paths can be tested on the host.
+
+#[cfg(not(feature = "rt"))]
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// SIO block base address.
+pub const SIO_BASE: usize = 0xD000_0000;
+/// Spinlock held by the critical section.
+pub const CRITICAL_SECTION_SPINLOCK: usize = 31;
+
+#[cfg(feature = "rt")]
+const CPUID: usize = 0x000;
+#[cfg(feature = "rt")]
+const SPINLOCK0: usize = 0x100;
+
+#[cfg(not(feature = "rt"))]
+static HOST_CORE: AtomicU8 = AtomicU8::new(0);
+#[cfg(not(feature = "rt"))]
+static HOST_SPINLOCKS: [AtomicBool; 32] = [const { AtomicBool::new(false) }; 32];
+
+/// Index of the executing core, 0 or 1.
+pub fn core_id() -> u8 {
+    #[cfg(feature = "rt")]
+    {
+        // Safety: CPUID is a read-only register of the core-local SIO.
+        unsafe { core::ptr::read_volatile((SIO_BASE + CPUID) as *const u32) as u8 }
+    }
+    #[cfg(not(feature = "rt"))]
+    {
+        HOST_CORE.load(Ordering::Relaxed)
+    }
+}
+
+/// Makes the host pretend to run on `core`.
+#[cfg(all(test, not(feature = "rt")))]
+pub(crate) fn set_host_core(core: u8) {
+    HOST_CORE.store(core & 1, Ordering::Relaxed);
+}
+
+/// Tries to claim spinlock `n` (0–31) without waiting.
+pub fn try_claim(n: usize) -> Option<SpinlockGuard> {
+    assert!(n < 32, "RP2040 has 32 spinlocks");
+    #[cfg(feature = "rt")]
+    // Safety: reading a spinlock register claims it if free; nothing else.
+    let claimed = unsafe { core::ptr::read_volatile((SIO_BASE + SPINLOCK0 + 4 * n) as *const u32) } != 0;
+    #[cfg(not(feature = "rt"))]
+    let claimed = !HOST_SPINLOCKS[n].swap(true, Ordering::Acquire);
+    claimed.then_some(SpinlockGuard { n })
+}
+
+/// Claims spinlock `n` (0–31), spinning until the other core lets go.
+pub fn claim(n: usize) -> SpinlockGuard {
+    loop {
+        if let Some(guard) = try_claim(n) {
+            return guard;
+        }
+        core::hint::spin_loop();
+    }
+}
+
+/// A claimed spinlock, released on drop.
+#[must_use = "the spinlock is released when the guard drops"]
+#[derive(Debug)]
+pub struct SpinlockGuard {
+    n: usize,
+}
+
+impl Drop for SpinlockGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "rt")]
+        // Safety: any write releases the lock this guard holds.
+        unsafe {
+            core::ptr::write_volatile((SIO_BASE + SPINLOCK0 + 4 * self.n) as *mut u32, 1)
+        }
+        #[cfg(not(feature = "rt"))]
+        HOST_SPINLOCKS[self.n].store(false, Ordering::Release);
+    }
+}
+
+#[cfg(feature = "rt")]
+mod critical {
+    use core::sync::atomic::{AtomicU8, Ordering};
+
+    use hal_cmsis::primask;
+
+    use super::{core_id, try_claim, CRITICAL_SECTION_SPINLOCK};
+
+    /// Core holding the section, or [`NO_OWNER`].
+    static OWNER: AtomicU8 = AtomicU8::new(NO_OWNER);
+    const NO_OWNER: u8 = 0xFF;
+
+    /// Restore state of a section nested in one this core already holds.
+    const NESTED: u8 = 2;
+
+    struct SioCriticalSection;
+    critical_section::set_impl!(SioCriticalSection);
+
+    unsafe impl critical_section::Impl for SioCriticalSection {
+        unsafe fn acquire() -> u8 {
+            let core = core_id();
+            // Only this core can have stored its own id, so a plain load is
+            // enough to detect nesting.
+            if OWNER.load(Ordering::Relaxed) == core {
+                return NESTED;
+            }
+            let enabled = !primask::is_masked();
+            loop {
+                primask::disable();
+                if let Some(guard) = try_claim(CRITICAL_SECTION_SPINLOCK) {
+                    core::mem::forget(guard);
+                    break;
+                }
+                // Let this core's interrupts in while the other core holds it.
+                if enabled {
+                    unsafe { primask::enable() }
+                }
+            }
+            OWNER.store(core, Ordering::Relaxed);
+            u8::from(enabled)
+        }
+
+        unsafe fn release(token: u8) {
+            if token == NESTED {
+                return;
+            }
+            OWNER.store(NO_OWNER, Ordering::Relaxed);
+            drop(super::SpinlockGuard { n: CRITICAL_SECTION_SPINLOCK });
+            if token == 1 {
+                unsafe { primask::enable() }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "rt")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_spinlock_is_held_until_the_guard_drops() {
+        let guard = claim(3);
+        assert!(try_claim(3).is_none());
+        assert!(try_claim(4).is_some());
+        drop(guard);
+        assert!(try_claim(3).is_some());
+    }
+}
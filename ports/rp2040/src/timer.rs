@@ -0,0 +1,131 @@
+//! TIMER alarm-driven QP tick.
+//!
+//! The RP2040 TIMER counts microseconds in 64 bits and compares the low word
+//! against four one-shot alarms. [`SystemTimer::configure_periodic`] arms
+//! alarm 0 one period ahead and enables `TIMER_IRQ_0`. The application binds
+//! that interrupt to a handler calling [`on_timer_alarm`], which re-arms the
+//! alarm from its previous deadline, so the tick does not drift with
+//! interrupt latency, and counts the tick. The thread-mode loop then runs the
+//! timer wheel once per counted tick with
+//! [`Rp2040QkRuntime::service_ticks`](crate::Rp2040QkRuntime::service_ticks).
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// TIMER peripheral base address.
+pub const TIMER_BASE: usize = 0x4005_4000;
+/// NVIC line of alarm 0.
+pub const TIMER_IRQ_0: u32 = 0;
+
+#[cfg(feature = "rt")]
+const ALARM0: usize = 0x10;
+#[cfg(feature = "rt")]
+const TIMERAWL: usize = 0x28;
+#[cfg(feature = "rt")]
+const INTR: usize = 0x34;
+#[cfg(feature = "rt")]
+const INTE: usize = 0x38;
+#[cfg(feature = "rt")]
+const ALARM0_BIT: u32 = 1 << 0;
+
+/// Ticks signalled by the alarm and not yet serviced.
+static PENDING_TICKS: AtomicU32 = AtomicU32::new(0);
+/// Tick period in microseconds; 0 until configured.
+static PERIOD_US: AtomicU32 = AtomicU32::new(0);
+/// Deadline alarm 0 is armed for.
+#[cfg(feature = "rt")]
+static DEADLINE: AtomicU32 = AtomicU32::new(0);
+
+/// Alarm 0 driving QK ticks.
+#[derive(Debug)]
+pub struct SystemTimer {
+    tick_hz: AtomicU32,
+}
+
+impl Default for SystemTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemTimer {
+    /// Creates a timer without an active periodic configuration.
+    pub const fn new() -> Self {
+        Self {
+            tick_hz: AtomicU32::new(0),
+        }
+    }
+
+    /// Starts the periodic tick at `tick_hz` (at least 1 Hz) and enables the
+    /// alarm interrupt.
+    pub fn configure_periodic(&self, tick_hz: u32) {
+        self.tick_hz.store(tick_hz, Ordering::Release);
+        let period_us = 1_000_000 / tick_hz.max(1);
+        PERIOD_US.store(period_us, Ordering::Release);
+        #[cfg(feature = "rt")]
+        {
+            use hal::interrupt::InterruptController;
+            let deadline = read(TIMERAWL).wrapping_add(period_us);
+            DEADLINE.store(deadline, Ordering::Relaxed);
+            write(INTR, ALARM0_BIT);
+            write(INTE, read(INTE) | ALARM0_BIT);
+            write(ALARM0, deadline);
+            // Safety: the port owns TIMER_IRQ_0 on this core.
+            let _ = unsafe { hal_cmsis::nvic::NvicController::new() }.enable_interrupt(TIMER_IRQ_0);
+        }
+    }
+
+    /// Returns the currently configured tick frequency in hertz.
+    pub fn tick_hz(&self) -> u32 {
+        self.tick_hz.load(Ordering::Acquire)
+    }
+
+    /// Ticks counted by [`on_timer_alarm`] since the last call, which resets
+    /// the count.
+    pub fn take_pending_ticks(&self) -> u32 {
+        // The M0+ has no atomic swap; keep the alarm out while taking.
+        #[cfg(feature = "rt")]
+        {
+            critical_section::with(|_| {
+                let ticks = PENDING_TICKS.load(Ordering::Relaxed);
+                PENDING_TICKS.store(0, Ordering::Relaxed);
+                ticks
+            })
+        }
+        #[cfg(not(feature = "rt"))]
+        {
+            PENDING_TICKS.swap(0, Ordering::AcqRel)
+        }
+    }
+}
+
+/// Alarm 0 interrupt body: acknowledges the alarm, arms the next one and
+/// counts one tick. Call it from the `TIMER_IRQ_0` handler.
+pub fn on_timer_alarm() {
+    #[cfg(feature = "rt")]
+    {
+        let period_us = PERIOD_US.load(Ordering::Acquire);
+        write(INTR, ALARM0_BIT);
+        let mut deadline = DEADLINE.load(Ordering::Relaxed).wrapping_add(period_us);
+        let now = read(TIMERAWL);
+        // Late by more than a period: restart from now instead of firing a burst.
+        if deadline.wrapping_sub(now) as i32 <= 0 {
+            deadline = now.wrapping_add(period_us);
+        }
+        DEADLINE.store(deadline, Ordering::Relaxed);
+        write(ALARM0, deadline);
+    }
+    // Only this handler adds, and it cannot preempt itself.
+    PENDING_TICKS.store(PENDING_TICKS.load(Ordering::Relaxed).wrapping_add(1), Ordering::Relaxed);
+}
+
+#[cfg(feature = "rt")]
+fn read(offset: usize) -> u32 {
+    // Safety: TIMER registers are plain MMIO words.
+    unsafe { core::ptr::read_volatile((TIMER_BASE + offset) as *const u32) }
+}
+
+#[cfg(feature = "rt")]
+fn write(offset: usize, value: u32) {
+    // Safety: as for `read`; the port owns alarm 0.
+    unsafe { core::ptr::write_volatile((TIMER_BASE + offset) as *mut u32, value) }
+}
@@ -0,0 +1,260 @@
+//! Host simulation port driven by a virtual clock.
+//!
+//! A [`SimRuntime`] owns a [`QkKernel`] and its timer wheel, but no tick
+//! source: time only moves when the test says so. [`advance_ticks`] runs the
+//! timer wheel once per tick and lets the kernel go idle after each one, so a
+//! test steps through timeouts in order and never sleeps. Trace records are
+//! stamped with the simulation's tick count rather than the host's clock:
+//! [`SimRuntime::with_tracer`] wires a tracer to it, and [`SimRuntime::clock`]
+//! hands the same count to any other [`QsConfig`].
+//!
+//! ```rust,ignore
+//! use qf_port_sim as sim;
+//!
+//! let (runtime, tracer) = sim::SimRuntime::with_tracer(builder, backend)?;
+//! runtime.register_time_event(blink_timer.clone());
+//! blink_timer.arm_periodic(10u64, 10u64);
+//!
+//! sim::advance_ticks(25)?; // two BLINK timeouts, stamped 10 and 20
+//! assert_eq!(sim::now(), 25);
+//! ```
+//!
+//! The free functions act on the simulation most recently created on the
+//! calling thread, so tests running in parallel each keep their own.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+use qf::time::TimeEvent;
+use qk::{QkKernel, QkKernelBuilder, QkKernelError, QkTimeEventError, QkTimerWheel};
+use qs::{ManualClock, QsConfig, TraceBackend, Tracer, TracerHandle};
+
+thread_local! {
+    static CURRENT: RefCell<Weak<Inner>> = const { RefCell::new(Weak::new()) };
+}
+
+struct Inner {
+    kernel: Arc<QkKernel>,
+    timers: Mutex<QkTimerWheel>,
+    ticks: AtomicU64,
+    clock: ManualClock,
+}
+
+impl Inner {
+    fn advance_ticks(&self, n: u64) -> Result<(), QkTimeEventError> {
+        let timers = self.timers.lock().unwrap();
+        for _ in 0..n {
+            self.ticks.fetch_add(1, Ordering::Relaxed);
+            self.clock.advance(1);
+            timers.tick()?;
+            self.kernel.run_until_idle();
+        }
+        Ok(())
+    }
+}
+
+/// A QK kernel run on virtual time.
+#[derive(Clone)]
+pub struct SimRuntime {
+    inner: Arc<Inner>,
+}
+
+impl SimRuntime {
+    /// Wraps a started kernel at tick 0 and makes it the calling thread's
+    /// current simulation.
+    pub fn new(kernel: Arc<QkKernel>) -> Self {
+        Self::with_clock(kernel, ManualClock::new())
+    }
+
+    fn with_clock(kernel: Arc<QkKernel>, clock: ManualClock) -> Self {
+        let timers = Mutex::new(QkTimerWheel::new(Arc::clone(&kernel)));
+        let inner = Arc::new(Inner {
+            kernel,
+            timers,
+            ticks: AtomicU64::new(0),
+            clock,
+        });
+        CURRENT.with(|current| *current.borrow_mut() = Arc::downgrade(&inner));
+        Self { inner }
+    }
+
+    /// Builds and starts a kernel from `builder`.
+    pub fn with_builder(builder: QkKernelBuilder) -> Result<Self, QkKernelError> {
+        let kernel = Arc::new(builder.build()?);
+        kernel.start();
+        Ok(Self::new(kernel))
+    }
+
+    /// Builds and starts a kernel traced into `backend`, with records
+    /// stamped in virtual ticks. The initial transitions are stamped 0.
+    pub fn with_tracer<B: TraceBackend + 'static>(
+        builder: QkKernelBuilder,
+        backend: B,
+    ) -> Result<(Self, TracerHandle<B>), QkKernelError> {
+        let clock = ManualClock::new();
+        let tracer = Tracer::new(
+            QsConfig::default().with_timestamp_source(clock.clone()),
+            backend,
+        )
+        .into_handle();
+        let kernel = Arc::new(builder.with_trace_hook(tracer.hook()).build()?);
+        kernel.start();
+        Ok((Self::with_clock(kernel, clock), tracer))
+    }
+
+    /// Makes this the calling thread's current simulation again.
+    pub fn make_current(&self) {
+        CURRENT.with(|current| *current.borrow_mut() = Arc::downgrade(&self.inner));
+    }
+
+    /// Returns a clone of the kernel handle.
+    pub fn kernel(&self) -> Arc<QkKernel> {
+        Arc::clone(&self.inner.kernel)
+    }
+
+    /// The virtual tick count as a QS timestamp source (wrapping at 32 bits).
+    pub fn clock(&self) -> ManualClock {
+        self.inner.clock.clone()
+    }
+
+    /// Registers a time event with the timer wheel.
+    pub fn register_time_event(&self, event: Arc<TimeEvent>) {
+        self.inner.timers.lock().unwrap().register(event);
+    }
+
+    /// Ticks elapsed since the simulation started.
+    pub fn now(&self) -> u64 {
+        self.inner.ticks.load(Ordering::Relaxed)
+    }
+
+    /// Moves virtual time forward by `n` ticks, running the kernel until idle
+    /// after each one.
+    pub fn advance_ticks(&self, n: u64) -> Result<(), QkTimeEventError> {
+        self.inner.advance_ticks(n)
+    }
+
+    /// Dispatches ready work without moving time.
+    pub fn run_until_idle(&self) {
+        self.inner.kernel.run_until_idle();
+    }
+
+    /// Indicates whether there is outstanding work for the kernel.
+    pub fn has_pending_work(&self) -> bool {
+        self.inner.kernel.has_pending_work()
+    }
+}
+
+/// Portable driver API — lets application code be generic over the runtime.
+impl qf::port::Runtime for SimRuntime {
+    type TickError = QkTimeEventError;
+
+    fn tick(&self) -> Result<(), Self::TickError> {
+        self.advance_ticks(1)
+    }
+
+    fn run_until_idle(&self) {
+        SimRuntime::run_until_idle(self);
+    }
+
+    fn has_pending_work(&self) -> bool {
+        SimRuntime::has_pending_work(self)
+    }
+}
+
+fn current() -> SimRuntime {
+    let inner = CURRENT
+        .with(|current| current.borrow().upgrade())
+        .expect("no simulation is running on this thread");
+    SimRuntime { inner }
+}
+
+/// Moves the current simulation forward by `n` ticks (see
+/// [`SimRuntime::advance_ticks`]).
+///
+/// # Panics
+///
+/// If no [`SimRuntime`] is alive on the calling thread.
+pub fn advance_ticks(n: u64) -> Result<(), QkTimeEventError> {
+    current().advance_ticks(n)
+}
+
+/// Ticks elapsed in the current simulation.
+///
+/// # Panics
+///
+/// If no [`SimRuntime`] is alive on the calling thread.
+pub fn now() -> u64 {
+    current().now()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use qf::active::{new_active_object, ActiveContext, ActiveObjectId, SignalHandler};
+    use qf::event::Signal;
+    use qf::time::TimeEventConfig;
+    use qs::TraceError;
+
+    const TIMEOUT: Signal = Signal(10);
+
+    struct Recorder {
+        log: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl SignalHandler for Recorder {
+        fn handle_signal(&mut self, signal: Signal, _ctx: &mut ActiveContext) {
+            if signal == TIMEOUT {
+                self.log.lock().unwrap().push(now());
+            }
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct Records(Arc<Mutex<Vec<Vec<u8>>>>);
+
+    impl TraceBackend for Records {
+        fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
+            self.0.lock().unwrap().push(frame.to_vec());
+            Ok(())
+        }
+    }
+
+    fn builder(id: ActiveObjectId, log: &Arc<Mutex<Vec<u64>>>) -> QkKernelBuilder {
+        let ao = new_active_object(id, 1, Recorder { log: Arc::clone(log) });
+        QkKernel::builder().register(ao).unwrap()
+    }
+
+    #[test]
+    fn timeouts_fire_on_the_virtual_ticks_they_were_armed_for() {
+        let id = ActiveObjectId::new(1);
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let runtime = SimRuntime::with_builder(builder(id, &log)).unwrap();
+
+        let timer = Arc::new(TimeEvent::new(id, TimeEventConfig::new(TIMEOUT)));
+        runtime.register_time_event(Arc::clone(&timer));
+        timer.arm_periodic(3u64, 5u64);
+
+        advance_ticks(14).unwrap();
+        assert_eq!(*log.lock().unwrap(), vec![3, 8, 13]);
+        assert_eq!(now(), 14);
+    }
+
+    #[test]
+    fn records_are_stamped_with_virtual_time() {
+        let id = ActiveObjectId::new(1);
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (runtime, tracer) = SimRuntime::with_tracer(builder(id, &log), Records::default()).unwrap();
+
+        runtime.advance_ticks(7).unwrap();
+        let record = tracer.emit_with_timestamp(100, &[]).unwrap();
+        assert_eq!(record.timestamp, Some(7));
+    }
+
+    #[test]
+    #[should_panic(expected = "no simulation")]
+    fn advancing_without_a_simulation_panics() {
+        let _ = advance_ticks(1);
+    }
+}
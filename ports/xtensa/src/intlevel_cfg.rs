@@ -39,3 +39,11 @@ pub fn qk_lock() -> u32 {
 #[cfg(not(feature = "hw"))]
 #[inline]
 pub fn qk_unlock(_prev: u32) {}
+
+/// The scheduler lock as the kernels' idle [`InterruptLock`]: the idle
+/// callback runs at [`QK_INTLEVEL`]. `WAITI 0` lowers the level as it sleeps,
+/// so `cx.sleep(|| hal_lxsis::asm::waiti(0))` sleeps without missing a post.
+///
+/// [`InterruptLock`]: qf::idle::InterruptLock
+pub const IDLE_LOCK: qf::idle::InterruptLock =
+    qf::idle::InterruptLock { lock: qk_lock, unlock: qk_unlock };
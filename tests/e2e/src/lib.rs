@@ -0,0 +1,7 @@
+//! End-to-end tests that cross crate boundaries.
+//!
+//! The example applications are binaries, and some of them cannot be built
+//! on every host (the DPP example pulls in ESP-IDF build tooling), so the
+//! tests under `tests/` compile the examples' state-machine sources
+//! directly, run them on the POSIX port, and decode the resulting QS stream
+//! with the `qspy` library. This crate has no code of its own.
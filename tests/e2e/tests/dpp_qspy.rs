@@ -0,0 +1,156 @@
+//! DPP → POSIX port → UDP → qspy, end to end.
+//!
+//! Runs the Dining Philosophers state machines from `examples/dpp` on the QK
+//! kernel for a bounded number of virtual ticks, traces them over UDP to a
+//! socket owned by this test, and decodes the datagrams with the `qspy`
+//! library as the `qspy` binary would. The assertions cover the three kinds
+//! of record a QSPY user relies on: dictionaries, framework records
+//! (`TE-Post`) and application user records (`PHILO_STAT`), all resolved to
+//! the philosophers' names.
+//!
+//! The state machines come from `dpp-core`, the crate the example's
+//! binaries share; the board below reports them the way the host one does.
+
+use std::net::UdpSocket;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use dpp_core::sig::TIMEOUT_SIG;
+use dpp_core::{
+    eating, hungry, philo_id, thinking, Bsp, PhiloData, TableData, NAMES, PAUSED_STAT_RECORD,
+    PHILO_STAT_RECORD, SIGNALS, TABLE_ID,
+};
+use qf::active::new_active_object;
+use qf::time::{TimeEvent, TimeEventConfig, TimeEventTraceInfo};
+use qf_port_posix::{PosixPort, PosixQkRuntime};
+use qk::QkKernel;
+use qs::{TargetInfo, UdpBatching, UserRecordBuilder};
+use qspy::{FrameInterpreter, HdlcDecoder};
+
+static PORT: OnceLock<Arc<PosixPort>> = OnceLock::new();
+
+static TRACED: Bsp = Bsp {
+    philo_stat: |index, stat| {
+        let mut buf = [0u8; 32];
+        let mut record = UserRecordBuilder::encode_into(&mut buf);
+        record.push_u8(1, index as u8).push_str(stat);
+        if let (Some(port), Ok(payload)) = (PORT.get(), record.finish()) {
+            port.emit_record(PHILO_STAT_RECORD, payload, true).unwrap();
+        }
+    },
+    ..Bsp::SILENT
+};
+
+/// Virtual ticks to run: long enough for every philosopher to eat.
+const RUN_TICKS: usize = 80;
+/// User record sent after the run; the receiver stops on it.
+const END_OF_RUN_RECORD: u8 = 124;
+
+fn philo_obj(i: usize) -> u64 {
+    0x2000 + 0x100 * i as u64
+}
+
+fn philo_timer_obj(i: usize) -> u64 {
+    philo_obj(i) + 0x10
+}
+
+fn emit_dictionaries(port: &PosixPort) {
+    port.emit_target_info(&TargetInfo::default()).unwrap();
+    port.emit_usr_dict(PHILO_STAT_RECORD, "PHILO_STAT").unwrap();
+    port.emit_usr_dict(PAUSED_STAT_RECORD, "PAUSED_STAT").unwrap();
+    for (signal, name) in SIGNALS {
+        port.emit_sig_dict(signal.0, 0, name).unwrap();
+    }
+    for (i, name) in NAMES.iter().enumerate() {
+        port.emit_obj_dict(philo_obj(i), &format!("Philo::{name}")).unwrap();
+        port.emit_obj_dict(philo_timer_obj(i), &format!("Philo::{name}.m_timeEvt")).unwrap();
+    }
+    port.emit_fun_dict(thinking as *const () as usize as u64, "Philo::thinking").unwrap();
+    port.emit_fun_dict(hungry as *const () as usize as u64, "Philo::hungry").unwrap();
+    port.emit_fun_dict(eating as *const () as usize as u64, "Philo::eating").unwrap();
+}
+
+fn run_dpp(port: Arc<PosixPort>) {
+    PORT.set(Arc::clone(&port)).unwrap_or_else(|_| panic!("port already set"));
+    emit_dictionaries(&port);
+
+    let mut builder = QkKernel::builder()
+        .register(new_active_object(TABLE_ID, 10, TableData::new(&TRACED).into_hsm()))
+        .unwrap();
+    let mut timers = Vec::new();
+    for index in 0..NAMES.len() {
+        let id = philo_id(index);
+        let timer = TimeEvent::new(id, TimeEventConfig::new(TIMEOUT_SIG));
+        timer.set_trace_meta(TimeEventTraceInfo {
+            time_event_addr: philo_timer_obj(index),
+            target_addr: philo_obj(index),
+            tick_rate: 0,
+        });
+        timers.push(Arc::clone(&timer));
+        let philo = PhiloData::new(index, timer, &TRACED).into_hsm();
+        builder = builder.register(new_active_object(id, index as u8 + 1, philo)).unwrap();
+    }
+
+    let mut runtime = PosixQkRuntime::with_port(builder, &port).unwrap();
+    for timer in timers {
+        runtime.register_time_event(timer);
+    }
+
+    for _ in 0..RUN_TICKS {
+        runtime.tick().unwrap();
+        runtime.run_until_idle();
+    }
+    port.emit_record(END_OF_RUN_RECORD, &[], false).unwrap();
+}
+
+/// What the `qspy` binary does with a UDP link, up to the end-of-run record.
+fn collect(socket: &UdpSocket) -> Vec<String> {
+    socket.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+    let mut decoder = HdlcDecoder::new();
+    let mut interpreter = FrameInterpreter::new();
+    let mut lines = Vec::new();
+    let mut buf = [0u8; 2048];
+    while let Ok(n) = socket.recv(&mut buf) {
+        for frame in decoder.push_bytes(&buf[..n]).into_iter().flatten() {
+            if frame.record_type == END_OF_RUN_RECORD {
+                return lines;
+            }
+            lines.extend(interpreter.interpret(&frame));
+        }
+    }
+    panic!("trace ended without the end-of-run record");
+}
+
+#[test]
+fn dpp_trace_decodes_in_qspy() {
+    let qspy = UdpSocket::bind("127.0.0.1:0").unwrap();
+    // Batched, so a burst of records cannot overrun the receive buffer.
+    let port = Arc::new(
+        PosixPort::connect_udp_batched(qspy.local_addr().unwrap(), UdpBatching::default()).unwrap(),
+    );
+
+    // Stamp records with the virtual ticks that drive the run.
+    port.set_timestamp_source(qf::time::KernelTicks);
+
+    let receiver = std::thread::spawn(move || collect(&qspy));
+    run_dpp(port);
+    let lines = receiver.join().unwrap();
+
+    let has = |needle: &str| lines.iter().any(|l| l.contains(needle));
+    let stamps: Vec<u32> = lines.iter().filter_map(|l| l.get(..10)?.parse().ok()).collect();
+    assert!(!stamps.is_empty());
+    assert!(stamps.windows(2).all(|w| w[0] <= w[1]), "timestamps follow the ticks");
+    assert!(stamps.iter().all(|&ts| ts <= RUN_TICKS as u32), "timestamps count virtual ticks");
+    assert!(has("Usr-Dict 100->PHILO_STAT"));
+    assert!(has("Sig-Dict 0x0000000A,Obj=0x0000000000000000->TIMEOUT_SIG"));
+    for (i, name) in NAMES.iter().enumerate() {
+        assert!(has(&format!("->Philo::{name}.m_timeEvt")), "{name}'s timer in the object dictionary");
+        assert!(
+            has(&format!("TE0-Post Obj=Philo::{name}.m_timeEvt,Sig=TIMEOUT_SIG,AO=Philo::{name}")),
+            "{name}'s timeout decoded with names"
+        );
+        for state in ["thinking", "hungry", "eating"] {
+            assert!(has(&format!("PHILO_STAT {i} {state}")), "{name} reported {state}");
+        }
+    }
+}
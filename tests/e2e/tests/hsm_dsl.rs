@@ -0,0 +1,188 @@
+//! `qp_macros::hsm!` → `QHsm` → qs dictionary.
+//!
+//! A toaster oven declared with the DSL: a composite `door_closed` state
+//! with an initial transition, guarded and internal transitions, entry and
+//! exit actions and a transition to history. The generated handlers must
+//! behave as hand-written ones would, and the dictionary must name them.
+//! A latch whose last rule for a signal is guarded checks that the guard
+//! reaches the trace.
+
+use std::sync::{Arc, Mutex};
+
+use qf::event::{DynEvent, Signal};
+use qf::hsm::QHsm;
+use qf::TraceHook;
+
+const TOAST: Signal = Signal(10);
+const TICK: Signal = Signal(11);
+const OPEN: Signal = Signal(12);
+const CLOSE: Signal = Signal(13);
+
+#[derive(Default)]
+struct Oven {
+    log: Vec<&'static str>,
+    ticks: u32,
+    lamp: bool,
+}
+
+qp_macros::hsm! {
+    /// The oven's control logic.
+    #[dictionary]
+    machine oven for Oven {
+        initial { sm.log.push("power on"); } => door_closed;
+
+        state door_closed {
+            initial => heating;
+            on OPEN => door_open;
+        }
+
+        state heating {
+            parent door_closed;
+            entry { sm.log.push("heating"); }
+            on TOAST => toasting;
+        }
+
+        state toasting {
+            parent door_closed;
+            entry { sm.ticks = 0; }
+            exit { sm.log.push("toasting done"); }
+            on TICK if sm.ticks >= 2 => heating;
+            on TICK { sm.ticks += 1; }
+        }
+
+        state door_open {
+            parent top;
+            entry { sm.lamp = true; }
+            exit { sm.lamp = false; }
+            on CLOSE => history(door_closed);
+        }
+    }
+}
+
+const PUSH: Signal = Signal(14);
+
+#[derive(Default)]
+struct Latch {
+    unlocked: bool,
+}
+
+qp_macros::hsm! {
+    machine latch for Latch {
+        initial => shut;
+
+        state shut {
+            on PUSH if sm.unlocked => open;
+        }
+
+        state open {}
+    }
+}
+
+fn dispatch(hsm: &mut QHsm<Oven>, signal: Signal) {
+    hsm.dispatch(&DynEvent::empty_dyn(signal));
+}
+
+#[test]
+fn generated_handlers_run_the_machine() {
+    let mut hsm = oven::new(Oven::default());
+    hsm.init();
+    assert!(hsm.is_in(oven::heating));
+    assert!(hsm.is_in(oven::door_closed));
+    assert_eq!(hsm.sm().log, ["power on", "heating"]);
+
+    dispatch(&mut hsm, TOAST);
+    dispatch(&mut hsm, TICK);
+    dispatch(&mut hsm, TICK);
+    assert!(
+        hsm.is_in(oven::toasting),
+        "the guard holds the oven until the third tick"
+    );
+    assert_eq!(hsm.sm().ticks, 2);
+
+    dispatch(&mut hsm, OPEN);
+    assert!(hsm.is_in(oven::door_open));
+    assert!(hsm.sm().lamp);
+    dispatch(&mut hsm, CLOSE);
+    assert!(
+        hsm.is_in(oven::toasting),
+        "closing the door resumes toasting"
+    );
+    assert!(!hsm.sm().lamp);
+
+    dispatch(&mut hsm, TICK);
+    dispatch(&mut hsm, TICK);
+    dispatch(&mut hsm, TICK);
+    assert!(hsm.is_in(oven::heating));
+    assert_eq!(
+        hsm.sm().log,
+        [
+            "power on",
+            "heating",
+            "toasting done",
+            "toasting done",
+            "heating"
+        ]
+    );
+
+    dispatch(&mut hsm, CLOSE);
+    assert!(hsm.is_in(oven::heating), "unhandled events are ignored");
+}
+
+#[test]
+fn the_dictionary_names_every_handler() {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&records);
+    let hook: TraceHook = Arc::new(move |record, payload, _| {
+        sink.lock().unwrap().push((record, payload.to_vec()));
+        Ok(())
+    });
+    oven::emit_dictionary(&hook).unwrap();
+
+    let records = records.lock().unwrap();
+    let names: Vec<String> = records
+        .iter()
+        .map(|(record, payload)| {
+            assert_eq!(*record, qs::predefined::FUN_DICT);
+            let name = &payload[8..payload.len() - 1];
+            String::from_utf8(name.to_vec()).unwrap()
+        })
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "QP::QHsm::top",
+            "oven::initial",
+            "oven::door_closed",
+            "oven::heating",
+            "oven::toasting",
+            "oven::door_open",
+        ]
+    );
+    let heating = oven::heating as qf::hsm::StateHandler<Oven> as usize as u64;
+    assert_eq!(records[3].1[..8], heating.to_le_bytes());
+}
+
+#[test]
+fn a_trailing_guard_is_traced_by_the_dispatcher() {
+    let guards = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&guards);
+    let hook: TraceHook = Arc::new(move |record, payload, _| {
+        if record == qs::records::qep::GUARD {
+            sink.lock().unwrap().push(*payload.last().unwrap());
+        }
+        Ok(())
+    });
+
+    let mut hsm = latch::new(Latch::default());
+    hsm.init();
+    hsm.dispatch_traced(&DynEvent::empty_dyn(PUSH), Some(Arc::clone(&hook)));
+    assert!(
+        hsm.is_in(latch::shut),
+        "a failed guard leaves the latch shut"
+    );
+
+    hsm.sm_mut().unlocked = true;
+    hsm.dispatch_traced(&DynEvent::empty_dyn(PUSH), Some(hook));
+    assert!(hsm.is_in(latch::open));
+    assert_eq!(*guards.lock().unwrap(), [0, 1]);
+}
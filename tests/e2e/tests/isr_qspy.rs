@@ -0,0 +1,75 @@
+//! `#[qs_traced_isr]` handlers → qs → qspy.
+//!
+//! Two traced handlers, one preempting the other as a higher-priority
+//! interrupt would, run against a manual clock. qspy must show both entries
+//! and exits with their nesting and priority, and time each handler, even
+//! when it returns early.
+
+use std::sync::{Arc, Mutex};
+
+use qs::{ManualClock, QsConfig, TimestampClock, TraceBackend, TraceError, Tracer};
+use qspy::{FrameInterpreter, HdlcDecoder};
+
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<u8>>>);
+
+impl TraceBackend for Capture {
+    fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
+        self.0.lock().unwrap().extend_from_slice(frame);
+        Ok(())
+    }
+}
+
+static CLOCK: Mutex<Option<ManualClock>> = Mutex::new(None);
+
+fn advance(ticks: u32) {
+    CLOCK.lock().unwrap().as_ref().unwrap().advance(ticks);
+}
+
+#[qs::qs_traced_isr(prio = 1)]
+fn uart_rx() {
+    advance(5);
+    timer0(false);
+    advance(3);
+}
+
+#[qs::qs_traced_isr(prio = 4)]
+fn timer0(spurious: bool) -> u32 {
+    advance(2);
+    if !spurious {
+        return 0;
+    }
+    advance(100);
+    1
+}
+
+#[test]
+fn traced_handlers_show_nesting_priority_and_run_time() {
+    let clock = ManualClock::new();
+    *CLOCK.lock().unwrap() = Some(clock.clone());
+    let capture = Capture::default();
+    let tracer = Tracer::new(QsConfig::default(), capture.clone()).into_handle();
+    tracer.set_timestamp_source(Some(TimestampClock::new(clock.clone())));
+    qs::set_isr_tracer(&tracer);
+
+    clock.set(1000);
+    uart_rx();
+    assert_eq!(qs::isr::isr_nesting(), 0);
+
+    let mut interp = FrameInterpreter::new();
+    let lines: Vec<String> = HdlcDecoder::new()
+        .push_bytes(&capture.0.lock().unwrap())
+        .into_iter()
+        .map(|frame| frame.expect("well-formed frame"))
+        .flat_map(|frame| interp.interpret(&frame))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            "0000001000 QF-IsrE  Nesting=1,Pri=1",
+            "0000001005 QF-IsrE  Nesting=2,Pri=4",
+            "0000001007 QF-IsrX  Nesting=2,Pri=4,Ran=2",
+            "0000001010 QF-IsrX  Nesting=1,Pri=1,Ran=10",
+        ]
+    );
+}
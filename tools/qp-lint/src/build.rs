@@ -0,0 +1,59 @@
+//! Running the lints from a build script.
+//!
+//! Add `qp-lint` as a build dependency and call one of these from `build.rs`;
+//! every `cargo build` of the crate then lints its `src/` tree:
+//!
+//! ```no_run
+//! // build.rs
+//! # use qp_lint as _;
+//! fn main() {
+//!     qp_lint::build::deny();
+//! }
+//! ```
+//!
+//! Findings are shown as cargo warnings. A file that does not parse is left
+//! to the compiler to report.
+
+use std::env;
+use std::path::PathBuf;
+
+use crate::{lint_path, Diagnostic, Error};
+
+/// Reports findings as warnings.
+pub fn warn() {
+    report();
+}
+
+/// Reports findings as warnings and fails the build if there are any.
+pub fn deny() {
+    let findings = report();
+    if !findings.is_empty() {
+        panic!("qp-lint: {} finding(s); see the warnings above", findings.len());
+    }
+}
+
+fn report() -> Vec<Diagnostic> {
+    let manifest = env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_default();
+    let src = manifest.join("src");
+    println!("cargo:rerun-if-changed={}", src.display());
+    let findings = match lint_path(&src) {
+        Ok(findings) => findings,
+        Err(Error::Parse { .. }) => return Vec::new(),
+        Err(e) => {
+            println!("cargo:warning=qp-lint: {e}");
+            return Vec::new();
+        }
+    };
+    for finding in &findings {
+        let location = finding.path.strip_prefix(&manifest).unwrap_or(&finding.path);
+        println!(
+            "cargo:warning=qp::{}: {} ({}:{}:{})",
+            finding.lint,
+            finding.message,
+            location.display(),
+            finding.line,
+            finding.column
+        );
+    }
+    findings
+}
@@ -0,0 +1,189 @@
+//! Lints for misuses of QP idioms that the compiler accepts.
+//!
+//! `qp-lint` parses an application crate's sources and reports:
+//!
+//! | lint | finds |
+//! |------|-------|
+//! | `blocking_in_handler` | sleeping, joining or waiting on a channel or condition variable inside an AO handler |
+//! | `post_in_drop` | posting or publishing from a `Drop` impl |
+//! | `reserved_signal` | `Signal(n)` with `n` below the first user signal |
+//! | `unfinished_qs_record` | a user QS record that is begun but never finished |
+//!
+//! Handlers are the methods of `SignalHandler` and `ActiveBehavior` impls and
+//! every function returning a `QHsmResult` or `QMsmResult`. A QS record is
+//! begun by creating a `qs::record::UserRecordBuilder` or
+//! encoder — the Rust form of `QS_BEGIN_ID` — and finished by `into_vec`,
+//! `finish` or handing it on; pushing fields into it and dropping it is the
+//! `QS_BEGIN` without a `QS_END`.
+//!
+//! The checks run on the syntax tree, so they see names, not types: a local
+//! `fn sleep` is still a blocking call. Silence a finding with a
+//! `// qp-lint: allow(<lint>)` comment on its line or the line above.
+//!
+//! Run it as `cargo qp-lint [--deny] [PATH…]`, or from a build script through
+//! [`build`] so every build of the crate is checked.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub mod build;
+mod visit;
+
+/// The first signal available to applications (`Q_USER_SIG`).
+pub const USER_SIG: u16 = 4;
+
+/// One kind of finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    BlockingInHandler,
+    PostInDrop,
+    ReservedSignal,
+    UnfinishedQsRecord,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 4] =
+        [Lint::BlockingInHandler, Lint::PostInDrop, Lint::ReservedSignal, Lint::UnfinishedQsRecord];
+
+    /// The name used in reports and `allow` comments.
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::BlockingInHandler  => "blocking_in_handler",
+            Lint::PostInDrop         => "post_in_drop",
+            Lint::ReservedSignal     => "reserved_signal",
+            Lint::UnfinishedQsRecord => "unfinished_qs_record",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A finding, located by file, 1-based line and 1-based column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub lint:    Lint,
+    pub path:    PathBuf,
+    pub line:    usize,
+    pub column:  usize,
+    pub message: String,
+}
+
+/// Formatted like a compiler warning:
+///
+/// ```text
+/// warning[qp::post_in_drop]: `publish` in `Drop::drop` runs while the framework may be shutting down
+///   --> src/sensor.rs:42:9
+/// ```
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "warning[qp::{}]: {}\n  --> {}:{}:{}",
+            self.lint,
+            self.message,
+            self.path.display(),
+            self.line,
+            self.column
+        )
+    }
+}
+
+/// Why a file could not be linted.
+#[derive(Debug)]
+pub enum Error {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, line: usize, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Error::Parse { path, line, message } => write!(f, "{}:{line}: {message}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Parse { .. } => None,
+        }
+    }
+}
+
+/// Lints the source text of one file; `path` only labels the findings.
+pub fn lint_source(path: &Path, source: &str) -> Result<Vec<Diagnostic>, Error> {
+    let file = syn::parse_file(source).map_err(|e| Error::Parse {
+        path:    path.to_owned(),
+        line:    e.span().start().line,
+        message: e.to_string(),
+    })?;
+    let mut findings = visit::check(&file);
+    findings.retain(|f| !allowed(source, f.line, f.lint));
+    findings.sort_by_key(|f| (f.line, f.column));
+    Ok(findings
+        .into_iter()
+        .map(|f| Diagnostic {
+            lint:    f.lint,
+            path:    path.to_owned(),
+            line:    f.line,
+            column:  f.column + 1,
+            message: f.message,
+        })
+        .collect())
+}
+
+/// Lints a file, or every `.rs` file below a directory in path order.
+pub fn lint_path(path: &Path) -> Result<Vec<Diagnostic>, Error> {
+    let mut files = Vec::new();
+    collect_sources(path, &mut files)?;
+    let mut all = Vec::new();
+    for file in files {
+        let source = fs::read_to_string(&file).map_err(|source| Error::Io { path: file.clone(), source })?;
+        all.extend(lint_source(&file, &source)?);
+    }
+    Ok(all)
+}
+
+fn collect_sources(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let io_err = |source| Error::Io { path: path.to_owned(), source };
+    if !fs::metadata(path).map_err(io_err)?.is_dir() {
+        files.push(path.to_owned());
+        return Ok(());
+    }
+    let mut entries: Vec<PathBuf> =
+        fs::read_dir(path).map_err(io_err)?.filter_map(|e| e.ok().map(|e| e.path())).collect();
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "rs") {
+            collect_sources(&entry, files)?;
+        }
+    }
+    Ok(())
+}
+
+/// `true` if line `line`, or the one above it, carries an allow comment
+/// naming `lint`.
+fn allowed(source: &str, line: usize, lint: Lint) -> bool {
+    let lines: Vec<&str> = source.lines().collect();
+    [line.checked_sub(2), line.checked_sub(1)].into_iter().flatten().any(|i| {
+        lines.get(i).and_then(|text| text.split("qp-lint: allow(").nth(1)).is_some_and(|rest| {
+            rest.split(')').next().unwrap_or("").split(',').any(|name| name.trim() == lint.name())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,43 @@
+//! `cargo qp-lint [--deny] [PATH…]`: lints `src/` (or the given files and
+//! directories) and prints the findings. With `--deny` any finding makes the
+//! exit status non-zero.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    // Cargo passes the subcommand name as the first argument.
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "qp-lint") {
+        args.remove(0);
+    }
+    let deny = args.iter().any(|a| a == "--deny");
+    let mut paths: Vec<PathBuf> = args.iter().filter(|a| !a.starts_with("--")).map(PathBuf::from).collect();
+    if paths.is_empty() {
+        paths.push(PathBuf::from("src"));
+    }
+
+    let mut count = 0;
+    for path in &paths {
+        match qp_lint::lint_path(path) {
+            Ok(findings) => {
+                for finding in &findings {
+                    println!("{finding}\n");
+                }
+                count += findings.len();
+            }
+            Err(e) => {
+                eprintln!("qp-lint: {e}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    if count > 0 {
+        println!("qp-lint: {count} finding(s)");
+    }
+    if deny && count > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use crate::{lint_source, Lint};
+
+fn lints(source: &str) -> Vec<(Lint, usize)> {
+    lint_source(Path::new("src/lib.rs"), source).unwrap().into_iter().map(|d| (d.lint, d.line)).collect()
+}
+
+#[test]
+fn blocking_calls_in_handlers_are_reported() {
+    let source = r#"
+impl SignalHandler for Logger {
+    fn handle_signal(&mut self, signal: Signal, ctx: &mut ActiveContext) {
+        std::thread::sleep(Duration::from_millis(5));
+        let line = self.rx.recv().unwrap();
+        let name = ["a", "b"].join(",");
+    }
+}
+
+impl Blinky {
+    fn on(&mut self, event: &DynEvent) -> QHsmResult<Self> {
+        self.worker.take().unwrap().join().unwrap();
+        QHsmResult::Handled
+    }
+
+    fn setup(&mut self) {
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+"#;
+    assert_eq!(
+        lints(source),
+        [(Lint::BlockingInHandler, 4), (Lint::BlockingInHandler, 5), (Lint::BlockingInHandler, 12)]
+    );
+}
+
+#[test]
+fn posting_from_drop_is_reported() {
+    let source = r#"
+impl Drop for Sensor {
+    fn drop(&mut self) {
+        self.kernel.publish(SHUTDOWN_SIG, DynEvent::empty_dyn(SHUTDOWN_SIG));
+        let _ = self.kernel.post(self.owner, DynEvent::empty_dyn(GONE_SIG));
+    }
+}
+
+impl Sensor {
+    fn stop(&self) {
+        self.kernel.publish(SHUTDOWN_SIG, DynEvent::empty_dyn(SHUTDOWN_SIG));
+    }
+}
+"#;
+    assert_eq!(lints(source), [(Lint::PostInDrop, 4), (Lint::PostInDrop, 5)]);
+}
+
+#[test]
+fn reserved_signals_are_reported_in_expressions_and_patterns() {
+    let source = r#"
+const TICK_SIG: Signal = Signal(2);
+const BUTTON_SIG: Signal = Signal(4);
+
+fn dispatch(signal: Signal) {
+    match signal {
+        Signal(1) => {}
+        Signal(5) => {}
+        _ => {}
+    }
+}
+"#;
+    assert_eq!(lints(source), [(Lint::ReservedSignal, 2), (Lint::ReservedSignal, 7)]);
+}
+
+#[test]
+fn user_records_must_be_finished() {
+    let source = r#"
+fn report(tracer: &TracerHandle<B>, level: u8) {
+    let mut forgotten = UserRecordBuilder::new();
+    forgotten.push_u8(0, level).push_str("low");
+
+    let mut sent = UserRecordBuilder::with_capacity(8);
+    sent.push_u8(0, level);
+    tracer.emit(USER_REC, &sent.into_vec()).ok();
+
+    UserRecordBuilder::new().push_u8(0, level);
+}
+
+fn build(level: u8) -> UserRecordBuilder {
+    let mut record = UserRecordBuilder::new();
+    record.push_u8(0, level);
+    record
+}
+"#;
+    assert_eq!(lints(source), [(Lint::UnfinishedQsRecord, 3), (Lint::UnfinishedQsRecord, 10)]);
+}
+
+#[test]
+fn allow_comments_silence_one_lint() {
+    let source = r#"
+impl ActiveBehavior for Pump {
+    fn on_event(&mut self, ctx: &mut ActiveContext, event: DynEvent) {
+        // qp-lint: allow(blocking_in_handler)
+        std::thread::sleep(Duration::from_millis(1));
+        std::thread::sleep(Duration::from_millis(1)); // qp-lint: allow(post_in_drop)
+    }
+}
+"#;
+    assert_eq!(lints(source), [(Lint::BlockingInHandler, 6)]);
+}
+
+#[test]
+fn findings_read_like_compiler_warnings() {
+    let source = "fn f() { let s = Signal(0); }\n";
+    let finding = &lint_source(Path::new("src/sig.rs"), source).unwrap()[0];
+    assert_eq!(
+        finding.to_string(),
+        "warning[qp::reserved_signal]: `Signal(0)` is a reserved framework signal; application signals \
+         start at 4\n  --> src/sig.rs:1:18"
+    );
+    assert_eq!(Lint::from_name("reserved_signal"), Some(Lint::ReservedSignal));
+}
@@ -0,0 +1,305 @@
+//! The syntax-tree walk behind every lint.
+
+use std::collections::HashMap;
+
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ImplItemFn, ItemFn, ItemImpl, Lit, Pat, ReturnType, Stmt, Type};
+
+use crate::{Lint, USER_SIG};
+
+/// A finding before it is tied to a file; `column` is 0-based.
+pub(crate) struct Finding {
+    pub lint:    Lint,
+    pub line:    usize,
+    pub column:  usize,
+    pub message: String,
+}
+
+/// Traits whose methods run as part of an AO's run-to-completion step.
+const HANDLER_TRAITS: [&str; 2] = ["SignalHandler", "ActiveBehavior"];
+/// Return types that mark a state-handler function.
+const HANDLER_RESULTS: [&str; 2] = ["QHsmResult", "QMsmResult"];
+/// Free functions that block the calling thread.
+const BLOCKING_FNS: [&str; 4] = ["sleep", "park", "park_timeout", "block_on"];
+/// Methods that block the calling thread whatever their arguments.
+const BLOCKING_METHODS: [&str; 8] = [
+    "recv",
+    "recv_timeout",
+    "wait",
+    "wait_timeout",
+    "wait_while",
+    "read_line",
+    "blocking_recv",
+    "blocking_lock",
+];
+/// Methods that post or publish an event.
+const POST_METHODS: [&str; 4] = ["post", "post_lifo", "publish", "post_from_isr"];
+/// Constructors that begin a user QS record.
+const RECORD_STARTS: [&str; 3] = ["new", "with_capacity", "encode_into"];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Context {
+    Plain,
+    Handler,
+    Drop,
+}
+
+pub(crate) fn check(file: &syn::File) -> Vec<Finding> {
+    let mut checker = Checker { context: Context::Plain, impl_trait: None, findings: Vec::new() };
+    checker.visit_file(file);
+    checker.findings
+}
+
+struct Checker {
+    context:    Context,
+    /// Last path segment of the trait of the enclosing impl, if any.
+    impl_trait: Option<String>,
+    findings:   Vec<Finding>,
+}
+
+impl Checker {
+    fn report(&mut self, lint: Lint, span: Span, message: String) {
+        let start = span.start();
+        self.findings.push(Finding { lint, line: start.line, column: start.column, message });
+    }
+
+    /// Runs `f` in `context`, restoring the outer one afterwards.
+    fn within(&mut self, context: Context, f: impl FnOnce(&mut Self)) {
+        let outer = std::mem::replace(&mut self.context, context);
+        f(self);
+        self.context = outer;
+    }
+
+    fn fn_context(&self, name: &str, output: &ReturnType) -> Context {
+        match self.impl_trait.as_deref() {
+            Some("Drop") if name == "drop" => return Context::Drop,
+            Some(t) if HANDLER_TRAITS.contains(&t) => return Context::Handler,
+            _ => {}
+        }
+        match output {
+            ReturnType::Type(_, ty) if returns_handler_result(ty) => Context::Handler,
+            _ => Context::Plain,
+        }
+    }
+
+    fn check_reserved_signal(&mut self, callee: Option<&str>, arg: Option<&Lit>, span: Span) {
+        if callee != Some("Signal") {
+            return;
+        }
+        if let Some(Lit::Int(value)) = arg {
+            if value.base10_parse::<u16>().is_ok_and(|n| n < USER_SIG) {
+                self.report(
+                    Lint::ReservedSignal,
+                    span,
+                    format!(
+                        "`Signal({})` is a reserved framework signal; application signals start at {USER_SIG}",
+                        value.base10_digits()
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Reports user records in `block` that are begun and never finished.
+    fn check_records(&mut self, block: &syn::Block) {
+        let mut uses = RecordUses::default();
+        uses.visit_block(block);
+        for (name, (span, finished)) in uses.locals {
+            if !finished {
+                self.report(
+                    Lint::UnfinishedQsRecord,
+                    span,
+                    format!("QS record `{name}` is begun but never finished or emitted"),
+                );
+            }
+        }
+        for span in uses.dropped {
+            self.report(
+                Lint::UnfinishedQsRecord,
+                span,
+                "QS record is begun and dropped without being finished or emitted".to_owned(),
+            );
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Checker {
+    fn visit_item_impl(&mut self, item: &'ast ItemImpl) {
+        let trait_name = item.trait_.as_ref().and_then(|(_, path, _)| last_segment(path));
+        let outer = std::mem::replace(&mut self.impl_trait, trait_name);
+        self.within(Context::Plain, |this| visit::visit_item_impl(this, item));
+        self.impl_trait = outer;
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast ImplItemFn) {
+        let context = self.fn_context(&item.sig.ident.to_string(), &item.sig.output);
+        self.check_records(&item.block);
+        self.within(context, |this| visit::visit_impl_item_fn(this, item));
+    }
+
+    fn visit_item_fn(&mut self, item: &'ast ItemFn) {
+        // A nested fn item is not part of the enclosing impl.
+        let outer = self.impl_trait.take();
+        let context = self.fn_context(&item.sig.ident.to_string(), &item.sig.output);
+        self.check_records(&item.block);
+        self.within(context, |this| visit::visit_item_fn(this, item));
+        self.impl_trait = outer;
+    }
+
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        let callee = match &*call.func {
+            Expr::Path(p) => last_segment(&p.path),
+            _ => None,
+        };
+        let arg = match call.args.first() {
+            Some(Expr::Lit(lit)) if call.args.len() == 1 => Some(&lit.lit),
+            _ => None,
+        };
+        self.check_reserved_signal(callee.as_deref(), arg, call.span());
+        if let (Context::Handler, Some(name)) = (self.context, callee.as_deref()) {
+            if BLOCKING_FNS.contains(&name) {
+                self.report(
+                    Lint::BlockingInHandler,
+                    call.span(),
+                    format!("`{name}` blocks inside an active-object handler"),
+                );
+            }
+        }
+        visit::visit_expr_call(self, call);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        let name = call.method.to_string();
+        let span = call.method.span();
+        match self.context {
+            Context::Handler
+                if BLOCKING_METHODS.contains(&name.as_str()) || (name == "join" && call.args.is_empty()) =>
+            {
+                self.report(
+                    Lint::BlockingInHandler,
+                    span,
+                    format!("`.{name}()` blocks inside an active-object handler"),
+                );
+            }
+            Context::Drop if POST_METHODS.contains(&name.as_str()) => {
+                self.report(
+                    Lint::PostInDrop,
+                    span,
+                    format!("`{name}` in `Drop::drop` runs while the framework may be shutting down"),
+                );
+            }
+            _ => {}
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_pat(&mut self, pat: &'ast Pat) {
+        if let Pat::TupleStruct(ts) = pat {
+            let arg = match ts.elems.first() {
+                Some(Pat::Lit(lit)) if ts.elems.len() == 1 => Some(&lit.lit),
+                _ => None,
+            };
+            self.check_reserved_signal(last_segment(&ts.path).as_deref(), arg, ts.span());
+        }
+        visit::visit_pat(self, pat);
+    }
+}
+
+/// How the user records begun in one function body are used.
+#[derive(Default)]
+struct RecordUses {
+    /// Record locals by name: where each is begun, and whether it is finished.
+    locals:  HashMap<String, (Span, bool)>,
+    /// Records begun, filled and dropped in a single statement.
+    dropped: Vec<Span>,
+}
+
+impl<'ast> Visit<'ast> for RecordUses {
+    fn visit_item_fn(&mut self, _: &'ast ItemFn) {
+        // Checked on its own.
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        if let (Pat::Ident(ident), Some(init)) = (&local.pat, &local.init) {
+            if begins_record(&init.expr) {
+                self.locals.insert(ident.ident.to_string(), (local.span(), false));
+                return;
+            }
+        }
+        visit::visit_local(self, local);
+    }
+
+    fn visit_stmt(&mut self, stmt: &'ast Stmt) {
+        if let Stmt::Expr(expr, Some(_)) = stmt {
+            if begins_record(expr) {
+                self.dropped.push(expr.span());
+                return;
+            }
+        }
+        visit::visit_stmt(self, stmt);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        // Filling a record does not finish it.
+        if call.method.to_string().starts_with("push_") && self.record_local(&call.receiver).is_some() {
+            for arg in &call.args {
+                self.visit_expr(arg);
+            }
+            return;
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_expr_path(&mut self, path: &'ast syn::ExprPath) {
+        // Any other use hands the record on.
+        if let Some(ident) = path.path.get_ident() {
+            if let Some((_, finished)) = self.locals.get_mut(&ident.to_string()) {
+                *finished = true;
+            }
+        }
+    }
+}
+
+impl RecordUses {
+    fn record_local(&self, expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Path(p) => p.path.get_ident().map(|i| i.to_string()).filter(|n| self.locals.contains_key(n)),
+            Expr::MethodCall(call) if call.method.to_string().starts_with("push_") => {
+                self.record_local(&call.receiver)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// `true` for `UserRecordBuilder::new()` and friends, with any number of
+/// `push_*` calls chained on.
+fn begins_record(expr: &Expr) -> bool {
+    match expr {
+        Expr::Call(call) => match &*call.func {
+            Expr::Path(p) => {
+                let segments: Vec<String> = p.path.segments.iter().map(|s| s.ident.to_string()).collect();
+                matches!(
+                    segments.as_slice(),
+                    [.., ty, ctor] if ty == "UserRecordBuilder" && RECORD_STARTS.contains(&ctor.as_str())
+                )
+            }
+            _ => false,
+        },
+        Expr::MethodCall(call) if call.method.to_string().starts_with("push_") => begins_record(&call.receiver),
+        _ => false,
+    }
+}
+
+fn returns_handler_result(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) => last_segment(&p.path).is_some_and(|name| HANDLER_RESULTS.contains(&name.as_str())),
+        _ => false,
+    }
+}
+
+fn last_segment(path: &syn::Path) -> Option<String> {
+    path.segments.last().map(|s| s.ident.to_string())
+}
@@ -0,0 +1,217 @@
+//! The window: group checkboxes on top, the dictionary on the left, and the
+//! trace or the state timelines in the middle.
+
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use eframe::egui::{
+    self, Align2, Color32, FontId, RichText, ScrollArea, Sense, Stroke, StrokeKind, TextStyle, Ui,
+};
+use qspy::RecordGroup;
+
+use crate::model::{StateSpan, TraceModel};
+use crate::source::Message;
+
+/// Frames taken from the source per repaint, so a fast target cannot freeze
+/// the window.
+const FRAMES_PER_UPDATE: usize = 20_000;
+/// Width of the object names left of the timelines.
+const TIMELINE_LABEL: f32 = 140.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Trace,
+    Timeline,
+}
+
+pub struct ViewApp {
+    model:   TraceModel,
+    frames:  Receiver<Message>,
+    source:  String,
+    status:  String,
+    corrupt: u64,
+    paused:  bool,
+    tab:     Tab,
+    search:  String,
+}
+
+impl ViewApp {
+    pub fn new(model: TraceModel, frames: Receiver<Message>, source: String) -> Self {
+        Self {
+            model,
+            frames,
+            status: format!("reading {source}"),
+            source,
+            corrupt: 0,
+            paused: false,
+            tab: Tab::Trace,
+            search: String::new(),
+        }
+    }
+
+    /// Moves waiting frames into the model, unless paused. Returns `true` if
+    /// frames are left over for the next repaint.
+    fn drain(&mut self) -> bool {
+        if self.paused {
+            return false;
+        }
+        for _ in 0..FRAMES_PER_UPDATE {
+            match self.frames.try_recv() {
+                Ok(Message::Frame(frame)) => self.model.push(&frame),
+                Ok(Message::Corrupt) => self.corrupt += 1,
+                Ok(Message::Ended) => self.status = format!("end of {}", self.source),
+                Ok(Message::Failed(e)) => self.status = format!("{}: {e}", self.source),
+                Err(TryRecvError::Empty | TryRecvError::Disconnected) => return false,
+            }
+        }
+        true
+    }
+
+    fn toolbar(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            for group in RecordGroup::SELECTABLE {
+                let mut shown = self.model.shows(group);
+                let label = RichText::new(group.name()).color(group_color(group));
+                if ui.checkbox(&mut shown, label).changed() {
+                    self.model.set_shown(group, shown);
+                }
+            }
+            ui.separator();
+            ui.toggle_value(&mut self.paused, "Pause");
+            if ui.button("Clear").clicked() {
+                self.model.clear();
+            }
+            ui.separator();
+            ui.selectable_value(&mut self.tab, Tab::Trace, "Trace");
+            ui.selectable_value(&mut self.tab, Tab::Timeline, "Timeline");
+        });
+    }
+
+    fn status_bar(&self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label(&self.status);
+            ui.separator();
+            ui.label(format!("{} frames", self.model.frames()));
+            if self.corrupt > 0 {
+                ui.colored_label(Color32::LIGHT_RED, format!("{} corrupt", self.corrupt));
+            }
+        });
+    }
+
+    fn dictionary(&mut self, ui: &mut Ui) {
+        ui.heading("Dictionary");
+        ui.add(egui::TextEdit::singleline(&mut self.search).hint_text("search"));
+        ui.separator();
+        ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+            egui::Grid::new("dictionary").striped(true).show(ui, |ui| {
+                for row in self.model.dictionary(&self.search) {
+                    ui.label(RichText::new(row.kind).weak());
+                    ui.label(row.name);
+                    ui.label(RichText::new(row.value).monospace());
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn trace(&self, ui: &mut Ui) {
+        let rows = self.model.visible();
+        let height = ui.text_style_height(&TextStyle::Monospace);
+        ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .stick_to_bottom(true)
+            .show_rows(ui, height, rows.len(), |ui, range| {
+                for row in &rows[range] {
+                    ui.label(RichText::new(&row.text).monospace().color(group_color(row.group)));
+                }
+            });
+    }
+
+    fn timeline(&self, ui: &mut Ui) {
+        let timelines = self.model.timelines();
+        if timelines.is_empty() {
+            ui.label("No transitions yet. Enable the SM records on the target.");
+            return;
+        }
+        let start = timelines.values().filter_map(|t| t.spans.first()).map(|s| s.start).min().unwrap_or(0);
+        let end = self.model.now().max(start + 1);
+        let row_height = ui.text_style_height(&TextStyle::Body) + 6.0;
+
+        ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+            for (&obj, timeline) in timelines {
+                let width = ui.available_width();
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(width, row_height), Sense::hover());
+                let painter = ui.painter_at(rect);
+                let text_color = ui.visuals().text_color();
+                painter.text(
+                    rect.left_center(),
+                    Align2::LEFT_CENTER,
+                    self.model.object_name(obj),
+                    FontId::proportional(13.0),
+                    text_color,
+                );
+                let bars = egui::Rect::from_min_max(
+                    egui::pos2(rect.left() + TIMELINE_LABEL, rect.top() + 2.0),
+                    egui::pos2(rect.right(), rect.bottom() - 2.0),
+                );
+                let x = |t: u64| bars.left() + bars.width() * (t - start) as f32 / (end - start) as f32;
+                for (i, span) in timeline.spans.iter().enumerate() {
+                    let until = timeline.spans.get(i + 1).map_or(end, |next: &StateSpan| next.start);
+                    let span_rect = egui::Rect::from_min_max(
+                        egui::pos2(x(span.start), bars.top()),
+                        egui::pos2(x(until).max(x(span.start) + 1.0), bars.bottom()),
+                    );
+                    let name = self.model.state_name(span.state).to_string();
+                    painter.rect_filled(span_rect, 2.0, state_color(span.state));
+                    painter.rect_stroke(span_rect, 2.0, Stroke::new(1.0, Color32::BLACK), StrokeKind::Inside);
+                    let label_width = name.len() as f32 * 7.0;
+                    if span_rect.width() > label_width {
+                        painter.text(
+                            span_rect.left_center() + egui::vec2(4.0, 0.0),
+                            Align2::LEFT_CENTER,
+                            &name,
+                            FontId::proportional(12.0),
+                            Color32::BLACK,
+                        );
+                    }
+                    ui.interact(span_rect, ui.id().with((obj, i)), Sense::hover())
+                        .on_hover_text(format!("{name}\nfrom {} until {until}", span.start));
+                }
+            }
+        });
+    }
+}
+
+impl eframe::App for ViewApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.drain() {
+            ctx.request_repaint();
+        }
+        egui::TopBottomPanel::top("groups").show(ctx, |ui| self.toolbar(ui));
+        egui::TopBottomPanel::bottom("status").show(ctx, |ui| self.status_bar(ui));
+        egui::SidePanel::left("dictionary").default_width(260.0).show(ctx, |ui| self.dictionary(ui));
+        egui::CentralPanel::default().show(ctx, |ui| match self.tab {
+            Tab::Trace => self.trace(ui),
+            Tab::Timeline => self.timeline(ui),
+        });
+    }
+}
+
+/// Color of a group's rows, after the console colors of `qspy`.
+fn group_color(group: RecordGroup) -> Color32 {
+    match group {
+        RecordGroup::Sm => Color32::from_rgb(0x98, 0xc3, 0x79),
+        RecordGroup::Ao => Color32::from_rgb(0xe5, 0xc0, 0x7b),
+        RecordGroup::Eq | RecordGroup::Mp | RecordGroup::Te | RecordGroup::Qf => Color32::from_rgb(0xc6, 0x78, 0xdd),
+        RecordGroup::Sc => Color32::from_rgb(0x61, 0xaf, 0xef),
+        RecordGroup::Sem | RecordGroup::Mtx => Color32::from_rgb(0x8c, 0xc8, 0xff),
+        RecordGroup::Usr => Color32::WHITE,
+        RecordGroup::Info => Color32::GRAY,
+    }
+}
+
+/// A stable pastel per state handler, so a state keeps its color across rows.
+fn state_color(state: u64) -> Color32 {
+    let h = state.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 40;
+    let channel = |shift: u32| 0x90 + ((h >> shift) & 0x5F) as u8;
+    Color32::from_rgb(channel(0), channel(8), channel(16))
+}
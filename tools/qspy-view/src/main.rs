@@ -0,0 +1,94 @@
+//! `qspy-view`: a window onto a live or saved QS trace.
+//!
+//! ```text
+//! qspy-view -f run.qs            # a capture saved with `qspy -s`
+//! qspy-view -t 0.0.0.0:6601      # a target connecting over TCP
+//! qspy-view --udp 0.0.0.0:7701   # a target sending datagrams
+//! ```
+//!
+//! Frames are decoded with the same `FrameInterpreter` as the `qspy`
+//! console, so the lines read the same.
+
+mod app;
+mod model;
+mod source;
+
+#[cfg(test)]
+mod tests;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+use eframe::egui;
+use qspy::FrameInterpreter;
+
+use crate::app::ViewApp;
+use crate::model::TraceModel;
+use crate::source::Source;
+
+#[derive(Parser, Debug)]
+#[command(name = "qspy-view", about = "Graphical viewer for QS traces")]
+struct Args {
+    /// Open a binary capture saved with `qspy -s`.
+    #[arg(short = 'f', long = "file", value_name = "FILE", conflicts_with_all = ["tcp", "udp"])]
+    file: Option<PathBuf>,
+
+    /// Listen for a target on this TCP address (port 6601 when given alone).
+    #[arg(short = 't', long = "tcp", value_name = "ADDR", num_args = 0..=1,
+          default_missing_value = "0.0.0.0:6601", conflicts_with = "udp")]
+    tcp: Option<String>,
+
+    /// Receive datagrams on this UDP address (the default source).
+    #[arg(long = "udp", value_name = "ADDR")]
+    udp: Option<String>,
+
+    /// Load dictionaries saved by `qspy -d` before the first frame.
+    #[arg(short = 'd', value_name = "FILE")]
+    dictionaries: Option<PathBuf>,
+
+    /// Trace lines kept in memory.
+    #[arg(long = "lines", value_name = "N", default_value_t = model::DEFAULT_CAPACITY)]
+    lines: usize,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let source = match (args.file, args.tcp, args.udp) {
+        (Some(path), _, _) => Source::File(path),
+        (_, Some(addr), _) => Source::Tcp(addr),
+        (_, _, udp) => Source::Udp(udp.unwrap_or_else(|| "0.0.0.0:7701".to_owned())),
+    };
+
+    let mut interp = FrameInterpreter::new();
+    if let Some(path) = &args.dictionaries {
+        if let Err(e) = interp.load_dictionaries(path) {
+            eprintln!("qspy-view: {}: {e}", path.display());
+            return ExitCode::FAILURE;
+        }
+    }
+    let model = TraceModel::with_capacity(interp, args.lines);
+    let title = format!("qspy-view — {}", source.describe());
+
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_title(&title).with_inner_size([1200.0, 760.0]),
+        ..Default::default()
+    };
+    let result = eframe::run_native(
+        "qspy-view",
+        options,
+        Box::new(move |cc| {
+            let ctx = cc.egui_ctx.clone();
+            let describe = source.describe();
+            let frames = source.spawn(move || ctx.request_repaint());
+            Ok(Box::new(ViewApp::new(model, frames, describe)))
+        }),
+    );
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("qspy-view: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Everything the window shows, kept apart from the drawing code.
+//!
+//! [`TraceModel`] runs each frame through a [`FrameInterpreter`] and keeps the
+//! decoded lines, the record groups the user has switched off, and the state
+//! history of every state machine seen in a transition record.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use qs::records::qep;
+use qspy::{FrameInterpreter, Label, QsFrame, RecordGroup};
+
+/// Lines kept before the oldest are dropped.
+pub const DEFAULT_CAPACITY: usize = 100_000;
+
+/// One decoded line of the trace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    /// Position in the whole session, counting dropped rows.
+    pub index: u64,
+    pub group: RecordGroup,
+    pub text:  String,
+}
+
+/// A state one machine was in, from `start` until the next span begins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateSpan {
+    pub start: u64,
+    pub state: u64,
+}
+
+/// One state machine's history.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timeline {
+    pub spans: Vec<StateSpan>,
+}
+
+impl Timeline {
+    /// Adds a state entered at `start`; an unchanged state extends the
+    /// current span.
+    fn enter(&mut self, start: u64, state: u64) {
+        if self.spans.last().is_some_and(|span| span.state == state) {
+            return;
+        }
+        self.spans.push(StateSpan { start, state });
+    }
+}
+
+/// One dictionary entry, for the browser.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DictRow {
+    pub kind:  &'static str,
+    pub name:  String,
+    pub value: String,
+}
+
+pub struct TraceModel {
+    interp:    FrameInterpreter,
+    rows:      VecDeque<Row>,
+    capacity:  usize,
+    next:      u64,
+    hidden:    Vec<RecordGroup>,
+    timelines: BTreeMap<u64, Timeline>,
+    /// Latest timestamp seen, the right edge of the timeline view.
+    now:       u64,
+    frames:    u64,
+}
+
+impl TraceModel {
+    pub fn with_capacity(interp: FrameInterpreter, capacity: usize) -> Self {
+        Self {
+            interp,
+            rows: VecDeque::new(),
+            capacity: capacity.max(1),
+            next: 0,
+            hidden: Vec::new(),
+            timelines: BTreeMap::new(),
+            now: 0,
+            frames: 0,
+        }
+    }
+
+    /// Decodes `frame` and records what it shows.
+    pub fn push(&mut self, frame: &QsFrame) {
+        self.frames += 1;
+        let group = self.interp.group_of(frame.record_type);
+        for text in self.interp.interpret(frame) {
+            if self.rows.len() == self.capacity {
+                self.rows.pop_front();
+            }
+            self.rows.push_back(Row { index: self.next, group, text });
+            self.next += 1;
+        }
+
+        let record = self.interp.decode(frame);
+        if let Some(ts) = record.timestamp() {
+            self.now = self.now.max(ts);
+        }
+        if matches!(frame.record_type, qep::TRAN | qep::INIT_TRAN | qep::TRAN_HIST) {
+            if let (Some(obj), Some(state)) = (record.object(), record.state()) {
+                // A history transition is untimed: it lands at the latest time.
+                let at = record.timestamp().unwrap_or(self.now);
+                self.timelines.entry(obj).or_default().enter(at, state);
+            }
+        }
+    }
+
+    /// Rows of the groups that are switched on, oldest first.
+    pub fn visible(&self) -> Vec<&Row> {
+        self.rows.iter().filter(|row| self.shows(row.group)).collect()
+    }
+
+    /// `true` if rows of `group` are shown. Info rows always are.
+    pub fn shows(&self, group: RecordGroup) -> bool {
+        group == RecordGroup::Info || !self.hidden.contains(&group)
+    }
+
+    pub fn set_shown(&mut self, group: RecordGroup, shown: bool) {
+        self.hidden.retain(|&g| g != group);
+        if !shown {
+            self.hidden.push(group);
+        }
+    }
+
+    /// Forgets the rows and timelines; dictionaries and sizes stay.
+    pub fn clear(&mut self) {
+        self.rows.clear();
+        self.timelines.clear();
+    }
+
+    pub fn timelines(&self) -> &BTreeMap<u64, Timeline> {
+        &self.timelines
+    }
+
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Frames received so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Every dictionary entry whose name contains `search` (any case),
+    /// sorted by kind and name.
+    pub fn dictionary(&self, search: &str) -> Vec<DictRow> {
+        let sizes = self.interp.sizes();
+        let addr = |a: u64, size: u8| qspy::TargetSizes::fmt_addr(a, size);
+        let objects = self.interp.objects().map(|(a, n)| ("Obj", n, addr(a, sizes.obj_ptr_size)));
+        let functions = self.interp.functions().map(|(a, n)| ("Fun", n, addr(a, sizes.fun_ptr_size)));
+        let signals = self.interp.signals().map(|(sig, obj, n)| {
+            let value = match obj {
+                0 => sig.to_string(),
+                obj => format!("{sig} @ {}", self.object_name(obj)),
+            };
+            ("Sig", n, value)
+        });
+        let users = self.interp.user_records().map(|(id, n)| ("Usr", n, id.to_string()));
+        let search = search.to_lowercase();
+        let mut rows: Vec<DictRow> = objects
+            .chain(functions)
+            .chain(signals)
+            .chain(users)
+            .filter(|(_, name, _)| name.to_lowercase().contains(&search))
+            .map(|(kind, name, value)| DictRow { kind, name: name.to_owned(), value })
+            .collect();
+        rows.sort();
+        rows
+    }
+
+    /// Dictionary name of the object at `addr`, or the address.
+    pub fn object_name(&self, addr: u64) -> Label<'_> {
+        self.interp.object_label(addr)
+    }
+
+    /// Dictionary name of the state handler at `addr`, or the address.
+    pub fn state_name(&self, addr: u64) -> Label<'_> {
+        self.interp.function_label(addr)
+    }
+}
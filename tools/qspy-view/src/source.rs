@@ -0,0 +1,118 @@
+//! Where frames come from: a saved capture, a TCP listener or a UDP socket.
+//!
+//! Each source reads on its own thread, runs the bytes through an
+//! [`HdlcDecoder`] and sends the frames to the window, waking it with
+//! `notify` after every read.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::net::{TcpListener, UdpSocket};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use qspy::{HdlcDecoder, QsFrame};
+
+#[derive(Debug, Clone)]
+pub enum Source {
+    /// A binary capture saved with `qspy -s`.
+    File(PathBuf),
+    /// Listen for a target connecting over TCP.
+    Tcp(String),
+    /// Receive datagrams from a target.
+    Udp(String),
+}
+
+impl Source {
+    /// Starts reading. I/O errors end the thread and are sent as the last
+    /// message.
+    pub fn spawn(self, notify: impl Fn() + Send + 'static) -> Receiver<Message> {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut feed = Feed { decoder: HdlcDecoder::new(), tx, notify: &notify };
+            let result = match &self {
+                Source::File(path) => File::open(path).and_then(|file| feed.stream(file)),
+                Source::Tcp(addr) => listen(addr, &mut feed),
+                Source::Udp(addr) => receive(addr, &mut feed),
+            };
+            let end = match result {
+                Ok(()) => Message::Ended,
+                Err(e) => Message::Failed(e.to_string()),
+            };
+            let _ = feed.tx.send(end);
+            notify();
+        });
+        rx
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Source::File(path) => path.display().to_string(),
+            Source::Tcp(addr) => format!("tcp://{addr}"),
+            Source::Udp(addr) => format!("udp://{addr}"),
+        }
+    }
+}
+
+/// What a source sends to the window.
+#[derive(Debug)]
+pub enum Message {
+    Frame(QsFrame),
+    /// A corrupt frame was skipped.
+    Corrupt,
+    /// The source has no more data.
+    Ended,
+    Failed(String),
+}
+
+struct Feed<'a> {
+    decoder: HdlcDecoder,
+    tx:      Sender<Message>,
+    notify:  &'a dyn Fn(),
+}
+
+impl Feed<'_> {
+    /// Decodes `bytes`; `false` once the window has gone away.
+    fn bytes(&mut self, bytes: &[u8]) -> bool {
+        for result in self.decoder.push_bytes(bytes) {
+            let message = result.map_or(Message::Corrupt, Message::Frame);
+            if self.tx.send(message).is_err() {
+                return false;
+            }
+        }
+        (self.notify)();
+        true
+    }
+
+    fn stream(&mut self, mut reader: impl Read) -> io::Result<()> {
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 || !self.bytes(&buf[..n]) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Serves one target connection after another.
+fn listen(addr: &str, feed: &mut Feed<'_>) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    loop {
+        let (stream, _) = listener.accept()?;
+        feed.decoder.reset();
+        // A dropped connection just waits for the next one.
+        let _ = feed.stream(stream);
+    }
+}
+
+fn receive(addr: &str, feed: &mut Feed<'_>) -> io::Result<()> {
+    let socket = UdpSocket::bind(addr)?;
+    let mut buf = [0u8; 65536];
+    loop {
+        let (n, _) = socket.recv_from(&mut buf)?;
+        if !feed.bytes(&buf[..n]) {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use qs::predefined;
+use qs::records::{qep, qf, RecordSizes};
+use qspy::{FrameInterpreter, QsFrame, RecordGroup};
+
+use crate::model::{StateSpan, TraceModel};
+
+/// `RecordSizes` matching `qspy::TargetSizes::default()`.
+const SIZES: RecordSizes =
+    RecordSizes { signal: 2, obj_ptr: 4, fun_ptr: 4, equeue_ctr: 1, time_evt_ctr: 2, mpool_ctr: 2 };
+
+const BLINKY: u64 = 0x2000;
+
+fn timed(record_type: u8, ts: u32, body: &[u8]) -> QsFrame {
+    let mut payload = ts.to_le_bytes().to_vec();
+    payload.extend_from_slice(body);
+    QsFrame { seq: 0, record_type, payload }
+}
+
+fn dict(record_type: u8, addr: u32, name: &str) -> QsFrame {
+    let mut payload = addr.to_le_bytes().to_vec();
+    payload.extend_from_slice(name.as_bytes());
+    payload.push(0);
+    QsFrame { seq: 0, record_type, payload }
+}
+
+fn model() -> TraceModel {
+    TraceModel::with_capacity(FrameInterpreter::new(), 1000)
+}
+
+#[test]
+fn hidden_groups_drop_out_of_the_visible_rows() {
+    let mut model = model();
+    model.push(&dict(predefined::OBJ_DICT, BLINKY as u32, "l_blinky"));
+    model.push(&timed(qep::DISPATCH, 1, &qep::dispatch(BLINKY, 4, 0x100).encode(&SIZES)));
+    model.push(&timed(qf::ACTIVE_POST, 2, &qf::active_post(0, BLINKY, 4, 0, 0, 3, 2).encode(&SIZES)));
+    assert_eq!(model.frames(), 3);
+    let groups = |m: &TraceModel| m.visible().iter().map(|r| r.group).collect::<Vec<_>>();
+    assert_eq!(groups(&model), [RecordGroup::Info, RecordGroup::Sm, RecordGroup::Ao]);
+
+    model.set_shown(RecordGroup::Sm, false);
+    model.set_shown(RecordGroup::Info, false);
+    assert_eq!(groups(&model), [RecordGroup::Info, RecordGroup::Ao]);
+    assert!(model.visible()[1].text.contains("l_blinky"));
+
+    model.set_shown(RecordGroup::Sm, true);
+    assert_eq!(model.visible().len(), 3);
+}
+
+#[test]
+fn transitions_build_a_timeline_per_object() {
+    let mut model = model();
+    model.push(&timed(qep::INIT_TRAN, 5, &qep::init_tran(BLINKY, 0x100).encode(&SIZES)));
+    model.push(&timed(qep::TRAN, 10, &qep::tran(BLINKY, 4, 0x100, 0x140).encode(&SIZES)));
+    // An internal transition or a self-transition starts no new span.
+    model.push(&timed(qep::INTERN_TRAN, 12, &qep::intern_tran(BLINKY, 4, 0x140).encode(&SIZES)));
+    model.push(&timed(qep::TRAN, 20, &qep::tran(BLINKY, 4, 0x140, 0x140).encode(&SIZES)));
+    model.push(&timed(qep::TRAN, 30, &qep::tran(0x3000, 5, 0x200, 0x240).encode(&SIZES)));
+
+    let blinky = &model.timelines()[&BLINKY];
+    assert_eq!(blinky.spans, [StateSpan { start: 5, state: 0x100 }, StateSpan { start: 10, state: 0x140 }]);
+    assert_eq!(model.timelines().len(), 2);
+    assert_eq!(model.now(), 30);
+
+    model.clear();
+    assert!(model.timelines().is_empty() && model.visible().is_empty());
+}
+
+#[test]
+fn old_rows_are_dropped_at_capacity() {
+    let mut model = TraceModel::with_capacity(FrameInterpreter::new(), 2);
+    for ts in 0..5 {
+        model.push(&timed(qep::DISPATCH, ts, &qep::dispatch(BLINKY, 4, 0x100).encode(&SIZES)));
+    }
+    let indices: Vec<u64> = model.visible().iter().map(|r| r.index).collect();
+    assert_eq!(indices, [3, 4]);
+}
+
+#[test]
+fn dictionary_lists_matching_entries_by_kind() {
+    let mut model = model();
+    model.push(&dict(predefined::OBJ_DICT, BLINKY as u32, "l_blinky"));
+    model.push(&dict(predefined::FUN_DICT, 0x100, "Blinky_off"));
+    model.push(&dict(predefined::FUN_DICT, 0x140, "Blinky_on"));
+    model.push(&dict(predefined::OBJ_DICT, 0x3000, "l_table"));
+
+    let names: Vec<_> = model.dictionary("BLINKY").into_iter().map(|r| (r.kind, r.name)).collect();
+    assert_eq!(
+        names,
+        [("Fun", "Blinky_off".to_owned()), ("Fun", "Blinky_on".to_owned()), ("Obj", "l_blinky".to_owned())]
+    );
+    assert_eq!(model.state_name(0x140).to_string(), "Blinky_on");
+    assert_eq!(model.object_name(0x4000).to_string(), "0x00004000");
+}
@@ -0,0 +1,8 @@
+//! Arbitrary bytes through the HDLC decoder, the interpreter and the
+//! record decoder. See `qspy::fuzz::check_stream`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| qspy::fuzz::check_stream_bytes(data));
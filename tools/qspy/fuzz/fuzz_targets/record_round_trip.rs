@@ -0,0 +1,8 @@
+//! Records built by `qs` at arbitrary widths, framed, damaged and decoded
+//! again. See `qspy::fuzz::RoundTrip`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| qspy::fuzz::check_round_trip_bytes(data));
@@ -6,30 +6,32 @@ pub const QS_RX_INFO:           u8 = 0;
 pub const QS_RX_COMMAND:        u8 = 1;
 pub const QS_RX_RESET:          u8 = 2;
 pub const QS_RX_TICK:           u8 = 3;
-#[allow(dead_code)] pub const QS_RX_PEEK:           u8 = 4;
-#[allow(dead_code)] pub const QS_RX_POKE:           u8 = 5;
+pub const QS_RX_PEEK:           u8 = 4;
+pub const QS_RX_POKE:           u8 = 5;
 #[allow(dead_code)] pub const QS_RX_FILL:           u8 = 6;
 #[allow(dead_code)] pub const QS_RX_TEST_SETUP:     u8 = 7;
 #[allow(dead_code)] pub const QS_RX_TEST_TEARDOWN:  u8 = 8;
 #[allow(dead_code)] pub const QS_RX_TEST_PROBE:     u8 = 9;
-#[allow(dead_code)] pub const QS_RX_GLB_FILTER:     u8 = 10;
+pub const QS_RX_GLB_FILTER:     u8 = 10;
 #[allow(dead_code)] pub const QS_RX_LOC_FILTER:     u8 = 11;
 #[allow(dead_code)] pub const QS_RX_AO_FILTER:      u8 = 12;
-#[allow(dead_code)] pub const QS_RX_CURR_OBJ:       u8 = 13;
+pub const QS_RX_CURR_OBJ:       u8 = 13;
 #[allow(dead_code)] pub const QS_RX_CONTINUE:       u8 = 14;
-#[allow(dead_code)] pub const QS_RX_QUERY_CURR:     u8 = 15;
+pub const QS_RX_QUERY_CURR:     u8 = 15;
 #[allow(dead_code)] pub const QS_RX_EVENT:          u8 = 16;
 
-const FLAG: u8 = 0x7E;
-const ESC:  u8 = 0x7D;
-const XOR:  u8 = 0x20;
-
 /// Shared handle to the target's command stream (set when target connects).
 pub type SharedSender = Arc<Mutex<Option<CommandSender>>>;
 
 pub struct CommandSender {
     writer: Box<dyn Write + Send>,
     seq:    u8,
+    /// Global filter the target applies, as far as the host knows: its
+    /// power-up default until a `QS_RX_GLB_FILTER` goes out.
+    glb:    [u8; 16],
+    /// [`FrameInterpreter::target_resets`](crate::FrameInterpreter::target_resets)
+    /// when `glb` was last brought up to date.
+    resets: u32,
 }
 
 impl CommandSender {
@@ -37,7 +39,23 @@ impl CommandSender {
     /// …) as a QS-RX command sender. Callers that need transport-specific
     /// setup (e.g. `TcpStream::set_nodelay`) do it before boxing.
     pub fn new(writer: Box<dyn Write + Send>) -> Self {
-        Self { writer, seq: 0 }
+        Self { writer, seq: 0, glb: [0xFF; 16], resets: 0 }
+    }
+
+    /// The global filter the target was last sent, or its all-records
+    /// default.
+    pub fn glb_filter(&self) -> &[u8; 16] {
+        &self.glb
+    }
+
+    /// Takes note of target resets, which restore the default filter.
+    /// Returns `true` if a filter sent earlier was lost to a reset.
+    pub fn sync_resets(&mut self, resets: u32) -> bool {
+        if resets == self.resets {
+            return false;
+        }
+        self.resets = resets;
+        core::mem::replace(&mut self.glb, [0xFF; 16]) != [0xFF; 16]
     }
 
     pub fn send_info(&mut self) -> io::Result<()> {
@@ -54,7 +72,13 @@ impl CommandSender {
 
     /// Forward a pre-built QS-RX payload verbatim (used by the raw front-end passthrough).
     pub fn send_raw(&mut self, record_id: u8, payload: &[u8]) -> io::Result<()> {
-        self.send(record_id, payload)
+        self.send(record_id, payload)?;
+        if let (QS_RX_GLB_FILTER, [16, mask @ ..]) = (record_id, payload) {
+            if let Ok(mask) = mask.try_into() {
+                self.glb = mask;
+            }
+        }
+        Ok(())
     }
 
     #[allow(dead_code)]
@@ -77,7 +101,15 @@ impl CommandSender {
         self.send(QS_RX_AO_FILTER, &[prio])
     }
 
-    #[allow(dead_code)]
+    /// Makes the object at `addr` the target's current object of `kind`.
+    pub fn send_curr_obj(&mut self, kind: u8, addr: u64) -> io::Result<()> {
+        let mut payload = [0u8; 9];
+        payload[0] = kind;
+        payload[1..].copy_from_slice(&addr.to_le_bytes());
+        self.send(QS_RX_CURR_OBJ, &payload)
+    }
+
+    /// Asks for a `QS_QUERY_DATA` snapshot of the current object of `kind`.
     pub fn send_query_curr(&mut self, kind: u8) -> io::Result<()> {
         self.send(QS_RX_QUERY_CURR, &[kind])
     }
@@ -91,12 +123,37 @@ impl CommandSender {
         self.send(QS_RX_COMMAND, &payload)
     }
 
-    #[allow(dead_code)]
+    /// Reads `num` elements of `size` bytes at `addr + offset`; the target
+    /// answers with `QS_PEEK_DATA`.
+    pub fn send_peek(&mut self, addr: u64, offset: u16, size: u8, num: u8) -> io::Result<()> {
+        let mut payload = [0u8; 12];
+        payload[..8].copy_from_slice(&addr.to_le_bytes());
+        payload[8..10].copy_from_slice(&offset.to_le_bytes());
+        payload[10] = size;
+        payload[11] = num;
+        self.send(QS_RX_PEEK, &payload)
+    }
+
+    /// Writes `data` as `data.len() / size` elements of `size` bytes at
+    /// `addr + offset`.
+    pub fn send_poke(&mut self, addr: u64, offset: u16, size: u8, data: &[u8]) -> io::Result<()> {
+        let num = u8::try_from(data.len() / usize::from(size.max(1)))
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "poke data too long"))?;
+        let mut payload = Vec::with_capacity(12 + data.len());
+        payload.extend_from_slice(&addr.to_le_bytes());
+        payload.extend_from_slice(&offset.to_le_bytes());
+        payload.extend_from_slice(&[size, num]);
+        payload.extend_from_slice(data);
+        self.send(QS_RX_POKE, &payload)
+    }
+
     pub fn send_glb_filter(&mut self, mask: &[u8; 16]) -> io::Result<()> {
         let mut payload = [0u8; 17];
         payload[0] = 16;
         payload[1..].copy_from_slice(mask);
-        self.send(QS_RX_GLB_FILTER, &payload)
+        self.send(QS_RX_GLB_FILTER, &payload)?;
+        self.glb = *mask;
+        Ok(())
     }
 
     #[allow(dead_code)]
@@ -116,28 +173,13 @@ impl CommandSender {
 
 /// HDLC-encode a QS-RX frame: `FLAG [seq] [record_id] [payload] [chk] FLAG`
 fn build_frame(seq: u8, record_id: u8, payload: &[u8]) -> Vec<u8> {
-    // Assemble raw bytes: seq + record_id + payload
-    let mut raw: Vec<u8> = Vec::with_capacity(2 + payload.len() + 1);
-    raw.push(seq);
-    raw.push(record_id);
-    raw.extend_from_slice(payload);
-
-    // Checksum = bitwise-NOT of the running sum over all raw bytes.
-    let sum: u8 = raw.iter().fold(0u8, |a, b| a.wrapping_add(*b));
-    raw.push(!sum);
-
-    // HDLC byte-stuffing inside FLAG delimiters.
-    let mut out = Vec::with_capacity(raw.len() + 4);
-    out.push(FLAG);
-    for byte in raw {
-        if byte == FLAG || byte == ESC {
-            out.push(ESC);
-            out.push(byte ^ XOR);
-        } else {
-            out.push(byte);
-        }
-    }
-    out.push(FLAG);
+    let mut out = Vec::with_capacity(qs::hdlc::max_frame_len(2 + payload.len()) + 1);
+    out.push(qs::hdlc::FLAG);
+    let mut frame = qs::hdlc::FrameEncoder::new(|b| out.push(b));
+    frame.push(seq);
+    frame.push(record_id);
+    frame.extend(payload);
+    frame.finish();
     out
 }
 
@@ -1,6 +1,12 @@
-const FLAG: u8 = 0x7E;
-const ESC: u8 = 0x7D;
-const ESC_XOR: u8 = 0x20;
+use qs::hdlc::{self, Unstuffed, Unstuffer, ESC, ESC_XOR, FLAG};
+
+/// Default for [`HdlcDecoder::max_frame_len`]: far above any record a
+/// target's `max_record_len` lets through, but a bound on what a stream
+/// that lost its flags can make the decoder hold.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 64 * 1024;
+
+/// Shortest frame: sequence number, record type and checksum.
+const MIN_FRAME_LEN: usize = 3;
 
 /// Represents a fully decoded QS frame.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -13,6 +19,38 @@ pub struct QsFrame {
     pub payload: Vec<u8>,
 }
 
+/// Part of a frame, as [`HdlcDecoder::push_chunks`] delivers it.
+///
+/// A frame that fits in [`max_frame_len`](HdlcDecoder::max_frame_len) comes
+/// as one chunk. A longer one comes as several, each `more` but the last.
+/// The checksum covers the whole frame and is checked when the last chunk
+/// arrives: an `Err` in its place voids the chunks before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameChunk {
+    pub seq: u8,
+    pub record_type: u8,
+    /// Where `payload` starts within the frame's payload.
+    pub offset: usize,
+    pub payload: Vec<u8>,
+    /// `true` if more of the frame follows.
+    pub more: bool,
+}
+
+impl FrameChunk {
+    /// `true` if the chunk is a frame on its own.
+    pub fn is_whole(&self) -> bool {
+        self.offset == 0 && !self.more
+    }
+
+    fn whole(frame: QsFrame) -> Self {
+        Self { seq: frame.seq, record_type: frame.record_type, offset: 0, payload: frame.payload, more: false }
+    }
+
+    fn into_frame(self) -> QsFrame {
+        QsFrame { seq: self.seq, record_type: self.record_type, payload: self.payload }
+    }
+}
+
 /// Errors produced while decoding QS HDLC frames.
 #[derive(Debug, PartialEq, Eq)]
 pub enum DecodeError {
@@ -20,6 +58,12 @@ pub enum DecodeError {
     FrameTooShort(usize),
     /// The trailing checksum did not match the computed value.
     InvalidChecksum { expected: u8, found: u8 },
+    /// An escape was followed by a byte no encoder stuffs, or by the flag.
+    /// Holds that byte as it was on the wire.
+    InvalidEscape(u8),
+    /// A frame of `len` unstuffed bytes, over the decoder's `max`, was
+    /// dropped whole.
+    FrameTooLarge { len: usize, max: usize },
 }
 
 impl core::fmt::Display for DecodeError {
@@ -31,32 +75,83 @@ impl core::fmt::Display for DecodeError {
                 "checksum mismatch: expected {:#04x}, found {:#04x}",
                 expected, found
             ),
+            Self::InvalidEscape(byte) => write!(f, "invalid escape before {:#04x}", byte),
+            Self::FrameTooLarge { len, max } => write!(f, "frame too large (len={}, max={})", len, max),
         }
     }
 }
 
 impl std::error::Error for DecodeError {}
 
+/// A frame being delivered in chunks: its header and what went out so far.
+#[derive(Debug)]
+struct Streamed {
+    seq: u8,
+    record_type: u8,
+    offset: usize,
+    /// Sum of the bytes sent in chunks, for the checksum.
+    sum: u8,
+}
+
 /// Incremental HDLC decoder that accepts arbitrary byte chunks and yields
 /// verified QS frames.
-#[derive(Debug, Default)]
+///
+/// The decoder holds at most [`max_frame_len`](Self::max_frame_len)
+/// unstuffed bytes of a frame. [`push_bytes`](Self::push_bytes) drops a
+/// longer frame and reports it as [`DecodeError::FrameTooLarge`];
+/// [`push_chunks`](Self::push_chunks) passes it on in pieces. Use one or
+/// the other on a decoder.
+#[derive(Debug)]
 pub struct HdlcDecoder {
     buffer: Vec<u8>,
-    escape_next: bool,
+    unstuffer: Unstuffer,
+    escaped: bool,
+    /// First invalid escape in the frame so far.
+    bad_escape: Option<u8>,
+    max_frame_len: usize,
+    streamed: Option<Streamed>,
+    /// Unstuffed length of a frame being dropped for its size.
+    dropped: Option<usize>,
+}
+
+impl Default for HdlcDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl HdlcDecoder {
     pub fn new() -> Self {
+        Self::with_max_frame_len(DEFAULT_MAX_FRAME_LEN)
+    }
+
+    /// A decoder for frames of up to `max` unstuffed bytes: sequence number,
+    /// record type, payload and checksum. `max` is at least 3.
+    pub fn with_max_frame_len(max: usize) -> Self {
         Self {
             buffer: Vec::new(),
-            escape_next: false,
+            unstuffer: Unstuffer::new(),
+            escaped: false,
+            bad_escape: None,
+            max_frame_len: max.max(MIN_FRAME_LEN),
+            streamed: None,
+            dropped: None,
         }
     }
 
+    /// Longest frame held whole, in unstuffed bytes.
+    pub fn max_frame_len(&self) -> usize {
+        self.max_frame_len
+    }
+
     /// Clears any partial frame state.
     pub fn reset(&mut self) {
         self.buffer.clear();
-        self.escape_next = false;
+        self.unstuffer.reset();
+        self.escaped = false;
+        self.bad_escape = None;
+        self.streamed = None;
+        self.dropped = None;
     }
 
     /// Feeds raw bytes into the decoder and returns the outcome of every
@@ -68,35 +163,121 @@ impl HdlcDecoder {
     /// `Err` in place and decoding continues with the next frame, instead of
     /// aborting the whole call and silently dropping every frame still left
     /// in `input`.
+    ///
+    /// Only [`FLAG`] and [`ESC`] are ever stuffed. Any other byte after an
+    /// escape, or an escape right before the flag, fails the frame with
+    /// [`DecodeError::InvalidEscape`] even if the checksum happens to add up,
+    /// so every frame returned is exactly what an encoder would have sent.
+    ///
+    /// A frame over [`max_frame_len`](Self::max_frame_len) is dropped as it
+    /// grows and reported as [`DecodeError::FrameTooLarge`] at its flag.
     pub fn push_bytes(&mut self, input: &[u8]) -> Vec<Result<QsFrame, DecodeError>> {
         let mut results = Vec::new();
+        self.push(input, false, &mut results);
+        results.into_iter().map(|result| result.map(FrameChunk::into_frame)).collect()
+    }
+
+    /// Like [`push_bytes`](Self::push_bytes), but a frame over
+    /// [`max_frame_len`](Self::max_frame_len) is delivered in chunks as it
+    /// arrives, none longer than the limit, instead of being dropped.
+    ///
+    /// For memory dumps and other records too large to hold, or to show
+    /// before they have arrived in full. A frame that fits still comes whole.
+    pub fn push_chunks(&mut self, input: &[u8]) -> Vec<Result<FrameChunk, DecodeError>> {
+        let mut results = Vec::new();
+        self.push(input, true, &mut results);
+        results
+    }
 
+    fn push(&mut self, input: &[u8], stream: bool, results: &mut Vec<Result<FrameChunk, DecodeError>>) {
         for &byte in input {
-            if byte == FLAG {
+            match self.unstuffer.push(byte) {
                 // A FLAG always terminates framing; a dangling escape from a
                 // corrupt prior frame must not bleed into the next one.
-                self.escape_next = false;
-                if !self.buffer.is_empty() {
-                    let frame_bytes = std::mem::take(&mut self.buffer);
-                    results.push(Self::decode_frame(&frame_bytes));
+                Unstuffed::End => {
+                    if std::mem::take(&mut self.escaped) {
+                        self.bad_escape.get_or_insert(FLAG);
+                    }
+                    if let Some(result) = self.end_frame() {
+                        results.push(result);
+                    }
+                }
+                Unstuffed::Byte(b) => {
+                    if std::mem::take(&mut self.escaped) && b != FLAG && b != ESC {
+                        self.bad_escape.get_or_insert(b ^ ESC_XOR);
+                    }
+                    self.body_byte(b, stream, results);
                 }
-                continue;
+                Unstuffed::Escape => self.escaped = true,
             }
+        }
+    }
 
-            if self.escape_next {
-                self.buffer.push(byte ^ ESC_XOR);
-                self.escape_next = false;
-                continue;
+    fn body_byte(&mut self, b: u8, stream: bool, results: &mut Vec<Result<FrameChunk, DecodeError>>) {
+        if let Some(len) = self.dropped.as_mut() {
+            *len += 1;
+            return;
+        }
+        self.buffer.push(b);
+        if self.buffer.len() <= self.max_frame_len {
+            return;
+        }
+        if !stream {
+            self.dropped = Some(self.buffer.len());
+            self.buffer = Vec::new();
+            return;
+        }
+        // The last byte stays behind: it may turn out to be the checksum.
+        let held = self.buffer.pop().unwrap_or_default();
+        let sum = self.buffer.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+        let mut streamed = match self.streamed.take() {
+            Some(streamed) => streamed,
+            None => {
+                let (seq, record_type) = (self.buffer[0], self.buffer[1]);
+                self.buffer.drain(..2);
+                Streamed { seq, record_type, offset: 0, sum: 0 }
             }
+        };
+        let payload = std::mem::replace(&mut self.buffer, vec![held]);
+        let offset = streamed.offset;
+        streamed.offset += payload.len();
+        streamed.sum = streamed.sum.wrapping_add(sum);
+        results.push(Ok(FrameChunk { seq: streamed.seq, record_type: streamed.record_type, offset, payload, more: true }));
+        self.streamed = Some(streamed);
+    }
 
-            if byte == ESC {
-                self.escape_next = true;
-            } else {
-                self.buffer.push(byte);
-            }
+    /// The outcome of the frame a flag just closed, if it had any bytes.
+    fn end_frame(&mut self) -> Option<Result<FrameChunk, DecodeError>> {
+        let frame_bytes = std::mem::take(&mut self.buffer);
+        let streamed = self.streamed.take();
+        let dropped = self.dropped.take();
+        if let Some(byte) = self.bad_escape.take() {
+            return Some(Err(DecodeError::InvalidEscape(byte)));
+        }
+        if let Some(len) = dropped {
+            return Some(Err(DecodeError::FrameTooLarge { len, max: self.max_frame_len }));
+        }
+        match streamed {
+            None if frame_bytes.is_empty() => None,
+            None => Some(Self::decode_frame(&frame_bytes).map(FrameChunk::whole)),
+            Some(streamed) => Some(Self::last_chunk(streamed, &frame_bytes)),
         }
+    }
 
-        results
+    /// Checks the checksum of a streamed frame against the `rest` of it.
+    fn last_chunk(streamed: Streamed, rest: &[u8]) -> Result<FrameChunk, DecodeError> {
+        let (&found, payload) = rest.split_last().unwrap_or((&0, &[]));
+        let expected = !payload.iter().fold(streamed.sum, |sum, &b| sum.wrapping_add(b));
+        if found != expected {
+            return Err(DecodeError::InvalidChecksum { expected, found });
+        }
+        Ok(FrameChunk {
+            seq: streamed.seq,
+            record_type: streamed.record_type,
+            offset: streamed.offset,
+            payload: payload.to_vec(),
+            more: false,
+        })
     }
 
     fn decode_frame(data: &[u8]) -> Result<QsFrame, DecodeError> {
@@ -107,11 +288,7 @@ impl HdlcDecoder {
         let payload = &data[..data.len() - 1];
         let checksum = data[data.len() - 1];
 
-        let mut sum: u8 = 0;
-        for byte in payload {
-            sum = sum.wrapping_add(*byte);
-        }
-        let expected = !sum;
+        let expected = hdlc::checksum(payload);
 
         if checksum != expected {
             return Err(DecodeError::InvalidChecksum {
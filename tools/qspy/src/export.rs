@@ -0,0 +1,239 @@
+//! Columnar data export (the QSPY "MATLAB output" equivalent).
+//!
+//! Selected trace records are flattened into numeric rows, one output file
+//! per record group so every file has a uniform column layout that plots
+//! directly: queue depth over time from `ao`/`eq`, pool usage from `mp`,
+//! RTC latency from consecutive `qep` dispatch timestamps, and so on.
+//!
+//! Two flavours are written:
+//! - **MATLAB** (`--matlab`): whitespace-separated numbers, `%`-commented
+//!   header, missing values as `NaN` — loadable with `load -ascii`.
+//! - **CSV** (`--csv`): comma-separated with a header row, missing values
+//!   left empty.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use qs::records::{qep, qf, sched};
+
+use crate::cursor::Cursor;
+use crate::sizes::TargetSizes;
+use crate::QsFrame;
+
+/// Output flavour of an [`Exporter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Matlab,
+    Csv,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Matlab => "mat",
+            Self::Csv    => "csv",
+        }
+    }
+}
+
+/// Record group sharing one columnar schema (and one output file).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExportGroup {
+    /// QEP dispatch / transitions: `time rec sig obj state`.
+    Qep,
+    /// Active-object queue activity: `time rec sig obj free min`.
+    Active,
+    /// Raw event queues: `time rec sig obj free min`.
+    Equeue,
+    /// Memory pools: `time rec obj free min`.
+    Mpool,
+    /// Scheduler: `time rec prio prev`.
+    Sched,
+}
+
+impl ExportGroup {
+    /// Short name used as the output file suffix.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Qep    => "qep",
+            Self::Active => "ao",
+            Self::Equeue => "eq",
+            Self::Mpool  => "mp",
+            Self::Sched  => "sched",
+        }
+    }
+
+    /// Column names of this group's schema, in row order.
+    pub fn columns(self) -> &'static [&'static str] {
+        match self {
+            Self::Qep    => &["time", "rec", "sig", "obj", "state"],
+            Self::Active => &["time", "rec", "sig", "obj", "free", "min"],
+            Self::Equeue => &["time", "rec", "sig", "obj", "free", "min"],
+            Self::Mpool  => &["time", "rec", "obj", "free", "min"],
+            Self::Sched  => &["time", "rec", "prio", "prev"],
+        }
+    }
+}
+
+/// One exported row: the group it belongs to and its values in schema order
+/// (`None` where the record does not carry that column).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportRow {
+    pub group:  ExportGroup,
+    pub values: Vec<Option<u64>>,
+}
+
+/// Flatten a frame into an [`ExportRow`], or `None` if the record is not
+/// exported (dictionaries, user records, malformed payloads, …).
+pub fn decode_row(frame: &QsFrame, sizes: &TargetSizes) -> Option<ExportRow> {
+    let rec = frame.record_type;
+    let mut cur = Cursor::new(&frame.payload);
+    let ts = cur.read_sized(sizes.time_size)?;
+    let rec64 = Some(rec as u64);
+
+    let (group, values) = match rec {
+        qep::DISPATCH | qep::INTERN_TRAN | qep::IGNORED | qep::TRAN => {
+            let sig   = cur.read_sized(sizes.signal_size)?;
+            let obj   = cur.read_sized(sizes.obj_ptr_size)?;
+            let state = cur.read_sized(sizes.fun_ptr_size)?;
+            (ExportGroup::Qep, vec![Some(ts), rec64, Some(sig), Some(obj), Some(state)])
+        }
+        qf::ACTIVE_POST | qf::ACTIVE_POST_LIFO | qf::ACTIVE_POST_ATTEMPT => {
+            let sig = cur.read_sized(sizes.signal_size)?;
+            let _sdr = cur.read_sized(sizes.obj_ptr_size)?;
+            let ao  = cur.read_sized(sizes.obj_ptr_size)?;
+            cur.read_bytes(2)?; // pool, ref
+            let free = cur.read_sized(sizes.equeue_ctr)?;
+            let min  = cur.read_sized(sizes.equeue_ctr)?;
+            (ExportGroup::Active, vec![Some(ts), rec64, Some(sig), Some(ao), Some(free), Some(min)])
+        }
+        qf::ACTIVE_GET => {
+            let sig = cur.read_sized(sizes.signal_size)?;
+            let ao  = cur.read_sized(sizes.obj_ptr_size)?;
+            cur.read_bytes(2)?;
+            let free = cur.read_sized(sizes.equeue_ctr)?;
+            (ExportGroup::Active, vec![Some(ts), rec64, Some(sig), Some(ao), Some(free), None])
+        }
+        qf::EQUEUE_POST | qf::EQUEUE_POST_LIFO | qf::EQUEUE_POST_ATTEMPT => {
+            let sig = cur.read_sized(sizes.signal_size)?;
+            let eq  = cur.read_sized(sizes.obj_ptr_size)?;
+            cur.read_bytes(2)?;
+            let free = cur.read_sized(sizes.equeue_ctr)?;
+            let min  = cur.read_sized(sizes.equeue_ctr)?;
+            (ExportGroup::Equeue, vec![Some(ts), rec64, Some(sig), Some(eq), Some(free), Some(min)])
+        }
+        qf::EQUEUE_GET => {
+            let sig = cur.read_sized(sizes.signal_size)?;
+            let eq  = cur.read_sized(sizes.obj_ptr_size)?;
+            cur.read_bytes(2)?;
+            let free = cur.read_sized(sizes.equeue_ctr)?;
+            (ExportGroup::Equeue, vec![Some(ts), rec64, Some(sig), Some(eq), Some(free), None])
+        }
+        qf::MPOOL_GET | qf::MPOOL_GET_ATTEMPT => {
+            let mp   = cur.read_sized(sizes.obj_ptr_size)?;
+            let free = cur.read_sized(sizes.mpool_ctr)?;
+            let min  = cur.read_sized(sizes.mpool_ctr)?;
+            (ExportGroup::Mpool, vec![Some(ts), rec64, Some(mp), Some(free), Some(min)])
+        }
+        qf::MPOOL_PUT => {
+            let mp   = cur.read_sized(sizes.obj_ptr_size)?;
+            let free = cur.read_sized(sizes.mpool_ctr)?;
+            (ExportGroup::Mpool, vec![Some(ts), rec64, Some(mp), Some(free), None])
+        }
+        sched::LOCK | sched::UNLOCK | sched::NEXT => {
+            let a = cur.read_u8()?;
+            let b = cur.read_u8()?;
+            (ExportGroup::Sched, vec![Some(ts), rec64, Some(a as u64), Some(b as u64)])
+        }
+        sched::IDLE => {
+            let prev = cur.read_u8()?;
+            (ExportGroup::Sched, vec![Some(ts), rec64, Some(0), Some(prev as u64)])
+        }
+        _ => return None,
+    };
+    Some(ExportRow { group, values })
+}
+
+/// Render a row in the given format (no trailing newline).
+pub fn format_row(format: ExportFormat, values: &[Option<u64>]) -> String {
+    let (sep, missing) = match format {
+        ExportFormat::Matlab => (" ", "NaN"),
+        ExportFormat::Csv    => (",", ""),
+    };
+    values
+        .iter()
+        .map(|v| v.map_or_else(|| missing.to_string(), |v| v.to_string()))
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+/// Render the schema header of a group in the given format.
+pub fn format_header(format: ExportFormat, group: ExportGroup) -> String {
+    match format {
+        ExportFormat::Matlab => format!("% {}: {}", group.name(), group.columns().join(" ")),
+        ExportFormat::Csv    => group.columns().join(","),
+    }
+}
+
+type Opener = Box<dyn FnMut(ExportGroup) -> io::Result<Box<dyn Write + Send>> + Send>;
+
+/// Writes exported rows to one lazily-opened file per [`ExportGroup`].
+pub struct Exporter {
+    format:  ExportFormat,
+    open:    Opener,
+    writers: HashMap<ExportGroup, Box<dyn Write + Send>>,
+}
+
+impl Exporter {
+    /// Export next to `base`: group files are named `<stem>_<group>.<ext>`.
+    /// An empty `base` picks a timestamped `qspy…` stem.
+    pub fn create(format: ExportFormat, base: &Path) -> Self {
+        let stem = if base.as_os_str().is_empty() {
+            PathBuf::from(crate::output::timestamped_name(format.extension()))
+                .with_extension("")
+        } else {
+            base.with_extension("")
+        };
+        println!("{} export: {}_*.{}",
+                 if format == ExportFormat::Csv { "csv" } else { "matlab" },
+                 stem.display(), format.extension());
+        let ext = format.extension();
+        Self::with_opener(format, Box::new(move |group| {
+            let mut name = stem.clone().into_os_string();
+            name.push(format!("_{}.{ext}", group.name()));
+            let f = File::create(PathBuf::from(name))?;
+            Ok(Box::new(BufWriter::with_capacity(64 * 1024, f)) as Box<dyn Write + Send>)
+        }))
+    }
+
+    /// Export through a caller-supplied writer factory (one call per group).
+    pub fn with_opener(format: ExportFormat, open: Opener) -> Self {
+        Self { format, open, writers: HashMap::new() }
+    }
+
+    pub fn format(&self) -> ExportFormat { self.format }
+
+    /// Export `frame` if its record belongs to a known group.
+    pub fn write_frame(&mut self, frame: &QsFrame, sizes: &TargetSizes) -> io::Result<()> {
+        let Some(row) = decode_row(frame, sizes) else { return Ok(()) };
+        let format = self.format;
+        let w = match self.writers.entry(row.group) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => {
+                let mut w = (self.open)(row.group)?;
+                writeln!(w, "{}", format_header(format, row.group))?;
+                e.insert(w)
+            }
+        };
+        writeln!(w, "{}", format_row(format, &row.values))
+    }
+
+    pub fn flush(&mut self) {
+        for w in self.writers.values_mut() {
+            let _ = w.flush();
+        }
+    }
+}
@@ -0,0 +1,513 @@
+//! Invariants of the decoding path, checked on arbitrary input.
+//!
+//! The property tests and the `cargo fuzz` targets in `tools/qspy/fuzz`
+//! share these checks; each one panics on the first broken invariant, which
+//! is how both report a failure.
+//!
+//! [`check_stream`] takes any bytes at all. However they are split between
+//! calls, [`HdlcDecoder`] must report the same frames, and every frame it
+//! accepts must be byte for byte what `qs::encode_frame` sends for it. The
+//! interpreter and [`DecodedRecord`] must get through all of them without a
+//! panic. Under a frame-size limit exactly the longer frames fail, and
+//! streamed chunks join up into the frames a decoder without one returns.
+//!
+//! [`RoundTrip`] starts from the other end: records built with the
+//! `qs::records` builders at arbitrary field widths, framed as a target
+//! frames them. Each must come back in order with consecutive sequence
+//! numbers, decode as complete and read every field back, by name, with
+//! the value it was built with. A damaged frame must not take any other
+//! frame with it.
+//!
+//! ```
+//! use qs::records::RecordSizes;
+//! use qspy::fuzz::{self, RoundTrip};
+//!
+//! let sizes = RecordSizes { signal: 1, obj_ptr: 2, fun_ptr: 8, equeue_ctr: 4, time_evt_ctr: 1, mpool_ctr: 2 };
+//! let samples: Vec<_> = (0..fuzz::SAMPLE_KINDS).map(|kind| fuzz::sample(kind, 7, [0x1234_5678_9ABC; 7], &sizes)).collect();
+//! let trip = RoundTrip { sizes, first_seq: 250, ..RoundTrip::default() };
+//! trip.check(&samples);
+//! trip.check_damaged(&samples, 3, 5, 0x7E);
+//! ```
+//!
+//! Object and function fields are compared as built, so the checks assume
+//! `qs::symbols` is left in address mode.
+
+use qs::hdlc::{Unstuffed, Unstuffer, FLAG};
+use qs::records::{qep, qf, qf::time_evt, qxk, Predefined, RecordSizes};
+use qs::{encode_frame, QsRecord, TimestampSize};
+
+use crate::{DecodeError, DecodedRecord, FrameChunk, FrameInterpreter, HdlcDecoder, QsFrame, TargetSizes};
+
+/// Decodes `input` whole and in `chunk`-byte pieces and checks the
+/// invariants that hold for any byte stream.
+pub fn check_stream(input: &[u8], chunk: usize) {
+    // The reference decoder has no size limit.
+    let whole = HdlcDecoder::with_max_frame_len(usize::MAX).push_bytes(input);
+    let mut decoder = HdlcDecoder::with_max_frame_len(usize::MAX);
+    let pieces: Vec<_> = input.chunks(chunk.max(1)).flat_map(|c| decoder.push_bytes(c)).collect();
+    assert_eq!(whole, pieces, "decoding depends on how the input is split");
+
+    // Every flag-terminated run of bytes is one frame or one error.
+    let mut segments: Vec<&[u8]> = input.split(|&b| b == FLAG).collect();
+    segments.pop();
+    segments.retain(|s| !s.is_empty());
+    assert_eq!(segments.len(), whole.len(), "frames went missing or appeared");
+
+    let mut interp = FrameInterpreter::new();
+    for (segment, result) in segments.iter().zip(&whole) {
+        let Ok(frame) = result else { continue };
+        let wire = reencode(frame);
+        assert_eq!(wire[..wire.len() - 1], **segment, "accepted a frame no encoder sends: {frame:?}");
+
+        interp.interpret(frame);
+        let record = interp.decode(frame);
+        let _ = (record.fields(), record.object_name(), record.signal_name(), record.state_name());
+    }
+
+    check_limit(input, &segments, &whole, chunk % 64 + 3);
+    let mut decoder = HdlcDecoder::with_max_frame_len(chunk % 64 + 3);
+    let streamed: Vec<_> = input.chunks(chunk.max(1)).flat_map(|c| decoder.push_chunks(c)).collect();
+    assert_eq!(reassemble(streamed, decoder.max_frame_len()), whole, "chunks do not add up to the frames");
+}
+
+/// A decoder limited to `max` bytes drops exactly the longer frames, each
+/// reported with its length.
+fn check_limit(input: &[u8], segments: &[&[u8]], whole: &[Result<QsFrame, DecodeError>], max: usize) {
+    let limited = HdlcDecoder::with_max_frame_len(max).push_bytes(input);
+    assert_eq!(limited.len(), whole.len(), "frames went missing or appeared at max={max}");
+    for ((segment, full), cut) in segments.iter().zip(whole).zip(&limited) {
+        let len = unstuffed_len(segment);
+        match full {
+            Err(DecodeError::InvalidEscape(_)) => assert_eq!(cut, full),
+            _ if len > max => assert_eq!(*cut, Err(DecodeError::FrameTooLarge { len, max })),
+            _ => assert_eq!(cut, full, "a frame within max={max} changed"),
+        }
+    }
+}
+
+fn unstuffed_len(segment: &[u8]) -> usize {
+    let mut unstuffer = Unstuffer::new();
+    segment.iter().filter(|&&b| matches!(unstuffer.push(b), Unstuffed::Byte(_))).count()
+}
+
+/// Joins chunks from [`HdlcDecoder::push_chunks`] back into frames,
+/// checking each continues where the last left off and fits in `max`.
+fn reassemble(chunks: Vec<Result<FrameChunk, DecodeError>>, max: usize) -> Vec<Result<QsFrame, DecodeError>> {
+    let mut frames = Vec::new();
+    let mut partial: Option<QsFrame> = None;
+    for chunk in chunks {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                partial = None;
+                frames.push(Err(e));
+                continue;
+            }
+        };
+        assert!(chunk.payload.len() <= max, "chunk of {} bytes over max={max}", chunk.payload.len());
+        let frame = partial.get_or_insert_with(|| QsFrame {
+            seq: chunk.seq,
+            record_type: chunk.record_type,
+            payload: Vec::new(),
+        });
+        assert_eq!(
+            (frame.seq, frame.record_type, frame.payload.len()),
+            (chunk.seq, chunk.record_type, chunk.offset),
+            "chunk does not continue its frame",
+        );
+        frame.payload.extend(chunk.payload);
+        if !chunk.more {
+            frames.extend(partial.take().map(Ok));
+        }
+    }
+    frames
+}
+
+/// Runs [`check_stream`] on fuzzer input: the first byte picks the chunk
+/// size, the rest is the stream.
+pub fn check_stream_bytes(data: &[u8]) {
+    if let Some((&chunk, input)) = data.split_first() {
+        check_stream(input, usize::from(chunk));
+    }
+}
+
+fn reencode(frame: &QsFrame) -> Vec<u8> {
+    let record = QsRecord {
+        seq: frame.seq,
+        record_type: frame.record_type,
+        timestamp: None,
+        payload: frame.payload.clone(),
+    };
+    encode_frame(&record, TimestampSize::default())
+}
+
+/// Number of distinct records [`sample`] builds.
+pub const SAMPLE_KINDS: u8 = 28;
+
+/// A predefined record and the fields qspy must decode from it.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub record: Predefined,
+    /// Sent when the record is timestamped; cut to the timestamp width.
+    pub timestamp: u32,
+    /// Every field of the record, by the name [`DecodedRecord::field`]
+    /// knows it under, with the value it was built with.
+    pub fields: Vec<(&'static str, u64)>,
+}
+
+/// Builds record `kind` (taken modulo [`SAMPLE_KINDS`]) from `args`, each
+/// cut to the width its field has at `sizes` so it reads back unchanged.
+pub fn sample(kind: u8, timestamp: u32, args: [u64; 7], sizes: &RecordSizes) -> Sample {
+    let a = Args { raw: args, sizes: *sizes };
+    let (record, fields) = match kind % SAMPLE_KINDS {
+        0 => {
+            let (obj, state) = (a.obj(0), a.fun(1));
+            (qep::state_entry(obj, state), vec![("obj", obj), ("state", state)])
+        }
+        1 => {
+            let (obj, state) = (a.obj(0), a.fun(1));
+            (qep::state_exit(obj, state), vec![("obj", obj), ("state", state)])
+        }
+        2 => {
+            let (obj, source, target) = (a.obj(0), a.fun(1), a.fun(2));
+            (qep::state_init(obj, source, target), vec![("obj", obj), ("source", source), ("target", target)])
+        }
+        3 => {
+            let (obj, target) = (a.obj(0), a.fun(1));
+            (qep::init_tran(obj, target), vec![("obj", obj), ("target", target)])
+        }
+        4 => {
+            let (obj, sig, state) = (a.obj(0), a.sig(1), a.fun(2));
+            (qep::intern_tran(obj, sig, state), vec![("sig", sig.into()), ("obj", obj), ("state", state)])
+        }
+        5 => {
+            let (obj, sig, source, target) = (a.obj(0), a.sig(1), a.fun(2), a.fun(3));
+            let fields = vec![("sig", sig.into()), ("obj", obj), ("source", source), ("target", target)];
+            (qep::tran(obj, sig, source, target), fields)
+        }
+        6 => {
+            let (obj, sig, state) = (a.obj(0), a.sig(1), a.fun(2));
+            (qep::ignored(obj, sig, state), vec![("sig", sig.into()), ("obj", obj), ("state", state)])
+        }
+        7 => {
+            let (obj, sig, state) = (a.obj(0), a.sig(1), a.fun(2));
+            (qep::dispatch(obj, sig, state), vec![("sig", sig.into()), ("obj", obj), ("state", state)])
+        }
+        8 => {
+            let (obj, sig, state) = (a.obj(0), a.sig(1), a.fun(2));
+            (qep::unhandled(obj, sig, state), vec![("sig", sig.into()), ("obj", obj), ("state", state)])
+        }
+        9 => {
+            let (obj, source, target) = (a.obj(0), a.fun(1), a.fun(2));
+            (qep::tran_hist(obj, source, target), vec![("obj", obj), ("source", source), ("target", target)])
+        }
+        10 => {
+            let (obj, sig, state, target, passed) = (a.obj(0), a.sig(1), a.fun(2), a.fun(3), a.raw[4] & 1 == 1);
+            let fields =
+                vec![("sig", sig.into()), ("obj", obj), ("state", state), ("target", target), ("passed", passed.into())];
+            (qep::guard(obj, sig, state, target, passed), fields)
+        }
+        11 => {
+            let (sender, ao, sig, pool, ref_ctr) = (a.obj(0), a.obj(1), a.sig(2), a.u8(3), a.u8(4));
+            let (free, min) = (a.eq_ctr(5), a.eq_ctr(6));
+            let fields = vec![
+                ("sig", sig.into()), ("sender", sender), ("ao", ao), ("pool", pool.into()), ("ref", ref_ctr.into()),
+                ("free", free.into()), ("min", min.into()),
+            ];
+            (qf::active_post(sender, ao, sig, pool, ref_ctr, free, min), fields)
+        }
+        12 => {
+            let (ao, sig) = (a.obj(0), a.sig(1));
+            (qf::subscribe(ao, sig), vec![("sig", sig.into()), ("ao", ao)])
+        }
+        13 => {
+            let (ao, sig) = (a.obj(0), a.sig(1));
+            (qf::unsubscribe(ao, sig), vec![("sig", sig.into()), ("ao", ao)])
+        }
+        14 => {
+            let (sender, sig, pool, ref_ctr) = (a.obj(0), a.sig(1), a.u8(2), a.u8(3));
+            let fields = vec![("sender", sender), ("sig", sig.into()), ("pool", pool.into()), ("ref", ref_ctr.into())];
+            (qf::publish(sender, sig, pool, ref_ctr), fields)
+        }
+        15 => {
+            let (sig, pool, ref_ctr) = (a.sig(0), a.u8(1), a.u8(2));
+            (qf::gc(sig, pool, ref_ctr), vec![("sig", sig.into()), ("pool", pool.into()), ("ref", ref_ctr.into())])
+        }
+        16 => {
+            let (sig, pool, ref_ctr) = (a.sig(0), a.u8(1), a.u8(2));
+            let fields = vec![("sig", sig.into()), ("pool", pool.into()), ("ref", ref_ctr.into())];
+            (qf::gc_attempt(sig, pool, ref_ctr), fields)
+        }
+        17 => {
+            let (ctr, rate) = (a.te_ctr(0), a.u8(1));
+            (qf::tick(ctr, rate), vec![("ctr", ctr.into()), ("rate", rate.into())])
+        }
+        18..=20 => {
+            let (te, ao, ctr, interval, rate) = (a.obj(0), a.obj(1), a.te_ctr(2), a.te_ctr(3), a.u8(4));
+            let build = match kind % SAMPLE_KINDS {
+                18 => time_evt::arm,
+                19 => time_evt::disarm,
+                _ => time_evt::rearm,
+            };
+            let fields =
+                vec![("te", te), ("ao", ao), ("ctr", ctr.into()), ("interval", interval.into()), ("rate", rate.into())];
+            (build(te, ao, ctr, interval, rate), fields)
+        }
+        21 => {
+            let (te, ao, rate) = (a.obj(0), a.obj(1), a.u8(2));
+            (time_evt::auto_disarm(te, ao, rate), vec![("te", te), ("ao", ao), ("rate", rate.into())])
+        }
+        22 => {
+            let (te, ao, rate) = (a.obj(0), a.obj(1), a.u8(2));
+            (time_evt::disarm_attempt(te, ao, rate), vec![("te", te), ("ao", ao), ("rate", rate.into())])
+        }
+        23 => {
+            let (te, sig, ao, rate) = (a.obj(0), a.sig(1), a.obj(2), a.u8(3));
+            let fields = vec![("te", te), ("sig", sig.into()), ("ao", ao), ("rate", rate.into())];
+            (time_evt::post(te, sig, ao, rate), fields)
+        }
+        24 => {
+            let (ao, len, status) = (a.obj(0), a.u16(1), a.u8(2));
+            (qf::ao_save(ao, len, status), vec![("ao", ao), ("len", len.into()), ("status", status.into())])
+        }
+        25 => {
+            let (ao, len, status) = (a.obj(0), a.u16(1), a.u8(2));
+            (qf::ao_restore(ao, len, status), vec![("ao", ao), ("len", len.into()), ("status", status.into())])
+        }
+        26 => {
+            let (te, ao, samples, overlaps) = (a.obj(0), a.obj(1), a.u16(2), a.u16(6));
+            let (last, max, mean) = (a.te_ctr(3), a.te_ctr(4), a.te_ctr(5));
+            let fields = vec![
+                ("te", te), ("ao", ao), ("samples", samples.into()), ("last", last.into()), ("max", max.into()),
+                ("mean", mean.into()), ("overlaps", overlaps.into()),
+            ];
+            (qf::timeevt_jitter(te, ao, samples.into(), last, max, mean, overlaps.into()), fields)
+        }
+        _ => {
+            let (thread, size, used, overflow) = (a.u8(0), a.u32(1), a.u32(2), a.u8(3));
+            let fields =
+                vec![("thread", thread.into()), ("size", size.into()), ("used", used.into()), ("overflow", overflow.into())];
+            (qxk::thread_stack(thread, size, used, overflow), fields)
+        }
+    };
+    Sample { record, timestamp, fields }
+}
+
+/// Builder arguments, cut to their field widths.
+struct Args {
+    raw: [u64; 7],
+    sizes: RecordSizes,
+}
+
+impl Args {
+    fn cut(&self, i: usize, width: u8) -> u64 {
+        match width {
+            1..=7 => self.raw[i] & ((1 << (8 * u32::from(width))) - 1),
+            _ => self.raw[i],
+        }
+    }
+
+    fn obj(&self, i: usize) -> u64 {
+        self.cut(i, self.sizes.obj_ptr)
+    }
+
+    fn fun(&self, i: usize) -> u64 {
+        self.cut(i, self.sizes.fun_ptr)
+    }
+
+    fn sig(&self, i: usize) -> u16 {
+        self.cut(i, self.sizes.signal.min(2)) as u16
+    }
+
+    fn eq_ctr(&self, i: usize) -> u32 {
+        self.cut(i, self.sizes.equeue_ctr.min(4)) as u32
+    }
+
+    fn te_ctr(&self, i: usize) -> u32 {
+        self.cut(i, self.sizes.time_evt_ctr.min(4)) as u32
+    }
+
+    fn u8(&self, i: usize) -> u8 {
+        self.raw[i] as u8
+    }
+
+    fn u16(&self, i: usize) -> u16 {
+        self.raw[i] as u16
+    }
+
+    fn u32(&self, i: usize) -> u32 {
+        self.raw[i] as u32
+    }
+}
+
+/// How [`Sample`]s are framed and fed back to the decoder.
+#[derive(Debug, Clone, Copy)]
+pub struct RoundTrip {
+    /// Field widths the records are encoded at and decoded with.
+    pub sizes: RecordSizes,
+    pub time_size: TimestampSize,
+    /// Sequence number of the first frame; later ones count up from it,
+    /// wrapping.
+    pub first_seq: u8,
+    /// Bytes handed to the decoder per call.
+    pub chunk: usize,
+}
+
+impl Default for RoundTrip {
+    fn default() -> Self {
+        Self { sizes: RecordSizes::NATIVE, time_size: TimestampSize::default(), first_seq: 1, chunk: 64 }
+    }
+}
+
+impl RoundTrip {
+    /// Frames `samples`, decodes them and checks each against what it was
+    /// built from. Decoded again in chunks of a few bytes, they must join
+    /// up into the same frames.
+    pub fn check(&self, samples: &[Sample]) {
+        let (wire, _) = self.frame(samples);
+        let frames: Vec<QsFrame> = self
+            .decode(&wire)
+            .into_iter()
+            .enumerate()
+            .map(|(i, result)| result.unwrap_or_else(|e| panic!("frame {i} failed to decode: {e}")))
+            .collect();
+        assert_eq!(frames.len(), samples.len(), "frames went missing or appeared");
+
+        let mut decoder = HdlcDecoder::with_max_frame_len(self.chunk % 32 + 3);
+        let streamed: Vec<_> = wire.chunks(self.chunk.max(1)).flat_map(|c| decoder.push_chunks(c)).collect();
+        let whole: Vec<_> = frames.iter().cloned().map(Ok).collect();
+        assert_eq!(reassemble(streamed, decoder.max_frame_len()), whole, "streamed frames changed");
+
+        let mut interp = FrameInterpreter::with_sizes(self.target_sizes());
+        for (i, (frame, sample)) in frames.iter().zip(samples).enumerate() {
+            assert_eq!(frame.seq, self.first_seq.wrapping_add(i as u8), "sequence broken at frame {i}");
+            assert_eq!(*frame, self.expected_frame(i, sample), "frame {i} changed on the way");
+            interp.interpret(frame);
+            check_fields(&interp.decode(frame), sample, self.time_size);
+        }
+    }
+
+    /// Like [`check`](Self::check), with one byte of frame `frame` XORed
+    /// with `xor` on the wire. The byte is picked by `offset`, never the
+    /// closing flag. Every other frame must still come through intact.
+    pub fn check_damaged(&self, samples: &[Sample], frame: usize, offset: usize, xor: u8) {
+        if samples.is_empty() || xor == 0 {
+            return;
+        }
+        let (mut wire, bounds) = self.frame(samples);
+        let damaged = frame % samples.len();
+        let (start, end) = bounds[damaged];
+        wire[start + offset % (end - start - 1)] ^= xor;
+
+        let results = self.decode(&wire);
+        // A flag put in the middle splits the frame in two.
+        let tail = samples.len() - damaged - 1;
+        assert!(
+            results.len() == samples.len() || results.len() == samples.len() + 1,
+            "damaging frame {damaged} turned {} frames into {}", samples.len(), results.len(),
+        );
+        let intact = results[..damaged].iter().chain(&results[results.len() - tail..]);
+        let neighbours = (0..damaged).chain(damaged + 1..samples.len());
+        for (result, i) in intact.zip(neighbours) {
+            assert_eq!(result.as_ref().ok(), Some(&self.expected_frame(i, &samples[i])), "damage spread to frame {i}");
+        }
+    }
+
+    /// The wire bytes of `samples` and where each frame starts and ends.
+    fn frame(&self, samples: &[Sample]) -> (Vec<u8>, Vec<(usize, usize)>) {
+        let mut wire = Vec::new();
+        let mut bounds = Vec::with_capacity(samples.len());
+        for (i, sample) in samples.iter().enumerate() {
+            let start = wire.len();
+            wire.extend(encode_frame(&self.record(i, sample), self.time_size));
+            bounds.push((start, wire.len()));
+        }
+        (wire, bounds)
+    }
+
+    fn record(&self, i: usize, sample: &Sample) -> QsRecord {
+        let record = &sample.record;
+        QsRecord {
+            seq: self.first_seq.wrapping_add(i as u8),
+            record_type: record.record_type(),
+            timestamp: record.has_timestamp().then(|| cut_timestamp(sample.timestamp, self.time_size)),
+            payload: record.encode(&self.sizes).to_vec(),
+        }
+    }
+
+    fn expected_frame(&self, i: usize, sample: &Sample) -> QsFrame {
+        let record = self.record(i, sample);
+        let width = usize::from(self.time_size.bytes());
+        let mut payload = record.timestamp.map(|ts| ts.to_le_bytes()[..width].to_vec()).unwrap_or_default();
+        payload.extend(&record.payload);
+        QsFrame { seq: record.seq, record_type: record.record_type, payload }
+    }
+
+    fn decode(&self, wire: &[u8]) -> Vec<Result<QsFrame, DecodeError>> {
+        let mut decoder = HdlcDecoder::new();
+        wire.chunks(self.chunk.max(1)).flat_map(|c| decoder.push_bytes(c)).collect()
+    }
+
+    fn target_sizes(&self) -> TargetSizes {
+        TargetSizes {
+            time_size: self.time_size.bytes(),
+            obj_ptr_size: self.sizes.obj_ptr,
+            fun_ptr_size: self.sizes.fun_ptr,
+            signal_size: self.sizes.signal,
+            equeue_ctr: self.sizes.equeue_ctr,
+            timeevt_ctr: self.sizes.time_evt_ctr,
+            mpool_ctr: self.sizes.mpool_ctr,
+            ..TargetSizes::default()
+        }
+    }
+}
+
+fn cut_timestamp(ts: u32, size: TimestampSize) -> u32 {
+    match size {
+        TimestampSize::One => ts & 0xFF,
+        TimestampSize::Two => ts & 0xFFFF,
+        TimestampSize::Four => ts,
+    }
+}
+
+fn check_fields(record: &DecodedRecord<'_>, sample: &Sample, time_size: TimestampSize) {
+    let id = record.record_type();
+    assert!(record.is_complete(), "record {id} did not decode to the end: {record:?}");
+    let timestamp = sample.record.has_timestamp().then(|| u64::from(cut_timestamp(sample.timestamp, time_size)));
+    assert_eq!(record.timestamp(), timestamp, "record {id}: timestamp");
+    assert_eq!(record.fields().len(), sample.fields.len(), "record {id}: fields {:?}", record.fields());
+    for &(name, value) in &sample.fields {
+        assert_eq!(record.field(name).and_then(|v| v.as_u64()), Some(value), "record {id}: field `{name}`");
+    }
+}
+
+/// Runs [`RoundTrip::check`] and [`RoundTrip::check_damaged`] on records
+/// described by fuzzer input: widths, sequence and chunk size up front, then
+/// one kind byte and seven argument words per record.
+pub fn check_round_trip_bytes(data: &[u8]) {
+    const HEADER: usize = 12;
+    const RECORD: usize = 1 + 4 + 7 * 8;
+    let Some((header, mut rest)) = data.split_first_chunk::<HEADER>() else { return };
+    let width = |b: u8, widths: &[u8]| widths[usize::from(b) % widths.len()];
+    let sizes = RecordSizes {
+        signal: width(header[0], &[1, 2, 4]),
+        obj_ptr: width(header[1], &[1, 2, 4, 8]),
+        fun_ptr: width(header[2], &[1, 2, 4, 8]),
+        equeue_ctr: width(header[3], &[1, 2, 4]),
+        time_evt_ctr: width(header[4], &[1, 2, 4]),
+        mpool_ctr: width(header[5], &[1, 2, 4]),
+    };
+    let time_size = [TimestampSize::One, TimestampSize::Two, TimestampSize::Four][usize::from(header[6]) % 3];
+    let trip = RoundTrip { sizes, time_size, first_seq: header[7], chunk: usize::from(header[8]) + 1 };
+
+    let mut samples = Vec::new();
+    while let Some((record, next)) = rest.split_first_chunk::<RECORD>() {
+        let word = |at: usize| u64::from_le_bytes(record[at..at + 8].try_into().unwrap());
+        let timestamp = u32::from_le_bytes(record[1..5].try_into().unwrap());
+        samples.push(sample(record[0], timestamp, core::array::from_fn(|i| word(5 + 8 * i)), &sizes));
+        rest = next;
+    }
+    trip.check(&samples);
+    trip.check_damaged(&samples, usize::from(header[9]), usize::from(header[10]), header[11]);
+}
+
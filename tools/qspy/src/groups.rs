@@ -0,0 +1,58 @@
+//! Record groups: the coarse classification behind `--filter` and console
+//! colors.
+//!
+//! Every record id maps to exactly one [`RecordGroup`]. The mapping lives in
+//! `qs_protocol`, so target-side filter masks agree with the console. It is
+//! made on the numeric record type (plus the user-record dictionary, see
+//! [`FrameInterpreter::group_of`](crate::FrameInterpreter::group_of)), never on
+//! the rendered text, so relabelling a record cannot change which group it
+//! lands in.
+
+pub use qs_protocol::groups::{filter_mask, RecordGroup};
+
+/// Set of groups shown on the console and written to the text output.
+///
+/// [`RecordGroup::Info`] always passes: dictionaries and target resets stay
+/// visible whatever the selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GroupFilter {
+    mask: u16,
+}
+
+impl Default for GroupFilter {
+    fn default() -> Self {
+        Self::allow_all()
+    }
+}
+
+impl GroupFilter {
+    /// Show every group.
+    pub const fn allow_all() -> Self {
+        Self { mask: u16::MAX }
+    }
+
+    /// Parse a comma-separated group list, e.g. `"SC,TE"`.
+    pub fn parse(list: &str) -> Result<Self, String> {
+        let mut mask = bit(RecordGroup::Info);
+        for name in list.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let group = RecordGroup::from_name(name).ok_or_else(|| {
+                let known: Vec<_> = RecordGroup::SELECTABLE.iter().map(|g| g.name()).collect();
+                format!("unknown record group '{name}' (expected {})", known.join(","))
+            })?;
+            mask |= bit(group);
+        }
+        if mask == bit(RecordGroup::Info) {
+            return Err("empty record-group list".to_string());
+        }
+        Ok(Self { mask })
+    }
+
+    /// Returns `true` if records of `group` are shown.
+    pub fn allows(&self, group: RecordGroup) -> bool {
+        self.mask & bit(group) != 0
+    }
+}
+
+fn bit(group: RecordGroup) -> u16 {
+    1 << group as u16
+}
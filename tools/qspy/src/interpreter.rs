@@ -1,12 +1,21 @@
 use std::collections::HashMap;
 use std::io::{self, BufRead, BufReader, Write};
 use std::path::Path;
+use std::rc::Rc;
 
 use crate::cursor::Cursor;
+use crate::groups::RecordGroup;
+use crate::names::{Label, Names};
+use crate::pools::PoolForecast;
+use crate::profile::BatchProfile;
+use crate::record::DecodedRecord;
 use crate::sizes::TargetSizes;
 use crate::QsFrame;
 use qs::predefined;
+use qs_defmt::{Chunk, Reassembler};
+use qs_protocol::TargetInfo;
 use qs::records::{infra, qep, qf, qf::time_evt, qxk, sched};
+use qs::symbols::SymbolMode;
 use qs::{
     FMT_F32, FMT_F64, FMT_FUN, FMT_HEX, FMT_I16, FMT_I32, FMT_I64, FMT_I8_ENUM, FMT_MEM,
     FMT_OBJ, FMT_SIG, FMT_STR, FMT_U16, FMT_U32, FMT_U64, FMT_U8,
@@ -21,12 +30,30 @@ use qs::{
 /// lives in the consuming crate (see [`FrameInterpreter::add_user_formatter`]).
 pub type UserRecordFormatter = Box<dyn Fn(&str, &[String]) -> Option<String>>;
 
+/// Observer of every decoded record (see [`FrameInterpreter::on_record`]).
+pub type RecordHook = dyn Fn(&DecodedRecord<'_>);
+
+/// Renders one defmt frame unwrapped from `QS_DEFMT` records (see the
+/// `qs-defmt` crate), or returns `None` to fall back to a hex dump. Decoding
+/// needs the firmware's ELF, so qspy leaves it to the consuming crate
+/// (typically a `defmt-decoder` table).
+pub type DefmtFrameDecoder = Box<dyn Fn(&[u8]) -> Option<String>>;
+
 /// Translates QS frames into human-readable messages while tracking runtime dictionaries.
 pub struct FrameInterpreter {
     dict:            Dictionaries,
     sizes:           TargetSizes,
     qs_version:      u16,
-    user_formatters: Vec<UserRecordFormatter>,
+    user_formatters: Vec<Rc<UserRecordFormatter>>,
+    record_hooks:    Vec<Rc<RecordHook>>,
+    batches:         BatchProfile,
+    pools:           PoolForecast,
+    defmt:           Reassembler,
+    defmt_decoder:   Option<Rc<DefmtFrameDecoder>>,
+    target_resets:   u32,
+    symbols:         SymbolMode,
+    crit_spans:      Spans,
+    isr_spans:       Spans,
 }
 
 impl Default for FrameInterpreter {
@@ -40,6 +67,15 @@ impl FrameInterpreter {
             sizes: TargetSizes::default(),
             qs_version: 700,
             user_formatters: Vec::new(),
+            record_hooks: Vec::new(),
+            batches: BatchProfile::new(),
+            pools: PoolForecast::new(),
+            defmt: Reassembler::new(),
+            defmt_decoder: None,
+            target_resets: 0,
+            symbols: SymbolMode::Addresses,
+            crit_spans: Spans::default(),
+            isr_spans: Spans::default(),
         }
     }
 
@@ -49,11 +85,115 @@ impl FrameInterpreter {
             sizes,
             qs_version: 700,
             user_formatters: Vec::new(),
+            record_hooks: Vec::new(),
+            batches: BatchProfile::new(),
+            pools: PoolForecast::new(),
+            defmt: Reassembler::new(),
+            defmt_decoder: None,
+            target_resets: 0,
+            symbols: SymbolMode::Addresses,
+            crit_spans: Spans::default(),
+            isr_spans: Spans::default(),
+        }
+    }
+
+    /// A new interpreter for another target of the same kind: same sizes,
+    /// user formatters and console settings as `self`, but none of its
+    /// dictionaries or other state learned from its own target, which may run
+    /// different firmware.
+    pub fn fork(&self) -> Self {
+        Self {
+            dict: Dictionaries::default(),
+            sizes: self.sizes,
+            qs_version: 700,
+            user_formatters: self.user_formatters.clone(),
+            record_hooks: self.record_hooks.clone(),
+            batches: BatchProfile::new(),
+            pools: PoolForecast::new().with_margin(self.pools.margin()),
+            defmt: Reassembler::new(),
+            defmt_decoder: self.defmt_decoder.clone(),
+            target_resets: 0,
+            symbols: self.symbols,
+            crit_spans: Spans::default(),
+            isr_spans: Spans::default(),
         }
     }
 
     pub fn sizes(&self) -> &TargetSizes { &self.sizes }
+    /// Dispatch-batch profile accumulated from `QS_RUN_BATCH` records.
+    pub fn batch_profile(&self) -> &BatchProfile { &self.batches }
+    /// Pool occupancy accumulated from `MPOOL` and `QF_NEW`/`QF_GC` records.
+    pub fn pool_forecast(&self) -> &PoolForecast { &self.pools }
+    /// Safety margin of the pool sizing advice (`0.25` = 25 %).
+    pub fn set_pool_margin(&mut self, margin: f64) {
+        self.pools = std::mem::take(&mut self.pools).with_margin(margin);
+    }
+    /// Pool sizing report, with pools named from the object dictionary.
+    pub fn pool_report(&self) -> Vec<String> {
+        self.pools.summary_lines(|mp| self.object_label(mp).to_string())
+    }
+    /// Resets the target has announced with `QS_TARGET_INFO`.
+    pub fn target_resets(&self) -> u32 { self.target_resets }
     pub fn set_sizes(&mut self, s: TargetSizes) { self.sizes = s; }
+    /// Name of the object at `addr` from `QS_OBJ_DICT` records seen so far.
+    pub fn object_name(&self, addr: u64) -> Option<&str> {
+        self.dict.objects.get(&addr).map(|name| &**name)
+    }
+    /// Name of signal `signal` as seen by the object at `obj`, falling back to
+    /// the object-independent `QS_SIG_DICT` entry.
+    pub fn signal_name(&self, signal: u64, obj: u64) -> Option<&str> {
+        let sig32 = signal as u32;
+        self.dict.signals.get(&(sig32, obj))
+            .or_else(|| self.dict.signals.get(&(sig32, 0)))
+            .map(|name| &**name)
+    }
+    /// Name of the function (state handler) at `addr` from `QS_FUN_DICT`.
+    pub fn function_name(&self, addr: u64) -> Option<&str> {
+        self.dict.functions.get(&addr).map(|name| &**name)
+    }
+    /// What the target's object and function fields carry, as announced by
+    /// `QS_SYM_MODE`: addresses until told otherwise.
+    pub fn symbol_mode(&self) -> SymbolMode { self.symbols }
+    /// The object's dictionary name, or its address (or id); formats without
+    /// allocating.
+    pub fn object_label(&self, addr: u64) -> Label<'_> {
+        self.symbol_label(self.object_name(addr), addr, self.sizes.obj_ptr_size)
+    }
+    /// As [`object_label`](Self::object_label), for signals.
+    pub fn signal_label(&self, signal: u64, obj: u64) -> Label<'_> {
+        Label::or_addr(self.signal_name(signal, obj), signal, self.sizes.signal_size)
+    }
+    /// As [`object_label`](Self::object_label), for functions.
+    pub fn function_label(&self, addr: u64) -> Label<'_> {
+        self.symbol_label(self.function_name(addr), addr, self.sizes.fun_ptr_size)
+    }
+    fn symbol_label<'a>(&self, name: Option<&'a str>, value: u64, size: u8) -> Label<'a> {
+        match (name, self.symbols) {
+            (None, SymbolMode::StableIds) => Label::Id(value),
+            _ => Label::or_addr(name, value, size),
+        }
+    }
+    /// Address of the object named `name` in the object dictionary.
+    pub fn object_addr(&self, name: &str) -> Option<u64> {
+        self.dict.objects.iter().find(|(_, n)| &***n == name).map(|(&addr, _)| addr)
+    }
+    /// Every `QS_OBJ_DICT` entry as `(addr, name)`, in no particular order.
+    pub fn objects(&self) -> impl Iterator<Item = (u64, &str)> {
+        self.dict.objects.iter().map(|(&addr, name)| (addr, &**name))
+    }
+    /// Every `QS_FUN_DICT` entry as `(addr, name)`.
+    pub fn functions(&self) -> impl Iterator<Item = (u64, &str)> {
+        self.dict.functions.iter().map(|(&addr, name)| (addr, &**name))
+    }
+    /// Every `QS_SIG_DICT` entry as `(signal, obj, name)`; `obj` is 0 for
+    /// signals not scoped to an object.
+    pub fn signals(&self) -> impl Iterator<Item = (u64, u64, &str)> {
+        self.dict.signals.iter().map(|(&(sig, obj), name)| (u64::from(sig), obj, &**name))
+    }
+    /// Every `QS_USR_DICT` entry as `(record id, name)`.
+    pub fn user_records(&self) -> impl Iterator<Item = (u8, &str)> {
+        self.dict.users.iter().map(|(&id, name)| (id, &**name))
+    }
     pub fn set_qs_version(&mut self, v: u16) { self.qs_version = v; }
 
     /// Register a project-specific user-record pretty-printer.
@@ -63,10 +203,40 @@ impl FrameInterpreter {
     /// `NAME field0 field1 …` rendering is used. This is how a consuming
     /// crate teaches qspy its own records without qspy depending on them.
     pub fn add_user_formatter(&mut self, formatter: UserRecordFormatter) {
-        self.user_formatters.push(formatter);
+        self.user_formatters.push(Rc::new(formatter));
+    }
+
+    /// Call `hook` with every record [`interpret`](Self::interpret) sees,
+    /// decoded with the sizes and dictionaries known so far, before it is
+    /// formatted. Lets an embedder forward records to a database, an alerting
+    /// system or its own view while qspy keeps printing them. Hooks run in
+    /// registration order; a [`fork`](Self::fork) keeps them.
+    pub fn on_record(&mut self, hook: impl Fn(&DecodedRecord<'_>) + 'static) {
+        self.record_hooks.push(Rc::new(hook));
+    }
+
+    /// Install the decoder for defmt frames carried in `QS_DEFMT` records.
+    pub fn set_defmt_decoder(&mut self, decoder: DefmtFrameDecoder) {
+        self.defmt_decoder = Some(Rc::new(decoder));
+    }
+
+    /// Group of `record_type`, counting records named in the user-record
+    /// dictionary as [`RecordGroup::Usr`] even below id 100.
+    pub fn group_of(&self, record_type: u8) -> RecordGroup {
+        if self.dict.users.contains_key(&record_type) {
+            RecordGroup::Usr
+        } else {
+            RecordGroup::of(record_type)
+        }
     }
 
     pub fn interpret(&mut self, frame: &QsFrame) -> Vec<String> {
+        if !self.record_hooks.is_empty() {
+            let record = self.decode(frame);
+            for hook in &self.record_hooks {
+                hook(&record);
+            }
+        }
         let mut lines = Vec::new();
         match frame.record_type {
             // ── Dictionaries & target info ─────────────────────────────────
@@ -76,6 +246,8 @@ impl FrameInterpreter {
             predefined::FUN_DICT    => self.handle_fun_dict(&frame.payload, &mut lines),
             predefined::USR_DICT    => self.handle_usr_dict(&frame.payload, &mut lines),
             predefined::TARGET_INFO => self.handle_target_info(&frame.payload, &mut lines),
+            predefined::DICT_HASH   => self.handle_dict_hash(&frame.payload, &mut lines),
+            predefined::SYM_MODE    => self.handle_sym_mode(&frame.payload, &mut lines),
 
             // ── QEP: state machine ─────────────────────────────────────────
             qep::STATE_ENTRY  => self.handle_state_entry(&frame.payload, &mut lines),
@@ -88,6 +260,8 @@ impl FrameInterpreter {
             qep::DISPATCH     => self.handle_dispatch(&frame.payload, &mut lines),
             qep::UNHANDLED    => self.handle_unhandled(&frame.payload, &mut lines),
             qep::TRAN_HIST    => self.handle_tran_hist(&frame.payload, &mut lines),
+            qep::CONTRACT_VIOLATION => self.handle_contract_violation(&frame.payload, &mut lines),
+            qep::GUARD        => self.handle_guard(&frame.payload, &mut lines),
 
             // ── QF: active object ─────────────────────────────────────────
             qf::ACTIVE_DEFER         => self.handle_ao_defer_recall(&frame.payload, "AO-Defer ", &mut lines),
@@ -99,6 +273,8 @@ impl FrameInterpreter {
             qf::ACTIVE_GET           => self.handle_ao_get(&frame.payload, &mut lines),
             qf::ACTIVE_GET_LAST      => self.handle_ao_get_last(&frame.payload, &mut lines),
             qf::ACTIVE_POST_ATTEMPT  => self.handle_ao_post(&frame.payload, "AO-PostA", &mut lines),
+            qf::ACTIVE_RECALL_ATTEMPT => self.handle_ao_recall_attempt(&frame.payload, &mut lines),
+            qf::ACTIVE_DEFER_ATTEMPT => self.handle_ao_defer_recall(&frame.payload, "AO-DefrA ", &mut lines),
 
             // ── QF: event queues ─────────────────────────────────────────
             qf::EQUEUE_INIT          => self.handle_equeue_init(&frame.payload, &mut lines),
@@ -111,22 +287,25 @@ impl FrameInterpreter {
             qf::MPOOL_INIT        => self.handle_mpool_init(&frame.payload, &mut lines),
             qf::MPOOL_GET         => self.handle_mpool_get(&frame.payload, &mut lines),
             qf::MPOOL_PUT         => self.handle_mpool_put(&frame.payload, &mut lines),
-            qf::MPOOL_GET_ATTEMPT => self.handle_mpool_get_labeled(&frame.payload, "MP-GetA ", &mut lines),
+            qf::MPOOL_GET_ATTEMPT => self.handle_mpool_get_attempt(&frame.payload, &mut lines),
 
             // ── QF: event lifecycle ───────────────────────────────────────
             qf::PUBLISH    => self.handle_qf_publish(&frame.payload, &mut lines),
             qf::NEW_REF    => self.handle_qf_evt_ref(&frame.payload, "New-Ref ", &mut lines),
             qf::NEW        => self.handle_qf_new(&frame.payload, &mut lines),
-            qf::GC_ATTEMPT => self.handle_qf_gc(&frame.payload, "QF-gcA  ", &mut lines),
-            qf::GC         => self.handle_qf_gc(&frame.payload, "QF-gc   ", &mut lines),
+            qf::NEW_ATTEMPT => self.handle_qf_new_attempt(&frame.payload, &mut lines),
+            qf::GC_ATTEMPT => self.handle_qf_gc_attempt(&frame.payload, &mut lines),
+            qf::GC         => self.handle_qf_gc(&frame.payload, &mut lines),
             qf::TICK       => self.handle_qf_tick(&frame.payload, &mut lines),
             qf::DELETE_REF => self.handle_qf_evt_ref(&frame.payload, "QF-DelRf", &mut lines),
 
             // ── QF: critical section / ISR ────────────────────────────────
-            qf::CRIT_ENTRY => self.handle_crit(&frame.payload, "QF-CritE", &mut lines),
-            qf::CRIT_EXIT  => self.handle_crit(&frame.payload, "QF-CritX", &mut lines),
-            qf::ISR_ENTRY  => self.handle_isr(&frame.payload, "QF-IsrE ", &mut lines),
-            qf::ISR_EXIT   => self.handle_isr(&frame.payload, "QF-IsrX ", &mut lines),
+            qf::CRIT_ENTRY => self.handle_crit(&frame.payload, true, &mut lines),
+            qf::CRIT_EXIT  => self.handle_crit(&frame.payload, false, &mut lines),
+            qf::ISR_ENTRY  => self.handle_isr_span(&frame.payload, true, &mut lines),
+            qf::ISR_EXIT   => self.handle_isr_span(&frame.payload, false, &mut lines),
+            qf::INT_DISABLE => self.handle_isr(&frame.payload, "QF-IntD ", &mut lines),
+            qf::INT_ENABLE  => self.handle_isr(&frame.payload, "QF-IntE ", &mut lines),
 
             // ── QF: time events ───────────────────────────────────────────
             time_evt::ARM            => self.handle_time_evt_arm(&frame.payload, &mut lines),
@@ -139,8 +318,20 @@ impl FrameInterpreter {
             // ── Scheduler ─────────────────────────────────────────────────
             sched::LOCK   => self.handle_sched_lock(&frame.payload, &mut lines),
             sched::UNLOCK => self.handle_sched_unlock(&frame.payload, &mut lines),
+            sched::PREEMPT => self.handle_sched_switch(&frame.payload, "Sch-Pre ", &mut lines),
+            sched::RESTORE => self.handle_sched_switch(&frame.payload, "Sch-Rest", &mut lines),
             sched::NEXT   => self.handle_sched_next(&frame.payload, &mut lines),
             sched::IDLE   => self.handle_sched_idle(&frame.payload, &mut lines),
+            qf::RUN_BATCH => self.handle_run_batch(&frame.payload, &mut lines),
+            qf::RTC_OVERRUN => self.handle_rtc_overrun(&frame.payload, &mut lines),
+            qf::RTC_STATS => self.handle_rtc_stats(&frame.payload, &mut lines),
+            qf::AO_STALL => self.handle_ao_stall(&frame.payload, &mut lines),
+            qf::METRICS => self.handle_metrics(&frame.payload, &mut lines),
+            qf::WDT_RESET => self.handle_wdt_reset(&frame.payload, &mut lines),
+            qf::AO_SAVE    => self.handle_ao_persist(&frame.payload, "AO-Save ", &mut lines),
+            qf::AO_RESTORE => self.handle_ao_persist(&frame.payload, "AO-Rstr ", &mut lines),
+            qf::TIMEEVT_JITTER => self.handle_time_evt_jitter(&frame.payload, &mut lines),
+            qf::EVT_LOG => self.handle_evt_log(&frame.payload, &mut lines),
 
             // ── QXK: semaphore ────────────────────────────────────────────
             qxk::SEM_TAKE          => self.handle_sem(&frame.payload, "Sem-Take ", &mut lines),
@@ -155,6 +346,7 @@ impl FrameInterpreter {
             qxk::MTX_LOCK_ATTEMPT   => self.handle_mtx(&frame.payload, "Mtx-LockA", &mut lines),
             qxk::MTX_BLOCK_ATTEMPT  => self.handle_mtx(&frame.payload, "Mtx-BlkA ", &mut lines),
             qxk::MTX_UNLOCK_ATTEMPT => self.handle_mtx(&frame.payload, "Mtx-UnlkA", &mut lines),
+            qxk::THREAD_STACK       => self.handle_thread_stack(&frame.payload, &mut lines),
 
             // ── Infrastructure / test back-channel ────────────────────────
             infra::TEST_PAUSED => lines.push("           TstPause".to_string()),
@@ -163,6 +355,7 @@ impl FrameInterpreter {
             infra::PEEK_DATA   => self.handle_peek_data(&frame.payload, &mut lines),
             infra::ASSERT_FAIL => self.handle_assert_fail(&frame.payload, &mut lines),
             infra::QF_RUN      => lines.push("           QF RUN".to_string()),
+            infra::OVERFLOW    => self.handle_overflow(&frame.payload, &mut lines),
 
             // ── QSPY back-channel ─────────────────────────────────────────
             infra::TARGET_DONE => lines.push(format!(
@@ -171,6 +364,11 @@ impl FrameInterpreter {
             infra::RX_STATUS => self.handle_rx_status(&frame.payload, &mut lines),
 
             // ── User records ──────────────────────────────────────────────
+            rec if self.dict.users.get(&rec).is_some_and(|name| &**name == qs_defmt::DICT_NAME) => {
+                // Chunks that do not complete a frame print nothing.
+                self.handle_defmt_chunk(&frame.payload, &mut lines);
+                return lines;
+            }
             rec if rec >= 100 || self.dict.users.contains_key(&rec)
                 => self.handle_user_record(rec, &frame.payload, &mut lines),
 
@@ -183,30 +381,6 @@ impl FrameInterpreter {
         lines
     }
 
-    // ── Dictionary string helpers ─────────────────────────────────────────────
-
-    fn obj_str(&self, addr: u64) -> String {
-        self.dict.objects.get(&addr)
-            .cloned()
-            .unwrap_or_else(|| TargetSizes::fmt_addr(addr, self.sizes.obj_ptr_size))
-    }
-
-    fn fun_str(&self, addr: u64) -> String {
-        self.dict.functions.get(&addr)
-            .cloned()
-            .unwrap_or_else(|| TargetSizes::fmt_addr(addr, self.sizes.fun_ptr_size))
-    }
-
-    fn sig_str(&self, signal: u64, obj: u64) -> String {
-        let sig32 = signal as u32;
-        if let Some(name) = self.dict.signals.get(&(sig32, obj))
-            .or_else(|| self.dict.signals.get(&(sig32, 0)))
-        {
-            return name.clone();
-        }
-        TargetSizes::fmt_addr(signal, self.sizes.signal_size)
-    }
-
     // ── Predefined record handlers ────────────────────────────────────────────
 
     fn handle_sig_dict(&mut self, payload: &[u8], lines: &mut Vec<String>) {
@@ -216,7 +390,7 @@ impl FrameInterpreter {
             cur.read_sized(self.sizes.obj_ptr_size),
             cur.read_c_string(),
         ) {
-            self.dict.signals.insert((signal as u32, object), name.clone());
+            self.dict.signals.insert((signal as u32, object), self.dict.names.intern(&name));
             lines.push(format!(
                 "           Sig-Dict {signal:#010X},Obj={obj}->{name}",
                 obj = TargetSizes::fmt_addr(object, self.sizes.obj_ptr_size)
@@ -229,7 +403,7 @@ impl FrameInterpreter {
         if let (Some(addr), Some(name)) =
             (cur.read_sized(self.sizes.obj_ptr_size), cur.read_c_string())
         {
-            self.dict.objects.insert(addr, name.clone());
+            self.dict.objects.insert(addr, self.dict.names.intern(&name));
             lines.push(format!(
                 "           Obj-Dict {addr}->{name}",
                 addr = TargetSizes::fmt_addr(addr, self.sizes.obj_ptr_size)
@@ -242,7 +416,7 @@ impl FrameInterpreter {
         if let (Some(addr), Some(name)) =
             (cur.read_sized(self.sizes.fun_ptr_size), cur.read_c_string())
         {
-            self.dict.functions.insert(addr, name.clone());
+            self.dict.functions.insert(addr, self.dict.names.intern(&name));
             lines.push(format!(
                 "           Fun-Dict {addr}->{name}",
                 addr = TargetSizes::fmt_addr(addr, self.sizes.fun_ptr_size)
@@ -253,7 +427,7 @@ impl FrameInterpreter {
     fn handle_usr_dict(&mut self, payload: &[u8], lines: &mut Vec<String>) {
         let mut cur = Cursor::new(payload);
         if let (Some(id), Some(name)) = (cur.read_u8(), cur.read_c_string()) {
-            self.dict.users.insert(id, name.clone());
+            self.dict.users.insert(id, self.dict.names.intern(&name));
             lines.push(format!("           Usr-Dict {id:03}->{name}"));
         }
     }
@@ -263,34 +437,62 @@ impl FrameInterpreter {
         if let (Some(val), Some(grp), Some(name)) =
             (cur.read_u8(), cur.read_u8(), cur.read_c_string())
         {
-            self.dict.enums.insert((grp, val), name.clone());
+            self.dict.enums.insert((grp, val), self.dict.names.intern(&name));
             lines.push(format!("           Enum-Dict grp={grp} {val}->{name}"));
         }
     }
 
-    fn handle_target_info(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+    fn handle_dict_hash(&mut self, payload: &[u8], lines: &mut Vec<String>) {
         let mut cur = Cursor::new(payload);
-        if let (
-            Some(reset), Some(version),
-            Some(sig_evt), Some(eq_te), Some(mp_sizes), Some(ptr_sizes),
-            Some(time_size), Some(max_active), Some(max_pool_tick),
-            Some(second), Some(minute), Some(hour),
-            Some(day), Some(month), Some(year),
-        ) = (
-            cur.read_u8(),  cur.read_u16(), cur.read_u8(), cur.read_u8(),
-            cur.read_u8(),  cur.read_u8(),  cur.read_u8(), cur.read_u8(),
-            cur.read_u8(),  cur.read_u8(),  cur.read_u8(), cur.read_u8(),
-            cur.read_u8(),  cur.read_u8(),  cur.read_u8(),
-        ) {
-            let stamp = format!("{day:02}{month:02}{year:02}_{hour:02}{minute:02}{second:02}");
-            let reset_tag = if reset == 0xFF { "RST" } else { "INF" };
-            lines.push(format!("########## Trg-{reset_tag}  QP-Ver={version},Build={stamp}"));
-            lines.push(format!(
-                "           Cfg Sig/Evt={sig_evt:#04X} Eq/Te={eq_te:#04X} Mp={mp_sizes:#04X} \
-                 Ptr={ptr_sizes:#04X} Time={time_size:#04X} Active={max_active} \
-                 Pools/Ticks={max_pool_tick:#04X}"
-            ));
-            self.sizes.update_from_target_info(payload);
+        if let (Some(hash), Some(entries)) = (cur.read_u32(), cur.read_u16()) {
+            let held = self.dict_hash();
+            let verdict = if (held.hash(), held.entries()) == (hash, entries) {
+                "dictionaries match".to_string()
+            } else {
+                format!(
+                    "qspy holds {:#010X} entries={}: dictionaries are stale, \
+                     reset the target to resend them",
+                    held.hash(), held.entries()
+                )
+            };
+            lines.push(format!("           Dict-Hash {hash:#010X} entries={entries} ({verdict})"));
+        }
+    }
+
+    fn handle_sym_mode(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        let Some(mode) = SymbolMode::from_payload(payload) else { return };
+        self.symbols = mode;
+        let carried = match mode {
+            SymbolMode::Addresses => "addresses",
+            SymbolMode::StableIds => "stable ids",
+        };
+        lines.push(format!("           Sym-Mode {carried}"));
+    }
+
+    fn handle_target_info(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        let Some(info) = TargetInfo::decode(payload) else { return };
+        let (hour, minute, second) = info.build_time;
+        let (day, month, year) = info.build_date;
+        let stamp = format!("{day:02}{month:02}{year:02}_{hour:02}{minute:02}{second:02}");
+        let reset_tag = if info.is_reset == 0xFF { "RST" } else { "INF" };
+        if info.is_reset == 0xFF {
+            self.target_resets += 1;
+            // A restarted target announces stable ids again if it uses them.
+            self.symbols = SymbolMode::Addresses;
+        }
+        lines.push(format!("########## Trg-{reset_tag}  QP-Ver={},Build={stamp}", info.version));
+        // The packed configuration bytes, as the reference QSpy shows them.
+        let packed = info.encode();
+        lines.push(format!(
+            "           Cfg Sig/Evt={:#04X} Eq/Te={:#04X} Mp={:#04X} \
+             Ptr={:#04X} Time={:#04X} Active={} \
+             Pools/Ticks={:#04X}",
+            packed[3], packed[4], packed[5], packed[6], packed[7], packed[8], packed[9],
+        ));
+        let before = self.sizes;
+        self.sizes.update_from_target_info(&info);
+        if self.sizes != before {
+            lines.push(format!("           Cfg resync: {}", self.sizes));
         }
     }
 
@@ -314,7 +516,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "===RTC===> St-Entry Obj={},State={}",
-                self.obj_str(obj), self.fun_str(state)
+                self.object_label(obj), self.function_label(state)
             ));
         }
     }
@@ -327,7 +529,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "===RTC===> St-Exit  Obj={},State={}",
-                self.obj_str(obj), self.fun_str(state)
+                self.object_label(obj), self.function_label(state)
             ));
         }
     }
@@ -341,7 +543,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "===RTC===> St-Init  Obj={},State={}->{}",
-                self.obj_str(obj), self.fun_str(src), self.fun_str(tgt)
+                self.object_label(obj), self.function_label(src), self.function_label(tgt)
             ));
         }
     }
@@ -355,7 +557,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} Init===> Obj={},State={}",
-                self.obj_str(obj), self.fun_str(tgt)
+                self.object_label(obj), self.function_label(tgt)
             ));
         }
     }
@@ -370,7 +572,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} =>Intern Obj={},Sig={},State={}",
-                self.obj_str(obj), self.sig_str(signal, obj), self.fun_str(state)
+                self.object_label(obj), self.signal_label(signal, obj), self.function_label(state)
             ));
         }
     }
@@ -386,8 +588,8 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} ===>Tran Obj={},Sig={},State={}->{}",
-                self.obj_str(obj), self.sig_str(signal, obj),
-                self.fun_str(src), self.fun_str(tgt)
+                self.object_label(obj), self.signal_label(signal, obj),
+                self.function_label(src), self.function_label(tgt)
             ));
         }
     }
@@ -402,7 +604,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} =>Ignore Obj={},Sig={},State={}",
-                self.obj_str(obj), self.sig_str(signal, obj), self.fun_str(state)
+                self.object_label(obj), self.signal_label(signal, obj), self.function_label(state)
             ));
         }
     }
@@ -417,7 +619,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} Disp===> Obj={},Sig={},State={}",
-                self.obj_str(obj), self.sig_str(signal, obj), self.fun_str(state)
+                self.object_label(obj), self.signal_label(signal, obj), self.function_label(state)
             ));
         }
     }
@@ -431,7 +633,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "===RTC===> =>UnHndl Obj={},Sig={},State={}",
-                self.obj_str(obj), self.sig_str(signal, obj), self.fun_str(state)
+                self.object_label(obj), self.signal_label(signal, obj), self.function_label(state)
             ));
         }
     }
@@ -446,14 +648,52 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "===RTC===> St-Hist  Obj={},State={}->{}",
-                self.obj_str(obj), self.fun_str(src), self.fun_str(tgt)
+                self.object_label(obj), self.function_label(src), self.function_label(tgt)
+            ));
+        }
+    }
+
+    /// `QS_CONTRACT_VIOLATION` (57): [ts | state | limit u32 | elapsed u32],
+    /// limit and elapsed in clock ticks
+    fn handle_contract_violation(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(state), Some(limit), Some(elapsed)) = (
+            cur.read_sized(self.sizes.time_size),
+            cur.read_sized(self.sizes.fun_ptr_size),
+            cur.read_u32(),
+            cur.read_u32(),
+        ) {
+            lines.push(format!(
+                "{ts:010} St-Late  State={},Limit={limit},Elapsed={elapsed}",
+                self.function_label(state)
+            ));
+        }
+    }
+
+    /// `QS_QEP_GUARD` (92): [ts | sig | obj | state | target | passed u8]
+    fn handle_guard(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(signal), Some(obj), Some(state), Some(tgt), Some(passed)) = (
+            cur.read_sized(self.sizes.time_size),
+            cur.read_sized(self.sizes.signal_size),
+            cur.read_sized(self.sizes.obj_ptr_size),
+            cur.read_sized(self.sizes.fun_ptr_size),
+            cur.read_sized(self.sizes.fun_ptr_size),
+            cur.read_u8(),
+        ) {
+            lines.push(format!(
+                "{ts:010} St-Guard Obj={},Sig={},State={}->{},Pass={}",
+                self.object_label(obj), self.signal_label(signal, obj),
+                self.function_label(state), self.function_label(tgt),
+                if passed != 0 { "T" } else { "F" }
             ));
         }
     }
 
     // ── QF: active object handlers ────────────────────────────────────────────
 
-    /// `QS_QF_ACTIVE_DEFER` (10) / `QS_QF_ACTIVE_RECALL` (11): [ts | ao | eq | sig | pool | ref]
+    /// `QS_QF_ACTIVE_DEFER` (10) / `QS_QF_ACTIVE_RECALL` (11) /
+    /// `QS_QF_ACTIVE_DEFER_ATTEMPT` (81): [ts | ao | eq | sig | pool | ref]
     fn handle_ao_defer_recall(&mut self, payload: &[u8], label: &str, lines: &mut Vec<String>) {
         let mut cur = Cursor::new(payload);
         if let (Some(ts), Some(ao), Some(eq), Some(sig), Some(pool), Some(rref)) = (
@@ -465,7 +705,22 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} {label} Obj={},Que={},Evt<Sig={},Pool={pool},Ref={rref}>",
-                self.obj_str(ao), self.obj_str(eq), self.sig_str(sig, ao)
+                self.object_label(ao), self.object_label(eq), self.signal_label(sig, ao)
+            ));
+        }
+    }
+
+    /// `QS_QF_ACTIVE_RECALL_ATTEMPT` (18): [ts | ao | eq] — nothing was deferred
+    fn handle_ao_recall_attempt(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(ao), Some(eq)) = (
+            cur.read_sized(self.sizes.time_size),
+            cur.read_sized(self.sizes.obj_ptr_size),
+            cur.read_sized(self.sizes.obj_ptr_size),
+        ) {
+            lines.push(format!(
+                "{ts:010} AO-RcllA Obj={},Que={}",
+                self.object_label(ao), self.object_label(eq)
             ));
         }
     }
@@ -480,7 +735,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} AO-Subsc Obj={},Sig={}",
-                self.obj_str(ao), self.sig_str(sig, ao)
+                self.object_label(ao), self.signal_label(sig, ao)
             ));
         }
     }
@@ -495,7 +750,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} AO-Unsub Obj={},Sig={}",
-                self.obj_str(ao), self.sig_str(sig, ao)
+                self.object_label(ao), self.signal_label(sig, ao)
             ));
         }
     }
@@ -515,7 +770,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} {label} Sdr={},Obj={},Evt<Sig={},Pool={pool},Ref={rref}>,Que<Free={free},Min={min}>",
-                self.obj_str(sdr), self.obj_str(ao), self.sig_str(sig, ao)
+                self.object_label(sdr), self.object_label(ao), self.signal_label(sig, ao)
             ));
         }
     }
@@ -532,7 +787,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} AO-Get   Obj={},Evt<Sig={},Pool={pool},Ref={rref}>,Que<Free={free}>",
-                self.obj_str(ao), self.sig_str(sig, ao)
+                self.object_label(ao), self.signal_label(sig, ao)
             ));
         }
     }
@@ -548,7 +803,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} AO-GetL  Obj={},Evt<Sig={},Pool={pool},Ref={rref}>",
-                self.obj_str(ao), self.sig_str(sig, ao)
+                self.object_label(ao), self.signal_label(sig, ao)
             ));
         }
     }
@@ -565,7 +820,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} EQ-Init  Obj={},Len={len}",
-                self.obj_str(eq)
+                self.object_label(eq)
             ));
             if !cur.is_empty() {
                 lines.push(format!(
@@ -585,9 +840,10 @@ impl FrameInterpreter {
             cur.read_sized(self.sizes.mpool_ctr),
             cur.read_sized(self.sizes.mpool_ctr),
         ) {
+            self.pools.mpool_init(mp, ts, n_free);
             lines.push(format!(
                 "{ts:010} MP-Init  Obj={},NFree={n_free},NMin={n_min}",
-                self.obj_str(mp)
+                self.object_label(mp)
             ));
             if !cur.is_empty() {
                 lines.push(format!(
@@ -614,7 +870,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} {label} Obj={},Evt<Sig={},Pool={pool},Ref={rref}>,Que<Free={free},Min={min}>",
-                self.obj_str(eq), self.sig_str(sig, eq)
+                self.object_label(eq), self.signal_label(sig, eq)
             ));
         }
     }
@@ -632,7 +888,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} {label} Obj={},Evt<Sig={},Pool={pool},Ref={rref}>,Que<Free={free}>",
-                self.obj_str(eq), self.sig_str(sig, eq)
+                self.object_label(eq), self.signal_label(sig, eq)
             ));
         }
     }
@@ -641,24 +897,37 @@ impl FrameInterpreter {
 
     /// `QS_QF_MPOOL_GET` (24) / `QS_QF_MPOOL_GET_ATTEMPT` (47): [ts | mp | free | min]
     fn handle_mpool_get(&mut self, payload: &[u8], lines: &mut Vec<String>) {
-        self.handle_mpool_get_labeled(payload, "MP-Get  ", lines);
+        if let Some((ts, mp, free, min)) = self.handle_mpool_get_labeled(payload, "MP-Get  ", lines) {
+            self.pools.mpool_get(mp, ts, free, min);
+        }
     }
 
-    fn handle_mpool_get_labeled(&mut self, payload: &[u8], label: &str, lines: &mut Vec<String>) {
-        let mut cur = Cursor::new(payload);
-        if let (Some(ts), Some(mp), Some(free), Some(min)) = (
-            cur.read_sized(self.sizes.time_size),
-            cur.read_sized(self.sizes.obj_ptr_size),
-            cur.read_sized(self.sizes.mpool_ctr),
-            cur.read_sized(self.sizes.mpool_ctr),
-        ) {
-            lines.push(format!(
-                "{ts:010} {label} Obj={},Free={free},Min={min}",
-                self.obj_str(mp)
-            ));
+    fn handle_mpool_get_attempt(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        if let Some((ts, mp, free, _)) = self.handle_mpool_get_labeled(payload, "MP-GetA ", lines) {
+            self.pools.mpool_exhausted(mp, ts, free);
         }
     }
 
+    fn handle_mpool_get_labeled(
+        &self,
+        payload: &[u8],
+        label: &str,
+        lines: &mut Vec<String>,
+    ) -> Option<(u64, u64, u64, u64)> {
+        let mut cur = Cursor::new(payload);
+        let (ts, mp, free, min) = (
+            cur.read_sized(self.sizes.time_size)?,
+            cur.read_sized(self.sizes.obj_ptr_size)?,
+            cur.read_sized(self.sizes.mpool_ctr)?,
+            cur.read_sized(self.sizes.mpool_ctr)?,
+        );
+        lines.push(format!(
+            "{ts:010} {label} Obj={},Free={free},Min={min}",
+            self.object_label(mp)
+        ));
+        Some((ts, mp, free, min))
+    }
+
     /// `QS_QF_MPOOL_PUT` (25): [ts | mp | free]
     fn handle_mpool_put(&mut self, payload: &[u8], lines: &mut Vec<String>) {
         let mut cur = Cursor::new(payload);
@@ -667,9 +936,10 @@ impl FrameInterpreter {
             cur.read_sized(self.sizes.obj_ptr_size),
             cur.read_sized(self.sizes.mpool_ctr),
         ) {
+            self.pools.mpool_put(mp, ts, free);
             lines.push(format!(
                 "{ts:010} MP-Put   Obj={},Free={free}",
-                self.obj_str(mp)
+                self.object_label(mp)
             ));
         }
     }
@@ -687,7 +957,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} QF-Pub   Sdr={},Evt<Sig={},Pool={pool},Ref={rref}>",
-                self.obj_str(sdr), self.sig_str(sig, 0)
+                self.object_label(sdr), self.signal_label(sig, 0)
             ));
         }
     }
@@ -700,33 +970,62 @@ impl FrameInterpreter {
             cur.read_sized(self.sizes.event_size),
             cur.read_sized(self.sizes.signal_size),
         ) {
+            self.pools.event_new(size, sig);
             lines.push(format!(
                 "{ts:010} QF-New   Sig={},Size={size}",
-                self.sig_str(sig, 0)
+                self.signal_label(sig, 0)
             ));
         }
     }
 
-    /// `QS_QF_GC_ATTEMPT` (29) / `QS_QF_GC` (30): [ts | sig | pool | ref]
-    fn handle_qf_gc(&mut self, payload: &[u8], label: &str, lines: &mut Vec<String>) {
+    /// `QS_QF_NEW_ATTEMPT` (89): [ts | size u16 | pool u8 | free u16 | total u16]
+    fn handle_qf_new_attempt(&self, payload: &[u8], lines: &mut Vec<String>) {
         let mut cur = Cursor::new(payload);
-        if let (Some(ts), Some(sig), Some(pool), Some(rref)) = (
+        if let (Some(ts), Some(size), Some(pool), Some(free), Some(total)) = (
             cur.read_sized(self.sizes.time_size),
-            cur.read_sized(self.sizes.signal_size),
-            cur.read_u8(), cur.read_u8(),
+            cur.read_u16(),
+            cur.read_u8(),
+            cur.read_u16(),
+            cur.read_u16(),
         ) {
-            lines.push(format!(
-                "{ts:010} {label} Evt<Sig={},Pool={pool},Ref={rref}>",
-                self.sig_str(sig, 0)
-            ));
+            lines.push(format!("{ts:010} QF-NewA  Size={size},Pool={pool},Free={free}/{total}"));
+        }
+    }
+
+    /// `QS_QF_GC_ATTEMPT` (29) / `QS_QF_GC` (30): [ts | sig | pool | ref]
+    fn handle_qf_gc_labeled(&self, payload: &[u8], label: &str, lines: &mut Vec<String>) -> Option<(u64, u8)> {
+        let mut cur = Cursor::new(payload);
+        let (ts, sig, pool, rref) = (
+            cur.read_sized(self.sizes.time_size)?,
+            cur.read_sized(self.sizes.signal_size)?,
+            cur.read_u8()?, cur.read_u8()?,
+        );
+        lines.push(format!(
+            "{ts:010} {label} Evt<Sig={},Pool={pool},Ref={rref}>",
+            self.signal_label(sig, 0)
+        ));
+        Some((sig, pool))
+    }
+
+    fn handle_qf_gc(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        if let Some((sig, pool)) = self.handle_qf_gc_labeled(payload, "QF-gc   ", lines) {
+            self.pools.event_gc(sig, pool);
         }
     }
 
-    /// `QS_QF_TICK` (31): [ts | rate]
+    fn handle_qf_gc_attempt(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        self.handle_qf_gc_labeled(payload, "QF-gcA  ", lines);
+    }
+
+    /// `QS_QF_TICK` (31): [ts | tick ctr | rate]
     fn handle_qf_tick(&self, payload: &[u8], lines: &mut Vec<String>) {
         let mut cur = Cursor::new(payload);
-        if let (Some(ts), Some(rate)) = (cur.read_sized(self.sizes.time_size), cur.read_u8()) {
-            lines.push(format!("{ts:010} QF-Tick  Rate={rate}"));
+        if let (Some(ts), Some(ctr), Some(rate)) = (
+            cur.read_sized(self.sizes.time_size),
+            cur.read_sized(self.sizes.timeevt_ctr),
+            cur.read_u8(),
+        ) {
+            lines.push(format!("{ts:010} QF-Tick  Rate={rate},Ctr={ctr}"));
         }
     }
 
@@ -740,20 +1039,49 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} {label} Evt<Sig={},Pool={pool},Ref={rref}>",
-                self.sig_str(sig, 0)
+                self.signal_label(sig, 0)
             ));
         }
     }
 
     /// `QS_TR_CRIT_ENTRY` (39) / `QS_TR_CRIT_EXIT` (40): [ts | nesting]
-    fn handle_crit(&self, payload: &[u8], label: &str, lines: &mut Vec<String>) {
+    ///
+    /// An exit matching a seen entry also shows how long the section was
+    /// held, in timestamp ticks.
+    fn handle_crit(&mut self, payload: &[u8], entry: bool, lines: &mut Vec<String>) {
         let mut cur = Cursor::new(payload);
-        if let (Some(ts), Some(nesting)) = (cur.read_sized(self.sizes.time_size), cur.read_u8()) {
-            lines.push(format!("{ts:010} {label} Nesting={nesting}"));
+        let (Some(ts), Some(nesting)) = (cur.read_sized(self.sizes.time_size), cur.read_u8()) else { return };
+        if entry {
+            self.crit_spans.enter(nesting, ts);
+            lines.push(format!("{ts:010} QF-CritE Nesting={nesting}"));
+            return;
+        }
+        match self.crit_spans.exit(nesting, ts, &self.sizes) {
+            Some(held) => lines.push(format!("{ts:010} QF-CritX Nesting={nesting},Held={held}")),
+            None => lines.push(format!("{ts:010} QF-CritX Nesting={nesting}")),
         }
     }
 
     /// `QS_TR_ISR_ENTRY` (41) / `QS_TR_ISR_EXIT` (42): [ts | nesting | prio]
+    ///
+    /// An exit matching a seen entry also shows how long the handler ran,
+    /// nested handlers included.
+    fn handle_isr_span(&mut self, payload: &[u8], entry: bool, lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        let (Some(ts), Some(nesting), Some(prio)) =
+            (cur.read_sized(self.sizes.time_size), cur.read_u8(), cur.read_u8()) else { return };
+        if entry {
+            self.isr_spans.enter(nesting, ts);
+            lines.push(format!("{ts:010} QF-IsrE  Nesting={nesting},Pri={prio}"));
+            return;
+        }
+        match self.isr_spans.exit(nesting, ts, &self.sizes) {
+            Some(ran) => lines.push(format!("{ts:010} QF-IsrX  Nesting={nesting},Pri={prio},Ran={ran}")),
+            None => lines.push(format!("{ts:010} QF-IsrX  Nesting={nesting},Pri={prio}")),
+        }
+    }
+
+    /// `QS_QF_INT_DISABLE` (43) / `QS_QF_INT_ENABLE` (44): [ts | nesting | prio]
     fn handle_isr(&self, payload: &[u8], label: &str, lines: &mut Vec<String>) {
         let mut cur = Cursor::new(payload);
         if let (Some(ts), Some(nesting), Some(prio)) = (
@@ -766,33 +1094,33 @@ impl FrameInterpreter {
 
     // ── QXK semaphore / mutex handlers ───────────────────────────────────────
 
-    /// Semaphore records (71–74): [ts | sem_ptr(8) | thread_prio(1) | count(2)]
+    /// Semaphore records (71–74): [ts | sem | thread_prio(1) | count(2)]
     fn handle_sem(&mut self, payload: &[u8], label: &str, lines: &mut Vec<String>) {
         let mut cur = Cursor::new(payload);
         if let (Some(ts), Some(sem), Some(prio), Some(count)) = (
             cur.read_sized(self.sizes.time_size),
-            cur.read_u64(),
+            cur.read_sized(self.sizes.obj_ptr_size),
             cur.read_u8(),
             cur.read_u16(),
         ) {
             lines.push(format!(
                 "{ts:010} {label} Obj={},Pri={prio},Cnt={count}",
-                self.obj_str(sem)
+                self.object_label(sem)
             ));
         }
     }
 
-    /// Mutex records (75–80): [ts | mtx_ptr(8) | thread_prio(1)]
+    /// Mutex records (75–80): [ts | mtx | thread_prio(1)]
     fn handle_mtx(&mut self, payload: &[u8], label: &str, lines: &mut Vec<String>) {
         let mut cur = Cursor::new(payload);
         if let (Some(ts), Some(mtx), Some(prio)) = (
             cur.read_sized(self.sizes.time_size),
-            cur.read_u64(),
+            cur.read_sized(self.sizes.obj_ptr_size),
             cur.read_u8(),
         ) {
             lines.push(format!(
                 "{ts:010} {label} Obj={},Pri={prio}",
-                self.obj_str(mtx)
+                self.object_label(mtx)
             ));
         }
     }
@@ -811,7 +1139,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} TE{rate}-Arm  Obj={},AO={},Tim={timeout},Int={interval}",
-                self.obj_str(timer), self.obj_str(target)
+                self.object_label(timer), self.object_label(target)
             ));
         }
     }
@@ -825,7 +1153,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "           TE{rate}-ADis Obj={},AO={}",
-                self.obj_str(timer), self.obj_str(target)
+                self.object_label(timer), self.object_label(target)
             ));
         }
     }
@@ -840,7 +1168,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} TE{rate}-DisA Obj={},AO={}",
-                self.obj_str(timer), self.obj_str(target)
+                self.object_label(timer), self.object_label(target)
             ));
         }
     }
@@ -857,7 +1185,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} TE{rate}-Dis  Obj={},AO={},Tim={remaining},Int={interval}",
-                self.obj_str(timer), self.obj_str(target)
+                self.object_label(timer), self.object_label(target)
             ));
         }
     }
@@ -874,7 +1202,7 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} TE{rate}-Rarm Obj={},AO={},Tim={remaining},Int={interval}",
-                self.obj_str(timer), self.obj_str(target)
+                self.object_label(timer), self.object_label(target)
             ));
         }
     }
@@ -890,9 +1218,30 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} TE{rate}-Post Obj={},Sig={},AO={}",
-                self.obj_str(timer),
-                self.sig_str(signal, target),
-                self.obj_str(target)
+                self.object_label(timer),
+                self.signal_label(signal, target),
+                self.object_label(target)
+            ));
+        }
+    }
+
+    /// `QS_QF_TIMEEVT_JITTER` (85): [ts | te | ao | samples: u16 | last |
+    /// max | mean | overlaps: u16], lateness as time-event counters.
+    fn handle_time_evt_jitter(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(timer), Some(target), Some(samples), Some(last), Some(max), Some(mean), Some(overlaps)) = (
+            cur.read_sized(self.sizes.time_size),
+            cur.read_sized(self.sizes.obj_ptr_size),
+            cur.read_sized(self.sizes.obj_ptr_size),
+            cur.read_u16(),
+            cur.read_sized(self.sizes.timeevt_ctr),
+            cur.read_sized(self.sizes.timeevt_ctr),
+            cur.read_sized(self.sizes.timeevt_ctr),
+            cur.read_u16(),
+        ) {
+            lines.push(format!(
+                "{ts:010} TE-Jitr  Obj={},AO={},N={samples},Last={last},Max={max},Mean={mean},Ovl={overlaps}",
+                self.object_label(timer), self.object_label(target)
             ));
         }
     }
@@ -913,7 +1262,7 @@ impl FrameInterpreter {
         if let (Some(ts), Some(prev), Some(new)) =
             (cur.read_sized(self.sizes.time_size), cur.read_u8(), cur.read_u8())
         {
-            lines.push(format!("{ts:010} Sch-Unlk Ceil={new}->{prev}"));
+            lines.push(format!("{ts:010} Sch-Unlk Ceil={prev}->{new}"));
         }
     }
 
@@ -926,6 +1275,16 @@ impl FrameInterpreter {
         }
     }
 
+    /// `QS_SCHED_PREEMPT` / `QS_SCHED_RESTORE`: [ts | prio | prev_prio]
+    fn handle_sched_switch(&self, payload: &[u8], label: &str, lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(prio), Some(prev_prio)) =
+            (cur.read_sized(self.sizes.time_size), cur.read_u8(), cur.read_u8())
+        {
+            lines.push(format!("{ts:010} {label} Pri={prev_prio}->{prio}"));
+        }
+    }
+
     fn handle_sched_idle(&self, payload: &[u8], lines: &mut Vec<String>) {
         let mut cur = Cursor::new(payload);
         if let (Some(ts), Some(prev)) =
@@ -935,6 +1294,186 @@ impl FrameInterpreter {
         }
     }
 
+    /// `QS_RUN_BATCH` (56): [ts | events: u16 | duration_us: u32]
+    fn handle_run_batch(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(events), Some(dur)) =
+            (cur.read_sized(self.sizes.time_size), cur.read_u16(), cur.read_u32())
+        {
+            self.batches.record(u32::from(events), dur);
+            lines.push(format!("{ts:010} Sch-Batch Evts={events},Dur={dur}us"));
+        }
+    }
+
+    /// `QS_RTC_OVERRUN` (82): [ts | prio: u8 | sig: u16 | budget: u32 |
+    /// elapsed: u32], in the target's budget-clock units
+    fn handle_rtc_overrun(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(prio), Some(sig), Some(budget), Some(elapsed)) = (
+            cur.read_sized(self.sizes.time_size),
+            cur.read_u8(),
+            cur.read_u16(),
+            cur.read_u32(),
+            cur.read_u32(),
+        ) {
+            lines.push(format!(
+                "{ts:010} RTC-Over Pri={prio},Sig={},Budget={budget},Elapsed={elapsed}",
+                self.signal_label(u64::from(sig), 0)
+            ));
+        }
+    }
+
+    /// `QS_RTC_STATS` (88): [ts | prio: u8 | steps: u32 | mean: u32 |
+    /// max: u32 | max_sig: u16 | overruns: u32], in budget-clock units
+    fn handle_rtc_stats(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(prio), Some(steps), Some(mean), Some(max), Some(sig), Some(overruns)) = (
+            cur.read_sized(self.sizes.time_size),
+            cur.read_u8(),
+            cur.read_u32(),
+            cur.read_u32(),
+            cur.read_u32(),
+            cur.read_u16(),
+            cur.read_u32(),
+        ) {
+            lines.push(format!(
+                "{ts:010} RTC-Stat Pri={prio},Steps={steps},Mean={mean},Max={max},MaxSig={},Over={overruns}",
+                self.signal_label(u64::from(sig), 0)
+            ));
+        }
+    }
+
+    /// `QS_AO_STALL` (93): [ts | prio: u8 | sig: u16 | queued: u16 |
+    /// max: u16 | stale: u32], `stale` in ticks
+    fn handle_ao_stall(&self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(prio), Some(sig), Some(queued), Some(max), Some(stale)) = (
+            cur.read_sized(self.sizes.time_size),
+            cur.read_u8(),
+            cur.read_u16(),
+            cur.read_u16(),
+            cur.read_u16(),
+            cur.read_u32(),
+        ) {
+            lines.push(format!(
+                "{ts:010} AO-Stall Pri={prio},Sig={},Queued={queued}/{max},Stale={stale}",
+                self.signal_label(u64::from(sig), 0)
+            ));
+        }
+    }
+
+    /// `QS_WDT_RESET` (95): [ts | reason: u32], the chip's reset-reason code
+    fn handle_wdt_reset(&self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(reason)) = (cur.read_sized(self.sizes.time_size), cur.read_u32()) {
+            lines.push(format!("{ts:010} WDT-Rst  Reason=0x{reason:02X}"));
+        }
+    }
+
+    /// `QS_METRICS` (94): [ts | kind: u8 | key: u16 | dispatches: u32 |
+    /// mean: u32 | max: u32 | hwm: u16 | dropped: u32]; `key` is a priority
+    /// for kind 0 and a signal for kind 1
+    fn handle_metrics(&self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(kind), Some(key), Some(disp), Some(mean), Some(max), Some(hwm), Some(dropped)) = (
+            cur.read_sized(self.sizes.time_size),
+            cur.read_u8(),
+            cur.read_u16(),
+            cur.read_u32(),
+            cur.read_u32(),
+            cur.read_u32(),
+            cur.read_u16(),
+            cur.read_u32(),
+        ) {
+            let line = match kind {
+                0 => format!("Pri={key},Disp={disp},Mean={mean},Max={max},HWM={hwm},Drop={dropped}"),
+                _ => format!(
+                    "Sig={},Disp={disp},Mean={mean},Max={max},Drop={dropped}",
+                    self.signal_label(u64::from(key), 0)
+                ),
+            };
+            lines.push(format!("{ts:010} Metrics  {line}"));
+        }
+    }
+
+    /// `QS_QXK_THREAD_STACK` (86): [ts | thread: u8 | size: u32 | used: u32 |
+    /// overflow: u8]
+    fn handle_thread_stack(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(thread), Some(size), Some(used), Some(overflow)) = (
+            cur.read_sized(self.sizes.time_size),
+            cur.read_u8(),
+            cur.read_u32(),
+            cur.read_u32(),
+            cur.read_u8(),
+        ) {
+            let percent = if size == 0 { 0 } else { u64::from(used) * 100 / u64::from(size) };
+            let label = if overflow != 0 { "Thr-Ovfl" } else { "Thr-Stk " };
+            lines.push(format!("{ts:010} {label} Thr={thread},Used={used}/{size} ({percent}%)"));
+        }
+    }
+
+    /// `QS_OVERFLOW` (90): [ts | dropped: u32 | overwritten: u32], running
+    /// totals of frames the target's trace buffer lost.
+    fn handle_overflow(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(dropped), Some(overwritten)) =
+            (cur.read_sized(self.sizes.time_size), cur.read_u32(), cur.read_u32())
+        {
+            lines.push(format!("{ts:010} QS-Ovfl  Dropped={dropped},Overwritten={overwritten}"));
+        }
+    }
+
+    /// `QS_AO_SAVE` (83) / `QS_AO_RESTORE` (84): [ts | ao | len: u16 |
+    /// status: u8]
+    fn handle_ao_persist(&mut self, payload: &[u8], label: &str, lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(ts), Some(ao), Some(len), Some(status)) = (
+            cur.read_sized(self.sizes.time_size),
+            cur.read_sized(self.sizes.obj_ptr_size),
+            cur.read_u16(),
+            cur.read_u8(),
+        ) {
+            let status = match status {
+                0 => "OK".to_string(),
+                1 => "Missing".to_string(),
+                2 => "Rejected".to_string(),
+                3 => "TooLarge".to_string(),
+                4 => "StoreFailed".to_string(),
+                other => other.to_string(),
+            };
+            lines.push(format!("{ts:010} {label} Obj={},Len={len},Status={status}", self.object_label(ao)));
+        }
+    }
+
+    /// `QS_QF_EVT_LOG` (91): [tick: u32 | ao: u8 | source: u8 | sender: u8 |
+    /// lifo: u8 | sig: u16 | len: u16 | snapshot] — no timestamp; the tick
+    /// stands in for it
+    fn handle_evt_log(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        if let (Some(tick), Some(ao), Some(source), Some(sender), Some(lifo), Some(sig), Some(len)) = (
+            cur.read_u32(),
+            cur.read_u8(),
+            cur.read_u8(),
+            cur.read_u8(),
+            cur.read_u8(),
+            cur.read_u16(),
+            cur.read_u16(),
+        ) {
+            let source = match source {
+                0 => "Ext".to_string(),
+                1 => "TE".to_string(),
+                2 => format!("AO{sender}"),
+                other => other.to_string(),
+            };
+            let lifo = if lifo != 0 { ",LIFO" } else { "" };
+            lines.push(format!(
+                "{tick:010} Evt-Log  AO={ao},Sig={},Src={source},Len={len}{lifo}",
+                self.signal_label(u64::from(sig), 0)
+            ));
+        }
+    }
+
     // ── Infrastructure / test handlers ───────────────────────────────────────
 
     /// `QS_TEST_PROBE_GET` (59): [ts | api_fun | data_u32]
@@ -947,29 +1486,58 @@ impl FrameInterpreter {
         ) {
             lines.push(format!(
                 "{ts:010} TstProbe Fun={},Data={data:#010X}",
-                self.fun_str(api)
+                self.function_label(api)
             ));
         }
     }
 
-    /// `QS_QUERY_DATA` (67): [ts | kind | obj]
+    /// `QS_QUERY_DATA` (67): [ts | kind | obj | kind-specific data]
+    ///
+    /// SM: `state`; AO and EQ: `free | min` (equeue counters); MP: `free |
+    /// min` (pool counters); TE: `ao | ctr | interval | sig | flags`.
     fn handle_query_data(&mut self, payload: &[u8], lines: &mut Vec<String>) {
         let mut cur = Cursor::new(payload);
-        if let (Some(ts), Some(kind), Some(obj)) = (
+        let (Some(ts), Some(kind), Some(obj)) = (
             cur.read_sized(self.sizes.time_size),
             cur.read_u8(),
             cur.read_sized(self.sizes.obj_ptr_size),
-        ) {
-            let kind_str = match kind {
-                0 => "SM",  1 => "AO",  2 => "MP",
-                3 => "EQ",  4 => "TE",  5 => "AP",
-                _ => "??",
-            };
-            lines.push(format!(
-                "{ts:010} Query-{kind_str} Obj={}",
-                self.obj_str(obj)
-            ));
-        }
+        ) else {
+            return;
+        };
+        let obj_str = self.object_label(obj);
+        let detail = match kind {
+            0 => cur.read_sized(self.sizes.fun_ptr_size)
+                .map(|state| format!(",State={}", self.function_label(state))),
+            1 | 3 => cur.read_sized(self.sizes.equeue_ctr)
+                .zip(cur.read_sized(self.sizes.equeue_ctr))
+                .map(|(free, min)| format!(",Que<Free={free},Min={min}>")),
+            2 => cur.read_sized(self.sizes.mpool_ctr)
+                .zip(cur.read_sized(self.sizes.mpool_ctr))
+                .map(|(free, min)| format!(",Free={free},Min={min}")),
+            4 => match (
+                cur.read_sized(self.sizes.obj_ptr_size),
+                cur.read_sized(self.sizes.timeevt_ctr),
+                cur.read_sized(self.sizes.timeevt_ctr),
+                cur.read_sized(self.sizes.signal_size),
+                cur.read_u8(),
+            ) {
+                (Some(ao), Some(ctr), Some(interval), Some(sig), Some(flags)) => Some(format!(
+                    ",AO={},Sig={},Tim={ctr},Int={interval},Flags={flags:#04X}",
+                    self.object_label(ao), self.signal_label(sig, ao)
+                )),
+                _ => None,
+            },
+            _ => None,
+        };
+        let kind_str = match kind {
+            0 => "SM",  1 => "AO",  2 => "MP",
+            3 => "EQ",  4 => "TE",  5 => "AP",
+            _ => "??",
+        };
+        lines.push(format!(
+            "{ts:010} Query-{kind_str} Obj={obj_str}{}",
+            detail.unwrap_or_default()
+        ));
     }
 
     /// `QS_PEEK_DATA` (68): [offset_u16 | size | data...]
@@ -1000,7 +1568,7 @@ impl FrameInterpreter {
 
     fn handle_user_record(&mut self, record: u8, payload: &[u8], lines: &mut Vec<String>) {
         let name = self.dict.users.get(&record).cloned()
-            .unwrap_or_else(|| format!("USR({record})"));
+            .unwrap_or_else(|| Rc::from(format!("USR({record})")));
 
         let mut cur = Cursor::new(payload);
         let ts = match cur.read_sized(self.sizes.time_size) {
@@ -1088,19 +1656,19 @@ impl FrameInterpreter {
                 }
                 FMT_SIG => {
                     if let Some(v) = cur.read_sized(self.sizes.signal_size) {
-                        values.push(self.sig_str(v, 0));
+                        values.push(self.signal_label(v, 0).to_string());
                     } else { break; }
                     hex_flag = false;
                 }
                 FMT_OBJ => {
                     if let Some(v) = cur.read_sized(self.sizes.obj_ptr_size) {
-                        values.push(self.obj_str(v));
+                        values.push(self.object_label(v).to_string());
                     } else { break; }
                     hex_flag = false;
                 }
                 FMT_FUN => {
                     if let Some(v) = cur.read_sized(self.sizes.fun_ptr_size) {
-                        values.push(self.fun_str(v));
+                        values.push(self.function_label(v).to_string());
                     } else { break; }
                     hex_flag = false;
                 }
@@ -1141,6 +1709,26 @@ impl FrameInterpreter {
         lines.push(format!("{ts:010} {name} {}", values.join(" ")));
     }
 
+    fn handle_defmt_chunk(&mut self, payload: &[u8], lines: &mut Vec<String>) {
+        let mut cur = Cursor::new(payload);
+        let ts = cur.read_sized(self.sizes.time_size);
+        let chunk = cur.read_bytes(cur.remaining()).and_then(Chunk::parse);
+        let (Some(ts), Some(chunk)) = (ts, chunk) else {
+            lines.push(format!("           DEFMT bad chunk payload={}", hex_bytes(payload)));
+            return;
+        };
+        let lost = self.defmt.lost();
+        let frame = self.defmt.push(chunk);
+        if self.defmt.lost() != lost {
+            lines.push(format!("{ts:010} DEFMT frame lost"));
+        }
+        if let Some(frame) = frame {
+            let text = self.defmt_decoder.as_ref().and_then(|decode| decode(&frame))
+                .unwrap_or_else(|| format!("frame={}", hex_bytes(&frame)));
+            lines.push(format!("{ts:010} DEFMT {text}"));
+        }
+    }
+
     fn fallback_line(&self, frame: &QsFrame) -> String {
         format!(
             "           rec={:#04X} len={} payload={}",
@@ -1152,6 +1740,38 @@ impl FrameInterpreter {
 
     // ── Dictionary persistence ────────────────────────────────────────────────
 
+    /// Digest of the dictionaries held, loaded or received, computed as the
+    /// target computes its `QS_DICT_HASH`: each entry is re-encoded as the
+    /// record payload it came from at the current sizes.
+    pub fn dict_hash(&self) -> predefined::DictHash {
+        fn entry(record_type: u8, head: &[&[u8]], name: &str) -> u32 {
+            let mut payload: Vec<u8> = head.concat();
+            payload.extend_from_slice(name.as_bytes());
+            payload.push(0);
+            predefined::DictHash::entry(record_type, &payload)
+        }
+        let sized = |value: u64, size: u8| value.to_le_bytes()[..usize::from(size.min(8))].to_vec();
+        let sizes = &self.sizes;
+        let mut digest = predefined::DictHash::new();
+        for (&addr, name) in &self.dict.objects {
+            digest.add_entry(entry(predefined::OBJ_DICT, &[&sized(addr, sizes.obj_ptr_size)], name));
+        }
+        for (&addr, name) in &self.dict.functions {
+            digest.add_entry(entry(predefined::FUN_DICT, &[&sized(addr, sizes.fun_ptr_size)], name));
+        }
+        for (&(sig, obj), name) in &self.dict.signals {
+            let head = [&sized(u64::from(sig), sizes.signal_size)[..], &sized(obj, sizes.obj_ptr_size)];
+            digest.add_entry(entry(predefined::SIG_DICT, &head, name));
+        }
+        for (&id, name) in &self.dict.users {
+            digest.add_entry(entry(predefined::USR_DICT, &[&[id]], name));
+        }
+        for (&(grp, val), name) in &self.dict.enums {
+            digest.add_entry(entry(predefined::ENUM_DICT, &[&[val, grp]], name));
+        }
+        digest
+    }
+
     pub fn save_dictionaries(&self, path: &Path) -> io::Result<()> {
         let file = std::fs::File::create(path)?;
         let mut w = io::BufWriter::new(file);
@@ -1185,27 +1805,27 @@ impl FrameInterpreter {
             match parts.as_slice() {
                 ["OBJ", addr, name] => {
                     if let Ok(a) = parse_addr(addr) {
-                        self.dict.objects.insert(a, (*name).to_owned());
+                        self.dict.objects.insert(a, self.dict.names.intern(name));
                     }
                 }
                 ["FUN", addr, name] => {
                     if let Ok(a) = parse_addr(addr) {
-                        self.dict.functions.insert(a, (*name).to_owned());
+                        self.dict.functions.insert(a, self.dict.names.intern(name));
                     }
                 }
                 ["SIG", sig, obj, name] => {
                     if let (Ok(s), Ok(o)) = (sig.parse::<u32>(), parse_addr(obj)) {
-                        self.dict.signals.insert((s, o), (*name).to_owned());
+                        self.dict.signals.insert((s, o), self.dict.names.intern(name));
                     }
                 }
                 ["USR", id, name] => {
                     if let Ok(i) = id.parse::<u8>() {
-                        self.dict.users.insert(i, (*name).to_owned());
+                        self.dict.users.insert(i, self.dict.names.intern(name));
                     }
                 }
                 ["ENUM", grp, val, name] => {
                     if let (Ok(g), Ok(v)) = (grp.parse::<u8>(), val.parse::<u8>()) {
-                        self.dict.enums.insert((g, v), (*name).to_owned());
+                        self.dict.enums.insert((g, v), self.dict.names.intern(name));
                     }
                 }
                 _ => {}
@@ -1217,14 +1837,15 @@ impl FrameInterpreter {
 
 // ── Dictionaries ──────────────────────────────────────────────────────────────
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct Dictionaries {
-    objects:   HashMap<u64, String>,
-    functions: HashMap<u64, String>,
-    signals:   HashMap<(u32, u64), String>,
-    users:     HashMap<u8, String>,
+    objects:   HashMap<u64, Rc<str>>,
+    functions: HashMap<u64, Rc<str>>,
+    signals:   HashMap<(u32, u64), Rc<str>>,
+    users:     HashMap<u8, Rc<str>>,
     /// Keyed by (group, value).
-    enums:     HashMap<(u8, u8), String>,
+    enums:     HashMap<(u8, u8), Rc<str>>,
+    names:     Names,
 }
 
 // ── Utilities ─────────────────────────────────────────────────────────────────
@@ -1243,3 +1864,29 @@ fn parse_addr(s: &str) -> Result<u64, std::num::ParseIntError> {
     u64::from_str_radix(hex, 16)
 }
 
+/// Entry timestamps of the open nested spans (critical sections or
+/// interrupt handlers), outermost first.
+#[derive(Debug, Default, Clone)]
+struct Spans(Vec<u64>);
+
+impl Spans {
+    /// A span at `nesting` (1 = outermost) opened at `ts`. Deeper spans left
+    /// open were missed; a gap below means this one cannot be timed.
+    fn enter(&mut self, nesting: u8, ts: u64) {
+        let depth = usize::from(nesting);
+        self.0.truncate(depth.saturating_sub(1));
+        if self.0.len() + 1 == depth {
+            self.0.push(ts);
+        }
+    }
+
+    /// Ticks since the matching entry of the span at `nesting` closing at
+    /// `ts`, if that entry was seen.
+    fn exit(&mut self, nesting: u8, ts: u64, sizes: &TargetSizes) -> Option<u64> {
+        if nesting == 0 || self.0.len() != usize::from(nesting) {
+            self.0.clear();
+            return None;
+        }
+        self.0.pop().map(|since| sizes.time_delta(since, ts))
+    }
+}
@@ -1,18 +1,45 @@
 pub(crate) mod cursor;
 pub mod commands;
 mod decoder;
+pub mod export;
 pub mod frontend;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzz;
+pub mod groups;
 mod interpreter;
+pub mod loadgen;
+pub mod msc;
+mod names;
 pub mod output;
+pub mod pools;
+pub mod profile;
+pub mod record;
+pub mod replay;
+mod rtt;
 mod runtime;
+pub mod selftest;
+mod serial;
+mod session;
 mod sizes;
+pub mod spec;
 
 pub use commands::{CommandSender, SharedSender, try_send};
-pub use decoder::{DecodeError, HdlcDecoder, QsFrame};
-pub use interpreter::{FrameInterpreter, UserRecordFormatter};
+pub use decoder::{DecodeError, FrameChunk, HdlcDecoder, QsFrame, DEFAULT_MAX_FRAME_LEN};
+pub use export::{ExportFormat, ExportGroup, Exporter};
+pub use groups::{GroupFilter, RecordGroup};
+pub use interpreter::{DefmtFrameDecoder, FrameInterpreter, RecordHook, UserRecordFormatter};
+pub use loadgen::{GenConfig, GenStats, LoadGen, RecordMix};
+pub use msc::{MscFormat, MscWriter};
+pub use names::Label;
 pub use output::{OutputSinks, stdout_is_tty};
+pub use pools::{PoolForecast, PoolUsage, SizeUsage};
+pub use profile::BatchProfile;
+pub use record::{DecodedRecord, Field, RecordVisitor, Value};
+pub use replay::{ReplayScript, TargetMap};
 pub use runtime::{run, run_with_custom_handler, CustomCommandHandler};
+pub use selftest::{SelftestChecker, SelftestReport};
 pub use sizes::TargetSizes;
+pub use spec::{Spec, SpecChecker};
 
 #[cfg(test)]
 mod tests;
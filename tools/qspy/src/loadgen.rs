@@ -0,0 +1,307 @@
+//! Synthetic QS traffic for exercising the decoder without a target.
+//!
+//! [`LoadGen`] plays a made-up application: a few active objects moving
+//! between states, posting to each other, arming time events and emitting a
+//! user record. Its output is a well-formed QS stream, preceded by the target
+//! info and dictionaries a real target would send, so qspy decodes it like
+//! the real thing. Error injection damages the stream on purpose. A corrupted
+//! frame has one byte changed and fails its checksum. A dropped frame uses up
+//! a sequence number without being sent, which qspy reports as a gap.
+//!
+//! `qspy gen` drives a generator over UDP, TCP, a serial port or into a file;
+//! tests and benchmarks can call it directly. The same seed always produces
+//! the same bytes.
+
+use qs::hdlc::{ESC, FLAG};
+use qs::predefined::{self, TargetInfo};
+use qs::records::{self, Predefined, RecordSizes};
+use qs::{encode_frame, QsRecord, TimestampSize, UserRecordBuilder};
+
+/// User record id of the synthetic user record.
+pub const GEN_USER_RECORD: u8 = 100;
+
+const AO_BASE: u64 = 0x2000_0000;
+const TE_BASE: u64 = 0x2000_8000;
+const STATE_BASE: u64 = 0x0800_1000;
+const STATES: u64 = 4;
+/// Signals `FIRST_SIG..FIRST_SIG + SIGNALS` are used by the synthetic AOs.
+const FIRST_SIG: u16 = 4;
+const SIGNALS: u16 = 5;
+const QUEUE_DEPTH: u32 = 16;
+
+/// Relative weights of the kinds of records generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordMix {
+    /// State-machine dispatches and transitions.
+    pub sm: u32,
+    /// Posts between active objects.
+    pub ao: u32,
+    /// Time-event arms and posts.
+    pub te: u32,
+    /// User records.
+    pub usr: u32,
+}
+
+impl Default for RecordMix {
+    fn default() -> Self {
+        Self { sm: 4, ao: 3, te: 2, usr: 1 }
+    }
+}
+
+impl RecordMix {
+    /// Parses `sm=4,ao=3,te=2,usr=1`. Kinds left out get weight 0.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut mix = Self { sm: 0, ao: 0, te: 0, usr: 0 };
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (kind, weight) = part
+                .split_once('=')
+                .ok_or_else(|| format!("`{part}`: expected KIND=WEIGHT"))?;
+            let weight: u32 = weight
+                .trim()
+                .parse()
+                .map_err(|_| format!("`{part}`: weight must be a whole number"))?;
+            let slot = match kind.trim().to_ascii_lowercase().as_str() {
+                "sm" => &mut mix.sm,
+                "ao" => &mut mix.ao,
+                "te" => &mut mix.te,
+                "usr" => &mut mix.usr,
+                other => return Err(format!("unknown record kind `{other}` (sm, ao, te, usr)")),
+            };
+            *slot = weight;
+        }
+        if mix.total() == 0 {
+            return Err("the mix needs at least one non-zero weight".into());
+        }
+        Ok(mix)
+    }
+
+    fn total(&self) -> u32 {
+        self.sm + self.ao + self.te + self.usr
+    }
+}
+
+/// What to generate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenConfig {
+    /// Record kinds and their weights.
+    pub mix: RecordMix,
+    /// Number of synthetic active objects (at least one).
+    pub aos: u8,
+    /// Fraction of frames to corrupt, `0.0..=1.0`.
+    pub corrupt: f64,
+    /// Fraction of frames to drop, `0.0..=1.0`.
+    pub drop: f64,
+    /// Seed of the generator's random numbers.
+    pub seed: u64,
+}
+
+impl Default for GenConfig {
+    fn default() -> Self {
+        Self { mix: RecordMix::default(), aos: 4, corrupt: 0.0, drop: 0.0, seed: 1 }
+    }
+}
+
+/// Counts of what a [`LoadGen`] produced.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GenStats {
+    /// Records generated after the preamble, sent or not.
+    pub records: u64,
+    /// Frames handed out, preamble included.
+    pub frames: u64,
+    /// Frames that were corrupted.
+    pub corrupted: u64,
+    /// Records that were dropped.
+    pub dropped: u64,
+    /// Bytes handed out.
+    pub bytes: u64,
+}
+
+/// Produces a synthetic QS stream, one frame at a time.
+pub struct LoadGen {
+    cfg: GenConfig,
+    sizes: RecordSizes,
+    rng: u64,
+    seq: u8,
+    time: u32,
+    /// Current state of each AO.
+    states: Vec<u64>,
+    /// Free entries in each AO's queue, and its low-water mark.
+    queues: Vec<(u32, u32)>,
+    user_ctr: u32,
+    stats: GenStats,
+}
+
+impl LoadGen {
+    pub fn new(cfg: GenConfig) -> Self {
+        let aos = usize::from(cfg.aos.max(1));
+        Self {
+            // xorshift needs a non-zero state.
+            rng: cfg.seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+            sizes: RecordSizes::NATIVE,
+            seq: 0,
+            time: 0,
+            states: vec![STATE_BASE; aos],
+            queues: vec![(QUEUE_DEPTH, QUEUE_DEPTH); aos],
+            user_ctr: 0,
+            stats: GenStats::default(),
+            cfg,
+        }
+    }
+
+    /// What has been produced so far.
+    pub fn stats(&self) -> GenStats {
+        self.stats
+    }
+
+    /// The frames that start the stream: target info and dictionaries. They
+    /// are never corrupted or dropped.
+    pub fn preamble(&mut self) -> Vec<Vec<u8>> {
+        let info = TargetInfo {
+            signal_size: self.sizes.signal,
+            equeue_ctr_size: self.sizes.equeue_ctr,
+            time_evt_ctr_size: self.sizes.time_evt_ctr,
+            mpool_ctr_size: self.sizes.mpool_ctr,
+            obj_ptr_size: self.sizes.obj_ptr,
+            fun_ptr_size: self.sizes.fun_ptr,
+            time_size: 4,
+            max_active: self.cfg.aos.max(1),
+            ..TargetInfo::default()
+        };
+        let mut out = Vec::new();
+        let mut push = |gen: &mut Self, record_type, payload: Vec<u8>| {
+            let frame = gen.encode(record_type, None, payload);
+            gen.stats.frames += 1;
+            gen.stats.bytes += frame.len() as u64;
+            out.push(frame);
+        };
+        // The target info restarts the host's sequence check.
+        self.seq = 0;
+        push(self, predefined::TARGET_INFO, predefined::target_info_payload(&info));
+        for i in 0..self.states.len() as u64 {
+            push(self, predefined::OBJ_DICT, predefined::obj_dict_payload(ao_addr(i), &format!("AO_{i}")));
+            push(self, predefined::OBJ_DICT, predefined::obj_dict_payload(te_addr(i), &format!("te_{i}")));
+        }
+        for k in 0..STATES {
+            let name = format!("state_{k}");
+            push(self, predefined::FUN_DICT, predefined::fun_dict_payload(STATE_BASE + k * 0x40, &name));
+        }
+        for sig in FIRST_SIG..FIRST_SIG + SIGNALS {
+            push(self, predefined::SIG_DICT, predefined::sig_dict_payload(sig, 0, &format!("SIG_{sig}")));
+        }
+        push(self, predefined::USR_DICT, predefined::usr_dict_payload(GEN_USER_RECORD, "GEN_USR"));
+        out
+    }
+
+    /// Generates the next record. Returns its frame, which may be corrupted,
+    /// or `None` if the record was dropped.
+    pub fn next_frame(&mut self) -> Option<Vec<u8>> {
+        self.time = self.time.wrapping_add(1 + self.below(50) as u32);
+        self.stats.records += 1;
+        let (record_type, payload) = self.next_record();
+        let mut frame = self.encode(record_type, Some(self.time), payload);
+        if self.chance(self.cfg.drop) {
+            self.stats.dropped += 1;
+            return None;
+        }
+        if self.chance(self.cfg.corrupt) {
+            self.corrupt(&mut frame);
+            self.stats.corrupted += 1;
+        }
+        self.stats.frames += 1;
+        self.stats.bytes += frame.len() as u64;
+        Some(frame)
+    }
+
+    fn next_record(&mut self) -> (u8, Vec<u8>) {
+        let mix = self.cfg.mix;
+        let mut pick = self.below(u64::from(mix.total())) as u32;
+        let ao = self.below(self.states.len() as u64) as usize;
+        let sig = FIRST_SIG + self.below(u64::from(SIGNALS)) as u16;
+        let obj = ao_addr(ao as u64);
+
+        if pick < mix.sm {
+            let state = self.states[ao];
+            if self.chance(0.5) {
+                return self.predefined(records::qep::dispatch(obj, sig, state));
+            }
+            let target = STATE_BASE + self.below(STATES) * 0x40;
+            self.states[ao] = target;
+            return self.predefined(records::qep::tran(obj, sig, state, target));
+        }
+        pick -= mix.sm;
+        if pick < mix.ao {
+            let sender = ao_addr(self.below(self.states.len() as u64));
+            // Queues fill and drain at random, with a sticky low-water mark.
+            let fill = self.chance(0.5);
+            let (free, min) = &mut self.queues[ao];
+            *free = if fill && *free > 1 { *free - 1 } else { (*free + 1).min(QUEUE_DEPTH) };
+            *min = (*min).min(*free);
+            let (free, min) = (*free, *min);
+            return self.predefined(records::qf::active_post(sender, obj, sig, 0, 0, free, min));
+        }
+        pick -= mix.ao;
+        if pick < mix.te {
+            let te = te_addr(ao as u64);
+            if self.chance(0.5) {
+                let ticks = 1 + self.below(100) as u32;
+                return self.predefined(records::qf::time_evt::arm(te, obj, ticks, 0, 0));
+            }
+            return self.predefined(records::qf::time_evt::post(te, sig, obj, 0));
+        }
+        self.user_ctr = self.user_ctr.wrapping_add(1);
+        let mut user = UserRecordBuilder::new();
+        user.push_u32(0, self.user_ctr).push_u16(0, self.below(4096) as u16);
+        (GEN_USER_RECORD, user.into_vec())
+    }
+
+    fn predefined(&self, record: Predefined) -> (u8, Vec<u8>) {
+        (record.record_type(), record.encode(&self.sizes).to_vec())
+    }
+
+    fn encode(&mut self, record_type: u8, timestamp: Option<u32>, payload: Vec<u8>) -> Vec<u8> {
+        self.seq = self.seq.wrapping_add(1);
+        let record = QsRecord { seq: self.seq, record_type, timestamp, payload };
+        encode_frame(&record, TimestampSize::Four)
+    }
+
+    /// Changes one byte of `frame` so that its checksum fails but its framing
+    /// stays intact: never a flag, an escape or an escaped byte.
+    fn corrupt(&mut self, frame: &mut [u8]) {
+        let body = frame.len() - 1;
+        let candidates: Vec<usize> = (0..body)
+            .filter(|&i| frame[i] != ESC && (i == 0 || frame[i - 1] != ESC))
+            .collect();
+        let at = candidates[self.below(candidates.len() as u64) as usize];
+        let mut byte = frame[at];
+        while byte == frame[at] || byte == FLAG || byte == ESC {
+            byte = self.next_u64() as u8;
+        }
+        frame[at] = byte;
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng = x;
+        x
+    }
+
+    /// A number in `0..n`.
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n.max(1)
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        p > 0.0 && ((self.next_u64() >> 11) as f64) < p * (1u64 << 53) as f64
+    }
+}
+
+fn ao_addr(i: u64) -> u64 {
+    AO_BASE + i * 0x100
+}
+
+fn te_addr(i: u64) -> u64 {
+    TE_BASE + i * 0x20
+}
@@ -0,0 +1,251 @@
+//! Message sequence charts of a trace (`--msc`).
+//!
+//! Event traffic between objects is collected while the trace is decoded and
+//! written as a sequence diagram when the session ends. Every object that
+//! sends or receives an event becomes a lifeline, labelled with its
+//! `QS_OBJ_DICT` name, in order of first appearance:
+//!
+//! - `ACTIVE_POST`/`POST_LIFO` draw an arrow from the sender to the active
+//!   object, `ACTIVE_POST_ATTEMPT` a lost message;
+//! - `PUBLISH` draws an arrow from the sender to a `QF` lifeline (the
+//!   deliveries to subscribers follow as posts);
+//! - `TIMEEVT_POST` draws a dashed arrow from the time event to its AO;
+//! - `QEP_TRAN` marks the new state on the object's lifeline.
+//!
+//! Two flavours are written, picked from the file extension: mscgen for
+//! `.msc`, PlantUML for anything else (`.puml`, `.plantuml`, …).
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use qs::records::{qep, qf, qf::time_evt};
+
+use crate::cursor::Cursor;
+use crate::{FrameInterpreter, QsFrame};
+
+/// Output flavour of an [`MscWriter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MscFormat {
+    PlantUml,
+    Mscgen,
+}
+
+impl MscFormat {
+    /// mscgen for a `.msc` file, PlantUML otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("msc") => Self::Mscgen,
+            _ => Self::PlantUml,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Arrow {
+    Post,
+    Lost,
+    Timer,
+}
+
+enum Step {
+    Message { from: usize, to: usize, arrow: Arrow, label: String },
+    State { on: usize, state: String },
+}
+
+struct Lifeline {
+    /// Object address; `None` for the framework's `QF` lifeline.
+    addr: Option<u64>,
+    name: String,
+}
+
+/// Collects the event traffic of a trace and writes it as a sequence diagram.
+pub struct MscWriter {
+    format:    MscFormat,
+    out:       Option<Box<dyn Write + Send>>,
+    lifelines: Vec<Lifeline>,
+    steps:     Vec<Step>,
+}
+
+impl MscWriter {
+    /// Write the chart to `path` when the writer is finished or dropped.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let format = MscFormat::from_path(path);
+        let f = File::create(path)?;
+        println!("sequence chart: {}", path.display());
+        Ok(Self::new(format, Box::new(BufWriter::new(f))))
+    }
+
+    /// Write the chart to `out` instead of a file.
+    pub fn new(format: MscFormat, out: Box<dyn Write + Send>) -> Self {
+        Self { format, out: Some(out), lifelines: Vec::new(), steps: Vec::new() }
+    }
+
+    pub fn format(&self) -> MscFormat { self.format }
+
+    /// Add `frame` to the chart if it is one of the tracked records.
+    ///
+    /// Names come from the dictionaries `interp` has seen so far, so call this
+    /// after the interpreter has decoded the frame.
+    pub fn record(&mut self, frame: &QsFrame, interp: &FrameInterpreter) {
+        let sizes = *interp.sizes();
+        let mut cur = Cursor::new(&frame.payload);
+        match frame.record_type {
+            qf::ACTIVE_POST | qf::ACTIVE_POST_LIFO | qf::ACTIVE_POST_ATTEMPT => {
+                if let (Some(_ts), Some(sig), Some(sdr), Some(ao)) = (
+                    cur.read_sized(sizes.time_size),
+                    cur.read_sized(sizes.signal_size),
+                    cur.read_sized(sizes.obj_ptr_size),
+                    cur.read_sized(sizes.obj_ptr_size),
+                ) {
+                    let mut label = signal(interp, sig, ao);
+                    if frame.record_type == qf::ACTIVE_POST_LIFO {
+                        label.push_str(" (LIFO)");
+                    }
+                    let arrow = if frame.record_type == qf::ACTIVE_POST_ATTEMPT {
+                        Arrow::Lost
+                    } else {
+                        Arrow::Post
+                    };
+                    self.message(interp, Some(sdr), Some(ao), arrow, label);
+                }
+            }
+            qf::PUBLISH => {
+                if let (Some(_ts), Some(sdr), Some(sig)) = (
+                    cur.read_sized(sizes.time_size),
+                    cur.read_sized(sizes.obj_ptr_size),
+                    cur.read_sized(sizes.signal_size),
+                ) {
+                    let label = signal(interp, sig, 0);
+                    self.message(interp, Some(sdr), None, Arrow::Post, label);
+                }
+            }
+            time_evt::POST => {
+                if let (Some(_ts), Some(timer), Some(sig), Some(ao)) = (
+                    cur.read_sized(sizes.time_size),
+                    cur.read_sized(sizes.obj_ptr_size),
+                    cur.read_sized(sizes.signal_size),
+                    cur.read_sized(sizes.obj_ptr_size),
+                ) {
+                    let label = signal(interp, sig, ao);
+                    self.message(interp, Some(timer), Some(ao), Arrow::Timer, label);
+                }
+            }
+            qep::TRAN => {
+                if let (Some(_ts), Some(_sig), Some(obj), Some(_src), Some(tgt)) = (
+                    cur.read_sized(sizes.time_size),
+                    cur.read_sized(sizes.signal_size),
+                    cur.read_sized(sizes.obj_ptr_size),
+                    cur.read_sized(sizes.fun_ptr_size),
+                    cur.read_sized(sizes.fun_ptr_size),
+                ) {
+                    let on = self.lifeline(interp, Some(obj));
+                    let state = interp.function_label(tgt).to_string();
+                    self.steps.push(Step::State { on, state });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Write the chart. Later calls, and the drop, do nothing.
+    pub fn finish(&mut self) -> io::Result<()> {
+        let Some(mut out) = self.out.take() else { return Ok(()) };
+        match self.format {
+            MscFormat::PlantUml => self.write_plantuml(&mut out)?,
+            MscFormat::Mscgen   => self.write_mscgen(&mut out)?,
+        }
+        out.flush()
+    }
+
+    fn message(
+        &mut self,
+        interp: &FrameInterpreter,
+        from: Option<u64>,
+        to: Option<u64>,
+        arrow: Arrow,
+        label: String,
+    ) {
+        let from = self.lifeline(interp, from);
+        let to = self.lifeline(interp, to);
+        self.steps.push(Step::Message { from, to, arrow, label });
+    }
+
+    /// Index of the lifeline of `addr`, added on first sight. A dictionary
+    /// entry that arrives later still renames it.
+    fn lifeline(&mut self, interp: &FrameInterpreter, addr: Option<u64>) -> usize {
+        let name = match addr {
+            Some(a) => interp.object_label(a).to_string(),
+            None => "QF".to_owned(),
+        };
+        if let Some(i) = self.lifelines.iter().position(|l| l.addr == addr) {
+            self.lifelines[i].name = name;
+            return i;
+        }
+        self.lifelines.push(Lifeline { addr, name });
+        self.lifelines.len() - 1
+    }
+
+    fn write_plantuml(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "@startuml")?;
+        for (i, l) in self.lifelines.iter().enumerate() {
+            writeln!(out, "participant \"{}\" as L{i}", escape(&l.name))?;
+        }
+        for step in &self.steps {
+            match step {
+                Step::Message { from, to, arrow, label } => {
+                    let arrow = match arrow {
+                        Arrow::Post  => "->",
+                        Arrow::Lost  => "->x",
+                        Arrow::Timer => "-->",
+                    };
+                    writeln!(out, "L{from} {arrow} L{to} : {label}")?;
+                }
+                Step::State { on, state } => writeln!(out, "hnote over L{on} : {state}")?,
+            }
+        }
+        writeln!(out, "@enduml")
+    }
+
+    fn write_mscgen(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "msc {{")?;
+        let entities: Vec<String> = self.lifelines.iter().enumerate()
+            .map(|(i, l)| format!("L{i} [label=\"{}\"]", escape(&l.name)))
+            .collect();
+        if !entities.is_empty() {
+            writeln!(out, "  {};", entities.join(", "))?;
+        }
+        for step in &self.steps {
+            match step {
+                Step::Message { from, to, arrow, label } => {
+                    let arrow = match arrow {
+                        Arrow::Post  => "->",
+                        Arrow::Lost  => "-x",
+                        Arrow::Timer => ">>",
+                    };
+                    writeln!(out, "  L{from} {arrow} L{to} [label=\"{}\"];", escape(label))?;
+                }
+                Step::State { on, state } => {
+                    writeln!(out, "  L{on} abox L{on} [label=\"{}\"];", escape(state))?;
+                }
+            }
+        }
+        writeln!(out, "}}")
+    }
+}
+
+impl Drop for MscWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            eprintln!("sequence chart error: {e}");
+        }
+    }
+}
+
+fn signal(interp: &FrameInterpreter, sig: u64, obj: u64) -> String {
+    interp.signal_label(sig, obj).to_string()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,69 @@
+//! Dictionary names without per-record allocation.
+//!
+//! Every dictionary name is interned once as an `Rc<str>`, so the same
+//! signal name scoped to many objects is stored once and copying the
+//! dictionaries (see [`FrameInterpreter::fork`]) only bumps reference counts.
+//! Lookups hand out a [`Label`]: a borrowed name, or the address (or stable
+//! id) itself when the dictionary has no entry. Either formats straight into the output line.
+//!
+//! [`FrameInterpreter::fork`]: crate::FrameInterpreter::fork
+
+use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
+
+/// A dictionary name, or the raw value when there is none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label<'a> {
+    Name(&'a str),
+    /// Printed as hex, `size` bytes wide.
+    Addr { value: u64, size: u8 },
+    /// A stable symbol id (see `qs::symbols`), printed as `#id`.
+    Id(u64),
+}
+
+impl<'a> Label<'a> {
+    /// `name`, or `value` printed at the width of `size`.
+    pub fn or_addr(name: Option<&'a str>, value: u64, size: u8) -> Self {
+        name.map_or(Label::Addr { value, size }, Label::Name)
+    }
+
+    /// The dictionary name, if there was one.
+    pub fn name(self) -> Option<&'a str> {
+        match self {
+            Label::Name(name) => Some(name),
+            Label::Addr { .. } | Label::Id(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Label<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Label::Name(name) => f.pad(name),
+            Label::Id(id) => f.pad(&format!("#{id}")),
+            Label::Addr { value, size: 1 } => write!(f, "0x{value:02X}"),
+            Label::Addr { value, size: 2 } => write!(f, "0x{value:04X}"),
+            Label::Addr { value, size: 4 } => write!(f, "0x{value:08X}"),
+            Label::Addr { value, .. } => write!(f, "0x{value:016X}"),
+        }
+    }
+}
+
+/// Interned dictionary names.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Names {
+    names: HashSet<Rc<str>>,
+}
+
+impl Names {
+    /// The shared copy of `name`, added on first sight.
+    pub(crate) fn intern(&mut self, name: &str) -> Rc<str> {
+        if let Some(existing) = self.names.get(name) {
+            return Rc::clone(existing);
+        }
+        let name: Rc<str> = Rc::from(name);
+        self.names.insert(Rc::clone(&name));
+        name
+    }
+}
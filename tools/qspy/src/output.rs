@@ -3,16 +3,32 @@ use std::io::{self, BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use crate::export::{ExportFormat, Exporter};
+use crate::groups::{GroupFilter, RecordGroup};
+use crate::msc::MscWriter;
+use crate::{FrameInterpreter, QsFrame};
+
 pub struct OutputSinks {
     quiet:    bool,
     color:    bool,
     text_out: Option<BufWriter<File>>,
     bin_out:  Option<BufWriter<File>>,
+    export:   Option<Exporter>,
+    msc:      Option<MscWriter>,
+    filter:   GroupFilter,
 }
 
 impl OutputSinks {
     pub fn new(quiet: bool, color: bool) -> Self {
-        Self { quiet, color, text_out: None, bin_out: None }
+        Self {
+            quiet,
+            color,
+            text_out: None,
+            bin_out: None,
+            export: None,
+            msc: None,
+            filter: GroupFilter::allow_all(),
+        }
     }
 
     /// Open a text output file. `path = None` means auto-generate a timestamped name.
@@ -39,6 +55,70 @@ impl OutputSinks {
         Ok(())
     }
 
+    /// Start a MATLAB/CSV columnar export. `base = None` means auto-generate a
+    /// timestamped stem; see [`Exporter::create`] for the file naming.
+    pub fn open_export(&mut self, format: ExportFormat, base: Option<&Path>) {
+        self.export = Some(Exporter::create(format, base.unwrap_or(Path::new(""))));
+    }
+
+    /// Collect a sequence chart, written to `path` when the sinks are dropped;
+    /// see [`MscWriter`].
+    pub fn open_msc(&mut self, path: &Path) -> io::Result<()> {
+        self.msc = Some(MscWriter::create(path)?);
+        Ok(())
+    }
+
+    /// Forward a frame `interp` has just decoded to the columnar export and
+    /// the sequence chart, if open.
+    pub fn write_frame(&mut self, frame: &QsFrame, interp: &FrameInterpreter) {
+        if let Some(ex) = &mut self.export {
+            if let Err(e) = ex.write_frame(frame, interp.sizes()) {
+                eprintln!("export error: {e}; export closed");
+                self.export = None;
+            }
+        }
+        if let Some(msc) = &mut self.msc {
+            msc.record(frame, interp);
+        }
+    }
+
+    /// Restrict decoded record output to the groups in `filter`.
+    ///
+    /// Only the console and the text file are filtered; the binary save, the
+    /// columnar export, the sequence chart and the front-end still see every
+    /// record.
+    pub fn set_filter(&mut self, filter: GroupFilter) {
+        self.filter = filter;
+    }
+
+    /// The groups currently shown.
+    pub fn filter(&self) -> GroupFilter {
+        self.filter
+    }
+
+    /// Write one line decoded from a record of `group`, colored by group and
+    /// prefixed with the session `tag`, if any. Lines of groups excluded by
+    /// the filter are dropped.
+    pub fn write_record(&mut self, tag: Option<&str>, group: RecordGroup, line: &str) {
+        if !self.filter.allows(group) {
+            return;
+        }
+        if !self.quiet {
+            match (tag, self.color) {
+                (Some(t), true)  => println!("{BOLD}{t}{RESET} {}", colorize_record(group, line)),
+                (Some(t), false) => println!("{t} {line}"),
+                (None, true)     => println!("{}", colorize_record(group, line)),
+                (None, false)    => println!("{line}"),
+            }
+        }
+        if let Some(f) = &mut self.text_out {
+            let _ = match tag {
+                Some(t) => writeln!(f, "{t} {line}"),
+                None    => writeln!(f, "{line}"),
+            };
+        }
+    }
+
     /// Write a decoded text line to console (unless quiet) and to the text file.
     /// ANSI colors are applied to the console only; the text file always gets plain text.
     pub fn write_line(&mut self, line: &str) {
@@ -64,6 +144,7 @@ impl OutputSinks {
     pub fn flush(&mut self) {
         if let Some(f) = &mut self.text_out { let _ = f.flush(); }
         if let Some(f) = &mut self.bin_out  { let _ = f.flush(); }
+        if let Some(ex) = &mut self.export  { ex.flush(); }
     }
 
     /// Toggle text output file: close it if open, open a new auto-named one if closed.
@@ -102,6 +183,7 @@ const CYAN:         &str = "\x1b[36m";
 const GREEN:        &str = "\x1b[32m";
 const YELLOW:       &str = "\x1b[33m";
 const BLUE:         &str = "\x1b[34m";
+const BRIGHT_BLUE:  &str = "\x1b[94m";
 const MAGENTA:      &str = "\x1b[35m";
 const BRIGHT_WHITE: &str = "\x1b[97m";
 const RED:          &str = "\x1b[31m";
@@ -131,20 +213,56 @@ pub fn colorize_line(line: &str) -> String {
     }
 
     // Timestamped records: 10-digit timestamp + space + content
-    if line.len() >= 11 {
-        let ts_bytes = &line.as_bytes()[..10];
-        if ts_bytes.iter().all(|b| b.is_ascii_digit()) && line.as_bytes()[10] == b' ' {
-            let ts   = &line[..10];
-            let rest = &line[10..]; // leading space included
-            let kw   = rest.trim_start();
-            let color = keyword_color(kw);
-            return format!("{CYAN}{ts}{RESET}{color}{rest}{RESET}");
-        }
+    if let Some((ts, rest)) = split_timestamp(line) {
+        let color = keyword_color(rest.trim_start());
+        return format!("{CYAN}{ts}{RESET}{color}{rest}{RESET}");
     }
 
     line.to_string()
 }
 
+/// Apply ANSI color codes to a line decoded from a record of `group`.
+///
+/// Same layout rules as [`colorize_line`], but the body color comes from the
+/// record's group rather than from its label:
+/// - Green: SM · Yellow: AO · Magenta: EQ / MP / TE / QF · Blue: SC
+/// - Bright blue: SEM / MTX · Bright white: USR
+/// - INFO keeps the label-based colors (red `=ASSERT=`, …)
+pub fn colorize_record(group: RecordGroup, line: &str) -> String {
+    let color = match group {
+        RecordGroup::Sm  => GREEN,
+        RecordGroup::Ao  => YELLOW,
+        RecordGroup::Eq | RecordGroup::Mp | RecordGroup::Te | RecordGroup::Qf => MAGENTA,
+        RecordGroup::Sc  => BLUE,
+        RecordGroup::Sem | RecordGroup::Mtx => BRIGHT_BLUE,
+        RecordGroup::Usr => BRIGHT_WHITE,
+        RecordGroup::Info => return colorize_line(line),
+    };
+
+    if line.starts_with("===RTC===>") {
+        let (pfx, rest) = line.split_at(10);
+        return format!("{BOLD}{CYAN}{pfx}{RESET}{rest}");
+    }
+    if line.starts_with("           ") || line.starts_with("########## ") {
+        return format!("{DIM}{line}{RESET}");
+    }
+    match split_timestamp(line) {
+        Some((ts, rest)) => format!("{CYAN}{ts}{RESET}{color}{rest}{RESET}"),
+        None => format!("{color}{line}{RESET}"),
+    }
+}
+
+/// Split a `NNNNNNNNNN <body>` line into the 10-digit timestamp and the rest
+/// (leading space included).
+fn split_timestamp(line: &str) -> Option<(&str, &str)> {
+    let bytes = line.as_bytes();
+    if bytes.len() >= 11 && bytes[..10].iter().all(u8::is_ascii_digit) && bytes[10] == b' ' {
+        Some(line.split_at(10))
+    } else {
+        None
+    }
+}
+
 fn keyword_color(kw: &str) -> &'static str {
     if kw.starts_with("=>Intern") || kw.starts_with("===>Tran")
        || kw.starts_with("Init===>") || kw.starts_with("=>Ignore")
@@ -0,0 +1,285 @@
+//! Pool sizing from a trace.
+//!
+//! Two views of the target's memory use are rebuilt from the records:
+//!
+//! * Memory pools, from `QS_QF_MPOOL_INIT/GET/PUT/GET_ATTEMPT`. Each pool is
+//!   keyed by its address. The record's own low-water mark (`min`) gives the
+//!   peak number of blocks in use, and a least-squares fit of the free count
+//!   over time forecasts when a draining pool would run out.
+//! * Event sizes, from `QS_QF_NEW` and `QS_QF_GC`. A new event is live until
+//!   the collection of an event with the same signal, so the peak of
+//!   concurrently live events of each size is the number of blocks a pool of
+//!   that block size needs.
+//!
+//! Each view recommends a block count: the peak plus a safety margin.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{self, Read};
+
+use crate::{FrameInterpreter, HdlcDecoder};
+
+/// Default safety margin added to observed peaks.
+pub const DEFAULT_MARGIN: f64 = 0.25;
+
+/// Least-squares line through `(t, y)` samples, kept as running sums.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Trend {
+    t0: Option<u64>,
+    n: f64,
+    sx: f64,
+    sy: f64,
+    sxy: f64,
+    sxx: f64,
+}
+
+impl Trend {
+    fn add(&mut self, ts: u64, y: u64) {
+        let t0 = *self.t0.get_or_insert(ts);
+        let (x, y) = (ts.saturating_sub(t0) as f64, y as f64);
+        self.n += 1.0;
+        self.sx += x;
+        self.sy += y;
+        self.sxy += x * y;
+        self.sxx += x * x;
+    }
+
+    /// Change of `y` per timestamp unit, once there are two distinct times.
+    fn slope(&self) -> Option<f64> {
+        let denom = self.n * self.sxx - self.sx * self.sx;
+        (self.n >= 2.0 && denom > 0.0).then(|| (self.n * self.sxy - self.sx * self.sy) / denom)
+    }
+}
+
+/// What the trace showed about one memory pool.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PoolUsage {
+    /// Blocks in the pool, from `MPOOL_INIT`.
+    pub capacity: Option<u64>,
+    /// Most free blocks seen, a lower bound of the capacity without `MPOOL_INIT`.
+    pub max_free: u64,
+    /// Fewest free blocks ever (the pool's low-water mark).
+    pub min_free: Option<u64>,
+    /// Free blocks after the last record.
+    pub free: u64,
+    /// Timestamp of the last record.
+    pub last_ts: u64,
+    pub gets: u64,
+    pub puts: u64,
+    /// Failed gets: the pool was empty.
+    pub exhausted: u64,
+    trend: Trend,
+}
+
+impl PoolUsage {
+    fn sample(&mut self, ts: u64, free: u64) {
+        self.free = free;
+        self.last_ts = ts;
+        self.max_free = self.max_free.max(free);
+        self.min_free = Some(self.min_free.map_or(free, |m| m.min(free)));
+        self.trend.add(ts, free);
+    }
+
+    /// Known or inferred number of blocks.
+    pub fn blocks(&self) -> u64 {
+        self.capacity.unwrap_or(self.max_free)
+    }
+
+    /// Most blocks in use at once.
+    pub fn peak_used(&self) -> u64 {
+        self.blocks().saturating_sub(self.min_free.unwrap_or(self.blocks()))
+    }
+
+    /// Free blocks gained per timestamp unit; negative while the pool drains.
+    pub fn free_slope(&self) -> Option<f64> {
+        self.trend.slope()
+    }
+
+    /// Timestamp at which the pool runs out if it keeps draining at the
+    /// fitted rate.
+    pub fn exhaustion_forecast(&self) -> Option<u64> {
+        let slope = self.free_slope().filter(|s| *s < 0.0)?;
+        Some(self.last_ts.saturating_add((self.free as f64 / -slope).ceil() as u64))
+    }
+}
+
+/// Allocations of one event size.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SizeUsage {
+    /// Events allocated.
+    pub allocated: u64,
+    /// Events live now.
+    pub live: u64,
+    /// Most events live at once.
+    pub peak: u64,
+    /// Pools (1-based ids from `QS_QF_GC`) events of this size returned to.
+    pub pools: BTreeSet<u8>,
+}
+
+/// Pool occupancy rebuilt from a trace, with sizing advice.
+#[derive(Debug, Clone)]
+pub struct PoolForecast {
+    margin: f64,
+    pools: BTreeMap<u64, PoolUsage>,
+    sizes: BTreeMap<u64, SizeUsage>,
+    /// Sizes of the live events of each signal, newest last.
+    live: HashMap<u64, Vec<u64>>,
+}
+
+impl Default for PoolForecast {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PoolForecast {
+    pub fn new() -> Self {
+        Self { margin: DEFAULT_MARGIN, pools: BTreeMap::new(), sizes: BTreeMap::new(), live: HashMap::new() }
+    }
+
+    /// Uses `margin` (`0.25` = 25 %) on top of observed peaks.
+    pub fn with_margin(mut self, margin: f64) -> Self {
+        self.margin = margin.max(0.0);
+        self
+    }
+
+    /// Safety margin on top of observed peaks.
+    pub fn margin(&self) -> f64 {
+        self.margin
+    }
+
+    /// `QS_QF_MPOOL_INIT`: a pool of `n_free` blocks.
+    pub fn mpool_init(&mut self, mp: u64, ts: u64, n_free: u64) {
+        let pool = self.pools.entry(mp).or_default();
+        *pool = PoolUsage { capacity: Some(n_free), ..PoolUsage::default() };
+        pool.sample(ts, n_free);
+    }
+
+    /// `QS_QF_MPOOL_GET`: a block taken, leaving `free`; `min` is the pool's
+    /// own low-water mark.
+    pub fn mpool_get(&mut self, mp: u64, ts: u64, free: u64, min: u64) {
+        let pool = self.pools.entry(mp).or_default();
+        pool.gets += 1;
+        pool.sample(ts, free);
+        pool.min_free = pool.min_free.map(|m| m.min(min));
+    }
+
+    /// `QS_QF_MPOOL_PUT`: a block returned, leaving `free`.
+    pub fn mpool_put(&mut self, mp: u64, ts: u64, free: u64) {
+        let pool = self.pools.entry(mp).or_default();
+        pool.puts += 1;
+        pool.sample(ts, free);
+    }
+
+    /// `QS_QF_MPOOL_GET_ATTEMPT`: a get failed.
+    pub fn mpool_exhausted(&mut self, mp: u64, ts: u64, free: u64) {
+        let pool = self.pools.entry(mp).or_default();
+        pool.exhausted += 1;
+        pool.sample(ts, free);
+    }
+
+    /// `QS_QF_NEW`: an event of `size` bytes allocated.
+    pub fn event_new(&mut self, size: u64, sig: u64) {
+        let usage = self.sizes.entry(size).or_default();
+        usage.allocated += 1;
+        usage.live += 1;
+        usage.peak = usage.peak.max(usage.live);
+        self.live.entry(sig).or_default().push(size);
+    }
+
+    /// `QS_QF_GC`: an event with `sig` freed to `pool`. Collections of events
+    /// whose allocation was not traced are ignored.
+    pub fn event_gc(&mut self, sig: u64, pool: u8) {
+        let Some(size) = self.live.get_mut(&sig).and_then(Vec::pop) else { return };
+        if let Some(usage) = self.sizes.get_mut(&size) {
+            usage.live = usage.live.saturating_sub(1);
+            if pool != 0 {
+                usage.pools.insert(pool);
+            }
+        }
+    }
+
+    pub fn pools(&self) -> &BTreeMap<u64, PoolUsage> {
+        &self.pools
+    }
+
+    pub fn sizes(&self) -> &BTreeMap<u64, SizeUsage> {
+        &self.sizes
+    }
+
+    /// Blocks to configure for a peak of `peak`: the peak plus the margin,
+    /// and at least one spare.
+    pub fn recommend(&self, peak: u64) -> u64 {
+        let with_margin = (peak as f64 * (1.0 + self.margin)).ceil() as u64;
+        with_margin.max(peak + 1)
+    }
+
+    /// Blocks to configure for `pool`. An exhausted pool needed more than it
+    /// had, so the advice starts from its full capacity.
+    pub fn recommend_pool(&self, pool: &PoolUsage) -> u64 {
+        if pool.exhausted > 0 {
+            self.recommend(pool.blocks()).max(pool.blocks() + 1)
+        } else {
+            self.recommend(pool.peak_used())
+        }
+    }
+
+    /// Report of both views; `name` renders a pool address.
+    pub fn summary_lines(&self, name: impl Fn(u64) -> String) -> Vec<String> {
+        let margin = (self.margin * 100.0).round();
+        if self.pools.is_empty() && self.sizes.is_empty() {
+            return vec!["           Pool report: no MPOOL or QF_NEW/GC records".to_string()];
+        }
+        let mut lines = vec![format!("           Pool report (margin {margin}%)")];
+        if !self.pools.is_empty() {
+            lines.push("           Pool                 Blocks    Peak  Exhaust  Advice  Trend".to_string());
+            for (&mp, pool) in &self.pools {
+                let blocks = match pool.capacity {
+                    Some(n) => n.to_string(),
+                    None => format!(">={}", pool.max_free),
+                };
+                let trend = match (pool.exhaustion_forecast(), pool.free_slope()) {
+                    (Some(at), _) => format!("empty at ~{at}"),
+                    (None, Some(_)) => "steady".to_string(),
+                    (None, None) => "-".to_string(),
+                };
+                lines.push(format!(
+                    "           {:<20} {:>6} {:>7} {:>8} {:>7}  {trend}",
+                    name(mp), blocks, pool.peak_used(), pool.exhausted, self.recommend_pool(pool),
+                ));
+            }
+        }
+        if !self.sizes.is_empty() {
+            lines.push("           Evt size   Allocs   Peak live  Advice  Pools".to_string());
+            for (&size, usage) in &self.sizes {
+                let pools = if usage.pools.is_empty() {
+                    "-".to_string()
+                } else {
+                    usage.pools.iter().map(u8::to_string).collect::<Vec<_>>().join(",")
+                };
+                lines.push(format!(
+                    "           {size:>8} {:>8} {:>11} {:>7}  {pools}",
+                    usage.allocated, usage.peak, self.recommend(usage.peak),
+                ));
+            }
+        }
+        lines
+    }
+}
+
+/// Runs a saved capture through `interpreter`, whose
+/// [`pool_forecast`](FrameInterpreter::pool_forecast) then covers it.
+pub fn analyze_capture<R: Read>(mut source: R, interpreter: &mut FrameInterpreter) -> io::Result<()> {
+    let mut decoder = HdlcDecoder::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = match source.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for frame in decoder.push_bytes(&buf[..n]).into_iter().flatten() {
+            interpreter.interpret(&frame);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Dispatch-batch profile built from `QS_RUN_BATCH` records.
+//!
+//! Each record describes one `run_until_idle` pass on the target: how many
+//! events it dispatched and how long it kept the kernel away from its idle
+//! loop. Batches are bucketed by size (powers of two) so an event storm shows
+//! up as a populated tail, with the worst-case duration alongside.
+
+/// Upper bound (inclusive) of each events-per-batch bucket; the last bucket
+/// is open-ended.
+const BUCKET_BOUNDS: [u32; 8] = [1, 3, 7, 15, 31, 63, 127, 255];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Bucket {
+    batches:   u64,
+    total_us:  u64,
+    max_us:    u32,
+}
+
+/// Aggregated `run_until_idle` batches.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BatchProfile {
+    buckets:    [Bucket; BUCKET_BOUNDS.len() + 1],
+    events:     u64,
+    max_events: u32,
+}
+
+impl BatchProfile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one batch of `events` dispatched in `duration_us`.
+    pub fn record(&mut self, events: u32, duration_us: u32) {
+        let idx = BUCKET_BOUNDS.iter().position(|&b| events <= b).unwrap_or(BUCKET_BOUNDS.len());
+        let b = &mut self.buckets[idx];
+        b.batches += 1;
+        b.total_us += u64::from(duration_us);
+        b.max_us = b.max_us.max(duration_us);
+        self.events += u64::from(events);
+        self.max_events = self.max_events.max(events);
+    }
+
+    /// Number of batches recorded.
+    pub fn batches(&self) -> u64 {
+        self.buckets.iter().map(|b| b.batches).sum()
+    }
+
+    /// Largest batch seen, in events.
+    pub fn max_events(&self) -> u32 {
+        self.max_events
+    }
+
+    /// Longest batch seen, in microseconds.
+    pub fn max_duration_us(&self) -> u32 {
+        self.buckets.iter().map(|b| b.max_us).max().unwrap_or(0)
+    }
+
+    /// Human-readable table, one line per populated bucket.
+    pub fn summary_lines(&self) -> Vec<String> {
+        let batches = self.batches();
+        if batches == 0 {
+            return vec!["           Batch profile: no QS_RUN_BATCH records".to_string()];
+        }
+        let mut lines = vec![format!(
+            "           Batch profile: {batches} batches, {} events, max {} events / {} us",
+            self.events, self.max_events, self.max_duration_us()
+        )];
+        lines.push("           Events      Batches   Share   Avg(us)   Max(us)".to_string());
+        let mut lo = 1;
+        for (i, b) in self.buckets.iter().enumerate() {
+            let range = match BUCKET_BOUNDS.get(i) {
+                Some(&hi) if hi == lo => format!("{lo}"),
+                Some(&hi) => format!("{lo}-{hi}"),
+                None => format!("{lo}+"),
+            };
+            lo = BUCKET_BOUNDS.get(i).map_or(lo, |&hi| hi + 1);
+            if b.batches == 0 {
+                continue;
+            }
+            lines.push(format!(
+                "           {range:<10} {:>8} {:>6.1}% {:>9} {:>9}",
+                b.batches,
+                b.batches as f64 * 100.0 / batches as f64,
+                b.total_us / b.batches,
+                b.max_us,
+            ));
+        }
+        lines
+    }
+}
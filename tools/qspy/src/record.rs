@@ -0,0 +1,428 @@
+//! Typed access to decoded records, for analyzers built outside qspy.
+//!
+//! [`DecodedRecord`] splits a frame's payload into its timestamp and named,
+//! typed [`Field`]s using the target's [`TargetSizes`], so a coverage or
+//! latency tool reads `record.signal()` instead of parsing console text:
+//!
+//! ```
+//! use qspy::{DecodedRecord, QsFrame, TargetSizes};
+//! use qs::records::qep;
+//!
+//! let payload = qep::dispatch(0x2000_0100, 7, 0x0800_1000)
+//!     .encode(&qs::RecordSizes::from_target_info(&qs::TargetInfo::default()));
+//! let mut bytes = 42u32.to_le_bytes().to_vec();
+//! bytes.extend_from_slice(&payload);
+//! let frame = QsFrame { seq: 1, record_type: qep::DISPATCH, payload: bytes };
+//!
+//! let sizes = TargetSizes { obj_ptr_size: 8, fun_ptr_size: 8, ..TargetSizes::default() };
+//! let record = DecodedRecord::decode(&frame, &sizes);
+//! assert_eq!(record.timestamp(), Some(42));
+//! assert_eq!(record.signal(), Some(7));
+//! assert_eq!(record.object(), Some(0x2000_0100));
+//! ```
+//!
+//! Records decoded through [`FrameInterpreter::decode`] also resolve names
+//! from the dictionaries seen so far. A [`RecordVisitor`] gets each record
+//! handed to the method of its [`RecordGroup`].
+//!
+//! Field names are stable: `obj`, `ao`, `sender`, `queue`, `pool_obj`, `te`
+//! for objects; `sig` for signals; `state`, `source`, `target` for state
+//! handlers; counters and the rest as named in the record's builder in
+//! `qs::records`. User records yield one field per formatted value, named
+//! by position (`0`, `1`, …).
+
+use std::borrow::Cow;
+use std::fmt;
+
+use qs::records::{infra, qep, qf, qf::time_evt, qxk, sched};
+use qs::{
+    FMT_F32, FMT_F64, FMT_FUN, FMT_HEX, FMT_I16, FMT_I32, FMT_I64, FMT_I8_ENUM, FMT_MEM, FMT_OBJ,
+    FMT_SIG, FMT_STR, FMT_U16, FMT_U32, FMT_U64, FMT_U8,
+};
+
+use crate::cursor::Cursor;
+use crate::groups::RecordGroup;
+use crate::sizes::TargetSizes;
+use crate::{FrameInterpreter, QsFrame};
+
+/// A decoded field value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+    Str(String),
+    Mem(Vec<u8>),
+    /// A signal number.
+    Signal(u64),
+    /// An object address.
+    Object(u64),
+    /// A function (state handler) address.
+    Function(u64),
+}
+
+impl Value {
+    /// The value as an integer, for every variant that is one.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::Unsigned(v) | Value::Signal(v) | Value::Object(v) | Value::Function(v) => Some(v),
+            Value::Signed(v) => u64::try_from(v).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// One named field of a record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name:  Cow<'static, str>,
+    pub value: Value,
+}
+
+/// A frame split into its timestamp and typed fields.
+#[derive(Clone)]
+pub struct DecodedRecord<'a> {
+    frame:     &'a QsFrame,
+    group:     RecordGroup,
+    timestamp: Option<u64>,
+    fields:    Vec<Field>,
+    complete:  bool,
+    names:     Option<&'a FrameInterpreter>,
+}
+
+impl<'a> DecodedRecord<'a> {
+    /// Decodes `frame` with the field widths in `sizes`. Records without a
+    /// known layout (dictionaries, target info, test records) have no fields.
+    pub fn decode(frame: &'a QsFrame, sizes: &TargetSizes) -> Self {
+        let group = RecordGroup::of(frame.record_type);
+        let (timestamp, fields, complete) = match group {
+            RecordGroup::Usr => decode_user(&frame.payload, sizes),
+            _ => match layout(frame.record_type) {
+                Some((timed, layout)) => decode_layout(&frame.payload, sizes, timed, layout),
+                None => (None, Vec::new(), false),
+            },
+        };
+        Self { frame, group, timestamp, fields, complete, names: None }
+    }
+
+    pub fn frame(&self) -> &'a QsFrame { self.frame }
+
+    pub fn record_type(&self) -> u8 { self.frame.record_type }
+
+    pub fn seq(&self) -> u8 { self.frame.seq }
+
+    pub fn group(&self) -> RecordGroup { self.group }
+
+    /// Target timestamp; `None` for untimed records.
+    pub fn timestamp(&self) -> Option<u64> { self.timestamp }
+
+    /// All fields, in payload order.
+    pub fn fields(&self) -> &[Field] { &self.fields }
+
+    /// `false` if the payload ended before the layout did, ran on past it,
+    /// or the record has no known layout; the fields read so far are still
+    /// there. Bytes left over mean the sizes in use are not the target's.
+    pub fn is_complete(&self) -> bool { self.complete }
+
+    /// The field called `name`.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        self.fields.iter().find(|f| f.name == name).map(|f| &f.value)
+    }
+
+    /// The object the record is about: the receiving AO of a post, the state
+    /// machine of a QEP record, the pool, queue, time event or kernel object.
+    pub fn object(&self) -> Option<u64> {
+        ["ao", "obj", "queue", "pool_obj", "te"].iter().find_map(|n| self.field(n)).and_then(Value::as_u64)
+    }
+
+    /// The signal the record carries.
+    pub fn signal(&self) -> Option<u64> {
+        self.fields.iter().find_map(|f| match f.value {
+            Value::Signal(s) => Some(s),
+            _ => None,
+        })
+    }
+
+    /// The state a QEP record reports: the target of a transition, the
+    /// current state otherwise.
+    pub fn state(&self) -> Option<u64> {
+        self.field("target").or_else(|| self.field("state")).and_then(Value::as_u64)
+    }
+
+    /// Dictionary name of [`object`](Self::object), when decoded through a
+    /// [`FrameInterpreter`] that has seen it.
+    pub fn object_name(&self) -> Option<&'a str> {
+        self.names?.object_name(self.object()?)
+    }
+
+    /// Dictionary name of [`signal`](Self::signal).
+    pub fn signal_name(&self) -> Option<&'a str> {
+        self.names?.signal_name(self.signal()?, self.object().unwrap_or(0))
+    }
+
+    /// Dictionary name of [`state`](Self::state).
+    pub fn state_name(&self) -> Option<&'a str> {
+        self.names?.function_name(self.state()?)
+    }
+}
+
+impl fmt::Debug for DecodedRecord<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecodedRecord")
+            .field("record_type", &self.frame.record_type)
+            .field("group", &self.group)
+            .field("timestamp", &self.timestamp)
+            .field("fields", &self.fields)
+            .field("complete", &self.complete)
+            .finish()
+    }
+}
+
+impl QsFrame {
+    /// Shorthand for [`DecodedRecord::decode`].
+    pub fn decode(&self, sizes: &TargetSizes) -> DecodedRecord<'_> {
+        DecodedRecord::decode(self, sizes)
+    }
+}
+
+impl FrameInterpreter {
+    /// Decodes `frame` with the target sizes and dictionaries this
+    /// interpreter has learned; run the frame through
+    /// [`interpret`](Self::interpret) first so its own dictionary entries
+    /// count.
+    pub fn decode<'a>(&'a self, frame: &'a QsFrame) -> DecodedRecord<'a> {
+        let mut record = DecodedRecord::decode(frame, self.sizes());
+        record.group = self.group_of(frame.record_type);
+        record.names = Some(self);
+        record
+    }
+
+    /// Decodes `frame` and hands it to `visitor`.
+    pub fn visit(&self, frame: &QsFrame, visitor: &mut (impl RecordVisitor + ?Sized)) {
+        visitor.visit_record(&self.decode(frame));
+    }
+}
+
+/// Receives decoded records, one method per [`RecordGroup`].
+///
+/// Override [`visit_record`](Self::visit_record) to see every record, or
+/// only the group methods of interest; the default `visit_record` routes
+/// through [`walk_record`].
+pub trait RecordVisitor {
+    fn visit_record(&mut self, record: &DecodedRecord<'_>) {
+        walk_record(self, record);
+    }
+    /// QEP records: dispatch, transitions, entry and exit.
+    fn visit_state_machine(&mut self, _record: &DecodedRecord<'_>) {}
+    /// Active-object queues, defer and recall, subscriptions.
+    fn visit_active(&mut self, _record: &DecodedRecord<'_>) {}
+    /// Raw event queues.
+    fn visit_queue(&mut self, _record: &DecodedRecord<'_>) {}
+    /// Memory pools.
+    fn visit_pool(&mut self, _record: &DecodedRecord<'_>) {}
+    /// Time events and ticks.
+    fn visit_time_event(&mut self, _record: &DecodedRecord<'_>) {}
+    /// Scheduler and run-to-completion statistics.
+    fn visit_scheduler(&mut self, _record: &DecodedRecord<'_>) {}
+    /// QXK semaphores and mutexes.
+    fn visit_sync(&mut self, _record: &DecodedRecord<'_>) {}
+    /// Publish, event lifecycle, critical sections and interrupts.
+    fn visit_framework(&mut self, _record: &DecodedRecord<'_>) {}
+    /// Application records.
+    fn visit_user(&mut self, _record: &DecodedRecord<'_>) {}
+    /// Dictionaries, target info, trace overflow and the test back-channel.
+    fn visit_info(&mut self, _record: &DecodedRecord<'_>) {}
+}
+
+/// Calls the group method of `visitor` that matches `record`.
+pub fn walk_record<V: RecordVisitor + ?Sized>(visitor: &mut V, record: &DecodedRecord<'_>) {
+    match record.group() {
+        RecordGroup::Sm   => visitor.visit_state_machine(record),
+        RecordGroup::Ao   => visitor.visit_active(record),
+        RecordGroup::Eq   => visitor.visit_queue(record),
+        RecordGroup::Mp   => visitor.visit_pool(record),
+        RecordGroup::Te   => visitor.visit_time_event(record),
+        RecordGroup::Sc   => visitor.visit_scheduler(record),
+        RecordGroup::Sem | RecordGroup::Mtx => visitor.visit_sync(record),
+        RecordGroup::Qf   => visitor.visit_framework(record),
+        RecordGroup::Usr  => visitor.visit_user(record),
+        RecordGroup::Info => visitor.visit_info(record),
+    }
+}
+
+/// Wire type of a predefined field.
+#[derive(Clone, Copy)]
+enum Kind {
+    Sig,
+    /// A signal sent as a plain `u16`.
+    Sig16,
+    Obj,
+    Fun,
+    EqCtr,
+    MpCtr,
+    TeCtr,
+    EvtSize,
+    U8,
+    U16,
+    U32,
+}
+
+type Layout = &'static [(&'static str, Kind)];
+
+/// Whether `record` is timestamped, and its fields after the timestamp.
+fn layout(record: u8) -> Option<(bool, Layout)> {
+    use Kind::*;
+    const POOL_REF: [(&str, Kind); 2] = [("pool", U8), ("ref", U8)];
+    let timed = |l: Layout| Some((true, l));
+    let untimed = |l: Layout| Some((false, l));
+    match record {
+        qep::STATE_ENTRY | qep::STATE_EXIT => untimed(&[("obj", Obj), ("state", Fun)]),
+        qep::STATE_INIT | qep::TRAN_HIST => untimed(&[("obj", Obj), ("source", Fun), ("target", Fun)]),
+        qep::INIT_TRAN => timed(&[("obj", Obj), ("target", Fun)]),
+        qep::INTERN_TRAN | qep::IGNORED | qep::DISPATCH => timed(&[("sig", Sig), ("obj", Obj), ("state", Fun)]),
+        qep::UNHANDLED => untimed(&[("sig", Sig), ("obj", Obj), ("state", Fun)]),
+        qep::TRAN => timed(&[("sig", Sig), ("obj", Obj), ("source", Fun), ("target", Fun)]),
+        qep::CONTRACT_VIOLATION => timed(&[("state", Fun), ("limit", U32), ("elapsed", U32)]),
+        qep::GUARD => timed(&[("sig", Sig), ("obj", Obj), ("state", Fun), ("target", Fun), ("passed", U8)]),
+
+        qf::ACTIVE_DEFER | qf::ACTIVE_RECALL | qf::ACTIVE_DEFER_ATTEMPT => {
+            timed(&[("ao", Obj), ("queue", Obj), ("sig", Sig), POOL_REF[0], POOL_REF[1]])
+        }
+        qf::ACTIVE_RECALL_ATTEMPT => timed(&[("ao", Obj), ("queue", Obj)]),
+        qf::ACTIVE_SUBSCRIBE | qf::ACTIVE_UNSUBSCRIBE => timed(&[("sig", Sig), ("ao", Obj)]),
+        qf::ACTIVE_POST | qf::ACTIVE_POST_LIFO | qf::ACTIVE_POST_ATTEMPT => timed(&[
+            ("sig", Sig), ("sender", Obj), ("ao", Obj), POOL_REF[0], POOL_REF[1], ("free", EqCtr), ("min", EqCtr),
+        ]),
+        qf::ACTIVE_GET => timed(&[("sig", Sig), ("ao", Obj), POOL_REF[0], POOL_REF[1], ("free", EqCtr)]),
+        qf::ACTIVE_GET_LAST => timed(&[("sig", Sig), ("ao", Obj), POOL_REF[0], POOL_REF[1]]),
+        qf::AO_SAVE | qf::AO_RESTORE => timed(&[("ao", Obj), ("len", U16), ("status", U8)]),
+        qf::EVT_LOG => untimed(&[
+            ("tick", U32), ("ao", U8), ("source", U8), ("sender", U8), ("lifo", U8), ("sig", U16), ("len", U16),
+        ]),
+
+        qf::EQUEUE_INIT => timed(&[("queue", Obj), ("len", EqCtr)]),
+        qf::EQUEUE_POST | qf::EQUEUE_POST_LIFO | qf::EQUEUE_POST_ATTEMPT => timed(&[
+            ("sig", Sig), ("queue", Obj), POOL_REF[0], POOL_REF[1], ("free", EqCtr), ("min", EqCtr),
+        ]),
+        qf::EQUEUE_GET => timed(&[("sig", Sig), ("queue", Obj), POOL_REF[0], POOL_REF[1], ("free", EqCtr)]),
+
+        qf::MPOOL_INIT | qf::MPOOL_GET | qf::MPOOL_GET_ATTEMPT => {
+            timed(&[("pool_obj", Obj), ("free", MpCtr), ("min", MpCtr)])
+        }
+        qf::MPOOL_PUT => timed(&[("pool_obj", Obj), ("free", MpCtr)]),
+
+        qf::PUBLISH => timed(&[("sender", Obj), ("sig", Sig), POOL_REF[0], POOL_REF[1]]),
+        qf::NEW => timed(&[("size", EvtSize), ("sig", Sig)]),
+        qf::NEW_ATTEMPT => timed(&[("size", U16), ("pool", U8), ("free", U16), ("total", U16)]),
+        qf::NEW_REF | qf::DELETE_REF | qf::GC_ATTEMPT | qf::GC => timed(&[("sig", Sig), POOL_REF[0], POOL_REF[1]]),
+        qf::CRIT_ENTRY | qf::CRIT_EXIT => timed(&[("nesting", U8)]),
+        qf::ISR_ENTRY | qf::ISR_EXIT | qf::INT_DISABLE | qf::INT_ENABLE => {
+            timed(&[("nesting", U8), ("prio", U8)])
+        }
+
+        qf::TICK => timed(&[("ctr", TeCtr), ("rate", U8)]),
+        time_evt::ARM | time_evt::DISARM | time_evt::REARM => timed(&[
+            ("te", Obj), ("ao", Obj), ("ctr", TeCtr), ("interval", TeCtr), ("rate", U8),
+        ]),
+        time_evt::AUTO_DISARM => untimed(&[("te", Obj), ("ao", Obj), ("rate", U8)]),
+        time_evt::DISARM_ATTEMPT => timed(&[("te", Obj), ("ao", Obj), ("rate", U8)]),
+        time_evt::POST => timed(&[("te", Obj), ("sig", Sig), ("ao", Obj), ("rate", U8)]),
+        qf::TIMEEVT_JITTER => timed(&[
+            ("te", Obj), ("ao", Obj), ("samples", U16), ("last", TeCtr), ("max", TeCtr), ("mean", TeCtr),
+            ("overlaps", U16),
+        ]),
+
+        sched::LOCK | sched::UNLOCK => timed(&[("prev", U8), ("new", U8)]),
+        sched::PREEMPT | sched::RESTORE | sched::NEXT => timed(&[("prio", U8), ("prev", U8)]),
+        sched::IDLE => timed(&[("prev", U8)]),
+        qf::RUN_BATCH => timed(&[("events", U16), ("duration", U32)]),
+        qf::RTC_OVERRUN => timed(&[("prio", U8), ("sig", Sig16), ("budget", U32), ("elapsed", U32)]),
+        qf::AO_STALL => timed(&[("prio", U8), ("sig", Sig16), ("queued", U16), ("max", U16), ("stale", U32)]),
+        qf::WDT_RESET => timed(&[("reason", U32)]),
+        qf::METRICS => timed(&[
+            ("kind", U8), ("key", U16), ("dispatches", U32), ("mean", U32), ("max", U32), ("hwm", U16), ("dropped", U32),
+        ]),
+        qf::RTC_STATS => timed(&[
+            ("prio", U8), ("steps", U32), ("mean", U32), ("max", U32), ("sig", Sig16), ("overruns", U32),
+        ]),
+        qxk::THREAD_STACK => timed(&[("thread", U8), ("size", U32), ("used", U32), ("overflow", U8)]),
+        qxk::SEM_TAKE..=qxk::SEM_BLOCK_ATTEMPT => timed(&[("obj", Obj), ("prio", U8), ("count", U16)]),
+        qxk::MTX_LOCK..=qxk::MTX_UNLOCK_ATTEMPT => timed(&[("obj", Obj), ("prio", U8)]),
+        infra::OVERFLOW => timed(&[("dropped", U32), ("overwritten", U32)]),
+
+        _ => None,
+    }
+}
+
+fn decode_layout(
+    payload: &[u8],
+    sizes: &TargetSizes,
+    timed: bool,
+    layout: Layout,
+) -> (Option<u64>, Vec<Field>, bool) {
+    let mut cur = Cursor::new(payload);
+    let timestamp = if timed {
+        match cur.read_sized(sizes.time_size) {
+            Some(ts) => Some(ts),
+            None => return (None, Vec::new(), false),
+        }
+    } else {
+        None
+    };
+    let mut fields = Vec::with_capacity(layout.len());
+    for &(name, kind) in layout {
+        let value = match kind {
+            Kind::Sig     => cur.read_sized(sizes.signal_size).map(Value::Signal),
+            Kind::Sig16   => cur.read_u16().map(|v| Value::Signal(v.into())),
+            Kind::Obj     => cur.read_sized(sizes.obj_ptr_size).map(Value::Object),
+            Kind::Fun     => cur.read_sized(sizes.fun_ptr_size).map(Value::Function),
+            Kind::EqCtr   => cur.read_sized(sizes.equeue_ctr).map(Value::Unsigned),
+            Kind::MpCtr   => cur.read_sized(sizes.mpool_ctr).map(Value::Unsigned),
+            Kind::TeCtr   => cur.read_sized(sizes.timeevt_ctr).map(Value::Unsigned),
+            Kind::EvtSize => cur.read_sized(sizes.event_size).map(Value::Unsigned),
+            Kind::U8      => cur.read_u8().map(|v| Value::Unsigned(v.into())),
+            Kind::U16     => cur.read_u16().map(|v| Value::Unsigned(v.into())),
+            Kind::U32     => cur.read_u32().map(|v| Value::Unsigned(v.into())),
+        };
+        match value {
+            Some(value) => fields.push(Field { name: Cow::Borrowed(name), value }),
+            None => return (timestamp, fields, false),
+        }
+    }
+    (timestamp, fields, cur.is_empty())
+}
+
+/// Splits a user record into its format-tagged values.
+fn decode_user(payload: &[u8], sizes: &TargetSizes) -> (Option<u64>, Vec<Field>, bool) {
+    let mut cur = Cursor::new(payload);
+    let Some(timestamp) = cur.read_sized(sizes.time_size) else {
+        return (None, Vec::new(), false);
+    };
+    let mut fields = Vec::new();
+    while let Some(fmt) = cur.read_u8() {
+        let value = match fmt & 0x0F {
+            FMT_HEX => continue,
+            FMT_I8_ENUM => cur.read_u8().map(|v| Value::Signed((v as i8).into())),
+            FMT_U8  => cur.read_u8().map(|v| Value::Unsigned(v.into())),
+            FMT_I16 => cur.read_u16().map(|v| Value::Signed((v as i16).into())),
+            FMT_U16 => cur.read_u16().map(|v| Value::Unsigned(v.into())),
+            FMT_I32 => cur.read_u32().map(|v| Value::Signed((v as i32).into())),
+            FMT_U32 => cur.read_u32().map(|v| Value::Unsigned(v.into())),
+            FMT_I64 => cur.read_u64().map(|v| Value::Signed(v as i64)),
+            FMT_U64 => cur.read_u64().map(Value::Unsigned),
+            FMT_F32 => cur.read_u32().map(|v| Value::Float(f32::from_bits(v).into())),
+            FMT_F64 => cur.read_u64().map(|v| Value::Float(f64::from_bits(v))),
+            FMT_STR => cur.read_c_string().map(Value::Str),
+            FMT_MEM => cur.read_u8().and_then(|len| cur.read_bytes(len.into())).map(|b| Value::Mem(b.to_vec())),
+            FMT_SIG => cur.read_sized(sizes.signal_size).map(Value::Signal),
+            FMT_OBJ => cur.read_sized(sizes.obj_ptr_size).map(Value::Object),
+            FMT_FUN => cur.read_sized(sizes.fun_ptr_size).map(Value::Function),
+            _ => None,
+        };
+        match value {
+            Some(value) => fields.push(Field { name: Cow::Owned(fields.len().to_string()), value }),
+            None => return (Some(timestamp), fields, false),
+        }
+    }
+    (Some(timestamp), fields, true)
+}
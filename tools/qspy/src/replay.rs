@@ -0,0 +1,160 @@
+//! Trace-driven stimulation: replaying a capture's posts into a host kernel.
+//!
+//! A field capture records every event posted to an active object as a
+//! `QS_QF_ACTIVE_POST` (or `_LIFO`) record. [`ReplayScript::from_capture`]
+//! pulls those out, in capture order, and [`ReplayScript::replay`] hands them
+//! to a closure that posts them into a freshly built host kernel — so a
+//! scenario seen on the target can be re-run against modified state-machine
+//! code, and the new trace compared with the original.
+//!
+//! The capture identifies the receiving AO by object address, which means
+//! nothing to the host build; a [`TargetMap`] binds addresses, or the names
+//! the target's `QS_OBJ_DICT` records gave them, to host AO ids. Records
+//! carry only the signal, so replayed events have no parameters, and posts
+//! that failed on the target (`QS_QF_ACTIVE_POST_ATTEMPT`) are not replayed.
+//!
+//! ```ignore
+//! let script = ReplayScript::from_capture(File::open("field.qs")?, &mut FrameInterpreter::new())?;
+//! let mut targets = TargetMap::new();
+//! targets.bind_name("Table", TABLE_ID.0);
+//! script.replay(&targets, |post, id| {
+//!     kernel.post(ActiveObjectId::new(id), DynEvent::empty_dyn(Signal(post.signal)))?;
+//!     kernel.run_until_idle();
+//!     Ok::<_, KernelError>(())
+//! })?;
+//! ```
+
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+use qs::records::qf;
+
+use crate::cursor::Cursor;
+use crate::{FrameInterpreter, HdlcDecoder};
+
+/// One post taken from a capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayPost {
+    /// Target timestamp of the original post.
+    pub timestamp: u64,
+    /// Posted signal.
+    pub signal: u16,
+    /// Address of the receiving AO on the target.
+    pub target: u64,
+    /// Name of the receiving AO from the capture's object dictionary.
+    pub target_name: Option<String>,
+    /// Posted LIFO (`QS_QF_ACTIVE_POST_LIFO`).
+    pub lifo: bool,
+}
+
+/// The posts of one capture, in capture order.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayScript {
+    posts: Vec<ReplayPost>,
+}
+
+/// Binds capture AOs to host AO ids.
+#[derive(Debug, Clone, Default)]
+pub struct TargetMap {
+    by_name: HashMap<String, u8>,
+    by_addr: HashMap<u64, u8>,
+}
+
+impl TargetMap {
+    pub fn new() -> Self { Self::default() }
+
+    /// Posts to the AO the capture named `name` go to host AO `id`.
+    pub fn bind_name(&mut self, name: impl Into<String>, id: u8) -> &mut Self {
+        self.by_name.insert(name.into(), id);
+        self
+    }
+
+    /// Posts to target address `addr` go to host AO `id`; takes precedence
+    /// over a name binding.
+    pub fn bind_addr(&mut self, addr: u64, id: u8) -> &mut Self {
+        self.by_addr.insert(addr, id);
+        self
+    }
+
+    /// The host AO id for `post`, if bound.
+    pub fn resolve(&self, post: &ReplayPost) -> Option<u8> {
+        self.by_addr.get(&post.target).copied().or_else(|| {
+            post.target_name.as_ref().and_then(|name| self.by_name.get(name).copied())
+        })
+    }
+}
+
+/// Outcome of [`ReplayScript::replay`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplaySummary {
+    /// Posts handed to the closure.
+    pub posted: usize,
+    /// Posts skipped because their target is not in the [`TargetMap`].
+    pub unmapped: usize,
+}
+
+impl ReplayScript {
+    /// Decodes `source` and collects its posts. `interpreter` sees every
+    /// frame, so dictionaries and `QS_TARGET_INFO` sizes in the capture are
+    /// honoured; seed it with `load_dictionaries` for captures that lack them.
+    pub fn from_capture<R: Read>(mut source: R, interpreter: &mut FrameInterpreter) -> io::Result<Self> {
+        let mut decoder = HdlcDecoder::new();
+        let mut script = Self::default();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = match source.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            for frame in decoder.push_bytes(&buf[..n]).into_iter().flatten() {
+                interpreter.interpret(&frame);
+                let lifo = match frame.record_type {
+                    qf::ACTIVE_POST => false,
+                    qf::ACTIVE_POST_LIFO => true,
+                    _ => continue,
+                };
+                let sizes = *interpreter.sizes();
+                let mut cur = Cursor::new(&frame.payload);
+                if let (Some(timestamp), Some(signal), Some(_sender), Some(target)) = (
+                    cur.read_sized(sizes.time_size),
+                    cur.read_sized(sizes.signal_size),
+                    cur.read_sized(sizes.obj_ptr_size),
+                    cur.read_sized(sizes.obj_ptr_size),
+                ) {
+                    script.posts.push(ReplayPost {
+                        timestamp,
+                        signal: signal as u16,
+                        target,
+                        target_name: interpreter.object_name(target).map(str::to_owned),
+                        lifo,
+                    });
+                }
+            }
+        }
+        Ok(script)
+    }
+
+    pub fn posts(&self) -> &[ReplayPost] { &self.posts }
+
+    /// Calls `post` with every post whose target `targets` resolves, in
+    /// capture order, stopping at the first error.
+    pub fn replay<E>(
+        &self,
+        targets: &TargetMap,
+        mut post: impl FnMut(&ReplayPost, u8) -> Result<(), E>,
+    ) -> Result<ReplaySummary, E> {
+        let mut summary = ReplaySummary::default();
+        for p in &self.posts {
+            match targets.resolve(p) {
+                Some(id) => {
+                    post(p, id)?;
+                    summary.posted += 1;
+                }
+                None => summary.unmapped += 1,
+            }
+        }
+        Ok(summary)
+    }
+}
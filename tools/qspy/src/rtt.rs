@@ -0,0 +1,102 @@
+//! SEGGER RTT telemetry input.
+//!
+//! qspy does not talk to debug probes itself. An RTT up-channel reaches it
+//! as a raw byte stream from one of two places:
+//!
+//! - a file or FIFO that a logger keeps appending to (`JLinkRTTLogger`,
+//!   `probe-rs` with its channel output redirected, ...): `--rtt PATH`. The
+//!   file is followed like `tail -f`, so qspy can start before the logger.
+//! - a command whose standard output is the channel: `--rtt 'cmd:<command>'`,
+//!   run through the shell. The stream ends when the command exits.
+//!
+//! Either way the bytes are the same HDLC stream a UART would carry.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// How long a followed file is left alone after reaching its end.
+const FOLLOW_POLL: Duration = Duration::from_millis(20);
+
+/// Where the RTT channel bytes come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RttSource {
+    /// A file or FIFO, followed as it grows.
+    File(PathBuf),
+    /// A shell command writing the channel to stdout.
+    Command(String),
+}
+
+impl RttSource {
+    /// Parses the `--rtt` argument: `cmd:<command>` or a path.
+    pub fn parse(arg: &str) -> Result<Self, String> {
+        match arg.strip_prefix("cmd:") {
+            Some(cmd) if cmd.trim().is_empty() => Err("empty RTT command".into()),
+            Some(cmd) => Ok(Self::Command(cmd.trim().to_string())),
+            None if arg.is_empty() => Err("empty RTT path".into()),
+            None => Ok(Self::File(PathBuf::from(arg))),
+        }
+    }
+
+    /// Opens the source for reading.
+    pub fn open(&self) -> io::Result<RttReader> {
+        match self {
+            Self::File(path) => Ok(RttReader::File(File::open(path)?)),
+            Self::Command(cmd) => {
+                let mut child = shell(cmd).stdout(Stdio::piped()).spawn()?;
+                let stdout = child.stdout.take().expect("stdout is piped");
+                Ok(RttReader::Command { child, stdout })
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn shell(cmd: &str) -> Command {
+    let mut c = Command::new("sh");
+    c.arg("-c").arg(cmd);
+    c
+}
+
+#[cfg(windows)]
+fn shell(cmd: &str) -> Command {
+    let mut c = Command::new("cmd");
+    c.arg("/C").arg(cmd);
+    c
+}
+
+/// An open RTT source.
+pub enum RttReader {
+    File(File),
+    Command { child: Child, stdout: ChildStdout },
+}
+
+impl Read for RttReader {
+    /// A followed file at its end reports [`io::ErrorKind::TimedOut`] after a
+    /// short pause, like a silent serial port, so the caller keeps polling;
+    /// a command's stream ends when the command does.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::File(file) => match file.read(buf)? {
+                0 if !buf.is_empty() => {
+                    thread::sleep(FOLLOW_POLL);
+                    Err(io::ErrorKind::TimedOut.into())
+                }
+                n => Ok(n),
+            },
+            Self::Command { stdout, .. } => stdout.read(buf),
+        }
+    }
+}
+
+impl Drop for RttReader {
+    fn drop(&mut self) {
+        if let Self::Command { child, .. } = self {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
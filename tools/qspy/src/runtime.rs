@@ -1,24 +1,40 @@
 use std::error::Error;
-use std::io::{self, BufRead, Read};
-use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::io::{self, BufRead, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
 use std::path::{Path, PathBuf};
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 use crate::commands::{try_send, CommandSender, SharedSender};
+use crate::export::ExportFormat;
 use crate::frontend::{FrontendCmd, FrontendServer};
+use crate::groups::{filter_mask, GroupFilter, RecordGroup};
+use crate::loadgen::{GenConfig, LoadGen, RecordMix};
 use crate::output::{stdout_is_tty, OutputSinks};
-use crate::{FrameInterpreter, HdlcDecoder, TargetSizes};
+use crate::pools;
+use crate::rtt::RttSource;
+use crate::serial;
+use crate::selftest;
+use crate::session::{Session, SessionTable};
+use crate::spec::{self, Spec};
+use crate::{FrameInterpreter, TargetSizes, DEFAULT_MAX_FRAME_LEN};
 
 // ── CLI ───────────────────────────────────────────────────────────────────────
 
 #[derive(Parser, Debug)]
-#[command(author, version, about = "QSpy host-side decoder and tracing console")]
+#[command(author, version, about = "QSpy host-side decoder and tracing console",
+          args_conflicts_with_subcommands = true)]
 struct Opts {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     // ── Telemetry input (pick one; default = UDP) ──
-    /// TCP telemetry listen address or port (target connects here to send QS frames).
-    #[arg(short = 't', long = "tcp", value_name = "ADDR",
+    /// TCP telemetry listen address or port (target connects here to send QS
+    /// frames; default port 6601 when the flag is given alone).
+    #[arg(short = 't', long = "tcp", value_name = "ADDR", num_args = 0..=1,
+          default_missing_value = "6601",
           conflicts_with_all = ["serial", "serial_path", "file", "tcp_remote"])]
     tcp: Option<String>,
 
@@ -36,7 +52,7 @@ struct Opts {
     #[arg(long = "udp", value_name = "ADDR", default_value = "0.0.0.0:7701")]
     udp_addr: String,
 
-    /// Serial device path (e.g. /dev/ttyACM0).
+    /// Serial device path (e.g. /dev/ttyACM0, COM3).
     #[arg(short = 'c', long = "serial", value_name = "PATH",
           conflicts_with_all = ["tcp", "file"])]
     serial: Option<PathBuf>,
@@ -45,11 +61,30 @@ struct Opts {
     #[arg(short = 'b', long = "baud", default_value_t = 115_200)]
     baud: u32,
 
+    /// Serial read timeout in ms (keeps commands responsive while the target is silent; 0 = block).
+    #[arg(long = "serial-timeout", value_name = "MS", default_value_t = 100)]
+    serial_timeout: u32,
+
+    /// USB-UART latency timer to request in ms (FTDI default is 16).
+    #[arg(long = "serial-latency", value_name = "MS", default_value_t = 1)]
+    serial_latency: u8,
+
+    /// SEGGER RTT up-channel: a file/FIFO a probe logger appends to, or
+    /// `cmd:<command>` whose stdout is the raw channel.
+    #[arg(long = "rtt", value_name = "SOURCE", value_parser = RttSource::parse,
+          conflicts_with_all = ["serial", "serial_path", "tcp", "tcp_remote", "file"])]
+    rtt: Option<RttSource>,
+
     /// Serial device (positional shorthand).
     #[arg(value_name = "SERIAL_PATH",
           conflicts_with_all = ["serial", "tcp", "file"])]
     serial_path: Option<PathBuf>,
 
+    /// Longest frame decoded, in bytes after unstuffing; a longer one is
+    /// reported and skipped.
+    #[arg(long = "max-frame", value_name = "BYTES", default_value_t = DEFAULT_MAX_FRAME_LEN)]
+    max_frame: usize,
+
     // ── Output ──
     /// Write decoded text to file (auto-named when no path given).
     #[arg(short = 'o', value_name = "FILE", num_args = 0..=1,
@@ -63,10 +98,31 @@ struct Opts {
           help = "Binary save file (auto-named if no argument)")]
     bin_out: Option<String>,
 
+    /// Export columnar MATLAB data, one `<stem>_<group>.mat` file per record group.
+    #[arg(short = 'm', long = "matlab", value_name = "FILE", num_args = 0..=1,
+          default_missing_value = "", conflicts_with = "csv_out",
+          help = "MATLAB export file stem (auto-named if no argument)")]
+    matlab_out: Option<String>,
+
+    /// Export columnar CSV data, one `<stem>_<group>.csv` file per record group.
+    #[arg(long = "csv", value_name = "FILE", num_args = 0..=1,
+          default_missing_value = "",
+          help = "CSV export file stem (auto-named if no argument)")]
+    csv_out: Option<String>,
+
+    /// Write a message sequence chart of the event traffic on exit
+    /// (mscgen for `.msc`, PlantUML otherwise).
+    #[arg(long = "msc", value_name = "FILE")]
+    msc_out: Option<PathBuf>,
+
     /// Suppress console output.
     #[arg(short = 'q', long = "quiet")]
     quiet: bool,
 
+    /// Show only these record groups (comma-separated: SM,AO,EQ,MP,TE,SC,SEM,MTX,USR,QF).
+    #[arg(long = "filter", value_name = "GROUPS", value_parser = GroupFilter::parse)]
+    filter: Option<GroupFilter>,
+
     // ── Channels ──
     /// TCP command-channel listen address (target connects here to receive QS-RX frames).
     #[arg(long = "cmd", default_value = "127.0.0.1:6601", value_name = "ADDR")]
@@ -99,6 +155,25 @@ struct Opts {
     #[arg(short = 'k', long = "no-kbd")]
     no_kbd: bool,
 
+    /// Check the `-f` capture against a trace specification and exit
+    /// (non-zero status on violations); see `qspy::spec`.
+    #[arg(long = "check", value_name = "SPEC", requires = "file")]
+    check: Option<PathBuf>,
+
+    /// Rebuild pool occupancy from the `-f` capture, print sizing advice and
+    /// exit; see `qspy::pools`.
+    #[arg(long = "pool-report", requires = "file", conflicts_with = "check")]
+    pool_report: bool,
+
+    /// Check the `-f` capture of the target's `qs::qs_selftest()` suite and
+    /// exit (non-zero status if any record is missing or misdecoded).
+    #[arg(long = "verify-selftest", requires = "file", conflicts_with_all = ["check", "pool_report"])]
+    verify_selftest: bool,
+
+    /// Safety margin in percent added to observed pool peaks.
+    #[arg(long = "pool-margin", value_name = "PCT", default_value_t = 25.0)]
+    pool_margin: f64,
+
     /// Backwards-compatible QS version (e.g. 700 = "7.0.0", default 700).
     #[arg(short = 'v', value_name = "VER", default_value_t = 700)]
     qs_version: u16,
@@ -110,6 +185,8 @@ struct Opts {
     #[arg(short = 'O', value_name = "N", default_value_t = 4)] obj_ptr_size: u8,
     /// QS_FUN_PTR_SIZE in bytes.
     #[arg(short = 'F', value_name = "N", default_value_t = 4)] fun_ptr_size: u8,
+    /// Q_SIGNAL_SIZE in bytes.
+    #[arg(short = 'S', value_name = "N", default_value_t = 2)] signal_size:  u8,
     /// QF_EVENT_SIZ_SIZE in bytes.
     #[arg(short = 'E', value_name = "N", default_value_t = 2)] event_size:   u8,
     /// QF_EQUEUE_CTR_SIZE in bytes.
@@ -122,6 +199,75 @@ struct Opts {
     #[arg(short = 'C', value_name = "N", default_value_t = 2)] timeevt_ctr:  u8,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Generate synthetic QS traffic, for load-testing the decoder and exporters.
+    Gen(GenOpts),
+}
+
+#[derive(Args, Debug)]
+struct GenOpts {
+    /// Send UDP datagrams to this address (the default transport).
+    #[arg(long = "udp", value_name = "ADDR", default_value = "127.0.0.1:7701")]
+    udp: String,
+
+    /// Connect to a qspy TCP listener (`qspy --tcp`) and send over it.
+    #[arg(long = "tcp", value_name = "ADDR", conflicts_with_all = ["serial", "out"])]
+    tcp: Option<String>,
+
+    /// Write to a serial device, such as one end of a `socat` pty pair.
+    #[arg(short = 'c', long = "serial", value_name = "PATH", conflicts_with_all = ["tcp", "out"])]
+    serial: Option<PathBuf>,
+
+    /// Serial baud rate.
+    #[arg(short = 'b', long = "baud", default_value_t = 115_200)]
+    baud: u32,
+
+    /// Write the stream to a file, to replay with `qspy -f`.
+    #[arg(short = 'o', long = "out", value_name = "FILE", conflicts_with_all = ["tcp", "serial"])]
+    out: Option<PathBuf>,
+
+    /// Records to generate after the dictionaries.
+    #[arg(short = 'n', long = "count", default_value_t = 10_000)]
+    count: u64,
+
+    /// Records per second (0 = as fast as the transport takes them).
+    #[arg(short = 'r', long = "rate", default_value_t = 0)]
+    rate: u32,
+
+    /// Relative weights of the record kinds: sm, ao, te and usr.
+    #[arg(long = "mix", value_name = "KIND=W,...", value_parser = RecordMix::parse,
+          default_value = "sm=4,ao=3,te=2,usr=1")]
+    mix: RecordMix,
+
+    /// Number of synthetic active objects.
+    #[arg(long = "aos", default_value_t = 4)]
+    aos: u8,
+
+    /// Fraction of frames to corrupt (0.0 to 1.0).
+    #[arg(long = "corrupt", value_name = "P", value_parser = parse_fraction, default_value_t = 0.0)]
+    corrupt: f64,
+
+    /// Fraction of records to drop, leaving sequence gaps (0.0 to 1.0).
+    #[arg(long = "drop", value_name = "P", value_parser = parse_fraction, default_value_t = 0.0)]
+    drop: f64,
+
+    /// Random seed; the same seed gives the same stream.
+    #[arg(long = "seed", default_value_t = 1)]
+    seed: u64,
+
+    /// Bytes of whole frames gathered into one write (one UDP datagram).
+    #[arg(long = "batch", value_name = "BYTES", default_value_t = 1024)]
+    batch: usize,
+}
+
+fn parse_fraction(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(p) if (0.0..=1.0).contains(&p) => Ok(p),
+        _ => Err(format!("`{s}` is not a fraction between 0.0 and 1.0")),
+    }
+}
+
 /// Custom handler callback for project-specific console commands.
 ///
 /// Given a typed command string and the shared command sender handle,
@@ -130,21 +276,83 @@ pub type CustomCommandHandler = Arc<dyn Fn(&str, &SharedSender) -> bool + Send +
 
 // ── User command enum ─────────────────────────────────────────────────────────
 
-enum UserCmd {
+#[derive(Debug, PartialEq)]
+pub(crate) enum UserCmd {
     Info,
     Reset,
     Tick(u8),
     SendCommand { id: u8, p1: u32, p2: u32, p3: u32 },
+    /// Set the target's global filter.
+    Filter(TargetFilter),
+    /// Change the groups shown on the console.
+    Show(GroupFilter),
+    /// Bring the target's global filter in line with the shown groups.
+    PushFilter,
+    /// Read `len` bytes of target memory.
+    Peek { addr: u64, len: u8 },
+    /// Write bytes into target memory.
+    Poke { addr: u64, data: Vec<u8> },
+    /// Make an object, by address or dictionary name, current on the target.
+    CurrObj { kind: u8, obj: String },
+    /// Query the target's current object of a kind.
+    Query(u8),
     SaveDict(PathBuf),
     ClearScreen,
     ToggleQuiet,
     Help,
     ToggleTextOut,
     ToggleBinOut,
+    Profile,
+    Pools,
     Custom(String),
     Quit,
 }
 
+/// Records a `filter` command lets through on the target.
+#[derive(Debug, PartialEq)]
+pub(crate) enum TargetFilter {
+    All,
+    /// Only dictionaries, target info and the other always-shown records.
+    None,
+    Groups(GroupFilter),
+}
+
+impl TargetFilter {
+    /// The 128-bit `QS_RX_GLB_FILTER` mask, grouping records as `interp`
+    /// does on the console.
+    fn mask(&self, interp: &FrameInterpreter) -> [u8; 16] {
+        filter_mask(|record| {
+            let group = interp.group_of(record);
+            match self {
+                Self::All => true,
+                Self::None => group == RecordGroup::Info,
+                Self::Groups(filter) => filter.allows(group),
+            }
+        })
+    }
+}
+
+/// What pushing `wanted` to a target filtering with `sent` would change, or
+/// `None` if they agree.
+pub(crate) fn push_offer(sent: &[u8; 16], wanted: &[u8; 16]) -> Option<String> {
+    let count = |f: fn(u8, u8) -> u8| -> u32 {
+        sent.iter().zip(wanted).map(|(&s, &w)| f(s, w).count_ones()).sum()
+    };
+    let hidden = count(|s, w| s & !w);
+    let blocked = count(|s, w| w & !s);
+    let mut parts = Vec::new();
+    if hidden > 0 {
+        parts.push(format!("{hidden} hidden record type(s) still cross the link"));
+    }
+    if blocked > 0 {
+        parts.push(format!("{blocked} shown record type(s) are blocked on the target"));
+    }
+    (!parts.is_empty()).then(|| format!(
+        "           {}; `push` to filter on the target (the binary save and export follow)",
+        parts.join(", "),
+    ))
+}
+
 // ── Entry point ───────────────────────────────────────────────────────────────
 
 /// Run the qspy console using default configuration.
@@ -165,12 +373,15 @@ where
     F: FnOnce(&mut FrameInterpreter),
 {
     let opts = Opts::parse();
+    if let Some(Command::Gen(gen)) = opts.command {
+        return run_gen(gen);
+    }
 
     let sizes = TargetSizes {
         time_size:    opts.time_size,
         obj_ptr_size: opts.obj_ptr_size,
         fun_ptr_size: opts.fun_ptr_size,
-        signal_size:  2,
+        signal_size:  opts.signal_size,
         event_size:   opts.event_size,
         equeue_ctr:   opts.equeue_ctr,
         mpool_ctr:    opts.mpool_ctr,
@@ -182,6 +393,9 @@ where
         && std::env::var_os("NO_COLOR").is_none()
         && stdout_is_tty();
     let mut sinks = OutputSinks::new(opts.quiet, color);
+    if let Some(filter) = opts.filter {
+        sinks.set_filter(filter);
+    }
     if let Some(ref arg) = opts.text_out {
         let p = if arg.is_empty() { None } else { Some(Path::new(arg.as_str())) };
         sinks.open_text(p)?;
@@ -190,6 +404,15 @@ where
         let p = if arg.is_empty() { None } else { Some(Path::new(arg.as_str())) };
         sinks.open_binary(p)?;
     }
+    let export = opts.matlab_out.as_ref().map(|a| (ExportFormat::Matlab, a))
+        .or_else(|| opts.csv_out.as_ref().map(|a| (ExportFormat::Csv, a)));
+    if let Some((format, arg)) = export {
+        let p = if arg.is_empty() { None } else { Some(Path::new(arg.as_str())) };
+        sinks.open_export(format, p);
+    }
+    if let Some(ref path) = opts.msc_out {
+        sinks.open_msc(path)?;
+    }
 
     let mut interpreter = FrameInterpreter::with_sizes(sizes);
     interpreter.set_qs_version(opts.qs_version);
@@ -206,8 +429,57 @@ where
     // Let the caller install project-specific record formatters.
     register(&mut interpreter);
 
+    if let (Some(spec_path), Some(path)) = (&opts.check, &opts.file) {
+        let spec = Spec::parse(&std::fs::read_to_string(spec_path)?)
+            .map_err(|e| format!("{}: {e}", spec_path.display()))?;
+        let report = spec::check_capture(std::fs::File::open(path)?, &mut interpreter, spec)?;
+        for line in report.summary_lines() {
+            println!("{line}");
+        }
+        if !report.passed() {
+            return Err(format!("{} spec violation(s)", report.violations.len()).into());
+        }
+        return Ok(());
+    }
+
+    if let (true, Some(path)) = (opts.verify_selftest, &opts.file) {
+        let report = selftest::verify_capture(std::fs::File::open(path)?, &mut interpreter)?;
+        for line in report.summary_lines() {
+            println!("{line}");
+        }
+        if !report.passed() {
+            let failed = report.results.values().filter(|r| r.is_err()).count();
+            return Err(format!("{failed} self-test record(s) failed").into());
+        }
+        return Ok(());
+    }
+
+    if let (true, Some(path)) = (opts.pool_report, &opts.file) {
+        interpreter.set_pool_margin(opts.pool_margin / 100.0);
+        pools::analyze_capture(std::fs::File::open(path)?, &mut interpreter)?;
+        for line in interpreter.pool_report() {
+            println!("{line}");
+        }
+        return Ok(());
+    }
+
+    // Bind the telemetry listener first: QSPY's default port doubles as the
+    // command-channel port, and then one listener serves both.
+    let tcp_listener = match opts.tcp {
+        Some(ref addr) => {
+            let bind_addr = if addr.contains(':') { addr.clone() } else { format!("0.0.0.0:{addr}") };
+            let listener = TcpListener::bind(&bind_addr)?;
+            println!("qspy listening on tcp://{bind_addr}");
+            Some(listener)
+        }
+        None => None,
+    };
+    let cmd_on_telemetry = tcp_listener.as_ref()
+        .and_then(|l| l.local_addr().ok())
+        .is_some_and(|a| port_of(&opts.cmd_addr) == Some(a.port()));
+
     let shared_sender: SharedSender = Arc::new(Mutex::new(None));
-    if !opts.no_cmd {
+    if !opts.no_cmd && !cmd_on_telemetry {
         let addr   = opts.cmd_addr.clone();
         let sender = Arc::clone(&shared_sender);
         thread::spawn(move || cmd_listener(&addr, sender));
@@ -230,40 +502,28 @@ where
     let serial_path = opts.serial.clone().or_else(|| opts.serial_path.clone());
 
     if let Some(ref path) = serial_path {
-        let s = serial::open(path, opts.baud)?;
+        let s = serial::open(path, opts.baud, opts.serial_timeout)?;
+        serial::tune_latency(path, opts.serial_latency);
         // Serial is inherently duplex — register a cloned handle as the
         // command sender so keyboard commands reach real hardware over the
         // same link, matching the `--tcp-remote` self-registration above.
         if let Ok(cmd_handle) = s.try_clone() {
             *shared_sender.lock().unwrap() = Some(CommandSender::new(Box::new(cmd_handle)));
         }
-        run_reader(s, &mut interpreter, &mut sinks, &mut frontend, &shared_sender, &kbd_rx, &custom_handler);
+        run_reader(s, opts.max_frame, &mut interpreter, &mut sinks, &mut frontend, &shared_sender, &kbd_rx, &custom_handler);
+    } else if let Some(ref source) = opts.rtt {
+        let reader = source.open()?;
+        match source {
+            RttSource::File(path) => println!("qspy following RTT channel in {}", path.display()),
+            RttSource::Command(cmd) => println!("qspy reading RTT channel from `{cmd}`"),
+        }
+        run_reader(reader, opts.max_frame, &mut interpreter, &mut sinks, &mut frontend, &shared_sender, &kbd_rx, &custom_handler);
     } else if let Some(ref path) = opts.file {
         println!("qspy replaying {}", path.display());
         let f = std::fs::File::open(path)?;
-        run_reader(f, &mut interpreter, &mut sinks, &mut frontend, &shared_sender, &kbd_rx, &custom_handler);
-    } else if let Some(ref addr) = opts.tcp {
-        let bind_addr = if addr.contains(':') { addr.clone() } else { format!("0.0.0.0:{addr}") };
-        let listener = TcpListener::bind(&bind_addr)?;
-        println!("qspy listening on tcp://{bind_addr}");
-        loop {
-            match listener.accept() {
-                Ok((stream, peer)) => {
-                    println!("telemetry connected: {peer}");
-                    if let Ok(cmd_stream) = stream.try_clone() {
-                        if cmd_stream.set_nodelay(true).is_ok() {
-                            *shared_sender.lock().unwrap() =
-                                Some(CommandSender::new(Box::new(cmd_stream)));
-                        }
-                    }
-                    run_reader(stream, &mut interpreter, &mut sinks, &mut frontend,
-                               &shared_sender, &kbd_rx, &custom_handler);
-                    println!("telemetry disconnected: {peer}");
-                }
-                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
-                Err(e) => return Err(e.into()),
-            }
-        }
+        run_reader(f, opts.max_frame, &mut interpreter, &mut sinks, &mut frontend, &shared_sender, &kbd_rx, &custom_handler);
+    } else if let Some(listener) = tcp_listener {
+        run_tcp_server(listener, opts.max_frame, &mut interpreter, &mut sinks, &mut frontend, &shared_sender, &kbd_rx, &custom_handler);
     } else if let Some(ref addr) = opts.tcp_remote {
         let addr = if addr.contains(':') { addr.clone() } else { format!("127.0.0.1:{addr}") };
         println!("qspy connecting to tcp://{addr}");
@@ -279,21 +539,109 @@ where
                 *shared_sender.lock().unwrap() = Some(CommandSender::new(Box::new(cmd_stream)));
             }
         }
-        run_reader(stream, &mut interpreter, &mut sinks, &mut frontend, &shared_sender, &kbd_rx, &custom_handler);
+        run_reader(stream, opts.max_frame, &mut interpreter, &mut sinks, &mut frontend, &shared_sender, &kbd_rx, &custom_handler);
         println!("qspy disconnected from {addr}");
     } else {
         let socket = UdpSocket::bind(&opts.udp_addr)?;
         println!("qspy listening on udp://{}", opts.udp_addr);
-        run_udp(socket, &mut interpreter, &mut sinks, &mut frontend, &shared_sender, &kbd_rx, &custom_handler);
+        run_udp(socket, opts.max_frame, &mut interpreter, &mut sinks, &mut frontend, &shared_sender, &kbd_rx, &custom_handler);
+    }
+
+    Ok(())
+}
+
+// ── Synthetic traffic (`qspy gen`) ────────────────────────────────────────────
+
+/// Sends each write as one datagram.
+struct Datagrams(UdpSocket);
+
+impl Write for Datagrams {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.send(buf) }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+/// Gathers whole frames into writes of at most `limit` bytes.
+struct Batcher {
+    sink:  Box<dyn Write>,
+    buf:   Vec<u8>,
+    limit: usize,
+}
+
+impl Batcher {
+    fn push(&mut self, frame: &[u8]) -> io::Result<()> {
+        if self.buf.len() + frame.len() > self.limit {
+            self.flush()?;
+        }
+        self.buf.extend_from_slice(frame);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            self.sink.write_all(&self.buf)?;
+            self.buf.clear();
+        }
+        self.sink.flush()
     }
+}
 
+fn run_gen(opts: GenOpts) -> Result<(), Box<dyn Error>> {
+    let (sink, target): (Box<dyn Write>, String) = if let Some(ref addr) = opts.tcp {
+        let addr = if addr.contains(':') { addr.clone() } else { format!("127.0.0.1:{addr}") };
+        (Box::new(TcpStream::connect(&addr)?), format!("tcp://{addr}"))
+    } else if let Some(ref path) = opts.serial {
+        (Box::new(serial::open(path, opts.baud, 0)?), path.display().to_string())
+    } else if let Some(ref path) = opts.out {
+        (Box::new(io::BufWriter::new(std::fs::File::create(path)?)), path.display().to_string())
+    } else {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(&opts.udp)?;
+        (Box::new(Datagrams(socket)), format!("udp://{}", opts.udp))
+    };
+
+    let mut gen = LoadGen::new(GenConfig {
+        mix: opts.mix,
+        aos: opts.aos,
+        corrupt: opts.corrupt,
+        drop: opts.drop,
+        seed: opts.seed,
+    });
+    let mut out = Batcher { sink, buf: Vec::with_capacity(opts.batch), limit: opts.batch };
+    let start = Instant::now();
+    for frame in gen.preamble() {
+        out.push(&frame)?;
+    }
+    for n in 1..=opts.count {
+        if let Some(frame) = gen.next_frame() {
+            out.push(&frame)?;
+        }
+        if opts.rate > 0 {
+            let due = start + Duration::from_secs_f64(n as f64 / f64::from(opts.rate));
+            if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                out.flush()?;
+                thread::sleep(wait);
+            }
+        }
+    }
+    out.flush()?;
+
+    let secs = start.elapsed().as_secs_f64().max(1e-9);
+    let stats = gen.stats();
+    println!(
+        "qspy gen: {} records to {target}: {} frames ({} corrupted, {} dropped), {} bytes in {secs:.2} s \
+         ({:.0} records/s, {:.2} MB/s)",
+        stats.records, stats.frames, stats.corrupted, stats.dropped, stats.bytes,
+        stats.records as f64 / secs, stats.bytes as f64 / secs / 1e6,
+    );
     Ok(())
 }
 
 // ── Generic streaming reader ──────────────────────────────────────────────────
 
+#[allow(clippy::too_many_arguments)]
 fn run_reader<R: Read>(
     mut source:     R,
+    max_frame:      usize,
     interpreter:    &mut FrameInterpreter,
     sinks:          &mut OutputSinks,
     frontend:       &mut Option<FrontendServer>,
@@ -301,16 +649,17 @@ fn run_reader<R: Read>(
     kbd_rx:         &mpsc::Receiver<UserCmd>,
     custom_handler: &Option<CustomCommandHandler>,
 ) {
-    let mut decoder = HdlcDecoder::new();
+    let mut session = Session::primary().with_max_frame_len(max_frame);
     let mut buf = [0u8; 4096];
 
     loop {
         match source.read(&mut buf) {
             Ok(0) => break,
             Ok(n) => {
-                process_chunk(&buf[..n], &mut decoder, interpreter, sinks, frontend);
+                process_chunk(&buf[..n], &mut session, interpreter, sinks, frontend);
             }
             Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
             Err(e) => { eprintln!("read error: {e}"); break; }
         }
 
@@ -323,8 +672,10 @@ fn run_reader<R: Read>(
 
 // ── UDP telemetry reader ──────────────────────────────────────────────────────
 
+#[allow(clippy::too_many_arguments)]
 fn run_udp(
     socket:         UdpSocket,
+    max_frame:      usize,
     interpreter:    &mut FrameInterpreter,
     sinks:          &mut OutputSinks,
     frontend:       &mut Option<FrontendServer>,
@@ -334,19 +685,20 @@ fn run_udp(
 ) {
     socket.set_read_timeout(Some(std::time::Duration::from_millis(100))).ok();
 
-    let mut decoder   = HdlcDecoder::new();
-    let mut last_peer = String::new();
+    // One session per source address; tags appear once a second peer shows up.
+    let mut sessions: SessionTable<SocketAddr> = SessionTable::with_max_frame_len(max_frame);
     let mut buf = [0u8; 4096];
 
     loop {
         match socket.recv_from(&mut buf) {
             Ok((n, peer)) => {
-                let peer_s = peer.to_string();
-                if peer_s != last_peer {
-                    println!("telemetry from {peer}");
-                    last_peer = peer_s;
+                let (session, opened) = sessions.open(peer, interpreter);
+                match opened {
+                    Some(1) => println!("telemetry from {peer}"),
+                    Some(id) => println!("telemetry from {peer} (session #{id})"),
+                    None => {}
                 }
-                process_chunk(&buf[..n], &mut decoder, interpreter, sinks, frontend);
+                process_chunk(&buf[..n], session, interpreter, sinks, frontend);
             }
             Err(e) if e.kind() == io::ErrorKind::WouldBlock
                    || e.kind() == io::ErrorKind::TimedOut => {}
@@ -361,16 +713,108 @@ fn run_udp(
     }
 }
 
+// ── TCP telemetry server ──────────────────────────────────────────────────────
+
+enum TcpInput {
+    Data(SocketAddr, Vec<u8>),
+    Closed(SocketAddr),
+}
+
+/// Accept target connections on `listener` and decode each one in its own
+/// session. A target may reconnect at any time (after a reset, or when its
+/// link comes back); the newest connection also carries QS-RX commands.
+#[allow(clippy::too_many_arguments)]
+fn run_tcp_server(
+    listener:       TcpListener,
+    max_frame:      usize,
+    interpreter:    &mut FrameInterpreter,
+    sinks:          &mut OutputSinks,
+    frontend:       &mut Option<FrontendServer>,
+    sender:         &SharedSender,
+    kbd_rx:         &mpsc::Receiver<UserCmd>,
+    custom_handler: &Option<CustomCommandHandler>,
+) {
+    let (tx, rx) = mpsc::channel::<TcpInput>();
+    let accept_sender = Arc::clone(sender);
+    thread::spawn(move || tcp_accept_loop(listener, accept_sender, tx));
+
+    let mut sessions: SessionTable<SocketAddr> = SessionTable::with_max_frame_len(max_frame);
+    loop {
+        match rx.recv_timeout(std::time::Duration::from_millis(100)) {
+            Ok(TcpInput::Data(peer, raw)) => {
+                let (session, opened) = sessions.open(peer, interpreter);
+                if let (Some(id), Some(_)) = (opened, &session.tag) {
+                    println!("telemetry from {peer} (session #{id})");
+                }
+                process_chunk(&raw, session, interpreter, sinks, frontend);
+            }
+            Ok(TcpInput::Closed(peer)) => {
+                sessions.close(&peer);
+                println!("target disconnected: {peer}");
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        if poll_commands(kbd_rx, frontend, interpreter, sender, sinks, custom_handler) {
+            break;
+        }
+        sinks.flush();
+    }
+}
+
+fn tcp_accept_loop(listener: TcpListener, sender: SharedSender, tx: mpsc::Sender<TcpInput>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => { eprintln!("telemetry accept error: {e}"); continue; }
+        };
+        let Ok(peer) = stream.peer_addr() else { continue };
+        println!("target connected: {peer}");
+        if let Ok(cmd_stream) = stream.try_clone() {
+            if cmd_stream.set_nodelay(true).is_ok() {
+                *sender.lock().unwrap() = Some(CommandSender::new(Box::new(cmd_stream)));
+            }
+        }
+        let tx = tx.clone();
+        thread::spawn(move || tcp_read_loop(stream, peer, tx));
+    }
+}
+
+fn tcp_read_loop(mut stream: TcpStream, peer: SocketAddr, tx: mpsc::Sender<TcpInput>) {
+    let mut buf = [0u8; 4096];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                if tx.send(TcpInput::Data(peer, buf[..n].to_vec())).is_err() {
+                    return;
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => { eprintln!("telemetry read error from {peer}: {e}"); break; }
+        }
+    }
+    let _ = tx.send(TcpInput::Closed(peer));
+}
+
+fn port_of(addr: &str) -> Option<u16> {
+    addr.rsplit(':').next()?.parse().ok()
+}
+
 // ── Core frame processing ─────────────────────────────────────────────────────
 
 fn process_chunk(
     raw:         &[u8],
-    decoder:     &mut HdlcDecoder,
-    interpreter: &mut FrameInterpreter,
+    session:     &mut Session,
+    primary:     &mut FrameInterpreter,
     sinks:       &mut OutputSinks,
     frontend:    &mut Option<FrontendServer>,
 ) {
     sinks.write_raw(raw);
+    let Session { tag, decoder, seq, interpreter } = session;
+    let interpreter = interpreter.as_mut().unwrap_or(primary);
+    let tag = tag.as_deref();
     for result in decoder.push_bytes(raw) {
         let frame = match result {
             Ok(frame) => frame,
@@ -379,12 +823,19 @@ fn process_chunk(
                 continue;
             }
         };
+        if let Some(missed) = seq.check(&frame) {
+            sinks.write_record(tag, RecordGroup::Info, &format!(
+                "           *** Dropped {missed} record(s) before seq={}", frame.seq
+            ));
+        }
+        let group = interpreter.group_of(frame.record_type);
         for line in interpreter.interpret(&frame) {
-            sinks.write_line(&line);
+            sinks.write_record(tag, group, &line);
             if let Some(fe) = frontend.as_mut() {
                 fe.forward_text(&line);
             }
         }
+        sinks.write_frame(&frame, interpreter);
         if let Some(fe) = frontend.as_mut() {
             fe.forward_frame(frame.record_type, &frame.payload);
         }
@@ -413,11 +864,20 @@ fn poll_commands(
             dispatch_fe_cmd(fe_cmd, sender, sinks);
         }
     }
+    if let Some(s) = sender.lock().unwrap().as_mut() {
+        if s.sync_resets(interp.target_resets()) {
+            let wanted = TargetFilter::Groups(sinks.filter()).mask(interp);
+            if let Some(offer) = push_offer(s.glb_filter(), &wanted) {
+                sinks.write_line("           target reset: its global filter lets every record through again");
+                sinks.write_line(&offer);
+            }
+        }
+    }
     false
 }
 
 /// Dispatch a user command.  Returns `true` if the caller should quit.
-fn dispatch_cmd(
+pub(crate) fn dispatch_cmd(
     cmd:            UserCmd,
     sender:         &SharedSender,
     interp:         &mut FrameInterpreter,
@@ -430,6 +890,33 @@ fn dispatch_cmd(
         UserCmd::Tick(n)         => try_send(sender, |s| s.send_tick(n)),
         UserCmd::SendCommand { id, p1, p2, p3 } =>
             try_send(sender, |s| s.send_command(id, p1, p2, p3)),
+        UserCmd::Filter(ref filter) => {
+            let mask = filter.mask(interp);
+            try_send(sender, |s| s.send_glb_filter(&mask));
+        }
+        UserCmd::Show(filter) => {
+            sinks.set_filter(filter);
+            let wanted = TargetFilter::Groups(filter).mask(interp);
+            let offer = sender.lock().unwrap().as_ref().and_then(|s| push_offer(s.glb_filter(), &wanted));
+            if let Some(offer) = offer {
+                sinks.write_line(&offer);
+            }
+        }
+        UserCmd::PushFilter => {
+            let wanted = TargetFilter::Groups(sinks.filter()).mask(interp);
+            try_send(sender, |s| {
+                if s.glb_filter() == &wanted { Ok(()) } else { s.send_glb_filter(&wanted) }
+            });
+        }
+        UserCmd::Peek { addr, len } => try_send(sender, |s| s.send_peek(addr, 0, 1, len)),
+        UserCmd::Poke { addr, ref data } => try_send(sender, |s| s.send_poke(addr, 0, 1, data)),
+        UserCmd::CurrObj { kind, ref obj } => {
+            match parse_number(obj).or_else(|| interp.object_addr(obj)) {
+                Some(addr) => try_send(sender, |s| s.send_curr_obj(kind, addr)),
+                None => eprintln!("curr: no object named {obj} in the dictionary"),
+            }
+        }
+        UserCmd::Query(kind) => try_send(sender, |s| s.send_query_curr(kind)),
         UserCmd::SaveDict(ref p) => match interp.save_dictionaries(p) {
             Ok(())  => println!("dictionaries saved to {}", p.display()),
             Err(e)  => eprintln!("dict save error: {e}"),
@@ -442,10 +929,20 @@ fn dispatch_cmd(
         UserCmd::Help          => print_help(),
         UserCmd::ToggleTextOut => sinks.toggle_text(),
         UserCmd::ToggleBinOut  => sinks.toggle_binary(),
+        UserCmd::Profile       => {
+            for line in interp.batch_profile().summary_lines() {
+                sinks.write_line(&line);
+            }
+        }
+        UserCmd::Pools         => {
+            for line in interp.pool_report() {
+                sinks.write_line(&line);
+            }
+        }
         UserCmd::Custom(ref line) => {
             if let Some(ref handler) = custom_handler {
                 if !handler(line, sender) {
-                    eprintln!("unknown command: {line}  (r/i/t/u/d/c/cls/quiet/help/text/bin/q)");
+                    eprintln!("unknown command: {line}  (r/i/t/u/d/c/cls/quiet/help/text/bin/prof/pools/q)");
                 }
             }
         }
@@ -458,7 +955,13 @@ fn print_help() {
     println!("           Keys (raw mode): X=Quit  Q=Quiet  C=Clear  H=Help");
     println!("                           R=Reset  I=Info   T=Tick(0)  U=Tick(1)");
     println!("                           O=TextOut(toggle)  S/B=BinOut(toggle)  D=SaveDict");
-    println!("           Line mode cmds: r/i/t/u/d/c/cls/quiet/help/text/bin/q");
+    println!("                           P=BatchProfile  M=PoolReport  :=type a command line");
+    println!("           Line mode cmds: r/i/t/u/d/c/cls/quiet/help/text/bin/prof/pools/q");
+    println!("                           reset  info  tick [rate]  cmd <id> [p1] [p2] [p3]");
+    println!("                           filter all|none|<GROUPS>  peek <addr> <len>");
+    println!("                           show all|<GROUPS>  push   (push = filter the target as shown)");
+    println!("                           poke <addr> <byte>...  curr <kind> <addr|name>");
+    println!("                           query <kind>   (kind: sm|ao|mp|eq|te|ap)");
 }
 
 fn dispatch_fe_cmd(cmd: FrontendCmd, sender: &SharedSender, sinks: &mut OutputSinks) {
@@ -539,8 +1042,8 @@ fn keyboard_loop_raw(
     cmd_aliases:    &'static [(&'static str, u8)],
     custom_handler: Option<CustomCommandHandler>,
 ) {
-    let _raw = match raw_terminal::RawTerminal::enter() {
-        Some(r) => r,
+    let mut _raw = match raw_terminal::RawTerminal::enter() {
+        Some(r) => Some(r),
         None    => { keyboard_loop_line(tx, cmd_aliases, custom_handler); return; }
     };
     let stdin = io::stdin();
@@ -550,7 +1053,19 @@ fn keyboard_loop_raw(
         match locked.read(&mut buf) {
             Ok(0) | Err(_) => break,
             Ok(_) => {
-                let cmd = map_raw_key(buf[0], custom_handler.is_some());
+                let cmd = if buf[0] == b':' {
+                    // Back to a cooked, echoing terminal for one command line.
+                    _raw = None;
+                    print!(":");
+                    let _ = io::Write::flush(&mut io::stdout());
+                    let mut line = String::new();
+                    let read = locked.read_line(&mut line);
+                    _raw = raw_terminal::RawTerminal::enter();
+                    if read.is_err() { break; }
+                    parse_keyboard_cmd(line.trim(), cmd_aliases, &custom_handler)
+                } else {
+                    map_raw_key(buf[0], custom_handler.is_some())
+                };
                 let quit = matches!(cmd, Some(UserCmd::Quit));
                 if let Some(c) = cmd {
                     if tx.send(c).is_err() { break; }
@@ -569,6 +1084,8 @@ fn map_raw_key(b: u8, has_custom: bool) -> Option<UserCmd> {
         b'C' | b'c'         => Some(UserCmd::ClearScreen),
         b'H' | b'h' | b'?' => Some(UserCmd::Help),
         b'O' | b'o'         => Some(UserCmd::ToggleTextOut),
+        b'P' | b'p'         => Some(UserCmd::Profile),
+        b'M' | b'm'         => Some(UserCmd::Pools),
         b'S' | b's' | b'B' | b'b' => Some(UserCmd::ToggleBinOut),
         b'R' | b'r'         => Some(UserCmd::Reset),
         b'E' | b'e'         => Some(UserCmd::Custom((b as char).to_string())),
@@ -583,17 +1100,17 @@ fn map_raw_key(b: u8, has_custom: bool) -> Option<UserCmd> {
     }
 }
 
-fn parse_keyboard_cmd(
+pub(crate) fn parse_keyboard_cmd(
     line:           &str,
     cmd_aliases:    &'static [(&'static str, u8)],
     custom_handler: &Option<CustomCommandHandler>,
 ) -> Option<UserCmd> {
-    let mut parts = line.splitn(5, ' ');
-    let word = parts.clone().next()?;
+    let mut parts = line.split_whitespace();
+    let word = parts.clone().next().unwrap_or("");
     if let Some(&(_, id)) = cmd_aliases.iter().find(|&&(alias, _)| alias == word) {
         return Some(UserCmd::SendCommand { id, p1: 0, p2: 0, p3: 0 });
     }
-    match parts.next()? {
+    match parts.next().unwrap_or("") {
         "r" | "reset"                      => Some(UserCmd::Reset),
         "er" | "esp-reset" | "board-reset" | "hard-reset"
             if custom_handler.is_some() => Some(UserCmd::Custom(word.to_string())),
@@ -614,29 +1131,96 @@ fn parse_keyboard_cmd(
         }
         "c" | "cmd"    => {
             let id = parts.next()?.parse::<u8>().ok()?;
-            let p1 = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0u32);
-            let p2 = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0u32);
-            let p3 = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0u32);
+            let mut param = || {
+                parts.next().and_then(parse_number).and_then(|n| u32::try_from(n).ok()).unwrap_or(0)
+            };
+            let (p1, p2, p3) = (param(), param(), param());
             Some(UserCmd::SendCommand { id, p1, p2, p3 })
         }
+        "filter" => {
+            let filter = match parts.next() {
+                Some("all") => TargetFilter::All,
+                Some("none") => TargetFilter::None,
+                Some(list) => match GroupFilter::parse(list) {
+                    Ok(groups) => TargetFilter::Groups(groups),
+                    Err(e) => { eprintln!("filter: {e}"); return None; }
+                },
+                None => { eprintln!("usage: filter all|none|<GROUPS>"); return None; }
+            };
+            Some(UserCmd::Filter(filter))
+        }
+        "show" => match parts.next() {
+            Some("all") => Some(UserCmd::Show(GroupFilter::allow_all())),
+            Some(list) => match GroupFilter::parse(list) {
+                Ok(groups) => Some(UserCmd::Show(groups)),
+                Err(e) => { eprintln!("show: {e}"); None }
+            },
+            None => { eprintln!("usage: show all|<GROUPS>"); None }
+        },
+        "push"             => Some(UserCmd::PushFilter),
+        "peek" => {
+            let addr = parts.next().and_then(parse_number);
+            let len = parts.next().and_then(parse_number).and_then(|n| u8::try_from(n).ok());
+            match (addr, len) {
+                (Some(addr), Some(len)) => Some(UserCmd::Peek { addr, len }),
+                _ => { eprintln!("usage: peek <addr> <len 1..255>"); None }
+            }
+        }
+        "poke" => {
+            let addr = parts.next().and_then(parse_number);
+            let data: Option<Vec<u8>> = parts
+                .map(|b| parse_number(b).and_then(|n| u8::try_from(n).ok()))
+                .collect();
+            match (addr, data) {
+                (Some(addr), Some(data)) if !data.is_empty() && data.len() <= 255 =>
+                    Some(UserCmd::Poke { addr, data }),
+                _ => { eprintln!("usage: poke <addr> <byte>..."); None }
+            }
+        }
+        "curr" => match (parts.next().and_then(parse_obj_kind), parts.next()) {
+            (Some(kind), Some(obj)) => Some(UserCmd::CurrObj { kind, obj: obj.to_string() }),
+            _ => { eprintln!("usage: curr sm|ao|mp|eq|te|ap <addr|name>"); None }
+        },
+        "query" => match parts.next().and_then(parse_obj_kind) {
+            Some(kind) => Some(UserCmd::Query(kind)),
+            None => { eprintln!("usage: query sm|ao|mp|eq|te|ap"); None }
+        },
         "cls"              => Some(UserCmd::ClearScreen),
         "quiet"            => Some(UserCmd::ToggleQuiet),
         "help"             => Some(UserCmd::Help),
         "text"             => Some(UserCmd::ToggleTextOut),
         "bin"              => Some(UserCmd::ToggleBinOut),
+        "p" | "prof"       => Some(UserCmd::Profile),
+        "pools"            => Some(UserCmd::Pools),
         "q" | "quit"       => Some(UserCmd::Quit),
         ""                 => None,
         other              => {
             if custom_handler.is_some() {
                 Some(UserCmd::Custom(other.to_string()))
             } else {
-                eprintln!("unknown command: {other}  (r/reset/er/esp-reset/board-reset/i/t/u/d/c/cls/quiet/help/text/bin/q)");
+                eprintln!("unknown command: {other}  (r/reset/er/esp-reset/board-reset/i/t/u/d/c/filter/show/push/peek/poke/curr/query/cls/quiet/help/text/bin/prof/pools/q)");
                 None
             }
         }
     }
 }
 
+/// A decimal or `0x`-prefixed hexadecimal number.
+/// Object kind code of `QS_RX_CURR_OBJ` / `QS_RX_QUERY_CURR`.
+fn parse_obj_kind(text: &str) -> Option<u8> {
+    ["sm", "ao", "mp", "eq", "te", "ap"]
+        .iter()
+        .position(|kind| kind.eq_ignore_ascii_case(text))
+        .map(|code| code as u8)
+}
+
+fn parse_number(text: &str) -> Option<u64> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
 // ── Raw terminal mode (Unix only) ────────────────────────────────────────────
 
 #[cfg(unix)]
@@ -673,90 +1257,3 @@ mod raw_terminal {
         }
     }
 }
-
-// ── Serial port (Unix only) ───────────────────────────────────────────────────
-
-#[cfg(unix)]
-mod serial {
-    use std::fs::{File, OpenOptions};
-    use std::io;
-    use std::mem::MaybeUninit;
-    use std::os::fd::AsRawFd;
-    use std::os::unix::fs::OpenOptionsExt;
-    use std::path::Path;
-
-    pub fn open(path: &Path, baud: u32) -> io::Result<File> {
-        let file = OpenOptions::new()
-            .read(true).write(true)
-            .custom_flags(libc::O_NOCTTY)
-            .open(path)?;
-        configure(file.as_raw_fd(), baud)?;
-        Ok(file)
-    }
-
-    fn configure(fd: libc::c_int, baud: u32) -> io::Result<()> {
-        let mut t = current_termios(fd)?;
-        let speed = baud_constant(baud)?;
-        unsafe {
-            libc::cfmakeraw(&mut t);
-            if libc::cfsetispeed(&mut t, speed) != 0 {
-                return Err(io::Error::last_os_error());
-            }
-            if libc::cfsetospeed(&mut t, speed) != 0 {
-                return Err(io::Error::last_os_error());
-            }
-        }
-        t.c_cflag |= libc::CLOCAL | libc::CREAD;
-        t.c_cflag &= !libc::CSIZE;
-        t.c_cflag |= libc::CS8;
-        t.c_cflag &= !(libc::PARENB | libc::CSTOPB);
-        #[cfg(any(target_os = "android", target_os = "linux"))]
-        { t.c_cflag &= !libc::CRTSCTS; }
-        t.c_iflag &= !(libc::IXON | libc::IXOFF | libc::IXANY);
-        t.c_cc[libc::VMIN]  = 1;
-        t.c_cc[libc::VTIME] = 0;
-        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &t) } != 0 {
-            return Err(io::Error::last_os_error());
-        }
-        Ok(())
-    }
-
-    fn current_termios(fd: libc::c_int) -> io::Result<libc::termios> {
-        let mut t = MaybeUninit::uninit();
-        if unsafe { libc::tcgetattr(fd, t.as_mut_ptr()) } != 0 {
-            return Err(io::Error::last_os_error());
-        }
-        Ok(unsafe { t.assume_init() })
-    }
-
-    fn baud_constant(baud: u32) -> io::Result<libc::speed_t> {
-        Ok(match baud {
-            9_600   => libc::B9600,
-            19_200  => libc::B19200,
-            38_400  => libc::B38400,
-            57_600  => libc::B57600,
-            115_200 => libc::B115200,
-            230_400 => libc::B230400,
-            460_800 => libc::B460800,
-            921_600 => libc::B921600,
-            _ => return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                format!("unsupported baud rate: {baud}"),
-            )),
-        })
-    }
-}
-
-#[cfg(not(unix))]
-mod serial {
-    use std::fs::File;
-    use std::io;
-    use std::path::Path;
-
-    pub fn open(_path: &Path, _baud: u32) -> io::Result<File> {
-        Err(io::Error::new(
-            io::ErrorKind::Unsupported,
-            "serial devices are only supported on Unix targets",
-        ))
-    }
-}
@@ -0,0 +1,188 @@
+//! Checks a capture of the target's trace self-test ([`qs::qs_selftest`]).
+//!
+//! `qspy -f selftest.qs --verify-selftest` passes when every record of the
+//! suite is in the capture at least once with exactly the payload the target
+//! meant to send at the sizes it announced, and decodes to the known values:
+//! objects [`OBJ`], functions [`FUN`], signals [`SIG`] and every other number
+//! [`NUM`]. The dictionary entries must resolve to their names and the
+//! closing `QS_DICT_HASH` must agree with them. Other records in the capture
+//! are ignored, so the suite can run next to the application.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+use qs::predefined;
+use qs::records::RecordSizes;
+use qs::selftest::{self, ENUM_NAME, FUN, FUN_NAME, NUM, OBJ, OBJ_NAME, SIG, SIG_NAME, USR, USR_NAME};
+use qs_protocol::TargetInfo;
+
+use crate::{FrameInterpreter, HdlcDecoder, QsFrame, TargetSizes, Value};
+
+/// Outcome per record id of the suite.
+#[derive(Debug, Clone)]
+pub struct SelftestReport {
+    /// `Err` holds what was wrong with the last frame of that id, or that
+    /// none arrived.
+    pub results: BTreeMap<u8, Result<(), String>>,
+    /// Frames examined.
+    pub records: u64,
+}
+
+impl SelftestReport {
+    /// `true` when every record of the suite arrived intact.
+    pub fn passed(&self) -> bool {
+        self.results.values().all(Result::is_ok)
+    }
+
+    /// Human-readable verdict, one line per failed record.
+    pub fn summary_lines(&self) -> Vec<String> {
+        let ok = self.results.values().filter(|r| r.is_ok()).count();
+        let mut lines = vec![format!(
+            "self-test: {ok}/{} record types OK, {} record(s) read",
+            self.results.len(), self.records
+        )];
+        for (id, result) in &self.results {
+            if let Err(problem) = result {
+                lines.push(format!("  FAIL rec={id}: {problem}"));
+            }
+        }
+        lines
+    }
+}
+
+/// Checks frames against the self-test suite as they arrive.
+pub struct SelftestChecker {
+    report: SelftestReport,
+}
+
+impl Default for SelftestChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SelftestChecker {
+    pub fn new() -> Self {
+        let dictionaries = [
+            predefined::TARGET_INFO, predefined::OBJ_DICT, predefined::FUN_DICT, predefined::SIG_DICT,
+            predefined::USR_DICT, predefined::ENUM_DICT, predefined::DICT_HASH,
+        ];
+        let results = selftest::suite()
+            .iter()
+            .map(|record| record.record_type())
+            .chain(dictionaries)
+            .map(|id| (id, Err("not in the capture".to_string())))
+            .collect();
+        Self { report: SelftestReport { results, records: 0 } }
+    }
+
+    /// Interprets `frame` and checks it if it belongs to the suite.
+    pub fn feed(&mut self, interp: &mut FrameInterpreter, frame: &QsFrame) {
+        self.report.records += 1;
+        let lines = interp.interpret(frame);
+        let Some(result) = self.report.results.get_mut(&frame.record_type) else { return };
+        if result.is_ok() {
+            return;
+        }
+        *result = check(interp, frame, &lines);
+    }
+
+    pub fn finish(self) -> SelftestReport {
+        self.report
+    }
+}
+
+/// Runs a saved capture through `interpreter` and checks the self-test in it.
+pub fn verify_capture<R: Read>(mut source: R, interpreter: &mut FrameInterpreter) -> io::Result<SelftestReport> {
+    let mut decoder = HdlcDecoder::new();
+    let mut checker = SelftestChecker::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = match source.read(&mut buf) {
+            Ok(0) => return Ok(checker.finish()),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for frame in decoder.push_bytes(&buf[..n]).into_iter().flatten() {
+            checker.feed(interpreter, &frame);
+        }
+    }
+}
+
+fn check(interp: &FrameInterpreter, frame: &QsFrame, lines: &[String]) -> Result<(), String> {
+    let sizes = interp.sizes();
+    let named = |found: bool, what: &str| match found {
+        true => Ok(()),
+        false => Err(format!("{what} did not resolve: {}", lines.join(" | "))),
+    };
+    match frame.record_type {
+        predefined::TARGET_INFO => TargetInfo::decode(&frame.payload)
+            .map(|_| ())
+            .ok_or_else(|| format!("undecodable target info, {} bytes", frame.payload.len())),
+        predefined::OBJ_DICT => named(interp.object_name(low(OBJ, sizes.obj_ptr_size)) == Some(OBJ_NAME), OBJ_NAME),
+        predefined::FUN_DICT => named(interp.function_name(low(FUN, sizes.fun_ptr_size)) == Some(FUN_NAME), FUN_NAME),
+        predefined::SIG_DICT => named(interp.signal_name(u64::from(SIG), 0) == Some(SIG_NAME), SIG_NAME),
+        predefined::USR_DICT => named(interp.user_records().any(|r| r == (USR, USR_NAME)), USR_NAME),
+        predefined::ENUM_DICT => named(lines.iter().any(|l| l.ends_with(ENUM_NAME)), ENUM_NAME),
+        predefined::DICT_HASH => match lines.iter().any(|l| l.contains("(dictionaries match)")) {
+            true => Ok(()),
+            false => Err(lines.join(" | ")),
+        },
+        record_type => check_fields(interp, frame, record_type),
+    }
+}
+
+fn check_fields(interp: &FrameInterpreter, frame: &QsFrame, record_type: u8) -> Result<(), String> {
+    let sizes = interp.sizes();
+    let record = selftest::suite()
+        .into_iter()
+        .find(|record| record.record_type() == record_type)
+        .expect("only suite records are checked");
+    let skip = if record.has_timestamp() { usize::from(sizes.time_size) } else { 0 };
+    let expected = record.encode(&record_sizes(sizes));
+    let sent = frame.payload.get(skip..).unwrap_or_default();
+    if sent != &expected[..] {
+        return Err(format!("payload {} expected {}", hex(sent), hex(&expected)));
+    }
+
+    let decoded = interp.decode(frame);
+    if !decoded.is_complete() {
+        return Err("qspy has no complete layout for it".to_string());
+    }
+    for field in decoded.fields() {
+        let want = match field.value {
+            Value::Object(_) => Value::Object(low(OBJ, sizes.obj_ptr_size)),
+            Value::Function(_) => Value::Function(low(FUN, sizes.fun_ptr_size)),
+            Value::Signal(_) => Value::Signal(u64::from(SIG)),
+            _ => Value::Unsigned(u64::from(NUM)),
+        };
+        if field.value != want {
+            return Err(format!("{} decoded as {:?}, expected {want:?}", field.name, field.value));
+        }
+    }
+    Ok(())
+}
+
+fn record_sizes(sizes: &TargetSizes) -> RecordSizes {
+    RecordSizes {
+        signal: sizes.signal_size,
+        obj_ptr: sizes.obj_ptr_size,
+        fun_ptr: sizes.fun_ptr_size,
+        equeue_ctr: sizes.equeue_ctr,
+        time_evt_ctr: sizes.timeevt_ctr,
+        mpool_ctr: sizes.mpool_ctr,
+    }
+}
+
+/// `value` cut to `size` bytes, as a narrower target sends it.
+fn low(value: u64, size: u8) -> u64 {
+    match size {
+        1..=7 => value & ((1 << (8 * u32::from(size))) - 1),
+        _ => value,
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02X}")).collect()
+}
@@ -0,0 +1,301 @@
+//! Serial-port telemetry transport (Unix termios and Windows COM ports).
+//!
+//! Reads use a short timeout so the main loop keeps servicing keyboard and
+//! front-end commands while the target is silent; a timed-out read surfaces
+//! as [`io::ErrorKind::TimedOut`] rather than `Ok(0)`, which would look like
+//! end-of-stream to the generic reader.
+//!
+//! USB-UART bridges (FTDI in particular) buffer received bytes for up to
+//! their *latency timer* — 16 ms by default — before handing them to the
+//! host. That batching shows up as bursts of identical host receive times,
+//! so [`tune_latency`] lowers the timer where the OS allows it and otherwise
+//! prints how to do it by hand.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// Latency timers above this (ms) are reported as likely to skew timing.
+const LATENCY_WARN_MS: u8 = 2;
+
+/// An open, configured serial port.
+pub struct Port {
+    file: File,
+}
+
+impl Port {
+    /// Independent handle to the same device, used as the command channel.
+    pub fn try_clone(&self) -> io::Result<File> {
+        self.file.try_clone()
+    }
+}
+
+impl Read for Port {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.file.read(buf)? {
+            0 if !buf.is_empty() => Err(io::ErrorKind::TimedOut.into()),
+            n => Ok(n),
+        }
+    }
+}
+
+impl Write for Port {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.file.write(buf) }
+    fn flush(&mut self) -> io::Result<()> { self.file.flush() }
+}
+
+/// Open `path` at `baud`, 8N1 raw, with reads returning after at most
+/// `read_timeout_ms` when no data arrives.
+pub fn open(path: &Path, baud: u32, read_timeout_ms: u32) -> io::Result<Port> {
+    sys::open(path, baud, read_timeout_ms).map(|file| Port { file })
+}
+
+/// Lower the USB-UART latency timer of `path` to `target_ms` when possible;
+/// otherwise warn (with instructions) if the current timer is coarse.
+pub fn tune_latency(path: &Path, target_ms: u8) {
+    sys::tune_latency(path, target_ms);
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::fs::{File, OpenOptions};
+    use std::io;
+    use std::mem::MaybeUninit;
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::path::{Path, PathBuf};
+
+    use super::LATENCY_WARN_MS;
+
+    pub fn open(path: &Path, baud: u32, read_timeout_ms: u32) -> io::Result<File> {
+        let file = OpenOptions::new()
+            .read(true).write(true)
+            .custom_flags(libc::O_NOCTTY)
+            .open(path)?;
+        configure(file.as_raw_fd(), baud, read_timeout_ms)?;
+        Ok(file)
+    }
+
+    fn configure(fd: libc::c_int, baud: u32, read_timeout_ms: u32) -> io::Result<()> {
+        let mut t = current_termios(fd)?;
+        let speed = baud_constant(baud)?;
+        unsafe {
+            libc::cfmakeraw(&mut t);
+            if libc::cfsetispeed(&mut t, speed) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::cfsetospeed(&mut t, speed) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        t.c_cflag |= libc::CLOCAL | libc::CREAD;
+        t.c_cflag &= !libc::CSIZE;
+        t.c_cflag |= libc::CS8;
+        t.c_cflag &= !(libc::PARENB | libc::CSTOPB);
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        { t.c_cflag &= !libc::CRTSCTS; }
+        t.c_iflag &= !(libc::IXON | libc::IXOFF | libc::IXANY);
+        // VTIME counts deciseconds: 0 keeps a fully blocking read, otherwise
+        // round up so a 1..99 ms request still times out.
+        if read_timeout_ms == 0 {
+            t.c_cc[libc::VMIN]  = 1;
+            t.c_cc[libc::VTIME] = 0;
+        } else {
+            t.c_cc[libc::VMIN]  = 0;
+            t.c_cc[libc::VTIME] = read_timeout_ms.div_ceil(100).min(255) as libc::cc_t;
+        }
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &t) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn current_termios(fd: libc::c_int) -> io::Result<libc::termios> {
+        let mut t = MaybeUninit::uninit();
+        if unsafe { libc::tcgetattr(fd, t.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(unsafe { t.assume_init() })
+    }
+
+    fn baud_constant(baud: u32) -> io::Result<libc::speed_t> {
+        Ok(match baud {
+            9_600   => libc::B9600,
+            19_200  => libc::B19200,
+            38_400  => libc::B38400,
+            57_600  => libc::B57600,
+            115_200 => libc::B115200,
+            230_400 => libc::B230400,
+            460_800 => libc::B460800,
+            921_600 => libc::B921600,
+            _ => return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported baud rate: {baud}"),
+            )),
+        })
+    }
+
+    /// Linux exposes the FTDI latency timer as
+    /// `/sys/bus/usb-serial/devices/<tty>/latency_timer`.
+    pub fn tune_latency(path: &Path, target_ms: u8) {
+        let Some(knob) = latency_knob(path) else { return };
+        let Some(current) = std::fs::read_to_string(&knob).ok()
+            .and_then(|s| s.trim().parse::<u8>().ok())
+        else { return };
+        if current <= target_ms {
+            return;
+        }
+        match std::fs::write(&knob, format!("{target_ms}\n")) {
+            Ok(()) => println!("serial latency timer: {current} ms -> {target_ms} ms"),
+            Err(e) if current > LATENCY_WARN_MS => eprintln!(
+                "warning: serial latency timer is {current} ms ({e}); host timing will be \
+                 batched. Lower it with: echo {target_ms} | sudo tee {}",
+                knob.display()
+            ),
+            Err(_) => {}
+        }
+    }
+
+    fn latency_knob(path: &Path) -> Option<PathBuf> {
+        let dev = std::fs::canonicalize(path).ok()?;
+        let tty = dev.file_name()?;
+        let knob = Path::new("/sys/bus/usb-serial/devices").join(tty).join("latency_timer");
+        knob.exists().then_some(knob)
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::ffi::c_void;
+    use std::fs::{File, OpenOptions};
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+    use std::path::{Path, PathBuf};
+
+    use super::LATENCY_WARN_MS;
+
+    #[repr(C)]
+    #[allow(non_snake_case)]
+    struct Dcb {
+        DCBlength:  u32,
+        BaudRate:   u32,
+        flags:      u32,
+        wReserved:  u16,
+        XonLim:     u16,
+        XoffLim:    u16,
+        ByteSize:   u8,
+        Parity:     u8,
+        StopBits:   u8,
+        XonChar:    i8,
+        XoffChar:   i8,
+        ErrorChar:  i8,
+        EofChar:    i8,
+        EvtChar:    i8,
+        wReserved1: u16,
+    }
+
+    #[repr(C)]
+    #[allow(non_snake_case)]
+    struct CommTimeouts {
+        ReadIntervalTimeout:         u32,
+        ReadTotalTimeoutMultiplier:  u32,
+        ReadTotalTimeoutConstant:    u32,
+        WriteTotalTimeoutMultiplier: u32,
+        WriteTotalTimeoutConstant:   u32,
+    }
+
+    // DCB bit-field flags: fBinary, DTR_CONTROL_ENABLE, RTS_CONTROL_ENABLE.
+    const F_BINARY:     u32 = 1 << 0;
+    const F_DTR_ENABLE: u32 = 1 << 4;
+    const F_RTS_ENABLE: u32 = 1 << 12;
+    const NOPARITY:     u8  = 0;
+    const ONESTOPBIT:   u8  = 0;
+    const MAXDWORD:     u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCommState(file: *mut c_void, dcb: *mut Dcb) -> i32;
+        fn SetCommState(file: *mut c_void, dcb: *const Dcb) -> i32;
+        fn SetCommTimeouts(file: *mut c_void, timeouts: *const CommTimeouts) -> i32;
+        fn SetupComm(file: *mut c_void, in_queue: u32, out_queue: u32) -> i32;
+    }
+
+    pub fn open(path: &Path, baud: u32, read_timeout_ms: u32) -> io::Result<File> {
+        let file = OpenOptions::new().read(true).write(true).open(device_path(path))?;
+        let h = file.as_raw_handle() as *mut c_void;
+
+        let mut dcb: Dcb = unsafe { std::mem::zeroed() };
+        dcb.DCBlength = std::mem::size_of::<Dcb>() as u32;
+        if unsafe { GetCommState(h, &mut dcb) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        dcb.BaudRate = baud;
+        dcb.flags    = F_BINARY | F_DTR_ENABLE | F_RTS_ENABLE;
+        dcb.ByteSize = 8;
+        dcb.Parity   = NOPARITY;
+        dcb.StopBits = ONESTOPBIT;
+        if unsafe { SetCommState(h, &dcb) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // Larger driver queues ride out bursts while the console is busy.
+        unsafe { SetupComm(h, 64 * 1024, 4 * 1024) };
+
+        // MAXDWORD/MAXDWORD/constant: return at once with whatever is buffered,
+        // else wait up to `constant` ms for the first byte. All-zero timeouts
+        // would instead block until the whole read buffer fills.
+        let constant = match read_timeout_ms {
+            0 => MAXDWORD - 1,
+            ms => ms.min(MAXDWORD - 1),
+        };
+        let timeouts = CommTimeouts {
+            ReadIntervalTimeout:         MAXDWORD,
+            ReadTotalTimeoutMultiplier:  MAXDWORD,
+            ReadTotalTimeoutConstant:    constant,
+            WriteTotalTimeoutMultiplier: 0,
+            WriteTotalTimeoutConstant:   1_000,
+        };
+        if unsafe { SetCommTimeouts(h, &timeouts) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(file)
+    }
+
+    /// `COM10` and above only open through the `\\.\` device namespace;
+    /// using it for every COM port is harmless.
+    fn device_path(path: &Path) -> PathBuf {
+        let s = path.to_string_lossy();
+        if s.len() > 3 && s[..3].eq_ignore_ascii_case("COM") {
+            PathBuf::from(format!(r"\\.\{s}"))
+        } else {
+            path.to_owned()
+        }
+    }
+
+    /// The FTDI VCP latency timer is a per-port driver setting with no
+    /// user-mode API, so only a hint can be given.
+    pub fn tune_latency(path: &Path, target_ms: u8) {
+        if target_ms <= LATENCY_WARN_MS {
+            eprintln!(
+                "note: for FTDI adapters set Device Manager > {} > Port Settings > \
+                 Advanced > Latency Timer to {target_ms} ms (default 16 ms batches reads)",
+                path.display()
+            );
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::path::Path;
+
+    pub fn open(_path: &Path, _baud: u32, _read_timeout_ms: u32) -> io::Result<File> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "serial devices are not supported on this platform",
+        ))
+    }
+
+    pub fn tune_latency(_path: &Path, _target_ms: u8) {}
+}
@@ -0,0 +1,116 @@
+//! Per-source decoding state.
+//!
+//! Every telemetry source gets its own [`Session`]: an HDLC decoder, a
+//! sequence check and, for all but the first source, its own
+//! [`FrameInterpreter`] so dictionaries and target sizes of one device never
+//! leak into another. With several sources on one socket (a fleet of devices
+//! tracing over UDP) each output line carries the session tag.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::{FrameInterpreter, HdlcDecoder, QsFrame, DEFAULT_MAX_FRAME_LEN};
+use qs::predefined;
+
+/// Detects frames lost between consecutive sequence numbers.
+#[derive(Debug, Default)]
+pub struct SeqCheck {
+    last: Option<u8>,
+}
+
+impl SeqCheck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `frame` and return how many frames were missed before it.
+    ///
+    /// A `TARGET_INFO` frame marks a (re)start of the target's sequence
+    /// counter and is never reported as a gap.
+    pub fn check(&mut self, frame: &QsFrame) -> Option<u8> {
+        let prev = self.last.replace(frame.seq);
+        if frame.record_type == predefined::TARGET_INFO {
+            return None;
+        }
+        let missed = frame.seq.wrapping_sub(prev?.wrapping_add(1));
+        (missed != 0).then_some(missed)
+    }
+}
+
+/// Decoding state of one telemetry source.
+pub struct Session {
+    /// Prefix for this session's output lines; `None` while it is the only one.
+    pub tag:         Option<String>,
+    pub decoder:     HdlcDecoder,
+    pub seq:         SeqCheck,
+    /// Own interpreter, or `None` to use the console's primary interpreter.
+    pub interpreter: Option<FrameInterpreter>,
+}
+
+impl Session {
+    /// Session decoded by the console's primary interpreter.
+    pub fn primary() -> Self {
+        Self { tag: None, decoder: HdlcDecoder::new(), seq: SeqCheck::new(), interpreter: None }
+    }
+
+    /// Session with its own interpreter, forked from `template`.
+    pub fn forked(template: &FrameInterpreter) -> Self {
+        Self { interpreter: Some(template.fork()), ..Self::primary() }
+    }
+
+    /// The same session, decoding frames of up to `max` bytes.
+    pub fn with_max_frame_len(self, max: usize) -> Self {
+        Self { decoder: HdlcDecoder::with_max_frame_len(max), ..self }
+    }
+}
+
+/// Live sessions keyed by source (a UDP peer address, a TCP connection).
+///
+/// Sessions are numbered in opening order. The first live session is decoded
+/// by the primary interpreter, later ones fork it; once more than one is live
+/// every session carries its `#n` tag. Closing a session frees its slot, so a
+/// target that reconnects after a reset is decoded by the primary again.
+pub struct SessionTable<K> {
+    sessions:      HashMap<K, (usize, Session)>,
+    opened:        usize,
+    max_frame_len: usize,
+}
+
+impl<K: Eq + Hash> Default for SessionTable<K> {
+    fn default() -> Self {
+        Self::with_max_frame_len(DEFAULT_MAX_FRAME_LEN)
+    }
+}
+
+impl<K: Eq + Hash> SessionTable<K> {
+    /// A table whose sessions decode frames of up to `max` bytes.
+    pub fn with_max_frame_len(max: usize) -> Self {
+        Self { sessions: HashMap::new(), opened: 0, max_frame_len: max }
+    }
+
+    /// Session for `key`, opening one on first use. The second value is the
+    /// session number when it was just opened.
+    pub fn open(&mut self, key: K, template: &FrameInterpreter) -> (&mut Session, Option<usize>) {
+        if self.sessions.contains_key(&key) {
+            return (&mut self.sessions.get_mut(&key).unwrap().1, None);
+        }
+        self.opened += 1;
+        let n = self.opened;
+        let primary_taken = self.sessions.values().any(|(_, s)| s.interpreter.is_none());
+        let session = if primary_taken { Session::forked(template) } else { Session::primary() };
+        let mut session = session.with_max_frame_len(self.max_frame_len);
+        if !self.sessions.is_empty() {
+            for (id, s) in self.sessions.values_mut().filter(|(_, s)| s.tag.is_none()) {
+                s.tag = Some(format!("#{id}"));
+            }
+            session.tag = Some(format!("#{n}"));
+        }
+        let (_, session) = self.sessions.entry(key).or_insert((n, session));
+        (session, Some(n))
+    }
+
+    /// Drop the session for `key`; returns `false` if there was none.
+    pub fn close(&mut self, key: &K) -> bool {
+        self.sessions.remove(key).is_some()
+    }
+}
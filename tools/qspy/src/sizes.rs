@@ -1,8 +1,14 @@
+use std::fmt;
+
+use qs_protocol::TargetInfo;
+
+use crate::names::Label;
+
 /// Target-side type widths, reported via `TARGET_INFO` and overridable via CLI flags.
 ///
 /// All sizes are in bytes. Valid values are 1, 2, 4, or 8 (invalid packed nibbles fall
 /// back to the field's current default).
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TargetSizes {
     pub time_size:    u8,
     pub obj_ptr_size: u8,
@@ -32,43 +38,44 @@ impl Default for TargetSizes {
 }
 
 impl TargetSizes {
-    /// Update from a `TARGET_INFO` frame payload (starting at `payload[0]` = `is_reset`).
-    ///
-    /// The packed byte layout matches `predefined::target_info_payload`:
-    /// - `payload[3]`: `signal_size | (event_size << 4)`
-    /// - `payload[4]`: `equeue_ctr | (timeevt_ctr << 4)`
-    /// - `payload[5]`: `mpool_siz  | (mpool_ctr   << 4)`
-    /// - `payload[6]`: `obj_ptr    | (fun_ptr      << 4)`
-    /// - `payload[7]`: `time_size`
-    pub fn update_from_target_info(&mut self, payload: &[u8]) {
-        if payload.len() < 8 {
-            return;
-        }
-        let sig_evt = payload[3];
-        let eq_te   = payload[4];
-        let mp      = payload[5];
-        let ptrs    = payload[6];
-        let time    = payload[7];
+    /// Update from a decoded `TARGET_INFO` record. Widths other than 1, 2,
+    /// 4 or 8 leave the field as it was.
+    pub fn update_from_target_info(&mut self, info: &TargetInfo) {
+        self.signal_size  = valid_size(info.signal_size, self.signal_size);
+        self.event_size   = valid_size(info.event_size, self.event_size);
+        self.equeue_ctr   = valid_size(info.equeue_ctr_size, self.equeue_ctr);
+        self.timeevt_ctr  = valid_size(info.time_evt_ctr_size, self.timeevt_ctr);
+        self.mpool_siz    = valid_size(info.mpool_size_size, self.mpool_siz);
+        self.mpool_ctr    = valid_size(info.mpool_ctr_size, self.mpool_ctr);
+        self.obj_ptr_size = valid_size(info.obj_ptr_size, self.obj_ptr_size);
+        self.fun_ptr_size = valid_size(info.fun_ptr_size, self.fun_ptr_size);
+        self.time_size    = valid_size(info.time_size, self.time_size);
+    }
 
-        self.signal_size  = valid_size(sig_evt & 0x0F, self.signal_size);
-        self.event_size   = valid_size((sig_evt >> 4) & 0x0F, self.event_size);
-        self.equeue_ctr   = valid_size(eq_te & 0x0F, self.equeue_ctr);
-        self.timeevt_ctr  = valid_size((eq_te >> 4) & 0x0F, self.timeevt_ctr);
-        self.mpool_siz    = valid_size(mp & 0x0F, self.mpool_siz);
-        self.mpool_ctr    = valid_size((mp >> 4) & 0x0F, self.mpool_ctr);
-        self.obj_ptr_size = valid_size(ptrs & 0x0F, self.obj_ptr_size);
-        self.fun_ptr_size = valid_size((ptrs >> 4) & 0x0F, self.fun_ptr_size);
-        self.time_size    = valid_size(time, self.time_size);
+    /// Ticks from timestamp `since` to `until`, allowing for one wrap of a
+    /// `time_size`-byte counter.
+    pub fn time_delta(&self, since: u64, until: u64) -> u64 {
+        let delta = until.wrapping_sub(since);
+        match self.time_size {
+            1..=7 => delta & ((1 << (8 * u32::from(self.time_size))) - 1),
+            _ => delta,
+        }
     }
 
     /// Format an address with the correct hex width for the given pointer size.
     pub fn fmt_addr(addr: u64, size: u8) -> String {
-        match size {
-            1 => format!("0x{addr:02X}"),
-            2 => format!("0x{addr:04X}"),
-            4 => format!("0x{addr:08X}"),
-            _ => format!("0x{addr:016X}"),
-        }
+        Label::Addr { value: addr, size }.to_string()
+    }
+}
+
+impl fmt::Display for TargetSizes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "T={} O={} F={} S={} E={} Q={} C={} B={} P={}",
+            self.time_size, self.obj_ptr_size, self.fun_ptr_size, self.signal_size,
+            self.event_size, self.equeue_ctr, self.timeevt_ctr, self.mpool_siz, self.mpool_ctr,
+        )
     }
 }
 
@@ -0,0 +1,386 @@
+//! Offline trace-specification checker ("LTL-lite").
+//!
+//! A spec file lists simple temporal properties, one per line, that are
+//! evaluated against a saved capture (`qspy -f trace.qs --check dpp.spec`):
+//!
+//! ```text
+//! # every philosopher told to eat reports DONE within 5000 ticks
+//! after Disp===> Obj=philo[*] Sig=EAT_SIG expect QF-Pub Sdr=philo[*] Sig=DONE_SIG within 5000
+//! # the table never grants twice in a row without a DONE in between
+//! never AO-Post Obj=philo[*] Sig=EAT_SIG twice without QF-Pub Sdr=philo[*] Sig=DONE_SIG
+//! ```
+//!
+//! Patterns match the decoded console lines, so they use the same names the
+//! console shows. A bare word matches the record label (`Disp===>`,
+//! `AO-Post`, ...); `Key=Value` matches a field anywhere in the line,
+//! including inside `Evt<...>`. One `*` in a value matches any text and binds
+//! it: when both sides of a property bind, they must bind the same text, so
+//! `philo[*]` checks every philosopher separately.
+//!
+//! `within` is in target timestamp units; records without a timestamp keep
+//! the time of the record before them. Violations are reported with the
+//! range of record numbers (frames from the start of the capture) that
+//! demonstrates them. Triggers still waiting for a response when the capture
+//! ends are listed as unresolved, not as violations.
+
+use std::fmt;
+use std::io::{self, Read};
+
+use crate::{FrameInterpreter, HdlcDecoder};
+
+/// A property as written in the spec file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Property {
+    /// `after <trigger> expect <response> within <ticks>`
+    Response { trigger: Pattern, response: Pattern, within: u64 },
+    /// `never <event> twice without <reset>`
+    Exclusive { event: Pattern, reset: Pattern },
+}
+
+/// One parsed spec line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    /// 1-based line in the spec file.
+    pub line:     usize,
+    /// The line as written, for reports.
+    pub text:     String,
+    pub property: Property,
+}
+
+/// A parsed spec file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Spec {
+    pub rules: Vec<Rule>,
+}
+
+impl Spec {
+    /// Parse a spec; `#` starts a comment, blank lines are ignored.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut rules = Vec::new();
+        for (i, raw) in text.lines().enumerate() {
+            let line = raw.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let property = parse_property(line).map_err(|e| format!("line {}: {e}", i + 1))?;
+            rules.push(Rule { line: i + 1, text: line.to_string(), property });
+        }
+        Ok(Self { rules })
+    }
+}
+
+fn parse_property(line: &str) -> Result<Property, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let split = |from: usize, kw: &str| -> Result<usize, String> {
+        words[from..].iter().position(|w| *w == kw).map(|p| from + p)
+            .ok_or_else(|| format!("expected '{kw}'"))
+    };
+    match words[0] {
+        "after" => {
+            let expect = split(1, "expect")?;
+            let within = split(expect + 1, "within")?;
+            let ticks = match &words[within + 1..] {
+                [t] => t.parse().map_err(|_| format!("bad tick count '{t}'"))?,
+                _ => return Err("expected one tick count after 'within'".to_string()),
+            };
+            Ok(Property::Response {
+                trigger:  Pattern::parse(&words[1..expect])?,
+                response: Pattern::parse(&words[expect + 1..within])?,
+                within:   ticks,
+            })
+        }
+        "never" => {
+            let twice = split(1, "twice")?;
+            if words.get(twice + 1) != Some(&"without") {
+                return Err("expected 'without' after 'twice'".to_string());
+            }
+            Ok(Property::Exclusive {
+                event: Pattern::parse(&words[1..twice])?,
+                reset: Pattern::parse(&words[twice + 2..])?,
+            })
+        }
+        other => Err(format!("unknown property '{other}' (expected 'after' or 'never')")),
+    }
+}
+
+// ── Patterns ──────────────────────────────────────────────────────────────────
+
+/// Conjunction of a record label and `Key=Value` terms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    label:  Option<String>,
+    fields: Vec<(String, String)>,
+}
+
+impl Pattern {
+    fn parse(terms: &[&str]) -> Result<Self, String> {
+        if terms.is_empty() {
+            return Err("empty pattern".to_string());
+        }
+        let mut pattern = Self { label: None, fields: Vec::new() };
+        for term in terms {
+            // Labels such as `Disp===>` and `=>Intern` contain '=' too.
+            let field = term.split_once('=')
+                .filter(|(k, v)| !k.is_empty() && !v.starts_with('='));
+            match field {
+                Some((key, value)) => {
+                    if value.matches('*').count() > 1 {
+                        return Err(format!("'{term}': at most one '*' per value"));
+                    }
+                    pattern.fields.push((key.to_string(), value.to_string()));
+                }
+                None if pattern.label.is_none() => pattern.label = Some(term.to_string()),
+                None => return Err(format!("'{term}': only one record label per pattern")),
+            }
+        }
+        Ok(pattern)
+    }
+
+    /// `Some(binding)` if `rec` matches; the binding is the text matched by
+    /// the first `*`.
+    fn matches(&self, rec: &Line<'_>) -> Option<Option<String>> {
+        if self.label.as_deref().is_some_and(|l| l != rec.label) {
+            return None;
+        }
+        let mut binding = None;
+        for (key, value) in &self.fields {
+            let bound = rec.fields.iter()
+                .filter(|(k, _)| k == key)
+                .find_map(|(_, v)| glob(value, v))?;
+            binding = binding.or(bound);
+        }
+        Some(binding)
+    }
+}
+
+/// Matches `text` against a value with at most one `*`.
+fn glob(pattern: &str, text: &str) -> Option<Option<String>> {
+    match pattern.split_once('*') {
+        None => (pattern == text).then_some(None),
+        Some((pre, post)) => {
+            let rest = text.strip_prefix(pre)?.strip_suffix(post)?;
+            Some(Some(rest.to_string()))
+        }
+    }
+}
+
+/// A decoded console line split into label and fields.
+struct Line<'a> {
+    ts:     Option<u64>,
+    label:  &'a str,
+    fields: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Line<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        let head = words.next()?;
+        let ts = head.parse().ok();
+        let label = words.next()?;
+        // `Evt<Sig=X,Pool=0,Ref=1>`: keys lose their group prefix, values
+        // the closing brackets.
+        let fields = words
+            .flat_map(|w| w.split(','))
+            .filter_map(|f| f.trim_end_matches('>').split_once('='))
+            .map(|(k, v)| (k.rsplit('<').next().unwrap_or(k), v))
+            .collect();
+        Some(Self { ts, label, fields })
+    }
+}
+
+/// Bindings are compatible unless both sides bound different text.
+fn compatible(a: &Option<String>, b: &Option<String>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a == b,
+        _ => true,
+    }
+}
+
+// ── Checking ──────────────────────────────────────────────────────────────────
+
+/// A property that failed (or could not be decided) on a record range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Index of the rule in [`Spec::rules`].
+    pub rule:   usize,
+    /// First record of the counterexample.
+    pub first:  u64,
+    /// Last record of the counterexample.
+    pub last:   u64,
+    /// What went wrong, naming the binding if any.
+    pub detail: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "records #{}..=#{}: {}", self.first, self.last, self.detail)
+    }
+}
+
+struct Pending {
+    binding: Option<String>,
+    record:  u64,
+    ts:      u64,
+}
+
+enum RuleState {
+    Response(Vec<Pending>),
+    Exclusive(Vec<(Option<String>, u64)>),
+}
+
+/// Evaluates a [`Spec`] over decoded lines, record by record.
+pub struct SpecChecker {
+    spec:       Spec,
+    states:     Vec<RuleState>,
+    now:        u64,
+    records:    u64,
+    violations: Vec<Violation>,
+}
+
+impl SpecChecker {
+    pub fn new(spec: Spec) -> Self {
+        let states = spec.rules.iter().map(|r| match r.property {
+            Property::Response { .. } => RuleState::Response(Vec::new()),
+            Property::Exclusive { .. } => RuleState::Exclusive(Vec::new()),
+        }).collect();
+        Self { spec, states, now: 0, records: 0, violations: Vec::new() }
+    }
+
+    /// Feed one decoded line belonging to record number `record`.
+    pub fn feed(&mut self, record: u64, line: &str) {
+        self.records = self.records.max(record + 1);
+        let Some(rec) = Line::parse(line) else { return };
+        if let Some(ts) = rec.ts {
+            self.now = ts;
+        }
+        let now = self.now;
+        for (i, (rule, state)) in self.spec.rules.iter().zip(&mut self.states).enumerate() {
+            match (&rule.property, state) {
+                (Property::Response { trigger, response, within }, RuleState::Response(pending)) => {
+                    pending.retain(|p| {
+                        if now.saturating_sub(p.ts) <= *within {
+                            return true;
+                        }
+                        self.violations.push(Violation {
+                            rule:   i,
+                            first:  p.record,
+                            last:   record,
+                            detail: format!("no response within {within}{}", bound(&p.binding)),
+                        });
+                        false
+                    });
+                    if let Some(b) = response.matches(&rec) {
+                        pending.retain(|p| !compatible(&p.binding, &b));
+                    }
+                    if let Some(b) = trigger.matches(&rec) {
+                        if !pending.iter().any(|p| p.binding == b) {
+                            pending.push(Pending { binding: b, record, ts: now });
+                        }
+                    }
+                }
+                (Property::Exclusive { event, reset }, RuleState::Exclusive(armed)) => {
+                    if let Some(b) = reset.matches(&rec) {
+                        armed.retain(|(a, _)| !compatible(a, &b));
+                    }
+                    if let Some(b) = event.matches(&rec) {
+                        if let Some(slot) = armed.iter_mut().find(|(a, _)| *a == b) {
+                            self.violations.push(Violation {
+                                rule:   i,
+                                first:  slot.1,
+                                last:   record,
+                                detail: format!("repeated without reset{}", bound(&b)),
+                            });
+                            slot.1 = record;
+                        } else {
+                            armed.push((b, record));
+                        }
+                    }
+                }
+                _ => unreachable!("rule state built from its property"),
+            }
+        }
+    }
+
+    /// End of capture: the verdict.
+    pub fn finish(self) -> Report {
+        let last = self.records.saturating_sub(1);
+        let mut unresolved = Vec::new();
+        for (i, state) in self.states.iter().enumerate() {
+            if let RuleState::Response(pending) = state {
+                unresolved.extend(pending.iter().map(|p| Violation {
+                    rule:   i,
+                    first:  p.record,
+                    last,
+                    detail: format!("capture ended while waiting{}", bound(&p.binding)),
+                }));
+            }
+        }
+        Report { spec: self.spec, records: self.records, violations: self.violations, unresolved }
+    }
+}
+
+fn bound(binding: &Option<String>) -> String {
+    binding.as_ref().map(|b| format!(" (*={b})")).unwrap_or_default()
+}
+
+/// Outcome of checking one capture.
+#[derive(Debug, Clone)]
+pub struct Report {
+    spec:           Spec,
+    /// Records (frames) examined.
+    pub records:    u64,
+    pub violations: Vec<Violation>,
+    /// Response triggers the capture ended too early to decide.
+    pub unresolved: Vec<Violation>,
+}
+
+impl Report {
+    /// `true` when no property was violated.
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Human-readable verdict, one line per finding.
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "spec check: {} rule(s), {} record(s), {} violation(s), {} unresolved",
+            self.spec.rules.len(), self.records, self.violations.len(), self.unresolved.len()
+        )];
+        for (kind, list) in [("FAIL", &self.violations), ("OPEN", &self.unresolved)] {
+            for v in list {
+                let rule = &self.spec.rules[v.rule];
+                lines.push(format!("  {kind} line {}: {}", rule.line, rule.text));
+                lines.push(format!("       {v}"));
+            }
+        }
+        lines
+    }
+}
+
+/// Decode a capture and check `spec` against it.
+pub fn check_capture<R: Read>(
+    mut source:  R,
+    interpreter: &mut FrameInterpreter,
+    spec:        Spec,
+) -> io::Result<Report> {
+    let mut decoder = HdlcDecoder::new();
+    let mut checker = SpecChecker::new(spec);
+    let mut record = 0u64;
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = match source.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for frame in decoder.push_bytes(&buf[..n]).into_iter().flatten() {
+            for line in interpreter.interpret(&frame) {
+                checker.feed(record, &line);
+            }
+            record += 1;
+        }
+    }
+    checker.records = record;
+    Ok(checker.finish())
+}
@@ -0,0 +1,164 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use qs::rx::{RxCmd, RxParser};
+
+use crate::runtime::{dispatch_cmd, parse_keyboard_cmd, push_offer, TargetFilter, UserCmd};
+use crate::{CommandSender, FrameInterpreter, GroupFilter, OutputSinks, SharedSender};
+
+/// The bytes written to the command channel.
+#[derive(Clone, Default)]
+struct Wire(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Wire {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn parse(line: &str) -> Option<UserCmd> {
+    parse_keyboard_cmd(line, &[], &None)
+}
+
+/// What the target receives for each console line.
+fn sent(lines: &[&str]) -> Vec<RxCmd> {
+    sent_with(&mut FrameInterpreter::new(), lines)
+}
+
+/// [`sent`], resolving names through the dictionaries of `interp`.
+fn sent_with(interp: &mut FrameInterpreter, lines: &[&str]) -> Vec<RxCmd> {
+    let wire = Wire::default();
+    let sender: SharedSender =
+        Arc::new(Mutex::new(Some(CommandSender::new(Box::new(wire.clone())))));
+    let mut sinks = OutputSinks::new(true, false);
+    for line in lines {
+        let cmd = parse(line).unwrap_or_else(|| panic!("`{line}` did not parse"));
+        assert!(!dispatch_cmd(cmd, &sender, interp, &mut sinks, &None));
+    }
+    let bytes = wire.0.lock().unwrap().clone();
+    RxParser::new().push_slice(&bytes)
+}
+
+#[test]
+fn console_lines_become_qs_rx_frames() {
+    assert_eq!(
+        sent(&["reset", "info", "tick 1", "cmd 7 1 2 0x30"]),
+        [
+            RxCmd::Reset,
+            RxCmd::Info,
+            RxCmd::Tick { rate: 1 },
+            RxCmd::Command { id: 7, p1: 1, p2: 2, p3: 0x30 },
+        ]
+    );
+}
+
+#[test]
+fn peek_and_poke_address_bytes() {
+    assert_eq!(
+        sent(&["peek 0x20001000 16", "poke 0x20001000 0xAA 1 0x7e"]),
+        [
+            RxCmd::Peek { addr: 0x2000_1000, offset: 0, size: 1, num: 16 },
+            RxCmd::Poke { addr: 0x2000_1000, offset: 0, size: 1, num: 3, data: vec![0xAA, 1, 0x7E] },
+        ]
+    );
+}
+
+#[test]
+fn filter_sets_the_records_of_the_chosen_groups() {
+    let cmds = sent(&["filter all", "filter none", "filter SM"]);
+    let masks: Vec<u128> = cmds
+        .iter()
+        .map(|cmd| match cmd {
+            RxCmd::GlbFilter { bits } => u128::from_le_bytes(*bits),
+            other => panic!("expected a global filter, got {other:?}"),
+        })
+        .collect();
+    let bit = |record: u8| 1u128 << record;
+
+    assert_eq!(masks[0], u128::MAX);
+    let info = masks[1];
+    assert_ne!(info & bit(qs::predefined::SIG_DICT), 0);
+    assert_eq!(info & bit(qs::records::qep::DISPATCH), 0);
+    let sm = (bit(10) - bit(1))
+        | bit(qs::records::qep::TRAN_HIST)
+        | bit(qs::records::qep::CONTRACT_VIOLATION)
+        | bit(qs::records::qep::GUARD);
+    assert_eq!(masks[2], info | sm);
+}
+
+#[test]
+fn push_filters_the_target_as_the_console_shows() {
+    let cmds = sent(&["push", "show SC", "push", "push", "show all", "push"]);
+    let masks: Vec<u128> = cmds
+        .iter()
+        .map(|cmd| match cmd {
+            RxCmd::GlbFilter { bits } => u128::from_le_bytes(*bits),
+            other => panic!("expected a global filter, got {other:?}"),
+        })
+        .collect();
+    let filtered = sent(&["filter SC"]);
+    assert_eq!(cmds[0], filtered[0]);
+    assert_eq!(masks[1], u128::MAX);
+    assert_eq!(masks.len(), 2);
+}
+
+#[test]
+fn push_offer_counts_both_directions() {
+    let all = [0xFF; 16];
+    let mut sm = [0u8; 16];
+    sm[0] = 0x0F;
+    assert_eq!(push_offer(&all, &all), None);
+    let narrow = push_offer(&all, &sm).unwrap();
+    assert!(narrow.contains("124 hidden record type(s)"), "{narrow}");
+    let wide = push_offer(&sm, &all).unwrap();
+    assert!(wide.contains("124 shown record type(s) are blocked"), "{wide}");
+}
+
+#[test]
+fn a_target_reset_forgets_the_pushed_filter() {
+    let mut sender = CommandSender::new(Box::new(Wire::default()));
+    assert!(!sender.sync_resets(1));
+    sender.send_glb_filter(&[0x0F; 16]).unwrap();
+    assert_eq!(sender.glb_filter(), &[0x0F; 16]);
+    assert!(!sender.sync_resets(1));
+    assert!(sender.sync_resets(2));
+    assert_eq!(sender.glb_filter(), &[0xFF; 16]);
+}
+
+#[test]
+fn curr_and_query_select_and_query_objects() {
+    let mut interp = FrameInterpreter::new();
+    let mut dict = 0x2000_0040u32.to_le_bytes().to_vec();
+    dict.extend_from_slice(b"l_table\0");
+    interp.interpret(&crate::QsFrame { seq: 0, record_type: qs::predefined::OBJ_DICT, payload: dict });
+
+    assert_eq!(
+        sent_with(&mut interp, &["curr mp 0x20001000", "curr AO l_table", "query te"]),
+        [
+            RxCmd::CurrObj { kind: 2, obj_ptr: 0x2000_1000 },
+            RxCmd::CurrObj { kind: 1, obj_ptr: 0x2000_0040 },
+            RxCmd::QueryCurr { kind: 4 },
+        ]
+    );
+}
+
+#[test]
+fn malformed_lines_are_rejected() {
+    assert_eq!(parse("peek 0x100"), None);
+    assert_eq!(parse("peek 0x100 256"), None);
+    assert_eq!(parse("poke 0x100"), None);
+    assert_eq!(parse("poke 0x100 0x1FF"), None);
+    assert_eq!(parse("filter XYZ"), None);
+    assert_eq!(parse("show"), None);
+    assert_eq!(parse("show XYZ"), None);
+    assert_eq!(parse("curr xx 0x100"), None);
+    assert_eq!(parse("curr sm"), None);
+    assert_eq!(parse("query"), None);
+    let groups = GroupFilter::parse("AO,SM").unwrap();
+    assert_eq!(parse("filter AO,SM"), Some(UserCmd::Filter(TargetFilter::Groups(groups))));
+}
@@ -1,8 +1,8 @@
 use std::sync::{Arc, Mutex};
 
-use qs::{QsConfig, TraceBackend, TraceError, Tracer};
+use qs::{encode_frame, QsConfig, QsRecord, TimestampSize, TraceBackend, TraceError, Tracer};
 
-use crate::{DecodeError, HdlcDecoder, QsFrame};
+use crate::{DecodeError, FrameChunk, HdlcDecoder, QsFrame};
 
 #[derive(Clone, Default)]
 struct CaptureBackend {
@@ -89,3 +89,69 @@ fn bad_frame_does_not_desync_following_frames() {
     let good = results[1].as_ref().expect("second frame must still decode");
     assert_eq!(good.record_type, 0x43);
 }
+
+/// An untimed frame of `payload`, HDLC-encoded.
+fn wire(seq: u8, record_type: u8, payload: Vec<u8>) -> Vec<u8> {
+    encode_frame(&QsRecord { seq, record_type, timestamp: None, payload }, TimestampSize::Four)
+}
+
+/// A memory dump that needs stuffing here and there.
+fn dump(len: usize) -> Vec<u8> {
+    (0..len).map(|i| [0x7E, 0x7D, i as u8][i % 3]).collect()
+}
+
+#[test]
+fn an_oversize_frame_fails_with_its_length_and_the_next_one_decodes() {
+    let mut input = wire(1, 0x70, dump(100));
+    input.extend(wire(2, 0x71, vec![9]));
+
+    let mut decoder = HdlcDecoder::with_max_frame_len(32);
+    let results = decoder.push_bytes(&input);
+    assert_eq!(
+        results,
+        [
+            Err(DecodeError::FrameTooLarge { len: 103, max: 32 }),
+            Ok(QsFrame { seq: 2, record_type: 0x71, payload: vec![9] }),
+        ]
+    );
+    assert_eq!(HdlcDecoder::with_max_frame_len(0).max_frame_len(), 3);
+}
+
+#[test]
+fn streaming_delivers_a_large_frame_in_chunks_within_the_limit() {
+    let payload = dump(300);
+    let mut input = wire(7, 0x70, payload.clone());
+    input.extend(wire(8, 0x71, vec![9]));
+
+    let mut decoder = HdlcDecoder::with_max_frame_len(64);
+    let chunks: Vec<FrameChunk> =
+        input.chunks(5).flat_map(|c| decoder.push_chunks(c)).map(Result::unwrap).collect();
+    let (dump_chunks, rest) = chunks.split_at(chunks.len() - 1);
+
+    assert!(dump_chunks.len() > 1);
+    assert!(dump_chunks.iter().all(|c| (c.seq, c.record_type) == (7, 0x70) && c.payload.len() <= 64));
+    assert!(dump_chunks.iter().rev().skip(1).all(|c| c.more));
+    assert!(!dump_chunks.last().unwrap().more);
+    let mut joined = Vec::new();
+    for chunk in dump_chunks {
+        assert_eq!(chunk.offset, joined.len());
+        joined.extend_from_slice(&chunk.payload);
+    }
+    assert_eq!(joined, payload);
+
+    // Frames within the limit still come whole.
+    assert!(rest[0].is_whole());
+    assert_eq!(rest[0].payload, [9]);
+}
+
+#[test]
+fn a_bad_checksum_voids_a_streamed_frame_at_its_end() {
+    let mut input = wire(7, 0x70, dump(100));
+    let checksum = input.len() - 2;
+    input[checksum] ^= 0x01;
+
+    let results = HdlcDecoder::with_max_frame_len(32).push_chunks(&input);
+    let (last, chunks) = results.split_last().unwrap();
+    assert!(chunks.iter().all(|c| c.as_ref().is_ok_and(|c| c.more)));
+    assert!(matches!(last, Err(DecodeError::InvalidChecksum { .. })));
+}
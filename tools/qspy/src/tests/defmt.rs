@@ -0,0 +1,77 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use qs::TraceHook;
+use qs_defmt::DefmtBridge;
+
+use crate::{FrameInterpreter, QsFrame};
+
+/// A bridge whose records become frames stamped with `ts` 42, as a tracer
+/// with a 4-byte timestamp would send them.
+fn bridge(record: u8) -> (DefmtBridge, Arc<Mutex<Vec<QsFrame>>>) {
+    let frames = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&frames);
+    let hook: TraceHook = Arc::new(move |record_type, payload: &[u8], with_ts| {
+        let mut bytes = if with_ts { 42u32.to_le_bytes().to_vec() } else { Vec::new() };
+        bytes.extend_from_slice(payload);
+        sink.lock().unwrap().push(QsFrame { seq: 0, record_type, payload: bytes });
+        Ok(())
+    });
+    (DefmtBridge::new(record, hook).with_max_record_len(8), frames)
+}
+
+fn interpret_all(interp: &mut FrameInterpreter, frames: &[QsFrame]) -> Vec<String> {
+    frames.iter().flat_map(|f| interp.interpret(f)).collect()
+}
+
+#[test]
+fn wrapped_frame_prints_once_in_the_timeline() {
+    let (bridge, frames) = bridge(110);
+    bridge.announce().unwrap();
+    bridge.forward(&[1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+
+    let lines = interpret_all(&mut FrameInterpreter::new(), &frames.lock().unwrap());
+    assert_eq!(lines, ["           Usr-Dict 110->QS_DEFMT", "0000000042 DEFMT frame=010203040506070809"]);
+}
+
+#[test]
+fn installed_decoder_renders_frames() {
+    let (bridge, frames) = bridge(121);
+    bridge.announce().unwrap();
+    bridge.forward(b"\x05hello").unwrap();
+
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let log = Rc::clone(&seen);
+    let mut interp = FrameInterpreter::new();
+    interp.set_defmt_decoder(Box::new(move |frame| {
+        log.borrow_mut().push(frame.to_vec());
+        Some(format!("INFO {}", std::str::from_utf8(&frame[1..]).ok()?))
+    }));
+
+    let lines = interpret_all(&mut interp, &frames.lock().unwrap());
+    assert_eq!(lines.last().unwrap(), "0000000042 DEFMT INFO hello");
+    assert_eq!(*seen.borrow(), [b"\x05hello".to_vec()]);
+}
+
+#[test]
+fn dropped_chunk_reports_a_lost_frame() {
+    let (bridge, frames) = bridge(110);
+    bridge.announce().unwrap();
+    bridge.forward(&[1; 10]).unwrap();
+    bridge.forward(&[2; 3]).unwrap();
+
+    let mut frames = frames.lock().unwrap().clone();
+    frames.remove(2);
+    let lines = interpret_all(&mut FrameInterpreter::new(), &frames);
+    assert_eq!(lines[1..], ["0000000042 DEFMT frame lost", "0000000042 DEFMT frame=020202"]);
+}
+
+#[test]
+fn record_without_the_dictionary_name_stays_a_user_record() {
+    let (bridge, frames) = bridge(110);
+    bridge.forward(&[7]).unwrap();
+
+    let lines = interpret_all(&mut FrameInterpreter::new(), &frames.lock().unwrap());
+    assert_eq!(lines, ["0000000042 USR(110) 3 mem:07"]);
+}
@@ -0,0 +1,94 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use qs::records::{qf, sched};
+
+use crate::export::{decode_row, ExportFormat, ExportGroup, Exporter};
+use crate::{QsFrame, TargetSizes};
+
+#[derive(Clone, Default)]
+struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+/// `ACTIVE_POST` with default sizes: ts(4) sig(2) sdr(4) ao(4) pool ref free(1) min(1).
+fn ao_post(ts: u32, free: u8, min: u8) -> QsFrame {
+    let mut payload = ts.to_le_bytes().to_vec();
+    payload.extend_from_slice(&7u16.to_le_bytes());
+    payload.extend_from_slice(&0x1000u32.to_le_bytes());
+    payload.extend_from_slice(&0x2000u32.to_le_bytes());
+    payload.extend_from_slice(&[0, 0, free, min]);
+    QsFrame { seq: 0, record_type: qf::ACTIVE_POST, payload }
+}
+
+#[test]
+fn ao_post_maps_to_queue_depth_row() {
+    let row = decode_row(&ao_post(100, 5, 3), &TargetSizes::default()).unwrap();
+    assert_eq!(row.group, ExportGroup::Active);
+    assert_eq!(row.values.len(), ExportGroup::Active.columns().len());
+    assert_eq!(
+        row.values,
+        vec![Some(100), Some(qf::ACTIVE_POST as u64), Some(7), Some(0x2000), Some(5), Some(3)]
+    );
+}
+
+#[test]
+fn truncated_and_unknown_records_are_skipped() {
+    let sizes = TargetSizes::default();
+    let mut short = ao_post(1, 1, 1);
+    short.payload.truncate(10);
+    assert!(decode_row(&short, &sizes).is_none());
+    let user = QsFrame { seq: 0, record_type: 100, payload: vec![0; 8] };
+    assert!(decode_row(&user, &sizes).is_none());
+}
+
+#[test]
+fn exporter_writes_header_once_per_group() {
+    let ao  = SharedBuf::default();
+    let sch = SharedBuf::default();
+    let (ao_w, sch_w) = (ao.clone(), sch.clone());
+    let mut ex = Exporter::with_opener(ExportFormat::Csv, Box::new(move |group| {
+        Ok(match group {
+            ExportGroup::Active => Box::new(ao_w.clone()) as Box<dyn Write + Send>,
+            _                   => Box::new(sch_w.clone()),
+        })
+    }));
+    let sizes = TargetSizes::default();
+    ex.write_frame(&ao_post(10, 4, 2), &sizes).unwrap();
+    ex.write_frame(&ao_post(20, 3, 2), &sizes).unwrap();
+    let idle = QsFrame {
+        seq: 0,
+        record_type: sched::IDLE,
+        payload: vec![30, 0, 0, 0, 2],
+    };
+    ex.write_frame(&idle, &sizes).unwrap();
+
+    let ao = String::from_utf8(ao.0.lock().unwrap().clone()).unwrap();
+    assert_eq!(ao, "time,rec,sig,obj,free,min\n10,14,7,8192,4,2\n20,14,7,8192,3,2\n");
+    let sch = String::from_utf8(sch.0.lock().unwrap().clone()).unwrap();
+    assert_eq!(sch, "time,rec,prio,prev\n30,53,0,2\n");
+}
+
+#[test]
+fn matlab_rows_use_nan_for_missing_columns() {
+    let buf = SharedBuf::default();
+    let w = buf.clone();
+    let mut ex = Exporter::with_opener(ExportFormat::Matlab, Box::new(move |_| {
+        Ok(Box::new(w.clone()) as Box<dyn Write + Send>)
+    }));
+    // MPOOL_PUT: ts(4) mp(4) free(2) — no min column.
+    let mut payload = 5u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&0x30u32.to_le_bytes());
+    payload.extend_from_slice(&9u16.to_le_bytes());
+    let put = QsFrame { seq: 0, record_type: qf::MPOOL_PUT, payload };
+    ex.write_frame(&put, &TargetSizes::default()).unwrap();
+
+    let out = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+    assert_eq!(out, format!("% mp: time rec obj free min\n5 {} 48 9 NaN\n", qf::MPOOL_PUT));
+}
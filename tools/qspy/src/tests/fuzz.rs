@@ -0,0 +1,116 @@
+use proptest::prelude::*;
+use qs::hdlc::{ESC, FLAG};
+use qs::records::{qep, RecordSizes};
+use qs::TimestampSize;
+
+use crate::fuzz::{self, RoundTrip, Sample};
+use crate::{DecodeError, HdlcDecoder, QsFrame, TargetSizes};
+
+/// Stream bytes, with flags and escapes far more common than on the wire.
+fn stream() -> impl Strategy<Value = Vec<u8>> {
+    let byte = prop_oneof![2 => Just(FLAG), 2 => Just(ESC), 1 => Just(ESC ^ FLAG), 8 => any::<u8>()];
+    prop::collection::vec(byte, 0..512)
+}
+
+fn record_sizes() -> impl Strategy<Value = RecordSizes> {
+    let ctr = || prop::sample::select(vec![1u8, 2, 4]);
+    let ptr = || prop::sample::select(vec![1u8, 2, 4, 8]);
+    (ctr(), ptr(), ptr(), ctr(), ctr(), ctr()).prop_map(|(signal, obj_ptr, fun_ptr, equeue_ctr, time_evt_ctr, mpool_ctr)| {
+        RecordSizes { signal, obj_ptr, fun_ptr, equeue_ctr, time_evt_ctr, mpool_ctr }
+    })
+}
+
+fn round_trip() -> impl Strategy<Value = RoundTrip> {
+    let time_size = prop::sample::select(vec![TimestampSize::One, TimestampSize::Two, TimestampSize::Four]);
+    (record_sizes(), time_size, any::<u8>(), 1..80usize)
+        .prop_map(|(sizes, time_size, first_seq, chunk)| RoundTrip { sizes, time_size, first_seq, chunk })
+}
+
+fn samples(sizes: RecordSizes) -> impl Strategy<Value = Vec<Sample>> {
+    let sample = (any::<u8>(), any::<u32>(), any::<[u64; 7]>());
+    prop::collection::vec(sample, 1..40).prop_map(move |samples| {
+        samples.into_iter().map(|(kind, ts, args)| fuzz::sample(kind, ts, args, &sizes)).collect()
+    })
+}
+
+proptest! {
+    #[test]
+    fn any_stream_decodes_the_same_however_it_is_split(input in stream(), chunk in 1..64usize) {
+        fuzz::check_stream(&input, chunk);
+    }
+
+    #[test]
+    fn encoded_records_decode_to_what_they_were_built_from(
+        (trip, samples) in round_trip().prop_flat_map(|trip| (Just(trip), samples(trip.sizes))),
+    ) {
+        trip.check(&samples);
+    }
+
+    #[test]
+    fn a_damaged_frame_leaves_its_neighbours_intact(
+        (trip, samples) in round_trip().prop_flat_map(|trip| (Just(trip), samples(trip.sizes))),
+        frame in any::<usize>(),
+        offset in any::<usize>(),
+        xor in 1..=255u8,
+    ) {
+        trip.check_damaged(&samples, frame, offset, xor);
+    }
+
+    #[test]
+    fn fuzzer_entry_points_accept_any_input(data in prop::collection::vec(any::<u8>(), 0..400)) {
+        fuzz::check_stream_bytes(&data);
+        fuzz::check_round_trip_bytes(&data);
+    }
+}
+
+#[test]
+fn every_sample_kind_round_trips_at_the_extreme_widths() {
+    for sizes in [
+        RecordSizes { signal: 1, obj_ptr: 1, fun_ptr: 1, equeue_ctr: 1, time_evt_ctr: 1, mpool_ctr: 1 },
+        RecordSizes { signal: 4, obj_ptr: 8, fun_ptr: 8, equeue_ctr: 4, time_evt_ctr: 4, mpool_ctr: 4 },
+    ] {
+        let samples: Vec<_> = (0..fuzz::SAMPLE_KINDS).map(|kind| fuzz::sample(kind, u32::MAX, [u64::MAX; 7], &sizes)).collect();
+        RoundTrip { sizes, first_seq: 255, chunk: 1, ..RoundTrip::default() }.check(&samples);
+    }
+}
+
+#[test]
+fn an_escape_before_the_flag_fails_the_frame_even_with_a_good_checksum() {
+    // seq 1, record 0x10, checksum 0xEE: valid but for the dangling escape.
+    let results = HdlcDecoder::new().push_bytes(&[0x01, 0x10, 0xEE, ESC, FLAG]);
+    assert_eq!(results, [Err(DecodeError::InvalidEscape(FLAG))]);
+
+    // Alone between flags, too.
+    let results = HdlcDecoder::new().push_bytes(&[FLAG, ESC, FLAG]);
+    assert_eq!(results, [Err(DecodeError::InvalidEscape(FLAG))]);
+}
+
+#[test]
+fn only_stuffed_flags_and_escapes_may_follow_an_escape() {
+    // `ESC 0x30` unstuffs to the record type 0x10, but no encoder sends it.
+    let mut decoder = HdlcDecoder::new();
+    let results = decoder.push_bytes(&[0x01, ESC, 0x30, 0xEE, FLAG, 0x01, 0x10, 0xEE, FLAG]);
+    assert_eq!(
+        results,
+        [
+            Err(DecodeError::InvalidEscape(0x30)),
+            Ok(QsFrame { seq: 1, record_type: 0x10, payload: Vec::new() }),
+        ]
+    );
+}
+
+#[test]
+fn bytes_past_the_layout_make_a_record_incomplete() {
+    // A dispatch sent with 8-byte pointers, read as 4-byte ones: every field
+    // is there, but the reads stop halfway through the payload.
+    let wide = RecordSizes { signal: 2, obj_ptr: 8, fun_ptr: 8, equeue_ctr: 1, time_evt_ctr: 2, mpool_ctr: 2 };
+    let mut payload = 5u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&qep::dispatch(0x2000, 4, 0x100).encode(&wide));
+    let frame = QsFrame { seq: 0, record_type: qep::DISPATCH, payload };
+
+    let record = frame.decode(&TargetSizes::default());
+    assert_eq!(record.fields().len(), 3);
+    assert!(!record.is_complete());
+    let record = frame.decode(&TargetSizes { obj_ptr_size: 8, fun_ptr_size: 8, ..TargetSizes::default() });
+    assert!(record.is_complete());
+}
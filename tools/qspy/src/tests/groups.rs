@@ -0,0 +1,59 @@
+use qs::predefined;
+use qs::records::{infra, qep, qf, qxk, sched};
+
+use crate::output::colorize_record;
+use crate::{FrameInterpreter, GroupFilter, QsFrame, RecordGroup};
+
+#[test]
+fn records_map_to_their_groups() {
+    assert_eq!(RecordGroup::of(qep::DISPATCH), RecordGroup::Sm);
+    assert_eq!(RecordGroup::of(qep::TRAN_HIST), RecordGroup::Sm);
+    assert_eq!(RecordGroup::of(qf::ACTIVE_POST_ATTEMPT), RecordGroup::Ao);
+    assert_eq!(RecordGroup::of(qf::ACTIVE_DEFER_ATTEMPT), RecordGroup::Ao);
+    assert_eq!(RecordGroup::of(qf::EQUEUE_POST_ATTEMPT), RecordGroup::Eq);
+    assert_eq!(RecordGroup::of(qf::MPOOL_GET_ATTEMPT), RecordGroup::Mp);
+    assert_eq!(RecordGroup::of(qf::TICK), RecordGroup::Te);
+    assert_eq!(RecordGroup::of(qf::time_evt::POST), RecordGroup::Te);
+    assert_eq!(RecordGroup::of(qf::ISR_ENTRY), RecordGroup::Qf);
+    assert_eq!(RecordGroup::of(qf::NEW_ATTEMPT), RecordGroup::Qf);
+    assert_eq!(RecordGroup::of(sched::NEXT), RecordGroup::Sc);
+    assert_eq!(RecordGroup::of(qxk::SEM_SIGNAL), RecordGroup::Sem);
+    assert_eq!(RecordGroup::of(qxk::MTX_UNLOCK_ATTEMPT), RecordGroup::Mtx);
+    assert_eq!(RecordGroup::of(infra::ASSERT_FAIL), RecordGroup::Info);
+    assert_eq!(RecordGroup::of(predefined::SIG_DICT), RecordGroup::Info);
+    assert_eq!(RecordGroup::of(120), RecordGroup::Usr);
+}
+
+#[test]
+fn dictionary_named_records_are_user_records() {
+    let mut interp = FrameInterpreter::new();
+    assert_eq!(interp.group_of(99), RecordGroup::Info);
+
+    let mut payload = vec![99];
+    payload.extend_from_slice(b"MY_REC\0");
+    interp.interpret(&QsFrame { seq: 0, record_type: predefined::USR_DICT, payload });
+    assert_eq!(interp.group_of(99), RecordGroup::Usr);
+}
+
+#[test]
+fn filter_parses_group_lists() {
+    let f = GroupFilter::parse("sc, TE").unwrap();
+    assert!(f.allows(RecordGroup::Sc));
+    assert!(f.allows(RecordGroup::Te));
+    assert!(!f.allows(RecordGroup::Sm));
+    assert!(!f.allows(RecordGroup::Usr));
+    assert!(f.allows(RecordGroup::Info));
+
+    assert!(GroupFilter::parse("SC,XYZ").unwrap_err().contains("XYZ"));
+    assert!(GroupFilter::parse(" , ").is_err());
+    assert!(GroupFilter::parse("INFO").is_err());
+}
+
+#[test]
+fn record_color_follows_group_not_label() {
+    let line = "0000000042 AO-Post  Sdr=QS_RX,Obj=l_table";
+    assert_eq!(
+        colorize_record(RecordGroup::Sc, line),
+        "\x1b[36m0000000042\x1b[0m\x1b[34m AO-Post  Sdr=QS_RX,Obj=l_table\x1b[0m"
+    );
+}
@@ -0,0 +1,478 @@
+use qs::predefined;
+use qs::records::qf;
+
+use crate::{FrameInterpreter, QsFrame, TargetSizes, Value};
+
+fn frame(record_type: u8, payload: Vec<u8>) -> QsFrame {
+    QsFrame { seq: 0, record_type, payload }
+}
+
+fn obj_dict(addr: u32, name: &str) -> QsFrame {
+    let mut payload = addr.to_le_bytes().to_vec();
+    payload.extend_from_slice(name.as_bytes());
+    payload.push(0);
+    frame(predefined::OBJ_DICT, payload)
+}
+
+#[test]
+fn ao_post_resolves_names_and_sizes_counters() {
+    let mut interp = FrameInterpreter::with_sizes(TargetSizes {
+        equeue_ctr: 2,
+        ..TargetSizes::default()
+    });
+    interp.interpret(&obj_dict(0x2000, "l_table"));
+
+    // ts(4) sig(2) sdr(4) ao(4) pool ref free(2) min(2)
+    let mut payload = 7u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&5u16.to_le_bytes());
+    payload.extend_from_slice(&0u32.to_le_bytes());
+    payload.extend_from_slice(&0x2000u32.to_le_bytes());
+    payload.extend_from_slice(&[1, 2]);
+    payload.extend_from_slice(&300u16.to_le_bytes());
+    payload.extend_from_slice(&260u16.to_le_bytes());
+    let lines = interp.interpret(&frame(qf::ACTIVE_POST, payload));
+
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].contains("Obj=l_table"), "{}", lines[0]);
+    assert!(lines[0].contains("Que<Free=300,Min=260>"), "{}", lines[0]);
+}
+
+#[test]
+fn recall_attempt_and_int_lock_records_are_decoded() {
+    let mut interp = FrameInterpreter::new();
+    interp.interpret(&obj_dict(0x10, "ao"));
+
+    let mut payload = 1u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&0x10u32.to_le_bytes());
+    payload.extend_from_slice(&0x14u32.to_le_bytes());
+    let lines = interp.interpret(&frame(qf::ACTIVE_RECALL_ATTEMPT, payload));
+    assert_eq!(lines, vec!["0000000001 AO-RcllA Obj=ao,Que=0x00000014".to_string()]);
+
+    let lines = interp.interpret(&frame(qf::INT_DISABLE, vec![2, 0, 0, 0, 1, 3]));
+    assert_eq!(lines, vec!["0000000002 QF-IntD  Nesting=1,Pri=3".to_string()]);
+}
+
+#[test]
+fn target_info_resyncs_signal_and_pointer_sizes() {
+    let mut interp = FrameInterpreter::new();
+    let info = qs::TargetInfo {
+        is_reset: 0xFF,
+        version: 810,
+        signal_size: 4,
+        event_size: 2,
+        equeue_ctr_size: 1,
+        time_evt_ctr_size: 2,
+        mpool_size_size: 2,
+        mpool_ctr_size: 2,
+        obj_ptr_size: 2,
+        fun_ptr_size: 2,
+        time_size: 4,
+        max_active: 8,
+        max_event_pools: 3,
+        max_tick_rate: 1,
+        build_time: (0, 0, 0),
+        build_date: (1, 1, 26),
+    };
+    let lines = interp.interpret(&frame(
+        predefined::TARGET_INFO,
+        predefined::target_info_payload(&info),
+    ));
+    assert!(lines.iter().any(|l| l.contains("Cfg resync")), "{lines:?}");
+    assert_eq!(interp.sizes().signal_size, 4);
+    assert_eq!(interp.sizes().obj_ptr_size, 2);
+
+    // DISPATCH: ts(4) sig(4) obj(2) state(2)
+    let mut payload = 9u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&0x0001_0005u32.to_le_bytes());
+    payload.extend_from_slice(&0xBEEFu16.to_le_bytes());
+    payload.extend_from_slice(&0x1234u16.to_le_bytes());
+    let lines = interp.interpret(&frame(qs::records::qep::DISPATCH, payload));
+    assert_eq!(
+        lines,
+        vec!["0000000009 Disp===> Obj=0xBEEF,Sig=0x00010005,State=0x1234".to_string()]
+    );
+
+    // A repeated, identical TARGET_INFO leaves the sizes alone.
+    let lines = interp.interpret(&frame(
+        predefined::TARGET_INFO,
+        predefined::target_info_payload(&info),
+    ));
+    assert!(!lines.iter().any(|l| l.contains("Cfg resync")));
+}
+
+#[test]
+fn scheduler_preempt_restore_and_unlock_records_are_decoded() {
+    let mut interp = FrameInterpreter::new();
+    let with_ts = |bytes: &[u8]| {
+        let mut payload = 9u32.to_le_bytes().to_vec();
+        payload.extend_from_slice(bytes);
+        payload
+    };
+
+    let lines = interp.interpret(&frame(qs::records::sched::PREEMPT, with_ts(&[5, 2])));
+    assert_eq!(lines, vec!["0000000009 Sch-Pre  Pri=2->5".to_string()]);
+    let lines = interp.interpret(&frame(qs::records::sched::RESTORE, with_ts(&[2, 5])));
+    assert_eq!(lines, vec!["0000000009 Sch-Rest Pri=5->2".to_string()]);
+    // Payload is [old ceiling, new ceiling].
+    let lines = interp.interpret(&frame(qs::records::sched::UNLOCK, with_ts(&[5, 0])));
+    assert_eq!(lines, vec!["0000000009 Sch-Unlk Ceil=5->0".to_string()]);
+}
+
+#[test]
+fn contract_violation_names_the_late_state() {
+    let mut interp = FrameInterpreter::new();
+    let mut dict = 0x2000u32.to_le_bytes().to_vec();
+    dict.extend_from_slice(b"hungry\0");
+    interp.interpret(&frame(predefined::FUN_DICT, dict));
+
+    let mut payload = 12u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&0x2000u32.to_le_bytes());
+    payload.extend_from_slice(&50u32.to_le_bytes());
+    payload.extend_from_slice(&63u32.to_le_bytes());
+    let lines = interp.interpret(&frame(qs::records::qep::CONTRACT_VIOLATION, payload));
+    assert_eq!(lines, vec!["0000000012 St-Late  State=hungry,Limit=50,Elapsed=63".to_string()]);
+}
+
+#[test]
+fn guard_shows_the_transition_it_allowed_or_blocked() {
+    let mut interp = FrameInterpreter::new();
+    let mut dict = 0x2000u32.to_le_bytes().to_vec();
+    dict.extend_from_slice(b"idle\0");
+    interp.interpret(&frame(predefined::FUN_DICT, dict));
+
+    let guard = |passed: u8| {
+        let mut payload = 7u32.to_le_bytes().to_vec();
+        payload.extend_from_slice(&4u16.to_le_bytes());
+        payload.extend_from_slice(&0x1000u32.to_le_bytes());
+        payload.extend_from_slice(&0x2000u32.to_le_bytes());
+        payload.extend_from_slice(&0x2040u32.to_le_bytes());
+        payload.push(passed);
+        frame(qs::records::qep::GUARD, payload)
+    };
+    assert_eq!(
+        interp.interpret(&guard(0)),
+        ["0000000007 St-Guard Obj=0x00001000,Sig=0x0004,State=idle->0x00002040,Pass=F"]
+    );
+    assert_eq!(
+        interp.interpret(&guard(1)),
+        ["0000000007 St-Guard Obj=0x00001000,Sig=0x0004,State=idle->0x00002040,Pass=T"]
+    );
+}
+
+#[test]
+fn qf_tick_shows_rate_and_counter() {
+    let mut payload = 90u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&513u16.to_le_bytes());
+    payload.push(1);
+    let lines = FrameInterpreter::new().interpret(&frame(qf::TICK, payload));
+    assert_eq!(lines, ["0000000090 QF-Tick  Rate=1,Ctr=513"]);
+}
+
+#[test]
+fn query_data_shows_each_kind_of_snapshot() {
+    use qs::records::{infra, RecordSizes};
+
+    let mut interp = FrameInterpreter::new();
+    interp.interpret(&obj_dict(0x2000, "l_table"));
+    interp.interpret(&obj_dict(0x2010, "l_table.m_timeEvt"));
+    let sizes = RecordSizes { signal: 2, obj_ptr: 4, fun_ptr: 4, equeue_ctr: 1, time_evt_ctr: 2, mpool_ctr: 2 };
+    let query = |interp: &mut FrameInterpreter, record: qs::Predefined| {
+        let mut payload = 9u32.to_le_bytes().to_vec();
+        payload.extend_from_slice(&record.encode(&sizes));
+        interp.interpret(&frame(infra::QUERY_DATA, payload))
+    };
+
+    assert_eq!(
+        query(&mut interp, infra::query_ao(0x2000, 6, 2)),
+        ["0000000009 Query-AO Obj=l_table,Que<Free=6,Min=2>"]
+    );
+    assert_eq!(
+        query(&mut interp, infra::query_mp(0x3000, 300, 12)),
+        ["0000000009 Query-MP Obj=0x00003000,Free=300,Min=12"]
+    );
+    assert_eq!(
+        query(&mut interp, infra::query_te(0x2010, 0x2000, 5, 10, 7, 1)),
+        ["0000000009 Query-TE Obj=l_table.m_timeEvt,AO=l_table,Sig=0x0007,Tim=5,Int=10,Flags=0x01"]
+    );
+    assert_eq!(query(&mut interp, infra::query_ap(0x2000)), ["0000000009 Query-AP Obj=l_table"]);
+}
+
+#[test]
+fn ao_save_and_restore_show_size_and_status() {
+    let mut interp = FrameInterpreter::new();
+    interp.interpret(&obj_dict(0x2000, "doser"));
+
+    let record = |ts: u32, len: u16, status: u8| {
+        let mut payload = ts.to_le_bytes().to_vec();
+        payload.extend_from_slice(&0x2000u32.to_le_bytes());
+        payload.extend_from_slice(&len.to_le_bytes());
+        payload.push(status);
+        payload
+    };
+    let lines = interp.interpret(&frame(qf::AO_RESTORE, record(3, 12, 0)));
+    assert_eq!(lines, ["0000000003 AO-Rstr  Obj=doser,Len=12,Status=OK"]);
+    let lines = interp.interpret(&frame(qf::AO_SAVE, record(4, 0, 4)));
+    assert_eq!(lines, ["0000000004 AO-Save  Obj=doser,Len=0,Status=StoreFailed"]);
+}
+
+#[test]
+fn evt_log_shows_the_post_and_its_origin() {
+    let mut interp = FrameInterpreter::new();
+    let mut payload = 12u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&[3, 2, 5, 1]);
+    payload.extend_from_slice(&7u16.to_le_bytes());
+    payload.extend_from_slice(&2u16.to_le_bytes());
+    payload.extend_from_slice(&[0xAB, 0xCD]);
+    let lines = interp.interpret(&frame(qf::EVT_LOG, payload));
+    assert_eq!(lines, ["0000000012 Evt-Log  AO=3,Sig=0x0007,Src=AO5,Len=2,LIFO"]);
+}
+
+#[test]
+fn time_event_jitter_reports_lateness() {
+    let mut interp = FrameInterpreter::new();
+    interp.interpret(&obj_dict(0x2000, "blinky"));
+    interp.interpret(&obj_dict(0x2010, "blinky.timeEvt"));
+
+    let mut payload = 40u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&0x2010u32.to_le_bytes());
+    payload.extend_from_slice(&0x2000u32.to_le_bytes());
+    for value in [25u16, 1, 3, 0, 2] {
+        payload.extend_from_slice(&value.to_le_bytes());
+    }
+    let lines = interp.interpret(&frame(qf::TIMEEVT_JITTER, payload));
+    assert_eq!(lines, ["0000000040 TE-Jitr  Obj=blinky.timeEvt,AO=blinky,N=25,Last=1,Max=3,Mean=0,Ovl=2"]);
+}
+
+#[test]
+fn rtc_stats_show_the_longest_step() {
+    let mut interp = FrameInterpreter::new();
+    let mut payload = 9u32.to_le_bytes().to_vec();
+    payload.push(3);
+    for value in [120u32, 40, 900] {
+        payload.extend_from_slice(&value.to_le_bytes());
+    }
+    payload.extend_from_slice(&7u16.to_le_bytes());
+    payload.extend_from_slice(&2u32.to_le_bytes());
+    let lines = interp.interpret(&frame(qf::RTC_STATS, payload));
+    assert_eq!(lines, ["0000000009 RTC-Stat Pri=3,Steps=120,Mean=40,Max=900,MaxSig=0x0007,Over=2"]);
+}
+
+#[test]
+fn ao_stall_shows_the_waiting_events() {
+    let mut interp = FrameInterpreter::new();
+    let mut payload = 70u32.to_le_bytes().to_vec();
+    payload.push(4);
+    for value in [11u16, 3, 5] {
+        payload.extend_from_slice(&value.to_le_bytes());
+    }
+    payload.extend_from_slice(&25u32.to_le_bytes());
+    let lines = interp.interpret(&frame(qf::AO_STALL, payload));
+    assert_eq!(lines, ["0000000070 AO-Stall Pri=4,Sig=0x000B,Queued=3/5,Stale=25"]);
+}
+
+#[test]
+fn watchdog_resets_show_the_reset_reason() {
+    let mut interp = FrameInterpreter::new();
+    let mut payload = 3u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&7u32.to_le_bytes());
+    let lines = interp.interpret(&frame(qf::WDT_RESET, payload));
+    assert_eq!(lines, ["0000000003 WDT-Rst  Reason=0x07"]);
+}
+
+#[test]
+fn metrics_show_an_active_object_or_a_signal() {
+    let mut interp = FrameInterpreter::new();
+    let record = |kind: u8, key: u16, hwm: u16| {
+        let mut payload = 90u32.to_le_bytes().to_vec();
+        payload.push(kind);
+        payload.extend_from_slice(&key.to_le_bytes());
+        for value in [12u32, 40, 95] {
+            payload.extend_from_slice(&value.to_le_bytes());
+        }
+        payload.extend_from_slice(&hwm.to_le_bytes());
+        payload.extend_from_slice(&2u32.to_le_bytes());
+        payload
+    };
+    let lines = interp.interpret(&frame(qf::METRICS, record(0, 3, 4)));
+    assert_eq!(lines, ["0000000090 Metrics  Pri=3,Disp=12,Mean=40,Max=95,HWM=4,Drop=2"]);
+    let lines = interp.interpret(&frame(qf::METRICS, record(1, 11, 0)));
+    assert_eq!(lines, ["0000000090 Metrics  Sig=0x000B,Disp=12,Mean=40,Max=95,Drop=2"]);
+}
+
+#[test]
+fn thread_stack_shows_usage_and_overflow() {
+    let mut interp = FrameInterpreter::new();
+    let record = |ts: u32, used: u32, overflow: u8| {
+        let mut payload = ts.to_le_bytes().to_vec();
+        payload.push(2);
+        payload.extend_from_slice(&1024u32.to_le_bytes());
+        payload.extend_from_slice(&used.to_le_bytes());
+        payload.push(overflow);
+        payload
+    };
+    let lines = interp.interpret(&frame(qs::records::qxk::THREAD_STACK, record(5, 870, 0)));
+    assert_eq!(lines, ["0000000005 Thr-Stk  Thr=2,Used=870/1024 (84%)"]);
+    let lines = interp.interpret(&frame(qs::records::qxk::THREAD_STACK, record(6, 1024, 1)));
+    assert_eq!(lines, ["0000000006 Thr-Ovfl Thr=2,Used=1024/1024 (100%)"]);
+}
+
+/// The bytes a target's tracer sends.
+#[derive(Clone, Default)]
+struct Link(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for Link {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// What qspy prints for the dictionaries and hash a target with `objects`
+/// sends, the dictionaries sent twice as for a late viewer.
+fn dict_hash_line(interp: &mut FrameInterpreter, objects: &[(u64, &str)], receive_dicts: bool) -> String {
+    let link = Link::default();
+    let tracer = qs::Tracer::new(qs::QsConfig::default(), qs::WriterBackend::new(link.clone())).into_handle();
+    for _ in 0..2 {
+        tracer.emit(predefined::SIG_DICT, &predefined::sig_dict_payload(5, 0, "EAT")).unwrap();
+        tracer.emit(predefined::USR_DICT, &predefined::usr_dict_payload(100, "LOG")).unwrap();
+        for &(addr, name) in objects {
+            tracer.emit(predefined::OBJ_DICT, &predefined::obj_dict_payload(addr, name)).unwrap();
+        }
+    }
+    tracer.emit_dict_hash().unwrap();
+
+    let bytes = link.0.lock().unwrap().clone();
+    let frames: Vec<QsFrame> = crate::HdlcDecoder::new().push_bytes(&bytes).into_iter().map(Result::unwrap).collect();
+    let (hash, dicts) = frames.split_last().unwrap();
+    if receive_dicts {
+        for frame in dicts {
+            interp.interpret(frame);
+        }
+    }
+    interp.interpret(hash).pop().unwrap()
+}
+
+#[test]
+fn dict_hash_validates_cached_dictionaries() {
+    let sizes = TargetSizes { obj_ptr_size: 8, fun_ptr_size: 8, ..TargetSizes::default() };
+    let firmware = [(0x1000, "Table"), (0x1040, "Philo[0]")];
+
+    let mut live = FrameInterpreter::with_sizes(sizes);
+    let line = dict_hash_line(&mut live, &firmware, true);
+    assert!(line.contains("entries=4 (dictionaries match)"), "{line}");
+
+    let path = std::env::temp_dir().join(format!("qspy-dict-hash-{}", std::process::id()));
+    live.save_dictionaries(&path).unwrap();
+    let mut cached = FrameInterpreter::with_sizes(sizes);
+    cached.load_dictionaries(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(cached.dict_hash(), live.dict_hash());
+
+    // A viewer attaching late relies on its cache.
+    let line = dict_hash_line(&mut cached, &firmware, false);
+    assert!(line.contains("(dictionaries match)"), "{line}");
+    let line = dict_hash_line(&mut cached, &[(0x1000, "Table"), (0x1080, "Philo[0]")], false);
+    assert!(line.contains("dictionaries are stale"), "{line}");
+}
+
+#[test]
+fn dictionary_names_are_interned_and_labels_fall_back_to_addresses() {
+    let mut interp = FrameInterpreter::new();
+    for obj in [0x10u32, 0x20] {
+        let mut payload = 5u16.to_le_bytes().to_vec();
+        payload.extend_from_slice(&obj.to_le_bytes());
+        payload.extend_from_slice(b"TIMEOUT_SIG\0");
+        interp.interpret(&frame(predefined::SIG_DICT, payload));
+    }
+    interp.interpret(&obj_dict(0x10, "Philo[0]"));
+
+    // One copy of the name serves every object it is scoped to.
+    let a = interp.signal_name(5, 0x10).unwrap();
+    let b = interp.signal_name(5, 0x20).unwrap();
+    assert_eq!(a, "TIMEOUT_SIG");
+    assert!(std::ptr::eq(a, b));
+
+    assert_eq!(interp.object_label(0x10).name(), Some("Philo[0]"));
+    assert_eq!(interp.object_label(0x30).to_string(), "0x00000030");
+    assert_eq!(format!("{:<10}|", interp.object_label(0x10)), "Philo[0]  |");
+    assert_eq!(interp.signal_label(6, 0x10).to_string(), "0x0006");
+}
+
+#[test]
+fn stable_ids_label_unnamed_symbols_until_the_target_resets() {
+    use qs::symbols::SymbolMode;
+
+    let mut interp = FrameInterpreter::new();
+    interp.interpret(&obj_dict(1, "Table::inst"));
+    let lines = interp.interpret(&frame(predefined::SYM_MODE, SymbolMode::StableIds.payload().to_vec()));
+    assert_eq!(lines, ["           Sym-Mode stable ids"]);
+    assert_eq!(interp.symbol_mode(), SymbolMode::StableIds);
+
+    assert_eq!(interp.object_label(1).to_string(), "Table::inst");
+    assert_eq!(interp.object_label(7).to_string(), "#7");
+    assert_eq!(format!("{:<4}|", interp.function_label(12)), "#12 |");
+
+    interp.interpret(&frame(predefined::TARGET_INFO, qs::TargetInfo::default().encode().to_vec()));
+    assert_eq!(interp.symbol_mode(), SymbolMode::Addresses);
+    assert_eq!(interp.object_label(7).to_string(), "0x0000000000000007");
+}
+
+#[test]
+fn overflow_record_reports_lost_frames() {
+    let mut interp = FrameInterpreter::new();
+    let mut payload = 12u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&qs::QsStats { dropped: 3, overwritten: 40 }.payload());
+    let frame = frame(qs::records::infra::OVERFLOW, payload);
+    assert_eq!(interp.interpret(&frame), vec!["0000000012 QS-Ovfl  Dropped=3,Overwritten=40".to_string()]);
+    assert_eq!(interp.decode(&frame).field("overwritten").and_then(Value::as_u64), Some(40));
+}
+
+#[test]
+fn critical_section_exit_shows_hold_time() {
+    use qs::records::qf::{CRIT_ENTRY, CRIT_EXIT};
+    let mut interp = FrameInterpreter::new();
+    let mut crit = |record, ts: u32, nesting: u8| {
+        let mut payload = ts.to_le_bytes().to_vec();
+        payload.push(nesting);
+        interp.interpret(&frame(record, payload)).join("")
+    };
+    assert_eq!(crit(CRIT_ENTRY, 100, 1), "0000000100 QF-CritE Nesting=1");
+    crit(CRIT_ENTRY, 104, 2);
+    assert_eq!(crit(CRIT_EXIT, 110, 2), "0000000110 QF-CritX Nesting=2,Held=6");
+    assert_eq!(crit(CRIT_EXIT, 125, 1), "0000000125 QF-CritX Nesting=1,Held=25");
+    // Entry lost: no hold time rather than a wrong one.
+    assert_eq!(crit(CRIT_EXIT, 130, 1), "0000000130 QF-CritX Nesting=1");
+    crit(CRIT_ENTRY, u32::MAX - 1, 1);
+    assert_eq!(crit(CRIT_EXIT, 3, 1), "0000000003 QF-CritX Nesting=1,Held=5");
+}
+
+#[test]
+fn record_hooks_see_every_record_before_it_is_printed() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let mut interp = FrameInterpreter::new();
+    let sink = Rc::clone(&seen);
+    interp.on_record(move |record| {
+        let name = record.object_name().map(str::to_string);
+        sink.borrow_mut().push((record.record_type(), record.timestamp(), name));
+    });
+
+    // Forks share the hooks.
+    let mut fork = interp.fork();
+    fork.interpret(&obj_dict(0x10, "Pool0"));
+    let mut payload = 7u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&0x10u32.to_le_bytes());
+    payload.extend_from_slice(&3u16.to_le_bytes());
+    fork.interpret(&frame(qf::MPOOL_PUT, payload));
+
+    assert_eq!(
+        *seen.borrow(),
+        [(predefined::OBJ_DICT, None, None), (qf::MPOOL_PUT, Some(7), Some("Pool0".to_string()))]
+    );
+}
@@ -0,0 +1,76 @@
+use crate::loadgen::{GenConfig, LoadGen, RecordMix, GEN_USER_RECORD};
+use crate::session::SeqCheck;
+use crate::{FrameInterpreter, HdlcDecoder};
+
+fn stream(cfg: GenConfig, records: usize) -> (LoadGen, Vec<u8>) {
+    let mut gen = LoadGen::new(cfg);
+    let mut bytes: Vec<u8> = gen.preamble().concat();
+    for _ in 0..records {
+        if let Some(frame) = gen.next_frame() {
+            bytes.extend_from_slice(&frame);
+        }
+    }
+    (gen, bytes)
+}
+
+#[test]
+fn injected_errors_are_what_the_decoder_reports() {
+    let cfg = GenConfig { corrupt: 0.05, drop: 0.05, seed: 7, ..GenConfig::default() };
+    let (gen, bytes) = stream(cfg, 2000);
+    let stats = gen.stats();
+    assert_eq!(stats.records, 2000);
+    assert_eq!(stats.bytes, bytes.len() as u64);
+    assert!(stats.corrupted > 0 && stats.dropped > 0);
+
+    let mut decoder = HdlcDecoder::new();
+    let mut seq = SeqCheck::new();
+    let mut interp = FrameInterpreter::new();
+    let (mut bad, mut good, mut missed) = (0, 0, 0);
+    let mut lines = Vec::new();
+    for result in decoder.push_bytes(&bytes) {
+        let Ok(frame) = result else {
+            bad += 1;
+            continue;
+        };
+        good += 1;
+        missed += u64::from(seq.check(&frame).unwrap_or(0));
+        lines.extend(interp.interpret(&frame));
+    }
+    assert_eq!(bad, stats.corrupted);
+    assert_eq!(good, stats.frames - stats.corrupted);
+    // A corrupted frame is discarded, so it shows up as a gap too.
+    assert_eq!(missed, stats.dropped + stats.corrupted);
+
+    let has = |needle: &str| lines.iter().any(|l| l.contains(needle));
+    assert!(has("AO-Post  Sdr=AO_"));
+    assert!(has("===>Tran Obj=AO_"));
+    assert!(has("TE0-Arm  Obj=te_"));
+    assert!(has("GEN_USR "));
+}
+
+#[test]
+fn a_seed_always_gives_the_same_stream() {
+    let cfg = GenConfig { corrupt: 0.1, seed: 42, ..GenConfig::default() };
+    assert_eq!(stream(cfg.clone(), 300).1, stream(cfg.clone(), 300).1);
+    assert_ne!(stream(cfg, 300).1, stream(GenConfig::default(), 300).1);
+}
+
+#[test]
+fn the_mix_selects_record_kinds() {
+    let mix = RecordMix::parse("usr=1").unwrap();
+    assert_eq!(mix, RecordMix { sm: 0, ao: 0, te: 0, usr: 1 });
+    let (_, bytes) = stream(GenConfig { mix, ..GenConfig::default() }, 50);
+    let mut decoder = HdlcDecoder::new();
+    let records: Vec<u8> = decoder
+        .push_bytes(&bytes)
+        .into_iter()
+        .map(|f| f.unwrap().record_type)
+        .filter(|r| !(54..=64).contains(r))
+        .collect();
+    assert_eq!(records, vec![GEN_USER_RECORD; 50]);
+
+    assert_eq!(RecordMix::parse(" SM=2, te=1 ").unwrap(), RecordMix { sm: 2, ao: 0, te: 1, usr: 0 });
+    assert!(RecordMix::parse("sm=0").is_err());
+    assert!(RecordMix::parse("qf=1").is_err());
+    assert!(RecordMix::parse("sm").is_err());
+}
@@ -1 +1,17 @@
+mod console;
 mod decoder;
+mod defmt;
+mod export;
+mod fuzz;
+mod groups;
+mod interpreter;
+mod loadgen;
+mod msc;
+mod pools;
+mod profile;
+mod record;
+mod replay;
+mod rtt;
+mod selftest;
+mod session;
+mod spec;
@@ -0,0 +1,115 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use qs::predefined;
+use qs::records::{qep, qf, qf::time_evt};
+
+use crate::msc::{MscFormat, MscWriter};
+use crate::{FrameInterpreter, QsFrame};
+
+#[derive(Clone, Default)]
+struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+fn frame(record_type: u8, payload: Vec<u8>) -> QsFrame {
+    QsFrame { seq: 0, record_type, payload }
+}
+
+fn dict(record_type: u8, key: &[u8], name: &str) -> QsFrame {
+    let mut payload = key.to_vec();
+    payload.extend_from_slice(name.as_bytes());
+    payload.push(0);
+    frame(record_type, payload)
+}
+
+/// Default sizes: ts(4) sig(2) sdr(4) ao(4) pool ref free(1) min(1).
+fn ao_post(record_type: u8, sig: u16, sdr: u32, ao: u32) -> QsFrame {
+    let mut payload = 1u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&sig.to_le_bytes());
+    payload.extend_from_slice(&sdr.to_le_bytes());
+    payload.extend_from_slice(&ao.to_le_bytes());
+    payload.extend_from_slice(&[0, 0, 4, 2]);
+    frame(record_type, payload)
+}
+
+/// A philosopher's timer fires, it asks the table to eat, the table publishes
+/// and moves on to serving.
+fn chart(format: MscFormat) -> String {
+    let mut interp = FrameInterpreter::new();
+    let feed = [
+        dict(predefined::OBJ_DICT, &0x1000u32.to_le_bytes(), "philo[0]"),
+        dict(predefined::OBJ_DICT, &0x2000u32.to_le_bytes(), "table"),
+        dict(predefined::OBJ_DICT, &0x3000u32.to_le_bytes(), "philo[0].te"),
+        dict(predefined::SIG_DICT, &[5, 0, 0, 0, 0, 0], "HUNGRY_SIG"),
+        dict(predefined::SIG_DICT, &[6, 0, 0, 0, 0, 0], "EAT_SIG"),
+        dict(predefined::SIG_DICT, &[7, 0, 0, 0, 0, 0], "TIMEOUT_SIG"),
+        dict(predefined::FUN_DICT, &0x4000u32.to_le_bytes(), "Table::serving"),
+        frame(time_evt::POST, [
+            &1u32.to_le_bytes()[..], &0x3000u32.to_le_bytes(), &7u16.to_le_bytes(),
+            &0x1000u32.to_le_bytes(), &[0],
+        ].concat()),
+        ao_post(qf::ACTIVE_POST, 5, 0x1000, 0x2000),
+        ao_post(qf::ACTIVE_POST_ATTEMPT, 9, 0x1000, 0x2000),
+        frame(qf::PUBLISH, [
+            &2u32.to_le_bytes()[..], &0x2000u32.to_le_bytes(), &6u16.to_le_bytes(), &[0, 0],
+        ].concat()),
+        frame(qep::TRAN, [
+            &3u32.to_le_bytes()[..], &5u16.to_le_bytes(), &0x2000u32.to_le_bytes(),
+            &0x5000u32.to_le_bytes(), &0x4000u32.to_le_bytes(),
+        ].concat()),
+    ];
+
+    let buf = SharedBuf::default();
+    let mut msc = MscWriter::new(format, Box::new(buf.clone()));
+    for f in &feed {
+        interp.interpret(f);
+        msc.record(f, &interp);
+    }
+    drop(msc);
+    let text = buf.0.lock().unwrap().clone();
+    String::from_utf8(text).unwrap()
+}
+
+#[test]
+fn plantuml_chart_has_lifelines_and_arrows() {
+    assert_eq!(chart(MscFormat::PlantUml), "\
+@startuml
+participant \"philo[0].te\" as L0
+participant \"philo[0]\" as L1
+participant \"table\" as L2
+participant \"QF\" as L3
+L0 --> L1 : TIMEOUT_SIG
+L1 -> L2 : HUNGRY_SIG
+L1 ->x L2 : 0x0009
+L2 -> L3 : EAT_SIG
+hnote over L2 : Table::serving
+@enduml
+");
+}
+
+#[test]
+fn mscgen_chart_declares_entities_first() {
+    let text = chart(MscFormat::Mscgen);
+    let mut lines = text.lines();
+    assert_eq!(lines.next(), Some("msc {"));
+    assert_eq!(
+        lines.next(),
+        Some("  L0 [label=\"philo[0].te\"], L1 [label=\"philo[0]\"], L2 [label=\"table\"], L3 [label=\"QF\"];")
+    );
+    assert_eq!(lines.next(), Some("  L0 >> L1 [label=\"TIMEOUT_SIG\"];"));
+    assert!(text.contains("  L2 abox L2 [label=\"Table::serving\"];\n}\n"), "{text}");
+}
+
+#[test]
+fn format_follows_the_extension() {
+    use std::path::Path;
+    assert_eq!(MscFormat::from_path(Path::new("dpp.msc")), MscFormat::Mscgen);
+    assert_eq!(MscFormat::from_path(Path::new("dpp.puml")), MscFormat::PlantUml);
+}
@@ -0,0 +1,87 @@
+use qs::records::qf;
+
+use crate::{FrameInterpreter, PoolForecast, QsFrame};
+
+const POOL: u64 = 0x2000_1000;
+
+#[test]
+fn pool_peak_comes_from_the_low_water_mark() {
+    let mut f = PoolForecast::new();
+    f.mpool_init(POOL, 0, 10);
+    f.mpool_get(POOL, 5, 9, 9);
+    f.mpool_get(POOL, 6, 8, 8);
+    f.mpool_put(POOL, 7, 9);
+    // The target saw a deeper dip between records that were filtered out.
+    f.mpool_get(POOL, 9, 8, 4);
+
+    let pool = &f.pools()[&POOL];
+    assert_eq!((pool.capacity, pool.peak_used(), pool.gets, pool.puts), (Some(10), 6, 3, 1));
+    // 6 blocks in use plus 25 % is 7.5, rounded up.
+    assert_eq!(f.recommend_pool(pool), 8);
+}
+
+#[test]
+fn exhaustion_and_draining_pools_are_flagged() {
+    let mut f = PoolForecast::new().with_margin(0.5);
+    for t in 0..4 {
+        f.mpool_get(POOL, t * 10, 6 - 2 * t, 6 - 2 * t);
+    }
+    f.mpool_exhausted(POOL, 40, 0);
+
+    let pool = &f.pools()[&POOL];
+    assert_eq!(pool.capacity, None);
+    assert_eq!((pool.blocks(), pool.exhausted), (6, 1));
+    assert!(pool.free_slope().unwrap() < 0.0);
+    // Already empty at the last record.
+    assert_eq!(pool.exhaustion_forecast(), Some(40));
+    assert_eq!(f.recommend_pool(pool), 9);
+
+    let lines = f.summary_lines(|_| "EvtPool1".to_string());
+    assert!(lines[0].contains("margin 50%"));
+    assert!(lines.iter().any(|l| l.contains("EvtPool1") && l.contains(">=6") && l.contains("empty at ~40")));
+}
+
+#[test]
+fn live_events_are_counted_per_size() {
+    let mut f = PoolForecast::new();
+    f.event_new(8, 4);
+    f.event_new(8, 5);
+    f.event_new(24, 6);
+    f.event_gc(4, 1);
+    f.event_new(8, 4);
+    f.event_new(8, 7);
+    f.event_gc(4, 1);
+    f.event_gc(5, 1);
+    f.event_gc(6, 2);
+    // A collection whose allocation was not traced changes nothing.
+    f.event_gc(99, 1);
+
+    let small = &f.sizes()[&8];
+    assert_eq!((small.allocated, small.peak, small.live), (4, 3, 1));
+    assert_eq!(small.pools.iter().copied().collect::<Vec<_>>(), [1]);
+    assert_eq!(f.sizes()[&24].live, 0);
+    assert_eq!(f.recommend(small.peak), 4);
+}
+
+fn frame(record_type: u8, fields: &[&[u8]]) -> QsFrame {
+    QsFrame { seq: 0, record_type, payload: fields.concat() }
+}
+
+#[test]
+fn interpreter_feeds_the_forecast() {
+    let mut interp = FrameInterpreter::new();
+    let ts = 1u32.to_le_bytes();
+    let mp = (POOL as u32).to_le_bytes();
+    interp.interpret(&frame(qf::MPOOL_INIT, &[&ts, &mp, &4u16.to_le_bytes(), &4u16.to_le_bytes()]));
+    interp.interpret(&frame(qf::MPOOL_GET, &[&ts, &mp, &3u16.to_le_bytes(), &3u16.to_le_bytes()]));
+    interp.interpret(&frame(qf::NEW, &[&ts, &12u16.to_le_bytes(), &9u16.to_le_bytes()]));
+    interp.interpret(&frame(qf::GC_ATTEMPT, &[&ts, &9u16.to_le_bytes(), &[1, 2]]));
+    assert_eq!(interp.pool_forecast().sizes()[&12].live, 1);
+    interp.interpret(&frame(qf::GC, &[&ts, &9u16.to_le_bytes(), &[1, 1]]));
+
+    let forecast = interp.pool_forecast();
+    assert_eq!(forecast.pools()[&POOL].peak_used(), 1);
+    assert_eq!(forecast.sizes()[&12].live, 0);
+    let report = interp.pool_report();
+    assert!(report.iter().any(|l| l.contains("0x20001000")), "{report:?}");
+}
@@ -0,0 +1,44 @@
+use qs::records::qf;
+
+use crate::{BatchProfile, FrameInterpreter, QsFrame};
+
+#[test]
+fn batches_land_in_power_of_two_buckets() {
+    let mut p = BatchProfile::new();
+    p.record(1, 10);
+    p.record(2, 30);
+    p.record(3, 50);
+    p.record(400, 9000);
+
+    assert_eq!(p.batches(), 4);
+    assert_eq!(p.max_events(), 400);
+    assert_eq!(p.max_duration_us(), 9000);
+
+    let lines = p.summary_lines();
+    assert!(lines[0].contains("4 batches, 406 events"));
+    assert!(lines.iter().any(|l| l.trim_start().starts_with("1 ") && l.contains("25.0%")));
+    assert!(lines.iter().any(|l| l.trim_start().starts_with("2-3 ") && l.ends_with("40        50")));
+    assert!(lines.iter().any(|l| l.trim_start().starts_with("256+ ")));
+    assert!(!lines.iter().any(|l| l.trim_start().starts_with("4-7 ")));
+}
+
+#[test]
+fn empty_profile_says_so() {
+    let lines = BatchProfile::new().summary_lines();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].contains("no QS_RUN_BATCH records"));
+}
+
+#[test]
+fn run_batch_records_feed_the_interpreter_profile() {
+    let mut interp = FrameInterpreter::new();
+    let mut payload = 7u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(&12u16.to_le_bytes());
+    payload.extend_from_slice(&345u32.to_le_bytes());
+
+    let lines = interp.interpret(&QsFrame { seq: 0, record_type: qf::RUN_BATCH, payload });
+    assert_eq!(lines, vec!["0000000007 Sch-Batch Evts=12,Dur=345us".to_string()]);
+    assert_eq!(interp.batch_profile().batches(), 1);
+    assert_eq!(interp.batch_profile().max_events(), 12);
+    assert!(interp.fork().batch_profile().batches() == 0);
+}
@@ -0,0 +1,108 @@
+use std::borrow::Cow;
+
+use qs::records::{qep, qf, RecordSizes};
+use qs::{predefined, UserRecordBuilder};
+
+use crate::record::walk_record;
+use crate::{DecodedRecord, FrameInterpreter, QsFrame, RecordGroup, RecordVisitor, TargetSizes, Value};
+
+/// `RecordSizes` matching `TargetSizes::default()`.
+const SIZES: RecordSizes =
+    RecordSizes { signal: 2, obj_ptr: 4, fun_ptr: 4, equeue_ctr: 1, time_evt_ctr: 2, mpool_ctr: 2 };
+
+fn timed(record_type: u8, ts: u32, body: &[u8]) -> QsFrame {
+    let mut payload = ts.to_le_bytes().to_vec();
+    payload.extend_from_slice(body);
+    QsFrame { seq: 0, record_type, payload }
+}
+
+#[test]
+fn predefined_records_expose_typed_fields() {
+    let frame = timed(qep::TRAN, 500, &qep::tran(0x2000, 9, 0x100, 0x140).encode(&SIZES));
+    let record = DecodedRecord::decode(&frame, &TargetSizes::default());
+    assert!(record.is_complete());
+    assert_eq!(record.group(), RecordGroup::Sm);
+    assert_eq!(record.timestamp(), Some(500));
+    assert_eq!(record.signal(), Some(9));
+    assert_eq!(record.object(), Some(0x2000));
+    assert_eq!(record.state(), Some(0x140));
+    assert_eq!(record.field("source"), Some(&Value::Function(0x100)));
+
+    // A post is about the receiver, not the sender.
+    let post = timed(qf::ACTIVE_POST, 7, &qf::active_post(0x1000, 0x3000, 4, 0, 0, 5, 2).encode(&SIZES));
+    let record = post.decode(&TargetSizes::default());
+    assert_eq!(record.object(), Some(0x3000));
+    assert_eq!(record.field("sender").and_then(Value::as_u64), Some(0x1000));
+    assert_eq!(record.field("free").and_then(Value::as_u64), Some(5));
+    assert_eq!(record.fields().len(), 7);
+}
+
+#[test]
+fn entry_records_are_untimed_and_short_payloads_are_incomplete() {
+    let payload = qep::state_entry(0x2000, 0x180).encode(&SIZES).to_vec();
+    let entry = QsFrame { seq: 0, record_type: qep::STATE_ENTRY, payload };
+    let record = entry.decode(&TargetSizes::default());
+    assert_eq!(record.timestamp(), None);
+    assert_eq!(record.state(), Some(0x180));
+
+    let mut short = timed(qep::DISPATCH, 1, &qep::dispatch(0x2000, 5, 0x100).encode(&SIZES));
+    short.payload.truncate(8);
+    let record = short.decode(&TargetSizes::default());
+    assert!(!record.is_complete());
+    assert_eq!(record.signal(), Some(5));
+    assert_eq!(record.object(), None);
+}
+
+#[test]
+fn user_records_yield_positional_values() {
+    let mut builder = UserRecordBuilder::new();
+    builder.push_u16(0, 300).push_i8_enum(-2).push_str("hot").push_f32(1.5).push_mem(&[1, 2]);
+    let frame = timed(120, 3, &builder.into_vec());
+    let record = frame.decode(&TargetSizes::default());
+    assert!(record.is_complete());
+    assert_eq!(record.group(), RecordGroup::Usr);
+    let values: Vec<_> = record.fields().iter().map(|f| f.value.clone()).collect();
+    assert_eq!(
+        values,
+        [Value::Unsigned(300), Value::Signed(-2), Value::Str("hot".into()), Value::Float(1.5), Value::Mem(vec![1, 2])]
+    );
+    assert_eq!(record.fields()[2].name, Cow::Borrowed("2"));
+}
+
+#[test]
+fn interpreter_resolves_names_and_drives_visitors() {
+    let mut interp = FrameInterpreter::default();
+    let mut dict = 0x2000u32.to_le_bytes().to_vec();
+    dict.extend_from_slice(b"l_blinky\0");
+    interp.interpret(&QsFrame { seq: 0, record_type: predefined::OBJ_DICT, payload: dict });
+
+    let dispatch = timed(qep::DISPATCH, 10, &qep::dispatch(0x2000, 4, 0x100).encode(&SIZES));
+    let post = timed(qf::ACTIVE_POST, 11, &qf::active_post(0, 0x2000, 4, 0, 0, 1, 1).encode(&SIZES));
+    assert_eq!(interp.decode(&dispatch).object_name(), Some("l_blinky"));
+
+    #[derive(Default)]
+    struct Counter {
+        dispatched: Vec<u64>,
+        posted:     usize,
+        all:        usize,
+    }
+    impl RecordVisitor for Counter {
+        fn visit_record(&mut self, record: &DecodedRecord<'_>) {
+            self.all += 1;
+            walk_record(self, record);
+        }
+        fn visit_state_machine(&mut self, record: &DecodedRecord<'_>) {
+            self.dispatched.extend(record.signal());
+        }
+        fn visit_active(&mut self, _: &DecodedRecord<'_>) {
+            self.posted += 1;
+        }
+    }
+    let mut counter = Counter::default();
+    for frame in [&dispatch, &post, &dispatch] {
+        interp.visit(frame, &mut counter);
+    }
+    assert_eq!(counter.dispatched, [4, 4]);
+    assert_eq!(counter.posted, 1);
+    assert_eq!(counter.all, 3);
+}
@@ -0,0 +1,100 @@
+use std::sync::{Arc, Mutex};
+
+use qf::active::{new_active_object, ActiveContext, SignalHandler};
+use qf::event::DynEvent;
+use qf::{ActiveObjectId, Kernel, Signal};
+use qs::predefined;
+use qs::records::qf as rec;
+use qs::{QsConfig, TraceBackend, TraceError, Tracer};
+
+use crate::replay::{ReplaySummary, TargetMap};
+use crate::{FrameInterpreter, ReplayScript};
+
+#[derive(Clone, Default)]
+struct CaptureBackend {
+    bytes: Arc<Mutex<Vec<u8>>>,
+}
+
+impl TraceBackend for CaptureBackend {
+    fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
+        self.bytes.lock().unwrap().extend_from_slice(frame);
+        Ok(())
+    }
+}
+
+/// `ACTIVE_POST*` with default sizes: ts(4) sig(2) sdr(4) ao(4) pool ref free min.
+fn post(ts: u32, sig: u16, ao: u32) -> Vec<u8> {
+    let mut p = ts.to_le_bytes().to_vec();
+    p.extend_from_slice(&sig.to_le_bytes());
+    p.extend_from_slice(&0x1000u32.to_le_bytes());
+    p.extend_from_slice(&ao.to_le_bytes());
+    p.extend_from_slice(&[0, 0, 5, 3]);
+    p
+}
+
+fn capture() -> Vec<u8> {
+    let backend = CaptureBackend::default();
+    let cfg = QsConfig { include_timestamp: false, ..QsConfig::default() };
+    let mut tracer = Tracer::new(cfg, backend.clone());
+    let mut dict = 0x2000u32.to_le_bytes().to_vec();
+    dict.extend_from_slice(b"Table\0");
+    tracer.record(predefined::OBJ_DICT, &dict, false).unwrap();
+    tracer.record(rec::ACTIVE_POST, &post(10, 7, 0x2000), false).unwrap();
+    tracer.record(rec::ACTIVE_POST_ATTEMPT, &post(11, 8, 0x2000), false).unwrap();
+    tracer.record(rec::ACTIVE_POST_LIFO, &post(12, 9, 0x3000), false).unwrap();
+    tracer.record(rec::ACTIVE_POST, &post(13, 4, 0x4000), false).unwrap();
+    tracer.record(rec::ACTIVE_POST, &post(14, 6, 0x2000), false).unwrap();
+    let bytes = backend.bytes.lock().unwrap().clone();
+    bytes
+}
+
+#[derive(Clone, Default)]
+struct Recorder {
+    seen: Arc<Mutex<Vec<(u8, Signal)>>>,
+    id: u8,
+}
+
+impl SignalHandler for Recorder {
+    fn on_start(&mut self, _ctx: &mut ActiveContext) {}
+
+    fn handle_signal(&mut self, signal: Signal, _ctx: &mut ActiveContext) {
+        self.seen.lock().unwrap().push((self.id, signal));
+    }
+}
+
+#[test]
+fn capture_yields_successful_posts_with_names() {
+    let script = ReplayScript::from_capture(&capture()[..], &mut FrameInterpreter::new()).unwrap();
+    let posts = script.posts();
+    assert_eq!(posts.len(), 4);
+    assert_eq!((posts[0].timestamp, posts[0].signal, posts[0].target), (10, 7, 0x2000));
+    assert_eq!(posts[0].target_name.as_deref(), Some("Table"));
+    assert!(posts[1].lifo);
+    assert_eq!(posts[1].target_name, None);
+}
+
+#[test]
+fn replay_posts_into_a_host_kernel() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let table = Recorder { seen: Arc::clone(&seen), id: 1 };
+    let philo = Recorder { seen: Arc::clone(&seen), id: 2 };
+    let kernel = Kernel::builder()
+        .register(new_active_object(ActiveObjectId::new(1), 2, table))
+        .register(new_active_object(ActiveObjectId::new(2), 1, philo))
+        .build();
+    kernel.start();
+
+    let script = ReplayScript::from_capture(&capture()[..], &mut FrameInterpreter::new()).unwrap();
+    let mut targets = TargetMap::new();
+    targets.bind_name("Table", 1).bind_addr(0x3000, 2);
+    let summary = script
+        .replay(&targets, |post, id| {
+            kernel.post(ActiveObjectId::new(id), DynEvent::empty_dyn(Signal(post.signal)))?;
+            kernel.run_until_idle();
+            Ok::<_, qf::kernel::KernelError>(())
+        })
+        .unwrap();
+
+    assert_eq!(summary, ReplaySummary { posted: 3, unmapped: 1 });
+    assert_eq!(*seen.lock().unwrap(), [(1, Signal(7)), (2, Signal(9)), (1, Signal(6))]);
+}
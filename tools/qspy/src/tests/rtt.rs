@@ -0,0 +1,45 @@
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Read, Write};
+use std::path::PathBuf;
+
+use crate::rtt::RttSource;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("qspy-rtt-{}-{name}", std::process::id()))
+}
+
+#[test]
+fn parses_paths_and_commands() {
+    assert_eq!(RttSource::parse("/tmp/rtt.bin"), Ok(RttSource::File("/tmp/rtt.bin".into())));
+    assert_eq!(
+        RttSource::parse("cmd: JLinkRTTLogger -RTTChannel 0 /dev/stdout"),
+        Ok(RttSource::Command("JLinkRTTLogger -RTTChannel 0 /dev/stdout".into()))
+    );
+    assert!(RttSource::parse("cmd:  ").is_err());
+    assert!(RttSource::parse("").is_err());
+}
+
+#[test]
+fn followed_file_picks_up_appended_bytes() {
+    let path = temp_path("follow");
+    std::fs::write(&path, [0x7E, 1]).unwrap();
+    let mut reader = RttSource::File(path.clone()).open().unwrap();
+    let mut buf = [0u8; 16];
+
+    assert_eq!(reader.read(&mut buf).unwrap(), 2);
+    assert_eq!(reader.read(&mut buf).unwrap_err().kind(), ErrorKind::TimedOut);
+
+    OpenOptions::new().append(true).open(&path).unwrap().write_all(&[2, 3]).unwrap();
+    assert_eq!(reader.read(&mut buf).unwrap(), 2);
+    assert_eq!(&buf[..2], &[2, 3]);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn command_stream_ends_with_the_command() {
+    let mut reader = RttSource::parse("cmd:printf 'QS'").unwrap().open().unwrap();
+    let mut out = Vec::new();
+    reader.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"QS");
+}
@@ -0,0 +1,64 @@
+use std::sync::{Arc, Mutex};
+
+use qs::records::{qep, RecordSizes};
+use qs::{QsConfig, TraceBackend, TraceError, Tracer, TimestampSize};
+
+use crate::selftest::verify_capture;
+use crate::{FrameInterpreter, HdlcDecoder, QsFrame, SelftestChecker};
+
+#[derive(Clone, Default)]
+struct CaptureBackend {
+    bytes: Arc<Mutex<Vec<u8>>>,
+}
+
+impl TraceBackend for CaptureBackend {
+    fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
+        self.bytes.lock().unwrap().extend_from_slice(frame);
+        Ok(())
+    }
+}
+
+fn selftest_capture(cfg: QsConfig) -> Vec<u8> {
+    let backend = CaptureBackend::default();
+    let tracer = Tracer::new(cfg, backend.clone()).into_handle();
+    qs::qs_selftest(&tracer).unwrap();
+    let bytes = backend.bytes.lock().unwrap().clone();
+    bytes
+}
+
+#[test]
+fn selftest_suite_verifies_at_native_and_narrow_sizes() {
+    let narrow = RecordSizes { signal: 1, obj_ptr: 2, fun_ptr: 2, equeue_ctr: 1, time_evt_ctr: 1, mpool_ctr: 1 };
+    for cfg in [
+        QsConfig::default(),
+        QsConfig::default().with_sizes(narrow).with_timestamp_size(TimestampSize::Two),
+    ] {
+        let capture = selftest_capture(cfg);
+        let report = verify_capture(&capture[..], &mut FrameInterpreter::new()).unwrap();
+        assert!(report.passed(), "{:#?}", report.summary_lines());
+        assert!(report.results.len() > 80);
+    }
+}
+
+#[test]
+fn selftest_reports_missing_and_altered_records() {
+    let capture = selftest_capture(QsConfig::default());
+    let frames: Vec<QsFrame> = HdlcDecoder::new().push_bytes(&capture).into_iter().map(Result::unwrap).collect();
+
+    let mut interp = FrameInterpreter::new();
+    let mut checker = SelftestChecker::new();
+    for mut frame in frames {
+        match frame.record_type {
+            qep::STATE_ENTRY => continue,
+            qep::TRAN => *frame.payload.last_mut().unwrap() ^= 0xFF,
+            _ => {}
+        }
+        checker.feed(&mut interp, &frame);
+    }
+    let report = checker.finish();
+    assert!(!report.passed());
+    let failed: Vec<u8> = report.results.iter().filter(|(_, r)| r.is_err()).map(|(&id, _)| id).collect();
+    assert_eq!(failed, [qep::STATE_ENTRY, qep::TRAN]);
+    assert_eq!(report.results[&qep::STATE_ENTRY], Err("not in the capture".to_string()));
+    assert!(report.summary_lines()[0].contains("record types OK"));
+}
@@ -0,0 +1,76 @@
+use qs::predefined;
+
+use crate::session::{SeqCheck, Session, SessionTable};
+use crate::{FrameInterpreter, QsFrame};
+
+fn frame(seq: u8, record_type: u8) -> QsFrame {
+    QsFrame { seq, record_type, payload: Vec::new() }
+}
+
+#[test]
+fn seq_check_reports_gaps_and_wraps() {
+    let mut check = SeqCheck::new();
+    assert_eq!(check.check(&frame(1, 8)), None);
+    assert_eq!(check.check(&frame(2, 8)), None);
+    assert_eq!(check.check(&frame(5, 8)), Some(2));
+    assert_eq!(check.check(&frame(255, 8)), Some(249));
+    assert_eq!(check.check(&frame(0, 8)), None);
+}
+
+#[test]
+fn target_info_restarts_the_sequence() {
+    let mut check = SeqCheck::new();
+    check.check(&frame(40, 8));
+    assert_eq!(check.check(&frame(1, predefined::TARGET_INFO)), None);
+    assert_eq!(check.check(&frame(2, 8)), None);
+}
+
+#[test]
+fn forked_sessions_keep_separate_dictionaries() {
+    let mut primary = FrameInterpreter::new();
+    let mut payload = vec![98];
+    payload.extend_from_slice(b"ONLY_PRIMARY\0");
+    primary.interpret(&QsFrame { seq: 0, record_type: predefined::USR_DICT, payload });
+
+    let mut fork = Session::forked(&primary).interpreter.unwrap();
+    let mut payload = vec![99];
+    payload.extend_from_slice(b"ONLY_FORK\0");
+    fork.interpret(&QsFrame { seq: 0, record_type: predefined::USR_DICT, payload });
+
+    // Each target's dictionaries stay with its own session.
+    assert_eq!(fork.group_of(98), crate::RecordGroup::Info);
+    assert_eq!(fork.group_of(99), crate::RecordGroup::Usr);
+    assert_eq!(primary.group_of(98), crate::RecordGroup::Usr);
+    assert_eq!(primary.group_of(99), crate::RecordGroup::Info);
+}
+
+#[test]
+fn table_tags_sessions_once_a_second_one_is_live() {
+    let primary = FrameInterpreter::new();
+    let mut table = SessionTable::default();
+
+    let (first, opened) = table.open("a", &primary);
+    assert_eq!(opened, Some(1));
+    assert!(first.interpreter.is_none() && first.tag.is_none());
+    assert_eq!(table.open("a", &primary).1, None);
+
+    let (second, opened) = table.open("b", &primary);
+    assert_eq!(opened, Some(2));
+    assert!(second.interpreter.is_some());
+    assert_eq!(second.tag.as_deref(), Some("#2"));
+    assert_eq!(table.open("a", &primary).0.tag.as_deref(), Some("#1"));
+}
+
+#[test]
+fn closed_primary_slot_goes_to_the_next_session() {
+    let primary = FrameInterpreter::new();
+    let mut table = SessionTable::default();
+    table.open("a", &primary);
+    assert!(table.close(&"a"));
+    assert!(!table.close(&"a"));
+
+    // A target reconnecting after a reset is decoded by the primary again.
+    let (session, opened) = table.open("a2", &primary);
+    assert_eq!(opened, Some(2));
+    assert!(session.interpreter.is_none() && session.tag.is_none());
+}
@@ -0,0 +1,95 @@
+use std::sync::{Arc, Mutex};
+
+use qs::records::sched;
+use qs::{QsConfig, TraceBackend, TraceError, Tracer};
+
+use crate::spec::{self, Violation};
+use crate::{FrameInterpreter, Spec, SpecChecker};
+
+fn run(spec: &str, lines: &[&str]) -> spec::Report {
+    let mut checker = SpecChecker::new(Spec::parse(spec).unwrap());
+    for (i, line) in lines.iter().enumerate() {
+        checker.feed(i as u64, line);
+    }
+    checker.finish()
+}
+
+#[test]
+fn malformed_rules_name_their_line() {
+    assert_eq!(
+        Spec::parse("# dpp\n\nafter Disp===> expect QF-Pub").unwrap_err(),
+        "line 3: expected 'within'"
+    );
+    assert!(Spec::parse("always Sig=X").unwrap_err().contains("unknown property 'always'"));
+    assert!(Spec::parse("never Obj=a[*]*] twice without Sig=B").is_err());
+}
+
+#[test]
+fn response_deadline_is_checked_per_binding() {
+    let report = run(
+        "after Disp===> Obj=philo[*] Sig=EAT_SIG expect QF-Pub Sdr=philo[*] Sig=DONE_SIG within 100",
+        &[
+            "0000000010 Disp===> Obj=philo[1],Sig=EAT_SIG,State=thinking",
+            "0000000012 Disp===> Obj=philo[2],Sig=EAT_SIG,State=thinking",
+            "0000000020 QF-Pub   Sdr=philo[1],Evt<Sig=DONE_SIG,Pool=1,Ref=0>",
+            "0000000200 QF-Tick  Rate=0",
+        ],
+    );
+    assert!(!report.passed());
+    assert_eq!(report.violations, vec![Violation {
+        rule: 0, first: 1, last: 3, detail: "no response within 100 (*=2)".to_string(),
+    }]);
+    assert!(report.unresolved.is_empty());
+}
+
+#[test]
+fn repeated_event_needs_an_intervening_reset() {
+    let spec = "never AO-Post Obj=philo[*] Sig=EAT_SIG twice without QF-Pub Sdr=philo[*] Sig=DONE_SIG";
+    let grant = |n: u8| format!(
+        "0000000001 AO-Post  Sdr=table,Obj=philo[{n}],Evt<Sig=EAT_SIG,Pool=0,Ref=0>,Que<Free=5,Min=5>"
+    );
+    let done = "0000000002 QF-Pub   Sdr=philo[1],Evt<Sig=DONE_SIG,Pool=1,Ref=0>";
+
+    let report = run(spec, &[&grant(1), &grant(2), done, &grant(1)]);
+    assert!(report.passed());
+
+    let report = run(spec, &[&grant(1), &grant(2), &grant(1)]);
+    assert_eq!(report.violations.len(), 1);
+    assert_eq!((report.violations[0].first, report.violations[0].last), (0, 2));
+}
+
+#[derive(Clone, Default)]
+struct CaptureBackend {
+    frames: Arc<Mutex<Vec<u8>>>,
+}
+
+impl TraceBackend for CaptureBackend {
+    fn write_frame(&self, frame: &[u8]) -> Result<(), TraceError> {
+        self.frames.lock().unwrap().extend_from_slice(frame);
+        Ok(())
+    }
+}
+
+#[test]
+fn capture_reports_failures_and_open_triggers() {
+    let backend = CaptureBackend::default();
+    let cfg = QsConfig { include_timestamp: false, ..QsConfig::default() };
+    let mut tracer = Tracer::new(cfg, backend.clone());
+    for (rec, ts) in [(sched::PREEMPT, 9u32), (sched::RESTORE, 30), (sched::PREEMPT, 40)] {
+        let mut payload = ts.to_le_bytes().to_vec();
+        payload.extend_from_slice(&[5, 2]);
+        tracer.record(rec, &payload, false).unwrap();
+    }
+
+    let spec = Spec::parse("after Sch-Pre expect Sch-Rest within 10").unwrap();
+    let capture = backend.frames.lock().unwrap().clone();
+    let report = spec::check_capture(&capture[..], &mut FrameInterpreter::new(), spec).unwrap();
+
+    assert_eq!(report.records, 3);
+    assert_eq!((report.violations[0].first, report.violations[0].last), (0, 1));
+    assert_eq!((report.unresolved[0].first, report.unresolved[0].last), (2, 2));
+    let summary = report.summary_lines();
+    assert!(summary[0].ends_with("1 violation(s), 1 unresolved"));
+    assert!(summary.iter().any(|l| l.contains("FAIL line 1: after Sch-Pre")));
+    assert!(summary.iter().any(|l| l.contains("records #2..=#2: capture ended while waiting")));
+}